@@ -0,0 +1,198 @@
+// containers.rs - Runtime list, dict and string operations and boxed arithmetic
+//
+// Calls the runtime functions compiled code calls, so rewrites of the
+// container runtime can be compared without codegen in the measurement.
+//
+// Run with `cargo bench --features benchmarks --bench containers`.
+
+use cheetah::compiler::runtime::dict::{dict_free, dict_get, dict_new, dict_set};
+use cheetah::compiler::runtime::list::{
+    list_append_tagged, list_free, list_get, list_len, list_new, list_slice, RawList, TypeTag,
+};
+use cheetah::compiler::runtime::string::{free_string, int_to_string, string_concat, string_len};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use std::ffi::{c_void, CString};
+use std::os::raw::c_char;
+
+const N: i64 = 10_000;
+
+/// Heap-allocated int, as compiled code stores one in a container
+fn box_int(value: i64) -> *mut c_void {
+    unsafe {
+        let boxed = libc::malloc(8) as *mut i64;
+        *boxed = value;
+        boxed as *mut c_void
+    }
+}
+
+fn unbox_int(value: *mut c_void) -> i64 {
+    unsafe { *(value as *const i64) }
+}
+
+/// List of the boxed ints `0..n`
+fn int_list(n: i64) -> *mut RawList {
+    let list = list_new();
+    for i in 0..n {
+        list_append_tagged(list, box_int(i), TypeTag::Int);
+    }
+    list
+}
+
+fn lists(c: &mut Criterion) {
+    let mut group = c.benchmark_group("list");
+    group.throughput(Throughput::Elements(N as u64));
+
+    group.bench_function("append", |b| {
+        b.iter(|| {
+            let list = int_list(black_box(N));
+            let len = list_len(list);
+            list_free(list);
+            len
+        })
+    });
+
+    let list = int_list(N);
+    group.bench_function("index", |b| {
+        b.iter(|| (0..N).map(|i| unbox_int(list_get(list, black_box(i)))).sum::<i64>())
+    });
+    group.bench_function("slice", |b| {
+        b.iter(|| {
+            let slice = list_slice(list, black_box(0), N, 1);
+            let len = list_len(slice);
+            // The slice shares its elements with `list`
+            unsafe { (*slice).length = 0 };
+            list_free(slice);
+            len
+        })
+    });
+    list_free(list);
+    group.finish();
+}
+
+fn dicts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dict");
+    group.throughput(Throughput::Elements(N as u64));
+
+    let int_keys: Vec<i64> = (0..N).collect();
+    let str_keys: Vec<CString> = (0..N)
+        .map(|i| CString::new(format!("key{}", i)).unwrap())
+        .collect();
+    let int_key = |key: &i64| key as *const i64 as *mut c_void;
+    let str_key = |key: &CString| key.as_ptr() as *mut c_void;
+
+    let fill_int = || {
+        let dict = dict_new();
+        for (i, key) in int_keys.iter().enumerate() {
+            let mut value = i as i64;
+            let value = &mut value as *mut i64 as *mut c_void;
+            dict_set(dict, int_key(key), value, TypeTag::Int, TypeTag::Int);
+        }
+        dict
+    };
+    let fill_str = || {
+        let dict = dict_new();
+        for (i, key) in str_keys.iter().enumerate() {
+            let mut value = i as i64;
+            let value = &mut value as *mut i64 as *mut c_void;
+            dict_set(dict, str_key(key), value, TypeTag::String, TypeTag::Int);
+        }
+        dict
+    };
+
+    group.bench_function("insert_int", |b| b.iter(|| dict_free(black_box(fill_int()))));
+    group.bench_function("insert_str", |b| b.iter(|| dict_free(black_box(fill_str()))));
+
+    let dict = fill_int();
+    group.bench_function("lookup_int", |b| {
+        b.iter(|| {
+            int_keys
+                .iter()
+                .map(|key| unbox_int(dict_get(dict, int_key(black_box(key)), TypeTag::Int)))
+                .sum::<i64>()
+        })
+    });
+    dict_free(dict);
+
+    let dict = fill_str();
+    group.bench_function("lookup_str", |b| {
+        b.iter(|| {
+            str_keys
+                .iter()
+                .map(|key| unbox_int(dict_get(dict, str_key(black_box(key)), TypeTag::String)))
+                .sum::<i64>()
+        })
+    });
+    dict_free(dict);
+    group.finish();
+}
+
+fn strings(c: &mut Criterion) {
+    const PARTS: i64 = 1_000;
+    let mut group = c.benchmark_group("string");
+    group.throughput(Throughput::Elements(PARTS as u64));
+
+    let piece = CString::new("abc").unwrap();
+    let separator = CString::new(", ").unwrap();
+
+    // `s = s + "abc"` in a loop, copying the whole string each time
+    group.bench_function("concat", |b| {
+        b.iter(|| {
+            let mut s = CString::default().into_raw();
+            for _ in 0..PARTS {
+                let next = string_concat(s, black_box(piece.as_ptr()));
+                free_string(s);
+                s = next;
+            }
+            let len = string_len(s);
+            free_string(s);
+            len
+        })
+    });
+
+    // f"{i}, {i}", as compiled: each part converted and concatenated in turn
+    group.bench_function("format", |b| {
+        b.iter(|| {
+            let mut total = 0;
+            for i in 0..PARTS {
+                let number = int_to_string(black_box(i));
+                let parts: [*const c_char; 3] = [number, separator.as_ptr(), number];
+                let mut s = CString::default().into_raw();
+                for part in parts {
+                    let next = string_concat(s, part);
+                    free_string(s);
+                    s = next;
+                }
+                total += string_len(s);
+                free_string(s);
+                free_string(number);
+            }
+            total
+        })
+    });
+    group.finish();
+}
+
+fn boxed_arithmetic(c: &mut Criterion) {
+    let mut group = c.benchmark_group("boxed");
+    group.throughput(Throughput::Elements(N as u64));
+
+    // `ys[i] = xs[i] * 3 + 1`: unbox the operand, compute, box the result
+    let xs = int_list(N);
+    group.bench_function("int_arithmetic", |b| {
+        b.iter(|| {
+            let ys = list_new();
+            for i in 0..N {
+                let x = unbox_int(list_get(xs, black_box(i)));
+                list_append_tagged(ys, box_int(x * 3 + 1), TypeTag::Int);
+            }
+            let last = unbox_int(list_get(ys, N - 1));
+            list_free(ys);
+            last
+        })
+    });
+    list_free(xs);
+    group.finish();
+}
+
+criterion_group!(benches, lists, dicts, strings, boxed_arithmetic);
+criterion_main!(benches);
@@ -0,0 +1,72 @@
+// numeric_loops.rs - Tight integer loops over constant and runtime range() bounds
+//
+// Run with `cargo bench --features benchmarks --bench numeric_loops`.
+
+use cheetah::compiler::runtime::{abi, traceback};
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use inkwell::context::Context;
+use inkwell::targets::{InitializationConfig, Target};
+use inkwell::OptimizationLevel;
+
+const LOOPS: &str = r#"
+N = 100000
+
+def sum_constant() -> int:
+    total = 0
+    for i in range(N):
+        total = total + i * 3
+    return total
+
+def sum_runtime(n: int) -> int:
+    total = 0
+    for i in range(n):
+        total = total + i * 3
+    return total
+
+def strided_constant() -> int:
+    total = 0
+    for i in range(N - 1, -1, -7):
+        total = total + (i & 255)
+    return total
+"#;
+
+fn numeric_loops(c: &mut Criterion) {
+    Target::initialize_native(&InitializationConfig::default()).expect("native target");
+    let ast = parse(LOOPS).expect("loops should parse");
+
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "numeric_loops");
+    compiler.compile_module(&ast).expect("loops should compile");
+    compiler.optimize(3).expect("loops should optimize");
+
+    let engine = compiler
+        .get_module()
+        .create_jit_execution_engine(OptimizationLevel::Aggressive)
+        .expect("engine should be created");
+    abi::register_abi_runtime_functions(&engine, compiler.get_module()).expect("abi functions");
+    traceback::register_traceback_runtime_functions(&engine, compiler.get_module())
+        .expect("traceback functions");
+
+    unsafe {
+        let sum_constant = engine
+            .get_function::<unsafe extern "C" fn() -> i64>("sum_constant")
+            .expect("sum_constant should be compiled");
+        let sum_runtime = engine
+            .get_function::<unsafe extern "C" fn(i64) -> i64>("sum_runtime")
+            .expect("sum_runtime should be compiled");
+        let strided_constant = engine
+            .get_function::<unsafe extern "C" fn() -> i64>("strided_constant")
+            .expect("strided_constant should be compiled");
+
+        c.bench_function("sum_constant_range", |b| b.iter(|| sum_constant.call()));
+        c.bench_function("sum_runtime_range", |b| {
+            b.iter(|| sum_runtime.call(black_box(100000)))
+        });
+        c.bench_function("strided_constant_range", |b| b.iter(|| strided_constant.call()));
+    }
+}
+
+criterion_group!(benches, numeric_loops);
+criterion_main!(benches);
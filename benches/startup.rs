@@ -0,0 +1,42 @@
+// startup.rs - Compile and JIT startup latency for small scripts
+//
+// Run with `cargo bench --features benchmarks --bench startup`.
+
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use inkwell::context::Context;
+use inkwell::targets::{InitializationConfig, Target};
+use inkwell::OptimizationLevel;
+
+const SMALL_SCRIPT: &str = "x = 40 + 2\nprint(x)\n";
+
+fn startup(c: &mut Criterion) {
+    Target::initialize_native(&InitializationConfig::default()).expect("native target");
+    let ast = parse(SMALL_SCRIPT).expect("script should parse");
+
+    c.bench_function("compile_small_script", |b| {
+        b.iter(|| {
+            let context = Context::create();
+            let mut compiler = Compiler::new(&context, "startup");
+            compiler.compile_module(black_box(&ast)).expect("script should compile");
+            compiler.get_module().get_functions().count()
+        })
+    });
+
+    c.bench_function("jit_engine_small_script", |b| {
+        b.iter(|| {
+            let context = Context::create();
+            let mut compiler = Compiler::new(&context, "startup");
+            compiler.compile_module(black_box(&ast)).expect("script should compile");
+            let engine = compiler
+                .get_module()
+                .create_jit_execution_engine(OptimizationLevel::None)
+                .expect("engine should be created");
+            drop(engine);
+        })
+    });
+}
+
+criterion_group!(benches, startup);
+criterion_main!(benches);
@@ -1,6 +1,8 @@
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "node")]
 pub enum Stmt {
     FunctionDef {
         name: String,
@@ -11,6 +13,7 @@ pub enum Stmt {
         is_async: bool,
         line: usize,
         column: usize,
+        span: Span,
     },
     ClassDef {
         name: String,
@@ -20,22 +23,26 @@ pub enum Stmt {
         decorator_list: Vec<Box<Expr>>,
         line: usize,
         column: usize,
+        span: Span,
     },
     Return {
         value: Option<Box<Expr>>,
         line: usize,
         column: usize,
+        span: Span,
     },
     Delete {
         targets: Vec<Box<Expr>>,
         line: usize,
         column: usize,
+        span: Span,
     },
     Assign {
         targets: Vec<Box<Expr>>,
         value: Box<Expr>,
         line: usize,
         column: usize,
+        span: Span,
     },
     AugAssign {
         target: Box<Expr>,
@@ -43,6 +50,7 @@ pub enum Stmt {
         value: Box<Expr>,
         line: usize,
         column: usize,
+        span: Span,
     },
     AnnAssign {
         target: Box<Expr>,
@@ -50,6 +58,7 @@ pub enum Stmt {
         value: Option<Box<Expr>>,
         line: usize,
         column: usize,
+        span: Span,
     },
     For {
         target: Box<Expr>,
@@ -59,6 +68,7 @@ pub enum Stmt {
         is_async: bool,
         line: usize,
         column: usize,
+        span: Span,
     },
     While {
         test: Box<Expr>,
@@ -66,6 +76,7 @@ pub enum Stmt {
         orelse: Vec<Box<Stmt>>,
         line: usize,
         column: usize,
+        span: Span,
     },
     If {
         test: Box<Expr>,
@@ -73,6 +84,7 @@ pub enum Stmt {
         orelse: Vec<Box<Stmt>>,
         line: usize,
         column: usize,
+        span: Span,
     },
     With {
         items: Vec<(Box<Expr>, Option<Box<Expr>>)>,
@@ -80,12 +92,14 @@ pub enum Stmt {
         is_async: bool,
         line: usize,
         column: usize,
+        span: Span,
     },
     Raise {
         exc: Option<Box<Expr>>,
         cause: Option<Box<Expr>>,
         line: usize,
         column: usize,
+        span: Span,
     },
     Try {
         body: Vec<Box<Stmt>>,
@@ -94,17 +108,20 @@ pub enum Stmt {
         finalbody: Vec<Box<Stmt>>,
         line: usize,
         column: usize,
+        span: Span,
     },
     Assert {
         test: Box<Expr>,
         msg: Option<Box<Expr>>,
         line: usize,
         column: usize,
+        span: Span,
     },
     Import {
         names: Vec<Alias>,
         line: usize,
         column: usize,
+        span: Span,
     },
     ImportFrom {
         module: Option<String>,
@@ -112,49 +129,59 @@ pub enum Stmt {
         level: usize,
         line: usize,
         column: usize,
+        span: Span,
     },
     Global {
         names: Vec<String>,
         line: usize,
         column: usize,
+        span: Span,
     },
     Nonlocal {
         names: Vec<String>,
         line: usize,
         column: usize,
+        span: Span,
     },
     Expr {
         value: Box<Expr>,
         line: usize,
         column: usize,
+        span: Span,
     },
     Pass {
         line: usize,
         column: usize,
+        span: Span,
     },
     Break {
         line: usize,
         column: usize,
+        span: Span,
     },
     Continue {
         line: usize,
         column: usize,
+        span: Span,
     },
     Match {
         subject: Box<Expr>,
         cases: Vec<(Box<Expr>, Option<Box<Expr>>, Vec<Box<Stmt>>)>,
         line: usize,
         column: usize,
+        span: Span,
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "node")]
 pub enum Expr {
     BoolOp {
         op: BoolOperator,
         values: Vec<Box<Expr>>,
         line: usize,
         column: usize,
+        span: Span,
     },
     BinOp {
         left: Box<Expr>,
@@ -162,6 +189,7 @@ pub enum Expr {
         right: Box<Expr>,
         line: usize,
         column: usize,
+        span: Span,
     },
     Slice {
         lower: Option<Box<Expr>>,
@@ -169,18 +197,21 @@ pub enum Expr {
         step: Option<Box<Expr>>,
         line: usize,
         column: usize,
+        span: Span,
     },
     UnaryOp {
         op: UnaryOperator,
         operand: Box<Expr>,
         line: usize,
         column: usize,
+        span: Span,
     },
     Lambda {
         args: Vec<Parameter>,
         body: Box<Expr>,
         line: usize,
         column: usize,
+        span: Span,
     },
     IfExp {
         test: Box<Expr>,
@@ -188,29 +219,34 @@ pub enum Expr {
         orelse: Box<Expr>,
         line: usize,
         column: usize,
+        span: Span,
     },
     Dict {
         keys: Vec<Option<Box<Expr>>>,
         values: Vec<Box<Expr>>,
         line: usize,
         column: usize,
+        span: Span,
     },
     Set {
         elts: Vec<Box<Expr>>,
         line: usize,
         column: usize,
+        span: Span,
     },
     ListComp {
         elt: Box<Expr>,
         generators: Vec<Comprehension>,
         line: usize,
         column: usize,
+        span: Span,
     },
     SetComp {
         elt: Box<Expr>,
         generators: Vec<Comprehension>,
         line: usize,
         column: usize,
+        span: Span,
     },
     DictComp {
         key: Box<Expr>,
@@ -218,27 +254,32 @@ pub enum Expr {
         generators: Vec<Comprehension>,
         line: usize,
         column: usize,
+        span: Span,
     },
     GeneratorExp {
         elt: Box<Expr>,
         generators: Vec<Comprehension>,
         line: usize,
         column: usize,
+        span: Span,
     },
     Await {
         value: Box<Expr>,
         line: usize,
         column: usize,
+        span: Span,
     },
     Yield {
         value: Option<Box<Expr>>,
         line: usize,
         column: usize,
+        span: Span,
     },
     YieldFrom {
         value: Box<Expr>,
         line: usize,
         column: usize,
+        span: Span,
     },
     Compare {
         left: Box<Expr>,
@@ -246,6 +287,7 @@ pub enum Expr {
         comparators: Vec<Box<Expr>>,
         line: usize,
         column: usize,
+        span: Span,
     },
     Call {
         func: Box<Expr>,
@@ -253,16 +295,19 @@ pub enum Expr {
         keywords: Vec<(Option<String>, Box<Expr>)>,
         line: usize,
         column: usize,
+        span: Span,
     },
     Num {
         value: Number,
         line: usize,
         column: usize,
+        span: Span,
     },
     Str {
         value: String,
         line: usize,
         column: usize,
+        span: Span,
     },
     FormattedValue {
         value: Box<Expr>,
@@ -270,30 +315,36 @@ pub enum Expr {
         format_spec: Option<Box<Expr>>,
         line: usize,
         column: usize,
+        span: Span,
     },
     JoinedStr {
         values: Vec<Box<Expr>>,
         line: usize,
         column: usize,
+        span: Span,
     },
     Bytes {
         value: Vec<u8>,
         line: usize,
         column: usize,
+        span: Span,
     },
     NameConstant {
         value: NameConstant,
         line: usize,
         column: usize,
+        span: Span,
     },
     Ellipsis {
         line: usize,
         column: usize,
+        span: Span,
     },
     Constant {
         value: Constant,
         line: usize,
         column: usize,
+        span: Span,
     },
     Attribute {
         value: Box<Expr>,
@@ -301,6 +352,7 @@ pub enum Expr {
         ctx: ExprContext,
         line: usize,
         column: usize,
+        span: Span,
     },
     Subscript {
         value: Box<Expr>,
@@ -308,53 +360,59 @@ pub enum Expr {
         ctx: ExprContext,
         line: usize,
         column: usize,
+        span: Span,
     },
     Starred {
         value: Box<Expr>,
         ctx: ExprContext,
         line: usize,
         column: usize,
+        span: Span,
     },
     Name {
         id: String,
         ctx: ExprContext,
         line: usize,
         column: usize,
+        span: Span,
     },
     List {
         elts: Vec<Box<Expr>>,
         ctx: ExprContext,
         line: usize,
         column: usize,
+        span: Span,
     },
     Tuple {
         elts: Vec<Box<Expr>>,
         ctx: ExprContext,
         line: usize,
         column: usize,
+        span: Span,
     },
     NamedExpr {
         target: Box<Expr>,
         value: Box<Expr>,
         line: usize,
         column: usize,
+        span: Span,
     },
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ExprContext {
     Load,
     Store,
     Del,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum BoolOperator {
     And,
     Or,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Operator {
     Add,
     Sub,
@@ -371,7 +429,7 @@ pub enum Operator {
     BitAnd,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum UnaryOperator {
     Invert,
     Not,
@@ -379,7 +437,7 @@ pub enum UnaryOperator {
     USub,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum CmpOperator {
     Eq,
     NotEq,
@@ -393,21 +451,21 @@ pub enum CmpOperator {
     NotIn,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Number {
     Integer(i64),
     Float(f64),
     Complex { real: f64, imag: f64 },
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum NameConstant {
     None,
     True,
     False,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Constant {
     Num(Number),
     Str(String),
@@ -416,7 +474,7 @@ pub enum Constant {
     Ellipsis,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Comprehension {
     pub target: Box<Expr>,
     pub iter: Box<Expr>,
@@ -424,7 +482,7 @@ pub struct Comprehension {
     pub is_async: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExceptHandler {
     pub typ: Option<Box<Expr>>,
     pub name: Option<String>,
@@ -433,13 +491,13 @@ pub struct ExceptHandler {
     pub column: usize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Alias {
     pub name: String,
     pub asname: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Parameter {
     pub name: String,
     pub typ: Option<Box<Expr>>,
@@ -448,11 +506,394 @@ pub struct Parameter {
     pub is_kwarg: bool,
 }
 
-#[derive(Debug, Clone)]
+/// Where a node is in the source: byte offsets of its first and last
+/// characters (`end` is exclusive) and the line and column just past its
+/// end. Nodes built by the compiler rather than parsed have an empty span.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl Span {
+    /// Whether the span covers no source text
+    pub fn is_empty(&self) -> bool {
+        self.end <= self.start
+    }
+
+    /// The text the span covers in `source`
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        source.get(self.start..self.end).unwrap_or("")
+    }
+
+    /// The line and column, both from 1, where the span starts in `source`
+    pub fn start_position(&self, source: &str) -> (usize, usize) {
+        let before = source.get(..self.start).unwrap_or(source);
+        let line_start = before.rfind('\n').map_or(0, |at| at + 1);
+        (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Module {
     pub body: Vec<Box<Stmt>>,
 }
 
+impl Stmt {
+    /// The source line the statement starts on
+    pub fn line(&self) -> usize {
+        match self {
+            Stmt::FunctionDef { line, .. }
+            | Stmt::ClassDef { line, .. }
+            | Stmt::Return { line, .. }
+            | Stmt::Delete { line, .. }
+            | Stmt::Assign { line, .. }
+            | Stmt::AugAssign { line, .. }
+            | Stmt::AnnAssign { line, .. }
+            | Stmt::For { line, .. }
+            | Stmt::While { line, .. }
+            | Stmt::If { line, .. }
+            | Stmt::With { line, .. }
+            | Stmt::Raise { line, .. }
+            | Stmt::Try { line, .. }
+            | Stmt::Assert { line, .. }
+            | Stmt::Import { line, .. }
+            | Stmt::ImportFrom { line, .. }
+            | Stmt::Global { line, .. }
+            | Stmt::Nonlocal { line, .. }
+            | Stmt::Expr { line, .. }
+            | Stmt::Pass { line, .. }
+            | Stmt::Break { line, .. }
+            | Stmt::Continue { line, .. }
+            | Stmt::Match { line, .. } => *line,
+        }
+    }
+
+    /// The source line the statement starts on, for moving it after an edit
+    pub fn line_mut(&mut self) -> &mut usize {
+        match self {
+            Stmt::FunctionDef { line, .. }
+            | Stmt::ClassDef { line, .. }
+            | Stmt::Return { line, .. }
+            | Stmt::Delete { line, .. }
+            | Stmt::Assign { line, .. }
+            | Stmt::AugAssign { line, .. }
+            | Stmt::AnnAssign { line, .. }
+            | Stmt::For { line, .. }
+            | Stmt::While { line, .. }
+            | Stmt::If { line, .. }
+            | Stmt::With { line, .. }
+            | Stmt::Raise { line, .. }
+            | Stmt::Try { line, .. }
+            | Stmt::Assert { line, .. }
+            | Stmt::Import { line, .. }
+            | Stmt::ImportFrom { line, .. }
+            | Stmt::Global { line, .. }
+            | Stmt::Nonlocal { line, .. }
+            | Stmt::Expr { line, .. }
+            | Stmt::Pass { line, .. }
+            | Stmt::Break { line, .. }
+            | Stmt::Continue { line, .. }
+            | Stmt::Match { line, .. } => line,
+        }
+    }
+
+    /// The source column the statement starts at
+    pub fn column(&self) -> usize {
+        match self {
+            Stmt::FunctionDef { column, .. }
+            | Stmt::ClassDef { column, .. }
+            | Stmt::Return { column, .. }
+            | Stmt::Delete { column, .. }
+            | Stmt::Assign { column, .. }
+            | Stmt::AugAssign { column, .. }
+            | Stmt::AnnAssign { column, .. }
+            | Stmt::For { column, .. }
+            | Stmt::While { column, .. }
+            | Stmt::If { column, .. }
+            | Stmt::With { column, .. }
+            | Stmt::Raise { column, .. }
+            | Stmt::Try { column, .. }
+            | Stmt::Assert { column, .. }
+            | Stmt::Import { column, .. }
+            | Stmt::ImportFrom { column, .. }
+            | Stmt::Global { column, .. }
+            | Stmt::Nonlocal { column, .. }
+            | Stmt::Expr { column, .. }
+            | Stmt::Pass { column, .. }
+            | Stmt::Break { column, .. }
+            | Stmt::Continue { column, .. }
+            | Stmt::Match { column, .. } => *column,
+        }
+    }
+
+    /// The statement's source span
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::FunctionDef { span, .. }
+            | Stmt::ClassDef { span, .. }
+            | Stmt::Return { span, .. }
+            | Stmt::Delete { span, .. }
+            | Stmt::Assign { span, .. }
+            | Stmt::AugAssign { span, .. }
+            | Stmt::AnnAssign { span, .. }
+            | Stmt::For { span, .. }
+            | Stmt::While { span, .. }
+            | Stmt::If { span, .. }
+            | Stmt::With { span, .. }
+            | Stmt::Raise { span, .. }
+            | Stmt::Try { span, .. }
+            | Stmt::Assert { span, .. }
+            | Stmt::Import { span, .. }
+            | Stmt::ImportFrom { span, .. }
+            | Stmt::Global { span, .. }
+            | Stmt::Nonlocal { span, .. }
+            | Stmt::Expr { span, .. }
+            | Stmt::Pass { span, .. }
+            | Stmt::Break { span, .. }
+            | Stmt::Continue { span, .. }
+            | Stmt::Match { span, .. } => *span,
+        }
+    }
+
+    /// The statement's span, for the parser to fill in
+    pub fn span_mut(&mut self) -> &mut Span {
+        match self {
+            Stmt::FunctionDef { span, .. }
+            | Stmt::ClassDef { span, .. }
+            | Stmt::Return { span, .. }
+            | Stmt::Delete { span, .. }
+            | Stmt::Assign { span, .. }
+            | Stmt::AugAssign { span, .. }
+            | Stmt::AnnAssign { span, .. }
+            | Stmt::For { span, .. }
+            | Stmt::While { span, .. }
+            | Stmt::If { span, .. }
+            | Stmt::With { span, .. }
+            | Stmt::Raise { span, .. }
+            | Stmt::Try { span, .. }
+            | Stmt::Assert { span, .. }
+            | Stmt::Import { span, .. }
+            | Stmt::ImportFrom { span, .. }
+            | Stmt::Global { span, .. }
+            | Stmt::Nonlocal { span, .. }
+            | Stmt::Expr { span, .. }
+            | Stmt::Pass { span, .. }
+            | Stmt::Break { span, .. }
+            | Stmt::Continue { span, .. }
+            | Stmt::Match { span, .. } => span,
+        }
+    }
+}
+
+impl Expr {
+    /// The source line the expression starts on
+    pub fn line(&self) -> usize {
+        match self {
+            Expr::BoolOp { line, .. }
+            | Expr::BinOp { line, .. }
+            | Expr::Slice { line, .. }
+            | Expr::UnaryOp { line, .. }
+            | Expr::Lambda { line, .. }
+            | Expr::IfExp { line, .. }
+            | Expr::Dict { line, .. }
+            | Expr::Set { line, .. }
+            | Expr::ListComp { line, .. }
+            | Expr::SetComp { line, .. }
+            | Expr::DictComp { line, .. }
+            | Expr::GeneratorExp { line, .. }
+            | Expr::Await { line, .. }
+            | Expr::Yield { line, .. }
+            | Expr::YieldFrom { line, .. }
+            | Expr::Compare { line, .. }
+            | Expr::Call { line, .. }
+            | Expr::Num { line, .. }
+            | Expr::Str { line, .. }
+            | Expr::FormattedValue { line, .. }
+            | Expr::JoinedStr { line, .. }
+            | Expr::Bytes { line, .. }
+            | Expr::NameConstant { line, .. }
+            | Expr::Ellipsis { line, .. }
+            | Expr::Constant { line, .. }
+            | Expr::Attribute { line, .. }
+            | Expr::Subscript { line, .. }
+            | Expr::Starred { line, .. }
+            | Expr::Name { line, .. }
+            | Expr::List { line, .. }
+            | Expr::Tuple { line, .. }
+            | Expr::NamedExpr { line, .. } => *line,
+        }
+    }
+
+    /// The source line the expression starts on, for moving it after an edit
+    pub fn line_mut(&mut self) -> &mut usize {
+        match self {
+            Expr::BoolOp { line, .. }
+            | Expr::BinOp { line, .. }
+            | Expr::Slice { line, .. }
+            | Expr::UnaryOp { line, .. }
+            | Expr::Lambda { line, .. }
+            | Expr::IfExp { line, .. }
+            | Expr::Dict { line, .. }
+            | Expr::Set { line, .. }
+            | Expr::ListComp { line, .. }
+            | Expr::SetComp { line, .. }
+            | Expr::DictComp { line, .. }
+            | Expr::GeneratorExp { line, .. }
+            | Expr::Await { line, .. }
+            | Expr::Yield { line, .. }
+            | Expr::YieldFrom { line, .. }
+            | Expr::Compare { line, .. }
+            | Expr::Call { line, .. }
+            | Expr::Num { line, .. }
+            | Expr::Str { line, .. }
+            | Expr::FormattedValue { line, .. }
+            | Expr::JoinedStr { line, .. }
+            | Expr::Bytes { line, .. }
+            | Expr::NameConstant { line, .. }
+            | Expr::Ellipsis { line, .. }
+            | Expr::Constant { line, .. }
+            | Expr::Attribute { line, .. }
+            | Expr::Subscript { line, .. }
+            | Expr::Starred { line, .. }
+            | Expr::Name { line, .. }
+            | Expr::List { line, .. }
+            | Expr::Tuple { line, .. }
+            | Expr::NamedExpr { line, .. } => line,
+        }
+    }
+
+    /// The source column the expression starts at
+    pub fn column(&self) -> usize {
+        match self {
+            Expr::BoolOp { column, .. }
+            | Expr::BinOp { column, .. }
+            | Expr::Slice { column, .. }
+            | Expr::UnaryOp { column, .. }
+            | Expr::Lambda { column, .. }
+            | Expr::IfExp { column, .. }
+            | Expr::Dict { column, .. }
+            | Expr::Set { column, .. }
+            | Expr::ListComp { column, .. }
+            | Expr::SetComp { column, .. }
+            | Expr::DictComp { column, .. }
+            | Expr::GeneratorExp { column, .. }
+            | Expr::Await { column, .. }
+            | Expr::Yield { column, .. }
+            | Expr::YieldFrom { column, .. }
+            | Expr::Compare { column, .. }
+            | Expr::Call { column, .. }
+            | Expr::Num { column, .. }
+            | Expr::Str { column, .. }
+            | Expr::FormattedValue { column, .. }
+            | Expr::JoinedStr { column, .. }
+            | Expr::Bytes { column, .. }
+            | Expr::NameConstant { column, .. }
+            | Expr::Ellipsis { column, .. }
+            | Expr::Constant { column, .. }
+            | Expr::Attribute { column, .. }
+            | Expr::Subscript { column, .. }
+            | Expr::Starred { column, .. }
+            | Expr::Name { column, .. }
+            | Expr::List { column, .. }
+            | Expr::Tuple { column, .. }
+            | Expr::NamedExpr { column, .. } => *column,
+        }
+    }
+
+    /// The expression's source span
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::BoolOp { span, .. }
+            | Expr::BinOp { span, .. }
+            | Expr::Slice { span, .. }
+            | Expr::UnaryOp { span, .. }
+            | Expr::Lambda { span, .. }
+            | Expr::IfExp { span, .. }
+            | Expr::Dict { span, .. }
+            | Expr::Set { span, .. }
+            | Expr::ListComp { span, .. }
+            | Expr::SetComp { span, .. }
+            | Expr::DictComp { span, .. }
+            | Expr::GeneratorExp { span, .. }
+            | Expr::Await { span, .. }
+            | Expr::Yield { span, .. }
+            | Expr::YieldFrom { span, .. }
+            | Expr::Compare { span, .. }
+            | Expr::Call { span, .. }
+            | Expr::Num { span, .. }
+            | Expr::Str { span, .. }
+            | Expr::FormattedValue { span, .. }
+            | Expr::JoinedStr { span, .. }
+            | Expr::Bytes { span, .. }
+            | Expr::NameConstant { span, .. }
+            | Expr::Ellipsis { span, .. }
+            | Expr::Constant { span, .. }
+            | Expr::Attribute { span, .. }
+            | Expr::Subscript { span, .. }
+            | Expr::Starred { span, .. }
+            | Expr::Name { span, .. }
+            | Expr::List { span, .. }
+            | Expr::Tuple { span, .. }
+            | Expr::NamedExpr { span, .. } => *span,
+        }
+    }
+
+    /// The expression's span, for the parser to fill in
+    pub fn span_mut(&mut self) -> &mut Span {
+        match self {
+            Expr::BoolOp { span, .. }
+            | Expr::BinOp { span, .. }
+            | Expr::Slice { span, .. }
+            | Expr::UnaryOp { span, .. }
+            | Expr::Lambda { span, .. }
+            | Expr::IfExp { span, .. }
+            | Expr::Dict { span, .. }
+            | Expr::Set { span, .. }
+            | Expr::ListComp { span, .. }
+            | Expr::SetComp { span, .. }
+            | Expr::DictComp { span, .. }
+            | Expr::GeneratorExp { span, .. }
+            | Expr::Await { span, .. }
+            | Expr::Yield { span, .. }
+            | Expr::YieldFrom { span, .. }
+            | Expr::Compare { span, .. }
+            | Expr::Call { span, .. }
+            | Expr::Num { span, .. }
+            | Expr::Str { span, .. }
+            | Expr::FormattedValue { span, .. }
+            | Expr::JoinedStr { span, .. }
+            | Expr::Bytes { span, .. }
+            | Expr::NameConstant { span, .. }
+            | Expr::Ellipsis { span, .. }
+            | Expr::Constant { span, .. }
+            | Expr::Attribute { span, .. }
+            | Expr::Subscript { span, .. }
+            | Expr::Starred { span, .. }
+            | Expr::Name { span, .. }
+            | Expr::List { span, .. }
+            | Expr::Tuple { span, .. }
+            | Expr::NamedExpr { span, .. } => span,
+        }
+    }
+}
+
+impl Module {
+    /// The module as pretty-printed JSON. Statements and expressions are
+    /// objects tagged with their kind under `"node"`, e.g.
+    /// `{"node": "Name", "id": "x", ...}`.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("syntax trees always serialize")
+    }
+
+    /// Load a module written by [`Module::to_json`]
+    pub fn from_json(json: &str) -> Result<Module, String> {
+        serde_json::from_str(json).map_err(|e| format!("Invalid syntax tree JSON: {}", e))
+    }
+}
+
 impl fmt::Display for Module {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "Module:")?;
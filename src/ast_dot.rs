@@ -0,0 +1,149 @@
+// ast_dot.rs - Graphviz graphs of syntax trees
+//
+// Every node of the tree is drawn as a box labelled with its kind and its
+// scalar fields (names, operators, constants); child nodes hang off edges
+// labelled with the field that holds them. Source positions are left out.
+//
+//     cheetah parse --dot example.ch | dot -Tsvg -o example.svg
+
+use crate::ast::Module;
+use serde_json::{Map, Value};
+
+/// Fields that only locate a node in the source
+const POSITION_FIELDS: &[&str] = &["line", "column", "span"];
+
+/// Render `module` as a Graphviz `digraph`
+pub fn module_to_dot(module: &Module) -> String {
+    let tree = serde_json::to_value(module).expect("syntax trees always serialize");
+    let mut graph = DotGraph::default();
+    graph.object("Module", tree.as_object().expect("modules serialize to objects"));
+
+    let mut dot = String::from("digraph AST {\n");
+    dot.push_str("    graph [ordering=out];\n");
+    dot.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+    for (id, label) in graph.nodes.iter().enumerate() {
+        dot.push_str(&format!("    n{} [label=\"{}\"];\n", id, label));
+    }
+    for (from, to, field) in &graph.edges {
+        dot.push_str(&format!("    n{} -> n{} [label=\"{}\"];\n", from, to, escape(field)));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+#[derive(Default)]
+struct DotGraph {
+    nodes: Vec<String>,
+    edges: Vec<(usize, usize, String)>,
+}
+
+impl DotGraph {
+    /// Add a node for a serialized AST node or helper struct, returning its id
+    fn object(&mut self, kind: &str, fields: &Map<String, Value>) -> usize {
+        let id = self.nodes.len();
+        self.nodes.push(String::new());
+
+        let mut label = escape(fields.get("node").and_then(Value::as_str).unwrap_or(kind));
+        for (field, value) in fields {
+            if field == "node" || POSITION_FIELDS.contains(&field.as_str()) {
+                continue;
+            }
+            match inline(value) {
+                Some(text) => label.push_str(&format!("\\n{} = {}", field, escape(&text))),
+                None => self.children(id, field, value),
+            }
+        }
+        self.nodes[id] = label;
+        id
+    }
+
+    /// Link the node or nodes held by `field` of node `parent`
+    fn children(&mut self, parent: usize, field: &str, value: &Value) {
+        match value {
+            Value::Null => {}
+            Value::Array(items) => {
+                for item in items {
+                    match item {
+                        // Tuples such as a match case's (pattern, guard, body)
+                        Value::Array(parts) => {
+                            let tuple = self.nodes.len();
+                            self.nodes.push(escape(helper_kind(field)));
+                            self.edges.push((parent, tuple, field.to_string()));
+                            for (index, part) in parts.iter().enumerate() {
+                                let name = tuple_fields(field).get(index).copied().unwrap_or("item");
+                                self.children(tuple, name, part);
+                            }
+                        }
+                        _ => self.children(parent, field, item),
+                    }
+                }
+            }
+            Value::Object(fields) => {
+                let child = self.object(helper_kind(field), fields);
+                self.edges.push((parent, child, field.to_string()));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// How a helper struct or tuple reached through `field` is labelled
+fn helper_kind(field: &str) -> &'static str {
+    match field {
+        "params" | "args" => "Parameter",
+        "handlers" => "ExceptHandler",
+        "names" => "Alias",
+        "generators" => "Comprehension",
+        "items" => "WithItem",
+        "cases" => "MatchCase",
+        _ => "Value",
+    }
+}
+
+/// Names of the parts of a tuple reached through `field`
+fn tuple_fields(field: &str) -> &'static [&'static str] {
+    match field {
+        "items" => &["context_expr", "optional_vars"],
+        "cases" => &["pattern", "guard", "body"],
+        _ => &[],
+    }
+}
+
+/// The text of a value small enough to show in its parent's label
+fn inline(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::Bool(flag) => Some(flag.to_string()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::String(text) => Some(text.clone()),
+        Value::Array(items)
+            if !items.is_empty() && items.iter().all(|item| !item.is_object() && !item.is_array()) =>
+        {
+            let items: Vec<String> = items.iter().filter_map(inline).collect();
+            Some(format!("[{}]", items.join(", ")))
+        }
+        Value::Array(_) => None,
+        // Constants are externally tagged enums: {"Num": {"Integer": 1}}
+        Value::Object(fields) if fields.len() == 1 && !fields.contains_key("node") => {
+            let (tag, inner) = fields.iter().next()?;
+            match (tag.as_str(), inner) {
+                ("Str", Value::String(text)) => Some(format!("{:?}", text)),
+                ("Num" | "Integer" | "Float" | "NameConstant", _) => inline(inner),
+                ("Complex", Value::Object(parts)) => Some(format!(
+                    "{}+{}j",
+                    parts.get("real").map(Value::to_string).unwrap_or_default(),
+                    parts.get("imag").map(Value::to_string).unwrap_or_default()
+                )),
+                (_, Value::Object(_)) => None,
+                _ => Some(format!("{}({})", tag, inline(inner)?)),
+            }
+        }
+        Value::Object(_) => None,
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,46 @@
+// abstract_class.rs - Classes that leave abstract methods unimplemented
+//
+// When a class's definition runs, the abstract methods it defines or
+// inherits that nothing overrides are handed to the instance runtime, which
+// then refuses to create its instances. The type checker already rejects
+// instantiating such a class by name; this covers classes only known when
+// the program runs.
+
+use crate::ast::Stmt;
+use crate::compiler::context::CompilationContext;
+use crate::typechecker::{abstract_methods, mro};
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// Record the methods of class `name`, and tell the instance runtime
+    /// which abstract methods the class leaves unimplemented
+    pub fn register_class_methods(&mut self, name: &str, body: &[Box<Stmt>]) -> Result<(), String> {
+        let redefined = self
+            .class_methods
+            .insert(name.to_string(), abstract_methods::class_methods(body))
+            .is_some();
+
+        let bases_of = |class: &str| self.class_bases.get(class).cloned().unwrap_or_default();
+        // The type checker reports classes without a consistent MRO
+        let Ok(order) = mro::linearize(name, &bases_of) else {
+            return Ok(());
+        };
+        let missing = abstract_methods::unimplemented(&order, |class| {
+            self.class_methods.get(class).cloned().unwrap_or_default()
+        });
+        // A redefinition may implement what an earlier definition left out
+        if missing.is_empty() && !redefined {
+            return Ok(());
+        }
+
+        let function = self
+            .module
+            .get_function("instance_set_abstract_methods")
+            .ok_or_else(|| "instance_set_abstract_methods function not found".to_string())?;
+        let class = self.string_constant(name.as_bytes());
+        let methods = self.string_constant(missing.join(",").as_bytes());
+        self.builder
+            .build_call(function, &[class.into(), methods.into()], "")
+            .unwrap();
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+// attribute_cache.rs - Attribute loads and stores on class instances
+//
+// Each `obj.attr` site gets its own private cache global, passed to the
+// runtime's instance_get_attr()/instance_set_attr(). The runtime fills it
+// with the shape (class) and slot it found, so while a hot loop keeps
+// seeing instances of the same class the lookup by name is skipped:
+//
+//     p.x                     # instance_get_attr(p, "x", @attr_cache_3)
+//     p.x = 1.5               # instance_set_attr(p, "x", <bits>, @attr_cache_4)
+//
+// Slots hold 64-bit words, so values are converted to and from the
+// attribute's type around the call.
+
+use crate::compiler::context::CompilationContext;
+use crate::compiler::types::Type;
+use inkwell::module::Linkage;
+use inkwell::values::{BasicValueEnum, IntValue, PointerValue};
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// A fresh cache for one attribute access site, matching no shape yet
+    fn attribute_cache_site(&mut self) -> PointerValue<'ctx> {
+        let i64_type = self.llvm_context.i64_type();
+        let cache_type = self
+            .llvm_context
+            .struct_type(&[i64_type.into(), i64_type.into(), i64_type.into()], false);
+        let empty = i64_type.const_int(-1i64 as u64, true);
+
+        let name = format!("attr_cache_{}", self.get_unique_id());
+        let global = self.module.add_global(cache_type, None, &name);
+        global.set_linkage(Linkage::Private);
+        global.set_initializer(&cache_type.const_named_struct(&[empty.into(), empty.into(), empty.into()]));
+        global.as_pointer_value()
+    }
+
+    /// The declared type of `attr` on instances of `class`, falling back to
+    /// its class attributes, or Any
+    pub fn instance_attribute_type(&self, class: &Type, attr: &str) -> Type {
+        let Type::Class { name, fields, .. } = class else {
+            return Type::Any;
+        };
+        fields
+            .get(attr)
+            .cloned()
+            .or_else(|| {
+                self.class_layouts
+                    .get(name)
+                    .and_then(|layout| layout.field_type(attr).cloned())
+            })
+            .or_else(|| {
+                self.class_attribute_type(name, attr)
+                    .map(|(_, ty)| ty.clone())
+                    .filter(|ty| *ty != Type::Unknown)
+            })
+            .unwrap_or(Type::Any)
+    }
+
+    /// Load `attr` from `instance`, a value of class type `class`
+    pub fn compile_instance_load(
+        &mut self,
+        instance: BasicValueEnum<'ctx>,
+        class: &Type,
+        attr: &str,
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        let get_attr = self
+            .runtime_function("instance_get_attr")
+            .ok_or_else(|| "instance_get_attr function not found".to_string())?;
+        let name = self.string_constant(attr.as_bytes());
+        let cache = self.attribute_cache_site();
+
+        let word = self
+            .builder
+            .build_call(
+                get_attr,
+                &[instance.into_pointer_value().into(), name.into(), cache.into()],
+                "attr_word",
+            )
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .ok_or_else(|| format!("Failed to load attribute '{}'", attr))?
+            .into_int_value();
+
+        let ty = self.instance_attribute_type(class, attr);
+        Ok((self.value_from_word(word, &ty), ty))
+    }
+
+    /// Store `value` of type `value_type` as `attr` of `instance`
+    pub fn compile_instance_store(
+        &mut self,
+        instance: BasicValueEnum<'ctx>,
+        attr: &str,
+        value: BasicValueEnum<'ctx>,
+        value_type: &Type,
+    ) -> Result<(), String> {
+        let set_attr = self
+            .runtime_function("instance_set_attr")
+            .ok_or_else(|| "instance_set_attr function not found".to_string())?;
+        let name = self.string_constant(attr.as_bytes());
+        let cache = self.attribute_cache_site();
+        let word = self.word_from_value(value, value_type)?;
+
+        self.builder
+            .build_call(
+                set_attr,
+                &[
+                    instance.into_pointer_value().into(),
+                    name.into(),
+                    word.into(),
+                    cache.into(),
+                ],
+                "",
+            )
+            .unwrap();
+        Ok(())
+    }
+
+    /// `value` as the 64-bit word stored in an instance slot
+    pub(crate) fn word_from_value(&self, value: BasicValueEnum<'ctx>, ty: &Type) -> Result<IntValue<'ctx>, String> {
+        let i64_type = self.llvm_context.i64_type();
+        Ok(match value {
+            BasicValueEnum::IntValue(int) if matches!(ty, Type::Bool) => {
+                self.builder.build_int_z_extend(int, i64_type, "bool_word").unwrap()
+            }
+            BasicValueEnum::IntValue(int) if int.get_type().get_bit_width() < 64 => {
+                self.builder.build_int_s_extend(int, i64_type, "int_word").unwrap()
+            }
+            BasicValueEnum::IntValue(int) => int,
+            BasicValueEnum::FloatValue(float) => self
+                .builder
+                .build_bit_cast(float, i64_type, "float_word")
+                .unwrap()
+                .into_int_value(),
+            BasicValueEnum::PointerValue(ptr) => {
+                self.builder.build_ptr_to_int(ptr, i64_type, "ptr_word").unwrap()
+            }
+            _ => return Err(format!("Cannot store a value of type {} as an attribute", ty)),
+        })
+    }
+
+    /// A slot's 64-bit word as a value of type `ty`
+    pub(crate) fn value_from_word(&self, word: IntValue<'ctx>, ty: &Type) -> BasicValueEnum<'ctx> {
+        match ty.to_llvm_type(self.llvm_context) {
+            inkwell::types::BasicTypeEnum::IntType(int_type) => {
+                self.builder.build_int_truncate_or_bit_cast(word, int_type, "attr_int").unwrap().into()
+            }
+            inkwell::types::BasicTypeEnum::FloatType(float_type) => {
+                self.builder.build_bit_cast(word, float_type, "attr_float").unwrap()
+            }
+            _ => {
+                let ptr_type = self.llvm_context.ptr_type(inkwell::AddressSpace::default());
+                self.builder.build_int_to_ptr(word, ptr_type, "attr_ptr").unwrap().into()
+            }
+        }
+    }
+}
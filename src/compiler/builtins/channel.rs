@@ -0,0 +1,137 @@
+// channel.rs - Compilation of the channel(), send() and recv() built-ins
+//
+// A channel is a pointer to a runtime `Channel`, typed `Channel[T]` after
+// the values it carries. Values are sent boxed with their type tag, like list
+// elements, and what recv() returns is unboxed by the channel's type:
+//
+//     ch = channel(int)           # channel_new()
+//     send(ch, n)                 # channel_send(ch, &n, Int)
+//     n = recv(ch)                # load i64 from channel_recv(ch)
+//     n = recv(ch, 1.5)           # load i64 from channel_recv_timeout(ch, 1.5)
+
+use crate::ast::Expr;
+use crate::compiler::class_layout::annotation_type;
+use crate::compiler::context::CompilationContext;
+use crate::compiler::expr::ExprCompiler;
+use crate::compiler::types::{is_reference_type, Type};
+use inkwell::values::{BasicValueEnum, PointerValue};
+
+/// Channel built-ins
+pub const CHANNEL_BUILTINS: &[&str] = &["channel", "send", "recv"];
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// Whether a call to `name` refers to a channel built-in rather than a user definition
+    pub fn is_channel_builtin(&self, name: &str) -> bool {
+        CHANNEL_BUILTINS.contains(&name)
+            && self.runtime_function(name).is_none()
+            && self.scope_stack.get_variable(name).is_none()
+    }
+
+    /// Compile a call to channel(), send() or recv()
+    pub fn compile_channel_call(
+        &mut self,
+        name: &str,
+        args: &[Box<Expr>],
+        keywords: &[(Option<String>, Box<Expr>)],
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        if !keywords.is_empty() {
+            return Err(format!("{}() takes no keyword arguments", name));
+        }
+        match name {
+            "channel" => self.compile_channel_new(args),
+            "send" => self.compile_send_call(args),
+            "recv" => self.compile_recv_call(args),
+            _ => Err(format!("{}() is not a channel built-in", name)),
+        }
+    }
+
+    /// Compile channel([type]), creating an empty channel
+    fn compile_channel_new(&mut self, args: &[Box<Expr>]) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        if args.len() > 1 {
+            return Err(format!("channel() takes at most 1 argument ({} given)", args.len()));
+        }
+        let element = match args.first() {
+            Some(arg) => annotation_type(arg)
+                .ok_or("channel() takes the type of the values it carries, such as int or str")?,
+            None => Type::Any,
+        };
+        let channel = self
+            .call_runtime_function("channel_new", &[])?
+            .ok_or("channel_new returned void")?;
+        Ok((channel, Type::channel(element)))
+    }
+
+    /// Compile the channel argument of `function`, returning it and the type
+    /// of the values it carries
+    fn compile_channel_arg(
+        &mut self,
+        arg: &Expr,
+        function: &str,
+    ) -> Result<(PointerValue<'ctx>, Type), String> {
+        let (channel, ty) = self.compile_expr(arg)?;
+        let element = match ty.channel_element() {
+            Some(element) => element.clone(),
+            None if ty == Type::Any => Type::Any,
+            None => return Err(format!("{}() expects a channel, not {}", function, ty)),
+        };
+        if !channel.is_pointer_value() {
+            return Err(format!("{}() expects a channel, not {}", function, ty));
+        }
+        Ok((channel.into_pointer_value(), element))
+    }
+
+    /// Compile send(channel, value). The runtime copies the value, so it is
+    /// boxed on the stack.
+    fn compile_send_call(&mut self, args: &[Box<Expr>]) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        if args.len() != 2 {
+            return Err(format!("send() takes 2 arguments ({} given)", args.len()));
+        }
+        let (channel, element) = self.compile_channel_arg(&args[0], "send")?;
+        let (mut value, mut ty) = self.compile_expr(&args[1])?;
+        if matches!((&ty, &element), (Type::Int | Type::Bool, Type::Float)) {
+            value = self.convert_type(value, &ty, &Type::Float)?;
+            ty = Type::Float;
+        }
+
+        let tag = self.type_tag(&ty);
+        let value_ptr = if is_reference_type(&ty) && value.is_pointer_value() {
+            value.into_pointer_value()
+        } else {
+            let slot = self.builder.build_alloca(value.get_type(), "send_value").unwrap();
+            self.builder.build_store(slot, value).unwrap();
+            slot
+        };
+        self.call_runtime_function("channel_send", &[channel.into(), value_ptr.into(), tag.into()])?;
+        Ok((self.llvm_context.i32_type().const_zero().into(), Type::None))
+    }
+
+    /// Compile recv(channel[, timeout]), returning the value received
+    fn compile_recv_call(&mut self, args: &[Box<Expr>]) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        if args.is_empty() || args.len() > 2 {
+            return Err(format!("recv() takes 1 or 2 arguments ({} given)", args.len()));
+        }
+        let (channel, element) = self.compile_channel_arg(&args[0], "recv")?;
+        let item = match args.get(1) {
+            Some(timeout) => {
+                let (seconds, seconds_type) = self.compile_expr(timeout)?;
+                if !matches!(seconds_type, Type::Int | Type::Float | Type::Bool) {
+                    return Err(format!("recv() timeout must be a number, not {}", seconds_type));
+                }
+                let seconds = self.convert_type(seconds, &seconds_type, &Type::Float)?;
+                self.call_runtime_function("channel_recv_timeout", &[channel.into(), seconds.into()])?
+            }
+            None => self.call_runtime_function("channel_recv", &[channel.into()])?,
+        };
+        let item_ptr = item.ok_or("channel_recv returned void")?.into_pointer_value();
+
+        match element {
+            Type::Unknown | Type::Any => Ok((item_ptr.into(), Type::Any)),
+            ty if is_reference_type(&ty) => Ok((item_ptr.into(), ty)),
+            ty => {
+                let llvm_type = self.get_llvm_type(&ty);
+                let item = self.builder.build_load(llvm_type, item_ptr, "recv_item").unwrap();
+                Ok((item, ty))
+            }
+        }
+    }
+}
@@ -0,0 +1,106 @@
+// context_managers.rs - Resources released when a `with` block exits
+
+use crate::ast::Expr;
+use crate::compiler::context::CompilationContext;
+use inkwell::values::{BasicValueEnum, PointerValue};
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// The runtime function releasing what a `with` item produces, if it is a
+    /// context manager the compiler knows: `os.tempdir()`, `os.tempfile()`,
+    /// `open(...)` or a variable holding a file object
+    pub fn with_item_cleanup(&self, expr: &Expr) -> Option<&'static str> {
+        match expr {
+            Expr::Call { func, .. } if self.os_function_name(func).is_some() => Some("os_temp_cleanup"),
+            Expr::Call { func, .. }
+                if matches!(func.as_ref(), Expr::Name { id, .. } if self.is_open_builtin(id)) =>
+            {
+                Some("file_close")
+            }
+            Expr::Name { id, .. } if self.scope_stack.get_type(id).is_some_and(|ty| ty.is_file()) => {
+                Some("file_close")
+            }
+            _ => None,
+        }
+    }
+
+    /// Enter a `with` item whose value is released by `cleanup` when the block exits
+    pub fn enter_with_scope(
+        &mut self,
+        value: BasicValueEnum<'ctx>,
+        cleanup: &'static str,
+    ) -> Result<(), String> {
+        let function = self
+            .builder
+            .get_insert_block()
+            .and_then(|block| block.get_parent())
+            .ok_or("with statement outside of a function")?;
+
+        // The slot lives in the entry block so every early exit can reach it
+        let entry = function
+            .get_first_basic_block()
+            .ok_or("function has no entry block")?;
+        let entry_builder = self.llvm_context.create_builder();
+        match entry.get_first_instruction() {
+            Some(first) => entry_builder.position_before(&first),
+            None => entry_builder.position_at_end(entry),
+        }
+        let ptr_type = self.llvm_context.ptr_type(inkwell::AddressSpace::default());
+        let slot = entry_builder.build_alloca(ptr_type, "with_resource").unwrap();
+        entry_builder.build_store(slot, ptr_type.const_null()).unwrap();
+
+        self.builder.build_store(slot, value).unwrap();
+        self.with_scopes
+            .push((function, self.loop_stack.len(), slot, cleanup));
+        Ok(())
+    }
+
+    /// Leave the innermost `with` item, releasing its value unless the block
+    /// already exited through `return`, `break` or `continue`
+    pub fn exit_with_scope(&mut self) -> Result<(), String> {
+        let (_, _, slot, cleanup) = self
+            .with_scopes
+            .pop()
+            .ok_or("no with statement to exit")?;
+        let terminated = self
+            .builder
+            .get_insert_block()
+            .and_then(|block| block.get_terminator())
+            .is_some();
+        if !terminated {
+            self.emit_with_cleanup(slot, cleanup)?;
+        }
+        Ok(())
+    }
+
+    /// Release the values of `with` blocks left by an early exit.
+    ///
+    /// A `return` (`loop_depth` of None) leaves every block of the current
+    /// function; `break` and `continue` leave those inside the innermost loop.
+    pub fn emit_with_cleanups(&self, loop_depth: Option<usize>) -> Result<(), String> {
+        let function = match self.builder.get_insert_block().and_then(|b| b.get_parent()) {
+            Some(f) => f,
+            None => return Ok(()),
+        };
+        for (_, _, slot, cleanup) in self.with_scopes.iter().rev().filter(|(f, depth, _, _)| {
+            *f == function && loop_depth.is_none_or(|d| *depth >= d)
+        }) {
+            self.emit_with_cleanup(*slot, cleanup)?;
+        }
+        Ok(())
+    }
+
+    fn emit_with_cleanup(&self, slot: PointerValue<'ctx>, cleanup: &str) -> Result<(), String> {
+        let cleanup_fn = self
+            .runtime_function(cleanup)
+            .ok_or_else(|| format!("{} function not found", cleanup))?;
+        let ptr_type = self.llvm_context.ptr_type(inkwell::AddressSpace::default());
+        let value = self
+            .builder
+            .build_load(ptr_type, slot, "with_value")
+            .unwrap();
+        self.builder
+            .build_call(cleanup_fn, &[value.into()], "with_cleanup")
+            .unwrap();
+        Ok(())
+    }
+}
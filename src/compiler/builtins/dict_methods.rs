@@ -0,0 +1,173 @@
+// dict_methods.rs - Compilation of method calls on dict values
+
+use crate::ast::Expr;
+use crate::compiler::context::CompilationContext;
+use crate::compiler::expr::ExprCompiler;
+use crate::compiler::types::{is_reference_type, Type};
+use inkwell::values::{BasicValueEnum, PointerValue};
+use inkwell::AddressSpace;
+
+/// Methods handled here, with their minimum and maximum argument counts.
+/// `keys`, `values` and `items` are compiled inline by the expression compiler.
+pub const DICT_METHODS: &[(&str, usize, usize)] = &[
+    ("get", 1, 2),
+    ("pop", 1, 2),
+    ("setdefault", 2, 2),
+    ("update", 1, 1),
+    ("clear", 0, 0),
+];
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// Compile `dict.method(args...)` for a dict-typed receiver
+    pub fn compile_dict_method_call(
+        &mut self,
+        dict_val: BasicValueEnum<'ctx>,
+        key_type: &Type,
+        value_type: &Type,
+        method: &str,
+        args: &[Box<Expr>],
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        let (min_args, max_args) = match DICT_METHODS.iter().find(|(name, ..)| *name == method) {
+            Some((_, min, max)) => (*min, *max),
+            None => return Err(format!("'dict' object has no attribute '{}'", method)),
+        };
+
+        if args.len() < min_args || args.len() > max_args {
+            let expected = if min_args == max_args {
+                format!("exactly {}", min_args)
+            } else {
+                format!("{} to {}", min_args, max_args)
+            };
+            return Err(format!(
+                "dict.{}() takes {} argument{} ({} given)",
+                method,
+                expected,
+                if max_args == 1 { "" } else { "s" },
+                args.len()
+            ));
+        }
+
+        let dict_ptr = dict_val.into_pointer_value();
+        let none: BasicValueEnum<'ctx> = self.llvm_context.i32_type().const_zero().into();
+
+        match method {
+            "clear" => {
+                self.call_runtime_function("dict_clear", &[dict_ptr.into()])?;
+                Ok((none, Type::None))
+            }
+            "update" => {
+                let (other, other_type) = self.compile_expr(&args[0])?;
+                if !matches!(other_type, Type::Dict(..)) {
+                    return Err(format!(
+                        "dict.update() argument must be a dict, not {:?}",
+                        other_type
+                    ));
+                }
+                self.call_runtime_function("dict_update", &[dict_ptr.into(), other.into()])?;
+                Ok((none, Type::None))
+            }
+            _ => {
+                let (key_ptr, key_tag) = self.compile_dict_key(&args[0], key_type, method)?;
+                let null = self.llvm_context.ptr_type(AddressSpace::default()).const_null();
+
+                let value_ptr = match method {
+                    "setdefault" => {
+                        let (default_ptr, default_type) =
+                            self.compile_dict_default(&args[1], value_type)?;
+                        self.call_runtime_function(
+                            "dict_setdefault",
+                            &[
+                                dict_ptr.into(),
+                                key_ptr.into(),
+                                default_ptr.into(),
+                                key_tag.into(),
+                                self.type_tag(&default_type).into(),
+                            ],
+                        )?
+                    }
+                    _ => {
+                        // get() falls back to None; pop() reports a KeyError without a default
+                        let default_ptr = match args.get(1) {
+                            Some(default) => self.compile_dict_default(default, value_type)?.0,
+                            None if method == "get" && !is_reference_type(value_type) => {
+                                let llvm_type = self.get_llvm_type(value_type);
+                                let slot = self.builder.build_alloca(llvm_type, "dict_none").unwrap();
+                                self.builder.build_store(slot, llvm_type.const_zero()).unwrap();
+                                slot
+                            }
+                            None => null,
+                        };
+                        let fn_name = if method == "get" { "dict_get_default" } else { "dict_pop" };
+                        self.call_runtime_function(
+                            fn_name,
+                            &[dict_ptr.into(), key_ptr.into(), default_ptr.into(), key_tag.into()],
+                        )?
+                    }
+                }
+                .ok_or_else(|| format!("dict.{}() returned void", method))?
+                .into_pointer_value();
+
+                match value_type {
+                    Type::Unknown | Type::Any => Ok((value_ptr.into(), Type::Any)),
+                    ty if is_reference_type(ty) => Ok((value_ptr.into(), ty.clone())),
+                    ty => {
+                        let llvm_type = self.get_llvm_type(ty);
+                        let value = self
+                            .builder
+                            .build_load(llvm_type, value_ptr, &format!("dict_{}_value", method))
+                            .unwrap();
+                        Ok((value, ty.clone()))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Compile a key argument into a pointer and its runtime tag
+    fn compile_dict_key(
+        &mut self,
+        arg: &Expr,
+        key_type: &Type,
+        method: &str,
+    ) -> Result<(PointerValue<'ctx>, inkwell::values::IntValue<'ctx>), String> {
+        let (key, ty) = self.compile_expr(arg)?;
+        if !matches!(key_type, Type::Unknown | Type::Any) && !ty.can_coerce_to(key_type) {
+            return Err(format!(
+                "dict.{}() key must be {:?}, not {:?}",
+                method, key_type, ty
+            ));
+        }
+
+        let tag = self.type_tag(&ty);
+        if is_reference_type(&ty) && key.is_pointer_value() {
+            return Ok((key.into_pointer_value(), tag));
+        }
+
+        let slot = self.builder.build_alloca(key.get_type(), "dict_key").unwrap();
+        self.builder.build_store(slot, key).unwrap();
+        Ok((slot, tag))
+    }
+
+    /// Compile a default value, converting numbers to the dict's value type
+    fn compile_dict_default(
+        &mut self,
+        arg: &Expr,
+        value_type: &Type,
+    ) -> Result<(PointerValue<'ctx>, Type), String> {
+        let (value, ty) = self.compile_expr(arg)?;
+        if is_reference_type(&ty) && value.is_pointer_value() {
+            return Ok((value.into_pointer_value(), ty));
+        }
+
+        let converts = ty.numeric_rank().is_some() && value_type.numeric_rank().is_some();
+        let (value, ty) = if converts && ty != *value_type {
+            (self.convert_type(value, &ty, value_type)?, value_type.clone())
+        } else {
+            (value, ty)
+        };
+
+        let slot = self.builder.build_alloca(value.get_type(), "dict_default").unwrap();
+        self.builder.build_store(slot, value).unwrap();
+        Ok((slot, ty))
+    }
+}
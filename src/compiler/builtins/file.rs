@@ -0,0 +1,128 @@
+// file.rs - Compilation of open() and file object methods
+
+use crate::ast::Expr;
+use crate::compiler::context::CompilationContext;
+use crate::compiler::expr::ExprCompiler;
+use crate::compiler::types::Type;
+use inkwell::values::BasicValueEnum;
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// Whether a call to `open` refers to the built-in rather than a user definition
+    pub fn is_open_builtin(&self, name: &str) -> bool {
+        name == "open"
+            && self.runtime_function(name).is_none()
+            && self.scope_stack.get_variable(name).is_none()
+    }
+
+    /// Compile a call to open(path[, mode][, encoding=...]), returning a file object
+    pub fn compile_open_call(
+        &mut self,
+        args: &[Box<Expr>],
+        keywords: &[(Option<String>, Box<Expr>)],
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        if args.is_empty() || args.len() > 2 {
+            return Err(format!(
+                "open() takes 1 or 2 positional arguments ({} given)",
+                args.len()
+            ));
+        }
+
+        let mut mode_arg = args.get(1).map(|arg| arg.as_ref());
+        let mut encoding_arg = None;
+        for (name, value) in keywords {
+            match name.as_deref() {
+                Some("mode") if mode_arg.is_none() => mode_arg = Some(value.as_ref()),
+                Some("encoding") if encoding_arg.is_none() => encoding_arg = Some(value.as_ref()),
+                Some(name @ ("mode" | "encoding")) => {
+                    return Err(format!("open() got multiple values for argument '{}'", name))
+                }
+                Some(name) => return Err(format!("open() got an unexpected keyword argument '{}'", name)),
+                None => return Err("open() does not take **kwargs".to_string()),
+            }
+        }
+
+        let path = self.compile_string_arg(&args[0], "open", "path")?;
+        let mode = match mode_arg {
+            Some(arg) => self.compile_string_arg(arg, "open", "mode")?,
+            None => self.make_cstr("open_mode_r", b"r\0").into(),
+        };
+
+        let file = match encoding_arg {
+            Some(arg) => {
+                let encoding = self.compile_string_arg(arg, "open", "encoding")?;
+                self.call_runtime_function("file_open_encoded", &[path.into(), mode.into(), encoding.into()])?
+                    .ok_or("file_open_encoded returned void")?
+            }
+            None => self
+                .call_runtime_function("file_open", &[path.into(), mode.into()])?
+                .ok_or("file_open returned void")?,
+        };
+        Ok((file, Type::file()))
+    }
+
+    /// Compile a method call on a file object
+    pub fn compile_file_method_call(
+        &mut self,
+        file: BasicValueEnum<'ctx>,
+        method: &str,
+        args: &[Box<Expr>],
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        let expected_args = if method == "write" { 1 } else { 0 };
+        if args.len() != expected_args {
+            return Err(format!(
+                "file.{}() takes {} argument{} ({} given)",
+                method,
+                expected_args,
+                if expected_args == 1 { "" } else { "s" },
+                args.len()
+            ));
+        }
+
+        match method {
+            "read" | "readline" => {
+                let text = self
+                    .call_runtime_function(&format!("file_{}", method), &[file.into()])?
+                    .ok_or_else(|| format!("file_{} returned void", method))?;
+                Ok((text, Type::String))
+            }
+            "write" => {
+                let text = self.compile_string_arg(&args[0], "write", "text")?;
+                let written = self
+                    .call_runtime_function("file_write", &[file.into(), text.into()])?
+                    .ok_or("file_write returned void")?;
+                Ok((written, Type::Int))
+            }
+            "close" => {
+                self.call_runtime_function("file_close", &[file.into()])?;
+                Ok((self.llvm_context.i32_type().const_zero().into(), Type::None))
+            }
+            _ => Err(format!("'File' object has no attribute '{}'", method)),
+        }
+    }
+
+    /// `file.closed`
+    pub fn compile_file_closed(
+        &mut self,
+        file: BasicValueEnum<'ctx>,
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        let closed = self
+            .call_runtime_function("file_closed", &[file.into()])?
+            .ok_or("file_closed returned void")?;
+        Ok((closed, Type::Bool))
+    }
+
+    fn compile_string_arg(
+        &mut self,
+        arg: &Expr,
+        function: &str,
+        param: &str,
+    ) -> Result<BasicValueEnum<'ctx>, String> {
+        match self.compile_expr(arg)? {
+            (value, Type::String) => Ok(value),
+            (_, other) => Err(format!(
+                "{}() {} must be a string, not {:?}",
+                function, param, other
+            )),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+// input.rs - Compilation of the input() built-in
+
+use crate::ast::Expr;
+use crate::compiler::context::CompilationContext;
+use crate::compiler::expr::ExprCompiler;
+use crate::compiler::types::Type;
+use inkwell::AddressSpace;
+use inkwell::values::BasicValueEnum;
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// Whether a call to `input` refers to the built-in rather than a user definition
+    pub fn is_input_builtin(&self, name: &str) -> bool {
+        name == "input"
+            && self.runtime_function(name).is_none()
+            && self.scope_stack.get_variable(name).is_none()
+    }
+
+    /// Compile a call to input([prompt]), returning the line read as a new string
+    pub fn compile_input_call(
+        &mut self,
+        args: &[Box<Expr>],
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        if args.len() > 1 {
+            return Err(format!(
+                "input expected at most 1 argument, got {}",
+                args.len()
+            ));
+        }
+
+        let prompt = match args.first() {
+            Some(arg) => match self.compile_expr(arg)? {
+                (value, Type::String) => value,
+                (_, other) => {
+                    return Err(format!(
+                        "input() prompt must be a string, not {:?}",
+                        other
+                    ))
+                }
+            },
+            None => self
+                .llvm_context
+                .ptr_type(AddressSpace::default())
+                .const_null()
+                .into(),
+        };
+
+        let line = self
+            .call_runtime_function("input_string", &[prompt.into()])?
+            .ok_or("input_string returned void")?;
+        Ok((line, Type::String))
+    }
+}
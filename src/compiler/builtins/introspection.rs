@@ -0,0 +1,181 @@
+// introspection.rs - Compilation of the dir(), getattr() and setattr() built-ins
+//
+// They work on class instances and class objects through the instance
+// runtime's attribute tables, so attributes can be listed, read and written
+// by a name computed when the program runs:
+//
+//     dir(p)                      # instance_dir(p), a sorted list of names
+//     getattr(p, "x")             # the same as p.x
+//     getattr(p, name, 0)         # instance_get_attr_or(p, name, 0)
+//     setattr(p, name, 1.5)       # instance_set_attr(p, name, <bits>, null)
+//
+// A literal attribute name gives getattr() the attribute's declared type.
+// With a computed name the result takes the type of the default, so one is
+// required.
+
+use crate::ast::Expr;
+use crate::compiler::context::CompilationContext;
+use crate::compiler::expr::ExprCompiler;
+use crate::compiler::types::Type;
+use inkwell::values::{BasicValueEnum, PointerValue};
+
+/// Attribute introspection built-ins
+pub const INTROSPECTION_BUILTINS: &[&str] = &["dir", "getattr", "setattr"];
+
+/// The attribute name of a call, when it is a string literal
+fn literal_name(expr: &Expr) -> Option<&str> {
+    match expr {
+        Expr::Str { value, .. } => Some(value),
+        _ => None,
+    }
+}
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// Whether a call to `name` refers to an introspection built-in rather than a user definition
+    pub fn is_introspection_builtin(&self, name: &str) -> bool {
+        INTROSPECTION_BUILTINS.contains(&name)
+            && self.runtime_function(name).is_none()
+            && self.scope_stack.get_variable(name).is_none()
+    }
+
+    /// Compile a call to dir(), getattr() or setattr()
+    pub fn compile_introspection_call(
+        &mut self,
+        name: &str,
+        args: &[Box<Expr>],
+        keywords: &[(Option<String>, Box<Expr>)],
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        if !keywords.is_empty() {
+            return Err(format!("{}() takes no keyword arguments", name));
+        }
+        match name {
+            "dir" => self.compile_dir_call(args),
+            "getattr" => self.compile_getattr_call(args),
+            "setattr" => self.compile_setattr_call(args),
+            _ => Err(format!("{}() is not an introspection built-in", name)),
+        }
+    }
+
+    /// Compile the object argument of `function`, a class instance or a class object
+    fn compile_attribute_owner(
+        &mut self,
+        arg: &Expr,
+        function: &str,
+    ) -> Result<(PointerValue<'ctx>, Type), String> {
+        let (value, ty) = self.compile_expr(arg)?;
+        match ty {
+            Type::Class { .. } if !ty.is_exception() && !ty.is_file() => Ok((value.into_pointer_value(), ty)),
+            Type::ClassObject(_) => Ok((value.into_pointer_value(), ty)),
+            _ => Err(format!(
+                "{}() is only supported on class instances and classes, not {}",
+                function, ty
+            )),
+        }
+    }
+
+    /// Compile the attribute name argument of `function` to a C string
+    fn compile_attribute_name(&mut self, arg: &Expr, function: &str) -> Result<PointerValue<'ctx>, String> {
+        if let Some(name) = literal_name(arg) {
+            return Ok(self.string_constant(name.as_bytes()));
+        }
+        match self.compile_expr(arg)? {
+            (value, Type::String) => Ok(value.into_pointer_value()),
+            (_, ty) => Err(format!("{}(): attribute name must be a string, not {}", function, ty)),
+        }
+    }
+
+    /// dir(obj) -> sorted list of the attribute and method names of obj
+    fn compile_dir_call(&mut self, args: &[Box<Expr>]) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        if args.len() != 1 {
+            return Err(format!("dir expected 1 argument, got {}", args.len()));
+        }
+        let (owner, owner_type) = self.compile_attribute_owner(&args[0], "dir")?;
+        let function = match owner_type {
+            Type::ClassObject(_) => "class_dir",
+            _ => "instance_dir",
+        };
+        let names = self
+            .call_runtime_function(function, &[owner.into()])?
+            .ok_or_else(|| "Failed to list attributes".to_string())?;
+        Ok((names, Type::List(Box::new(Type::String))))
+    }
+
+    /// getattr(obj, name[, default]) -> the attribute, or default when it isn't set
+    fn compile_getattr_call(&mut self, args: &[Box<Expr>]) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        if args.len() != 2 && args.len() != 3 {
+            return Err(format!("getattr expected 2 or 3 arguments, got {}", args.len()));
+        }
+        let literal = literal_name(&args[1]);
+        if let (Some(attr), None) = (literal, args.get(2)) {
+            let (owner, owner_type) = self.compile_attribute_owner(&args[0], "getattr")?;
+            return match &owner_type {
+                Type::ClassObject(class) => self.compile_class_attribute_load(owner.into(), class, attr),
+                _ => self.compile_instance_load(owner.into(), &owner_type, attr),
+            };
+        }
+        let Some(default) = args.get(2) else {
+            return Err(
+                "getattr() with a computed attribute name needs a default to give the result a type".to_string(),
+            );
+        };
+
+        let (owner, owner_type) = self.compile_attribute_owner(&args[0], "getattr")?;
+        let name = self.compile_attribute_name(&args[1], "getattr")?;
+        let declared = literal.and_then(|attr| match &owner_type {
+            Type::ClassObject(class) => self
+                .class_attribute_type(class, attr)
+                .map(|(_, ty)| ty.clone())
+                .filter(|ty| *ty != Type::Unknown),
+            _ => Some(self.instance_attribute_type(&owner_type, attr)).filter(|ty| *ty != Type::Any),
+        });
+
+        let (default, default_type) = self.compile_expr(default)?;
+        let result_type = declared.unwrap_or_else(|| default_type.clone());
+        let default = self.convert_type(default, &default_type, &result_type)?;
+        let default = self.word_from_value(default, &result_type)?;
+
+        let function = match owner_type {
+            Type::ClassObject(_) => "class_get_attr_or",
+            _ => "instance_get_attr_or",
+        };
+        let word = self
+            .call_runtime_function(function, &[owner.into(), name.into(), default.into()])?
+            .ok_or_else(|| "Failed to load attribute".to_string())?
+            .into_int_value();
+        Ok((self.value_from_word(word, &result_type), result_type))
+    }
+
+    /// setattr(obj, name, value) -> None
+    fn compile_setattr_call(&mut self, args: &[Box<Expr>]) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        if args.len() != 3 {
+            return Err(format!("setattr expected 3 arguments, got {}", args.len()));
+        }
+        let (owner, owner_type) = self.compile_attribute_owner(&args[0], "setattr")?;
+        let literal = literal_name(&args[1]);
+        let name = self.compile_attribute_name(&args[1], "setattr")?;
+        let (value, value_type) = self.compile_expr(&args[2])?;
+
+        match (&owner_type, literal) {
+            (Type::ClassObject(class), Some(attr)) => {
+                self.store_class_attribute(owner, class, attr, value, &value_type)?;
+            }
+            (Type::ClassObject(_), None) => {
+                let word = self.word_from_value(value, &value_type)?;
+                self.call_runtime_function("class_set_attr", &[owner.into(), name.into(), word.into()])?;
+            }
+            (_, Some(attr)) => {
+                self.compile_instance_store(owner.into(), attr, value, &value_type)?;
+            }
+            (_, None) => {
+                // Computed names skip the per-site cache
+                let word = self.word_from_value(value, &value_type)?;
+                let no_cache = self.llvm_context.ptr_type(inkwell::AddressSpace::default()).const_null();
+                self.call_runtime_function(
+                    "instance_set_attr",
+                    &[owner.into(), name.into(), word.into(), no_cache.into()],
+                )?;
+            }
+        }
+        self.compile_name_constant(&crate::ast::NameConstant::None)
+    }
+}
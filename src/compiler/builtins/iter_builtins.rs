@@ -0,0 +1,657 @@
+// iter_builtins.rs - Compilation of the enumerate(), zip(), map() and filter() built-ins
+
+use crate::ast::Expr;
+use crate::compiler::context::CompilationContext;
+use crate::compiler::expr::ExprCompiler;
+use crate::compiler::types::{is_reference_type, Type};
+use inkwell::types::BasicTypeEnum;
+use inkwell::values::{BasicValueEnum, FunctionValue, IntValue, PointerValue};
+use inkwell::{FloatPredicate, IntPredicate};
+
+/// Iteration built-ins; each one is evaluated eagerly into a new list
+pub const ITER_BUILTINS: &[&str] = &["enumerate", "zip", "map", "filter"];
+
+/// A variable bound by a loop target, with the field path into the item it reads
+pub(crate) type IterationSlot<'ctx> = (PointerValue<'ctx>, Vec<u32>, Type);
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// Whether a call to `name` refers to an iteration built-in rather than a user definition
+    pub fn is_iter_builtin(&self, name: &str) -> bool {
+        ITER_BUILTINS.contains(&name)
+            && self.runtime_function(name).is_none()
+            && self.scope_stack.get_variable(name).is_none()
+    }
+
+    /// Compile a call to enumerate(), zip(), map() or filter()
+    pub fn compile_iter_builtin_call(
+        &mut self,
+        name: &str,
+        args: &[Box<Expr>],
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        match name {
+            "enumerate" => self.compile_enumerate_call(args),
+            "zip" => self.compile_zip_call(args),
+            "map" | "filter" => self.compile_map_filter_call(name, args),
+            _ => Err(format!("{}() is not an iteration built-in", name)),
+        }
+    }
+
+    /// enumerate(list[, start]) -> list of (index, item) tuples
+    fn compile_enumerate_call(
+        &mut self,
+        args: &[Box<Expr>],
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        if args.is_empty() || args.len() > 2 {
+            return Err(format!(
+                "enumerate() takes 1 to 2 arguments ({} given)",
+                args.len()
+            ));
+        }
+
+        let (list_ptr, item_type) = self.compile_iterable_arg(&args[0], "enumerate")?;
+        let start = match args.get(1) {
+            Some(arg) => {
+                let (value, ty) = self.compile_expr(arg)?;
+                if !matches!(ty, Type::Int | Type::Bool) {
+                    return Err(format!(
+                        "enumerate() start must be an integer, not {:?}",
+                        ty
+                    ));
+                }
+                self.convert_type(value, &ty, &Type::Int)?.into_int_value()
+            }
+            None => self.llvm_context.i64_type().const_zero(),
+        };
+
+        let len = self.build_list_len(list_ptr)?;
+        let result = self.build_list_with_capacity(len)?;
+        let tuple_types = vec![Type::Int, item_type.clone()];
+
+        self.build_index_loop(len, "enumerate", |ctx, index| {
+            let count = ctx
+                .builder
+                .build_int_add(start, index, "enumerate_count")
+                .unwrap();
+            let item = ctx.load_list_item(list_ptr, index, &item_type)?;
+            let tuple = ctx.box_tuple(&[count.into(), item], &tuple_types)?;
+            ctx.append_list_item(result, tuple, &Type::Tuple(tuple_types.clone()))
+        })?;
+
+        Ok((result.into(), Type::List(Box::new(Type::Tuple(tuple_types)))))
+    }
+
+    /// zip(a, b, ...) -> list of tuples, as long as the shortest argument
+    fn compile_zip_call(
+        &mut self,
+        args: &[Box<Expr>],
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        if args.is_empty() {
+            return Err("zip() takes at least 1 argument (0 given)".to_string());
+        }
+
+        let mut lists = Vec::with_capacity(args.len());
+        for arg in args {
+            lists.push(self.compile_iterable_arg(arg, "zip")?);
+        }
+
+        let len = self.build_shortest_len(&lists)?;
+        let result = self.build_list_with_capacity(len)?;
+        let tuple_types: Vec<Type> = lists.iter().map(|(_, ty)| ty.clone()).collect();
+
+        self.build_index_loop(len, "zip", |ctx, index| {
+            let mut items = Vec::with_capacity(lists.len());
+            for (list_ptr, item_type) in &lists {
+                items.push(ctx.load_list_item(*list_ptr, index, item_type)?);
+            }
+            let tuple = ctx.box_tuple(&items, &tuple_types)?;
+            ctx.append_list_item(result, tuple, &Type::Tuple(tuple_types.clone()))
+        })?;
+
+        Ok((result.into(), Type::List(Box::new(Type::Tuple(tuple_types)))))
+    }
+
+    /// map(fn, list, ...) and filter(fn, list), calling a compiled function per item
+    fn compile_map_filter_call(
+        &mut self,
+        name: &str,
+        args: &[Box<Expr>],
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        if name == "filter" && args.len() != 2 {
+            return Err(format!(
+                "filter() takes exactly 2 arguments ({} given)",
+                args.len()
+            ));
+        }
+        if args.len() < 2 {
+            return Err("map() must have at least two arguments".to_string());
+        }
+        if args.len() == 2 {
+            return self.compile_iter_pipeline(name, args);
+        }
+
+        let function = self.resolve_iter_callback(&args[0], name)?;
+        let mut lists = Vec::with_capacity(args.len() - 1);
+        for arg in &args[1..] {
+            lists.push(self.compile_iterable_arg(arg, name)?);
+        }
+        check_callback_arity(function, name, lists.len())?;
+
+        let return_type = callback_return_type(function, name)?;
+        let param_types = function.get_type().get_param_types();
+
+        let len = self.build_shortest_len(&lists)?;
+        let result = self.build_list_with_capacity(len)?;
+
+        self.build_index_loop(len, name, |ctx, index| {
+            let mut call_args = Vec::with_capacity(lists.len());
+            for ((list_ptr, item_type), param_type) in lists.iter().zip(&param_types) {
+                let item = ctx.load_list_item(*list_ptr, index, item_type)?;
+                let arg = match ctx.numeric_param_type(item_type, *param_type) {
+                    Some(target) => ctx.convert_type(item, item_type, &target)?,
+                    None => item,
+                };
+                call_args.push(arg.into());
+            }
+
+            let value = ctx
+                .builder
+                .build_call(function, &call_args, &format!("{}_call", name))
+                .unwrap()
+                .try_as_basic_value()
+                .left()
+                .ok_or_else(|| format!("{}() function returned void", name))?;
+            ctx.emit_call_check()?;
+            let boxed = ctx.box_list_item(value, &return_type)?;
+            ctx.append_list_item(result, boxed, &return_type)
+        })?;
+
+        Ok((result.into(), Type::List(Box::new(return_type))))
+    }
+
+    /// Compile a chain of single-iterable map() and filter() calls as one loop.
+    ///
+    /// `map(f, filter(p, xs))` runs `p` and then `f` on each item of `xs` in
+    /// turn, so no intermediate list is built between the stages.
+    fn compile_iter_pipeline(
+        &mut self,
+        name: &str,
+        args: &[Box<Expr>],
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        // Stages are collected outermost first and run innermost first
+        let mut stages = vec![(name, args[0].as_ref())];
+        let mut source = args[1].as_ref();
+        while let Some((inner_name, inner_args)) = self.fusable_iter_call(source) {
+            stages.push((inner_name, inner_args[0].as_ref()));
+            source = inner_args[1].as_ref();
+        }
+        stages.reverse();
+
+        let mut callbacks = Vec::with_capacity(stages.len());
+        for (stage, callback) in &stages {
+            let function = self.resolve_iter_callback(callback, stage)?;
+            check_callback_arity(function, stage, 1)?;
+            callbacks.push((*stage, function, callback_return_type(function, stage)?));
+        }
+
+        let (list_ptr, item_type) = self.compile_iterable_arg(source, stages[0].0)?;
+        let result_type = callbacks
+            .iter()
+            .rev()
+            .find(|(stage, _, _)| *stage == "map")
+            .map_or_else(|| item_type.clone(), |(_, _, ty)| ty.clone());
+
+        let len = self.build_list_len(list_ptr)?;
+        let result = self.build_list_with_capacity(len)?;
+        let current_function = self
+            .builder
+            .get_insert_block()
+            .and_then(|block| block.get_parent())
+            .ok_or("map()/filter() used outside of a function")?;
+
+        self.build_index_loop(len, name, |ctx, index| {
+            let mut value = ctx.load_list_item(list_ptr, index, &item_type)?;
+            let mut value_type = item_type.clone();
+            let next_block = ctx
+                .llvm_context
+                .append_basic_block(current_function, &format!("{}.next", name));
+
+            for (stage, function, return_type) in &callbacks {
+                let param_type = function.get_type().get_param_types()[0];
+                let arg = match ctx.numeric_param_type(&value_type, param_type) {
+                    Some(target) => ctx.convert_type(value, &value_type, &target)?,
+                    None => value,
+                };
+                let output = ctx
+                    .builder
+                    .build_call(*function, &[arg.into()], &format!("{}_call", stage))
+                    .unwrap()
+                    .try_as_basic_value()
+                    .left()
+                    .ok_or_else(|| format!("{}() function returned void", stage))?;
+                ctx.emit_call_check()?;
+
+                if *stage == "map" {
+                    value = output;
+                    value_type = return_type.clone();
+                    continue;
+                }
+
+                let keep_block = ctx
+                    .llvm_context
+                    .append_basic_block(current_function, "filter.keep");
+                let keep = ctx.build_truth_test(output)?;
+                ctx.builder
+                    .build_conditional_branch(keep, keep_block, next_block)
+                    .unwrap();
+                ctx.builder.position_at_end(keep_block);
+            }
+
+            let boxed = ctx.box_list_item(value, &value_type)?;
+            ctx.append_list_item(result, boxed, &value_type)?;
+            ctx.builder.build_unconditional_branch(next_block).unwrap();
+
+            ctx.builder.position_at_end(next_block);
+            Ok(())
+        })?;
+
+        Ok((result.into(), Type::List(Box::new(result_type))))
+    }
+
+    /// The name and arguments of a map() or filter() call over a single iterable
+    fn fusable_iter_call<'e>(&self, expr: &'e Expr) -> Option<(&'static str, &'e [Box<Expr>])> {
+        match expr {
+            Expr::Call { func, args, keywords, .. } if args.len() == 2 && keywords.is_empty() => {
+                let stage = match func.as_ref() {
+                    Expr::Name { id, .. } if id == "map" => "map",
+                    Expr::Name { id, .. } if id == "filter" => "filter",
+                    _ => return None,
+                };
+                self.is_iter_builtin(stage).then_some((stage, args.as_slice()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Allocate the variables named by a loop target for items of `item_type`.
+    ///
+    /// Tuple targets unpack tuple items field by field, recursively.
+    pub(crate) fn declare_iteration_target(
+        &mut self,
+        target: &Expr,
+        item_type: &Type,
+    ) -> Result<Vec<IterationSlot<'ctx>>, String> {
+        let mut slots = Vec::new();
+        self.declare_iteration_target_at(target, item_type, Vec::new(), &mut slots)?;
+        Ok(slots)
+    }
+
+    fn declare_iteration_target_at(
+        &mut self,
+        target: &Expr,
+        item_type: &Type,
+        path: Vec<u32>,
+        slots: &mut Vec<IterationSlot<'ctx>>,
+    ) -> Result<(), String> {
+        match target {
+            Expr::Name { id, .. } => {
+                let llvm_type = self.get_llvm_type(item_type);
+                let slot = self.builder.build_alloca(llvm_type, id).unwrap();
+                self.scope_stack
+                    .add_variable(id.clone(), slot, item_type.clone());
+                slots.push((slot, path, item_type.clone()));
+                Ok(())
+            }
+            Expr::Tuple { elts, .. } | Expr::List { elts, .. } => match item_type {
+                Type::Tuple(field_types) if field_types.len() == elts.len() => {
+                    for (i, (elt, field_type)) in elts.iter().zip(field_types).enumerate() {
+                        let mut field_path = path.clone();
+                        field_path.push(i as u32);
+                        self.declare_iteration_target_at(elt, field_type, field_path, slots)?;
+                    }
+                    Ok(())
+                }
+                _ => Err(format!(
+                    "cannot unpack {:?} into {} loop variables",
+                    item_type,
+                    elts.len()
+                )),
+            },
+            _ => Err("Unsupported loop target".to_string()),
+        }
+    }
+
+    /// Store one item into the variables of a declared loop target
+    pub(crate) fn store_iteration_target(
+        &mut self,
+        slots: &[IterationSlot<'ctx>],
+        item: BasicValueEnum<'ctx>,
+    ) -> Result<(), String> {
+        for (slot, path, _) in slots {
+            let mut value = item;
+            for index in path {
+                value = self
+                    .builder
+                    .build_extract_value(value.into_struct_value(), *index, "unpack")
+                    .map_err(|e| format!("Failed to unpack loop item: {}", e))?;
+            }
+            self.builder.build_store(*slot, value).unwrap();
+        }
+        Ok(())
+    }
+
+    /// The list a for loop over `value` walks, with the type of its items, or
+    /// None when `value` is an int counting the loop's iterations. Dicts give
+    /// their keys; strings and values whose type is only known at runtime go
+    /// through iter_items(), which dispatches on the runtime tag.
+    pub(crate) fn iteration_list(
+        &mut self,
+        value: BasicValueEnum<'ctx>,
+        ty: &Type,
+    ) -> Result<Option<(PointerValue<'ctx>, Type)>, String> {
+        let (list_ptr, item_type) = match ty {
+            Type::Int => return Ok(None),
+            Type::List(elem) if value.is_pointer_value() => {
+                let item_type = match &**elem {
+                    Type::Unknown => Type::Any,
+                    ty => ty.clone(),
+                };
+                (value.into_pointer_value(), item_type)
+            }
+            Type::Dict(key_type, _) if value.is_pointer_value() => {
+                let keys = self
+                    .call_runtime_function("dict_keys", &[value.into()])?
+                    .ok_or("dict_keys returned void")?
+                    .into_pointer_value();
+                (keys, (**key_type).clone())
+            }
+            Type::String | Type::Any | Type::Unknown => {
+                let value = match value {
+                    BasicValueEnum::PointerValue(ptr) => ptr,
+                    BasicValueEnum::IntValue(int) => self
+                        .builder
+                        .build_int_to_ptr(int, self.llvm_context.ptr_type(inkwell::AddressSpace::default()), "iter_value")
+                        .unwrap(),
+                    _ => return Err(format!("'{}' object is not iterable", ty)),
+                };
+                let tag = self.type_tag(ty);
+                let items = self
+                    .call_runtime_function("iter_items", &[value.into(), tag.into()])?
+                    .ok_or("iter_items returned void")?
+                    .into_pointer_value();
+                let item_type = match ty {
+                    Type::String => Type::String,
+                    _ => Type::Any,
+                };
+                (items, item_type)
+            }
+            _ => return Err(format!("'{}' object is not iterable", ty)),
+        };
+        let item_type = match item_type {
+            Type::Unknown => Type::Any,
+            ty => ty,
+        };
+        Ok(Some((list_ptr, item_type)))
+    }
+
+    /// Load item `index` of a list as a value of its static type
+    pub(crate) fn load_list_item(
+        &mut self,
+        list_ptr: PointerValue<'ctx>,
+        index: IntValue<'ctx>,
+        item_type: &Type,
+    ) -> Result<BasicValueEnum<'ctx>, String> {
+        let item_ptr = self
+            .call_runtime_function("list_get", &[list_ptr.into(), index.into()])?
+            .ok_or("list_get returned void")?
+            .into_pointer_value();
+
+        Ok(self.unbox_list_item(item_ptr, item_type))
+    }
+
+    /// The value of type `ty` a list stores under `item_ptr`, as boxed by `box_list_item`
+    pub(crate) fn unbox_list_item(&mut self, item_ptr: PointerValue<'ctx>, ty: &Type) -> BasicValueEnum<'ctx> {
+        if is_boxed_in_place(ty) {
+            return item_ptr.into();
+        }
+
+        let llvm_type = self.get_llvm_type(ty);
+        self.builder.build_load(llvm_type, item_ptr, "list_item").unwrap()
+    }
+
+    /// Give a value the pointer representation a list stores it under
+    pub(crate) fn box_list_item(
+        &mut self,
+        value: BasicValueEnum<'ctx>,
+        ty: &Type,
+    ) -> Result<PointerValue<'ctx>, String> {
+        if is_boxed_in_place(ty) && value.is_pointer_value() {
+            return Ok(value.into_pointer_value());
+        }
+
+        let slot = self
+            .builder
+            .build_malloc(value.get_type(), "list_item_box")
+            .map_err(|e| format!("Failed to allocate list item: {}", e))?;
+        self.builder.build_store(slot, value).unwrap();
+        Ok(slot)
+    }
+
+    /// Allocate a heap tuple holding `values`, freed by the owning list
+    fn box_tuple(
+        &mut self,
+        values: &[BasicValueEnum<'ctx>],
+        types: &[Type],
+    ) -> Result<PointerValue<'ctx>, String> {
+        let field_types: Vec<BasicTypeEnum> = types.iter().map(|ty| self.get_llvm_type(ty)).collect();
+        let struct_type = self.llvm_context.struct_type(&field_types, false);
+        let tuple_ptr = self
+            .builder
+            .build_malloc(struct_type, "tuple_box")
+            .map_err(|e| format!("Failed to allocate tuple: {}", e))?;
+
+        for (i, value) in values.iter().enumerate() {
+            let field_ptr = self
+                .builder
+                .build_struct_gep(struct_type, tuple_ptr, i as u32, &format!("tuple_field_{}", i))
+                .unwrap();
+            self.builder.build_store(field_ptr, *value).unwrap();
+        }
+        Ok(tuple_ptr)
+    }
+
+    pub(crate) fn append_list_item(
+        &mut self,
+        list_ptr: PointerValue<'ctx>,
+        item_ptr: PointerValue<'ctx>,
+        ty: &Type,
+    ) -> Result<(), String> {
+        let tag = self.type_tag(ty);
+        self.call_runtime_function(
+            "list_append_tagged",
+            &[list_ptr.into(), item_ptr.into(), tag.into()],
+        )?;
+        Ok(())
+    }
+
+    /// Compile an argument that must evaluate to a list, returning its item type
+    pub(crate) fn compile_iterable_arg(
+        &mut self,
+        arg: &Expr,
+        builtin: &str,
+    ) -> Result<(PointerValue<'ctx>, Type), String> {
+        let (value, ty) = self.compile_expr(arg)?;
+        match ty {
+            Type::List(item_type) if value.is_pointer_value() => {
+                let item_type = match *item_type {
+                    Type::Unknown => Type::Any,
+                    ty => ty,
+                };
+                Ok((value.into_pointer_value(), item_type))
+            }
+            other => Err(format!(
+                "{}() argument must be a list, not {:?}",
+                builtin, other
+            )),
+        }
+    }
+
+    /// Look up the module-level function passed to map() or filter()
+    pub(crate) fn resolve_iter_callback(
+        &self,
+        arg: &Expr,
+        builtin: &str,
+    ) -> Result<FunctionValue<'ctx>, String> {
+        match arg {
+            Expr::Name { id, .. } => self
+                .runtime_function(id)
+                .ok_or_else(|| format!("{}() function '{}' is not defined", builtin, id)),
+            _ => Err(format!(
+                "{}() first argument must be the name of a function",
+                builtin
+            )),
+        }
+    }
+
+    pub(crate) fn build_list_len(&mut self, list_ptr: PointerValue<'ctx>) -> Result<IntValue<'ctx>, String> {
+        Ok(self
+            .call_runtime_function("list_len", &[list_ptr.into()])?
+            .ok_or("list_len returned void")?
+            .into_int_value())
+    }
+
+    pub(crate) fn build_list_with_capacity(&mut self, capacity: IntValue<'ctx>) -> Result<PointerValue<'ctx>, String> {
+        Ok(self
+            .call_runtime_function("list_with_capacity", &[capacity.into()])?
+            .ok_or("list_with_capacity returned void")?
+            .into_pointer_value())
+    }
+
+    /// Length of the shortest of several lists
+    fn build_shortest_len(&mut self, lists: &[(PointerValue<'ctx>, Type)]) -> Result<IntValue<'ctx>, String> {
+        let mut shortest = self.build_list_len(lists[0].0)?;
+        for (list_ptr, _) in &lists[1..] {
+            let len = self.build_list_len(*list_ptr)?;
+            let shorter = self
+                .builder
+                .build_int_compare(IntPredicate::SLT, len, shortest, "shorter")
+                .unwrap();
+            shortest = self
+                .builder
+                .build_select(shorter, len, shortest, "shortest_len")
+                .unwrap()
+                .into_int_value();
+        }
+        Ok(shortest)
+    }
+
+    /// Emit a loop running `body` for each index in `0..len`
+    pub(crate) fn build_index_loop<F>(&mut self, len: IntValue<'ctx>, name: &str, mut body: F) -> Result<(), String>
+    where
+        F: FnMut(&mut Self, IntValue<'ctx>) -> Result<(), String>,
+    {
+        let i64_type = self.llvm_context.i64_type();
+        let function = self
+            .builder
+            .get_insert_block()
+            .and_then(|block| block.get_parent())
+            .ok_or_else(|| format!("{}() used outside of a function", name))?;
+
+        let cond_block = self
+            .llvm_context
+            .append_basic_block(function, &format!("{}.cond", name));
+        let body_block = self
+            .llvm_context
+            .append_basic_block(function, &format!("{}.body", name));
+        let end_block = self
+            .llvm_context
+            .append_basic_block(function, &format!("{}.end", name));
+
+        let index_ptr = self
+            .builder
+            .build_alloca(i64_type, &format!("{}.index", name))
+            .unwrap();
+        self.builder.build_store(index_ptr, i64_type.const_zero()).unwrap();
+        self.builder.build_unconditional_branch(cond_block).unwrap();
+
+        self.builder.position_at_end(cond_block);
+        let index = self
+            .builder
+            .build_load(i64_type, index_ptr, "index")
+            .unwrap()
+            .into_int_value();
+        let more = self
+            .builder
+            .build_int_compare(IntPredicate::SLT, index, len, "more")
+            .unwrap();
+        self.builder
+            .build_conditional_branch(more, body_block, end_block)
+            .unwrap();
+
+        self.builder.position_at_end(body_block);
+        body(self, index)?;
+        let next = self
+            .builder
+            .build_int_add(index, i64_type.const_int(1, false), "next_index")
+            .unwrap();
+        self.builder.build_store(index_ptr, next).unwrap();
+        self.builder.build_unconditional_branch(cond_block).unwrap();
+
+        self.builder.position_at_end(end_block);
+        Ok(())
+    }
+
+    /// Python truthiness of a callback result
+    fn build_truth_test(&mut self, value: BasicValueEnum<'ctx>) -> Result<IntValue<'ctx>, String> {
+        let test = match value {
+            BasicValueEnum::IntValue(int) => self
+                .builder
+                .build_int_compare(IntPredicate::NE, int, int.get_type().const_zero(), "truthy")
+                .unwrap(),
+            BasicValueEnum::FloatValue(float) => self
+                .builder
+                .build_float_compare(FloatPredicate::ONE, float, float.get_type().const_zero(), "truthy")
+                .unwrap(),
+            BasicValueEnum::PointerValue(ptr) => self.builder.build_is_not_null(ptr, "truthy").unwrap(),
+            other => return Err(format!("filter() cannot test {:?} for truth", other.get_type())),
+        };
+        Ok(test)
+    }
+}
+
+/// Whether a list stores values of this type as the pointer itself rather than a boxed copy
+fn is_boxed_in_place(ty: &Type) -> bool {
+    is_reference_type(ty) || matches!(ty, Type::Any | Type::Unknown)
+}
+
+/// Reject a map() or filter() function that cannot take one item from each iterable
+fn check_callback_arity(function: FunctionValue, builtin: &str, iterables: usize) -> Result<(), String> {
+    if function.count_params() as usize == iterables {
+        return Ok(());
+    }
+    Err(format!(
+        "{}() function takes {} argument{} but {} iterable{} given",
+        builtin,
+        function.count_params(),
+        if function.count_params() == 1 { "" } else { "s" },
+        iterables,
+        if iterables == 1 { " was" } else { "s were" }
+    ))
+}
+
+/// The static type of the value a map() or filter() function returns
+fn callback_return_type(function: FunctionValue, builtin: &str) -> Result<Type, String> {
+    match function.get_type().get_return_type() {
+        Some(ty) => Ok(value_type_of(ty)),
+        None => Err(format!("{}() function must return a value", builtin)),
+    }
+}
+
+/// The static type of a value returned with the given LLVM type
+pub(crate) fn value_type_of(ty: BasicTypeEnum) -> Type {
+    match ty {
+        BasicTypeEnum::FloatType(_) => Type::Float,
+        BasicTypeEnum::IntType(int) if int.get_bit_width() == 1 => Type::Bool,
+        BasicTypeEnum::IntType(_) => Type::Int,
+        _ => Type::Any,
+    }
+}
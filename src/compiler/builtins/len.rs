@@ -15,28 +15,28 @@ impl<'ctx> CompilationContext<'ctx> {
         let ptr_type = context.ptr_type(AddressSpace::default());
 
         // len()
-        if module.get_function("len").is_none() {
+        if !self.runtime_catalog.provides(context, "len") && module.get_function("len").is_none() {
             let fn_type = context.i64_type().fn_type(&[ptr_type.into()], false);
             let function = module.add_function("len", fn_type, None);
             self.functions.insert("len".to_string(), function);
         }
 
         // list_len()
-        if module.get_function("list_len").is_none() {
+        if !self.runtime_catalog.provides(context, "list_len") && module.get_function("list_len").is_none() {
             let list_len_type = context.i64_type().fn_type(&[ptr_type.into()], false);
             let list_len_fn = module.add_function("list_len", list_len_type, None);
             self.functions.insert("list_len".to_string(), list_len_fn);
         }
 
         // string_len()
-        if module.get_function("string_len").is_none() {
+        if !self.runtime_catalog.provides(context, "string_len") && module.get_function("string_len").is_none() {
             let string_len_type = context.i64_type().fn_type(&[ptr_type.into()], false);
             let string_len_fn = module.add_function("string_len", string_len_type, None);
             self.functions.insert("string_len".to_string(), string_len_fn);
         }
 
         // dict_len()
-        if module.get_function("dict_len").is_none() {
+        if !self.runtime_catalog.provides(context, "dict_len") && module.get_function("dict_len").is_none() {
             let dict_len_type = context.i64_type().fn_type(&[ptr_type.into()], false);
             let dict_len_fn = module.add_function("dict_len", dict_len_type, None);
             self.functions.insert("dict_len".to_string(), dict_len_fn);
@@ -76,7 +76,7 @@ impl<'ctx> CompilationContext<'ctx> {
             _ => return Err(format!("Object of type '{:?}' has no len()", arg_type)),
         };
 
-        let fn_val = self.module.get_function(fn_name)
+        let fn_val = self.runtime_function(fn_name)
             .ok_or_else(|| format!("{} function not found", fn_name))?;
 
         // Ensure pointer
@@ -103,7 +103,7 @@ impl<'ctx> CompilationContext<'ctx> {
         &self,
         value: BasicValueEnum<'ctx>,
     ) -> Result<BasicValueEnum<'ctx>, String> {
-        let f = self.module.get_function("string_len")
+        let f = self.runtime_function("string_len")
             .ok_or("string_len function not found".to_string())?;
         let ptr = value.into_pointer_value();
         let call = self.builder.build_call(f, &[ptr.into()], "str_len").unwrap();
@@ -114,7 +114,7 @@ impl<'ctx> CompilationContext<'ctx> {
         &self,
         value: BasicValueEnum<'ctx>,
     ) -> Result<BasicValueEnum<'ctx>, String> {
-        let f = self.module.get_function("list_len")
+        let f = self.runtime_function("list_len")
             .ok_or("list_len function not found".to_string())?;
         let ptr = value.into_pointer_value();
         let call = self.builder.build_call(f, &[ptr.into()], "list_len").unwrap();
@@ -125,7 +125,7 @@ impl<'ctx> CompilationContext<'ctx> {
         &self,
         value: BasicValueEnum<'ctx>,
     ) -> Result<BasicValueEnum<'ctx>, String> {
-        let f = self.module.get_function("dict_len")
+        let f = self.runtime_function("dict_len")
             .ok_or("dict_len function not found".to_string())?;
         let ptr = value.into_pointer_value();
         let call = self.builder.build_call(f, &[ptr.into()], "dict_len").unwrap();
@@ -0,0 +1,218 @@
+// list_methods.rs - Compilation of method calls on list values
+
+use crate::ast::Expr;
+use crate::compiler::builtins::sequence::{keyword_args, Keywords};
+use crate::compiler::context::CompilationContext;
+use crate::compiler::expr::ExprCompiler;
+use crate::compiler::types::{is_reference_type, Type};
+use inkwell::values::{BasicMetadataValueEnum, BasicValueEnum, IntValue, PointerValue};
+
+/// Methods supported on list values, with their minimum and maximum argument counts
+pub const LIST_METHODS: &[(&str, usize, usize)] = &[
+    ("append", 1, 1),
+    ("pop", 0, 1),
+    ("insert", 2, 2),
+    ("remove", 1, 1),
+    ("sort", 0, 0),
+    ("extend", 1, 1),
+    ("index", 1, 1),
+    ("count", 1, 1),
+];
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// Compile `list.method(args...)` for any list-typed receiver
+    pub fn compile_list_method_call(
+        &mut self,
+        list_val: BasicValueEnum<'ctx>,
+        element_type: &Type,
+        method: &str,
+        args: &[Box<Expr>],
+        keywords: &Keywords,
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        let (min_args, max_args) = match LIST_METHODS.iter().find(|(name, ..)| *name == method) {
+            Some((_, min, max)) => (*min, *max),
+            None => return Err(format!("'list' object has no attribute '{}'", method)),
+        };
+
+        if args.len() < min_args || args.len() > max_args {
+            let expected = if min_args == max_args {
+                format!("exactly {}", min_args)
+            } else {
+                format!("{} to {}", min_args, max_args)
+            };
+            return Err(format!(
+                "list.{}() takes {} argument{} ({} given)",
+                method,
+                expected,
+                if max_args == 1 { "" } else { "s" },
+                args.len()
+            ));
+        }
+
+        let builtin = format!("list.{}", method);
+        let [key, reverse] = match method {
+            "sort" => keyword_args(&builtin, keywords, ["key", "reverse"])?,
+            _ => {
+                keyword_args(&builtin, keywords, [])?;
+                [None, None]
+            }
+        };
+
+        let list_ptr = list_val.into_pointer_value();
+        let none: BasicValueEnum<'ctx> = self.llvm_context.i32_type().const_zero().into();
+
+        match method {
+            "append" => {
+                let (value, tag) = self.compile_list_element(&args[0], true)?;
+                self.call_runtime_function(
+                    "list_append_tagged",
+                    &[list_ptr.into(), value.into(), tag.into()],
+                )?;
+                Ok((none, Type::None))
+            }
+            "insert" => {
+                let index = self.compile_list_index_arg(&args[0], method)?;
+                let (value, tag) = self.compile_list_element(&args[1], true)?;
+                self.call_runtime_function(
+                    "list_insert",
+                    &[list_ptr.into(), index.into(), value.into(), tag.into()],
+                )?;
+                Ok((none, Type::None))
+            }
+            "extend" => {
+                let (other, other_type) = self.compile_expr(&args[0])?;
+                if !matches!(other_type, Type::List(_)) {
+                    return Err(format!(
+                        "list.extend() argument must be a list, not {:?}",
+                        other_type
+                    ));
+                }
+                self.call_runtime_function("list_extend", &[list_ptr.into(), other.into()])?;
+                Ok((none, Type::None))
+            }
+            "sort" if key.is_none() && reverse.is_none() => {
+                self.call_runtime_function("list_sort", &[list_ptr.into()])?;
+                Ok((none, Type::None))
+            }
+            "sort" => {
+                let item_type = match element_type {
+                    Type::Unknown => Type::Any,
+                    ty => ty.clone(),
+                };
+                let reverse = match reverse {
+                    Some(expr) => self.compile_flag_arg(expr, &builtin, "reverse")?,
+                    None => self.llvm_context.i64_type().const_zero(),
+                };
+                let keys = match self.compile_sort_keys(list_ptr, &item_type, key, &builtin)? {
+                    Some(keys) => keys,
+                    None => self.llvm_context.ptr_type(inkwell::AddressSpace::default()).const_null(),
+                };
+                self.call_runtime_function(
+                    "list_sort_by_keys",
+                    &[list_ptr.into(), keys.into(), reverse.into()],
+                )?;
+                Ok((none, Type::None))
+            }
+            "remove" => {
+                let (value, tag) = self.compile_list_element(&args[0], false)?;
+                self.call_runtime_function("list_remove", &[list_ptr.into(), value.into(), tag.into()])?;
+                Ok((none, Type::None))
+            }
+            "index" | "count" => {
+                let (value, tag) = self.compile_list_element(&args[0], false)?;
+                let fn_name = format!("list_{}", method);
+                let result = self
+                    .call_runtime_function(&fn_name, &[list_ptr.into(), value.into(), tag.into()])?
+                    .ok_or_else(|| format!("{} returned void", fn_name))?;
+                Ok((result, Type::Int))
+            }
+            _ => {
+                // pop([index]) defaults to the last element
+                let index = match args.first() {
+                    Some(arg) => self.compile_list_index_arg(arg, method)?,
+                    None => self.llvm_context.i64_type().const_int(u64::MAX, true),
+                };
+                let item_ptr = self
+                    .call_runtime_function("list_pop", &[list_ptr.into(), index.into()])?
+                    .ok_or("list_pop returned void")?
+                    .into_pointer_value();
+
+                match element_type {
+                    Type::Unknown | Type::Any => Ok((item_ptr.into(), Type::Any)),
+                    ty if is_reference_type(ty) => Ok((item_ptr.into(), ty.clone())),
+                    ty => {
+                        let llvm_type = self.get_llvm_type(ty);
+                        let item = self
+                            .builder
+                            .build_load(llvm_type, item_ptr, "list_pop_item")
+                            .unwrap();
+                        Ok((item, ty.clone()))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Compile a list element argument into a pointer and its runtime tag.
+    ///
+    /// Scalars are boxed on the heap when the list keeps them (`owned`), and on
+    /// the stack when they are only compared against.
+    pub(crate) fn compile_list_element(
+        &mut self,
+        arg: &Expr,
+        owned: bool,
+    ) -> Result<(PointerValue<'ctx>, IntValue<'ctx>), String> {
+        let (value, ty) = self.compile_expr(arg)?;
+        let tag = self.type_tag(&ty);
+
+        if is_reference_type(&ty) && value.is_pointer_value() {
+            return Ok((value.into_pointer_value(), tag));
+        }
+
+        let slot = if owned {
+            self.builder
+                .build_malloc(value.get_type(), "list_elem")
+                .map_err(|e| format!("Failed to allocate list element: {}", e))?
+        } else {
+            self.builder
+                .build_alloca(value.get_type(), "list_probe")
+                .unwrap()
+        };
+        self.builder.build_store(slot, value).unwrap();
+        Ok((slot, tag))
+    }
+
+    /// Compile an integer index argument of a list method
+    fn compile_list_index_arg(
+        &mut self,
+        arg: &Expr,
+        method: &str,
+    ) -> Result<IntValue<'ctx>, String> {
+        let (index, index_type) = self.compile_expr(arg)?;
+        if !matches!(index_type, Type::Int | Type::Bool) {
+            return Err(format!(
+                "list.{}() index must be an integer, not {:?}",
+                method, index_type
+            ));
+        }
+        Ok(self
+            .convert_type(index, &index_type, &Type::Int)?
+            .into_int_value())
+    }
+
+    /// Call a declared runtime function, returning its result if it has one
+    pub(crate) fn call_runtime_function(
+        &mut self,
+        fn_name: &str,
+        args: &[BasicMetadataValueEnum<'ctx>],
+    ) -> Result<Option<BasicValueEnum<'ctx>>, String> {
+        let function = self
+            .runtime_function(fn_name)
+            .ok_or_else(|| format!("{} function not found", fn_name))?;
+        let call = self
+            .builder
+            .build_call(function, args, &format!("{}_call", fn_name))
+            .unwrap();
+        Ok(call.try_as_basic_value().left())
+    }
+}
@@ -4,8 +4,8 @@ use crate::ast::Expr;
 use crate::compiler::context::CompilationContext;
 use crate::compiler::expr::ExprCompiler;
 use crate::compiler::types::Type;
-use inkwell::AddressSpace;
 use inkwell::values::BasicValueEnum;
+use inkwell::{AddressSpace, FloatPredicate, IntPredicate};
 
 impl<'ctx> CompilationContext<'ctx> {
     /// Register min, max, and their type‑specific variants
@@ -14,31 +14,31 @@ impl<'ctx> CompilationContext<'ctx> {
         let m = &mut self.module;
 
         // min_int, min_float
-        if m.get_function("min_int").is_none() {
+        if !self.runtime_catalog.provides(ctx, "min_int") && m.get_function("min_int").is_none() {
             let t = ctx.i64_type().fn_type(&[ctx.i64_type().into(), ctx.i64_type().into()], false);
             let f = m.add_function("min_int", t, None);
             self.functions.insert("min_int".into(), f);
         }
-        if m.get_function("min_float").is_none() {
+        if !self.runtime_catalog.provides(ctx, "min_float") && m.get_function("min_float").is_none() {
             let t = ctx.f64_type().fn_type(&[ctx.f64_type().into(), ctx.f64_type().into()], false);
             let f = m.add_function("min_float", t, None);
             self.functions.insert("min_float".into(), f);
         }
 
         // max_int, max_float
-        if m.get_function("max_int").is_none() {
+        if !self.runtime_catalog.provides(ctx, "max_int") && m.get_function("max_int").is_none() {
             let t = ctx.i64_type().fn_type(&[ctx.i64_type().into(), ctx.i64_type().into()], false);
             let f = m.add_function("max_int", t, None);
             self.functions.insert("max_int".into(), f);
         }
-        if m.get_function("max_float").is_none() {
+        if !self.runtime_catalog.provides(ctx, "max_float") && m.get_function("max_float").is_none() {
             let t = ctx.f64_type().fn_type(&[ctx.f64_type().into(), ctx.f64_type().into()], false);
             let f = m.add_function("max_float", t, None);
             self.functions.insert("max_float".into(), f);
         }
 
         // min(ptr, ptr)
-        if m.get_function("min").is_none() {
+        if !self.runtime_catalog.provides(ctx, "min") && m.get_function("min").is_none() {
             let ptr_t = ctx.ptr_type(AddressSpace::default());
             let t = ptr_t.fn_type(&[ptr_t.into(), ptr_t.into()], false);
             let f = m.add_function("min", t, None);
@@ -46,7 +46,7 @@ impl<'ctx> CompilationContext<'ctx> {
         }
 
         // max(ptr, ptr)
-        if m.get_function("max").is_none() {
+        if !self.runtime_catalog.provides(ctx, "max") && m.get_function("max").is_none() {
             let ptr_t = ctx.ptr_type(AddressSpace::default());
             let t = ptr_t.fn_type(&[ptr_t.into(), ptr_t.into()], false);
             let f = m.add_function("max", t, None);
@@ -66,8 +66,8 @@ impl<'ctx> CompilationContext<'ctx> {
         let (v2, t2) = self.compile_expr(&args[1])?;
 
         match (&t1, &t2) {
-            (Type::Int, Type::Int) => self.call_int_fn("min_int", v1, v2).map(|r| (r, Type::Int)),
-            (Type::Float, Type::Float) => self.call_float_fn("min_float", v1, v2).map(|r| (r, Type::Float)),
+            (Type::Int, Type::Int) => Ok((self.select_int(IntPredicate::SLT, v1, v2, "min"), Type::Int)),
+            (Type::Float, Type::Float) => Ok((self.select_float(FloatPredicate::OLT, v1, v2, "min"), Type::Float)),
             (Type::Int, Type::Float) | (Type::Float, Type::Int) => {
                 // Always promote to float
                 let f1 = if let Type::Int = t1 {
@@ -82,7 +82,7 @@ impl<'ctx> CompilationContext<'ctx> {
                 } else {
                     v2
                 };
-                self.call_float_fn("min_float", f1, f2).map(|r| (r, Type::Float))
+                Ok((self.select_float(FloatPredicate::OLT, f1, f2, "min"), Type::Float))
             }
             _ => Err(format!("min() not supported for types {:?} and {:?}", t1, t2)),
         }
@@ -100,8 +100,8 @@ impl<'ctx> CompilationContext<'ctx> {
         let (v2, t2) = self.compile_expr(&args[1])?;
 
         match (&t1, &t2) {
-            (Type::Int, Type::Int) => self.call_int_fn("max_int", v1, v2).map(|r| (r, Type::Int)),
-            (Type::Float, Type::Float) => self.call_float_fn("max_float", v1, v2).map(|r| (r, Type::Float)),
+            (Type::Int, Type::Int) => Ok((self.select_int(IntPredicate::SGT, v1, v2, "max"), Type::Int)),
+            (Type::Float, Type::Float) => Ok((self.select_float(FloatPredicate::OGT, v1, v2, "max"), Type::Float)),
             (Type::Int, Type::Float) | (Type::Float, Type::Int) => {
                 let f1 = if let Type::Int = t1 {
                     let i = v1.into_int_value();
@@ -115,39 +115,36 @@ impl<'ctx> CompilationContext<'ctx> {
                 } else {
                     v2
                 };
-                self.call_float_fn("max_float", f1, f2).map(|r| (r, Type::Float))
+                Ok((self.select_float(FloatPredicate::OGT, f1, f2, "max"), Type::Float))
             }
             _ => Err(format!("max() not supported for types {:?} and {:?}", t1, t2)),
         }
     }
 
-    /// Helper to call an integer min/max function
-    fn call_int_fn(
-        &mut self,
-        name: &str,
+    /// `a` if `a <predicate> b` holds, else `b`, as min_int/max_int compute it
+    fn select_int(
+        &self,
+        predicate: IntPredicate,
         a: BasicValueEnum<'ctx>,
         b: BasicValueEnum<'ctx>,
-    ) -> Result<BasicValueEnum<'ctx>, String> {
-        let f = self.module.get_function(name)
-            .ok_or_else(|| format!("{} not found", name))?;
-        let ai = a.into_int_value();
-        let bi = b.into_int_value();
-        let call = self.builder.build_call(f, &[ai.into(), bi.into()], "int_cmp").unwrap();
-        call.try_as_basic_value().left().ok_or("Failed int call".to_string())
+        name: &str,
+    ) -> BasicValueEnum<'ctx> {
+        let (a, b) = (a.into_int_value(), b.into_int_value());
+        let pick_a = self.builder.build_int_compare(predicate, a, b, "pick_a").unwrap();
+        self.builder.build_select(pick_a, a, b, name).unwrap()
     }
 
-    /// Helper to call a float min/max function
-    fn call_float_fn(
-        &mut self,
-        name: &str,
+    /// `a` if `a <predicate> b` holds, else `b`, as min_float/max_float compute it.
+    /// Ordered predicates pick `b` when either operand is NaN.
+    fn select_float(
+        &self,
+        predicate: FloatPredicate,
         a: BasicValueEnum<'ctx>,
         b: BasicValueEnum<'ctx>,
-    ) -> Result<BasicValueEnum<'ctx>, String> {
-        let f = self.module.get_function(name)
-            .ok_or_else(|| format!("{} not found", name))?;
-        let af = a.into_float_value();
-        let bf = b.into_float_value();
-        let call = self.builder.build_call(f, &[af.into(), bf.into()], "flt_cmp").unwrap();
-        call.try_as_basic_value().left().ok_or("Failed float call".to_string())
+        name: &str,
+    ) -> BasicValueEnum<'ctx> {
+        let (a, b) = (a.into_float_value(), b.into_float_value());
+        let pick_a = self.builder.build_float_compare(predicate, a, b, "pick_a").unwrap();
+        self.builder.build_select(pick_a, a, b, name).unwrap()
     }
 }
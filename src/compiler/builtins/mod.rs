@@ -2,4 +2,16 @@
 
 pub mod len;
 pub mod print;
+pub mod input;
 pub mod min_max;
+pub mod list_methods;
+pub mod string_methods;
+pub mod dict_methods;
+pub mod iter_builtins;
+pub mod os_module;
+pub mod sequence;
+pub mod introspection;
+pub mod file;
+pub mod context_managers;
+pub mod channel;
+pub mod timeout;
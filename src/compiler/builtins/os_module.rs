@@ -0,0 +1,52 @@
+// os_module.rs - Compilation of the os module's temporary file helpers
+
+use crate::ast::Expr;
+use crate::compiler::context::CompilationContext;
+use crate::compiler::types::Type;
+use inkwell::values::BasicValueEnum;
+
+/// Helpers callable as `os.<name>()`, with the runtime function behind each
+pub const OS_FUNCTIONS: &[(&str, &str)] = &[("tempdir", "os_tempdir"), ("tempfile", "os_tempfile")];
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// The helper name if `func` is `os.tempdir` or `os.tempfile` and `os` is not a variable
+    pub fn os_function_name<'a>(&self, func: &'a Expr) -> Option<&'a str> {
+        match func {
+            Expr::Attribute { value, attr, .. }
+                if matches!(value.as_ref(), Expr::Name { id, .. } if id == "os")
+                    && self.get_variable_ptr("os").is_none()
+                    && OS_FUNCTIONS.iter().any(|(name, _)| name == attr) =>
+            {
+                Some(attr.as_str())
+            }
+            _ => None,
+        }
+    }
+
+    /// Compile `os.tempdir()` or `os.tempfile()`, returning the new path.
+    ///
+    /// Outside a `with` block the path is removed when the program exits.
+    pub fn compile_os_call(
+        &mut self,
+        name: &str,
+        args: &[Box<Expr>],
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        if !args.is_empty() {
+            return Err(format!(
+                "os.{}() takes no arguments ({} given)",
+                name,
+                args.len()
+            ));
+        }
+
+        let runtime_name = OS_FUNCTIONS
+            .iter()
+            .find(|(helper, _)| *helper == name)
+            .map(|(_, runtime_name)| *runtime_name)
+            .ok_or_else(|| format!("module 'os' has no attribute '{}'", name))?;
+        let path = self
+            .call_runtime_function(runtime_name, &[])?
+            .ok_or_else(|| format!("{} returned void", runtime_name))?;
+        Ok((path, Type::String))
+    }
+}
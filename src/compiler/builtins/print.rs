@@ -30,33 +30,32 @@ impl<'ctx> CompilationContext<'ctx> {
         let m = &mut self.module;
 
         // print_string
-        if m.get_function("print_string").is_none() {
+        if !self.runtime_catalog.provides(ctx, "print_string") && m.get_function("print_string").is_none() {
             let ty = ctx.ptr_type(AddressSpace::default()).into();
             m.add_function("print_string", ctx.void_type().fn_type(&[ty], false), None);
         }
         // print_int
-        if m.get_function("print_int").is_none() {
+        if !self.runtime_catalog.provides(ctx, "print_int") && m.get_function("print_int").is_none() {
             m.add_function("print_int", ctx.void_type().fn_type(&[ctx.i64_type().into()], false), None);
         }
         // print_float
-        if m.get_function("print_float").is_none() {
+        if !self.runtime_catalog.provides(ctx, "print_float") && m.get_function("print_float").is_none() {
             m.add_function("print_float", ctx.void_type().fn_type(&[ctx.f64_type().into()], false), None);
         }
         // print_bool
-        if m.get_function("print_bool").is_none() {
+        if !self.runtime_catalog.provides(ctx, "print_bool") && m.get_function("print_bool").is_none() {
             m.add_function("print_bool", ctx.void_type().fn_type(&[ctx.bool_type().into()], false), None);
         }
     }
 
-    /// Create a global C string and return i8* pointer
+    /// Get the pooled global C string for `bytes` and return i8* pointer
     pub fn make_cstr(&mut self, name: &str, bytes: &[u8]) -> PointerValue<'ctx> {
-        let const_str = self.llvm_context.const_string(bytes, false);
-        let global = self.module.add_global(const_str.get_type(), None, name);
-        global.set_initializer(&const_str);
+        let contents = bytes.strip_suffix(b"\0").unwrap_or(bytes);
+        let global = self.pooled_string(name, contents);
         // with opaque pointers the cast is often a no‑op -> use the helper
         Self::cast_or_self(
             &self.builder,
-            global.as_pointer_value(),
+            global,
             self.llvm_context.ptr_type(AddressSpace::default()),
             &format!("{}_ptr", name),
         )
@@ -67,11 +66,11 @@ impl<'ctx> CompilationContext<'ctx> {
         &mut self,
         args: &[Expr],
     ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
-        let print_str = self.module.get_function("print_string").ok_or("print_string not found")?;
-        let print_int = self.module.get_function("print_int").ok_or("print_int not found")?;
-        let print_flt = self.module.get_function("print_float").ok_or("print_float not found")?;
-        let print_bool = self.module.get_function("print_bool").ok_or("print_bool not found")?;
-        let println_fn = self.module.get_function("println_string").ok_or("println_string not found")?;
+        let print_str = self.runtime_function("print_string").ok_or("print_string not found")?;
+        let print_int = self.runtime_function("print_int").ok_or("print_int not found")?;
+        let print_flt = self.runtime_function("print_float").ok_or("print_float not found")?;
+        let print_bool = self.runtime_function("print_bool").ok_or("print_bool not found")?;
+        let println_fn = self.runtime_function("println_string").ok_or("println_string not found")?;
 
         // For string quoting
         let quote = self.make_cstr("sq", b"'\0");
@@ -155,7 +154,7 @@ impl<'ctx> CompilationContext<'ctx> {
                     .builder
                     .build_load(self.llvm_context.i64_type(), int_ptr, "int_val")
                     .unwrap();
-                let print_int = self.module.get_function("print_int").ok_or("print_int not found")?;
+                let print_int = self.runtime_function("print_int").ok_or("print_int not found")?;
                 self.builder.build_call(print_int, &[int_val.into()], "pi").unwrap();
             }
 
@@ -171,7 +170,7 @@ impl<'ctx> CompilationContext<'ctx> {
                     .builder
                     .build_load(self.llvm_context.f64_type(), f_ptr, "fval")
                     .unwrap();
-                let print_flt = self.module.get_function("print_float").ok_or("print_float not found")?;
+                let print_flt = self.runtime_function("print_float").ok_or("print_float not found")?;
                 self.builder.build_call(print_flt, &[f_val.into()], "pf").unwrap();
             }
 
@@ -187,7 +186,7 @@ impl<'ctx> CompilationContext<'ctx> {
                     .builder
                     .build_load(self.llvm_context.bool_type(), b_ptr, "bval")
                     .unwrap();
-                let print_bool = self.module.get_function("print_bool").ok_or("print_bool not found")?;
+                let print_bool = self.runtime_function("print_bool").ok_or("print_bool not found")?;
                 self.builder.build_call(print_bool, &[b_val.into()], "pb").unwrap();
             }
 
@@ -198,14 +197,14 @@ impl<'ctx> CompilationContext<'ctx> {
                     void_ptr_t,
                     "str_ptr",
                 );
-                let print_str = self.module.get_function("print_string").ok_or("print_string not found")?;
+                let print_str = self.runtime_function("print_string").ok_or("print_string not found")?;
                 self.builder.build_call(print_str, &[quote.into()], "q1").unwrap(); // opening '
                 self.builder.build_call(print_str, &[str_ptr.into()], "ps").unwrap();           // body
                 self.builder.build_call(print_str, &[quote.into()], "q2").unwrap(); // closing '
             }
 
             Type::None => {
-                let print_str = self.module.get_function("print_string").ok_or("print_string not found")?;
+                let print_str = self.runtime_function("print_string").ok_or("print_string not found")?;
                 self.builder.build_call(print_str, &[none_lit.into()], "pnone").unwrap();
             }
 
@@ -235,7 +234,7 @@ impl<'ctx> CompilationContext<'ctx> {
 
             _ => {
                 let ph = self.make_cstr("ph2", b"<Any>\0");
-                let print_str = self.module.get_function("print_string").ok_or("print_string not found")?;
+                let print_str = self.runtime_function("print_string").ok_or("print_string not found")?;
                 self.builder.build_call(print_str, &[ph.into()], "ph").unwrap();
             }
         }
@@ -249,7 +248,7 @@ impl<'ctx> CompilationContext<'ctx> {
     /// ── `list_ptr` is `*mut RawList` (LLVM pointer value)
     /// ── `elem_ty` is the *static* element type known at compile time
     ///
-    fn print_list(
+    pub(crate) fn print_list(
     &mut self,
     list_ptr: PointerValue<'ctx>,
     elem_type: &Type,
@@ -262,7 +261,7 @@ impl<'ctx> CompilationContext<'ctx> {
     const MAX_RECURSION_DEPTH: usize = 10;
     if recursion_depth >= MAX_RECURSION_DEPTH {
         let max_depth_str = self.make_cstr("max_depth", b"[max recursion depth]\0");
-        let print_str = self.module.get_function("print_string").ok_or("print_string not found")?;
+        let print_str = self.runtime_function("print_string").ok_or("print_string not found")?;
         self.builder.build_call(print_str, &[max_depth_str.into()], "pr_max_depth").unwrap();
         return Ok(());
     }
@@ -273,7 +272,7 @@ impl<'ctx> CompilationContext<'ctx> {
     let i8_t       = ctx.i8_type();
     let void_ptr_t = ctx.ptr_type(AddressSpace::default());
 
-    let print_str  = self.module.get_function("print_string").ok_or("print_string not found")?;
+    let print_str  = self.runtime_function("print_string").ok_or("print_string not found")?;
     let lbrack     = self.make_cstr("lb",   b"[\0");
     let rbrack     = self.make_cstr("rb",   b"]\0");
     let comma      = self.make_cstr("cm",   b", \0");
@@ -310,7 +309,7 @@ impl<'ctx> CompilationContext<'ctx> {
     self.builder.position_at_end(bb_body);
 
     // elem_ptr = list_get(list_ptr, idx)
-    let list_get = self.module.get_function("list_get").unwrap_or_else(|| {
+    let list_get = self.runtime_function("list_get").unwrap_or_else(|| {
         let fn_ty = void_ptr_t.fn_type(&[void_ptr_t.into(), i64_t.into()], false);
         self.module.add_function("list_get", fn_ty, None)
     });
@@ -329,7 +328,7 @@ impl<'ctx> CompilationContext<'ctx> {
 
         // dynamic dispatch for List[Any]
         Type::Any => {
-            let list_get_tag = self.module.get_function("list_get_tag").unwrap_or_else(|| {
+            let list_get_tag = self.runtime_function("list_get_tag").unwrap_or_else(|| {
                 let fn_ty = i8_t.fn_type(&[void_ptr_t.into(), i64_t.into()], false);
                 self.module.add_function("list_get_tag", fn_ty, None)
             });
@@ -485,17 +484,16 @@ impl<'ctx> CompilationContext<'ctx> {
             println!("Hit maximum recursion depth in tuple: {}", recursion_depth);
             let max_depth_str = self.make_cstr("max_tuple_depth", b"[max tuple recursion depth]\0");
             let print_str = self
-                .module
-                .get_function("print_string")
+                .runtime_function("print_string")
                 .ok_or("print_string not found")?;
             self.builder.build_call(print_str, &[max_depth_str.into()], "pr_max_tuple_depth").unwrap();
             return Ok(());
         }
 
-        let print_str = self.module.get_function("print_string").unwrap();
-        let print_int = self.module.get_function("print_int").unwrap();
-        let print_flt = self.module.get_function("print_float").unwrap();
-        let print_bool = self.module.get_function("print_bool").unwrap();
+        let print_str = self.runtime_function("print_string").unwrap();
+        let print_int = self.runtime_function("print_int").unwrap();
+        let print_flt = self.runtime_function("print_float").unwrap();
+        let print_bool = self.runtime_function("print_bool").unwrap();
 
         let lp = self.make_cstr("lp", b"(\0");
         let rp = self.make_cstr("rp", b")\0");
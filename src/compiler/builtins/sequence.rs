@@ -0,0 +1,400 @@
+// sequence.rs - Compilation of the sorted(), reversed(), sum(), abs() and round() built-ins
+
+use crate::ast::{Expr, NameConstant};
+use crate::compiler::builtins::iter_builtins::value_type_of;
+use crate::compiler::context::CompilationContext;
+use crate::compiler::expr::ExprCompiler;
+use crate::compiler::types::Type;
+use inkwell::values::{BasicValueEnum, IntValue, PointerValue};
+use inkwell::IntPredicate;
+
+/// Sequence and numeric built-ins, dispatched on the static type of their arguments
+pub const SEQUENCE_BUILTINS: &[&str] = &["sorted", "reversed", "sum", "abs", "round"];
+
+/// Keyword arguments of a built-in call
+pub(crate) type Keywords = [(Option<String>, Box<Expr>)];
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// Whether a call to `name` refers to a sequence built-in rather than a user definition
+    pub fn is_sequence_builtin(&self, name: &str) -> bool {
+        SEQUENCE_BUILTINS.contains(&name)
+            && self.runtime_function(name).is_none()
+            && self.scope_stack.get_variable(name).is_none()
+    }
+
+    /// Compile a call to sorted(), reversed(), sum(), abs() or round()
+    pub fn compile_sequence_builtin_call(
+        &mut self,
+        name: &str,
+        args: &[Box<Expr>],
+        keywords: &Keywords,
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        match name {
+            "sorted" => {
+                let [key, reverse] = keyword_args(name, keywords, ["key", "reverse"])?;
+                self.compile_sorted_call(args, key, reverse)
+            }
+            "reversed" => {
+                keyword_args(name, keywords, [])?;
+                self.compile_reversed_call(args)
+            }
+            "sum" => {
+                let [start] = keyword_args(name, keywords, ["start"])?;
+                let start = positional_or_keyword(name, args, 1, start, "start")?;
+                self.compile_sum_call(args, start)
+            }
+            "abs" => {
+                keyword_args(name, keywords, [])?;
+                self.compile_abs_call(args)
+            }
+            "round" => {
+                let [ndigits] = keyword_args(name, keywords, ["ndigits"])?;
+                let ndigits = positional_or_keyword(name, args, 1, ndigits, "ndigits")?;
+                self.compile_round_call(args, ndigits)
+            }
+            _ => Err(format!("{}() is not a sequence built-in", name)),
+        }
+    }
+
+    /// sorted(iterable, key=None, reverse=False) -> new list in stable order
+    fn compile_sorted_call(
+        &mut self,
+        args: &[Box<Expr>],
+        key: Option<&Expr>,
+        reverse: Option<&Expr>,
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        if args.len() != 1 {
+            return Err(format!(
+                "sorted expected 1 argument, got {}",
+                args.len()
+            ));
+        }
+
+        let (value, ty) = self.compile_expr(&args[0])?;
+        let (list_ptr, item_type) = self
+            .iteration_list(value, &ty)?
+            .ok_or_else(|| format!("'{}' object is not iterable", ty))?;
+        let reverse = match reverse {
+            Some(expr) => self.compile_flag_arg(expr, "sorted", "reverse")?,
+            None => self.llvm_context.i64_type().const_zero(),
+        };
+
+        let sorted = match self.compile_sort_keys(list_ptr, &item_type, key, "sorted")? {
+            None => self.call_runtime_function("list_sorted", &[list_ptr.into(), reverse.into()])?,
+            Some(keys) => self.call_runtime_function(
+                "list_sorted_by_keys",
+                &[list_ptr.into(), keys.into(), reverse.into()],
+            )?,
+        };
+
+        let sorted = sorted.ok_or("list_sorted returned void")?;
+        Ok((sorted, Type::List(Box::new(item_type))))
+    }
+
+    /// The sort keys of the items of a list: a new list holding the result of
+    /// calling the `key` function on each item once, or None without a key
+    pub(crate) fn compile_sort_keys(
+        &mut self,
+        list_ptr: PointerValue<'ctx>,
+        item_type: &Type,
+        key: Option<&Expr>,
+        builtin: &str,
+    ) -> Result<Option<PointerValue<'ctx>>, String> {
+        let key = match key {
+            None | Some(Expr::NameConstant { value: NameConstant::None, .. }) => return Ok(None),
+            Some(key) => key,
+        };
+        let function = self.resolve_iter_callback(key, builtin)?;
+        if function.count_params() != 1 {
+            return Err(format!(
+                "{}() key function must take 1 argument, not {}",
+                builtin,
+                function.count_params()
+            ));
+        }
+        let key_type = match function.get_type().get_return_type() {
+            Some(ty) => value_type_of(ty),
+            None => return Err(format!("{}() key function must return a value", builtin)),
+        };
+        let param_type = function.get_type().get_param_types()[0];
+
+        let len = self.build_list_len(list_ptr)?;
+        let keys = self.build_list_with_capacity(len)?;
+        self.build_index_loop(len, "sort_key", |ctx, index| {
+            let item = ctx.load_list_item(list_ptr, index, item_type)?;
+            let arg = match ctx.numeric_param_type(item_type, param_type) {
+                Some(target) => ctx.convert_type(item, item_type, &target)?,
+                None => item,
+            };
+            let key = ctx
+                .builder
+                .build_call(function, &[arg.into()], "sort_key_call")
+                .unwrap()
+                .try_as_basic_value()
+                .left()
+                .ok_or_else(|| format!("{}() key function returned void", builtin))?;
+            ctx.emit_call_check()?;
+            let boxed = ctx.box_list_item(key, &key_type)?;
+            ctx.append_list_item(keys, boxed, &key_type)
+        })?;
+        Ok(Some(keys))
+    }
+
+    /// reversed(list) -> new list with the items in reverse order
+    fn compile_reversed_call(
+        &mut self,
+        args: &[Box<Expr>],
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        if args.len() != 1 {
+            return Err(format!(
+                "reversed expected 1 argument, got {}",
+                args.len()
+            ));
+        }
+
+        let (list_ptr, item_type) = self.compile_iterable_arg(&args[0], "reversed")?;
+        let reversed = self
+            .call_runtime_function("list_reversed", &[list_ptr.into()])?
+            .ok_or("list_reversed returned void")?;
+        Ok((reversed, Type::List(Box::new(item_type))))
+    }
+
+    /// sum(list, start=0): an int for lists of ints and bools, otherwise a float
+    fn compile_sum_call(
+        &mut self,
+        args: &[Box<Expr>],
+        start: Option<&Expr>,
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        if args.is_empty() || args.len() > 2 {
+            return Err(format!(
+                "sum() takes 1 to 2 arguments ({} given)",
+                args.len()
+            ));
+        }
+
+        let (list_ptr, item_type) = self.compile_iterable_arg(&args[0], "sum")?;
+        let (total, total_type) = match item_type {
+            Type::Int | Type::Bool => (
+                self.call_runtime_function("sum_int_list", &[list_ptr.into()])?,
+                Type::Int,
+            ),
+            Type::Float | Type::Any => (
+                self.call_runtime_function("sum_float_list", &[list_ptr.into()])?,
+                Type::Float,
+            ),
+            other => {
+                return Err(format!(
+                    "unsupported operand type(s) for +: 'int' and '{:?}'",
+                    other
+                ))
+            }
+        };
+        let total = total.ok_or("sum returned void")?;
+
+        let start = match start {
+            Some(expr) => expr,
+            None => return Ok((total, total_type)),
+        };
+        let (start_value, start_type) = self.compile_expr(start)?;
+        let result_type = match start_type {
+            Type::Int | Type::Bool if total_type == Type::Int => Type::Int,
+            Type::Int | Type::Bool | Type::Float => Type::Float,
+            other => {
+                return Err(format!(
+                    "sum() start must be a number, not {:?}",
+                    other
+                ))
+            }
+        };
+
+        let start_value = self.convert_type(start_value, &start_type, &result_type)?;
+        let total = self.convert_type(total, &total_type, &result_type)?;
+        let result: BasicValueEnum<'ctx> = if result_type == Type::Int {
+            self.builder
+                .build_int_add(start_value.into_int_value(), total.into_int_value(), "sum_start")
+                .unwrap()
+                .into()
+        } else {
+            self.builder
+                .build_float_add(start_value.into_float_value(), total.into_float_value(), "sum_start")
+                .unwrap()
+                .into()
+        };
+        Ok((result, result_type))
+    }
+
+    /// abs(x) for ints, bools and floats
+    fn compile_abs_call(
+        &mut self,
+        args: &[Box<Expr>],
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        if args.len() != 1 {
+            return Err(format!(
+                "abs() takes exactly one argument ({} given)",
+                args.len()
+            ));
+        }
+
+        let (value, ty) = self.compile_expr(&args[0])?;
+        match ty {
+            Type::Int | Type::Bool => {
+                // Negation wraps for the most negative int, as abs_int does
+                let value = self.convert_type(value, &ty, &Type::Int)?.into_int_value();
+                let negative = self
+                    .builder
+                    .build_int_compare(IntPredicate::SLT, value, value.get_type().const_zero(), "negative")
+                    .unwrap();
+                let negated = self.builder.build_int_neg(value, "negated").unwrap();
+                let result = self.builder.build_select(negative, negated, value, "abs").unwrap();
+                Ok((result, Type::Int))
+            }
+            Type::Float => {
+                // Clear the sign bit, which LLVM recognizes as fabs
+                let i64_type = self.llvm_context.i64_type();
+                let bits = self
+                    .builder
+                    .build_bit_cast(value, i64_type, "float_bits")
+                    .unwrap()
+                    .into_int_value();
+                let magnitude = self
+                    .builder
+                    .build_and(bits, i64_type.const_int(i64::MAX as u64, false), "magnitude")
+                    .unwrap();
+                let result = self
+                    .builder
+                    .build_bit_cast(magnitude, self.llvm_context.f64_type(), "abs")
+                    .unwrap();
+                Ok((result, Type::Float))
+            }
+            other => Err(format!("bad operand type for abs(): {:?}", other)),
+        }
+    }
+
+    /// round(x[, ndigits]), rounding halves to even.
+    ///
+    /// Floats round to an int without `ndigits` and to a float with it; ints stay ints.
+    fn compile_round_call(
+        &mut self,
+        args: &[Box<Expr>],
+        ndigits: Option<&Expr>,
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        if args.is_empty() || args.len() > 2 {
+            return Err(format!(
+                "round() takes 1 to 2 arguments ({} given)",
+                args.len()
+            ));
+        }
+
+        let (value, ty) = self.compile_expr(&args[0])?;
+        let ndigits = match ndigits {
+            Some(expr) => {
+                let (digits, digits_type) = self.compile_expr(expr)?;
+                if !matches!(digits_type, Type::Int | Type::Bool) {
+                    return Err(format!(
+                        "round() ndigits must be an integer, not {:?}",
+                        digits_type
+                    ));
+                }
+                Some(self.convert_type(digits, &digits_type, &Type::Int)?)
+            }
+            None => None,
+        };
+
+        let (runtime_name, value_type, result_type) = match (&ty, ndigits.is_some()) {
+            (Type::Float, false) => ("round_float", Type::Float, Type::Int),
+            (Type::Float, true) => ("round_float_digits", Type::Float, Type::Float),
+            (Type::Int | Type::Bool, false) => {
+                let value = self.convert_type(value, &ty, &Type::Int)?;
+                return Ok((value, Type::Int));
+            }
+            (Type::Int | Type::Bool, true) => ("round_int_digits", Type::Int, Type::Int),
+            (other, _) => {
+                return Err(format!(
+                    "type {:?} doesn't define __round__ method",
+                    other
+                ))
+            }
+        };
+
+        let value = self.convert_type(value, &ty, &value_type)?;
+        let mut call_args = vec![value.into()];
+        if let Some(digits) = ndigits {
+            call_args.push(digits.into());
+        }
+        let result = self
+            .call_runtime_function(runtime_name, &call_args)?
+            .ok_or_else(|| format!("{} returned void", runtime_name))?;
+        Ok((result, result_type))
+    }
+
+    /// Compile a boolean flag such as `reverse=` to an i64 of 0 or 1
+    pub(crate) fn compile_flag_arg(
+        &mut self,
+        expr: &Expr,
+        builtin: &str,
+        keyword: &str,
+    ) -> Result<IntValue<'ctx>, String> {
+        let (value, ty) = self.compile_expr(expr)?;
+        match ty {
+            Type::Bool | Type::Int => {
+                let flag = self.convert_type(value, &ty, &Type::Bool)?.into_int_value();
+                Ok(self
+                    .builder
+                    .build_int_z_extend(flag, self.llvm_context.i64_type(), keyword)
+                    .unwrap())
+            }
+            other => Err(format!(
+                "{}() {} must be a bool, not {:?}",
+                builtin, keyword, other
+            )),
+        }
+    }
+}
+
+/// Match keyword arguments against the names a built-in accepts, in order
+pub(crate) fn keyword_args<'a, const N: usize>(
+    builtin: &str,
+    keywords: &'a Keywords,
+    names: [&str; N],
+) -> Result<[Option<&'a Expr>; N], String> {
+    let mut found = [None; N];
+    for (name, value) in keywords {
+        let name = name
+            .as_deref()
+            .ok_or_else(|| format!("{}() does not accept **kwargs", builtin))?;
+        let slot = names
+            .iter()
+            .position(|accepted| *accepted == name)
+            .ok_or_else(|| {
+                format!(
+                    "{}() got an unexpected keyword argument '{}'",
+                    builtin, name
+                )
+            })?;
+        if found[slot].replace(value.as_ref()).is_some() {
+            return Err(format!(
+                "{}() got multiple values for keyword argument '{}'",
+                builtin, name
+            ));
+        }
+    }
+    Ok(found)
+}
+
+/// An optional argument that may be given by position or by keyword, but not both
+fn positional_or_keyword<'a>(
+    builtin: &str,
+    args: &'a [Box<Expr>],
+    position: usize,
+    keyword: Option<&'a Expr>,
+    name: &str,
+) -> Result<Option<&'a Expr>, String> {
+    match (args.get(position), keyword) {
+        (Some(_), Some(_)) => Err(format!(
+            "{}() got multiple values for argument '{}'",
+            builtin, name
+        )),
+        (Some(arg), None) => Ok(Some(arg.as_ref())),
+        (None, keyword) => Ok(keyword),
+    }
+}
@@ -0,0 +1,101 @@
+// string_methods.rs - Compilation of method calls on str values
+
+use crate::ast::Expr;
+use crate::compiler::context::CompilationContext;
+use crate::compiler::expr::ExprCompiler;
+use crate::compiler::types::Type;
+use inkwell::values::{BasicMetadataValueEnum, BasicValueEnum};
+
+/// Methods supported on str values, with their argument counts
+pub const STRING_METHODS: &[(&str, usize)] = &[
+    ("split", 1),
+    ("join", 1),
+    ("strip", 0),
+    ("upper", 0),
+    ("lower", 0),
+    ("replace", 2),
+    ("find", 1),
+    ("startswith", 1),
+    ("endswith", 1),
+];
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// Compile `value.method(args...)` where `value` is a str
+    pub fn compile_string_method_call(
+        &mut self,
+        value: BasicValueEnum<'ctx>,
+        method: &str,
+        args: &[Box<Expr>],
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        let arity = match STRING_METHODS.iter().find(|(name, _)| *name == method) {
+            Some((_, arity)) => *arity,
+            None => return Err(format!("'str' object has no attribute '{}'", method)),
+        };
+
+        // split() with no separator splits on whitespace
+        let accepts_no_args = method == "split" && args.is_empty();
+        if args.len() != arity && !accepts_no_args {
+            return Err(format!(
+                "str.{}() takes exactly {} argument{} ({} given)",
+                method,
+                arity,
+                if arity == 1 { "" } else { "s" },
+                args.len()
+            ));
+        }
+
+        let mut call_args: Vec<BasicMetadataValueEnum<'ctx>> = vec![value.into()];
+        for arg in args {
+            let (arg_val, arg_type) = self.compile_expr(arg)?;
+            let expected = if method == "join" {
+                matches!(arg_type, Type::List(ref elem) if matches!(**elem, Type::String | Type::Any | Type::Unknown))
+            } else {
+                matches!(arg_type, Type::String)
+            };
+            if !expected {
+                return Err(format!(
+                    "str.{}() argument must be {}, not {:?}",
+                    method,
+                    if method == "join" { "a list of str" } else { "str" },
+                    arg_type
+                ));
+            }
+            call_args.push(arg_val.into());
+        }
+
+        if accepts_no_args {
+            let null = self
+                .llvm_context
+                .ptr_type(inkwell::AddressSpace::default())
+                .const_null();
+            call_args.push(null.into());
+        }
+
+        let (fn_name, result_type) = match method {
+            "split" => ("string_split", Type::List(Box::new(Type::String))),
+            "find" => ("string_find", Type::Int),
+            "startswith" => ("string_startswith", Type::Bool),
+            "endswith" => ("string_endswith", Type::Bool),
+            "join" => ("string_join", Type::String),
+            "strip" => ("string_strip", Type::String),
+            "upper" => ("string_upper", Type::String),
+            "lower" => ("string_lower", Type::String),
+            _ => ("string_replace", Type::String),
+        };
+
+        let fn_val = self
+            .runtime_function(fn_name)
+            .ok_or_else(|| format!("{} function not found", fn_name))?;
+
+        let call_site = self
+            .builder
+            .build_call(fn_val, &call_args, &format!("{}_result", fn_name))
+            .unwrap();
+        let result = call_site
+            .try_as_basic_value()
+            .left()
+            .ok_or_else(|| format!("Failed to get result of str.{}()", method))?;
+
+        Ok((result, result_type))
+    }
+}
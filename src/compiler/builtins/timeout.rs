@@ -0,0 +1,71 @@
+// timeout.rs - Compilation of the with_timeout() built-in
+//
+// with_timeout(seconds, f) calls the module-level function `f`, which takes
+// no arguments, between arming and disarming a deadline (see
+// `runtime/timeout.rs`). It returns what `f` returns, or raises TimeoutError
+// once `f` has been interrupted:
+//
+//     result = with_timeout(0.5, work)    # depth = timeout_enter(0.5)
+//                                         # result = work()
+//                                         # timeout_exit(depth)
+
+use crate::ast::Expr;
+use crate::compiler::builtins::iter_builtins::value_type_of;
+use crate::compiler::context::CompilationContext;
+use crate::compiler::expr::ExprCompiler;
+use crate::compiler::types::Type;
+use inkwell::values::BasicValueEnum;
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// Whether a call to `name` refers to with_timeout() rather than a user definition
+    pub fn is_timeout_builtin(&self, name: &str) -> bool {
+        name == "with_timeout"
+            && self.runtime_function(name).is_none()
+            && self.scope_stack.get_variable(name).is_none()
+    }
+
+    /// Compile with_timeout(seconds, f)
+    pub fn compile_with_timeout_call(
+        &mut self,
+        args: &[Box<Expr>],
+        keywords: &[(Option<String>, Box<Expr>)],
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        if !keywords.is_empty() {
+            return Err("with_timeout() takes no keyword arguments".to_string());
+        }
+        if args.len() != 2 {
+            return Err(format!("with_timeout() takes 2 arguments ({} given)", args.len()));
+        }
+        let function = self.resolve_iter_callback(&args[1], "with_timeout")?;
+        if function.count_params() != 0 {
+            return Err(format!(
+                "with_timeout() function must take no arguments, not {}",
+                function.count_params()
+            ));
+        }
+
+        let (seconds, seconds_type) = self.compile_expr(&args[0])?;
+        if !matches!(seconds_type, Type::Int | Type::Float | Type::Bool) {
+            return Err(format!("with_timeout() seconds must be a number, not {}", seconds_type));
+        }
+        let seconds = self.convert_type(seconds, &seconds_type, &Type::Float)?;
+        let depth = self
+            .call_runtime_function("timeout_enter", &[seconds.into()])?
+            .ok_or("timeout_enter returned void")?;
+
+        let result = self
+            .builder
+            .build_call(function, &[], "with_timeout_call")
+            .unwrap()
+            .try_as_basic_value()
+            .left();
+        self.call_runtime_function("timeout_exit", &[depth.into()])?;
+        // An enclosing deadline that passed still interrupts this function
+        self.emit_call_check()?;
+
+        Ok(match result {
+            Some(value) => (value, value_type_of(value.get_type())),
+            None => (self.llvm_context.i32_type().const_zero().into(), Type::None),
+        })
+    }
+}
@@ -0,0 +1,191 @@
+// cdylib.rs - Shared library builds callable from C and other languages
+//
+// A cdylib build exports every top-level function of the program as
+// `<prefix>_<name>`, where the prefix is the library name, and the module's
+// top-level code as `<prefix>_init`, which must be called once before any
+// other export. All other functions defined in the module are made internal
+// so libraries built from different programs don't clash. A C header with the
+// exported prototypes is written next to the library.
+
+use crate::compiler::Compiler;
+use inkwell::context::Context;
+use inkwell::module::Linkage;
+use inkwell::types::{BasicMetadataTypeEnum, BasicTypeEnum};
+
+/// What an AOT build links
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CrateType {
+    /// An executable running the module's top-level code
+    #[default]
+    Bin,
+    /// A shared library exporting the module's functions with the C ABI
+    Cdylib,
+}
+
+impl CrateType {
+    /// Parse a crate type as given to `--crate-type`
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "bin" => Ok(CrateType::Bin),
+            "cdylib" => Ok(CrateType::Cdylib),
+            other => Err(format!(
+                "unknown crate type '{}' (expected 'bin' or 'cdylib')",
+                other
+            )),
+        }
+    }
+}
+
+/// File name of the shared library for `name` on the host platform
+pub fn library_file_name(name: &str) -> String {
+    if cfg!(target_os = "macos") {
+        format!("lib{}.dylib", name)
+    } else if cfg!(windows) {
+        format!("{}.dll", name)
+    } else {
+        format!("lib{}.so", name)
+    }
+}
+
+/// Symbol prefix for a library name: the name with anything that can't appear
+/// in a C identifier replaced by `_`
+pub fn symbol_prefix(name: &str) -> String {
+    let mut prefix: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if prefix.is_empty() || prefix.starts_with(|c: char| c.is_ascii_digit()) {
+        prefix.insert(0, '_');
+    }
+    prefix
+}
+
+/// A function exported from a shared library, with its C prototype
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportedFunction {
+    pub symbol: String,
+    pub return_type: String,
+    pub param_types: Vec<String>,
+}
+
+impl ExportedFunction {
+    /// C declaration of the function
+    pub fn prototype(&self) -> String {
+        let params = if self.param_types.is_empty() {
+            "void".to_string()
+        } else {
+            self.param_types.join(", ")
+        };
+        format!("{} {}({});", self.return_type, self.symbol, params)
+    }
+}
+
+/// C header declaring the exports of the library with symbol prefix `prefix`
+pub fn c_header(prefix: &str, exports: &[ExportedFunction]) -> String {
+    let guard = format!("{}_H", prefix.to_ascii_uppercase());
+    let mut header = format!(
+        "/* Generated by cheetah: exports of {prefix}.\n   Call {prefix}_init() once before any other function. */\n\
+         #ifndef {guard}\n#define {guard}\n\n#include <stdbool.h>\n#include <stdint.h>\n\n\
+         #ifdef __cplusplus\nextern \"C\" {{\n#endif\n\n",
+        prefix = prefix,
+        guard = guard,
+    );
+    for export in exports {
+        header.push_str(&export.prototype());
+        header.push('\n');
+    }
+    header.push_str("\n#ifdef __cplusplus\n}\n#endif\n\n#endif\n");
+    header
+}
+
+/// C spelling of an LLVM parameter or return type, if it has one
+fn c_type<'ctx>(context: &'ctx Context, ty: BasicTypeEnum<'ctx>) -> Option<String> {
+    match ty {
+        BasicTypeEnum::IntType(int) => match int.get_bit_width() {
+            1 => Some("bool".to_string()),
+            bits @ (8 | 16 | 32 | 64) => Some(format!("int{}_t", bits)),
+            _ => None,
+        },
+        BasicTypeEnum::FloatType(float) => {
+            if float == context.f64_type() {
+                Some("double".to_string())
+            } else if float == context.f32_type() {
+                Some("float".to_string())
+            } else {
+                None
+            }
+        }
+        BasicTypeEnum::PointerType(_) => Some("void *".to_string()),
+        _ => None,
+    }
+}
+
+impl<'ctx> Compiler<'ctx> {
+    /// Rename the module's top-level functions and entry point to their exported
+    /// symbols and internalize every other defined function.
+    ///
+    /// Returns the exports, plus the names of top-level functions that could not be
+    /// exported because their signature has no C equivalent.
+    pub fn export_c_symbols(&mut self, prefix: &str) -> (Vec<ExportedFunction>, Vec<String>) {
+        let mut exports = Vec::new();
+        let mut skipped = Vec::new();
+        let mut exported_functions = Vec::new();
+        let context = self.context.llvm_context;
+
+        if let Some(main) = self.context.module.get_function("main") {
+            let symbol = format!("{}_init", prefix);
+            main.as_global_value().set_name(&symbol);
+            exported_functions.push(main);
+            exports.push(ExportedFunction {
+                symbol,
+                return_type: "void".to_string(),
+                param_types: Vec::new(),
+            });
+        }
+
+        for name in &self.top_level_functions {
+            let Some(function) = self.context.module.get_function(name) else {
+                continue;
+            };
+            let fn_type = function.get_type();
+            let return_type = match fn_type.get_return_type() {
+                Some(ty) => c_type(context, ty),
+                None => Some("void".to_string()),
+            };
+            let param_types: Option<Vec<String>> = fn_type
+                .get_param_types()
+                .into_iter()
+                .map(|ty| match ty {
+                    BasicMetadataTypeEnum::IntType(ty) => c_type(context, ty.into()),
+                    BasicMetadataTypeEnum::FloatType(ty) => c_type(context, ty.into()),
+                    BasicMetadataTypeEnum::PointerType(ty) => c_type(context, ty.into()),
+                    _ => None,
+                })
+                .collect();
+
+            match (return_type, param_types) {
+                (Some(return_type), Some(param_types)) => {
+                    let symbol = format!("{}_{}", prefix, name);
+                    function.as_global_value().set_name(&symbol);
+                    exported_functions.push(function);
+                    exports.push(ExportedFunction {
+                        symbol,
+                        return_type,
+                        param_types,
+                    });
+                }
+                _ => skipped.push(name.clone()),
+            }
+        }
+
+        let mut function = self.context.module.get_first_function();
+        while let Some(f) = function {
+            if f.count_basic_blocks() > 0 && !exported_functions.contains(&f) {
+                f.set_linkage(Linkage::Internal);
+            }
+            function = f.get_next_function();
+        }
+
+        (exports, skipped)
+    }
+}
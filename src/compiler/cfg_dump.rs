@@ -0,0 +1,187 @@
+// cfg_dump.rs - Graphviz dumps of the LLVM control flow graph of compiled functions
+
+use crate::compiler::Compiler;
+use inkwell::basic_block::BasicBlock;
+use inkwell::module::Module;
+use inkwell::values::{FunctionValue, InstructionOpcode};
+use inkwell::Either;
+
+/// A basic block in a control flow graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct CfgBlock {
+    pub name: String,
+    /// The block's instructions as printed in the IR
+    pub instructions: Vec<String>,
+    /// Successor blocks, with an edge label for the arms of a conditional branch
+    pub successors: Vec<(String, Option<&'static str>)>,
+}
+
+/// The control flow graph of one function
+#[derive(Debug, Clone, PartialEq)]
+pub struct CfgGraph {
+    pub function: String,
+    pub blocks: Vec<CfgBlock>,
+}
+
+impl CfgGraph {
+    /// Build the graph of a function with a body
+    pub fn from_function(function: FunctionValue<'_>) -> Self {
+        let blocks = function.get_basic_blocks();
+        let names: Vec<String> = blocks
+            .iter()
+            .enumerate()
+            .map(|(index, block)| block_name(block, index))
+            .collect();
+        let name_of = |target: &BasicBlock<'_>| {
+            blocks
+                .iter()
+                .position(|block| block == target)
+                .map(|index| names[index].clone())
+                .unwrap_or_else(|| "<unknown>".to_string())
+        };
+
+        let blocks = blocks
+            .iter()
+            .zip(&names)
+            .map(|(block, name)| {
+                let instructions = block
+                    .get_instructions()
+                    .map(|instruction| instruction.print_to_string().to_string().trim().to_string())
+                    .collect();
+
+                let mut successors = Vec::new();
+                if let Some(terminator) = block.get_terminator() {
+                    let targets: Vec<BasicBlock<'_>> = (0..terminator.get_num_operands())
+                        .filter_map(|index| match terminator.get_operand(index) {
+                            Some(Either::Right(target)) => Some(target),
+                            _ => None,
+                        })
+                        .collect();
+
+                    // A conditional branch lists its false destination first
+                    if terminator.get_opcode() == InstructionOpcode::Br && targets.len() == 2 {
+                        successors.push((name_of(&targets[1]), Some("true")));
+                        successors.push((name_of(&targets[0]), Some("false")));
+                    } else {
+                        successors.extend(targets.iter().map(|target| (name_of(target), None)));
+                    }
+                }
+
+                CfgBlock { name: name.clone(), instructions, successors }
+            })
+            .collect();
+
+        Self {
+            function: function.get_name().to_string_lossy().into_owned(),
+            blocks,
+        }
+    }
+
+    /// Names of blocks that do not end in a terminator instruction
+    pub fn unterminated_blocks(&self) -> Vec<&str> {
+        self.blocks
+            .iter()
+            .filter(|block| {
+                block.successors.is_empty()
+                    && !block.instructions.last().is_some_and(|last| is_exit_instruction(last))
+            })
+            .map(|block| block.name.as_str())
+            .collect()
+    }
+
+    /// Render the graph in Graphviz dot syntax
+    pub fn to_dot(&self) -> String {
+        let mut dot = format!("digraph \"CFG for {}\" {{\n", escape(&self.function));
+        dot.push_str(&format!("    label=\"CFG for '{}'\";\n", escape(&self.function)));
+        dot.push_str("    node [shape=record, fontname=\"monospace\"];\n");
+
+        for block in &self.blocks {
+            let mut label = format!("{}:\\l", escape(&block.name));
+            for instruction in &block.instructions {
+                label.push_str(&format!("  {}\\l", escape(instruction)));
+            }
+            dot.push_str(&format!(
+                "    \"{}\" [label=\"{{{}}}\"];\n",
+                escape(&block.name),
+                label.replace('{', "\\{").replace('}', "\\}").replace('|', "\\|")
+            ));
+        }
+
+        for block in &self.blocks {
+            for (target, label) in &block.successors {
+                dot.push_str(&format!("    \"{}\" -> \"{}\"", escape(&block.name), escape(target)));
+                if let Some(label) = label {
+                    dot.push_str(&format!(" [label=\"{}\"]", label));
+                }
+                dot.push_str(";\n");
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn block_name(block: &BasicBlock<'_>, index: usize) -> String {
+    let name = block.get_name().to_string_lossy();
+    if name.is_empty() {
+        format!("bb{}", index)
+    } else {
+        name.into_owned()
+    }
+}
+
+/// Whether a printed instruction leaves the function (ret or unreachable)
+fn is_exit_instruction(instruction: &str) -> bool {
+    instruction.starts_with("ret") || instruction.starts_with("unreachable")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Graphs for the functions of a module that have a body, optionally only the one named `only`
+pub fn module_cfgs(module: &Module<'_>, only: Option<&str>) -> Result<Vec<CfgGraph>, String> {
+    let mut graphs = Vec::new();
+    let mut function = module.get_first_function();
+    while let Some(f) = function {
+        let name = f.get_name().to_string_lossy();
+        if f.count_basic_blocks() > 0 && only.is_none_or(|only| only == name) {
+            graphs.push(CfgGraph::from_function(f));
+        }
+        function = f.get_next_function();
+    }
+
+    match only {
+        Some(name) if graphs.is_empty() => Err(format!("No compiled function named '{}'", name)),
+        _ => Ok(graphs),
+    }
+}
+
+/// Names of functions in a module that fail LLVM verification
+pub fn unverified_functions(module: &Module<'_>, only: Option<&str>) -> Vec<String> {
+    let mut failures = Vec::new();
+    let mut function = module.get_first_function();
+    while let Some(f) = function {
+        let name = f.get_name().to_string_lossy().into_owned();
+        if f.count_basic_blocks() > 0 && only.is_none_or(|only| only == name) && !f.verify(false) {
+            failures.push(name);
+        }
+        function = f.get_next_function();
+    }
+    failures
+}
+
+impl<'ctx> Compiler<'ctx> {
+    /// Control flow graphs of the compiled functions, before optimization
+    pub fn cfg_graphs(&self, only: Option<&str>) -> Result<Vec<CfgGraph>, String> {
+        module_cfgs(&self.context.module, only)
+    }
+
+    /// Control flow graphs after running the optimization pipeline for `level` on a copy of the module
+    pub fn optimized_cfg_graphs(&self, only: Option<&str>, level: u8) -> Result<Vec<CfgGraph>, String> {
+        let module = self.context.module.clone();
+        self.run_optimization_pipeline(&module, level)?;
+        module_cfgs(&module, only)
+    }
+}
@@ -0,0 +1,302 @@
+// class_layout.rs - Fixed struct layouts for classes with a static attribute set
+//
+// When every attribute of a class is assigned with an annotation in
+// `__init__` (`self.x: int = x`), its instances can be laid out as a struct
+// with one field per attribute, in the order they're first assigned, so
+// attribute access is a load or store at a known offset. A class keeps
+// dict-backed attributes when anything could add attributes the layout
+// doesn't have: base classes, unannotated or later-added attributes,
+// attribute stores from outside the class, `del`, `setattr`, `delattr` or
+// `__dict__`.
+
+use crate::ast::{Expr, Stmt};
+use crate::compiler::types::Type;
+use crate::linter::{blocks, exprs};
+use crate::typechecker::lints::children;
+#[cfg(feature = "codegen")]
+use inkwell::context::Context;
+#[cfg(feature = "codegen")]
+use inkwell::types::{BasicTypeEnum, StructType};
+
+/// Functions that add or remove attributes by name at runtime
+const DYNAMIC_ATTRIBUTE_FUNCTIONS: [&str; 2] = ["setattr", "delattr"];
+
+/// Field order and types of a class whose instances are fixed structs
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassLayout {
+    pub name: String,
+    pub fields: Vec<(String, Type)>,
+}
+
+impl ClassLayout {
+    /// Index of the struct field holding `attribute`
+    pub fn field_index(&self, attribute: &str) -> Option<usize> {
+        self.fields.iter().position(|(name, _)| name == attribute)
+    }
+
+    /// Declared type of `attribute`
+    pub fn field_type(&self, attribute: &str) -> Option<&Type> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name == attribute)
+            .map(|(_, ty)| ty)
+    }
+
+    /// LLVM struct type of an instance, named after the class
+    #[cfg(feature = "codegen")]
+    pub fn struct_type<'ctx>(&self, context: &'ctx Context) -> StructType<'ctx> {
+        let field_types: Vec<BasicTypeEnum> = self
+            .fields
+            .iter()
+            .map(|(_, ty)| ty.to_llvm_type(context))
+            .collect();
+
+        let struct_type = context.opaque_struct_type(&self.name);
+        struct_type.set_body(&field_types, false);
+        struct_type
+    }
+}
+
+/// How instances of a class store their attributes
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeStorage {
+    Fixed(ClassLayout),
+    /// A per-instance dict, for the reason given
+    Dynamic(String),
+}
+
+/// Decide how instances of `class`, a class definition in `module`, store
+/// their attributes
+pub fn analyze(class: &Stmt, module: &[Box<Stmt>]) -> AttributeStorage {
+    let Stmt::ClassDef {
+        name, bases, body, ..
+    } = class
+    else {
+        return AttributeStorage::Dynamic("not a class definition".to_string());
+    };
+    match layout(name, bases, body).and_then(|layout| check_uses(layout, module)) {
+        Ok(layout) => AttributeStorage::Fixed(layout),
+        Err(reason) => AttributeStorage::Dynamic(reason),
+    }
+}
+
+/// The layout declared by the annotated assignments in `__init__`
+fn layout(name: &str, bases: &[Box<Expr>], body: &[Box<Stmt>]) -> Result<ClassLayout, String> {
+    if let Some(base) = bases
+        .iter()
+        .find(|base| !matches!(base.as_ref(), Expr::Name { id, .. } if id == "object"))
+    {
+        return Err(match base.as_ref() {
+            Expr::Name { id, .. } => format!("{} inherits from {}", name, id),
+            _ => format!("{} has a base class", name),
+        });
+    }
+
+    let mut layout = ClassLayout {
+        name: name.to_string(),
+        fields: Vec::new(),
+    };
+    let init = body.iter().find_map(|stmt| match stmt.as_ref() {
+        Stmt::FunctionDef {
+            name, params, body, ..
+        } if name == "__init__" => Some((params, body)),
+        _ => None,
+    });
+    if let Some((params, body)) = init {
+        if let Some(receiver) = params.first() {
+            declare_fields(&mut layout, &receiver.name, body)?;
+        }
+    }
+    Ok(layout)
+}
+
+/// Add the attributes `__init__` assigns to `layout`, in order
+fn declare_fields(layout: &mut ClassLayout, receiver: &str, body: &[Box<Stmt>]) -> Result<(), String> {
+    for stmt in body {
+        match stmt.as_ref() {
+            // Nested functions can run later, like any other method
+            Stmt::FunctionDef { .. } | Stmt::ClassDef { .. } => continue,
+            Stmt::AnnAssign {
+                target, annotation, ..
+            } => {
+                if let Some(attr) = receiver_attribute(target, receiver) {
+                    let ty = annotation_type(annotation).ok_or_else(|| {
+                        format!("the annotation of '{}' is not a type", attr)
+                    })?;
+                    match layout.field_type(attr) {
+                        Some(existing) if *existing != ty => {
+                            return Err(format!(
+                                "'{}' is annotated as both {} and {}",
+                                attr, existing, ty
+                            ))
+                        }
+                        Some(_) => {}
+                        None => layout.fields.push((attr.to_string(), ty)),
+                    }
+                }
+            }
+            stmt => {
+                for target in store_targets(stmt) {
+                    if let Some(attr) = receiver_attribute(target, receiver) {
+                        if layout.field_index(attr).is_none() {
+                            return Err(format!("'{}' is assigned without an annotation", attr));
+                        }
+                    }
+                }
+            }
+        }
+        for block in blocks(stmt) {
+            declare_fields(layout, receiver, block)?;
+        }
+    }
+    Ok(())
+}
+
+/// `layout` if nothing in `module` adds attributes it doesn't have
+fn check_uses(layout: ClassLayout, module: &[Box<Stmt>]) -> Result<ClassLayout, String> {
+    check_block(&layout, module, None)?;
+    Ok(layout)
+}
+
+/// Check `body`, where `receiver` is the name of the instance a method of
+/// `class` was called on
+fn check_block(layout: &ClassLayout, body: &[Box<Stmt>], receiver: Option<(&str, &str)>) -> Result<(), String> {
+    for stmt in body {
+        if let Stmt::ClassDef { name, body, .. } = stmt.as_ref() {
+            for member in body {
+                match member.as_ref() {
+                    Stmt::FunctionDef { params, body, .. } => {
+                        let receiver = params.first().map(|param| (name.as_str(), param.name.as_str()));
+                        check_exprs(member)?;
+                        check_block(layout, body, receiver)?;
+                    }
+                    _ => check_block(layout, std::slice::from_ref(member), None)?,
+                }
+            }
+            continue;
+        }
+
+        let deletes = matches!(stmt.as_ref(), Stmt::Delete { .. });
+        for target in store_targets(stmt) {
+            let Expr::Attribute { value, attr, .. } = target else {
+                continue;
+            };
+            let on_receiver = match (value.as_ref(), receiver) {
+                (Expr::Name { id, .. }, Some((class, name))) if id == name => Some(class),
+                _ => None,
+            };
+            match on_receiver {
+                // Another class's instance
+                Some(class) if class != layout.name => {}
+                Some(_) if deletes => return Err(format!("'{}' is deleted", attr)),
+                Some(_) if layout.field_index(attr).is_none() => {
+                    return Err(format!("'{}' is assigned outside __init__", attr))
+                }
+                None if deletes && layout.field_index(attr).is_some() => {
+                    return Err(format!("'{}' may be deleted", attr))
+                }
+                None if layout.field_index(attr).is_none() => {
+                    return Err(format!("'{}' may be assigned from outside the class", attr))
+                }
+                _ => {}
+            }
+        }
+        check_exprs(stmt)?;
+        for block in blocks(stmt) {
+            check_block(layout, block, receiver)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reject `setattr`, `delattr` and `__dict__` in the expressions of `stmt`
+fn check_exprs(stmt: &Stmt) -> Result<(), String> {
+    let mut pending = exprs(stmt);
+    while let Some(expr) = pending.pop() {
+        match expr {
+            Expr::Call { func, .. } => {
+                if let Expr::Name { id, .. } = func.as_ref() {
+                    if DYNAMIC_ATTRIBUTE_FUNCTIONS.contains(&id.as_str()) {
+                        return Err(format!("{} is used", id));
+                    }
+                }
+            }
+            Expr::Attribute { attr, .. } if attr == "__dict__" => {
+                return Err("__dict__ is used".to_string())
+            }
+            _ => {}
+        }
+        pending.extend(children(expr));
+    }
+    Ok(())
+}
+
+/// Expressions `stmt` assigns to or deletes, with tuple targets unpacked
+fn store_targets(stmt: &Stmt) -> Vec<&Expr> {
+    let mut targets: Vec<&Expr> = match stmt {
+        Stmt::Assign { targets, .. } | Stmt::Delete { targets, .. } => {
+            targets.iter().map(|e| &**e).collect()
+        }
+        Stmt::AugAssign { target, .. } | Stmt::AnnAssign { target, .. } | Stmt::For { target, .. } => {
+            vec![target]
+        }
+        Stmt::With { items, .. } => items.iter().filter_map(|(_, target)| target.as_deref()).collect(),
+        _ => Vec::new(),
+    };
+    let mut i = 0;
+    while i < targets.len() {
+        match targets[i] {
+            Expr::Tuple { elts, .. } | Expr::List { elts, .. } => {
+                let elts: Vec<&Expr> = elts.iter().map(|e| &**e).collect();
+                targets.splice(i..=i, elts);
+            }
+            Expr::Starred { value, .. } => targets[i] = value,
+            _ => i += 1,
+        }
+    }
+    targets
+}
+
+/// The attribute `target` stores into on `receiver`, as in `self.x`
+fn receiver_attribute<'a>(target: &'a Expr, receiver: &str) -> Option<&'a str> {
+    match target {
+        Expr::Attribute { value, attr, .. } => match value.as_ref() {
+            Expr::Name { id, .. } if id == receiver => Some(attr),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Field type for an annotation, if it names one
+pub(crate) fn annotation_type(annotation: &Expr) -> Option<Type> {
+    match annotation {
+        Expr::Name { id, .. } => Some(match id.as_str() {
+            "int" => Type::Int,
+            "float" => Type::Float,
+            "bool" => Type::Bool,
+            "str" => Type::String,
+            "list" => Type::List(Box::new(Type::Any)),
+            "dict" => Type::Dict(Box::new(Type::Any), Box::new(Type::Any)),
+            "set" => Type::Set(Box::new(Type::Any)),
+            class => Type::class(class),
+        }),
+        // A forward reference to a class defined later
+        Expr::Str { value, .. } => Some(Type::class(value)),
+        Expr::Subscript { value, slice, .. } => {
+            let Expr::Name { id, .. } = value.as_ref() else {
+                return None;
+            };
+            match (id.as_str(), slice.as_ref()) {
+                ("list" | "List", element) => Some(Type::List(Box::new(annotation_type(element)?))),
+                ("set" | "Set", element) => Some(Type::Set(Box::new(annotation_type(element)?))),
+                ("dict" | "Dict", Expr::Tuple { elts, .. }) if elts.len() == 2 => Some(Type::Dict(
+                    Box::new(annotation_type(&elts[0])?),
+                    Box::new(annotation_type(&elts[1])?),
+                )),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,284 @@
+// class_object.rs - Classes as runtime values
+//
+// A class definition creates a class object when it runs, and the class
+// attributes its body assigns are stored on it. The class's name then
+// evaluates to that object, which can be stored and passed around like
+// any other value and called to create an instance:
+//
+//     class Counter:
+//         count = 0               # class_set_attr(@Counter, "count", 0)
+//
+//     Counter.count += 1          # class_get_attr / class_set_attr
+//     make = Counter
+//     c = make()                  # class_instantiate(make), then __init__
+//
+// A class attribute's type comes from its annotation or the literal it is
+// assigned, so code compiled before the class body knows it. Other
+// attributes are typed when the body is compiled.
+
+use crate::ast::{Expr, NameConstant, Number, Stmt};
+use crate::compiler::class_layout::annotation_type;
+use crate::compiler::context::CompilationContext;
+use crate::compiler::expr::ExprCompiler;
+use crate::compiler::super_call::method_symbol;
+use crate::compiler::types::Type;
+use crate::intern::Symbol;
+use crate::typechecker::mro;
+use inkwell::values::{BasicMetadataValueEnum, BasicValueEnum, PointerValue};
+
+/// The class attributes assigned in a class body, with their targets'
+/// annotations and values
+fn class_attribute_assignments(body: &[Box<Stmt>]) -> Vec<(&str, Option<&Expr>, &Expr)> {
+    let mut assignments = Vec::new();
+    for stmt in body {
+        match stmt.as_ref() {
+            Stmt::Assign { targets, value, .. } => {
+                for target in targets {
+                    if let Expr::Name { id, .. } = target.as_ref() {
+                        assignments.push((id.as_str(), None, value.as_ref()));
+                    }
+                }
+            }
+            Stmt::AnnAssign {
+                target,
+                annotation,
+                value: Some(value),
+                ..
+            } => {
+                if let Expr::Name { id, .. } = target.as_ref() {
+                    assignments.push((id.as_str(), Some(annotation.as_ref()), value.as_ref()));
+                }
+            }
+            _ => {}
+        }
+    }
+    assignments
+}
+
+/// The type of a class attribute known without compiling its value
+fn declared_attribute_type(annotation: Option<&Expr>, value: &Expr) -> Type {
+    if let Some(ty) = annotation.and_then(annotation_type) {
+        return ty;
+    }
+    match value {
+        Expr::Num { value: Number::Integer(_), .. } => Type::Int,
+        Expr::Num { value: Number::Float(_), .. } => Type::Float,
+        Expr::Str { .. } => Type::String,
+        Expr::NameConstant { value: NameConstant::None, .. } => Type::None,
+        Expr::NameConstant { .. } => Type::Bool,
+        _ => Type::Unknown,
+    }
+}
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// Record class `name` and the types of the class attributes its body
+    /// declares, before any code using them is compiled
+    pub fn declare_class_attributes(&mut self, name: &str, body: &[Box<Stmt>]) {
+        let attributes = class_attribute_assignments(body)
+            .into_iter()
+            .map(|(attr, annotation, value)| {
+                (attr.to_string(), declared_attribute_type(annotation, value))
+            })
+            .collect();
+        self.class_attributes.insert(name.to_string(), attributes);
+    }
+
+    /// Whether `name` is a class of the module being compiled
+    pub fn is_class_name(&self, name: &str) -> bool {
+        self.class_attributes.contains_key(name)
+    }
+
+    /// The class object of `name`
+    pub fn compile_class_object(&mut self, name: &str) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        let class_object = self
+            .runtime_function("class_object")
+            .ok_or_else(|| "class_object function not found".to_string())?;
+        let class_name = self.string_constant(name.as_bytes());
+        let object = self
+            .builder
+            .build_call(class_object, &[class_name.into()], "class_object")
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .ok_or_else(|| format!("Failed to get the class object of '{}'", name))?;
+        Ok((object, Type::ClassObject(name.to_string())))
+    }
+
+    /// Run the body of class `name`: create its class object, record its
+    /// methods for dir() and store the class attributes the body assigns
+    pub fn compile_class_body(&mut self, name: &str, body: &[Box<Stmt>]) -> Result<(), String> {
+        let class_define = self
+            .runtime_function("class_define")
+            .ok_or_else(|| "class_define function not found".to_string())?;
+        let bases_of = |class: &str| self.class_bases.get(class).cloned().unwrap_or_default();
+        let order = mro::linearize(name, &bases_of).unwrap_or_else(|_| vec![name.to_string()]);
+        let class_name = self.string_constant(name.as_bytes());
+        let following = self.string_constant(order[1..].join(",").as_bytes());
+        let object = self
+            .builder
+            .build_call(class_define, &[class_name.into(), following.into()], "class")
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .ok_or_else(|| format!("Failed to define class '{}'", name))?
+            .into_pointer_value();
+
+        let methods: Vec<String> = self
+            .class_methods
+            .get(name)
+            .map(|methods| methods.iter().map(|(method, _)| method.clone()).collect())
+            .unwrap_or_default();
+        if !methods.is_empty() {
+            let set_methods = self
+                .runtime_function("class_set_methods")
+                .ok_or_else(|| "class_set_methods function not found".to_string())?;
+            let methods = self.string_constant(methods.join(",").as_bytes());
+            self.builder
+                .build_call(set_methods, &[class_name.into(), methods.into()], "")
+                .unwrap();
+        }
+
+        if !self.class_attributes.contains_key(name) {
+            self.declare_class_attributes(name, body);
+        }
+        for (attr, _, value) in class_attribute_assignments(body) {
+            let (value, value_type) = self.compile_expr(value)?;
+            self.store_class_attribute(object, name, attr, value, &value_type)?;
+        }
+        Ok(())
+    }
+
+    /// The type of class attribute `attr` of `class`, looking through its MRO
+    pub(crate) fn class_attribute_type(&self, class: &str, attr: &str) -> Option<(&str, &Type)> {
+        let bases_of = |class: &str| self.class_bases.get(class).cloned().unwrap_or_default();
+        let order = mro::linearize(class, &bases_of).unwrap_or_else(|_| vec![class.to_string()]);
+        order.iter().find_map(|owner| {
+            let (owner, attributes) = self.class_attributes.get_key_value(owner.as_str())?;
+            Some((owner.as_str(), attributes.get(attr)?))
+        })
+    }
+
+    /// Load class attribute `attr` from `object`, the class object of `class`
+    pub fn compile_class_attribute_load(
+        &mut self,
+        object: BasicValueEnum<'ctx>,
+        class: &str,
+        attr: &str,
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        let ty = match self.class_attribute_type(class, attr) {
+            Some((owner, Type::Unknown)) => {
+                return Err(format!(
+                    "The type of class attribute '{}.{}' isn't known before the class body runs; annotate it",
+                    owner, attr
+                ))
+            }
+            Some((_, ty)) => ty.clone(),
+            // Attributes can be added at runtime, so a missing one is the
+            // runtime's AttributeError
+            None => Type::Any,
+        };
+
+        let get_attr = self
+            .runtime_function("class_get_attr")
+            .ok_or_else(|| "class_get_attr function not found".to_string())?;
+        let name = self.string_constant(attr.as_bytes());
+        let word = self
+            .builder
+            .build_call(get_attr, &[object.into_pointer_value().into(), name.into()], "class_attr_word")
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .ok_or_else(|| format!("Failed to load class attribute '{}'", attr))?
+            .into_int_value();
+        Ok((self.value_from_word(word, &ty), ty))
+    }
+
+    /// Store `value` as class attribute `attr` of `object`, the class object
+    /// of `class`, converted to the attribute's declared type
+    pub fn store_class_attribute(
+        &mut self,
+        object: PointerValue<'ctx>,
+        class: &str,
+        attr: &str,
+        value: BasicValueEnum<'ctx>,
+        value_type: &Type,
+    ) -> Result<(), String> {
+        let declared = self
+            .class_attribute_type(class, attr)
+            .map(|(_, ty)| ty.clone())
+            .filter(|ty| *ty != Type::Unknown);
+        let (value, ty) = match declared {
+            Some(ty) => (self.convert_type(value, value_type, &ty)?, ty),
+            None => {
+                self.class_attributes
+                    .entry(class.to_string())
+                    .or_default()
+                    .insert(attr.to_string(), value_type.clone());
+                (value, value_type.clone())
+            }
+        };
+
+        let set_attr = self
+            .runtime_function("class_set_attr")
+            .ok_or_else(|| "class_set_attr function not found".to_string())?;
+        let name = self.string_constant(attr.as_bytes());
+        let word = self.word_from_value(value, &ty)?;
+        self.builder
+            .build_call(set_attr, &[object.into(), name.into(), word.into()], "")
+            .unwrap();
+        Ok(())
+    }
+
+    /// Call `object`, the class object of `class`: create an instance and
+    /// run the `__init__` its MRO provides on it with `args`
+    pub fn compile_class_call(
+        &mut self,
+        object: BasicValueEnum<'ctx>,
+        class: &str,
+        args: &[Box<Expr>],
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        let bases_of = |name: &str| self.class_bases.get(name).cloned().unwrap_or_default();
+        let order = mro::linearize(class, &bases_of).unwrap_or_else(|_| vec![class.to_string()]);
+        let init = order
+            .iter()
+            .find_map(|owner| self.module.get_function(&method_symbol(owner, "__init__")));
+        let defines_init = order.iter().any(|owner| {
+            self.class_methods
+                .get(owner)
+                .is_some_and(|methods| methods.iter().any(|(method, _)| method == "__init__"))
+        });
+        if init.is_none() && defines_init {
+            return Err(format!("Method calls for class '{}' are not yet implemented", class));
+        }
+        if init.is_none() && !args.is_empty() {
+            return Err(format!("{}() takes no arguments", class));
+        }
+
+        let instantiate = self
+            .runtime_function("class_instantiate")
+            .ok_or_else(|| "class_instantiate function not found".to_string())?;
+        let instance = self
+            .builder
+            .build_call(instantiate, &[object.into_pointer_value().into()], "instance")
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .ok_or_else(|| format!("Failed to instantiate class '{}'", class))?;
+
+        if let Some(init) = init {
+            let mut call_args: Vec<BasicMetadataValueEnum<'ctx>> = vec![instance.into()];
+            for arg in args {
+                let (value, _) = self.compile_expr(arg)?;
+                call_args.push(value.into());
+            }
+            self.builder.build_call(init, &call_args, "").unwrap();
+            self.emit_call_check()?;
+        }
+
+        let instance_type = match self.type_env.get(&Symbol::intern(class)) {
+            Some(ty @ Type::Class { .. }) => ty.clone(),
+            _ => Type::class(class),
+        };
+        Ok((instance, instance_type))
+    }
+}
@@ -0,0 +1,57 @@
+// constant_pool.rs - Module-wide pool of constant globals
+//
+// String literals, f-string pieces and the strings used by print() and str()
+// are emitted as constant globals. The pool hands out one global per distinct
+// contents, so a literal repeated throughout a program (or the empty string
+// every f-string starts from) is stored once per module.
+
+use crate::compiler::context::CompilationContext;
+use inkwell::module::Linkage;
+use inkwell::values::{GlobalValue, PointerValue};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Constant globals of a module, keyed by their contents
+#[derive(Default)]
+pub struct ConstantPool<'ctx> {
+    strings: RefCell<HashMap<Vec<u8>, GlobalValue<'ctx>>>,
+}
+
+impl<'ctx> ConstantPool<'ctx> {
+    /// Create an empty pool
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of distinct string constants emitted so far
+    pub fn string_count(&self) -> usize {
+        self.strings.borrow().len()
+    }
+}
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// Pointer to a NUL-terminated constant holding `bytes`, shared by every
+    /// use of the same contents in the module
+    pub fn string_constant(&self, bytes: &[u8]) -> PointerValue<'ctx> {
+        self.pooled_string("str_const", bytes)
+    }
+
+    /// Like `string_constant`, naming the global `name` if the contents are new
+    pub(crate) fn pooled_string(&self, name: &str, bytes: &[u8]) -> PointerValue<'ctx> {
+        if let Some(global) = self.constant_pool.strings.borrow().get(bytes) {
+            return global.as_pointer_value();
+        }
+
+        let value = self.llvm_context.const_string(bytes, true);
+        let global = self.module.add_global(value.get_type(), None, name);
+        global.set_constant(true);
+        global.set_linkage(Linkage::Private);
+        global.set_unnamed_addr(true);
+        global.set_initializer(&value);
+        self.constant_pool
+            .strings
+            .borrow_mut()
+            .insert(bytes.to_vec(), global);
+        global.as_pointer_value()
+    }
+}
@@ -3,14 +3,20 @@ use inkwell::builder::Builder;
 use inkwell::context::Context;
 use inkwell::module::Module;
 use inkwell::values::BasicValueEnum;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 // use inkwell::types::BasicType;
 use crate::ast;
+use crate::compiler::class_layout::ClassLayout;
 use crate::compiler::closure::ClosureEnvironment;
+use crate::compiler::constant_pool::ConstantPool;
+use crate::compiler::debug_info::DebugInfo;
+use crate::compiler::ffi::ExternSignature;
+use crate::compiler::runtime::RuntimeCatalog;
 use crate::compiler::scope::ScopeStack;
 use crate::compiler::stmt::StmtCompiler;
 use crate::compiler::types::is_reference_type;
 use crate::compiler::types::Type;
+use crate::intern::Symbol;
 
 /// Loop context for managing break and continue statements
 pub struct LoopContext<'ctx> {
@@ -30,16 +36,26 @@ pub struct CompilationContext<'ctx> {
     pub builder: Builder<'ctx>,
 
     /// Type environment mapping variable names to their types
-    pub type_env: HashMap<String, Type>,
+    pub type_env: HashMap<Symbol, Type>,
 
     /// Map of function names to their LLVM function values
     pub functions: HashMap<String, inkwell::values::FunctionValue<'ctx>>,
 
     /// Map of class names to their LLVM struct types
     pub class_types: HashMap<String, inkwell::types::StructType<'ctx>>,
+    /// Field layouts of the classes whose instances are fixed structs
+    pub class_layouts: HashMap<String, ClassLayout>,
+    /// Base classes of each class compiled, for method resolution order
+    pub class_bases: HashMap<String, Vec<String>>,
+    /// Methods of each class compiled, with whether each is abstract
+    pub class_methods: HashMap<String, Vec<(String, bool)>>,
+    /// Types of the class attributes of each class, by class name
+    pub class_attributes: HashMap<String, HashMap<String, Type>>,
+    /// The class whose method is being compiled, which `super()` starts from
+    pub current_class: Option<String>,
 
     /// Map of variable names to their LLVM pointer values (storage locations)
-    pub variables: HashMap<String, inkwell::values::PointerValue<'ctx>>,
+    pub variables: HashMap<Symbol, inkwell::values::PointerValue<'ctx>>,
 
     /// Stack of loop contexts for break/continue statements
     pub loop_stack: Vec<LoopContext<'ctx>>,
@@ -51,7 +67,7 @@ pub struct CompilationContext<'ctx> {
     pub current_function: Option<inkwell::values::FunctionValue<'ctx>>,
 
     /// Local variables in the current function scope
-    pub local_vars: HashMap<String, inkwell::values::PointerValue<'ctx>>,
+    pub local_vars: HashMap<Symbol, inkwell::values::PointerValue<'ctx>>,
 
     /// Stack of variable scopes
     pub scope_stack: ScopeStack<'ctx>,
@@ -70,6 +86,47 @@ pub struct CompilationContext<'ctx> {
 
     /// Temporary objects that need cleanup
     pub temp_objects: Vec<(*mut std::ffi::c_void, fn(*mut std::ffi::c_void))>,
+
+    /// Except blocks being compiled, with their function and the loop depth they are in
+    pub exception_handlers: Vec<(inkwell::values::FunctionValue<'ctx>, usize)>,
+
+    /// Resources owned by enclosing `with` blocks, with their function, loop
+    /// depth, the slot holding the resource and the runtime function releasing it
+    pub with_scopes: Vec<(
+        inkwell::values::FunctionValue<'ctx>,
+        usize,
+        inkwell::values::PointerValue<'ctx>,
+        &'static str,
+    )>,
+
+    /// Strings accumulated with `+=` in the loops being compiled, mapped to
+    /// the runtime builder collecting their pieces
+    pub string_builders: HashMap<String, inkwell::values::PointerValue<'ctx>>,
+
+    /// Traceback frame depth saved on entry to the function being compiled,
+    /// restored when it returns
+    pub trace_frame: Option<inkwell::values::IntValue<'ctx>>,
+
+    /// Constant globals shared across the module
+    pub constant_pool: ConstantPool<'ctx>,
+
+    /// DWARF debug info state, when debug info is enabled
+    pub debug_info: Option<DebugInfo<'ctx>>,
+
+    /// Runtime functions, declared in the module on first use
+    pub runtime_catalog: RuntimeCatalog<'ctx>,
+
+    /// Module-level integer constants that `range()` bounds are folded through
+    pub range_constants: HashMap<String, i64>,
+
+    /// Return types of module-level functions, as annotated or inferred by the type checker
+    pub return_types: HashMap<String, Type>,
+
+    /// Comprehension result lists allocated with room for every element
+    pub presized_lists: HashSet<inkwell::values::PointerValue<'ctx>>,
+
+    /// C functions declared with `@extern`, by their name in the program
+    pub extern_functions: HashMap<String, ExternSignature>,
 }
 
 impl<'ctx> CompilationContext<'ctx> {
@@ -85,6 +142,11 @@ impl<'ctx> CompilationContext<'ctx> {
             type_env: HashMap::new(),
             functions: HashMap::new(),
             class_types: HashMap::new(),
+            class_layouts: HashMap::new(),
+            class_bases: HashMap::new(),
+            class_methods: HashMap::new(),
+            class_attributes: HashMap::new(),
+            current_class: None,
             variables: HashMap::new(),
             loop_stack: Vec::new(),
             polymorphic_functions: HashMap::new(),
@@ -96,9 +158,35 @@ impl<'ctx> CompilationContext<'ctx> {
             unique_id_counter: 0,
             pending_method_calls: HashMap::new(),
             temp_objects: Vec::new(),
+            exception_handlers: Vec::new(),
+            with_scopes: Vec::new(),
+            string_builders: HashMap::new(),
+            trace_frame: None,
+            constant_pool: ConstantPool::new(),
+            debug_info: None,
+            runtime_catalog: RuntimeCatalog::new(),
+            range_constants: HashMap::new(),
+            return_types: HashMap::new(),
+            presized_lists: HashSet::new(),
+            extern_functions: HashMap::new(),
         }
     }
 
+    /// Look up a function in the module, declaring it first if it is a runtime
+    /// function that has not been referenced yet
+    pub fn runtime_function(&self, name: &str) -> Option<inkwell::values::FunctionValue<'ctx>> {
+        self.module
+            .get_function(name)
+            .or_else(|| self.runtime_catalog.declare(self.llvm_context, &self.module, name))
+    }
+
+    /// Runtime type tag for a value of `ty`, as passed to tagged container functions
+    pub fn type_tag(&self, ty: &Type) -> inkwell::values::IntValue<'ctx> {
+        self.llvm_context
+            .i8_type()
+            .const_int(crate::compiler::runtime::list::TypeTag::for_type(ty) as u64, false)
+    }
+
     /// Get or create a type in the LLVM context
     pub fn get_llvm_type(&self, ty: &Type) -> inkwell::types::BasicTypeEnum<'ctx> {
         ty.to_llvm_type(self.llvm_context)
@@ -106,7 +194,7 @@ impl<'ctx> CompilationContext<'ctx> {
 
     /// Register a variable with its type
     pub fn register_variable(&mut self, name: String, ty: Type) {
-        self.type_env.insert(name, ty);
+        self.type_env.insert(Symbol::intern(&name), ty);
     }
 
     /// Look up a variable's type
@@ -115,7 +203,7 @@ impl<'ctx> CompilationContext<'ctx> {
             return Some(ty);
         }
 
-        self.type_env.get(name)
+        self.type_env.get(&Symbol::intern(name))
     }
 
     /// Register a class type
@@ -132,7 +220,24 @@ impl<'ctx> CompilationContext<'ctx> {
             self.class_types.insert(name.clone(), struct_type);
         }
 
-        self.type_env.insert(name, ty);
+        self.type_env.insert(Symbol::intern(&name), ty);
+    }
+
+    /// Register a class whose instances are structs laid out by `layout`
+    pub fn register_class_layout(&mut self, layout: ClassLayout) {
+        let name = layout.name.clone();
+        self.class_types
+            .insert(name.clone(), layout.struct_type(self.llvm_context));
+        self.type_env.insert(
+            Symbol::intern(&name),
+            Type::Class {
+                name: name.clone(),
+                base_classes: vec![],
+                methods: HashMap::new(),
+                fields: layout.fields.iter().cloned().collect(),
+            },
+        );
+        self.class_layouts.insert(name, layout);
     }
 
     pub fn declare_variable(
@@ -186,14 +291,15 @@ impl<'ctx> CompilationContext<'ctx> {
         let ptr = self.builder.build_alloca(llvm_type, &var_name).unwrap();
 
         self.builder.position_at_end(current_position);
+        self.declare_debug_variable(&name, ptr, ty);
 
-        self.variables.insert(name.clone(), ptr);
+        self.variables.insert(Symbol::intern(&name), ptr);
 
         self.add_variable_to_scope(name.clone(), ptr, ty.clone());
 
         println!("Added variable '{}' to current scope", name);
 
-        if !self.type_env.contains_key(&name) {
+        if !self.type_env.contains_key(&Symbol::intern(&name)) {
             self.register_variable(name, ty.clone());
         }
 
@@ -206,11 +312,11 @@ impl<'ctx> CompilationContext<'ctx> {
             return Some(*ptr);
         }
 
-        if let Some(&ptr) = self.local_vars.get(name) {
+        if let Some(&ptr) = self.local_vars.get(&Symbol::intern(name)) {
             return Some(ptr);
         }
 
-        self.variables.get(name).copied()
+        self.variables.get(&Symbol::intern(name)).copied()
     }
 
     /// Ensure a variable exists in the current scope or create it if it's a global variable
@@ -219,8 +325,8 @@ impl<'ctx> CompilationContext<'ctx> {
             return Some(ptr);
         }
 
-        if self.type_env.contains_key(name) {
-            let ty = self.type_env.get(name).unwrap().clone();
+        if self.type_env.contains_key(&Symbol::intern(name)) {
+            let ty = self.type_env.get(&Symbol::intern(name)).unwrap().clone();
             let ptr = self.allocate_variable(name.to_string(), &ty);
             return Some(ptr);
         }
@@ -313,7 +419,7 @@ impl<'ctx> CompilationContext<'ctx> {
         let function_name = current_function.get_name().to_str().unwrap_or("unknown");
         let shadow_name = format!("shadow_{}_{}", function_name, name);
 
-        let shadow_ptr = if let Some(shadow) = self.variables.get(&shadow_name) {
+        let shadow_ptr = if let Some(shadow) = self.variables.get(&Symbol::intern(&shadow_name)) {
             *shadow
         } else {
             let shadow = self.create_shadow_variable(ptr, llvm_type, name);
@@ -336,7 +442,7 @@ impl<'ctx> CompilationContext<'ctx> {
 
             self.builder.position_at_end(current_position);
 
-            self.variables.insert(shadow_name.clone(), shadow);
+            self.variables.insert(Symbol::intern(&shadow_name), shadow);
 
             shadow
         };
@@ -362,12 +468,12 @@ impl<'ctx> CompilationContext<'ctx> {
         let function_name = current_function.get_name().to_str().unwrap_or("unknown");
         let shadow_name = format!("shadow_{}_{}", function_name, name);
 
-        let shadow_ptr = if let Some(shadow) = self.variables.get(&shadow_name) {
+        let shadow_ptr = if let Some(shadow) = self.variables.get(&Symbol::intern(&shadow_name)) {
             *shadow
         } else {
             let shadow = self.create_shadow_variable(ptr, value.get_type(), name);
 
-            self.variables.insert(shadow_name.clone(), shadow);
+            self.variables.insert(Symbol::intern(&shadow_name), shadow);
 
             shadow
         };
@@ -565,12 +671,68 @@ impl<'ctx> CompilationContext<'ctx> {
         }
     }
 
+    /// Unify the element types of a container literal.
+    ///
+    /// Numeric elements are promoted along the `Bool < Int < Float` lattice and
+    /// converted to the promoted type; any other mix of types becomes `Any`.
+    pub fn unify_element_types(
+        &self,
+        elements: Vec<(BasicValueEnum<'ctx>, Type)>,
+    ) -> Result<(Vec<(BasicValueEnum<'ctx>, Type)>, Type), String> {
+        let mut common_type = Type::Unknown;
+        for (_, ty) in &elements {
+            common_type = if common_type == Type::Unknown || common_type == *ty {
+                ty.clone()
+            } else {
+                Type::promote_numeric(&common_type, ty).unwrap_or(Type::Any)
+            };
+        }
+
+        if common_type.numeric_rank().is_none() {
+            return Ok((elements, common_type));
+        }
+
+        let mut converted = Vec::with_capacity(elements.len());
+        for (value, ty) in elements {
+            let value = self.convert_type(value, &ty, &common_type)?;
+            converted.push((value, common_type.clone()));
+        }
+        Ok((converted, common_type))
+    }
+
+    /// Numeric type an argument must be promoted to for an LLVM parameter.
+    ///
+    /// Returns `None` when no conversion is needed or the parameter is not a
+    /// numeric type the argument can be promoted to without loss.
+    pub fn numeric_param_type(
+        &self,
+        arg_type: &Type,
+        param_type: inkwell::types::BasicMetadataTypeEnum<'ctx>,
+    ) -> Option<Type> {
+        let target = if param_type.is_float_type() {
+            Type::Float
+        } else if param_type.is_int_type() && param_type.into_int_type().get_bit_width() == 64 {
+            Type::Int
+        } else {
+            return None;
+        };
+
+        match Type::promote_numeric(arg_type, &target) {
+            Some(promoted) if promoted == target && *arg_type != target => Some(target),
+            _ => None,
+        }
+    }
+
     /// Helper method to get the common type for binary operations
     pub fn get_common_type(&self, type1: &Type, type2: &Type) -> Result<Type, String> {
         if type1 == type2 {
             return Ok(type1.clone());
         }
 
+        if let Some(promoted) = Type::promote_numeric(type1, type2) {
+            return Ok(promoted);
+        }
+
         if let Type::Tuple(_) = type1 {
             return Ok(type1.clone());
         }
@@ -587,10 +749,6 @@ impl<'ctx> CompilationContext<'ctx> {
         }
 
         match (type1, type2) {
-            (Type::Int, Type::Float) | (Type::Float, Type::Int) => Ok(Type::Float),
-            (Type::Int, Type::Bool) | (Type::Bool, Type::Int) => Ok(Type::Int),
-            (Type::Float, Type::Bool) | (Type::Bool, Type::Float) => Ok(Type::Float),
-
             (Type::List(_), Type::Int) => Ok(type1.clone()),
             (Type::Int, Type::List(_)) => Ok(type2.clone()),
 
@@ -606,8 +764,7 @@ impl<'ctx> CompilationContext<'ctx> {
         int_val: inkwell::values::IntValue<'ctx>,
     ) -> Result<inkwell::values::BasicValueEnum<'ctx>, String> {
         let int_to_string_fn = self
-            .module
-            .get_function("int_to_string")
+            .runtime_function("int_to_string")
             .unwrap_or_else(|| {
                 let str_ptr_type = self.llvm_context.ptr_type(inkwell::AddressSpace::default());
                 let fn_type = str_ptr_type.fn_type(&[self.llvm_context.i64_type().into()], false);
@@ -631,8 +788,7 @@ impl<'ctx> CompilationContext<'ctx> {
         float_val: inkwell::values::FloatValue<'ctx>,
     ) -> Result<inkwell::values::BasicValueEnum<'ctx>, String> {
         let float_to_string_fn = self
-            .module
-            .get_function("float_to_string")
+            .runtime_function("float_to_string")
             .unwrap_or_else(|| {
                 let str_ptr_type = self.llvm_context.ptr_type(inkwell::AddressSpace::default());
                 let fn_type = str_ptr_type.fn_type(&[self.llvm_context.f64_type().into()], false);
@@ -670,7 +826,7 @@ impl<'ctx> CompilationContext<'ctx> {
                 }
             },
             crate::compiler::types::Type::Int => {
-                let int_to_string_fn = match self.module.get_function("int_to_string") {
+                let int_to_string_fn = match self.runtime_function("int_to_string") {
                     Some(f) => f,
                     None => return Err("int_to_string function not found".to_string()),
                 };
@@ -692,7 +848,7 @@ impl<'ctx> CompilationContext<'ctx> {
                 Ok(result.into_pointer_value())
             },
             crate::compiler::types::Type::Float => {
-                let float_to_string_fn = match self.module.get_function("float_to_string") {
+                let float_to_string_fn = match self.runtime_function("float_to_string") {
                     Some(f) => f,
                     None => return Err("float_to_string function not found".to_string()),
                 };
@@ -716,21 +872,13 @@ impl<'ctx> CompilationContext<'ctx> {
             crate::compiler::types::Type::Bool => {
                 // Convert boolean to "True" or "False"
                 let bool_val = value.into_int_value();
-                let true_str = self.llvm_context.const_string("True".as_bytes(), true);
-                let false_str = self.llvm_context.const_string("False".as_bytes(), true);
-
-                let true_global = self.module.add_global(true_str.get_type(), None, "true_str");
-                true_global.set_constant(true);
-                true_global.set_initializer(&true_str);
-
-                let false_global = self.module.add_global(false_str.get_type(), None, "false_str");
-                false_global.set_constant(true);
-                false_global.set_initializer(&false_str);
+                let true_global = self.pooled_string("true_str", b"True");
+                let false_global = self.pooled_string("false_str", b"False");
 
                 let true_ptr = self
                     .builder
                     .build_pointer_cast(
-                        true_global.as_pointer_value(),
+                        true_global,
                         self.llvm_context.ptr_type(inkwell::AddressSpace::default()),
                         "true_ptr",
                     )
@@ -739,7 +887,7 @@ impl<'ctx> CompilationContext<'ctx> {
                 let false_ptr = self
                     .builder
                     .build_pointer_cast(
-                        false_global.as_pointer_value(),
+                        false_global,
                         self.llvm_context.ptr_type(inkwell::AddressSpace::default()),
                         "false_ptr",
                     )
@@ -763,15 +911,12 @@ impl<'ctx> CompilationContext<'ctx> {
             },
             crate::compiler::types::Type::None => {
                 // Convert None to "None"
-                let none_str = self.llvm_context.const_string("None".as_bytes(), true);
-                let none_global = self.module.add_global(none_str.get_type(), None, "none_str");
-                none_global.set_constant(true);
-                none_global.set_initializer(&none_str);
+                let none_global = self.pooled_string("none_str", b"None");
 
                 let none_ptr = self
                     .builder
                     .build_pointer_cast(
-                        none_global.as_pointer_value(),
+                        none_global,
                         self.llvm_context.ptr_type(inkwell::AddressSpace::default()),
                         "none_ptr",
                     )
@@ -782,15 +927,12 @@ impl<'ctx> CompilationContext<'ctx> {
             _ => {
                 // For other types, use a placeholder string
                 let placeholder = format!("<{:?}>", value_type);
-                let placeholder_str = self.llvm_context.const_string(placeholder.as_bytes(), true);
-                let placeholder_global = self.module.add_global(placeholder_str.get_type(), None, "placeholder_str");
-                placeholder_global.set_constant(true);
-                placeholder_global.set_initializer(&placeholder_str);
+                let placeholder_global = self.pooled_string("placeholder_str", placeholder.as_bytes());
 
                 let placeholder_ptr = self
                     .builder
                     .build_pointer_cast(
-                        placeholder_global.as_pointer_value(),
+                        placeholder_global,
                         self.llvm_context.ptr_type(inkwell::AddressSpace::default()),
                         "placeholder_ptr",
                     )
@@ -806,8 +948,7 @@ impl<'ctx> CompilationContext<'ctx> {
         bool_val: inkwell::values::IntValue<'ctx>,
     ) -> Result<inkwell::values::BasicValueEnum<'ctx>, String> {
         let bool_to_string_fn = self
-            .module
-            .get_function("bool_to_string")
+            .runtime_function("bool_to_string")
             .unwrap_or_else(|| {
                 let str_ptr_type = self.llvm_context.ptr_type(inkwell::AddressSpace::default());
                 let fn_type = str_ptr_type.fn_type(&[self.llvm_context.i64_type().into()], false);
@@ -835,8 +976,7 @@ impl<'ctx> CompilationContext<'ctx> {
         string_ptr: inkwell::values::PointerValue<'ctx>,
     ) -> Result<inkwell::values::BasicValueEnum<'ctx>, String> {
         let string_to_int_fn = self
-            .module
-            .get_function("string_to_int")
+            .runtime_function("string_to_int")
             .unwrap_or_else(|| {
                 let i64_type = self.llvm_context.i64_type();
                 let str_ptr_type = self.llvm_context.ptr_type(inkwell::AddressSpace::default());
@@ -865,8 +1005,7 @@ impl<'ctx> CompilationContext<'ctx> {
         string_ptr: inkwell::values::PointerValue<'ctx>,
     ) -> Result<inkwell::values::BasicValueEnum<'ctx>, String> {
         let string_to_float_fn = self
-            .module
-            .get_function("string_to_float")
+            .runtime_function("string_to_float")
             .unwrap_or_else(|| {
                 let f64_type = self.llvm_context.f64_type();
                 let str_ptr_type = self.llvm_context.ptr_type(inkwell::AddressSpace::default());
@@ -895,8 +1034,7 @@ impl<'ctx> CompilationContext<'ctx> {
         string_ptr: inkwell::values::PointerValue<'ctx>,
     ) -> Result<inkwell::values::BasicValueEnum<'ctx>, String> {
         let string_to_bool_fn = self
-            .module
-            .get_function("string_to_bool")
+            .runtime_function("string_to_bool")
             .unwrap_or_else(|| {
                 let bool_type = self.llvm_context.bool_type();
                 let str_ptr_type = self.llvm_context.ptr_type(inkwell::AddressSpace::default());
@@ -989,7 +1127,7 @@ impl<'ctx> CompilationContext<'ctx> {
 
         if name.contains('.') {
             if let Some(current_scope) = self.scope_stack.current_scope() {
-                nonlocal_vars = current_scope.nonlocal_vars.clone();
+                nonlocal_vars = current_scope.nonlocal_vars.iter().map(|name| name.to_string()).collect();
             }
 
             if let Some(env) = self.get_closure_environment(name) {
@@ -1060,6 +1198,10 @@ impl<'ctx> CompilationContext<'ctx> {
 
         self.builder.position_at_end(basic_block);
 
+        let first_line = body.first().map_or(0, |stmt| stmt.line());
+        let saved_debug_scope = self.enter_debug_scope(function, name, first_line);
+        let saved_trace_frame = self.enter_trace_frame(name)?;
+
         println!("Compiling nested function body for {}", name);
         println!(
             "Current scope stack size: {}",
@@ -1085,7 +1227,7 @@ impl<'ctx> CompilationContext<'ctx> {
 
             self.builder.build_store(alloca, param_value).unwrap();
 
-            local_vars.insert(param.name.clone(), alloca);
+            local_vars.insert(Symbol::intern(&param.name), alloca);
 
             self.add_variable_to_scope(param.name.clone(), alloca, Type::Int);
 
@@ -1284,7 +1426,7 @@ impl<'ctx> CompilationContext<'ctx> {
 
                     self.add_variable_to_scope(unique_name.clone(), local_ptr, var_type.clone());
 
-                    local_vars.insert(unique_name.clone(), local_ptr);
+                    local_vars.insert(Symbol::intern(&unique_name), local_ptr);
 
                     self.register_variable(unique_name.clone(), var_type.clone());
                 } else {
@@ -1387,10 +1529,13 @@ impl<'ctx> CompilationContext<'ctx> {
             .get_terminator()
             .is_some()
         {
+            self.emit_trace_frame_exit()?;
             let zero = context.i64_type().const_int(0, false);
             self.builder.build_return(Some(&zero)).unwrap();
         }
 
+        self.leave_trace_frame(saved_trace_frame);
+        self.leave_debug_scope(saved_debug_scope);
         self.current_function = old_function;
         self.local_vars = old_local_vars;
 
@@ -1607,7 +1752,7 @@ impl<'ctx> CompilationContext<'ctx> {
 
     /// Get or create the malloc function
     fn get_or_create_malloc_function(&self) -> inkwell::values::FunctionValue<'ctx> {
-        if let Some(malloc_fn) = self.module.get_function("malloc") {
+        if let Some(malloc_fn) = self.runtime_function("malloc") {
             return malloc_fn;
         }
 
@@ -0,0 +1,224 @@
+// debug_info.rs - DWARF debug info for compiled programs
+//
+// With debug info enabled every compiled function gets a DISubprogram and
+// every statement sets the builder's debug location to its source line, so
+// gdb and lldb can set breakpoints on and step through .ch lines in AOT
+// binaries. Local int, float, bool and str variables are described too, so
+// debuggers can show their values. Without it these hooks do nothing.
+
+use crate::compiler::context::CompilationContext;
+use crate::compiler::types::Type;
+use inkwell::debug_info::{
+    AsDIScope, DICompileUnit, DIFile, DIFlags, DIFlagsConstants, DIScope, DIType,
+    DWARFEmissionKind, DWARFSourceLanguage, DebugInfoBuilder,
+};
+use inkwell::module::FlagBehavior;
+use inkwell::values::{FunctionValue, PointerValue};
+use inkwell::AddressSpace;
+use std::path::Path;
+
+/// DWARF base type encodings (DW_ATE_*)
+const DW_ATE_BOOLEAN: u32 = 0x02;
+const DW_ATE_FLOAT: u32 = 0x04;
+const DW_ATE_SIGNED: u32 = 0x05;
+const DW_ATE_SIGNED_CHAR: u32 = 0x06;
+
+/// Debug info state for a module being compiled
+pub struct DebugInfo<'ctx> {
+    builder: DebugInfoBuilder<'ctx>,
+    compile_unit: DICompileUnit<'ctx>,
+    file: DIFile<'ctx>,
+    /// Scope of the function being compiled and the line last emitted in it
+    location: Option<(DIScope<'ctx>, u32)>,
+}
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// Emit debug info for `source_path` in the functions compiled from now on
+    pub fn enable_debug_info(&mut self, source_path: &str) {
+        let path = Path::new(source_path);
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| source_path.to_string());
+        let directory = path
+            .parent()
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let debug_version = self.llvm_context.i32_type().const_int(3, false);
+        self.module
+            .add_basic_value_flag("Debug Info Version", FlagBehavior::Warning, debug_version.into());
+        let dwarf_version = self.llvm_context.i32_type().const_int(4, false);
+        self.module
+            .add_basic_value_flag("Dwarf Version", FlagBehavior::Warning, dwarf_version.into());
+
+        let (builder, compile_unit) = self.module.create_debug_info_builder(
+            true,
+            DWARFSourceLanguage::Python,
+            &filename,
+            &directory,
+            "cheetah",
+            false,
+            "",
+            0,
+            "",
+            DWARFEmissionKind::Full,
+            0,
+            false,
+            false,
+            "",
+            "",
+        );
+        let file = compile_unit.get_file();
+
+        self.debug_info = Some(DebugInfo {
+            builder,
+            compile_unit,
+            file,
+            location: None,
+        });
+    }
+
+    /// Attach a subprogram to `function`, whose entry block is being built, and
+    /// point the debug location at its first line.
+    ///
+    /// Returns the location of the enclosing function, to be restored with
+    /// `leave_debug_scope` once this function is done.
+    pub fn enter_debug_scope(
+        &mut self,
+        function: FunctionValue<'ctx>,
+        name: &str,
+        line: usize,
+    ) -> Option<(DIScope<'ctx>, u32)> {
+        let debug_info = self.debug_info.as_mut()?;
+        let line = line.max(1) as u32;
+
+        let subroutine_type =
+            debug_info
+                .builder
+                .create_subroutine_type(debug_info.file, None, &[], DIFlags::ZERO);
+        let subprogram = debug_info.builder.create_function(
+            debug_info.compile_unit.as_debug_info_scope(),
+            name,
+            None,
+            debug_info.file,
+            line,
+            subroutine_type,
+            true,
+            true,
+            line,
+            DIFlags::ZERO,
+            false,
+        );
+        function.set_subprogram(subprogram);
+
+        let saved = debug_info
+            .location
+            .replace((subprogram.as_debug_info_scope(), line));
+        self.set_debug_line(line as usize);
+        saved
+    }
+
+    /// Restore the debug location of the enclosing function after compiling a function body
+    pub fn leave_debug_scope(&mut self, saved: Option<(DIScope<'ctx>, u32)>) {
+        let Some(debug_info) = self.debug_info.as_mut() else {
+            return;
+        };
+        debug_info.location = saved;
+        match saved {
+            Some((_, line)) => self.set_debug_line(line as usize),
+            None => self.builder.unset_current_debug_location(),
+        }
+    }
+
+    /// Attribute the instructions built from now on to `line` of the current function
+    pub fn set_debug_line(&mut self, line: usize) {
+        let Some(debug_info) = self.debug_info.as_mut() else {
+            return;
+        };
+        let Some((scope, current_line)) = debug_info.location.as_mut() else {
+            return;
+        };
+        if line > 0 {
+            *current_line = line as u32;
+        }
+        let location = debug_info.builder.create_debug_location(
+            self.llvm_context,
+            *current_line,
+            0,
+            *scope,
+            None,
+        );
+        self.builder.set_current_debug_location(location);
+    }
+
+    /// Describe the variable `name` stored at `ptr` so debuggers can show its
+    /// value. Variables of types other than int, float, bool and str are skipped.
+    pub fn declare_debug_variable(&mut self, name: &str, ptr: PointerValue<'ctx>, ty: &Type) {
+        let Some(debug_info) = self.debug_info.as_ref() else {
+            return;
+        };
+        let (Some((scope, line)), Some(block)) =
+            (debug_info.location, self.builder.get_insert_block())
+        else {
+            return;
+        };
+        let Some(di_type) = debug_info.variable_type(ty) else {
+            return;
+        };
+
+        let variable = debug_info.builder.create_auto_variable(
+            scope,
+            name,
+            debug_info.file,
+            line,
+            di_type,
+            true,
+            DIFlags::ZERO,
+            0,
+        );
+        let location =
+            debug_info
+                .builder
+                .create_debug_location(self.llvm_context, line, 0, scope, None);
+        debug_info
+            .builder
+            .insert_declare_at_end(ptr, Some(variable), None, location, block);
+    }
+
+    /// Resolve the debug info metadata once the whole module has been compiled
+    pub fn finalize_debug_info(&self) {
+        if let Some(debug_info) = &self.debug_info {
+            debug_info.builder.finalize();
+        }
+    }
+}
+
+impl<'ctx> DebugInfo<'ctx> {
+    /// Debug type of a variable of type `ty`, if debuggers can display it
+    fn variable_type(&self, ty: &Type) -> Option<DIType<'ctx>> {
+        let basic = |name: &str, bits: u64, encoding: u32| {
+            self.builder
+                .create_basic_type(name, bits, encoding, DIFlags::ZERO)
+                .ok()
+                .map(|basic| basic.as_type())
+        };
+        match ty {
+            Type::Int => basic("int", 64, DW_ATE_SIGNED),
+            Type::Float => basic("float", 64, DW_ATE_FLOAT),
+            Type::Bool => basic("bool", 8, DW_ATE_BOOLEAN),
+            Type::String => {
+                let char_type = basic("char", 8, DW_ATE_SIGNED_CHAR)?;
+                let pointer = self.builder.create_pointer_type(
+                    "str",
+                    char_type,
+                    64,
+                    0,
+                    AddressSpace::default(),
+                );
+                Some(pointer.as_type())
+            }
+            _ => None,
+        }
+    }
+}
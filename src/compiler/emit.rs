@@ -0,0 +1,114 @@
+// emit.rs - Output formats for `cheetah compile --emit`
+
+use crate::compiler::source_map::SourceMap;
+use crate::compiler::Compiler;
+use inkwell::targets::FileType;
+use std::path::{Path, PathBuf};
+
+/// What `cheetah compile` writes for a module
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmitKind {
+    /// Target assembly
+    Asm,
+    /// Textual LLVM IR
+    LlvmIr,
+    /// LLVM bitcode
+    LlvmBc,
+    /// Object file
+    Obj,
+    /// Executable linked against the runtime
+    Exe,
+}
+
+impl EmitKind {
+    /// Parse an output kind as given to `--emit`
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "asm" => Ok(EmitKind::Asm),
+            "llvm-ir" => Ok(EmitKind::LlvmIr),
+            "llvm-bc" => Ok(EmitKind::LlvmBc),
+            "obj" => Ok(EmitKind::Obj),
+            "exe" => Ok(EmitKind::Exe),
+            other => Err(format!(
+                "unknown emit kind '{}' (expected asm, llvm-ir, llvm-bc, obj or exe)",
+                other
+            )),
+        }
+    }
+
+    /// Name as accepted by `--emit`
+    pub fn name(self) -> &'static str {
+        match self {
+            EmitKind::Asm => "asm",
+            EmitKind::LlvmIr => "llvm-ir",
+            EmitKind::LlvmBc => "llvm-bc",
+            EmitKind::Obj => "obj",
+            EmitKind::Exe => "exe",
+        }
+    }
+
+    /// File extension of the output, empty for executables
+    pub fn extension(self) -> &'static str {
+        match self {
+            EmitKind::Asm => "s",
+            EmitKind::LlvmIr => "ll",
+            EmitKind::LlvmBc => "bc",
+            EmitKind::Obj => "o",
+            EmitKind::Exe => "",
+        }
+    }
+}
+
+impl<'ctx> Compiler<'ctx> {
+    /// Write the compiled module to `path` as `kind`.
+    ///
+    /// Executables are linked in the current directory, named after the stem of `path`.
+    pub fn emit(&mut self, kind: EmitKind, path: &Path) -> Result<(), String> {
+        match kind {
+            EmitKind::LlvmIr => self.write_to_file(path),
+            EmitKind::LlvmBc => {
+                if self.context.module.write_bitcode_to_path(path) {
+                    Ok(())
+                } else {
+                    Err(format!("Failed to write bitcode to {}", path.display()))
+                }
+            }
+            EmitKind::Asm | EmitKind::Obj => {
+                let file_type = if kind == EmitKind::Asm {
+                    FileType::Assembly
+                } else {
+                    FileType::Object
+                };
+                let tm = self.prepare_codegen()?;
+                tm.write_to_file(&self.context.module, file_type, path)
+                    .map_err(|e| format!("Failed to write {}: {:?}", path.display(), e))
+            }
+            EmitKind::Exe => {
+                let exe_name = path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .ok_or_else(|| "Invalid output filename".to_string())?;
+                self.emit_to_aot(exe_name)
+            }
+        }
+    }
+
+    /// Write the module's LLVM IR to `ir_path` and a source map of it to
+    /// `<ir_path>.map`, returning the map's path. Lines are only mapped when
+    /// debug info is enabled.
+    pub fn emit_source_map(&self, ir_path: &Path) -> Result<PathBuf, String> {
+        self.write_to_file(ir_path)?;
+        let generated = ir_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let map = SourceMap::from_llvm_ir(&self.get_ir(), &generated);
+
+        let mut map_path = ir_path.as_os_str().to_owned();
+        map_path.push(".map");
+        let map_path = PathBuf::from(map_path);
+        std::fs::write(&map_path, map.to_json())
+            .map_err(|e| format!("Failed to write {}: {}", map_path.display(), e))?;
+        Ok(map_path)
+    }
+}
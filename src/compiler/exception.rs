@@ -4,6 +4,7 @@ use crate::ast::{ExceptHandler, Expr, Stmt};
 use crate::compiler::context::CompilationContext;
 use crate::compiler::expr::ExprCompiler;
 use crate::compiler::stmt::StmtCompiler;
+use crate::compiler::types::Type;
 use inkwell::values::{BasicValueEnum, PointerValue};
 
 impl<'ctx> CompilationContext<'ctx> {
@@ -36,8 +37,6 @@ impl<'ctx> CompilationContext<'ctx> {
         let finally_block = self.llvm_context.append_basic_block(function, "finally");
         let exit_block = self.llvm_context.append_basic_block(function, "exit");
 
-        let exception_raised = self.create_exception_state();
-
         self.ensure_block_has_terminator();
 
         let _ = self.builder.build_unconditional_branch(try_block);
@@ -46,10 +45,6 @@ impl<'ctx> CompilationContext<'ctx> {
 
         self.ensure_block_has_terminator();
 
-        self.reset_exception_state(exception_raised);
-
-        self.ensure_block_has_terminator();
-
         let mut has_terminator = false;
         for (i, stmt) in body.iter().enumerate() {
             if self
@@ -86,7 +81,7 @@ impl<'ctx> CompilationContext<'ctx> {
         if !has_terminator {
             self.ensure_block_has_terminator();
 
-            let exception_value = self.load_exception_state(exception_raised);
+            let exception_value = self.load_exception_state();
             let _ = self.builder.build_conditional_branch(
                 exception_value,
                 except_blocks[0],
@@ -125,11 +120,9 @@ impl<'ctx> CompilationContext<'ctx> {
 
             self.ensure_block_has_terminator();
 
-            if let Some(name) = &handler.name {
-                self.ensure_block_has_terminator();
-
-                let exception = self.get_current_exception();
+            let exception = self.enter_exception_handler();
 
+            if let Some(name) = &handler.name {
                 self.ensure_block_has_terminator();
 
                 let exception_var = self
@@ -149,7 +142,7 @@ impl<'ctx> CompilationContext<'ctx> {
                 self.scope_stack.add_variable(
                     name.clone(),
                     exception_var,
-                    crate::compiler::types::Type::Any,
+                    crate::compiler::types::Type::exception(),
                 );
             }
 
@@ -188,23 +181,7 @@ impl<'ctx> CompilationContext<'ctx> {
                 }
             }
 
-            self.ensure_block_has_terminator();
-
-            self.reset_exception_state(exception_raised);
-
-            self.ensure_block_has_terminator();
-
-            if let Some(clear_current_exception_fn) =
-                self.module.get_function("clear_current_exception")
-            {
-                let _ = self.builder.build_call(
-                    clear_current_exception_fn,
-                    &[],
-                    "clear_exception_result",
-                );
-
-                self.ensure_block_has_terminator();
-            }
+            self.exit_exception_handler(!has_terminator);
 
             if !has_terminator {
                 self.ensure_block_has_terminator();
@@ -339,20 +316,24 @@ impl<'ctx> CompilationContext<'ctx> {
         exc: &Option<Box<Expr>>,
         cause: &Option<Box<Expr>>,
     ) -> Result<(), String> {
-        let exception_raise_fn = match self.module.get_function("exception_raise") {
+        let exception_raise_fn = match self.runtime_function("exception_raise") {
             Some(f) => f,
             None => return Err("exception_raise function not found".to_string()),
         };
 
         let exception = if let Some(exc_expr) = exc {
-            let (exc_val, _) = self.compile_expr(exc_expr)?;
+            if let Some(exception) = self.compile_builtin_exception(exc_expr)? {
+                exception
+            } else {
+                let (exc_val, _) = self.compile_expr(exc_expr)?;
 
-            if !self.is_exception_type(exc_val) {
-                let exc_str = self.convert_exception_to_string(exc_val)?;
+                if !self.is_exception_type(exc_val) {
+                    let exc_str = self.convert_exception_to_string(exc_val)?;
 
-                self.create_exception("Exception", exc_str)
-            } else {
-                exc_val.into_pointer_value()
+                    self.create_exception("Exception", exc_str)
+                } else {
+                    exc_val.into_pointer_value()
+                }
             }
         } else {
             self.get_current_exception()
@@ -366,10 +347,7 @@ impl<'ctx> CompilationContext<'ctx> {
             .builder
             .build_call(exception_raise_fn, &[exception.into()], "raise_result");
 
-        let exception_raised = self.create_exception_state();
-        self.set_exception_state(exception_raised, true);
-
-        if let Some(set_current_exception_fn) = self.module.get_function("set_current_exception") {
+        if let Some(set_current_exception_fn) = self.runtime_function("set_current_exception") {
             let _ = self.builder.build_call(
                 set_current_exception_fn,
                 &[exception.into()],
@@ -380,54 +358,77 @@ impl<'ctx> CompilationContext<'ctx> {
         Ok(())
     }
 
-    /// Create a global variable to track if an exception was raised
-    pub fn create_exception_state(&self) -> PointerValue<'ctx> {
-        if let Some(var) = self.module.get_global("__exception_raised") {
-            return var.as_pointer_value();
-        }
-
-        let global =
-            self.module
-                .add_global(self.llvm_context.bool_type(), None, "__exception_raised");
+    /// Whether a raised exception is waiting for a handler on this thread
+    pub fn load_exception_state(&self) -> inkwell::values::IntValue<'ctx> {
+        let exception_is_pending_fn = self
+            .runtime_function("exception_is_pending")
+            .expect("exception_is_pending function not found");
+        self.builder
+            .build_call(exception_is_pending_fn, &[], "exception_raised")
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .expect("Failed to load exception state")
+            .into_int_value()
+    }
 
-        global.set_initializer(&self.llvm_context.bool_type().const_int(0, false));
+    /// Enter an except block, returning the exception it handles
+    pub fn enter_exception_handler(&mut self) -> PointerValue<'ctx> {
+        if let Some(function) = self.current_function {
+            self.exception_handlers
+                .push((function, self.loop_stack.len()));
+        }
 
-        global.as_pointer_value()
+        match self.runtime_function("exception_enter_handler") {
+            Some(enter_fn) => self
+                .builder
+                .build_call(enter_fn, &[], "handled_exception")
+                .unwrap()
+                .try_as_basic_value()
+                .left()
+                .unwrap()
+                .into_pointer_value(),
+            None => self.get_current_exception(),
+        }
     }
 
-    /// Reset the exception state to false
-    pub fn reset_exception_state(&self, exception_raised: PointerValue<'ctx>) {
-        let false_val = self.llvm_context.bool_type().const_int(0, false);
-        let _ = self.builder.build_store(exception_raised, false_val);
+    /// Leave the innermost except block; `emit` is false when its body already
+    /// ended in a return, break or continue that restored the state itself
+    pub fn exit_exception_handler(&mut self, emit: bool) {
+        self.exception_handlers.pop();
+        if emit {
+            self.emit_exception_handler_exit();
+        }
     }
 
-    /// Set the exception state
-    fn set_exception_state(&self, exception_raised: PointerValue<'ctx>, raised: bool) {
-        let val = self
-            .llvm_context
-            .bool_type()
-            .const_int(if raised { 1 } else { 0 }, false);
-        let _ = self.builder.build_store(exception_raised, val);
+    /// Restore the exception state for except blocks left by an early exit.
+    ///
+    /// A `return` (`loop_depth` of None) leaves every handler of the current
+    /// function; `break` and `continue` leave those inside the innermost loop.
+    pub fn emit_exception_handler_exits(&self, loop_depth: Option<usize>) {
+        let function = match self.current_function {
+            Some(f) => f,
+            None => return,
+        };
+        let count = self
+            .exception_handlers
+            .iter()
+            .filter(|(f, depth)| *f == function && loop_depth.is_none_or(|d| *depth >= d))
+            .count();
+        for _ in 0..count {
+            self.emit_exception_handler_exit();
+        }
     }
 
-    /// Load the exception state
-    pub fn load_exception_state(
-        &self,
-        exception_raised: PointerValue<'ctx>,
-    ) -> inkwell::values::IntValue<'ctx> {
-        self.builder
-            .build_load(
-                self.llvm_context.bool_type(),
-                exception_raised,
-                "exception_raised",
-            )
-            .expect("Failed to load exception state")
-            .into_int_value()
+    fn emit_exception_handler_exit(&self) {
+        if let Some(exit_fn) = self.runtime_function("exception_exit_handler") {
+            let _ = self.builder.build_call(exit_fn, &[], "exit_handler");
+        }
     }
 
     /// Get the current exception
     pub fn get_current_exception(&self) -> PointerValue<'ctx> {
-        let get_current_exception_fn = match self.module.get_function("get_current_exception") {
+        let get_current_exception_fn = match self.runtime_function("get_current_exception") {
             Some(f) => f,
             None => {
                 return self.create_dummy_exception();
@@ -448,7 +449,7 @@ impl<'ctx> CompilationContext<'ctx> {
 
     /// Create a dummy exception for testing
     fn create_dummy_exception(&self) -> PointerValue<'ctx> {
-        let exception_new_fn = self.module.get_function("exception_new").unwrap();
+        let exception_new_fn = self.runtime_function("exception_new").unwrap();
 
         let type_str = self.create_string_constant("Exception");
         let msg_str = self.create_string_constant("Unknown exception");
@@ -476,11 +477,7 @@ impl<'ctx> CompilationContext<'ctx> {
 
     /// Create a string constant
     fn create_string_constant(&self, s: &str) -> PointerValue<'ctx> {
-        let string_val = self
-            .builder
-            .build_global_string_ptr(s, "str_const")
-            .expect("Failed to create string constant");
-        string_val.as_pointer_value()
+        self.string_constant(s.as_bytes())
     }
 
     /// Convert a value to a string for exception handling
@@ -494,7 +491,7 @@ impl<'ctx> CompilationContext<'ctx> {
 
     /// Create a new exception
     fn create_exception(&self, typ: &str, message: PointerValue<'ctx>) -> PointerValue<'ctx> {
-        let exception_new_fn = self.module.get_function("exception_new").unwrap();
+        let exception_new_fn = self.runtime_function("exception_new").unwrap();
 
         let type_str = self.create_string_constant(typ);
 
@@ -513,4 +510,112 @@ impl<'ctx> CompilationContext<'ctx> {
             .unwrap()
             .into_pointer_value()
     }
+
+    /// Build the exception for `raise ValueError(...)` or `raise KeyError` when the
+    /// name refers to a builtin exception class rather than a user definition
+    fn compile_builtin_exception(
+        &mut self,
+        expr: &Expr,
+    ) -> Result<Option<PointerValue<'ctx>>, String> {
+        let (id, args) = match expr {
+            Expr::Name { id, .. } => (id, &[][..]),
+            Expr::Call { func, args, .. } => match func.as_ref() {
+                Expr::Name { id, .. } => (id, &args[..]),
+                _ => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        if !self.is_builtin_exception_name(id) {
+            return Ok(None);
+        }
+
+        let args_list = self
+            .call_runtime_function("list_new", &[])?
+            .ok_or("list_new returned void")?
+            .into_pointer_value();
+        for arg in args {
+            let (value, tag) = self.compile_list_element(arg, true)?;
+            self.call_runtime_function(
+                "list_append_tagged",
+                &[args_list.into(), value.into(), tag.into()],
+            )?;
+        }
+
+        let type_str = self.create_string_constant(id);
+        let exception = self
+            .call_runtime_function(
+                "exception_new_with_args",
+                &[type_str.into(), args_list.into()],
+            )?
+            .ok_or("exception_new_with_args returned void")?
+            .into_pointer_value();
+        Ok(Some(exception))
+    }
+
+    /// Whether `id` names a builtin exception class that is not shadowed
+    fn is_builtin_exception_name(&self, id: &str) -> bool {
+        let looks_like_exception = id.ends_with("Error")
+            || id.ends_with("Exception")
+            || matches!(id, "StopIteration" | "KeyboardInterrupt" | "SystemExit");
+        let nested_name = self
+            .current_function
+            .map(|f| format!("{}.{}", f.get_name().to_string_lossy(), id));
+
+        looks_like_exception
+            && self.get_variable_ptr(id).is_none()
+            && self.runtime_function(id).is_none()
+            && nested_name.is_none_or(|name| self.runtime_function(&name).is_none())
+    }
+
+    /// Compile `e.message` or `e.args` on a caught exception
+    pub fn compile_exception_attribute(
+        &mut self,
+        exception: BasicValueEnum<'ctx>,
+        attr: &str,
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        let (fn_name, result_type) = match attr {
+            "message" => ("exception_get_message", Type::String),
+            "args" => ("exception_get_args", Type::List(Box::new(Type::Any))),
+            _ => return Err(format!("'Exception' object has no attribute '{}'", attr)),
+        };
+        let result = self
+            .call_runtime_function(fn_name, &[exception.into_pointer_value().into()])?
+            .ok_or_else(|| format!("{} returned void", fn_name))?;
+        Ok((result, result_type))
+    }
+
+    /// Compile `sys.exc_info()`: a `(type name, exception, traceback)` tuple for the
+    /// exception currently being handled. Outside a handler the type name is empty
+    /// and the exception is null; the traceback is always None.
+    pub fn compile_exc_info(
+        &mut self,
+        args: &[Box<Expr>],
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        if !args.is_empty() {
+            return Err(format!(
+                "sys.exc_info() takes no arguments ({} given)",
+                args.len()
+            ));
+        }
+
+        let exception = self.get_current_exception();
+        let type_name = self
+            .call_runtime_function("exception_get_type", &[exception.into()])?
+            .ok_or("exception_get_type returned void")?;
+        let traceback = self
+            .llvm_context
+            .ptr_type(inkwell::AddressSpace::default())
+            .const_null();
+
+        let info_type = Type::exc_info();
+        let element_types = match &info_type {
+            Type::Tuple(types) => types.clone(),
+            _ => unreachable!(),
+        };
+        let tuple_ptr = self.build_tuple(
+            vec![type_name, exception.into(), traceback.into()],
+            &element_types,
+        )?;
+        Ok((tuple_ptr.into(), info_type))
+    }
 }
@@ -2,6 +2,8 @@ use crate::ast::{BoolOperator, CmpOperator, Expr, NameConstant, Number, Operator
 use crate::compiler::context::CompilationContext;
 use crate::compiler::types::is_reference_type;
 use crate::compiler::types::Type;
+use crate::compiler::types::{binary_op_lowering, unsupported_operand_types, BinaryOpLowering};
+use crate::intern::Symbol;
 use inkwell::types::BasicTypeEnum;
 use inkwell::values::{BasicValueEnum, FunctionValue, IntValue};
 
@@ -39,6 +41,7 @@ pub trait ExprCompiler<'ctx> {
         elt: &Expr,
         generator: &crate::ast::Comprehension,
         list_ptr: inkwell::values::PointerValue<'ctx>,
+        element_type: &Type,
         result_list: inkwell::values::PointerValue<'ctx>,
         list_append_fn: inkwell::values::FunctionValue<'ctx>,
     ) -> Result<(), String>;
@@ -276,12 +279,18 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
             Expr::NameConstant { value, .. } => self.compile_name_constant(value),
 
             Expr::BinOp {
-                left, op, right, ..
+                left,
+                op,
+                right,
+                line,
+                column,
+                span: _,
             } => {
                 let (left_val, left_type) = self.compile_expr(left)?;
                 let (right_val, right_type) = self.compile_expr(right)?;
 
                 self.compile_binary_op(left_val, &left_type, op.clone(), right_val, &right_type)
+                    .map_err(|e| format!("{} at line {}, column {}", e, line, column))
             }
 
             Expr::UnaryOp { op, operand, .. } => {
@@ -540,7 +549,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                         global_scope.add_variable(id.to_string(), ptr, var_type.clone());
                     }
 
-                    self.variables.insert(id.to_string(), ptr);
+                    self.variables.insert(Symbol::intern(id), ptr);
 
                     let value = self
                         .builder
@@ -584,7 +593,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
 
                         let ptr = global_var.as_pointer_value();
 
-                        self.variables.insert(id.to_string(), ptr);
+                        self.variables.insert(Symbol::intern(id), ptr);
 
                         let value = self
                             .builder
@@ -682,18 +691,12 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
             }
 
             Expr::Str { value, .. } => {
-                let const_str = self.llvm_context.const_string(value.as_bytes(), true);
-
-                let str_type = const_str.get_type();
-
-                let global_str = self.module.add_global(str_type, None, "str_const");
-                global_str.set_constant(true);
-                global_str.set_initializer(&const_str);
+                let global_str = self.string_constant(value.as_bytes());
 
                 let str_ptr = self
                     .builder
                     .build_pointer_cast(
-                        global_str.as_pointer_value(),
+                        global_str,
                         self.llvm_context.ptr_type(inkwell::AddressSpace::default()),
                         "str_ptr",
                     )
@@ -704,18 +707,15 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
             Expr::JoinedStr { values, .. } => {
                 // 1) Get or declare the string_concat runtime function
                 let str_ptr_t = self.llvm_context.ptr_type(inkwell::AddressSpace::default());
-                let concat_fn = self.module.get_function("string_concat").unwrap_or_else(|| {
+                let concat_fn = self.runtime_function("string_concat").unwrap_or_else(|| {
                     let fn_ty = str_ptr_t.fn_type(&[str_ptr_t.into(), str_ptr_t.into()], false);
                     self.module.add_function("string_concat", fn_ty, None)
                 });
 
-                // 2) Start result as the empty string global
-                let empty_cs = self.llvm_context.const_string(b"", true);
-                let empty_glob = self.module.add_global(empty_cs.get_type(), None, "fstr_empty");
-                empty_glob.set_constant(true);
-                empty_glob.set_initializer(&empty_cs);
+                // 2) Start result as the shared empty string constant
+                let empty_glob = self.pooled_string("fstr_empty", b"");
                 let mut result_ptr = self.builder.build_pointer_cast(
-                    empty_glob.as_pointer_value(),
+                    empty_glob,
                     str_ptr_t,
                     "fstr_empty_ptr",
                 ).unwrap();
@@ -888,13 +888,47 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                 keywords,
                 ..
             } => {
+                if let Expr::Name { id, .. } = func.as_ref() {
+                    if self.extern_functions.contains_key(id) && keywords.is_empty() {
+                        return self.compile_extern_call(id, args);
+                    }
+
+                    let class_value = match self.lookup_variable_type(id) {
+                        Some(Type::ClassObject(_)) => true,
+                        _ => self.get_variable_ptr(id).is_none() && self.is_class_name(id),
+                    };
+                    if class_value && keywords.is_empty() {
+                        let (object, object_type) = self.compile_expr(func)?;
+                        if let Type::ClassObject(class) = object_type {
+                            return self.compile_class_call(object, &class, args);
+                        }
+                    }
+                }
+
                 if let Expr::Attribute { value, attr, .. } = func.as_ref() {
+                    if matches!(value.as_ref(), Expr::Name { id, .. } if id == "sys")
+                        && attr == "exc_info"
+                        && self.get_variable_ptr("sys").is_none()
+                    {
+                        return self.compile_exc_info(args);
+                    }
+
+                    if let Some(name) = self.os_function_name(func) {
+                        return self.compile_os_call(name, args);
+                    }
+
+                    if let Some(super_args) = crate::compiler::super_call::super_call_args(value) {
+                        if self.get_variable_ptr("super").is_none() {
+                            return self.compile_super_call(super_args, attr, args);
+                        }
+                    }
+
                     let (obj_val, obj_type) = self.compile_expr(value)?;
 
                     match &obj_type {
                         Type::Dict(key_type, value_type) => match attr.as_str() {
                             "keys" => {
-                                let dict_keys_fn = match self.module.get_function("dict_keys") {
+                                let dict_keys_fn = match self.runtime_function("dict_keys") {
                                     Some(f) => f,
                                     None => return Err("dict_keys function not found".to_string()),
                                 };
@@ -920,7 +954,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                                 return Ok((keys_list_ptr, Type::List(key_type.clone())));
                             }
                             "values" => {
-                                let dict_values_fn = match self.module.get_function("dict_values") {
+                                let dict_values_fn = match self.runtime_function("dict_values") {
                                     Some(f) => f,
                                     None => {
                                         return Err("dict_values function not found".to_string())
@@ -948,7 +982,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                                 return Ok((values_list_ptr, Type::List(value_type.clone())));
                             }
                             "items" => {
-                                let dict_items_fn = match self.module.get_function("dict_items") {
+                                let dict_items_fn = match self.runtime_function("dict_items") {
                                     Some(f) => f,
                                     None => return Err("dict_items function not found".to_string()),
                                 };
@@ -976,12 +1010,26 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                                 return Ok((items_list_ptr, Type::List(Box::new(tuple_type))));
                             }
                             _ => {
-                                return Err(format!(
-                                    "Unknown method '{}' for dictionary type",
-                                    attr
-                                ))
+                                return self.compile_dict_method_call(
+                                    obj_val, key_type, value_type, attr, args,
+                                );
                             }
                         },
+                        Type::List(element_type) => {
+                            return self.compile_list_method_call(
+                                obj_val,
+                                element_type,
+                                attr,
+                                args,
+                                keywords,
+                            );
+                        }
+                        Type::String => {
+                            return self.compile_string_method_call(obj_val, attr, args);
+                        }
+                        ty if ty.is_file() => {
+                            return self.compile_file_method_call(obj_val, attr, args);
+                        }
                         _ => {
                             return Err(format!(
                                 "Type {:?} does not support method calls",
@@ -993,6 +1041,29 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
 
                 match func.as_ref() {
                     Expr::Name { id, .. } => {
+                        if self.is_iter_builtin(id) && keywords.is_empty() {
+                            return self.compile_iter_builtin_call(id, args);
+                        }
+                        if self.is_sequence_builtin(id) {
+                            return self.compile_sequence_builtin_call(id, args, keywords);
+                        }
+                        if self.is_introspection_builtin(id) {
+                            return self.compile_introspection_call(id, args, keywords);
+                        }
+
+                        if self.is_input_builtin(id) && keywords.is_empty() {
+                            return self.compile_input_call(args);
+                        }
+                        if self.is_open_builtin(id) {
+                            return self.compile_open_call(args, keywords);
+                        }
+                        if self.is_channel_builtin(id) {
+                            return self.compile_channel_call(id, args, keywords);
+                        }
+                        if self.is_timeout_builtin(id) {
+                            return self.compile_with_timeout_call(args, keywords);
+                        }
+
                         let mut arg_values = Vec::with_capacity(args.len());
                         let mut arg_types = Vec::with_capacity(args.len());
 
@@ -1057,8 +1128,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
 
                             // Choose the tagged append helper and build the tag constant --------------
                             let append_tagged_fn = self
-                                .module
-                                .get_function("list_append_tagged")
+                                .runtime_function("list_append_tagged")
                                 .ok_or("list_append_tagged not found")?;
 
                             use crate::compiler::runtime::list::TypeTag;
@@ -1111,6 +1181,10 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                             return self.compile_max_call(&args_slice);
                         }
 
+                        if id == "str" && arg_types.len() == 1 && arg_types[0].is_exception() {
+                            return self.compile_exception_attribute(arg_values[0], "message");
+                        }
+
                         if id == "str" && !arg_types.is_empty() {
                             if let Some(func_value) =
                                 self.get_polymorphic_function(id, &arg_types[0])
@@ -1183,14 +1257,14 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
 
                                 println!("Looking for nested function: {}", qualified_name);
 
-                                if self.module.get_function(&qualified_name).is_some() {
+                                if self.runtime_function(&qualified_name).is_some() {
                                     found_function = true;
                                     println!("Found nested function: {}", qualified_name);
                                 }
                             }
 
                             let func_value = if found_function {
-                                match self.module.get_function(&qualified_name) {
+                                match self.runtime_function(&qualified_name) {
                                     Some(f) => f,
                                     None => {
                                         return Err(format!(
@@ -1202,19 +1276,19 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                             } else {
                                 if id == "range" {
                                     match args.len() {
-                                        1 => match self.module.get_function("range_1") {
+                                        1 => match self.runtime_function("range_1") {
                                             Some(f) => f,
                                             None => {
                                                 return Err("range_1 function not found".to_string())
                                             }
                                         },
-                                        2 => match self.module.get_function("range_2") {
+                                        2 => match self.runtime_function("range_2") {
                                             Some(f) => f,
                                             None => {
                                                 return Err("range_2 function not found".to_string())
                                             }
                                         },
-                                        3 => match self.module.get_function("range_3") {
+                                        3 => match self.runtime_function("range_3") {
                                             Some(f) => f,
                                             None => {
                                                 return Err("range_3 function not found".to_string())
@@ -1283,20 +1357,15 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                                                 .unwrap();
                                             call_args.push(ptr_val.into());
                                         }
-                                    } else if arg_type == &Type::Bool
-                                        && param_type.is_int_type()
-                                        && param_type.into_int_type().get_bit_width() == 64
+                                    } else if let Some(param_numeric) = self
+                                        .numeric_param_type(arg_type, *param_type)
                                     {
-                                        let bool_val = arg_value.into_int_value();
-                                        let int_val = self
-                                            .builder
-                                            .build_int_z_extend(
-                                                bool_val,
-                                                self.llvm_context.i64_type(),
-                                                "bool_to_i64",
-                                            )
-                                            .unwrap();
-                                        call_args.push(int_val.into());
+                                        let converted = self.convert_type(
+                                            arg_value,
+                                            arg_type,
+                                            &param_numeric,
+                                        )?;
+                                        call_args.push(converted.into());
                                     } else if let Type::Tuple(_) = arg_type {
                                         if param_type.is_int_type() {
                                             let ptr_val = if arg_value.is_pointer_value() {
@@ -1349,7 +1418,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                                     qualified_name, nonlocal_vars
                                 );
 
-                                if let Some(func) = self.module.get_function(&qualified_name) {
+                                if let Some(func) = self.runtime_function(&qualified_name) {
                                     let param_count = func.count_params();
                                     println!(
                                         "Function {} has {} parameters in LLVM IR",
@@ -1357,7 +1426,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                                     );
                                 }
 
-                                if let Some(func) = self.module.get_function(&qualified_name) {
+                                if let Some(func) = self.runtime_function(&qualified_name) {
                                     let param_count = func.count_params();
                                     let expected_param_count = args.len() + nonlocal_vars.len() + 1;
 
@@ -1526,9 +1595,13 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                                     ),
                                 )
                                 .unwrap();
+                            self.emit_call_check()?;
 
                             if let Some(ret_val) = call.try_as_basic_value().left() {
-                                let return_type = if id == "str"
+                                let known = self.return_types.get(id).filter(|_| !found_function);
+                                let return_type = if let Some(ty) = known {
+                                    ty.clone()
+                                } else if id == "str"
                                     || id == "int_to_string"
                                     || id == "float_to_string"
                                     || id == "bool_to_string"
@@ -1582,7 +1655,16 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                             }
                         }
                     }
-                    _ => Err("Indirect function calls not yet implemented".to_string()),
+                    _ => {
+                        // A class object fetched from a container or returned by a call
+                        let (object, object_type) = self.compile_expr(func)?;
+                        match object_type {
+                            Type::ClassObject(class) if keywords.is_empty() => {
+                                self.compile_class_call(object, &class, args)
+                            }
+                            _ => Err("Indirect function calls not yet implemented".to_string()),
+                        }
+                    }
                 }
             }
 
@@ -1707,42 +1789,12 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                     element_types.push(ty);
                 }
 
-                let element_type = if element_types.is_empty() {
-                    Type::Unknown
-                } else {
-                    let first_type = &element_types[0];
-                    let all_same = element_types.iter().all(|t| t == first_type);
-
-                    if all_same {
-                        println!("All list elements have the same type: {:?}", first_type);
-                        first_type.clone()
-                    } else {
-                        let mut common_type = element_types[0].clone();
-                        for ty in &element_types[1..] {
-                            common_type = match self.get_common_type(&common_type, ty) {
-                                Ok(t) => t,
-                                Err(_) => {
-                                    println!("Could not find common type between {:?} and {:?}, using Any", common_type, ty);
-                                    Type::Any
-                                }
-                            };
-                        }
-                        println!(
-                            "List elements have different types, using common type: {:?}",
-                            common_type
-                        );
-                        common_type
-                    }
-                };
-
-                let final_element_type = element_type.clone();
+                let (elements, final_element_type) = self
+                    .unify_element_types(element_values.into_iter().zip(element_types).collect())?;
 
                 println!("Final list element type: {:?}", final_element_type);
 
-                let list_ptr = self.build_list(
-                    element_values.into_iter().zip(element_types).collect(),
-                    &final_element_type
-                )?;
+                let list_ptr = self.build_list(elements, &final_element_type)?;
 
                 Ok((list_ptr.into(), Type::List(Box::new(final_element_type))))
             }
@@ -1858,7 +1910,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
     }
 
     fn build_empty_list(&self, name: &str) -> Result<inkwell::values::PointerValue<'ctx>, String> {
-        let list_new_fn = match self.module.get_function("list_new") {
+        let list_new_fn = match self.runtime_function("list_new") {
             Some(f) => f,
             None => return Err("list_new function not found".to_string()),
         };
@@ -1882,8 +1934,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
 
         /* ── 1. allocate the backing RawList with exact capacity ───────── */
         let with_cap = self
-            .module
-            .get_function("list_with_capacity")
+            .runtime_function("list_with_capacity")
             .ok_or("list_with_capacity not found")?;
         let len_val = self
             .llvm_context
@@ -1900,8 +1951,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
 
         /* ── 2. helper and append function we’ll use for every element ─── */
         let append_tagged = self
-            .module
-            .get_function("list_append_tagged")
+            .runtime_function("list_append_tagged")
             .ok_or("list_append_tagged not found")?;
 
         /* ── 3. append every literal value together with its tag ───────── */
@@ -2025,9 +2075,9 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
         elem_ty: &Type,
     ) -> Result<(), String> {
         // Runtime helpers we already have in src/runtime/list.rs
-        let list_len = self.module.get_function("list_len").ok_or("list_len missing")?;
-        let list_get = self.module.get_function("list_get").ok_or("list_get missing")?;
-        let list_slice = self.module.get_function("list_slice").ok_or("list_slice missing")?;
+        let list_len = self.runtime_function("list_len").ok_or("list_len missing")?;
+        let list_get = self.runtime_function("list_get").ok_or("list_get missing")?;
+        let list_slice = self.runtime_function("list_slice").ok_or("list_slice missing")?;
 
         let i64_type = self.llvm_context.i64_type();
 
@@ -2059,7 +2109,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                 ).unwrap();
             self.insert_runtime_assert(
                 cmp,
-                "Type error: list length does not match number of targets",
+                "list length does not match number of targets",
             )?;
         }
 
@@ -2154,30 +2204,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
         cond: inkwell::values::IntValue<'ctx>,
         msg: &str,
     ) -> Result<(), String> {
-        let cur_fn = self.builder.get_insert_block().unwrap().get_parent().unwrap();
-        let ok_bb = self.llvm_context.append_basic_block(cur_fn, "assert.ok");
-        let fail_bb = self.llvm_context.append_basic_block(cur_fn, "assert.fail");
-
-        self.builder.build_conditional_branch(cond, fail_bb, ok_bb).unwrap();
-
-        // fail_bb: call puts(msg); exit(1)
-        self.builder.position_at_end(fail_bb);
-        let puts = self
-            .module
-            .get_function("puts")
-            .ok_or("puts not declared")?;
-        let cstr = self.make_cstr("assert_msg", format!("{}\0", msg).as_bytes());
-        self.builder.build_call(puts, &[cstr.into()], "puts").unwrap();
-        let abort = self
-            .module
-            .get_function("abort")
-            .ok_or("abort not declared")?;
-        self.builder.build_call(abort, &[], "").unwrap();
-        self.builder.build_unreachable().unwrap();
-
-        // ok_bb
-        self.builder.position_at_end(ok_bb);
-        Ok(())
+        self.emit_runtime_abort(cond, msg)
     }
 
 
@@ -2281,8 +2308,13 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                     index_val.into_int_value()
                 };
 
-                let item_ptr =
-                    self.build_list_get_item(value_val.into_pointer_value(), index_int)?;
+                let item_ptr = self
+                    .call_runtime_function(
+                        "list_get_checked",
+                        &[value_val.into_pointer_value().into(), index_int.into()],
+                    )?
+                    .ok_or("list_get_checked returned void")?
+                    .into_pointer_value();
 
                 let element_type_ref = element_type.as_ref();
 
@@ -2442,7 +2474,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                     index_val.into_int_value()
                 };
 
-                let int_to_string_fn = match self.module.get_function("int_to_string") {
+                let int_to_string_fn = match self.runtime_function("int_to_string") {
                     Some(f) => f,
                     None => return Err("int_to_string function not found".to_string()),
                 };
@@ -2685,7 +2717,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
     }
 
     fn build_empty_dict(&self, name: &str) -> Result<inkwell::values::PointerValue<'ctx>, String> {
-        let dict_new_fn = match self.module.get_function("dict_new") {
+        let dict_new_fn = match self.runtime_function("dict_new") {
             Some(f) => f,
             None => return Err("dict_new function not found".to_string()),
         };
@@ -2706,7 +2738,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
         key_type: &Type,
         value_type: &Type,
     ) -> Result<inkwell::values::PointerValue<'ctx>, String> {
-        let dict_with_capacity_fn = match self.module.get_function("dict_with_capacity") {
+        let dict_with_capacity_fn = match self.runtime_function("dict_with_capacity") {
             Some(f) => f,
             None => return Err("dict_with_capacity function not found".to_string()),
         };
@@ -2729,7 +2761,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
 
         let dict_ptr = dict_ptr.into_pointer_value();
 
-        let dict_set_fn = match self.module.get_function("dict_set") {
+        let dict_set_fn = match self.runtime_function("dict_set") {
             Some(f) => f,
             None => return Err("dict_set function not found".to_string()),
         };
@@ -2760,7 +2792,13 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
             self.builder
                 .build_call(
                     dict_set_fn,
-                    &[dict_ptr.into(), key_ptr.into(), value_ptr.into()],
+                    &[
+                        dict_ptr.into(),
+                        key_ptr.into(),
+                        value_ptr.into(),
+                        self.type_tag(key_type).into(),
+                        self.type_tag(value_type).into(),
+                    ],
                     &format!("dict_set_{}", i),
                 )
                 .unwrap();
@@ -2791,7 +2829,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
     ) -> Result<inkwell::values::PointerValue<'ctx>, String> {
         self.ensure_block_has_terminator();
 
-        let list_get_fn = match self.module.get_function("list_get") {
+        let list_get_fn = match self.runtime_function("list_get") {
             Some(f) => f,
             None => return Err("list_get function not found".to_string()),
         };
@@ -2829,7 +2867,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
         stop: inkwell::values::IntValue<'ctx>,
         step: inkwell::values::IntValue<'ctx>,
     ) -> Result<inkwell::values::PointerValue<'ctx>, String> {
-        let list_slice_fn = match self.module.get_function("list_slice") {
+        let list_slice_fn = match self.runtime_function("list_slice") {
             Some(f) => f,
             None => return Err("list_slice function not found".to_string()),
         };
@@ -2875,7 +2913,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
 
         match &value_type {
             Type::List(element_type) => {
-                let list_len_fn = match self.module.get_function("list_len") {
+                let list_len_fn = match self.runtime_function("list_len") {
                     Some(f) => f,
                     None => return Err("list_len function not found".to_string()),
                 };
@@ -2976,7 +3014,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                 Ok((slice_ptr.into(), Type::List(element_type.clone())))
             }
             Type::String => {
-                let string_len_fn = match self.module.get_function("string_len") {
+                let string_len_fn = match self.runtime_function("string_len") {
                     Some(f) => f,
                     None => return Err("string_len function not found".to_string()),
                 };
@@ -3076,7 +3114,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
     ) -> Result<inkwell::values::PointerValue<'ctx>, String> {
         self.ensure_block_has_terminator();
 
-        let dict_get_fn = match self.module.get_function("dict_get") {
+        let dict_get_fn = match self.runtime_function("dict_get") {
             Some(f) => f,
             None => return Err("dict_get function not found".to_string()),
         };
@@ -3104,7 +3142,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
             .builder
             .build_call(
                 dict_get_fn,
-                &[dict_ptr.into(), key_ptr.into()],
+                &[dict_ptr.into(), key_ptr.into(), self.type_tag(key_type).into()],
                 "dict_get_result",
             )
             .unwrap();
@@ -3126,7 +3164,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
     ) -> Result<BasicValueEnum<'ctx>, String> {
         self.ensure_block_has_terminator();
 
-        let string_get_char_fn = match self.module.get_function("string_get_char") {
+        let string_get_char_fn = match self.runtime_function("string_get_char") {
             Some(f) => f,
             None => return Err("string_get_char function not found".to_string()),
         };
@@ -3149,10 +3187,10 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
 
         self.ensure_block_has_terminator();
 
-        let char_to_string_fn = match self.module.get_function("char_to_string") {
+        let char_to_string_fn = match self.runtime_function("char_to_string") {
             Some(f) => f,
             None => {
-                let int_to_string_fn = match self.module.get_function("int_to_string") {
+                let int_to_string_fn = match self.runtime_function("int_to_string") {
                     Some(f) => f,
                     None => return Err("int_to_string function not found".to_string()),
                 };
@@ -3203,7 +3241,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
         stop: inkwell::values::IntValue<'ctx>,
         step: inkwell::values::IntValue<'ctx>,
     ) -> Result<inkwell::values::PointerValue<'ctx>, String> {
-        let string_slice_fn = match self.module.get_function("string_slice") {
+        let string_slice_fn = match self.runtime_function("string_slice") {
             Some(f) => f,
             None => return Err("string_slice function not found".to_string()),
         };
@@ -3346,26 +3384,32 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
             // Create a result list for the outer comprehension
             let result_list = self.build_empty_list("optimized_nested_comp_result")?;
 
-            // Get the list_append function
-            let list_append_fn = match self.module.get_function("list_append") {
+            // Get the list_append_tagged function
+            let list_append_fn = match self.runtime_function("list_append_tagged") {
                 Some(f) => f,
-                None => return Err("list_append function not found".to_string()),
+                None => return Err("list_append_tagged function not found".to_string()),
             };
 
             // Get the list_len function
-            let list_len_fn = match self.module.get_function("list_len") {
+            let list_len_fn = match self.runtime_function("list_len") {
                 Some(f) => f,
                 None => return Err("list_len function not found".to_string()),
             };
 
             // Get the list_get function
-            let list_get_fn = match self.module.get_function("list_get") {
+            let list_get_fn = match self.runtime_function("list_get") {
                 Some(f) => f,
                 None => return Err("list_get function not found".to_string()),
             };
 
+            // Get the list_get_tag function
+            let list_get_tag_fn = match self.runtime_function("list_get_tag") {
+                Some(f) => f,
+                None => return Err("list_get_tag function not found".to_string()),
+            };
+
             // Get the list_free function
-            let list_free_fn = match self.module.get_function("list_free") {
+            let list_free_fn = match self.runtime_function("list_free") {
                 Some(f) => f,
                 None => return Err("list_free function not found".to_string()),
             };
@@ -3434,11 +3478,25 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                 .ok_or_else(|| "Failed to get element from inner list".to_string())?
                 .into_pointer_value();
 
+            // Keep the element's tag so the copy is as typed as the inner list
+            let tag_call = self.builder
+                .build_call(
+                    list_get_tag_fn,
+                    &[inner_list_ptr.into(), current_index.into()],
+                    "get_element_tag",
+                )
+                .unwrap();
+            let element_tag = tag_call
+                .try_as_basic_value()
+                .left()
+                .ok_or_else(|| "Failed to get element tag from inner list".to_string())?
+                .into_int_value();
+
             // Append element to result list
             self.builder
                 .build_call(
                     list_append_fn,
-                    &[result_list.into(), element_ptr.into()],
+                    &[result_list.into(), element_ptr.into(), element_tag.into()],
                     "append_element",
                 )
                 .unwrap();
@@ -3544,11 +3602,11 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
         self.ensure_block_has_terminator();
 
         // Create a result list to hold the comprehension results
-        let result_list = self.build_empty_list("list_comp_result")?;
+        let result_list = self.build_comprehension_list(generators, "list_comp_result")?;
 
         self.ensure_block_has_terminator();
 
-        let list_append_fn = match self.module.get_function("list_append") {
+        let list_append_fn = match self.runtime_function("list_append") {
             Some(f) => f,
             None => return Err("list_append function not found".to_string()),
         };
@@ -3612,7 +3670,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                                     };
 
                                     // Use our specialized function to create the range list directly
-                                    let list_from_range_fn = match self.module.get_function("list_from_range") {
+                                    let list_from_range_fn = match self.runtime_function("list_from_range") {
                                         Some(f) => f,
                                         None => {
                                             // Fall back to regular handling if function not found
@@ -3648,6 +3706,9 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                                         .left()
                                         .ok_or_else(|| "Failed to create optimized range list".to_string())?;
 
+                                    // The range list replaces the (possibly preallocated) result list
+                                    self.call_runtime_function("list_free", &[result_list.into()])?;
+
                                     // Pop the scope
                                     self.scope_stack.pop_scope();
 
@@ -3689,47 +3750,17 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                 element_types.push(ty.clone());
             }
 
-            let element_type = if element_types.is_empty() {
-                Type::Unknown
-            } else {
-                let first_type = &element_types[0];
-                let all_same = element_types.iter().all(|t| t == first_type);
+            let (elements, element_type) = self
+                .unify_element_types(element_values.into_iter().zip(element_types).collect())?;
 
-                if all_same {
-                    println!("All list elements have the same type: {:?}", first_type);
-                    first_type.clone()
-                } else {
-                    let mut common_type = element_types[0].clone();
-                    for ty in &element_types[1..] {
-                        common_type = match self.get_common_type(&common_type, ty) {
-                            Ok(t) => t,
-                            Err(_) => {
-                                println!(
-                                    "Could not find common type between {:?} and {:?}, using Any",
-                                    common_type, ty
-                                );
-                                Type::Any
-                            }
-                        };
-                    }
-                    println!(
-                        "List literal elements have different types, using common type: {:?}",
-                        common_type
-                    );
-                    common_type
-                }
-            };
-
-            let list_ptr = self.build_list(
-                element_values.into_iter().zip(element_types).collect(),
-                &element_type
-            )?;
+            let list_ptr = self.build_list(elements, &element_type)?;
 
             // Handle list iteration without popping the scope
             self.handle_list_iteration_for_comprehension(
                 elt,
                 generator,
                 list_ptr,
+                &element_type,
                 result_list,
                 list_append_fn,
             )?;
@@ -3743,11 +3774,12 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
             return Ok((result_list.into(), Type::List(Box::new(element_type))));
         } else {
             match iter_type {
-                Type::List(_) => {
+                Type::List(element_type) => {
                     self.handle_list_iteration_for_comprehension(
                         elt,
                         generator,
                         iter_val.into_pointer_value(),
+                        &element_type,
                         result_list,
                         list_append_fn,
                     )?;
@@ -4125,6 +4157,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
         elt: &Expr,
         generator: &crate::ast::Comprehension,
         list_ptr: inkwell::values::PointerValue<'ctx>,
+        element_type: &Type,
         result_list: inkwell::values::PointerValue<'ctx>,
         list_append_fn: inkwell::values::FunctionValue<'ctx>,
     ) -> Result<(), String> {
@@ -4136,7 +4169,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
         self.scope_stack.push_scope(false, false, false);
 
         // Get the list length
-        let list_len_fn = match self.module.get_function("list_len") {
+        let list_len_fn = match self.runtime_function("list_len") {
             Some(f) => f,
             None => return Err("list_len function not found".to_string()),
         };
@@ -4151,12 +4184,6 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
             .left()
             .ok_or_else(|| "Failed to get list length".to_string())?;
 
-        // Get the list_get function
-        let list_get_fn = match self.module.get_function("list_get") {
-            Some(f) => f,
-            None => return Err("list_get function not found".to_string()),
-        };
-
         // Get the current function
         let current_function = self
             .builder
@@ -4184,40 +4211,12 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
             .build_alloca(self.llvm_context.i64_type(), "list_comp_index")
             .unwrap();
 
-        // Allocate target variable(s)
-        let target_var = match &*generator.target {
-            Expr::Name { id, .. } => {
-                // Allocate storage for a simple named target
-                let elem_alloca = self
-                    .builder
-                    .build_alloca(
-                        self.llvm_context.i64_type(),
-                        &format!("{}_list_comp_{}", id, self.scope_stack.get_depth())
-                    )
-                    .unwrap();
-                Some((id.clone(), elem_alloca))
-            },
-            Expr::Tuple { elts, .. } => {
-                // For tuple unpacking, we need separate allocations
-                if !elts.is_empty() {
-                    if let Expr::Name { id, .. } = &*elts[0] {
-                        let elem_alloca = self
-                            .builder
-                            .build_alloca(
-                                self.llvm_context.i64_type(),
-                                &format!("{}_tuple_elem_0", id)
-                            )
-                            .unwrap();
-                        Some((id.clone(), elem_alloca))
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            },
-            _ => None
+        // Allocate target variable(s); tuple targets unpack tuple items
+        let element_type = match element_type {
+            Type::Unknown => Type::Any,
+            ty => ty.clone(),
         };
+        let target_slots = self.declare_iteration_target(&generator.target, &element_type)?;
 
         // Return to original position
         self.builder.position_at_end(current_block);
@@ -4268,53 +4267,9 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
         // Loop body
         self.builder.position_at_end(loop_body_block);
 
-        // Get element from list
-        let call_site_value = self
-            .builder
-            .build_call(
-                list_get_fn,
-                &[list_ptr.into(), current_index.into()],
-                "list_get_result",
-            )
-            .unwrap();
-
-        let element_ptr = call_site_value
-            .try_as_basic_value()
-            .left()
-            .ok_or_else(|| "Failed to get list element".to_string())?;
-
-        // Determine element type
-        let element_type = match self.lookup_variable_type(&generator.iter.to_string()) {
-            Some(Type::List(element_type)) => *element_type.clone(),
-            _ => Type::Int
-        };
-
-        // Add variable to scope
-        match &*generator.target {
-            Expr::Name { id, .. } => {
-                if let Some((_, alloca)) = &target_var {
-                    // Load element from list
-                    let element_val = self.builder.build_load(
-                        self.get_llvm_type(&element_type),
-                        element_ptr.into_pointer_value(),
-                        &format!("load_{}", id)
-                    ).unwrap();
-
-                    // Store in our pre-allocated variable
-                    self.builder.build_store(*alloca, element_val).unwrap();
-
-                    // Add to scope
-                    println!("Setting list comprehension variable '{}' to type: {:?}", id, element_type);
-                    self.scope_stack.add_variable(id.clone(), *alloca, element_type.clone());
-                }
-            },
-            Expr::Tuple {  .. } => {
-                // Handle tuple unpacking - would need more complex logic here
-                // but let's keep it simple for now
-                return Err("Tuple unpacking in nested list comprehensions is not fully implemented".to_string());
-            },
-            _ => return Err("Only simple variable targets are supported in list comprehensions".to_string()),
-        }
+        // Get element from list and bind it to the target
+        let element = self.load_list_item(list_ptr, current_index, &element_type)?;
+        self.store_iteration_target(&target_slots, element)?;
 
         // Evaluate conditions
         let should_append = self.evaluate_comprehension_conditions(generator, current_function)?;
@@ -4364,7 +4319,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
         println!("Creating new scope for string iteration in comprehension");
         self.scope_stack.push_scope(false, false, false);
 
-        let string_len_fn = match self.module.get_function("string_len") {
+        let string_len_fn = match self.runtime_function("string_len") {
             Some(f) => f,
             None => return Err("string_len function not found".to_string()),
         };
@@ -4379,7 +4334,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
             .left()
             .ok_or_else(|| "Failed to get string length".to_string())?;
 
-        let string_get_fn = match self.module.get_function("string_get_char") {
+        let string_get_fn = match self.runtime_function("string_get_char") {
             Some(f) => f,
             None => return Err("string_get_char function not found".to_string()),
         };
@@ -4930,7 +4885,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
         };
 
         // Use tagged append if available
-        let list_append_tagged_fn = match self.module.get_function("list_append_tagged") {
+        let list_append_tagged_fn = match self.comprehension_append_fn(result_list) {
             Some(f) => f,
             None => {
                 // Fall back to regular append
@@ -5029,10 +4984,14 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
             return Ok((placeholder.into(), fn_type));
         }
 
+        if value_type.is_file() && attr == "closed" {
+            return self.compile_file_closed(value_val);
+        }
+
         match &value_type {
             Type::Dict(key_type, value_type) => match attr {
                 "keys" => {
-                    let dict_keys_fn = match self.module.get_function("dict_keys") {
+                    let dict_keys_fn = match self.runtime_function("dict_keys") {
                         Some(f) => f,
                         None => return Err("dict_keys function not found".to_string()),
                     };
@@ -5054,7 +5013,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                     Ok((keys_list_ptr, Type::List(key_type.clone())))
                 }
                 "values" => {
-                    let dict_values_fn = match self.module.get_function("dict_values") {
+                    let dict_values_fn = match self.runtime_function("dict_values") {
                         Some(f) => f,
                         None => return Err("dict_values function not found".to_string()),
                     };
@@ -5076,7 +5035,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                     Ok((values_list_ptr, Type::List(value_type.clone())))
                 }
                 "items" => {
-                    let dict_items_fn = match self.module.get_function("dict_items") {
+                    let dict_items_fn = match self.runtime_function("dict_items") {
                         Some(f) => f,
                         None => return Err("dict_items function not found".to_string()),
                     };
@@ -5136,24 +5095,20 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                 },
                 _ => Err(format!("Unknown method '{}' for list type", attr)),
             },
-            Type::Class {
-                name,
-                methods,
-                fields,
-                ..
-            } => {
+            Type::Class { .. } if value_type.is_exception() => {
+                self.compile_exception_attribute(value_val, attr)
+            }
+            Type::ClassObject(class) => self.compile_class_attribute_load(value_val, class, attr),
+            Type::Class { name, methods, .. } => {
                 if let Some(_method_type) = methods.get(attr) {
                     Err(format!(
                         "Method access for class '{}' not yet implemented",
                         name
                     ))
-                } else if let Some(_field_type) = fields.get(attr) {
-                    Err(format!(
-                        "Field access for class '{}' not yet implemented",
-                        name
-                    ))
                 } else {
-                    Err(format!("Unknown attribute '{}' for class '{}'", attr, name))
+                    // Attributes can be added at runtime, so a missing one
+                    // is the runtime's AttributeError
+                    self.compile_instance_load(value_val, &value_type, attr)
                 }
             }
 
@@ -5188,6 +5143,18 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                 _ => Err(format!("Unknown method '{}' for unknown type", attr)),
             },
 
+            Type::String => {
+                use crate::compiler::builtins::string_methods::STRING_METHODS;
+                if STRING_METHODS.iter().any(|(name, _)| *name == attr) {
+                    Err(format!(
+                        "str method '{}' must be called; bound methods are not first-class values",
+                        attr
+                    ))
+                } else {
+                    Err(format!("'str' object has no attribute '{}'", attr))
+                }
+            }
+
             _ => {
                 println!("DEBUG: Type {:?} does not support attribute access for method {}", value_type, attr);
                 Err(format!(
@@ -5209,9 +5176,9 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
             return Err("Dictionary comprehension must have at least one generator".to_string());
         }
 
-        let result_dict = self.build_empty_dict("dict_comp_result")?;
+        let result_dict = self.build_comprehension_dict(generators, "dict_comp_result")?;
 
-        let dict_set_fn = match self.module.get_function("dict_set") {
+        let dict_set_fn = match self.runtime_function("dict_set") {
             Some(f) => f,
             None => return Err("dict_set function not found".to_string()),
         };
@@ -5338,6 +5305,8 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                                     result_dict.into(),
                                     key_ptr.into(),
                                     value_ptr.into(),
+                                    self.type_tag(&key_type).into(),
+                                    self.type_tag(&value_type).into(),
                                 ],
                                 "dict_set_result"
                             ).unwrap();
@@ -5371,7 +5340,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
 
         match iter_type {
             Type::List(_) => {
-                let list_len_fn = match self.module.get_function("list_len") {
+                let list_len_fn = match self.runtime_function("list_len") {
                     Some(f) => f,
                     None => return Err("list_len function not found".to_string()),
                 };
@@ -5387,7 +5356,7 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                     .left()
                     .ok_or_else(|| "Failed to get list length".to_string())?;
 
-                let list_get_fn = match self.module.get_function("list_get") {
+                let list_get_fn = match self.runtime_function("list_get") {
                     Some(f) => f,
                     None => return Err("list_get function not found".to_string()),
                 };
@@ -5540,6 +5509,8 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
                                 result_dict.into(),
                                 key_ptr.into(),
                                 value_ptr.into(),
+                                self.type_tag(&key_type).into(),
+                                self.type_tag(&value_type).into(),
                             ],
                             "dict_set_result"
                         ).unwrap();
@@ -5589,17 +5560,17 @@ impl<'ctx> ExprCompiler<'ctx> for CompilationContext<'ctx> {
         println!("Compiling simple list comprehension for variable '{}' with {} elements and {} predicates",
                 var_name, elements.len(), predicates.len());
 
-        // Create a result list
-        let result_list = self.build_empty_list("simple_list_comp_result")?;
+        // Create a result list with room for one result per element
+        let result_list = self.build_presized_list(elements.len() as u64, "simple_list_comp_result")?;
 
         // Get the list_append function
-        let list_append_fn = match self.module.get_function("list_append") {
+        let list_append_fn = match self.runtime_function("list_append") {
             Some(f) => f,
             None => return Err("list_append function not found".to_string()),
         };
 
-        // Get the list_append_tagged function
-        let list_append_tagged_fn = self.module.get_function("list_append_tagged");
+        // Get the tagged append function for the presized result list
+        let list_append_tagged_fn = self.comprehension_append_fn(result_list);
 
         // Get the current function
         let current_function = self
@@ -5815,7 +5786,26 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
         right: inkwell::values::BasicValueEnum<'ctx>,
         right_type: &Type,
     ) -> Result<(inkwell::values::BasicValueEnum<'ctx>, Type), String> {
-        let common_type = self.get_common_type(left_type, right_type)?;
+        let lowering = binary_op_lowering(&op, left_type, right_type)
+            .ok_or_else(|| unsupported_operand_types(&op, left_type, right_type))?;
+
+        // Repetition is always lowered with the sequence as the left operand
+        let (left, left_type, right, right_type) = match lowering {
+            BinaryOpLowering::StringRepeat { swapped: true }
+            | BinaryOpLowering::ListRepeat { swapped: true } => (right, right_type, left, left_type),
+            _ => (left, left_type, right, right_type),
+        };
+
+        let (common_type, right_target) = match &lowering {
+            BinaryOpLowering::Numeric(ty) => (ty.clone(), ty.clone()),
+            BinaryOpLowering::StringConcat => (Type::String, Type::String),
+            BinaryOpLowering::StringRepeat { .. } => (Type::String, Type::Int),
+            BinaryOpLowering::ListConcat => {
+                let common_type = self.get_common_type(left_type, right_type)?;
+                (common_type.clone(), common_type)
+            }
+            BinaryOpLowering::ListRepeat { .. } => (left_type.clone(), Type::Int),
+        };
 
         let left_converted = if left_type != &common_type {
             self.convert_type(left, left_type, &common_type)?
@@ -5823,8 +5813,8 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
             left
         };
 
-        let right_converted = if right_type != &common_type {
-            self.convert_type(right, right_type, &common_type)?
+        let right_converted = if right_type != &right_target {
+            self.convert_type(right, right_type, &right_target)?
         } else {
             right
         };
@@ -5851,8 +5841,7 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
                 }
                 Type::String => {
                     let string_concat_fn = self
-                        .module
-                        .get_function("string_concat")
+                        .runtime_function("string_concat")
                         .unwrap_or_else(|| {
                             let str_ptr_type =
                                 self.llvm_context.ptr_type(inkwell::AddressSpace::default());
@@ -5879,7 +5868,7 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
                     }
                 }
                 Type::List(elem_type) => {
-                    let list_concat_fn = match self.module.get_function("list_concat") {
+                    let list_concat_fn = match self.runtime_function("list_concat") {
                         Some(f) => f,
                         None => return Err("list_concat function not found".to_string()),
                     };
@@ -5901,7 +5890,7 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
                         Err("Failed to concatenate lists".to_string())
                     }
                 }
-                _ => Err(format!("Addition not supported for type {:?}", common_type)),
+                _ => Err(unsupported_operand_types(&op, left_type, right_type)),
             },
 
             Operator::Sub => match common_type {
@@ -5923,10 +5912,7 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
                         .unwrap();
                     Ok((result.into(), Type::Float))
                 }
-                _ => Err(format!(
-                    "Subtraction not supported for type {:?}",
-                    common_type
-                )),
+                _ => Err(unsupported_operand_types(&op, left_type, right_type)),
             },
 
             Operator::Mult => match common_type {
@@ -5949,75 +5935,59 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
                     Ok((result.into(), Type::Float))
                 }
                 Type::String => {
-                    if let Type::Int = *right_type {
-                        let string_repeat_fn = self
-                            .module
-                            .get_function("string_repeat")
-                            .unwrap_or_else(|| {
-                                let str_ptr_type =
-                                    self.llvm_context.ptr_type(inkwell::AddressSpace::default());
-                                let fn_type = str_ptr_type.fn_type(
-                                    &[str_ptr_type.into(), self.llvm_context.i64_type().into()],
-                                    false,
-                                );
-                                self.module.add_function("string_repeat", fn_type, None)
-                            });
+                    let string_repeat_fn = self
+                        .runtime_function("string_repeat")
+                        .unwrap_or_else(|| {
+                            let str_ptr_type =
+                                self.llvm_context.ptr_type(inkwell::AddressSpace::default());
+                            let fn_type = str_ptr_type.fn_type(
+                                &[str_ptr_type.into(), self.llvm_context.i64_type().into()],
+                                false,
+                            );
+                            self.module.add_function("string_repeat", fn_type, None)
+                        });
 
-                        let left_ptr = left_converted.into_pointer_value();
-                        let right_int = right_converted.into_int_value();
-                        let result = self
-                            .builder
-                            .build_call(
-                                string_repeat_fn,
-                                &[left_ptr.into(), right_int.into()],
-                                "string_repeat_result",
-                            )
-                            .unwrap();
+                    let left_ptr = left_converted.into_pointer_value();
+                    let right_int = right_converted.into_int_value();
+                    let result = self
+                        .builder
+                        .build_call(
+                            string_repeat_fn,
+                            &[left_ptr.into(), right_int.into()],
+                            "string_repeat_result",
+                        )
+                        .unwrap();
 
-                        if let Some(result_val) = result.try_as_basic_value().left() {
-                            return Ok((result_val, Type::String));
-                        } else {
-                            return Err("Failed to repeat string".to_string());
-                        }
+                    if let Some(result_val) = result.try_as_basic_value().left() {
+                        Ok((result_val, Type::String))
+                    } else {
+                        Err("Failed to repeat string".to_string())
                     }
-                    Err(format!(
-                        "String repetition requires an integer, got {:?}",
-                        right_type
-                    ))
                 }
                 Type::List(elem_type) => {
-                    if let Type::Int = right_type {
-                        let list_repeat_fn = match self.module.get_function("list_repeat") {
-                            Some(f) => f,
-                            None => return Err("list_repeat function not found".to_string()),
-                        };
+                    let list_repeat_fn = match self.runtime_function("list_repeat") {
+                        Some(f) => f,
+                        None => return Err("list_repeat function not found".to_string()),
+                    };
 
-                        let left_ptr = left_converted.into_pointer_value();
-                        let right_int = right_converted.into_int_value();
-                        let call_site_value = self
-                            .builder
-                            .build_call(
-                                list_repeat_fn,
-                                &[left_ptr.into(), right_int.into()],
-                                "list_repeat_result",
-                            )
-                            .unwrap();
+                    let left_ptr = left_converted.into_pointer_value();
+                    let right_int = right_converted.into_int_value();
+                    let call_site_value = self
+                        .builder
+                        .build_call(
+                            list_repeat_fn,
+                            &[left_ptr.into(), right_int.into()],
+                            "list_repeat_result",
+                        )
+                        .unwrap();
 
-                        if let Some(ret_val) = call_site_value.try_as_basic_value().left() {
-                            return Ok((ret_val, Type::List(elem_type.clone())));
-                        } else {
-                            return Err("Failed to repeat list".to_string());
-                        }
+                    if let Some(ret_val) = call_site_value.try_as_basic_value().left() {
+                        Ok((ret_val, Type::List(elem_type.clone())))
+                    } else {
+                        Err("Failed to repeat list".to_string())
                     }
-                    Err(format!(
-                        "List repetition requires an integer, got {:?}",
-                        right_type
-                    ))
                 }
-                _ => Err(format!(
-                    "Multiplication not supported for type {:?}",
-                    common_type
-                )),
+                _ => Err(unsupported_operand_types(&op, left_type, right_type)),
             },
 
             Operator::Div => match common_type {
@@ -6076,6 +6046,7 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
                     let div_bb = self.builder.get_insert_block().unwrap();
 
                     self.builder.position_at_end(div_by_zero_bb);
+                    self.emit_runtime_error("ZeroDivisionError", "division by zero")?;
                     let error_value = self.llvm_context.f64_type().const_float(f64::NAN);
                     self.builder.build_unconditional_branch(cont_bb).unwrap();
                     let div_by_zero_bb = self.builder.get_insert_block().unwrap();
@@ -6133,6 +6104,7 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
                     let div_bb = self.builder.get_insert_block().unwrap();
 
                     self.builder.position_at_end(div_by_zero_bb);
+                    self.emit_runtime_error("ZeroDivisionError", "float division by zero")?;
                     let error_value = self.llvm_context.f64_type().const_float(f64::NAN);
                     self.builder.build_unconditional_branch(cont_bb).unwrap();
                     let div_by_zero_bb = self.builder.get_insert_block().unwrap();
@@ -6146,7 +6118,7 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
 
                     Ok((phi.as_basic_value(), Type::Float))
                 }
-                _ => Err(format!("Division not supported for type {:?}", common_type)),
+                _ => Err(unsupported_operand_types(&op, left_type, right_type)),
             },
 
             Operator::FloorDiv => match common_type {
@@ -6189,6 +6161,7 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
                     let div_bb = self.builder.get_insert_block().unwrap();
 
                     self.builder.position_at_end(div_by_zero_bb);
+                    self.emit_runtime_error("ZeroDivisionError", "integer division or modulo by zero")?;
                     let error_value = self.llvm_context.i64_type().const_zero();
                     self.builder.build_unconditional_branch(cont_bb).unwrap();
                     let div_by_zero_bb = self.builder.get_insert_block().unwrap();
@@ -6245,8 +6218,8 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
                     let floor_result = self
                         .builder
                         .build_call(
-                            self.module
-                                .get_function("llvm.floor.f64")
+                            self
+                                .runtime_function("llvm.floor.f64")
                                 .unwrap_or_else(|| {
                                     let f64_type = self.llvm_context.f64_type();
                                     let function_type = f64_type.fn_type(&[f64_type.into()], false);
@@ -6262,6 +6235,7 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
                     let div_bb = self.builder.get_insert_block().unwrap();
 
                     self.builder.position_at_end(div_by_zero_bb);
+                    self.emit_runtime_error("ZeroDivisionError", "float floor division by zero")?;
                     let error_value = self.llvm_context.f64_type().const_float(f64::NAN);
                     self.builder.build_unconditional_branch(cont_bb).unwrap();
                     let div_by_zero_bb = self.builder.get_insert_block().unwrap();
@@ -6275,10 +6249,7 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
 
                     Ok((phi.as_basic_value(), Type::Float))
                 }
-                _ => Err(format!(
-                    "Floor division not supported for type {:?}",
-                    common_type
-                )),
+                _ => Err(unsupported_operand_types(&op, left_type, right_type)),
             },
 
             Operator::Mod => match common_type {
@@ -6321,6 +6292,7 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
                     let mod_bb = self.builder.get_insert_block().unwrap();
 
                     self.builder.position_at_end(mod_by_zero_bb);
+                    self.emit_runtime_error("ZeroDivisionError", "integer modulo by zero")?;
                     let error_value = self.llvm_context.i64_type().const_zero();
                     self.builder.build_unconditional_branch(cont_bb).unwrap();
                     let mod_by_zero_bb = self.builder.get_insert_block().unwrap();
@@ -6373,7 +6345,7 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
                     let mod_result = self
                         .builder
                         .build_call(
-                            self.module.get_function("fmod").unwrap_or_else(|| {
+                            self.runtime_function("fmod").unwrap_or_else(|| {
                                 let f64_type = self.llvm_context.f64_type();
                                 let function_type =
                                     f64_type.fn_type(&[f64_type.into(), f64_type.into()], false);
@@ -6388,6 +6360,7 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
                     let mod_bb = self.builder.get_insert_block().unwrap();
 
                     self.builder.position_at_end(mod_by_zero_bb);
+                    self.emit_runtime_error("ZeroDivisionError", "float modulo")?;
                     let error_value = self.llvm_context.f64_type().const_float(f64::NAN);
                     self.builder.build_unconditional_branch(cont_bb).unwrap();
                     let mod_by_zero_bb = self.builder.get_insert_block().unwrap();
@@ -6401,7 +6374,7 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
 
                     Ok((phi.as_basic_value(), Type::Float))
                 }
-                _ => Err(format!("Modulo not supported for type {:?}", common_type)),
+                _ => Err(unsupported_operand_types(&op, left_type, right_type)),
             },
 
             Operator::Pow => match common_type {
@@ -6413,7 +6386,7 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
                     let pow_result = self
                         .builder
                         .build_call(
-                            self.module.get_function("llvm.pow.f64").unwrap_or_else(|| {
+                            self.runtime_function("llvm.pow.f64").unwrap_or_else(|| {
                                 let f64_type = self.llvm_context.f64_type();
                                 let function_type =
                                     f64_type.fn_type(&[f64_type.into(), f64_type.into()], false);
@@ -6440,7 +6413,7 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
                     let pow_result = self
                         .builder
                         .build_call(
-                            self.module.get_function("llvm.pow.f64").unwrap_or_else(|| {
+                            self.runtime_function("llvm.pow.f64").unwrap_or_else(|| {
                                 let f64_type = self.llvm_context.f64_type();
                                 let function_type =
                                     f64_type.fn_type(&[f64_type.into(), f64_type.into()], false);
@@ -6456,10 +6429,7 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
 
                     Ok((pow_float, Type::Float))
                 }
-                _ => Err(format!(
-                    "Power operation not supported for type {:?}",
-                    common_type
-                )),
+                _ => Err(unsupported_operand_types(&op, left_type, right_type)),
             },
 
             Operator::BitOr => match common_type {
@@ -6472,10 +6442,7 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
                         .unwrap();
                     Ok((result.into(), Type::Int))
                 }
-                _ => Err(format!(
-                    "Bitwise OR not supported for type {:?}",
-                    common_type
-                )),
+                _ => Err(unsupported_operand_types(&op, left_type, right_type)),
             },
 
             Operator::BitXor => match common_type {
@@ -6488,10 +6455,7 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
                         .unwrap();
                     Ok((result.into(), Type::Int))
                 }
-                _ => Err(format!(
-                    "Bitwise XOR not supported for type {:?}",
-                    common_type
-                )),
+                _ => Err(unsupported_operand_types(&op, left_type, right_type)),
             },
 
             Operator::BitAnd => match common_type {
@@ -6504,10 +6468,7 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
                         .unwrap();
                     Ok((result.into(), Type::Int))
                 }
-                _ => Err(format!(
-                    "Bitwise AND not supported for type {:?}",
-                    common_type
-                )),
+                _ => Err(unsupported_operand_types(&op, left_type, right_type)),
             },
 
             Operator::LShift => match common_type {
@@ -6520,10 +6481,7 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
                         .unwrap();
                     Ok((result.into(), Type::Int))
                 }
-                _ => Err(format!(
-                    "Left shift not supported for type {:?}",
-                    common_type
-                )),
+                _ => Err(unsupported_operand_types(&op, left_type, right_type)),
             },
 
             Operator::RShift => match common_type {
@@ -6536,13 +6494,10 @@ impl<'ctx> BinaryOpCompiler<'ctx> for CompilationContext<'ctx> {
                         .unwrap();
                     Ok((result.into(), Type::Int))
                 }
-                _ => Err(format!(
-                    "Right shift not supported for type {:?}",
-                    common_type
-                )),
+                _ => Err(unsupported_operand_types(&op, left_type, right_type)),
             },
 
-            Operator::MatMult => Err("Matrix multiplication not yet implemented".to_string()),
+            Operator::MatMult => Err(unsupported_operand_types(&op, left_type, right_type)),
 
             #[allow(unreachable_patterns)]
             _ => Err(format!("Binary operator {:?} not implemented", op)),
@@ -6638,7 +6593,7 @@ impl<'ctx> ComparisonCompiler<'ctx> for CompilationContext<'ctx> {
                         return Err(format!("Type mismatch for 'in' operator: {:?} is not compatible with dictionary key type {:?}", left_type, key_type));
                     }
 
-                    let dict_contains_fn = match self.module.get_function("dict_contains") {
+                    let dict_contains_fn = match self.runtime_function("dict_contains") {
                         Some(f) => f,
                         None => return Err("dict_contains function not found".to_string()),
                     };
@@ -6665,7 +6620,11 @@ impl<'ctx> ComparisonCompiler<'ctx> for CompilationContext<'ctx> {
                         .builder
                         .build_call(
                             dict_contains_fn,
-                            &[right.into_pointer_value().into(), key_ptr.into()],
+                            &[
+                                right.into_pointer_value().into(),
+                                key_ptr.into(),
+                                self.type_tag(left_type).into(),
+                            ],
                             "dict_contains_result",
                         )
                         .unwrap();
@@ -6801,8 +6760,8 @@ impl<'ctx> ComparisonCompiler<'ctx> for CompilationContext<'ctx> {
 
             Type::String => {
                 let string_equals_fn =
-                    self.module
-                        .get_function("string_equals")
+                    self
+                        .runtime_function("string_equals")
                         .unwrap_or_else(|| {
                             let str_ptr_type =
                                 self.llvm_context.ptr_type(inkwell::AddressSpace::default());
@@ -6947,7 +6906,7 @@ impl<'ctx> AssignmentCompiler<'ctx> for CompilationContext<'ctx> {
                                     );
                                 });
 
-                                self.variables.insert(id.clone(), local_ptr);
+                                self.variables.insert(Symbol::intern(id), local_ptr);
 
                                 self.register_variable(id.clone(), value_type.clone());
 
@@ -7164,8 +7123,12 @@ impl<'ctx> AssignmentCompiler<'ctx> for CompilationContext<'ctx> {
                 }
             }
 
-            Expr::Subscript { value, slice, .. } => {
-                let (container_val, container_type) = self.compile_expr(value)?;
+            Expr::Subscript {
+                value: container,
+                slice,
+                ..
+            } => {
+                let (container_val, container_type) = self.compile_expr(container)?;
 
                 let (index_val, index_type) = self.compile_expr(slice)?;
 
@@ -7178,18 +7141,16 @@ impl<'ctx> AssignmentCompiler<'ctx> for CompilationContext<'ctx> {
                             ));
                         }
 
-                        let list_set_fn = match self.module.get_function("list_set") {
+                        let list_set_fn = match self.runtime_function("list_set") {
                             Some(f) => f,
                             None => return Err("list_set function not found".to_string()),
                         };
 
-                        let (value_val, _) = self.compile_expr(value)?;
-
                         let value_alloca = self
                             .builder
-                            .build_alloca(value_val.get_type(), "list_set_value")
+                            .build_alloca(value.get_type(), "list_set_value")
                             .unwrap();
-                        self.builder.build_store(value_alloca, value_val).unwrap();
+                        self.builder.build_store(value_alloca, value).unwrap();
 
                         self.builder
                             .build_call(
@@ -7221,7 +7182,7 @@ impl<'ctx> AssignmentCompiler<'ctx> for CompilationContext<'ctx> {
                             ));
                         }
 
-                        let dict_set_fn = match self.module.get_function("dict_set") {
+                        let dict_set_fn = match self.runtime_function("dict_set") {
                             Some(f) => f,
                             None => return Err("dict_set function not found".to_string()),
                         };
@@ -7237,13 +7198,16 @@ impl<'ctx> AssignmentCompiler<'ctx> for CompilationContext<'ctx> {
                             key_alloca.into()
                         };
 
-                        let (value_val, _value_type) = self.compile_expr(target)?;
-
-                        let value_alloca = self
-                            .builder
-                            .build_alloca(value_val.get_type(), "dict_value_temp")
-                            .unwrap();
-                        self.builder.build_store(value_alloca, value_val).unwrap();
+                        let value_ptr = if crate::compiler::types::is_reference_type(value_type) {
+                            value
+                        } else {
+                            let value_alloca = self
+                                .builder
+                                .build_alloca(value.get_type(), "dict_value_temp")
+                                .unwrap();
+                            self.builder.build_store(value_alloca, value).unwrap();
+                            value_alloca.into()
+                        };
 
                         self.builder
                             .build_call(
@@ -7251,7 +7215,9 @@ impl<'ctx> AssignmentCompiler<'ctx> for CompilationContext<'ctx> {
                                 &[
                                     container_val.into_pointer_value().into(),
                                     key_ptr.into(),
-                                    value_alloca.into(),
+                                    value_ptr.into(),
+                                    self.type_tag(&index_type).into(),
+                                    self.type_tag(value_type).into(),
                                 ],
                                 "dict_set_result",
                             )
@@ -7269,6 +7235,23 @@ impl<'ctx> AssignmentCompiler<'ctx> for CompilationContext<'ctx> {
                 }
             }
 
+            Expr::Attribute { value: object, attr, .. } => {
+                let (object_val, object_type) = self.compile_expr(object)?;
+                if let Type::ClassObject(class) = &object_type {
+                    return self.store_class_attribute(
+                        object_val.into_pointer_value(),
+                        class,
+                        attr,
+                        value,
+                        value_type,
+                    );
+                }
+                if !object_type.is_class() || object_type.is_exception() {
+                    return Err(format!("Cannot assign attribute '{}' on value of type {}", attr, object_type));
+                }
+                self.compile_instance_store(object_val, attr, value, value_type)
+            }
+
             _ => Err(format!("Unsupported assignment target: {:?}", target)),
         }
     }
@@ -5,6 +5,7 @@ use crate::ast::{BoolOperator, CmpOperator, Expr, Operator, UnaryOperator};
 use crate::compiler::context::CompilationContext;
 use crate::compiler::expr::{BinaryOpCompiler, ComparisonCompiler, ExprCompiler};
 use crate::compiler::types::Type;
+use crate::intern::Symbol;
 use inkwell::values::BasicValueEnum;
 use std::collections::VecDeque;
 
@@ -32,6 +33,8 @@ enum ExprTask<'a> {
 
     ProcessBinaryOp {
         op: Operator,
+        line: usize,
+        column: usize,
     },
 
     ProcessUnaryOp {
@@ -104,9 +107,18 @@ impl<'ctx> ExprNonRecursive<'ctx> for CompilationContext<'ctx> {
                         result_stack.push(ExprResult { value, ty });
                     }
                     Expr::BinOp {
-                        left, op, right, ..
+                        left,
+                        op,
+                        right,
+                        line,
+                        column,
+                        span: _,
                     } => {
-                        work_stack.push_front(ExprTask::ProcessBinaryOp { op: op.clone() });
+                        work_stack.push_front(ExprTask::ProcessBinaryOp {
+                            op: op.clone(),
+                            line: *line,
+                            column: *column,
+                        });
 
                         work_stack.push_front(ExprTask::Evaluate(right));
 
@@ -250,8 +262,8 @@ impl<'ctx> ExprNonRecursive<'ctx> for CompilationContext<'ctx> {
                             }
                         }
                         // Next, try to find the variable in the global variables
-                        else if let Some(var_ptr) = self.variables.get(id) {
-                            if let Some(var_type) = self.type_env.get(id) {
+                        else if let Some(var_ptr) = self.variables.get(&Symbol::intern(id)) {
+                            if let Some(var_type) = self.type_env.get(&Symbol::intern(id)) {
                                 let llvm_type = self.get_llvm_type(var_type);
 
                                 let var_val = self
@@ -294,6 +306,9 @@ impl<'ctx> ExprNonRecursive<'ctx> for CompilationContext<'ctx> {
                                 } else {
                                     return Err(format!("Variable found but type unknown: {}", id));
                                 }
+                            } else if self.is_class_name(id) {
+                                let (value, ty) = self.compile_class_object(id)?;
+                                result_stack.push(ExprResult { value, ty });
                             } else {
                                 return Err(format!("Undefined variable: {}", id));
                             }
@@ -331,18 +346,12 @@ impl<'ctx> ExprNonRecursive<'ctx> for CompilationContext<'ctx> {
                     }
 
                     Expr::Str { value, .. } => {
-                        let const_str = self.llvm_context.const_string(value.as_bytes(), true);
-
-                        let str_type = const_str.get_type();
-
-                        let global_str = self.module.add_global(str_type, None, "str_const");
-                        global_str.set_constant(true);
-                        global_str.set_initializer(&const_str);
+                        let global_str = self.string_constant(value.as_bytes());
 
                         let str_ptr = self
                             .builder
                             .build_pointer_cast(
-                                global_str.as_pointer_value(),
+                                global_str,
                                 self.llvm_context.ptr_type(inkwell::AddressSpace::default()),
                                 "str_ptr",
                             )
@@ -434,7 +443,7 @@ impl<'ctx> ExprNonRecursive<'ctx> for CompilationContext<'ctx> {
                         result_stack.push(ExprResult { value, ty });
                     }
                 },
-                ExprTask::ProcessBinaryOp { op } => {
+                ExprTask::ProcessBinaryOp { op, line, column } => {
                     if result_stack.len() < 2 {
                         return Err(format!(
                             "Not enough operands for binary operation: stack size = {}",
@@ -448,13 +457,15 @@ impl<'ctx> ExprNonRecursive<'ctx> for CompilationContext<'ctx> {
                     let right_result = &result_stack[right_idx];
                     let left_result = &result_stack[left_idx];
 
-                    let (result_value, result_type) = self.compile_binary_op(
-                        left_result.value,
-                        &left_result.ty,
-                        op,
-                        right_result.value,
-                        &right_result.ty,
-                    )?;
+                    let (result_value, result_type) = self
+                        .compile_binary_op(
+                            left_result.value,
+                            &left_result.ty,
+                            op,
+                            right_result.value,
+                            &right_result.ty,
+                        )
+                        .map_err(|e| format!("{} at line {}, column {}", e, line, column))?;
 
                     result_stack.remove(right_idx);
                     result_stack.remove(left_idx);
@@ -823,24 +834,16 @@ impl<'ctx> ExprNonRecursive<'ctx> for CompilationContext<'ctx> {
                     }
 
                     let mut elements = Vec::with_capacity(elements_count);
-                    let mut element_types = Vec::with_capacity(elements_count);
-                    let mut common_element_type = Type::Unknown;
 
                     for _ in 0..elements_count {
                         let idx = result_stack.len() - 1;
                         let element = result_stack.remove(idx);
-                        elements.push((element.value, element.ty.clone()));
-                        element_types.push(element.ty.clone());
-
-                        if common_element_type == Type::Unknown {
-                            common_element_type = element.ty;
-                        } else if common_element_type != element.ty {
-                            common_element_type = Type::Any;
-                        }
+                        elements.push((element.value, element.ty));
                     }
 
                     elements.reverse();
-                    element_types.reverse();
+
+                    let (elements, common_element_type) = self.unify_element_types(elements)?;
 
                     let list_ptr = self.build_list(elements, &common_element_type)?;
 
@@ -860,34 +863,25 @@ impl<'ctx> ExprNonRecursive<'ctx> for CompilationContext<'ctx> {
 
                     let mut keys = Vec::with_capacity(elements_count);
                     let mut values = Vec::with_capacity(elements_count);
-                    let mut key_type = Type::Unknown;
-                    let mut value_type = Type::Unknown;
 
                     for _ in 0..elements_count {
                         let value_idx = result_stack.len() - 1;
                         let value = result_stack.remove(value_idx);
-                        values.push(value.value);
-
-                        if value_type == Type::Unknown {
-                            value_type = value.ty;
-                        } else if value_type != value.ty {
-                            value_type = Type::Any;
-                        }
+                        values.push((value.value, value.ty));
 
                         let key_idx = result_stack.len() - 1;
                         let key = result_stack.remove(key_idx);
-                        keys.push(key.value);
-
-                        if key_type == Type::Unknown {
-                            key_type = key.ty;
-                        } else if key_type != key.ty {
-                            key_type = Type::Any;
-                        }
+                        keys.push((key.value, key.ty));
                     }
 
                     keys.reverse();
                     values.reverse();
 
+                    let (keys, key_type) = self.unify_element_types(keys)?;
+                    let (values, value_type) = self.unify_element_types(values)?;
+                    let keys = keys.into_iter().map(|(value, _)| value).collect();
+                    let values = values.into_iter().map(|(value, _)| value).collect();
+
                     let dict_ptr = self.build_dict(keys, values, &key_type, &value_type)?;
 
                     result_stack.push(ExprResult {
@@ -905,22 +899,18 @@ impl<'ctx> ExprNonRecursive<'ctx> for CompilationContext<'ctx> {
                     }
 
                     let mut elements = Vec::with_capacity(elements_count);
-                    let mut element_type = Type::Unknown;
 
                     for _ in 0..elements_count {
                         let idx = result_stack.len() - 1;
                         let element = result_stack.remove(idx);
-                        elements.push(element.value);
-
-                        if element_type == Type::Unknown {
-                            element_type = element.ty;
-                        } else if element_type != element.ty {
-                            element_type = Type::Any;
-                        }
+                        elements.push((element.value, element.ty));
                     }
 
                     elements.reverse();
 
+                    let (elements, element_type) = self.unify_element_types(elements)?;
+                    let elements = elements.into_iter().map(|(value, _)| value).collect();
+
                     let set_ptr = self.build_set(elements, &element_type)?;
 
                     result_stack.push(ExprResult {
@@ -938,7 +928,8 @@ impl<'ctx> ExprNonRecursive<'ctx> for CompilationContext<'ctx> {
 
                     let (attr_val, attr_type) = match value_result.ty {
                         Type::Dict(_, _) => match attr.as_str() {
-                            "keys" | "values" | "items" | "get" | "pop" | "clear" | "update" => {
+                            "keys" | "values" | "items" | "get" | "pop" | "clear" | "update"
+                            | "setdefault" => {
                                 let placeholder = self.llvm_context.i32_type().const_int(0, false);
                                 (placeholder.into(), Type::function(vec![], Type::Any))
                             }
@@ -948,7 +939,7 @@ impl<'ctx> ExprNonRecursive<'ctx> for CompilationContext<'ctx> {
                         },
                         Type::List(_) | Type::Unknown => match attr.as_str() {
                             "append" | "pop" | "clear" | "extend" | "insert" | "remove"
-                            | "sort" => {
+                            | "sort" | "index" | "count" => {
                                 // Return a function that will be called with the argument
                                 let list_ptr = value_result.value.into_pointer_value();
 
@@ -989,19 +980,25 @@ impl<'ctx> ExprNonRecursive<'ctx> for CompilationContext<'ctx> {
                             _ => return Err(format!("Unknown attribute '{}' for list", attr)),
                         },
                         Type::String => match attr.as_str() {
-                            "upper" | "lower" | "strip" | "split" | "join" | "replace" => {
+                            "upper" | "lower" | "strip" | "split" | "join" | "replace" | "find"
+                            | "startswith" | "endswith" => {
                                 let placeholder = self.llvm_context.i32_type().const_int(0, false);
                                 (placeholder.into(), Type::function(vec![], Type::Any))
                             }
                             _ => return Err(format!("Unknown attribute '{}' for string", attr)),
                         },
-                        Type::Class { methods, .. } => {
+                        Type::Class { .. } if value_result.ty.is_exception() => {
+                            self.compile_exception_attribute(value_result.value, &attr)?
+                        }
+                        Type::ClassObject(ref class) => {
+                            self.compile_class_attribute_load(value_result.value, class, &attr)?
+                        }
+                        Type::Class { ref methods, .. } => {
                             if let Some(method_type) = methods.get(&attr) {
                                 let placeholder = self.llvm_context.i32_type().const_int(0, false);
                                 (placeholder.into(), (**method_type).clone())
                             } else {
-                                let placeholder = self.llvm_context.i32_type().const_int(0, false);
-                                (placeholder.into(), Type::Any)
+                                self.compile_instance_load(value_result.value, &value_result.ty, &attr)?
                             }
                         }
 
@@ -1071,7 +1068,7 @@ impl<'ctx> ExprNonRecursive<'ctx> for CompilationContext<'ctx> {
                                         len_args.iter().map(|arg| (**arg).clone()).collect();
                                     let (len_val, _) = self.compile_len_call(&args_slice)?;
 
-                                    let range_1_fn = match self.module.get_function("range_1") {
+                                    let range_1_fn = match self.runtime_function("range_1") {
                                         Some(f) => f,
                                         None => {
                                             return Err("range_1 function not found".to_string())
@@ -0,0 +1,255 @@
+// ffi.rs - Calling C functions declared with `@extern`
+//
+// A module-level function decorated with `@extern` declares an external C
+// function instead of defining one. `@extern("symbol")` binds a C symbol with
+// a different name:
+//
+//     @extern
+//     def cos(x: float) -> float: ...
+//
+//     @extern("strlen")
+//     def c_strlen(s: str) -> int: ...
+//
+// Every parameter needs an annotation and the body must be `...`, `pass` or a
+// docstring. Types map to C as int -> int64_t, float -> double, bool -> bool
+// and str -> const char *; a missing or None return type is void. AOT builds
+// resolve the symbols at link time (`cheetah build -l m`); the JIT resolves
+// them in the running process and in libraries loaded with `-l`.
+
+use crate::ast::{Expr, NameConstant, Parameter, Stmt};
+#[cfg(feature = "codegen")]
+use crate::compiler::cdylib::library_file_name;
+#[cfg(feature = "codegen")]
+use crate::compiler::context::CompilationContext;
+#[cfg(feature = "codegen")]
+use crate::compiler::expr::ExprCompiler;
+#[cfg(feature = "codegen")]
+use crate::compiler::plugins::lookup_builtin;
+use crate::compiler::types::Type;
+#[cfg(feature = "codegen")]
+use inkwell::types::{BasicMetadataTypeEnum, BasicType};
+#[cfg(feature = "codegen")]
+use inkwell::values::{BasicMetadataValueEnum, BasicValueEnum};
+#[cfg(feature = "codegen")]
+use std::path::{Path, PathBuf};
+
+/// Signature of a C function declared with `@extern`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternSignature {
+    /// Symbol the declaration binds to
+    pub symbol: String,
+    pub param_types: Vec<Type>,
+    pub return_type: Type,
+}
+
+impl ExternSignature {
+    /// Signature of a function definition, or None if it is not decorated with `@extern`
+    pub fn from_def(
+        name: &str,
+        params: &[Parameter],
+        returns: &Option<Box<Expr>>,
+        decorator_list: &[Box<Expr>],
+        body: &[Box<Stmt>],
+    ) -> Result<Option<Self>, String> {
+        let Some(symbol) = extern_symbol(name, decorator_list)? else {
+            return Ok(None);
+        };
+        if !is_stub_body(body) {
+            return Err("the body of an @extern function must be '...' or 'pass'".to_string());
+        }
+
+        let mut param_types = Vec::with_capacity(params.len());
+        for param in params {
+            if param.is_vararg || param.is_kwarg || param.default.is_some() {
+                return Err(format!(
+                    "parameter '{}' cannot be variadic or have a default",
+                    param.name
+                ));
+            }
+            let annotation = param
+                .typ
+                .as_ref()
+                .ok_or_else(|| format!("parameter '{}' needs a type annotation", param.name))?;
+            let ty = ffi_type(annotation)?;
+            if ty == Type::None {
+                return Err(format!("parameter '{}' cannot be None", param.name));
+            }
+            param_types.push(ty);
+        }
+
+        let return_type = match returns {
+            Some(annotation) => ffi_type(annotation)?,
+            None => Type::None,
+        };
+
+        Ok(Some(Self {
+            symbol,
+            param_types,
+            return_type,
+        }))
+    }
+}
+
+/// C symbol named by an `@extern` decorator, if the function has one
+pub fn extern_symbol(name: &str, decorator_list: &[Box<Expr>]) -> Result<Option<String>, String> {
+    for decorator in decorator_list {
+        match decorator.as_ref() {
+            Expr::Name { id, .. } if id == "extern" => return Ok(Some(name.to_string())),
+            Expr::Call { func, args, keywords, .. }
+                if matches!(func.as_ref(), Expr::Name { id, .. } if id == "extern") =>
+            {
+                return match (args.as_slice(), keywords.is_empty()) {
+                    ([symbol], true) => match symbol.as_ref() {
+                        Expr::Str { value, .. } if !value.is_empty() => Ok(Some(value.clone())),
+                        _ => Err("@extern takes the C symbol name as a string".to_string()),
+                    },
+                    _ => Err("@extern takes the C symbol name as a single string".to_string()),
+                };
+            }
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+/// Type of an annotation in an `@extern` signature
+pub fn ffi_type(annotation: &Expr) -> Result<Type, String> {
+    match annotation {
+        Expr::Name { id, .. } => match id.as_str() {
+            "int" => Ok(Type::Int),
+            "float" => Ok(Type::Float),
+            "bool" => Ok(Type::Bool),
+            "str" => Ok(Type::String),
+            "None" => Ok(Type::None),
+            other => Err(format!(
+                "type '{}' has no C equivalent (expected int, float, bool, str or None)",
+                other
+            )),
+        },
+        Expr::NameConstant { value: NameConstant::None, .. } => Ok(Type::None),
+        other => Err(format!(
+            "type '{}' has no C equivalent (expected int, float, bool, str or None)",
+            other
+        )),
+    }
+}
+
+/// Whether a function body only holds `...`, `pass` or a docstring
+pub(crate) fn is_stub_body(body: &[Box<Stmt>]) -> bool {
+    body.iter().all(|stmt| match stmt.as_ref() {
+        Stmt::Pass { .. } => true,
+        Stmt::Expr { value, .. } => matches!(value.as_ref(), Expr::Ellipsis { .. } | Expr::Str { .. }),
+        _ => false,
+    })
+}
+
+/// Path the JIT loads for `-l name`: the first search directory holding the
+/// library, otherwise its file name for the dynamic loader to find
+#[cfg(feature = "codegen")]
+pub fn library_path(name: &str, search_paths: &[String]) -> PathBuf {
+    if name.contains('/') || name.contains(".so") || name.ends_with(".dylib") || name.ends_with(".dll") {
+        return PathBuf::from(name);
+    }
+    let file_name = library_file_name(name);
+    search_paths
+        .iter()
+        .map(|dir| Path::new(dir).join(&file_name))
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| PathBuf::from(file_name))
+}
+
+/// Load libraries into the process so JIT-compiled code can call their symbols.
+///
+/// Returns the paths that could not be loaded.
+#[cfg(feature = "codegen")]
+pub fn load_libraries(libs: &[String], search_paths: &[String]) -> Vec<PathBuf> {
+    libs.iter()
+        .map(|name| library_path(name, search_paths))
+        .filter(|path| inkwell::support::load_library_permanently(path))
+        .collect()
+}
+
+#[cfg(feature = "codegen")]
+impl<'ctx> CompilationContext<'ctx> {
+    /// Declare the C function behind an `@extern` definition of `name`
+    pub fn declare_extern(&mut self, name: &str, signature: ExternSignature) -> Result<(), String> {
+        let param_types: Vec<BasicMetadataTypeEnum<'ctx>> = signature
+            .param_types
+            .iter()
+            .map(|ty| self.get_llvm_type(ty).into())
+            .collect();
+        let fn_type = match &signature.return_type {
+            Type::None => self.llvm_context.void_type().fn_type(&param_types, false),
+            ty => self.get_llvm_type(ty).fn_type(&param_types, false),
+        };
+
+        match self.module.get_function(&signature.symbol) {
+            Some(existing) if existing.get_type() != fn_type => {
+                return Err(format!(
+                    "@extern declaration of '{}' conflicts with an existing declaration of that symbol",
+                    signature.symbol
+                ));
+            }
+            Some(_) => {}
+            None => {
+                self.module.add_function(&signature.symbol, fn_type, None);
+            }
+        }
+
+        self.extern_functions.insert(name.to_string(), signature);
+        Ok(())
+    }
+
+    /// Compile a call to an `@extern` function, converting arguments to its parameter types
+    pub fn compile_extern_call(
+        &mut self,
+        name: &str,
+        args: &[Box<Expr>],
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        let signature = self
+            .extern_functions
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("Undefined extern function: {}", name))?;
+        let folded = lookup_builtin(name)
+            .filter(|builtin| builtin.signature == signature)
+            .and_then(|builtin| builtin.fold_call(args));
+        if let Some(value) = folded {
+            return self.compile_expr(&value.to_expr(0, 0));
+        }
+        if args.len() != signature.param_types.len() {
+            return Err(format!(
+                "{}() takes {} arguments but {} were given",
+                name,
+                signature.param_types.len(),
+                args.len()
+            ));
+        }
+        let function = self
+            .module
+            .get_function(&signature.symbol)
+            .ok_or_else(|| format!("extern function {} not declared", signature.symbol))?;
+
+        let mut call_args: Vec<BasicMetadataValueEnum<'ctx>> = Vec::with_capacity(args.len());
+        for (arg, param_type) in args.iter().zip(&signature.param_types) {
+            let (value, ty) = self.compile_expr(arg)?;
+            call_args.push(self.convert_type(value, &ty, param_type)?.into());
+        }
+
+        let call = self
+            .builder
+            .build_call(function, &call_args, &format!("{}_call", name))
+            .unwrap();
+
+        match call.try_as_basic_value().left() {
+            Some(value) => Ok((value, signature.return_type)),
+            None => Ok((
+                self.llvm_context
+                    .ptr_type(inkwell::AddressSpace::default())
+                    .const_null()
+                    .into(),
+                Type::None,
+            )),
+        }
+    }
+}
@@ -0,0 +1,346 @@
+// ir_codegen.rs - LLVM code generation from the typed IR
+//
+// Lowers an `ir::Program` into the module being built, one LLVM function
+// per IR function. Every IR local gets a stack slot in the entry block and
+// every IR block an LLVM block, so LLVM's mem2reg turns the locals back
+// into registers. Functions take and return pointers, the boxed values the
+// IR passes between functions, except for the parameters and results an
+// unboxed copy takes and returns as plain values, and functions returning
+// None, such as `main`, return nothing:
+//
+//     let program = ir::lower_module(&module)?;
+//     compiler.context.compile_ir_program(&program)?;
+//
+// Operations reuse the compiler's lowering of binary operators,
+// comparisons and conversions, and lists use the same runtime functions and
+// item boxes as code compiled from the AST. Functions push traceback frames,
+// `line` instructions record the statement being run and set the debug
+// location, and calls are checked for a callee cut short, as in code
+// compiled from the AST (see `traceback.rs`). A shim's frame would only
+// repeat its copy's, so shims have none.
+//
+// This only handles what the IR covers; the AST compiler stays the main
+// code generator (see `Compiler::compile_module` for when this one is used).
+
+use crate::ast::UnaryOperator;
+use crate::compiler::context::CompilationContext;
+use crate::compiler::expr::{BinaryOpCompiler, ComparisonCompiler};
+use crate::compiler::types::Type;
+use crate::ir::specialize::{unboxed_name, UNBOXED_SUFFIX};
+use crate::ir::{self, Const, Inst, Operand, Rvalue, Terminator};
+use inkwell::basic_block::BasicBlock;
+use inkwell::types::{BasicMetadataTypeEnum, BasicType};
+use inkwell::values::{BasicValueEnum, FunctionValue, PointerValue};
+use inkwell::AddressSpace;
+
+/// Where an IR function's locals and blocks live in LLVM
+struct FunctionFrame<'a, 'ctx> {
+    function: &'a ir::Function,
+    slots: Vec<PointerValue<'ctx>>,
+    blocks: Vec<BasicBlock<'ctx>>,
+}
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// Generate code for every function of `program`. A `main` the caller
+    /// has already started is continued from where it left off.
+    pub fn compile_ir_program(&mut self, program: &ir::Program) -> Result<(), String> {
+        for function in &program.functions {
+            self.declare_ir_function(function)?;
+        }
+        for function in &program.functions {
+            let is_shim = program.function(&unboxed_name(&function.name)).is_some();
+            self.compile_ir_function(function, is_shim)?;
+        }
+        Ok(())
+    }
+
+    fn declare_ir_function(&mut self, function: &ir::Function) -> Result<FunctionValue<'ctx>, String> {
+        if let Some(declared) = self.module.get_function(&function.name) {
+            return Ok(declared);
+        }
+        let ptr_type = self.llvm_context.ptr_type(AddressSpace::default());
+        let params: Vec<BasicMetadataTypeEnum> = function
+            .params
+            .iter()
+            .map(|param| self.get_llvm_type(function.local_type(*param)).into())
+            .collect();
+        let fn_type = if function.signature.returns == Type::None {
+            self.llvm_context.void_type().fn_type(&params, false)
+        } else if function.returns_unboxed {
+            self.get_llvm_type(&function.signature.returns).fn_type(&params, false)
+        } else {
+            ptr_type.fn_type(&params, false)
+        };
+        Ok(self.module.add_function(&function.name, fn_type, None))
+    }
+
+    fn compile_ir_function(&mut self, function: &ir::Function, is_shim: bool) -> Result<(), String> {
+        let llvm_function = self.declare_ir_function(function)?;
+        let entry = match llvm_function.get_last_basic_block() {
+            Some(block) if block.get_terminator().is_none() => block,
+            _ => self.llvm_context.append_basic_block(llvm_function, "entry"),
+        };
+        self.builder.position_at_end(entry);
+
+        let name = match function.name.as_str() {
+            ir::MAIN => "<module>",
+            name => name.strip_suffix(UNBOXED_SUFFIX).unwrap_or(name),
+        };
+        let first_line = function.blocks[0].insts.iter().find_map(|inst| match inst {
+            Inst::Line(line) => Some(*line),
+            _ => None,
+        });
+        let saved_debug_scope = self.enter_debug_scope(llvm_function, name, first_line.unwrap_or(1));
+        let saved_trace_frame = if is_shim {
+            self.trace_frame.take()
+        } else {
+            self.enter_trace_frame(name)?
+        };
+
+        let slots = function
+            .locals
+            .iter()
+            .enumerate()
+            .map(|(i, local)| {
+                let name = local.name.clone().unwrap_or_else(|| format!("t{}", i));
+                self.builder.build_alloca(self.get_llvm_type(&local.ty), &name).unwrap()
+            })
+            .collect();
+        let blocks = (0..function.blocks.len())
+            .map(|i| self.llvm_context.append_basic_block(llvm_function, &format!("bb{}", i)))
+            .collect();
+        let frame = FunctionFrame {
+            function,
+            slots,
+            blocks,
+        };
+
+        for (i, param) in function.params.iter().enumerate() {
+            let value = llvm_function
+                .get_nth_param(i as u32)
+                .ok_or_else(|| format!("{} has no parameter {}", function.name, i))?;
+            self.builder.build_store(frame.slots[param.0], value).unwrap();
+        }
+        self.builder.build_unconditional_branch(frame.blocks[0]).unwrap();
+
+        for (block, llvm_block) in function.blocks.iter().zip(&frame.blocks) {
+            self.builder.position_at_end(*llvm_block);
+            for inst in &block.insts {
+                self.compile_ir_inst(&frame, inst)?;
+            }
+            self.compile_ir_terminator(&frame, &block.terminator)?;
+        }
+        self.leave_trace_frame(saved_trace_frame);
+        self.leave_debug_scope(saved_debug_scope);
+        Ok(())
+    }
+
+    fn compile_ir_operand(&mut self, frame: &FunctionFrame<'_, 'ctx>, operand: &Operand) -> BasicValueEnum<'ctx> {
+        match operand {
+            Operand::Local(local) => {
+                let ty = self.get_llvm_type(frame.function.local_type(*local));
+                self.builder.build_load(ty, frame.slots[local.0], "load").unwrap()
+            }
+            Operand::Const(Const::Int(value)) => self.llvm_context.i64_type().const_int(*value as u64, true).into(),
+            Operand::Const(Const::Float(value)) => self.llvm_context.f64_type().const_float(*value).into(),
+            Operand::Const(Const::Bool(value)) => self.llvm_context.bool_type().const_int(*value as u64, false).into(),
+            Operand::Const(Const::Str(value)) => self.make_cstr("ir_str", value.as_bytes()).into(),
+            Operand::Const(Const::None) => self.llvm_context.ptr_type(AddressSpace::default()).const_null().into(),
+        }
+    }
+
+    fn compile_ir_inst(&mut self, frame: &FunctionFrame<'_, 'ctx>, inst: &Inst) -> Result<(), String> {
+        match inst {
+            Inst::Line(line) => {
+                self.set_debug_line(*line);
+                self.emit_trace_line(*line)?;
+            }
+            Inst::Assign { dest, value } => {
+                let ty = frame.function.local_type(*dest);
+                let value = self.compile_ir_rvalue(frame, value, ty)?;
+                self.builder.build_store(frame.slots[dest.0], value).unwrap();
+            }
+            Inst::Print(operands) => {
+                let print_str = self.runtime_function("print_string").ok_or("print_string not found")?;
+                let space = self.make_cstr("sp", b" ");
+                for (i, operand) in operands.iter().enumerate() {
+                    if i > 0 {
+                        self.builder.build_call(print_str, &[space.into()], "print_space").unwrap();
+                    }
+                    let value = self.compile_ir_operand(frame, operand);
+                    match frame.function.operand_type(operand) {
+                        Type::Int => {
+                            self.call_runtime_function("print_int", &[value.into()])?;
+                        }
+                        Type::Float => {
+                            self.call_runtime_function("print_float", &[value.into()])?;
+                        }
+                        Type::Bool => {
+                            self.call_runtime_function("print_bool", &[value.into()])?;
+                        }
+                        Type::String => {
+                            self.call_runtime_function("print_string", &[value.into()])?;
+                        }
+                        Type::None => {
+                            let none = self.make_cstr("none", b"None");
+                            self.builder.build_call(print_str, &[none.into()], "print_none").unwrap();
+                        }
+                        Type::List(element) => self.print_list(value.into_pointer_value(), &element, 0)?,
+                        other => return Err(format!("cannot print a value of type {}", other)),
+                    }
+                }
+                let newline = self.make_cstr("nl", b"\n");
+                self.call_runtime_function("println_string", &[newline.into()])?;
+            }
+            Inst::ListPush { list, value } => {
+                let Type::List(element) = frame.function.operand_type(list) else {
+                    return Err("list.push on a value that isn't a list".to_string());
+                };
+                let list = self.compile_ir_operand(frame, list).into_pointer_value();
+                let value = self.compile_ir_operand(frame, value).into_pointer_value();
+                self.append_list_item(list, value, &element)?;
+            }
+            Inst::ListSet { list, index, value } => {
+                let list = self.compile_ir_operand(frame, list);
+                let index = self.compile_ir_operand(frame, index);
+                let value = self.compile_ir_operand(frame, value);
+                self.call_runtime_function("list_set", &[list.into(), index.into(), value.into()])?;
+            }
+        }
+        Ok(())
+    }
+
+    fn compile_ir_rvalue(
+        &mut self,
+        frame: &FunctionFrame<'_, 'ctx>,
+        rvalue: &Rvalue,
+        ty: &Type,
+    ) -> Result<BasicValueEnum<'ctx>, String> {
+        let function = frame.function;
+        let (value, value_type) = match rvalue {
+            Rvalue::Use(operand) => (self.compile_ir_operand(frame, operand), function.operand_type(operand)),
+            Rvalue::Binary { op, left, right } => {
+                let (left_value, right_value) = (self.compile_ir_operand(frame, left), self.compile_ir_operand(frame, right));
+                self.compile_binary_op(
+                    left_value,
+                    &function.operand_type(left),
+                    op.clone(),
+                    right_value,
+                    &function.operand_type(right),
+                )?
+            }
+            Rvalue::Unary { op, operand } => {
+                let value = self.compile_ir_operand(frame, operand);
+                let value: BasicValueEnum = match (op, value) {
+                    (UnaryOperator::UAdd, value) => value,
+                    (UnaryOperator::USub, BasicValueEnum::FloatValue(value)) => {
+                        self.builder.build_float_neg(value, "neg").unwrap().into()
+                    }
+                    (UnaryOperator::USub, BasicValueEnum::IntValue(value)) => {
+                        self.builder.build_int_neg(value, "neg").unwrap().into()
+                    }
+                    (UnaryOperator::Not | UnaryOperator::Invert, BasicValueEnum::IntValue(value)) => {
+                        self.builder.build_not(value, "not").unwrap().into()
+                    }
+                    _ => return Err(format!("bad operand for unary operator {:?}", op)),
+                };
+                (value, ty.clone())
+            }
+            Rvalue::Compare { op, left, right } => {
+                let (left_value, right_value) = (self.compile_ir_operand(frame, left), self.compile_ir_operand(frame, right));
+                self.compile_comparison(
+                    left_value,
+                    &function.operand_type(left),
+                    op.clone(),
+                    right_value,
+                    &function.operand_type(right),
+                )?
+            }
+            Rvalue::Cast { value, to } => {
+                let from = function.operand_type(value);
+                let value = self.compile_ir_operand(frame, value);
+                (self.convert_type(value, &from, to)?, to.clone())
+            }
+            Rvalue::Box(operand) => {
+                let value = self.compile_ir_operand(frame, operand);
+                let boxed = self.box_list_item(value, &function.operand_type(operand))?;
+                (boxed.into(), Type::Any)
+            }
+            Rvalue::Unbox { value, to } => {
+                let boxed = self.compile_ir_operand(frame, value).into_pointer_value();
+                (self.unbox_list_item(boxed, to), to.clone())
+            }
+            Rvalue::Call { function: callee, args } => {
+                let callee = self
+                    .module
+                    .get_function(callee)
+                    .ok_or_else(|| format!("Function {} not found", callee))?;
+                let args: Vec<_> = args.iter().map(|arg| self.compile_ir_operand(frame, arg).into()).collect();
+                let result = self.builder.build_call(callee, &args, "call").unwrap().try_as_basic_value().left();
+                self.emit_call_check()?;
+                // A box, or the value itself from an unboxed copy. None is its own box.
+                let result = match result {
+                    Some(result) => result,
+                    None => self.llvm_context.ptr_type(AddressSpace::default()).const_null().into(),
+                };
+                (result, ty.clone())
+            }
+            Rvalue::Len(operand) => {
+                let runtime = match function.operand_type(operand) {
+                    Type::String => "string_len",
+                    _ => "list_len",
+                };
+                let value = self.compile_ir_operand(frame, operand);
+                let length = self
+                    .call_runtime_function(runtime, &[value.into()])?
+                    .ok_or("len returned void")?;
+                (length, Type::Int)
+            }
+            Rvalue::ListNew => {
+                let list = self
+                    .call_runtime_function("list_new", &[])?
+                    .ok_or("list_new returned void")?;
+                (list, ty.clone())
+            }
+            Rvalue::ListGet { list, index } => {
+                let (list, index) = (self.compile_ir_operand(frame, list), self.compile_ir_operand(frame, index));
+                let item = self
+                    .call_runtime_function("list_get_checked", &[list.into(), index.into()])?
+                    .ok_or("list_get_checked returned void")?;
+                (item, Type::Any)
+            }
+        };
+        if value_type != *ty && value_type.numeric_rank().is_some() && ty.numeric_rank().is_some() {
+            return self.convert_type(value, &value_type, ty);
+        }
+        Ok(value)
+    }
+
+    fn compile_ir_terminator(&mut self, frame: &FunctionFrame<'_, 'ctx>, terminator: &Terminator) -> Result<(), String> {
+        match terminator {
+            Terminator::Goto(target) => {
+                self.builder.build_unconditional_branch(frame.blocks[target.0]).unwrap();
+            }
+            Terminator::Branch {
+                cond,
+                then_block,
+                else_block,
+            } => {
+                let cond = self.compile_ir_operand(frame, cond).into_int_value();
+                self.builder
+                    .build_conditional_branch(cond, frame.blocks[then_block.0], frame.blocks[else_block.0])
+                    .unwrap();
+            }
+            Terminator::Return(_) if frame.function.signature.returns == Type::None => {
+                self.emit_trace_frame_exit()?;
+                self.builder.build_return(None).unwrap();
+            }
+            Terminator::Return(value) => {
+                let value = self.compile_ir_operand(frame, value);
+                self.emit_trace_frame_exit()?;
+                self.builder.build_return(Some(&value)).unwrap();
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,461 @@
+// loop_fusion.rs - Fusion of chained comprehensions into single loops
+//
+// `[f(x) for x in [g(y) for y in data]]` builds an intermediate list only to
+// walk it once more. This pass rewrites such chains before code generation so
+// the consumer iterates the producer's source directly:
+//
+//     [f(g(y)) for y in data]
+//
+// The producer's element is substituted for the consumer's loop variable, and
+// the consumer's conditions are appended to the producer's. A chain is only
+// fused when that substitution evaluates every producer element exactly once,
+// and no name in the consumer would be captured by the producer's loop
+// variable. Fusion interleaves the two stages per item instead of running
+// them one after the other, as with any lazy pipeline.
+//
+// Chains of map() and filter() calls are fused during code generation instead,
+// where the callbacks' return types are known (see `iter_builtins.rs`).
+
+use crate::ast::{Comprehension, Expr, ExprContext, Module, Stmt};
+use std::collections::HashSet;
+
+/// Return a copy of `module` with every fusable comprehension chain fused
+pub fn fuse_module(module: &Module) -> Module {
+    let mut fused = module.clone();
+    fuse_body(&mut fused.body);
+    fused
+}
+
+fn fuse_body(body: &mut [Box<Stmt>]) {
+    for stmt in body {
+        fuse_stmt(stmt);
+    }
+}
+
+fn fuse_stmt(stmt: &mut Stmt) {
+    match stmt {
+        Stmt::FunctionDef { params, body, decorator_list, returns, .. } => {
+            for param in params {
+                if let Some(default) = &mut param.default {
+                    fuse_expr(default);
+                }
+            }
+            decorator_list.iter_mut().for_each(|e| fuse_expr(e));
+            if let Some(returns) = returns {
+                fuse_expr(returns);
+            }
+            fuse_body(body);
+        }
+        Stmt::ClassDef { bases, keywords, body, decorator_list, .. } => {
+            bases.iter_mut().for_each(|e| fuse_expr(e));
+            keywords.iter_mut().for_each(|(_, e)| fuse_expr(e));
+            decorator_list.iter_mut().for_each(|e| fuse_expr(e));
+            fuse_body(body);
+        }
+        Stmt::Return { value, .. } => {
+            if let Some(value) = value {
+                fuse_expr(value);
+            }
+        }
+        Stmt::Delete { targets, .. } => targets.iter_mut().for_each(|e| fuse_expr(e)),
+        Stmt::Assign { targets, value, .. } => {
+            targets.iter_mut().for_each(|e| fuse_expr(e));
+            fuse_expr(value);
+        }
+        Stmt::AugAssign { target, value, .. } => {
+            fuse_expr(target);
+            fuse_expr(value);
+        }
+        Stmt::AnnAssign { target, value, .. } => {
+            fuse_expr(target);
+            if let Some(value) = value {
+                fuse_expr(value);
+            }
+        }
+        Stmt::For { target, iter, body, orelse, .. } => {
+            fuse_expr(target);
+            fuse_expr(iter);
+            fuse_body(body);
+            fuse_body(orelse);
+        }
+        Stmt::While { test, body, orelse, .. } | Stmt::If { test, body, orelse, .. } => {
+            fuse_expr(test);
+            fuse_body(body);
+            fuse_body(orelse);
+        }
+        Stmt::With { items, body, .. } => {
+            for (context, target) in items {
+                fuse_expr(context);
+                if let Some(target) = target {
+                    fuse_expr(target);
+                }
+            }
+            fuse_body(body);
+        }
+        Stmt::Raise { exc, cause, .. } => {
+            exc.iter_mut().chain(cause.iter_mut()).for_each(|e| fuse_expr(e));
+        }
+        Stmt::Try { body, handlers, orelse, finalbody, .. } => {
+            fuse_body(body);
+            for handler in handlers {
+                if let Some(typ) = &mut handler.typ {
+                    fuse_expr(typ);
+                }
+                fuse_body(&mut handler.body);
+            }
+            fuse_body(orelse);
+            fuse_body(finalbody);
+        }
+        Stmt::Assert { test, msg, .. } => {
+            fuse_expr(test);
+            if let Some(msg) = msg {
+                fuse_expr(msg);
+            }
+        }
+        Stmt::Expr { value, .. } => fuse_expr(value),
+        Stmt::Match { subject, cases, .. } => {
+            fuse_expr(subject);
+            for (pattern, guard, body) in cases {
+                fuse_expr(pattern);
+                if let Some(guard) = guard {
+                    fuse_expr(guard);
+                }
+                fuse_body(body);
+            }
+        }
+        Stmt::Import { .. }
+        | Stmt::ImportFrom { .. }
+        | Stmt::Global { .. }
+        | Stmt::Nonlocal { .. }
+        | Stmt::Pass { .. }
+        | Stmt::Break { .. }
+        | Stmt::Continue { .. } => {}
+    }
+}
+
+/// Fuse the chains inside `expr` bottom-up, then `expr` itself
+fn fuse_expr(expr: &mut Expr) {
+    for child in children_mut(expr) {
+        fuse_expr(child);
+    }
+    while fuse_comprehension(expr) {}
+}
+
+/// Fuse a comprehension with the comprehension it iterates over, if that is safe
+fn fuse_comprehension(expr: &mut Expr) -> bool {
+    let (outputs, generators): (Vec<&mut Box<Expr>>, &mut Vec<Comprehension>) = match expr {
+        Expr::ListComp { elt, generators, .. }
+        | Expr::SetComp { elt, generators, .. }
+        | Expr::GeneratorExp { elt, generators, .. } => (vec![elt], generators),
+        Expr::DictComp { key, value, generators, .. } => (vec![key, value], generators),
+        _ => return false,
+    };
+    if generators.len() != 1 || generators[0].is_async {
+        return false;
+    }
+
+    let consumer = &generators[0];
+    let (producer_elt, producer) = match consumer.iter.as_ref() {
+        Expr::ListComp { elt, generators, .. } | Expr::GeneratorExp { elt, generators, .. }
+            if generators.len() == 1 && !generators[0].is_async =>
+        {
+            (elt.as_ref(), &generators[0])
+        }
+        _ => return false,
+    };
+
+    let mut bindings = Vec::new();
+    if !bind_target(&consumer.target, producer_elt, &mut bindings) {
+        return false;
+    }
+    let bound: HashSet<&str> = bindings.iter().map(|(name, _)| name.as_str()).collect();
+    if bound.len() != bindings.len() {
+        return false;
+    }
+
+    // Every expression of the consumer that mentions its loop variable
+    let uses: Vec<&Expr> = outputs
+        .iter()
+        .map(|e| e.as_ref())
+        .chain(consumer.ifs.iter().map(|e| e.as_ref()))
+        .collect();
+
+    // The producer's loop variables must not capture the consumer's other names
+    let mut producer_names = HashSet::new();
+    target_names(&producer.target, &mut producer_names);
+    let mut free = HashSet::new();
+    uses.iter().for_each(|e| names_in(e, &mut free));
+    if free
+        .iter()
+        .any(|name| !bound.contains(name.as_str()) && producer_names.contains(name))
+    {
+        return false;
+    }
+
+    for (name, value) in &bindings {
+        let mut count = 0;
+        if !uses.iter().all(|e| count_uses(e, name, &mut count)) {
+            return false;
+        }
+        if count != 1 && !is_trivial(value) {
+            return false;
+        }
+    }
+
+    let bindings: Vec<(String, Expr)> = bindings
+        .into_iter()
+        .map(|(name, value)| (name, value.clone()))
+        .collect();
+    let mut fused = producer.clone();
+    let mut conditions = generators[0].ifs.clone();
+    for condition in &mut conditions {
+        substitute(condition, &bindings);
+    }
+    fused.ifs.extend(conditions);
+
+    for output in outputs {
+        substitute(output, &bindings);
+    }
+    *generators = vec![fused];
+    true
+}
+
+/// Pair the names of a loop target with the parts of the element bound to them
+fn bind_target<'e>(target: &Expr, value: &'e Expr, bindings: &mut Vec<(String, &'e Expr)>) -> bool {
+    match (target, value) {
+        (Expr::Name { id, .. }, _) => {
+            bindings.push((id.clone(), value));
+            true
+        }
+        (
+            Expr::Tuple { elts: targets, .. } | Expr::List { elts: targets, .. },
+            Expr::Tuple { elts: values, .. } | Expr::List { elts: values, .. },
+        ) if targets.len() == values.len() => targets
+            .iter()
+            .zip(values)
+            .all(|(target, value)| bind_target(target, value, bindings)),
+        _ => false,
+    }
+}
+
+/// Whether an expression is cheap and side-effect free to evaluate any number of times
+fn is_trivial(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Name { .. } | Expr::Num { .. } | Expr::Str { .. } | Expr::NameConstant { .. } | Expr::Constant { .. }
+    )
+}
+
+/// Count the loads of `name` in `expr`. Returns false if the name appears
+/// inside a nested scope, where substituting it would change how often (or in
+/// which scope) the producer's element is evaluated.
+fn count_uses(expr: &Expr, name: &str, count: &mut usize) -> bool {
+    match expr {
+        Expr::Name { id, .. } => {
+            if id == name {
+                *count += 1;
+            }
+            true
+        }
+        Expr::ListComp { .. }
+        | Expr::SetComp { .. }
+        | Expr::DictComp { .. }
+        | Expr::GeneratorExp { .. }
+        | Expr::Lambda { .. }
+        | Expr::NamedExpr { .. } => {
+            let mut names = HashSet::new();
+            names_in(expr, &mut names);
+            !names.contains(name)
+        }
+        _ => children(expr).into_iter().all(|child| count_uses(child, name, count)),
+    }
+}
+
+/// Replace loads of the bound names with their values
+fn substitute(expr: &mut Expr, bindings: &[(String, Expr)]) {
+    if let Expr::Name { id, ctx: ExprContext::Load, .. } = expr {
+        if let Some((_, value)) = bindings.iter().find(|(name, _)| name == id) {
+            *expr = value.clone();
+        }
+        return;
+    }
+    for child in children_mut(expr) {
+        substitute(child, bindings);
+    }
+}
+
+/// Names bound by a loop target
+fn target_names(target: &Expr, names: &mut HashSet<String>) {
+    match target {
+        Expr::Name { id, .. } => {
+            names.insert(id.clone());
+        }
+        Expr::Tuple { elts, .. } | Expr::List { elts, .. } => {
+            elts.iter().for_each(|elt| target_names(elt, names));
+        }
+        Expr::Starred { value, .. } => target_names(value, names),
+        _ => {}
+    }
+}
+
+/// Every name mentioned anywhere in `expr`, including lambda parameters
+fn names_in(expr: &Expr, names: &mut HashSet<String>) {
+    match expr {
+        Expr::Name { id, .. } => {
+            names.insert(id.clone());
+        }
+        Expr::Lambda { args, .. } => {
+            names.extend(args.iter().map(|param| param.name.clone()));
+        }
+        _ => {}
+    }
+    for child in children(expr) {
+        names_in(child, names);
+    }
+}
+
+fn comprehension_children(generators: &[Comprehension]) -> impl Iterator<Item = &Expr> {
+    generators.iter().flat_map(|generator| {
+        [generator.target.as_ref(), generator.iter.as_ref()]
+            .into_iter()
+            .chain(generator.ifs.iter().map(|e| e.as_ref()))
+    })
+}
+
+/// The direct subexpressions of an expression
+pub(crate) fn children(expr: &Expr) -> Vec<&Expr> {
+    let mut out: Vec<&Expr> = Vec::new();
+    match expr {
+        Expr::BoolOp { values, .. } | Expr::JoinedStr { values, .. } => {
+            out.extend(values.iter().map(|e| e.as_ref()))
+        }
+        Expr::BinOp { left, right, .. } => out.extend([left.as_ref(), right.as_ref()]),
+        Expr::Slice { lower, upper, step, .. } => {
+            out.extend([lower, upper, step].into_iter().flatten().map(|e| e.as_ref()))
+        }
+        Expr::UnaryOp { operand: value, .. }
+        | Expr::Await { value, .. }
+        | Expr::YieldFrom { value, .. }
+        | Expr::Starred { value, .. }
+        | Expr::Attribute { value, .. } => out.push(value),
+        Expr::Lambda { args, body, .. } => {
+            out.extend(args.iter().filter_map(|param| param.default.as_deref()));
+            out.push(body);
+        }
+        Expr::IfExp { test, body, orelse, .. } => out.extend([test.as_ref(), body.as_ref(), orelse.as_ref()]),
+        Expr::Dict { keys, values, .. } => {
+            out.extend(keys.iter().flatten().map(|e| e.as_ref()));
+            out.extend(values.iter().map(|e| e.as_ref()));
+        }
+        Expr::Set { elts, .. } | Expr::List { elts, .. } | Expr::Tuple { elts, .. } => {
+            out.extend(elts.iter().map(|e| e.as_ref()))
+        }
+        Expr::ListComp { elt, generators, .. }
+        | Expr::SetComp { elt, generators, .. }
+        | Expr::GeneratorExp { elt, generators, .. } => {
+            out.push(elt);
+            out.extend(comprehension_children(generators));
+        }
+        Expr::DictComp { key, value, generators, .. } => {
+            out.extend([key.as_ref(), value.as_ref()]);
+            out.extend(comprehension_children(generators));
+        }
+        Expr::Yield { value, .. } => out.extend(value.as_deref()),
+        Expr::Compare { left, comparators, .. } => {
+            out.push(left);
+            out.extend(comparators.iter().map(|e| e.as_ref()));
+        }
+        Expr::Call { func, args, keywords, .. } => {
+            out.push(func);
+            out.extend(args.iter().map(|e| e.as_ref()));
+            out.extend(keywords.iter().map(|(_, e)| e.as_ref()));
+        }
+        Expr::FormattedValue { value, format_spec, .. } => {
+            out.push(value);
+            out.extend(format_spec.as_deref());
+        }
+        Expr::Subscript { value, slice, .. } => out.extend([value.as_ref(), slice.as_ref()]),
+        Expr::NamedExpr { target, value, .. } => out.extend([target.as_ref(), value.as_ref()]),
+        Expr::Num { .. }
+        | Expr::Str { .. }
+        | Expr::Bytes { .. }
+        | Expr::NameConstant { .. }
+        | Expr::Ellipsis { .. }
+        | Expr::Constant { .. }
+        | Expr::Name { .. } => {}
+    }
+    out
+}
+
+fn comprehension_children_mut(generators: &mut [Comprehension]) -> impl Iterator<Item = &mut Expr> {
+    generators.iter_mut().flat_map(|generator| {
+        [generator.target.as_mut(), generator.iter.as_mut()]
+            .into_iter()
+            .chain(generator.ifs.iter_mut().map(|e| e.as_mut()))
+    })
+}
+
+/// The direct subexpressions of an expression, mutably
+fn children_mut(expr: &mut Expr) -> Vec<&mut Expr> {
+    let mut out: Vec<&mut Expr> = Vec::new();
+    match expr {
+        Expr::BoolOp { values, .. } | Expr::JoinedStr { values, .. } => {
+            out.extend(values.iter_mut().map(|e| e.as_mut()))
+        }
+        Expr::BinOp { left, right, .. } => out.extend([left.as_mut(), right.as_mut()]),
+        Expr::Slice { lower, upper, step, .. } => {
+            out.extend([lower, upper, step].into_iter().flatten().map(|e| e.as_mut()))
+        }
+        Expr::UnaryOp { operand: value, .. }
+        | Expr::Await { value, .. }
+        | Expr::YieldFrom { value, .. }
+        | Expr::Starred { value, .. }
+        | Expr::Attribute { value, .. } => out.push(value),
+        Expr::Lambda { args, body, .. } => {
+            out.extend(args.iter_mut().filter_map(|param| param.default.as_deref_mut()));
+            out.push(body);
+        }
+        Expr::IfExp { test, body, orelse, .. } => out.extend([test.as_mut(), body.as_mut(), orelse.as_mut()]),
+        Expr::Dict { keys, values, .. } => {
+            out.extend(keys.iter_mut().flatten().map(|e| e.as_mut()));
+            out.extend(values.iter_mut().map(|e| e.as_mut()));
+        }
+        Expr::Set { elts, .. } | Expr::List { elts, .. } | Expr::Tuple { elts, .. } => {
+            out.extend(elts.iter_mut().map(|e| e.as_mut()))
+        }
+        Expr::ListComp { elt, generators, .. }
+        | Expr::SetComp { elt, generators, .. }
+        | Expr::GeneratorExp { elt, generators, .. } => {
+            out.push(elt);
+            out.extend(comprehension_children_mut(generators));
+        }
+        Expr::DictComp { key, value, generators, .. } => {
+            out.extend([key.as_mut(), value.as_mut()]);
+            out.extend(comprehension_children_mut(generators));
+        }
+        Expr::Yield { value, .. } => out.extend(value.as_deref_mut()),
+        Expr::Compare { left, comparators, .. } => {
+            out.push(left);
+            out.extend(comparators.iter_mut().map(|e| e.as_mut()));
+        }
+        Expr::Call { func, args, keywords, .. } => {
+            out.push(func);
+            out.extend(args.iter_mut().map(|e| e.as_mut()));
+            out.extend(keywords.iter_mut().map(|(_, e)| e.as_mut()));
+        }
+        Expr::FormattedValue { value, format_spec, .. } => {
+            out.push(value);
+            out.extend(format_spec.as_deref_mut());
+        }
+        Expr::Subscript { value, slice, .. } => out.extend([value.as_mut(), slice.as_mut()]),
+        Expr::NamedExpr { target, value, .. } => out.extend([target.as_mut(), value.as_mut()]),
+        Expr::Num { .. }
+        | Expr::Str { .. }
+        | Expr::Bytes { .. }
+        | Expr::NameConstant { .. }
+        | Expr::Ellipsis { .. }
+        | Expr::Constant { .. }
+        | Expr::Name { .. } => {}
+    }
+    out
+}
@@ -6,7 +6,10 @@ use inkwell::{
     values::{BasicValueEnum, FunctionValue, IntValue},
     IntPredicate,
 };
+use crate::ast::{self, Expr, Number, Operator, Stmt, UnaryOperator};
 use crate::compiler::runtime::memory_profiler;
+use crate::typechecker::lints::children;
+use std::collections::HashMap;
 
 // === Loop Flattening ===
 
@@ -839,4 +842,282 @@ impl<'ctx> ParallelLoopOptimizer<'ctx> {
 
         entry
     }
-}
\ No newline at end of file
+}
+// === Constant Range Folding ===
+
+/// A `range()` whose bounds are known at compile time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConstantRange {
+    pub start: i64,
+    pub stop: i64,
+    pub step: i64,
+}
+
+impl ConstantRange {
+    /// Fold the arguments of a `range()` call, resolving names with `lookup`.
+    /// Returns None unless every argument is constant and the step is nonzero.
+    pub fn fold(args: &[Box<Expr>], lookup: &dyn Fn(&str) -> Option<i64>) -> Option<Self> {
+        let mut values = Vec::with_capacity(args.len());
+        for arg in args {
+            values.push(fold_int_expr(arg, lookup)?);
+        }
+        let (start, stop, step) = match values[..] {
+            [stop] => (0, stop, 1),
+            [start, stop] => (start, stop, 1),
+            [start, stop, step] if step != 0 => (start, stop, step),
+            _ => return None,
+        };
+        Some(Self { start, stop, step })
+    }
+
+    /// Number of iterations of the loop
+    pub fn trip_count(&self) -> u64 {
+        let (start, stop, step) = (self.start as i128, self.stop as i128, self.step as i128);
+        let span = if step > 0 { stop - start } else { start - stop };
+        if span <= 0 {
+            return 0;
+        }
+        let step = step.abs();
+        ((span + step - 1) / step) as u64
+    }
+
+    /// Value of the loop variable on the last iteration, if there is one
+    pub fn last(&self) -> Option<i64> {
+        match self.trip_count() {
+            0 => None,
+            n => Some((self.start as i128 + (n as i128 - 1) * self.step as i128) as i64),
+        }
+    }
+}
+
+/// Evaluate an integer expression made of literals, names resolved by
+/// `lookup` and arithmetic that cannot overflow. Division and modulo only fold
+/// for non-negative operands, where truncating and floor semantics agree.
+pub fn fold_int_expr(expr: &Expr, lookup: &dyn Fn(&str) -> Option<i64>) -> Option<i64> {
+    match expr {
+        Expr::Num { value: Number::Integer(value), .. } => Some(*value),
+        Expr::Name { id, .. } => lookup(id),
+        Expr::UnaryOp { op, operand, .. } => {
+            let value = fold_int_expr(operand, lookup)?;
+            match op {
+                UnaryOperator::USub => value.checked_neg(),
+                UnaryOperator::UAdd => Some(value),
+                UnaryOperator::Invert => Some(!value),
+                UnaryOperator::Not => None,
+            }
+        }
+        Expr::BinOp { left, op, right, .. } => {
+            let left = fold_int_expr(left, lookup)?;
+            let right = fold_int_expr(right, lookup)?;
+            match op {
+                Operator::Add => left.checked_add(right),
+                Operator::Sub => left.checked_sub(right),
+                Operator::Mult => left.checked_mul(right),
+                Operator::FloorDiv if left >= 0 && right > 0 => Some(left / right),
+                Operator::Mod if left >= 0 && right > 0 => Some(left % right),
+                Operator::BitAnd => Some(left & right),
+                Operator::BitOr => Some(left | right),
+                Operator::BitXor => Some(left ^ right),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Module-level names that always hold the same integer: assigned once, at
+/// the top level, to a constant expression, and bound nowhere else in the
+/// module (no reassignment, `global` declaration, parameter or local of that
+/// name).
+pub fn module_int_constants(module: &ast::Module) -> HashMap<String, i64> {
+    let mut bindings: HashMap<String, usize> = HashMap::new();
+    for stmt in &module.body {
+        count_stmt_bindings(stmt, &mut bindings);
+    }
+
+    let mut constants = HashMap::new();
+    for stmt in &module.body {
+        if let Stmt::Assign { targets, value, .. } = stmt.as_ref() {
+            if let [target] = &targets[..] {
+                if let Expr::Name { id, .. } = target.as_ref() {
+                    if bindings.get(id) == Some(&1) {
+                        if let Some(value) = fold_int_expr(value, &|name| constants.get(name).copied()) {
+                            constants.insert(id.clone(), value);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    constants
+}
+
+/// Count every place a name is bound in `stmt`, including nested scopes
+fn count_stmt_bindings(stmt: &Stmt, bindings: &mut HashMap<String, usize>) {
+    let bind = |name: &str, bindings: &mut HashMap<String, usize>| {
+        *bindings.entry(name.to_string()).or_insert(0) += 1;
+    };
+    let body_bindings = |body: &[Box<Stmt>], bindings: &mut HashMap<String, usize>| {
+        for stmt in body {
+            count_stmt_bindings(stmt, bindings);
+        }
+    };
+
+    match stmt {
+        Stmt::FunctionDef { name, params, body, decorator_list, returns, .. } => {
+            bind(name, bindings);
+            for param in params {
+                bind(&param.name, bindings);
+                for expr in param.typ.iter().chain(&param.default) {
+                    count_expr_bindings(expr, bindings);
+                }
+            }
+            for expr in decorator_list.iter().chain(returns) {
+                count_expr_bindings(expr, bindings);
+            }
+            body_bindings(body, bindings);
+        }
+        Stmt::ClassDef { name, bases, keywords, body, decorator_list, .. } => {
+            bind(name, bindings);
+            for expr in bases.iter().chain(decorator_list).chain(keywords.iter().map(|(_, e)| e)) {
+                count_expr_bindings(expr, bindings);
+            }
+            body_bindings(body, bindings);
+        }
+        Stmt::Assign { targets, value, .. } => {
+            for target in targets {
+                count_target_bindings(target, bindings);
+            }
+            count_expr_bindings(value, bindings);
+        }
+        Stmt::AugAssign { target, value, .. } => {
+            count_target_bindings(target, bindings);
+            count_expr_bindings(value, bindings);
+        }
+        Stmt::AnnAssign { target, value, .. } => {
+            count_target_bindings(target, bindings);
+            if let Some(value) = value {
+                count_expr_bindings(value, bindings);
+            }
+        }
+        Stmt::Delete { targets, .. } => {
+            for target in targets {
+                count_target_bindings(target, bindings);
+            }
+        }
+        Stmt::For { target, iter, body, orelse, .. } => {
+            count_target_bindings(target, bindings);
+            count_expr_bindings(iter, bindings);
+            body_bindings(body, bindings);
+            body_bindings(orelse, bindings);
+        }
+        Stmt::While { test, body, orelse, .. } | Stmt::If { test, body, orelse, .. } => {
+            count_expr_bindings(test, bindings);
+            body_bindings(body, bindings);
+            body_bindings(orelse, bindings);
+        }
+        Stmt::With { items, body, .. } => {
+            for (context, alias) in items {
+                count_expr_bindings(context, bindings);
+                if let Some(alias) = alias {
+                    count_target_bindings(alias, bindings);
+                }
+            }
+            body_bindings(body, bindings);
+        }
+        Stmt::Try { body, handlers, orelse, finalbody, .. } => {
+            body_bindings(body, bindings);
+            for handler in handlers {
+                if let Some(name) = &handler.name {
+                    bind(name, bindings);
+                }
+                body_bindings(&handler.body, bindings);
+            }
+            body_bindings(orelse, bindings);
+            body_bindings(finalbody, bindings);
+        }
+        Stmt::Import { names, .. } | Stmt::ImportFrom { names, .. } => {
+            for alias in names {
+                bind(alias.asname.as_ref().unwrap_or(&alias.name), bindings);
+            }
+        }
+        Stmt::Global { names, .. } | Stmt::Nonlocal { names, .. } => {
+            for name in names {
+                bind(name, bindings);
+            }
+        }
+        Stmt::Match { subject, cases, .. } => {
+            count_expr_bindings(subject, bindings);
+            for (pattern, guard, body) in cases {
+                count_target_bindings(pattern, bindings);
+                if let Some(guard) = guard {
+                    count_expr_bindings(guard, bindings);
+                }
+                body_bindings(body, bindings);
+            }
+        }
+        Stmt::Return { value, .. } => {
+            if let Some(value) = value {
+                count_expr_bindings(value, bindings);
+            }
+        }
+        Stmt::Raise { exc, cause, .. } => {
+            for expr in exc.iter().chain(cause) {
+                count_expr_bindings(expr, bindings);
+            }
+        }
+        Stmt::Assert { test, msg, .. } => {
+            count_expr_bindings(test, bindings);
+            if let Some(msg) = msg {
+                count_expr_bindings(msg, bindings);
+            }
+        }
+        Stmt::Expr { value, .. } => count_expr_bindings(value, bindings),
+        Stmt::Pass { .. } | Stmt::Break { .. } | Stmt::Continue { .. } => {}
+    }
+}
+
+/// Count every name in an assignment target (or match pattern) as bound
+fn count_target_bindings(target: &Expr, bindings: &mut HashMap<String, usize>) {
+    if let Expr::Name { id, .. } = target {
+        *bindings.entry(id.clone()).or_insert(0) += 1;
+    }
+    for child in children(target) {
+        count_target_bindings(child, bindings);
+    }
+}
+
+/// Count the names bound inside an expression: walrus targets, comprehension
+/// variables and lambda parameters
+fn count_expr_bindings(expr: &Expr, bindings: &mut HashMap<String, usize>) {
+    match expr {
+        Expr::NamedExpr { target, value, .. } => {
+            count_target_bindings(target, bindings);
+            count_expr_bindings(value, bindings);
+        }
+        Expr::ListComp { generators, .. }
+        | Expr::SetComp { generators, .. }
+        | Expr::GeneratorExp { generators, .. }
+        | Expr::DictComp { generators, .. } => {
+            for comp in generators {
+                count_target_bindings(&comp.target, bindings);
+            }
+            for child in children(expr) {
+                count_expr_bindings(child, bindings);
+            }
+        }
+        Expr::Lambda { args, .. } => {
+            for param in args {
+                *bindings.entry(param.name.clone()).or_insert(0) += 1;
+            }
+            for child in children(expr) {
+                count_expr_bindings(child, bindings);
+            }
+        }
+        _ => {
+            for child in children(expr) {
+                count_expr_bindings(child, bindings);
+            }
+        }
+    }
+}
@@ -1,85 +1,217 @@
+// mod.rs - Cheetah compiler
+//
+// Code generation needs LLVM and is only built with the `codegen` feature.
+// The type definitions the type checker shares with code generation
+// (`types`, `ffi`) and the runtime's formatting and output settings are
+// always available.
+
+#[cfg(feature = "codegen")]
 use crate::ast;
+#[cfg(feature = "codegen")]
 use crate::typechecker;
+#[cfg(feature = "codegen")]
+pub mod abstract_class;
+#[cfg(feature = "codegen")]
+pub mod attribute_cache;
+#[cfg(feature = "codegen")]
 pub mod builtins;
+#[cfg(feature = "codegen")]
+pub mod cdylib;
+#[cfg(feature = "codegen")]
+pub mod cfg_dump;
+pub mod class_layout;
+#[cfg(feature = "codegen")]
+pub mod class_object;
+#[cfg(feature = "codegen")]
 pub mod closure;
+#[cfg(feature = "codegen")]
+pub mod constant_pool;
+#[cfg(feature = "codegen")]
 pub mod context;
+#[cfg(feature = "codegen")]
+pub mod debug_info;
+#[cfg(feature = "codegen")]
+pub mod emit;
+#[cfg(feature = "codegen")]
 pub mod exception;
+#[cfg(feature = "codegen")]
 pub mod expr;
+#[cfg(feature = "codegen")]
 pub mod expr_non_recursive;
+pub mod ffi;
+#[cfg(feature = "codegen")]
+pub mod ir_codegen;
+#[cfg(feature = "codegen")]
+pub mod loop_fusion;
+#[cfg(feature = "codegen")]
 pub mod loop_transformers;
+#[cfg(feature = "codegen")]
+pub mod optimizer;
+pub mod plugins;
+#[cfg(feature = "codegen")]
+pub mod prealloc;
+#[cfg(feature = "codegen")]
+pub mod repl;
 pub mod runtime;
+#[cfg(feature = "codegen")]
+pub mod sanitizer;
+#[cfg(feature = "codegen")]
 pub mod scope;
+pub mod source_map;
+#[cfg(feature = "codegen")]
 pub mod stmt;
+#[cfg(feature = "codegen")]
 pub mod stmt_non_recursive;
+#[cfg(feature = "codegen")]
+pub mod string_builder;
+#[cfg(feature = "codegen")]
+pub mod super_call;
+#[cfg(feature = "codegen")]
+pub mod target;
+#[cfg(feature = "codegen")]
 pub mod tail_call_optimizer;
+#[cfg(feature = "codegen")]
+pub mod traceback;
 pub mod types;
+#[cfg(feature = "codegen")]
+pub mod wasm;
 
+#[cfg(feature = "codegen")]
+use class_layout::AttributeStorage;
+#[cfg(feature = "codegen")]
 use crate::compiler::context::CompilationContext;
+#[cfg(feature = "codegen")]
+use crate::intern::Symbol;
+#[cfg(feature = "codegen")]
+use inkwell::context::Context;
+#[cfg(feature = "codegen")]
 use inkwell::passes::PassManager;
-use inkwell::{context::Context, targets::TargetMachine};
+#[cfg(feature = "codegen")]
+use inkwell::types::BasicType;
+#[cfg(feature = "codegen")]
 use std::collections::HashMap;
+#[cfg(feature = "codegen")]
 use std::path::Path;
+#[cfg(feature = "codegen")]
 use stmt::StmtCompiler;
+#[cfg(feature = "codegen")]
 use types::Type;
 
 // No need to import builtins modules directly as they're already available through the module system
 
 /// Compiler for Cheetah language
+#[cfg(feature = "codegen")]
 pub struct Compiler<'ctx> {
     pub context: CompilationContext<'ctx>,
     pub optimize: bool,
+    /// Optimization level (0-3) for the optimization pipeline and code generation
+    pub opt_level: u8,
+    /// Machine that AOT builds generate code for
+    pub target: target::TargetSpec,
+    /// Sanitizers AOT builds are instrumented with
+    pub sanitizers: Vec<sanitizer::Sanitizer>,
+    /// Whether AOT builds link an executable or a shared library
+    pub crate_type: cdylib::CrateType,
+    /// Names of the functions defined at the top level of the compiled module
+    pub top_level_functions: Vec<String>,
+    /// Extra libraries (`-l`) AOT builds link against
+    pub link_libs: Vec<String>,
+    /// Extra library search directories (`-L`) for AOT builds
+    pub link_search_paths: Vec<String>,
+    /// Line endings the compiled program writes to stdout
+    pub newline: runtime::output::Newline,
 }
 
+#[cfg(feature = "codegen")]
 impl<'ctx> Compiler<'ctx> {
     /// Create a new compiler with the given module name
     pub fn new(context: &'ctx Context, module_name: &str) -> Self {
         Self {
             context: CompilationContext::new(context, module_name),
             optimize: true,
+            opt_level: 0,
+            target: target::TargetSpec::host(),
+            sanitizers: Vec::new(),
+            crate_type: cdylib::CrateType::Bin,
+            top_level_functions: Vec::new(),
+            link_libs: Vec::new(),
+            link_search_paths: Vec::new(),
+            newline: runtime::output::Newline::default(),
         }
     }
 
+    /// Generate code for `target`, setting the module's triple and data layout to match
+    pub fn set_target(&mut self, target: target::TargetSpec) -> Result<(), String> {
+        let machine = target.create_target_machine(inkwell::OptimizationLevel::Default)?;
+        self.context.module.set_triple(&machine.get_triple());
+        self.context
+            .module
+            .set_data_layout(&machine.get_target_data().get_data_layout());
+        self.target = target;
+        Ok(())
+    }
+
+    /// Target machine for code generation, with the module set up and instrumented for it
+    pub fn prepare_codegen(&mut self) -> Result<inkwell::targets::TargetMachine, String> {
+        // Shared libraries need position-independent code
+        let reloc = match self.crate_type {
+            cdylib::CrateType::Bin => inkwell::targets::RelocMode::Default,
+            cdylib::CrateType::Cdylib => inkwell::targets::RelocMode::PIC,
+        };
+        let tm = self
+            .target
+            .create_target_machine_with_reloc(optimizer::codegen_level(self.opt_level), reloc)?;
+
+        let module = &mut self.context.module;
+        module.set_triple(&tm.get_triple());
+        module.set_data_layout(&tm.get_target_data().get_data_layout());
+        self.apply_sanitizers(&tm)?;
+        Ok(tm)
+    }
+
     pub fn emit_to_aot(&mut self, filename: &str) -> Result<(), String> {
-        use inkwell::targets::{CodeModel, FileType, InitializationConfig, RelocMode, Target};
+        use inkwell::targets::FileType;
         use std::path::Path;
         use std::process::Command;
 
-        Target::initialize_all(&InitializationConfig::default());
-
-        let triple = TargetMachine::get_default_triple();
-        let target =
-            Target::from_triple(&triple).map_err(|e| format!("No target for {}: {}", triple, e))?;
+        let shared = self.crate_type == cdylib::CrateType::Cdylib;
+        let exports = if shared {
+            let prefix = cdylib::symbol_prefix(filename);
+            let (exports, skipped) = self.export_c_symbols(&prefix);
+            for name in skipped {
+                println!(
+                    "⚠️  {}() has no C signature and is not exported from the library",
+                    name
+                );
+            }
+            Some((prefix, exports))
+        } else {
+            None
+        };
 
-        let tm = target
-            .create_target_machine(
-                &triple,
-                &TargetMachine::get_host_cpu_name().to_string(),
-                &TargetMachine::get_host_cpu_features().to_string(),
-                inkwell::OptimizationLevel::Aggressive,
-                RelocMode::Default,
-                CodeModel::Default,
-            )
-            .ok_or("Failed to create TargetMachine")?;
-
-        let module = &mut self.context.module;
-        module.set_triple(&triple);
+        let tm = self.prepare_codegen()?;
 
+        let module = &self.context.module;
         let obj_path = format!("{}.o", filename);
         tm.write_to_file(module, FileType::Object, Path::new(&obj_path))
             .map_err(|e| format!("Failed to write object file: {:?}", e))?;
 
-        let runtime_lib_dir = match std::env::var("CARGO_MANIFEST_DIR") {
-            Ok(manifest) => format!("{}/target/release", manifest),
-            Err(_) => {
-                let mut exe = std::env::current_exe()
-                    .map_err(|e| format!("Failed to locate current exe: {}", e))?;
-                exe.pop();
-                exe.pop();
-                exe.push("lib");
-                exe.push("cheetah");
-                exe.to_string_lossy().into_owned()
-            }
-        };
+        // The host linker and runtime library can't link for another target
+        if self.target.is_cross() {
+            println!(
+                "✅ Cross-compiled object → ./{} (link with a {} toolchain and runtime)",
+                obj_path,
+                tm.get_triple()
+            );
+            return Ok(());
+        }
+
+        // Sanitized builds link a runtime built with the same sanitizer
+        let runtime_target_dir = self
+            .sanitizers
+            .first()
+            .map_or("", |sanitizer| sanitizer.runtime_target_dir());
+        let runtime_lib_dir = runtime_library_dir(runtime_target_dir)?;
 
         let llvm_config = std::env::var("LLVM_CONFIG").unwrap_or_else(|_| "llvm-config".into());
         let llvm_output = Command::new(&llvm_config)
@@ -112,7 +244,26 @@ impl<'ctx> Compiler<'ctx> {
             .arg("-lffi")
             .arg("-ltinfo");
 
-        cmd.arg("-o").arg(filename);
+        for sanitizer in &self.sanitizers {
+            cmd.arg(format!("-fsanitize={}", sanitizer.name()));
+        }
+
+        // Libraries providing @extern functions
+        for dir in &self.link_search_paths {
+            cmd.arg(format!("-L{}", dir));
+        }
+        for lib in &self.link_libs {
+            cmd.arg(format!("-l{}", lib));
+        }
+
+        let output = match &exports {
+            Some(_) => {
+                cmd.arg(if cfg!(target_os = "macos") { "-dynamiclib" } else { "-shared" });
+                cdylib::library_file_name(filename)
+            }
+            None => filename.to_string(),
+        };
+        cmd.arg("-o").arg(&output);
 
         let status = cmd
             .status()
@@ -121,21 +272,135 @@ impl<'ctx> Compiler<'ctx> {
             return Err(format!("Linker exited with: {}", status));
         }
 
+        if let Some((prefix, exports)) = exports {
+            let header_path = format!("{}.h", filename);
+            std::fs::write(&header_path, cdylib::c_header(&prefix, &exports))
+                .map_err(|e| format!("Failed to write {}: {}", header_path, e))?;
+            println!("✅ Shared library → ./{} (header ./{})", output, header_path);
+            return Ok(());
+        }
+
         println!("✅ AOT build → ./{}", filename);
         Ok(())
     }
 
-    /// Compile an AST module to LLVM IR
+    /// Emit DWARF debug info mapping the compiled code back to `source_path`
+    pub fn enable_debug_info(&mut self, source_path: &str) {
+        self.context.enable_debug_info(source_path);
+    }
+
+    /// Compile an AST module to LLVM IR, after running the registered
+    /// [`transform`](crate::transform) passes over it.
+    ///
+    /// A module the typed IR (see `ir`) covers is compiled through it when
+    /// it has functions over ints, floats or bools, so those run unboxed.
+    /// Other modules are compiled from the AST, which preallocates
+    /// lists of known length and fuses comprehensions, as are shared
+    /// libraries, whose exports keep the signatures the AST gives them.
     pub fn compile_module(&mut self, module: &ast::Module) -> Result<(), String> {
-        if let Err(type_error) = typechecker::check_module(module) {
+        let module = crate::transform::apply(module)?;
+        let mut checker = typechecker::TypeChecker::new();
+        if let Err(type_error) = checker.check_module(&module) {
             return Err(format!("Type error: {}", type_error));
         }
 
-        if self.optimize {
+        if self.crate_type == cdylib::CrateType::Bin {
+            if let Ok(mut program) = crate::ir::lower_module(&module) {
+                crate::ir::specialize(&mut program);
+                let unboxed = program
+                    .functions
+                    .iter()
+                    .any(|function| function.name.ends_with(crate::ir::specialize::UNBOXED_SUFFIX));
+                if unboxed {
+                    return self.compile_ir(&program);
+                }
+            }
+        }
+
+        let functions: Vec<&String> = module
+            .body
+            .iter()
+            .filter_map(|stmt| match stmt.as_ref() {
+                ast::Stmt::FunctionDef { name, .. } => Some(name),
+                _ => None,
+            })
+            .collect();
+        self.record_return_types(&checker, functions);
+
+        self.compile_checked_module(&module)
+    }
+
+    /// Compile an AST module by lowering it to the typed IR (see `ir`),
+    /// giving functions over ints, floats and bools unboxed copies and
+    /// generating code from that. Unlike `compile_module`, anything the IR
+    /// doesn't support is an error rather than compiled from the AST.
+    pub fn compile_module_ir(&mut self, module: &ast::Module) -> Result<(), String> {
+        let module = crate::transform::apply(module)?;
+        let mut program = crate::ir::lower_module(&module)?;
+        crate::ir::specialize(&mut program);
+        self.compile_ir(&program)
+    }
+
+    /// Generate code for a program lowered to the IR
+    pub fn compile_ir(&mut self, program: &crate::ir::Program) -> Result<(), String> {
+        let fn_type = Type::get_void_type(self.context.llvm_context).fn_type(&[], false);
+        let function = self.context.module.add_function("main", fn_type, None);
+        let entry = self.context.llvm_context.append_basic_block(function, "entry");
+        self.context.builder.position_at_end(entry);
+
+        self.embed_runtime_functions();
+        self.start_runtime()?;
+        self.context.compile_ir_program(program)?;
+        self.top_level_functions = program
+            .functions
+            .iter()
+            .map(|function| &function.name)
+            .filter(|name| *name != crate::ir::MAIN && !name.ends_with(crate::ir::specialize::UNBOXED_SUFFIX))
+            .cloned()
+            .collect();
+        self.context.finalize_debug_info();
+
+        if let Err(err) = self.context.module.verify() {
+            return Err(format!("Module verification failed: {}", err));
+        }
+        Ok(())
+    }
+
+    /// Declare and call the functions named in `names` with the return types
+    /// `checker` found for them, where those have a fixed representation;
+    /// the rest keep the types guessed from their names
+    pub fn record_return_types<'a>(
+        &mut self,
+        checker: &typechecker::TypeChecker,
+        names: impl IntoIterator<Item = &'a String>,
+    ) {
+        for name in names {
+            match checker.return_type(name) {
+                Some(ty @ (Type::Int | Type::Float | Type::Bool | Type::String | Type::List(_) | Type::Dict(..))) => {
+                    self.context.return_types.insert(name.clone(), ty);
+                }
+                _ => {
+                    self.context.return_types.remove(name);
+                }
+            }
+        }
+    }
+
+    /// Compile an AST module the caller has already type checked, such as a
+    /// REPL cell checked against the definitions of earlier cells
+    pub fn compile_checked_module(&mut self, module: &ast::Module) -> Result<(), String> {
+        // Fuse chained comprehensions once the source has been checked as written
+        let fused;
+        let module = if self.optimize {
             let pass_manager = PassManager::create(());
 
             pass_manager.run_on(&self.context.module);
-        }
+
+            fused = loop_fusion::fuse_module(module);
+            &fused
+        } else {
+            module
+        };
 
         let void_type = Type::get_void_type(self.context.llvm_context);
         let fn_type = void_type.fn_type(&[], false);
@@ -179,14 +444,41 @@ impl<'ctx> Compiler<'ctx> {
 
         self.embed_runtime_functions();
 
+        self.context.range_constants = loop_transformers::module_int_constants(module);
+
+        self.context.declare_plugin_builtins()?;
+
         let mut function_defs = Vec::new();
 
         for stmt in &module.body {
             match stmt.as_ref() {
-                ast::Stmt::FunctionDef { name, params, .. } => {
+                ast::Stmt::FunctionDef {
+                    name,
+                    params,
+                    body,
+                    decorator_list,
+                    returns,
+                    ..
+                } => {
+                    if let Some(signature) =
+                        ffi::ExternSignature::from_def(name, params, returns, decorator_list, body)
+                            .map_err(|e| format!("Invalid extern declaration of '{}': {}", name, e))?
+                    {
+                        self.context.declare_extern(name, signature)?;
+                        continue;
+                    }
+                    // A definition shadows a plugin builtin of the same name
+                    self.context.extern_functions.remove(name);
                     self.declare_function(name, params)?;
+                    self.top_level_functions.push(name.clone());
                     function_defs.push(stmt);
                 }
+                ast::Stmt::ClassDef {
+                    name, bases, body, ..
+                } => {
+                    self.context.register_class_bases(name, bases);
+                    self.context.declare_class_attributes(name, body);
+                }
                 _ => {}
             }
         }
@@ -205,10 +497,8 @@ impl<'ctx> Compiler<'ctx> {
         for stmt in &module.body {
             match stmt.as_ref() {
                 ast::Stmt::FunctionDef { .. } => {}
-                ast::Stmt::ClassDef {
-                    name, bases, body, ..
-                } => {
-                    self.compile_class(name, bases, body)?;
+                ast::Stmt::ClassDef { .. } => {
+                    self.compile_class(stmt, &module.body)?;
                 }
                 _ => {
                     self.context.compile_stmt(stmt.as_ref())?;
@@ -231,15 +521,54 @@ impl<'ctx> Compiler<'ctx> {
     /// Compile the body of an AST module
     fn compile_module_body(&mut self, module: &ast::Module) -> Result<(), String> {
         self.embed_runtime_functions();
+        self.start_runtime()?;
+
+        let main_function = self
+            .context
+            .builder
+            .get_insert_block()
+            .and_then(|block| block.get_parent());
+        let saved_debug_scope = match main_function {
+            Some(function) => self.context.enter_debug_scope(function, "<module>", 1),
+            None => None,
+        };
+        let saved_trace_frame = self.context.enter_trace_frame("<module>")?;
+
+        self.context.range_constants = loop_transformers::module_int_constants(module);
+
+        self.context.declare_plugin_builtins()?;
 
         let mut function_defs = Vec::new();
 
         for stmt in &module.body {
             match stmt.as_ref() {
-                ast::Stmt::FunctionDef { name, params, .. } => {
+                ast::Stmt::FunctionDef {
+                    name,
+                    params,
+                    body,
+                    decorator_list,
+                    returns,
+                    ..
+                } => {
+                    if let Some(signature) =
+                        ffi::ExternSignature::from_def(name, params, returns, decorator_list, body)
+                            .map_err(|e| format!("Invalid extern declaration of '{}': {}", name, e))?
+                    {
+                        self.context.declare_extern(name, signature)?;
+                        continue;
+                    }
+                    // A definition shadows a plugin builtin of the same name
+                    self.context.extern_functions.remove(name);
                     self.declare_function(name, params)?;
+                    self.top_level_functions.push(name.clone());
                     function_defs.push(stmt);
                 }
+                ast::Stmt::ClassDef {
+                    name, bases, body, ..
+                } => {
+                    self.context.register_class_bases(name, bases);
+                    self.context.declare_class_attributes(name, body);
+                }
                 _ => {}
             }
         }
@@ -258,10 +587,8 @@ impl<'ctx> Compiler<'ctx> {
         for stmt in &module.body {
             match stmt.as_ref() {
                 ast::Stmt::FunctionDef { .. } => {}
-                ast::Stmt::ClassDef {
-                    name, bases, body, ..
-                } => {
-                    self.compile_class(name, bases, body)?;
+                ast::Stmt::ClassDef { .. } => {
+                    self.compile_class(stmt, &module.body)?;
                 }
                 _ => {
                     self.context.compile_stmt(stmt.as_ref())?;
@@ -271,8 +598,12 @@ impl<'ctx> Compiler<'ctx> {
 
         let current_block = self.context.builder.get_insert_block().unwrap();
         if current_block.get_terminator().is_none() {
+            self.context.emit_trace_frame_exit()?;
             self.context.builder.build_return(None).unwrap();
         }
+        self.context.leave_trace_frame(saved_trace_frame);
+        self.context.leave_debug_scope(saved_debug_scope);
+        self.context.finalize_debug_info();
 
         if let Err(err) = self.context.module.verify() {
             return Err(format!("Module verification failed: {}", err));
@@ -281,6 +612,27 @@ impl<'ctx> Compiler<'ctx> {
         Ok(())
     }
 
+    /// Check the runtime's ABI and configure its output at the start of `main`
+    fn start_runtime(&mut self) -> Result<(), String> {
+        // Stop at startup if an AOT binary is linked against an incompatible runtime
+        let abi_version = self
+            .context
+            .llvm_context
+            .i64_type()
+            .const_int(runtime::abi::RUNTIME_ABI_VERSION as u64, false);
+        self.context
+            .call_runtime_function("cheetah_check_abi", &[abi_version.into()])?;
+
+        let newline = self
+            .context
+            .llvm_context
+            .i64_type()
+            .const_int(self.newline.code() as u64, false);
+        self.context
+            .call_runtime_function("cheetah_configure_output", &[newline.into()])?;
+        Ok(())
+    }
+
     fn embed_runtime_functions(&mut self) {
         self.create_conversion_functions();
 
@@ -288,8 +640,6 @@ impl<'ctx> Compiler<'ctx> {
 
         self.create_string_conversion_functions();
 
-        runtime::register_runtime_functions(self.context.llvm_context, &mut self.context.module);
-
         self.context.register_len_function();
         self.context.register_print_function();
         self.context.register_min_max_functions();
@@ -418,20 +768,17 @@ impl<'ctx> Compiler<'ctx> {
     fn register_polymorphic_str(&mut self) {
         let int_to_string = self
             .context
-            .module
-            .get_function("int_to_string")
+            .runtime_function("int_to_string")
             .expect("int_to_string function not found");
 
         let float_to_string = self
             .context
-            .module
-            .get_function("float_to_string")
+            .runtime_function("float_to_string")
             .expect("float_to_string function not found");
 
         let bool_to_string = self
             .context
-            .module
-            .get_function("bool_to_string")
+            .runtime_function("bool_to_string")
             .expect("bool_to_string function not found");
 
         let mut str_variants = HashMap::new();
@@ -494,7 +841,9 @@ impl<'ctx> Compiler<'ctx> {
             }
         }
 
-        let function_type = if name == "get_first"
+        let function_type = if let Some(return_type) = self.context.return_types.get(name) {
+            self.context.get_llvm_type(return_type).fn_type(&param_types, false)
+        } else if name == "get_first"
             || name == "append_to_list"
             || name == "create_person"
             || name == "add_phone"
@@ -584,6 +933,10 @@ impl<'ctx> Compiler<'ctx> {
 
         self.context.builder.position_at_end(basic_block);
 
+        let first_line = body.first().map_or(0, |stmt| stmt.line());
+        let saved_debug_scope = self.context.enter_debug_scope(function, name, first_line);
+        let saved_trace_frame = self.context.enter_trace_frame(name)?;
+
         self.context.push_scope(true, false, false);
 
         let mut local_vars = HashMap::new();
@@ -630,7 +983,7 @@ impl<'ctx> Compiler<'ctx> {
                 .build_store(alloca, param_value)
                 .unwrap();
 
-            local_vars.insert(param.name.clone(), alloca);
+            local_vars.insert(Symbol::intern(&param.name), alloca);
 
             self.context
                 .add_variable_to_scope(param.name.clone(), alloca, param_type.clone());
@@ -656,10 +1009,16 @@ impl<'ctx> Compiler<'ctx> {
             .get_terminator()
             .is_some()
         {
-            let zero = context.i64_type().const_int(0, false);
+            self.context.emit_trace_frame_exit()?;
+            let zero = match function.get_type().get_return_type() {
+                Some(return_type) => return_type.const_zero(),
+                None => context.i64_type().const_zero().into(),
+            };
             self.context.builder.build_return(Some(&zero)).unwrap();
         }
 
+        self.context.leave_trace_frame(saved_trace_frame);
+        self.context.leave_debug_scope(saved_debug_scope);
         self.context.current_function = old_function;
         self.context.local_vars = old_local_vars;
 
@@ -673,15 +1032,23 @@ impl<'ctx> Compiler<'ctx> {
     }
 
     /// Compile a class definition
+    /// Compile a class definition in `module`. Only the instance layout is
+    /// settled so far: a class whose attributes are fixed gets a struct type.
+    /// A class with unimplemented abstract methods is marked uninstantiable.
+    /// Running the definition creates the class object and its attributes.
     fn compile_class(
         &mut self,
-        name: &str,
-        bases: &[Box<ast::Expr>],
-        body: &[Box<ast::Stmt>],
+        class: &ast::Stmt,
+        module: &[Box<ast::Stmt>],
     ) -> Result<(), String> {
-        let _ = body;
-        let _ = bases;
-        let _ = name;
+        if let ast::Stmt::ClassDef { name, bases, body, .. } = class {
+            self.context.register_class_bases(name, bases);
+            self.context.register_class_methods(name, body)?;
+            self.context.compile_class_body(name, body)?;
+        }
+        if let AttributeStorage::Fixed(layout) = class_layout::analyze(class, module) {
+            self.context.register_class_layout(layout);
+        }
 
         Ok(())
     }
@@ -778,3 +1145,25 @@ impl<'ctx> Compiler<'ctx> {
         }
     }
 }
+
+/// Directory of the runtime library (`libcheetah.a`) executables link against:
+/// the crate's `target/[runtime_target_dir/]release` when run through cargo,
+/// otherwise `lib/cheetah` under the installed binary's prefix
+#[cfg(feature = "codegen")]
+pub fn runtime_library_dir(runtime_target_dir: &str) -> Result<String, String> {
+    match std::env::var("CARGO_MANIFEST_DIR") {
+        Ok(manifest) if !runtime_target_dir.is_empty() => {
+            Ok(format!("{}/target/{}/release", manifest, runtime_target_dir))
+        }
+        Ok(manifest) => Ok(format!("{}/target/release", manifest)),
+        Err(_) => {
+            let mut exe = std::env::current_exe()
+                .map_err(|e| format!("Failed to locate current exe: {}", e))?;
+            exe.pop();
+            exe.pop();
+            exe.push("lib");
+            exe.push("cheetah");
+            Ok(exe.to_string_lossy().into_owned())
+        }
+    }
+}
@@ -0,0 +1,61 @@
+// optimizer.rs - LLVM optimization pipelines for JIT and AOT compilation
+//
+// Optimization levels 0-3 map to LLVM's standard `default<On>` pipelines on
+// the new pass manager, and to the matching code generation level.
+
+use crate::compiler::Compiler;
+use inkwell::module::Module;
+use inkwell::passes::PassBuilderOptions;
+use inkwell::OptimizationLevel;
+
+/// Pass pipeline for an optimization level; levels above 3 are treated as 3
+pub fn pass_pipeline(level: u8) -> &'static str {
+    match level {
+        0 => "default<O0>",
+        1 => "default<O1>",
+        2 => "default<O2>",
+        _ => "default<O3>",
+    }
+}
+
+/// Code generation level for an optimization level
+pub fn codegen_level(level: u8) -> OptimizationLevel {
+    match level {
+        0 => OptimizationLevel::None,
+        1 => OptimizationLevel::Less,
+        2 => OptimizationLevel::Default,
+        _ => OptimizationLevel::Aggressive,
+    }
+}
+
+impl<'ctx> Compiler<'ctx> {
+    /// Set the optimization level (0-3) used by `optimize_module`, JIT engines and AOT code generation
+    pub fn set_opt_level(&mut self, level: u8) {
+        self.opt_level = level.min(3);
+    }
+
+    /// Optimize the compiled module at the compiler's optimization level
+    pub fn optimize_module(&self) -> Result<(), String> {
+        self.optimize(self.opt_level)
+    }
+
+    /// Optimize the compiled module at `level` (0-3)
+    pub fn optimize(&self, level: u8) -> Result<(), String> {
+        self.run_optimization_pipeline(&self.context.module, level)
+    }
+
+    /// Run the optimization pipeline for `level` over `module`
+    pub fn run_optimization_pipeline(&self, module: &Module<'ctx>, level: u8) -> Result<(), String> {
+        let machine = self.target.create_target_machine(codegen_level(level))?;
+
+        let options = PassBuilderOptions::create();
+        options.set_loop_vectorization(level >= 2);
+        options.set_loop_slp_vectorization(level >= 2);
+        options.set_loop_unrolling(level >= 2);
+        options.set_loop_interleaving(level >= 2);
+
+        module
+            .run_passes(pass_pipeline(level), &machine, options)
+            .map_err(|e| format!("Optimization failed: {}", e))
+    }
+}
@@ -0,0 +1,211 @@
+// plugins.rs - Builtin functions registered by embedding crates
+//
+// A domain-specific runtime (a graphics or robotics API, say) registers its
+// functions once at startup:
+//
+//     let clamp = PluginBuiltin::new("clamp", "robot_clamp", vec![Type::Float; 3], Type::Float)
+//         .with_address(robot_clamp as *const () as usize)
+//         .with_const_eval(|args| match args {
+//             [ConstValue::Float(x), ConstValue::Float(lo), ConstValue::Float(hi)] => {
+//                 Some(ConstValue::Float(x.clamp(*lo, *hi)))
+//             }
+//             _ => None,
+//         });
+//     register_builtin(clamp)?;
+//
+// Programs then call `clamp(...)` like any other builtin. Calls compile the
+// way `@extern` calls do, with the same C types: the JIT calls `address`
+// (or finds `symbol` in the process), and AOT builds resolve `symbol` at link
+// time. A call whose arguments are all literals is folded with `const_eval`
+// when it returns a value.
+
+use crate::ast::{Expr, NameConstant, Number, Span};
+#[cfg(feature = "codegen")]
+use crate::compiler::context::CompilationContext;
+use crate::compiler::ffi::ExternSignature;
+use crate::compiler::types::Type;
+use crate::lexer::keywords;
+use std::sync::RwLock;
+
+/// A literal argument or result of compile-time evaluation
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+}
+
+impl ConstValue {
+    /// The value of a literal expression
+    pub fn from_expr(expr: &Expr) -> Option<Self> {
+        match expr {
+            Expr::Num { value: Number::Integer(value), .. } => Some(ConstValue::Int(*value)),
+            Expr::Num { value: Number::Float(value), .. } => Some(ConstValue::Float(*value)),
+            Expr::Str { value, .. } => Some(ConstValue::Str(value.clone())),
+            Expr::NameConstant { value: NameConstant::True, .. } => Some(ConstValue::Bool(true)),
+            Expr::NameConstant { value: NameConstant::False, .. } => Some(ConstValue::Bool(false)),
+            _ => None,
+        }
+    }
+
+    /// A literal expression for the value, positioned at `line`:`column`
+    pub fn to_expr(&self, line: usize, column: usize) -> Expr {
+        match self {
+            ConstValue::Int(value) => Expr::Num { value: Number::Integer(*value), line, column, span: Span::default() },
+            ConstValue::Float(value) => Expr::Num { value: Number::Float(*value), line, column, span: Span::default() },
+            ConstValue::Bool(value) => Expr::NameConstant {
+                value: if *value { NameConstant::True } else { NameConstant::False },
+                line,
+                column,
+                span: Span::default(),
+            },
+            ConstValue::Str(value) => Expr::Str { value: value.clone(), line, column, span: Span::default() },
+        }
+    }
+
+    /// The Cheetah type of the value
+    pub fn get_type(&self) -> Type {
+        match self {
+            ConstValue::Int(_) => Type::Int,
+            ConstValue::Float(_) => Type::Float,
+            ConstValue::Bool(_) => Type::Bool,
+            ConstValue::Str(_) => Type::String,
+        }
+    }
+}
+
+/// Evaluates a call with literal arguments at compile time, or returns None
+/// to leave the call to run
+pub type ConstEval = fn(&[ConstValue]) -> Option<ConstValue>;
+
+/// A builtin function provided by the embedding application
+#[derive(Debug, Clone)]
+pub struct PluginBuiltin {
+    /// Name programs call it by
+    pub name: String,
+    /// Runtime symbol and C signature of the implementation
+    pub signature: ExternSignature,
+    /// Address of the implementation for JIT-compiled code
+    pub address: Option<usize>,
+    pub const_eval: Option<ConstEval>,
+}
+
+impl PluginBuiltin {
+    /// A builtin `name` implemented by the C function `symbol`
+    pub fn new(name: &str, symbol: &str, param_types: Vec<Type>, return_type: Type) -> Self {
+        Self {
+            name: name.to_string(),
+            signature: ExternSignature {
+                symbol: symbol.to_string(),
+                param_types,
+                return_type,
+            },
+            address: None,
+            const_eval: None,
+        }
+    }
+
+    /// Call the implementation at `address` from JIT-compiled code, so it
+    /// needn't be exported from the process
+    pub fn with_address(mut self, address: usize) -> Self {
+        self.address = Some(address);
+        self
+    }
+
+    /// Fold calls with literal arguments at compile time
+    pub fn with_const_eval(mut self, const_eval: ConstEval) -> Self {
+        self.const_eval = Some(const_eval);
+        self
+    }
+
+    /// Type the type checker gives the builtin
+    pub fn function_type(&self) -> Type {
+        Type::function(
+            self.signature.param_types.clone(),
+            self.signature.return_type.clone(),
+        )
+    }
+
+    /// Fold a call to the builtin if every argument is a literal and
+    /// `const_eval` returns a value of the declared return type
+    pub fn fold_call(&self, args: &[Box<Expr>]) -> Option<ConstValue> {
+        let const_eval = self.const_eval?;
+        let values = args
+            .iter()
+            .map(|arg| ConstValue::from_expr(arg))
+            .collect::<Option<Vec<_>>>()?;
+        const_eval(&values).filter(|value| value.get_type() == self.signature.return_type)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let is_identifier = self.name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+            && self.name.chars().all(|c| c.is_alphanumeric() || c == '_');
+        if !is_identifier || keywords::lookup(&self.name).is_some() {
+            return Err(format!("'{}' is not a valid builtin name", self.name));
+        }
+        if self.signature.symbol.is_empty() {
+            return Err(format!("builtin '{}' needs a runtime symbol", self.name));
+        }
+        for ty in &self.signature.param_types {
+            if !matches!(ty, Type::Int | Type::Float | Type::Bool | Type::String) {
+                return Err(format!(
+                    "builtin '{}' has a parameter of type {}; expected int, float, bool or str",
+                    self.name, ty
+                ));
+            }
+        }
+        if !matches!(
+            self.signature.return_type,
+            Type::Int | Type::Float | Type::Bool | Type::String | Type::None
+        ) {
+            return Err(format!(
+                "builtin '{}' returns {}; expected int, float, bool, str or None",
+                self.name, self.signature.return_type
+            ));
+        }
+        Ok(())
+    }
+}
+
+static BUILTINS: RwLock<Vec<PluginBuiltin>> = RwLock::new(Vec::new());
+
+/// Make `builtin` available to every program compiled from now on, replacing
+/// any registered builtin of the same name
+pub fn register_builtin(builtin: PluginBuiltin) -> Result<(), String> {
+    builtin.validate()?;
+    let mut builtins = BUILTINS.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    builtins.retain(|existing| existing.name != builtin.name);
+    builtins.push(builtin);
+    Ok(())
+}
+
+/// The registered builtins, in registration order
+pub fn registered_builtins() -> Vec<PluginBuiltin> {
+    BUILTINS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+}
+
+/// The registered builtin called `name`
+pub fn lookup_builtin(name: &str) -> Option<PluginBuiltin> {
+    BUILTINS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .iter()
+        .find(|builtin| builtin.name == name)
+        .cloned()
+}
+
+#[cfg(feature = "codegen")]
+impl<'ctx> CompilationContext<'ctx> {
+    /// Declare the registered builtins so calls to them compile like `@extern` calls
+    pub fn declare_plugin_builtins(&mut self) -> Result<(), String> {
+        for builtin in registered_builtins() {
+            self.declare_extern(&builtin.name, builtin.signature)
+                .map_err(|e| format!("Plugin builtin '{}': {}", builtin.name, e))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,112 @@
+// prealloc.rs - Preallocated result containers for comprehensions
+//
+// When every generator of a comprehension iterates something whose length is
+// known at compile time (a list, tuple, set or string literal, or a range()
+// with constant bounds), the product of those lengths bounds the number of
+// elements the comprehension produces. The result list or dict is created with
+// that capacity up front, and list comprehensions append with
+// `list_push_presized`, which skips the growth check of `list_append_tagged`.
+
+use crate::ast::{Comprehension, Expr};
+use crate::compiler::context::CompilationContext;
+use crate::compiler::stmt_non_recursive::StmtNonRecursive;
+use inkwell::values::{FunctionValue, PointerValue};
+
+/// Largest hint a result container is preallocated for
+pub const MAX_PREALLOCATED_ELEMENTS: u64 = 1 << 20;
+
+/// Number of items iterating a literal yields at most
+pub fn literal_length(expr: &Expr) -> Option<u64> {
+    match expr {
+        Expr::List { elts, .. } | Expr::Tuple { elts, .. } | Expr::Set { elts, .. } => {
+            Some(elts.len() as u64)
+        }
+        // Byte length, which bounds the number of characters
+        Expr::Str { value, .. } => Some(value.len() as u64),
+        _ => None,
+    }
+}
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// Upper bound on the number of elements a comprehension over `generators`
+    /// produces, if every generator's length is known at compile time
+    pub(crate) fn comprehension_length_hint(&self, generators: &[Comprehension]) -> Option<u64> {
+        let mut hint: u64 = 1;
+        for generator in generators {
+            let len = match literal_length(&generator.iter) {
+                Some(len) => len,
+                None => self.fold_constant_range(&generator.iter)?.trip_count(),
+            };
+            hint = hint.checked_mul(len)?;
+        }
+        (hint <= MAX_PREALLOCATED_ELEMENTS).then_some(hint)
+    }
+
+    /// Create a list with room for `capacity` elements, appended to with
+    /// `list_push_presized` by comprehensions
+    pub(crate) fn build_presized_list(&mut self, capacity: u64, name: &str) -> Result<PointerValue<'ctx>, String> {
+        let with_capacity = self
+            .runtime_function("list_with_capacity")
+            .ok_or("list_with_capacity not found")?;
+        let capacity = self.llvm_context.i64_type().const_int(capacity, false);
+        let list_ptr = self
+            .builder
+            .build_call(with_capacity, &[capacity.into()], name)
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .ok_or("list_with_capacity returned void")?
+            .into_pointer_value();
+        self.presized_lists.insert(list_ptr);
+        Ok(list_ptr)
+    }
+
+    /// Result list of a list comprehension, preallocated when its length is known
+    pub(crate) fn build_comprehension_list(
+        &mut self,
+        generators: &[Comprehension],
+        name: &str,
+    ) -> Result<PointerValue<'ctx>, String> {
+        use crate::compiler::expr::ExprCompiler;
+
+        match self.comprehension_length_hint(generators) {
+            Some(hint) => self.build_presized_list(hint, name),
+            None => self.build_empty_list(name),
+        }
+    }
+
+    /// Result dict of a dict comprehension, preallocated when its length is known
+    pub(crate) fn build_comprehension_dict(
+        &mut self,
+        generators: &[Comprehension],
+        name: &str,
+    ) -> Result<PointerValue<'ctx>, String> {
+        use crate::compiler::expr::ExprCompiler;
+
+        let Some(hint) = self.comprehension_length_hint(generators) else {
+            return self.build_empty_dict(name);
+        };
+        let with_capacity = self
+            .runtime_function("dict_with_capacity")
+            .ok_or("dict_with_capacity not found")?;
+        let capacity = self.llvm_context.i64_type().const_int(hint, false);
+        Ok(self
+            .builder
+            .build_call(with_capacity, &[capacity.into()], name)
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .ok_or("dict_with_capacity returned void")?
+            .into_pointer_value())
+    }
+
+    /// Tagged append function for a comprehension's result list: the unchecked
+    /// `list_push_presized` for preallocated lists, `list_append_tagged` otherwise
+    pub(crate) fn comprehension_append_fn(&self, result_list: PointerValue<'ctx>) -> Option<FunctionValue<'ctx>> {
+        if self.presized_lists.contains(&result_list) {
+            self.runtime_function("list_push_presized")
+        } else {
+            self.runtime_function("list_append_tagged")
+        }
+    }
+}
@@ -0,0 +1,404 @@
+// repl.rs - Incremental compilation for the JIT REPL
+//
+// Every cell is compiled to its own module and added to one execution engine.
+// Top-level variables live in host-side slots that later cells read and write
+// in place, and top-level functions are declared in later cells and mapped to
+// the code compiled for them earlier. A cell ending in an expression prints
+// its value, like Python's REPL.
+
+use crate::ast::{Expr, ExprContext, Module as AstModule, Operator, Stmt};
+use crate::compiler::optimizer::codegen_level;
+use crate::compiler::runtime::{
+    abort, buffer, jit, memory_profiler, os, parallel_ops, range, traceback,
+};
+use crate::compiler::types::Type;
+use crate::compiler::Compiler;
+use crate::intern::Symbol;
+use crate::typechecker::TypeChecker;
+use inkwell::context::Context;
+use inkwell::execution_engine::ExecutionEngine;
+use inkwell::module::Linkage;
+use inkwell::types::{BasicTypeEnum, FunctionType};
+use inkwell::values::{FunctionValue, InstructionOpcode, PointerValue};
+use inkwell::AddressSpace;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+
+/// A top-level variable defined by an earlier cell
+struct Binding {
+    ty: Type,
+    /// Holds the value; boxed so the address compiled into later cells stays put
+    slot: Box<Cell<u64>>,
+}
+
+/// A top-level function compiled by an earlier cell
+struct CellFunction<'ctx> {
+    fn_type: FunctionType<'ctx>,
+    address: usize,
+}
+
+/// A variable whose slot the cell being compiled writes, with its new type
+/// and the slot to allocate for it if it is new
+type PendingBinding = (String, Type, Option<Box<Cell<u64>>>);
+
+/// Compiles and runs REPL cells, each seeing the top-level variables and
+/// functions of the cells before it
+pub struct ReplSession<'ctx> {
+    context: &'ctx Context,
+    opt_level: u8,
+    /// Created with the first cell; the modules of later cells are added to it
+    execution_engine: Option<ExecutionEngine<'ctx>>,
+    /// Compilers of the cells run so far, kept alive with the modules the engine runs
+    cells: Vec<Compiler<'ctx>>,
+    /// Checks each cell against the definitions of the cells before it
+    checker: TypeChecker,
+    variables: HashMap<String, Binding>,
+    functions: HashMap<String, CellFunction<'ctx>>,
+}
+
+impl<'ctx> ReplSession<'ctx> {
+    /// Start a session that compiles cells in `context` at `opt_level`
+    pub fn new(context: &'ctx Context, opt_level: u8) -> Self {
+        buffer::init();
+        range::init();
+        parallel_ops::init();
+
+        Self {
+            context,
+            opt_level: opt_level.min(3),
+            execution_engine: None,
+            cells: Vec::new(),
+            checker: TypeChecker::new(),
+            variables: HashMap::new(),
+            functions: HashMap::new(),
+        }
+    }
+
+    /// Type of `expr` given the definitions of the cells run so far
+    pub fn expression_type(&self, expr: &Expr) -> Option<Type> {
+        self.checker.expression_type(expr)
+    }
+
+    /// LLVM IR of the last cell that ran
+    pub fn last_ir(&self) -> Option<String> {
+        self.cells.last().map(Compiler::get_ir)
+    }
+
+    /// Compile `module` as the next cell and run it. A cell that fails to
+    /// compile leaves the session as it was.
+    pub fn run_cell(&mut self, module: &AstModule) -> Result<(), String> {
+        let module = &*crate::transform::apply(module)?;
+        let mut checker = self.checker.clone();
+        checker
+            .check_module(module)
+            .map_err(|e| format!("Type error: {}", e))?;
+
+        let index = self.cells.len() + 1;
+        let mut compiler = Compiler::new(self.context, &format!("repl_{}", index));
+        compiler.set_opt_level(self.opt_level);
+
+        let echoed = echo_last_expression(module, &checker);
+        let module = echoed.as_ref().unwrap_or(module);
+        let defined = defined_functions(module);
+        let imported = self.declare_earlier_cells(&mut compiler, &defined);
+        compiler.record_return_types(&checker, defined.iter().chain(self.functions.keys()));
+        compiler.compile_checked_module(module)?;
+
+        let main = compiler
+            .context
+            .module
+            .get_function("main")
+            .ok_or("Failed to find main function")?;
+        let pending = self.store_variables(&compiler, main, &checker);
+        let entry = format!("__repl_cell_{}", index);
+        let exported = export_symbols(&compiler, main, &entry, index);
+        compiler
+            .context
+            .module
+            .verify()
+            .map_err(|e| format!("Module verification failed: {}", e))?;
+        compiler.optimize_module()?;
+
+        let engine = match &self.execution_engine {
+            Some(engine) => {
+                engine
+                    .add_module(compiler.get_module())
+                    .map_err(|_| "Failed to add the cell to the execution engine".to_string())?;
+                engine
+            }
+            None => {
+                let engine = compiler
+                    .get_module()
+                    .create_jit_execution_engine(codegen_level(compiler.opt_level))
+                    .map_err(|e| format!("Failed to create execution engine: {}", e))?;
+                self.execution_engine.insert(engine)
+            }
+        };
+        jit::register_runtime_functions(engine, compiler.get_module())?;
+        for (declaration, address) in &imported {
+            engine.add_global_mapping(declaration, *address);
+        }
+
+        let result = unsafe {
+            let run = engine
+                .get_function::<unsafe extern "C" fn()>(&entry)
+                .map_err(|e| format!("Failed to find main function: {}", e))?;
+            abort::catch_abort(|| run.call())
+        };
+        buffer::flush();
+        range::cleanup();
+        os::cleanup();
+        traceback::reset();
+        memory_profiler::cleanup();
+        parallel_ops::cleanup();
+        // The cell stopped partway, so none of its definitions are kept
+        result.map_err(|error| error.to_string())?;
+
+        for (name, ty, slot) in pending {
+            self.functions.remove(&name);
+            match slot {
+                Some(slot) => {
+                    self.variables.insert(name, Binding { ty, slot });
+                }
+                None => {
+                    if let Some(binding) = self.variables.get_mut(&name) {
+                        binding.ty = ty;
+                    }
+                }
+            }
+        }
+        for (name, function, symbol) in exported {
+            let address = engine
+                .get_function_address(&symbol)
+                .map_err(|e| format!("Failed to find function '{}': {}", name, e))?;
+            self.variables.remove(&name);
+            self.functions.insert(
+                name,
+                CellFunction {
+                    fn_type: function.get_type(),
+                    address,
+                },
+            );
+        }
+        self.checker = checker;
+        self.cells.push(compiler);
+        Ok(())
+    }
+
+    /// Make the variables and functions of earlier cells visible to the cell
+    /// about to be compiled, except functions the cell defines again. Returns
+    /// the function declarations with the addresses they resolve to.
+    fn declare_earlier_cells(
+        &self,
+        compiler: &mut Compiler<'ctx>,
+        defined: &HashSet<String>,
+    ) -> Vec<(FunctionValue<'ctx>, usize)> {
+        let ctx = &mut compiler.context;
+        for (name, binding) in &self.variables {
+            if defined.contains(name) {
+                continue;
+            }
+            let ptr = slot_pointer(self.context, &binding.slot);
+            if let Some(scope) = ctx.scope_stack.global_scope_mut() {
+                scope.add_variable(name.clone(), ptr, binding.ty.clone());
+            }
+            ctx.variables.insert(Symbol::intern(name), ptr);
+            ctx.register_variable(name.clone(), binding.ty.clone());
+        }
+
+        let mut imported = Vec::new();
+        for (name, function) in &self.functions {
+            if defined.contains(name) {
+                continue;
+            }
+            let declaration = ctx.module.add_function(name, function.fn_type, None);
+            ctx.functions.insert(name.clone(), declaration);
+            imported.push((declaration, function.address));
+        }
+        imported
+    }
+
+    /// Copy the cell's top-level variables to their slots before `main`
+    /// returns. Variables whose values don't fit a slot stay local to the cell.
+    fn store_variables(
+        &self,
+        compiler: &Compiler<'ctx>,
+        main: FunctionValue<'ctx>,
+        checker: &TypeChecker,
+    ) -> Vec<PendingBinding> {
+        let ctx = &compiler.context;
+        let Some(scope) = ctx.scope_stack.global_scope() else {
+            return Vec::new();
+        };
+        let returns: Vec<_> = main
+            .get_basic_blocks()
+            .into_iter()
+            .filter_map(|block| block.get_terminator())
+            .filter(|terminator| terminator.get_opcode() == InstructionOpcode::Return)
+            .collect();
+
+        let mut pending = Vec::new();
+        for (&symbol, &ptr) in &scope.variables {
+            let name = symbol.as_str();
+            // Only variables of the program, not the compiler's temporaries
+            if checker.variable_type(name).is_none() || !is_visible_in(ptr, main) {
+                continue;
+            }
+            let Some(ty) = scope.types.get(&symbol).or_else(|| ctx.lookup_variable_type(name)) else {
+                continue;
+            };
+            let llvm_type = ctx.get_llvm_type(ty);
+            if !matches!(
+                llvm_type,
+                BasicTypeEnum::IntType(_) | BasicTypeEnum::FloatType(_) | BasicTypeEnum::PointerType(_)
+            ) {
+                continue;
+            }
+
+            let (slot_ptr, new_slot) = match self.variables.get(name) {
+                Some(binding) => (slot_pointer(self.context, &binding.slot), None),
+                None => {
+                    let slot = Box::new(Cell::new(0));
+                    (slot_pointer(self.context, &slot), Some(slot))
+                }
+            };
+            if slot_ptr != ptr {
+                for ret in &returns {
+                    ctx.builder.position_before(ret);
+                    let value = ctx.builder.build_load(llvm_type, ptr, name).unwrap();
+                    ctx.builder.build_store(slot_ptr, value).unwrap();
+                }
+            }
+            pending.push((name.to_string(), ty.clone(), new_slot));
+        }
+        pending
+    }
+}
+
+/// Rewrite a cell whose last statement is an expression to print its value.
+/// Strings are shown quoted like Python's repr; expressions without a value,
+/// such as calls returning None, stay silent.
+fn echo_last_expression(module: &AstModule, checker: &TypeChecker) -> Option<AstModule> {
+    let Some(Stmt::Expr { value, line, column, span }) = module.body.last().map(|stmt| stmt.as_ref())
+    else {
+        return None;
+    };
+    let (line, column, span) = (*line, *column, *span);
+    let concat = |left: Box<Expr>, right: Box<Expr>| {
+        Box::new(Expr::BinOp {
+            left,
+            op: Operator::Add,
+            right,
+            line,
+            column,
+            span,
+        })
+    };
+    let quote = || {
+        Box::new(Expr::Str {
+            value: "'".to_string(),
+            line,
+            column,
+            span,
+        })
+    };
+
+    let shown = match checker.expression_type(value)? {
+        Type::None | Type::Void | Type::Unknown => return None,
+        Type::String => concat(concat(quote(), value.clone()), quote()),
+        _ => value.clone(),
+    };
+    let print = Expr::Call {
+        func: Box::new(Expr::Name {
+            id: "print".to_string(),
+            ctx: ExprContext::Load,
+            line,
+            column,
+            span,
+        }),
+        args: vec![shown],
+        keywords: Vec::new(),
+        line,
+        column,
+        span,
+    };
+
+    let mut echoed = module.clone();
+    **echoed.body.last_mut()? = Stmt::Expr {
+        value: Box::new(print),
+        line,
+        column,
+        span,
+    };
+    Some(echoed)
+}
+
+/// Names of the functions a cell defines at the top level
+fn defined_functions(module: &AstModule) -> HashSet<String> {
+    module
+        .body
+        .iter()
+        .filter_map(|stmt| match stmt.as_ref() {
+            Stmt::FunctionDef { name, .. } => Some(name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Constant pointer to a slot, usable from any function of any cell
+fn slot_pointer<'ctx>(context: &'ctx Context, slot: &Cell<u64>) -> PointerValue<'ctx> {
+    context
+        .i64_type()
+        .const_int(slot.as_ptr() as u64, false)
+        .const_to_pointer(context.ptr_type(AddressSpace::default()))
+}
+
+/// Whether `ptr` can be loaded in `function`: a global, a constant or one of its allocas
+fn is_visible_in<'ctx>(ptr: PointerValue<'ctx>, function: FunctionValue<'ctx>) -> bool {
+    match ptr.as_instruction() {
+        Some(instruction) => instruction
+            .get_parent()
+            .and_then(|block| block.get_parent())
+            .is_some_and(|parent| parent == function),
+        None => true,
+    }
+}
+
+/// Give the cell's entry point and top-level functions names unique to the
+/// cell and make everything else it defines internal, so cells never clash
+/// in the shared execution engine. Returns the exported functions with their
+/// source names and symbols.
+fn export_symbols<'ctx>(
+    compiler: &Compiler<'ctx>,
+    main: FunctionValue<'ctx>,
+    entry: &str,
+    index: usize,
+) -> Vec<(String, FunctionValue<'ctx>, String)> {
+    let exported: Vec<_> = compiler
+        .top_level_functions
+        .iter()
+        .filter_map(|name| {
+            let function = *compiler.context.functions.get(name)?;
+            Some((name.clone(), function, format!("__repl_{}_{}", index, name)))
+        })
+        .collect();
+
+    let module = &compiler.context.module;
+    for function in module.get_functions() {
+        let is_exported = function == main || exported.iter().any(|(_, f, _)| *f == function);
+        if function.count_basic_blocks() > 0 && !is_exported {
+            function.set_linkage(Linkage::Internal);
+        }
+    }
+    for global in module.get_globals() {
+        if !global.is_declaration() {
+            global.set_linkage(Linkage::Internal);
+        }
+    }
+
+    main.as_global_value().as_pointer_value().set_name(entry);
+    for (_, function, symbol) in &exported {
+        function.as_global_value().as_pointer_value().set_name(symbol);
+    }
+    exported
+}
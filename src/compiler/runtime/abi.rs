@@ -0,0 +1,74 @@
+// abi.rs - Runtime ABI version, checked by compiled programs at startup
+//
+// Generated code calls `cheetah_check_abi` with the ABI version of the
+// compiler that produced it. An AOT binary linked against a libcheetah built
+// from a different version stops with a clear message instead of crashing
+// somewhere inside a runtime call. Bump the version whenever the signature or
+// behavior of a runtime function used by generated code changes.
+
+use inkwell::context::Context;
+use inkwell::execution_engine::ExecutionEngine;
+use inkwell::module::Module;
+
+/// ABI version of the runtime functions called by generated code
+pub const RUNTIME_ABI_VERSION: i64 = 2;
+
+/// The error reported when a program compiled for `expected` runs against this runtime
+pub fn abi_mismatch_message(expected: i64) -> Option<String> {
+    if expected == RUNTIME_ABI_VERSION {
+        return None;
+    }
+    Some(format!(
+        "cheetah: runtime ABI mismatch: this program was compiled for runtime ABI version {}, \
+         but the linked libcheetah provides version {}. Rebuild the program and the runtime \
+         with the same version of cheetah.",
+        expected, RUNTIME_ABI_VERSION
+    ))
+}
+
+/// ABI version of this runtime
+#[no_mangle]
+pub extern "C" fn cheetah_runtime_abi_version() -> i64 {
+    RUNTIME_ABI_VERSION
+}
+
+/// Exit with an error unless the program was compiled for this runtime's ABI
+#[no_mangle]
+pub extern "C" fn cheetah_check_abi(expected: i64) {
+    if let Some(message) = abi_mismatch_message(expected) {
+        eprintln!("{}", message);
+        std::process::exit(super::abort::EXIT_CODE);
+    }
+}
+
+/// Register ABI check functions in the LLVM module
+pub fn register_abi_functions<'ctx>(context: &'ctx Context, module: &mut Module<'ctx>) {
+    let i64_type = context.i64_type();
+
+    let declarations = [
+        ("cheetah_runtime_abi_version", i64_type.fn_type(&[], false)),
+        ("cheetah_check_abi", context.void_type().fn_type(&[i64_type.into()], false)),
+    ];
+    for (name, fn_type) in declarations {
+        if module.get_function(name).is_none() {
+            module.add_function(name, fn_type, None);
+        }
+    }
+}
+
+/// Map ABI check functions to their runtime addresses in the JIT
+pub fn register_abi_runtime_functions(
+    engine: &ExecutionEngine<'_>,
+    module: &Module<'_>,
+) -> Result<(), String> {
+    let mappings: [(&str, usize); 2] = [
+        ("cheetah_runtime_abi_version", cheetah_runtime_abi_version as *const () as usize),
+        ("cheetah_check_abi", cheetah_check_abi as *const () as usize),
+    ];
+    for (name, address) in mappings {
+        if let Some(f) = module.get_function(name) {
+            engine.add_global_mapping(&f, address);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,122 @@
+// abort.rs - Runtime errors that stop the program
+//
+// Compiled code calls `cheetah_runtime_abort` for errors it can't raise as
+// an exception, such as unpacking a list into the wrong number of targets.
+// The error is reported on one line with the source location of the
+// innermost traceback frame:
+//
+//     program.ch:12: runtime error in unpack: list length does not match number of targets
+//
+// A compiled program prints it to stderr and exits with code 70. A JIT host
+// runs compiled code through `catch_abort`, which returns the error instead
+// of ending the process: the aborting function returns at once, and every
+// function that called it returns as soon as the call does, as when a
+// with_timeout() deadline passes. Only the thread that called `catch_abort`
+// is caught; an error on a thread it started still ends the process.
+
+use inkwell::context::Context;
+use inkwell::execution_engine::ExecutionEngine;
+use inkwell::module::Module;
+use inkwell::AddressSpace;
+use std::cell::{Cell, RefCell};
+use std::ffi::CStr;
+use std::fmt;
+use std::os::raw::c_char;
+
+use crate::compiler::runtime::{buffer, traceback};
+
+/// Exit code of a program stopped by a runtime error
+pub const EXIT_CODE: i32 = 70;
+
+thread_local! {
+    /// The error stopping this thread's compiled code, until the host takes it
+    static ERROR: RefCell<Option<RuntimeAbort>> = const { RefCell::new(None) };
+    /// Number of `catch_abort` calls running on this thread
+    static CATCHING: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A runtime error that stopped the program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeAbort {
+    pub message: String,
+    /// Where it happened: source name, line and function, when compiled
+    /// code was running
+    pub location: Option<(String, i64, String)>,
+}
+
+impl fmt::Display for RuntimeAbort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some((source, line, function)) => {
+                write!(f, "{}:{}: runtime error in {}: {}", source, line, function, self.message)
+            }
+            None => write!(f, "runtime error: {}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for RuntimeAbort {}
+
+/// Whether a runtime error is stopping this thread's compiled code
+pub fn aborting() -> bool {
+    ERROR.with(|error| error.borrow().is_some())
+}
+
+/// Run compiled code, returning the runtime error that stopped it, if any,
+/// instead of letting it end the process
+pub fn catch_abort<T>(run: impl FnOnce() -> T) -> Result<T, RuntimeAbort> {
+    CATCHING.with(|catching| catching.set(catching.get() + 1));
+    let value = run();
+    CATCHING.with(|catching| catching.set(catching.get() - 1));
+    match ERROR.with(|error| error.borrow_mut().take()) {
+        Some(error) => Err(error),
+        None => Ok(value),
+    }
+}
+
+fn message_text(message: *const c_char) -> String {
+    if message.is_null() {
+        return "aborted".to_string();
+    }
+    unsafe { CStr::from_ptr(message) }.to_string_lossy().into_owned()
+}
+
+/// Stop the program with a runtime error. Outside `catch_abort` this prints
+/// the error and exits; inside it, the caller returns and the error goes to
+/// the host.
+#[no_mangle]
+pub extern "C" fn cheetah_runtime_abort(message: *const c_char) {
+    let error = RuntimeAbort {
+        message: message_text(message),
+        location: traceback::current_location(),
+    };
+
+    if CATCHING.with(Cell::get) == 0 {
+        buffer::flush();
+        eprintln!("{}", error);
+        std::process::exit(EXIT_CODE);
+    }
+    ERROR.with(|reported| {
+        reported.borrow_mut().get_or_insert(error);
+    });
+}
+
+/// Register the abort function in the LLVM module
+pub fn register_abort_functions<'ctx>(context: &'ctx Context, module: &mut Module<'ctx>) {
+    let ptr_type = context.ptr_type(AddressSpace::default());
+    if module.get_function("cheetah_runtime_abort").is_none() {
+        module.add_function(
+            "cheetah_runtime_abort",
+            context.void_type().fn_type(&[ptr_type.into()], false),
+            None,
+        );
+    }
+}
+
+/// Map the abort function to its runtime address in the JIT
+pub fn register_abort_runtime_functions(engine: &ExecutionEngine<'_>, module: &Module<'_>) -> Result<(), String> {
+    if let Some(f) = module.get_function("cheetah_runtime_abort") {
+        engine.add_global_mapping(&f, cheetah_runtime_abort as *const () as usize);
+    }
+    Ok(())
+}
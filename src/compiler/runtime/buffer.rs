@@ -1,11 +1,27 @@
-// buffer.rs - Combined circular & buffered output
+// buffer.rs - Per-thread line-buffered output
+//
+// Each thread collects what it prints in its own buffer and writes only
+// whole lines to stdout, holding the stdout lock for each write, so output
+// from threads printing at once never interleaves mid-line:
+//
+// - every line a thread prints (up to LINE_CAP bytes) appears whole
+// - a thread's lines appear in the order it printed them; lines of
+//   different threads may come in any order relative to each other
+// - a line still being printed stays buffered until its newline, or until
+//   `flush`, which the runtime calls before reading input and at exit
+//
+// A parallel loop flushes the calling thread before it starts and every
+// worker when it ends, so the loop's output comes after what was printed
+// before it and before what is printed after it. A thread an embedding
+// application spawns should call `flush` before it finishes: its buffer is
+// otherwise only written when the thread's locals are destroyed, which can
+// come after the thread is joined.
 
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::io::{self, Write};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::thread_local;
-use ryu;
 use itoa;
 use std::sync::atomic::AtomicBool;
 
@@ -15,36 +31,48 @@ static BYTES_WRITTEN: AtomicUsize = AtomicUsize::new(0);
 static BYTES_SAVED: AtomicUsize = AtomicUsize::new(0);
 static FORCE_DIRECT: AtomicBool = AtomicBool::new(false);
 
-// Circular buffer
-const CIRC_CAP: usize = 8192;
+/// Buffered bytes past which complete lines are written out
 const FLUSH_TH: usize = 4096;
+/// Longest unfinished line kept back; a longer one is written in pieces
+const LINE_CAP: usize = 64 * 1024;
 const MAX_INTERNED: usize = 64;
 
-struct CircularBuffer { buf: Vec<u8>, read: usize, write: usize, size: usize, cap: usize }
-impl CircularBuffer {
-    fn new(cap: usize) -> Self { CircularBuffer { buf: vec![0;cap], read:0, write:0, size:0, cap } }
-    fn write_byte(&mut self, b: u8) -> io::Result<()> { /* flush if full, then write */
-        if self.size==self.cap { self.flush()? }
-        self.buf[self.write]=b; self.write=(self.write+1)%self.cap; self.size+=1; Ok(())
-    }
-    fn write(&mut self, s: &[u8]) -> io::Result<()> { if s.len()>self.cap { self.flush()?; io::stdout().write_all(s)?; return Ok(()) }
-        if s.len()>self.cap-self.size { self.flush()? }
-        for &b in s { self.write_byte(b)? }
-        if self.size>FLUSH_TH { self.flush()? }
+/// Output a thread has printed but not yet written
+struct LineBuffer { buf: Vec<u8> }
+impl LineBuffer {
+    fn new() -> Self { LineBuffer { buf: Vec::with_capacity(FLUSH_TH * 2) } }
+    fn write(&mut self, s: &[u8]) -> io::Result<()> {
+        self.buf.extend_from_slice(s);
+        if self.buf.len() > FLUSH_TH {
+            match self.buf.iter().rposition(|&b| b == b'\n') {
+                Some(last) => self.write_out(last + 1)?,
+                None if self.buf.len() > LINE_CAP => self.flush()?,
+                None => {}
+            }
+        }
         Ok(())
     }
-    fn flush(&mut self) -> io::Result<()> {
-        if self.size==0 { return Ok(()) }
-        if self.read<self.write { io::stdout().write_all(&self.buf[self.read..self.write])?; }
-        else { io::stdout().write_all(&self.buf[self.read..self.cap])?; io::stdout().write_all(&self.buf[0..self.write])?; }
-        io::stdout().flush()?;
-        self.read=0; self.write=0; self.size=0; Ok(())
+    /// Write everything buffered, finished line or not
+    fn flush(&mut self) -> io::Result<()> { self.write_out(self.buf.len()) }
+    /// Write the first `len` buffered bytes in one locked write
+    fn write_out(&mut self, len: usize) -> io::Result<()> {
+        if len == 0 { return Ok(()) }
+        let mut stdout = io::stdout().lock();
+        let result = stdout.write_all(&self.buf[..len]).and_then(|_| stdout.flush());
+        self.buf.drain(..len);
+        result
     }
 }
+impl Drop for LineBuffer {
+    /// A thread's last, unfinished line is written when the thread exits
+    fn drop(&mut self) { let _=self.flush(); }
+}
 
 thread_local! {
-    static CIRC: RefCell<CircularBuffer> = RefCell::new(CircularBuffer::new(CIRC_CAP));
+    static LINES: RefCell<LineBuffer> = RefCell::new(LineBuffer::new());
     static CACHE: RefCell<HashMap<u64,Vec<u8>>> = RefCell::new(HashMap::with_capacity(MAX_INTERNED));
+    /// Output collected instead of written while capturing, as for doctests
+    static CAPTURE: RefCell<Option<Vec<u8>>> = const { RefCell::new(None) };
 }
 
 /// Initialize buffer systems
@@ -53,24 +81,37 @@ pub fn init() {
     BYTES_WRITTEN.store(0, Ordering::Relaxed);
     BYTES_SAVED.store(0, Ordering::Relaxed);
     FORCE_DIRECT.store(false, Ordering::Relaxed);
-    CIRC.with(|c| c.borrow_mut().flush().ok());
+    LINES.with(|c| c.borrow_mut().flush().ok());
     CACHE.with(|c| c.borrow_mut().clear());
 }
 
-/// Write raw bytes
+/// Write raw bytes, translating newlines for the output's newline mode
 fn write_bytes(b: &[u8]) {
     OPERATIONS.fetch_add(1,Ordering::Relaxed);
+    let captured = CAPTURE.with(|c| c.borrow_mut().as_mut().map(|out| out.extend_from_slice(b)).is_some());
+    if captured { return; }
+    let b=&*super::output::translate_newlines(b);
     if FORCE_DIRECT.load(Ordering::Relaxed) {
-        let _=io::stdout().write_all(b);
+        let _=io::stdout().lock().write_all(b);
         return;
     }
-    if let Err(_) = CIRC.with(|c| c.borrow_mut().write(b)) {
-        let _=io::stdout().write_all(b);
-    }
+    let _=LINES.with(|c| c.borrow_mut().write(b));
+}
+
+/// Collect output written on this thread until `finish_capture` instead of writing it to stdout
+pub fn start_capture() {
+    flush();
+    CAPTURE.with(|c| *c.borrow_mut() = Some(Vec::new()));
+}
+
+/// Stop capturing output, returning what was written since `start_capture`
+pub fn finish_capture() -> String {
+    let out = CAPTURE.with(|c| c.borrow_mut().take()).unwrap_or_default();
+    String::from_utf8_lossy(&out).into_owned()
 }
 
-/// Flush
-pub fn flush() { let _=CIRC.with(|c| c.borrow_mut().flush()); }
+/// Write this thread's buffered output, finished line or not
+pub fn flush() { let _=LINES.with(|c| c.borrow_mut().flush()); }
 
 /// Write string
 pub fn write_str(s: &str) { write_bytes(s.as_bytes()); }
@@ -79,17 +120,15 @@ pub fn write_newline() { write_bytes(b"\n"); flush(); }
 /// Write int
 pub fn write_int(v: i64) {
     OPERATIONS.fetch_add(1,Ordering::Relaxed);
-    if FORCE_DIRECT.load(Ordering::Relaxed) { let _=write!(io::stdout(),"{}",v); return; }
-    static mut ITOA_BUF: [Option<itoa::Buffer>;10] = [None,None,None,None,None,None,None,None,None,None];
-    let idx = 0;
-    let buf = unsafe { ITOA_BUF[idx].get_or_insert_with(|| itoa::Buffer::new()) };
+    if FORCE_DIRECT.load(Ordering::Relaxed) && CAPTURE.with(|c| c.borrow().is_none()) { let _=write!(io::stdout(),"{}",v); return; }
+    let mut buf = itoa::Buffer::new();
     write_bytes(buf.format(v).as_bytes());
 }
 
 /// Write float
 pub fn write_float(v: f64) { OPERATIONS.fetch_add(1,Ordering::Relaxed);
-    if FORCE_DIRECT.load(Ordering::Relaxed) { let _=write!(io::stdout(),"{}",v); return; }
-    let mut b=ryu::Buffer::new(); write_bytes(b.format(v).as_bytes());
+    let s=super::number_format::format_float(v);
+    write_bytes(s.as_bytes());
 }
 
 /// Write bool
@@ -0,0 +1,190 @@
+// channel.rs - Channels for passing values between threads
+//
+// A channel is a queue that any number of threads send to and, typically,
+// one thread receives from. `recv` blocks until a value arrives, or raises
+// TimeoutError once its timeout runs out:
+//
+//     jobs = channel(int)         # channel_new()
+//     send(jobs, 42)              # channel_send(jobs, &42, Int)
+//     n = recv(jobs)              # *channel_recv(jobs)
+//     n = recv(jobs, 0.5)         # *channel_recv_timeout(jobs, 0.5)
+//
+// Values have a single owner, so a channel never shares one between threads.
+// Numbers, bools, strings and lists (with their elements) are copied when
+// they are sent, and the sender keeps its own. Other values, such as tuples
+// and class instances, are moved: the sender must not use them after
+// sending. What `recv` returns belongs to the receiver. Channels live until
+// the program exits.
+
+use inkwell::context::Context;
+use inkwell::execution_engine::ExecutionEngine;
+use inkwell::module::Module;
+use inkwell::AddressSpace;
+use std::collections::VecDeque;
+use std::ffi::{c_void, CString};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::compiler::runtime::dict::copy_tagged;
+use crate::compiler::runtime::exception::exception_raise_new;
+use crate::compiler::runtime::list::{list_append_tagged, list_get, list_get_tag, list_len, list_new, RawList, TypeTag};
+
+/// What `channel_recv_timeout` returns when it times out, readable as any scalar
+static TIMED_OUT: [u64; 2] = [0; 2];
+
+/// A sent value, owned by the channel until it's received
+struct Message {
+    value: *mut c_void,
+}
+
+// A message's value is reachable from no thread but the channel's
+unsafe impl Send for Message {}
+
+/// A queue of values sent between threads
+pub struct Channel {
+    queue: Mutex<VecDeque<Message>>,
+    ready: Condvar,
+}
+
+impl Channel {
+    fn lock(&self) -> MutexGuard<'_, VecDeque<Message>> {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn raw_channel<'a>(channel: *mut Channel) -> Option<&'a Channel> {
+    unsafe { channel.as_ref() }
+}
+
+/// A copy of `value` that shares nothing with it, or `value` itself for
+/// values that are moved
+fn copy_value(value: *mut c_void, tag: TypeTag) -> *mut c_void {
+    match tag {
+        TypeTag::List if !value.is_null() => {
+            let list = value as *mut RawList;
+            let copy = list_new();
+            for i in 0..list_len(list) {
+                let tag = list_get_tag(list, i);
+                list_append_tagged(copy, copy_value(list_get(list, i), tag), tag);
+            }
+            copy as *mut c_void
+        }
+        _ => copy_tagged(value, tag, true),
+    }
+}
+
+/// Create an empty channel
+#[no_mangle]
+pub extern "C" fn channel_new() -> *mut Channel {
+    Box::into_raw(Box::new(Channel {
+        queue: Mutex::new(VecDeque::new()),
+        ready: Condvar::new(),
+    }))
+}
+
+/// Queue `value`, which has runtime type `tag`, waking a receiver
+#[no_mangle]
+pub extern "C" fn channel_send(channel: *mut Channel, value: *mut c_void, tag: TypeTag) {
+    let Some(channel) = raw_channel(channel) else {
+        return;
+    };
+    let message = Message {
+        value: copy_value(value, tag),
+    };
+    channel.lock().push_back(message);
+    channel.ready.notify_one();
+}
+
+/// Take the oldest value, waiting for one to be sent if there is none
+#[no_mangle]
+pub extern "C" fn channel_recv(channel: *mut Channel) -> *mut c_void {
+    let Some(channel) = raw_channel(channel) else {
+        return TIMED_OUT.as_ptr() as *mut c_void;
+    };
+    let mut queue = channel.lock();
+    loop {
+        if let Some(message) = queue.pop_front() {
+            return message.value;
+        }
+        queue = channel.ready.wait(queue).unwrap_or_else(|e| e.into_inner());
+    }
+}
+
+/// Take the oldest value, waiting at most `seconds` for one. Raises
+/// TimeoutError if none arrives in time.
+#[no_mangle]
+pub extern "C" fn channel_recv_timeout(channel: *mut Channel, seconds: f64) -> *mut c_void {
+    // A timeout too long to represent waits as long as it takes
+    let Ok(timeout) = Duration::try_from_secs_f64(seconds.max(0.0)) else {
+        return channel_recv(channel);
+    };
+    let Some(channel) = raw_channel(channel) else {
+        return TIMED_OUT.as_ptr() as *mut c_void;
+    };
+    let queue = channel.lock();
+    let (mut queue, _) = channel
+        .ready
+        .wait_timeout_while(queue, timeout, |queue| queue.is_empty())
+        .unwrap_or_else(|e| e.into_inner());
+    match queue.pop_front() {
+        Some(message) => message.value,
+        None => {
+            let typ = CString::new("TimeoutError").unwrap();
+            let message = CString::new(format!("recv() timed out after {} seconds", seconds)).unwrap();
+            exception_raise_new(typ.as_ptr(), message.as_ptr());
+            TIMED_OUT.as_ptr() as *mut c_void
+        }
+    }
+}
+
+/// Number of values sent but not yet received
+#[no_mangle]
+pub extern "C" fn channel_len(channel: *mut Channel) -> i64 {
+    raw_channel(channel).map_or(0, |channel| channel.lock().len() as i64)
+}
+
+/// Register channel functions in the LLVM module
+pub fn register_channel_functions<'ctx>(context: &'ctx Context, module: &mut Module<'ctx>) {
+    let ptr_type = context.ptr_type(AddressSpace::default());
+
+    let functions = [
+        ("channel_new", ptr_type.fn_type(&[], false)),
+        (
+            "channel_send",
+            context
+                .void_type()
+                .fn_type(&[ptr_type.into(), ptr_type.into(), context.i8_type().into()], false),
+        ),
+        ("channel_recv", ptr_type.fn_type(&[ptr_type.into()], false)),
+        (
+            "channel_recv_timeout",
+            ptr_type.fn_type(&[ptr_type.into(), context.f64_type().into()], false),
+        ),
+        ("channel_len", context.i64_type().fn_type(&[ptr_type.into()], false)),
+    ];
+    for (name, fn_type) in functions {
+        if module.get_function(name).is_none() {
+            module.add_function(name, fn_type, None);
+        }
+    }
+}
+
+/// Map channel functions to their runtime addresses in the JIT
+pub fn register_channel_runtime_functions(
+    engine: &ExecutionEngine<'_>,
+    module: &Module<'_>,
+) -> Result<(), String> {
+    let mappings: [(&str, usize); 5] = [
+        ("channel_new", channel_new as *const () as usize),
+        ("channel_send", channel_send as *const () as usize),
+        ("channel_recv", channel_recv as *const () as usize),
+        ("channel_recv_timeout", channel_recv_timeout as *const () as usize),
+        ("channel_len", channel_len as *const () as usize),
+    ];
+    for (name, address) in mappings {
+        if let Some(f) = module.get_function(name) {
+            engine.add_global_mapping(&f, address);
+        }
+    }
+    Ok(())
+}
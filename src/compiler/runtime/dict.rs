@@ -1,19 +1,37 @@
 // dict.rs - Combined dictionary runtime & LLVM registration
+//
+// Dictionaries keep insertion order, as in Python 3.7+: iteration, keys(),
+// values(), items() and update() see keys in the order they were first added.
+// Entries are stored densely in that order, and an open-addressing table maps
+// each hash to a position among them. Assigning to an existing key keeps its
+// place; removing a key and adding it again moves it to the end.
 
 use inkwell::context::Context;
+use inkwell::execution_engine::ExecutionEngine;
 use inkwell::module::Module;
 use inkwell::types::{BasicType, BasicTypeEnum, StructType};
 use inkwell::AddressSpace;
 
+use libc::{c_char, calloc, free, malloc};
+use std::ffi::{c_void, CStr, CString};
+use std::hash::{Hash, Hasher};
 use std::ptr;
-use std::ffi::c_void;
+
+use crate::compiler::runtime::exception::{exception_new, exception_raise, set_current_exception};
+use crate::compiler::runtime::list::{tagged_eq, TypeTag};
 
 /// C-compatible dict struct
 #[repr(C)]
 pub struct Dict {
     count: i64,
+    /// Slots in `indices`, a power of two
     capacity: i64,
+    /// Entries in insertion order; removed ones are left with a null key
     entries: *mut DictEntry,
+    /// Entries written so far, removed ones included
+    used: i64,
+    /// Position in `entries` of the key hashed to each slot, or EMPTY / REMOVED
+    indices: *mut i64,
 }
 
 #[repr(C)]
@@ -21,6 +39,8 @@ pub struct DictEntry {
     key: *mut c_void,
     value: *mut c_void,
     hash: i64,
+    key_tag: TypeTag,
+    value_tag: TypeTag,
 }
 
 #[repr(C)]
@@ -59,60 +79,421 @@ unsafe fn tuple_new(length: i64) -> *mut Tuple {
 #[no_mangle]
 pub unsafe extern "C" fn dict_keys(dict: *mut Dict) -> *mut List {
     if dict.is_null() { return ptr::null_mut(); }
-    let count = (*dict).count;
-    let entries = (*dict).entries;
-    let keys_list = list_with_capacity(count);
-    let mut added = 0;
-    for i in 0..(*dict).capacity {
-        let entry = entries.add(i as usize);
-        if !(*entry).key.is_null() {
-            *(*keys_list).data.add(added as usize) = (*entry).key;
-            added += 1;
-        }
+    let keys_list = list_with_capacity((*dict).count);
+    for (i, entry) in live_entries(&*dict).enumerate() {
+        *(*keys_list).data.add(i) = entry.key;
     }
-    (*keys_list).length = added;
+    (*keys_list).length = (*dict).count;
     keys_list
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dict_values(dict: *mut Dict) -> *mut List {
     if dict.is_null() { return ptr::null_mut(); }
-    let count = (*dict).count;
-    let entries = (*dict).entries;
-    let values_list = list_with_capacity(count);
-    let mut added = 0;
-    for i in 0..(*dict).capacity {
-        let entry = entries.add(i as usize);
-        if !(*entry).key.is_null() {
-            *(*values_list).data.add(added as usize) = (*entry).value;
-            added += 1;
-        }
+    let values_list = list_with_capacity((*dict).count);
+    for (i, entry) in live_entries(&*dict).enumerate() {
+        *(*values_list).data.add(i) = entry.value;
     }
-    (*values_list).length = added;
+    (*values_list).length = (*dict).count;
     values_list
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dict_items(dict: *mut Dict) -> *mut List {
     if dict.is_null() { return ptr::null_mut(); }
-    let count = (*dict).count;
-    let entries = (*dict).entries;
-    let items_list = list_with_capacity(count);
-    let mut added = 0;
-    for i in 0..(*dict).capacity {
-        let entry = entries.add(i as usize);
-        if !(*entry).key.is_null() {
-            let tpl = tuple_new(2);
-            *(*tpl).data.add(0) = (*entry).key;
-            *(*tpl).data.add(1) = (*entry).value;
-            *(*items_list).data.add(added as usize) = tpl as *mut c_void;
-            added += 1;
-        }
+    let items_list = list_with_capacity((*dict).count);
+    for (i, entry) in live_entries(&*dict).enumerate() {
+        let tpl = tuple_new(2);
+        *(*tpl).data.add(0) = entry.key;
+        *(*tpl).data.add(1) = entry.value;
+        *(*items_list).data.add(i) = tpl as *mut c_void;
     }
-    (*items_list).length = added;
+    (*items_list).length = (*dict).count;
     items_list
 }
 
+const MIN_CAPACITY: i64 = 8;
+
+/// Index slot that has never held a key; probing stops here
+const EMPTY: i64 = -1;
+
+/// Index slot whose key was removed; probing continues past it
+const REMOVED: i64 = -2;
+
+/// Zeroed storage handed out for missing keys, so loads read 0, 0.0, False or ""
+static MISSING_VALUE: [u64; 2] = [0; 2];
+
+fn missing_value() -> *mut c_void {
+    MISSING_VALUE.as_ptr() as *mut c_void
+}
+
+/// Record and report a KeyError for a failed lookup
+fn raise_key_error() {
+    let typ = CString::new("KeyError").unwrap();
+    let message = CString::new("key not found in dictionary").unwrap();
+    let exception = exception_new(typ.as_ptr(), message.as_ptr());
+    set_current_exception(exception);
+    exception_raise(exception);
+}
+
+fn raw_dict<'a>(dict: *mut Dict) -> Option<&'a mut Dict> {
+    unsafe { dict.as_mut() }
+}
+
+/// Hash a tagged key; numbers that compare equal hash equally (`1 == 1.0 == True`)
+fn hash_key(key: *mut c_void, tag: TypeTag) -> i64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    unsafe {
+        match tag {
+            _ if key.is_null() => 0u8.hash(&mut hasher),
+            TypeTag::Int => (*(key as *const i64)).hash(&mut hasher),
+            TypeTag::Bool => ((*(key as *const u8) != 0) as i64).hash(&mut hasher),
+            TypeTag::Float => {
+                let f = *(key as *const f64);
+                if f.fract() == 0.0 && f.abs() < i64::MAX as f64 {
+                    (f as i64).hash(&mut hasher)
+                } else {
+                    f.to_bits().hash(&mut hasher)
+                }
+            }
+            TypeTag::String => CStr::from_ptr(key as *const c_char).hash(&mut hasher),
+            _ => (key as usize).hash(&mut hasher),
+        }
+    }
+    hasher.finish() as i64
+}
+
+/// Copy a scalar (or string key) into storage owned by the dictionary
+pub(crate) fn copy_tagged(value: *mut c_void, tag: TypeTag, copy_strings: bool) -> *mut c_void {
+    if value.is_null() { return value; }
+    unsafe {
+        match tag {
+            TypeTag::Bool => {
+                let out = malloc(1) as *mut u8;
+                *out = *(value as *const u8);
+                out as *mut c_void
+            }
+            TypeTag::Int | TypeTag::Float => {
+                let out = malloc(8) as *mut u64;
+                *out = *(value as *const u64);
+                out as *mut c_void
+            }
+            TypeTag::String if copy_strings => libc::strdup(value as *const c_char) as *mut c_void,
+            _ => value,
+        }
+    }
+}
+
+/// Free storage created by `copy_tagged`
+unsafe fn free_tagged(value: *mut c_void, tag: TypeTag, owns_strings: bool) {
+    if value.is_null() { return; }
+    match tag {
+        TypeTag::Bool | TypeTag::Int | TypeTag::Float => free(value),
+        TypeTag::String if owns_strings => free(value),
+        _ => {}
+    }
+}
+
+/// Entries a table with `capacity` slots holds before it's rebuilt
+fn usable(capacity: i64) -> i64 {
+    capacity * 3 / 4
+}
+
+/// Smallest table that holds `count` entries
+fn capacity_for(count: i64) -> i64 {
+    let mut capacity = MIN_CAPACITY;
+    while usable(capacity) < count {
+        capacity *= 2;
+    }
+    capacity
+}
+
+unsafe fn alloc_entries(capacity: i64) -> *mut DictEntry {
+    calloc(usable(capacity) as usize, std::mem::size_of::<DictEntry>()) as *mut DictEntry
+}
+
+unsafe fn alloc_indices(capacity: i64) -> *mut i64 {
+    let indices = malloc(capacity as usize * std::mem::size_of::<i64>()) as *mut i64;
+    // All bits set is EMPTY
+    ptr::write_bytes(indices, 0xFF, capacity as usize);
+    indices
+}
+
+/// Entries still in the dictionary, in insertion order
+fn live_entries(dict: &Dict) -> impl Iterator<Item = &DictEntry> {
+    (0..dict.used as usize)
+        .map(move |i| unsafe { &*dict.entries.add(i) })
+        .filter(|entry| !entry.key.is_null())
+}
+
+/// Index slot holding `key` with the position of its entry, or the slot
+/// where it would be inserted
+fn find_slot(dict: &Dict, key: *mut c_void, tag: TypeTag, hash: i64) -> (usize, Option<usize>) {
+    let mask = (dict.capacity - 1) as usize;
+    let mut i = (hash as u64 as usize) & mask;
+    let mut reusable = None;
+    loop {
+        match unsafe { *dict.indices.add(i) } {
+            EMPTY => return (reusable.unwrap_or(i), None),
+            REMOVED => {
+                reusable.get_or_insert(i);
+            }
+            index => {
+                let entry = unsafe { &*dict.entries.add(index as usize) };
+                if entry.hash == hash && tagged_eq(entry.key, entry.key_tag, key, tag) {
+                    return (i, Some(index as usize));
+                }
+            }
+        }
+        i = (i + 1) & mask;
+    }
+}
+
+/// Move the live entries, still in order, into a fresh table of `capacity` slots
+unsafe fn rebuild(dict: &mut Dict, capacity: i64) {
+    let old_entries = dict.entries;
+    let old_used = dict.used;
+    free(dict.indices as *mut c_void);
+    dict.capacity = capacity;
+    dict.entries = alloc_entries(capacity);
+    dict.indices = alloc_indices(capacity);
+    dict.used = 0;
+    for i in 0..old_used as usize {
+        let entry = ptr::read(old_entries.add(i));
+        if !entry.key.is_null() {
+            let (slot, _) = find_slot(dict, entry.key, entry.key_tag, entry.hash);
+            *dict.indices.add(slot) = dict.used;
+            ptr::write(dict.entries.add(dict.used as usize), entry);
+            dict.used += 1;
+        }
+    }
+    free(old_entries as *mut c_void);
+}
+
+/// Look up `key`, returning the position of its entry when present
+fn lookup(dict: *mut Dict, key: *mut c_void, tag: TypeTag) -> Option<(&'static mut Dict, usize)> {
+    let d = raw_dict(dict)?;
+    if d.count == 0 { return None; }
+    let hash = hash_key(key, tag);
+    match find_slot(d, key, tag, hash) {
+        (_, Some(index)) => Some((d, index)),
+        _ => None,
+    }
+}
+
+/// Unlink the entry for `key` and hand it back; the caller frees what it owns
+fn take_entry(dict: *mut Dict, key: *mut c_void, tag: TypeTag) -> Option<DictEntry> {
+    let d = raw_dict(dict)?;
+    if d.count == 0 { return None; }
+    let hash = hash_key(key, tag);
+    match find_slot(d, key, tag, hash) {
+        (slot, Some(index)) => unsafe {
+            *d.indices.add(slot) = REMOVED;
+            let entry = ptr::read(d.entries.add(index));
+            (*d.entries.add(index)).key = ptr::null_mut();
+            d.count -= 1;
+            Some(entry)
+        },
+        _ => None,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn dict_new() -> *mut Dict {
+    dict_with_capacity(MIN_CAPACITY)
+}
+
+#[no_mangle]
+pub extern "C" fn dict_with_capacity(capacity: i64) -> *mut Dict {
+    let capacity = capacity_for(capacity.max(1));
+    unsafe {
+        let dict = malloc(std::mem::size_of::<Dict>()) as *mut Dict;
+        if dict.is_null() { return dict; }
+        (*dict).count = 0;
+        (*dict).capacity = capacity;
+        (*dict).entries = alloc_entries(capacity);
+        (*dict).used = 0;
+        (*dict).indices = alloc_indices(capacity);
+        dict
+    }
+}
+
+/// d[key] - reports a KeyError and yields zeroed storage when the key is missing
+#[no_mangle]
+pub extern "C" fn dict_get(dict: *mut Dict, key: *mut c_void, key_tag: TypeTag) -> *mut c_void {
+    match lookup(dict, key, key_tag) {
+        Some((d, index)) => unsafe { (*d.entries.add(index)).value },
+        None => {
+            raise_key_error();
+            missing_value()
+        }
+    }
+}
+
+/// d.get(key, default) - returns `default` when the key is missing
+#[no_mangle]
+pub extern "C" fn dict_get_default(
+    dict: *mut Dict,
+    key: *mut c_void,
+    default: *mut c_void,
+    key_tag: TypeTag,
+) -> *mut c_void {
+    match lookup(dict, key, key_tag) {
+        Some((d, index)) => unsafe { (*d.entries.add(index)).value },
+        None => default,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn dict_set(
+    dict: *mut Dict,
+    key: *mut c_void,
+    value: *mut c_void,
+    key_tag: TypeTag,
+    value_tag: TypeTag,
+) {
+    let d = match raw_dict(dict) { Some(d) => d, None => return };
+    unsafe {
+        let hash = hash_key(key, key_tag);
+        let value = copy_tagged(value, value_tag, false);
+        match find_slot(d, key, key_tag, hash) {
+            // An existing key keeps its place in the order
+            (_, Some(index)) => {
+                let entry = &mut *d.entries.add(index);
+                free_tagged(entry.value, entry.value_tag, false);
+                entry.value = value;
+                entry.value_tag = value_tag;
+            }
+            (mut slot, None) => {
+                if d.used == usable(d.capacity) {
+                    rebuild(d, capacity_for((d.count + 1) * 2));
+                    slot = find_slot(d, key, key_tag, hash).0;
+                }
+                *d.indices.add(slot) = d.used;
+                ptr::write(
+                    d.entries.add(d.used as usize),
+                    DictEntry {
+                        key: copy_tagged(key, key_tag, true),
+                        value,
+                        hash,
+                        key_tag,
+                        value_tag,
+                    },
+                );
+                d.used += 1;
+                d.count += 1;
+            }
+        }
+    }
+}
+
+/// d.setdefault(key, default) - inserts `default` if missing and returns the stored value
+#[no_mangle]
+pub extern "C" fn dict_setdefault(
+    dict: *mut Dict,
+    key: *mut c_void,
+    default: *mut c_void,
+    key_tag: TypeTag,
+    value_tag: TypeTag,
+) -> *mut c_void {
+    if lookup(dict, key, key_tag).is_none() {
+        dict_set(dict, key, default, key_tag, value_tag);
+    }
+    dict_get_default(dict, key, missing_value(), key_tag)
+}
+
+#[no_mangle]
+pub extern "C" fn dict_contains(dict: *mut Dict, key: *mut c_void, key_tag: TypeTag) -> u8 {
+    lookup(dict, key, key_tag).is_some() as u8
+}
+
+#[no_mangle]
+pub extern "C" fn dict_remove(dict: *mut Dict, key: *mut c_void, key_tag: TypeTag) -> u8 {
+    match take_entry(dict, key, key_tag) {
+        Some(entry) => unsafe {
+            free_tagged(entry.key, entry.key_tag, true);
+            free_tagged(entry.value, entry.value_tag, false);
+            1
+        },
+        None => 0,
+    }
+}
+
+/// d.pop(key[, default]) - a null `default` reports a KeyError for missing keys
+#[no_mangle]
+pub extern "C" fn dict_pop(
+    dict: *mut Dict,
+    key: *mut c_void,
+    default: *mut c_void,
+    key_tag: TypeTag,
+) -> *mut c_void {
+    match take_entry(dict, key, key_tag) {
+        // The popped value is handed to the caller, so only the key copy is freed
+        Some(entry) => unsafe {
+            free_tagged(entry.key, entry.key_tag, true);
+            entry.value
+        },
+        None if !default.is_null() => default,
+        None => {
+            raise_key_error();
+            missing_value()
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn dict_clear(dict: *mut Dict) {
+    let d = match raw_dict(dict) { Some(d) => d, None => return };
+    unsafe {
+        for entry in live_entries(d) {
+            free_tagged(entry.key, entry.key_tag, true);
+            free_tagged(entry.value, entry.value_tag, false);
+        }
+        ptr::write_bytes(d.entries, 0, d.used as usize);
+        ptr::write_bytes(d.indices, 0xFF, d.capacity as usize);
+    }
+    d.count = 0;
+    d.used = 0;
+}
+
+#[no_mangle]
+pub extern "C" fn dict_len(dict: *mut Dict) -> i64 {
+    raw_dict(dict).map(|d| d.count).unwrap_or(0)
+}
+
+#[no_mangle]
+pub extern "C" fn dict_free(dict: *mut Dict) {
+    dict_clear(dict);
+    if let Some(d) = raw_dict(dict) {
+        unsafe {
+            free(d.entries as *mut c_void);
+            free(d.indices as *mut c_void);
+            free(dict as *mut c_void);
+        }
+    }
+}
+
+/// d.update(other) - copies every entry of `other` into `dict`, in `other`'s order
+#[no_mangle]
+pub extern "C" fn dict_update(dict: *mut Dict, other: *mut Dict) {
+    let o = match raw_dict(other) { Some(o) => o, None => return };
+    let entries: Vec<(*mut c_void, *mut c_void, TypeTag, TypeTag)> = live_entries(o)
+        .map(|e| (e.key, e.value, e.key_tag, e.value_tag))
+        .collect();
+    for (key, value, key_tag, value_tag) in entries {
+        dict_set(dict, key, value, key_tag, value_tag);
+    }
+}
+
+/// {**a, **b} - a new dictionary holding the entries of `a`, then those of `b`
+#[no_mangle]
+pub extern "C" fn dict_merge(a: *mut Dict, b: *mut Dict) -> *mut Dict {
+    let out = dict_with_capacity(dict_len(a) + dict_len(b));
+    dict_update(out, a);
+    dict_update(out, b);
+    out
+}
+
 /// Register dictionary functions in the LLVM module
 pub fn register_dict_functions<'ctx>(context: &'ctx Context, module: &mut Module<'ctx>) {
     context.struct_type(
@@ -126,6 +507,8 @@ pub fn register_dict_functions<'ctx>(context: &'ctx Context, module: &mut Module
             context.i64_type().into(),
             context.i64_type().into(),
             context.ptr_type(AddressSpace::default()).into(),
+            context.i64_type().into(),
+            context.ptr_type(AddressSpace::default()).into(),
         ], false);
 
     module.add_function(
@@ -143,6 +526,17 @@ pub fn register_dict_functions<'ctx>(context: &'ctx Context, module: &mut Module
         context.ptr_type(AddressSpace::default()).fn_type(&[
             context.ptr_type(AddressSpace::default()).into(),
             context.ptr_type(AddressSpace::default()).into(),
+            context.i8_type().into(),
+        ], false),
+        None,
+    );
+    module.add_function(
+        "dict_get_default",
+        context.ptr_type(AddressSpace::default()).fn_type(&[
+            context.ptr_type(AddressSpace::default()).into(),
+            context.ptr_type(AddressSpace::default()).into(),
+            context.ptr_type(AddressSpace::default()).into(),
+            context.i8_type().into(),
         ], false),
         None,
     );
@@ -152,6 +546,19 @@ pub fn register_dict_functions<'ctx>(context: &'ctx Context, module: &mut Module
             context.ptr_type(AddressSpace::default()).into(),
             context.ptr_type(AddressSpace::default()).into(),
             context.ptr_type(AddressSpace::default()).into(),
+            context.i8_type().into(),
+            context.i8_type().into(),
+        ], false),
+        None,
+    );
+    module.add_function(
+        "dict_setdefault",
+        context.ptr_type(AddressSpace::default()).fn_type(&[
+            context.ptr_type(AddressSpace::default()).into(),
+            context.ptr_type(AddressSpace::default()).into(),
+            context.ptr_type(AddressSpace::default()).into(),
+            context.i8_type().into(),
+            context.i8_type().into(),
         ], false),
         None,
     );
@@ -160,6 +567,7 @@ pub fn register_dict_functions<'ctx>(context: &'ctx Context, module: &mut Module
         context.i8_type().fn_type(&[
             context.ptr_type(AddressSpace::default()).into(),
             context.ptr_type(AddressSpace::default()).into(),
+            context.i8_type().into(),
         ], false),
         None,
     );
@@ -168,6 +576,17 @@ pub fn register_dict_functions<'ctx>(context: &'ctx Context, module: &mut Module
         context.i8_type().fn_type(&[
             context.ptr_type(AddressSpace::default()).into(),
             context.ptr_type(AddressSpace::default()).into(),
+            context.i8_type().into(),
+        ], false),
+        None,
+    );
+    module.add_function(
+        "dict_pop",
+        context.ptr_type(AddressSpace::default()).fn_type(&[
+            context.ptr_type(AddressSpace::default()).into(),
+            context.ptr_type(AddressSpace::default()).into(),
+            context.ptr_type(AddressSpace::default()).into(),
+            context.i8_type().into(),
         ], false),
         None,
     );
@@ -225,6 +644,8 @@ pub fn get_dict_struct_type<'ctx>(context: &'ctx Context) -> StructType<'ctx> {
             context.i64_type().into(),
             context.i64_type().into(),
             context.ptr_type(AddressSpace::default()).into(),
+            context.i64_type().into(),
+            context.ptr_type(AddressSpace::default()).into(),
         ],
         false,
     )
@@ -236,6 +657,8 @@ pub fn get_dict_entry_struct_type<'ctx>(context: &'ctx Context) -> StructType<'c
             context.ptr_type(AddressSpace::default()).into(),
             context.ptr_type(AddressSpace::default()).into(),
             context.i64_type().into(),
+            context.i8_type().into(),
+            context.i8_type().into(),
         ],
         false,
     )
@@ -243,4 +666,36 @@ pub fn get_dict_entry_struct_type<'ctx>(context: &'ctx Context) -> StructType<'c
 
 pub fn get_dict_element_ptr_type<'ctx>(context: &'ctx Context) -> BasicTypeEnum<'ctx> {
     context.ptr_type(AddressSpace::default()).as_basic_type_enum()
-}
\ No newline at end of file
+}
+
+/// Register dictionary runtime mappings for the JIT engine
+pub fn register_dict_runtime_functions(
+    engine: &ExecutionEngine<'_>,
+    module: &Module<'_>,
+) -> Result<(), String> {
+    let mappings: [(&str, usize); 17] = [
+        ("dict_new", dict_new as *const () as usize),
+        ("dict_with_capacity", dict_with_capacity as *const () as usize),
+        ("dict_get", dict_get as *const () as usize),
+        ("dict_get_default", dict_get_default as *const () as usize),
+        ("dict_set", dict_set as *const () as usize),
+        ("dict_setdefault", dict_setdefault as *const () as usize),
+        ("dict_contains", dict_contains as *const () as usize),
+        ("dict_remove", dict_remove as *const () as usize),
+        ("dict_pop", dict_pop as *const () as usize),
+        ("dict_clear", dict_clear as *const () as usize),
+        ("dict_len", dict_len as *const () as usize),
+        ("dict_free", dict_free as *const () as usize),
+        ("dict_merge", dict_merge as *const () as usize),
+        ("dict_update", dict_update as *const () as usize),
+        ("dict_keys", dict_keys as *const () as usize),
+        ("dict_values", dict_values as *const () as usize),
+        ("dict_items", dict_items as *const () as usize),
+    ];
+    for (name, address) in mappings {
+        if let Some(f) = module.get_function(name) {
+            engine.add_global_mapping(&f, address);
+        }
+    }
+    Ok(())
+}
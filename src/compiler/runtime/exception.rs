@@ -1,11 +1,18 @@
 // exception.rs - Combined exception operations, state management, and runtime
 
-use std::ffi::{CStr, CString};
+use std::cell::RefCell;
+use std::ffi::{c_void, CStr, CString};
 use std::os::raw::c_char;
 use std::ptr;
 use inkwell::context::Context;
+use inkwell::execution_engine::ExecutionEngine;
 use inkwell::module::Module;
 
+use crate::compiler::runtime::list::{
+    list_append_tagged, list_free, list_get, list_get_tag, list_len, list_new, RawList, TypeTag,
+};
+use crate::compiler::runtime::traceback::format_traceback;
+
 use inkwell::AddressSpace;
 
 /// Exception object
@@ -13,6 +20,8 @@ use inkwell::AddressSpace;
 pub struct Exception {
     typ: *mut c_char,
     message: *mut c_char,
+    /// Constructor arguments; built lazily from the message when null
+    args: *mut RawList,
 }
 
 // -------- C-compatible runtime functions --------
@@ -30,10 +39,81 @@ pub extern "C" fn exception_new(
     let exc = Box::new(Exception {
         typ: typ_owned.into_raw(),
         message: msg_owned.into_raw(),
+        args: ptr::null_mut(),
     });
     Box::into_raw(exc)
 }
 
+/// Create an exception from its constructor arguments, as in `ValueError("bad", 3)`.
+///
+/// The exception takes ownership of `args`. Its message follows `str(e)`: empty for no
+/// arguments, the argument itself for one, and the tuple repr for several.
+#[unsafe(no_mangle)]
+pub extern "C" fn exception_new_with_args(
+    typ: *const c_char,
+    args: *mut RawList
+) -> *mut Exception {
+    let parts: Vec<(*mut c_void, TypeTag)> = (0..list_len(args))
+        .map(|i| (list_get(args, i), list_get_tag(args, i)))
+        .collect();
+    let message = match parts.as_slice() {
+        [] => String::new(),
+        [(value, tag)] => format_arg(*value, *tag, false),
+        _ => {
+            let reprs: Vec<String> = parts.iter().map(|(v, t)| format_arg(*v, *t, true)).collect();
+            format!("({})", reprs.join(", "))
+        }
+    };
+    let message = CString::new(message.replace('\0', "")).unwrap();
+    let exception = exception_new(typ, message.as_ptr());
+    if let Some(e) = raw_exception(exception) {
+        e.args = args;
+    }
+    exception
+}
+
+/// Get the exception's constructor arguments as a list
+#[unsafe(no_mangle)]
+pub extern "C" fn exception_get_args(exception: *mut Exception) -> *mut RawList {
+    let e = match raw_exception(exception) {
+        Some(e) => e,
+        None => return list_new(),
+    };
+    if e.args.is_null() {
+        e.args = list_new();
+        let message = unsafe { CStr::from_ptr(e.message) };
+        if !message.to_bytes().is_empty() {
+            let copy = CString::from(message).into_raw();
+            list_append_tagged(e.args, copy as *mut c_void, TypeTag::String);
+        }
+    }
+    e.args
+}
+
+fn raw_exception<'a>(exception: *mut Exception) -> Option<&'a mut Exception> {
+    unsafe { exception.as_mut() }
+}
+
+/// Format a tagged argument like `str()` or, when `repr` is set, like `repr()`
+pub(crate) fn format_arg(value: *mut c_void, tag: TypeTag, repr: bool) -> String {
+    if value.is_null() {
+        return "None".to_string();
+    }
+    unsafe {
+        match tag {
+            TypeTag::Int => (*(value as *const i64)).to_string(),
+            TypeTag::Float => super::number_format::format_float(*(value as *const f64)),
+            TypeTag::Bool => if *(value as *const u8) != 0 { "True" } else { "False" }.to_string(),
+            TypeTag::None_ => "None".to_string(),
+            TypeTag::String => {
+                let text = CStr::from_ptr(value as *const c_char).to_string_lossy();
+                if repr { format!("'{}'", text) } else { text.into_owned() }
+            }
+            _ => format!("<object at {:p}>", value),
+        }
+    }
+}
+
 /// Raise an exception (logs to stderr)
 #[unsafe(no_mangle)]
 pub extern "C" fn exception_raise(exception: *mut Exception) {
@@ -41,9 +121,21 @@ pub extern "C" fn exception_raise(exception: *mut Exception) {
     let e = unsafe { &*exception };
     let typ = unsafe { CStr::from_ptr(e.typ).to_string_lossy() };
     let msg = unsafe { CStr::from_ptr(e.message).to_string_lossy() };
+    if let Some(traceback) = format_traceback() {
+        eprint!("{}", traceback);
+    }
     eprintln!("Exception raised: {} - {}", typ, msg);
 }
 
+/// Raise a new exception for an error detected by compiled code, such as a
+/// division by zero, so `except` blocks can catch it
+#[unsafe(no_mangle)]
+pub extern "C" fn exception_raise_new(typ: *const c_char, message: *const c_char) {
+    let exception = exception_new(typ, message);
+    set_current_exception(exception);
+    exception_raise(exception);
+}
+
 /// Check exception type
 #[unsafe(no_mangle)]
 pub extern "C" fn exception_check(
@@ -86,28 +178,90 @@ pub extern "C" fn exception_free(exception: *mut Exception) {
     let e = unsafe { Box::from_raw(exception) };
     unsafe { let _ = CString::from_raw(e.typ); }
     unsafe { let _ = CString::from_raw(e.message); }
+    if !e.args.is_null() {
+        list_free(e.args);
+    }
 }
 
-// -------- Global exception state --------
+// -------- Per-thread exception state --------
 
-static mut GLOBAL_EXCEPTION: *mut Exception = ptr::null_mut();
+/// Exception state of one thread: the raised exception still looking for a handler,
+/// and the exceptions being handled by the enclosing `except` blocks, innermost last
+struct ExceptionState {
+    pending: *mut Exception,
+    handling: Vec<*mut Exception>,
+}
 
-/// Get current exception
+thread_local! {
+    static EXCEPTION_STATE: RefCell<ExceptionState> = const {
+        RefCell::new(ExceptionState {
+            pending: ptr::null_mut(),
+            handling: Vec::new(),
+        })
+    };
+}
+
+/// Get current exception: the pending one, or else the one being handled
 #[no_mangle]
 pub extern "C" fn get_current_exception() -> *mut Exception {
-    unsafe { GLOBAL_EXCEPTION }
+    EXCEPTION_STATE.with(|state| {
+        let state = state.borrow();
+        if state.pending.is_null() {
+            state.handling.last().copied().unwrap_or(ptr::null_mut())
+        } else {
+            state.pending
+        }
+    })
 }
 
-/// Set current exception
+/// Set current exception (marks it as raised and pending)
 #[no_mangle]
 pub extern "C" fn set_current_exception(exc: *mut Exception) {
-    unsafe { GLOBAL_EXCEPTION = exc; }
+    EXCEPTION_STATE.with(|state| state.borrow_mut().pending = exc);
 }
 
 /// Clear current exception
 #[no_mangle]
 pub extern "C" fn clear_current_exception() {
-    unsafe { GLOBAL_EXCEPTION = ptr::null_mut(); }
+    EXCEPTION_STATE.with(|state| state.borrow_mut().pending = ptr::null_mut());
+}
+
+/// Whether a raised exception is waiting for a handler
+#[no_mangle]
+pub extern "C" fn exception_is_pending() -> bool {
+    EXCEPTION_STATE.with(|state| !state.borrow().pending.is_null())
+}
+
+/// Enter an `except` block: the pending exception becomes the one being handled
+#[no_mangle]
+pub extern "C" fn exception_enter_handler() -> *mut Exception {
+    EXCEPTION_STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let exception = std::mem::replace(&mut state.pending, ptr::null_mut());
+        state.handling.push(exception);
+        exception
+    })
+}
+
+/// Leave an `except` block, restoring the exception handled by the enclosing one.
+/// An exception raised inside the block stays pending.
+#[no_mangle]
+pub extern "C" fn exception_exit_handler() {
+    EXCEPTION_STATE.with(|state| {
+        state.borrow_mut().handling.pop();
+    });
+}
+
+/// Number of `except` blocks currently executing on this thread
+#[no_mangle]
+pub extern "C" fn exception_handler_depth() -> i64 {
+    EXCEPTION_STATE.with(|state| state.borrow().handling.len() as i64)
+}
+
+/// Leave the `except` blocks entered after `depth` of them, as when the
+/// functions running them were interrupted before they could
+pub fn truncate_handlers(depth: usize) {
+    EXCEPTION_STATE.with(|state| state.borrow_mut().handling.truncate(depth));
 }
 
 // -------- LLVM module registration --------
@@ -120,7 +274,7 @@ pub fn register_exception_functions<'ctx>(
     let ptr_t = context.ptr_type(AddressSpace::default());
     // Exception struct type
     let _ = context.struct_type(
-        &[ptr_t.into(), ptr_t.into()],
+        &[ptr_t.into(), ptr_t.into(), ptr_t.into()],
         false
     );
     // exception_new
@@ -129,12 +283,30 @@ pub fn register_exception_functions<'ctx>(
         ptr_t.fn_type(&[ptr_t.into(), ptr_t.into()], false),
         None,
     );
+    // exception_new_with_args
+    module.add_function(
+        "exception_new_with_args",
+        ptr_t.fn_type(&[ptr_t.into(), ptr_t.into()], false),
+        None,
+    );
+    // exception_get_args
+    module.add_function(
+        "exception_get_args",
+        ptr_t.fn_type(&[ptr_t.into()], false),
+        None,
+    );
     // exception_raise
     module.add_function(
         "exception_raise",
         context.void_type().fn_type(&[ptr_t.into()], false),
         None,
     );
+    // exception_raise_new
+    module.add_function(
+        "exception_raise_new",
+        context.void_type().fn_type(&[ptr_t.into(), ptr_t.into()], false),
+        None,
+    );
     // exception_check
     module.add_function(
         "exception_check",
@@ -161,37 +333,78 @@ pub fn register_exception_functions<'ctx>(
     );
 }
 
-/// Register exception state functions and global
+/// Register the per-thread exception state functions
 pub fn register_exception_state<'ctx>(
     context: &'ctx Context,
     module: &mut Module<'ctx>
 ) {
     let ptr_t = context.ptr_type(AddressSpace::default());
-    // Global variable
-    let global = module.add_global(ptr_t, None, "__current_exception");
-    global.set_initializer(&ptr_t.const_null());
-
-    // Declare these functions as external instead of defining them
-    // This prevents duplicate symbol errors during linking
 
-    // get_current_exception - declare as external
+    // The state lives in the runtime, per thread, so these are external declarations
     module.add_function(
         "get_current_exception",
         ptr_t.fn_type(&[], false),
         None,
     );
-
-    // set_current_exception - declare as external
     module.add_function(
         "set_current_exception",
         context.void_type().fn_type(&[ptr_t.into()], false),
         None,
     );
-
-    // clear_current_exception - declare as external
     module.add_function(
         "clear_current_exception",
         context.void_type().fn_type(&[], false),
         None,
     );
+    module.add_function(
+        "exception_is_pending",
+        context.bool_type().fn_type(&[], false),
+        None,
+    );
+    module.add_function(
+        "exception_enter_handler",
+        ptr_t.fn_type(&[], false),
+        None,
+    );
+    module.add_function(
+        "exception_exit_handler",
+        context.void_type().fn_type(&[], false),
+        None,
+    );
+    module.add_function(
+        "exception_handler_depth",
+        context.i64_type().fn_type(&[], false),
+        None,
+    );
+}
+
+/// Register exception runtime mappings for the JIT engine
+pub fn register_exception_runtime_functions(
+    engine: &ExecutionEngine<'_>,
+    module: &Module<'_>,
+) -> Result<(), String> {
+    let mappings: [(&str, usize); 16] = [
+        ("exception_new", exception_new as *const () as usize),
+        ("exception_new_with_args", exception_new_with_args as *const () as usize),
+        ("exception_get_args", exception_get_args as *const () as usize),
+        ("exception_raise", exception_raise as *const () as usize),
+        ("exception_raise_new", exception_raise_new as *const () as usize),
+        ("exception_check", exception_check as *const () as usize),
+        ("exception_get_message", exception_get_message as *const () as usize),
+        ("exception_get_type", exception_get_type as *const () as usize),
+        ("exception_free", exception_free as *const () as usize),
+        ("get_current_exception", get_current_exception as *const () as usize),
+        ("set_current_exception", set_current_exception as *const () as usize),
+        ("clear_current_exception", clear_current_exception as *const () as usize),
+        ("exception_is_pending", exception_is_pending as *const () as usize),
+        ("exception_enter_handler", exception_enter_handler as *const () as usize),
+        ("exception_exit_handler", exception_exit_handler as *const () as usize),
+        ("exception_handler_depth", exception_handler_depth as *const () as usize),
+    ];
+    for (name, address) in mappings {
+        if let Some(f) = module.get_function(name) {
+            engine.add_global_mapping(&f, address);
+        }
+    }
+    Ok(())
 }
@@ -0,0 +1,361 @@
+// file.rs - Runtime support for file objects returned by open()
+
+use inkwell::context::Context;
+use inkwell::execution_engine::ExecutionEngine;
+use inkwell::module::Module;
+use inkwell::AddressSpace;
+use std::ffi::{CStr, CString};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::raw::c_char;
+
+use crate::compiler::runtime::exception::{exception_new, exception_raise, set_current_exception};
+
+/// An open file. Reads are buffered; writes go straight to the file so
+/// nothing is lost when a program forgets to call close().
+pub struct FileHandle {
+    reader: Option<BufReader<File>>,
+    writer: Option<File>,
+    encoding: Encoding,
+}
+
+/// Text encoding of a file, chosen with open(..., encoding=...)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Latin1,
+    Ascii,
+}
+
+impl Encoding {
+    /// Encoding for a Python codec name, e.g. "utf-8", "latin-1" or "ascii"
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().replace('_', "-").as_str() {
+            "utf-8" | "utf8" | "u8" => Some(Encoding::Utf8),
+            "latin-1" | "latin1" | "iso-8859-1" | "iso8859-1" | "l1" => Some(Encoding::Latin1),
+            "ascii" | "us-ascii" => Some(Encoding::Ascii),
+            _ => None,
+        }
+    }
+
+    /// Python's name for the codec
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Latin1 => "latin-1",
+            Encoding::Ascii => "ascii",
+        }
+    }
+
+    /// Decode bytes read from a file, or the message of a UnicodeDecodeError
+    pub fn decode(self, bytes: Vec<u8>) -> Result<String, String> {
+        match self {
+            Encoding::Utf8 => String::from_utf8(bytes).map_err(|e| {
+                let position = e.utf8_error().valid_up_to();
+                format!(
+                    "'utf-8' codec can't decode byte 0x{:02x} in position {}: invalid utf-8",
+                    e.as_bytes()[position],
+                    position
+                )
+            }),
+            Encoding::Latin1 => Ok(bytes.iter().map(|&b| b as char).collect()),
+            Encoding::Ascii => match bytes.iter().position(|b| !b.is_ascii()) {
+                Some(position) => Err(format!(
+                    "'ascii' codec can't decode byte 0x{:02x} in position {}: ordinal not in range(128)",
+                    bytes[position], position
+                )),
+                None => Ok(bytes.iter().map(|&b| b as char).collect()),
+            },
+        }
+    }
+
+    /// Encode text written to a file, or the message of a UnicodeEncodeError
+    pub fn encode(self, text: &str) -> Result<Vec<u8>, String> {
+        let limit = match self {
+            Encoding::Utf8 => return Ok(text.as_bytes().to_vec()),
+            Encoding::Latin1 => 0x100,
+            Encoding::Ascii => 0x80,
+        };
+        text.chars()
+            .enumerate()
+            .map(|(position, c)| {
+                if (c as u32) < limit {
+                    Ok(c as u8)
+                } else {
+                    Err(format!(
+                        "'{}' codec can't encode character '\\u{:04x}' in position {}: ordinal not in range({})",
+                        self.name(),
+                        c as u32,
+                        position,
+                        limit
+                    ))
+                }
+            })
+            .collect()
+    }
+}
+
+fn raise_error(typ: &str, message: &str) {
+    let typ = CString::new(typ).unwrap();
+    let message = CString::new(message.replace('\0', "")).unwrap();
+    let exception = exception_new(typ.as_ptr(), message.as_ptr());
+    set_current_exception(exception);
+    exception_raise(exception);
+}
+
+fn c_string(value: String) -> *mut c_char {
+    CString::new(value.replace('\0', "")).unwrap_or_default().into_raw()
+}
+
+fn str_arg(value: *const c_char) -> Option<String> {
+    if value.is_null() {
+        return None;
+    }
+    Some(unsafe { CStr::from_ptr(value) }.to_string_lossy().into_owned())
+}
+
+fn handle<'a>(file: *mut FileHandle) -> Option<&'a mut FileHandle> {
+    unsafe { file.as_mut() }
+}
+
+/// The handle behind a file pointer, raising ValueError if it has been closed
+fn open_handle<'a>(file: *mut FileHandle) -> Option<&'a mut FileHandle> {
+    match handle(file) {
+        Some(handle) if handle.reader.is_some() || handle.writer.is_some() => Some(handle),
+        _ => {
+            raise_error("ValueError", "I/O operation on closed file.");
+            None
+        }
+    }
+}
+
+/// Open options for a Python mode string; `b` and `t` are accepted and ignored
+fn open_options(mode: &str) -> Option<(OpenOptions, bool)> {
+    let base: String = mode.chars().filter(|c| !matches!(c, 'b' | 't')).collect();
+    let mut options = OpenOptions::new();
+    let readable = match base.as_str() {
+        "r" => {
+            options.read(true);
+            true
+        }
+        "w" => {
+            options.write(true).create(true).truncate(true);
+            false
+        }
+        "a" => {
+            options.append(true).create(true);
+            false
+        }
+        "x" => {
+            options.write(true).create_new(true);
+            false
+        }
+        _ => return None,
+    };
+    Some((options, readable))
+}
+
+/// open(path, mode): a new UTF-8 file object, or null after raising an exception
+#[no_mangle]
+pub extern "C" fn file_open(path: *const c_char, mode: *const c_char) -> *mut FileHandle {
+    file_open_encoded(path, mode, std::ptr::null())
+}
+
+/// open(path, mode, encoding=...): a new file object, or null after raising an
+/// exception. A null encoding means UTF-8.
+#[no_mangle]
+pub extern "C" fn file_open_encoded(
+    path: *const c_char,
+    mode: *const c_char,
+    encoding: *const c_char,
+) -> *mut FileHandle {
+    let path = str_arg(path).unwrap_or_default();
+    let mode = str_arg(mode).unwrap_or_else(|| "r".to_string());
+
+    let (options, readable) = match open_options(&mode) {
+        Some(options) => options,
+        None => {
+            raise_error("ValueError", &format!("invalid mode: '{}'", mode));
+            return std::ptr::null_mut();
+        }
+    };
+    let encoding = match str_arg(encoding) {
+        None => Encoding::Utf8,
+        Some(_) if mode.contains('b') => {
+            raise_error("ValueError", "binary mode doesn't take an encoding argument");
+            return std::ptr::null_mut();
+        }
+        Some(name) => match Encoding::from_name(&name) {
+            Some(encoding) => encoding,
+            None => {
+                raise_error("LookupError", &format!("unknown encoding: {}", name));
+                return std::ptr::null_mut();
+            }
+        },
+    };
+
+    match options.open(&path) {
+        Ok(file) => {
+            let handle = if readable {
+                FileHandle { reader: Some(BufReader::new(file)), writer: None, encoding }
+            } else {
+                FileHandle { reader: None, writer: Some(file), encoding }
+            };
+            Box::into_raw(Box::new(handle))
+        }
+        Err(e) => {
+            let typ = match e.kind() {
+                std::io::ErrorKind::NotFound => "FileNotFoundError",
+                std::io::ErrorKind::AlreadyExists => "FileExistsError",
+                std::io::ErrorKind::PermissionDenied => "PermissionError",
+                _ => "OSError",
+            };
+            raise_error(typ, &format!("{}: '{}'", e, path));
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Decode bytes read from `handle`, raising UnicodeDecodeError if they aren't valid
+fn decode_text(handle: &FileHandle, bytes: Vec<u8>) -> String {
+    handle.encoding.decode(bytes).unwrap_or_else(|message| {
+        raise_error("UnicodeDecodeError", &message);
+        String::new()
+    })
+}
+
+/// file.read(): the rest of the file as a new string
+#[no_mangle]
+pub extern "C" fn file_read(file: *mut FileHandle) -> *mut c_char {
+    let mut text = String::new();
+    if let Some(handle) = open_handle(file) {
+        match handle.reader.as_mut() {
+            Some(reader) => {
+                let mut bytes = Vec::new();
+                match reader.read_to_end(&mut bytes) {
+                    Ok(_) => text = decode_text(handle, bytes),
+                    Err(e) => raise_error("OSError", &e.to_string()),
+                }
+            }
+            None => raise_error("UnsupportedOperation", "not readable"),
+        }
+    }
+    c_string(text)
+}
+
+/// file.readline(): the next line including its newline, or "" at end of file
+#[no_mangle]
+pub extern "C" fn file_readline(file: *mut FileHandle) -> *mut c_char {
+    let mut line = String::new();
+    if let Some(handle) = open_handle(file) {
+        match handle.reader.as_mut() {
+            Some(reader) => {
+                let mut bytes = Vec::new();
+                match reader.read_until(b'\n', &mut bytes) {
+                    Ok(_) => line = decode_text(handle, bytes),
+                    Err(e) => raise_error("OSError", &e.to_string()),
+                }
+            }
+            None => raise_error("UnsupportedOperation", "not readable"),
+        }
+    }
+    c_string(line)
+}
+
+/// file.write(text): the number of characters written
+#[no_mangle]
+pub extern "C" fn file_write(file: *mut FileHandle, text: *const c_char) -> i64 {
+    let text = str_arg(text).unwrap_or_default();
+    let handle = match open_handle(file) {
+        Some(handle) => handle,
+        None => return 0,
+    };
+    let bytes = match handle.encoding.encode(&text) {
+        Ok(bytes) => bytes,
+        Err(message) => {
+            raise_error("UnicodeEncodeError", &message);
+            return 0;
+        }
+    };
+    match handle.writer.as_mut() {
+        Some(writer) => match writer.write_all(&bytes) {
+            Ok(()) => text.chars().count() as i64,
+            Err(e) => {
+                raise_error("OSError", &e.to_string());
+                0
+            }
+        },
+        None => {
+            raise_error("UnsupportedOperation", "not writable");
+            0
+        }
+    }
+}
+
+/// file.close(): release the underlying file. Closing twice does nothing.
+///
+/// The handle itself stays allocated so later calls can report the file as closed.
+#[no_mangle]
+pub extern "C" fn file_close(file: *mut FileHandle) {
+    if let Some(handle) = handle(file) {
+        handle.reader = None;
+        if let Some(mut writer) = handle.writer.take() {
+            let _ = writer.flush();
+        }
+    }
+}
+
+/// Whether a file object has been closed
+#[no_mangle]
+pub extern "C" fn file_closed(file: *mut FileHandle) -> bool {
+    handle(file).is_none_or(|handle| handle.reader.is_none() && handle.writer.is_none())
+}
+
+/// Register file functions in the LLVM module
+pub fn register_file_functions<'ctx>(context: &'ctx Context, module: &mut Module<'ctx>) {
+    let ptr_type = context.ptr_type(AddressSpace::default());
+
+    let declarations = [
+        ("file_open", ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false)),
+        (
+            "file_open_encoded",
+            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into(), ptr_type.into()], false),
+        ),
+        ("file_read", ptr_type.fn_type(&[ptr_type.into()], false)),
+        ("file_readline", ptr_type.fn_type(&[ptr_type.into()], false)),
+        (
+            "file_write",
+            context.i64_type().fn_type(&[ptr_type.into(), ptr_type.into()], false),
+        ),
+        ("file_close", context.void_type().fn_type(&[ptr_type.into()], false)),
+        ("file_closed", context.bool_type().fn_type(&[ptr_type.into()], false)),
+    ];
+    for (name, fn_type) in declarations {
+        if module.get_function(name).is_none() {
+            module.add_function(name, fn_type, None);
+        }
+    }
+}
+
+/// Map file functions to their runtime addresses in the JIT
+pub fn register_file_runtime_functions(
+    engine: &ExecutionEngine<'_>,
+    module: &Module<'_>,
+) -> Result<(), String> {
+    let mappings: [(&str, usize); 7] = [
+        ("file_open", file_open as *const () as usize),
+        ("file_open_encoded", file_open_encoded as *const () as usize),
+        ("file_read", file_read as *const () as usize),
+        ("file_readline", file_readline as *const () as usize),
+        ("file_write", file_write as *const () as usize),
+        ("file_close", file_close as *const () as usize),
+        ("file_closed", file_closed as *const () as usize),
+    ];
+    for (name, address) in mappings {
+        if let Some(f) = module.get_function(name) {
+            engine.add_global_mapping(&f, address);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,570 @@
+// instance.rs - Class instances with per-site inline caches for attribute access
+//
+// Every class gets a shape: a table from attribute name to slot index,
+// shared by all of its instances. Finding a slot by name is a hash lookup,
+// so each attribute access site in compiled code owns an `AttributeCache`
+// remembering the shape it last saw and the slot it found. While the shape
+// and the global shape version still match, the access is a single indexed
+// load or store. Any change to a shape table bumps the version, so a cache
+// can never hand out a stale slot.
+//
+// A class with abstract methods nothing implements is marked as such when
+// its definition runs, and instance_new() refuses to create its instances.
+//
+// A class is also a value: its `ClassObject` holds the class attributes
+// assigned in its body, and calling it creates an instance. Reading an
+// attribute an instance doesn't have falls back to the class attributes of
+// its class and then of the classes after it in its MRO.
+//
+// dir(), getattr() and setattr() look attributes up by a name only known
+// when the program runs, so they skip the caches and return a default
+// rather than raising when the name isn't set.
+
+use inkwell::context::Context;
+use inkwell::execution_engine::ExecutionEngine;
+use inkwell::module::Module;
+use inkwell::AddressSpace;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Mutex;
+
+use super::exception::exception_raise_new;
+use super::list::{list_append_tagged, list_new, RawList, TypeTag};
+
+/// Attribute table shared by the instances of one class
+struct Shape {
+    class: String,
+    slots: HashMap<String, usize>,
+    /// Abstract methods the class leaves unimplemented
+    abstract_methods: Vec<String>,
+    /// Class attributes, as 64-bit words like instance slots
+    attributes: HashMap<String, i64>,
+    /// Names of the methods the class body defines
+    methods: Vec<String>,
+    /// Shapes of the classes after this one in its MRO
+    mro: Vec<i64>,
+    /// Address of the class object, once one is needed
+    object: usize,
+}
+
+static SHAPES: Mutex<Vec<Shape>> = Mutex::new(Vec::new());
+
+/// Bumped whenever a shape table changes
+static SHAPE_VERSION: AtomicI64 = AtomicI64::new(0);
+
+/// An instance of a class: its shape and one slot per attribute of the
+/// shape. Attribute values are stored as raw 64-bit words; compiled code
+/// converts them to and from their declared types.
+pub struct Instance {
+    shape: i64,
+    slots: Vec<Option<i64>>,
+}
+
+/// A class used as a value. Each class has exactly one, living as long as
+/// the program, so the same class always gives the same pointer.
+pub struct ClassObject {
+    shape: i64,
+}
+
+/// What one attribute access site last resolved. Compiled code allocates
+/// one per site, initialized with `AttributeCache::EMPTY`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AttributeCache {
+    pub shape: i64,
+    pub version: i64,
+    pub slot: i64,
+}
+
+impl AttributeCache {
+    /// A cache that matches no shape
+    pub const EMPTY: AttributeCache = AttributeCache {
+        shape: -1,
+        version: -1,
+        slot: -1,
+    };
+}
+
+/// The current shape version
+pub fn shape_version() -> i64 {
+    SHAPE_VERSION.load(Ordering::Acquire)
+}
+
+fn c_str(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(ptr) }.to_string_lossy().into_owned()
+}
+
+/// The shape id of `class`, registering it the first time
+pub fn class_shape(class: &str) -> i64 {
+    let mut shapes = SHAPES.lock().unwrap();
+    if let Some(id) = shapes.iter().position(|shape| shape.class == class) {
+        return id as i64;
+    }
+    shapes.push(Shape {
+        class: class.to_string(),
+        slots: HashMap::new(),
+        abstract_methods: Vec::new(),
+        attributes: HashMap::new(),
+        methods: Vec::new(),
+        mro: Vec::new(),
+        object: 0,
+    });
+    SHAPE_VERSION.fetch_add(1, Ordering::AcqRel);
+    shapes.len() as i64 - 1
+}
+
+/// The slot of `attr` in `shape`, adding one when `create` is set
+fn find_slot(shape: i64, attr: &str, create: bool) -> Option<usize> {
+    let mut shapes = SHAPES.lock().unwrap();
+    let shape = shapes.get_mut(shape as usize)?;
+    if let Some(&slot) = shape.slots.get(attr) {
+        return Some(slot);
+    }
+    if !create {
+        return None;
+    }
+    let slot = shape.slots.len();
+    shape.slots.insert(attr.to_string(), slot);
+    SHAPE_VERSION.fetch_add(1, Ordering::AcqRel);
+    Some(slot)
+}
+
+fn raw_instance<'a>(instance: *mut Instance) -> Option<&'a mut Instance> {
+    unsafe { instance.as_mut() }
+}
+
+fn class_name(shape: i64) -> String {
+    let shapes = SHAPES.lock().unwrap();
+    shapes
+        .get(shape as usize)
+        .map(|shape| shape.class.clone())
+        .unwrap_or_else(|| "object".to_string())
+}
+
+/// The slot for `attr` on `instance`, from `cache` when it still matches
+fn resolve(instance: &Instance, attr: *const c_char, cache: *mut AttributeCache, create: bool) -> Option<usize> {
+    let version = shape_version();
+    if let Some(cache) = unsafe { cache.as_ref() } {
+        if cache.shape == instance.shape && cache.version == version {
+            return Some(cache.slot as usize);
+        }
+    }
+
+    let slot = find_slot(instance.shape, &c_str(attr), create)?;
+    if let Some(cache) = unsafe { cache.as_mut() } {
+        *cache = AttributeCache {
+            shape: instance.shape,
+            // Adding the slot may have bumped the version
+            version: shape_version(),
+            slot: slot as i64,
+        };
+    }
+    Some(slot)
+}
+
+fn raise_attribute_error(instance: &Instance, attr: *const c_char) {
+    let message = format!(
+        "'{}' object has no attribute '{}'",
+        class_name(instance.shape),
+        c_str(attr)
+    );
+    let typ = CString::new("AttributeError").unwrap();
+    let message = CString::new(message).unwrap_or_default();
+    exception_raise_new(typ.as_ptr(), message.as_ptr());
+}
+
+/// Record the abstract methods `class` leaves unimplemented, as a
+/// comma-separated list. An empty list makes the class instantiable.
+#[no_mangle]
+pub extern "C" fn instance_set_abstract_methods(class: *const c_char, methods: *const c_char) {
+    let shape = class_shape(&c_str(class));
+    let methods = c_str(methods);
+    let mut shapes = SHAPES.lock().unwrap();
+    shapes[shape as usize].abstract_methods = methods
+        .split(',')
+        .filter(|method| !method.is_empty())
+        .map(str::to_string)
+        .collect();
+}
+
+/// Create an instance of `class` with no attributes set. Raises TypeError
+/// and returns null when the class has unimplemented abstract methods.
+#[no_mangle]
+pub extern "C" fn instance_new(class: *const c_char) -> *mut Instance {
+    new_instance(class_shape(&c_str(class)))
+}
+
+fn new_instance(shape: i64) -> *mut Instance {
+    let (class, abstract_methods) = {
+        let shapes = SHAPES.lock().unwrap();
+        let entry = &shapes[shape as usize];
+        (entry.class.clone(), entry.abstract_methods.join(", "))
+    };
+    if !abstract_methods.is_empty() {
+        let message = format!(
+            "Can't instantiate abstract class '{}' with abstract methods {}",
+            class, abstract_methods
+        );
+        let typ = CString::new("TypeError").unwrap();
+        let message = CString::new(message).unwrap_or_default();
+        exception_raise_new(typ.as_ptr(), message.as_ptr());
+        return std::ptr::null_mut();
+    }
+    Box::into_raw(Box::new(Instance {
+        shape,
+        slots: Vec::new(),
+    }))
+}
+
+/// Free an instance created by instance_new()
+#[no_mangle]
+pub extern "C" fn instance_free(instance: *mut Instance) {
+    if let Some(instance) = raw_instance(instance) {
+        unsafe { drop(Box::from_raw(instance as *mut Instance)) };
+    }
+}
+
+/// Load attribute `attr` of `instance`, through the access site's `cache`.
+/// Raises AttributeError and returns 0 when the attribute isn't set.
+#[no_mangle]
+pub extern "C" fn instance_get_attr(
+    instance: *mut Instance,
+    attr: *const c_char,
+    cache: *mut AttributeCache,
+) -> i64 {
+    let Some(instance) = raw_instance(instance) else {
+        return 0;
+    };
+    let value = resolve(instance, attr, cache, false)
+        .and_then(|slot| instance.slots.get(slot).copied().flatten())
+        .or_else(|| class_attribute(instance.shape, &c_str(attr)));
+    match value {
+        Some(value) => value,
+        None => {
+            raise_attribute_error(instance, attr);
+            0
+        }
+    }
+}
+
+/// Store `value` as attribute `attr` of `instance`, through the access
+/// site's `cache`
+#[no_mangle]
+pub extern "C" fn instance_set_attr(
+    instance: *mut Instance,
+    attr: *const c_char,
+    value: i64,
+    cache: *mut AttributeCache,
+) {
+    let Some(instance) = raw_instance(instance) else {
+        return;
+    };
+    let Some(slot) = resolve(instance, attr, cache, true) else {
+        return;
+    };
+    if instance.slots.len() <= slot {
+        instance.slots.resize(slot + 1, None);
+    }
+    instance.slots[slot] = Some(value);
+}
+
+/// Delete attribute `attr` of `instance`. Raises AttributeError and returns
+/// 0 when it isn't set.
+#[no_mangle]
+pub extern "C" fn instance_del_attr(instance: *mut Instance, attr: *const c_char) -> i64 {
+    let Some(instance) = raw_instance(instance) else {
+        return 0;
+    };
+    let removed = find_slot(instance.shape, &c_str(attr), false)
+        .and_then(|slot| instance.slots.get_mut(slot))
+        .and_then(Option::take)
+        .is_some();
+    if !removed {
+        raise_attribute_error(instance, attr);
+    }
+    removed as i64
+}
+
+/// The class object of `shape`, creating it the first time
+fn class_object_of(shape: i64) -> *mut ClassObject {
+    let mut shapes = SHAPES.lock().unwrap();
+    let entry = &mut shapes[shape as usize];
+    if entry.object == 0 {
+        entry.object = Box::into_raw(Box::new(ClassObject { shape })) as usize;
+    }
+    entry.object as *mut ClassObject
+}
+
+/// `attr` among the class attributes of `shape` and the classes after it
+/// in its MRO
+fn class_attribute(shape: i64, attr: &str) -> Option<i64> {
+    let shapes = SHAPES.lock().unwrap();
+    let mro = &shapes.get(shape as usize)?.mro;
+    std::iter::once(shape)
+        .chain(mro.iter().copied())
+        .find_map(|shape| shapes.get(shape as usize)?.attributes.get(attr).copied())
+}
+
+fn raw_class<'a>(class: *mut ClassObject) -> Option<&'a ClassObject> {
+    unsafe { class.as_ref() }
+}
+
+/// The class object of `class`
+#[no_mangle]
+pub extern "C" fn class_object(class: *const c_char) -> *mut ClassObject {
+    class_object_of(class_shape(&c_str(class)))
+}
+
+/// Run the definition of `class`: forget the class attributes and methods
+/// of any earlier definition, record the classes after it in its MRO (a
+/// comma-separated list) and return its class object
+#[no_mangle]
+pub extern "C" fn class_define(class: *const c_char, mro: *const c_char) -> *mut ClassObject {
+    let shape = class_shape(&c_str(class));
+    let mro: Vec<i64> = c_str(mro)
+        .split(',')
+        .filter(|base| !base.is_empty())
+        .map(class_shape)
+        .collect();
+    {
+        let mut shapes = SHAPES.lock().unwrap();
+        let entry = &mut shapes[shape as usize];
+        entry.attributes.clear();
+        entry.methods.clear();
+        entry.mro = mro;
+    }
+    class_object_of(shape)
+}
+
+/// Load class attribute `attr` of `class`, looking through its MRO.
+/// Raises AttributeError and returns 0 when no class defines it.
+#[no_mangle]
+pub extern "C" fn class_get_attr(class: *mut ClassObject, attr: *const c_char) -> i64 {
+    let Some(class) = raw_class(class) else {
+        return 0;
+    };
+    match class_attribute(class.shape, &c_str(attr)) {
+        Some(value) => value,
+        None => {
+            let message = format!(
+                "type object '{}' has no attribute '{}'",
+                class_name(class.shape),
+                c_str(attr)
+            );
+            let typ = CString::new("AttributeError").unwrap();
+            let message = CString::new(message).unwrap_or_default();
+            exception_raise_new(typ.as_ptr(), message.as_ptr());
+            0
+        }
+    }
+}
+
+/// Store `value` as class attribute `attr` of `class`
+#[no_mangle]
+pub extern "C" fn class_set_attr(class: *mut ClassObject, attr: *const c_char, value: i64) {
+    let Some(class) = raw_class(class) else {
+        return;
+    };
+    let mut shapes = SHAPES.lock().unwrap();
+    shapes[class.shape as usize]
+        .attributes
+        .insert(c_str(attr), value);
+}
+
+/// Create an instance of `class`, like instance_new()
+#[no_mangle]
+pub extern "C" fn class_instantiate(class: *mut ClassObject) -> *mut Instance {
+    match raw_class(class) {
+        Some(class) => new_instance(class.shape),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Record the methods `class` defines, as a comma-separated list
+#[no_mangle]
+pub extern "C" fn class_set_methods(class: *const c_char, methods: *const c_char) {
+    let shape = class_shape(&c_str(class));
+    let methods = c_str(methods);
+    let mut shapes = SHAPES.lock().unwrap();
+    shapes[shape as usize].methods = methods
+        .split(',')
+        .filter(|method| !method.is_empty())
+        .map(str::to_string)
+        .collect();
+}
+
+/// Class attribute and method names of `shape` and the classes after it in
+/// its MRO
+fn class_names(shape: i64) -> Vec<String> {
+    let shapes = SHAPES.lock().unwrap();
+    let Some(entry) = shapes.get(shape as usize) else {
+        return Vec::new();
+    };
+    std::iter::once(shape)
+        .chain(entry.mro.iter().copied())
+        .filter_map(|shape| shapes.get(shape as usize))
+        .flat_map(|class| class.attributes.keys().chain(class.methods.iter()).cloned())
+        .collect()
+}
+
+/// `names` sorted and deduplicated, as a list of strings
+fn name_list(mut names: Vec<String>) -> *mut RawList {
+    names.sort();
+    names.dedup();
+    let list = list_new();
+    for name in names {
+        let name = CString::new(name).unwrap_or_default().into_raw();
+        list_append_tagged(list, name as *mut std::ffi::c_void, TypeTag::String);
+    }
+    list
+}
+
+/// dir(instance): the attributes set on `instance` and the class
+/// attributes and methods of its class, sorted
+#[no_mangle]
+pub extern "C" fn instance_dir(instance: *mut Instance) -> *mut RawList {
+    let Some(instance) = raw_instance(instance) else {
+        return name_list(Vec::new());
+    };
+    let mut names = class_names(instance.shape);
+    {
+        let shapes = SHAPES.lock().unwrap();
+        if let Some(shape) = shapes.get(instance.shape as usize) {
+            names.extend(
+                shape
+                    .slots
+                    .iter()
+                    .filter(|(_, &slot)| matches!(instance.slots.get(slot), Some(Some(_))))
+                    .map(|(name, _)| name.clone()),
+            );
+        }
+    }
+    name_list(names)
+}
+
+/// dir(class): the class attributes and methods of `class` and its MRO,
+/// sorted
+#[no_mangle]
+pub extern "C" fn class_dir(class: *mut ClassObject) -> *mut RawList {
+    match raw_class(class) {
+        Some(class) => name_list(class_names(class.shape)),
+        None => name_list(Vec::new()),
+    }
+}
+
+/// getattr(instance, attr, default): attribute `attr` of `instance` or its
+/// class, or `default` when neither has it
+#[no_mangle]
+pub extern "C" fn instance_get_attr_or(instance: *mut Instance, attr: *const c_char, default: i64) -> i64 {
+    let Some(instance) = raw_instance(instance) else {
+        return default;
+    };
+    let attr = c_str(attr);
+    find_slot(instance.shape, &attr, false)
+        .and_then(|slot| instance.slots.get(slot).copied().flatten())
+        .or_else(|| class_attribute(instance.shape, &attr))
+        .unwrap_or(default)
+}
+
+/// getattr(class, attr, default): class attribute `attr` of `class`, or
+/// `default` when no class in its MRO defines it
+#[no_mangle]
+pub extern "C" fn class_get_attr_or(class: *mut ClassObject, attr: *const c_char, default: i64) -> i64 {
+    raw_class(class)
+        .and_then(|class| class_attribute(class.shape, &c_str(attr)))
+        .unwrap_or(default)
+}
+
+/// Register instance functions in the LLVM module
+pub fn register_instance_functions<'ctx>(context: &'ctx Context, module: &mut Module<'ctx>) {
+    let ptr_type = context.ptr_type(AddressSpace::default());
+    let i64_type = context.i64_type();
+
+    let declarations = [
+        ("instance_new", ptr_type.fn_type(&[ptr_type.into()], false)),
+        ("instance_free", context.void_type().fn_type(&[ptr_type.into()], false)),
+        (
+            "instance_set_abstract_methods",
+            context.void_type().fn_type(&[ptr_type.into(), ptr_type.into()], false),
+        ),
+        (
+            "instance_get_attr",
+            i64_type.fn_type(&[ptr_type.into(), ptr_type.into(), ptr_type.into()], false),
+        ),
+        (
+            "instance_set_attr",
+            context
+                .void_type()
+                .fn_type(&[ptr_type.into(), ptr_type.into(), i64_type.into(), ptr_type.into()], false),
+        ),
+        ("instance_del_attr", i64_type.fn_type(&[ptr_type.into(), ptr_type.into()], false)),
+        ("class_object", ptr_type.fn_type(&[ptr_type.into()], false)),
+        ("class_define", ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false)),
+        ("class_get_attr", i64_type.fn_type(&[ptr_type.into(), ptr_type.into()], false)),
+        (
+            "class_set_attr",
+            context
+                .void_type()
+                .fn_type(&[ptr_type.into(), ptr_type.into(), i64_type.into()], false),
+        ),
+        ("class_instantiate", ptr_type.fn_type(&[ptr_type.into()], false)),
+        (
+            "class_set_methods",
+            context.void_type().fn_type(&[ptr_type.into(), ptr_type.into()], false),
+        ),
+        ("instance_dir", ptr_type.fn_type(&[ptr_type.into()], false)),
+        ("class_dir", ptr_type.fn_type(&[ptr_type.into()], false)),
+        (
+            "instance_get_attr_or",
+            i64_type.fn_type(&[ptr_type.into(), ptr_type.into(), i64_type.into()], false),
+        ),
+        (
+            "class_get_attr_or",
+            i64_type.fn_type(&[ptr_type.into(), ptr_type.into(), i64_type.into()], false),
+        ),
+    ];
+    for (name, fn_type) in declarations {
+        if module.get_function(name).is_none() {
+            module.add_function(name, fn_type, None);
+        }
+    }
+}
+
+/// Map instance functions to their runtime addresses in the JIT
+pub fn register_instance_runtime_functions(
+    engine: &ExecutionEngine<'_>,
+    module: &Module<'_>,
+) -> Result<(), String> {
+    let mappings: [(&str, usize); 16] = [
+        ("instance_new", instance_new as *const () as usize),
+        ("instance_free", instance_free as *const () as usize),
+        (
+            "instance_set_abstract_methods",
+            instance_set_abstract_methods as *const () as usize,
+        ),
+        ("instance_get_attr", instance_get_attr as *const () as usize),
+        ("instance_set_attr", instance_set_attr as *const () as usize),
+        ("instance_del_attr", instance_del_attr as *const () as usize),
+        ("class_object", class_object as *const () as usize),
+        ("class_define", class_define as *const () as usize),
+        ("class_get_attr", class_get_attr as *const () as usize),
+        ("class_set_attr", class_set_attr as *const () as usize),
+        ("class_instantiate", class_instantiate as *const () as usize),
+        ("class_set_methods", class_set_methods as *const () as usize),
+        ("instance_dir", instance_dir as *const () as usize),
+        ("class_dir", class_dir as *const () as usize),
+        ("instance_get_attr_or", instance_get_attr_or as *const () as usize),
+        ("class_get_attr_or", class_get_attr_or as *const () as usize),
+    ];
+    for (name, address) in mappings {
+        if let Some(f) = module.get_function(name) {
+            engine.add_global_mapping(&f, address);
+        }
+    }
+    Ok(())
+}
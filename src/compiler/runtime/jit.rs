@@ -0,0 +1,105 @@
+// jit.rs - Mapping runtime functions into a JIT execution engine
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+
+use inkwell::execution_engine::ExecutionEngine;
+use inkwell::module::Module;
+
+use super::{
+    abi, abort, channel, dict, exception, file, instance, list, min_max_ops, os, output, print_ops, range, sequence_ops, string, timeout, traceback,
+};
+
+/// Maps one group of runtime functions into an execution engine
+type RegisterFn = fn(&ExecutionEngine<'_>, &Module<'_>) -> Result<(), String>;
+
+/// Map every runtime function the module declares to its implementation in
+/// this process, so JIT-compiled code can call it.
+///
+/// Registration continues past a failing runtime group; the error lists every
+/// group that failed.
+pub fn register_runtime_functions(engine: &ExecutionEngine<'_>, module: &Module<'_>) -> Result<(), String> {
+    let groups: [(&str, RegisterFn); 14] = [
+        ("list", list::register_list_runtime_functions),
+        ("dict", dict::register_dict_runtime_functions),
+        ("exception", exception::register_exception_runtime_functions),
+        ("string", string::register_string_runtime_functions),
+        ("instance", instance::register_instance_runtime_functions),
+        ("os", os::register_os_runtime_functions),
+        ("sequence", sequence_ops::register_sequence_runtime_functions),
+        ("file", file::register_file_runtime_functions),
+        ("channel", channel::register_channel_runtime_functions),
+        ("timeout", timeout::register_timeout_runtime_functions),
+        ("traceback", traceback::register_traceback_runtime_functions),
+        ("abort", abort::register_abort_runtime_functions),
+        ("ABI check", abi::register_abi_runtime_functions),
+        ("output", output::register_output_runtime_functions),
+    ];
+    let failures: Vec<String> = groups
+        .iter()
+        .filter_map(|(group, register)| {
+            register(engine, module)
+                .err()
+                .map(|e| format!("{} runtime functions: {}", group, e))
+        })
+        .collect();
+
+    let mappings: [(&str, usize); 28] = [
+        ("int_to_string", string::int_to_string as *const () as usize),
+        ("float_to_string", string::float_to_string as *const () as usize),
+        ("bool_to_string", string::bool_to_string as *const () as usize),
+        ("range_1", range::range_1 as *const () as usize),
+        ("range_2", range::range_2 as *const () as usize),
+        ("range_3", range::range_3 as *const () as usize),
+        ("range_cleanup", range::range_cleanup as *const () as usize),
+        ("string_to_int", string::string_to_int as *const () as usize),
+        ("string_to_float", string::string_to_float as *const () as usize),
+        ("string_to_bool", string::string_to_bool as *const () as usize),
+        ("char_to_string", string::char_to_string as *const () as usize),
+        ("free_string", string::free_string as *const () as usize),
+        ("str_int", string::int_to_string as *const () as usize),
+        ("str_float", string::float_to_string as *const () as usize),
+        ("str_bool", str_bool as *const () as usize),
+        ("print_string", print_ops::print_string as *const () as usize),
+        ("println_string", print_ops::println_string as *const () as usize),
+        ("print_int", print_ops::print_int as *const () as usize),
+        ("print_float", print_ops::print_float as *const () as usize),
+        ("print_bool", print_ops::print_bool as *const () as usize),
+        ("input_string", print_ops::input_string as *const () as usize),
+        ("string_concat", string::string_concat as *const () as usize),
+        ("string_equals", string_equals as *const () as usize),
+        ("string_length", string::string_len as *const () as usize),
+        ("min_int", min_max_ops::min_int as *const () as usize),
+        ("min_float", min_max_ops::min_float as *const () as usize),
+        ("max_int", min_max_ops::max_int as *const () as usize),
+        ("max_float", min_max_ops::max_float as *const () as usize),
+    ];
+    for (name, address) in mappings {
+        if let Some(function) = module.get_function(name) {
+            engine.add_global_mapping(&function, address);
+        }
+    }
+    for builtin in crate::compiler::plugins::registered_builtins() {
+        let function = module.get_function(&builtin.signature.symbol);
+        if let (Some(function), Some(address)) = (function, builtin.address) {
+            engine.add_global_mapping(&function, address);
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures.join("; "))
+    }
+}
+
+/// str() of a bool, which the compiler passes as an i1 rather than an i64
+extern "C" fn str_bool(value: bool) -> *mut c_char {
+    string::bool_to_string(value as i64)
+}
+
+extern "C" fn string_equals(left: *const c_char, right: *const c_char) -> bool {
+    let left = unsafe { CStr::from_ptr(left) };
+    let right = unsafe { CStr::from_ptr(right) };
+    left.to_str().unwrap_or("") == right.to_str().unwrap_or("")
+}
@@ -10,6 +10,7 @@ use libc::{calloc, free, malloc, realloc, c_char};
 use std::ffi::c_void;
 use std::ptr;
 
+use crate::compiler::runtime::exception::{exception_raise_new, format_arg};
 use crate::compiler::runtime::string::free_string;
 
 #[repr(u8)]
@@ -25,6 +26,23 @@ pub enum TypeTag {
     Tuple = 7,
 }
 
+impl TypeTag {
+    /// Runtime tag recorded alongside a value of the given static type
+    pub fn for_type(ty: &crate::compiler::types::Type) -> Self {
+        use crate::compiler::types::Type;
+        match ty {
+            Type::None => TypeTag::None_,
+            Type::Bool => TypeTag::Bool,
+            Type::Int => TypeTag::Int,
+            Type::Float => TypeTag::Float,
+            Type::String => TypeTag::String,
+            Type::List(_) => TypeTag::List,
+            Type::Tuple(_) => TypeTag::Tuple,
+            _ => TypeTag::Any,
+        }
+    }
+}
+
 /// C-compatible raw list struct
 #[repr(C)]
 pub struct RawList {
@@ -159,6 +177,25 @@ pub extern "C" fn list_append_tagged(list_ptr: *mut RawList,
     }
 }
 
+/// Append to a list created with room for every element it will receive,
+/// without the growth check of `list_append_tagged`
+///
+/// # Safety
+///
+/// `list_ptr` must be a valid list whose length is below its capacity.
+#[no_mangle]
+pub unsafe extern "C" fn list_push_presized(list_ptr: *mut RawList,
+                                            value: *mut c_void,
+                                            tag:   TypeTag)
+{
+    let rl = &mut *list_ptr;
+    debug_assert!(rl.length < rl.capacity, "list_push_presized past capacity");
+
+    *rl.data.add(rl.length as usize) = value;
+    *rl.tags.add(rl.length as usize) = tag;
+    rl.length += 1;
+}
+
 #[no_mangle]
 pub extern "C" fn list_get_tag(list_ptr: *mut RawList, index: i64) -> TypeTag {
     unsafe {
@@ -183,6 +220,29 @@ pub extern "C" fn list_get(list_ptr: *mut RawList, index: i64) -> *mut c_void {
     }
 }
 
+/// Zeroed storage handed out for a failed index, so compiled code can still load from it
+static MISSING_ITEM: [u64; 2] = [0; 2];
+
+/// Raise a `typ` exception from a list operation
+fn raise(typ: &str, message: &str) {
+    let typ = std::ffi::CString::new(typ).unwrap();
+    let message = std::ffi::CString::new(message.replace('\0', "")).unwrap();
+    exception_raise_new(typ.as_ptr(), message.as_ptr());
+}
+
+/// list[index] in user code: negative indices count from the end, and an
+/// index out of range raises IndexError
+#[no_mangle]
+pub extern "C" fn list_get_checked(list_ptr: *mut RawList, index: i64) -> *mut c_void {
+    let length = list_len(list_ptr);
+    let resolved = if index < 0 { index + length } else { index };
+    if resolved < 0 || resolved >= length {
+        raise("IndexError", "list index out of range");
+        return MISSING_ITEM.as_ptr() as *mut c_void;
+    }
+    list_get(list_ptr, resolved)
+}
+
 #[no_mangle]
 pub extern "C" fn list_set(list_ptr: *mut RawList, index: i64, value: *mut c_void) {
     unsafe {
@@ -199,8 +259,8 @@ pub extern "C" fn list_concat(a: *mut RawList, b: *mut RawList) -> *mut RawList
         let ra = &*a;
         let rb = &*b;
         let out = list_with_capacity(ra.length + rb.length);
-        for i in 0..ra.length { list_append(out, list_get(a, i)); }
-        for i in 0..rb.length { list_append(out, list_get(b, i)); }
+        for i in 0..ra.length { list_append_tagged(out, list_get(a, i), list_get_tag(a, i)); }
+        for i in 0..rb.length { list_append_tagged(out, list_get(b, i), list_get_tag(b, i)); }
         out
     }
 }
@@ -211,7 +271,7 @@ pub extern "C" fn list_repeat(src: *mut RawList, times: i64) -> *mut RawList {
         let rs = &*src;
         let out = list_with_capacity(rs.length * times);
         for _ in 0..times {
-            for i in 0..rs.length { list_append(out, list_get(src, i)); }
+            for i in 0..rs.length { list_append_tagged(out, list_get(src, i), list_get_tag(src, i)); }
         }
         out
     }
@@ -222,7 +282,7 @@ pub extern "C" fn list_slice(src: *mut RawList, start: i64, stop: i64, step: i64
     let out = list_new();
     let mut i = start;
     while (step > 0 && i < stop) || (step < 0 && i > stop) {
-        list_append(out, list_get(src, i));
+        list_append_tagged(out, list_get(src, i), list_get_tag(src, i));
         i += step;
     }
     out
@@ -305,6 +365,175 @@ pub extern "C" fn list_len(list_ptr: *mut RawList) -> i64 {
     }
 }
 
+fn raw_list<'a>(list_ptr: *mut RawList) -> Option<&'a mut RawList> {
+    unsafe { list_ptr.as_mut() }
+}
+
+/// Numeric value of a Bool, Int or Float element
+pub(crate) fn tagged_number(value: *mut c_void, tag: TypeTag) -> Option<f64> {
+    if value.is_null() { return None; }
+    unsafe {
+        match tag {
+            TypeTag::Bool  => Some(if *(value as *const u8) != 0 { 1.0 } else { 0.0 }),
+            TypeTag::Int   => Some(*(value as *const i64) as f64),
+            TypeTag::Float => Some(*(value as *const f64)),
+            _ => None,
+        }
+    }
+}
+
+/// Order two tagged elements: numbers numerically, strings lexicographically,
+/// anything else by tag and then identity. NaN orders as equal to every
+/// number so sorting stays total; use `tagged_eq` to test for equality.
+pub(crate) fn tagged_cmp(a: *mut c_void, a_tag: TypeTag, b: *mut c_void, b_tag: TypeTag) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    if a_tag == TypeTag::Int && b_tag == TypeTag::Int && !a.is_null() && !b.is_null() {
+        return unsafe { (*(a as *const i64)).cmp(&*(b as *const i64)) };
+    }
+    if let (Some(x), Some(y)) = (tagged_number(a, a_tag), tagged_number(b, b_tag)) {
+        return x.partial_cmp(&y).unwrap_or(Ordering::Equal);
+    }
+    if a_tag == TypeTag::String && b_tag == TypeTag::String && !a.is_null() && !b.is_null() {
+        let x = unsafe { std::ffi::CStr::from_ptr(a as *const c_char) };
+        let y = unsafe { std::ffi::CStr::from_ptr(b as *const c_char) };
+        return x.cmp(y);
+    }
+    (a_tag as u8).cmp(&(b_tag as u8)).then((a as usize).cmp(&(b as usize)))
+}
+
+/// Whether two tagged elements are equal: numbers by value, so NaN equals
+/// nothing, strings by content and anything else by identity
+pub(crate) fn tagged_eq(a: *mut c_void, a_tag: TypeTag, b: *mut c_void, b_tag: TypeTag) -> bool {
+    if a_tag == TypeTag::Int && b_tag == TypeTag::Int && !a.is_null() && !b.is_null() {
+        return unsafe { *(a as *const i64) == *(b as *const i64) };
+    }
+    if let (Some(x), Some(y)) = (tagged_number(a, a_tag), tagged_number(b, b_tag)) {
+        return x == y;
+    }
+    tagged_cmp(a, a_tag, b, b_tag) == std::cmp::Ordering::Equal
+}
+
+/// Resolve a Python-style index (negative counts from the end)
+fn normalize_index(index: i64, length: i64) -> i64 {
+    if index < 0 { index + length } else { index }
+}
+
+/// list.pop(index) - removes and returns the element pointer; an empty list or
+/// an index out of range raises IndexError and yields a zeroed item
+#[no_mangle]
+pub extern "C" fn list_pop(list_ptr: *mut RawList, index: i64) -> *mut c_void {
+    let rl = match raw_list(list_ptr) { Some(rl) => rl, None => return ptr::null_mut() };
+    if rl.length == 0 {
+        raise("IndexError", "pop from empty list");
+        return MISSING_ITEM.as_ptr() as *mut c_void;
+    }
+    let index = normalize_index(index, rl.length);
+    if index < 0 || index >= rl.length {
+        raise("IndexError", "pop index out of range");
+        return MISSING_ITEM.as_ptr() as *mut c_void;
+    }
+
+    unsafe {
+        let i = index as usize;
+        let value = *rl.data.add(i);
+        let tail = (rl.length as usize) - i - 1;
+        ptr::copy(rl.data.add(i + 1), rl.data.add(i), tail);
+        ptr::copy(rl.tags.add(i + 1), rl.tags.add(i), tail);
+        rl.length -= 1;
+        value
+    }
+}
+
+/// list.insert(index, value) - indices past either end clamp like Python
+#[no_mangle]
+pub extern "C" fn list_insert(list_ptr: *mut RawList, index: i64, value: *mut c_void, tag: TypeTag) {
+    let length = match raw_list(list_ptr) { Some(rl) => rl.length, None => return };
+    let index = normalize_index(index, length).clamp(0, length) as usize;
+
+    // Grow through the regular append path, then shift the tail right by one
+    list_append_tagged(list_ptr, value, tag);
+    if let Some(rl) = raw_list(list_ptr) {
+        unsafe {
+            let last = (rl.length - 1) as usize;
+            ptr::copy(rl.data.add(index), rl.data.add(index + 1), last - index);
+            ptr::copy(rl.tags.add(index), rl.tags.add(index + 1), last - index);
+            *rl.data.add(index) = value;
+            *rl.tags.add(index) = tag;
+        }
+    }
+}
+
+/// list.remove(value) - removes the first equal element; raises ValueError
+/// and returns false if none matched
+#[no_mangle]
+pub extern "C" fn list_remove(list_ptr: *mut RawList, value: *mut c_void, tag: TypeTag) -> bool {
+    match find(list_ptr, value, tag) {
+        Some(index) => {
+            list_pop(list_ptr, index);
+            true
+        }
+        None => {
+            raise("ValueError", "list.remove(x): x not in list");
+            false
+        }
+    }
+}
+
+/// list.index(value) - position of the first equal element; raises ValueError
+/// and returns -1 if none matched
+#[no_mangle]
+pub extern "C" fn list_index(list_ptr: *mut RawList, value: *mut c_void, tag: TypeTag) -> i64 {
+    match find(list_ptr, value, tag) {
+        Some(index) => index,
+        None => {
+            raise("ValueError", &format!("{} is not in list", format_arg(value, tag, true)));
+            -1
+        }
+    }
+}
+
+/// Position of the first element equal to `value`
+fn find(list_ptr: *mut RawList, value: *mut c_void, tag: TypeTag) -> Option<i64> {
+    (0..list_len(list_ptr)).find(|&i| tagged_eq(list_get(list_ptr, i), list_get_tag(list_ptr, i), value, tag))
+}
+
+/// list.count(value) - number of equal elements
+#[no_mangle]
+pub extern "C" fn list_count(list_ptr: *mut RawList, value: *mut c_void, tag: TypeTag) -> i64 {
+    (0..list_len(list_ptr))
+        .filter(|&i| tagged_eq(list_get(list_ptr, i), list_get_tag(list_ptr, i), value, tag))
+        .count() as i64
+}
+
+/// list.extend(other) - appends every element of `other`, keeping its tags
+#[no_mangle]
+pub extern "C" fn list_extend(list_ptr: *mut RawList, other: *mut RawList) {
+    // Snapshot the length so `xs.extend(xs)` terminates
+    let count = list_len(other);
+    for i in 0..count {
+        list_append_tagged(list_ptr, list_get(other, i), list_get_tag(other, i));
+    }
+}
+
+/// list.sort() - stable, tag-aware in-place sort
+#[no_mangle]
+pub extern "C" fn list_sort(list_ptr: *mut RawList) {
+    let rl = match raw_list(list_ptr) { Some(rl) => rl, None => return };
+    if rl.length < 2 { return; }
+
+    unsafe {
+        let len = rl.length as usize;
+        let mut items: Vec<(*mut c_void, TypeTag)> =
+            (0..len).map(|i| (*rl.data.add(i), *rl.tags.add(i))).collect();
+        items.sort_by(|a, b| tagged_cmp(a.0, a.1, b.0, b.1));
+        for (i, (value, tag)) in items.into_iter().enumerate() {
+            *rl.data.add(i) = value;
+            *rl.tags.add(i) = tag;
+        }
+    }
+}
+
 /// Register list operation functions in the LLVM module
 pub fn register_list_functions<'ctx>(context: &'ctx Context, module: &mut Module<'ctx>) {
     let _list_struct_type = context.struct_type(
@@ -352,6 +581,15 @@ pub fn register_list_functions<'ctx>(context: &'ctx Context, module: &mut Module
         ], false),
         None,
     );
+    module.add_function(
+        "list_push_presized",
+        context.void_type().fn_type(&[
+            context.ptr_type(AddressSpace::default()).into(),
+            context.ptr_type(AddressSpace::default()).into(),
+            context.i8_type().into(),
+        ], false),
+        None,
+    );
     module.add_function(
         "list_get",
         context.ptr_type(AddressSpace::default()).fn_type(&[
@@ -360,6 +598,14 @@ pub fn register_list_functions<'ctx>(context: &'ctx Context, module: &mut Module
         ], false),
         None,
     );
+    module.add_function(
+        "list_get_checked",
+        context.ptr_type(AddressSpace::default()).fn_type(&[
+            context.ptr_type(AddressSpace::default()).into(),
+            context.i64_type().into(),
+        ], false),
+        None,
+    );
     module.add_function(
         "list_get_tag",
         context.i8_type().fn_type(&[
@@ -411,6 +657,43 @@ pub fn register_list_functions<'ctx>(context: &'ctx Context, module: &mut Module
         context.i64_type().fn_type(&[context.ptr_type(AddressSpace::default()).into()], false),
         None,
     );
+
+    let ptr_type = context.ptr_type(AddressSpace::default());
+    let tag_type = context.i8_type();
+    module.add_function(
+        "list_pop",
+        ptr_type.fn_type(&[ptr_type.into(), context.i64_type().into()], false),
+        None,
+    );
+    module.add_function(
+        "list_insert",
+        context.void_type().fn_type(&[
+            ptr_type.into(), context.i64_type().into(), ptr_type.into(), tag_type.into(),
+        ], false),
+        None,
+    );
+    module.add_function(
+        "list_remove",
+        context.bool_type().fn_type(&[ptr_type.into(), ptr_type.into(), tag_type.into()], false),
+        None,
+    );
+    for name in ["list_index", "list_count"] {
+        module.add_function(
+            name,
+            context.i64_type().fn_type(&[ptr_type.into(), ptr_type.into(), tag_type.into()], false),
+            None,
+        );
+    }
+    module.add_function(
+        "list_extend",
+        context.void_type().fn_type(&[ptr_type.into(), ptr_type.into()], false),
+        None,
+    );
+    module.add_function(
+        "list_sort",
+        context.void_type().fn_type(&[ptr_type.into()], false),
+        None,
+    );
 }
 
 pub fn get_list_struct_type<'ctx>(context: &'ctx Context) -> StructType<'ctx> {
@@ -448,7 +731,9 @@ pub fn register_list_runtime_functions(
     if let Some(f) = module.get_function("list_from_range") { engine.add_global_mapping(&f, list_from_range as usize); }
     if let Some(f) = module.get_function("list_append") { engine.add_global_mapping(&f, list_append as usize); }
     if let Some(f) = module.get_function("list_append_tagged") { engine.add_global_mapping(&f, list_append_tagged as usize); }
+    if let Some(f) = module.get_function("list_push_presized") { engine.add_global_mapping(&f, list_push_presized as *const () as usize); }
     if let Some(f) = module.get_function("list_get") { engine.add_global_mapping(&f, list_get as usize); }
+    if let Some(f) = module.get_function("list_get_checked") { engine.add_global_mapping(&f, list_get_checked as *const () as usize); }
     if let Some(f) = module.get_function("list_get_tag") { engine.add_global_mapping(&f, list_get_tag as usize); }
     if let Some(f) = module.get_function("list_set") { engine.add_global_mapping(&f, list_set as usize); }
     if let Some(f) = module.get_function("list_concat") { engine.add_global_mapping(&f, list_concat as usize); }
@@ -456,5 +741,12 @@ pub fn register_list_runtime_functions(
     if let Some(f) = module.get_function("list_slice") { engine.add_global_mapping(&f, list_slice as usize); }
     if let Some(f) = module.get_function("list_free") { engine.add_global_mapping(&f, list_free as usize); }
     if let Some(f) = module.get_function("list_len") { engine.add_global_mapping(&f, list_len as usize); }
+    if let Some(f) = module.get_function("list_pop") { engine.add_global_mapping(&f, list_pop as *const () as usize); }
+    if let Some(f) = module.get_function("list_insert") { engine.add_global_mapping(&f, list_insert as *const () as usize); }
+    if let Some(f) = module.get_function("list_remove") { engine.add_global_mapping(&f, list_remove as *const () as usize); }
+    if let Some(f) = module.get_function("list_index") { engine.add_global_mapping(&f, list_index as *const () as usize); }
+    if let Some(f) = module.get_function("list_count") { engine.add_global_mapping(&f, list_count as *const () as usize); }
+    if let Some(f) = module.get_function("list_extend") { engine.add_global_mapping(&f, list_extend as *const () as usize); }
+    if let Some(f) = module.get_function("list_sort") { engine.add_global_mapping(&f, list_sort as *const () as usize); }
     Ok(())
 }
\ No newline at end of file
@@ -1,22 +1,108 @@
 // Runtime support module for the Cheetah compiler
+//
+// Everything but float formatting and output settings, which the interpreter
+// shares, needs the `codegen` feature.
 
+#[cfg(feature = "codegen")]
+pub mod abi;
+#[cfg(feature = "codegen")]
+pub mod abort;
+#[cfg(feature = "codegen")]
 pub mod buffer;
+#[cfg(feature = "codegen")]
+pub mod channel;
+#[cfg(feature = "codegen")]
 pub mod debug_utils;
+#[cfg(feature = "codegen")]
 pub mod dict;
+#[cfg(feature = "codegen")]
 pub mod exception;
+#[cfg(feature = "codegen")]
+pub mod file;
+#[cfg(feature = "codegen")]
+pub mod instance;
+#[cfg(feature = "codegen")]
 pub mod int_ops;
+#[cfg(feature = "codegen")]
+pub mod jit;
+#[cfg(feature = "codegen")]
 pub mod list;
+#[cfg(feature = "codegen")]
 pub mod memory_profiler;
+#[cfg(feature = "codegen")]
 pub mod min_max_ops;
+pub mod number_format;
+#[cfg(feature = "codegen")]
+pub mod os;
+pub mod output;
+#[cfg(feature = "codegen")]
 pub mod parallel_ops;
+#[cfg(feature = "codegen")]
 pub mod print_ops;
+#[cfg(feature = "codegen")]
 pub mod range;
+#[cfg(feature = "codegen")]
+pub mod sequence_ops;
+#[cfg(feature = "codegen")]
 pub mod string;
+#[cfg(feature = "codegen")]
+pub mod timeout;
+#[cfg(feature = "codegen")]
+pub mod traceback;
 
+#[cfg(feature = "codegen")]
 use inkwell::context::Context;
+#[cfg(feature = "codegen")]
 use inkwell::module::Module;
+#[cfg(feature = "codegen")]
+use inkwell::values::FunctionValue;
+#[cfg(feature = "codegen")]
+use std::cell::OnceCell;
+
+/// Declarations of every runtime function, kept in a side module and copied
+/// into the module being compiled the first time each one is referenced, so
+/// small programs only declare (and the JIT only maps) what they use.
+#[cfg(feature = "codegen")]
+#[derive(Default)]
+pub struct RuntimeCatalog<'ctx> {
+    declarations: OnceCell<Module<'ctx>>,
+}
+
+#[cfg(feature = "codegen")]
+impl<'ctx> RuntimeCatalog<'ctx> {
+    /// Create a catalog; its declarations are built on first lookup
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn declarations(&self, context: &'ctx Context) -> &Module<'ctx> {
+        self.declarations.get_or_init(|| {
+            let mut module = context.create_module("runtime_declarations");
+            register_runtime_functions(context, &mut module);
+            module
+        })
+    }
+
+    /// Whether the runtime provides a function called `name`
+    pub fn provides(&self, context: &'ctx Context, name: &str) -> bool {
+        self.declarations(context).get_function(name).is_some()
+    }
+
+    /// Declare the runtime function `name` in `module`, returning None if the
+    /// runtime has no such function
+    pub fn declare(
+        &self,
+        context: &'ctx Context,
+        module: &Module<'ctx>,
+        name: &str,
+    ) -> Option<FunctionValue<'ctx>> {
+        let declaration = self.declarations(context).get_function(name)?;
+        Some(module.add_function(name, declaration.get_type(), None))
+    }
+}
 
 /// Register all runtime functions in the module
+#[cfg(feature = "codegen")]
 pub fn register_runtime_functions<'ctx>(context: &'ctx Context, module: &mut Module<'ctx>) {
     // Register list operation functions
     list::register_list_functions(context, module);
@@ -27,6 +113,9 @@ pub fn register_runtime_functions<'ctx>(context: &'ctx Context, module: &mut Mod
     // Register dictionary operation functions
     dict::register_dict_functions(context, module);
 
+    // Register class instance functions
+    instance::register_instance_functions(context, module);
+
     // Register integer operation functions
     int_ops::register_int_functions(context, module);
 
@@ -47,4 +136,31 @@ pub fn register_runtime_functions<'ctx>(context: &'ctx Context, module: &mut Mod
 
     // Register min and max functions
     min_max_ops::register_min_max_functions(context, module);
+
+    // Register os module functions
+    os::register_os_functions(context, module);
+
+    // Register sorted, reversed, sum, abs and round functions
+    sequence_ops::register_sequence_functions(context, module);
+
+    // Register file object functions
+    file::register_file_functions(context, module);
+
+    // Register channel functions
+    channel::register_channel_functions(context, module);
+
+    // Register with_timeout deadline functions
+    timeout::register_timeout_functions(context, module);
+
+    // Register traceback frame functions
+    traceback::register_traceback_functions(context, module);
+
+    // Register the runtime error abort
+    abort::register_abort_functions(context, module);
+
+    // Register the runtime ABI check
+    abi::register_abi_functions(context, module);
+
+    // Register output newline and encoding configuration
+    output::register_output_functions(context, module);
 }
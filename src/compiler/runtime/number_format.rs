@@ -0,0 +1,78 @@
+// number_format.rs - Locale-independent float formatting
+//
+// Floats are printed the way Python's repr() prints them: the shortest digit
+// string that round-trips, laid out in fixed notation for decimal exponents in
+// [-4, 16) and in scientific notation otherwise, always with a '.' decimal
+// point. The digits come from Rust's shortest round-trip `{:e}` formatting and
+// are arranged here by hand, so the output never depends on the C locale of
+// the process printing it.
+
+/// Smallest decimal exponent printed in fixed notation
+const MIN_FIXED_EXPONENT: i32 = -4;
+
+/// Decimal exponent from which scientific notation is used
+const MAX_FIXED_EXPONENT: i32 = 16;
+
+/// Format a float as Python's repr() does, e.g. `1.0`, `0.1`, `1e+20`, `-inf`
+pub fn format_float(value: f64) -> String {
+    let mut out = String::with_capacity(24);
+    write_float_to(&mut out, value);
+    out
+}
+
+/// Append the repr() of a float to `out`
+pub fn write_float_to(out: &mut String, value: f64) {
+    if value.is_nan() {
+        out.push_str("nan");
+        return;
+    }
+    if value.is_sign_negative() {
+        out.push('-');
+    }
+    if value.is_infinite() {
+        out.push_str("inf");
+        return;
+    }
+    if value == 0.0 {
+        out.push_str("0.0");
+        return;
+    }
+
+    // `{:e}` yields the shortest round-trip digits as `d[.ddd]e[-]x`
+    let scientific = format!("{:e}", value.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    let digits: String = mantissa.chars().filter(|c| c.is_ascii_digit()).collect();
+
+    if (MIN_FIXED_EXPONENT..MAX_FIXED_EXPONENT).contains(&exponent) {
+        if exponent < 0 {
+            out.push_str("0.");
+            out.extend(std::iter::repeat_n('0', (-exponent - 1) as usize));
+            out.push_str(&digits);
+        } else {
+            let int_len = exponent as usize + 1;
+            if digits.len() > int_len {
+                out.push_str(&digits[..int_len]);
+                out.push('.');
+                out.push_str(&digits[int_len..]);
+            } else {
+                out.push_str(&digits);
+                out.extend(std::iter::repeat_n('0', int_len - digits.len()));
+                out.push_str(".0");
+            }
+        }
+    } else {
+        out.push_str(&digits[..1]);
+        if digits.len() > 1 {
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+        out.push('e');
+        out.push(if exponent < 0 { '-' } else { '+' });
+        let magnitude = exponent.unsigned_abs();
+        if magnitude < 10 {
+            out.push('0');
+        }
+        out.push_str(&magnitude.to_string());
+    }
+}
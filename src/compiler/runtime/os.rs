@@ -0,0 +1,150 @@
+// os.rs - Runtime support for the os module's temporary file helpers
+
+use inkwell::context::Context;
+use inkwell::execution_engine::ExecutionEngine;
+use inkwell::module::Module;
+use inkwell::AddressSpace;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Temporary paths created by this process that have not been removed yet
+static TEMP_PATHS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A fresh path in the system temp directory; nothing is created yet
+fn unique_temp_path(kind: &str) -> PathBuf {
+    let n = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!("cheetah-{}-{}-{}", kind, std::process::id(), n))
+}
+
+fn register_temp_path(path: PathBuf) -> *mut c_char {
+    let raw = CString::new(path.to_string_lossy().into_owned())
+        .unwrap_or_default()
+        .into_raw();
+    TEMP_PATHS.lock().unwrap().push(path);
+    raw
+}
+
+fn path_from_c(path: *const c_char) -> Option<PathBuf> {
+    if path.is_null() {
+        return None;
+    }
+    let path = unsafe { CStr::from_ptr(path) };
+    Some(PathBuf::from(path.to_string_lossy().into_owned()))
+}
+
+fn remove_temp_path(path: &Path) -> bool {
+    let removed = if path.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    };
+    removed.is_ok() || !path.exists()
+}
+
+/// Create a new empty temporary directory and return its path
+#[no_mangle]
+pub extern "C" fn os_tempdir() -> *mut c_char {
+    let mut path = unique_temp_path("dir");
+    while std::fs::create_dir(&path).is_err() {
+        if !std::env::temp_dir().is_dir() {
+            return CString::default().into_raw();
+        }
+        path = unique_temp_path("dir");
+    }
+    register_temp_path(path)
+}
+
+/// Create a new empty temporary file and return its path
+#[no_mangle]
+pub extern "C" fn os_tempfile() -> *mut c_char {
+    let mut path = unique_temp_path("file");
+    while std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .is_err()
+    {
+        if !std::env::temp_dir().is_dir() {
+            return CString::default().into_raw();
+        }
+        path = unique_temp_path("file");
+    }
+    register_temp_path(path)
+}
+
+/// Remove a path created by `os_tempdir` or `os_tempfile`, with everything in it.
+///
+/// Paths that were not created by those helpers are left alone.
+#[no_mangle]
+pub extern "C" fn os_temp_cleanup(path: *const c_char) -> bool {
+    let path = match path_from_c(path) {
+        Some(path) => path,
+        None => return false,
+    };
+
+    let mut paths = TEMP_PATHS.lock().unwrap();
+    match paths.iter().position(|p| *p == path) {
+        Some(index) => {
+            paths.remove(index);
+            remove_temp_path(&path)
+        }
+        None => false,
+    }
+}
+
+/// Number of temporary paths still waiting to be removed
+#[no_mangle]
+pub extern "C" fn os_temp_live_count() -> i64 {
+    TEMP_PATHS.lock().unwrap().len() as i64
+}
+
+/// Remove every temporary path that is still registered, e.g. when a program
+/// exits from inside a `with` block
+pub fn cleanup() {
+    let paths = std::mem::take(&mut *TEMP_PATHS.lock().unwrap());
+    for path in paths {
+        remove_temp_path(&path);
+    }
+}
+
+/// Register os functions in the LLVM module
+pub fn register_os_functions<'ctx>(context: &'ctx Context, module: &mut Module<'ctx>) {
+    let ptr_type = context.ptr_type(AddressSpace::default());
+
+    if module.get_function("os_tempdir").is_none() {
+        module.add_function("os_tempdir", ptr_type.fn_type(&[], false), None);
+    }
+    if module.get_function("os_tempfile").is_none() {
+        module.add_function("os_tempfile", ptr_type.fn_type(&[], false), None);
+    }
+    if module.get_function("os_temp_cleanup").is_none() {
+        let fn_type = context.bool_type().fn_type(&[ptr_type.into()], false);
+        module.add_function("os_temp_cleanup", fn_type, None);
+    }
+    if module.get_function("os_temp_live_count").is_none() {
+        module.add_function("os_temp_live_count", context.i64_type().fn_type(&[], false), None);
+    }
+}
+
+/// Map os functions to their runtime addresses in the JIT
+pub fn register_os_runtime_functions(
+    engine: &ExecutionEngine<'_>,
+    module: &Module<'_>,
+) -> Result<(), String> {
+    let mappings: [(&str, usize); 4] = [
+        ("os_tempdir", os_tempdir as *const () as usize),
+        ("os_tempfile", os_tempfile as *const () as usize),
+        ("os_temp_cleanup", os_temp_cleanup as *const () as usize),
+        ("os_temp_live_count", os_temp_live_count as *const () as usize),
+    ];
+    for (name, address) in mappings {
+        if let Some(f) = module.get_function(name) {
+            engine.add_global_mapping(&f, address);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,139 @@
+// output.rs - Newline translation and console encoding for program output
+//
+// Programs write '\n' line endings. With `Newline::CrLf` everything a program
+// writes to stdout has them translated to "\r\n", as Python's text-mode stdout
+// does on Windows; `Newline::Native` picks CrLf on Windows and Lf elsewhere.
+// Compiled programs call `cheetah_configure_output` at startup with the mode
+// they were compiled with (`--newline`). On Windows it also switches the
+// console to UTF-8, so non-ASCII output isn't garbled by a legacy code page.
+
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(feature = "codegen")]
+use inkwell::context::Context;
+#[cfg(feature = "codegen")]
+use inkwell::execution_engine::ExecutionEngine;
+#[cfg(feature = "codegen")]
+use inkwell::module::Module;
+
+static TRANSLATE_CRLF: AtomicBool = AtomicBool::new(false);
+
+/// Line endings written to stdout
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Newline {
+    /// "\n" everywhere
+    Lf,
+    /// "\r\n" everywhere
+    CrLf,
+    /// "\r\n" on Windows, "\n" elsewhere
+    #[default]
+    Native,
+}
+
+impl Newline {
+    /// Parse a newline mode as given to `--newline`
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "lf" => Ok(Newline::Lf),
+            "crlf" => Ok(Newline::CrLf),
+            "native" => Ok(Newline::Native),
+            other => Err(format!(
+                "unknown newline mode '{}' (expected 'lf', 'crlf' or 'native')",
+                other
+            )),
+        }
+    }
+
+    /// Code passed to `cheetah_configure_output`
+    pub fn code(self) -> i64 {
+        match self {
+            Newline::Lf => 0,
+            Newline::CrLf => 1,
+            Newline::Native => 2,
+        }
+    }
+
+    /// Mode for a `cheetah_configure_output` code; unknown codes mean Native
+    pub fn from_code(code: i64) -> Self {
+        match code {
+            0 => Newline::Lf,
+            1 => Newline::CrLf,
+            _ => Newline::Native,
+        }
+    }
+
+    /// Whether "\n" is written as "\r\n" on this platform
+    pub fn translates(self) -> bool {
+        match self {
+            Newline::Lf => false,
+            Newline::CrLf => true,
+            Newline::Native => cfg!(windows),
+        }
+    }
+}
+
+/// Set the line endings written to stdout
+pub fn set_newline(newline: Newline) {
+    TRANSLATE_CRLF.store(newline.translates(), Ordering::Relaxed);
+}
+
+/// Output bytes with "\n" translated for the current newline mode
+pub fn translate_newlines(bytes: &[u8]) -> Cow<'_, [u8]> {
+    if !TRANSLATE_CRLF.load(Ordering::Relaxed) || !bytes.contains(&b'\n') {
+        return Cow::Borrowed(bytes);
+    }
+    let mut translated = Vec::with_capacity(bytes.len() + 8);
+    for &byte in bytes {
+        if byte == b'\n' {
+            translated.push(b'\r');
+        }
+        translated.push(byte);
+    }
+    Cow::Owned(translated)
+}
+
+#[cfg(windows)]
+fn enable_utf8_console() {
+    const CP_UTF8: u32 = 65001;
+    extern "system" {
+        fn SetConsoleOutputCP(code_page: u32) -> i32;
+    }
+    unsafe {
+        SetConsoleOutputCP(CP_UTF8);
+    }
+}
+
+#[cfg(not(windows))]
+fn enable_utf8_console() {}
+
+/// Configure stdout for a program: newline mode (a `Newline::code`) and UTF-8 console output
+#[no_mangle]
+pub extern "C" fn cheetah_configure_output(newline: i64) {
+    set_newline(Newline::from_code(newline));
+    enable_utf8_console();
+}
+
+/// Register output configuration functions in the LLVM module
+#[cfg(feature = "codegen")]
+pub fn register_output_functions<'ctx>(context: &'ctx Context, module: &mut Module<'ctx>) {
+    if module.get_function("cheetah_configure_output").is_none() {
+        module.add_function(
+            "cheetah_configure_output",
+            context.void_type().fn_type(&[context.i64_type().into()], false),
+            None,
+        );
+    }
+}
+
+/// Map output configuration functions to their runtime addresses in the JIT
+#[cfg(feature = "codegen")]
+pub fn register_output_runtime_functions(
+    engine: &ExecutionEngine<'_>,
+    module: &Module<'_>,
+) -> Result<(), String> {
+    if let Some(f) = module.get_function("cheetah_configure_output") {
+        engine.add_global_mapping(&f, cheetah_configure_output as *const () as usize);
+    }
+    Ok(())
+}
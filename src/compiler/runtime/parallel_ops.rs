@@ -113,10 +113,14 @@ where
     if should_parallelize(size) {
         PARALLEL_OPERATIONS.fetch_add(1, Ordering::Relaxed);
 
+        // What was printed before the loop comes out before anything it prints
+        super::buffer::flush();
         (0..size).into_par_iter().for_each(|i| {
             let value = start + (i as i64) * step;
             f(value);
         });
+        // and the workers' unfinished lines before anything printed after it
+        rayon::broadcast(|_| super::buffer::flush());
     } else {
         SEQUENTIAL_OPERATIONS.fetch_add(1, Ordering::Relaxed);
 
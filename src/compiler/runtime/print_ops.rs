@@ -1,8 +1,11 @@
 // print_ops.rs - Runtime support for print function
 
-use std::ffi::CStr;
+use std::ffi::{CStr, CString};
+use std::io::{self, BufRead, Write};
 use std::os::raw::c_char;
 
+use crate::compiler::runtime::exception::{exception_new, exception_raise, set_current_exception};
+
 // Cache for the most recently printed string to optimize repeated prints
 thread_local! {
     static LAST_PRINTED: std::cell::RefCell<String> = std::cell::RefCell::new(String::new());
@@ -99,6 +102,53 @@ pub extern "C" fn print_bool(value: bool) {
     super::buffer::write_bool(value);
 }
 
+/// Read one line from `reader` without its line ending, or None at end of input
+pub fn read_input_line(reader: &mut impl BufRead) -> Option<String> {
+    let mut line = String::new();
+    match reader.read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => {
+            if line.ends_with('\n') {
+                line.pop();
+                if line.ends_with('\r') {
+                    line.pop();
+                }
+            }
+            Some(line)
+        }
+    }
+}
+
+fn write_prompt(prompt: *const c_char) {
+    if prompt.is_null() {
+        return;
+    }
+    let prompt = unsafe { CStr::from_ptr(prompt) };
+    let mut stdout = io::stdout();
+    let _ = stdout.write_all(prompt.to_bytes());
+    let _ = stdout.flush();
+}
+
+/// input(prompt): write the prompt, then read a line from stdin as a new heap string.
+///
+/// Raises EOFError and returns an empty string when stdin is exhausted.
+#[no_mangle]
+pub extern "C" fn input_string(prompt: *const c_char) -> *mut c_char {
+    // Pending print() output must appear before the prompt
+    super::buffer::flush();
+    write_prompt(prompt);
+
+    let line = read_input_line(&mut io::stdin().lock()).unwrap_or_else(|| {
+        let typ = CString::new("EOFError").unwrap();
+        let message = CString::new("EOF when reading a line").unwrap();
+        let exception = exception_new(typ.as_ptr(), message.as_ptr());
+        set_current_exception(exception);
+        exception_raise(exception);
+        String::new()
+    });
+    CString::new(line.replace('\0', "")).unwrap_or_default().into_raw()
+}
+
 /// Register print operation functions in the module
 pub fn register_print_functions<'ctx>(
     context: &'ctx inkwell::context::Context,
@@ -130,4 +180,8 @@ pub fn register_print_functions<'ctx>(
         .void_type()
         .fn_type(&[context.bool_type().into()], false);
     module.add_function("print_bool", print_bool_type, None);
+
+    let ptr_type = context.ptr_type(AddressSpace::default());
+    let input_string_type = ptr_type.fn_type(&[ptr_type.into()], false);
+    module.add_function("input_string", input_string_type, None);
 }
@@ -0,0 +1,312 @@
+// sequence_ops.rs - Runtime support for sorted, reversed, sum, abs, round and for loops
+
+use inkwell::context::Context;
+use inkwell::execution_engine::ExecutionEngine;
+use inkwell::module::Module;
+use inkwell::AddressSpace;
+use libc::free;
+use std::ffi::{c_char, c_void, CStr, CString};
+
+use crate::compiler::runtime::dict::copy_tagged;
+use crate::compiler::runtime::exception::exception_raise_new;
+use crate::compiler::runtime::list::{
+    list_append_tagged, list_new, list_with_capacity, tagged_cmp, tagged_number, RawList, TypeTag,
+};
+
+/// Items of a list with their tags, or nothing for a null list
+fn tagged_items(list_ptr: *mut RawList) -> Vec<(*mut c_void, TypeTag)> {
+    let rl = match unsafe { list_ptr.as_ref() } {
+        Some(rl) => rl,
+        None => return Vec::new(),
+    };
+    (0..rl.length as usize)
+        .map(|i| unsafe { (*rl.data.add(i), *rl.tags.add(i)) })
+        .collect()
+}
+
+/// Overwrite the items of a list, in place, with items it already holds
+fn set_tagged_items(list_ptr: *mut RawList, items: impl Iterator<Item = (*mut c_void, TypeTag)>) {
+    let rl = match unsafe { list_ptr.as_mut() } {
+        Some(rl) => rl,
+        None => return,
+    };
+    for (i, (value, tag)) in items.take(rl.length as usize).enumerate() {
+        unsafe {
+            *rl.data.add(i) = value;
+            *rl.tags.add(i) = tag;
+        }
+    }
+}
+
+/// A new list owning copies of the given items
+fn list_from_items(items: impl ExactSizeIterator<Item = (*mut c_void, TypeTag)>) -> *mut RawList {
+    let out = list_with_capacity(items.len() as i64);
+    for (value, tag) in items {
+        list_append_tagged(out, copy_tagged(value, tag, true), tag);
+    }
+    out
+}
+
+/// Absolute value of an integer (wraps for i64::MIN)
+#[no_mangle]
+pub extern "C" fn abs_int(value: i64) -> i64 {
+    value.wrapping_abs()
+}
+
+/// Absolute value of a float
+#[no_mangle]
+pub extern "C" fn abs_float(value: f64) -> f64 {
+    value.abs()
+}
+
+/// round(x) for a float: nearest integer, ties to even
+#[no_mangle]
+pub extern "C" fn round_float(value: f64) -> i64 {
+    value.round_ties_even() as i64
+}
+
+/// round(x, ndigits) for a float, ties to even at the requested digit
+#[no_mangle]
+pub extern "C" fn round_float_digits(value: f64, ndigits: i64) -> f64 {
+    if !value.is_finite() {
+        return value;
+    }
+    let scale = 10f64.powi(ndigits.unsigned_abs().min(308) as i32);
+    let rounded = if ndigits >= 0 {
+        (value * scale).round_ties_even() / scale
+    } else {
+        (value / scale).round_ties_even() * scale
+    };
+    if rounded.is_finite() { rounded } else { value }
+}
+
+/// round(x, ndigits) for an integer; only negative ndigits change the value
+#[no_mangle]
+pub extern "C" fn round_int_digits(value: i64, ndigits: i64) -> i64 {
+    if ndigits >= 0 {
+        return value;
+    }
+    let scale = match 10i64.checked_pow(ndigits.unsigned_abs().min(u32::MAX as u64) as u32) {
+        Some(scale) => scale,
+        None => return 0,
+    };
+    let quotient = value.div_euclid(scale);
+    let remainder = value.rem_euclid(scale);
+    let rounded_up = match (remainder * 2).cmp(&scale) {
+        std::cmp::Ordering::Greater => true,
+        std::cmp::Ordering::Less => false,
+        std::cmp::Ordering::Equal => quotient % 2 != 0,
+    };
+    (quotient + rounded_up as i64).wrapping_mul(scale)
+}
+
+/// sum() of a list of ints and bools
+#[no_mangle]
+pub extern "C" fn sum_int_list(list_ptr: *mut RawList) -> i64 {
+    tagged_items(list_ptr)
+        .into_iter()
+        .map(|(value, tag)| match tag {
+            TypeTag::Int if !value.is_null() => unsafe { *(value as *const i64) },
+            _ => tagged_number(value, tag).map_or(0, |n| n as i64),
+        })
+        .fold(0i64, |acc, n| acc.wrapping_add(n))
+}
+
+/// sum() of a list of numbers as a float
+#[no_mangle]
+pub extern "C" fn sum_float_list(list_ptr: *mut RawList) -> f64 {
+    tagged_items(list_ptr)
+        .into_iter()
+        .filter_map(|(value, tag)| tagged_number(value, tag))
+        .sum()
+}
+
+/// sorted(list): a new list in ascending (or, when `reverse` is non-zero,
+/// descending) order. The sort is stable in both directions.
+#[no_mangle]
+pub extern "C" fn list_sorted(list_ptr: *mut RawList, reverse: i64) -> *mut RawList {
+    let mut items = tagged_items(list_ptr);
+    if reverse != 0 {
+        items.sort_by(|a, b| tagged_cmp(b.0, b.1, a.0, a.1));
+    } else {
+        items.sort_by(|a, b| tagged_cmp(a.0, a.1, b.0, b.1));
+    }
+    list_from_items(items.into_iter())
+}
+
+/// sorted(list, key=...): order items by the precomputed key at the same index.
+///
+/// Takes ownership of the keys list and frees it.
+#[no_mangle]
+pub extern "C" fn list_sorted_by_keys(
+    list_ptr: *mut RawList,
+    keys_ptr: *mut RawList,
+    reverse: i64,
+) -> *mut RawList {
+    let items = tagged_items(list_ptr);
+    let keys = tagged_items(keys_ptr);
+    let sorted = if keys.len() != items.len() {
+        list_new()
+    } else {
+        let mut order: Vec<usize> = (0..items.len()).collect();
+        let by_key =
+            |a: &usize, b: &usize| tagged_cmp(keys[*a].0, keys[*a].1, keys[*b].0, keys[*b].1);
+        if reverse != 0 {
+            order.sort_by(|a, b| by_key(b, a));
+        } else {
+            order.sort_by(by_key);
+        }
+        list_from_items(order.into_iter().map(|i| items[i]))
+    };
+    free_keys(keys_ptr);
+    sorted
+}
+
+/// list.sort(key=..., reverse=...): reorder the list in place by the
+/// precomputed key at the same index, or by the items themselves when
+/// `keys_ptr` is null. Stable in both directions.
+///
+/// Takes ownership of the keys list and frees it.
+#[no_mangle]
+pub extern "C" fn list_sort_by_keys(list_ptr: *mut RawList, keys_ptr: *mut RawList, reverse: i64) {
+    let items = tagged_items(list_ptr);
+    let keys = match keys_ptr.is_null() {
+        true => items.clone(),
+        false => tagged_items(keys_ptr),
+    };
+    if keys.len() == items.len() && items.len() > 1 {
+        let mut order: Vec<usize> = (0..items.len()).collect();
+        let by_key = |a: &usize, b: &usize| tagged_cmp(keys[*a].0, keys[*a].1, keys[*b].0, keys[*b].1);
+        if reverse != 0 {
+            order.sort_by(|a, b| by_key(b, a));
+        } else {
+            order.sort_by(by_key);
+        }
+        set_tagged_items(list_ptr, order.into_iter().map(|i| items[i]));
+    }
+    free_keys(keys_ptr);
+}
+
+/// Free a list of sort keys. Only boxed scalars belong to it; strings and
+/// containers returned by the key function may still be referenced elsewhere.
+fn free_keys(keys_ptr: *mut RawList) {
+    if keys_ptr.is_null() {
+        return;
+    }
+    unsafe {
+        for (value, tag) in tagged_items(keys_ptr) {
+            if matches!(tag, TypeTag::Bool | TypeTag::Int | TypeTag::Float) && !value.is_null() {
+                free(value);
+            }
+        }
+        let rl = &*keys_ptr;
+        if !rl.data.is_null() {
+            free(rl.data as *mut c_void);
+        }
+        if !rl.tags.is_null() {
+            free(rl.tags as *mut c_void);
+        }
+        free(keys_ptr as *mut c_void);
+    }
+}
+
+/// reversed(list): a new list with the items in reverse order
+#[no_mangle]
+pub extern "C" fn list_reversed(list_ptr: *mut RawList) -> *mut RawList {
+    list_from_items(tagged_items(list_ptr).into_iter().rev())
+}
+
+/// The items a for loop over `value`, whose runtime tag is `tag`, visits, as
+/// a list: a list is its own items and a string gives its characters. A
+/// value of unknown type is a list, the container untyped code builds.
+/// Anything else raises TypeError and gives no items.
+#[no_mangle]
+pub extern "C" fn iter_items(value: *mut c_void, tag: TypeTag) -> *mut RawList {
+    match tag {
+        TypeTag::List | TypeTag::Any | TypeTag::String if value.is_null() => list_new(),
+        TypeTag::List | TypeTag::Any => value as *mut RawList,
+        TypeTag::String => {
+            let text = unsafe { CStr::from_ptr(value as *const c_char) }.to_string_lossy();
+            let out = list_with_capacity(text.chars().count() as i64);
+            for c in text.chars() {
+                let item = CString::new(c.to_string()).unwrap_or_default().into_raw();
+                list_append_tagged(out, item as *mut c_void, TypeTag::String);
+            }
+            out
+        }
+        _ => {
+            let type_name = match tag {
+                TypeTag::None_ => "NoneType",
+                TypeTag::Bool => "bool",
+                TypeTag::Int => "int",
+                TypeTag::Float => "float",
+                TypeTag::Tuple => "tuple",
+                _ => "object",
+            };
+            let typ = CString::new("TypeError").unwrap();
+            let message = CString::new(format!("'{}' object is not iterable", type_name)).unwrap();
+            exception_raise_new(typ.as_ptr(), message.as_ptr());
+            list_new()
+        }
+    }
+}
+
+/// Register sequence and numeric built-in functions in the LLVM module
+pub fn register_sequence_functions<'ctx>(context: &'ctx Context, module: &mut Module<'ctx>) {
+    let i64_type = context.i64_type();
+    let f64_type = context.f64_type();
+    let ptr_type = context.ptr_type(AddressSpace::default());
+
+    let declarations = [
+        ("abs_int", i64_type.fn_type(&[i64_type.into()], false)),
+        ("abs_float", f64_type.fn_type(&[f64_type.into()], false)),
+        ("round_float", i64_type.fn_type(&[f64_type.into()], false)),
+        ("round_float_digits", f64_type.fn_type(&[f64_type.into(), i64_type.into()], false)),
+        ("round_int_digits", i64_type.fn_type(&[i64_type.into(), i64_type.into()], false)),
+        ("sum_int_list", i64_type.fn_type(&[ptr_type.into()], false)),
+        ("sum_float_list", f64_type.fn_type(&[ptr_type.into()], false)),
+        ("list_sorted", ptr_type.fn_type(&[ptr_type.into(), i64_type.into()], false)),
+        (
+            "list_sorted_by_keys",
+            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into(), i64_type.into()], false),
+        ),
+        (
+            "list_sort_by_keys",
+            context.void_type().fn_type(&[ptr_type.into(), ptr_type.into(), i64_type.into()], false),
+        ),
+        ("list_reversed", ptr_type.fn_type(&[ptr_type.into()], false)),
+        ("iter_items", ptr_type.fn_type(&[ptr_type.into(), context.i8_type().into()], false)),
+    ];
+    for (name, fn_type) in declarations {
+        if module.get_function(name).is_none() {
+            module.add_function(name, fn_type, None);
+        }
+    }
+}
+
+/// Map sequence and numeric built-in functions to their runtime addresses in the JIT
+pub fn register_sequence_runtime_functions(
+    engine: &ExecutionEngine<'_>,
+    module: &Module<'_>,
+) -> Result<(), String> {
+    let mappings: [(&str, usize); 12] = [
+        ("abs_int", abs_int as *const () as usize),
+        ("abs_float", abs_float as *const () as usize),
+        ("round_float", round_float as *const () as usize),
+        ("round_float_digits", round_float_digits as *const () as usize),
+        ("round_int_digits", round_int_digits as *const () as usize),
+        ("sum_int_list", sum_int_list as *const () as usize),
+        ("sum_float_list", sum_float_list as *const () as usize),
+        ("list_sorted", list_sorted as *const () as usize),
+        ("list_sorted_by_keys", list_sorted_by_keys as *const () as usize),
+        ("list_sort_by_keys", list_sort_by_keys as *const () as usize),
+        ("list_reversed", list_reversed as *const () as usize),
+        ("iter_items", iter_items as *const () as usize),
+    ];
+    for (name, address) in mappings {
+        if let Some(f) = module.get_function(name) {
+            engine.add_global_mapping(&f, address);
+        }
+    }
+    Ok(())
+}
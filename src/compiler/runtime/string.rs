@@ -3,9 +3,16 @@
 use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
 use inkwell::context::Context;
+use inkwell::execution_engine::ExecutionEngine;
 use inkwell::module::Module;
 use inkwell::AddressSpace;
 
+use crate::compiler::runtime::exception::exception_raise_new;
+use crate::compiler::runtime::list::{
+    list_append_tagged, list_get, list_get_tag, list_len, list_new, list_with_capacity, RawList,
+    TypeTag,
+};
+
 #[no_mangle]
 pub extern "C" fn int_to_string(value: i64) -> *mut c_char {
     let s = format!("{}", value);
@@ -14,7 +21,7 @@ pub extern "C" fn int_to_string(value: i64) -> *mut c_char {
 
 #[no_mangle]
 pub extern "C" fn float_to_string(value: f64) -> *mut c_char {
-    let s = format!("{}", value);
+    let s = super::number_format::format_float(value);
     CString::new(s).unwrap().into_raw()
 }
 
@@ -107,6 +114,177 @@ pub extern "C" fn string_concat(s1: *const c_char, s2: *const c_char) -> *mut c_
     CString::new(format!("{}{}", s1, s2)).unwrap().into_raw()
 }
 
+fn str_arg<'a>(value: *const c_char) -> &'a str {
+    if value.is_null() { return ""; }
+    unsafe { CStr::from_ptr(value).to_str().unwrap_or("") }
+}
+
+fn into_c_string(s: String) -> *mut c_char {
+    CString::new(s).unwrap_or_default().into_raw()
+}
+
+/// A C string copied from `bytes`, allocated once at its final size
+fn c_string_from_bytes(bytes: &[u8]) -> *mut c_char {
+    let mut buffer = Vec::with_capacity(bytes.len() + 1);
+    buffer.extend_from_slice(bytes);
+    buffer.push(0);
+    CString::from_vec_with_nul(buffer).unwrap_or_default().into_raw()
+}
+
+/// Raise a `typ` exception from a string method
+fn raise(typ: &str, message: &str) {
+    let typ = CString::new(typ).unwrap();
+    let message = CString::new(message.replace('\0', "")).unwrap();
+    exception_raise_new(typ.as_ptr(), message.as_ptr());
+}
+
+/// str.split(sep) - a null separator, from `split()`, splits on runs of
+/// whitespace, and an empty one raises ValueError. The parts are found first
+/// so the list is allocated once at its final size.
+#[no_mangle]
+pub extern "C" fn string_split(value: *const c_char, sep: *const c_char) -> *mut RawList {
+    let s = str_arg(value);
+    let parts: Vec<&str> = if sep.is_null() {
+        s.split_whitespace().collect()
+    } else {
+        let sep = str_arg(sep);
+        if sep.is_empty() {
+            raise("ValueError", "empty separator");
+            return list_new();
+        }
+        s.split(sep).collect()
+    };
+    let list = list_with_capacity(parts.len() as i64);
+    for part in parts {
+        let part_ptr = c_string_from_bytes(part.as_bytes());
+        list_append_tagged(list, part_ptr as *mut std::ffi::c_void, TypeTag::String);
+    }
+    list
+}
+
+/// sep.join(list) - joins the string elements of a list into a string
+/// allocated once, at the summed length of the pieces and separators. An
+/// element not tagged as a string, including one of unknown type, raises
+/// TypeError and gives an empty string.
+#[no_mangle]
+pub extern "C" fn string_join(sep: *const c_char, list_ptr: *mut RawList) -> *mut c_char {
+    let sep = str_arg(sep).as_bytes();
+    let mut parts: Vec<&[u8]> = Vec::with_capacity(list_len(list_ptr) as usize);
+    for i in 0..list_len(list_ptr) {
+        let elem = list_get(list_ptr, i);
+        let found = match list_get_tag(list_ptr, i) {
+            _ if elem.is_null() => "NoneType",
+            TypeTag::String => {
+                parts.push(unsafe { CStr::from_ptr(elem as *const c_char) }.to_bytes());
+                continue;
+            }
+            TypeTag::Any => "object",
+            TypeTag::None_ => "NoneType",
+            TypeTag::Bool => "bool",
+            TypeTag::Int => "int",
+            TypeTag::Float => "float",
+            TypeTag::List => "list",
+            TypeTag::Tuple => "tuple",
+        };
+        raise(
+            "TypeError",
+            &format!("sequence item {}: expected str instance, {} found", i, found),
+        );
+        return into_c_string(String::new());
+    }
+    let size = parts.iter().map(|part| part.len()).sum::<usize>() + sep.len() * parts.len().saturating_sub(1);
+
+    let mut buffer = Vec::with_capacity(size + 1);
+    for (i, part) in parts.iter().enumerate() {
+        if i > 0 {
+            buffer.extend_from_slice(sep);
+        }
+        buffer.extend_from_slice(part);
+    }
+    buffer.push(0);
+    CString::from_vec_with_nul(buffer).unwrap_or_default().into_raw()
+}
+
+#[no_mangle]
+pub extern "C" fn string_strip(value: *const c_char) -> *mut c_char {
+    into_c_string(str_arg(value).trim().to_string())
+}
+
+#[no_mangle]
+pub extern "C" fn string_upper(value: *const c_char) -> *mut c_char {
+    into_c_string(str_arg(value).to_uppercase())
+}
+
+#[no_mangle]
+pub extern "C" fn string_lower(value: *const c_char) -> *mut c_char {
+    into_c_string(str_arg(value).to_lowercase())
+}
+
+#[no_mangle]
+pub extern "C" fn string_replace(
+    value: *const c_char,
+    old: *const c_char,
+    new: *const c_char,
+) -> *mut c_char {
+    into_c_string(str_arg(value).replace(str_arg(old), str_arg(new)))
+}
+
+/// str.find(sub) - character index of the first match, or -1
+#[no_mangle]
+pub extern "C" fn string_find(value: *const c_char, sub: *const c_char) -> i64 {
+    let s = str_arg(value);
+    match s.find(str_arg(sub)) {
+        Some(byte_idx) => s[..byte_idx].chars().count() as i64,
+        None => -1,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn string_startswith(value: *const c_char, prefix: *const c_char) -> bool {
+    str_arg(value).starts_with(str_arg(prefix))
+}
+
+#[no_mangle]
+pub extern "C" fn string_endswith(value: *const c_char, suffix: *const c_char) -> bool {
+    str_arg(value).ends_with(str_arg(suffix))
+}
+
+/// Growable buffer collecting the pieces of a string built with `+=` in a loop
+pub struct StringBuilder {
+    buffer: String,
+}
+
+fn builder_mut<'a>(builder: *mut StringBuilder) -> Option<&'a mut StringBuilder> {
+    unsafe { builder.as_mut() }
+}
+
+fn take_builder(builder: *mut StringBuilder) -> Option<Box<StringBuilder>> {
+    if builder.is_null() {
+        return None;
+    }
+    Some(unsafe { Box::from_raw(builder) })
+}
+
+/// Start a string builder holding a copy of `initial`
+#[no_mangle]
+pub extern "C" fn string_builder_new(initial: *const c_char) -> *mut StringBuilder {
+    Box::into_raw(Box::new(StringBuilder { buffer: str_arg(initial).to_string() }))
+}
+
+/// Append a piece in amortized constant time per byte
+#[no_mangle]
+pub extern "C" fn string_builder_append(builder: *mut StringBuilder, piece: *const c_char) {
+    if let Some(builder) = builder_mut(builder) {
+        builder.buffer.push_str(str_arg(piece));
+    }
+}
+
+/// Free the builder and return the string it built
+#[no_mangle]
+pub extern "C" fn string_builder_finish(builder: *mut StringBuilder) -> *mut c_char {
+    into_c_string(take_builder(builder).map(|b| b.buffer).unwrap_or_default())
+}
+
 /// Register string functions in the LLVM module
 pub fn register_string_functions<'ctx>(context: &'ctx Context, module: &mut Module<'ctx>) {
     module.add_function(
@@ -148,4 +326,64 @@ pub fn register_string_functions<'ctx>(context: &'ctx Context, module: &mut Modu
         context.void_type().fn_type(&[context.ptr_type(AddressSpace::default()).into()], false),
         None,
     );
+
+    let ptr_type = context.ptr_type(AddressSpace::default());
+    module.add_function(
+        "string_split",
+        ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+        None,
+    );
+    module.add_function(
+        "string_join",
+        ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+        None,
+    );
+    for name in ["string_strip", "string_upper", "string_lower"] {
+        module.add_function(name, ptr_type.fn_type(&[ptr_type.into()], false), None);
+    }
+    module.add_function(
+        "string_replace",
+        ptr_type.fn_type(&[ptr_type.into(), ptr_type.into(), ptr_type.into()], false),
+        None,
+    );
+    module.add_function(
+        "string_find",
+        context.i64_type().fn_type(&[ptr_type.into(), ptr_type.into()], false),
+        None,
+    );
+    for name in ["string_startswith", "string_endswith"] {
+        module.add_function(
+            name,
+            context.bool_type().fn_type(&[ptr_type.into(), ptr_type.into()], false),
+            None,
+        );
+    }
+
+    module.add_function("string_builder_new", ptr_type.fn_type(&[ptr_type.into()], false), None);
+    module.add_function(
+        "string_builder_append",
+        context.void_type().fn_type(&[ptr_type.into(), ptr_type.into()], false),
+        None,
+    );
+    module.add_function("string_builder_finish", ptr_type.fn_type(&[ptr_type.into()], false), None);
+}
+
+/// Map the string method runtime functions into a JIT execution engine
+pub fn register_string_runtime_functions(
+    engine: &ExecutionEngine<'_>,
+    module: &Module<'_>,
+) -> Result<(), String> {
+    if let Some(f) = module.get_function("string_split") { engine.add_global_mapping(&f, string_split as *const () as usize); }
+    if let Some(f) = module.get_function("string_join") { engine.add_global_mapping(&f, string_join as *const () as usize); }
+    if let Some(f) = module.get_function("string_strip") { engine.add_global_mapping(&f, string_strip as *const () as usize); }
+    if let Some(f) = module.get_function("string_upper") { engine.add_global_mapping(&f, string_upper as *const () as usize); }
+    if let Some(f) = module.get_function("string_lower") { engine.add_global_mapping(&f, string_lower as *const () as usize); }
+    if let Some(f) = module.get_function("string_replace") { engine.add_global_mapping(&f, string_replace as *const () as usize); }
+    if let Some(f) = module.get_function("string_find") { engine.add_global_mapping(&f, string_find as *const () as usize); }
+    if let Some(f) = module.get_function("string_startswith") { engine.add_global_mapping(&f, string_startswith as *const () as usize); }
+    if let Some(f) = module.get_function("string_endswith") { engine.add_global_mapping(&f, string_endswith as *const () as usize); }
+    if let Some(f) = module.get_function("string_builder_new") { engine.add_global_mapping(&f, string_builder_new as *const () as usize); }
+    if let Some(f) = module.get_function("string_builder_append") { engine.add_global_mapping(&f, string_builder_append as *const () as usize); }
+    if let Some(f) = module.get_function("string_builder_finish") { engine.add_global_mapping(&f, string_builder_finish as *const () as usize); }
+    Ok(())
 }
@@ -0,0 +1,180 @@
+// timeout.rs - Deadlines for with_timeout(), checked as each statement starts
+//
+// `with_timeout(seconds, f)` arms a deadline on the calling thread, calls
+// `f` and disarms it again:
+//
+//     result = with_timeout(0.5, work)    # depth = timeout_enter(0.5)
+//                                         # result = work()
+//                                         # timeout_exit(depth)
+//
+// A timer thread sets the thread's interrupt flag once the deadline passes.
+// Compiled code checks the flag as each statement starts (`trace_set_line`)
+// and after each call returns (`trace_stopping`), and returns a zero result
+// at once when it is set, so every function `f` has called returns before
+// using anything an interrupted callee returned. `timeout_exit` then drops the
+// traceback frames and `except` blocks those functions left without
+// cleaning up, clears the flag and raises TimeoutError.
+//
+// Interruption is cooperative: a long runtime call, such as a sleep or a
+// blocking recv(), finishes before the next statement sees the flag, and
+// worker threads of a parallel loop are not interrupted.
+
+use inkwell::context::Context;
+use inkwell::execution_engine::ExecutionEngine;
+use inkwell::module::Module;
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::compiler::runtime::{exception, traceback};
+
+/// Lets a timer thread know it has been disarmed
+#[derive(Default)]
+struct Disarm {
+    disarmed: Mutex<bool>,
+    signal: Condvar,
+}
+
+/// A deadline armed by timeout_enter()
+struct Deadline {
+    seconds: f64,
+    expired: Arc<AtomicBool>,
+    disarm: Arc<Disarm>,
+    /// Traceback frames and `except` blocks active when it was armed
+    frames: usize,
+    handlers: usize,
+}
+
+impl Deadline {
+    /// Stop its timer. Once this returns the deadline can no longer expire.
+    fn disarm(&self) {
+        *self.disarm.disarmed.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        self.disarm.signal.notify_one();
+    }
+}
+
+thread_local! {
+    /// Set by a timer thread when one of this thread's deadlines passes
+    static INTERRUPT: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+    /// This thread's armed deadlines, innermost last
+    static DEADLINES: RefCell<Vec<Deadline>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Whether a deadline has passed and the running function should return
+pub fn interrupted() -> bool {
+    INTERRUPT.with(|flag| flag.load(Ordering::Relaxed))
+}
+
+/// Arm a deadline `seconds` from now. Returns the depth to pass to timeout_exit().
+#[no_mangle]
+pub extern "C" fn timeout_enter(seconds: f64) -> i64 {
+    let deadline = Deadline {
+        seconds,
+        expired: Arc::new(AtomicBool::new(false)),
+        disarm: Arc::new(Disarm::default()),
+        frames: traceback::depth(),
+        handlers: exception::exception_handler_depth() as usize,
+    };
+
+    // A timeout too long to represent never expires
+    if let Ok(timeout) = Duration::try_from_secs_f64(seconds.max(0.0)) {
+        let (expired, disarm, interrupt) = (
+            deadline.expired.clone(),
+            deadline.disarm.clone(),
+            INTERRUPT.with(Arc::clone),
+        );
+        thread::spawn(move || {
+            let disarmed = disarm.disarmed.lock().unwrap_or_else(|e| e.into_inner());
+            let (disarmed, _) = disarm
+                .signal
+                .wait_timeout_while(disarmed, timeout, |disarmed| !*disarmed)
+                .unwrap_or_else(|e| e.into_inner());
+            if !*disarmed {
+                expired.store(true, Ordering::Relaxed);
+                interrupt.store(true, Ordering::Relaxed);
+            }
+        });
+    }
+
+    DEADLINES.with(|deadlines| {
+        let mut deadlines = deadlines.borrow_mut();
+        deadlines.push(deadline);
+        deadlines.len() as i64 - 1
+    })
+}
+
+/// Disarm the deadline timeout_enter() returned `depth` for, raising
+/// TimeoutError if it passed. Returns whether it did.
+#[no_mangle]
+pub extern "C" fn timeout_exit(depth: i64) -> bool {
+    let (deadline, outer_expired) = DEADLINES.with(|deadlines| {
+        let mut deadlines = deadlines.borrow_mut();
+        let depth = (depth.max(0) as usize).min(deadlines.len());
+        let inner = deadlines.split_off(depth);
+        for deadline in &inner {
+            deadline.disarm();
+        }
+        let outer_expired = deadlines.iter().any(|d| d.expired.load(Ordering::Relaxed));
+        (inner.into_iter().next(), outer_expired)
+    });
+    let Some(deadline) = deadline else {
+        return false;
+    };
+    if !interrupted() {
+        return false;
+    }
+
+    // The functions that returned early left their frames and handlers behind
+    traceback::trace_pop_frames(deadline.frames as i64);
+    exception::truncate_handlers(deadline.handlers);
+    // An enclosing deadline that passed keeps interrupting its own callee
+    if !outer_expired {
+        INTERRUPT.with(|flag| flag.store(false, Ordering::Relaxed));
+    }
+    if !deadline.expired.load(Ordering::Relaxed) {
+        return false;
+    }
+    let typ = CString::new("TimeoutError").unwrap();
+    let message = CString::new(format!("with_timeout() timed out after {} seconds", deadline.seconds)).unwrap();
+    exception::exception_raise_new(typ.as_ptr(), message.as_ptr());
+    true
+}
+
+/// Register timeout functions in the LLVM module
+pub fn register_timeout_functions<'ctx>(context: &'ctx Context, module: &mut Module<'ctx>) {
+    let functions = [
+        (
+            "timeout_enter",
+            context.i64_type().fn_type(&[context.f64_type().into()], false),
+        ),
+        (
+            "timeout_exit",
+            context.bool_type().fn_type(&[context.i64_type().into()], false),
+        ),
+    ];
+    for (name, fn_type) in functions {
+        if module.get_function(name).is_none() {
+            module.add_function(name, fn_type, None);
+        }
+    }
+}
+
+/// Map timeout functions to their runtime addresses in the JIT
+pub fn register_timeout_runtime_functions(
+    engine: &ExecutionEngine<'_>,
+    module: &Module<'_>,
+) -> Result<(), String> {
+    let mappings: [(&str, usize); 2] = [
+        ("timeout_enter", timeout_enter as *const () as usize),
+        ("timeout_exit", timeout_exit as *const () as usize),
+    ];
+    for (name, address) in mappings {
+        if let Some(f) = module.get_function(name) {
+            engine.add_global_mapping(&f, address);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,151 @@
+// traceback.rs - Call frames of the running program, reported when an exception is raised
+
+use inkwell::context::Context;
+use inkwell::execution_engine::ExecutionEngine;
+use inkwell::module::Module;
+use inkwell::AddressSpace;
+use std::cell::RefCell;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+
+use crate::compiler::runtime::{abort, timeout};
+
+/// A function being executed and the line it is currently running
+struct Frame {
+    function: String,
+    line: i64,
+}
+
+thread_local! {
+    static FRAMES: RefCell<Vec<Frame>> = const { RefCell::new(Vec::new()) };
+    static SOURCE_NAME: RefCell<String> = RefCell::new("<program>".to_string());
+}
+
+/// Set the file name shown in tracebacks
+pub fn set_source_name(name: &str) {
+    SOURCE_NAME.with(|source| *source.borrow_mut() = name.to_string());
+}
+
+fn function_name(function: *const c_char) -> String {
+    if function.is_null() {
+        return "<unknown>".to_string();
+    }
+    unsafe { CStr::from_ptr(function) }.to_string_lossy().into_owned()
+}
+
+/// Enter a function. Returns the frame depth to restore when it exits.
+#[no_mangle]
+pub extern "C" fn trace_push_frame(function: *const c_char) -> i64 {
+    let function = function_name(function);
+    FRAMES.with(|frames| {
+        let mut frames = frames.borrow_mut();
+        frames.push(Frame { function, line: 0 });
+        frames.len() as i64 - 1
+    })
+}
+
+/// Leave every frame above `depth`, as returned by the matching trace_push_frame()
+#[no_mangle]
+pub extern "C" fn trace_pop_frames(depth: i64) {
+    FRAMES.with(|frames| frames.borrow_mut().truncate(depth.max(0) as usize));
+}
+
+/// Record the source line the innermost frame is executing. Returns whether
+/// a with_timeout() deadline has passed or a runtime error is stopping the
+/// program, in which case the function returns at once (see `timeout.rs`
+/// and `abort.rs`).
+#[no_mangle]
+pub extern "C" fn trace_set_line(line: i64) -> bool {
+    FRAMES.with(|frames| {
+        if let Some(frame) = frames.borrow_mut().last_mut() {
+            frame.line = line;
+        }
+    });
+    trace_stopping()
+}
+
+/// Whether a with_timeout() deadline has passed or a runtime error is
+/// stopping the program. Compiled code checks this after each call it
+/// makes, so a caller never uses the zero result of a function cut short.
+#[no_mangle]
+pub extern "C" fn trace_stopping() -> bool {
+    timeout::interrupted() || abort::aborting()
+}
+
+/// The source name, line and function of the innermost frame, or None
+/// outside any compiled function
+pub fn current_location() -> Option<(String, i64, String)> {
+    let source = SOURCE_NAME.with(|source| source.borrow().clone());
+    FRAMES.with(|frames| {
+        let frames = frames.borrow();
+        let frame = frames.last()?;
+        Some((source, frame.line, frame.function.clone()))
+    })
+}
+
+/// Number of frames entered and not yet left
+pub fn depth() -> usize {
+    FRAMES.with(|frames| frames.borrow().len())
+}
+
+/// The current call stack formatted as a traceback, outermost call first,
+/// or None outside any compiled function
+pub fn format_traceback() -> Option<String> {
+    let source = SOURCE_NAME.with(|source| source.borrow().clone());
+    FRAMES.with(|frames| {
+        let frames = frames.borrow();
+        if frames.is_empty() {
+            return None;
+        }
+        let mut out = String::from("Traceback (most recent call last):\n");
+        for frame in frames.iter() {
+            out.push_str(&format!(
+                "  File \"{}\", line {}, in {}\n",
+                source, frame.line, frame.function
+            ));
+        }
+        Some(out)
+    })
+}
+
+/// Drop all frames, after a program finishes or is interrupted
+pub fn reset() {
+    FRAMES.with(|frames| frames.borrow_mut().clear());
+}
+
+/// Register traceback functions in the LLVM module
+pub fn register_traceback_functions<'ctx>(context: &'ctx Context, module: &mut Module<'ctx>) {
+    let ptr_type = context.ptr_type(AddressSpace::default());
+    let i64_type = context.i64_type();
+
+    let declarations = [
+        ("trace_push_frame", i64_type.fn_type(&[ptr_type.into()], false)),
+        ("trace_pop_frames", context.void_type().fn_type(&[i64_type.into()], false)),
+        ("trace_set_line", context.bool_type().fn_type(&[i64_type.into()], false)),
+        ("trace_stopping", context.bool_type().fn_type(&[], false)),
+    ];
+    for (name, fn_type) in declarations {
+        if module.get_function(name).is_none() {
+            module.add_function(name, fn_type, None);
+        }
+    }
+}
+
+/// Map traceback functions to their runtime addresses in the JIT
+pub fn register_traceback_runtime_functions(
+    engine: &ExecutionEngine<'_>,
+    module: &Module<'_>,
+) -> Result<(), String> {
+    let mappings: [(&str, usize); 4] = [
+        ("trace_push_frame", trace_push_frame as *const () as usize),
+        ("trace_pop_frames", trace_pop_frames as *const () as usize),
+        ("trace_set_line", trace_set_line as *const () as usize),
+        ("trace_stopping", trace_stopping as *const () as usize),
+    ];
+    for (name, address) in mappings {
+        if let Some(f) = module.get_function(name) {
+            engine.add_global_mapping(&f, address);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,94 @@
+// sanitizer.rs - Sanitizer instrumentation for AOT builds
+//
+// Instrumentation is applied to the generated module just before the object
+// file is written, and the matching sanitizer runtime is linked in. The Rust
+// runtime library is instrumented separately: build it with
+// `RUSTFLAGS="-Zsanitizer=address" cargo +nightly build --release --target-dir target/asan`
+// (or `ubsan` for `undefined`) and builds with that sanitizer link against it.
+
+use crate::compiler::Compiler;
+use inkwell::attributes::{Attribute, AttributeLoc};
+use inkwell::passes::PassBuilderOptions;
+use inkwell::targets::TargetMachine;
+
+/// A sanitizer that AOT builds can be instrumented with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sanitizer {
+    /// AddressSanitizer: out-of-bounds accesses, use-after-free and leaks
+    Address,
+    /// The IR-level subset of UndefinedBehaviorSanitizer. Most UBSan checks
+    /// are inserted by the C frontend, so generated code gets LLVM's bounds
+    /// checking; the runtime gets the full set when built with it.
+    Undefined,
+}
+
+impl Sanitizer {
+    /// Parse a sanitizer name as given to `--sanitize`
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "address" => Ok(Sanitizer::Address),
+            "undefined" => Ok(Sanitizer::Undefined),
+            other => Err(format!(
+                "unknown sanitizer '{}' (expected 'address' or 'undefined')",
+                other
+            )),
+        }
+    }
+
+    /// Name as accepted by `-fsanitize=`
+    pub fn name(self) -> &'static str {
+        match self {
+            Sanitizer::Address => "address",
+            Sanitizer::Undefined => "undefined",
+        }
+    }
+
+    /// Target directory of the runtime library built with this sanitizer
+    pub fn runtime_target_dir(self) -> &'static str {
+        match self {
+            Sanitizer::Address => "asan",
+            Sanitizer::Undefined => "ubsan",
+        }
+    }
+
+    /// LLVM pass pipeline that instruments a module
+    fn passes(self) -> &'static str {
+        match self {
+            Sanitizer::Address => "asan",
+            Sanitizer::Undefined => "function(bounds-checking)",
+        }
+    }
+
+    /// Function attribute that enables instrumentation of a function, if any
+    fn function_attribute(self) -> Option<&'static str> {
+        match self {
+            Sanitizer::Address => Some("sanitize_address"),
+            Sanitizer::Undefined => None,
+        }
+    }
+}
+
+impl<'ctx> Compiler<'ctx> {
+    /// Instrument every function defined in the module for the selected sanitizers
+    pub fn apply_sanitizers(&self, machine: &TargetMachine) -> Result<(), String> {
+        for &sanitizer in &self.sanitizers {
+            if let Some(name) = sanitizer.function_attribute() {
+                let kind = Attribute::get_named_enum_kind_id(name);
+                let attribute = self.context.llvm_context.create_enum_attribute(kind, 0);
+                let mut function = self.context.module.get_first_function();
+                while let Some(f) = function {
+                    if f.count_basic_blocks() > 0 {
+                        f.add_attribute(AttributeLoc::Function, attribute);
+                    }
+                    function = f.get_next_function();
+                }
+            }
+
+            self.context
+                .module
+                .run_passes(sanitizer.passes(), machine, PassBuilderOptions::create())
+                .map_err(|e| format!("Failed to apply {} sanitizer: {}", sanitizer.name(), e))?;
+        }
+        Ok(())
+    }
+}
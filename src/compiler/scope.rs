@@ -1,4 +1,5 @@
 use crate::compiler::types::Type;
+use crate::intern::Symbol;
 use inkwell::values::PointerValue;
 use std::collections::HashMap;
 
@@ -6,13 +7,13 @@ use std::collections::HashMap;
 #[derive(Debug, Clone)]
 pub struct Scope<'ctx> {
     /// Maps variable names to their storage locations
-    pub variables: HashMap<String, PointerValue<'ctx>>,
+    pub variables: HashMap<Symbol, PointerValue<'ctx>>,
     /// Maps variable names to their types
-    pub types: HashMap<String, Type>,
+    pub types: HashMap<Symbol, Type>,
     /// Variables declared as global in this scope
-    pub global_vars: Vec<String>,
+    pub global_vars: Vec<Symbol>,
     /// Variables declared as nonlocal in this scope
-    pub nonlocal_vars: Vec<String>,
+    pub nonlocal_vars: Vec<Symbol>,
     /// Whether this scope is a function scope
     pub is_function: bool,
     /// Whether this scope is a loop scope
@@ -21,12 +22,12 @@ pub struct Scope<'ctx> {
     pub is_class: bool,
     /// Maps nonlocal variable names to their captured pointers
     /// This is used for nested functions to access variables from outer scopes
-    pub captured_vars: HashMap<String, PointerValue<'ctx>>,
+    pub captured_vars: HashMap<Symbol, PointerValue<'ctx>>,
     /// Variables that need to be heap-allocated because they're accessed by nested functions
-    pub heap_vars: Vec<String>,
+    pub heap_vars: Vec<Symbol>,
     /// Maps original variable names to their unique names in the current scope
     /// This is used for nonlocal variables to avoid LLVM's dominance validation issues
-    pub nonlocal_mappings: HashMap<String, String>,
+    pub nonlocal_mappings: HashMap<Symbol, String>,
 }
 
 impl<'ctx> Scope<'ctx> {
@@ -47,82 +48,86 @@ impl<'ctx> Scope<'ctx> {
     }
 
     /// Add a mapping from an original variable name to a unique name
-    pub fn add_nonlocal_mapping(&mut self, original_name: String, unique_name: String) {
-        self.nonlocal_mappings.insert(original_name, unique_name);
+    pub fn add_nonlocal_mapping(&mut self, original_name: impl Into<Symbol>, unique_name: String) {
+        self.nonlocal_mappings.insert(original_name.into(), unique_name);
     }
 
     /// Get the unique name for a nonlocal variable
-    pub fn get_nonlocal_mapping(&self, original_name: &str) -> Option<&String> {
-        self.nonlocal_mappings.get(original_name)
+    pub fn get_nonlocal_mapping(&self, original_name: impl Into<Symbol>) -> Option<&String> {
+        self.nonlocal_mappings.get(&original_name.into())
     }
 
     /// Get a variable's storage location
-    pub fn get_variable(&self, name: &str) -> Option<&PointerValue<'ctx>> {
-        self.variables.get(name)
+    pub fn get_variable(&self, name: impl Into<Symbol>) -> Option<&PointerValue<'ctx>> {
+        self.variables.get(&name.into())
     }
 
     /// Get a variable's type
-    pub fn get_type(&self, name: &str) -> Option<&Type> {
-        self.types.get(name)
+    pub fn get_type(&self, name: impl Into<Symbol>) -> Option<&Type> {
+        self.types.get(&name.into())
     }
 
     /// Add a variable to this scope
-    pub fn add_variable(&mut self, name: String, ptr: PointerValue<'ctx>, ty: Type) {
-        self.variables.insert(name.clone(), ptr);
+    pub fn add_variable(&mut self, name: impl Into<Symbol>, ptr: PointerValue<'ctx>, ty: Type) {
+        let name = name.into();
+        self.variables.insert(name, ptr);
         self.types.insert(name, ty);
     }
 
     /// Add a type to this scope
-    pub fn add_type(&mut self, name: String, ty: Type) {
-        self.types.insert(name, ty);
+    pub fn add_type(&mut self, name: impl Into<Symbol>, ty: Type) {
+        self.types.insert(name.into(), ty);
     }
 
     /// Check if a variable is declared as global in this scope
-    pub fn is_global(&self, name: &str) -> bool {
-        self.global_vars.contains(&name.to_string())
+    pub fn is_global(&self, name: impl Into<Symbol>) -> bool {
+        self.global_vars.contains(&name.into())
     }
 
     /// Check if a variable is declared as nonlocal in this scope
-    pub fn is_nonlocal(&self, name: &str) -> bool {
-        self.nonlocal_vars.contains(&name.to_string())
+    pub fn is_nonlocal(&self, name: impl Into<Symbol>) -> bool {
+        self.nonlocal_vars.contains(&name.into())
     }
 
     /// Add a captured variable to this scope
-    pub fn add_captured_variable(&mut self, name: String, ptr: PointerValue<'ctx>) {
-        self.captured_vars.insert(name, ptr);
+    pub fn add_captured_variable(&mut self, name: impl Into<Symbol>, ptr: PointerValue<'ctx>) {
+        self.captured_vars.insert(name.into(), ptr);
     }
 
     /// Get a captured variable's storage location
-    pub fn get_captured_variable(&self, name: &str) -> Option<&PointerValue<'ctx>> {
-        self.captured_vars.get(name)
+    pub fn get_captured_variable(&self, name: impl Into<Symbol>) -> Option<&PointerValue<'ctx>> {
+        self.captured_vars.get(&name.into())
     }
 
     /// Check if a variable is captured in this scope
-    pub fn is_captured(&self, name: &str) -> bool {
-        self.captured_vars.contains_key(name)
+    pub fn is_captured(&self, name: impl Into<Symbol>) -> bool {
+        self.captured_vars.contains_key(&name.into())
     }
 
     /// Mark a variable as needing heap allocation
-    pub fn mark_as_heap_var(&mut self, name: String) {
+    pub fn mark_as_heap_var(&mut self, name: impl Into<Symbol>) {
+        let name = name.into();
         if !self.heap_vars.contains(&name) {
             self.heap_vars.push(name);
         }
     }
 
     /// Check if a variable needs heap allocation
-    pub fn is_heap_var(&self, name: &str) -> bool {
-        self.heap_vars.contains(&name.to_string())
+    pub fn is_heap_var(&self, name: impl Into<Symbol>) -> bool {
+        self.heap_vars.contains(&name.into())
     }
 
     /// Declare a variable as global in this scope
-    pub fn declare_global(&mut self, name: String) {
+    pub fn declare_global(&mut self, name: impl Into<Symbol>) {
+        let name = name.into();
         if !self.global_vars.contains(&name) {
             self.global_vars.push(name);
         }
     }
 
     /// Declare a variable as nonlocal in this scope
-    pub fn declare_nonlocal(&mut self, name: String) {
+    pub fn declare_nonlocal(&mut self, name: impl Into<Symbol>) {
+        let name = name.into();
         if !self.nonlocal_vars.contains(&name) {
             self.nonlocal_vars.push(name);
         }
@@ -167,7 +172,8 @@ impl<'ctx> ScopeStack<'ctx> {
     }
 
     /// Get a variable's storage location
-    pub fn get_variable(&self, name: &str) -> Option<&PointerValue<'ctx>> {
+    pub fn get_variable(&self, name: impl Into<Symbol>) -> Option<&PointerValue<'ctx>> {
+        let name = name.into();
         for scope in self.scopes.iter().rev() {
             if let Some(ptr) = scope.get_variable(name) {
                 return Some(ptr);
@@ -177,7 +183,8 @@ impl<'ctx> ScopeStack<'ctx> {
     }
 
     /// Get a variable's type
-    pub fn get_type(&self, name: &str) -> Option<&Type> {
+    pub fn get_type(&self, name: impl Into<Symbol>) -> Option<&Type> {
+        let name = name.into();
         for scope in self.scopes.iter().rev() {
             if let Some(ty) = scope.get_type(name) {
                 return Some(ty);
@@ -187,7 +194,8 @@ impl<'ctx> ScopeStack<'ctx> {
     }
 
     /// Add a variable to the current scope
-    pub fn add_variable(&mut self, name: String, ptr: PointerValue<'ctx>, ty: Type) {
+    pub fn add_variable(&mut self, name: impl Into<Symbol>, ptr: PointerValue<'ctx>, ty: Type) {
+        let name = name.into();
         if let Some(scope) = self.current_scope_mut() {
             scope.add_variable(name, ptr, ty);
         }
@@ -234,14 +242,16 @@ impl<'ctx> ScopeStack<'ctx> {
     }
 
     /// Declare a variable as global in the current scope
-    pub fn declare_global(&mut self, name: String) {
+    pub fn declare_global(&mut self, name: impl Into<Symbol>) {
+        let name = name.into();
         if let Some(scope) = self.current_scope_mut() {
             scope.declare_global(name);
         }
     }
 
     /// Declare a variable as nonlocal in the current scope
-    pub fn declare_nonlocal(&mut self, name: String) {
+    pub fn declare_nonlocal(&mut self, name: impl Into<Symbol>) {
+        let name = name.into();
         if let Some(scope) = self.current_scope_mut() {
             scope.declare_nonlocal(name);
         }
@@ -249,7 +259,8 @@ impl<'ctx> ScopeStack<'ctx> {
 
     /// Capture a variable from an outer scope for use in the current scope
     /// Returns true if the variable was found and captured, false otherwise
-    pub fn capture_variable(&mut self, name: &str) -> bool {
+    pub fn capture_variable(&mut self, name: impl Into<Symbol>) -> bool {
+        let name = name.into();
         let current_index = self.scopes.len() - 1;
 
         let mut found_ptr = None;
@@ -267,11 +278,11 @@ impl<'ctx> ScopeStack<'ctx> {
 
         if let (Some(ptr), Some(var_type)) = (found_ptr, found_type) {
             if let Some(current_scope) = self.current_scope_mut() {
-                current_scope.add_captured_variable(name.to_string(), ptr);
+                current_scope.add_captured_variable(name, ptr);
 
-                current_scope.add_type(name.to_string(), var_type);
+                current_scope.add_type(name, var_type);
 
-                self.scopes[found_scope_index].mark_as_heap_var(name.to_string());
+                self.scopes[found_scope_index].mark_as_heap_var(name);
 
                 return true;
             }
@@ -281,14 +292,16 @@ impl<'ctx> ScopeStack<'ctx> {
     }
 
     /// Mark a variable as needing heap allocation in the current scope
-    pub fn mark_as_heap_var(&mut self, name: String) {
+    pub fn mark_as_heap_var(&mut self, name: impl Into<Symbol>) {
+        let name = name.into();
         if let Some(scope) = self.current_scope_mut() {
             scope.mark_as_heap_var(name);
         }
     }
 
     /// Check if a variable needs heap allocation in the current scope
-    pub fn is_heap_var(&self, name: &str) -> bool {
+    pub fn is_heap_var(&self, name: impl Into<Symbol>) -> bool {
+        let name = name.into();
         if let Some(scope) = self.current_scope() {
             scope.is_heap_var(name)
         } else {
@@ -297,7 +310,8 @@ impl<'ctx> ScopeStack<'ctx> {
     }
 
     /// Get a variable's storage location, respecting global and nonlocal declarations
-    pub fn get_variable_respecting_declarations(&self, name: &str) -> Option<&PointerValue<'ctx>> {
+    pub fn get_variable_respecting_declarations(&self, name: impl Into<Symbol>) -> Option<&PointerValue<'ctx>> {
+        let name = name.into();
         if let Some(current_scope) = self.current_scope() {
             if current_scope.is_global(name) {
                 if let Some(global_scope) = self.global_scope() {
@@ -361,14 +375,16 @@ impl<'ctx> ScopeStack<'ctx> {
     }
 
     /// Add a mapping from an original variable name to a unique name in the current scope
-    pub fn add_nonlocal_mapping(&mut self, original_name: String, unique_name: String) {
+    pub fn add_nonlocal_mapping(&mut self, original_name: impl Into<Symbol>, unique_name: String) {
+        let original_name = original_name.into();
         if let Some(scope) = self.scopes.last_mut() {
             scope.add_nonlocal_mapping(original_name, unique_name);
         }
     }
 
     /// Get the unique name for a nonlocal variable in the current scope
-    pub fn get_nonlocal_mapping(&self, original_name: &str) -> Option<&String> {
+    pub fn get_nonlocal_mapping(&self, original_name: impl Into<Symbol>) -> Option<&String> {
+        let original_name = original_name.into();
         if let Some(scope) = self.scopes.last() {
             scope.get_nonlocal_mapping(original_name)
         } else {
@@ -377,7 +393,8 @@ impl<'ctx> ScopeStack<'ctx> {
     }
 
     /// Get a variable's type from the scope stack, respecting nonlocal declarations
-    pub fn get_type_respecting_declarations(&self, name: &str) -> Option<Type> {
+    pub fn get_type_respecting_declarations(&self, name: impl Into<Symbol>) -> Option<Type> {
+        let name = name.into();
         if let Some(current_scope) = self.current_scope() {
             if current_scope.is_global(name) {
                 if let Some(global_scope) = self.global_scope() {
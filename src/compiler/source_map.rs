@@ -0,0 +1,201 @@
+// source_map.rs - Source maps from generated LLVM IR back to Cheetah source
+//
+// Statements keep their source line through AST transforms, comprehension
+// fusion and LLVM's optimization passes by way of the `!dbg` locations on the
+// instructions built for them. Reading those locations out of the final IR
+// gives a Source Map v3 file that coverage tools and debuggers can use to map
+// each line of the generated IR to the line it came from.
+
+use serde_json::json;
+use std::collections::HashMap;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// A generated line attributed to a source position
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mapping {
+    /// Line in the generated file, 1-based
+    pub generated_line: usize,
+    /// Index into `SourceMap::sources`
+    pub source: usize,
+    /// Line in the source file, 1-based
+    pub line: usize,
+    /// Column in the source file, 0-based
+    pub column: usize,
+}
+
+/// Mappings from the lines of one generated file to source positions
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    /// Name of the generated file
+    pub file: String,
+    /// Source files the mappings refer to
+    pub sources: Vec<String>,
+    /// Mappings ordered by generated line
+    pub mappings: Vec<Mapping>,
+}
+
+impl SourceMap {
+    /// Map the lines of textual LLVM IR written to `file` using their `!dbg`
+    /// locations. IR compiled without debug info has no mappings.
+    pub fn from_llvm_ir(ir: &str, file: &str) -> Self {
+        let metadata: HashMap<&str, &str> = ir
+            .lines()
+            .filter_map(|line| {
+                let (id, body) = line.split_once(" = ")?;
+                id.starts_with('!').then_some((id.trim(), body))
+            })
+            .collect();
+
+        let mut map = SourceMap {
+            file: file.to_string(),
+            ..SourceMap::default()
+        };
+        for (index, line) in ir.lines().enumerate() {
+            if line.starts_with('!') {
+                continue;
+            }
+            let Some(position) = line.rfind("!dbg ") else {
+                continue;
+            };
+            let id = line[position + 5..]
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .next()
+                .unwrap_or("");
+            let Some((source, line_number, column)) = location(&metadata, id) else {
+                continue;
+            };
+
+            let source = match map.sources.iter().position(|s| *s == source) {
+                Some(existing) => existing,
+                None => {
+                    map.sources.push(source);
+                    map.sources.len() - 1
+                }
+            };
+            map.mappings.push(Mapping {
+                generated_line: index + 1,
+                source,
+                line: line_number,
+                column,
+            });
+        }
+        map
+    }
+
+    /// Source file and line that generated line `generated_line` came from
+    pub fn lookup(&self, generated_line: usize) -> Option<(&str, usize)> {
+        self.mappings
+            .iter()
+            .find(|m| m.generated_line == generated_line)
+            .map(|m| (self.sources[m.source].as_str(), m.line))
+    }
+
+    /// The map as Source Map v3 JSON
+    pub fn to_json(&self) -> String {
+        json!({
+            "version": 3,
+            "file": self.file,
+            "sources": self.sources,
+            "names": [],
+            "mappings": self.encode_mappings(),
+        })
+        .to_string()
+    }
+
+    /// The `mappings` field: one segment at the start of each mapped line,
+    /// with fields relative to the previous segment
+    fn encode_mappings(&self) -> String {
+        let mut encoded = String::new();
+        let (mut source, mut line, mut column) = (0i64, 0i64, 0i64);
+        let mut current_line = 1;
+        for mapping in &self.mappings {
+            while current_line < mapping.generated_line {
+                encoded.push(';');
+                current_line += 1;
+            }
+
+            let next = (
+                mapping.source as i64,
+                mapping.line as i64 - 1,
+                mapping.column as i64,
+            );
+            encode_vlq(0, &mut encoded);
+            encode_vlq(next.0 - source, &mut encoded);
+            encode_vlq(next.1 - line, &mut encoded);
+            encode_vlq(next.2 - column, &mut encoded);
+            (source, line, column) = next;
+        }
+        encoded
+    }
+}
+
+/// Source file, line and column of the `!DILocation` or `!DISubprogram` `id`
+fn location(metadata: &HashMap<&str, &str>, id: &str) -> Option<(String, usize, usize)> {
+    let body = metadata.get(id)?;
+    if !body.contains("!DILocation(") && !body.contains("!DISubprogram(") {
+        return None;
+    }
+    let line = field(body, "line")?.parse().ok().filter(|&line| line > 0)?;
+    let column = field(body, "column")
+        .and_then(|column| column.parse().ok())
+        .unwrap_or(0);
+
+    // Follow the scope chain up to the node naming the file
+    let mut node = *body;
+    for _ in 0..64 {
+        if let Some(file) = field(node, "file") {
+            let file = metadata.get(file)?;
+            let name = quoted_field(file, "filename")?;
+            let path = match quoted_field(file, "directory") {
+                Some(dir) if !dir.is_empty() && !name.starts_with('/') => {
+                    format!("{}/{}", dir.trim_end_matches('/'), name)
+                }
+                _ => name.to_string(),
+            };
+            return Some((path, line, column));
+        }
+        node = metadata.get(field(node, "scope")?)?;
+    }
+    None
+}
+
+/// Unquoted value of `key: value` in a metadata node
+fn field<'a>(body: &'a str, key: &str) -> Option<&'a str> {
+    let pattern = format!("{}: ", key);
+    body.match_indices(&pattern)
+        .find(|(at, _)| matches!(body[..*at].chars().last(), Some('(' | ' ')))
+        .map(|(at, _)| {
+            let rest = &body[at + pattern.len()..];
+            let end = rest.find([',', ')']).unwrap_or(rest.len());
+            rest[..end].trim()
+        })
+}
+
+/// Value of `key: "value"` in a metadata node
+fn quoted_field<'a>(body: &'a str, key: &str) -> Option<&'a str> {
+    let pattern = format!("{}: \"", key);
+    let start = body.find(&pattern)? + pattern.len();
+    let end = body[start..].find('"')?;
+    Some(&body[start..start + end])
+}
+
+/// Append `value` as a base64 VLQ
+fn encode_vlq(value: i64, out: &mut String) {
+    let mut vlq = if value < 0 {
+        ((-value) << 1) | 1
+    } else {
+        value << 1
+    };
+    loop {
+        let mut digit = (vlq & 31) as usize;
+        vlq >>= 5;
+        if vlq > 0 {
+            digit |= 32;
+        }
+        out.push(BASE64[digit] as char);
+        if vlq == 0 {
+            break;
+        }
+    }
+}
@@ -4,8 +4,10 @@
 use crate::ast::{Expr, Stmt};
 use crate::compiler::context::CompilationContext;
 use crate::compiler::expr::{AssignmentCompiler, BinaryOpCompiler, ExprCompiler};
+use crate::compiler::loop_transformers::ConstantRange;
 use crate::compiler::stmt::StmtCompiler;
 use crate::compiler::types::Type;
+use crate::intern::Symbol;
 use inkwell::values::BasicValueEnum;
 use std::collections::VecDeque;
 
@@ -30,6 +32,18 @@ pub trait StmtNonRecursive<'ctx> {
         stop_val: inkwell::values::IntValue<'ctx>,
         step_val: inkwell::values::IntValue<'ctx>
     ) -> Result<(), String>;
+
+    /// Fold a range call whose arguments are literals or module-level integer constants
+    fn fold_constant_range(&self, expr: &Expr) -> Option<ConstantRange>;
+
+    /// Generate a loop with a constant trip count for a folded range
+    fn generate_counted_range_loop(
+        &mut self,
+        target: &Expr,
+        body: &[Box<Stmt>],
+        orelse: &[Box<Stmt>],
+        range: ConstantRange,
+    ) -> Result<(), String>;
 }
 
 // Task for the work stack
@@ -62,6 +76,7 @@ enum StmtTask<'a, 'ctx> {
     },
 
     ProcessWith {
+        items: &'a [(Box<Expr>, Option<Box<Expr>>)],
         body: &'a [Box<Stmt>],
     },
 
@@ -386,6 +401,167 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
 
         Ok(())
     }
+
+    /// Fold a range call whose arguments are literals or module-level integer constants
+    fn fold_constant_range(&self, expr: &Expr) -> Option<ConstantRange> {
+        let Expr::Call { func, args, keywords, .. } = expr else {
+            return None;
+        };
+        if !matches!(func.as_ref(), Expr::Name { id, .. } if id == "range") || !keywords.is_empty() {
+            return None;
+        }
+
+        let lookup = |name: &str| {
+            let value = *self.range_constants.get(name)?;
+            let global = self
+                .scope_stack
+                .global_scope()
+                .and_then(|scope| scope.get_variable(name));
+            match self.scope_stack.get_variable(name) {
+                // Shadowed by a variable of an enclosing scope
+                Some(ptr) if Some(ptr) != global => None,
+                // Read at the top level before its assignment has run
+                None if self.scope_stack.find_function_scope().is_none() => None,
+                _ => Some(value),
+            }
+        };
+        ConstantRange::fold(args, &lookup)
+    }
+
+    /// Generate a loop with a constant trip count for a folded range
+    fn generate_counted_range_loop(
+        &mut self,
+        target: &Expr,
+        body: &[Box<Stmt>],
+        orelse: &[Box<Stmt>],
+        range: ConstantRange,
+    ) -> Result<(), String> {
+        let Expr::Name { id, .. } = target else {
+            return Err("Unsupported loop target".to_string());
+        };
+        let current_function = self
+            .builder
+            .get_insert_block()
+            .unwrap()
+            .get_parent()
+            .unwrap();
+        let i64_type = self.llvm_context.i64_type();
+
+        // Allocate the loop variable and counter in the entry block so they are promoted to registers
+        let current_block = self.builder.get_insert_block().unwrap();
+        let entry_block = current_function.get_first_basic_block().unwrap();
+        match entry_block.get_first_instruction() {
+            Some(first) => self.builder.position_before(&first),
+            None => self.builder.position_at_end(entry_block),
+        }
+        let var_ptr = self.builder.build_alloca(i64_type, id).unwrap();
+        let counter_ptr = self.builder.build_alloca(i64_type, "range.counter").unwrap();
+        self.builder.position_at_end(current_block);
+        self.scope_stack.add_variable(id.clone(), var_ptr, Type::Int);
+
+        let cond_block = self.llvm_context.append_basic_block(current_function, "counted.cond");
+        let body_block = self.llvm_context.append_basic_block(current_function, "counted.body");
+        let inc_block = self.llvm_context.append_basic_block(current_function, "counted.inc");
+        let else_block = self.llvm_context.append_basic_block(current_function, "counted.else");
+        let exit_block = self.llvm_context.append_basic_block(current_function, "counted.exit");
+
+        self.push_loop(inc_block, exit_block);
+
+        // Count from 0 to the trip count, so LLVM sees a canonical induction variable
+        self.builder.build_store(counter_ptr, i64_type.const_zero()).unwrap();
+        self.builder.build_unconditional_branch(cond_block).unwrap();
+
+        self.builder.position_at_end(cond_block);
+        let counter = self.builder
+            .build_load(i64_type, counter_ptr, "counter")
+            .unwrap()
+            .into_int_value();
+        let trip_count = i64_type.const_int(range.trip_count(), false);
+        let condition = self.builder
+            .build_int_compare(inkwell::IntPredicate::ULT, counter, trip_count, "in_range")
+            .unwrap();
+        self.builder
+            .build_conditional_branch(condition, body_block, else_block)
+            .unwrap();
+
+        // Body block: set the loop variable to start + counter * step
+        self.builder.position_at_end(body_block);
+        let offset = self.builder
+            .build_int_mul(counter, i64_type.const_int(range.step as u64, true), "offset")
+            .unwrap();
+        let value = self.builder
+            .build_int_add(i64_type.const_int(range.start as u64, true), offset, "value")
+            .unwrap();
+        self.builder.build_store(var_ptr, value).unwrap();
+
+        self.push_scope(false, true, false);
+        for stmt in body {
+            if self
+                .builder
+                .get_insert_block()
+                .unwrap()
+                .get_terminator()
+                .is_some()
+            {
+                break;
+            }
+            self.compile_stmt_non_recursive(stmt)?;
+        }
+        if self
+            .builder
+            .get_insert_block()
+            .unwrap()
+            .get_terminator()
+            .is_none()
+        {
+            self.builder.build_unconditional_branch(inc_block).unwrap();
+        }
+        self.pop_scope();
+
+        // Increment block: advance the counter
+        self.builder.position_at_end(inc_block);
+        let counter = self.builder
+            .build_load(i64_type, counter_ptr, "counter_inc")
+            .unwrap()
+            .into_int_value();
+        let next = self.builder
+            .build_int_nuw_add(counter, i64_type.const_int(1, false), "next")
+            .unwrap();
+        self.builder.build_store(counter_ptr, next).unwrap();
+        self.builder.build_unconditional_branch(cond_block).unwrap();
+
+        // Else block: runs when the loop finishes without break
+        self.builder.position_at_end(else_block);
+        self.push_scope(false, false, false);
+        for stmt in orelse {
+            if self
+                .builder
+                .get_insert_block()
+                .unwrap()
+                .get_terminator()
+                .is_some()
+            {
+                break;
+            }
+            self.compile_stmt_non_recursive(stmt)?;
+        }
+        if self
+            .builder
+            .get_insert_block()
+            .unwrap()
+            .get_terminator()
+            .is_none()
+        {
+            self.builder.build_unconditional_branch(exit_block).unwrap();
+        }
+        self.pop_scope();
+
+        self.builder.position_at_end(exit_block);
+        self.pop_loop();
+
+        Ok(())
+    }
+
     fn convert_to_bool(&self, value: BasicValueEnum<'ctx>) -> inkwell::values::IntValue<'ctx> {
         match value {
             BasicValueEnum::IntValue(int_val) => {
@@ -415,6 +591,8 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
     fn compile_stmt_non_recursive(&mut self, stmt: &Stmt) -> Result<(), String> {
         let mut work_stack: VecDeque<StmtTask> = VecDeque::new();
 
+        self.set_debug_line(stmt.line());
+        self.emit_trace_line(stmt.line())?;
         work_stack.push_back(StmtTask::Execute(stmt));
 
         while let Some(task) = work_stack.pop_front() {
@@ -435,20 +613,32 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
                     }
 
                     Stmt::AugAssign {
-                        target, op, value, ..
+                        target,
+                        op,
+                        value,
+                        line,
+                        column,
+                        span: _,
                     } => {
-                        let (target_val, target_type) = self.compile_expr(target)?;
-                        let (value_val, value_type) = self.compile_expr(value)?;
-
-                        let (result_val, result_type) = self.compile_binary_op(
-                            target_val,
-                            &target_type,
-                            op.clone(),
-                            value_val,
-                            &value_type,
-                        )?;
+                        if let Some(builder) = self.string_builder_for(target, op) {
+                            self.compile_string_builder_append(builder, value)
+                                .map_err(|e| format!("{} at line {}, column {}", e, line, column))?;
+                        } else {
+                            let (target_val, target_type) = self.compile_expr(target)?;
+                            let (value_val, value_type) = self.compile_expr(value)?;
+
+                            let (result_val, result_type) = self
+                                .compile_binary_op(
+                                    target_val,
+                                    &target_type,
+                                    op.clone(),
+                                    value_val,
+                                    &value_type,
+                                )
+                                .map_err(|e| format!("{} at line {}, column {}", e, line, column))?;
 
-                        self.compile_assignment(target, result_val, &result_type)?;
+                            self.compile_assignment(target, result_val, &result_type)?;
+                        }
                     }
 
                     Stmt::AnnAssign { target, value, .. } => {
@@ -547,8 +737,6 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
                         orelse,
                         ..
                     } => {
-                        let (_iter_val, _iter_type) = self.compile_expr(iter)?;
-
                         work_stack.push_front(StmtTask::ProcessFor {
                             target,
                             body,
@@ -581,8 +769,12 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
 
                     Stmt::Pass { .. } => {}
 
-                    Stmt::With { body, .. } => {
-                        work_stack.push_front(StmtTask::ProcessWith { body });
+                    Stmt::Raise { exc, cause, .. } => {
+                        self.compile_raise_stmt(exc, cause)?;
+                    }
+
+                    Stmt::With { items, body, .. } => {
+                        work_stack.push_front(StmtTask::ProcessWith { items, body });
                     }
 
                     Stmt::Try {
@@ -602,6 +794,8 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
 
                     Stmt::Break { .. } => {
                         if let Some(break_block) = self.current_break_block() {
+                            self.emit_exception_handler_exits(Some(self.loop_stack.len()));
+                            self.emit_with_cleanups(Some(self.loop_stack.len()))?;
                             self.builder
                                 .build_unconditional_branch(break_block)
                                 .unwrap();
@@ -612,6 +806,8 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
 
                     Stmt::Continue { .. } => {
                         if let Some(continue_block) = self.current_continue_block() {
+                            self.emit_exception_handler_exits(Some(self.loop_stack.len()));
+                            self.emit_with_cleanups(Some(self.loop_stack.len()))?;
                             self.builder
                                 .build_unconditional_branch(continue_block)
                                 .unwrap();
@@ -621,8 +817,15 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
                     }
 
                     Stmt::FunctionDef {
-                        name, params, body, ..
+                        name, params, body, decorator_list, ..
                     } => {
+                        if crate::compiler::ffi::extern_symbol(name, decorator_list)?.is_some() {
+                            return Err(format!(
+                                "@extern function '{}' must be declared at module level",
+                                name
+                            ));
+                        }
+
                         let parent_function_name = if let Some(current_function) =
                             self.current_function
                         {
@@ -653,7 +856,7 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
                             if self.scope_stack.scopes.len() >= 2 {
                                 let parent_scope_index = self.scope_stack.scopes.len() - 2;
                                 if let Some(_) =
-                                    self.scope_stack.scopes[parent_scope_index].get_variable(&name)
+                                    self.scope_stack.scopes[parent_scope_index].get_variable(name)
                                 {
                                     found_in_outer_scope = true;
                                     println!("Found variable '{}' in immediate outer scope {} for nonlocal declaration", name, parent_scope_index);
@@ -662,7 +865,7 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
 
                             if !found_in_outer_scope && self.scope_stack.scopes.len() >= 3 {
                                 for i in (0..self.scope_stack.scopes.len() - 2).rev() {
-                                    if let Some(_) = self.scope_stack.scopes[i].get_variable(&name)
+                                    if let Some(_) = self.scope_stack.scopes[i].get_variable(name)
                                     {
                                         found_in_outer_scope = true;
                                         println!("Found variable '{}' in outer scope {} for nonlocal declaration", name, i);
@@ -693,12 +896,12 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
                                         let parent_scope_index = current_index - 1;
                                         if let Some(ptr) = self.scope_stack.scopes
                                             [parent_scope_index]
-                                            .get_variable(&name)
+                                            .get_variable(name)
                                         {
                                             found_ptr = Some(*ptr);
                                             if let Some(ty) = self.scope_stack.scopes
                                                 [parent_scope_index]
-                                                .get_type(&name)
+                                                .get_type(name)
                                             {
                                                 found_type = Some(ty.clone());
                                             }
@@ -708,11 +911,11 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
                                     if found_ptr.is_none() && current_index > 1 {
                                         for i in (0..current_index - 1).rev() {
                                             if let Some(ptr) =
-                                                self.scope_stack.scopes[i].get_variable(&name)
+                                                self.scope_stack.scopes[i].get_variable(name)
                                             {
                                                 found_ptr = Some(*ptr);
                                                 if let Some(ty) =
-                                                    self.scope_stack.scopes[i].get_type(&name)
+                                                    self.scope_stack.scopes[i].get_type(name)
                                                 {
                                                     found_type = Some(ty.clone());
                                                 }
@@ -786,7 +989,7 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
                             if self.current_function.is_some() {
                                 let var_exists_in_global =
                                     if let Some(global_scope) = self.scope_stack.global_scope() {
-                                        global_scope.get_variable(&name).is_some()
+                                        global_scope.get_variable(name).is_some()
                                     } else {
                                         false
                                     };
@@ -816,9 +1019,9 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
                                         );
                                     }
 
-                                    self.variables.insert(name.clone(), ptr);
+                                    self.variables.insert(Symbol::intern(name), ptr);
 
-                                    self.type_env.insert(name.clone(), var_type.clone());
+                                    self.type_env.insert(Symbol::intern(name), var_type.clone());
                                 }
                             }
                         }
@@ -838,6 +1041,8 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
                             index: index + 1,
                         });
 
+                        self.set_debug_line(stmt.line());
+                        self.emit_trace_line(stmt.line())?;
                         work_stack.push_front(StmtTask::Execute(stmt.as_ref()));
 
                         if self
@@ -858,8 +1063,17 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
                     orelse,
                     iter,
                 } => {
+                    let string_builders = self.begin_string_builders(&[body, orelse], &[target])?;
+
                     // Check if this is a range-based for loop that we can optimize
-                    if let Ok(Some((start_val, stop_val, step_val))) = self.detect_range_call(iter) {
+                    let constant_range = match target {
+                        Expr::Name { .. } => self.fold_constant_range(iter),
+                        _ => None,
+                    };
+                    if let Some(range) = constant_range {
+                        // Constant bounds: a counted loop with no range arguments to evaluate
+                        self.generate_counted_range_loop(target, body, orelse, range)?;
+                    } else if let Ok(Some((start_val, stop_val, step_val))) = self.detect_range_call(iter) {
                         // This is a range-based for loop, use our optimized implementation
                         self.generate_optimized_range_loop(target, body, orelse, start_val, stop_val, step_val)?;
                     } else {
@@ -902,46 +1116,23 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
                             .build_store(index_ptr, i64_type.const_int(0, false))
                             .unwrap();
 
-                        let var_ptr = if let Expr::Name { id, .. } = target {
-                            let ptr = self.builder.build_alloca(i64_type, id).unwrap();
-                            self.scope_stack.add_variable(id.clone(), ptr, Type::Int);
-                            ptr
-                        } else {
-                            return Err("Unsupported loop target".to_string());
-                        };
-
                         let (iter_val, iter_type) = self.compile_expr(iter)?;
 
-                        let len_val = match iter_type {
-                            Type::List(_) => {
-                                let list_len_fn = self
-                                    .module
-                                    .get_function("list_len")
-                                    .ok_or("list_len function not found".to_string())?;
-                                let call = self
-                                    .builder
-                                    .build_call(
-                                        list_len_fn,
-                                        &[iter_val.into_pointer_value().into()],
-                                        "list_len_result",
-                                    )
-                                    .unwrap();
-                                call.try_as_basic_value().left().unwrap()
-                            }
-                            Type::Int => {
-                                if iter_val.is_pointer_value() {
-                                    self.builder
-                                        .build_load(
-                                            i64_type,
-                                            iter_val.into_pointer_value(),
-                                            "range_len",
-                                        )
-                                        .unwrap()
-                                } else {
-                                    iter_val
-                                }
-                            }
-                            _ => iter_val,
+                        // Iterables bind the items of the list they are walked as; ints bind the running index
+                        let iteration = self.iteration_list(iter_val, &iter_type)?;
+                        let item_type = iteration.as_ref().map(|(_, item_type)| item_type.clone());
+                        let target_slots = self.declare_iteration_target(
+                            target,
+                            item_type.as_ref().unwrap_or(&Type::Int),
+                        )?;
+
+                        let len_val: BasicValueEnum = match &iteration {
+                            Some((list_ptr, _)) => self.build_list_len(*list_ptr)?.into(),
+                            None if iter_val.is_pointer_value() => self
+                                .builder
+                                .build_load(i64_type, iter_val.into_pointer_value(), "range_len")
+                                .unwrap(),
+                            None => iter_val,
                         };
 
                         self.builder.build_unconditional_branch(cond_block).unwrap();
@@ -968,7 +1159,11 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
                         self.builder.position_at_end(body_block);
                         self.push_scope(false, true, false);
 
-                        self.builder.build_store(var_ptr, index_val).unwrap();
+                        let item = match &iteration {
+                            Some((list_ptr, ty)) => self.load_list_item(*list_ptr, index_val, ty)?,
+                            None => index_val.into(),
+                        };
+                        self.store_iteration_target(&target_slots, item)?;
 
                         for stmt in body {
                             if self
@@ -1039,16 +1234,19 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
                         self.builder.position_at_end(end_block);
                         self.pop_loop();
                     }
+
+                    self.finish_string_builders(string_builders)?;
                 }
 
                 StmtTask::ProcessWhile { test, body, orelse } => {
                     let context = self.llvm_context;
+                    let string_builders = self.begin_string_builders(&[body, orelse], &[test])?;
 
                     let function = match self.builder.get_insert_block() {
                         Some(block) => block.get_parent().unwrap(),
                         None => match self.current_function {
                             Some(f) => f,
-                            None => match self.module.get_function("main") {
+                            None => match self.runtime_function("main") {
                                 Some(f) => f,
                                 None => return Err("No main function found".to_string()),
                             },
@@ -1142,6 +1340,7 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
                     }
 
                     self.builder.position_at_end(end_block);
+                    self.finish_string_builders(string_builders)?;
                 }
 
                 StmtTask::ProcessTry {
@@ -1180,14 +1379,10 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
                     let finally_block = self.llvm_context.append_basic_block(function, "finally");
                     let exit_block = self.llvm_context.append_basic_block(function, "exit");
 
-                    let exception_raised = self.create_exception_state();
-
                     self.builder.build_unconditional_branch(try_block).unwrap();
 
                     self.builder.position_at_end(try_block);
 
-                    self.reset_exception_state(exception_raised);
-
                     for stmt in body {
                         if self
                             .builder
@@ -1211,7 +1406,7 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
                         .get_terminator()
                         .is_some()
                     {
-                        let exception_value = self.load_exception_state(exception_raised);
+                        let exception_value = self.load_exception_state();
                         self.builder
                             .build_conditional_branch(exception_value, except_blocks[0], else_block)
                             .unwrap();
@@ -1238,9 +1433,9 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
 
                         self.builder.position_at_end(handler_body_block);
 
-                        if let Some(name) = &handler.name {
-                            let exception = self.get_current_exception();
+                        let exception = self.enter_exception_handler();
 
+                        if let Some(name) = &handler.name {
                             let exception_ptr = self
                                 .builder
                                 .build_alloca(
@@ -1251,7 +1446,7 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
 
                             self.builder.build_store(exception_ptr, exception).unwrap();
 
-                            self.add_variable_to_scope(name.clone(), exception_ptr, Type::Any);
+                            self.add_variable_to_scope(name.clone(), exception_ptr, Type::exception());
                         }
 
                         for stmt in &handler.body {
@@ -1270,33 +1465,29 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
                             }
                         }
 
-                        self.reset_exception_state(exception_raised);
-
-                        if let Some(clear_current_exception_fn) =
-                            self.module.get_function("clear_current_exception")
-                        {
-                            self.builder
-                                .build_call(
-                                    clear_current_exception_fn,
-                                    &[],
-                                    "clear_exception_result",
-                                )
-                                .unwrap();
-                        }
-
-                        if !self
+                        let handler_ended = self
                             .builder
                             .get_insert_block()
                             .unwrap()
                             .get_terminator()
-                            .is_some()
-                        {
+                            .is_some();
+                        self.exit_exception_handler(!handler_ended);
+
+                        if !handler_ended {
                             self.builder
                                 .build_unconditional_branch(finally_block)
                                 .unwrap();
                         }
                     }
 
+                    // try/finally without handlers lets a pending exception through
+                    if handlers.is_empty() {
+                        self.builder.position_at_end(except_blocks[0]);
+                        self.builder
+                            .build_unconditional_branch(finally_block)
+                            .unwrap();
+                    }
+
                     self.builder.position_at_end(else_block);
 
                     for stmt in orelse {
@@ -1362,12 +1553,47 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
                     self.builder.position_at_end(exit_block);
                 }
 
-                StmtTask::ProcessWith { body } => {
-                    if !body.is_empty() {
-                        work_stack.push_front(StmtTask::ExecuteBlock {
-                            stmts: body,
-                            index: 0,
-                        });
+                StmtTask::ProcessWith { items, body } => {
+                    // Known context managers (os.tempdir(), os.tempfile(), open()) bind
+                    // their value and release it on exit; others only run the body
+                    let mut temp_scopes = 0;
+                    for (context_expr, optional_vars) in items {
+                        let cleanup = match self.with_item_cleanup(context_expr) {
+                            Some(cleanup) => cleanup,
+                            None => continue,
+                        };
+
+                        let (value, value_type) = self.compile_expr(context_expr)?;
+                        if let Some(target) = optional_vars {
+                            self.compile_assignment(target, value, &value_type)?;
+                        }
+                        self.enter_with_scope(value, cleanup)?;
+                        temp_scopes += 1;
+                    }
+
+                    if temp_scopes == 0 {
+                        if !body.is_empty() {
+                            work_stack.push_front(StmtTask::ExecuteBlock {
+                                stmts: body,
+                                index: 0,
+                            });
+                        }
+                    } else {
+                        for stmt in body {
+                            if self
+                                .builder
+                                .get_insert_block()
+                                .unwrap()
+                                .get_terminator()
+                                .is_some()
+                            {
+                                break;
+                            }
+                            self.compile_stmt_non_recursive(stmt)?;
+                        }
+                        for _ in 0..temp_scopes {
+                            self.exit_with_scope()?;
+                        }
                     }
                 }
 
@@ -1385,6 +1611,10 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
                     value_val,
                     value_type,
                 } => {
+                    self.emit_exception_handler_exits(None);
+                    self.emit_with_cleanups(None)?;
+                    self.emit_trace_frame_exit()?;
+
                     if let Some(ret_val) = value_val {
                         if let Some(current_function) = self.current_function {
                             let return_type = current_function.get_type().get_return_type();
@@ -1464,7 +1694,7 @@ impl<'ctx> StmtNonRecursive<'ctx> for CompilationContext<'ctx> {
                     Some(block) => block.get_parent().unwrap(),
                     None => match self.current_function {
                         Some(f) => f,
-                        None => match self.module.get_function("main") {
+                        None => match self.runtime_function("main") {
                             Some(f) => f,
                             None => return Err("No main function found".to_string()),
                         },
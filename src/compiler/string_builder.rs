@@ -0,0 +1,253 @@
+// string_builder.rs - Linear-time `s += piece` accumulation in loops
+//
+// Every `s = s + piece` copies the whole of `s`, so building a string with
+// `+=` in a loop takes quadratic time. When a loop only ever appends to a
+// string variable, the pieces are collected in a runtime string builder
+// instead, and the variable is assigned the finished string once the loop
+// exits:
+//
+//     out = ""
+//     for word in words:      # builder = string_builder_new(out)
+//         out += word         # string_builder_append(builder, word)
+//     print(out)              # out = string_builder_finish(builder)
+//
+// This is only done when nothing can observe the variable while the loop
+// runs: it is not read or assigned anywhere else in the loop, the loop
+// cannot leave the function early, and it calls no user-defined function
+// that could read it. `"".join(pieces)` is linear in every case.
+
+use crate::ast::{Expr, Operator, Span, Stmt};
+use crate::compiler::context::CompilationContext;
+use crate::compiler::expr::{AssignmentCompiler, ExprCompiler};
+use crate::compiler::loop_fusion::children;
+use crate::compiler::types::{unsupported_operand_types, Type};
+use inkwell::values::PointerValue;
+use std::collections::HashSet;
+
+/// Names only ever appended to with `name += value` in a loop's body and
+/// else block.
+///
+/// `exprs` are the loop's other parts that could observe a name, such as a
+/// for loop's target or a while loop's condition. `is_user_function` tells
+/// whether a called name is a function of the program being compiled.
+pub fn accumulated_strings(
+    bodies: &[&[Box<Stmt>]],
+    exprs: &[&Expr],
+    is_user_function: &dyn Fn(&str) -> bool,
+) -> Vec<String> {
+    let mut scan = Scan {
+        appended: Vec::new(),
+        mentioned: HashSet::new(),
+        blocked: false,
+        is_user_function,
+    };
+    for body in bodies {
+        scan.body(body);
+    }
+    for expr in exprs {
+        scan.expr(expr);
+    }
+
+    if scan.blocked {
+        return Vec::new();
+    }
+    let mut seen = HashSet::new();
+    scan.appended
+        .into_iter()
+        .filter(|name| !scan.mentioned.contains(name) && seen.insert(name.clone()))
+        .collect()
+}
+
+struct Scan<'f> {
+    appended: Vec<String>,
+    mentioned: HashSet<String>,
+    blocked: bool,
+    is_user_function: &'f dyn Fn(&str) -> bool,
+}
+
+impl Scan<'_> {
+    fn body(&mut self, body: &[Box<Stmt>]) {
+        for stmt in body {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::AugAssign { target, op: Operator::Add, value, .. }
+                if matches!(target.as_ref(), Expr::Name { .. }) =>
+            {
+                if let Expr::Name { id, .. } = target.as_ref() {
+                    self.appended.push(id.clone());
+                }
+                self.expr(value);
+            }
+            Stmt::AugAssign { target, value, .. } => {
+                self.expr(target);
+                self.expr(value);
+            }
+            Stmt::Assign { targets, value, .. } => {
+                targets.iter().for_each(|e| self.expr(e));
+                self.expr(value);
+            }
+            Stmt::AnnAssign { target, value, .. } => {
+                self.expr(target);
+                value.iter().for_each(|e| self.expr(e));
+            }
+            Stmt::Delete { targets, .. } => targets.iter().for_each(|e| self.expr(e)),
+            Stmt::For { target, iter, body, orelse, .. } => {
+                self.expr(target);
+                self.expr(iter);
+                self.body(body);
+                self.body(orelse);
+            }
+            Stmt::While { test, body, orelse, .. } | Stmt::If { test, body, orelse, .. } => {
+                self.expr(test);
+                self.body(body);
+                self.body(orelse);
+            }
+            Stmt::Raise { exc, cause, .. } => {
+                exc.iter().chain(cause.iter()).for_each(|e| self.expr(e));
+            }
+            Stmt::Assert { test, msg, .. } => {
+                self.expr(test);
+                msg.iter().for_each(|e| self.expr(e));
+            }
+            Stmt::Expr { value, .. } => self.expr(value),
+            Stmt::Match { subject, cases, .. } => {
+                self.expr(subject);
+                for (pattern, guard, body) in cases {
+                    self.expr(pattern);
+                    guard.iter().for_each(|e| self.expr(e));
+                    self.body(body);
+                }
+            }
+            Stmt::Pass { .. } | Stmt::Break { .. } | Stmt::Continue { .. } => {}
+            // Early exits, cleanups and nested scopes could all observe the
+            // variable before the loop finishes
+            Stmt::Return { .. }
+            | Stmt::Try { .. }
+            | Stmt::With { .. }
+            | Stmt::FunctionDef { .. }
+            | Stmt::ClassDef { .. }
+            | Stmt::Global { .. }
+            | Stmt::Nonlocal { .. }
+            | Stmt::Import { .. }
+            | Stmt::ImportFrom { .. } => self.blocked = true,
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Name { id, .. } => {
+                self.mentioned.insert(id.clone());
+            }
+            Expr::Call { func, .. } => {
+                if let Expr::Name { id, .. } = func.as_ref() {
+                    if (self.is_user_function)(id) {
+                        self.blocked = true;
+                    }
+                }
+            }
+            Expr::Yield { .. } | Expr::YieldFrom { .. } | Expr::Await { .. } => self.blocked = true,
+            _ => {}
+        }
+        for child in children(expr) {
+            self.expr(child);
+        }
+    }
+}
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// Start collecting the strings a loop accumulates with `+=` in builders.
+    ///
+    /// Returns the names given a builder, to be passed to
+    /// `finish_string_builders` once the loop has been compiled.
+    pub fn begin_string_builders(
+        &mut self,
+        bodies: &[&[Box<Stmt>]],
+        exprs: &[&Expr],
+    ) -> Result<Vec<String>, String> {
+        let candidates = accumulated_strings(bodies, exprs, &|name| {
+            self.functions.contains_key(name) || self.closure_environments.contains_key(name)
+        });
+
+        let mut started = Vec::new();
+        for name in candidates {
+            // An enclosing loop may already be collecting this string
+            if self.string_builders.contains_key(&name)
+                || self.scope_stack.get_type(&name) != Some(&Type::String)
+            {
+                continue;
+            }
+
+            let (initial, _) = self.compile_expr(&Expr::Name {
+                id: name.clone(),
+                ctx: crate::ast::ExprContext::Load,
+                line: 0,
+                column: 0,
+                span: Span::default(),
+            })?;
+            let builder = self
+                .call_runtime_function("string_builder_new", &[initial.into()])?
+                .ok_or("string_builder_new returned void")?
+                .into_pointer_value();
+            self.string_builders.insert(name.clone(), builder);
+            started.push(name);
+        }
+        Ok(started)
+    }
+
+    /// Assign each string collected by `begin_string_builders` its final value
+    pub fn finish_string_builders(&mut self, names: Vec<String>) -> Result<(), String> {
+        for name in names {
+            let builder = self
+                .string_builders
+                .remove(&name)
+                .ok_or_else(|| format!("no string builder for '{}'", name))?;
+            let terminated = self
+                .builder
+                .get_insert_block()
+                .and_then(|block| block.get_terminator())
+                .is_some();
+            if terminated {
+                continue;
+            }
+
+            let value = self
+                .call_runtime_function("string_builder_finish", &[builder.into()])?
+                .ok_or("string_builder_finish returned void")?;
+            let target = Expr::Name {
+                id: name,
+                ctx: crate::ast::ExprContext::Store,
+                line: 0,
+                column: 0,
+                span: Span::default(),
+            };
+            self.compile_assignment(&target, value, &Type::String)?;
+        }
+        Ok(())
+    }
+
+    /// The builder collecting appends to `target`, if it is being accumulated
+    pub fn string_builder_for(&self, target: &Expr, op: &Operator) -> Option<PointerValue<'ctx>> {
+        match (target, op) {
+            (Expr::Name { id, .. }, Operator::Add) => self.string_builders.get(id).copied(),
+            _ => None,
+        }
+    }
+
+    /// Compile `target += value` as an append to the target's string builder
+    pub fn compile_string_builder_append(
+        &mut self,
+        builder: PointerValue<'ctx>,
+        value: &Expr,
+    ) -> Result<(), String> {
+        let (piece, piece_type) = self.compile_expr(value)?;
+        if piece_type != Type::String {
+            return Err(unsupported_operand_types(&Operator::Add, &Type::String, &piece_type));
+        }
+        self.call_runtime_function("string_builder_append", &[builder.into(), piece.into()])?;
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+// super_call.rs - super() method calls resolved against the method resolution order
+//
+// A class's methods compile to functions named `Class.method`, taking the
+// instance first. `super().method(args)` inside a method of `C` is resolved
+// when compiling: the first class after `C` in C's MRO with a compiled
+// `method` is called directly with the method's own `self`:
+//
+//     class Dog(Animal):
+//         def __init__(self, name):
+//             super().__init__(name)    # call @"Animal.__init__"(self, name)
+//
+// `super(B, self)` starts the search after `B` instead. The type checker
+// has already rejected calls that no class in the MRO defines.
+
+use crate::ast::{Expr, NameConstant};
+use crate::compiler::context::CompilationContext;
+use crate::compiler::expr::ExprCompiler;
+use crate::compiler::types::Type;
+use crate::typechecker::mro;
+use inkwell::types::BasicTypeEnum;
+use inkwell::values::{BasicMetadataValueEnum, BasicValueEnum};
+
+/// Name of the function compiled for `class`'s method `method`
+pub fn method_symbol(class: &str, method: &str) -> String {
+    format!("{}.{}", class, method)
+}
+
+/// The arguments of `value` when it is a call to `super()`
+pub fn super_call_args(value: &Expr) -> Option<&[Box<Expr>]> {
+    match value {
+        Expr::Call { func, args, .. } if matches!(&**func, Expr::Name { id, .. } if id == "super") => {
+            Some(args)
+        }
+        _ => None,
+    }
+}
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// Record the base classes of class `name`
+    pub fn register_class_bases(&mut self, name: &str, bases: &[Box<Expr>]) {
+        let bases = bases
+            .iter()
+            .filter_map(|base| match &**base {
+                Expr::Name { id, .. } => Some(id.clone()),
+                _ => None,
+            })
+            .collect();
+        self.class_bases.insert(name.to_string(), bases);
+    }
+
+    /// Compile `super(*super_args).method(*args)` in a method of the current class
+    pub fn compile_super_call(
+        &mut self,
+        super_args: &[Box<Expr>],
+        method: &str,
+        args: &[Box<Expr>],
+    ) -> Result<(BasicValueEnum<'ctx>, Type), String> {
+        let class = self
+            .current_class
+            .clone()
+            .ok_or_else(|| "super() used outside a method".to_string())?;
+        let start = match super_args {
+            [] => class.clone(),
+            [first, _] => match &**first {
+                Expr::Name { id, .. } => id.clone(),
+                _ => return Err("super() takes a class name as its first argument".to_string()),
+            },
+            _ => return Err("super() takes no arguments or a class and an instance".to_string()),
+        };
+
+        let bases_of = |name: &str| self.class_bases.get(name).cloned().unwrap_or_default();
+        let order = mro::linearize(&class, &bases_of).map_err(|_| {
+            format!(
+                "Cannot create a consistent method resolution order for class '{}'",
+                class
+            )
+        })?;
+        let target = mro::following(&order, &start)
+            .iter()
+            .find_map(|owner| self.module.get_function(&method_symbol(owner, method)));
+
+        let Some(function) = target else {
+            // Every class inherits object.__init__, which does nothing
+            if method == "__init__" && args.is_empty() {
+                return self.compile_name_constant(&NameConstant::None);
+            }
+            return Err(format!(
+                "super(): no base of '{}' has a compiled method '{}'",
+                class, method
+            ));
+        };
+
+        let receiver = self
+            .current_function
+            .and_then(|current| current.get_first_param())
+            .ok_or_else(|| "super() used in a method without self".to_string())?;
+        let mut call_args: Vec<BasicMetadataValueEnum<'ctx>> = vec![receiver.into()];
+        for arg in args {
+            let (value, _) = self.compile_expr(arg)?;
+            call_args.push(value.into());
+        }
+
+        let call = self
+            .builder
+            .build_call(function, &call_args, "super_call")
+            .unwrap();
+        self.emit_call_check()?;
+        match (call.try_as_basic_value().left(), function.get_type().get_return_type()) {
+            (Some(value), Some(BasicTypeEnum::IntType(int_type))) if int_type.get_bit_width() == 1 => {
+                Ok((value, Type::Bool))
+            }
+            (Some(value), Some(BasicTypeEnum::IntType(_))) => Ok((value, Type::Int)),
+            (Some(value), Some(BasicTypeEnum::FloatType(_))) => Ok((value, Type::Float)),
+            (Some(value), _) => Ok((value, Type::Any)),
+            (None, _) => self.compile_name_constant(&NameConstant::None),
+        }
+    }
+}
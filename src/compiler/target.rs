@@ -0,0 +1,82 @@
+// target.rs - Target selection for AOT compilation, including cross-compilation
+
+use inkwell::targets::{
+    CodeModel, InitializationConfig, RelocMode, Target, TargetMachine, TargetTriple,
+};
+use inkwell::OptimizationLevel;
+
+/// The machine code is generated for. Unset fields default to the host,
+/// except that a foreign triple gets a generic CPU with no extra features.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TargetSpec {
+    pub triple: Option<String>,
+    pub cpu: Option<String>,
+    pub features: Option<String>,
+}
+
+impl TargetSpec {
+    /// Target the machine the compiler is running on
+    pub fn host() -> Self {
+        Self::default()
+    }
+
+    /// The target triple, normalized
+    pub fn triple(&self) -> TargetTriple {
+        match &self.triple {
+            Some(triple) => TargetMachine::normalize_triple(&TargetTriple::create(triple)),
+            None => TargetMachine::get_default_triple(),
+        }
+    }
+
+    /// Whether code is generated for a different triple than the host's
+    pub fn is_cross(&self) -> bool {
+        self.triple.is_some() && self.triple() != TargetMachine::get_default_triple()
+    }
+
+    /// CPU name passed to LLVM
+    pub fn cpu(&self) -> String {
+        match &self.cpu {
+            Some(cpu) => cpu.clone(),
+            None if self.is_cross() => "generic".to_string(),
+            None => TargetMachine::get_host_cpu_name().to_string(),
+        }
+    }
+
+    /// Feature string passed to LLVM, e.g. "+neon,-fp-armv8"
+    pub fn features(&self) -> String {
+        match &self.features {
+            Some(features) => features.clone(),
+            None if self.is_cross() => String::new(),
+            None => TargetMachine::get_host_cpu_features().to_string(),
+        }
+    }
+
+    /// Create a target machine generating code at `level`
+    pub fn create_target_machine(&self, level: OptimizationLevel) -> Result<TargetMachine, String> {
+        self.create_target_machine_with_reloc(level, RelocMode::Default)
+    }
+
+    /// Create a target machine generating code at `level` with the given relocation model
+    pub fn create_target_machine_with_reloc(
+        &self,
+        level: OptimizationLevel,
+        reloc: RelocMode,
+    ) -> Result<TargetMachine, String> {
+        Target::initialize_all(&InitializationConfig::default());
+
+        let triple = self.triple();
+        let target =
+            Target::from_triple(&triple).map_err(|e| format!("No target for {}: {}", triple, e))?;
+
+        target
+            .create_target_machine(
+                &triple,
+                &self.cpu(),
+                &self.features(),
+                level,
+                reloc,
+                CodeModel::Default,
+            )
+            .ok_or_else(|| format!("Failed to create TargetMachine for {}", triple))
+    }
+}
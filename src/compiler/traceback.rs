@@ -0,0 +1,146 @@
+// traceback.rs - Frames and source lines recorded for runtime tracebacks
+//
+// Each compiled function pushes a frame named after itself on entry and pops
+// it on every return, and each statement records its line in the innermost
+// frame. When an exception is raised the runtime prints the frames as a
+// traceback (see `runtime/traceback.rs`).
+//
+// A function cut short by a with_timeout() deadline or a runtime error
+// returns a zero value. Its caller checks for that as soon as the call
+// returns, before the rest of the statement can use the value, and returns
+// in turn.
+
+use crate::compiler::context::CompilationContext;
+use inkwell::values::{FunctionValue, IntValue};
+
+impl<'ctx> CompilationContext<'ctx> {
+    /// Push a traceback frame for the function whose entry block is being built.
+    ///
+    /// Returns the frame of the enclosing function being compiled, to be
+    /// restored with `leave_trace_frame` once this function is done.
+    pub fn enter_trace_frame(
+        &mut self,
+        function: &str,
+    ) -> Result<Option<inkwell::values::IntValue<'ctx>>, String> {
+        let name = self.make_cstr(
+            &format!("trace_name_{}", function),
+            format!("{}\0", function).as_bytes(),
+        );
+        let depth = self
+            .call_runtime_function("trace_push_frame", &[name.into()])?
+            .ok_or("trace_push_frame returned void")?
+            .into_int_value();
+        Ok(self.trace_frame.replace(depth))
+    }
+
+    /// Restore the frame of the enclosing function after compiling a function body
+    pub fn leave_trace_frame(&mut self, saved: Option<inkwell::values::IntValue<'ctx>>) {
+        self.trace_frame = saved;
+    }
+
+    /// Pop the current function's traceback frame before it returns
+    pub fn emit_trace_frame_exit(&mut self) -> Result<(), String> {
+        if let Some(depth) = self.trace_frame {
+            self.call_runtime_function("trace_pop_frames", &[depth.into()])?;
+        }
+        Ok(())
+    }
+
+    /// Record the line of the statement about to run, and return from the
+    /// function instead of running it once a with_timeout() deadline passes
+    pub fn emit_trace_line(&mut self, line: usize) -> Result<(), String> {
+        let block = match self.builder.get_insert_block() {
+            Some(block) if block.get_terminator().is_none() => block,
+            _ => return Ok(()),
+        };
+        let Some(function) = block.get_parent() else {
+            return Ok(());
+        };
+        if self.trace_frame.is_none() || line == 0 {
+            return Ok(());
+        }
+        let line = self.llvm_context.i64_type().const_int(line as u64, false);
+        let interrupted = self
+            .call_runtime_function("trace_set_line", &[line.into()])?
+            .ok_or("trace_set_line returned void")?
+            .into_int_value();
+        self.build_stop_branch(function, interrupted, "statement");
+        Ok(())
+    }
+
+    /// Return from the function if the call just made was cut short by a
+    /// with_timeout() deadline or a runtime error, so its zero result is
+    /// never used
+    pub fn emit_call_check(&mut self) -> Result<(), String> {
+        let block = match self.builder.get_insert_block() {
+            Some(block) if block.get_terminator().is_none() => block,
+            _ => return Ok(()),
+        };
+        let Some(function) = block.get_parent() else {
+            return Ok(());
+        };
+        if self.trace_frame.is_none() {
+            return Ok(());
+        }
+        let stopping = self
+            .call_runtime_function("trace_stopping", &[])?
+            .ok_or("trace_stopping returned void")?
+            .into_int_value();
+        self.build_stop_branch(function, stopping, "returned");
+        Ok(())
+    }
+
+    /// Return a zero value when `stop` is true, and otherwise continue in a
+    /// new block called `next`
+    fn build_stop_branch(&self, function: FunctionValue<'ctx>, stop: IntValue<'ctx>, next: &str) {
+        let stop_block = self.llvm_context.append_basic_block(function, "interrupted");
+        let run_block = self.llvm_context.append_basic_block(function, next);
+        self.builder
+            .build_conditional_branch(stop, stop_block, run_block)
+            .unwrap();
+        // timeout_exit() pops the frames and except blocks left behind
+        self.builder.position_at_end(stop_block);
+        self.build_zero_return(function);
+        self.builder.position_at_end(run_block);
+    }
+
+    /// Stop the program with a runtime error when `failed` is true (see
+    /// `runtime/abort.rs`). The function returns at once, since a JIT host
+    /// catching the error keeps running, and its callers return as soon as
+    /// the call does (see `emit_call_check`).
+    pub fn emit_runtime_abort(&mut self, failed: IntValue<'ctx>, message: &str) -> Result<(), String> {
+        let function = self
+            .builder
+            .get_insert_block()
+            .and_then(|block| block.get_parent())
+            .ok_or("runtime check outside a function")?;
+        let fail_block = self.llvm_context.append_basic_block(function, "abort");
+        let ok_block = self.llvm_context.append_basic_block(function, "checked");
+        self.builder
+            .build_conditional_branch(failed, fail_block, ok_block)
+            .unwrap();
+
+        self.builder.position_at_end(fail_block);
+        let message = self.make_cstr("abort_message", format!("{}\0", message).as_bytes());
+        self.call_runtime_function("cheetah_runtime_abort", &[message.into()])?;
+        self.build_zero_return(function);
+        self.builder.position_at_end(ok_block);
+        Ok(())
+    }
+
+    /// Return a zero value of `function`'s return type, for a function cut short
+    fn build_zero_return(&self, function: FunctionValue<'ctx>) {
+        match function.get_type().get_return_type() {
+            Some(return_type) => self.builder.build_return(Some(&return_type.const_zero())).unwrap(),
+            None => self.builder.build_return(None).unwrap(),
+        };
+    }
+
+    /// Raise `typ(message)` at runtime, as for an error found by compiled code
+    pub fn emit_runtime_error(&mut self, typ: &str, message: &str) -> Result<(), String> {
+        let typ_name = self.make_cstr("runtime_error_type", format!("{}\0", typ).as_bytes());
+        let message = self.make_cstr("runtime_error_message", format!("{}\0", message).as_bytes());
+        self.call_runtime_function("exception_raise_new", &[typ_name.into(), message.into()])?;
+        Ok(())
+    }
+}
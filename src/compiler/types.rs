@@ -1,6 +1,9 @@
-use crate::ast::{Expr, NameConstant, Number};
+use crate::ast::{Expr, NameConstant, Number, Operator};
+#[cfg(feature = "codegen")]
 use inkwell::context::Context;
+#[cfg(feature = "codegen")]
 use inkwell::types::{BasicType, BasicTypeEnum, FunctionType};
+#[cfg(feature = "codegen")]
 use inkwell::AddressSpace;
 use std::collections::HashMap;
 use std::fmt;
@@ -62,6 +65,50 @@ pub enum TypeError {
 
     /// When a type is not indexable
     NotIndexable(Type),
+
+    /// When an `@extern` function declaration has no C signature
+    InvalidExtern { function: String, reason: String },
+
+    /// When a built-in is called with a keyword argument it doesn't take
+    UnexpectedKeyword { function: String, keyword: String },
+
+    /// When a class's bases can't be put in a consistent method resolution order
+    InconsistentMro { class_name: String, bases: Vec<String> },
+
+    /// When `super()` is used outside a method or names nothing in the MRO
+    InvalidSuper(String),
+
+    /// When a class with abstract methods nothing implements is instantiated
+    AbstractInstantiation { class_name: String, methods: Vec<String> },
+
+    /// When a function reads a local variable before any assignment to it has run
+    UnboundLocal { name: String, function: String },
+}
+
+impl TypeError {
+    /// Stable diagnostic code for the kind of error
+    pub fn code(&self) -> &'static str {
+        match self {
+            TypeError::IncompatibleTypes { .. } => "incompatible-types",
+            TypeError::UndefinedVariable(_) => "undefined-variable",
+            TypeError::InvalidOperator { .. } => "invalid-operator",
+            TypeError::InvalidArgument { .. } => "invalid-argument",
+            TypeError::WrongArgumentCount { .. } | TypeError::InvalidArgumentCount { .. } => {
+                "wrong-argument-count"
+            }
+            TypeError::NotAClass { .. } => "not-a-class",
+            TypeError::UndefinedMember { .. } => "undefined-member",
+            TypeError::CannotInferType(_) => "cannot-infer-type",
+            TypeError::NotCallable(_) => "not-callable",
+            TypeError::NotIndexable(_) => "not-indexable",
+            TypeError::InvalidExtern { .. } => "invalid-extern",
+            TypeError::UnexpectedKeyword { .. } => "unexpected-keyword",
+            TypeError::InconsistentMro { .. } => "inconsistent-mro",
+            TypeError::InvalidSuper(_) => "invalid-super",
+            TypeError::AbstractInstantiation { .. } => "abstract-instantiation",
+            TypeError::UnboundLocal { .. } => "unbound-local",
+        }
+    }
 }
 
 impl fmt::Display for TypeError {
@@ -135,6 +182,29 @@ impl fmt::Display for TypeError {
             TypeError::NotIndexable(ty) => {
                 write!(f, "Type {} is not indexable", ty)
             }
+            TypeError::InvalidExtern { function, reason } => {
+                write!(f, "Invalid extern declaration of '{}': {}", function, reason)
+            }
+            TypeError::UnexpectedKeyword { function, keyword } => {
+                write!(f, "{}() got an unexpected keyword argument '{}'", function, keyword)
+            }
+            TypeError::InconsistentMro { class_name, bases } => {
+                write!(
+                    f,
+                    "Cannot create a consistent method resolution order for class '{}' with bases {}",
+                    class_name,
+                    bases.join(", ")
+                )
+            }
+            TypeError::InvalidSuper(message) => write!(f, "super(): {}", message),
+            TypeError::AbstractInstantiation { class_name, methods } => {
+                write!(
+                    f,
+                    "Cannot instantiate abstract class '{}' with abstract methods {}",
+                    class_name,
+                    methods.join(", ")
+                )
+            }
             TypeError::InvalidArgumentCount {
                 function,
                 expected,
@@ -146,6 +216,13 @@ impl fmt::Display for TypeError {
                     function, expected, got
                 )
             }
+            TypeError::UnboundLocal { name, function } => {
+                write!(
+                    f,
+                    "Local variable '{}' is read in '{}' before it is assigned",
+                    name, function
+                )
+            }
         }
     }
 }
@@ -181,6 +258,10 @@ pub enum Type {
         fields: HashMap<String, Type>,
     },
 
+    /// A class itself used as a value, e.g. `cls = Point`: it holds the
+    /// class attributes and calling it creates an instance
+    ClassObject(String),
+
     Any,
     Void,
     Unknown,
@@ -258,6 +339,10 @@ impl Hash for Type {
                 name.hash(state);
                 base_classes.hash(state);
             }
+            Type::ClassObject(name) => {
+                17.hash(state);
+                name.hash(state);
+            }
             Type::Any => {
                 12.hash(state);
             }
@@ -322,6 +407,7 @@ impl fmt::Display for Type {
                 write!(f, ") -> {}", return_type)
             }
             Type::Class { name, .. } => write!(f, "class {}", name),
+            Type::ClassObject(name) => write!(f, "type[{}]", name),
             Type::Any => write!(f, "Any"),
             Type::Void => write!(f, "void"),
             Type::Unknown => write!(f, "unknown"),
@@ -330,7 +416,10 @@ impl fmt::Display for Type {
                 base_type,
                 type_args,
             } => {
-                write!(f, "{}", base_type)?;
+                match base_type.as_ref() {
+                    Type::Class { name, .. } => write!(f, "{}", name)?,
+                    base_type => write!(f, "{}", base_type)?,
+                }
                 write!(f, "[")?;
                 for (i, arg) in type_args.iter().enumerate() {
                     if i > 0 {
@@ -344,6 +433,8 @@ impl fmt::Display for Type {
     }
 }
 
+/// LLVM representations of types
+#[cfg(feature = "codegen")]
 impl Type {
     /// Convert a Cheetah type to an LLVM type
     pub fn to_llvm_type<'ctx>(&self, context: &'ctx Context) -> BasicTypeEnum<'ctx> {
@@ -431,7 +522,7 @@ impl Type {
             Type::Function { .. } => context
                 .ptr_type(AddressSpace::default())
                 .as_basic_type_enum(),
-            Type::Class { .. } => context
+            Type::Class { .. } | Type::ClassObject(_) => context
                 .ptr_type(AddressSpace::default())
                 .as_basic_type_enum(),
             Type::Any | Type::Unknown | Type::TypeParam(_) | Type::Generic { .. } => context
@@ -534,6 +625,7 @@ impl Type {
             Type::Unknown => 15,
             Type::TypeParam(_) => 16,
             Type::Generic { .. } => 17,
+            Type::ClassObject(_) => 18,
         };
 
         let type_name = match self {
@@ -557,6 +649,9 @@ impl Type {
                 return self.create_function_type_info(context, return_type)
             }
             Type::Class { name, .. } => return self.create_class_type_info(context, name),
+            Type::ClassObject(name) => {
+                return self.create_named_type_info(context, "type", name)
+            }
             Type::Any => "Any",
             Type::Void => "void",
             Type::Unknown => "unknown",
@@ -759,7 +854,9 @@ impl Type {
 
         struct_type.const_named_struct(&[id_value.into(), name_value.into(), base_value.into()])
     }
+}
 
+impl Type {
     /// Infer the type of an AST expression
     pub fn from_expr(expr: &Expr) -> Self {
         match expr {
@@ -960,6 +1057,43 @@ impl Type {
         }
     }
 
+    /// Position of a numeric type in the promotion lattice `Bool < Int < Float`
+    pub fn numeric_rank(&self) -> Option<u8> {
+        match self {
+            Type::Bool => Some(0),
+            Type::Int => Some(1),
+            Type::Float => Some(2),
+            _ => None,
+        }
+    }
+
+    /// Least upper bound of two numeric types in the promotion lattice.
+    ///
+    /// Returns `None` unless both types are numeric.
+    pub fn promote_numeric(type1: &Type, type2: &Type) -> Option<Type> {
+        let rank1 = type1.numeric_rank()?;
+        let rank2 = type2.numeric_rank()?;
+        Some(if rank1 >= rank2 {
+            type1.clone()
+        } else {
+            type2.clone()
+        })
+    }
+
+    /// Result type of an arithmetic operation; arithmetic on bools yields an int
+    pub fn promote_arithmetic(type1: &Type, type2: &Type) -> Option<Type> {
+        let promoted = Type::promote_numeric(type1, type2)?;
+        Type::promote_numeric(&promoted, &Type::Int)
+    }
+
+    /// Whether an implicit numeric conversion moves down the promotion lattice
+    pub fn is_lossy_conversion(from: &Type, to: &Type) -> bool {
+        match (from.numeric_rank(), to.numeric_rank()) {
+            (Some(from_rank), Some(to_rank)) => to_rank < from_rank,
+            _ => false,
+        }
+    }
+
     /// Unify two types, if possible
     pub fn unify(type1: &Type, type2: &Type) -> Option<Type> {
         if type1 == type2 {
@@ -1046,9 +1180,9 @@ impl Type {
                 Type::unify(elem1, elem2).map(|unified_elem| Type::Set(Box::new(unified_elem)))
             }
 
-            (Type::Int, Type::Float) | (Type::Float, Type::Int) => Some(Type::Float),
-            (Type::Bool, Type::Int) | (Type::Int, Type::Bool) => Some(Type::Int),
-            (Type::Bool, Type::Float) | (Type::Float, Type::Bool) => Some(Type::Float),
+            (Type::Int | Type::Float | Type::Bool, Type::Int | Type::Float | Type::Bool) => {
+                Type::promote_numeric(type1, type2)
+            }
 
             (Type::TypeParam(name), other) | (other, Type::TypeParam(name)) => {
                 if !matches!(other, Type::TypeParam(_)) {
@@ -1248,6 +1382,74 @@ impl Type {
         }
     }
 
+    /// Type of the object bound by `except E as e`
+    pub fn exception() -> Self {
+        let mut fields = HashMap::new();
+        fields.insert("message".to_string(), Type::String);
+        fields.insert("args".to_string(), Type::List(Box::new(Type::Any)));
+        Type::Class {
+            name: "Exception".to_string(),
+            base_classes: vec![],
+            methods: HashMap::new(),
+            fields,
+        }
+    }
+
+    /// Whether this is the type of a caught exception object
+    pub fn is_exception(&self) -> bool {
+        matches!(self, Type::Class { name, .. } if name == "Exception")
+    }
+
+    /// Type of the file object returned by `open()`
+    pub fn file() -> Self {
+        let mut methods = HashMap::new();
+        methods.insert("read".to_string(), Box::new(Type::function(vec![], Type::String)));
+        methods.insert("readline".to_string(), Box::new(Type::function(vec![], Type::String)));
+        methods.insert(
+            "write".to_string(),
+            Box::new(Type::function(vec![Type::String], Type::Int)),
+        );
+        methods.insert("close".to_string(), Box::new(Type::function(vec![], Type::None)));
+        let mut fields = HashMap::new();
+        fields.insert("closed".to_string(), Type::Bool);
+        Type::Class {
+            name: "File".to_string(),
+            base_classes: vec![],
+            methods,
+            fields,
+        }
+    }
+
+    /// Whether this is the type of a file object
+    pub fn is_file(&self) -> bool {
+        matches!(self, Type::Class { name, .. } if name == "File")
+    }
+
+    /// Type of a channel carrying values of `element`, as `channel(int)` creates
+    pub fn channel(element: Type) -> Self {
+        Type::Generic {
+            base_type: Box::new(Type::class("Channel")),
+            type_args: vec![element],
+        }
+    }
+
+    /// The type of the values a channel carries, if this is a channel type
+    pub fn channel_element(&self) -> Option<&Type> {
+        match self {
+            Type::Generic { base_type, type_args }
+                if matches!(base_type.as_ref(), Type::Class { name, .. } if name == "Channel") =>
+            {
+                type_args.first()
+            }
+            _ => None,
+        }
+    }
+
+    /// Result of `sys.exc_info()`: the exception's type name, the exception, and a traceback
+    pub fn exc_info() -> Self {
+        Type::Tuple(vec![Type::String, Type::exception(), Type::None])
+    }
+
     /// Create a simple list type
     pub fn list(element_type: Type) -> Self {
         Type::List(Box::new(element_type))
@@ -1324,17 +1526,100 @@ impl Type {
                         return_type: Box::new(return_type),
                     })
                 }
+                "get" | "pop" | "setdefault" | "update" | "clear" => {
+                    let (param_types, defaults, return_type) = match member {
+                        "update" => (vec![self.clone()], vec![false], Type::None),
+                        "clear" => (vec![], vec![], Type::None),
+                        // setdefault() requires its default so the stored value is typed
+                        _ => (
+                            vec![*key_type.clone(), *value_type.clone()],
+                            vec![false, member != "setdefault"],
+                            *value_type.clone(),
+                        ),
+                    };
+                    Ok(Type::Function {
+                        param_names: (0..param_types.len()).map(|i| format!("arg{}", i)).collect(),
+                        param_types,
+                        has_varargs: false,
+                        has_kwargs: false,
+                        default_values: defaults,
+                        return_type: Box::new(return_type),
+                    })
+                }
                 _ => Err(TypeError::NotAClass {
                     expr_type: self.clone(),
                     member: member.to_string(),
                 }),
             },
+            Type::List(element_type) => {
+                let element = *element_type.clone();
+                let (param_types, defaults, return_type) = match member {
+                    "append" | "remove" => (vec![element], vec![false], Type::None),
+                    "insert" => (vec![Type::Int, element], vec![false, false], Type::None),
+                    "extend" => (vec![self.clone()], vec![false], Type::None),
+                    "pop" => (vec![Type::Int], vec![true], element),
+                    "sort" => (vec![], vec![], Type::None),
+                    "index" | "count" => (vec![element], vec![false], Type::Int),
+                    _ => {
+                        return Err(TypeError::NotAClass {
+                            expr_type: self.clone(),
+                            member: member.to_string(),
+                        })
+                    }
+                };
+                Ok(Type::Function {
+                    param_names: (0..param_types.len()).map(|i| format!("arg{}", i)).collect(),
+                    param_types,
+                    has_varargs: false,
+                    has_kwargs: false,
+                    default_values: defaults,
+                    return_type: Box::new(return_type),
+                })
+            }
+            Type::String => {
+                let (param_types, return_type) = match member {
+                    "split" => (vec![Type::String], Type::List(Box::new(Type::String))),
+                    "join" => (vec![Type::List(Box::new(Type::String))], Type::String),
+                    "strip" | "upper" | "lower" => (vec![], Type::String),
+                    "replace" => (vec![Type::String, Type::String], Type::String),
+                    "find" => (vec![Type::String], Type::Int),
+                    "startswith" | "endswith" => (vec![Type::String], Type::Bool),
+                    _ => {
+                        return Err(TypeError::NotAClass {
+                            expr_type: self.clone(),
+                            member: member.to_string(),
+                        })
+                    }
+                };
+                // split() may omit its separator
+                let default_values = vec![member == "split"; param_types.len()];
+                Ok(Type::Function {
+                    param_names: (0..param_types.len()).map(|i| format!("arg{}", i)).collect(),
+                    param_types,
+                    has_varargs: false,
+                    has_kwargs: false,
+                    default_values,
+                    return_type: Box::new(return_type),
+                })
+            }
             _ => Err(TypeError::NotAClass {
                 expr_type: self.clone(),
                 member: member.to_string(),
             }),
         }
     }
+
+    /// Names of the methods `get_member_type` knows for a builtin type
+    pub fn builtin_methods(&self) -> &'static [&'static str] {
+        match self {
+            Type::Dict(..) => &["clear", "get", "items", "keys", "pop", "setdefault", "update", "values"],
+            Type::List(_) => &["append", "count", "extend", "index", "insert", "pop", "remove", "sort"],
+            Type::String => &[
+                "endswith", "find", "join", "lower", "replace", "split", "startswith", "strip", "upper",
+            ],
+            _ => &[],
+        }
+    }
 }
 
 /// Determine if a type is a reference type (pointer to an object)
@@ -1351,6 +1636,106 @@ pub(crate) fn is_reference_type(ty: &Type) -> bool {
     )
 }
 
+/// How a supported binary operation is lowered to LLVM IR
+#[derive(Debug, Clone, PartialEq)]
+pub enum BinaryOpLowering {
+    /// Both operands are converted to the given numeric type
+    Numeric(Type),
+    /// String concatenation
+    StringConcat,
+    /// String repetition; `swapped` is set when the count is the left operand
+    StringRepeat { swapped: bool },
+    /// List concatenation
+    ListConcat,
+    /// List repetition; `swapped` is set when the count is the left operand
+    ListRepeat { swapped: bool },
+}
+
+/// Look up the lowering for an (operator, left type, right type) cell.
+///
+/// Returns `None` for combinations the compiler does not support.
+pub fn binary_op_lowering(op: &Operator, left: &Type, right: &Type) -> Option<BinaryOpLowering> {
+    let is_integral = |ty: &Type| matches!(ty, Type::Int | Type::Bool);
+    let is_numeric = |ty: &Type| matches!(ty, Type::Int | Type::Float | Type::Bool);
+
+    match op {
+        Operator::Add
+        | Operator::Sub
+        | Operator::Mult
+        | Operator::Div
+        | Operator::FloorDiv
+        | Operator::Mod
+        | Operator::Pow
+            if is_numeric(left) && is_numeric(right) =>
+        {
+            Type::promote_arithmetic(left, right).map(BinaryOpLowering::Numeric)
+        }
+
+        Operator::BitOr
+        | Operator::BitXor
+        | Operator::BitAnd
+        | Operator::LShift
+        | Operator::RShift
+            if is_integral(left) && is_integral(right) =>
+        {
+            Some(BinaryOpLowering::Numeric(Type::Int))
+        }
+
+        Operator::Add => match (left, right) {
+            (Type::String, Type::String) => Some(BinaryOpLowering::StringConcat),
+            (Type::List(_), Type::List(_)) => Some(BinaryOpLowering::ListConcat),
+            _ => None,
+        },
+
+        Operator::Mult => match (left, right) {
+            (Type::String, count) if is_integral(count) => {
+                Some(BinaryOpLowering::StringRepeat { swapped: false })
+            }
+            (count, Type::String) if is_integral(count) => {
+                Some(BinaryOpLowering::StringRepeat { swapped: true })
+            }
+            (Type::List(_), count) if is_integral(count) => {
+                Some(BinaryOpLowering::ListRepeat { swapped: false })
+            }
+            (count, Type::List(_)) if is_integral(count) => {
+                Some(BinaryOpLowering::ListRepeat { swapped: true })
+            }
+            _ => None,
+        },
+
+        _ => None,
+    }
+}
+
+/// Source-level spelling of a binary operator
+pub fn operator_symbol(op: &Operator) -> &'static str {
+    match op {
+        Operator::Add => "+",
+        Operator::Sub => "-",
+        Operator::Mult => "*",
+        Operator::MatMult => "@",
+        Operator::Div => "/",
+        Operator::FloorDiv => "//",
+        Operator::Mod => "%",
+        Operator::Pow => "**",
+        Operator::LShift => "<<",
+        Operator::RShift => ">>",
+        Operator::BitOr => "|",
+        Operator::BitXor => "^",
+        Operator::BitAnd => "&",
+    }
+}
+
+/// Uniform diagnostic for an unsupported binary operation
+pub fn unsupported_operand_types(op: &Operator, left: &Type, right: &Type) -> String {
+    format!(
+        "unsupported operand types for {}: '{}' and '{}'",
+        operator_symbol(op),
+        left,
+        right
+    )
+}
+
 /// Type context for tracking variable types during compilation
 pub struct TypeContext {
     variables: HashMap<String, Type>,
@@ -0,0 +1,227 @@
+// wasm.rs - WebAssembly output for running Cheetah programs in browsers and WASI runtimes
+//
+// Programs are compiled for wasm32 and linked in one of two ways:
+//
+// - `WasmRuntime::Wasi` links libcheetah built for wasm32-wasip1
+//   (`cargo build --release --target wasm32-wasip1`) into a WASI reactor,
+//   run with e.g. `wasmtime --invoke main program.wasm`.
+// - `WasmRuntime::Shim` leaves the runtime functions as imports from the
+//   `env` module and writes a JavaScript shim next to the .wasm file that
+//   supplies them. The shim implements printing; other runtime functions throw
+//   when called.
+
+use crate::compiler::optimizer::codegen_level;
+use crate::compiler::runtime::abi::RUNTIME_ABI_VERSION;
+use crate::compiler::target::TargetSpec;
+use crate::compiler::Compiler;
+use inkwell::targets::FileType;
+use std::path::Path;
+use std::process::Command;
+
+/// How the runtime is provided to a wasm module
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WasmRuntime {
+    /// Link libcheetah compiled to wasm32-wasip1
+    Wasi,
+    /// Import runtime functions from a generated JavaScript shim
+    Shim,
+}
+
+impl WasmRuntime {
+    /// Parse a runtime name as given to `--wasm`
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "wasi" => Ok(WasmRuntime::Wasi),
+            "shim" | "js" => Ok(WasmRuntime::Shim),
+            other => Err(format!(
+                "unknown wasm runtime '{}' (expected 'wasi' or 'shim')",
+                other
+            )),
+        }
+    }
+
+    /// Target the module is compiled for
+    pub fn target(self) -> TargetSpec {
+        let triple = match self {
+            WasmRuntime::Wasi => "wasm32-unknown-wasi",
+            WasmRuntime::Shim => "wasm32-unknown-unknown",
+        };
+        TargetSpec {
+            triple: Some(triple.to_string()),
+            cpu: None,
+            features: None,
+        }
+    }
+}
+
+/// Runtime functions the JavaScript shim implements
+const SHIM_FUNCTIONS: [&str; 11] = [
+    "print_string",
+    "println_string",
+    "print_int",
+    "print_float",
+    "print_bool",
+    "cheetah_check_abi",
+    "cheetah_configure_output",
+    "trace_push_frame",
+    "trace_pop_frames",
+    "trace_set_line",
+    "trace_stopping",
+];
+
+/// JavaScript module that instantiates `wasm_file` with the runtime functions it imports
+pub fn js_shim(wasm_file: &str, imports: &[String]) -> String {
+    let mut shim = format!(
+        r#"// JavaScript host for {wasm_file}, generated by cheetah.
+//
+// Node:    import {{ run }} from "./this-file.js"; await run(fs.readFileSync("{wasm_file}"));
+// Browser: await run(await (await fetch("{wasm_file}")).arrayBuffer());
+
+let memory;
+let pending = "";
+const decoder = new TextDecoder();
+
+function cString(ptr) {{
+    const bytes = new Uint8Array(memory.buffer, ptr);
+    let end = 0;
+    while (bytes[end] !== 0) end++;
+    return decoder.decode(bytes.subarray(0, end));
+}}
+
+function write(text) {{
+    pending += text;
+    const lines = pending.split("\n");
+    pending = lines.pop();
+    for (const line of lines) console.log(line);
+}}
+
+function unavailable(name) {{
+    return () => {{
+        throw new Error(`cheetah: runtime function '${{name}}' is not available in the JavaScript shim`);
+    }};
+}}
+
+const env = {{
+    print_string: (ptr) => write(cString(ptr)),
+    println_string: (ptr) => write(cString(ptr) + "\n"),
+    print_int: (value) => write(value.toString()),
+    print_float: (value) => write(Number.isInteger(value) ? value.toFixed(1) : String(value)),
+    print_bool: (value) => write(value ? "True" : "False"),
+    cheetah_check_abi: (expected) => {{
+        if (expected !== {abi}n) {{
+            throw new Error(`cheetah: runtime ABI mismatch: compiled for version ${{expected}}, shim provides {abi}`);
+        }}
+    }},
+    cheetah_configure_output: () => {{}},
+    trace_push_frame: () => 0n,
+    trace_pop_frames: () => {{}},
+    trace_set_line: () => 0,
+    trace_stopping: () => 0,
+"#,
+        wasm_file = wasm_file,
+        abi = RUNTIME_ABI_VERSION,
+    );
+
+    for name in imports {
+        if !SHIM_FUNCTIONS.contains(&name.as_str()) {
+            shim.push_str(&format!("    {}: unavailable(\"{}\"),\n", name, name));
+        }
+    }
+
+    shim.push_str(
+        r#"};
+
+export async function run(bytes) {
+    const { instance } = await WebAssembly.instantiate(bytes, { env });
+    memory = instance.exports.memory;
+    instance.exports.main();
+    if (pending) {
+        console.log(pending);
+        pending = "";
+    }
+}
+"#,
+    );
+    shim
+}
+
+impl<'ctx> Compiler<'ctx> {
+    /// Names of the functions the module declares but does not define
+    pub fn imported_functions(&self) -> Vec<String> {
+        let mut imports = Vec::new();
+        let mut function = self.context.module.get_first_function();
+        while let Some(f) = function {
+            let name = f.get_name().to_string_lossy().into_owned();
+            if f.count_basic_blocks() == 0 && !name.starts_with("llvm.") {
+                imports.push(name);
+            }
+            function = f.get_next_function();
+        }
+        imports
+    }
+
+    /// Write `<output>.wasm`, plus `<output>.js` when the runtime is the JavaScript shim.
+    ///
+    /// The module must have been compiled after `set_target(runtime.target())`.
+    pub fn emit_wasm(&mut self, output: &str, runtime: WasmRuntime) -> Result<(), String> {
+        let tm = self
+            .target
+            .create_target_machine(codegen_level(self.opt_level))?;
+
+        let obj_path = format!("{}.o", output);
+        let wasm_path = format!("{}.wasm", output);
+        tm.write_to_file(&self.context.module, FileType::Object, Path::new(&obj_path))
+            .map_err(|e| format!("Failed to write wasm object file: {:?}", e))?;
+
+        let mut cmd = match runtime {
+            WasmRuntime::Shim => {
+                let mut cmd = Command::new(std::env::var("WASM_LD").unwrap_or_else(|_| "wasm-ld".into()));
+                cmd.arg(&obj_path)
+                    .arg("--no-entry")
+                    .arg("--export=main")
+                    .arg("--allow-undefined");
+                cmd
+            }
+            WasmRuntime::Wasi => {
+                let runtime_lib_dir = match std::env::var("CARGO_MANIFEST_DIR") {
+                    Ok(manifest) => format!("{}/target/wasm32-wasip1/release", manifest),
+                    Err(_) => "target/wasm32-wasip1/release".to_string(),
+                };
+                let mut cmd = Command::new("clang");
+                cmd.arg("--target=wasm32-wasip1");
+                if let Ok(sysroot) = std::env::var("WASI_SYSROOT") {
+                    cmd.arg(format!("--sysroot={}", sysroot));
+                }
+                cmd.arg("-mexec-model=reactor")
+                    .arg(&obj_path)
+                    .arg("-L")
+                    .arg(&runtime_lib_dir)
+                    .arg("-lcheetah")
+                    .arg("-Wl,--export=main");
+                cmd
+            }
+        };
+        cmd.arg("-o").arg(&wasm_path);
+
+        let status = cmd
+            .status()
+            .map_err(|e| format!("Failed to spawn wasm linker: {}", e))?;
+        if !status.success() {
+            return Err(format!("wasm linker exited with: {}", status));
+        }
+
+        if runtime == WasmRuntime::Shim {
+            let wasm_file = Path::new(&wasm_path)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| wasm_path.clone());
+            let shim_path = format!("{}.js", output);
+            std::fs::write(&shim_path, js_shim(&wasm_file, &self.imported_functions()))
+                .map_err(|e| format!("Failed to write {}: {}", shim_path, e))?;
+            println!("✅ Wrote JavaScript shim to {}", shim_path);
+        }
+
+        println!("✅ WebAssembly build → {}", wasm_path);
+        Ok(())
+    }
+}
@@ -0,0 +1,262 @@
+// completion.rs - Completing names for editors and the REPL
+//
+// `complete` lists what the identifier being typed at a position can
+// become: keywords, builtins and the names visible in the scope around it,
+// or after a dot the members of a module with a stub or of a value whose
+// type the checker knows:
+//
+//     let source = "name = \"cheetah\"\nname.up";
+//     let module = cheetah::parse("name = \"cheetah\"\n")?;
+//     let (start, items) = complete(&module, source, Position::new(2, 8));
+//     // start is 2:6 and items holds `upper`, a method
+//
+// `module` is the last version of the source that parsed, since the line
+// being typed often doesn't. `NameCompleter` completes the same way over
+// the inputs of a REPL session.
+
+use crate::ast::Module;
+use crate::compiler::types::Type;
+use crate::lexer::KEYWORDS;
+use crate::parser::parse_expression;
+use crate::symtable::{Position, SymbolTable, SymbolType};
+use crate::typechecker::stubs::ModuleStub;
+use crate::typechecker::{TypeChecker, TypeEnvironment};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Builtins the type checker infers directly instead of declaring them
+const INFERRED_BUILTINS: &[&str] = &["range", "list", "dict", "set", "tuple", "channel", "send", "recv", "with_timeout"];
+
+/// Names of the builtin functions, whether declared or inferred by the type checker
+pub fn builtin_names() -> BTreeSet<String> {
+    let mut names: BTreeSet<String> = INFERRED_BUILTINS.iter().map(|name| name.to_string()).collect();
+    if let Some(builtins) = TypeEnvironment::new().get_current_scope() {
+        names.extend(builtins.get_functions().keys().cloned());
+    }
+    names
+}
+
+/// What Tab inserts at the start of a line, so blocks can still be indented
+pub const INDENT: &str = "    ";
+
+/// What a completion names
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    Keyword,
+    Builtin,
+    Module,
+    Class,
+    Function,
+    Method,
+    Variable,
+    Parameter,
+    /// A class's field or a module's constant
+    Field,
+}
+
+/// A name that can replace the word being typed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+}
+
+/// Complete the identifier ending just before `position` in `source`, with
+/// `module` giving the scopes and types. Returns where the identifier
+/// starts and the candidates that replace it, sorted by label.
+pub fn complete(module: &Module, source: &str, position: Position) -> (Position, Vec<Completion>) {
+    let line = source.lines().nth(position.line.saturating_sub(1)).unwrap_or("");
+    let cursor = line
+        .char_indices()
+        .nth(position.column.saturating_sub(1))
+        .map_or(line.len(), |(i, _)| i);
+
+    let table = SymbolTable::build(module);
+    let scope = table.scope_at_position(position.line, position.column);
+    let mut names = base_names();
+    for symbol in table.visible_symbols(scope) {
+        if let Some(kind) = symbol_kind(&symbol.symbol_type) {
+            names.insert(symbol.name.clone(), kind);
+        }
+    }
+    let mut checker = TypeChecker::new();
+    let _ = checker.check_module(module);
+
+    let (start, items) = complete_before(&names, &checker, &line[..cursor]);
+    (Position::new(position.line, line[..start].chars().count() + 1), items)
+}
+
+/// Completes keywords, builtins, the names defined so far in a REPL
+/// session and the members of values and modules
+#[derive(Debug, Clone)]
+pub struct NameCompleter {
+    names: BTreeMap<String, CompletionKind>,
+    /// Types of the session's definitions
+    checker: TypeChecker,
+}
+
+impl Default for NameCompleter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NameCompleter {
+    /// A completer that knows the keywords, builtin functions and shipped module stubs
+    pub fn new() -> Self {
+        Self {
+            names: base_names(),
+            checker: TypeChecker::new(),
+        }
+    }
+
+    /// Learn the members `stub` declares, offered after `module.`
+    pub fn add_stub(&mut self, stub: &ModuleStub) {
+        self.checker.add_stub(stub.clone());
+    }
+
+    /// Learn the top-level variables, functions, classes and imports
+    /// `module` defines, and their types if it type checks
+    pub fn add_module(&mut self, module: &Module) {
+        let table = SymbolTable::build(module);
+        for symbol in table.symbols_in_scope(SymbolTable::ROOT) {
+            if let Some(kind) = symbol_kind(&symbol.symbol_type) {
+                self.names.insert(symbol.name.clone(), kind);
+            }
+        }
+
+        let mut checker = self.checker.clone();
+        if checker.check_module(module).is_ok() {
+            self.checker = checker;
+        }
+    }
+
+    /// Complete the word ending at byte offset `pos` of `line`. Returns where
+    /// the word starts and the sorted candidates that replace it.
+    pub fn complete(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let before = &line[..pos];
+        if before.trim().is_empty() {
+            return (pos, vec![INDENT.to_string()]);
+        }
+        let (start, items) = complete_before(&self.names, &self.checker, before);
+        (start, items.into_iter().map(|item| item.label).collect())
+    }
+}
+
+/// Keywords and builtin functions
+fn base_names() -> BTreeMap<String, CompletionKind> {
+    let keywords = KEYWORDS
+        .iter()
+        .map(|keyword| (keyword.text.to_string(), CompletionKind::Keyword));
+    let builtins = builtin_names()
+        .into_iter()
+        .map(|name| (name, CompletionKind::Builtin));
+    keywords.chain(builtins).collect()
+}
+
+/// How a symbol is offered, if it names something code can use
+fn symbol_kind(symbol_type: &SymbolType) -> Option<CompletionKind> {
+    match symbol_type {
+        SymbolType::Variable => Some(CompletionKind::Variable),
+        SymbolType::Function => Some(CompletionKind::Function),
+        SymbolType::Class => Some(CompletionKind::Class),
+        SymbolType::Parameter => Some(CompletionKind::Parameter),
+        SymbolType::Import | SymbolType::ImportFrom => Some(CompletionKind::Module),
+        SymbolType::Global | SymbolType::Nonlocal => None,
+    }
+}
+
+/// Complete the word ending `before`, the text of a line up to the cursor.
+/// Returns the byte offset where the word starts and the candidates.
+fn complete_before(
+    names: &BTreeMap<String, CompletionKind>,
+    checker: &TypeChecker,
+    before: &str,
+) -> (usize, Vec<Completion>) {
+    let start = word_start(before);
+    let word = &before[start..];
+
+    if let Some(object) = before[..start].strip_suffix('.') {
+        let members = members(names, checker, object);
+        return (start, starting_with(members, word));
+    }
+    if word.is_empty() || word.starts_with(|c: char| c.is_ascii_digit()) {
+        return (start, Vec::new());
+    }
+    let candidates = names
+        .range(word.to_string()..)
+        .take_while(|(name, _)| name.starts_with(word))
+        .map(|(name, &kind)| (name.clone(), kind));
+    (start, starting_with(candidates, word))
+}
+
+/// Members of the dotted name ending `object`: a module's declarations, or
+/// the members of the value's type when the checker knows it
+fn members(
+    names: &BTreeMap<String, CompletionKind>,
+    checker: &TypeChecker,
+    object: &str,
+) -> Vec<(String, CompletionKind)> {
+    let start = object
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_' || *c == '.')
+        .last()
+        .map_or(object.len(), |(i, _)| i);
+    let text = &object[start..];
+    if text.is_empty() || text.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+        return Vec::new();
+    }
+
+    // A name the code defines shadows a module of that name
+    let shadowed = matches!(names.get(text), Some(kind) if *kind != CompletionKind::Module);
+    if let Some(stub) = checker.module_stub(text).filter(|_| !shadowed) {
+        return stub
+            .members
+            .iter()
+            .map(|(name, ty)| {
+                let kind = match ty {
+                    Type::Function { .. } => CompletionKind::Function,
+                    _ => CompletionKind::Field,
+                };
+                (name.clone(), kind)
+            })
+            .collect();
+    }
+
+    let Some(ty) = parse_expression(text).ok().and_then(|expr| checker.expression_type(&expr)) else {
+        return Vec::new();
+    };
+    match ty {
+        Type::Class { methods, fields, .. } => methods
+            .into_keys()
+            .map(|name| (name, CompletionKind::Method))
+            .chain(fields.into_keys().map(|name| (name, CompletionKind::Field)))
+            .collect(),
+        ty => ty
+            .builtin_methods()
+            .iter()
+            .map(|name| (name.to_string(), CompletionKind::Method))
+            .collect(),
+    }
+}
+
+/// Byte offset where the name ending `text` starts
+fn word_start(text: &str) -> usize {
+    text.char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_')
+        .last()
+        .map_or(text.len(), |(i, _)| i)
+}
+
+/// The candidates whose label starts with `prefix`, sorted by label
+fn starting_with(candidates: impl IntoIterator<Item = (String, CompletionKind)>, prefix: &str) -> Vec<Completion> {
+    let mut items: Vec<Completion> = candidates
+        .into_iter()
+        .filter(|(label, _)| label.starts_with(prefix))
+        .map(|(label, kind)| Completion { label, kind })
+        .collect();
+    items.sort_by(|a, b| a.label.cmp(&b.label));
+    items.dedup_by(|a, b| a.label == b.label);
+    items
+}
@@ -0,0 +1,292 @@
+// cst.rs - Lossless concrete syntax trees for round-trip tooling
+//
+// The AST drops whitespace, comments and the spelling of literals, so
+// tools that rewrite source (renames, import sorting, codemods) can't
+// print it back without reformatting the whole file. A concrete syntax
+// tree keeps every token of a lossless lex, with the text before it, in
+// source order, grouped into statements, indented blocks and bracketed
+// groups. Printing the tokens in order gives back the input byte for byte.
+
+use crate::lexer::{LexerConfig, Token, TokenType};
+use crate::parser::{self, ParseError};
+use std::fmt;
+
+/// What a node of the tree spans
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    /// The whole file, ending with the end-of-file token
+    Module,
+    /// One logical line that isn't a compound statement
+    SimpleStatement,
+    /// A compound statement with all its clauses and decorators
+    CompoundStatement,
+    /// An indented block, from its indent to its dedent
+    Block,
+    /// A bracketed group, from its opening to its closing bracket
+    Group,
+}
+
+/// A node of the tree or one of its tokens
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement<'a> {
+    Node(SyntaxNode<'a>),
+    Token(Token<'a>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxNode<'a> {
+    pub kind: SyntaxKind,
+    pub children: Vec<SyntaxElement<'a>>,
+}
+
+impl<'a> SyntaxNode<'a> {
+    fn new(kind: SyntaxKind) -> Self {
+        SyntaxNode {
+            kind,
+            children: Vec::new(),
+        }
+    }
+
+    /// The exact source text the node was parsed from
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for token in self.tokens() {
+            if let Some(source) = &token.source {
+                text.push_str(source.leading);
+                text.push_str(source.text);
+            }
+        }
+        text
+    }
+
+    /// The node's tokens, in source order
+    pub fn tokens(&self) -> Vec<&Token<'a>> {
+        let mut tokens = Vec::new();
+        for child in &self.children {
+            match child {
+                SyntaxElement::Token(token) => tokens.push(token),
+                SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+            }
+        }
+        tokens
+    }
+
+    /// The first token that stands for source text, skipping indents
+    pub fn first_token(&self) -> Option<&Token<'a>> {
+        self.tokens()
+            .into_iter()
+            .find(|token| !matches!(token.token_type, TokenType::Indent | TokenType::Dedent))
+    }
+
+    /// Line the node starts on
+    pub fn line(&self) -> usize {
+        self.first_token().map_or(0, |token| token.line)
+    }
+
+    /// Child nodes
+    pub fn nodes(&self) -> impl Iterator<Item = &SyntaxNode<'a>> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    /// Statements directly in this module or block, or in the blocks of
+    /// this compound statement
+    pub fn statements(&self) -> Vec<&SyntaxNode<'a>> {
+        match self.kind {
+            SyntaxKind::Module | SyntaxKind::Block => self
+                .nodes()
+                .filter(|node| {
+                    matches!(node.kind, SyntaxKind::SimpleStatement | SyntaxKind::CompoundStatement)
+                })
+                .collect(),
+            SyntaxKind::CompoundStatement => self
+                .nodes()
+                .filter(|node| node.kind == SyntaxKind::Block)
+                .flat_map(|block| block.statements())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for SyntaxNode<'_> {
+    /// An indented outline of the tree, one token per line with its text
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_outline(f, 0)
+    }
+}
+
+impl SyntaxNode<'_> {
+    fn write_outline(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        writeln!(f, "{}{:?}", "  ".repeat(depth), self.kind)?;
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => node.write_outline(f, depth + 1)?,
+                SyntaxElement::Token(token) => {
+                    let source = token.source.clone().unwrap_or_default();
+                    writeln!(
+                        f,
+                        "{}{:?} {:?} {:?}",
+                        "  ".repeat(depth + 1),
+                        token.token_type,
+                        source.leading,
+                        source.text
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse `source` into a concrete syntax tree whose text is `source`.
+/// Source the parser rejects is reported with the parser's errors.
+pub fn parse(source: &str) -> Result<SyntaxNode<'_>, Vec<ParseError>> {
+    let config = LexerConfig {
+        lossless: true,
+        ..LexerConfig::default()
+    };
+    let tokens = parser::tokenize_with_config(source, config)?;
+    parser::parse(tokens.clone())?;
+    Ok(build(tokens))
+}
+
+/// Group tokens from a lossless lex into a tree
+pub fn build(tokens: Vec<Token<'_>>) -> SyntaxNode<'_> {
+    let mut builder = Builder {
+        tokens: tokens.into_iter().peekable(),
+    };
+    let mut module = SyntaxNode::new(SyntaxKind::Module);
+    builder.statements(&mut module, false);
+    // Anything left over, such as a stray dedent, stays in the tree
+    module
+        .children
+        .extend(builder.tokens.map(SyntaxElement::Token));
+    module
+}
+
+struct Builder<'a> {
+    tokens: std::iter::Peekable<std::vec::IntoIter<Token<'a>>>,
+}
+
+impl<'a> Builder<'a> {
+    fn peek_type(&mut self) -> Option<&TokenType<'a>> {
+        self.tokens.peek().map(|token| &token.token_type)
+    }
+
+    fn take(&mut self, node: &mut SyntaxNode<'a>) {
+        if let Some(token) = self.tokens.next() {
+            node.children.push(SyntaxElement::Token(token));
+        }
+    }
+
+    /// Add statements to `parent` up to the end of the file, or the dedent
+    /// closing the block when `in_block` is set
+    fn statements(&mut self, parent: &mut SyntaxNode<'a>, in_block: bool) {
+        loop {
+            match self.peek_type() {
+                None => return,
+                Some(TokenType::EOF) => {
+                    if !in_block {
+                        self.take(parent);
+                    }
+                    return;
+                }
+                Some(TokenType::Dedent) if in_block => return,
+                // Blank lines and stray indentation belong to the enclosing node
+                Some(TokenType::Newline | TokenType::Dedent | TokenType::Indent) => self.take(parent),
+                Some(_) => {
+                    let statement = self.statement();
+                    parent.children.push(SyntaxElement::Node(statement));
+                }
+            }
+        }
+    }
+
+    /// One statement, with its decorators and every clause
+    fn statement(&mut self) -> SyntaxNode<'a> {
+        let mut statement = SyntaxNode::new(SyntaxKind::SimpleStatement);
+        loop {
+            let decorator = matches!(self.peek_type(), Some(TokenType::At));
+            if self.logical_line(&mut statement) {
+                statement.kind = SyntaxKind::CompoundStatement;
+                let mut block = SyntaxNode::new(SyntaxKind::Block);
+                self.take(&mut block);
+                self.statements(&mut block, true);
+                if matches!(self.peek_type(), Some(TokenType::Dedent)) {
+                    self.take(&mut block);
+                }
+                statement.children.push(SyntaxElement::Node(block));
+            }
+            if statement.first_token().is_some_and(|token| is_compound_keyword(&token.token_type)) {
+                statement.kind = SyntaxKind::CompoundStatement;
+            }
+
+            let continues = decorator
+                || matches!(
+                    self.peek_type(),
+                    Some(TokenType::Elif | TokenType::Else | TokenType::Except | TokenType::Finally)
+                ) && statement.kind == SyntaxKind::CompoundStatement;
+            if !continues || matches!(self.peek_type(), None | Some(TokenType::EOF)) {
+                return statement;
+            }
+        }
+    }
+
+    /// Add one logical line to `node`, through its newline. Returns whether
+    /// an indented block follows it.
+    fn logical_line(&mut self, node: &mut SyntaxNode<'a>) -> bool {
+        loop {
+            match self.peek_type() {
+                None | Some(TokenType::EOF | TokenType::Dedent | TokenType::Indent) => return false,
+                Some(TokenType::Newline) => {
+                    self.take(node);
+                    return matches!(self.peek_type(), Some(TokenType::Indent));
+                }
+                Some(TokenType::LeftParen | TokenType::LeftBracket | TokenType::LeftBrace) => {
+                    let group = self.group();
+                    node.children.push(SyntaxElement::Node(group));
+                }
+                Some(_) => self.take(node),
+            }
+        }
+    }
+
+    /// A bracketed group, with nested groups as child nodes
+    fn group(&mut self) -> SyntaxNode<'a> {
+        let mut group = SyntaxNode::new(SyntaxKind::Group);
+        self.take(&mut group);
+        loop {
+            match self.peek_type() {
+                None | Some(TokenType::EOF) => return group,
+                Some(TokenType::RightParen | TokenType::RightBracket | TokenType::RightBrace) => {
+                    self.take(&mut group);
+                    return group;
+                }
+                Some(TokenType::LeftParen | TokenType::LeftBracket | TokenType::LeftBrace) => {
+                    let nested = self.group();
+                    group.children.push(SyntaxElement::Node(nested));
+                }
+                Some(_) => self.take(&mut group),
+            }
+        }
+    }
+}
+
+/// Keywords that start a compound statement, for ones written on one line
+fn is_compound_keyword(token_type: &TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::If
+            | TokenType::While
+            | TokenType::For
+            | TokenType::Def
+            | TokenType::Class
+            | TokenType::Try
+            | TokenType::With
+            | TokenType::Async
+            | TokenType::At
+    )
+}
@@ -0,0 +1,157 @@
+// dap.rs - Debug Adapter Protocol bridge used by `cheetah debug`
+//
+// Editors talk DAP to `cheetah debug file.ch` over stdin/stdout. Breakpoints,
+// stepping and variable inspection are handled by a native DAP debugger
+// (lldb-dap or gdb) reading the DWARF info of a `-g` build; this module relays
+// messages between the two and builds the program when the editor launches it.
+
+use serde_json::{json, Value};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Environment variable naming the debugger command to run instead of the defaults
+pub const DEBUGGER_VAR: &str = "CHEETAH_DAP";
+
+/// Debuggers speaking DAP on stdin/stdout, tried in order
+const DEBUGGERS: [&[&str]; 3] = [&["lldb-dap"], &["lldb-vscode"], &["gdb", "-i", "dap"]];
+
+/// Read one `Content-Length` framed message, or `None` at end of input
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            if length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Content-Length") {
+                length = Some(value.trim().parse::<usize>().map_err(|e| {
+                    io::Error::new(io::ErrorKind::InvalidData, format!("bad Content-Length: {}", e))
+                })?);
+            }
+        }
+    }
+
+    let mut body = vec![0; length.unwrap_or(0)];
+    reader.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Write `message` with its `Content-Length` header
+pub fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+/// Failed response to `request`
+pub fn error_response(request: &Value, message: &str) -> Value {
+    json!({
+        "seq": 0,
+        "type": "response",
+        "request_seq": request["seq"],
+        "command": request["command"],
+        "success": false,
+        "message": message,
+    })
+}
+
+/// Whether `message` is the request starting the program
+pub fn is_launch_request(message: &Value) -> bool {
+    message["type"] == "request" && message["command"] == "launch"
+}
+
+/// Point a launch request at the built executable
+pub fn set_launch_program(request: &mut Value, program: &Path) {
+    if !request["arguments"].is_object() {
+        request["arguments"] = json!({});
+    }
+    request["arguments"]["program"] = json!(program.to_string_lossy());
+}
+
+/// The debugger command: `$CHEETAH_DAP` if set, otherwise the first of
+/// lldb-dap, lldb-vscode and `gdb -i dap` found on the PATH
+pub fn find_debugger() -> Option<Vec<String>> {
+    if let Ok(command) = std::env::var(DEBUGGER_VAR) {
+        let words: Vec<String> = command.split_whitespace().map(str::to_string).collect();
+        return (!words.is_empty()).then_some(words);
+    }
+
+    DEBUGGERS
+        .iter()
+        .find(|command| on_path(command[0]))
+        .map(|command| command.iter().map(|word| word.to_string()).collect())
+}
+
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+/// Relay DAP messages between stdin/stdout and `debugger` until the client
+/// disconnects. `build` is called on every launch request and returns the
+/// executable to debug; if it fails the launch is answered with its error.
+pub fn serve<F>(debugger: &[String], mut build: F) -> Result<(), String>
+where
+    F: FnMut() -> Result<PathBuf, String>,
+{
+    let (program, args) = debugger
+        .split_first()
+        .ok_or_else(|| "No debugger command given".to_string())?;
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", program, e))?;
+    let mut to_debugger = child.stdin.take().unwrap();
+    let mut from_debugger = BufReader::new(child.stdout.take().unwrap());
+
+    let client = Arc::new(Mutex::new(io::stdout()));
+    let relay_client = Arc::clone(&client);
+    let relay = thread::spawn(move || {
+        while let Ok(Some(message)) = read_message(&mut from_debugger) {
+            let mut out = relay_client.lock().unwrap_or_else(|p| p.into_inner());
+            if write_message(&mut *out, &message).is_err() {
+                break;
+            }
+        }
+    });
+
+    let stdin = io::stdin();
+    let mut from_client = stdin.lock();
+    while let Some(mut message) = read_message(&mut from_client).map_err(|e| e.to_string())? {
+        if is_launch_request(&message) {
+            match build() {
+                Ok(program) => set_launch_program(&mut message, &program),
+                Err(e) => {
+                    let response = error_response(&message, &e);
+                    let mut out = client.lock().unwrap_or_else(|p| p.into_inner());
+                    write_message(&mut *out, &response).map_err(|e| e.to_string())?;
+                    continue;
+                }
+            }
+        }
+        if write_message(&mut to_debugger, &message).is_err() {
+            break;
+        }
+    }
+
+    drop(to_debugger);
+    let _ = child.wait();
+    let _ = relay.join();
+    Ok(())
+}
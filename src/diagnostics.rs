@@ -0,0 +1,308 @@
+// diagnostics.rs - Machine-readable errors and warnings for CI systems and editors
+//
+// `--error-format json` prints one JSON object per diagnostic, and nothing
+// at all when there are none, and `--error-format sarif` a single SARIF 2.1.0
+// log, both on stdout since stderr also carries the CLI's notices. Every
+// diagnostic carries the stable code of its error or lint rule, so tools can
+// match on codes rather than messages.
+
+use crate::ast::Module;
+use crate::compiler::types::TypeError;
+use crate::error_codes;
+use crate::lexer::LexerError;
+use crate::linter::{self, Severity};
+use crate::parser::ParseError;
+use crate::typechecker::{TypeChecker, Warning};
+use serde_json::{json, Value};
+use std::fmt;
+
+/// How errors are reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorFormat {
+    /// Readable messages with source context
+    #[default]
+    Human,
+    /// One JSON object per line
+    Json,
+    /// A SARIF 2.1.0 log
+    Sarif,
+}
+
+impl ErrorFormat {
+    /// Parse an error format as given to `--error-format`
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "human" => Ok(ErrorFormat::Human),
+            "json" => Ok(ErrorFormat::Json),
+            "sarif" => Ok(ErrorFormat::Sarif),
+            other => Err(format!(
+                "unknown error format '{}' (expected human, json or sarif)",
+                other
+            )),
+        }
+    }
+}
+
+/// Whether a diagnostic stops the file from building
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+impl Level {
+    /// Name used in JSON and SARIF output
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+        }
+    }
+}
+
+/// Source range a diagnostic points at; lines and columns are 1-based and
+/// the end is exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl Span {
+    /// Empty span at a position
+    pub fn at(line: usize, column: usize) -> Self {
+        Span {
+            line,
+            column,
+            end_line: line,
+            end_column: column,
+        }
+    }
+}
+
+/// An error or warning from any stage of the compiler
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub code: &'static str,
+    pub level: Level,
+    pub message: String,
+    /// Where the problem is, if it's tied to a position in the source
+    pub span: Option<Span>,
+    pub suggestion: Option<String>,
+}
+
+impl Diagnostic {
+    /// Error not tied to a position in the source
+    pub fn error(code: &'static str, message: &str) -> Self {
+        Diagnostic {
+            code,
+            level: Level::Error,
+            message: message.to_string(),
+            span: None,
+            suggestion: None,
+        }
+    }
+
+    /// Type error found in the statement at `line` and `column`
+    pub fn type_error(error: &TypeError, line: usize, column: usize) -> Self {
+        Diagnostic {
+            code: error.code(),
+            level: Level::Error,
+            message: error.to_string(),
+            span: (line > 0).then(|| Span::at(line, column)),
+            suggestion: None,
+        }
+    }
+
+    /// Numbered id of the diagnostic's code, if it's an error `cheetah explain` knows
+    pub fn id(&self) -> Option<&'static str> {
+        error_codes::id_for(self.code)
+    }
+
+    /// The diagnostic as a JSON object, reported against `file`
+    pub fn to_json(&self, file: &str) -> Value {
+        json!({
+            "file": file,
+            "code": self.code,
+            "id": self.id(),
+            "severity": self.level.name(),
+            "message": self.message,
+            "span": self.span.map(|span| json!({
+                "line": span.line,
+                "column": span.column,
+                "end_line": span.end_line,
+                "end_column": span.end_column,
+            })),
+            "suggestion": self.suggestion,
+        })
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(span) = self.span {
+            write!(f, "Line {}, Col {}: ", span.line, span.column)?;
+        }
+        write!(
+            f,
+            "{}[{}]: {}",
+            self.level.name(),
+            self.id().unwrap_or(self.code),
+            self.message
+        )?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, " - Suggestion: {}", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&LexerError> for Diagnostic {
+    fn from(error: &LexerError) -> Self {
+        Diagnostic {
+            code: error.code(),
+            level: Level::Error,
+            message: error.message.clone(),
+            span: Some(Span::at(error.line, error.column)),
+            suggestion: error.suggestion.clone(),
+        }
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(error: &ParseError) -> Self {
+        Diagnostic {
+            code: error.code(),
+            level: Level::Error,
+            message: error.message(),
+            span: Some(Span::at(error.line(), error.column())),
+            suggestion: error.suggestion().map(str::to_string),
+        }
+    }
+}
+
+impl From<&Warning> for Diagnostic {
+    fn from(warning: &Warning) -> Self {
+        Diagnostic {
+            code: warning.kind.code(),
+            level: Level::Warning,
+            message: warning.message.clone(),
+            span: Some(Span::at(warning.line, warning.column)),
+            suggestion: None,
+        }
+    }
+}
+
+impl From<&linter::Diagnostic> for Diagnostic {
+    fn from(diagnostic: &linter::Diagnostic) -> Self {
+        let level = match diagnostic.severity {
+            Severity::Deny => Level::Error,
+            Severity::Allow | Severity::Warn => Level::Warning,
+        };
+        Diagnostic {
+            level,
+            ..Diagnostic::from(&diagnostic.warning)
+        }
+    }
+}
+
+/// The first type error in `module`, located at the statement it was found in
+pub fn type_check(module: &Module) -> Option<Diagnostic> {
+    let mut checker = TypeChecker::new();
+    let error = checker.check_module(module).err()?;
+    let (line, column) = checker.position();
+    Some(Diagnostic::type_error(&error, line, column))
+}
+
+/// `diagnostics` for `file` written out in `format`
+pub fn render(format: ErrorFormat, file: &str, diagnostics: &[Diagnostic]) -> String {
+    render_files(format, &[(file, diagnostics)])
+}
+
+/// The diagnostics of several files written out in `format`; SARIF puts them
+/// all in one log, while JSON is one line per diagnostic and so empty when
+/// there are none
+pub fn render_files(format: ErrorFormat, files: &[(&str, &[Diagnostic])]) -> String {
+    let each = files
+        .iter()
+        .flat_map(|(file, diagnostics)| diagnostics.iter().map(move |d| (*file, d)));
+    match format {
+        ErrorFormat::Human => each
+            .map(|(file, diagnostic)| format!("{}: {}\n", file, diagnostic))
+            .collect(),
+        ErrorFormat::Json => each
+            .map(|(file, diagnostic)| format!("{}\n", diagnostic.to_json(file)))
+            .collect(),
+        ErrorFormat::Sarif => format!("{}\n", sarif_log(files)),
+    }
+}
+
+/// `diagnostics` for `file` as a SARIF 2.1.0 log with a single run
+pub fn to_sarif(file: &str, diagnostics: &[Diagnostic]) -> Value {
+    sarif_log(&[(file, diagnostics)])
+}
+
+/// The diagnostics of several files as a SARIF 2.1.0 log with a single run
+pub fn sarif_log(files: &[(&str, &[Diagnostic])]) -> Value {
+    let mut rules: Vec<&str> = files
+        .iter()
+        .flat_map(|(_, diagnostics)| diagnostics.iter().map(|d| d.code))
+        .collect();
+    rules.sort_unstable();
+    rules.dedup();
+
+    let results: Vec<Value> = files
+        .iter()
+        .flat_map(|(file, diagnostics)| diagnostics.iter().map(move |d| (*file, d)))
+        .map(|(file, diagnostic)| {
+            let mut location = json!({ "artifactLocation": { "uri": file } });
+            if let Some(span) = diagnostic.span {
+                location["region"] = json!({
+                    "startLine": span.line,
+                    "startColumn": span.column.max(1),
+                    "endLine": span.end_line,
+                    "endColumn": span.end_column.max(1),
+                });
+            }
+            let mut message = diagnostic.message.clone();
+            if let Some(suggestion) = &diagnostic.suggestion {
+                message.push_str(&format!(" (suggestion: {})", suggestion));
+            }
+            json!({
+                "ruleId": diagnostic.code,
+                "ruleIndex": rules.iter().position(|rule| *rule == diagnostic.code),
+                "level": diagnostic.level.name(),
+                "message": { "text": message },
+                "locations": [{ "physicalLocation": location }],
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "cheetah",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules.iter().map(|rule| sarif_rule(rule)).collect::<Vec<_>>(),
+                }
+            },
+            "results": results,
+        }],
+    })
+}
+
+/// SARIF description of the rule `code`, with its explanation as help text
+fn sarif_rule(code: &str) -> Value {
+    let mut rule = json!({ "id": code });
+    if let Some(error) = error_codes::lookup(code) {
+        rule["name"] = json!(error.id);
+        rule["shortDescription"] = json!({ "text": error.summary() });
+        rule["help"] = json!({ "text": error.explanation, "markdown": error.explanation });
+    }
+    rule
+}
@@ -0,0 +1,159 @@
+// diff.rs - Line-based unified diffs, as printed by `cheetah format --diff`
+//
+// The edit script comes from a longest common subsequence of the lines
+// after trimming the prefix and suffix both texts share, which keeps the
+// table small for the usual case of a few changed lines in a large file.
+
+/// Lines of unchanged context around each change
+pub const CONTEXT_LINES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    /// Line `old` of the old text is line `new` of the new one
+    Keep(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Unified diff from `old` to `new`, labelled with the given file names;
+/// empty if the texts are the same
+pub fn unified(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    if old == new {
+        return String::new();
+    }
+    // Lines keep their newline, so a missing one at the end shows as a change
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let edits = edit_script(&old_lines, &new_lines);
+
+    let mut out = format!("--- {}\n+++ {}\n", old_name, new_name);
+    for hunk in hunks(&edits) {
+        write_hunk(&mut out, &edits[hunk.0..hunk.1], &old_lines, &new_lines);
+    }
+    out
+}
+
+/// Shortest list of edits turning `old` into `new`
+fn edit_script(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    // lengths[i][j]: longest common subsequence of old_mid[i..] and new_mid[j..]
+    let width = new_mid.len() + 1;
+    let mut lengths = vec![0usize; (old_mid.len() + 1) * width];
+    for i in (0..old_mid.len()).rev() {
+        for j in (0..new_mid.len()).rev() {
+            lengths[i * width + j] = if old_mid[i] == new_mid[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut edits: Vec<Edit> = (0..prefix).map(|i| Edit::Keep(i, i)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < old_mid.len() || j < new_mid.len() {
+        if i < old_mid.len() && j < new_mid.len() && old_mid[i] == new_mid[j] {
+            edits.push(Edit::Keep(prefix + i, prefix + j));
+            i += 1;
+            j += 1;
+        } else if j < new_mid.len()
+            && (i == old_mid.len() || lengths[i * width + j + 1] > lengths[(i + 1) * width + j])
+        {
+            edits.push(Edit::Insert(prefix + j));
+            j += 1;
+        } else {
+            edits.push(Edit::Delete(prefix + i));
+            i += 1;
+        }
+    }
+    let (old_end, new_end) = (prefix + old_mid.len(), prefix + new_mid.len());
+    edits.extend((0..suffix).map(|k| Edit::Keep(old_end + k, new_end + k)));
+    edits
+}
+
+/// Ranges of `edits` to print as hunks: each change with its context,
+/// merging changes whose context would overlap
+fn hunks(edits: &[Edit]) -> Vec<(usize, usize)> {
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (index, edit) in edits.iter().enumerate() {
+        if matches!(edit, Edit::Keep(..)) {
+            continue;
+        }
+        let start = index.saturating_sub(CONTEXT_LINES);
+        let end = (index + 1 + CONTEXT_LINES).min(edits.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    hunks
+}
+
+fn write_hunk(out: &mut String, edits: &[Edit], old: &[&str], new: &[&str]) {
+    // Position of the first line of the hunk in each text, 0-based
+    let (old_start, new_start) = match edits[0] {
+        Edit::Keep(i, j) => (i, j),
+        Edit::Delete(i) => (i, new_position(edits)),
+        Edit::Insert(j) => (old_position(edits), j),
+    };
+    let old_count = edits.iter().filter(|e| !matches!(e, Edit::Insert(_))).count();
+    let new_count = edits.iter().filter(|e| !matches!(e, Edit::Delete(_))).count();
+
+    out.push_str(&format!(
+        "@@ -{} +{} @@\n",
+        range(old_start, old_count),
+        range(new_start, new_count)
+    ));
+    for edit in edits {
+        let (marker, line) = match *edit {
+            Edit::Keep(i, _) => (' ', old[i]),
+            Edit::Delete(i) => ('-', old[i]),
+            Edit::Insert(j) => ('+', new[j]),
+        };
+        out.push(marker);
+        out.push_str(line);
+        if !line.ends_with('\n') {
+            out.push_str("\n\\ No newline at end of file\n");
+        }
+    }
+}
+
+/// Line in the old text where a hunk starting with insertions begins
+fn old_position(edits: &[Edit]) -> usize {
+    edits
+        .iter()
+        .find_map(|edit| match edit {
+            Edit::Keep(i, _) | Edit::Delete(i) => Some(*i),
+            Edit::Insert(_) => None,
+        })
+        .unwrap_or(0)
+}
+
+/// Line in the new text where a hunk starting with deletions begins
+fn new_position(edits: &[Edit]) -> usize {
+    edits
+        .iter()
+        .find_map(|edit| match edit {
+            Edit::Keep(_, j) | Edit::Insert(j) => Some(*j),
+            Edit::Delete(_) => None,
+        })
+        .unwrap_or(0)
+}
+
+/// Hunk range in unified diff notation; an empty range names the line before it
+fn range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, count),
+    }
+}
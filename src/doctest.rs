@@ -0,0 +1,216 @@
+// doctest.rs - Running the `>>>` examples in docstrings
+//
+// The docstrings of a module, its classes, functions and methods can show
+// REPL sessions; `cheetah doctest` runs them and checks the output:
+//
+//     def square(x: int) -> int:
+//         """
+//         >>> square(4)
+//         16
+//         >>> for i in range(2):
+//         ...     print(square(i))
+//         0
+//         1
+//         """
+//         return x * x
+//
+// Each docstring runs in a fresh JIT REPL session that has first run the
+// module itself, so its examples see the module's definitions and the
+// variables of the examples before them. The output an example prints,
+// including the echoed value of a final expression, must match the lines
+// after it up to the next prompt or blank line. `<BLANKLINE>` stands for an
+// empty line of output.
+
+use crate::ast::{Expr, Module, Stmt};
+
+/// Prompt starting an example
+pub const PROMPT: &str = ">>>";
+/// Prompt continuing an example over more lines
+pub const CONTINUATION: &str = "...";
+/// Marks an empty line in expected output
+pub const BLANKLINE: &str = "<BLANKLINE>";
+
+/// One `>>>` example
+#[derive(Debug, Clone, PartialEq)]
+pub struct Example {
+    /// Source of the example, its lines joined without prompts
+    pub source: String,
+    /// Expected output, each line ending in a newline
+    pub want: String,
+    /// Line of the `>>>` prompt in the file
+    pub line: usize,
+}
+
+/// The examples of one docstring
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocTest {
+    /// Dotted name of what the docstring documents, such as `Stack.push`
+    pub name: String,
+    /// Line of the docstring in the file
+    pub line: usize,
+    pub examples: Vec<Example>,
+}
+
+/// The examples in the docstrings of `module` and its definitions, in
+/// source order; docstrings without examples are left out
+pub fn extract(module: &Module, module_name: &str) -> Vec<DocTest> {
+    let mut tests = Vec::new();
+    collect(&module.body, module_name, &mut tests);
+    tests
+}
+
+fn collect(body: &[Box<Stmt>], name: &str, tests: &mut Vec<DocTest>) {
+    if let Some((doc, line)) = docstring(body) {
+        let examples = parse_examples(doc, line);
+        if !examples.is_empty() {
+            tests.push(DocTest {
+                name: name.to_string(),
+                line,
+                examples,
+            });
+        }
+    }
+    for stmt in body {
+        match stmt.as_ref() {
+            Stmt::FunctionDef { name: def, body, .. } | Stmt::ClassDef { name: def, body, .. } => {
+                collect(body, &format!("{}.{}", name, def), tests);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The docstring of a body and the line it starts on
+fn docstring(body: &[Box<Stmt>]) -> Option<(&str, usize)> {
+    match body.first().map(|stmt| stmt.as_ref()) {
+        Some(Stmt::Expr { value, .. }) => match value.as_ref() {
+            // Strings are positioned at their closing quotes
+            Expr::Str { value, line, .. } => Some((value, line.saturating_sub(value.matches('\n').count()))),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Examples in the text of a docstring whose first line is line `first_line` of the file
+pub fn parse_examples(doc: &str, first_line: usize) -> Vec<Example> {
+    let lines: Vec<&str> = doc.lines().collect();
+    let mut examples = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        let Some((indent, code)) = prompt_line(lines[index], PROMPT) else {
+            index += 1;
+            continue;
+        };
+        let line = first_line + index;
+        let mut source = vec![code];
+        index += 1;
+        while let Some(code) = lines.get(index).and_then(|text| continuation(text, indent)) {
+            source.push(code);
+            index += 1;
+        }
+
+        let mut want = String::new();
+        while let Some(text) = lines.get(index) {
+            if text.trim().is_empty() || prompt_line(text, PROMPT).is_some() {
+                break;
+            }
+            let text = dedent(text, indent);
+            if text.trim_end() != BLANKLINE {
+                want.push_str(text.trim_end());
+            }
+            want.push('\n');
+            index += 1;
+        }
+
+        let mut source = source.join("\n");
+        source.push('\n');
+        examples.push(Example { source, want, line });
+    }
+    examples
+}
+
+/// The indentation and code of a line starting with `prompt`
+fn prompt_line<'a>(text: &'a str, prompt: &str) -> Option<(usize, &'a str)> {
+    let indent = text.len() - text.trim_start().len();
+    let rest = text[indent..].strip_prefix(prompt)?;
+    match rest.strip_prefix(' ') {
+        Some(code) => Some((indent, code)),
+        None if rest.is_empty() => Some((indent, rest)),
+        None => None,
+    }
+}
+
+/// The code of a `...` line continuing an example whose prompt is indented by `indent`
+fn continuation(text: &str, indent: usize) -> Option<&str> {
+    prompt_line(text, CONTINUATION)
+        .filter(|(at, _)| *at == indent)
+        .map(|(_, code)| code)
+}
+
+/// `text` without up to `indent` leading spaces
+fn dedent(text: &str, indent: usize) -> &str {
+    let spaces = text.len() - text.trim_start_matches(' ').len();
+    &text[spaces.min(indent)..]
+}
+
+/// Whether `got` is the output `want` expects, ignoring trailing whitespace on each line
+pub fn output_matches(want: &str, got: &str) -> bool {
+    let lines = |text: &str| -> Vec<String> { text.lines().map(|line| line.trim_end().to_string()).collect() };
+    lines(want) == lines(got)
+}
+
+/// An example whose output didn't match
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    pub example: Example,
+    /// What the example printed, or why it couldn't run
+    pub got: String,
+}
+
+/// Outcome of the examples of one docstring
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocTestResult {
+    pub passed: usize,
+    pub failures: Vec<Failure>,
+}
+
+/// Run the examples of `test` in a fresh JIT session that has run `module` first
+#[cfg(feature = "codegen")]
+pub fn run(module: &Module, test: &DocTest, opt_level: u8) -> Result<DocTestResult, String> {
+    use crate::compiler::repl::ReplSession;
+    use crate::compiler::runtime::buffer;
+
+    let context = inkwell::context::Context::create();
+    let mut session = ReplSession::new(&context, opt_level);
+    buffer::start_capture();
+    let setup = session.run_cell(module);
+    buffer::finish_capture();
+    setup.map_err(|e| format!("the module failed to run: {}", e))?;
+
+    let mut result = DocTestResult::default();
+    for example in &test.examples {
+        let cell = crate::parse(&example.source).map_err(|errors| {
+            errors
+                .iter()
+                .map(|error| error.get_message())
+                .collect::<Vec<_>>()
+                .join("\n")
+        });
+        buffer::start_capture();
+        let ran = cell.and_then(|cell| session.run_cell(&cell));
+        let output = buffer::finish_capture();
+        match ran {
+            Ok(()) if output_matches(&example.want, &output) => result.passed += 1,
+            Ok(()) => result.failures.push(Failure {
+                example: example.clone(),
+                got: output,
+            }),
+            Err(e) => result.failures.push(Failure {
+                example: example.clone(),
+                got: format!("{}{}\n", output, e),
+            }),
+        }
+    }
+    Ok(result)
+}
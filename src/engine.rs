@@ -0,0 +1,411 @@
+// engine.rs - Embedding Cheetah in Rust programs
+//
+// An `Engine` compiles a Cheetah program from a source string, JIT-compiles it
+// into the host process and runs its top-level code. The host can then call the
+// program's top-level functions by name, exchanging ints, floats, bools and
+// strings:
+//
+//     let context = inkwell::context::Context::create();
+//     let mut engine = Engine::new(&context);
+//     engine.load("def add(a: int, b: int) -> int:\n    return a + b\n")?;
+//     assert_eq!(engine.call("add", &[2.into(), 3.into()])?, Value::Int(5));
+//
+// Every callable function gets a generated entry point taking its arguments and
+// result as arrays of 64-bit slots, so the host can call any signature through
+// one Rust function type.
+
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::os::raw::c_char;
+
+use inkwell::context::Context;
+use inkwell::execution_engine::ExecutionEngine;
+use inkwell::types::{BasicMetadataTypeEnum, BasicTypeEnum};
+use inkwell::values::BasicMetadataValueEnum;
+use inkwell::{AddressSpace, IntPredicate};
+
+use crate::ast::{Expr, Stmt};
+use crate::compiler::optimizer::codegen_level;
+use crate::compiler::runtime::{abort, buffer, jit, parallel_ops, range};
+use crate::compiler::Compiler;
+use crate::ir::specialize::unboxed_name;
+
+/// A value passed between the host and a Cheetah program
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    None,
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::None => write!(f, "None"),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", crate::compiler::runtime::number_format::format_float(*value)),
+            Value::Bool(value) => write!(f, "{}", if *value { "True" } else { "False" }),
+            Value::Str(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Str(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Str(value)
+    }
+}
+
+/// How a parameter or result crosses the host boundary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Slot {
+    Int,
+    Float,
+    Bool,
+    Str,
+    Void,
+}
+
+impl Slot {
+    /// Slot for an LLVM type; pointers are only exchanged as strings
+    fn of(context: &Context, ty: BasicTypeEnum<'_>, is_str: bool) -> Option<Self> {
+        match ty {
+            BasicTypeEnum::IntType(int) => match int.get_bit_width() {
+                1 => Some(Slot::Bool),
+                64 => Some(Slot::Int),
+                _ => None,
+            },
+            BasicTypeEnum::FloatType(float) if float == context.f64_type() => Some(Slot::Float),
+            BasicTypeEnum::PointerType(_) if is_str => Some(Slot::Str),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Slot::Int => "int",
+            Slot::Float => "float",
+            Slot::Bool => "bool",
+            Slot::Str => "str",
+            Slot::Void => "None",
+        }
+    }
+}
+
+/// Entry point generated for a callable function
+#[derive(Debug, Clone)]
+struct EntryPoint {
+    symbol: String,
+    params: Vec<Slot>,
+    result: Slot,
+}
+
+/// Host-side type of every generated entry point: argument slots, result slot
+type EntryFn = unsafe extern "C" fn(*const u64, *mut u64);
+
+/// A loaded program; the execution engine owns the compiled module
+struct Program<'ctx> {
+    execution_engine: ExecutionEngine<'ctx>,
+    /// Top-level functions, or None for those whose signature can't cross the host boundary
+    functions: HashMap<String, Option<EntryPoint>>,
+    _compiler: Compiler<'ctx>,
+}
+
+/// Compiles, JIT-runs and calls into Cheetah programs from Rust
+pub struct Engine<'ctx> {
+    context: &'ctx Context,
+    opt_level: u8,
+    program: Option<Program<'ctx>>,
+}
+
+impl<'ctx> Engine<'ctx> {
+    /// Create an engine that compiles programs in `context`
+    pub fn new(context: &'ctx Context) -> Self {
+        Self {
+            context,
+            opt_level: 0,
+            program: None,
+        }
+    }
+
+    /// Set the optimization level (0-3) programs are compiled at
+    pub fn set_opt_level(&mut self, level: u8) {
+        self.opt_level = level.min(3);
+    }
+
+    /// Compile `source`, run its top-level code and make its top-level
+    /// functions callable, replacing any previously loaded program
+    pub fn load(&mut self, source: &str) -> Result<(), String> {
+        let module = crate::parse(source).map_err(|errors| {
+            errors
+                .iter()
+                .map(|e| e.get_message())
+                .collect::<Vec<String>>()
+                .join("\n")
+        })?;
+
+        let mut compiler = Compiler::new(self.context, "engine");
+        compiler.set_opt_level(self.opt_level);
+        compiler.compile_module(&module)?;
+
+        let mut functions = HashMap::new();
+        for stmt in &module.body {
+            if let Stmt::FunctionDef { name, params, returns, .. } = stmt.as_ref() {
+                if compiler.top_level_functions.contains(name) {
+                    let str_params: Vec<bool> = params.iter().map(|p| is_str_annotation(p.typ.as_deref())).collect();
+                    let entry = add_entry_point(&compiler, name, &str_params, is_str_annotation(returns.as_deref()));
+                    functions.insert(name.clone(), entry);
+                }
+            }
+        }
+        compiler.optimize_module()?;
+
+        buffer::init();
+        range::init();
+        parallel_ops::init();
+
+        let execution_engine = compiler
+            .get_module()
+            .create_jit_execution_engine(codegen_level(compiler.opt_level))
+            .map_err(|e| format!("Failed to create execution engine: {}", e))?;
+        jit::register_runtime_functions(&execution_engine, compiler.get_module())?;
+
+        let result = unsafe {
+            let main = execution_engine
+                .get_function::<unsafe extern "C" fn()>("main")
+                .map_err(|e| format!("Failed to find main function: {}", e))?;
+            abort::catch_abort(|| main.call())
+        };
+        buffer::flush();
+        result.map_err(|error| error.to_string())?;
+
+        self.program = Some(Program {
+            execution_engine,
+            functions,
+            _compiler: compiler,
+        });
+        Ok(())
+    }
+
+    /// Names of the loaded program's top-level functions
+    pub fn functions(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .program
+            .iter()
+            .flat_map(|program| program.functions.keys().map(String::as_str))
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Call a top-level function of the loaded program
+    pub fn call(&self, name: &str, args: &[Value]) -> Result<Value, String> {
+        let program = self.program.as_ref().ok_or("No program is loaded")?;
+        let entry = program
+            .functions
+            .get(name)
+            .ok_or_else(|| format!("No function named '{}'", name))?
+            .as_ref()
+            .ok_or_else(|| {
+                format!(
+                    "'{}' takes or returns values that can't be passed to the host (only int, float, bool and str can)",
+                    name
+                )
+            })?;
+        if args.len() != entry.params.len() {
+            return Err(format!(
+                "{}() takes {} arguments but {} were given",
+                name,
+                entry.params.len(),
+                args.len()
+            ));
+        }
+
+        // Strings passed in stay alive until the call returns
+        let mut strings = Vec::new();
+        let mut slots = Vec::with_capacity(args.len());
+        for (index, (arg, slot)) in args.iter().zip(&entry.params).enumerate() {
+            let bits = match (slot, arg) {
+                (Slot::Int, Value::Int(value)) => *value as u64,
+                (Slot::Int, Value::Bool(value)) => *value as u64,
+                (Slot::Float, Value::Float(value)) => value.to_bits(),
+                (Slot::Float, Value::Int(value)) => (*value as f64).to_bits(),
+                (Slot::Bool, Value::Bool(value)) => *value as u64,
+                (Slot::Str, Value::Str(value)) => {
+                    let string = CString::new(value.as_str())
+                        .map_err(|_| format!("argument {} of {}() contains a NUL byte", index + 1, name))?;
+                    let pointer = string.as_ptr() as u64;
+                    strings.push(string);
+                    pointer
+                }
+                (slot, arg) => {
+                    return Err(format!(
+                        "argument {} of {}() must be {}, not {:?}",
+                        index + 1,
+                        name,
+                        slot.name(),
+                        arg
+                    ))
+                }
+            };
+            slots.push(bits);
+        }
+
+        let mut result = 0u64;
+        let outcome = unsafe {
+            let function = program
+                .execution_engine
+                .get_function::<EntryFn>(&entry.symbol)
+                .map_err(|e| format!("Failed to find entry point of {}: {}", name, e))?;
+            abort::catch_abort(|| function.call(slots.as_ptr(), &mut result))
+        };
+        buffer::flush();
+        drop(strings);
+        outcome.map_err(|error| error.to_string())?;
+
+        Ok(match entry.result {
+            Slot::Int => Value::Int(result as i64),
+            Slot::Float => Value::Float(f64::from_bits(result)),
+            Slot::Bool => Value::Bool(result != 0),
+            Slot::Str if result == 0 => Value::None,
+            Slot::Str => Value::Str(
+                unsafe { CStr::from_ptr(result as *const c_char) }
+                    .to_string_lossy()
+                    .into_owned(),
+            ),
+            Slot::Void => Value::None,
+        })
+    }
+}
+
+/// Symbol of the entry point generated for `name`
+fn entry_symbol(name: &str) -> String {
+    format!("__cheetah_entry_{}", name)
+}
+
+/// Whether a parameter or return annotation is `str`
+fn is_str_annotation(annotation: Option<&Expr>) -> bool {
+    matches!(annotation, Some(Expr::Name { id, .. }) if id == "str")
+}
+
+/// Generate `void entry(u64 *args, u64 *result)` calling `name`, or return
+/// None if its signature can't cross the host boundary. Pointers cross it only
+/// where the source annotates a `str`.
+fn add_entry_point(compiler: &Compiler<'_>, name: &str, str_params: &[bool], returns_str: bool) -> Option<EntryPoint> {
+    let ctx = &compiler.context;
+    let context = ctx.llvm_context;
+    // A function compiled through the IR is called through its unboxed copy,
+    // which takes and returns ints, floats and bools as they are
+    let function = ctx
+        .module
+        .get_function(&unboxed_name(name))
+        .or_else(|| ctx.module.get_function(name))?;
+    let fn_type = function.get_type();
+    if fn_type.count_param_types() as usize != str_params.len() {
+        return None;
+    }
+
+    let params: Option<Vec<(Slot, BasicTypeEnum<'_>)>> = fn_type
+        .get_param_types()
+        .into_iter()
+        .zip(str_params)
+        .map(|(ty, is_str)| {
+            let ty: BasicTypeEnum<'_> = match ty {
+                BasicMetadataTypeEnum::IntType(ty) => ty.into(),
+                BasicMetadataTypeEnum::FloatType(ty) => ty.into(),
+                BasicMetadataTypeEnum::PointerType(ty) => ty.into(),
+                _ => return None,
+            };
+            Slot::of(context, ty, *is_str).map(|slot| (slot, ty))
+        })
+        .collect();
+    let result = match fn_type.get_return_type() {
+        Some(ty) => Slot::of(context, ty, returns_str),
+        None => Some(Slot::Void),
+    };
+    let (params, result) = (params?, result?);
+
+    let symbol = entry_symbol(name);
+    let i64_type = context.i64_type();
+    let ptr_type = context.ptr_type(AddressSpace::default());
+    let entry = ctx.module.add_function(
+        &symbol,
+        context.void_type().fn_type(&[ptr_type.into(), ptr_type.into()], false),
+        None,
+    );
+    let saved_block = ctx.builder.get_insert_block();
+    ctx.builder.position_at_end(context.append_basic_block(entry, "entry"));
+
+    let args_ptr = entry.get_nth_param(0).unwrap().into_pointer_value();
+    let result_ptr = entry.get_nth_param(1).unwrap().into_pointer_value();
+    let mut call_args: Vec<BasicMetadataValueEnum<'_>> = Vec::with_capacity(params.len());
+    for (index, (slot, ty)) in params.iter().enumerate() {
+        let slot_ptr = unsafe {
+            ctx.builder
+                .build_in_bounds_gep(i64_type, args_ptr, &[i64_type.const_int(index as u64, false)], "arg_slot")
+                .unwrap()
+        };
+        let value = match slot {
+            Slot::Bool => {
+                let bits = ctx.builder.build_load(i64_type, slot_ptr, "arg_bits").unwrap();
+                ctx.builder
+                    .build_int_compare(IntPredicate::NE, bits.into_int_value(), i64_type.const_zero(), "arg")
+                    .unwrap()
+                    .into()
+            }
+            _ => ctx.builder.build_load(*ty, slot_ptr, "arg").unwrap(),
+        };
+        call_args.push(value.into());
+    }
+
+    let call = ctx.builder.build_call(function, &call_args, "result").unwrap();
+    if let Some(value) = call.try_as_basic_value().left() {
+        let value = if result == Slot::Bool {
+            ctx.builder
+                .build_int_z_extend(value.into_int_value(), i64_type, "result_bits")
+                .unwrap()
+                .into()
+        } else {
+            value
+        };
+        ctx.builder.build_store(result_ptr, value).unwrap();
+    }
+    ctx.builder.build_return(None).unwrap();
+
+    if let Some(block) = saved_block {
+        ctx.builder.position_at_end(block);
+    }
+
+    Some(EntryPoint {
+        symbol,
+        params: params.into_iter().map(|(slot, _)| slot).collect(),
+        result,
+    })
+}
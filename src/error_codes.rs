@@ -0,0 +1,78 @@
+// error_codes.rs - Stable error codes and the explanations behind `cheetah explain`
+//
+// Every lexer, parser and type error has a descriptive name, returned by the
+// error's `code()`, and a numbered id such as `CH0006`. Ids are never reused
+// or renumbered, so they can be searched for and linked to from docs. The
+// explanation of each code lives in `error_codes/<id>.md`.
+
+/// A kind of error with its id and explanation
+#[derive(Debug)]
+pub struct ErrorCode {
+    /// Numbered id, `CH` followed by four digits
+    pub id: &'static str,
+    /// Name returned by the error's `code()`
+    pub name: &'static str,
+    /// Markdown description with an erroneous example and a fixed one
+    pub explanation: &'static str,
+}
+
+impl ErrorCode {
+    /// First sentence of the explanation
+    pub fn summary(&self) -> &'static str {
+        self.explanation.lines().next().unwrap_or("")
+    }
+}
+
+macro_rules! error_codes {
+    ($($id:literal => $name:literal,)*) => {
+        &[$(ErrorCode {
+            id: $id,
+            name: $name,
+            explanation: include_str!(concat!("error_codes/", $id, ".md")),
+        },)*]
+    };
+}
+
+/// Every error code, in id order
+pub static ERROR_CODES: &[ErrorCode] = error_codes! {
+    "CH0001" => "inconsistent-indentation",
+    "CH0002" => "unterminated-literal",
+    "CH0003" => "invalid-literal",
+    "CH0004" => "invalid-escape",
+    "CH0005" => "unexpected-character",
+    "CH0006" => "unexpected-token",
+    "CH0007" => "invalid-syntax",
+    "CH0008" => "unexpected-eof",
+    "CH0009" => "undefined-variable",
+    "CH0010" => "incompatible-types",
+    "CH0011" => "invalid-operator",
+    "CH0012" => "invalid-argument",
+    "CH0013" => "wrong-argument-count",
+    "CH0014" => "not-a-class",
+    "CH0015" => "undefined-member",
+    "CH0016" => "cannot-infer-type",
+    "CH0017" => "not-callable",
+    "CH0018" => "not-indexable",
+    "CH0019" => "invalid-extern",
+    "CH0020" => "unexpected-keyword",
+    "CH0021" => "forbidden-construct",
+    "CH0022" => "inconsistent-mro",
+    "CH0023" => "invalid-super",
+    "CH0024" => "abstract-instantiation",
+    "CH0025" => "unbound-local",
+};
+
+/// The error code with id `code` (in any case) or named `code`
+pub fn lookup(code: &str) -> Option<&'static ErrorCode> {
+    ERROR_CODES
+        .iter()
+        .find(|error| error.id.eq_ignore_ascii_case(code) || error.name == code)
+}
+
+/// Id of the error code named `name`
+pub fn id_for(name: &str) -> Option<&'static str> {
+    ERROR_CODES
+        .iter()
+        .find(|error| error.name == name)
+        .map(|error| error.id)
+}
@@ -4,18 +4,42 @@ use crate::visitor::Visitor;
 pub struct CodeFormatter {
     indent_level: usize,
     indent_size: usize,
+    /// Width past which call arguments, boolean expressions and collection
+    /// literals are split across lines; `None` keeps everything on one line
+    max_width: Option<usize>,
+    /// Characters known to follow the expression being written on its line
+    trailing: usize,
     output: String,
 }
 
+/// One element of a bracketed list that may be split across lines
+enum Element<'a> {
+    Expr(&'a Expr),
+    /// `*value` or `**value`
+    Unpacked(&'static str, &'a Expr),
+    /// `name=value` in a call
+    Keyword(&'a str, &'a Expr),
+    /// `key: value` in a dict
+    Pair(&'a Expr, &'a Expr),
+}
+
 impl CodeFormatter {
     pub fn new(indent_size: usize) -> Self {
         CodeFormatter {
             indent_level: 0,
             indent_size,
+            max_width: None,
+            trailing: 0,
             output: String::new(),
         }
     }
 
+    /// Split long lines black-style so they stay within `width` columns where possible
+    pub fn with_max_width(mut self, width: usize) -> Self {
+        self.max_width = Some(width);
+        self
+    }
+
     pub fn get_output(&self) -> &str {
         &self.output
     }
@@ -48,6 +72,123 @@ impl CodeFormatter {
         }
     }
 
+    /// Column the next write starts at
+    fn column(&self) -> usize {
+        let line = self.output.rsplit('\n').next().unwrap_or("");
+        line.chars().count()
+    }
+
+    /// What `write` produces with everything kept on one line
+    fn flat(&self, write: impl FnOnce(&mut CodeFormatter)) -> String {
+        let mut formatter = CodeFormatter::new(self.indent_size);
+        formatter.indent_level = self.indent_level;
+        write(&mut formatter);
+        formatter.output
+    }
+
+    /// Write `expr` and then `text`, keeping room for `text` on the line
+    fn write_expr_followed_by(&mut self, expr: &Expr, text: &str) {
+        let trailing = std::mem::replace(&mut self.trailing, text.chars().count());
+        self.visit_expr(expr);
+        self.trailing = trailing;
+        self.write(text);
+    }
+
+    fn write_element(&mut self, element: &Element) {
+        match element {
+            Element::Expr(value) => self.visit_expr(value),
+            Element::Unpacked(stars, value) => {
+                self.write(stars);
+                self.visit_expr(value);
+            }
+            Element::Keyword(name, value) => {
+                self.write(name);
+                self.write("=");
+                self.visit_expr(value);
+            }
+            Element::Pair(key, value) => {
+                self.visit_expr(key);
+                self.write(": ");
+                self.visit_expr(value);
+            }
+        }
+    }
+
+    /// Write `elements` joined by commas, or by the boolean operator `op`;
+    /// `single_comma` keeps the comma after a lone element, as in `(x,)`
+    fn write_joined(&mut self, elements: &[Element], op: Option<&str>, single_comma: bool) {
+        for (i, element) in elements.iter().enumerate() {
+            if i > 0 {
+                match op {
+                    Some(op) => {
+                        self.write(" ");
+                        self.write(op);
+                        self.write(" ");
+                    }
+                    None => self.write(", "),
+                }
+            }
+            self.write_element(element);
+        }
+        if single_comma && elements.len() == 1 {
+            self.write(",");
+        }
+    }
+
+    /// Write `elements` between `open` and `close`. If they would run past the
+    /// max width they're moved to an indented line of their own, or failing
+    /// that put one per line: comma-joined ones with a trailing comma and
+    /// operator-joined ones with the operator leading each continuation line.
+    fn write_elements(
+        &mut self,
+        open: &str,
+        elements: &[Element],
+        op: Option<&str>,
+        close: &str,
+        single_comma: bool,
+    ) {
+        let flat = self.flat(|formatter| formatter.write_joined(elements, op, single_comma));
+        let flat_width = flat.chars().count();
+        let column = self.column() + open.len() + close.len() + self.trailing;
+        let width = match self.max_width {
+            Some(width) if !elements.is_empty() && column + flat_width > width => width,
+            _ => {
+                self.write(open);
+                self.write(&flat);
+                self.write(close);
+                return;
+            }
+        };
+
+        self.write(open);
+        self.write("\n");
+        self.increase_indent();
+        let trailing = std::mem::replace(&mut self.trailing, 0);
+        if self.indent().len() + flat_width <= width {
+            self.write_line(&flat);
+        } else {
+            for (i, element) in elements.iter().enumerate() {
+                self.write_indented("");
+                match op {
+                    Some(op) if i > 0 => {
+                        self.write(op);
+                        self.write(" ");
+                    }
+                    Some(_) => {}
+                    None => self.trailing = 1,
+                }
+                self.write_element(element);
+                if op.is_none() {
+                    self.write(",");
+                }
+                self.write("\n");
+            }
+        }
+        self.trailing = trailing;
+        self.decrease_indent();
+        self.write_indented(close);
+    }
+
     fn format_operator(&self, op: &Operator) -> &'static str {
         match op {
             Operator::Add => "+",
@@ -82,6 +223,127 @@ impl CodeFormatter {
         }
     }
 
+    /// Write `params` as a parameter list, with `sep` between a name and its default
+    fn write_params(&mut self, params: &[crate::ast::Parameter], sep: &str) {
+        for (i, param) in params.iter().enumerate() {
+            if i > 0 {
+                self.write(", ");
+            }
+
+            if param.is_vararg {
+                self.write("*");
+            } else if param.is_kwarg {
+                self.write("**");
+            }
+            self.write(&param.name);
+
+            if let Some(typ) = &param.typ {
+                self.write(": ");
+                self.visit_expr(typ);
+            }
+
+            if let Some(default) = &param.default {
+                self.write(sep);
+                self.visit_expr(default);
+            }
+        }
+    }
+
+    /// Write an assignment or loop target, leaving a tuple of several names
+    /// unparenthesized as it's parsed differently in parentheses
+    fn write_target(&mut self, target: &Expr) {
+        match target {
+            Expr::Tuple { elts, .. } if elts.len() > 1 => {
+                for (i, elt) in elts.iter().enumerate() {
+                    if i > 0 {
+                        self.write(", ");
+                    }
+                    self.visit_expr(elt);
+                }
+            }
+            _ => self.visit_expr(target),
+        }
+    }
+
+    /// Write an `if` statement, or the `elif` branch of one
+    fn write_if(&mut self, keyword: &str, test: &Expr, body: &[Box<Stmt>], orelse: &[Box<Stmt>]) {
+        self.write(keyword);
+        self.write(" ");
+        self.write_expr_followed_by(test, ":");
+        self.write("\n");
+
+        self.increase_indent();
+
+        if body.is_empty() {
+            self.write_line("pass");
+        } else {
+            for stmt in body {
+                self.visit_stmt(stmt);
+            }
+        }
+
+        self.decrease_indent();
+
+        if let [elif] = orelse {
+            if let Stmt::If {
+                test, body, orelse, ..
+            } = elif.as_ref()
+            {
+                self.write_indented("");
+                self.write_if("elif", test, body, orelse);
+                return;
+            }
+        }
+
+        if !orelse.is_empty() {
+            self.write_line("else:");
+            self.increase_indent();
+
+            for stmt in orelse {
+                self.visit_stmt(stmt);
+            }
+
+            self.decrease_indent();
+        }
+    }
+
+    /// Write the literal text and replacement fields of an f-string, without its quotes
+    fn write_fstring_parts(&mut self, values: &[Box<Expr>]) {
+        for value in values {
+            match value.as_ref() {
+                Expr::Str { value, .. } => {
+                    let escaped = escape_string(value).replace('{', "{{").replace('}', "}}");
+                    self.write(&escaped);
+                }
+                Expr::FormattedValue {
+                    value,
+                    conversion,
+                    format_spec,
+                    ..
+                } => {
+                    self.write("{");
+                    self.visit_expr(value);
+
+                    if *conversion != '\0' {
+                        self.write(&format!("!{}", conversion));
+                    }
+
+                    if let Some(spec) = format_spec {
+                        self.write(":");
+                        match spec.as_ref() {
+                            Expr::Str { value, .. } => self.write(&escape_string(value)),
+                            Expr::JoinedStr { values, .. } => self.write_fstring_parts(values),
+                            other => self.visit_expr(other),
+                        }
+                    }
+
+                    self.write("}");
+                }
+                other => self.visit_expr(other),
+            }
+        }
+    }
+
     fn format_cmp_operator(&self, op: &CmpOperator) -> &'static str {
         match op {
             CmpOperator::Eq => "==",
@@ -134,7 +396,8 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 returns,
                 line: _line,
                 column: _column,
-                is_async: _is_async,
+                is_async,
+                span: _,
             } => {
                 for decorator in decorator_list {
                     self.write_indented("@");
@@ -142,28 +405,10 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                     self.write("\n");
                 }
 
-                self.write_indented("def ");
+                self.write_indented(if *is_async { "async def " } else { "def " });
                 self.write(name);
                 self.write("(");
-
-                for (i, param) in params.iter().enumerate() {
-                    if i > 0 {
-                        self.write(", ");
-                    }
-
-                    self.write(&param.name);
-
-                    if let Some(typ) = &param.typ {
-                        self.write(": ");
-                        self.visit_expr(&**typ);
-                    }
-
-                    if let Some(default) = &param.default {
-                        self.write(" = ");
-                        self.visit_expr(&**default);
-                    }
-                }
-
+                self.write_params(params, " = ");
                 self.write(")");
 
                 if let Some(ret) = returns {
@@ -193,6 +438,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 decorator_list,
                 line: _line,
                 column: _column,
+                span: _,
             } => {
                 for decorator in decorator_list {
                     self.write_indented("@");
@@ -253,6 +499,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 value,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write_indented("return");
 
@@ -267,6 +514,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 targets,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write_indented("del ");
 
@@ -284,6 +532,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 value,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write_indented("");
 
@@ -291,7 +540,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                     if i > 0 {
                         self.write(" = ");
                     }
-                    self.visit_expr(&**target);
+                    self.write_target(target);
                 }
 
                 self.write(" = ");
@@ -304,6 +553,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 value,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write_indented("");
                 self.visit_expr(&**target);
@@ -319,6 +569,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 value,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write_indented("");
                 self.visit_expr(&**target);
@@ -339,13 +590,14 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 orelse,
                 line: _,
                 column: _,
-                is_async: _is_async,
+                is_async,
+                span: _,
             } => {
-                self.write_indented("for ");
-                self.visit_expr(&**target);
+                self.write_indented(if *is_async { "async for " } else { "for " });
+                self.write_target(target);
                 self.write(" in ");
-                self.visit_expr(&**iter);
-                self.write(":\n");
+                self.write_expr_followed_by(iter, ":");
+                self.write("\n");
 
                 self.increase_indent();
 
@@ -376,10 +628,11 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 orelse,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write_indented("while ");
-                self.visit_expr(&**test);
-                self.write(":\n");
+                self.write_expr_followed_by(test, ":");
+                self.write("\n");
 
                 self.increase_indent();
 
@@ -410,50 +663,20 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 orelse,
                 line: _,
                 column: _,
+                span: _,
             } => {
-                self.write_indented("if ");
-                self.visit_expr(&**test);
-                self.write(":\n");
-
-                self.increase_indent();
-
-                if body.is_empty() {
-                    self.write_line("pass");
-                } else {
-                    for stmt in body {
-                        self.visit_stmt(&**stmt);
-                    }
-                }
-
-                self.decrease_indent();
-
-                if orelse.len() == 1 {
-                    if let Stmt::If { .. } = orelse[0].as_ref() {
-                        self.write_indented("el");
-                        self.visit_stmt(&*orelse[0]);
-                        return;
-                    }
-                }
-
-                if !orelse.is_empty() {
-                    self.write_line("else:");
-                    self.increase_indent();
-
-                    for stmt in orelse {
-                        self.visit_stmt(&**stmt);
-                    }
-
-                    self.decrease_indent();
-                }
+                self.write_indented("");
+                self.write_if("if", test, body, orelse);
             }
             Stmt::With {
                 items,
                 body,
                 line: _,
                 column: _,
-                is_async: _,
+                is_async,
+                span: _,
             } => {
-                self.write_indented("with ");
+                self.write_indented(if *is_async { "async with " } else { "with " });
 
                 for (i, (item, target)) in items.iter().enumerate() {
                     if i > 0 {
@@ -487,6 +710,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 cause,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write_indented("raise");
 
@@ -509,6 +733,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 finalbody,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write_line("try:");
 
@@ -579,6 +804,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 msg,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write_indented("assert ");
                 self.visit_expr(&**test);
@@ -594,6 +820,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 names,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write_indented("import ");
 
@@ -618,6 +845,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 level,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write_indented("from ");
 
@@ -654,6 +882,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 names,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write_indented("global ");
 
@@ -671,6 +900,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 names,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write_indented("nonlocal ");
 
@@ -688,18 +918,19 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 value,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write_indented("");
                 self.visit_expr(&**value);
                 self.write("\n");
             }
-            Stmt::Pass { line: _, column: _ } => {
+            Stmt::Pass { line: _, column: _, span: _ } => {
                 self.write_line("pass");
             }
-            Stmt::Break { line: _, column: _ } => {
+            Stmt::Break { line: _, column: _, span: _ } => {
                 self.write_line("break");
             }
-            Stmt::Continue { line: _, column: _ } => {
+            Stmt::Continue { line: _, column: _, span: _ } => {
                 self.write_line("continue");
             }
             Stmt::Match {
@@ -707,6 +938,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 cases,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write_indented("match ");
                 self.visit_expr(&**subject);
@@ -750,22 +982,12 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 values,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 let op_str = self.format_bool_operator(op);
-
-                self.write("(");
-
-                for (i, value) in values.iter().enumerate() {
-                    if i > 0 {
-                        self.write(" ");
-                        self.write(op_str);
-                        self.write(" ");
-                    }
-
-                    self.visit_expr(&**value);
-                }
-
-                self.write(")");
+                let elements: Vec<Element> =
+                    values.iter().map(|value| Element::Expr(value)).collect();
+                self.write_elements("(", &elements, Some(op_str), ")", false);
             }
             Expr::BinOp {
                 left,
@@ -773,6 +995,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 right,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write("(");
                 self.visit_expr(&**left);
@@ -787,6 +1010,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 operand,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write("(");
                 self.write(self.format_unary_operator(op));
@@ -798,22 +1022,10 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 body,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write("lambda ");
-
-                for (i, param) in args.iter().enumerate() {
-                    if i > 0 {
-                        self.write(", ");
-                    }
-
-                    self.write(&param.name);
-
-                    if let Some(default) = &param.default {
-                        self.write("=");
-                        self.visit_expr(&**default);
-                    }
-                }
-
+                self.write_params(args, "=");
                 self.write(": ");
                 self.visit_expr(&**body);
             }
@@ -823,6 +1035,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 orelse,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write("(");
                 self.visit_expr(&**body);
@@ -837,45 +1050,29 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 values,
                 line: _,
                 column: _,
+                span: _,
             } => {
-                self.write("{");
-
-                for (i, (key, value)) in keys.iter().zip(values.iter()).enumerate() {
-                    if i > 0 {
-                        self.write(", ");
-                    }
-
-                    if let Some(key) = key {
-                        self.visit_expr(&**key);
-                        self.write(": ");
-                        self.visit_expr(&**value);
-                    } else {
-                        self.write("**");
-                        self.visit_expr(&**value);
-                    }
-                }
-
-                self.write("}");
+                let elements: Vec<Element> = keys
+                    .iter()
+                    .zip(values.iter())
+                    .map(|(key, value)| match key {
+                        Some(key) => Element::Pair(key, value),
+                        None => Element::Unpacked("**", value),
+                    })
+                    .collect();
+                self.write_elements("{", &elements, None, "}", false);
             }
             Expr::Set {
                 elts,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 if elts.is_empty() {
                     self.write("set()");
                 } else {
-                    self.write("{");
-
-                    for (i, elt) in elts.iter().enumerate() {
-                        if i > 0 {
-                            self.write(", ");
-                        }
-
-                        self.visit_expr(&**elt);
-                    }
-
-                    self.write("}");
+                    let elements: Vec<Element> = elts.iter().map(|elt| Element::Expr(elt)).collect();
+                    self.write_elements("{", &elements, None, "}", false);
                 }
             }
             Expr::ListComp {
@@ -883,6 +1080,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 generators,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write("[");
                 self.visit_expr(&**elt);
@@ -906,6 +1104,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 generators,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write("{");
                 self.visit_expr(&**elt);
@@ -930,6 +1129,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 generators,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write("{");
                 self.visit_expr(&**key);
@@ -955,6 +1155,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 generators,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write("(");
                 self.visit_expr(&**elt);
@@ -977,6 +1178,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 value,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write("await ");
                 self.visit_expr(&**value);
@@ -985,6 +1187,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 value,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write("yield");
 
@@ -997,6 +1200,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 value,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write("yield from ");
                 self.visit_expr(&**value);
@@ -1007,6 +1211,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 comparators,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.visit_expr(&**left);
 
@@ -1023,51 +1228,29 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 keywords,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.visit_expr(&**func);
-                self.write("(");
-
-                for (i, arg) in args.iter().enumerate() {
-                    if i > 0 {
-                        self.write(", ");
-                    }
-
-                    if let Expr::Starred { value, .. } = &**arg {
-                        self.write("*");
-                        self.visit_expr(&**value);
-                    } else {
-                        self.visit_expr(&**arg);
-                    }
-                }
-
-                if !args.is_empty() && !keywords.is_empty() {
-                    self.write(", ");
-                }
-
-                for (i, (key, value)) in keywords.iter().enumerate() {
-                    if i > 0 {
-                        self.write(", ");
-                    }
-
-                    if let Some(key) = key {
-                        self.write(key);
-                        self.write("=");
-                        self.visit_expr(&**value);
-                    } else {
-                        self.write("**");
-                        self.visit_expr(&**value);
-                    }
-                }
 
-                self.write(")");
+                let arguments = args.iter().map(|arg| match &**arg {
+                    Expr::Starred { value, .. } => Element::Unpacked("*", value),
+                    arg => Element::Expr(arg),
+                });
+                let keywords = keywords.iter().map(|(key, value)| match key {
+                    Some(key) => Element::Keyword(key, value),
+                    None => Element::Unpacked("**", value),
+                });
+                let elements: Vec<Element> = arguments.chain(keywords).collect();
+                self.write_elements("(", &elements, None, ")", false);
             }
             Expr::Num {
                 value,
                 line: _,
                 column: _,
+                span: _,
             } => match value {
                 crate::ast::Number::Integer(i) => self.write(&i.to_string()),
-                crate::ast::Number::Float(f) => self.write(&f.to_string()),
+                crate::ast::Number::Float(f) => self.write(&format!("{:?}", f)),
                 crate::ast::Number::Complex { real, imag } => {
                     self.write(&format!("{}+{}j", real, imag));
                 }
@@ -1076,47 +1259,30 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 value,
                 line: _,
                 column: _,
+                span: _,
             } => {
-                self.write(&format!("\"{}\"", value));
+                self.write(&format!("\"{}\"", escape_string(value)));
             }
-            Expr::FormattedValue {
-                value,
-                conversion,
-                format_spec,
-                line: _,
-                column: _,
-            } => {
-                self.write("{");
-                self.visit_expr(&**value);
-
-                if *conversion != '\0' {
-                    self.write(&format!("!{}", conversion));
-                }
-
-                if let Some(spec) = format_spec {
-                    self.write(":");
-                    self.visit_expr(&**spec);
-                }
-
-                self.write("}");
+            Expr::FormattedValue { .. } => {
+                self.write("f\"");
+                self.write_fstring_parts(std::slice::from_ref(&Box::new(expr.clone())));
+                self.write("\"");
             }
             Expr::JoinedStr {
                 values,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write("f\"");
-
-                for value in values {
-                    self.visit_expr(&**value);
-                }
-
+                self.write_fstring_parts(values);
                 self.write("\"");
             }
             Expr::Bytes {
                 value,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write("b\"");
                 for byte in value {
@@ -1132,27 +1298,29 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 value,
                 line: _,
                 column: _,
+                span: _,
             } => match value {
                 crate::ast::NameConstant::None => self.write("None"),
                 crate::ast::NameConstant::True => self.write("True"),
                 crate::ast::NameConstant::False => self.write("False"),
             },
-            Expr::Ellipsis { line: _, column: _ } => {
+            Expr::Ellipsis { line: _, column: _, span: _ } => {
                 self.write("...");
             }
             Expr::Constant {
                 value,
                 line: _,
                 column: _,
+                span: _,
             } => match value {
                 crate::ast::Constant::Num(num) => match num {
                     crate::ast::Number::Integer(i) => self.write(&i.to_string()),
-                    crate::ast::Number::Float(f) => self.write(&f.to_string()),
+                    crate::ast::Number::Float(f) => self.write(&format!("{:?}", f)),
                     crate::ast::Number::Complex { real, imag } => {
                         self.write(&format!("{}+{}j", real, imag));
                     }
                 },
-                crate::ast::Constant::Str(s) => self.write(&format!("\"{}\"", s)),
+                crate::ast::Constant::Str(s) => self.write(&format!("\"{}\"", escape_string(s))),
                 crate::ast::Constant::Bytes(bytes) => {
                     self.write("b\"");
                     for byte in bytes {
@@ -1177,6 +1345,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 ctx: _,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.visit_expr(&**value);
                 self.write(".");
@@ -1188,6 +1357,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 ctx: _,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.visit_expr(&**value);
                 self.write("[");
@@ -1199,6 +1369,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 ctx: _,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write("*");
                 self.visit_expr(&**value);
@@ -1208,6 +1379,7 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 ctx: _,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write(id);
             }
@@ -1216,49 +1388,27 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 ctx: _,
                 line: _,
                 column: _,
+                span: _,
             } => {
-                self.write("[");
-
-                for (i, elt) in elts.iter().enumerate() {
-                    if i > 0 {
-                        self.write(", ");
-                    }
-
-                    self.visit_expr(&**elt);
-                }
-
-                self.write("]");
+                let elements: Vec<Element> = elts.iter().map(|elt| Element::Expr(elt)).collect();
+                self.write_elements("[", &elements, None, "]", false);
             }
             Expr::Tuple {
                 elts,
                 ctx: _,
                 line: _,
                 column: _,
+                span: _,
             } => {
-                if elts.is_empty() {
-                    self.write("()");
-                } else if elts.len() == 1 {
-                    self.visit_expr(&*elts[0]);
-                    self.write(",");
-                } else {
-                    self.write("(");
-
-                    for (i, elt) in elts.iter().enumerate() {
-                        if i > 0 {
-                            self.write(", ");
-                        }
-
-                        self.visit_expr(&**elt);
-                    }
-
-                    self.write(")");
-                }
+                let elements: Vec<Element> = elts.iter().map(|elt| Element::Expr(elt)).collect();
+                self.write_elements("(", &elements, None, ")", true);
             }
             Expr::NamedExpr {
                 target,
                 value,
                 line: _,
                 column: _,
+                span: _,
             } => {
                 self.write("(");
                 self.visit_expr(target);
@@ -1272,8 +1422,8 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                 step,
                 line: _,
                 column: _,
+                span: _,
             } => {
-                self.write("[");
                 if let Some(lower_expr) = lower {
                     self.visit_expr(lower_expr);
                 }
@@ -1285,7 +1435,6 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
                     self.write(":");
                     self.visit_expr(step_expr);
                 }
-                self.write("]");
             }
         }
     }
@@ -1298,3 +1447,21 @@ impl<'ast> Visitor<'ast, ()> for CodeFormatter {
 
     fn visit_parameter(&mut self, _param: &'ast crate::ast::Parameter) -> () {}
 }
+
+/// `value` with backslashes, double quotes and control characters escaped, for
+/// writing between double quotes
+fn escape_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\x{:02x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
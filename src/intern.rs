@@ -0,0 +1,117 @@
+// intern.rs - Interning names as cheap symbol ids
+//
+// Every distinct name is stored once and stands for a `Symbol`, a u32 that
+// is copied, hashed and compared in one step. Maps keyed by names that are
+// looked up over and over, like the compiler's scopes, key by symbols
+// instead of Strings:
+//
+//     let count = Symbol::intern("count");
+//     assert_eq!(count, Symbol::intern("count"));
+//     assert_eq!(count.as_str(), "count");
+//
+// The interner is shared by the whole process and never forgets a name, so
+// a name's text lives as long as the program; there are only as many names
+// as distinct identifiers in the code compiled.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+
+/// An interned name
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// The symbol for `name`, interning it the first time it is seen
+    pub fn intern(name: &str) -> Symbol {
+        interner().lock().unwrap().intern(name)
+    }
+
+    /// The symbol for `name` if it has been interned, without interning it
+    pub fn lookup(name: &str) -> Option<Symbol> {
+        interner().lock().unwrap().ids.get(name).copied()
+    }
+
+    /// The name the symbol stands for
+    pub fn as_str(self) -> &'static str {
+        interner().lock().unwrap().names[self.0 as usize]
+    }
+
+    /// The symbol's id, dense from 0 in the order names were interned
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Symbol({:?})", self.as_str())
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Symbol::intern(name)
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(name: &String) -> Self {
+        Symbol::intern(name)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(name: String) -> Self {
+        Symbol::intern(&name)
+    }
+}
+
+impl From<&Symbol> for Symbol {
+    fn from(symbol: &Symbol) -> Self {
+        *symbol
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+/// Names and the symbols standing for them
+#[derive(Default)]
+struct Interner {
+    ids: HashMap<&'static str, Symbol>,
+    names: Vec<&'static str>,
+}
+
+impl Interner {
+    fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(&symbol) = self.ids.get(name) {
+            return symbol;
+        }
+        let symbol = Symbol(self.names.len() as u32);
+        let name: &'static str = Box::leak(name.into());
+        self.names.push(name);
+        self.ids.insert(name, symbol);
+        symbol
+    }
+}
+
+fn interner() -> &'static Mutex<Interner> {
+    static INTERNER: OnceLock<Mutex<Interner>> = OnceLock::new();
+    INTERNER.get_or_init(Default::default)
+}
@@ -0,0 +1,1082 @@
+// builtins.rs - Built-in functions, methods and exception classes of the tree-walking interpreter
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use crate::ast::Operator;
+use crate::trace::TRACE_FUNCTION;
+
+use super::format::format_value;
+use super::ops::{binary_op, compare_values, exception, iterate, normalize_index, OpResult};
+use super::value::{range_len, repr_str, Dict, Exception, Value};
+use super::{Exec, Interpreter};
+
+/// Built-in functions, by name
+const FUNCTIONS: &[&str] = &[
+    "abs", "all", "any", "bin", "bool", "chr", "dict", "divmod", "enumerate", "filter", "float", "format", "hex",
+    "input", "int", "isinstance", "len", "list", "map", "max", "min", "oct", "ord", "pow", "print", "range",
+    "repr", "reversed", "round", "set", "sorted", "str", "sum", "tuple", "zip", TRACE_FUNCTION,
+];
+
+/// Built-in exception classes and their base classes
+const EXCEPTIONS: &[(&str, &str)] = &[
+    ("BaseException", ""),
+    ("Exception", "BaseException"),
+    ("KeyboardInterrupt", "BaseException"),
+    ("ArithmeticError", "Exception"),
+    ("OverflowError", "ArithmeticError"),
+    ("ZeroDivisionError", "ArithmeticError"),
+    ("AssertionError", "Exception"),
+    ("AttributeError", "Exception"),
+    ("EOFError", "Exception"),
+    ("LookupError", "Exception"),
+    ("IndexError", "LookupError"),
+    ("KeyError", "LookupError"),
+    ("NameError", "Exception"),
+    ("OSError", "Exception"),
+    ("FileNotFoundError", "OSError"),
+    ("RuntimeError", "Exception"),
+    ("NotImplementedError", "RuntimeError"),
+    ("RecursionError", "RuntimeError"),
+    ("StopIteration", "Exception"),
+    ("TypeError", "Exception"),
+    ("ValueError", "Exception"),
+    ("UnicodeError", "ValueError"),
+    ("UnicodeDecodeError", "UnicodeError"),
+    ("UnicodeEncodeError", "UnicodeError"),
+];
+
+/// The built-in named `name`, if there is one
+pub fn lookup(name: &str) -> Option<Value> {
+    if let Some(function) = FUNCTIONS.iter().find(|function| **function == name) {
+        return Some(Value::Builtin(function));
+    }
+    EXCEPTIONS
+        .iter()
+        .find(|(exception, _)| *exception == name)
+        .map(|(exception, _)| Value::ExceptionType(Rc::from(*exception)))
+}
+
+/// Whether exception class `typ` is `base` or derives from it
+pub fn is_subclass(typ: &str, base: &str) -> bool {
+    let mut current = typ;
+    loop {
+        if current == base {
+            return true;
+        }
+        match EXCEPTIONS.iter().find(|(exception, _)| *exception == current) {
+            Some((_, parent)) if !parent.is_empty() => current = parent,
+            // Unknown exception types (e.g. raised by the runtime) derive from Exception
+            None => return base == "Exception" || base == "BaseException",
+            Some(_) => return false,
+        }
+    }
+}
+
+/// Arguments of a call to a built-in function or method
+struct Args {
+    name: String,
+    positional: Vec<Value>,
+    keywords: Vec<(String, Value)>,
+}
+
+impl Args {
+    fn new(name: impl Into<String>, positional: Vec<Value>, keywords: Vec<(String, Value)>) -> Self {
+        Args {
+            name: name.into(),
+            positional,
+            keywords,
+        }
+    }
+
+    /// Check the number of positional arguments
+    fn expect(&self, min: usize, max: usize) -> OpResult<()> {
+        let given = self.positional.len();
+        if (min..=max).contains(&given) {
+            return Ok(());
+        }
+        let expected = if min == max {
+            format!("exactly {} argument{}", min, if min == 1 { "" } else { "s" })
+        } else if given < min {
+            format!("at least {} argument{}", min, if min == 1 { "" } else { "s" })
+        } else {
+            format!("at most {} argument{}", max, if max == 1 { "" } else { "s" })
+        };
+        Err(exception(
+            "TypeError",
+            format!("{}() takes {} ({} given)", self.name, expected, given),
+        ))
+    }
+
+    /// Remove and return keyword argument `name`
+    fn keyword(&mut self, name: &str) -> Option<Value> {
+        let position = self.keywords.iter().position(|(keyword, _)| keyword == name)?;
+        Some(self.keywords.remove(position).1)
+    }
+
+    /// Reject keyword arguments the function doesn't take
+    fn no_more_keywords(&self) -> OpResult<()> {
+        match self.keywords.first() {
+            Some((keyword, _)) => Err(exception(
+                "TypeError",
+                format!("{}() got an unexpected keyword argument '{}'", self.name, keyword),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn get(&self, i: usize) -> Option<&Value> {
+        self.positional.get(i)
+    }
+
+    fn int(&self, i: usize) -> OpResult<i64> {
+        let value = &self.positional[i];
+        value.as_int().ok_or_else(|| {
+            exception(
+                "TypeError",
+                format!("'{}' object cannot be interpreted as an integer", value.type_name()),
+            )
+        })
+    }
+
+    fn str(&self, i: usize) -> OpResult<Rc<str>> {
+        match &self.positional[i] {
+            Value::Str(s) => Ok(s.clone()),
+            other => Err(exception(
+                "TypeError",
+                format!("{}() argument must be str, not {}", self.name, other.type_name()),
+            )),
+        }
+    }
+}
+
+/// Call the built-in function `name`
+pub(super) fn call_builtin(
+    interp: &mut Interpreter,
+    name: &str,
+    positional: Vec<Value>,
+    keywords: Vec<(String, Value)>,
+) -> Exec<Value> {
+    let mut args = Args::new(name, positional, keywords);
+    match name {
+        "print" => {
+            let sep = args.keyword("sep").map_or(" ".to_string(), |v| v.to_string());
+            let end = args.keyword("end").map_or("\n".to_string(), |v| v.to_string());
+            args.no_more_keywords()?;
+            let text: Vec<String> = args.positional.iter().map(Value::to_string).collect();
+            interp.write_output(&format!("{}{}", text.join(&sep), end))?;
+            return Ok(Value::None);
+        }
+        "min" | "max" => return min_max(interp, args),
+        TRACE_FUNCTION => {
+            args.no_more_keywords()?;
+            interp.trace_step(&args.positional)?;
+            return Ok(Value::None);
+        }
+        "sorted" => {
+            args.expect(1, 1)?;
+            let key = args.keyword("key");
+            let reverse = args.keyword("reverse").is_some_and(|v| v.is_truthy());
+            args.no_more_keywords()?;
+            let items = iterate(&args.positional[0])?.collect();
+            return Ok(Value::list(sort(interp, items, key.as_ref(), reverse)?));
+        }
+        "map" => {
+            args.expect(2, usize::MAX)?;
+            args.no_more_keywords()?;
+            let function = args.positional[0].clone();
+            let columns = zip_iterables(&args.positional[1..])?;
+            let mut results = Vec::with_capacity(columns.len());
+            for row in columns {
+                results.push(interp.call_value(&function, row, Vec::new())?);
+            }
+            return Ok(Value::list(results));
+        }
+        "filter" => {
+            args.expect(2, 2)?;
+            args.no_more_keywords()?;
+            let mut kept = Vec::new();
+            for item in iterate(&args.positional[1])? {
+                let keep = match &args.positional[0] {
+                    Value::None => item.is_truthy(),
+                    function => interp.call_value(function, vec![item.clone()], Vec::new())?.is_truthy(),
+                };
+                if keep {
+                    kept.push(item);
+                }
+            }
+            return Ok(Value::list(kept));
+        }
+        "input" => {
+            args.expect(0, 1)?;
+            args.no_more_keywords()?;
+            if let Some(prompt) = args.get(0) {
+                interp.write_output(&prompt.to_string())?;
+            }
+            return match interp.read_line()? {
+                Some(line) => Ok(Value::str(line.trim_end_matches(['\n', '\r']))),
+                None => Err(exception("EOFError", "EOF when reading a line").into()),
+            };
+        }
+        _ => {}
+    }
+
+    match name {
+        "sum" => {
+            args.expect(1, 2)?;
+            let start = args.keyword("start");
+            args.no_more_keywords()?;
+            let mut total = start.or_else(|| args.get(1).cloned()).unwrap_or(Value::Int(0));
+            for item in iterate(&args.positional[0])? {
+                total = binary_op(&Operator::Add, &total, &item)?;
+            }
+            Ok(total)
+        }
+        "enumerate" => {
+            args.expect(1, 2)?;
+            let start = args.keyword("start").or_else(|| args.get(1).cloned()).unwrap_or(Value::Int(0));
+            args.no_more_keywords()?;
+            let start = start.as_int().ok_or_else(|| exception("TypeError", "enumerate() start must be an int"))?;
+            let pairs = iterate(&args.positional[0])?
+                .enumerate()
+                .map(|(i, item)| Value::tuple(vec![Value::Int(start + i as i64), item]))
+                .collect();
+            Ok(Value::list(pairs))
+        }
+        _ => {
+            // dict() takes its entries as keyword arguments
+            if name != "dict" {
+                args.no_more_keywords()?;
+            }
+            Ok(call_simple_builtin(name, &args)?)
+        }
+    }
+}
+
+/// Built-in functions that take only positional arguments and call no user code
+fn call_simple_builtin(name: &str, args: &Args) -> OpResult<Value> {
+    match name {
+        "len" => {
+            args.expect(1, 1)?;
+            let len = match &args.positional[0] {
+                Value::Str(s) => s.chars().count(),
+                Value::List(items) => items.borrow().len(),
+                Value::Tuple(items) => items.len(),
+                Value::Dict(dict) | Value::Set(dict) => dict.borrow().len(),
+                Value::Range { start, stop, step } => range_len(*start, *stop, *step) as usize,
+                other => {
+                    return Err(exception(
+                        "TypeError",
+                        format!("object of type '{}' has no len()", other.type_name()),
+                    ))
+                }
+            };
+            Ok(Value::Int(len as i64))
+        }
+        "range" => {
+            args.expect(1, 3)?;
+            let (start, stop, step) = match args.positional.len() {
+                1 => (0, args.int(0)?, 1),
+                2 => (args.int(0)?, args.int(1)?, 1),
+                _ => (args.int(0)?, args.int(1)?, args.int(2)?),
+            };
+            if step == 0 {
+                return Err(exception("ValueError", "range() arg 3 must not be zero"));
+            }
+            Ok(Value::Range { start, stop, step })
+        }
+        "str" => {
+            args.expect(0, 1)?;
+            Ok(Value::str(&args.get(0).map_or(String::new(), Value::to_string)))
+        }
+        "repr" => {
+            args.expect(1, 1)?;
+            Ok(Value::str(&args.positional[0].repr()))
+        }
+        "format" => {
+            args.expect(1, 2)?;
+            let spec = if args.positional.len() == 2 { args.str(1)? } else { Rc::from("") };
+            Ok(Value::str(&format_value(&args.positional[0], &spec)?))
+        }
+        "int" => {
+            args.expect(0, 2)?;
+            match args.positional.len() {
+                0 => Ok(Value::Int(0)),
+                1 => to_int(&args.positional[0], 10),
+                _ => {
+                    let base = args.int(1)?;
+                    if !(2..=36).contains(&base) {
+                        return Err(exception("ValueError", "int() base must be >= 2 and <= 36, or 0"));
+                    }
+                    match &args.positional[0] {
+                        Value::Str(_) => to_int(&args.positional[0], base as u32),
+                        _ => Err(exception("TypeError", "int() can't convert non-string with explicit base")),
+                    }
+                }
+            }
+        }
+        "float" => {
+            args.expect(0, 1)?;
+            match args.get(0) {
+                None => Ok(Value::Float(0.0)),
+                Some(Value::Str(s)) => parse_float(s)
+                    .map(Value::Float)
+                    .ok_or_else(|| exception("ValueError", format!("could not convert string to float: {}", repr_str(s)))),
+                Some(value) => value.as_float().map(Value::Float).ok_or_else(|| {
+                    exception(
+                        "TypeError",
+                        format!("float() argument must be a string or a real number, not '{}'", value.type_name()),
+                    )
+                }),
+            }
+        }
+        "bool" => {
+            args.expect(0, 1)?;
+            Ok(Value::Bool(args.get(0).is_some_and(Value::is_truthy)))
+        }
+        "abs" => {
+            args.expect(1, 1)?;
+            match &args.positional[0] {
+                Value::Float(f) => Ok(Value::Float(f.abs())),
+                value if value.as_int().is_some() => value
+                    .as_int()
+                    .and_then(i64::checked_abs)
+                    .map(Value::Int)
+                    .ok_or_else(|| exception("OverflowError", "integer overflow")),
+                other => Err(exception(
+                    "TypeError",
+                    format!("bad operand type for abs(): '{}'", other.type_name()),
+                )),
+            }
+        }
+        "round" => {
+            args.expect(1, 2)?;
+            let ndigits = match args.get(1) {
+                Some(Value::None) | None => None,
+                Some(_) => Some(args.int(1)?),
+            };
+            match (&args.positional[0], ndigits) {
+                (value, _) if value.as_int().is_some() => Ok(Value::Int(value.as_int().unwrap_or(0))),
+                (Value::Float(f), None) => {
+                    if !f.is_finite() {
+                        return Err(exception("OverflowError", "cannot convert float infinity or NaN to integer"));
+                    }
+                    Ok(Value::Int(f.round_ties_even() as i64))
+                }
+                (Value::Float(f), Some(n)) => {
+                    let scale = 10f64.powi(n as i32);
+                    Ok(Value::Float((f * scale).round_ties_even() / scale))
+                }
+                (other, _) => Err(exception(
+                    "TypeError",
+                    format!("type {} doesn't define __round__ method", other.type_name()),
+                )),
+            }
+        }
+        "divmod" => {
+            args.expect(2, 2)?;
+            let quotient = binary_op(&Operator::FloorDiv, &args.positional[0], &args.positional[1])?;
+            let remainder = binary_op(&Operator::Mod, &args.positional[0], &args.positional[1])?;
+            Ok(Value::tuple(vec![quotient, remainder]))
+        }
+        "pow" => {
+            args.expect(2, 2)?;
+            binary_op(&Operator::Pow, &args.positional[0], &args.positional[1])
+        }
+        "hex" | "oct" | "bin" => {
+            args.expect(1, 1)?;
+            let n = args.int(0)?;
+            let sign = if n < 0 { "-" } else { "" };
+            let magnitude = n.unsigned_abs();
+            let digits = match name {
+                "hex" => format!("0x{:x}", magnitude),
+                "oct" => format!("0o{:o}", magnitude),
+                _ => format!("0b{:b}", magnitude),
+            };
+            Ok(Value::str(&format!("{}{}", sign, digits)))
+        }
+        "ord" => {
+            args.expect(1, 1)?;
+            let s = args.str(0)?;
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(Value::Int(c as i64)),
+                _ => Err(exception(
+                    "TypeError",
+                    format!("ord() expected a character, but string of length {} found", s.chars().count()),
+                )),
+            }
+        }
+        "chr" => {
+            args.expect(1, 1)?;
+            let code = args.int(0)?;
+            u32::try_from(code)
+                .ok()
+                .and_then(char::from_u32)
+                .map(|c| Value::str(c.encode_utf8(&mut [0; 4])))
+                .ok_or_else(|| exception("ValueError", "chr() arg not in range(0x110000)"))
+        }
+        "any" => {
+            args.expect(1, 1)?;
+            Ok(Value::Bool(iterate(&args.positional[0])?.any(|item| item.is_truthy())))
+        }
+        "all" => {
+            args.expect(1, 1)?;
+            Ok(Value::Bool(iterate(&args.positional[0])?.all(|item| item.is_truthy())))
+        }
+        "reversed" => {
+            args.expect(1, 1)?;
+            let mut items: Vec<Value> = iterate(&args.positional[0])?.collect();
+            items.reverse();
+            Ok(Value::list(items))
+        }
+        "zip" => Ok(Value::list(
+            zip_iterables(&args.positional)?.into_iter().map(Value::tuple).collect(),
+        )),
+        "list" => {
+            args.expect(0, 1)?;
+            Ok(Value::list(match args.get(0) {
+                Some(value) => iterate(value)?.collect(),
+                None => Vec::new(),
+            }))
+        }
+        "tuple" => {
+            args.expect(0, 1)?;
+            Ok(Value::tuple(match args.get(0) {
+                Some(value) => iterate(value)?.collect(),
+                None => Vec::new(),
+            }))
+        }
+        "set" => {
+            args.expect(0, 1)?;
+            let mut set = Dict::new();
+            if let Some(value) = args.get(0) {
+                for item in iterate(value)? {
+                    set_add(&mut set, item)?;
+                }
+            }
+            Ok(Value::set(set))
+        }
+        "dict" => {
+            args.expect(0, 1)?;
+            let mut dict = Dict::new();
+            if let Some(source) = args.get(0) {
+                dict_update(&mut dict, source)?;
+            }
+            for (key, value) in &args.keywords {
+                dict_insert(&mut dict, Value::str(key), value.clone())?;
+            }
+            Ok(Value::dict(dict))
+        }
+        "isinstance" => {
+            args.expect(2, 2)?;
+            let value = &args.positional[0];
+            let classes: Vec<Value> = match &args.positional[1] {
+                Value::Tuple(classes) => classes.to_vec(),
+                class => vec![class.clone()],
+            };
+            let mut result = false;
+            for class in &classes {
+                result |= match class {
+                    Value::Builtin(type_name) if matches!(*type_name, "bool" | "int" | "float" | "str" | "list" | "tuple" | "dict" | "set" | "range") => {
+                        value.type_name() == *type_name || (*type_name == "int" && matches!(value, Value::Bool(_)))
+                    }
+                    Value::ExceptionType(base) => matches!(value, Value::Exception(e) if is_subclass(&e.typ, base)),
+                    _ => {
+                        return Err(exception(
+                            "TypeError",
+                            "isinstance() arg 2 must be a type or tuple of types",
+                        ))
+                    }
+                };
+            }
+            Ok(Value::Bool(result))
+        }
+        _ => Err(exception("NameError", format!("name '{}' is not defined", name))),
+    }
+}
+
+fn min_max(interp: &mut Interpreter, mut args: Args) -> Exec<Value> {
+    args.expect(1, usize::MAX)?;
+    let key = args.keyword("key");
+    let default = args.keyword("default");
+    args.no_more_keywords()?;
+    let items: Vec<Value> = if args.positional.len() == 1 {
+        iterate(&args.positional[0])?.collect()
+    } else {
+        args.positional.clone()
+    };
+    let wanted = if args.name == "min" { Ordering::Less } else { Ordering::Greater };
+    let mut best: Option<(Value, Value)> = None;
+    for item in items {
+        let item_key = match &key {
+            Some(key) => interp.call_value(key, vec![item.clone()], Vec::new())?,
+            None => item.clone(),
+        };
+        let better = match &best {
+            None => true,
+            Some((_, best_key)) => compare_values(&item_key, best_key)? == wanted,
+        };
+        if better {
+            best = Some((item, item_key));
+        }
+    }
+    match (best, default) {
+        (Some((item, _)), _) => Ok(item),
+        (None, Some(default)) => Ok(default),
+        (None, None) => Err(exception("ValueError", format!("{}() arg is an empty sequence", args.name)).into()),
+    }
+}
+
+/// Sort `items`, by `key(item)` if a key function is given. The sort is stable
+/// in both directions, as in Python.
+fn sort(interp: &mut Interpreter, items: Vec<Value>, key: Option<&Value>, reverse: bool) -> Exec<Vec<Value>> {
+    let mut keyed = Vec::with_capacity(items.len());
+    for item in items {
+        let item_key = match key {
+            Some(Value::None) | None => item.clone(),
+            Some(key) => interp.call_value(key, vec![item.clone()], Vec::new())?,
+        };
+        keyed.push((item_key, item));
+    }
+    let error = RefCell::new(None);
+    keyed.sort_by(|(a, _), (b, _)| {
+        let (a, b) = if reverse { (b, a) } else { (a, b) };
+        compare_values(a, b).unwrap_or_else(|e| {
+            error.borrow_mut().get_or_insert(e);
+            Ordering::Equal
+        })
+    });
+    if let Some(e) = error.into_inner() {
+        return Err(e.into());
+    }
+    Ok(keyed.into_iter().map(|(_, item)| item).collect())
+}
+
+/// Rows of items taken in step from each iterable, stopping at the shortest
+fn zip_iterables(iterables: &[Value]) -> OpResult<Vec<Vec<Value>>> {
+    let mut columns = Vec::with_capacity(iterables.len());
+    for iterable in iterables {
+        columns.push(iterate(iterable)?.collect::<Vec<_>>());
+    }
+    let rows = columns.iter().map(Vec::len).min().unwrap_or(0);
+    Ok((0..rows)
+        .map(|row| columns.iter().map(|column| column[row].clone()).collect())
+        .collect())
+}
+
+fn to_int(value: &Value, base: u32) -> OpResult<Value> {
+    match value {
+        Value::Float(f) if f.is_nan() => Err(exception("ValueError", "cannot convert float NaN to integer")),
+        Value::Float(f) if f.is_infinite() => {
+            Err(exception("OverflowError", "cannot convert float infinity to integer"))
+        }
+        Value::Float(f) if f.trunc() >= i64::MAX as f64 || f.trunc() < i64::MIN as f64 => {
+            Err(exception("OverflowError", "integer overflow"))
+        }
+        Value::Float(f) => Ok(Value::Int(f.trunc() as i64)),
+        Value::Str(s) => {
+            let digits = s.trim().replace('_', "");
+            i64::from_str_radix(&digits, base).map(Value::Int).map_err(|_| {
+                exception(
+                    "ValueError",
+                    format!("invalid literal for int() with base {}: {}", base, repr_str(s)),
+                )
+            })
+        }
+        other => other.as_int().map(Value::Int).ok_or_else(|| {
+            exception(
+                "TypeError",
+                format!(
+                    "int() argument must be a string or a real number, not '{}'",
+                    other.type_name()
+                ),
+            )
+        }),
+    }
+}
+
+fn parse_float(s: &str) -> Option<f64> {
+    let s = s.trim();
+    match s.to_ascii_lowercase().trim_start_matches(['+', '-']) {
+        "inf" | "infinity" | "nan" => s.to_ascii_lowercase().replace("infinity", "inf").parse().ok(),
+        _ if s.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-' | '_')) => {
+            s.replace('_', "").parse().ok()
+        }
+        _ => None,
+    }
+}
+
+fn unhashable(value: &Value) -> Exception {
+    exception("TypeError", format!("unhashable type: '{}'", value.type_name()))
+}
+
+/// Add `item` to a set
+pub fn set_add(set: &mut Dict, item: Value) -> OpResult<()> {
+    let hash = item.hash_key().ok_or_else(|| unhashable(&item))?;
+    set.insert(hash, item, Value::None);
+    Ok(())
+}
+
+/// Set `dict[key] = value`
+pub fn dict_insert(dict: &mut Dict, key: Value, value: Value) -> OpResult<()> {
+    let hash = key.hash_key().ok_or_else(|| unhashable(&key))?;
+    dict.insert(hash, key, value);
+    Ok(())
+}
+
+/// `dict[key]`, raising KeyError if it is missing
+pub fn dict_get(dict: &Dict, key: &Value) -> OpResult<Value> {
+    let hash = key.hash_key().ok_or_else(|| unhashable(key))?;
+    dict.get(&hash).cloned().ok_or_else(|| exception("KeyError", key.repr()))
+}
+
+/// Add the entries of a dict, or of an iterable of key/value pairs, to `dict`
+fn dict_update(dict: &mut Dict, source: &Value) -> OpResult<()> {
+    if let Value::Dict(other) = source {
+        let entries: Vec<(Value, Value)> = other.borrow().items().cloned().collect();
+        for (key, value) in entries {
+            dict_insert(dict, key, value)?;
+        }
+        return Ok(());
+    }
+    for (i, pair) in iterate(source)?.enumerate() {
+        let items: Vec<Value> = iterate(&pair)?.collect();
+        if items.len() != 2 {
+            return Err(exception(
+                "ValueError",
+                format!(
+                    "dictionary update sequence element #{} has length {}; 2 is required",
+                    i,
+                    items.len()
+                ),
+            ));
+        }
+        let mut items = items.into_iter();
+        if let (Some(key), Some(value)) = (items.next(), items.next()) {
+            dict_insert(dict, key, value)?;
+        }
+    }
+    Ok(())
+}
+
+const STR_METHODS: &[&str] = &[
+    "capitalize", "center", "count", "endswith", "find", "index", "isalnum", "isalpha", "isdigit", "islower",
+    "isspace", "isupper", "join", "ljust", "lower", "lstrip", "replace", "rfind", "rjust", "rstrip", "split",
+    "splitlines", "startswith", "strip", "title", "upper", "zfill",
+];
+const LIST_METHODS: &[&str] = &[
+    "append", "clear", "copy", "count", "extend", "index", "insert", "pop", "remove", "reverse", "sort",
+];
+const DICT_METHODS: &[&str] = &[
+    "clear", "copy", "get", "items", "keys", "pop", "setdefault", "update", "values",
+];
+const SET_METHODS: &[&str] = &[
+    "add", "clear", "copy", "difference", "discard", "intersection", "pop", "remove", "union",
+];
+
+/// Whether values like `receiver` have a method called `name`
+pub fn has_method(receiver: &Value, name: &str) -> bool {
+    let methods = match receiver {
+        Value::Str(_) => STR_METHODS,
+        Value::List(_) => LIST_METHODS,
+        Value::Dict(_) => DICT_METHODS,
+        Value::Set(_) => SET_METHODS,
+        _ => return false,
+    };
+    methods.contains(&name)
+}
+
+/// Call method `name` of `receiver`
+pub(super) fn call_method(
+    interp: &mut Interpreter,
+    receiver: &Value,
+    name: &str,
+    positional: Vec<Value>,
+    keywords: Vec<(String, Value)>,
+) -> Exec<Value> {
+    let mut args = Args::new(format!("{}.{}", receiver.type_name(), name), positional, keywords);
+    match receiver {
+        Value::List(items) if name == "sort" => {
+            args.expect(0, 0)?;
+            let key = args.keyword("key");
+            let reverse = args.keyword("reverse").is_some_and(|v| v.is_truthy());
+            args.no_more_keywords()?;
+            let unsorted = items.borrow().clone();
+            let sorted = sort(interp, unsorted, key.as_ref(), reverse)?;
+            *items.borrow_mut() = sorted;
+            Ok(Value::None)
+        }
+        Value::Str(s) => {
+            args.no_more_keywords()?;
+            Ok(str_method(s, name, &args)?)
+        }
+        Value::List(items) => {
+            args.no_more_keywords()?;
+            Ok(list_method(items, name, &args)?)
+        }
+        Value::Dict(dict) => {
+            args.no_more_keywords()?;
+            Ok(dict_method(dict, name, &args)?)
+        }
+        Value::Set(set) => {
+            args.no_more_keywords()?;
+            Ok(set_method(set, name, &args)?)
+        }
+        other => Err(exception(
+            "AttributeError",
+            format!("'{}' object has no attribute '{}'", other.type_name(), name),
+        )
+        .into()),
+    }
+}
+
+fn str_method(s: &str, name: &str, args: &Args) -> OpResult<Value> {
+    let bool_value = |b: bool| Ok(Value::Bool(b));
+    let chars_arg = |i: usize| -> OpResult<Option<Rc<str>>> {
+        match args.get(i) {
+            None | Some(Value::None) => Ok(None),
+            Some(_) => args.str(i).map(Some),
+        }
+    };
+    match name {
+        "upper" => Ok(Value::str(&s.to_uppercase())),
+        "lower" => Ok(Value::str(&s.to_lowercase())),
+        "capitalize" => {
+            let mut chars = s.chars();
+            Ok(Value::str(&match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+                None => String::new(),
+            }))
+        }
+        "title" => {
+            let mut out = String::with_capacity(s.len());
+            let mut previous_is_letter = false;
+            for c in s.chars() {
+                if previous_is_letter {
+                    out.extend(c.to_lowercase());
+                } else {
+                    out.extend(c.to_uppercase());
+                }
+                previous_is_letter = c.is_alphabetic();
+            }
+            Ok(Value::str(&out))
+        }
+        "strip" | "lstrip" | "rstrip" => {
+            args.expect(0, 1)?;
+            let chars = chars_arg(0)?;
+            let matches = |c: char| match &chars {
+                Some(chars) => chars.contains(c),
+                None => c.is_whitespace(),
+            };
+            Ok(Value::str(match name {
+                "strip" => s.trim_matches(matches),
+                "lstrip" => s.trim_start_matches(matches),
+                _ => s.trim_end_matches(matches),
+            }))
+        }
+        "split" => {
+            args.expect(0, 2)?;
+            let separator = chars_arg(0)?;
+            let max_split = match args.get(1) {
+                Some(_) => args.int(1)?,
+                None => -1,
+            };
+            let parts: Vec<Value> = match separator {
+                Some(sep) if sep.is_empty() => return Err(exception("ValueError", "empty separator")),
+                Some(sep) if max_split >= 0 => s.splitn(max_split as usize + 1, &*sep).map(Value::str).collect(),
+                Some(sep) => s.split(&*sep).map(Value::str).collect(),
+                None => {
+                    let mut parts = Vec::new();
+                    let mut rest = s.trim_start();
+                    while !rest.is_empty() {
+                        if max_split >= 0 && parts.len() as i64 == max_split {
+                            parts.push(Value::str(rest.trim_end()));
+                            break;
+                        }
+                        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                        parts.push(Value::str(&rest[..end]));
+                        rest = rest[end..].trim_start();
+                    }
+                    parts
+                }
+            };
+            Ok(Value::list(parts))
+        }
+        "splitlines" => Ok(Value::list(s.lines().map(Value::str).collect())),
+        "join" => {
+            args.expect(1, 1)?;
+            let mut parts = Vec::new();
+            for (i, item) in iterate(&args.positional[0])?.enumerate() {
+                match item {
+                    Value::Str(part) => parts.push(part),
+                    other => {
+                        return Err(exception(
+                            "TypeError",
+                            format!("sequence item {}: expected str instance, {} found", i, other.type_name()),
+                        ))
+                    }
+                }
+            }
+            Ok(Value::str(&parts.iter().map(|p| &**p).collect::<Vec<_>>().join(s)))
+        }
+        "replace" => {
+            args.expect(2, 3)?;
+            let (old, new) = (args.str(0)?, args.str(1)?);
+            Ok(Value::str(&match args.get(2) {
+                Some(_) if args.int(2)? >= 0 => s.replacen(&*old, &new, args.int(2)? as usize),
+                _ => s.replace(&*old, &new),
+            }))
+        }
+        "find" | "rfind" | "index" => {
+            args.expect(1, 1)?;
+            let needle = args.str(0)?;
+            let byte_position = if name == "rfind" { s.rfind(&*needle) } else { s.find(&*needle) };
+            match byte_position {
+                Some(position) => Ok(Value::Int(s[..position].chars().count() as i64)),
+                None if name == "index" => Err(exception("ValueError", "substring not found")),
+                None => Ok(Value::Int(-1)),
+            }
+        }
+        "count" => {
+            args.expect(1, 1)?;
+            let needle = args.str(0)?;
+            Ok(Value::Int(if needle.is_empty() {
+                s.chars().count() as i64 + 1
+            } else {
+                s.matches(&*needle).count() as i64
+            }))
+        }
+        "startswith" | "endswith" => {
+            args.expect(1, 1)?;
+            let candidates: Vec<Value> = match &args.positional[0] {
+                Value::Tuple(items) => items.to_vec(),
+                other => vec![other.clone()],
+            };
+            let mut found = false;
+            for candidate in candidates {
+                let Value::Str(affix) = candidate else {
+                    return Err(exception(
+                        "TypeError",
+                        format!("{} first arg must be str or a tuple of str", name),
+                    ));
+                };
+                found |= if name == "startswith" { s.starts_with(&*affix) } else { s.ends_with(&*affix) };
+            }
+            bool_value(found)
+        }
+        "isdigit" => bool_value(!s.is_empty() && s.chars().all(|c| c.is_ascii_digit())),
+        "isalpha" => bool_value(!s.is_empty() && s.chars().all(char::is_alphabetic)),
+        "isalnum" => bool_value(!s.is_empty() && s.chars().all(char::is_alphanumeric)),
+        "isspace" => bool_value(!s.is_empty() && s.chars().all(char::is_whitespace)),
+        "isupper" => bool_value(s.chars().any(char::is_uppercase) && !s.chars().any(char::is_lowercase)),
+        "islower" => bool_value(s.chars().any(char::is_lowercase) && !s.chars().any(char::is_uppercase)),
+        "zfill" | "center" | "ljust" | "rjust" => {
+            args.expect(1, 2)?;
+            let width = args.int(0)?.max(0) as usize;
+            let fill = match args.get(1) {
+                Some(_) => args.str(1)?.chars().next().unwrap_or(' '),
+                None => ' ',
+            };
+            let padding = width.saturating_sub(s.chars().count());
+            let pad = |n: usize, c: char| c.to_string().repeat(n);
+            Ok(Value::str(&match name {
+                "zfill" => match s.strip_prefix(['-', '+']) {
+                    Some(digits) => format!("{}{}{}", &s[..1], pad(padding, '0'), digits),
+                    None => format!("{}{}", pad(padding, '0'), s),
+                },
+                "ljust" => format!("{}{}", s, pad(padding, fill)),
+                "rjust" => format!("{}{}", pad(padding, fill), s),
+                _ => {
+                    let left = padding / 2 + (padding & width & 1);
+                    format!("{}{}{}", pad(left, fill), s, pad(padding - left, fill))
+                }
+            }))
+        }
+        _ => Err(exception("AttributeError", format!("'str' object has no attribute '{}'", name))),
+    }
+}
+
+fn list_method(items: &Rc<RefCell<Vec<Value>>>, name: &str, args: &Args) -> OpResult<Value> {
+    match name {
+        "append" => {
+            args.expect(1, 1)?;
+            items.borrow_mut().push(args.positional[0].clone());
+            Ok(Value::None)
+        }
+        "extend" => {
+            args.expect(1, 1)?;
+            let extra: Vec<Value> = iterate(&args.positional[0])?.collect();
+            items.borrow_mut().extend(extra);
+            Ok(Value::None)
+        }
+        "insert" => {
+            args.expect(2, 2)?;
+            let mut items = items.borrow_mut();
+            let len = items.len() as i64;
+            let index = args.int(0)?;
+            let position = if index < 0 { (index + len).max(0) } else { index.min(len) };
+            items.insert(position as usize, args.positional[1].clone());
+            Ok(Value::None)
+        }
+        "pop" => {
+            args.expect(0, 1)?;
+            let mut items = items.borrow_mut();
+            if items.is_empty() {
+                return Err(exception("IndexError", "pop from empty list"));
+            }
+            let index = match args.get(0) {
+                Some(_) => args.int(0)?,
+                None => -1,
+            };
+            let position = normalize_index(index, items.len(), "pop")?;
+            Ok(items.remove(position))
+        }
+        "remove" => {
+            args.expect(1, 1)?;
+            let mut items = items.borrow_mut();
+            match items.iter().position(|item| *item == args.positional[0]) {
+                Some(position) => {
+                    items.remove(position);
+                    Ok(Value::None)
+                }
+                None => Err(exception("ValueError", "list.remove(x): x not in list")),
+            }
+        }
+        "index" => {
+            args.expect(1, 1)?;
+            items
+                .borrow()
+                .iter()
+                .position(|item| *item == args.positional[0])
+                .map(|position| Value::Int(position as i64))
+                .ok_or_else(|| exception("ValueError", format!("{} is not in list", args.positional[0].repr())))
+        }
+        "count" => {
+            args.expect(1, 1)?;
+            let count = items.borrow().iter().filter(|item| **item == args.positional[0]).count();
+            Ok(Value::Int(count as i64))
+        }
+        "reverse" => {
+            args.expect(0, 0)?;
+            items.borrow_mut().reverse();
+            Ok(Value::None)
+        }
+        "clear" => {
+            args.expect(0, 0)?;
+            items.borrow_mut().clear();
+            Ok(Value::None)
+        }
+        "copy" => {
+            args.expect(0, 0)?;
+            Ok(Value::list(items.borrow().clone()))
+        }
+        _ => Err(exception("AttributeError", format!("'list' object has no attribute '{}'", name))),
+    }
+}
+
+fn dict_method(dict: &Rc<RefCell<Dict>>, name: &str, args: &Args) -> OpResult<Value> {
+    match name {
+        "get" => {
+            args.expect(1, 2)?;
+            let key = &args.positional[0];
+            let hash = key.hash_key().ok_or_else(|| unhashable(key))?;
+            Ok(dict.borrow().get(&hash).cloned().unwrap_or_else(|| args.get(1).cloned().unwrap_or(Value::None)))
+        }
+        "keys" => Ok(Value::list(dict.borrow().keys().cloned().collect())),
+        "values" => Ok(Value::list(dict.borrow().values().cloned().collect())),
+        "items" => Ok(Value::list(
+            dict.borrow()
+                .items()
+                .map(|(key, value)| Value::tuple(vec![key.clone(), value.clone()]))
+                .collect(),
+        )),
+        "pop" => {
+            args.expect(1, 2)?;
+            let key = &args.positional[0];
+            let hash = key.hash_key().ok_or_else(|| unhashable(key))?;
+            match (dict.borrow_mut().remove(&hash), args.get(1)) {
+                (Some(value), _) => Ok(value),
+                (None, Some(default)) => Ok(default.clone()),
+                (None, None) => Err(exception("KeyError", key.repr())),
+            }
+        }
+        "setdefault" => {
+            args.expect(1, 2)?;
+            let key = &args.positional[0];
+            let hash = key.hash_key().ok_or_else(|| unhashable(key))?;
+            let mut dict = dict.borrow_mut();
+            if let Some(value) = dict.get(&hash) {
+                return Ok(value.clone());
+            }
+            let default = args.get(1).cloned().unwrap_or(Value::None);
+            dict.insert(hash, key.clone(), default.clone());
+            Ok(default)
+        }
+        "update" => {
+            args.expect(1, 1)?;
+            let source = match &args.positional[0] {
+                Value::Dict(other) if Rc::ptr_eq(other, dict) => return Ok(Value::None),
+                source => source.clone(),
+            };
+            dict_update(&mut dict.borrow_mut(), &source)?;
+            Ok(Value::None)
+        }
+        "clear" => {
+            dict.borrow_mut().clear();
+            Ok(Value::None)
+        }
+        "copy" => Ok(Value::dict(dict.borrow().clone())),
+        _ => Err(exception("AttributeError", format!("'dict' object has no attribute '{}'", name))),
+    }
+}
+
+fn set_method(set: &Rc<RefCell<Dict>>, name: &str, args: &Args) -> OpResult<Value> {
+    match name {
+        "add" => {
+            args.expect(1, 1)?;
+            set_add(&mut set.borrow_mut(), args.positional[0].clone())?;
+            Ok(Value::None)
+        }
+        "remove" | "discard" => {
+            args.expect(1, 1)?;
+            let item = &args.positional[0];
+            let hash = item.hash_key().ok_or_else(|| unhashable(item))?;
+            if set.borrow_mut().remove(&hash).is_none() && name == "remove" {
+                return Err(exception("KeyError", item.repr()));
+            }
+            Ok(Value::None)
+        }
+        "pop" => {
+            let mut set = set.borrow_mut();
+            let first = set.keys().next().cloned();
+            match first.and_then(|item| item.hash_key().map(|hash| (item, hash))) {
+                Some((item, hash)) => {
+                    set.remove(&hash);
+                    Ok(item)
+                }
+                None => Err(exception("KeyError", "'pop from an empty set'")),
+            }
+        }
+        "clear" => {
+            set.borrow_mut().clear();
+            Ok(Value::None)
+        }
+        "copy" => Ok(Value::set(set.borrow().clone())),
+        "union" | "intersection" | "difference" => {
+            args.expect(1, 1)?;
+            let mut other = Dict::new();
+            for item in iterate(&args.positional[0])? {
+                set_add(&mut other, item)?;
+            }
+            let op = match name {
+                "union" => Operator::BitOr,
+                "intersection" => Operator::BitAnd,
+                _ => Operator::Sub,
+            };
+            binary_op(&op, &Value::Set(set.clone()), &Value::set(other))
+        }
+        _ => Err(exception("AttributeError", format!("'set' object has no attribute '{}'", name))),
+    }
+}
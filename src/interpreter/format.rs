@@ -0,0 +1,229 @@
+// format.rs - Format specifications for f-strings and format()
+//
+// Supports [[fill]align][sign][0][width][,|_][.precision][type] with the
+// types s, d, b, o, x, X, e, E, f, F, g, G and %.
+
+use super::ops::{exception, OpResult};
+use super::value::{Exception, Value};
+
+struct Spec {
+    fill: char,
+    align: Option<char>,
+    sign: char,
+    zero: bool,
+    width: usize,
+    grouping: Option<char>,
+    precision: Option<usize>,
+    kind: Option<char>,
+}
+
+fn invalid(spec: &str) -> Exception {
+    exception("ValueError", format!("Invalid format specifier '{}'", spec))
+}
+
+fn parse_spec(spec: &str) -> OpResult<Spec> {
+    let chars: Vec<char> = spec.chars().collect();
+    let mut parsed = Spec {
+        fill: ' ',
+        align: None,
+        sign: '-',
+        zero: false,
+        width: 0,
+        grouping: None,
+        precision: None,
+        kind: None,
+    };
+    let mut i = 0;
+    let is_align = |c: char| matches!(c, '<' | '>' | '^' | '=');
+    if chars.len() >= 2 && is_align(chars[1]) {
+        parsed.fill = chars[0];
+        parsed.align = Some(chars[1]);
+        i = 2;
+    } else if !chars.is_empty() && is_align(chars[0]) {
+        parsed.align = Some(chars[0]);
+        i = 1;
+    }
+    if i < chars.len() && matches!(chars[i], '+' | '-' | ' ') {
+        parsed.sign = chars[i];
+        i += 1;
+    }
+    if i < chars.len() && chars[i] == '0' {
+        parsed.zero = true;
+        i += 1;
+    }
+    let digits = |i: &mut usize| {
+        let start = *i;
+        while *i < chars.len() && chars[*i].is_ascii_digit() {
+            *i += 1;
+        }
+        chars[start..*i].iter().collect::<String>().parse::<usize>().ok()
+    };
+    parsed.width = digits(&mut i).unwrap_or(0);
+    if i < chars.len() && matches!(chars[i], ',' | '_') {
+        parsed.grouping = Some(chars[i]);
+        i += 1;
+    }
+    if i < chars.len() && chars[i] == '.' {
+        i += 1;
+        parsed.precision = Some(digits(&mut i).ok_or_else(|| invalid(spec))?);
+    }
+    if i < chars.len() {
+        parsed.kind = Some(chars[i]);
+        i += 1;
+    }
+    if i != chars.len() {
+        return Err(invalid(spec));
+    }
+    Ok(parsed)
+}
+
+/// Insert a separator between groups of three digits
+fn group_digits(digits: &str, separator: char) -> String {
+    let (int_part, rest) = match digits.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => digits.split_at(i),
+        None => (digits, ""),
+    };
+    let mut grouped = String::new();
+    for (i, c) in int_part.chars().enumerate() {
+        if i > 0 && (int_part.len() - i) % 3 == 0 {
+            grouped.push(separator);
+        }
+        grouped.push(c);
+    }
+    grouped + rest
+}
+
+/// Python's exponent notation: at least two exponent digits, always signed
+fn exponent_notation(x: f64, precision: usize, upper: bool) -> String {
+    let formatted = format!("{:.*e}", precision, x);
+    let (mantissa, exponent) = formatted.split_once('e').unwrap_or((&formatted, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    let sign = if exponent < 0 { '-' } else { '+' };
+    let e = if upper { 'E' } else { 'e' };
+    format!("{}{}{}{:02}", mantissa, e, sign, exponent.abs())
+}
+
+/// Python's general notation: `precision` significant digits, trailing zeros removed
+fn general_notation(x: f64, precision: usize, upper: bool) -> String {
+    let precision = precision.max(1);
+    if x == 0.0 {
+        return "0".to_string();
+    }
+    let exponent = x.abs().log10().floor() as i64;
+    let strip = |s: String| {
+        if s.contains('.') {
+            s.trim_end_matches('0').trim_end_matches('.').to_string()
+        } else {
+            s
+        }
+    };
+    if exponent < -4 || exponent >= precision as i64 {
+        let formatted = exponent_notation(x, precision - 1, upper);
+        let e = if upper { 'E' } else { 'e' };
+        match formatted.split_once(e) {
+            Some((mantissa, exponent)) => format!("{}{}{}", strip(mantissa.to_string()), e, exponent),
+            None => formatted,
+        }
+    } else {
+        let decimals = (precision as i64 - 1 - exponent).max(0) as usize;
+        strip(format!("{:.*}", decimals, x))
+    }
+}
+
+/// Format `value` as `format(value, spec)` would
+pub fn format_value(value: &Value, spec: &str) -> OpResult<String> {
+    if spec.is_empty() {
+        return Ok(value.to_string());
+    }
+    let parsed = parse_spec(spec)?;
+    let wrong_type = |kind: char| {
+        exception(
+            "ValueError",
+            format!("Unknown format code '{}' for object of type '{}'", kind, value.type_name()),
+        )
+    };
+
+    let (negative, mut body, numeric) = match (parsed.kind, value) {
+        (Some('s') | None, Value::Str(s)) => {
+            let text: String = match parsed.precision {
+                Some(p) => s.chars().take(p).collect(),
+                None => s.to_string(),
+            };
+            (false, text, false)
+        }
+        (Some(kind @ ('d' | 'b' | 'o' | 'x' | 'X')), v) if v.as_int().is_some() => {
+            let n = v.as_int().unwrap_or(0);
+            let magnitude = n.unsigned_abs();
+            let digits = match kind {
+                'b' => format!("{:b}", magnitude),
+                'o' => format!("{:o}", magnitude),
+                'x' => format!("{:x}", magnitude),
+                'X' => format!("{:X}", magnitude),
+                _ => magnitude.to_string(),
+            };
+            (n < 0, digits, true)
+        }
+        (None, v @ (Value::Int(_) | Value::Bool(_))) if parsed.precision.is_none() => {
+            let n = v.as_int().unwrap_or(0);
+            (n < 0, n.unsigned_abs().to_string(), true)
+        }
+        (Some(kind @ ('e' | 'E' | 'f' | 'F' | 'g' | 'G' | '%')), v) if v.as_float().is_some() => {
+            let x = v.as_float().unwrap_or(0.0);
+            let precision = parsed.precision.unwrap_or(6);
+            let magnitude = x.abs();
+            let digits = if magnitude.is_nan() {
+                "nan".to_string()
+            } else if magnitude.is_infinite() {
+                "inf".to_string()
+            } else {
+                match kind {
+                    'e' | 'E' => exponent_notation(magnitude, precision, kind == 'E'),
+                    'g' | 'G' => general_notation(magnitude, precision, kind == 'G'),
+                    '%' => format!("{:.*}%", precision, magnitude * 100.0),
+                    _ => format!("{:.*}", precision, magnitude),
+                }
+            };
+            let digits = if kind.is_ascii_uppercase() { digits.to_uppercase() } else { digits };
+            (x.is_sign_negative() && !x.is_nan(), digits, true)
+        }
+        (None, Value::Float(x)) => {
+            let magnitude = x.abs();
+            let digits = match parsed.precision {
+                Some(p) => general_notation(magnitude, p, false),
+                None => Value::Float(magnitude).to_string(),
+            };
+            (x.is_sign_negative() && !x.is_nan(), digits, true)
+        }
+        (None, other) if parsed.precision.is_none() => (false, other.to_string(), false),
+        (Some(kind), _) => return Err(wrong_type(kind)),
+        (None, _) => return Err(invalid(spec)),
+    };
+
+    if let Some(separator) = parsed.grouping {
+        if numeric {
+            body = group_digits(&body, separator);
+        }
+    }
+    let sign = match (negative, parsed.sign) {
+        (true, _) => "-",
+        (false, '+') if numeric => "+",
+        (false, ' ') if numeric => " ",
+        _ => "",
+    };
+
+    let (fill, align) = match parsed.align {
+        Some(align) => (parsed.fill, align),
+        None if parsed.zero && numeric => ('0', '='),
+        None if numeric => (parsed.fill, '>'),
+        None => (parsed.fill, '<'),
+    };
+    let length = sign.chars().count() + body.chars().count();
+    let padding = parsed.width.saturating_sub(length);
+    let pad = |n: usize| fill.to_string().repeat(n);
+    Ok(match align {
+        '<' => format!("{}{}{}", sign, body, pad(padding)),
+        '^' => format!("{}{}{}{}", pad(padding / 2), sign, body, pad(padding - padding / 2)),
+        '=' => format!("{}{}{}", sign, pad(padding), body),
+        _ => format!("{}{}{}", pad(padding), sign, body),
+    })
+}
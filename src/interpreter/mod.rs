@@ -0,0 +1,1129 @@
+// mod.rs - Tree-walking interpreter backend
+//
+// Runs a parsed module directly from its AST, without LLVM. It is selected
+// with `cheetah run --backend interp` and is meant for platforms without an
+// LLVM toolchain and for quick runs of small scripts; the compiled backends
+// are much faster for anything long-running.
+//
+// Programs see the same semantics as compiled code where the two overlap:
+// 64-bit ints that raise OverflowError, Python-style division and modulo, and
+// the same float formatting. Classes, `with`, imports, generators, `match`
+// and async code are not supported and raise NotImplementedError.
+
+pub mod builtins;
+pub mod format;
+pub mod ops;
+pub mod scope;
+pub mod value;
+
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+
+use crate::ast::{
+    BoolOperator, Comprehension, Constant, ExceptHandler, Expr, Module, NameConstant, Number, Parameter, Stmt,
+};
+use crate::compiler::runtime::output::Newline;
+use crate::trace;
+
+use ops::{binary_op, compare, exception, iterate, normalize_index, slice_indices, unary_op, OpResult};
+use scope::Scope;
+use value::{Dict, Exception, Function, FunctionBody, Value};
+
+/// Deepest call nesting before RecursionError, as in CPython
+const MAX_CALL_DEPTH: usize = 1000;
+
+/// A function being executed and the line it is currently running
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub function: String,
+    pub line: usize,
+}
+
+/// An exception that propagated out of the program
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub exception: Exception,
+    /// Call stack where the exception was raised, outermost call first
+    pub frames: Vec<Frame>,
+}
+
+impl RuntimeError {
+    /// The error formatted as a Python traceback
+    pub fn traceback(&self, source_name: &str) -> String {
+        let mut out = String::from("Traceback (most recent call last):\n");
+        for frame in &self.frames {
+            out.push_str(&format!(
+                "  File \"{}\", line {}, in {}\n",
+                source_name, frame.line, frame.function
+            ));
+        }
+        out.push_str(&self.to_string());
+        out
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.exception.message.is_empty() {
+            write!(f, "{}", self.exception.typ)
+        } else {
+            write!(f, "{}: {}", self.exception.typ, self.exception.message)
+        }
+    }
+}
+
+/// An exception propagating through the interpreter. The call stack is
+/// recorded by the first statement it unwinds through.
+#[derive(Debug)]
+struct Raised {
+    exception: Rc<Exception>,
+    frames: Option<Vec<Frame>>,
+}
+
+impl From<Exception> for Raised {
+    fn from(exception: Exception) -> Self {
+        Raised {
+            exception: Rc::new(exception),
+            frames: None,
+        }
+    }
+}
+
+type Exec<T> = Result<T, Raised>;
+
+/// Called for each binding a comprehension produces
+type ComprehensionBody<'a> = dyn FnMut(&mut Interpreter, &Rc<RefCell<Scope>>) -> Exec<()> + 'a;
+
+/// How a statement finished
+enum Flow {
+    Normal,
+    Break,
+    Continue,
+    Return(Value),
+}
+
+fn not_supported(what: &str) -> Raised {
+    exception(
+        "NotImplementedError",
+        format!("{} not supported by the interpreter backend", what),
+    )
+    .into()
+}
+
+/// Evaluates programs statement by statement
+pub struct Interpreter {
+    globals: Rc<RefCell<Scope>>,
+    output: Box<dyn Write>,
+    input: Box<dyn BufRead>,
+    newline: Newline,
+    frames: Vec<Frame>,
+    /// Exceptions being handled by `except` blocks, innermost last, for bare `raise`
+    handling: Vec<Rc<Exception>>,
+    /// Step limit and steps taken so far, when tracing an instrumented module
+    trace: Option<(usize, usize)>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    /// An interpreter reading stdin and writing stdout
+    pub fn new() -> Self {
+        Self::with_io(Box::new(io::stdout()), Box::new(io::BufReader::new(io::stdin())))
+    }
+
+    /// An interpreter with its own input and output, e.g. for capturing output
+    pub fn with_io(output: Box<dyn Write>, input: Box<dyn BufRead>) -> Self {
+        Interpreter {
+            globals: Scope::module(),
+            output,
+            input,
+            newline: Newline::default(),
+            frames: Vec::new(),
+            handling: Vec::new(),
+            trace: None,
+        }
+    }
+
+    /// Run a module. Globals persist across calls, so a module can be run in pieces.
+    pub fn run(&mut self, module: &Module) -> Result<(), RuntimeError> {
+        self.frames = vec![Frame {
+            function: "<module>".to_string(),
+            line: 0,
+        }];
+        let globals = self.globals.clone();
+        let result = self.exec_block(&module.body, &globals);
+        let _ = self.output.flush();
+        self.frames.clear();
+        match result {
+            Ok(_) => Ok(()),
+            Err(raised) => Err(RuntimeError {
+                exception: (*raised.exception).clone(),
+                frames: raised.frames.unwrap_or_default(),
+            }),
+        }
+    }
+
+    /// Set the line endings the program's output is written with
+    pub fn set_newline(&mut self, newline: Newline) {
+        self.newline = newline;
+    }
+
+    /// Print the steps of a module instrumented by `trace::instrument`,
+    /// stopping it with a RuntimeError after `limit` steps
+    pub fn set_trace_limit(&mut self, limit: usize) {
+        self.trace = Some((limit, 0));
+    }
+
+    /// Print one traced step: its line, its source, then name and value pairs
+    fn trace_step(&mut self, args: &[Value]) -> Exec<()> {
+        let Some((limit, steps)) = &mut self.trace else {
+            return Ok(());
+        };
+        if *steps == *limit {
+            return Err(exception(
+                "RuntimeError",
+                format!("stopped after {} steps; raise the limit with --max-steps", limit),
+            )
+            .into());
+        }
+        *steps += 1;
+
+        let (line, text) = match args {
+            [Value::Int(line), Value::Str(text), ..] => (*line as usize, text.to_string()),
+            _ => return Err(exception("TypeError", "malformed trace call").into()),
+        };
+        let bindings: Vec<(String, String)> = args[2..]
+            .chunks(2)
+            .filter_map(|pair| match pair {
+                [name, value] => Some((name.to_string(), value.repr())),
+                _ => None,
+            })
+            .collect();
+        self.write_output(&trace::format_step(line, &text, &bindings))
+    }
+
+    /// Value of a global variable, after `run`
+    pub fn global(&self, name: &str) -> Option<Value> {
+        scope::lookup(&self.globals, name)
+    }
+
+    fn write_output(&mut self, text: &str) -> Exec<()> {
+        let result = if self.newline.translates() {
+            self.output.write_all(text.replace('\n', "\r\n").as_bytes())
+        } else {
+            self.output.write_all(text.as_bytes())
+        };
+        result.map_err(|e| exception("OSError", e.to_string()).into())
+    }
+
+    /// Read a line of input, or None at end of input
+    fn read_line(&mut self) -> Exec<Option<String>> {
+        let _ = self.output.flush();
+        let mut line = String::new();
+        match self.input.read_line(&mut line) {
+            Ok(0) => Ok(None),
+            Ok(_) => Ok(Some(line)),
+            Err(e) => Err(exception("OSError", e.to_string()).into()),
+        }
+    }
+
+    fn exec_block(&mut self, body: &[Box<Stmt>], scope: &Rc<RefCell<Scope>>) -> Exec<Flow> {
+        for stmt in body {
+            match self.exec_stmt(stmt, scope)? {
+                Flow::Normal => {}
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn exec_stmt(&mut self, stmt: &Stmt, scope: &Rc<RefCell<Scope>>) -> Exec<Flow> {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.line = stmt.line();
+        }
+        self.exec_stmt_inner(stmt, scope).map_err(|mut raised| {
+            if raised.frames.is_none() {
+                raised.frames = Some(self.frames.clone());
+            }
+            raised
+        })
+    }
+
+    fn exec_stmt_inner(&mut self, stmt: &Stmt, scope: &Rc<RefCell<Scope>>) -> Exec<Flow> {
+        match stmt {
+            Stmt::FunctionDef {
+                name,
+                params,
+                body,
+                decorator_list,
+                is_async,
+                ..
+            } => {
+                if *is_async {
+                    return Err(not_supported("async functions are"));
+                }
+                if !decorator_list.is_empty() {
+                    return Err(not_supported("decorators are"));
+                }
+                let function = self.make_function(name, params, FunctionBody::Block(body.clone()), scope)?;
+                scope::assign(scope, name, function);
+            }
+            Stmt::ClassDef { .. } => return Err(not_supported("class definitions are")),
+            Stmt::Return { value, .. } => {
+                let value = match value {
+                    Some(value) => self.eval(value, scope)?,
+                    None => Value::None,
+                };
+                return Ok(Flow::Return(value));
+            }
+            Stmt::Delete { targets, .. } => {
+                for target in targets {
+                    self.delete(target, scope)?;
+                }
+            }
+            Stmt::Assign { targets, value, .. } => {
+                let value = self.eval(value, scope)?;
+                for target in targets {
+                    self.assign(target, value.clone(), scope)?;
+                }
+            }
+            Stmt::AugAssign { target, op, value, .. } => match &**target {
+                Expr::Name { id, .. } => {
+                    let current = self.load_name(id, scope)?;
+                    let operand = self.eval(value, scope)?;
+                    let result = self.augmented(op, &current, operand)?;
+                    scope::assign(scope, id, result);
+                }
+                Expr::Subscript { value: container, slice, .. } => {
+                    let container = self.eval(container, scope)?;
+                    let index = self.eval(slice, scope)?;
+                    let current = self.subscript(&container, &index)?;
+                    let operand = self.eval(value, scope)?;
+                    let result = self.augmented(op, &current, operand)?;
+                    self.store_item(&container, index, result)?;
+                }
+                _ => return Err(not_supported("this augmented assignment target is")),
+            },
+            Stmt::AnnAssign { target, value, .. } => {
+                if let Some(value) = value {
+                    let value = self.eval(value, scope)?;
+                    self.assign(target, value, scope)?;
+                }
+            }
+            Stmt::For {
+                target,
+                iter,
+                body,
+                orelse,
+                is_async,
+                ..
+            } => {
+                if *is_async {
+                    return Err(not_supported("async for is"));
+                }
+                let iterable = self.eval(iter, scope)?;
+                for item in iterate(&iterable)? {
+                    self.assign(target, item, scope)?;
+                    match self.exec_block(body, scope)? {
+                        Flow::Break => return Ok(Flow::Normal),
+                        Flow::Return(value) => return Ok(Flow::Return(value)),
+                        Flow::Normal | Flow::Continue => {}
+                    }
+                }
+                return self.exec_block(orelse, scope);
+            }
+            Stmt::While { test, body, orelse, .. } => {
+                while self.eval(test, scope)?.is_truthy() {
+                    match self.exec_block(body, scope)? {
+                        Flow::Break => return Ok(Flow::Normal),
+                        Flow::Return(value) => return Ok(Flow::Return(value)),
+                        Flow::Normal | Flow::Continue => {}
+                    }
+                }
+                return self.exec_block(orelse, scope);
+            }
+            Stmt::If { test, body, orelse, .. } => {
+                return if self.eval(test, scope)?.is_truthy() {
+                    self.exec_block(body, scope)
+                } else {
+                    self.exec_block(orelse, scope)
+                };
+            }
+            Stmt::With { .. } => return Err(not_supported("with statements are")),
+            Stmt::Raise { exc, .. } => {
+                let Some(exc) = exc else {
+                    return Err(match self.handling.last() {
+                        Some(current) => Raised {
+                            exception: current.clone(),
+                            frames: None,
+                        },
+                        None => exception("RuntimeError", "No active exception to reraise").into(),
+                    });
+                };
+                let exception = match self.eval(exc, scope)? {
+                    Value::Exception(exception) => exception,
+                    Value::ExceptionType(typ) => Rc::new(Exception {
+                        typ: typ.to_string(),
+                        message: String::new(),
+                    }),
+                    _ => return Err(ops::exception("TypeError", "exceptions must derive from BaseException").into()),
+                };
+                return Err(Raised {
+                    exception,
+                    frames: None,
+                });
+            }
+            Stmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+                ..
+            } => {
+                let mut result = match self.exec_block(body, scope) {
+                    Ok(Flow::Normal) => self.exec_block(orelse, scope),
+                    Err(raised) => self.handle(raised, handlers, scope),
+                    flow => flow,
+                };
+                if !finalbody.is_empty() {
+                    match self.exec_block(finalbody, scope) {
+                        Ok(Flow::Normal) => {}
+                        // A jump or exception in `finally` replaces the pending outcome
+                        other => result = other,
+                    }
+                }
+                return result;
+            }
+            Stmt::Assert { test, msg, .. } => {
+                if !self.eval(test, scope)?.is_truthy() {
+                    let message = match msg {
+                        Some(msg) => self.eval(msg, scope)?.to_string(),
+                        None => String::new(),
+                    };
+                    return Err(exception("AssertionError", message).into());
+                }
+            }
+            Stmt::Import { .. } | Stmt::ImportFrom { .. } => return Err(not_supported("imports are")),
+            Stmt::Global { names, .. } => {
+                for name in names {
+                    scope.borrow_mut().declare_global(name);
+                }
+            }
+            Stmt::Nonlocal { names, .. } => {
+                for name in names {
+                    scope.borrow_mut().declare_nonlocal(name);
+                }
+            }
+            Stmt::Expr { value, .. } => {
+                self.eval(value, scope)?;
+            }
+            Stmt::Pass { .. } => {}
+            Stmt::Break { .. } => return Ok(Flow::Break),
+            Stmt::Continue { .. } => return Ok(Flow::Continue),
+            Stmt::Match { .. } => return Err(not_supported("match statements are")),
+        }
+        Ok(Flow::Normal)
+    }
+
+    /// Run the first `except` clause matching `raised`, or propagate it
+    fn handle(&mut self, raised: Raised, handlers: &[ExceptHandler], scope: &Rc<RefCell<Scope>>) -> Exec<Flow> {
+        for handler in handlers {
+            let matches = match &handler.typ {
+                None => true,
+                Some(typ) => {
+                    let classes = match self.eval(typ, scope)? {
+                        Value::Tuple(classes) => classes.to_vec(),
+                        class => vec![class],
+                    };
+                    let mut matches = false;
+                    for class in classes {
+                        match class {
+                            Value::ExceptionType(base) => {
+                                matches |= builtins::is_subclass(&raised.exception.typ, &base);
+                            }
+                            _ => {
+                                return Err(exception(
+                                    "TypeError",
+                                    "catching classes that do not inherit from BaseException is not allowed",
+                                )
+                                .into())
+                            }
+                        }
+                    }
+                    matches
+                }
+            };
+            if matches {
+                if let Some(name) = &handler.name {
+                    scope::assign(scope, name, Value::Exception(raised.exception.clone()));
+                }
+                self.handling.push(raised.exception.clone());
+                let result = self.exec_block(&handler.body, scope);
+                self.handling.pop();
+                return result;
+            }
+        }
+        Err(raised)
+    }
+
+    fn augmented(&mut self, op: &crate::ast::Operator, current: &Value, operand: Value) -> Exec<Value> {
+        // `list += iterable` extends the list in place
+        if let (crate::ast::Operator::Add, Value::List(items)) = (op, current) {
+            let extra: Vec<Value> = iterate(&operand)?.collect();
+            items.borrow_mut().extend(extra);
+            return Ok(current.clone());
+        }
+        Ok(binary_op(op, current, &operand)?)
+    }
+
+    fn make_function(
+        &mut self,
+        name: &str,
+        params: &[Parameter],
+        body: FunctionBody,
+        scope: &Rc<RefCell<Scope>>,
+    ) -> Exec<Value> {
+        let mut defaults = Vec::with_capacity(params.len());
+        for param in params {
+            defaults.push(match &param.default {
+                Some(default) => Some(self.eval(default, scope)?),
+                None => None,
+            });
+        }
+        Ok(Value::Function(Rc::new(Function {
+            name: name.to_string(),
+            params: params.to_vec(),
+            defaults,
+            body,
+            closure: scope.clone(),
+        })))
+    }
+
+    fn assign(&mut self, target: &Expr, value: Value, scope: &Rc<RefCell<Scope>>) -> Exec<()> {
+        match target {
+            Expr::Name { id, .. } => {
+                scope::assign(scope, id, value);
+                Ok(())
+            }
+            Expr::Tuple { elts, .. } | Expr::List { elts, .. } => {
+                let items: Vec<Value> = iterate(&value)?.collect();
+                let starred = elts.iter().position(|elt| matches!(**elt, Expr::Starred { .. }));
+                match starred {
+                    None => {
+                        if items.len() != elts.len() {
+                            return Err(unpack_error(elts.len(), items.len()).into());
+                        }
+                        for (elt, item) in elts.iter().zip(items) {
+                            self.assign(elt, item, scope)?;
+                        }
+                    }
+                    Some(star) => {
+                        let after = elts.len() - star - 1;
+                        if items.len() < elts.len() - 1 {
+                            return Err(exception(
+                                "ValueError",
+                                format!(
+                                    "not enough values to unpack (expected at least {}, got {})",
+                                    elts.len() - 1,
+                                    items.len()
+                                ),
+                            )
+                            .into());
+                        }
+                        let rest_end = items.len() - after;
+                        for (elt, item) in elts[..star].iter().zip(&items[..star]) {
+                            self.assign(elt, item.clone(), scope)?;
+                        }
+                        if let Expr::Starred { value: star_target, .. } = &*elts[star] {
+                            self.assign(star_target, Value::list(items[star..rest_end].to_vec()), scope)?;
+                        }
+                        for (elt, item) in elts[star + 1..].iter().zip(&items[rest_end..]) {
+                            self.assign(elt, item.clone(), scope)?;
+                        }
+                    }
+                }
+                Ok(())
+            }
+            Expr::Subscript { value: container, slice, .. } => {
+                let container = self.eval(container, scope)?;
+                let index = self.eval(slice, scope)?;
+                self.store_item(&container, index, value)
+            }
+            Expr::Attribute { .. } => Err(not_supported("attribute assignment is")),
+            _ => Err(exception("SyntaxError", "cannot assign to expression").into()),
+        }
+    }
+
+    fn store_item(&mut self, container: &Value, index: Value, value: Value) -> Exec<()> {
+        match container {
+            Value::List(items) => {
+                let mut items = items.borrow_mut();
+                let i = int_index(&index, "list")?;
+                let position = normalize_index(i, items.len(), "list assignment")?;
+                items[position] = value;
+                Ok(())
+            }
+            Value::Dict(dict) => Ok(builtins::dict_insert(&mut dict.borrow_mut(), index, value)?),
+            other => Err(exception(
+                "TypeError",
+                format!("'{}' object does not support item assignment", other.type_name()),
+            )
+            .into()),
+        }
+    }
+
+    fn delete(&mut self, target: &Expr, scope: &Rc<RefCell<Scope>>) -> Exec<()> {
+        match target {
+            Expr::Name { id, .. } => {
+                if !scope::delete(scope, id) {
+                    return Err(exception("NameError", format!("name '{}' is not defined", id)).into());
+                }
+                Ok(())
+            }
+            Expr::Subscript { value, slice, .. } => {
+                let container = self.eval(value, scope)?;
+                let index = self.eval(slice, scope)?;
+                match &container {
+                    Value::List(items) => {
+                        let mut items = items.borrow_mut();
+                        let i = int_index(&index, "list")?;
+                        let position = normalize_index(i, items.len(), "list assignment")?;
+                        items.remove(position);
+                        Ok(())
+                    }
+                    Value::Dict(dict) => {
+                        let hash = index.hash_key().ok_or_else(|| {
+                            exception("TypeError", format!("unhashable type: '{}'", index.type_name()))
+                        })?;
+                        match dict.borrow_mut().remove(&hash) {
+                            Some(_) => Ok(()),
+                            None => Err(exception("KeyError", index.repr()).into()),
+                        }
+                    }
+                    other => Err(exception(
+                        "TypeError",
+                        format!("'{}' object does not support item deletion", other.type_name()),
+                    )
+                    .into()),
+                }
+            }
+            Expr::Tuple { elts, .. } | Expr::List { elts, .. } => {
+                for elt in elts {
+                    self.delete(elt, scope)?;
+                }
+                Ok(())
+            }
+            _ => Err(exception("SyntaxError", "cannot delete expression").into()),
+        }
+    }
+
+    fn load_name(&self, name: &str, scope: &Rc<RefCell<Scope>>) -> Exec<Value> {
+        scope::lookup(scope, name)
+            .or_else(|| builtins::lookup(name))
+            .ok_or_else(|| exception("NameError", format!("name '{}' is not defined", name)).into())
+    }
+
+    fn eval(&mut self, expr: &Expr, scope: &Rc<RefCell<Scope>>) -> Exec<Value> {
+        match expr {
+            Expr::BoolOp { op, values, .. } => {
+                let mut result = Value::None;
+                for value in values {
+                    result = self.eval(value, scope)?;
+                    let done = match op {
+                        BoolOperator::And => !result.is_truthy(),
+                        BoolOperator::Or => result.is_truthy(),
+                    };
+                    if done {
+                        break;
+                    }
+                }
+                Ok(result)
+            }
+            Expr::BinOp { left, op, right, .. } => {
+                let left = self.eval(left, scope)?;
+                let right = self.eval(right, scope)?;
+                Ok(binary_op(op, &left, &right)?)
+            }
+            Expr::UnaryOp { op, operand, .. } => {
+                let operand = self.eval(operand, scope)?;
+                Ok(unary_op(op, &operand)?)
+            }
+            Expr::Lambda { args, body, .. } => {
+                self.make_function("<lambda>", args, FunctionBody::Lambda(body.clone()), scope)
+            }
+            Expr::IfExp { test, body, orelse, .. } => {
+                if self.eval(test, scope)?.is_truthy() {
+                    self.eval(body, scope)
+                } else {
+                    self.eval(orelse, scope)
+                }
+            }
+            Expr::Dict { keys, values, .. } => {
+                let mut dict = Dict::new();
+                for (key, value) in keys.iter().zip(values) {
+                    let value = self.eval(value, scope)?;
+                    match key {
+                        Some(key) => {
+                            let key = self.eval(key, scope)?;
+                            builtins::dict_insert(&mut dict, key, value)?;
+                        }
+                        // `**mapping`
+                        None => match value {
+                            Value::Dict(other) => {
+                                for (key, value) in other.borrow().items() {
+                                    builtins::dict_insert(&mut dict, key.clone(), value.clone())?;
+                                }
+                            }
+                            other => {
+                                return Err(exception(
+                                    "TypeError",
+                                    format!("'{}' object is not a mapping", other.type_name()),
+                                )
+                                .into())
+                            }
+                        },
+                    }
+                }
+                Ok(Value::dict(dict))
+            }
+            Expr::Set { elts, .. } => {
+                let mut set = Dict::new();
+                for item in self.eval_elements(elts, scope)? {
+                    builtins::set_add(&mut set, item)?;
+                }
+                Ok(Value::set(set))
+            }
+            Expr::List { elts, .. } => Ok(Value::list(self.eval_elements(elts, scope)?)),
+            Expr::Tuple { elts, .. } => Ok(Value::tuple(self.eval_elements(elts, scope)?)),
+            Expr::ListComp { elt, generators, .. } | Expr::GeneratorExp { elt, generators, .. } => {
+                let mut items = Vec::new();
+                self.comprehend(generators, &Scope::child(scope), &mut |interp, scope| {
+                    items.push(interp.eval(elt, scope)?);
+                    Ok(())
+                })?;
+                Ok(Value::list(items))
+            }
+            Expr::SetComp { elt, generators, .. } => {
+                let mut set = Dict::new();
+                self.comprehend(generators, &Scope::child(scope), &mut |interp, scope| {
+                    let item = interp.eval(elt, scope)?;
+                    Ok(builtins::set_add(&mut set, item)?)
+                })?;
+                Ok(Value::set(set))
+            }
+            Expr::DictComp { key, value, generators, .. } => {
+                let mut dict = Dict::new();
+                self.comprehend(generators, &Scope::child(scope), &mut |interp, scope| {
+                    let key = interp.eval(key, scope)?;
+                    let value = interp.eval(value, scope)?;
+                    Ok(builtins::dict_insert(&mut dict, key, value)?)
+                })?;
+                Ok(Value::dict(dict))
+            }
+            Expr::Await { .. } => Err(not_supported("await is")),
+            Expr::Yield { .. } | Expr::YieldFrom { .. } => Err(not_supported("generators are")),
+            Expr::Compare {
+                left,
+                ops,
+                comparators,
+                ..
+            } => {
+                let mut left = self.eval(left, scope)?;
+                for (op, right) in ops.iter().zip(comparators) {
+                    let right = self.eval(right, scope)?;
+                    if !compare(op, &left, &right)? {
+                        return Ok(Value::Bool(false));
+                    }
+                    left = right;
+                }
+                Ok(Value::Bool(true))
+            }
+            Expr::Call { func, args, keywords, .. } => {
+                let function = self.eval(func, scope)?;
+                let positional = self.eval_elements(args, scope)?;
+                let mut keyword_args = Vec::with_capacity(keywords.len());
+                for (name, value) in keywords {
+                    let value = self.eval(value, scope)?;
+                    match (name, value) {
+                        (Some(name), value) => keyword_args.push((name.clone(), value)),
+                        // `**mapping`
+                        (None, Value::Dict(dict)) => {
+                            for (key, value) in dict.borrow().items() {
+                                let Value::Str(key) = key else {
+                                    return Err(exception("TypeError", "keywords must be strings").into());
+                                };
+                                keyword_args.push((key.to_string(), value.clone()));
+                            }
+                        }
+                        (None, other) => {
+                            return Err(exception(
+                                "TypeError",
+                                format!("argument after ** must be a mapping, not {}", other.type_name()),
+                            )
+                            .into())
+                        }
+                    }
+                }
+                self.call_value(&function, positional, keyword_args)
+            }
+            Expr::Num { value, .. } | Expr::Constant { value: Constant::Num(value), .. } => match value {
+                Number::Integer(i) => Ok(Value::Int(*i)),
+                Number::Float(f) => Ok(Value::Float(*f)),
+                Number::Complex { .. } => Err(not_supported("complex numbers are")),
+            },
+            Expr::Str { value, .. } | Expr::Constant { value: Constant::Str(value), .. } => Ok(Value::str(value)),
+            Expr::JoinedStr { values, .. } => {
+                let mut text = String::new();
+                for value in values {
+                    text.push_str(&self.eval(value, scope)?.to_string());
+                }
+                Ok(Value::str(&text))
+            }
+            Expr::FormattedValue {
+                value,
+                conversion,
+                format_spec,
+                ..
+            } => {
+                let value = self.eval(value, scope)?;
+                let value = match conversion {
+                    'r' | 'a' => Value::str(&value.repr()),
+                    's' => Value::str(&value.to_string()),
+                    _ => value,
+                };
+                let spec = match format_spec {
+                    Some(spec) => self.eval(spec, scope)?.to_string(),
+                    None => String::new(),
+                };
+                Ok(Value::str(&format::format_value(&value, &spec)?))
+            }
+            Expr::Bytes { .. } | Expr::Constant { value: Constant::Bytes(_), .. } => {
+                Err(not_supported("bytes literals are"))
+            }
+            Expr::NameConstant { value, .. } | Expr::Constant { value: Constant::NameConstant(value), .. } => {
+                Ok(match value {
+                    NameConstant::None => Value::None,
+                    NameConstant::True => Value::Bool(true),
+                    NameConstant::False => Value::Bool(false),
+                })
+            }
+            Expr::Ellipsis { .. } | Expr::Constant { value: Constant::Ellipsis, .. } => Ok(Value::None),
+            Expr::Attribute { value, attr, .. } => {
+                let receiver = self.eval(value, scope)?;
+                match &receiver {
+                    Value::Exception(e) if attr == "args" => Ok(Value::tuple(vec![Value::str(&e.message)])),
+                    _ if builtins::has_method(&receiver, attr) => Ok(Value::Method {
+                        receiver: Box::new(receiver),
+                        name: Rc::from(attr.as_str()),
+                    }),
+                    _ => Err(exception(
+                        "AttributeError",
+                        format!("'{}' object has no attribute '{}'", receiver.type_name(), attr),
+                    )
+                    .into()),
+                }
+            }
+            Expr::Subscript { value, slice, .. } => {
+                let container = self.eval(value, scope)?;
+                if let Expr::Slice { lower, upper, step, .. } = &**slice {
+                    let mut bound = |bound: &Option<Box<Expr>>| -> Exec<Option<i64>> {
+                        match bound {
+                            None => Ok(None),
+                            Some(expr) => match self.eval(expr, scope)? {
+                                Value::None => Ok(None),
+                                value => Ok(Some(int_index(&value, "slice")?)),
+                            },
+                        }
+                    };
+                    let (lower, upper, step) = (bound(lower)?, bound(upper)?, bound(step)?);
+                    return Ok(slice_value(&container, lower, upper, step)?);
+                }
+                let index = self.eval(slice, scope)?;
+                self.subscript(&container, &index)
+            }
+            Expr::Starred { .. } => Err(exception("SyntaxError", "can't use starred expression here").into()),
+            Expr::Name { id, .. } => self.load_name(id, scope),
+            Expr::NamedExpr { target, value, .. } => {
+                let value = self.eval(value, scope)?;
+                self.assign(target, value.clone(), scope)?;
+                Ok(value)
+            }
+            Expr::Slice { .. } => Err(not_supported("slice objects are")),
+        }
+    }
+
+    /// Evaluate the elements of a list, tuple or set display, or call
+    /// arguments, unpacking `*iterable`
+    fn eval_elements(&mut self, elts: &[Box<Expr>], scope: &Rc<RefCell<Scope>>) -> Exec<Vec<Value>> {
+        let mut items = Vec::with_capacity(elts.len());
+        for elt in elts {
+            match &**elt {
+                Expr::Starred { value, .. } => {
+                    let iterable = self.eval(value, scope)?;
+                    items.extend(iterate(&iterable)?);
+                }
+                elt => items.push(self.eval(elt, scope)?),
+            }
+        }
+        Ok(items)
+    }
+
+    /// Run `each` for every binding produced by the `for`/`if` clauses of a comprehension
+    fn comprehend(
+        &mut self,
+        generators: &[Comprehension],
+        scope: &Rc<RefCell<Scope>>,
+        each: &mut ComprehensionBody<'_>,
+    ) -> Exec<()> {
+        let Some((generator, rest)) = generators.split_first() else {
+            return each(self, scope);
+        };
+        let iterable = self.eval(&generator.iter, scope)?;
+        'items: for item in iterate(&iterable)? {
+            self.assign(&generator.target, item, scope)?;
+            for condition in &generator.ifs {
+                if !self.eval(condition, scope)?.is_truthy() {
+                    continue 'items;
+                }
+            }
+            self.comprehend(rest, scope, each)?;
+        }
+        Ok(())
+    }
+
+    fn subscript(&mut self, container: &Value, index: &Value) -> Exec<Value> {
+        let item = match container {
+            Value::List(items) => {
+                let items = items.borrow();
+                let position = normalize_index(int_index(index, "list")?, items.len(), "list")?;
+                items[position].clone()
+            }
+            Value::Tuple(items) => {
+                let position = normalize_index(int_index(index, "tuple")?, items.len(), "tuple")?;
+                items[position].clone()
+            }
+            Value::Str(s) => {
+                let len = s.chars().count();
+                let position = normalize_index(int_index(index, "string")?, len, "string")?;
+                let c = s.chars().nth(position).unwrap_or_default();
+                Value::str(c.encode_utf8(&mut [0; 4]))
+            }
+            Value::Range { start, stop, step } => {
+                let len = value::range_len(*start, *stop, *step) as usize;
+                let position = normalize_index(int_index(index, "range")?, len, "range object")?;
+                Value::Int(start + position as i64 * step)
+            }
+            Value::Dict(dict) => builtins::dict_get(&dict.borrow(), index)?,
+            other => {
+                return Err(exception(
+                    "TypeError",
+                    format!("'{}' object is not subscriptable", other.type_name()),
+                )
+                .into())
+            }
+        };
+        Ok(item)
+    }
+
+    /// Call a function, builtin, method or exception class
+    fn call_value(&mut self, function: &Value, args: Vec<Value>, keywords: Vec<(String, Value)>) -> Exec<Value> {
+        match function {
+            Value::Function(function) => self.call_function(function, args, keywords),
+            Value::Builtin(name) => builtins::call_builtin(self, name, args, keywords),
+            Value::Method { receiver, name } => builtins::call_method(self, receiver, name, args, keywords),
+            Value::ExceptionType(typ) => {
+                let message = match args.as_slice() {
+                    [] => String::new(),
+                    [message] => message.to_string(),
+                    args => Value::tuple(args.to_vec()).to_string(),
+                };
+                Ok(Value::Exception(Rc::new(Exception {
+                    typ: typ.to_string(),
+                    message,
+                })))
+            }
+            other => Err(exception(
+                "TypeError",
+                format!("'{}' object is not callable", other.type_name()),
+            )
+            .into()),
+        }
+    }
+
+    fn call_function(
+        &mut self,
+        function: &Rc<Function>,
+        args: Vec<Value>,
+        keywords: Vec<(String, Value)>,
+    ) -> Exec<Value> {
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(exception("RecursionError", "maximum recursion depth exceeded").into());
+        }
+        let scope = Scope::child(&function.closure);
+        self.bind_arguments(function, args, keywords, &scope)?;
+
+        self.frames.push(Frame {
+            function: function.name.clone(),
+            line: 0,
+        });
+        let result = match &function.body {
+            FunctionBody::Block(body) => self.exec_block(body, &scope).map(|flow| match flow {
+                Flow::Return(value) => value,
+                _ => Value::None,
+            }),
+            FunctionBody::Lambda(body) => self.eval(body, &scope).map_err(|mut raised| {
+                if raised.frames.is_none() {
+                    raised.frames = Some(self.frames.clone());
+                }
+                raised
+            }),
+        };
+        self.frames.pop();
+        result
+    }
+
+    /// Bind call arguments to the parameters of `function` in its new scope
+    fn bind_arguments(
+        &mut self,
+        function: &Function,
+        args: Vec<Value>,
+        keywords: Vec<(String, Value)>,
+        scope: &Rc<RefCell<Scope>>,
+    ) -> OpResult<()> {
+        let name = &function.name;
+        let positional_params: Vec<usize> = (0..function.params.len())
+            .filter(|&i| !function.params[i].is_vararg && !function.params[i].is_kwarg)
+            .collect();
+        let vararg = function.params.iter().find(|param| param.is_vararg);
+        let kwarg = function.params.iter().find(|param| param.is_kwarg);
+
+        let mut bound: Vec<Option<Value>> = vec![None; function.params.len()];
+        let mut args = args.into_iter();
+        for &i in &positional_params {
+            match args.next() {
+                Some(arg) => bound[i] = Some(arg),
+                None => break,
+            }
+        }
+        let extra: Vec<Value> = args.collect();
+        match vararg {
+            Some(param) => scope::assign(scope, &param.name, Value::tuple(extra)),
+            None if !extra.is_empty() => {
+                return Err(exception(
+                    "TypeError",
+                    format!(
+                        "{}() takes {} positional argument{} but {} were given",
+                        name,
+                        positional_params.len(),
+                        if positional_params.len() == 1 { "" } else { "s" },
+                        positional_params.len() + extra.len()
+                    ),
+                ));
+            }
+            None => {}
+        }
+
+        let mut extra_keywords = Dict::new();
+        for (keyword, value) in keywords {
+            match positional_params.iter().find(|&&i| function.params[i].name == keyword) {
+                Some(&i) if bound[i].is_some() => {
+                    return Err(exception(
+                        "TypeError",
+                        format!("{}() got multiple values for argument '{}'", name, keyword),
+                    ));
+                }
+                Some(&i) => bound[i] = Some(value),
+                None if kwarg.is_some() => extra_keywords.insert(
+                    value::HashKey::Str(Rc::from(keyword.as_str())),
+                    Value::str(&keyword),
+                    value,
+                ),
+                None => {
+                    return Err(exception(
+                        "TypeError",
+                        format!("{}() got an unexpected keyword argument '{}'", name, keyword),
+                    ));
+                }
+            }
+        }
+        if let Some(param) = kwarg {
+            scope::assign(scope, &param.name, Value::dict(extra_keywords));
+        }
+
+        let mut missing = Vec::new();
+        for &i in &positional_params {
+            let value = match bound[i].take().or_else(|| function.defaults[i].clone()) {
+                Some(value) => value,
+                None => {
+                    missing.push(format!("'{}'", function.params[i].name));
+                    continue;
+                }
+            };
+            scope::assign(scope, &function.params[i].name, value);
+        }
+        if !missing.is_empty() {
+            return Err(exception(
+                "TypeError",
+                format!(
+                    "{}() missing {} required positional argument{}: {}",
+                    name,
+                    missing.len(),
+                    if missing.len() == 1 { "" } else { "s" },
+                    missing.join(" and ")
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn unpack_error(expected: usize, got: usize) -> Exception {
+    if got > expected {
+        exception("ValueError", format!("too many values to unpack (expected {})", expected))
+    } else {
+        exception(
+            "ValueError",
+            format!("not enough values to unpack (expected {}, got {})", expected, got),
+        )
+    }
+}
+
+/// An index into a sequence, which must be an int
+fn int_index(index: &Value, what: &str) -> OpResult<i64> {
+    index.as_int().ok_or_else(|| {
+        exception(
+            "TypeError",
+            format!("{} indices must be integers, not {}", what, index.type_name()),
+        )
+    })
+}
+
+/// `container[lower:upper:step]`
+fn slice_value(container: &Value, lower: Option<i64>, upper: Option<i64>, step: Option<i64>) -> OpResult<Value> {
+    match container {
+        Value::List(items) => {
+            let items = items.borrow();
+            let positions = slice_indices(lower, upper, step, items.len())?;
+            Ok(Value::list(positions.into_iter().map(|i| items[i].clone()).collect()))
+        }
+        Value::Tuple(items) => {
+            let positions = slice_indices(lower, upper, step, items.len())?;
+            Ok(Value::tuple(positions.into_iter().map(|i| items[i].clone()).collect()))
+        }
+        Value::Str(s) => {
+            let chars: Vec<char> = s.chars().collect();
+            let positions = slice_indices(lower, upper, step, chars.len())?;
+            Ok(Value::str(&positions.into_iter().map(|i| chars[i]).collect::<String>()))
+        }
+        other => Err(exception(
+            "TypeError",
+            format!("'{}' object is not subscriptable", other.type_name()),
+        )),
+    }
+}
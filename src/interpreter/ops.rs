@@ -0,0 +1,420 @@
+// ops.rs - Operators of the tree-walking interpreter
+//
+// Ints are 64-bit, as in compiled programs: arithmetic that leaves that range
+// raises OverflowError instead of promoting to a big integer.
+
+use std::cmp::Ordering;
+
+use crate::ast::{CmpOperator, Operator, UnaryOperator};
+
+use super::value::{range_len, Dict, Exception, Value};
+
+/// Result of an operation, or the exception it raises
+pub type OpResult<T> = Result<T, Exception>;
+
+/// An exception of type `typ`
+pub fn exception(typ: &str, message: impl Into<String>) -> Exception {
+    Exception {
+        typ: typ.to_string(),
+        message: message.into(),
+    }
+}
+
+fn overflow() -> Exception {
+    exception("OverflowError", "integer overflow")
+}
+
+fn operator_symbol(op: &Operator) -> &'static str {
+    match op {
+        Operator::Add => "+",
+        Operator::Sub => "-",
+        Operator::Mult => "*",
+        Operator::MatMult => "@",
+        Operator::Div => "/",
+        Operator::FloorDiv => "//",
+        Operator::Mod => "%",
+        Operator::Pow => "**",
+        Operator::LShift => "<<",
+        Operator::RShift => ">>",
+        Operator::BitOr => "|",
+        Operator::BitXor => "^",
+        Operator::BitAnd => "&",
+    }
+}
+
+fn unsupported(op: &Operator, left: &Value, right: &Value) -> Exception {
+    exception(
+        "TypeError",
+        format!(
+            "unsupported operand type(s) for {}: '{}' and '{}'",
+            operator_symbol(op),
+            left.type_name(),
+            right.type_name()
+        ),
+    )
+}
+
+/// Python's floor division of ints
+pub fn floor_div(a: i64, b: i64) -> OpResult<i64> {
+    if b == 0 {
+        return Err(exception("ZeroDivisionError", "integer division or modulo by zero"));
+    }
+    let q = a.checked_div(b).ok_or_else(overflow)?;
+    Ok(if a % b != 0 && ((a < 0) != (b < 0)) { q - 1 } else { q })
+}
+
+/// Python's modulo of ints: the result has the sign of the divisor
+pub fn floor_mod(a: i64, b: i64) -> OpResult<i64> {
+    if b == 0 {
+        return Err(exception("ZeroDivisionError", "integer division or modulo by zero"));
+    }
+    let r = a.checked_rem(b).unwrap_or(0);
+    Ok(if r != 0 && ((r < 0) != (b < 0)) { r + b } else { r })
+}
+
+/// `seq * count` for a sequence of `len` items
+fn repeat<T: Clone>(items: &[T], count: i64) -> Vec<T> {
+    let count = count.max(0) as usize;
+    let mut out = Vec::with_capacity(items.len() * count);
+    for _ in 0..count {
+        out.extend_from_slice(items);
+    }
+    out
+}
+
+/// Evaluate `left op right`
+pub fn binary_op(op: &Operator, left: &Value, right: &Value) -> OpResult<Value> {
+    if let (Some(a), Some(b)) = (left.as_int(), right.as_int()) {
+        if let (Value::Bool(x), Value::Bool(y), Operator::BitAnd | Operator::BitOr | Operator::BitXor) =
+            (left, right, op)
+        {
+            return Ok(Value::Bool(match op {
+                Operator::BitAnd => x & y,
+                Operator::BitOr => x | y,
+                _ => x ^ y,
+            }));
+        }
+        return int_op(op, a, b).map_err(|e| if e.typ == "TypeError" { unsupported(op, left, right) } else { e });
+    }
+    if let (Some(a), Some(b)) = (left.as_float(), right.as_float()) {
+        return float_op(op, a, b)
+            .map(Value::Float)
+            .map_err(|e| if e.typ == "TypeError" { unsupported(op, left, right) } else { e });
+    }
+
+    match (op, left, right) {
+        (Operator::Add, Value::Str(a), Value::Str(b)) => Ok(Value::str(&format!("{}{}", a, b))),
+        (Operator::Add, Value::List(a), Value::List(b)) => {
+            let mut items = a.borrow().clone();
+            items.extend(b.borrow().iter().cloned());
+            Ok(Value::list(items))
+        }
+        (Operator::Add, Value::Tuple(a), Value::Tuple(b)) => {
+            Ok(Value::tuple(a.iter().chain(b.iter()).cloned().collect()))
+        }
+        (Operator::Mult, Value::Str(s), n) | (Operator::Mult, n, Value::Str(s)) if n.as_int().is_some() => {
+            Ok(Value::str(&s.repeat(n.as_int().unwrap_or(0).max(0) as usize)))
+        }
+        (Operator::Mult, Value::List(items), n) | (Operator::Mult, n, Value::List(items)) if n.as_int().is_some() => {
+            Ok(Value::list(repeat(&items.borrow(), n.as_int().unwrap_or(0))))
+        }
+        (Operator::Mult, Value::Tuple(items), n) | (Operator::Mult, n, Value::Tuple(items)) if n.as_int().is_some() => {
+            Ok(Value::tuple(repeat(items, n.as_int().unwrap_or(0))))
+        }
+        (Operator::BitOr | Operator::BitAnd | Operator::Sub | Operator::BitXor, Value::Set(a), Value::Set(b)) => {
+            let (a, b) = (a.borrow(), b.borrow());
+            let mut result = Dict::new();
+            let mut keep = |key: &Value, present_in_other: bool| {
+                let wanted = match op {
+                    Operator::BitOr => true,
+                    Operator::BitAnd => present_in_other,
+                    _ => !present_in_other,
+                };
+                if wanted {
+                    if let Some(hash) = key.hash_key() {
+                        result.insert(hash, key.clone(), Value::None);
+                    }
+                }
+            };
+            for key in a.keys() {
+                keep(key, key.hash_key().is_some_and(|hash| b.contains(&hash)));
+            }
+            if matches!(op, Operator::BitOr | Operator::BitXor) {
+                for key in b.keys() {
+                    keep(key, key.hash_key().is_some_and(|hash| a.contains(&hash)));
+                }
+            }
+            Ok(Value::set(result))
+        }
+        _ => Err(unsupported(op, left, right)),
+    }
+}
+
+fn int_op(op: &Operator, a: i64, b: i64) -> OpResult<Value> {
+    let value = match op {
+        Operator::Add => a.checked_add(b).ok_or_else(overflow)?,
+        Operator::Sub => a.checked_sub(b).ok_or_else(overflow)?,
+        Operator::Mult => a.checked_mul(b).ok_or_else(overflow)?,
+        Operator::Div => {
+            if b == 0 {
+                return Err(exception("ZeroDivisionError", "division by zero"));
+            }
+            return Ok(Value::Float(a as f64 / b as f64));
+        }
+        Operator::FloorDiv => floor_div(a, b)?,
+        Operator::Mod => floor_mod(a, b)?,
+        Operator::Pow => {
+            if b < 0 {
+                if a == 0 {
+                    return Err(exception(
+                        "ZeroDivisionError",
+                        "0.0 cannot be raised to a negative power",
+                    ));
+                }
+                return Ok(Value::Float((a as f64).powf(b as f64)));
+            }
+            let exponent = u32::try_from(b).map_err(|_| overflow())?;
+            a.checked_pow(exponent).ok_or_else(overflow)?
+        }
+        Operator::LShift | Operator::RShift if b < 0 => {
+            return Err(exception("ValueError", "negative shift count"));
+        }
+        Operator::LShift => {
+            if a == 0 {
+                0
+            } else if b >= 64 {
+                return Err(overflow());
+            } else {
+                let shifted = a << b;
+                if shifted >> b != a {
+                    return Err(overflow());
+                }
+                shifted
+            }
+        }
+        Operator::RShift => a >> b.min(63),
+        Operator::BitOr => a | b,
+        Operator::BitXor => a ^ b,
+        Operator::BitAnd => a & b,
+        Operator::MatMult => return Err(exception("TypeError", "")),
+    };
+    Ok(Value::Int(value))
+}
+
+fn float_op(op: &Operator, a: f64, b: f64) -> OpResult<f64> {
+    match op {
+        Operator::Add => Ok(a + b),
+        Operator::Sub => Ok(a - b),
+        Operator::Mult => Ok(a * b),
+        Operator::Div if b == 0.0 => Err(exception("ZeroDivisionError", "float division by zero")),
+        Operator::Div => Ok(a / b),
+        Operator::FloorDiv if b == 0.0 => Err(exception("ZeroDivisionError", "float floor division by zero")),
+        Operator::FloorDiv => Ok((a / b).floor()),
+        Operator::Mod if b == 0.0 => Err(exception("ZeroDivisionError", "float modulo")),
+        Operator::Mod => {
+            let r = a % b;
+            Ok(if r != 0.0 && ((r < 0.0) != (b < 0.0)) { r + b } else { r })
+        }
+        Operator::Pow if a == 0.0 && b < 0.0 => Err(exception(
+            "ZeroDivisionError",
+            "0.0 cannot be raised to a negative power",
+        )),
+        Operator::Pow => Ok(a.powf(b)),
+        _ => Err(exception("TypeError", "")),
+    }
+}
+
+/// Evaluate `op operand`
+pub fn unary_op(op: &UnaryOperator, operand: &Value) -> OpResult<Value> {
+    match (op, operand) {
+        (UnaryOperator::Not, value) => Ok(Value::Bool(!value.is_truthy())),
+        (UnaryOperator::USub, Value::Float(f)) => Ok(Value::Float(-f)),
+        (UnaryOperator::UAdd, Value::Float(f)) => Ok(Value::Float(*f)),
+        (UnaryOperator::USub, value) if value.as_int().is_some() => {
+            Ok(Value::Int(value.as_int().unwrap_or(0).checked_neg().ok_or_else(overflow)?))
+        }
+        (UnaryOperator::UAdd, value) if value.as_int().is_some() => Ok(Value::Int(value.as_int().unwrap_or(0))),
+        (UnaryOperator::Invert, value) if value.as_int().is_some() => Ok(Value::Int(!value.as_int().unwrap_or(0))),
+        (op, value) => {
+            let symbol = match op {
+                UnaryOperator::Invert => "~",
+                UnaryOperator::UAdd => "+",
+                _ => "-",
+            };
+            Err(exception(
+                "TypeError",
+                format!("bad operand type for unary {}: '{}'", symbol, value.type_name()),
+            ))
+        }
+    }
+}
+
+/// Order of two values, for `<` and friends and for sorting
+pub fn compare_values(left: &Value, right: &Value) -> OpResult<Ordering> {
+    let unorderable = || {
+        exception(
+            "TypeError",
+            format!(
+                "'<' not supported between instances of '{}' and '{}'",
+                left.type_name(),
+                right.type_name()
+            ),
+        )
+    };
+    if let (Some(a), Some(b)) = (left.as_int(), right.as_int()) {
+        return Ok(a.cmp(&b));
+    }
+    if let (Some(a), Some(b)) = (left.as_float(), right.as_float()) {
+        return a.partial_cmp(&b).ok_or_else(unorderable);
+    }
+    match (left, right) {
+        (Value::Str(a), Value::Str(b)) => Ok(a.cmp(b)),
+        (Value::List(a), Value::List(b)) => compare_sequences(&a.borrow(), &b.borrow()),
+        (Value::Tuple(a), Value::Tuple(b)) => compare_sequences(a, b),
+        _ => Err(unorderable()),
+    }
+}
+
+fn compare_sequences(a: &[Value], b: &[Value]) -> OpResult<Ordering> {
+    for (x, y) in a.iter().zip(b) {
+        if x != y {
+            return compare_values(x, y);
+        }
+    }
+    Ok(a.len().cmp(&b.len()))
+}
+
+/// `item in container`
+pub fn contains(container: &Value, item: &Value) -> OpResult<bool> {
+    match container {
+        Value::Str(s) => match item {
+            Value::Str(sub) => Ok(s.contains(&**sub)),
+            other => Err(exception(
+                "TypeError",
+                format!("'in <string>' requires string as left operand, not {}", other.type_name()),
+            )),
+        },
+        Value::List(items) => Ok(items.borrow().contains(item)),
+        Value::Tuple(items) => Ok(items.contains(item)),
+        Value::Dict(dict) | Value::Set(dict) => match item.hash_key() {
+            Some(hash) => Ok(dict.borrow().contains(&hash)),
+            None => Err(exception("TypeError", format!("unhashable type: '{}'", item.type_name()))),
+        },
+        Value::Range { start, stop, step } => Ok(match item.as_int() {
+            Some(i) => {
+                let in_bounds = if *step > 0 { *start <= i && i < *stop } else { *stop < i && i <= *start };
+                in_bounds && (i - start) % step == 0 && range_len(*start, *stop, *step) > 0
+            }
+            None => false,
+        }),
+        other => Err(exception(
+            "TypeError",
+            format!("argument of type '{}' is not iterable", other.type_name()),
+        )),
+    }
+}
+
+/// The values a `for` loop over `value` visits. Ranges are iterated lazily;
+/// other containers are snapshotted, so the loop body may modify them.
+pub fn iterate(value: &Value) -> OpResult<Box<dyn Iterator<Item = Value>>> {
+    Ok(match value {
+        Value::Range { start, stop, step } => {
+            let (start, step) = (*start, *step);
+            Box::new((0..range_len(start, *stop, step)).map(move |i| Value::Int(start + i * step)))
+        }
+        Value::Str(s) => {
+            let chars: Vec<Value> = s.chars().map(|c| Value::str(c.encode_utf8(&mut [0; 4]))).collect();
+            Box::new(chars.into_iter())
+        }
+        Value::List(items) => Box::new(items.borrow().clone().into_iter()),
+        Value::Tuple(items) => {
+            let items = items.clone();
+            Box::new((0..items.len()).map(move |i| items[i].clone()))
+        }
+        Value::Dict(dict) | Value::Set(dict) => Box::new(dict.borrow().keys().cloned().collect::<Vec<_>>().into_iter()),
+        other => {
+            return Err(exception(
+                "TypeError",
+                format!("'{}' object is not iterable", other.type_name()),
+            ))
+        }
+    })
+}
+
+/// Evaluate one comparison of a chain such as `a < b < c`
+pub fn compare(op: &CmpOperator, left: &Value, right: &Value) -> OpResult<bool> {
+    Ok(match op {
+        CmpOperator::Eq => left == right,
+        CmpOperator::NotEq => left != right,
+        CmpOperator::Lt => compare_values(left, right)? == Ordering::Less,
+        CmpOperator::LtE => compare_values(left, right)? != Ordering::Greater,
+        CmpOperator::Gt => compare_values(left, right)? == Ordering::Greater,
+        CmpOperator::GtE => compare_values(left, right)? != Ordering::Less,
+        CmpOperator::Is => is_same(left, right),
+        CmpOperator::IsNot => !is_same(left, right),
+        CmpOperator::In => contains(right, left)?,
+        CmpOperator::NotIn => !contains(right, left)?,
+    })
+}
+
+/// `left is right`: identity for shared objects, equality for immutable scalars
+fn is_same(left: &Value, right: &Value) -> bool {
+    use std::rc::Rc;
+    match (left, right) {
+        (Value::None, Value::None) => true,
+        (Value::Bool(a), Value::Bool(b)) => a == b,
+        (Value::Int(a), Value::Int(b)) => a == b,
+        (Value::Str(a), Value::Str(b)) => Rc::ptr_eq(a, b) || a == b,
+        (Value::List(a), Value::List(b)) => Rc::ptr_eq(a, b),
+        (Value::Tuple(a), Value::Tuple(b)) => Rc::ptr_eq(a, b),
+        (Value::Dict(a), Value::Dict(b)) | (Value::Set(a), Value::Set(b)) => Rc::ptr_eq(a, b),
+        (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+        (Value::Exception(a), Value::Exception(b)) => Rc::ptr_eq(a, b),
+        (Value::Builtin(a), Value::Builtin(b)) => a == b,
+        (Value::ExceptionType(a), Value::ExceptionType(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Position of `index` in a sequence of `len` items, counting negative
+/// indices from the end
+pub fn normalize_index(index: i64, len: usize, what: &str) -> OpResult<usize> {
+    let len = len as i64;
+    let position = if index < 0 { index + len } else { index };
+    if position < 0 || position >= len {
+        return Err(exception("IndexError", format!("{} index out of range", what)));
+    }
+    Ok(position as usize)
+}
+
+/// Positions selected by `[lower:upper:step]` in a sequence of `len` items
+pub fn slice_indices(lower: Option<i64>, upper: Option<i64>, step: Option<i64>, len: usize) -> OpResult<Vec<usize>> {
+    let step = step.unwrap_or(1);
+    if step == 0 {
+        return Err(exception("ValueError", "slice step cannot be zero"));
+    }
+    let len = len as i64;
+    let clamp = |bound: i64, low: i64, high: i64| {
+        let bound = if bound < 0 { bound + len } else { bound };
+        bound.clamp(low, high)
+    };
+    let mut positions = Vec::new();
+    if step > 0 {
+        let start = lower.map_or(0, |b| clamp(b, 0, len));
+        let stop = upper.map_or(len, |b| clamp(b, 0, len));
+        let mut i = start;
+        while i < stop {
+            positions.push(i as usize);
+            i += step;
+        }
+    } else {
+        let start = lower.map_or(len - 1, |b| clamp(b, -1, len - 1));
+        let stop = upper.map_or(-1, |b| clamp(b, -1, len - 1));
+        let mut i = start;
+        while i > stop {
+            positions.push(i as usize);
+            i += step;
+        }
+    }
+    Ok(positions)
+}
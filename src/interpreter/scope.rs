@@ -0,0 +1,99 @@
+// scope.rs - Variable scopes of the tree-walking interpreter
+//
+// Each function call gets a scope whose parent is the scope the function was
+// defined in, so closures see their enclosing variables. Assignments bind in
+// the innermost scope unless the name was declared `global` (bound in the
+// module scope) or `nonlocal` (bound in the nearest enclosing scope defining it).
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use super::value::Value;
+
+#[derive(Debug, Default)]
+pub struct Scope {
+    vars: HashMap<String, Value>,
+    parent: Option<Rc<RefCell<Scope>>>,
+    globals: HashSet<String>,
+    nonlocals: HashSet<String>,
+}
+
+impl Scope {
+    /// The module scope
+    pub fn module() -> Rc<RefCell<Scope>> {
+        Rc::new(RefCell::new(Scope::default()))
+    }
+
+    /// A function call scope enclosed by `parent`
+    pub fn child(parent: &Rc<RefCell<Scope>>) -> Rc<RefCell<Scope>> {
+        Rc::new(RefCell::new(Scope {
+            parent: Some(parent.clone()),
+            ..Scope::default()
+        }))
+    }
+
+    pub fn declare_global(&mut self, name: &str) {
+        self.globals.insert(name.to_string());
+    }
+
+    pub fn declare_nonlocal(&mut self, name: &str) {
+        self.nonlocals.insert(name.to_string());
+    }
+}
+
+/// Value of `name` in `scope` or any scope enclosing it
+pub fn lookup(scope: &Rc<RefCell<Scope>>, name: &str) -> Option<Value> {
+    let mut current = scope.clone();
+    loop {
+        let next = {
+            let scope = current.borrow();
+            if let Some(value) = scope.vars.get(name) {
+                return Some(value.clone());
+            }
+            scope.parent.clone()?
+        };
+        current = next;
+    }
+}
+
+/// Scope an assignment to `name` in `scope` binds in
+fn binding_scope(scope: &Rc<RefCell<Scope>>, name: &str) -> Rc<RefCell<Scope>> {
+    let (is_global, is_nonlocal) = {
+        let inner = scope.borrow();
+        (inner.globals.contains(name), inner.nonlocals.contains(name))
+    };
+    if is_global {
+        let mut current = scope.clone();
+        loop {
+            let parent = current.borrow().parent.clone();
+            match parent {
+                Some(parent) => current = parent,
+                None => return current,
+            }
+        }
+    }
+    if is_nonlocal {
+        let mut current = scope.borrow().parent.clone();
+        while let Some(candidate) = current {
+            if candidate.borrow().vars.contains_key(name) {
+                return candidate;
+            }
+            current = candidate.borrow().parent.clone();
+        }
+    }
+    scope.clone()
+}
+
+/// Bind `name` to `value`
+pub fn assign(scope: &Rc<RefCell<Scope>>, name: &str, value: Value) {
+    binding_scope(scope, name)
+        .borrow_mut()
+        .vars
+        .insert(name.to_string(), value);
+}
+
+/// Unbind `name`, returning whether it was bound
+pub fn delete(scope: &Rc<RefCell<Scope>>, name: &str) -> bool {
+    binding_scope(scope, name).borrow_mut().vars.remove(name).is_some()
+}
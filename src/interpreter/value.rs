@@ -0,0 +1,344 @@
+// value.rs - Runtime values of the tree-walking interpreter
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::ast::{Expr, Parameter, Stmt};
+use crate::compiler::runtime::number_format::format_float;
+
+use super::scope::Scope;
+
+/// A value of a running program. Lists, dicts and sets are shared by reference,
+/// like Python objects; everything else is immutable.
+#[derive(Debug, Clone)]
+pub enum Value {
+    None,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(Rc<str>),
+    List(Rc<RefCell<Vec<Value>>>),
+    Tuple(Rc<Vec<Value>>),
+    Dict(Rc<RefCell<Dict>>),
+    Set(Rc<RefCell<Dict>>),
+    Range { start: i64, stop: i64, step: i64 },
+    Function(Rc<Function>),
+    Builtin(&'static str),
+    /// A method of a built-in type, bound to the object it was looked up on
+    Method { receiver: Box<Value>, name: Rc<str> },
+    /// An exception class, e.g. `ValueError`
+    ExceptionType(Rc<str>),
+    Exception(Rc<Exception>),
+}
+
+/// A raised or constructed exception
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exception {
+    pub typ: String,
+    pub message: String,
+}
+
+/// A function defined with `def` or `lambda`
+#[derive(Debug)]
+pub struct Function {
+    pub name: String,
+    pub params: Vec<Parameter>,
+    /// Default values, evaluated once when the function was defined
+    pub defaults: Vec<Option<Value>>,
+    pub body: FunctionBody,
+    /// Scope the function was defined in, for closures
+    pub closure: Rc<RefCell<Scope>>,
+}
+
+#[derive(Debug)]
+pub enum FunctionBody {
+    Block(Vec<Box<Stmt>>),
+    Lambda(Box<Expr>),
+}
+
+/// Key of a dict or set entry. Equal numbers hash alike, as in Python, so
+/// `1`, `1.0` and `True` are the same key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HashKey {
+    None,
+    Int(i64),
+    Float(u64),
+    Str(Rc<str>),
+    Tuple(Vec<HashKey>),
+}
+
+/// Insertion-ordered mapping used for dicts and sets
+#[derive(Debug, Clone, Default)]
+pub struct Dict {
+    entries: Vec<(Value, Value)>,
+    index: HashMap<HashKey, usize>,
+}
+
+impl Dict {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &HashKey) -> Option<&Value> {
+        self.index.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    pub fn contains(&self, key: &HashKey) -> bool {
+        self.index.contains_key(key)
+    }
+
+    pub fn insert(&mut self, hash: HashKey, key: Value, value: Value) {
+        match self.index.get(&hash) {
+            Some(&i) => self.entries[i].1 = value,
+            None => {
+                self.index.insert(hash, self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &HashKey) -> Option<Value> {
+        let i = self.index.remove(key)?;
+        let (_, value) = self.entries.remove(i);
+        for position in self.index.values_mut() {
+            if *position > i {
+                *position -= 1;
+            }
+        }
+        Some(value)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index.clear();
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Value> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = &(Value, Value)> {
+        self.entries.iter()
+    }
+}
+
+impl Value {
+    pub fn str(text: &str) -> Self {
+        Value::Str(Rc::from(text))
+    }
+
+    pub fn list(items: Vec<Value>) -> Self {
+        Value::List(Rc::new(RefCell::new(items)))
+    }
+
+    pub fn tuple(items: Vec<Value>) -> Self {
+        Value::Tuple(Rc::new(items))
+    }
+
+    pub fn dict(dict: Dict) -> Self {
+        Value::Dict(Rc::new(RefCell::new(dict)))
+    }
+
+    pub fn set(set: Dict) -> Self {
+        Value::Set(Rc::new(RefCell::new(set)))
+    }
+
+    /// Python's name for the value's type
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::None => "NoneType",
+            Value::Bool(_) => "bool",
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::Str(_) => "str",
+            Value::List(_) => "list",
+            Value::Tuple(_) => "tuple",
+            Value::Dict(_) => "dict",
+            Value::Set(_) => "set",
+            Value::Range { .. } => "range",
+            Value::Function(_) => "function",
+            Value::Builtin(_) | Value::Method { .. } => "builtin_function_or_method",
+            Value::ExceptionType(_) => "type",
+            Value::Exception(_) => "Exception",
+        }
+    }
+
+    /// Truth value, as tested by `if` and `while`
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::None => false,
+            Value::Bool(b) => *b,
+            Value::Int(i) => *i != 0,
+            Value::Float(f) => *f != 0.0,
+            Value::Str(s) => !s.is_empty(),
+            Value::List(items) => !items.borrow().is_empty(),
+            Value::Tuple(items) => !items.is_empty(),
+            Value::Dict(dict) | Value::Set(dict) => !dict.borrow().is_empty(),
+            Value::Range { start, stop, step } => range_len(*start, *stop, *step) > 0,
+            _ => true,
+        }
+    }
+
+    /// Dict key for the value, or None if it is unhashable
+    pub fn hash_key(&self) -> Option<HashKey> {
+        Some(match self {
+            Value::None => HashKey::None,
+            Value::Bool(b) => HashKey::Int(*b as i64),
+            Value::Int(i) => HashKey::Int(*i),
+            Value::Float(f) if f.fract() == 0.0 && f.abs() < 9.2e18 => HashKey::Int(*f as i64),
+            Value::Float(f) => HashKey::Float(f.to_bits()),
+            Value::Str(s) => HashKey::Str(s.clone()),
+            Value::Tuple(items) => {
+                HashKey::Tuple(items.iter().map(Value::hash_key).collect::<Option<Vec<_>>>()?)
+            }
+            _ => return None,
+        })
+    }
+
+    /// The value as an int, if it is an int or bool
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Value::Int(i) => Some(*i),
+            Value::Bool(b) => Some(*b as i64),
+            _ => None,
+        }
+    }
+
+    /// The value as a float, if it is a number
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Float(f) => Some(*f),
+            Value::Int(i) => Some(*i as f64),
+            Value::Bool(b) => Some(*b as i64 as f64),
+            _ => None,
+        }
+    }
+
+    /// repr() of the value
+    pub fn repr(&self) -> String {
+        match self {
+            Value::Str(s) => repr_str(s),
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::None, Value::None) => true,
+            (Value::Str(a), Value::Str(b)) => a == b,
+            (Value::List(a), Value::List(b)) => Rc::ptr_eq(a, b) || *a.borrow() == *b.borrow(),
+            (Value::Tuple(a), Value::Tuple(b)) => a == b,
+            (Value::Dict(a), Value::Dict(b)) => {
+                let (a, b) = (a.borrow(), b.borrow());
+                a.len() == b.len()
+                    && a.items().all(|(key, value)| {
+                        key.hash_key().and_then(|hash| b.get(&hash)).is_some_and(|other| other == value)
+                    })
+            }
+            (Value::Set(a), Value::Set(b)) => {
+                let (a, b) = (a.borrow(), b.borrow());
+                a.len() == b.len() && a.keys().all(|key| key.hash_key().is_some_and(|hash| b.contains(&hash)))
+            }
+            (
+                Value::Range { start, stop, step },
+                Value::Range { start: s2, stop: e2, step: t2 },
+            ) => (start, stop, step) == (s2, e2, t2),
+            (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+            (Value::Builtin(a), Value::Builtin(b)) => a == b,
+            (Value::ExceptionType(a), Value::ExceptionType(b)) => a == b,
+            (Value::Exception(a), Value::Exception(b)) => Rc::ptr_eq(a, b),
+            (a, b) => match (a.as_int(), b.as_int()) {
+                (Some(x), Some(y)) => x == y,
+                _ => matches!((a.as_float(), b.as_float()), (Some(x), Some(y)) if x == y),
+            },
+        }
+    }
+}
+
+/// str() of a value
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::None => write!(f, "None"),
+            Value::Bool(b) => write!(f, "{}", if *b { "True" } else { "False" }),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Float(x) => write!(f, "{}", format_float(*x)),
+            Value::Str(s) => write!(f, "{}", s),
+            Value::List(items) => write!(f, "[{}]", join_repr(items.borrow().iter())),
+            Value::Tuple(items) if items.len() == 1 => write!(f, "({},)", items[0].repr()),
+            Value::Tuple(items) => write!(f, "({})", join_repr(items.iter())),
+            Value::Dict(dict) => {
+                let dict = dict.borrow();
+                let items: Vec<String> = dict
+                    .items()
+                    .map(|(key, value)| format!("{}: {}", key.repr(), value.repr()))
+                    .collect();
+                write!(f, "{{{}}}", items.join(", "))
+            }
+            Value::Set(set) if set.borrow().is_empty() => write!(f, "set()"),
+            Value::Set(set) => write!(f, "{{{}}}", join_repr(set.borrow().keys())),
+            Value::Range { start, stop, step } if *step == 1 => write!(f, "range({}, {})", start, stop),
+            Value::Range { start, stop, step } => write!(f, "range({}, {}, {})", start, stop, step),
+            Value::Function(function) => write!(f, "<function {}>", function.name),
+            Value::Builtin(name) => write!(f, "<built-in function {}>", name),
+            Value::Method { receiver, name } => {
+                write!(f, "<built-in method {} of {} object>", name, receiver.type_name())
+            }
+            Value::ExceptionType(name) => write!(f, "<class '{}'>", name),
+            Value::Exception(exception) => write!(f, "{}", exception.message),
+        }
+    }
+}
+
+fn join_repr<'a>(values: impl Iterator<Item = &'a Value>) -> String {
+    values.map(Value::repr).collect::<Vec<_>>().join(", ")
+}
+
+/// repr() of a string: single-quoted unless it contains a single quote and no double quote
+pub fn repr_str(s: &str) -> String {
+    let quote = if s.contains('\'') && !s.contains('"') { '"' } else { '\'' };
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push(quote);
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c == quote => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push(quote);
+    out
+}
+
+/// Number of values in `range(start, stop, step)`
+pub fn range_len(start: i64, stop: i64, step: i64) -> i64 {
+    if step > 0 && start < stop {
+        (stop - start - 1) / step + 1
+    } else if step < 0 && start > stop {
+        (start - stop - 1) / (-step) + 1
+    } else {
+        0
+    }
+}
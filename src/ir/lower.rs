@@ -0,0 +1,1301 @@
+// lower.rs - Lowering a type-checked AST to the IR
+//
+// Each top-level function, and then the top-level statements as `main`, is
+// lowered on its own with a `FunctionLowering`. Variables become named
+// locals whose type is fixed by their annotation or first assignment;
+// anything the IR can't represent yet, such as classes, globals read from a
+// function or a variable that changes type, is an error naming what isn't
+// supported rather than a guess:
+//
+//     let program = ir::lower_module(&module)?;
+//     println!("{}", program);
+//
+// `for` loops over a range or a list, `while` loops and comprehensions
+// become a header block that tests the loop condition, the body and a latch
+// block that `continue` jumps to. Blocks nothing jumps to, such as the code
+// after a `return`, are dropped once a function is lowered.
+
+use super::{Block, BlockId, Const, Function, Inst, Local, LocalId, Operand, Program, Rvalue, Signature, Terminator, MAIN};
+use crate::ast::{BoolOperator, CmpOperator, Comprehension, Constant, Expr, Module, NameConstant, Number, Operator, Parameter, Stmt, UnaryOperator};
+use crate::compiler::class_layout::annotation_type;
+use crate::compiler::types::{binary_op_lowering, operator_symbol, unsupported_operand_types, BinaryOpLowering, Type};
+use crate::typechecker::TypeChecker;
+use std::collections::HashMap;
+
+/// Type check `module` and lower it to the IR
+pub fn lower_module(module: &Module) -> Result<Program, String> {
+    let mut checker = TypeChecker::new();
+    checker
+        .check_module(module)
+        .map_err(|type_error| format!("Type error: {}", type_error))?;
+
+    let mut signatures = HashMap::new();
+    let mut definitions = Vec::new();
+    let mut top_level = Vec::new();
+    for stmt in &module.body {
+        match stmt.as_ref() {
+            Stmt::FunctionDef {
+                name,
+                params,
+                body,
+                decorator_list,
+                returns,
+                is_async,
+                line,
+                ..
+            } => {
+                let at_line = |message: String| format!("line {}: {}", line, message);
+                if *is_async {
+                    return Err(at_line(not_supported("async functions are")));
+                }
+                if !decorator_list.is_empty() {
+                    return Err(at_line(not_supported("decorators are")));
+                }
+                if name == MAIN {
+                    return Err(at_line(not_supported("a function named `main` is")));
+                }
+                let signature = function_signature(&checker, name, params, returns).map_err(at_line)?;
+                if signatures.insert(name.clone(), signature).is_some() {
+                    return Err(at_line(not_supported(&format!("redefining `{}` is", name))));
+                }
+                definitions.push((name, params, body));
+            }
+            _ => top_level.push(stmt.clone()),
+        }
+    }
+
+    let mut functions = Vec::new();
+    for (name, params, body) in definitions {
+        let mut lowering = FunctionLowering::new(name, signatures[name].clone(), &signatures);
+        lowering.params(params);
+        lowering.body(body)?;
+        functions.push(lowering.finish());
+    }
+    let main_signature = Signature {
+        params: Vec::new(),
+        returns: Type::None,
+    };
+    let mut main = FunctionLowering::new(MAIN, main_signature, &signatures);
+    main.body(&top_level)?;
+    functions.push(main.finish());
+
+    Ok(Program { functions })
+}
+
+fn not_supported(what: &str) -> String {
+    format!("{} not supported by the IR yet", what)
+}
+
+/// Whether the IR can hold values of type `ty`
+fn is_supported(ty: &Type) -> bool {
+    match ty {
+        Type::Int | Type::Float | Type::Bool | Type::String | Type::None | Type::Any => true,
+        Type::List(element) => is_supported(element),
+        _ => false,
+    }
+}
+
+/// Type named by an annotation
+fn annotated_type(annotation: &Expr) -> Result<Type, String> {
+    let ty = match annotation {
+        Expr::NameConstant {
+            value: NameConstant::None,
+            ..
+        } => Type::None,
+        Expr::Name { id, .. } if id == "None" => Type::None,
+        _ => annotation_type(annotation).ok_or_else(|| not_supported("this type annotation is"))?,
+    };
+    if !is_supported(&ty) {
+        return Err(not_supported(&format!("values of type {} are", ty)));
+    }
+    Ok(ty)
+}
+
+/// Parameter and return types of a top-level function: as annotated, with
+/// unannotated parameters boxed and unannotated returns as the checker
+/// inferred them
+fn function_signature(
+    checker: &TypeChecker,
+    name: &str,
+    params: &[Parameter],
+    returns: &Option<Box<Expr>>,
+) -> Result<Signature, String> {
+    let mut param_types = Vec::with_capacity(params.len());
+    for param in params {
+        if param.is_vararg || param.is_kwarg {
+            return Err(not_supported("*args and **kwargs are"));
+        }
+        if param.default.is_some() {
+            return Err(not_supported("default arguments are"));
+        }
+        param_types.push(match &param.typ {
+            Some(annotation) => annotated_type(annotation)?,
+            None => Type::Any,
+        });
+    }
+    let returns = match returns {
+        Some(annotation) => annotated_type(annotation)?,
+        None => match checker.return_type(name) {
+            Some(Type::Void) => Type::None,
+            Some(ty) if is_supported(&ty) => ty,
+            _ => Type::Any,
+        },
+    };
+    Ok(Signature {
+        params: param_types,
+        returns,
+    })
+}
+
+/// Blocks a `break` and a `continue` in the innermost loop jump to
+#[derive(Clone, Copy)]
+struct LoopTargets {
+    continue_to: BlockId,
+    break_to: BlockId,
+}
+
+/// A `for` loop or comprehension generator whose body is being lowered
+struct ForLoop {
+    header: BlockId,
+    latch: BlockId,
+    /// Index into the list, or the range's current value
+    counter: LocalId,
+    step: i64,
+}
+
+/// Builds one function's locals and blocks
+struct FunctionLowering<'a> {
+    signatures: &'a HashMap<String, Signature>,
+    name: String,
+    signature: Signature,
+    params: Vec<LocalId>,
+    locals: Vec<Local>,
+    /// Instructions and, once it's been reached, the terminator of each block
+    blocks: Vec<(Vec<Inst>, Option<Terminator>)>,
+    current: BlockId,
+    variables: HashMap<String, LocalId>,
+    loops: Vec<LoopTargets>,
+    /// Line of the statement being lowered, for errors
+    line: usize,
+}
+
+impl<'a> FunctionLowering<'a> {
+    fn new(name: &str, signature: Signature, signatures: &'a HashMap<String, Signature>) -> Self {
+        Self {
+            signatures,
+            name: name.to_string(),
+            signature,
+            params: Vec::new(),
+            locals: Vec::new(),
+            blocks: vec![(Vec::new(), None)],
+            current: BlockId(0),
+            variables: HashMap::new(),
+            loops: Vec::new(),
+            line: 0,
+        }
+    }
+
+    /// Take each boxed argument and unbox it into its parameter's variable
+    fn params(&mut self, params: &[Parameter]) {
+        let types = self.signature.params.clone();
+        for (param, ty) in params.iter().zip(&types) {
+            // An unannotated parameter stays boxed
+            let name = (*ty == Type::Any).then_some(param.name.as_str());
+            let boxed = self.local(name, Type::Any);
+            self.params.push(boxed);
+        }
+        for ((param, ty), &boxed) in params.iter().zip(types).zip(&self.params.clone()) {
+            if ty == Type::Any {
+                self.variables.insert(param.name.clone(), boxed);
+                continue;
+            }
+            let variable = self.local(Some(&param.name), ty.clone());
+            self.emit(Inst::Assign {
+                dest: variable,
+                value: Rvalue::Unbox {
+                    value: Operand::Local(boxed),
+                    to: ty,
+                },
+            });
+            self.variables.insert(param.name.clone(), variable);
+        }
+    }
+
+    /// Lower the function's statements, reporting errors at the line they're on
+    fn body(&mut self, body: &[Box<Stmt>]) -> Result<(), String> {
+        self.stmts(body).map_err(|message| format!("line {}: {}", self.line, message))
+    }
+
+    /// The lowered function. Jumps to blocks that only jump on go straight
+    /// to where those lead, and the blocks left are laid out in reverse
+    /// postorder, which drops those nothing jumps to.
+    fn finish(self) -> Function {
+        let mut blocks: Vec<Block> = self
+            .blocks
+            .into_iter()
+            .map(|(insts, terminator)| Block {
+                insts,
+                terminator: terminator.unwrap_or(Terminator::Return(Operand::Const(Const::None))),
+            })
+            .collect();
+
+        let forward = |blocks: &[Block], mut target: BlockId| {
+            for _ in 0..blocks.len() {
+                match &blocks[target.0] {
+                    Block {
+                        insts,
+                        terminator: Terminator::Goto(next),
+                    } if insts.is_empty() && target.0 != 0 => target = *next,
+                    _ => break,
+                }
+            }
+            target
+        };
+        for i in 0..blocks.len() {
+            let mut terminator = blocks[i].terminator.clone();
+            for target in targets(&mut terminator) {
+                *target = forward(&blocks, *target);
+            }
+            blocks[i].terminator = terminator;
+        }
+
+        let mut visited = vec![false; blocks.len()];
+        let mut postorder = Vec::with_capacity(blocks.len());
+        let mut stack = vec![(BlockId(0), 0)];
+        visited[0] = true;
+        while let Some(&(block, next)) = stack.last() {
+            let mut terminator = blocks[block.0].terminator.clone();
+            // Visiting the else side first puts the then side first in reverse postorder
+            let successors: Vec<BlockId> = targets(&mut terminator).into_iter().rev().map(|target| *target).collect();
+            match successors.get(next) {
+                Some(&successor) => {
+                    stack.last_mut().unwrap().1 += 1;
+                    if !visited[successor.0] {
+                        visited[successor.0] = true;
+                        stack.push((successor, 0));
+                    }
+                }
+                None => {
+                    postorder.push(block);
+                    stack.pop();
+                }
+            }
+        }
+
+        let mut renumbered = vec![BlockId(0); blocks.len()];
+        for (new, old) in postorder.iter().rev().enumerate() {
+            renumbered[old.0] = BlockId(new);
+        }
+        let mut slots: Vec<Option<Block>> = blocks.into_iter().map(Some).collect();
+        let blocks = postorder
+            .iter()
+            .rev()
+            .map(|old| {
+                let mut block = slots[old.0].take().expect("each block is laid out once");
+                for target in targets(&mut block.terminator) {
+                    *target = renumbered[target.0];
+                }
+                block
+            })
+            .collect();
+
+        Function {
+            name: self.name,
+            signature: self.signature,
+            params: self.params,
+            returns_unboxed: false,
+            locals: self.locals,
+            blocks,
+        }
+    }
+
+    fn local(&mut self, name: Option<&str>, ty: Type) -> LocalId {
+        self.locals.push(Local {
+            name: name.map(str::to_string),
+            ty,
+        });
+        LocalId(self.locals.len() - 1)
+    }
+
+    fn block(&mut self) -> BlockId {
+        self.blocks.push((Vec::new(), None));
+        BlockId(self.blocks.len() - 1)
+    }
+
+    fn emit(&mut self, inst: Inst) {
+        self.blocks[self.current.0].0.push(inst);
+    }
+
+    /// Compute `value` into a new temporary of type `ty`
+    fn temp(&mut self, value: Rvalue, ty: Type) -> Operand {
+        let dest = self.local(None, ty);
+        self.emit(Inst::Assign { dest, value });
+        Operand::Local(dest)
+    }
+
+    /// End the current block with `terminator`
+    fn terminate(&mut self, terminator: Terminator) {
+        self.blocks[self.current.0].1 = Some(terminator);
+    }
+
+    /// End the current block with `terminator` and carry on in a block
+    /// nothing jumps to, for the code after a `return`, `break` or `continue`
+    fn terminate_unreachable(&mut self, terminator: Terminator) {
+        self.terminate(terminator);
+        self.current = self.block();
+    }
+
+    fn stmts(&mut self, stmts: &[Box<Stmt>]) -> Result<(), String> {
+        for stmt in stmts {
+            self.line = stmt.line();
+            self.emit(Inst::Line(self.line));
+            self.stmt(stmt)?;
+        }
+        Ok(())
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> Result<(), String> {
+        match stmt {
+            Stmt::Expr { value, .. } => {
+                self.expr(value)?;
+            }
+            Stmt::Pass { .. } => {}
+            Stmt::Assign { targets, value, .. } => {
+                let (value, ty) = match targets.as_slice() {
+                    [target] => match self.variable_type(target) {
+                        Some(expected) => (self.expr_as(value, &expected)?, expected),
+                        None => self.expr(value)?,
+                    },
+                    _ => self.expr(value)?,
+                };
+                for target in targets {
+                    self.assign(target, value.clone(), &ty)?;
+                }
+            }
+            Stmt::AnnAssign {
+                target, annotation, value, ..
+            } => {
+                let Expr::Name { id, .. } = target.as_ref() else {
+                    return Err(not_supported("annotated assignment to this target is"));
+                };
+                let ty = annotated_type(annotation)?;
+                let variable = self.declare(id, &ty)?;
+                if let Some(value) = value {
+                    let value = self.expr_as(value, &ty)?;
+                    self.emit(Inst::Assign {
+                        dest: variable,
+                        value: Rvalue::Use(value),
+                    });
+                }
+            }
+            Stmt::AugAssign { target, op, value, .. } => {
+                let Expr::Name { id, .. } = target.as_ref() else {
+                    return Err(not_supported("augmented assignment to this target is"));
+                };
+                let (current, current_type) = self.name(id)?;
+                let (value, value_type) = self.expr(value)?;
+                let (result, result_type) = self.binary(op, current, &current_type, value, &value_type)?;
+                self.assign_variable(id, result, &result_type)?;
+            }
+            Stmt::If { test, body, orelse, .. } => {
+                let cond = self.condition(test)?;
+                let (then_block, else_block, join) = (self.block(), self.block(), self.block());
+                self.terminate(Terminator::Branch {
+                    cond,
+                    then_block,
+                    else_block,
+                });
+                self.current = then_block;
+                self.stmts(body)?;
+                self.terminate(Terminator::Goto(join));
+                self.current = else_block;
+                self.stmts(orelse)?;
+                self.terminate(Terminator::Goto(join));
+                self.current = join;
+            }
+            Stmt::While { test, body, orelse, .. } => {
+                let (header, body_block, exhausted, exit) = (self.block(), self.block(), self.block(), self.block());
+                self.terminate(Terminator::Goto(header));
+                self.current = header;
+                let cond = self.condition(test)?;
+                self.terminate(Terminator::Branch {
+                    cond,
+                    then_block: body_block,
+                    else_block: exhausted,
+                });
+                self.current = body_block;
+                self.loop_body(header, exit, body)?;
+                self.terminate(Terminator::Goto(header));
+                self.current = exhausted;
+                self.stmts(orelse)?;
+                self.terminate(Terminator::Goto(exit));
+                self.current = exit;
+            }
+            Stmt::For {
+                target,
+                iter,
+                body,
+                orelse,
+                is_async,
+                ..
+            } => {
+                if *is_async {
+                    return Err(not_supported("async for is"));
+                }
+                let (exhausted, exit) = (self.block(), self.block());
+                let for_loop = self.begin_for(target, iter, exhausted)?;
+                self.loop_body(for_loop.latch, exit, body)?;
+                self.end_for(for_loop);
+                self.current = exhausted;
+                self.stmts(orelse)?;
+                self.terminate(Terminator::Goto(exit));
+                self.current = exit;
+            }
+            Stmt::Break { .. } => {
+                let targets = self.loops.last().ok_or("'break' outside loop")?;
+                let break_to = targets.break_to;
+                self.terminate_unreachable(Terminator::Goto(break_to));
+            }
+            Stmt::Continue { .. } => {
+                let targets = self.loops.last().ok_or("'continue' not properly in loop")?;
+                let continue_to = targets.continue_to;
+                self.terminate_unreachable(Terminator::Goto(continue_to));
+            }
+            Stmt::Return { value, .. } => {
+                if self.name == MAIN {
+                    return Err("'return' outside function".to_string());
+                }
+                let returns = self.signature.returns.clone();
+                let result = match value {
+                    Some(value) if returns != Type::None => {
+                        let value = self.expr_as(value, &returns)?;
+                        self.boxed(value, &returns)
+                    }
+                    Some(value) => {
+                        self.expr(value)?;
+                        Operand::Const(Const::None)
+                    }
+                    None => Operand::Const(Const::None),
+                };
+                self.terminate_unreachable(Terminator::Return(result));
+            }
+            Stmt::FunctionDef { .. } => return Err(not_supported("nested functions are")),
+            Stmt::ClassDef { .. } => return Err(not_supported("class definitions are")),
+            Stmt::Delete { .. } => return Err(not_supported("del statements are")),
+            Stmt::With { .. } => return Err(not_supported("with statements are")),
+            Stmt::Raise { .. } | Stmt::Try { .. } => return Err(not_supported("exceptions are")),
+            Stmt::Assert { .. } => return Err(not_supported("assert statements are")),
+            Stmt::Import { .. } | Stmt::ImportFrom { .. } => return Err(not_supported("imports are")),
+            Stmt::Global { .. } | Stmt::Nonlocal { .. } => {
+                return Err(not_supported("global and nonlocal declarations are"))
+            }
+            Stmt::Match { .. } => return Err(not_supported("match statements are")),
+        }
+        Ok(())
+    }
+
+    /// Lower a loop body in which `continue` jumps to `continue_to` and
+    /// `break` to `break_to`
+    fn loop_body(&mut self, continue_to: BlockId, break_to: BlockId, body: &[Box<Stmt>]) -> Result<(), String> {
+        self.loops.push(LoopTargets { continue_to, break_to });
+        let result = self.stmts(body);
+        self.loops.pop();
+        result
+    }
+
+    /// Start a loop over `iter`, leaving the current block at the start of
+    /// its body with `target` assigned. Jumps to `exhausted` once every
+    /// item has been taken.
+    fn begin_for(&mut self, target: &Expr, iter: &Expr, exhausted: BlockId) -> Result<ForLoop, String> {
+        let Expr::Name { id: target, .. } = target else {
+            return Err(not_supported("unpacking in a for target is"));
+        };
+        let (header, body, latch) = (self.block(), self.block(), self.block());
+
+        if let Some(args) = self.range_args(iter) {
+            if args.is_empty() || args.len() > 3 {
+                return Err(format!("range expected 1 to 3 arguments, got {}", args.len()));
+            }
+            let step = match args.get(2) {
+                Some(step) => constant_int(step).ok_or_else(|| not_supported("range() with a step that isn't a constant is"))?,
+                None => 1,
+            };
+            if step == 0 {
+                return Err("range() arg 3 must not be zero".to_string());
+            }
+            let mut bounds = Vec::new();
+            for arg in &args[..args.len().min(2)] {
+                let (value, ty) = self.expr(arg)?;
+                let value = self.convert(value, &ty, &Type::Int)?;
+                // The range is fixed when the loop starts
+                bounds.push(self.temp(Rvalue::Use(value), Type::Int));
+            }
+            let (start, stop) = match bounds.as_slice() {
+                [stop] => (Operand::Const(Const::Int(0)), stop.clone()),
+                [start, stop] => (start.clone(), stop.clone()),
+                _ => unreachable!("range() takes one or two bounds"),
+            };
+            let counter = self.local(None, Type::Int);
+            self.emit(Inst::Assign {
+                dest: counter,
+                value: Rvalue::Use(start),
+            });
+            self.terminate(Terminator::Goto(header));
+
+            self.current = header;
+            let op = if step > 0 { CmpOperator::Lt } else { CmpOperator::Gt };
+            let cond = self.temp(
+                Rvalue::Compare {
+                    op,
+                    left: Operand::Local(counter),
+                    right: stop,
+                },
+                Type::Bool,
+            );
+            self.terminate(Terminator::Branch {
+                cond,
+                then_block: body,
+                else_block: exhausted,
+            });
+
+            self.current = body;
+            self.assign_variable(target, Operand::Local(counter), &Type::Int)?;
+            return Ok(ForLoop {
+                header,
+                latch,
+                counter,
+                step,
+            });
+        }
+
+        let (list, list_type) = self.expr(iter)?;
+        let element = match &list_type {
+            Type::List(element) => element.as_ref().clone(),
+            Type::String => return Err(not_supported("iterating over a str is")),
+            other => return Err(format!("'{}' object is not iterable", other)),
+        };
+        // Rebinding the variable in the body doesn't change the list being iterated
+        let list = self.temp(Rvalue::Use(list), list_type);
+        let counter = self.local(None, Type::Int);
+        self.emit(Inst::Assign {
+            dest: counter,
+            value: Rvalue::Use(Operand::Const(Const::Int(0))),
+        });
+        self.terminate(Terminator::Goto(header));
+
+        self.current = header;
+        let length = self.temp(Rvalue::Len(list.clone()), Type::Int);
+        let cond = self.temp(
+            Rvalue::Compare {
+                op: CmpOperator::Lt,
+                left: Operand::Local(counter),
+                right: length,
+            },
+            Type::Bool,
+        );
+        self.terminate(Terminator::Branch {
+            cond,
+            then_block: body,
+            else_block: exhausted,
+        });
+
+        self.current = body;
+        let item = self.temp(
+            Rvalue::ListGet {
+                list,
+                index: Operand::Local(counter),
+            },
+            Type::Any,
+        );
+        let item = self.convert(item, &Type::Any, &element)?;
+        self.assign_variable(target, item, &element)?;
+        Ok(ForLoop {
+            header,
+            latch,
+            counter,
+            step: 1,
+        })
+    }
+
+    /// Finish the body of `for_loop`, stepping to the next item in its latch
+    fn end_for(&mut self, for_loop: ForLoop) {
+        self.terminate(Terminator::Goto(for_loop.latch));
+        self.current = for_loop.latch;
+        self.emit(Inst::Assign {
+            dest: for_loop.counter,
+            value: Rvalue::Binary {
+                op: Operator::Add,
+                left: Operand::Local(for_loop.counter),
+                right: Operand::Const(Const::Int(for_loop.step)),
+            },
+        });
+        self.terminate(Terminator::Goto(for_loop.header));
+    }
+
+    /// Arguments of `iter` when it calls the built-in range()
+    fn range_args<'e>(&self, iter: &'e Expr) -> Option<&'e [Box<Expr>]> {
+        match iter {
+            Expr::Call {
+                func, args, keywords, ..
+            } if keywords.is_empty() => match func.as_ref() {
+                Expr::Name { id, .. } if id == "range" && self.is_builtin(id) => Some(args),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Whether `name` refers to a built-in rather than a variable or function
+    fn is_builtin(&self, name: &str) -> bool {
+        !self.variables.contains_key(name) && !self.signatures.contains_key(name)
+    }
+
+    /// Type of the variable `target` names, if it's one already assigned
+    fn variable_type(&self, target: &Expr) -> Option<Type> {
+        match target {
+            Expr::Name { id, .. } => self.variables.get(id).map(|&local| self.locals[local.0].ty.clone()),
+            _ => None,
+        }
+    }
+
+    /// The local for variable `name`, of type `ty`
+    fn declare(&mut self, name: &str, ty: &Type) -> Result<LocalId, String> {
+        match self.variables.get(name) {
+            Some(&local) if &self.locals[local.0].ty == ty => Ok(local),
+            Some(&local) => Err(changes_type(name, &self.locals[local.0].ty, ty)),
+            None => {
+                let local = self.local(Some(name), ty.clone());
+                self.variables.insert(name.to_string(), local);
+                Ok(local)
+            }
+        }
+    }
+
+    /// Store `value` in variable `name`, declaring it with the value's type
+    /// on its first assignment
+    fn assign_variable(&mut self, name: &str, value: Operand, ty: &Type) -> Result<(), String> {
+        let (variable, value) = match self.variables.get(name) {
+            Some(&local) => {
+                let variable_type = self.locals[local.0].ty.clone();
+                if Type::is_lossy_conversion(ty, &variable_type) {
+                    return Err(changes_type(name, &variable_type, ty));
+                }
+                let value = self
+                    .convert(value, ty, &variable_type)
+                    .map_err(|_| changes_type(name, &variable_type, ty))?;
+                (local, value)
+            }
+            None => (self.declare(name, ty)?, value),
+        };
+        self.emit(Inst::Assign {
+            dest: variable,
+            value: Rvalue::Use(value),
+        });
+        Ok(())
+    }
+
+    /// Store `value` in an assignment target
+    fn assign(&mut self, target: &Expr, value: Operand, ty: &Type) -> Result<(), String> {
+        match target {
+            Expr::Name { id, .. } => self.assign_variable(id, value, ty),
+            Expr::Subscript { value: list, slice, .. } => {
+                let (list, list_type) = self.expr(list)?;
+                let Type::List(element) = &list_type else {
+                    return Err(not_supported(&format!("item assignment on {} is", list_type)));
+                };
+                let index = self.index(slice)?;
+                let value = self.convert(value, ty, element)?;
+                let value = self.boxed(value, element);
+                self.emit(Inst::ListSet { list, index, value });
+                Ok(())
+            }
+            Expr::Tuple { .. } | Expr::List { .. } => Err(not_supported("unpacking assignments are")),
+            Expr::Attribute { .. } => Err(not_supported("attribute assignment is")),
+            _ => Err("cannot assign to this expression".to_string()),
+        }
+    }
+
+    /// `value` as a value of type `to`, converting numbers, boxing and
+    /// unboxing as needed
+    fn convert(&mut self, value: Operand, from: &Type, to: &Type) -> Result<Operand, String> {
+        if from == to {
+            return Ok(value);
+        }
+        let is_numeric = |ty: &Type| ty.numeric_rank().is_some();
+        match (from, to, &value) {
+            (_, Type::Int, Operand::Const(Const::Bool(value))) => return Ok(Operand::Const(Const::Int(*value as i64))),
+            (_, Type::Float, Operand::Const(Const::Int(value))) => return Ok(Operand::Const(Const::Float(*value as f64))),
+            _ => {}
+        }
+        match (from, to) {
+            (from, to) if is_numeric(from) && is_numeric(to) => Ok(self.temp(
+                Rvalue::Cast {
+                    value,
+                    to: to.clone(),
+                },
+                to.clone(),
+            )),
+            (from, Type::Any) => Ok(self.boxed(value, from)),
+            (Type::Any, to) => Ok(self.temp(Rvalue::Unbox { value, to: to.clone() }, to.clone())),
+            // A list holds its items boxed whatever their static type
+            (Type::List(_), Type::List(element)) if **element == Type::Any => {
+                Ok(self.temp(Rvalue::Use(value), to.clone()))
+            }
+            _ => Err(format!("expected {}, got {}", to, from)),
+        }
+    }
+
+    /// The boxed form of `value`, of type `ty`. None is its own box.
+    fn boxed(&mut self, value: Operand, ty: &Type) -> Operand {
+        match ty {
+            Type::Any => value,
+            Type::None => Operand::Const(Const::None),
+            _ => self.temp(Rvalue::Box(value), Type::Any),
+        }
+    }
+
+    /// Lower `expr` as a value of type `expected`. An empty list literal
+    /// takes its element type from it.
+    fn expr_as(&mut self, expr: &Expr, expected: &Type) -> Result<Operand, String> {
+        if let (Expr::List { elts, .. }, Type::List(_)) = (expr, expected) {
+            if elts.is_empty() {
+                return Ok(self.temp(Rvalue::ListNew, expected.clone()));
+            }
+        }
+        let (value, ty) = self.expr(expr)?;
+        self.convert(value, &ty, expected)
+    }
+
+    /// Lower `expr` as the bool a branch tests
+    fn condition(&mut self, expr: &Expr) -> Result<Operand, String> {
+        let (value, ty) = self.expr(expr)?;
+        self.truth(value, &ty)
+    }
+
+    /// Whether `value` of type `ty` is true
+    fn truth(&mut self, value: Operand, ty: &Type) -> Result<Operand, String> {
+        let zero = match ty {
+            Type::Bool => return Ok(value),
+            Type::None => return Ok(Operand::Const(Const::Bool(false))),
+            Type::Int => Const::Int(0),
+            Type::Float => Const::Float(0.0),
+            Type::String | Type::List(_) => {
+                let length = self.temp(Rvalue::Len(value), Type::Int);
+                return Ok(self.temp(
+                    Rvalue::Compare {
+                        op: CmpOperator::NotEq,
+                        left: length,
+                        right: Operand::Const(Const::Int(0)),
+                    },
+                    Type::Bool,
+                ));
+            }
+            other => return Err(not_supported(&format!("testing whether a value of type {} is true is", other))),
+        };
+        Ok(self.temp(
+            Rvalue::Compare {
+                op: CmpOperator::NotEq,
+                left: value,
+                right: Operand::Const(zero),
+            },
+            Type::Bool,
+        ))
+    }
+
+    /// Lower `expr` to an operand and its type
+    fn expr(&mut self, expr: &Expr) -> Result<(Operand, Type), String> {
+        match expr {
+            Expr::Num { value, .. } => number(value),
+            Expr::Str { value, .. } => Ok((Operand::Const(Const::Str(value.clone())), Type::String)),
+            Expr::NameConstant { value, .. } => Ok(name_constant(value)),
+            Expr::Constant { value, .. } => match value {
+                Constant::Num(value) => number(value),
+                Constant::Str(value) => Ok((Operand::Const(Const::Str(value.clone())), Type::String)),
+                Constant::NameConstant(value) => Ok(name_constant(value)),
+                Constant::Bytes(_) => Err(not_supported("bytes literals are")),
+                Constant::Ellipsis => Err(not_supported("`...` is")),
+            },
+            Expr::Name { id, .. } => self.name(id),
+            Expr::BinOp { left, op, right, .. } => {
+                let (left, left_type) = self.expr(left)?;
+                let (right, right_type) = self.expr(right)?;
+                self.binary(op, left, &left_type, right, &right_type)
+            }
+            Expr::UnaryOp { op, operand, .. } => {
+                let (value, ty) = self.expr(operand)?;
+                self.unary(op, value, &ty)
+            }
+            Expr::Compare {
+                left, ops, comparators, ..
+            } => self.comparison(left, ops, comparators),
+            Expr::BoolOp { op, values, .. } => self.bool_op(op, values),
+            Expr::IfExp { test, body, orelse, .. } => self.if_exp(test, body, orelse),
+            Expr::Call {
+                func, args, keywords, ..
+            } => {
+                if !keywords.is_empty() {
+                    return Err(not_supported("keyword arguments are"));
+                }
+                let Expr::Name { id, .. } = func.as_ref() else {
+                    return Err(not_supported("calling this expression is"));
+                };
+                self.call(id, args)
+            }
+            Expr::List { elts, .. } => self.list(elts),
+            Expr::Subscript { value, slice, .. } => {
+                let (list, list_type) = self.expr(value)?;
+                let Type::List(element) = &list_type else {
+                    return Err(not_supported(&format!("indexing a value of type {} is", list_type)));
+                };
+                let index = self.index(slice)?;
+                let item = self.temp(Rvalue::ListGet { list, index }, Type::Any);
+                let item = self.convert(item, &Type::Any, element)?;
+                Ok((item, element.as_ref().clone()))
+            }
+            Expr::ListComp { elt, generators, .. } => self.list_comprehension(elt, generators),
+            Expr::NamedExpr { target, value, .. } => {
+                let Expr::Name { id, .. } = target.as_ref() else {
+                    return Err("cannot use an assignment expression with this target".to_string());
+                };
+                let (value, ty) = self.expr(value)?;
+                self.assign_variable(id, value, &ty)?;
+                self.name(id)
+            }
+            Expr::Lambda { .. } => Err(not_supported("lambdas are")),
+            Expr::Dict { .. } | Expr::DictComp { .. } => Err(not_supported("dicts are")),
+            Expr::Set { .. } | Expr::SetComp { .. } => Err(not_supported("sets are")),
+            Expr::Tuple { .. } => Err(not_supported("tuples are")),
+            Expr::GeneratorExp { .. } | Expr::Yield { .. } | Expr::YieldFrom { .. } => {
+                Err(not_supported("generators are"))
+            }
+            Expr::Await { .. } => Err(not_supported("await is")),
+            Expr::FormattedValue { .. } | Expr::JoinedStr { .. } => Err(not_supported("f-strings are")),
+            Expr::Bytes { .. } => Err(not_supported("bytes literals are")),
+            Expr::Ellipsis { .. } => Err(not_supported("`...` is")),
+            Expr::Attribute { .. } => Err(not_supported("attributes are")),
+            Expr::Slice { .. } => Err(not_supported("slices are")),
+            Expr::Starred { .. } => Err(not_supported("starred expressions are")),
+        }
+    }
+
+    /// The value of variable `name`
+    fn name(&self, name: &str) -> Result<(Operand, Type), String> {
+        if let Some(&local) = self.variables.get(name) {
+            return Ok((Operand::Local(local), self.locals[local.0].ty.clone()));
+        }
+        if self.signatures.contains_key(name) {
+            return Err(not_supported("functions as values are"));
+        }
+        Err(not_supported(&format!("reading `{}`, which isn't a local variable, is", name)))
+    }
+
+    /// An index into a list, as an int
+    fn index(&mut self, slice: &Expr) -> Result<Operand, String> {
+        if let Expr::Slice { .. } = slice {
+            return Err(not_supported("slices are"));
+        }
+        let (index, ty) = self.expr(slice)?;
+        if !matches!(ty, Type::Int | Type::Bool) {
+            return Err(format!("list indices must be integers, not {}", ty));
+        }
+        self.convert(index, &ty, &Type::Int)
+    }
+
+    fn binary(
+        &mut self,
+        op: &Operator,
+        left: Operand,
+        left_type: &Type,
+        right: Operand,
+        right_type: &Type,
+    ) -> Result<(Operand, Type), String> {
+        if *left_type == Type::Any || *right_type == Type::Any {
+            return Err(format!(
+                "`{}` needs operands of known types; annotate the parameters it uses",
+                operator_symbol(op)
+            ));
+        }
+        let result_type = match binary_op_lowering(op, left_type, right_type) {
+            // True division is always of floats
+            Some(BinaryOpLowering::Numeric(_)) if *op == Operator::Div => Type::Float,
+            Some(BinaryOpLowering::Numeric(ty)) => ty,
+            Some(BinaryOpLowering::StringConcat | BinaryOpLowering::StringRepeat { .. }) => Type::String,
+            Some(BinaryOpLowering::ListConcat) if left_type == right_type => left_type.clone(),
+            Some(BinaryOpLowering::ListRepeat { swapped: false }) => left_type.clone(),
+            Some(BinaryOpLowering::ListRepeat { swapped: true }) => right_type.clone(),
+            Some(BinaryOpLowering::ListConcat) | None => {
+                return Err(unsupported_operand_types(op, left_type, right_type))
+            }
+        };
+        let (left, right) = if result_type.numeric_rank().is_some() {
+            (
+                self.convert(left, left_type, &result_type)?,
+                self.convert(right, right_type, &result_type)?,
+            )
+        } else {
+            (left, right)
+        };
+        let value = Rvalue::Binary {
+            op: op.clone(),
+            left,
+            right,
+        };
+        Ok((self.temp(value, result_type.clone()), result_type))
+    }
+
+    fn unary(&mut self, op: &UnaryOperator, value: Operand, ty: &Type) -> Result<(Operand, Type), String> {
+        let operand_type = match (op, ty) {
+            (UnaryOperator::Not, _) => {
+                let operand = self.truth(value, ty)?;
+                let value = Rvalue::Unary { op: op.clone(), operand };
+                return Ok((self.temp(value, Type::Bool), Type::Bool));
+            }
+            (UnaryOperator::UAdd | UnaryOperator::USub, Type::Float) => Type::Float,
+            (_, Type::Int | Type::Bool) => Type::Int,
+            _ => return Err(format!("bad operand type for unary operator: '{}'", ty)),
+        };
+        let operand = self.convert(value, ty, &operand_type)?;
+        let value = Rvalue::Unary { op: op.clone(), operand };
+        Ok((self.temp(value, operand_type.clone()), operand_type))
+    }
+
+    /// `left op right`, after converting numbers to a common type
+    fn compare(
+        &mut self,
+        op: &CmpOperator,
+        left: Operand,
+        left_type: &Type,
+        right: Operand,
+        right_type: &Type,
+    ) -> Result<Operand, String> {
+        match op {
+            CmpOperator::In | CmpOperator::NotIn => return Err(not_supported("`in` is")),
+            CmpOperator::Is | CmpOperator::IsNot => return Err(not_supported("`is` is")),
+            _ => {}
+        }
+        let (left, right) = match Type::promote_numeric(left_type, right_type) {
+            Some(common) => (
+                self.convert(left, left_type, &common)?,
+                self.convert(right, right_type, &common)?,
+            ),
+            None if left_type == right_type && *left_type == Type::String => (left, right),
+            None => {
+                return Err(not_supported(&format!(
+                    "comparing values of types {} and {} is",
+                    left_type, right_type
+                )))
+            }
+        };
+        let value = Rvalue::Compare {
+            op: op.clone(),
+            left,
+            right,
+        };
+        Ok(self.temp(value, Type::Bool))
+    }
+
+    /// A comparison chain such as `a < b < c`, which stops at the first false link
+    fn comparison(&mut self, left: &Expr, ops: &[CmpOperator], comparators: &[Box<Expr>]) -> Result<(Operand, Type), String> {
+        let (mut left, mut left_type) = self.expr(left)?;
+        if let ([op], [right]) = (ops, comparators) {
+            let (right, right_type) = self.expr(right)?;
+            let result = self.compare(op, left, &left_type, right, &right_type)?;
+            return Ok((result, Type::Bool));
+        }
+
+        let result = self.local(None, Type::Bool);
+        let end = self.block();
+        for (i, (op, right)) in ops.iter().zip(comparators).enumerate() {
+            let (right, right_type) = self.expr(right)?;
+            let link = self.compare(op, left, &left_type, right.clone(), &right_type)?;
+            self.emit(Inst::Assign {
+                dest: result,
+                value: Rvalue::Use(link.clone()),
+            });
+            if i + 1 < ops.len() {
+                let next = self.block();
+                self.terminate(Terminator::Branch {
+                    cond: link,
+                    then_block: next,
+                    else_block: end,
+                });
+                self.current = next;
+            }
+            (left, left_type) = (right, right_type);
+        }
+        self.terminate(Terminator::Goto(end));
+        self.current = end;
+        Ok((Operand::Local(result), Type::Bool))
+    }
+
+    /// `a and b` or `a or b`, giving the first value that decides the result
+    fn bool_op(&mut self, op: &BoolOperator, values: &[Box<Expr>]) -> Result<(Operand, Type), String> {
+        let end = self.block();
+        let mut result: Option<(LocalId, Type)> = None;
+        for (i, value) in values.iter().enumerate() {
+            let (value, ty) = self.expr(value)?;
+            let (dest, result_type) = match &result {
+                Some((dest, result_type)) if *result_type == ty => (*dest, result_type.clone()),
+                Some(_) => return Err(not_supported("`and` and `or` of values of different types are")),
+                None => (self.local(None, ty.clone()), ty),
+            };
+            result = Some((dest, result_type.clone()));
+            self.emit(Inst::Assign {
+                dest,
+                value: Rvalue::Use(value),
+            });
+            if i + 1 < values.len() {
+                let cond = self.truth(Operand::Local(dest), &result_type)?;
+                let next = self.block();
+                let (then_block, else_block) = match op {
+                    BoolOperator::And => (next, end),
+                    BoolOperator::Or => (end, next),
+                };
+                self.terminate(Terminator::Branch {
+                    cond,
+                    then_block,
+                    else_block,
+                });
+                self.current = next;
+            }
+        }
+        self.terminate(Terminator::Goto(end));
+        self.current = end;
+        let (dest, ty) = result.ok_or("`and` or `or` without operands")?;
+        Ok((Operand::Local(dest), ty))
+    }
+
+    /// `body if test else orelse`
+    fn if_exp(&mut self, test: &Expr, body: &Expr, orelse: &Expr) -> Result<(Operand, Type), String> {
+        let cond = self.condition(test)?;
+        let (then_block, else_block, end) = (self.block(), self.block(), self.block());
+        self.terminate(Terminator::Branch {
+            cond,
+            then_block,
+            else_block,
+        });
+
+        self.current = then_block;
+        let (value, ty) = self.expr(body)?;
+        let dest = self.local(None, ty.clone());
+        self.emit(Inst::Assign {
+            dest,
+            value: Rvalue::Use(value),
+        });
+        self.terminate(Terminator::Goto(end));
+
+        self.current = else_block;
+        let (value, else_type) = self.expr(orelse)?;
+        if else_type != ty {
+            return Err(not_supported("conditional expressions with arms of different types are"));
+        }
+        self.emit(Inst::Assign {
+            dest,
+            value: Rvalue::Use(value),
+        });
+        self.terminate(Terminator::Goto(end));
+        self.current = end;
+        Ok((Operand::Local(dest), ty))
+    }
+
+    fn call(&mut self, name: &str, args: &[Box<Expr>]) -> Result<(Operand, Type), String> {
+        if let Some(signature) = self.signatures.get(name).cloned() {
+            if self.variables.contains_key(name) {
+                return Err(not_supported("calling a variable is"));
+            }
+            if args.len() != signature.params.len() {
+                return Err(format!(
+                    "{}() takes {} arguments ({} given)",
+                    name,
+                    signature.params.len(),
+                    args.len()
+                ));
+            }
+            let mut boxed = Vec::with_capacity(args.len());
+            for (arg, param_type) in args.iter().zip(&signature.params) {
+                let value = self.expr_as(arg, param_type)?;
+                boxed.push(self.boxed(value, param_type));
+            }
+            let call = Rvalue::Call {
+                function: name.to_string(),
+                args: boxed,
+            };
+            let result = self.temp(call, Type::Any);
+            return match signature.returns {
+                Type::None => Ok((Operand::Const(Const::None), Type::None)),
+                returns => Ok((self.convert(result, &Type::Any, &returns)?, returns)),
+            };
+        }
+        if !self.is_builtin(name) {
+            return Err(not_supported("calling a variable is"));
+        }
+
+        match (name, args) {
+            ("print", _) => {
+                let mut values = Vec::with_capacity(args.len());
+                for arg in args {
+                    let (value, ty) = self.expr(arg)?;
+                    if !matches!(ty, Type::Int | Type::Float | Type::Bool | Type::String | Type::None | Type::List(_)) {
+                        return Err(not_supported(&format!("printing a value of type {} is", ty)));
+                    }
+                    values.push(value);
+                }
+                self.emit(Inst::Print(values));
+                Ok((Operand::Const(Const::None), Type::None))
+            }
+            ("len", [arg]) => {
+                let (value, ty) = self.expr(arg)?;
+                if !matches!(ty, Type::String | Type::List(_)) {
+                    return Err(format!("object of type '{}' has no len()", ty));
+                }
+                Ok((self.temp(Rvalue::Len(value), Type::Int), Type::Int))
+            }
+            ("bool", [arg]) => {
+                let result = self.condition(arg)?;
+                Ok((result, Type::Bool))
+            }
+            ("int" | "float", [arg]) => {
+                let to = if name == "int" { Type::Int } else { Type::Float };
+                let (value, ty) = self.expr(arg)?;
+                if ty.numeric_rank().is_none() {
+                    return Err(not_supported(&format!("{}() of a value of type {} is", name, ty)));
+                }
+                Ok((self.convert(value, &ty, &to)?, to))
+            }
+            ("len" | "bool" | "int" | "float", _) => {
+                Err(format!("{}() takes exactly one argument ({} given)", name, args.len()))
+            }
+            _ => Err(not_supported(&format!("calling `{}()` is", name))),
+        }
+    }
+
+    /// A list literal, whose items all have one type or are all numbers
+    fn list(&mut self, elts: &[Box<Expr>]) -> Result<(Operand, Type), String> {
+        let mut items = Vec::with_capacity(elts.len());
+        let mut element: Option<Type> = None;
+        for elt in elts {
+            let (value, ty) = self.expr(elt)?;
+            element = Some(match element {
+                None => ty.clone(),
+                Some(element) if element == ty => element,
+                Some(element) => Type::promote_numeric(&element, &ty)
+                    .ok_or_else(|| not_supported("lists of values of different types are"))?,
+            });
+            items.push((value, ty));
+        }
+        let list_type = Type::List(Box::new(element.clone().unwrap_or(Type::Any)));
+        let list = self.temp(Rvalue::ListNew, list_type.clone());
+        let element = element.unwrap_or(Type::Any);
+        for (value, ty) in items {
+            let value = self.convert(value, &ty, &element)?;
+            let value = self.boxed(value, &element);
+            self.emit(Inst::ListPush {
+                list: list.clone(),
+                value,
+            });
+        }
+        Ok((list, list_type))
+    }
+
+    /// `[elt for ... in ... if ...]` as nested loops appending to a new list
+    fn list_comprehension(&mut self, elt: &Expr, generators: &[Comprehension]) -> Result<(Operand, Type), String> {
+        let list = self.local(None, Type::List(Box::new(Type::Any)));
+        self.emit(Inst::Assign {
+            dest: list,
+            value: Rvalue::ListNew,
+        });
+
+        // Comprehension variables don't outlive it
+        let outer = self.variables.clone();
+        let element = self.generator(elt, generators, list);
+        self.variables = outer;
+        let element = element?;
+
+        let list_type = Type::List(Box::new(element));
+        self.locals[list.0].ty = list_type.clone();
+        Ok((Operand::Local(list), list_type))
+    }
+
+    /// Lower the generators of a comprehension from the first, appending
+    /// `elt` to `list` in the innermost, and return the type of `elt`
+    fn generator(&mut self, elt: &Expr, generators: &[Comprehension], list: LocalId) -> Result<Type, String> {
+        let Some((generator, inner)) = generators.split_first() else {
+            let (value, ty) = self.expr(elt)?;
+            let value = self.boxed(value, &ty);
+            self.emit(Inst::ListPush {
+                list: Operand::Local(list),
+                value,
+            });
+            return Ok(ty);
+        };
+        if generator.is_async {
+            return Err(not_supported("async comprehensions are"));
+        }
+
+        let exhausted = self.block();
+        let for_loop = self.begin_for(&generator.target, &generator.iter, exhausted)?;
+        for condition in &generator.ifs {
+            let cond = self.condition(condition)?;
+            let next = self.block();
+            self.terminate(Terminator::Branch {
+                cond,
+                then_block: next,
+                else_block: for_loop.latch,
+            });
+            self.current = next;
+        }
+        let element = self.generator(elt, inner, list)?;
+        self.end_for(for_loop);
+        self.current = exhausted;
+        Ok(element)
+    }
+}
+
+fn changes_type(name: &str, from: &Type, to: &Type) -> String {
+    not_supported(&format!("`{}` holds both {} and {}; variables that change type are", name, from, to))
+}
+
+fn number(value: &Number) -> Result<(Operand, Type), String> {
+    match value {
+        Number::Integer(value) => Ok((Operand::Const(Const::Int(*value)), Type::Int)),
+        Number::Float(value) => Ok((Operand::Const(Const::Float(*value)), Type::Float)),
+        Number::Complex { .. } => Err(not_supported("complex numbers are")),
+    }
+}
+
+fn name_constant(value: &NameConstant) -> (Operand, Type) {
+    match value {
+        NameConstant::True => (Operand::Const(Const::Bool(true)), Type::Bool),
+        NameConstant::False => (Operand::Const(Const::Bool(false)), Type::Bool),
+        NameConstant::None => (Operand::Const(Const::None), Type::None),
+    }
+}
+
+/// The value of an integer literal, negated or not
+fn constant_int(expr: &Expr) -> Option<i64> {
+    match expr {
+        Expr::Num {
+            value: Number::Integer(value),
+            ..
+        } => Some(*value),
+        Expr::UnaryOp {
+            op: UnaryOperator::USub,
+            operand,
+            ..
+        } => constant_int(operand)?.checked_neg(),
+        _ => None,
+    }
+}
+
+/// The blocks `terminator` jumps to
+fn targets(terminator: &mut Terminator) -> Vec<&mut BlockId> {
+    match terminator {
+        Terminator::Goto(target) => vec![target],
+        Terminator::Branch {
+            then_block,
+            else_block,
+            ..
+        } => vec![then_block, else_block],
+        Terminator::Return(_) => Vec::new(),
+    }
+}
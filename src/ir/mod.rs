@@ -0,0 +1,366 @@
+// mod.rs - Typed mid-level IR between the AST and LLVM
+//
+// `lower_module` turns a type-checked module into a `Program`: one
+// `Function` per top-level function, plus `main` for the statements at the
+// top level. A function is a list of basic blocks over typed locals, with
+// the decisions code generation used to make while walking the AST already
+// made:
+//
+//     def scale(xs: list[int], k: float) -> list[float]:
+//         return [x * k for x in xs]
+//
+//     fn scale(%0, %1): (list[int], float) -> list[float]
+//       let %0: Any
+//       let %1: Any
+//       let %2 xs: list[int]
+//       let %3 k: float
+//       ...
+//     bb0:
+//       %2 = unbox %0 as list[int]
+//       %3 = unbox %1 as float
+//       line 2
+//       %4 = list.new
+//       %5 = %2
+//       %6 = 0
+//       goto bb1
+//     bb1:
+//       %7 = len %5
+//       %8 = %6 < %7
+//       branch %8, bb2, bb4
+//     bb2:
+//       %9 = list.get %5, %6
+//       %10 = unbox %9 as int
+//       %11 = %10
+//       %12 = %11 as float
+//       %13 = %12 * %3
+//       ...
+//
+// Every local has one type. Values of type `Any` are boxed: a pointer to
+// the value, as lists hold their items, and `box`/`unbox` convert between
+// the two. Functions take and return boxed values, so a backend needs one
+// calling convention whatever a function's types; `Function::signature`
+// keeps the static types each side unboxes to. `specialize` then gives
+// functions with int, float or bool parameters or results unboxed copies,
+// whose parameter locals have the parameters' own types. Numeric
+// conversions are explicit `as` casts, and loops, comprehensions,
+// `and`/`or` and conditional expressions are branches between blocks.
+// `line` marks where each statement starts.
+//
+// The IR covers part of the language so far, and generating code from it
+// (see `compiler/ir_codegen.rs`) is a second, partial backend next to the
+// AST compiler rather than a replacement for it: `Compiler::compile_module`
+// uses it only for programs the IR covers that have functions to unbox,
+// and compiles everything else from the AST, which keeps its own type
+// logic.
+
+pub mod lower;
+pub mod specialize;
+
+pub use lower::lower_module;
+pub use specialize::specialize;
+
+use crate::ast::{CmpOperator, Operator, UnaryOperator};
+use crate::compiler::types::{operator_symbol, Type};
+use std::fmt;
+
+/// Name of the function holding a module's top-level statements
+pub const MAIN: &str = "main";
+
+/// A module lowered to the IR
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    /// Top-level functions in definition order, then `main`
+    pub functions: Vec<Function>,
+}
+
+impl Program {
+    /// The function called `name`
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+}
+
+/// Static parameter and return types of a function
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub params: Vec<Type>,
+    pub returns: Type,
+}
+
+/// Index of a local in `Function::locals`, printed as `%n`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LocalId(pub usize);
+
+/// Index of a block in `Function::blocks`, printed as `bbN`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockId(pub usize);
+
+/// A function: its boxed parameters, its locals and its blocks, starting at
+/// `bb0`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub signature: Signature,
+    /// Locals the arguments arrive in, one per parameter: boxed, of type
+    /// `Any`, unless the function is an unboxed copy
+    pub params: Vec<LocalId>,
+    /// Whether it returns a value of `signature.returns` rather than a box
+    pub returns_unboxed: bool,
+    pub locals: Vec<Local>,
+    pub blocks: Vec<Block>,
+}
+
+impl Function {
+    /// Type of `local`
+    pub fn local_type(&self, local: LocalId) -> &Type {
+        &self.locals[local.0].ty
+    }
+
+    /// Add a temporary of type `ty`
+    pub fn add_local(&mut self, ty: Type) -> LocalId {
+        self.locals.push(Local { name: None, ty });
+        LocalId(self.locals.len() - 1)
+    }
+
+    /// Type of `operand`
+    pub fn operand_type(&self, operand: &Operand) -> Type {
+        match operand {
+            Operand::Local(local) => self.local_type(*local).clone(),
+            Operand::Const(constant) => constant.ty(),
+        }
+    }
+}
+
+/// A typed local: a source variable when it has a name, or a temporary
+#[derive(Debug, Clone, PartialEq)]
+pub struct Local {
+    pub name: Option<String>,
+    pub ty: Type,
+}
+
+/// Straight-line instructions ending in a jump or a return
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub insts: Vec<Inst>,
+    pub terminator: Terminator,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inst {
+    /// Start of the statement on a source line, for tracebacks, debug info
+    /// and with_timeout() deadlines
+    Line(usize),
+    /// Compute `value` into `dest`, which has the value's type
+    Assign { dest: LocalId, value: Rvalue },
+    /// print() its operands, separated by spaces
+    Print(Vec<Operand>),
+    /// Append a boxed `value` to `list`
+    ListPush { list: Operand, value: Operand },
+    /// Store a boxed `value` at `index` of `list`
+    ListSet { list: Operand, index: Operand, value: Operand },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rvalue {
+    Use(Operand),
+    /// An operator applied to operands the lowering has already converted,
+    /// such as both floats for `1 / 2`
+    Binary { op: Operator, left: Operand, right: Operand },
+    Unary { op: UnaryOperator, operand: Operand },
+    /// A comparison of converted operands, giving a bool
+    Compare { op: CmpOperator, left: Operand, right: Operand },
+    /// Convert between int, float and bool
+    Cast { value: Operand, to: Type },
+    /// The boxed form of a value, of type `Any`
+    Box(Operand),
+    /// The value a box of type `Any` holds, known to be of type `to`
+    Unbox { value: Operand, to: Type },
+    /// Call a function with arguments and a result boxed or not as it takes
+    /// and returns them
+    Call { function: String, args: Vec<Operand> },
+    /// Length of a list or string
+    Len(Operand),
+    /// A new empty list
+    ListNew,
+    /// The boxed item at `index` of `list`, counting negative indices from the end
+    ListGet { list: Operand, index: Operand },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator {
+    Goto(BlockId),
+    Branch { cond: Operand, then_block: BlockId, else_block: BlockId },
+    /// Return a value boxed unless the function returns unboxed, or `None`
+    /// from `main`
+    Return(Operand),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Local(LocalId),
+    Const(Const),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Const {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+    None,
+}
+
+impl Const {
+    /// Type of the constant
+    pub fn ty(&self) -> Type {
+        match self {
+            Const::Int(_) => Type::Int,
+            Const::Float(_) => Type::Float,
+            Const::Bool(_) => Type::Bool,
+            Const::Str(_) => Type::String,
+            Const::None => Type::None,
+        }
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, function) in self.functions.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", function)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let params: Vec<String> = self.params.iter().map(|p| p.to_string()).collect();
+        let types: Vec<String> = self.signature.params.iter().map(|t| t.to_string()).collect();
+        writeln!(
+            f,
+            "fn {}({}): ({}) -> {}",
+            self.name,
+            params.join(", "),
+            types.join(", "),
+            self.signature.returns
+        )?;
+        for (i, local) in self.locals.iter().enumerate() {
+            match &local.name {
+                Some(name) => writeln!(f, "  let {} {}: {}", LocalId(i), name, local.ty)?,
+                None => writeln!(f, "  let {}: {}", LocalId(i), local.ty)?,
+            }
+        }
+        for (i, block) in self.blocks.iter().enumerate() {
+            writeln!(f, "{}:", BlockId(i))?;
+            for inst in &block.insts {
+                writeln!(f, "  {}", inst)?;
+            }
+            writeln!(f, "  {}", block.terminator)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for LocalId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "%{}", self.0)
+    }
+}
+
+impl fmt::Display for BlockId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bb{}", self.0)
+    }
+}
+
+impl fmt::Display for Inst {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Inst::Line(line) => write!(f, "line {}", line),
+            Inst::Assign { dest, value } => write!(f, "{} = {}", dest, value),
+            Inst::Print(operands) => write!(f, "print {}", join(operands)),
+            Inst::ListPush { list, value } => write!(f, "list.push {}, {}", list, value),
+            Inst::ListSet { list, index, value } => {
+                write!(f, "list.set {}, {}, {}", list, index, value)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Rvalue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rvalue::Use(operand) => write!(f, "{}", operand),
+            Rvalue::Binary { op, left, right } => {
+                write!(f, "{} {} {}", left, operator_symbol(op), right)
+            }
+            Rvalue::Unary { op, operand } => match op {
+                UnaryOperator::Not => write!(f, "not {}", operand),
+                UnaryOperator::Invert => write!(f, "~{}", operand),
+                UnaryOperator::UAdd => write!(f, "+{}", operand),
+                UnaryOperator::USub => write!(f, "-{}", operand),
+            },
+            Rvalue::Compare { op, left, right } => {
+                write!(f, "{} {} {}", left, comparison_symbol(op), right)
+            }
+            Rvalue::Cast { value, to } => write!(f, "{} as {}", value, to),
+            Rvalue::Box(operand) => write!(f, "box {}", operand),
+            Rvalue::Unbox { value, to } => write!(f, "unbox {} as {}", value, to),
+            Rvalue::Call { function, args } => write!(f, "call {}({})", function, join(args)),
+            Rvalue::Len(operand) => write!(f, "len {}", operand),
+            Rvalue::ListNew => write!(f, "list.new"),
+            Rvalue::ListGet { list, index } => write!(f, "list.get {}, {}", list, index),
+        }
+    }
+}
+
+impl fmt::Display for Terminator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Terminator::Goto(block) => write!(f, "goto {}", block),
+            Terminator::Branch {
+                cond,
+                then_block,
+                else_block,
+            } => write!(f, "branch {}, {}, {}", cond, then_block, else_block),
+            Terminator::Return(operand) => write!(f, "return {}", operand),
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Local(local) => write!(f, "{}", local),
+            Operand::Const(Const::Int(value)) => write!(f, "{}", value),
+            Operand::Const(Const::Float(value)) => write!(f, "{:?}", value),
+            Operand::Const(Const::Bool(true)) => write!(f, "True"),
+            Operand::Const(Const::Bool(false)) => write!(f, "False"),
+            Operand::Const(Const::Str(value)) => write!(f, "{:?}", value),
+            Operand::Const(Const::None) => write!(f, "None"),
+        }
+    }
+}
+
+/// Source-level spelling of a comparison operator
+fn comparison_symbol(op: &CmpOperator) -> &'static str {
+    match op {
+        CmpOperator::Eq => "==",
+        CmpOperator::NotEq => "!=",
+        CmpOperator::Lt => "<",
+        CmpOperator::LtE => "<=",
+        CmpOperator::Gt => ">",
+        CmpOperator::GtE => ">=",
+        CmpOperator::Is => "is",
+        CmpOperator::IsNot => "is not",
+        CmpOperator::In => "in",
+        CmpOperator::NotIn => "not in",
+    }
+}
+
+fn join(operands: &[Operand]) -> String {
+    operands.iter().map(|operand| operand.to_string()).collect::<Vec<_>>().join(", ")
+}
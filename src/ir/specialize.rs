@@ -0,0 +1,430 @@
+// specialize.rs - Unboxed copies of functions over ints, floats and bools
+//
+// Functions take and return boxed values, so every call boxes its int
+// arguments only for the callee to unbox them again, and boxes a result
+// the caller unboxes. `specialize` gives each top-level function whose
+// parameters or result are statically int, float or bool a copy taking
+// and returning those unboxed, and turns the original into a shim for
+// callers that only know the boxed convention:
+//
+//     fn fib(%0): (int) -> int
+//       let %0: Any
+//       let %1: int
+//       let %2: int
+//       let %3: Any
+//     bb0:
+//       %1 = unbox %0 as int
+//       %2 = call fib.unboxed(%1)
+//       %3 = box %2
+//       return %3
+//
+// Calls in the program go straight to the copy. Where the lowering boxed an
+// argument just for the call, the copy gets the value itself, and the
+// `unbox` of the result that follows a call becomes the call's destination,
+// so `print(fib(10))` makes no boxes at all. The boxed parameters and
+// results this leaves unused are then dropped from the functions' locals.
+
+use super::{Block, Function, Inst, LocalId, Operand, Program, Rvalue, Signature, Terminator, MAIN};
+use crate::compiler::types::Type;
+use std::collections::HashMap;
+
+/// Suffix naming a function's unboxed copy
+pub const UNBOXED_SUFFIX: &str = ".unboxed";
+
+/// Name of the unboxed copy of the function called `name`
+pub fn unboxed_name(name: &str) -> String {
+    format!("{}{}", name, UNBOXED_SUFFIX)
+}
+
+/// Whether values of type `ty` can be passed unboxed
+fn is_scalar(ty: &Type) -> bool {
+    matches!(ty, Type::Int | Type::Float | Type::Bool)
+}
+
+/// Give every function with int, float or bool parameters or results an
+/// unboxed copy after it, make the function a shim calling the copy, call
+/// the copies directly and drop the locals that leaves unused
+pub fn specialize(program: &mut Program) {
+    let specialized: HashMap<String, Signature> = program
+        .functions
+        .iter()
+        .filter(|function| {
+            function.name != MAIN
+                && !function.name.ends_with(UNBOXED_SUFFIX)
+                && (function.signature.params.iter().any(is_scalar) || is_scalar(&function.signature.returns))
+        })
+        .map(|function| (function.name.clone(), function.signature.clone()))
+        .collect();
+    if specialized.is_empty() {
+        return;
+    }
+
+    let mut functions = Vec::with_capacity(program.functions.len() + specialized.len());
+    for function in program.functions.drain(..) {
+        if specialized.contains_key(&function.name) {
+            let copy = unboxed_copy(&function);
+            functions.push(shim(&function, &copy));
+            functions.push(copy);
+        } else {
+            functions.push(function);
+        }
+    }
+    for function in &mut functions {
+        call_unboxed(function, &specialized);
+        remove_dead_locals(function);
+    }
+    program.functions = functions;
+}
+
+/// `function` taking its int, float and bool parameters and returning its
+/// result unboxed
+fn unboxed_copy(function: &Function) -> Function {
+    let mut copy = function.clone();
+    copy.name = unboxed_name(&function.name);
+
+    for (i, ty) in function.signature.params.iter().enumerate() {
+        if !is_scalar(ty) {
+            continue;
+        }
+        // The lowering unboxes each typed parameter at the start of the entry block
+        let boxed = function.params[i];
+        let entry = &mut copy.blocks[0].insts;
+        let unbox = entry.iter().position(|inst| {
+            matches!(inst, Inst::Assign { value: Rvalue::Unbox { value: Operand::Local(local), .. }, .. } if *local == boxed)
+        });
+        copy.params[i] = match unbox.map(|position| entry.remove(position)) {
+            Some(Inst::Assign { dest, .. }) => dest,
+            _ => copy.add_local(ty.clone()),
+        };
+    }
+
+    let returns = function.signature.returns.clone();
+    if is_scalar(&returns) {
+        copy.returns_unboxed = true;
+        let uses = use_counts(&copy);
+        for i in 0..copy.blocks.len() {
+            let Terminator::Return(value) = copy.blocks[i].terminator.clone() else {
+                continue;
+            };
+            // The lowering boxes a result just before returning it
+            let boxed_result = match (&value, copy.blocks[i].insts.last()) {
+                (
+                    Operand::Local(local),
+                    Some(Inst::Assign {
+                        dest,
+                        value: Rvalue::Box(inner),
+                    }),
+                ) if dest == local && uses[local.0] == 1 => Some(inner.clone()),
+                _ => None,
+            };
+            let unboxed = match boxed_result {
+                Some(inner) => {
+                    copy.blocks[i].insts.pop();
+                    inner
+                }
+                None => {
+                    let local = copy.add_local(returns.clone());
+                    copy.blocks[i].insts.push(Inst::Assign {
+                        dest: local,
+                        value: Rvalue::Unbox {
+                            value,
+                            to: returns.clone(),
+                        },
+                    });
+                    Operand::Local(local)
+                }
+            };
+            copy.blocks[i].terminator = Terminator::Return(unboxed);
+        }
+    }
+    copy
+}
+
+/// `function` as a shim unboxing its arguments for `copy` and boxing the result
+fn shim(function: &Function, copy: &Function) -> Function {
+    let mut shim = Function {
+        name: function.name.clone(),
+        signature: function.signature.clone(),
+        params: Vec::new(),
+        returns_unboxed: false,
+        locals: function.params.iter().map(|param| function.locals[param.0].clone()).collect(),
+        blocks: Vec::new(),
+    };
+    shim.params = (0..shim.locals.len()).map(LocalId).collect();
+
+    let mut insts = Vec::new();
+    let mut args = Vec::new();
+    for (i, ty) in function.signature.params.iter().enumerate() {
+        let param = Operand::Local(shim.params[i]);
+        if !is_scalar(ty) {
+            args.push(param);
+            continue;
+        }
+        let unboxed = shim.add_local(ty.clone());
+        insts.push(Inst::Assign {
+            dest: unboxed,
+            value: Rvalue::Unbox {
+                value: param,
+                to: ty.clone(),
+            },
+        });
+        args.push(Operand::Local(unboxed));
+    }
+
+    let call = Rvalue::Call {
+        function: copy.name.clone(),
+        args,
+    };
+    let result = if copy.returns_unboxed {
+        let unboxed = shim.add_local(function.signature.returns.clone());
+        let boxed = shim.add_local(Type::Any);
+        insts.push(Inst::Assign { dest: unboxed, value: call });
+        insts.push(Inst::Assign {
+            dest: boxed,
+            value: Rvalue::Box(Operand::Local(unboxed)),
+        });
+        boxed
+    } else {
+        let boxed = shim.add_local(Type::Any);
+        insts.push(Inst::Assign { dest: boxed, value: call });
+        boxed
+    };
+    shim.blocks.push(Block {
+        insts,
+        terminator: Terminator::Return(Operand::Local(result)),
+    });
+    shim
+}
+
+/// Point the calls in `function` to the functions `specialized` names at
+/// their unboxed copies
+fn call_unboxed(function: &mut Function, specialized: &HashMap<String, Signature>) {
+    let uses = use_counts(function);
+    for i in 0..function.blocks.len() {
+        let mut insts = std::mem::take(&mut function.blocks[i].insts).into_iter().peekable();
+        // Instructions so far, with `None` for boxes a call no longer needs
+        let mut rewritten: Vec<Option<Inst>> = Vec::new();
+
+        while let Some(inst) = insts.next() {
+            let (dest, callee, args) = match inst {
+                Inst::Assign {
+                    dest,
+                    value: Rvalue::Call { function: callee, args },
+                } if specialized.contains_key(&callee) => (dest, callee, args),
+                inst => {
+                    rewritten.push(Some(inst));
+                    continue;
+                }
+            };
+            let signature = &specialized[&callee];
+
+            let mut unboxed_args = Vec::with_capacity(args.len());
+            for (arg, ty) in args.into_iter().zip(&signature.params) {
+                if !is_scalar(ty) {
+                    unboxed_args.push(arg);
+                } else if let Some(value) = take_boxed(function, &uses, &mut rewritten, &arg, ty) {
+                    unboxed_args.push(value);
+                } else {
+                    let unboxed = function.add_local(ty.clone());
+                    rewritten.push(Some(Inst::Assign {
+                        dest: unboxed,
+                        value: Rvalue::Unbox {
+                            value: arg,
+                            to: ty.clone(),
+                        },
+                    }));
+                    unboxed_args.push(Operand::Local(unboxed));
+                }
+            }
+            let call = Rvalue::Call {
+                function: unboxed_name(&callee),
+                args: unboxed_args,
+            };
+
+            if !is_scalar(&signature.returns) {
+                rewritten.push(Some(Inst::Assign { dest, value: call }));
+                continue;
+            }
+            match insts.peek() {
+                Some(Inst::Assign {
+                    dest: unboxed,
+                    value: Rvalue::Unbox {
+                        value: Operand::Local(boxed),
+                        to,
+                    },
+                }) if *boxed == dest && *to == signature.returns && uses[dest.0] == 1 => {
+                    rewritten.push(Some(Inst::Assign { dest: *unboxed, value: call }));
+                    insts.next();
+                }
+                _ => {
+                    let unboxed = function.add_local(signature.returns.clone());
+                    rewritten.push(Some(Inst::Assign { dest: unboxed, value: call }));
+                    rewritten.push(Some(Inst::Assign {
+                        dest,
+                        value: Rvalue::Box(Operand::Local(unboxed)),
+                    }));
+                }
+            }
+        }
+        function.blocks[i].insts = rewritten.into_iter().flatten().collect();
+    }
+}
+
+/// The value `arg` is a box of, if the box was made earlier in the block
+/// only to be passed as a `ty` argument and the value hasn't changed since.
+/// The box is dropped from `insts`.
+fn take_boxed(
+    function: &Function,
+    uses: &[usize],
+    insts: &mut [Option<Inst>],
+    arg: &Operand,
+    ty: &Type,
+) -> Option<Operand> {
+    let Operand::Local(boxed) = arg else {
+        return None;
+    };
+    if uses[boxed.0] != 1 {
+        return None;
+    }
+    let position = insts
+        .iter()
+        .rposition(|inst| matches!(inst, Some(Inst::Assign { dest, .. }) if dest == boxed))?;
+    let Some(Inst::Assign {
+        value: Rvalue::Box(value),
+        ..
+    }) = &insts[position]
+    else {
+        return None;
+    };
+    if function.operand_type(value) != *ty {
+        return None;
+    }
+    if let Operand::Local(local) = value {
+        let reassigned = insts[position + 1..]
+            .iter()
+            .flatten()
+            .any(|inst| matches!(inst, Inst::Assign { dest, .. } if dest == local));
+        if reassigned {
+            return None;
+        }
+    }
+    let value = value.clone();
+    insts[position] = None;
+    Some(value)
+}
+
+/// Drop the locals of `function` that are neither parameters nor assigned
+/// nor read, such as the boxed parameters of an unboxed copy, and renumber
+/// the rest
+fn remove_dead_locals(function: &mut Function) {
+    let mut live = vec![false; function.locals.len()];
+    for param in &function.params {
+        live[param.0] = true;
+    }
+    for local in locals_mut(function) {
+        live[local.0] = true;
+    }
+    if live.iter().all(|&live| live) {
+        return;
+    }
+
+    let mut renumbered = vec![LocalId(0); live.len()];
+    let mut locals = Vec::with_capacity(live.len());
+    for (i, local) in std::mem::take(&mut function.locals).into_iter().enumerate() {
+        if live[i] {
+            renumbered[i] = LocalId(locals.len());
+            locals.push(local);
+        }
+    }
+    function.locals = locals;
+    for param in &mut function.params {
+        *param = renumbered[param.0];
+    }
+    for local in locals_mut(function) {
+        *local = renumbered[local.0];
+    }
+}
+
+/// Every local the instructions and terminators of `function` assign or read
+fn locals_mut(function: &mut Function) -> Vec<&mut LocalId> {
+    let mut operands = Vec::new();
+    let mut dests = Vec::new();
+    for block in &mut function.blocks {
+        for inst in &mut block.insts {
+            match inst {
+                Inst::Line(_) => {}
+                Inst::Assign { dest, value } => {
+                    dests.push(dest);
+                    operands.extend(rvalue_operands_mut(value));
+                }
+                Inst::Print(values) => operands.extend(values.iter_mut()),
+                Inst::ListPush { list, value } => operands.extend([list, value]),
+                Inst::ListSet { list, index, value } => operands.extend([list, index, value]),
+            }
+        }
+        match &mut block.terminator {
+            Terminator::Goto(_) => {}
+            Terminator::Branch { cond, .. } => operands.push(cond),
+            Terminator::Return(value) => operands.push(value),
+        }
+    }
+    operands
+        .into_iter()
+        .filter_map(|operand| match operand {
+            Operand::Local(local) => Some(local),
+            Operand::Const(_) => None,
+        })
+        .chain(dests)
+        .collect()
+}
+
+/// Number of times each local of `function` is read
+fn use_counts(function: &Function) -> Vec<usize> {
+    let mut uses = vec![0; function.locals.len()];
+    let mut count = |operand: &Operand| {
+        if let Operand::Local(local) = operand {
+            uses[local.0] += 1;
+        }
+    };
+    for block in &function.blocks {
+        for inst in &block.insts {
+            match inst {
+                Inst::Line(_) => {}
+                Inst::Assign { value, .. } => rvalue_operands(value).into_iter().for_each(&mut count),
+                Inst::Print(operands) => operands.iter().for_each(&mut count),
+                Inst::ListPush { list, value } => [list, value].into_iter().for_each(&mut count),
+                Inst::ListSet { list, index, value } => [list, index, value].into_iter().for_each(&mut count),
+            }
+        }
+        match &block.terminator {
+            Terminator::Goto(_) => {}
+            Terminator::Branch { cond, .. } => count(cond),
+            Terminator::Return(value) => count(value),
+        }
+    }
+    uses
+}
+
+fn rvalue_operands_mut(rvalue: &mut Rvalue) -> Vec<&mut Operand> {
+    match rvalue {
+        Rvalue::Use(operand) | Rvalue::Box(operand) | Rvalue::Len(operand) => vec![operand],
+        Rvalue::Unary { operand, .. } => vec![operand],
+        Rvalue::Cast { value, .. } | Rvalue::Unbox { value, .. } => vec![value],
+        Rvalue::Binary { left, right, .. } | Rvalue::Compare { left, right, .. } => vec![left, right],
+        Rvalue::Call { args, .. } => args.iter_mut().collect(),
+        Rvalue::ListNew => Vec::new(),
+        Rvalue::ListGet { list, index } => vec![list, index],
+    }
+}
+
+fn rvalue_operands(rvalue: &Rvalue) -> Vec<&Operand> {
+    match rvalue {
+        Rvalue::Use(operand) | Rvalue::Box(operand) | Rvalue::Len(operand) => vec![operand],
+        Rvalue::Unary { operand, .. } => vec![operand],
+        Rvalue::Cast { value, .. } | Rvalue::Unbox { value, .. } => vec![value],
+        Rvalue::Binary { left, right, .. } | Rvalue::Compare { left, right, .. } => vec![left, right],
+        Rvalue::Call { args, .. } => args.iter().collect(),
+        Rvalue::ListNew => Vec::new(),
+        Rvalue::ListGet { list, index } => vec![list, index],
+    }
+}
@@ -1,3 +1,5 @@
+use super::token::TokenType;
+
 #[derive(Debug, Clone)]
 pub struct LexerConfig {
     pub tab_width: usize,
@@ -5,6 +7,13 @@ pub struct LexerConfig {
     pub standard_indent_size: usize,
     pub allow_trailing_semicolon: bool,
     pub allow_tabs_in_indentation: bool,
+    /// Lex soft keywords such as `match` as keywords everywhere, so they can't be used as names
+    pub reserve_soft_keywords: bool,
+    /// Record the exact source text of every token, so `tokens_to_source` can
+    /// reproduce the input byte for byte
+    pub lossless: bool,
+    /// Constructs reported as errors wherever they're used
+    pub forbidden: Vec<Construct>,
 }
 
 impl Default for LexerConfig {
@@ -15,6 +24,130 @@ impl Default for LexerConfig {
             standard_indent_size: 4,
             allow_trailing_semicolon: true,
             allow_tabs_in_indentation: false,
+            reserve_soft_keywords: false,
+            lossless: false,
+            forbidden: Vec::new(),
+        }
+    }
+}
+
+impl LexerConfig {
+    /// Whether `construct` is forbidden
+    pub fn forbids(&self, construct: Construct) -> bool {
+        self.forbidden.contains(&construct)
+    }
+}
+
+/// A language construct that can be forbidden, for example in teaching
+/// environments, each recognised by the tokens that introduce it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Construct {
+    Lambda,
+    Walrus,
+    Semicolon,
+    Global,
+    Nonlocal,
+    Yield,
+    Async,
+}
+
+impl Construct {
+    /// Every construct, in the order they're listed in help text
+    pub const ALL: [Construct; 7] = [
+        Construct::Lambda,
+        Construct::Walrus,
+        Construct::Semicolon,
+        Construct::Global,
+        Construct::Nonlocal,
+        Construct::Yield,
+        Construct::Async,
+    ];
+
+    /// Name used in cheetah.toml
+    pub fn name(self) -> &'static str {
+        match self {
+            Construct::Lambda => "lambda",
+            Construct::Walrus => "walrus",
+            Construct::Semicolon => "semicolon",
+            Construct::Global => "global",
+            Construct::Nonlocal => "nonlocal",
+            Construct::Yield => "yield",
+            Construct::Async => "async",
+        }
+    }
+
+    /// The construct called `name` in cheetah.toml
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|construct| construct.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|c| c.name()).collect();
+                format!(
+                    "unknown construct '{}' (expected one of {})",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+
+    /// Tokens that introduce the construct
+    pub fn tokens(self) -> &'static [TokenType<'static>] {
+        match self {
+            Construct::Lambda => &[TokenType::Lambda],
+            Construct::Walrus => &[TokenType::Walrus],
+            Construct::Semicolon => &[TokenType::SemiColon],
+            Construct::Global => &[TokenType::Global],
+            Construct::Nonlocal => &[TokenType::Nonlocal],
+            Construct::Yield => &[TokenType::Yield],
+            Construct::Async => &[TokenType::Async, TokenType::Await],
+        }
+    }
+
+    /// What the construct is, as the subject of the error reporting its use
+    pub fn description(self) -> &'static str {
+        match self {
+            Construct::Lambda => "Lambda expressions are",
+            Construct::Walrus => "The walrus operator `:=` is",
+            Construct::Semicolon => "Semicolons are",
+            Construct::Global => "`global` declarations are",
+            Construct::Nonlocal => "`nonlocal` declarations are",
+            Construct::Yield => "Generators (`yield`) are",
+            Construct::Async => "`async` and `await` are",
+        }
+    }
+
+    /// How to write the code without the construct
+    pub fn suggestion(self) -> &'static str {
+        match self {
+            Construct::Lambda => "Define a named function with `def` instead",
+            Construct::Walrus => "Assign the value on its own line before using it",
+            Construct::Semicolon => "Put each statement on its own line",
+            Construct::Global | Construct::Nonlocal => {
+                "Pass the value in as an argument and return the new value"
+            }
+            Construct::Yield => "Build a list and return it instead",
+            Construct::Async => "Define and call an ordinary function instead",
+        }
+    }
+
+    /// Constructs forbidden by the named profile: `full` allows everything and
+    /// `learning` keeps to the core language taught first
+    pub fn profile(name: &str) -> Result<Vec<Construct>, String> {
+        match name {
+            "full" => Ok(Vec::new()),
+            "learning" => Ok(vec![
+                Construct::Lambda,
+                Construct::Walrus,
+                Construct::Semicolon,
+                Construct::Global,
+                Construct::Nonlocal,
+                Construct::Async,
+            ]),
+            other => Err(format!(
+                "unknown profile '{}' (expected full or learning)",
+                other
+            )),
         }
     }
 }
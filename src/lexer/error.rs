@@ -1,5 +1,8 @@
 use std::fmt;
 
+/// End of the message of every error reporting a forbidden construct
+pub const FORBIDDEN: &str = "not allowed in this project";
+
 #[derive(Debug, Clone)]
 pub struct LexerError {
     pub message: String,
@@ -9,6 +12,26 @@ pub struct LexerError {
     pub suggestion: Option<String>,
 }
 
+impl LexerError {
+    /// Stable diagnostic code for the kind of error
+    pub fn code(&self) -> &'static str {
+        let message = self.message.as_str();
+        if message.ends_with(FORBIDDEN) {
+            "forbidden-construct"
+        } else if message.contains("indent") || message.starts_with("Tabs") {
+            "inconsistent-indentation"
+        } else if message.contains("escape") || message.contains("code point") {
+            "invalid-escape"
+        } else if message.starts_with("Unterminated") {
+            "unterminated-literal"
+        } else if message.contains("literal") || message.starts_with("Invalid") {
+            "invalid-literal"
+        } else {
+            "unexpected-character"
+        }
+    }
+}
+
 impl fmt::Display for LexerError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -78,7 +78,7 @@ impl<'a> Lexer<'a> {
         self.consume_while(|c| c == ' ' || c == '\t');
     }
 
-    pub fn get_slice(&self, start: usize, end: usize) -> &str {
+    pub fn get_slice(&self, start: usize, end: usize) -> &'a str {
         debug_assert!(
             self.input.is_char_boundary(start),
             "start must be at a character boundary"
@@ -90,6 +90,43 @@ impl<'a> Lexer<'a> {
         &self.input[start..end]
     }
 
+    /// Consume the rest of a string literal whose body has no escapes and
+    /// return the body, so its value can borrow the source. Returns `None`
+    /// without consuming anything when the body needs decoding or the
+    /// literal is not closed
+    pub fn take_plain_string_body(&mut self, quote_char: char, triple: bool) -> Option<&'a str> {
+        let rest = &self.input[self.position..];
+        let (body_len, quote_len) = if triple {
+            let closing = quote_char.to_string().repeat(3);
+            let body_len = rest.find(&closing)?;
+            if rest[..body_len].contains('\\') {
+                return None;
+            }
+            (body_len, 3)
+        } else {
+            let stop = rest.find([quote_char, '\\', '\n', '\r'])?;
+            if !rest[stop..].starts_with(quote_char) {
+                return None;
+            }
+            (stop, 1)
+        };
+        let body = &rest[..body_len];
+
+        let end = self.position + body_len + quote_len;
+        if triple {
+            while self.position < end {
+                self.consume_char();
+            }
+        } else {
+            // A single-quoted body has no line breaks, so jump over it
+            self.column += body.chars().count() + quote_len;
+            self.position = end;
+            self.lookahead_buffer.clear();
+            self.chars = self.input[end..].chars();
+        }
+        Some(body)
+    }
+
     pub fn skip_whitespace(&mut self) {
         loop {
             self.consume_whitespace();
@@ -0,0 +1,145 @@
+// keywords.rs - Table of reserved words shared by the lexer, parser and tools
+//
+// Hard keywords are always lexed as keywords. Soft keywords (`match`, `case`)
+// were added after programs were already using them as names, so they only
+// act as keywords at the start of the statements they introduce; anywhere else
+// they stay ordinary identifiers and the lint pass warns about the use.
+
+use super::TokenType;
+
+/// A reserved word and the token it lexes to
+#[derive(Debug)]
+pub struct Keyword {
+    pub text: &'static str,
+    pub token: TokenType<'static>,
+    /// Whether programs may still use the word as a name
+    pub soft: bool,
+}
+
+impl Keyword {
+    const fn hard(text: &'static str, token: TokenType<'static>) -> Self {
+        Keyword {
+            text,
+            token,
+            soft: false,
+        }
+    }
+
+    const fn soft(text: &'static str, token: TokenType<'static>) -> Self {
+        Keyword {
+            text,
+            token,
+            soft: true,
+        }
+    }
+}
+
+/// Reserved words of the language
+pub static KEYWORDS: &[Keyword] = &[
+    Keyword::hard("def", TokenType::Def),
+    Keyword::hard("return", TokenType::Return),
+    Keyword::hard("if", TokenType::If),
+    Keyword::hard("elif", TokenType::Elif),
+    Keyword::hard("else", TokenType::Else),
+    Keyword::hard("while", TokenType::While),
+    Keyword::hard("for", TokenType::For),
+    Keyword::hard("in", TokenType::In),
+    Keyword::hard("break", TokenType::Break),
+    Keyword::hard("continue", TokenType::Continue),
+    Keyword::hard("pass", TokenType::Pass),
+    Keyword::hard("import", TokenType::Import),
+    Keyword::hard("from", TokenType::From),
+    Keyword::hard("as", TokenType::As),
+    Keyword::hard("True", TokenType::True),
+    Keyword::hard("False", TokenType::False),
+    Keyword::hard("None", TokenType::None),
+    Keyword::hard("and", TokenType::And),
+    Keyword::hard("or", TokenType::Or),
+    Keyword::hard("not", TokenType::Not),
+    Keyword::hard("class", TokenType::Class),
+    Keyword::hard("with", TokenType::With),
+    Keyword::hard("assert", TokenType::Assert),
+    Keyword::hard("async", TokenType::Async),
+    Keyword::hard("await", TokenType::Await),
+    Keyword::hard("try", TokenType::Try),
+    Keyword::hard("except", TokenType::Except),
+    Keyword::hard("finally", TokenType::Finally),
+    Keyword::hard("raise", TokenType::Raise),
+    Keyword::hard("lambda", TokenType::Lambda),
+    Keyword::hard("global", TokenType::Global),
+    Keyword::hard("nonlocal", TokenType::Nonlocal),
+    Keyword::hard("yield", TokenType::Yield),
+    Keyword::hard("del", TokenType::Del),
+    Keyword::hard("is", TokenType::Is),
+    Keyword::soft("match", TokenType::Match),
+    Keyword::soft("case", TokenType::Case),
+];
+
+/// The keyword spelled `text`
+pub fn lookup(text: &str) -> Option<&'static Keyword> {
+    KEYWORDS.iter().find(|keyword| keyword.text == text)
+}
+
+/// The keyword lexed to `token`
+pub fn for_token(token: &TokenType) -> Option<&'static Keyword> {
+    KEYWORDS.iter().find(|keyword| keyword.token == *token)
+}
+
+/// Whether `name` is a soft keyword, and so may become reserved in future
+pub fn is_soft_keyword(name: &str) -> bool {
+    lookup(name).is_some_and(|keyword| keyword.soft)
+}
+
+/// Whether a soft keyword at the start of a line introduces a statement,
+/// given the `rest` of the source after it: the word must be followed by an
+/// operand rather than an assignment, attribute access or separator, and the
+/// logical line must end with the statement's `:`
+pub fn starts_statement(rest: &str) -> bool {
+    let after = rest.trim_start_matches([' ', '\t']);
+    let mut chars = after.chars();
+    match (chars.next(), chars.next()) {
+        (None | Some('\n' | '\r' | '#' | '.' | ',' | ':' | ';' | ')' | ']' | '}'), _) => {
+            return false
+        }
+        (Some('='), second) if second != Some('=') => return false,
+        (Some('+' | '-' | '*' | '/' | '%' | '&' | '|' | '^' | '@'), Some('=')) => return false,
+        _ => {}
+    }
+
+    let mut depth = 0usize;
+    let mut last = None;
+    let mut quote = None;
+    let mut chars = after.chars().peekable();
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                c if c == q => quote = None,
+                _ => {}
+            }
+            last = Some(c);
+            continue;
+        }
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            '\\' if chars.peek() == Some(&'\n') => {
+                chars.next();
+                continue;
+            }
+            '#' => {
+                while chars.next_if(|&c| c != '\n' && c != '\r').is_some() {}
+                continue;
+            }
+            '\n' | '\r' if depth == 0 => break,
+            _ => {}
+        }
+        if !c.is_whitespace() {
+            last = Some(c);
+        }
+    }
+    last == Some(':')
+}
@@ -1,13 +1,18 @@
+use crate::ast::Span;
+
 pub mod config;
 pub mod error;
 pub mod helpers;
+pub mod keywords;
 pub mod token;
 
-pub use config::LexerConfig;
+pub use config::{Construct, LexerConfig};
 pub use error::LexerError;
-use std::collections::HashSet;
+pub use keywords::KEYWORDS;
+use std::collections::VecDeque;
+use std::num::{IntErrorKind, ParseIntError};
 use std::str::FromStr;
-pub use token::{Token, TokenType};
+pub use token::{Token, TokenSource, TokenType};
 
 pub struct Lexer<'a> {
     input: &'a str,
@@ -23,21 +28,21 @@ pub struct Lexer<'a> {
     bracket_level: usize,
     brace_level: usize,
     lookahead_buffer: Vec<char>,
-    keywords: HashSet<&'static str>,
+    /// Byte offset where the token being lexed starts
+    token_start: usize,
+    /// Byte offset up to which tokens have recorded their source, in lossless mode
+    source_end: usize,
+    /// Tokens lexed but not yet returned: indents and dedents come out
+    /// together with the token after them
+    pending: VecDeque<Token<'a>>,
+    /// Whether the next token starts a logical line, so its indentation counts
+    at_line_start: bool,
+    /// Whether the end of the input has been reached
+    finished: bool,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
-        let mut keywords = HashSet::new();
-        for kw in &[
-            "def", "return", "if", "elif", "else", "while", "for", "in", "break", "continue",
-            "pass", "import", "from", "as", "True", "False", "None", "and", "or", "not", "class",
-            "with", "assert", "async", "await", "try", "except", "finally", "raise", "lambda",
-            "global", "nonlocal", "yield", "del", "is", "match", "case",
-        ] {
-            keywords.insert(*kw);
-        }
-
         Lexer {
             input,
             chars: input.chars(),
@@ -52,10 +57,27 @@ impl<'a> Lexer<'a> {
             bracket_level: 0,
             brace_level: 0,
             lookahead_buffer: Vec::new(),
-            keywords,
+            token_start: 0,
+            source_end: 0,
+            pending: VecDeque::new(),
+            at_line_start: true,
+            finished: false,
         }
     }
 
+    /// Lex `input` from byte `position`, the start of line `line`, as if
+    /// the lines before it held complete top-level statements. Tokens keep
+    /// their lines and byte offsets in the whole input.
+    pub fn starting_at(input: &'a str, position: usize, line: usize) -> Self {
+        let mut lexer = Lexer::new(input);
+        lexer.chars = input[position..].chars();
+        lexer.position = position;
+        lexer.line = line;
+        lexer.token_start = position;
+        lexer.source_end = position;
+        lexer
+    }
+
     pub fn with_config(input: &'a str, config: LexerConfig) -> Self {
         let mut lexer = Lexer::new(input);
         lexer.config = config;
@@ -66,67 +88,92 @@ impl<'a> Lexer<'a> {
         &self.errors
     }
 
-    pub fn tokenize(&mut self) -> Vec<Token> {
+    /// Lex the whole input. Iterating over the lexer yields the same
+    /// tokens one at a time, lexing each only when it is asked for.
+    pub fn tokenize(&mut self) -> Vec<Token<'a>> {
         let estimated_token_count = self.input.len() / 5;
         let mut tokens = Vec::with_capacity(estimated_token_count);
-        let mut pending_indentation_change = true;
-
-        while let Some(token) = self.next_token() {
-            match token.token_type {
-                TokenType::EOF => {
-                    while self.indent_stack.len() > 1 {
-                        self.indent_stack.pop();
-                        tokens.push(Token::new(
-                            TokenType::Dedent,
-                            self.line,
-                            self.column,
-                            "".to_string(),
-                        ));
-                    }
-
-                    tokens.push(token);
-                    break;
-                }
-                _ => {
-                    self.update_nesting_level(&token.token_type);
-
-                    let token_type = token.token_type.clone();
-                    let token_line = token.line;
-
-                    if pending_indentation_change
-                        && self.paren_level == 0
-                        && self.bracket_level == 0
-                        && self.brace_level == 0
-                    {
-                        self.handle_indentation_change(&mut tokens, token_line);
-                        pending_indentation_change = false;
-                    }
+        tokens.extend(self.by_ref());
+        tokens
+    }
 
-                    tokens.push(token);
+    /// Lex the next token, queueing it after the indents or dedents it starts with
+    fn lex_pending(&mut self) {
+        let Some(mut token) = self.next_token() else {
+            self.finished = true;
+            return;
+        };
+        self.record_source(&mut token);
 
-                    if matches!(token_type, TokenType::Newline)
-                        && self.paren_level == 0
-                        && self.bracket_level == 0
-                        && self.brace_level == 0
-                    {
-                        pending_indentation_change = true;
-                    }
-                }
+        if token.token_type == TokenType::EOF {
+            while self.indent_stack.len() > 1 {
+                self.indent_stack.pop();
+                self.pending
+                    .push_back(Token::new(TokenType::Dedent, self.line, self.column, ""));
             }
+            self.pending.push_back(token);
+            self.finished = true;
+            return;
         }
 
-        tokens
+        self.update_nesting_level(&token.token_type);
+        self.check_forbidden(&token);
+
+        let bracketed = self.paren_level > 0 || self.bracket_level > 0 || self.brace_level > 0;
+        if self.at_line_start && !bracketed {
+            self.handle_indentation_change(token.line);
+            self.at_line_start = false;
+        }
+        if token.token_type == TokenType::Newline && !bracketed {
+            self.at_line_start = true;
+        }
+        self.pending.push_back(token);
+    }
+
+    /// Record where `token` is in the input and, in lossless mode, the source
+    /// text consumed since the previous token as its leading text and its
+    /// own text
+    fn record_source(&mut self, token: &mut Token<'a>) {
+        let start = self.token_start.clamp(self.source_end, self.position);
+        let consumed = &self.input[start..self.position];
+        let end = if !token.lexeme.is_empty() && consumed.starts_with(&*token.lexeme) {
+            start + token.lexeme.len()
+        } else {
+            self.position
+        };
+        let text = &self.input[start..end];
+        let (end_line, end_column) = match text.rfind('\n') {
+            Some(newline) => (
+                token.line + text.matches('\n').count(),
+                text[newline + 1..].chars().count() + 1,
+            ),
+            None => (token.line, token.column + text.chars().count()),
+        };
+        token.span = Span {
+            start,
+            end,
+            end_line,
+            end_column,
+        };
+        if self.config.lossless {
+            token.source = Some(TokenSource {
+                leading: &self.input[self.source_end..start],
+                text,
+            });
+        }
+        self.source_end = end;
     }
 
-    fn next_token(&mut self) -> Option<Token> {
+    fn next_token(&mut self) -> Option<Token<'a>> {
         self.skip_whitespace();
+        self.token_start = self.position;
 
         if self.is_at_end() {
             return Some(Token::new(
                 TokenType::EOF,
                 self.line,
                 self.column,
-                "".to_string(),
+                "",
             ));
         }
 
@@ -267,7 +314,7 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    fn handle_indentation_change(&mut self, tokens: &mut Vec<Token>, token_line: usize) {
+    fn handle_indentation_change(&mut self, token_line: usize) {
         let current_indent = self.current_indent;
         let previous_indent = *self.indent_stack.last().unwrap_or(&0);
 
@@ -295,7 +342,7 @@ impl<'a> Lexer<'a> {
             let indent_token =
                 Token::new(TokenType::Indent, token_line, 1, " ".repeat(current_indent));
             self.indent_stack.push(current_indent);
-            tokens.push(indent_token);
+            self.pending.push_back(indent_token);
         } else if current_indent < previous_indent {
             let mut _dedent_count = 0;
 
@@ -319,12 +366,37 @@ impl<'a> Lexer<'a> {
             while self.indent_stack.len() > 1 && current_indent < *self.indent_stack.last().unwrap()
             {
                 self.indent_stack.pop();
-                tokens.push(Token::new(TokenType::Dedent, token_line, 1, "".to_string()));
+                self.pending
+                    .push_back(Token::new(TokenType::Dedent, token_line, 1, ""));
                 _dedent_count += 1;
             }
         }
     }
 
+    /// Report `token` if it introduces a construct the config forbids
+    fn check_forbidden(&mut self, token: &Token) {
+        let Some(construct) = self
+            .config
+            .forbidden
+            .iter()
+            .copied()
+            .find(|construct| construct.tokens().contains(&token.token_type))
+        else {
+            return;
+        };
+        // Already reported when the lexer was told not to allow them
+        if construct == Construct::Semicolon && !self.config.allow_trailing_semicolon {
+            return;
+        }
+        self.errors.push(LexerError {
+            message: format!("{} {}", construct.description(), error::FORBIDDEN),
+            line: token.line,
+            column: token.column,
+            snippet: self.get_error_context(),
+            suggestion: Some(construct.suggestion().to_string()),
+        });
+    }
+
     fn add_error(&mut self, message: &str) {
         let error = LexerError {
             message: message.to_string(),
@@ -347,6 +419,29 @@ impl<'a> Lexer<'a> {
         self.errors.push(error);
     }
 
+    /// Report an integer literal that does not fit in 64 bits, suggesting
+    /// alternatives, and return the error message
+    fn add_int_overflow_error(&mut self, text: &str, start_col: usize) -> String {
+        let message = format!(
+            "Integer literal {} is out of range: integers must be between {} and {}",
+            text,
+            i64::MIN,
+            i64::MAX
+        );
+        let suggestion = if text == "9223372036854775808" {
+            format!("write the smallest integer as {} - 1", i64::MIN + 1)
+        } else if text.chars().all(|c| c.is_ascii_digit()) {
+            format!(
+                "use a float ({}.0) for an approximate value, or a string (\"{}\") to keep every digit",
+                text, text
+            )
+        } else {
+            "use a float for an approximate value, or a string to keep every digit".to_string()
+        };
+        self.add_error_with_position(&message, &suggestion, self.line, start_col);
+        message
+    }
+
     fn get_error_context(&self) -> String {
         let lines: Vec<&str> = self.input.lines().collect();
         if self.line <= lines.len() {
@@ -388,7 +483,7 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    fn handle_newline(&mut self) -> Option<Token> {
+    fn handle_newline(&mut self) -> Option<Token<'a>> {
         let start_col = self.column;
         let start_line = self.line;
 
@@ -403,7 +498,7 @@ impl<'a> Lexer<'a> {
 
         let indent_size = self.count_indentation();
 
-        let newline_token = Token::new(TokenType::Newline, start_line, start_col, "\n".to_string());
+        let newline_token = Token::new(TokenType::Newline, start_line, start_col, "\n");
 
         self.current_indent = indent_size;
 
@@ -469,7 +564,7 @@ impl<'a> Lexer<'a> {
         count
     }
 
-    fn handle_identifier(&mut self) -> Token {
+    fn handle_identifier(&mut self) -> Token<'a> {
         let start_pos = self.position;
         let start_col = self.column;
 
@@ -477,55 +572,34 @@ impl<'a> Lexer<'a> {
 
         let text = self.get_slice(start_pos, self.position);
 
-        let token_type = if self.keywords.contains(text) {
-            match text {
-                "def" => TokenType::Def,
-                "return" => TokenType::Return,
-                "if" => TokenType::If,
-                "elif" => TokenType::Elif,
-                "else" => TokenType::Else,
-                "while" => TokenType::While,
-                "for" => TokenType::For,
-                "in" => TokenType::In,
-                "break" => TokenType::Break,
-                "continue" => TokenType::Continue,
-                "pass" => TokenType::Pass,
-                "True" => TokenType::True,
-                "False" => TokenType::False,
-                "None" => TokenType::None,
-                "and" => TokenType::And,
-                "or" => TokenType::Or,
-                "not" => TokenType::Not,
-                "is" => TokenType::Is,
-                "import" => TokenType::Import,
-                "from" => TokenType::From,
-                "as" => TokenType::As,
-                "class" => TokenType::Class,
-                "with" => TokenType::With,
-                "assert" => TokenType::Assert,
-                "async" => TokenType::Async,
-                "await" => TokenType::Await,
-                "try" => TokenType::Try,
-                "except" => TokenType::Except,
-                "finally" => TokenType::Finally,
-                "raise" => TokenType::Raise,
-                "lambda" => TokenType::Lambda,
-                "global" => TokenType::Global,
-                "nonlocal" => TokenType::Nonlocal,
-                "yield" => TokenType::Yield,
-                "del" => TokenType::Del,
-                "match" => TokenType::Match,
-                "case" => TokenType::Case,
-                _ => TokenType::Identifier(text.to_string()),
+        let token_type = match keywords::lookup(text) {
+            Some(keyword)
+                if !keyword.soft
+                    || self.config.reserve_soft_keywords
+                    || self.starts_soft_keyword_statement(start_pos) =>
+            {
+                keyword.token.clone()
             }
-        } else {
-            TokenType::Identifier(text.to_string())
+            _ => TokenType::Identifier(text.into()),
         };
 
-        Token::new(token_type, self.line, start_col, text.to_string())
+        Token::new(token_type, self.line, start_col, text)
+    }
+
+    /// Whether the soft keyword ending at the current position and starting
+    /// at `start_pos` begins a statement, rather than being used as a name
+    fn starts_soft_keyword_statement(&self, start_pos: usize) -> bool {
+        let at_line_start = self.paren_level == 0
+            && self.bracket_level == 0
+            && self.brace_level == 0
+            && self.input[..start_pos]
+                .rsplit('\n')
+                .next()
+                .is_some_and(|before| before.trim().is_empty());
+        at_line_start && keywords::starts_statement(&self.input[self.position..])
     }
 
-    fn handle_number(&mut self) -> Token {
+    fn handle_number(&mut self) -> Token<'a> {
         let start_pos = self.position;
         let start_col = self.column;
 
@@ -555,11 +629,11 @@ impl<'a> Lexer<'a> {
             self.consume_char();
 
             if self.is_at_end() || !self.peek_char().is_digit(10) {
-                let text = self.get_slice(start_pos, self.position).to_string();
+                let text = self.get_slice(start_pos, self.position);
                 self.add_error(
                     "Invalid float literal: must have at least one digit after decimal point",
                 );
-                return Token::error("Invalid float literal", self.line, start_col, &text);
+                return Token::error("Invalid float literal", self.line, start_col, text);
             }
 
             self.consume_while(|c| c.is_digit(10) || c == '_');
@@ -584,15 +658,15 @@ impl<'a> Lexer<'a> {
             }
 
             if self.is_at_end() || !self.peek_char().is_digit(10) {
-                let text = self.get_slice(start_pos, self.position).to_string();
+                let text = self.get_slice(start_pos, self.position);
                 self.add_error("Invalid exponent: must start with a digit");
-                return Token::error("Invalid exponent", self.line, start_col, &text);
+                return Token::error("Invalid exponent", self.line, start_col, text);
             }
 
             self.consume_while(|c| c.is_digit(10) || c == '_');
         }
 
-        let raw_text = self.get_slice(start_pos, self.position).to_string();
+        let raw_text = self.get_slice(start_pos, self.position);
         let text = raw_text.replace("_", "");
 
         if !self.is_at_end()
@@ -603,12 +677,12 @@ impl<'a> Lexer<'a> {
             self.add_error("Invalid number format: multiple decimal points");
             self.consume_char();
             self.consume_while(|c| c.is_digit(10) || c == '_' || c == '.');
-            let full_text = self.get_slice(start_pos, self.position).to_string();
+            let full_text = self.get_slice(start_pos, self.position);
             return Token::error(
                 "Invalid number format: multiple decimal points",
                 self.line,
                 start_col,
-                &full_text,
+                full_text,
             );
         }
 
@@ -624,6 +698,7 @@ impl<'a> Lexer<'a> {
         } else {
             match i64::from_str(&text) {
                 Ok(value) => TokenType::IntLiteral(value),
+                Err(e) if is_overflow(&e) => TokenType::Invalid(self.add_int_overflow_error(&text, start_col)),
                 Err(_) => {
                     let err_msg = format!("Invalid integer literal: {}", text);
                     self.add_error(&err_msg);
@@ -635,16 +710,16 @@ impl<'a> Lexer<'a> {
         Token::new(token_type, self.line, start_col, raw_text)
     }
 
-    fn handle_binary_literal(&mut self, start_pos: usize, start_col: usize) -> Token {
+    fn handle_binary_literal(&mut self, start_pos: usize, start_col: usize) -> Token<'a> {
         self.consume_char();
         self.consume_while(|c| c.is_digit(10) || c == '_');
-        let raw_text = self.get_slice(start_pos, self.position).to_string();
+        let raw_text = self.get_slice(start_pos, self.position);
         let text = raw_text.replace("_", "");
         let value_text = &text[2..];
         if value_text.is_empty() || value_text.chars().any(|c| c != '0' && c != '1') {
             let err_msg = format!("Invalid binary literal: {}", text);
             self.add_error(&err_msg);
-            return Token::error(&err_msg, self.line, start_col, &raw_text);
+            return Token::error(&err_msg, self.line, start_col, raw_text);
         }
         match i64::from_str_radix(value_text, 2) {
             Ok(value) => Token::new(
@@ -653,15 +728,19 @@ impl<'a> Lexer<'a> {
                 start_col,
                 raw_text,
             ),
+            Err(e) if is_overflow(&e) => {
+                let err_msg = self.add_int_overflow_error(&text, start_col);
+                Token::error(&err_msg, self.line, start_col, raw_text)
+            }
             Err(_) => {
                 let err_msg = format!("Invalid binary literal: {}", text);
                 self.add_error(&err_msg);
-                Token::error(&err_msg, self.line, start_col, &raw_text)
+                Token::error(&err_msg, self.line, start_col, raw_text)
             }
         }
     }
 
-    fn handle_octal_literal(&mut self, start_pos: usize, start_col: usize) -> Token {
+    fn handle_octal_literal(&mut self, start_pos: usize, start_col: usize) -> Token<'a> {
         self.consume_char();
 
         let mut seen_digit = false;
@@ -678,12 +757,12 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        let raw_text = self.get_slice(start_pos, self.position).to_string();
+        let raw_text = self.get_slice(start_pos, self.position);
 
         if !seen_digit {
             let err_msg = "Invalid octal literal: no digits after '0o'";
             self.add_error(err_msg);
-            return Token::error(err_msg, self.line, start_col, &raw_text);
+            return Token::error(err_msg, self.line, start_col, raw_text);
         }
 
         let digit_text = raw_text[2..].replace("_", "");
@@ -695,42 +774,55 @@ impl<'a> Lexer<'a> {
                 start_col,
                 raw_text,
             ),
+            Err(e) if is_overflow(&e) => {
+                let err_msg = self.add_int_overflow_error(&raw_text.replace("_", ""), start_col);
+                Token::error(&err_msg, self.line, start_col, raw_text)
+            }
             Err(_) => {
                 let err_msg = format!("Invalid octal literal: {}", raw_text);
                 self.add_error(&err_msg);
-                Token::error(&err_msg, self.line, start_col, &raw_text)
+                Token::error(&err_msg, self.line, start_col, raw_text)
             }
         }
     }
 
-    fn handle_hex_literal(&mut self, start_pos: usize, start_col: usize) -> Token {
+    fn handle_hex_literal(&mut self, start_pos: usize, start_col: usize) -> Token<'a> {
         self.consume_char();
         self.consume_while(|c| c.is_alphanumeric() || c == '_');
-        let raw_text = self.get_slice(start_pos, self.position).to_string();
+        let raw_text = self.get_slice(start_pos, self.position);
         let text = raw_text.replace("_", "");
         let value_text = &text[2..];
         if value_text.is_empty() || value_text.chars().any(|c| !c.is_ascii_hexdigit()) {
             let err_msg = format!("Invalid hex literal: {}", text);
             self.add_error(&err_msg);
-            return Token::error(&err_msg, self.line, start_col, &raw_text);
+            return Token::error(&err_msg, self.line, start_col, raw_text);
         }
         match i64::from_str_radix(value_text, 16) {
             Ok(value) => Token::new(TokenType::HexLiteral(value), self.line, start_col, raw_text),
+            Err(e) if is_overflow(&e) => {
+                let err_msg = self.add_int_overflow_error(&text, start_col);
+                Token::error(&err_msg, self.line, start_col, raw_text)
+            }
             Err(_) => {
                 let err_msg = format!("Invalid hex literal: {}", text);
                 self.add_error(&err_msg);
-                Token::error(&err_msg, self.line, start_col, &raw_text)
+                Token::error(&err_msg, self.line, start_col, raw_text)
             }
         }
     }
 
-    fn handle_string(&mut self) -> Token {
+    fn handle_string(&mut self) -> Token<'a> {
         let start_pos = self.position;
         let start_col = self.column;
         let quote_char = self.peek_char();
 
         self.consume_char();
 
+        if let Some(body) = self.take_plain_string_body(quote_char, false) {
+            let text = self.get_slice(start_pos, self.position);
+            return Token::new(TokenType::StringLiteral(body.into()), self.line, start_col, text);
+        }
+
         let mut escaped = false;
         let mut string_content = String::new();
 
@@ -796,7 +888,7 @@ impl<'a> Lexer<'a> {
                 self.consume_char();
                 break;
             } else if current_char == '\n' || current_char == '\r' {
-                let text = self.get_slice(start_pos, self.position).to_string();
+                let text = self.get_slice(start_pos, self.position);
                 self.add_error_with_suggestion(
                     "Unterminated string literal: newline in string",
                     "Add closing quote or use triple quotes for multi-line strings",
@@ -805,7 +897,7 @@ impl<'a> Lexer<'a> {
                     "Unterminated string literal: newline in string",
                     self.line,
                     start_col,
-                    &text,
+                    text,
                 );
             } else {
                 string_content.push(current_char);
@@ -813,47 +905,47 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        let text = self.get_slice(start_pos, self.position).to_string();
+        let text = self.get_slice(start_pos, self.position);
 
         if self.position >= self.input.len() && !text.ends_with(quote_char) {
             self.add_error_with_suggestion("Unterminated string literal", "Add closing quote");
-            return Token::error("Unterminated string literal", self.line, start_col, &text);
+            return Token::error("Unterminated string literal", self.line, start_col, text);
         }
 
         Token::new(
-            TokenType::StringLiteral(string_content),
+            TokenType::StringLiteral(string_content.into()),
             self.line,
             start_col,
             text,
         )
     }
 
-    fn handle_raw_string(&mut self) -> Token {
+    fn handle_raw_string(&mut self) -> Token<'a> {
         let start_pos = self.position - 1;
         let start_col = self.column - 1;
         let quote_char = self.peek_char();
 
         self.consume_char();
 
-        let mut string_content = String::new();
+        let body_start = self.position;
+        let mut body_end = None;
         let mut is_escaped = false;
 
         while !self.is_at_end() {
             let current_char = self.peek_char();
 
             if is_escaped {
-                string_content.push('\\');
-                string_content.push(current_char);
                 self.consume_char();
                 is_escaped = false;
             } else if current_char == '\\' {
                 is_escaped = true;
                 self.consume_char();
             } else if current_char == quote_char {
+                body_end = Some(self.position);
                 self.consume_char();
                 break;
             } else if current_char == '\n' {
-                let text = self.get_slice(start_pos, self.position).to_string();
+                let text = self.get_slice(start_pos, self.position);
                 self.add_error_with_suggestion(
                     "Unterminated raw string literal: newline in string",
                     "Add closing quote or use triple quotes for multi-line strings",
@@ -862,19 +954,14 @@ impl<'a> Lexer<'a> {
                     "Unterminated raw string literal",
                     self.line,
                     start_col,
-                    &text,
+                    text,
                 );
             } else {
-                string_content.push(current_char);
                 self.consume_char();
             }
         }
 
-        if is_escaped {
-            string_content.push('\\');
-        }
-
-        let text = self.get_slice(start_pos, self.position).to_string();
+        let text = self.get_slice(start_pos, self.position);
 
         if self.position >= self.input.len() && !text.ends_with(quote_char) {
             self.add_error("Unterminated raw string literal");
@@ -882,26 +969,24 @@ impl<'a> Lexer<'a> {
                 "Unterminated raw string literal",
                 self.line,
                 start_col,
-                &text,
+                text,
             );
         }
 
-        Token::new(
-            TokenType::RawString(string_content),
-            self.line,
-            start_col,
-            text,
-        )
+        // Raw strings keep their backslashes, so the value is the source text
+        let body = self.get_slice(body_start, body_end.unwrap_or(self.position));
+        Token::new(TokenType::RawString(body.into()), self.line, start_col, text)
     }
 
-    fn handle_formatted_string(&mut self) -> Token {
+    fn handle_formatted_string(&mut self) -> Token<'a> {
         let start_pos = self.position - 1;
         let start_col = self.column - 1;
         let quote_char = self.peek_char();
 
         self.consume_char();
 
-        let mut string_content = String::new();
+        let body_start = self.position;
+        let mut body_end = None;
         let mut in_expression = false;
         let mut brace_depth = 0;
 
@@ -911,15 +996,12 @@ impl<'a> Lexer<'a> {
             if !in_expression && current_char == '{' && self.peek_char_n(1) != '{' {
                 in_expression = true;
                 brace_depth = 1;
-                string_content.push(current_char);
                 self.consume_char();
             } else if in_expression && current_char == '{' {
                 brace_depth += 1;
-                string_content.push(current_char);
                 self.consume_char();
             } else if in_expression && current_char == '}' {
                 brace_depth -= 1;
-                string_content.push(current_char);
                 self.consume_char();
 
                 if brace_depth == 0 {
@@ -933,19 +1015,16 @@ impl<'a> Lexer<'a> {
                     break;
                 }
 
-                let escape_char = self.peek_char();
-                string_content.push('\\');
-                string_content.push(escape_char);
                 self.consume_char();
             } else if !in_expression && current_char == quote_char {
+                body_end = Some(self.position);
                 self.consume_char();
                 break;
             } else if current_char == '\n' && !in_expression {
-                let text = self.get_slice(start_pos, self.position).to_string();
+                let text = self.get_slice(start_pos, self.position);
                 self.add_error("Unterminated f-string literal: newline in string");
-                return Token::error("Unterminated f-string literal", self.line, start_col, &text);
+                return Token::error("Unterminated f-string literal", self.line, start_col, text);
             } else {
-                string_content.push(current_char);
                 self.consume_char();
             }
         }
@@ -954,22 +1033,19 @@ impl<'a> Lexer<'a> {
             self.add_error("Unterminated expression in f-string: missing '}'");
         }
 
-        let text = self.get_slice(start_pos, self.position).to_string();
+        let text = self.get_slice(start_pos, self.position);
 
         if self.position >= self.input.len() && !text.ends_with(quote_char) {
             self.add_error("Unterminated f-string literal");
-            return Token::error("Unterminated f-string literal", self.line, start_col, &text);
+            return Token::error("Unterminated f-string literal", self.line, start_col, text);
         }
 
-        Token::new(
-            TokenType::FString(string_content),
-            self.line,
-            start_col,
-            text,
-        )
+        // Escapes and expressions are decoded later, so the value is the source text
+        let body = self.get_slice(body_start, body_end.unwrap_or(self.position));
+        Token::new(TokenType::FString(body.into()), self.line, start_col, text)
     }
 
-    fn handle_bytes_string(&mut self) -> Token {
+    fn handle_bytes_string(&mut self) -> Token<'a> {
         let start_pos = self.position - 1;
         let start_col = self.column - 1;
         let quote_char = self.peek_char();
@@ -1029,9 +1105,9 @@ impl<'a> Lexer<'a> {
                 self.consume_char();
                 break;
             } else if current_char == '\n' {
-                let text = self.get_slice(start_pos, self.position).to_string();
+                let text = self.get_slice(start_pos, self.position);
                 self.add_error("Unterminated bytes literal: newline in string");
-                return Token::error("Unterminated bytes literal", self.line, start_col, &text);
+                return Token::error("Unterminated bytes literal", self.line, start_col, text);
             } else if !current_char.is_ascii() {
                 self.add_error("Non-ASCII character in bytes literal");
                 self.consume_char();
@@ -1041,17 +1117,17 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        let text = self.get_slice(start_pos, self.position).to_string();
+        let text = self.get_slice(start_pos, self.position);
 
         if self.position >= self.input.len() && !text.ends_with(quote_char) {
             self.add_error("Unterminated bytes literal");
-            return Token::error("Unterminated bytes literal", self.line, start_col, &text);
+            return Token::error("Unterminated bytes literal", self.line, start_col, text);
         }
 
         Token::new(TokenType::BytesLiteral(bytes), self.line, start_col, text)
     }
 
-    fn handle_triple_quoted_string(&mut self) -> Token {
+    fn handle_triple_quoted_string(&mut self) -> Token<'a> {
         let start_pos = self.position;
         let start_col = self.column;
         let quote_char = self.peek_char();
@@ -1060,6 +1136,11 @@ impl<'a> Lexer<'a> {
         self.consume_char();
         self.consume_char();
 
+        if let Some(body) = self.take_plain_string_body(quote_char, true) {
+            let text = self.get_slice(start_pos, self.position);
+            return Token::new(TokenType::StringLiteral(body.into()), self.line, start_col, text);
+        }
+
         let mut string_content = String::new();
         let mut consecutive_quotes = 0;
         let mut escaped = false;
@@ -1125,7 +1206,7 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        let text = self.get_slice(start_pos, self.position).to_string();
+        let text = self.get_slice(start_pos, self.position);
 
         if consecutive_quotes < 3 {
             self.add_error("Unterminated triple-quoted string");
@@ -1133,19 +1214,19 @@ impl<'a> Lexer<'a> {
                 "Unterminated triple-quoted string",
                 self.line,
                 start_col,
-                &text,
+                text,
             );
         }
 
         Token::new(
-            TokenType::StringLiteral(string_content),
+            TokenType::StringLiteral(string_content.into()),
             self.line,
             start_col,
             text,
         )
     }
 
-    fn handle_raw_triple_quoted_string(&mut self) -> Token {
+    fn handle_raw_triple_quoted_string(&mut self) -> Token<'a> {
         let start_pos = self.position - 1;
         let start_col = self.column - 1;
         let quote_char = self.peek_char();
@@ -1154,13 +1235,17 @@ impl<'a> Lexer<'a> {
         self.consume_char();
         self.consume_char();
 
-        let mut string_content = String::new();
+        let body_start = self.position;
+        let mut body_end = self.position;
         let mut consecutive_quotes = 0;
 
         while !self.is_at_end() {
             let current_char = self.peek_char();
 
             if current_char == quote_char {
+                if consecutive_quotes == 0 {
+                    body_end = self.position;
+                }
                 consecutive_quotes += 1;
                 self.consume_char();
 
@@ -1168,17 +1253,12 @@ impl<'a> Lexer<'a> {
                     break;
                 }
             } else {
-                for _ in 0..consecutive_quotes {
-                    string_content.push(quote_char);
-                }
                 consecutive_quotes = 0;
-
-                string_content.push(current_char);
                 self.consume_char();
             }
         }
 
-        let text = self.get_slice(start_pos, self.position).to_string();
+        let text = self.get_slice(start_pos, self.position);
 
         if consecutive_quotes < 3 {
             self.add_error("Unterminated raw triple-quoted string");
@@ -1186,19 +1266,15 @@ impl<'a> Lexer<'a> {
                 "Unterminated raw triple-quoted string",
                 self.line,
                 start_col,
-                &text,
+                text,
             );
         }
 
-        Token::new(
-            TokenType::RawString(string_content),
-            self.line,
-            start_col,
-            text,
-        )
+        let body = self.get_slice(body_start, body_end);
+        Token::new(TokenType::RawString(body.into()), self.line, start_col, text)
     }
 
-    fn handle_formatted_triple_quoted_string(&mut self) -> Token {
+    fn handle_formatted_triple_quoted_string(&mut self) -> Token<'a> {
         let start_pos = self.position - 1;
         let start_col = self.column - 1;
         let quote_char = self.peek_char();
@@ -1207,7 +1283,8 @@ impl<'a> Lexer<'a> {
         self.consume_char();
         self.consume_char();
 
-        let mut string_content = String::new();
+        let body_start = self.position;
+        let mut body_end = self.position;
         let mut consecutive_quotes = 0;
         let mut in_expression = false;
         let mut brace_depth = 0;
@@ -1216,6 +1293,9 @@ impl<'a> Lexer<'a> {
             let current_char = self.peek_char();
 
             if !in_expression && current_char == quote_char {
+                if consecutive_quotes == 0 {
+                    body_end = self.position;
+                }
                 consecutive_quotes += 1;
                 self.consume_char();
 
@@ -1223,41 +1303,29 @@ impl<'a> Lexer<'a> {
                     break;
                 }
             } else if !in_expression && current_char == '{' && self.peek_char_n(1) != '{' {
-                for _ in 0..consecutive_quotes {
-                    string_content.push(quote_char);
-                }
                 consecutive_quotes = 0;
-
                 in_expression = true;
                 brace_depth = 1;
-                string_content.push(current_char);
                 self.consume_char();
             } else if in_expression && current_char == '{' {
                 brace_depth += 1;
-                string_content.push(current_char);
                 self.consume_char();
             } else if in_expression && current_char == '}' {
                 brace_depth -= 1;
-                string_content.push(current_char);
                 self.consume_char();
 
                 if brace_depth == 0 {
                     in_expression = false;
                 }
             } else {
-                if consecutive_quotes > 0 && !in_expression {
-                    for _ in 0..consecutive_quotes {
-                        string_content.push(quote_char);
-                    }
+                if !in_expression {
                     consecutive_quotes = 0;
                 }
-
-                string_content.push(current_char);
                 self.consume_char();
             }
         }
 
-        let text = self.get_slice(start_pos, self.position).to_string();
+        let text = self.get_slice(start_pos, self.position);
 
         if in_expression {
             self.add_error("Unterminated expression in f-string: missing '}'");
@@ -1269,19 +1337,15 @@ impl<'a> Lexer<'a> {
                 "Unterminated formatted triple-quoted string",
                 self.line,
                 start_col,
-                &text,
+                text,
             );
         }
 
-        Token::new(
-            TokenType::FString(string_content),
-            self.line,
-            start_col,
-            text,
-        )
+        let body = self.get_slice(body_start, body_end);
+        Token::new(TokenType::FString(body.into()), self.line, start_col, text)
     }
 
-    fn handle_bytes_triple_quoted_string(&mut self) -> Token {
+    fn handle_bytes_triple_quoted_string(&mut self) -> Token<'a> {
         let start_pos = self.position - 1;
         let start_col = self.column - 1;
         let quote_char = self.peek_char();
@@ -1370,7 +1434,7 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        let text = self.get_slice(start_pos, self.position).to_string();
+        let text = self.get_slice(start_pos, self.position);
 
         if consecutive_quotes < 3 {
             self.add_error("Unterminated bytes triple-quoted string");
@@ -1378,14 +1442,14 @@ impl<'a> Lexer<'a> {
                 "Unterminated bytes triple-quoted string",
                 self.line,
                 start_col,
-                &text,
+                text,
             );
         }
 
         Token::new(TokenType::BytesLiteral(bytes), self.line, start_col, text)
     }
 
-    fn handle_operator_or_delimiter(&mut self) -> Token {
+    fn handle_operator_or_delimiter(&mut self) -> Token<'a> {
         let start_pos = self.position;
         let start_col = self.column;
         let current_char = self.peek_char();
@@ -1574,10 +1638,10 @@ impl<'a> Lexer<'a> {
         };
 
         let text = self.get_slice(start_pos, self.position);
-        Token::new(token_type, self.line, start_col, text.to_string())
+        Token::new(token_type, self.line, start_col, text)
     }
 
-    fn handle_ellipsis(&mut self) -> Token {
+    fn handle_ellipsis(&mut self) -> Token<'a> {
         let _start_pos = self.position;
         let start_col = self.column;
 
@@ -1585,7 +1649,7 @@ impl<'a> Lexer<'a> {
         self.consume_char();
         self.consume_char();
 
-        Token::new(TokenType::Ellipsis, self.line, start_col, "...".to_string())
+        Token::new(TokenType::Ellipsis, self.line, start_col, "...")
     }
 
     fn handle_octal_escape(&mut self, string_content: &mut String) -> char {
@@ -1735,3 +1799,77 @@ impl<'a> Lexer<'a> {
         '\0'
     }
 }
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
+
+    /// The next token, lexed on demand; `None` after the EOF token
+    fn next(&mut self) -> Option<Token<'a>> {
+        while self.pending.is_empty() && !self.finished {
+            self.lex_pending();
+        }
+        let mut token = self.pending.pop_front()?;
+        if self.config.lossless {
+            // Indents and dedents stand for text already recorded by other tokens
+            token.source.get_or_insert_with(TokenSource::default);
+        }
+        Some(token)
+    }
+}
+
+/// Reconstruct the source of tokens lexed in lossless mode. Tokens without
+/// recorded source contribute nothing, so the result is byte-for-byte equal
+/// to the input only when every token came from a lossless lexer.
+pub fn tokens_to_source(tokens: &[Token]) -> String {
+    let mut source = String::new();
+    for token in tokens {
+        if let Some(text) = &token.source {
+            source.push_str(text.leading);
+            source.push_str(text.text);
+        }
+    }
+    source
+}
+
+/// Whether interactive input stops partway through a statement: inside an open
+/// bracket or triple-quoted string, after a line continuation, or in a block
+/// that hasn't been closed with a blank line yet. Brackets and colons inside
+/// strings and comments don't count; other lexer errors are left for the
+/// parser to report once the input is run.
+pub fn needs_more_input(source: &str) -> bool {
+    let config = LexerConfig {
+        enforce_indent_consistency: false,
+        ..LexerConfig::default()
+    };
+    let mut lexer = Lexer::with_config(source, config);
+    let tokens = lexer.tokenize();
+
+    if lexer.paren_level > 0 || lexer.bracket_level > 0 || lexer.brace_level > 0 {
+        return true;
+    }
+    if lexer
+        .errors
+        .iter()
+        .any(|e| e.message.starts_with("Unterminated") && e.message.contains("triple-quoted"))
+    {
+        return true;
+    }
+
+    let last_line = source.lines().last().unwrap_or("");
+    if last_line.trim_end().ends_with('\\') {
+        return true;
+    }
+
+    let in_block = tokens.iter().any(|t| t.token_type == TokenType::Indent)
+        || tokens
+            .iter()
+            .rev()
+            .find(|t| !matches!(t.token_type, TokenType::Newline | TokenType::Dedent | TokenType::EOF))
+            .is_some_and(|t| t.token_type == TokenType::Colon);
+    in_block && !last_line.trim().is_empty()
+}
+
+/// Whether parsing an integer literal failed because it does not fit in an i64
+fn is_overflow(error: &ParseIntError) -> bool {
+    matches!(error.kind(), IntErrorKind::PosOverflow | IntErrorKind::NegOverflow)
+}
@@ -1,7 +1,12 @@
+use crate::ast::Span;
+use std::borrow::Cow;
 use std::fmt;
 
+/// The kind of a token and its value. Names borrow the source they were
+/// lexed from; string literals borrow it too unless escapes had to be
+/// decoded, so lexing a file allocates little beyond the token list.
 #[derive(Debug, PartialEq, Clone)]
-pub enum TokenType {
+pub enum TokenType<'a> {
     // Keywords
     Def,
     Return,
@@ -42,13 +47,13 @@ pub enum TokenType {
     Case,
 
     // Identifiers and literals
-    Identifier(String),
+    Identifier(Cow<'a, str>),
     IntLiteral(i64),
     FloatLiteral(f64),
-    StringLiteral(String),
+    StringLiteral(Cow<'a, str>),
     BytesLiteral(Vec<u8>),
-    RawString(String),
-    FString(String),
+    RawString(Cow<'a, str>),
+    FString(Cow<'a, str>),
     BinaryLiteral(i64),
     OctalLiteral(i64),
     HexLiteral(i64),
@@ -121,35 +126,166 @@ pub enum TokenType {
     Invalid(String),
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct Token {
-    pub token_type: TokenType,
+impl TokenType<'_> {
+    /// Copy any borrowed text so the token type outlives its source
+    pub fn into_owned(self) -> TokenType<'static> {
+        use TokenType::*;
+        match self {
+            Identifier(name) => Identifier(Cow::Owned(name.into_owned())),
+            StringLiteral(value) => StringLiteral(Cow::Owned(value.into_owned())),
+            RawString(value) => RawString(Cow::Owned(value.into_owned())),
+            FString(value) => FString(Cow::Owned(value.into_owned())),
+            Def => Def,
+            Return => Return,
+            If => If,
+            Elif => Elif,
+            Else => Else,
+            While => While,
+            For => For,
+            In => In,
+            Break => Break,
+            Continue => Continue,
+            Pass => Pass,
+            Import => Import,
+            From => From,
+            As => As,
+            True => True,
+            False => False,
+            None => None,
+            And => And,
+            Or => Or,
+            Not => Not,
+            Class => Class,
+            With => With,
+            Assert => Assert,
+            Async => Async,
+            Await => Await,
+            Try => Try,
+            Except => Except,
+            Finally => Finally,
+            Raise => Raise,
+            Lambda => Lambda,
+            Global => Global,
+            Nonlocal => Nonlocal,
+            Yield => Yield,
+            Del => Del,
+            Is => Is,
+            Match => Match,
+            Case => Case,
+            IntLiteral(value) => IntLiteral(value),
+            FloatLiteral(value) => FloatLiteral(value),
+            BytesLiteral(bytes) => BytesLiteral(bytes),
+            BinaryLiteral(value) => BinaryLiteral(value),
+            OctalLiteral(value) => OctalLiteral(value),
+            HexLiteral(value) => HexLiteral(value),
+            Plus => Plus,
+            Minus => Minus,
+            Multiply => Multiply,
+            Divide => Divide,
+            FloorDivide => FloorDivide,
+            Modulo => Modulo,
+            Power => Power,
+            BackSlash => BackSlash,
+            Assign => Assign,
+            PlusAssign => PlusAssign,
+            MinusAssign => MinusAssign,
+            MulAssign => MulAssign,
+            DivAssign => DivAssign,
+            ModAssign => ModAssign,
+            PowAssign => PowAssign,
+            MatrixMulAssign => MatrixMulAssign,
+            FloorDivAssign => FloorDivAssign,
+            BitwiseAndAssign => BitwiseAndAssign,
+            BitwiseOrAssign => BitwiseOrAssign,
+            BitwiseXorAssign => BitwiseXorAssign,
+            ShiftLeftAssign => ShiftLeftAssign,
+            ShiftRightAssign => ShiftRightAssign,
+            Equal => Equal,
+            NotEqual => NotEqual,
+            LessThan => LessThan,
+            LessEqual => LessEqual,
+            GreaterThan => GreaterThan,
+            GreaterEqual => GreaterEqual,
+            BitwiseAnd => BitwiseAnd,
+            BitwiseOr => BitwiseOr,
+            BitwiseXor => BitwiseXor,
+            BitwiseNot => BitwiseNot,
+            ShiftLeft => ShiftLeft,
+            ShiftRight => ShiftRight,
+            Walrus => Walrus,
+            Ellipsis => Ellipsis,
+            LeftParen => LeftParen,
+            RightParen => RightParen,
+            LeftBracket => LeftBracket,
+            RightBracket => RightBracket,
+            LeftBrace => LeftBrace,
+            RightBrace => RightBrace,
+            Comma => Comma,
+            Dot => Dot,
+            Colon => Colon,
+            SemiColon => SemiColon,
+            Arrow => Arrow,
+            At => At,
+            Indent => Indent,
+            Dedent => Dedent,
+            Newline => Newline,
+            EOF => EOF,
+            Invalid(message) => Invalid(message),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Token<'a> {
+    pub token_type: TokenType<'a>,
     pub line: usize,
     pub column: usize,
-    pub lexeme: String,
+    pub lexeme: Cow<'a, str>,
+    /// Exact source text of the token, recorded in lossless mode
+    pub source: Option<TokenSource<'a>>,
+    /// Where the token is in the input; empty for indents and dedents
+    pub span: Span,
+}
+
+/// Original text a token was lexed from
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TokenSource<'a> {
+    /// Whitespace, comments and line continuations before the token
+    pub leading: &'a str,
+    /// The token itself, as written
+    pub text: &'a str,
 }
 
-impl Token {
-    pub fn new(token_type: TokenType, line: usize, column: usize, lexeme: String) -> Self {
+impl<'a> Token<'a> {
+    pub fn new(token_type: TokenType<'a>, line: usize, column: usize, lexeme: impl Into<Cow<'a, str>>) -> Self {
         Token {
             token_type,
             line,
             column,
-            lexeme,
+            lexeme: lexeme.into(),
+            source: None,
+            span: Span::default(),
         }
     }
 
-    pub fn error(message: &str, line: usize, column: usize, lexeme: &str) -> Self {
-        Token::new(
-            TokenType::Invalid(message.to_string()),
-            line,
-            column,
-            lexeme.to_owned(),
-        )
+    pub fn error(message: &str, line: usize, column: usize, lexeme: impl Into<Cow<'a, str>>) -> Self {
+        Token::new(TokenType::Invalid(message.to_string()), line, column, lexeme)
+    }
+}
+
+/// Tokens at the same line and column are equal whatever their byte span,
+/// which follows from the position for any one input
+impl PartialEq for Token<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.token_type == other.token_type
+            && self.line == other.line
+            && self.column == other.column
+            && self.lexeme == other.lexeme
+            && self.source == other.source
     }
 }
 
-impl fmt::Display for Token {
+impl fmt::Display for Token<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
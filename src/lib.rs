@@ -1,10 +1,32 @@
 pub mod ast;
+pub mod ast_dot;
 pub mod lexer;
+pub mod linter;
 pub mod parser;
 pub use parser::{ParseError, ParseErrorFormatter};
 pub mod compiler;
+pub mod completion;
+pub mod cst;
+pub mod dap;
+pub mod diagnostics;
+pub mod diff;
+pub mod doctest;
+#[cfg(feature = "codegen")]
+pub mod engine;
+pub mod error_codes;
 pub mod formatter;
+pub mod intern;
+pub mod interpreter;
+pub mod ir;
+pub mod project;
+pub mod references;
+pub mod repl_command;
+pub mod repl_config;
+pub mod semantic_tokens;
+pub mod source_files;
 pub mod symtable;
+pub mod trace;
+pub mod transform;
 pub mod typechecker;
 pub mod visitor;
 
@@ -12,20 +34,7 @@ use crate::visitor::Visitor;
 
 /// Parse the given Python-like source code into an AST
 pub fn parse(source: &str) -> Result<ast::Module, Vec<parser::ParseError>> {
-    let mut lexer = lexer::Lexer::new(source);
-    let tokens = lexer.tokenize();
-
-    if !lexer.get_errors().is_empty() {
-        let errors = lexer
-            .get_errors()
-            .iter()
-            .map(|e| parser::ParseError::invalid_syntax(&e.message, e.line, e.column))
-            .collect();
-
-        return Err(errors);
-    }
-
-    parser::parse(tokens)
+    parser::parse_source(source)
 }
 
 /// Format the given AST back to Python-like source code
@@ -79,13 +88,13 @@ pub fn format_code(source: &str, indent_size: usize) -> Result<String, String> {
 pub fn analyze_code(source: &str) -> Result<(), String> {
     match parse(source) {
         Ok(module) => {
-            let symbol_table = build_symbol_table(&module);
-            symbol_table.print_symbol_table();
+            let builder = build_symbol_table(&module);
+            let undefined = builder.get_undefined_names().clone();
+            print!("{}", builder.into_symbol_table());
 
-            let undefined = symbol_table.get_undefined_names();
             if !undefined.is_empty() {
                 println!("\nUndefined names:");
-                for name in undefined {
+                for name in &undefined {
                     println!("  {}", name);
                 }
             }
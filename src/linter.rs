@@ -0,0 +1,500 @@
+// linter.rs - Rules behind `cheetah lint`, each with a configurable severity
+//
+// The lint command reports everything `cheetah check` warns about plus rules
+// that are too opinionated to run on every check: unused variables and
+// imports, shadowed names, unreachable code, `== None` comparisons and
+// strings built with `+=` in loops. Each
+// rule can be allowed, left as a warning or denied, which makes it an error.
+// `check` and `compile` take the same severities through `-W` flags.
+
+use crate::ast::{CmpOperator, Constant, Expr, Module, NameConstant, Operator, Stmt};
+use crate::symtable::{ScopeInfo, ScopeKind, SymbolTable, SymbolType};
+use crate::typechecker::lints::{children, filter_suppressed};
+use crate::typechecker::{self, Warning, WarningKind};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// How a rule's findings are reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Not reported
+    Allow,
+    /// Reported without failing the lint
+    Warn,
+    /// Reported and fails the lint
+    Deny,
+}
+
+impl Severity {
+    /// Label used in diagnostics
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Allow => "allow",
+            Severity::Warn => "warning",
+            Severity::Deny => "error",
+        }
+    }
+
+    /// Severity named `name`, as given to `-W`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "allow" => Some(Severity::Allow),
+            "warn" => Some(Severity::Warn),
+            "error" | "deny" => Some(Severity::Deny),
+            _ => None,
+        }
+    }
+}
+
+/// Severity of each rule; rules not configured are warnings
+#[derive(Debug, Clone, Default)]
+pub struct LintConfig {
+    levels: HashMap<WarningKind, Severity>,
+}
+
+impl LintConfig {
+    /// Report the rule with diagnostic code `code` (or every rule, for `all`) at `severity`
+    pub fn set(&mut self, code: &str, severity: Severity) -> Result<(), String> {
+        if code == "all" {
+            for kind in WarningKind::ALL {
+                self.levels.insert(kind, severity);
+            }
+            return Ok(());
+        }
+
+        let kind = WarningKind::from_code(code).ok_or_else(|| {
+            let codes: Vec<&str> = WarningKind::ALL.iter().map(|kind| kind.code()).collect();
+            format!("unknown lint '{}' (expected one of: {})", code, codes.join(", "))
+        })?;
+        self.levels.insert(kind, severity);
+        Ok(())
+    }
+
+    /// Apply a `-W` flag: `LEVEL` sets every rule, `LEVEL=RULE[,RULE...]` the
+    /// listed ones, and a bare `RULE` makes that rule a warning
+    pub fn apply_flag(&mut self, flag: &str) -> Result<(), String> {
+        const LEVELS: &str = "error, deny, warn or allow";
+        let (level, rules) = match flag.split_once('=') {
+            Some((level, rules)) => (level, rules),
+            None if Severity::from_name(flag).is_some() => (flag, "all"),
+            None if flag == "all" || WarningKind::from_code(flag).is_some() => ("warn", flag),
+            None => {
+                return Err(format!(
+                    "unknown warning level '{}' (expected {}, LEVEL=RULE or a rule name)",
+                    flag, LEVELS
+                ))
+            }
+        };
+        let severity = Severity::from_name(level).ok_or_else(|| {
+            format!("unknown warning level '{}' (expected {})", level, LEVELS)
+        })?;
+        for rule in rules.split(',') {
+            self.set(rule.trim(), severity)?;
+        }
+        Ok(())
+    }
+
+    /// Apply the severities `other` sets on top of these
+    pub fn extend(&mut self, other: &LintConfig) {
+        self.levels.extend(&other.levels);
+    }
+
+    /// `warnings` at their configured severities, without the allowed ones
+    pub fn classify(&self, warnings: Vec<Warning>) -> Vec<Diagnostic> {
+        warnings
+            .into_iter()
+            .map(|warning| Diagnostic {
+                severity: self.severity(warning.kind),
+                warning,
+            })
+            .filter(|diagnostic| diagnostic.severity != Severity::Allow)
+            .collect()
+    }
+
+    /// Severity `kind` is reported at
+    pub fn severity(&self, kind: WarningKind) -> Severity {
+        self.levels.get(&kind).copied().unwrap_or(Severity::Warn)
+    }
+}
+
+/// A warning at the severity its rule is configured to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub warning: Warning,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Line {}, Col {}: {}[{}]: {}",
+            self.warning.line,
+            self.warning.column,
+            self.severity.name(),
+            self.warning.kind.code(),
+            self.warning.message
+        )
+    }
+}
+
+/// Run every rule over `module`, honouring `# cheetah: ignore` comments in
+/// `source`, and return the findings of rules that aren't allowed
+pub fn lint(module: &Module, source: &str, config: &LintConfig) -> Vec<Diagnostic> {
+    let mut warnings = Vec::new();
+    check_scopes(module, &mut warnings);
+    check_unreachable(&module.body, &mut warnings);
+    for stmt in &module.body {
+        check_none_comparisons(stmt, &mut warnings);
+    }
+    check_string_building(&module.body, &mut warnings);
+
+    let mut warnings = filter_suppressed(warnings, source);
+    warnings.extend(typechecker::collect_warnings(module, source));
+    warnings.sort_by(|a, b| {
+        (a.line, a.column, &a.message).cmp(&(b.line, b.column, &b.message))
+    });
+
+    config.classify(warnings)
+}
+
+/// Unused variables and imports and shadowed names, from the symbol table
+fn check_scopes(module: &Module, warnings: &mut Vec<Warning>) {
+    let table = SymbolTable::build(module);
+    for scope in table.scopes() {
+        check_scope(&table, scope, warnings);
+    }
+}
+
+fn check_scope(table: &SymbolTable, scope: &ScopeInfo, warnings: &mut Vec<Warning>) {
+    // Lambdas and comprehensions are expressions rather than definitions
+    let is_definition = scope.kind == ScopeKind::Function;
+
+    for symbol in &scope.symbols {
+        if !symbol.is_defined || symbol.is_global || symbol.is_nonlocal {
+            continue;
+        }
+
+        let unused = !symbol.is_referenced && !symbol.name.starts_with('_');
+        match symbol.symbol_type {
+            SymbolType::Import | SymbolType::ImportFrom if unused && symbol.name != "*" => {
+                warnings.push(Warning {
+                    kind: WarningKind::UnusedImport,
+                    message: format!("'{}' is imported but never used", symbol.name),
+                    line: symbol.line,
+                    column: symbol.column,
+                });
+            }
+            SymbolType::Variable if unused && is_definition => {
+                warnings.push(Warning {
+                    kind: WarningKind::UnusedVariable,
+                    message: format!(
+                        "local variable '{}' is assigned but never read; prefix it with '_' if that's intended",
+                        symbol.name
+                    ),
+                    line: symbol.line,
+                    column: symbol.column,
+                });
+            }
+            _ => {}
+        }
+
+        if is_definition
+            && matches!(symbol.symbol_type, SymbolType::Variable | SymbolType::Parameter)
+        {
+            // Class bodies don't enclose the functions defined in them
+            let shadowed = table
+                .enclosing(scope.id)
+                .filter(|outer| outer.kind != ScopeKind::Class)
+                .find_map(|outer| outer.symbol(&symbol.name).filter(|s| s.is_defined));
+            if let Some(outer) = shadowed {
+                warnings.push(Warning {
+                    kind: WarningKind::ShadowedName,
+                    message: format!(
+                        "'{}' shadows the name defined on line {} in an enclosing scope",
+                        symbol.name, outer.line
+                    ),
+                    line: symbol.line,
+                    column: symbol.column,
+                });
+            }
+        }
+    }
+}
+
+/// Statements that follow one that always leaves the block
+fn check_unreachable(body: &[Box<Stmt>], warnings: &mut Vec<Warning>) {
+    let mut reported = false;
+    for (i, stmt) in body.iter().enumerate() {
+        let exit = match &**stmt {
+            Stmt::Return { .. } => Some("return"),
+            Stmt::Raise { .. } => Some("raise"),
+            Stmt::Break { .. } => Some("break"),
+            Stmt::Continue { .. } => Some("continue"),
+            _ => None,
+        };
+        if let (Some(exit), Some(next), false) = (exit, body.get(i + 1), reported) {
+            warnings.push(Warning {
+                kind: WarningKind::UnreachableCode,
+                message: format!("unreachable code after '{}' on line {}", exit, stmt.line()),
+                line: next.line(),
+                column: next.column(),
+            });
+            reported = true;
+        }
+
+        for block in blocks(stmt) {
+            check_unreachable(block, warnings);
+        }
+    }
+}
+
+/// `== None` and `!= None` comparisons in `stmt` and the statements nested in it
+fn check_none_comparisons(stmt: &Stmt, warnings: &mut Vec<Warning>) {
+    fn check_expr(expr: &Expr, warnings: &mut Vec<Warning>) {
+        if let Expr::Compare {
+            left,
+            ops,
+            comparators,
+            line,
+            column,
+            span: _,
+        } = expr
+        {
+            let mut lhs = &**left;
+            for (op, rhs) in ops.iter().zip(comparators) {
+                let suggestion = match op {
+                    CmpOperator::Eq => Some("is"),
+                    CmpOperator::NotEq => Some("is not"),
+                    _ => None,
+                };
+                if let Some(suggestion) = suggestion.filter(|_| is_none(lhs) || is_none(rhs)) {
+                    warnings.push(Warning {
+                        kind: WarningKind::NoneComparison,
+                        message: format!("comparison to None should use '{}'", suggestion),
+                        line: *line,
+                        column: *column,
+                    });
+                }
+                lhs = rhs;
+            }
+        }
+        for child in children(expr) {
+            check_expr(child, warnings);
+        }
+    }
+
+    for expr in exprs(stmt) {
+        check_expr(expr, warnings);
+    }
+    for block in blocks(stmt) {
+        for stmt in block {
+            check_none_comparisons(stmt, warnings);
+        }
+    }
+}
+
+/// Strings extended with `+=` inside a loop of the scope whose body is
+/// `body`, and of the scopes nested in it. Each `+=` copies the whole string;
+/// `"".join(pieces)` builds it in one pass.
+fn check_string_building(body: &[Box<Stmt>], warnings: &mut Vec<Warning>) {
+    let mut strings = HashSet::new();
+    string_names(body, &mut strings);
+    check_string_appends(body, &strings, false, warnings);
+}
+
+/// Names of the scope whose body is `body` that are assigned a string
+fn string_names<'a>(body: &'a [Box<Stmt>], strings: &mut HashSet<&'a str>) {
+    for stmt in body {
+        let assigned = match &**stmt {
+            Stmt::Assign { targets, value, .. } if is_string(value) => targets.iter().collect(),
+            Stmt::AnnAssign { target, annotation, .. }
+                if matches!(&**annotation, Expr::Name { id, .. } if id == "str") =>
+            {
+                vec![target]
+            }
+            Stmt::FunctionDef { .. } | Stmt::ClassDef { .. } => continue,
+            _ => Vec::new(),
+        };
+        for target in assigned {
+            if let Expr::Name { id, .. } = &**target {
+                strings.insert(id);
+            }
+        }
+        for block in blocks(stmt) {
+            string_names(block, strings);
+        }
+    }
+}
+
+fn check_string_appends(body: &[Box<Stmt>], strings: &HashSet<&str>, in_loop: bool, warnings: &mut Vec<Warning>) {
+    for stmt in body {
+        match &**stmt {
+            Stmt::AugAssign {
+                target,
+                op: Operator::Add,
+                value,
+                line,
+                column,
+                ..
+            } if in_loop => {
+                if let Expr::Name { id, .. } = &**target {
+                    if strings.contains(id.as_str()) || is_string(value) {
+                        warnings.push(Warning {
+                            kind: WarningKind::StringConcatInLoop,
+                            message: format!(
+                                "'{}' is built with += in a loop, copying it each time; append the pieces to a list and use \"\".join()",
+                                id
+                            ),
+                            line: *line,
+                            column: *column,
+                        });
+                    }
+                }
+            }
+            Stmt::FunctionDef { body, .. } | Stmt::ClassDef { body, .. } => check_string_building(body, warnings),
+            Stmt::For { body, orelse, .. } | Stmt::While { body, orelse, .. } => {
+                check_string_appends(body, strings, true, warnings);
+                check_string_appends(orelse, strings, in_loop, warnings);
+            }
+            _ => {
+                for block in blocks(stmt) {
+                    check_string_appends(block, strings, in_loop, warnings);
+                }
+            }
+        }
+    }
+}
+
+/// Whether `expr` is evidently a string
+fn is_string(expr: &Expr) -> bool {
+    match expr {
+        Expr::Str { .. } | Expr::JoinedStr { .. } | Expr::Constant { value: Constant::Str(_), .. } => true,
+        Expr::Call { func, .. } => matches!(&**func, Expr::Name { id, .. } if id == "str"),
+        Expr::BinOp {
+            left,
+            op: Operator::Add,
+            right,
+            ..
+        } => is_string(left) || is_string(right),
+        _ => false,
+    }
+}
+
+fn is_none(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::NameConstant {
+            value: NameConstant::None,
+            ..
+        } | Expr::Constant {
+            value: Constant::NameConstant(NameConstant::None),
+            ..
+        }
+    )
+}
+
+/// Blocks of statements nested directly in `stmt`
+pub(crate) fn blocks(stmt: &Stmt) -> Vec<&[Box<Stmt>]> {
+    match stmt {
+        Stmt::FunctionDef { body, .. } | Stmt::ClassDef { body, .. } | Stmt::With { body, .. } => {
+            vec![body]
+        }
+        Stmt::For { body, orelse, .. }
+        | Stmt::While { body, orelse, .. }
+        | Stmt::If { body, orelse, .. } => vec![body, orelse],
+        Stmt::Try {
+            body,
+            handlers,
+            orelse,
+            finalbody,
+            ..
+        } => {
+            let mut result: Vec<&[Box<Stmt>]> = vec![body];
+            result.extend(handlers.iter().map(|handler| handler.body.as_slice()));
+            result.push(orelse);
+            result.push(finalbody);
+            result
+        }
+        Stmt::Match { cases, .. } => cases.iter().map(|(_, _, body)| body.as_slice()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Expressions belonging directly to `stmt`, not to the statements nested in it
+pub(crate) fn exprs(stmt: &Stmt) -> Vec<&Expr> {
+    let mut result: Vec<&Expr> = Vec::new();
+    match stmt {
+        Stmt::FunctionDef {
+            params,
+            decorator_list,
+            returns,
+            ..
+        } => {
+            result.extend(decorator_list.iter().map(|e| &**e));
+            for param in params {
+                result.extend(param.typ.iter().chain(&param.default).map(|e| &**e));
+            }
+            result.extend(returns.iter().map(|e| &**e));
+        }
+        Stmt::ClassDef {
+            bases,
+            keywords,
+            decorator_list,
+            ..
+        } => {
+            result.extend(bases.iter().chain(decorator_list).map(|e| &**e));
+            result.extend(keywords.iter().map(|(_, e)| &**e));
+        }
+        Stmt::Return { value, .. } => result.extend(value.iter().map(|e| &**e)),
+        Stmt::Delete { targets, .. } => result.extend(targets.iter().map(|e| &**e)),
+        Stmt::Assign { targets, value, .. } => {
+            result.extend(targets.iter().map(|e| &**e));
+            result.push(value);
+        }
+        Stmt::AugAssign { target, value, .. } => result.extend([&**target, &**value]),
+        Stmt::AnnAssign {
+            target,
+            annotation,
+            value,
+            ..
+        } => {
+            result.extend([&**target, &**annotation]);
+            result.extend(value.iter().map(|e| &**e));
+        }
+        Stmt::For { target, iter, .. } => result.extend([&**target, &**iter]),
+        Stmt::While { test, .. } | Stmt::If { test, .. } => result.push(test),
+        Stmt::With { items, .. } => {
+            for (item, target) in items {
+                result.push(item);
+                result.extend(target.iter().map(|e| &**e));
+            }
+        }
+        Stmt::Raise { exc, cause, .. } => {
+            result.extend(exc.iter().chain(cause).map(|e| &**e));
+        }
+        Stmt::Try { handlers, .. } => {
+            result.extend(handlers.iter().filter_map(|h| h.typ.as_deref()));
+        }
+        Stmt::Assert { test, msg, .. } => {
+            result.push(test);
+            result.extend(msg.iter().map(|e| &**e));
+        }
+        Stmt::Expr { value, .. } => result.push(value),
+        Stmt::Match { subject, cases, .. } => {
+            result.push(subject);
+            for (pattern, guard, _) in cases {
+                result.push(pattern);
+                result.extend(guard.iter().map(|e| &**e));
+            }
+        }
+        Stmt::Import { .. }
+        | Stmt::ImportFrom { .. }
+        | Stmt::Global { .. }
+        | Stmt::Nonlocal { .. }
+        | Stmt::Pass { .. }
+        | Stmt::Break { .. }
+        | Stmt::Continue { .. } => {}
+    }
+    result
+}
@@ -1,58 +1,129 @@
 use anyhow::{Context, Result};
 use clap::{Parser as ClapParser, Subcommand};
 use colored::Colorize;
-use std::ffi::{CStr, CString};
+use std::borrow::Cow;
 use std::fs;
+use std::fmt::Write as _;
 use std::io::{self, Write};
-use std::os::raw::c_char;
 use std::os::unix::process::CommandExt;
-use std::path::PathBuf;
-
-use cheetah::compiler::runtime::{
-    buffer, parallel_ops,
-    print_ops::{print_bool, print_float, print_int, print_string, println_string},
-    range, min_max_ops,
-};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use cheetah::compiler::runtime::output::Newline;
+#[cfg(feature = "codegen")]
+use cheetah::compiler::runtime::{abort, buffer, jit, parallel_ops, range};
+#[cfg(feature = "codegen")]
+use cheetah::compiler::cdylib::{library_file_name, CrateType};
+#[cfg(feature = "codegen")]
+use cheetah::compiler::emit::EmitKind;
+#[cfg(feature = "codegen")]
+use cheetah::compiler::optimizer::{codegen_level, pass_pipeline};
+#[cfg(feature = "codegen")]
+use cheetah::compiler::repl::ReplSession;
+#[cfg(feature = "codegen")]
+use cheetah::compiler::sanitizer::Sanitizer;
+#[cfg(feature = "codegen")]
+use cheetah::compiler::target::TargetSpec;
+#[cfg(feature = "codegen")]
+use cheetah::compiler::wasm::WasmRuntime;
+#[cfg(feature = "codegen")]
 use cheetah::compiler::Compiler;
+use cheetah::completion::NameCompleter;
+use cheetah::diagnostics::{self, Diagnostic, ErrorFormat};
+#[cfg(feature = "codegen")]
+use cheetah::doctest::{self, DocTestResult};
+use cheetah::error_codes;
+use cheetah::compiler::types::Type;
 use cheetah::formatter::CodeFormatter;
-use cheetah::lexer::{Lexer, LexerConfig, Token, TokenType};
-use cheetah::parse;
+use cheetah::interpreter::Interpreter;
+use cheetah::lexer::{needs_more_input, Construct, Lexer, LexerConfig, Token, TokenType};
+use cheetah::linter::{self, LintConfig, Severity};
+use cheetah::{ast, parse, trace};
 use cheetah::parser::{self, ParseErrorFormatter};
+use cheetah::project::{Project, MANIFEST_NAME};
+use cheetah::repl_command::MetaCommand;
+use cheetah::repl_config::ReplConfig;
+use cheetah::source_files;
+use cheetah::typechecker::{self, TypeChecker};
 use cheetah::visitor::Visitor;
 use libc;
-
+use rayon::prelude::*;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Editor, Helper};
+
+#[cfg(feature = "codegen")]
 use inkwell::context;
-use inkwell::targets::{InitializationConfig, Target};
+#[cfg(feature = "codegen")]
+use inkwell::targets::{InitializationConfig, Target, TargetMachine};
 
 #[derive(ClapParser)]
 #[command(name = "cheetah")]
 #[command(version = "0.1.0")]
 #[command(about = "Cheetah programming language interpreter", long_about = None)]
 struct Cli {
-    /// Source file to run (with .ch extension)
+    /// Source file (with .ch extension) or project directory to run
     #[arg(value_name = "FILE")]
     file: Option<String>,
 
-    /// Use LLVM JIT compilation instead of interpreter
+    /// Use LLVM JIT compilation (same as `--backend jit`)
     #[arg(short = 'j', long, default_value = "false")]
     jit: bool,
 
+    /// How to run the file: aot (compile to an executable), jit or interp
+    #[arg(long, value_name = "BACKEND")]
+    backend: Option<String>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Run a Cheetah source file
+    /// Run a Cheetah source file or project
     Run {
-        /// The source file to run
+        /// The source file to run, or a project directory with a cheetah.toml
         file: String,
 
-        /// Use LLVM JIT compilation instead of interpreter
+        /// Use LLVM JIT compilation (same as `--backend jit`)
         #[arg(short = 'j', long)]
         jit: bool,
+
+        /// How to run the file: aot (run the executable from `cheetah build`), jit or interp
+        #[arg(long, value_name = "BACKEND")]
+        backend: Option<String>,
+
+        /// Optimization level (0-3) for JIT compilation
+        #[arg(short, long, default_value = "0")]
+        opt: u8,
+
+        /// Load a C library providing @extern functions into the JIT (repeatable)
+        #[arg(short = 'l', long = "link-lib", value_name = "LIB")]
+        link_lib: Vec<String>,
+
+        /// Add a directory to the library search path (repeatable)
+        #[arg(short = 'L', long = "link-path", value_name = "DIR")]
+        link_path: Vec<String>,
+
+        /// Line endings written to stdout: lf, crlf or native (crlf on Windows)
+        #[arg(long, value_name = "MODE", default_value = "native")]
+        newline: String,
+
+        /// Print each statement as it runs with the variables it sets
+        /// (uses the interp backend)
+        #[arg(long)]
+        trace: bool,
+
+        /// Stop a traced program after this many steps
+        #[arg(long, value_name = "STEPS", default_value_t = trace::DEFAULT_STEP_LIMIT, requires = "trace")]
+        max_steps: usize,
     },
     /// Build a Cheetah source file to an executable
+    #[cfg(feature = "codegen")]
     Build {
         /// The source file to compile
         file: String,
@@ -60,12 +131,64 @@ enum Commands {
         /// Optimization level (0-3)
         #[arg(short, long, default_value = "0")]
         opt: u8,
+
+        /// Emit DWARF debug info for stepping through source lines in gdb/lldb
+        #[arg(short = 'g', long)]
+        debug: bool,
+
+        /// Instrument the build with sanitizers (address, undefined)
+        #[arg(long, value_name = "SANITIZER", value_delimiter = ',')]
+        sanitize: Vec<String>,
+
+        /// Link an executable (bin) or a shared library exporting C symbols (cdylib)
+        #[arg(long, value_name = "TYPE", default_value = "bin")]
+        crate_type: String,
+
+        /// Link against a C library providing @extern functions (repeatable)
+        #[arg(short = 'l', long = "link-lib", value_name = "LIB")]
+        link_lib: Vec<String>,
+
+        /// Add a directory to the library search path (repeatable)
+        #[arg(short = 'L', long = "link-path", value_name = "DIR")]
+        link_path: Vec<String>,
+
+        /// Line endings written to stdout: lf, crlf or native (crlf on Windows)
+        #[arg(long, value_name = "MODE", default_value = "native")]
+        newline: String,
+    },
+    /// Debug a Cheetah program from an editor over the Debug Adapter Protocol
+    #[cfg(feature = "codegen")]
+    Debug {
+        /// The source file to debug
+        file: String,
     },
     /// Start a REPL session
     Repl {
         /// Use LLVM JIT compilation in REPL
         #[arg(short = 'j', long)]
         jit: bool,
+
+        /// Optimization level (0-3) for JIT compilation
+        #[arg(short, long, default_value = "0")]
+        opt: u8,
+
+        /// Startup script to run before the first prompt (default: ~/.cheetahrc)
+        #[arg(long, value_name = "FILE")]
+        rc: Option<String>,
+
+        /// Don't run a startup script
+        #[arg(long, conflicts_with = "rc")]
+        no_rc: bool,
+    },
+    /// Run the `>>>` examples in docstrings and check their output
+    Doctest {
+        /// Source files, directories or glob patterns to test
+        #[arg(required = true)]
+        paths: Vec<String>,
+
+        /// Optimization level (0-3) for JIT compilation
+        #[arg(short, long, default_value = "0")]
+        opt: u8,
     },
     /// Lex a file and print the tokens (for debugging)
     Lex {
@@ -83,6 +206,10 @@ enum Commands {
         /// Show line numbers in output
         #[arg(short = 'n', long)]
         line_numbers: bool,
+
+        /// Print what each range is for syntax highlighting (function, parameter, keyword, ...)
+        #[arg(long)]
+        semantic: bool,
     },
     /// Parse a file and print the AST (for debugging)
     Parse {
@@ -92,35 +219,103 @@ enum Commands {
         /// Show detailed AST information
         #[arg(short, long)]
         verbose: bool,
+
+        /// Print the lossless concrete syntax tree instead of the AST
+        #[arg(long)]
+        cst: bool,
+
+        /// Print the typed mid-level IR the AST lowers to, with functions
+        /// specialized as when compiling it, instead of the AST
+        #[arg(long, conflicts_with_all = ["cst", "dot"])]
+        ir: bool,
+
+        /// How to print the AST: text, or json to load with `ast::Module::from_json`
+        #[arg(long, value_name = "FORMAT", default_value = "text")]
+        output: String,
+
+        /// Print the AST as a Graphviz graph
+        #[arg(long)]
+        dot: bool,
+
+        /// With --dot, also print the control flow graph of each compiled function
+        #[arg(long, requires = "dot")]
+        cfg: bool,
+
+        /// How to report errors: human, json (one object per line) or sarif
+        #[arg(long, value_name = "FORMAT", default_value = "human")]
+        error_format: String,
     },
-    /// Check a file for syntax errors
+    /// Check files for syntax errors
     Check {
-        /// The source file to check
-        file: String,
+        /// Source files, directories or glob patterns to check
+        #[arg(required = true)]
+        paths: Vec<String>,
 
         /// Show detailed information about errors
         #[arg(short, long)]
         verbose: bool,
+
+        /// How to report errors: human, json (one object per line) or sarif
+        #[arg(long, value_name = "FORMAT", default_value = "human")]
+        error_format: String,
+
+        /// Set warning levels: `error` for every rule, or `LEVEL=RULE[,RULE...]`
+        /// with LEVEL one of error, warn or allow (repeatable)
+        #[arg(short = 'W', value_name = "LEVEL[=RULES]")]
+        warnings: Vec<String>,
+    },
+    /// Lint files for unused names, unreachable code and other likely mistakes
+    Lint {
+        /// Source files, directories or glob patterns to lint
+        #[arg(required = true)]
+        paths: Vec<String>,
+
+        /// Don't report a rule, by code (repeatable; `all` for every rule)
+        #[arg(short = 'A', long = "allow", value_name = "RULE")]
+        allow: Vec<String>,
+
+        /// Report a rule as a warning, or set levels as `check -W` does (repeatable)
+        #[arg(short = 'W', long = "warn", value_name = "RULE")]
+        warn: Vec<String>,
+
+        /// Report a rule as an error, failing the lint (repeatable)
+        #[arg(short = 'D', long = "deny", value_name = "RULE")]
+        deny: Vec<String>,
     },
-    /// Format a Cheetah source file
+    /// Format Cheetah source files
     Format {
-        /// The source file to format
-        file: String,
+        /// Source files, directories or glob patterns to format
+        #[arg(required = true)]
+        paths: Vec<String>,
 
-        /// Write changes to file instead of stdout
-        #[arg(short, long)]
+        /// Write changes to the files instead of stdout
+        #[arg(short, long, conflicts_with_all = ["check", "diff"])]
         write: bool,
 
-        /// Indentation size (number of spaces)
-        #[arg(short, long, default_value = "4")]
-        indent: usize,
+        /// Fail if any file would change, without writing it
+        #[arg(long)]
+        check: bool,
+
+        /// Print a unified diff of the changes instead of the formatted code
+        #[arg(long)]
+        diff: bool,
+
+        /// Indentation size in spaces (default: format.indent-size from cheetah.toml, or 4)
+        #[arg(short, long)]
+        indent: Option<usize>,
+
+        /// Split long call arguments, boolean expressions and collection literals to fit this
+        /// width (default: format.line-width from cheetah.toml)
+        #[arg(long, value_name = "COLUMNS")]
+        line_width: Option<usize>,
     },
     /// Compile a Cheetah source file to LLVM IR
+    #[cfg(feature = "codegen")]
     Compile {
         /// The source file to compile
         file: String,
 
-        /// Output path (defaults to input file name with .ll extension)
+        /// Output path (defaults to input file name with the extension of the emitted kind)
         #[arg(short, long)]
         output: Option<String>,
 
@@ -128,14 +323,103 @@ enum Commands {
         #[arg(short, long, default_value = "0")]
         opt: u8,
 
-        /// Compile to object file instead of LLVM IR
-        #[arg(short, long)]
+        /// Compile to an executable instead of LLVM IR (same as --emit exe)
+        #[arg(short, long, conflicts_with = "emit")]
         object: bool,
 
+        /// What to write: asm, llvm-ir, llvm-bc, obj or exe (default: llvm-ir)
+        #[arg(long, value_name = "KIND")]
+        emit: Option<String>,
+
         /// Target triple (default: host target)
         #[arg(short, long)]
         target: Option<String>,
+
+        /// Target CPU (default: host CPU, or "generic" when cross-compiling)
+        #[arg(long)]
+        cpu: Option<String>,
+
+        /// Target features, e.g. "+neon,-sve" (default: host features, or none when cross-compiling)
+        #[arg(long)]
+        features: Option<String>,
+
+        /// Emit DWARF debug info for stepping through source lines in gdb/lldb
+        #[arg(short = 'g', long)]
+        debug: bool,
+
+        /// Build a WebAssembly module, with the runtime from a JavaScript shim or linked for WASI
+        #[arg(long, value_name = "RUNTIME", num_args = 0..=1, default_missing_value = "shim")]
+        wasm: Option<String>,
+
+        /// Write a Source Map v3 file mapping the generated LLVM IR back to source lines
+        /// (implies -g; the IR is written next to other outputs)
+        #[arg(long)]
+        source_map: bool,
+
+        /// Write Graphviz CFGs before and after optimization (all functions, or only FUNCTION)
+        #[arg(long, value_name = "FUNCTION", num_args = 0..=1, default_missing_value = "")]
+        dump_cfg: Option<String>,
+
+        /// Link against a C library providing @extern functions (repeatable)
+        #[arg(short = 'l', long = "link-lib", value_name = "LIB")]
+        link_lib: Vec<String>,
+
+        /// Add a directory to the library search path (repeatable)
+        #[arg(short = 'L', long = "link-path", value_name = "DIR")]
+        link_path: Vec<String>,
+
+        /// Line endings written to stdout: lf, crlf or native (crlf on Windows)
+        #[arg(long, value_name = "MODE", default_value = "native")]
+        newline: String,
+
+        /// How to report errors: human, json (one object per line) or sarif
+        #[arg(long, value_name = "FORMAT", default_value = "human")]
+        error_format: String,
+
+        /// Set warning levels: `error` for every rule, or `LEVEL=RULE[,RULE...]`
+        /// with LEVEL one of error, warn or allow (repeatable)
+        #[arg(short = 'W', value_name = "LEVEL[=RULES]")]
+        warnings: Vec<String>,
+    },
+    /// Explain an error code such as CH0006, or list every code
+    Explain {
+        /// The error's id or name
+        code: Option<String>,
     },
+    /// Print reference documentation generated from the implementation
+    Docs {
+        /// The topic: precedence, the operator precedence table
+        topic: String,
+    },
+    /// Show the version, LLVM and target details, and enabled features (for bug reports)
+    Env,
+}
+
+/// How `cheetah run` executes a program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Backend {
+    /// Compile ahead of time and run the executable
+    Aot,
+    /// Compile with LLVM's JIT and run in this process
+    Jit,
+    /// Walk the AST with the interpreter; needs no LLVM
+    Interp,
+}
+
+impl Backend {
+    /// The backend named by `--backend`, or the one implied by `--jit`
+    fn resolve(name: Option<&str>, jit: bool) -> Result<Self> {
+        match name {
+            None if jit => Ok(Backend::Jit),
+            None | Some("aot") => Ok(Backend::Aot),
+            Some("jit") => Ok(Backend::Jit),
+            Some("interp") => Ok(Backend::Interp),
+            Some(other) => Err(anyhow::anyhow!(
+                "unknown backend '{}' (expected 'aot', 'jit' or 'interp')",
+                other
+            )),
+        }
+    }
 }
 
 // Function to increase the stack size limit
@@ -173,7 +457,7 @@ fn increase_stack_size() {
         if libc::setrlimit(libc::RLIMIT_STACK, &rlim) != 0 {
             eprintln!("Warning: Failed to increase stack size. Stack overflows may occur with large ranges.");
         } else {
-            println!(
+            eprintln!(
                 "{}",
                 format!(
                     "Stack size increased to {}MB for handling large ranges",
@@ -190,71 +474,57 @@ fn increase_stack_size() {
     eprintln!("Warning: Stack size adjustment not supported on this platform.");
 }
 
-extern "C" {
-    fn setlocale(category: i32, locale: *const i8) -> *mut i8;
-}
-fn init_locale() {
-    let locale = CString::new("C").unwrap();
-    unsafe {
-        setlocale(0 /*LC_ALL*/, locale.as_ptr())
-    };
-}
-
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    init_locale();
-
     increase_stack_size();
 
+    #[cfg(feature = "codegen")]
     initialize_llvm_targets();
 
     if let (None, Some(raw)) = (&cli.command, &cli.file) {
-        if cli.jit {
-            run_file_jit(raw)?;
+        let backend = Backend::resolve(cli.backend.as_deref(), cli.jit)?;
+        if let Some(dir) = project_dir(raw) {
+            return run_project(&dir, backend, None);
+        }
+        if backend == Backend::Interp {
+            run_file_interp(raw, Newline::default(), None)?;
+        } else if backend == Backend::Jit {
+            run_file_jit(raw, 0, Newline::default())?;
         } else {
-            let src = ensure_ch_extension(raw);
-            let abs_src = std::fs::canonicalize(&src)
-                .map_err(|e| anyhow::anyhow!("Cannot find {}: {}", src, e))?;
-
-            let cwd = std::env::current_dir()?;
-            let build_dir = cwd.join(".cheetah_build");
-            std::fs::create_dir_all(&build_dir)?;
-
-            let exe_stem = abs_src
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .ok_or_else(|| anyhow::anyhow!("Invalid filename"))?;
-            let exe_path = build_dir.join(exe_stem);
-
-            if !exe_path.exists() {
-                println!("⚙️  No existing build for `{}`, compiling…", exe_stem);
-                std::env::set_current_dir(&build_dir)?;
-                compile_file(
-                    abs_src.to_string_lossy().as_ref(),
-                    Some(exe_stem.to_string()),
-                    0,
-                    true,
-                    None,
-                )?;
-                std::env::set_current_dir(&cwd)?;
-                println!("⚙️ Built {}", exe_path.display());
-            } else {
-                println!("⏩ Found existing build: {}", exe_path.display());
-            }
-
-            println!("▶️  Running {}", exe_path.display());
-            let err = std::process::Command::new(&exe_path).exec();
-            eprintln!("❌ failed to exec `{}`: {}", exe_path.display(), err);
-            std::process::exit(1);
+            build_and_run(raw)?;
         }
         return Ok(());
     }
 
     match cli.command {
-        Some(Commands::Run { file, jit }) => {
-            if jit {
-                run_file_jit(&file)?;
+        Some(Commands::Run {
+            file,
+            jit,
+            backend,
+            opt,
+            link_lib,
+            link_path,
+            newline,
+            trace,
+            max_steps,
+        }) => {
+            let newline = Newline::from_name(&newline).map_err(|e| anyhow::anyhow!(e))?;
+            let backend = match backend.as_deref() {
+                None if trace && !jit => Backend::Interp,
+                name => Backend::resolve(name, jit)?,
+            };
+            if trace && backend != Backend::Interp {
+                return Err(anyhow::anyhow!("--trace is only supported by the interp backend"));
+            }
+            let trace = trace.then_some(max_steps);
+            if let Some(dir) = project_dir(&file) {
+                run_project(&dir, backend, trace)?;
+            } else if backend == Backend::Interp {
+                run_file_interp(&file, newline, trace)?;
+            } else if backend == Backend::Jit {
+                load_jit_libraries(&link_lib, &link_path);
+                run_file_jit(&file, opt, newline)?;
             } else {
                 let src = ensure_ch_extension(&file);
                 let cwd = std::env::current_dir()?;
@@ -278,7 +548,34 @@ fn main() -> Result<()> {
                 std::process::exit(1);
             }
         }
-        Some(Commands::Build { file, opt }) => {
+        #[cfg(feature = "codegen")]
+        Some(Commands::Build {
+            file,
+            opt,
+            debug,
+            sanitize,
+            crate_type,
+            link_lib,
+            link_path,
+            newline,
+        }) => {
+            let codegen = CodegenOptions {
+                debug_info: debug,
+                source_map: false,
+                sanitizers: sanitize
+                    .iter()
+                    .map(|name| Sanitizer::from_name(name))
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| anyhow::anyhow!(e))?,
+                wasm: None,
+                crate_type: CrateType::from_name(&crate_type).map_err(|e| anyhow::anyhow!(e))?,
+                link_libs: link_lib,
+                link_search_paths: link_path,
+                newline: Newline::from_name(&newline).map_err(|e| anyhow::anyhow!(e))?,
+                dump_cfg: None,
+                lints: LintConfig::default(),
+            };
+            let shared = codegen.crate_type == CrateType::Cdylib;
             let src = ensure_ch_extension(&file);
             let abs_src = std::fs::canonicalize(&src)
                 .map_err(|e| anyhow::anyhow!("Cannot find {}: {}", src, e))?;
@@ -291,7 +588,11 @@ fn main() -> Result<()> {
                 .file_stem()
                 .and_then(|s| s.to_str())
                 .ok_or_else(|| anyhow::anyhow!("Invalid filename"))?;
-            let exe_path = build_dir.join(exe_stem);
+            let exe_path = if shared {
+                build_dir.join(library_file_name(exe_stem))
+            } else {
+                build_dir.join(exe_stem)
+            };
 
             println!("🔨 Building {} → {}", file, exe_path.display());
             std::env::set_current_dir(&build_dir)?;
@@ -299,56 +600,193 @@ fn main() -> Result<()> {
                 abs_src.to_string_lossy().as_ref(),
                 Some(exe_stem.to_string()),
                 opt,
-                true,
-                None,
+                EmitKind::Exe,
+                TargetSpec::host(),
+                codegen,
+                ErrorFormat::Human,
             )?;
             std::env::set_current_dir(&cwd)?;
             println!("✅ Built {}", exe_path.display());
         }
 
-        Some(Commands::Repl { jit }) => {
+        #[cfg(feature = "codegen")]
+        Some(Commands::Debug { file }) => debug_file(&file)?,
+
+        Some(Commands::Repl { jit, opt, rc, no_rc }) => {
+            let config = load_repl_config(rc.as_deref(), no_rc)?;
             if jit {
-                run_repl_jit()?;
+                run_repl_jit(opt, config)?;
             } else {
-                run_repl()?;
+                run_repl(config)?;
             }
         }
+        Some(Commands::Doctest { paths, opt }) => doctest_files(&paths, opt)?,
         Some(Commands::Lex {
             file,
             verbose,
             color,
             line_numbers,
+            semantic,
         }) => {
-            lex_file(&file, verbose, color, line_numbers)?;
+            if semantic {
+                lex_file_semantic(&file)?;
+            } else {
+                lex_file(&file, verbose, color, line_numbers)?;
+            }
         }
-        Some(Commands::Parse { file, verbose }) => {
-            parse_file(&file, verbose)?;
+        Some(Commands::Parse {
+            file,
+            verbose,
+            cst,
+            ir,
+            output,
+            dot,
+            cfg,
+            error_format,
+        }) => {
+            let format = ErrorFormat::from_name(&error_format).map_err(|e| anyhow::anyhow!(e))?;
+            let json = match output.as_str() {
+                "text" => false,
+                "json" => true,
+                other => return Err(anyhow::anyhow!("Unknown output format '{}' (expected text or json)", other)),
+            };
+            if dot {
+                parse_file_dot(&file, cfg)?;
+            } else if cst {
+                parse_file_cst(&file)?;
+            } else if ir {
+                parse_file_ir(&file)?;
+            } else {
+                parse_file(&file, verbose, json, format)?;
+            }
         }
-        Some(Commands::Check { file, verbose }) => {
-            check_file(&file, verbose)?;
+        Some(Commands::Check {
+            paths,
+            verbose,
+            error_format,
+            warnings,
+        }) => {
+            let format = ErrorFormat::from_name(&error_format).map_err(|e| anyhow::anyhow!(e))?;
+            let lints = lint_config(&warnings)?;
+            for_each_file(&paths, format, |file, report| {
+                check_file(file, verbose, format, &lints, report)
+            })?;
+        }
+        Some(Commands::Lint {
+            paths,
+            allow,
+            warn,
+            deny,
+        }) => {
+            let mut config = lint_config(&warn)?;
+            for (rules, severity) in [(allow, Severity::Allow), (deny, Severity::Deny)] {
+                for rule in rules {
+                    config
+                        .set(&rule, severity)
+                        .map_err(|e| anyhow::anyhow!(e))?;
+                }
+            }
+            for_each_file(&paths, ErrorFormat::Human, |file, report| {
+                lint_file(file, &config, report)
+            })?;
         }
         Some(Commands::Format {
-            file,
+            paths,
             write,
+            check,
+            diff,
             indent,
+            line_width,
         }) => {
-            format_file(&file, write, indent)?;
+            let single_file = match paths.as_slice() {
+                [path] => !source_files::is_pattern(path) && !Path::new(path).is_dir(),
+                _ => false,
+            };
+            if !(write || check || diff || single_file) {
+                return Err(anyhow::anyhow!(
+                    "formatting more than one file needs --write, --check or --diff"
+                ));
+            }
+            let options = FormatOptions {
+                write,
+                check,
+                diff,
+                indent,
+                line_width,
+            };
+            // `--check` lists every file it would reformat before failing
+            let unformatted = AtomicBool::new(false);
+            for_each_file(&paths, ErrorFormat::Human, |file, report| {
+                if format_file(file, options, report)? {
+                    unformatted.store(true, Ordering::Relaxed);
+                }
+                Ok(())
+            })?;
+            if unformatted.into_inner() {
+                std::process::exit(1);
+            }
         }
+        #[cfg(feature = "codegen")]
         Some(Commands::Compile {
             file,
             output,
             opt,
             object,
+            emit,
             target,
+            cpu,
+            features,
+            debug,
+            source_map,
+            wasm,
+            dump_cfg,
+            link_lib,
+            link_path,
+            newline,
+            error_format,
+            warnings,
         }) => {
-            compile_file(&file, output, opt, object, target)?;
+            let format = ErrorFormat::from_name(&error_format).map_err(|e| anyhow::anyhow!(e))?;
+            let target = TargetSpec {
+                triple: target,
+                cpu,
+                features,
+            };
+            let codegen = CodegenOptions {
+                debug_info: debug || source_map,
+                source_map,
+                wasm: wasm
+                    .as_deref()
+                    .map(WasmRuntime::from_name)
+                    .transpose()
+                    .map_err(|e| anyhow::anyhow!(e))?,
+                link_libs: link_lib,
+                link_search_paths: link_path,
+                newline: Newline::from_name(&newline).map_err(|e| anyhow::anyhow!(e))?,
+                dump_cfg,
+                lints: lint_config(&warnings)?,
+                ..CodegenOptions::default()
+            };
+            if wasm.is_some() && emit.is_some() {
+                return Err(anyhow::anyhow!("--wasm writes its own outputs; drop --emit"));
+            }
+            let emit = match emit.as_deref() {
+                Some(name) => EmitKind::from_name(name).map_err(|e| anyhow::anyhow!(e))?,
+                None if object => EmitKind::Exe,
+                None => EmitKind::LlvmIr,
+            };
+            compile_file(&file, output, opt, emit, target, codegen, format)?;
         }
-        None => run_repl()?,
+        Some(Commands::Explain { code }) => explain(code.as_deref())?,
+        Some(Commands::Docs { topic }) => print_docs(&topic)?,
+        Some(Commands::Env) => print_env(),
+        None => run_repl(load_repl_config(None, false)?)?,
     }
 
     Ok(())
 }
 
+#[cfg(feature = "codegen")]
 fn initialize_llvm_targets() {
     let config = InitializationConfig {
         asm_parser: true,
@@ -362,6 +800,141 @@ fn initialize_llvm_targets() {
     Target::initialize_all(&config);
 }
 
+/// Print what a bug report about building or linking needs to know
+/// Serve DAP for `file`, rebuilding it with debug info on each launch
+#[cfg(feature = "codegen")]
+fn debug_file(file: &str) -> Result<()> {
+    let debugger = cheetah::dap::find_debugger().ok_or_else(|| {
+        anyhow::anyhow!(
+            "No DAP debugger found; install lldb-dap or gdb 14+, or set {}",
+            cheetah::dap::DEBUGGER_VAR
+        )
+    })?;
+    let src = ensure_ch_extension(file);
+    let exe_stem = Path::new(&src)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Invalid filename"))?
+        .to_string();
+    let exe_path = std::env::current_dir()?
+        .join(".cheetah_build")
+        .join(exe_stem);
+    let cheetah = std::env::current_exe()?;
+
+    // Build in a child process so its progress output stays off the DAP stream
+    cheetah::dap::serve(&debugger, || {
+        let output = std::process::Command::new(&cheetah)
+            .args(["build", "-g", &src])
+            .output()
+            .map_err(|e| format!("Failed to run cheetah build: {}", e))?;
+        if output.status.success() {
+            Ok(exe_path.clone())
+        } else {
+            Err(format!(
+                "Build failed:\n{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            ))
+        }
+    })
+    .map_err(|e| anyhow::anyhow!(e))
+}
+
+/// Print the explanation of error `code`, or a line about every code
+fn explain(code: Option<&str>) -> Result<()> {
+    let Some(code) = code else {
+        for error in error_codes::ERROR_CODES {
+            println!("{}  {:<26} {}", error.id, error.name, error.summary());
+        }
+        return Ok(());
+    };
+
+    let error = error_codes::lookup(code).ok_or_else(|| {
+        anyhow::anyhow!(
+            "unknown error code '{}'; run `cheetah explain` to list them",
+            code
+        )
+    })?;
+    println!("{} ({})\n", error.id.bold(), error.name);
+    print!("{}", error.explanation);
+    Ok(())
+}
+
+/// Print the reference `topic` names, generated from the tables the parser
+/// and compiler use so it can't go stale
+fn print_docs(topic: &str) -> Result<()> {
+    match topic {
+        "precedence" => print!("{}", parser::precedence::reference()),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "unknown docs topic '{}'; the topics are: precedence",
+                topic
+            ))
+        }
+    }
+    Ok(())
+}
+
+fn print_env() {
+    println!("cheetah {}", env!("CARGO_PKG_VERSION"));
+
+    let features: Vec<&str> = [
+        ("codegen", cfg!(feature = "codegen")),
+        ("benchmarks", cfg!(feature = "benchmarks")),
+    ]
+    .iter()
+    .filter(|(_, enabled)| *enabled)
+    .map(|(name, _)| *name)
+    .collect();
+    if features.is_empty() {
+        println!("features:        none");
+    } else {
+        println!("features:        {}", features.join(", "));
+    }
+    println!(
+        "host:            {}-{}",
+        std::env::consts::ARCH,
+        std::env::consts::OS
+    );
+
+    print_codegen_env();
+}
+
+#[cfg(feature = "codegen")]
+fn print_codegen_env() {
+    let (major, minor, patch) = inkwell::support::get_llvm_version();
+    println!("llvm:            {}.{}.{}", major, minor, patch);
+    println!("default target:  {}", TargetMachine::get_default_triple());
+    println!("host cpu:        {}", TargetMachine::get_host_cpu_name().to_string());
+
+    let mut targets = Vec::new();
+    let mut target = Target::get_first();
+    while let Some(current) = target {
+        targets.push(current.get_name().to_string_lossy().into_owned());
+        target = current.get_next();
+    }
+    targets.sort();
+    println!("targets:         {}", targets.join(", "));
+
+    match cheetah::compiler::runtime_library_dir("") {
+        Ok(dir) => {
+            let lib = Path::new(&dir).join("libcheetah.a");
+            let status = if lib.is_file() { "found" } else { "missing" };
+            println!("runtime library: {} ({})", lib.display(), status);
+        }
+        Err(e) => println!("runtime library: unresolved ({})", e),
+    }
+    println!(
+        "llvm-config:     {}",
+        std::env::var("LLVM_CONFIG").unwrap_or_else(|_| "llvm-config".into())
+    );
+}
+
+#[cfg(not(feature = "codegen"))]
+fn print_codegen_env() {
+    println!("llvm:            not built in (enable the `codegen` feature)");
+}
+
 /// Ensure the file has a .ch extension, adding it if necessary
 fn ensure_ch_extension(filename: &str) -> String {
     let path = PathBuf::from(filename);
@@ -376,7 +949,216 @@ fn ensure_ch_extension(filename: &str) -> String {
     path_with_ext.to_string_lossy().to_string()
 }
 
-fn run_file_jit(filename: &str) -> Result<()> {
+/// Compile the file ahead of time unless a build already exists, then exec it
+#[cfg(feature = "codegen")]
+fn build_and_run(filename: &str) -> Result<()> {
+    let src = ensure_ch_extension(filename);
+    let abs_src = std::fs::canonicalize(&src)
+        .map_err(|e| anyhow::anyhow!("Cannot find {}: {}", src, e))?;
+
+    let cwd = std::env::current_dir()?;
+    let build_dir = cwd.join(".cheetah_build");
+    std::fs::create_dir_all(&build_dir)?;
+
+    let exe_stem = abs_src
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Invalid filename"))?;
+    let exe_path = build_dir.join(exe_stem);
+
+    if !exe_path.exists() {
+        println!("⚙️  No existing build for `{}`, compiling…", exe_stem);
+        std::env::set_current_dir(&build_dir)?;
+        compile_file(
+            abs_src.to_string_lossy().as_ref(),
+            Some(exe_stem.to_string()),
+            0,
+            EmitKind::Exe,
+            TargetSpec::host(),
+            CodegenOptions::default(),
+            ErrorFormat::Human,
+        )?;
+        std::env::set_current_dir(&cwd)?;
+        println!("⚙️ Built {}", exe_path.display());
+    } else {
+        println!("⏩ Found existing build: {}", exe_path.display());
+    }
+
+    println!("▶️  Running {}", exe_path.display());
+    let err = std::process::Command::new(&exe_path).exec();
+    eprintln!("❌ failed to exec `{}`: {}", exe_path.display(), err);
+    std::process::exit(1);
+}
+
+/// The project directory `path` names: a directory, or its cheetah.toml
+fn project_dir(path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+    if path.is_dir() {
+        Some(path.to_path_buf())
+    } else if path.file_name().is_some_and(|name| name == MANIFEST_NAME) {
+        Some(path.parent().unwrap_or(Path::new(".")).to_path_buf())
+    } else {
+        None
+    }
+}
+
+/// Run a project's entry module with the settings from its cheetah.toml,
+/// tracing up to `trace` steps if it's set
+fn run_project(dir: &Path, backend: Backend, trace: Option<usize>) -> Result<()> {
+    let root = std::fs::canonicalize(dir)
+        .map_err(|e| anyhow::anyhow!("Cannot find {}: {}", dir.display(), e))?;
+    let project = Project::load(&root).map_err(|e| anyhow::anyhow!(e))?;
+    let entry = project.entry_path().map_err(|e| anyhow::anyhow!(e))?;
+    let entry = entry.to_string_lossy();
+    reject_forbidden_constructs(&entry, &project.forbidden_constructs())?;
+    let settings = &project.manifest.build;
+    let newline = settings
+        .newline
+        .as_deref()
+        .map(Newline::from_name)
+        .transpose()
+        .map_err(|e| anyhow::anyhow!(e))?
+        .unwrap_or_default();
+
+    match backend {
+        Backend::Interp => run_file_interp(&entry, newline, trace),
+        Backend::Jit => {
+            load_jit_libraries(&settings.link_libs, &project.link_paths());
+            run_file_jit(&entry, settings.opt, newline)
+        }
+        Backend::Aot => {
+            let exe_path = build_project(&project, &entry, newline)?;
+            println!("▶️  Running {}", exe_path.display());
+            let err = std::process::Command::new(&exe_path).exec();
+            eprintln!("❌ failed to exec `{}`: {}", exe_path.display(), err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The project whose cheetah.toml applies to `file`, if any
+fn project_containing(file: &str) -> Result<Option<Project>> {
+    Project::containing(Path::new(file)).map_err(|e| anyhow::anyhow!(e))
+}
+
+/// Lint severities for a file in `project`: the project's, overridden by those from flags
+fn project_lints(project: Option<&Project>, flags: &LintConfig) -> LintConfig {
+    let mut lints = project.map(Project::lint_config).unwrap_or_default();
+    lints.extend(flags);
+    lints
+}
+
+/// Fail if `file` uses any of the `forbidden` constructs, reporting each use
+fn reject_forbidden_constructs(file: &str, forbidden: &[Construct]) -> Result<()> {
+    if forbidden.is_empty() {
+        return Ok(());
+    }
+    let source =
+        fs::read_to_string(file).with_context(|| format!("Failed to read file: {}", file))?;
+    let config = LexerConfig {
+        forbidden: forbidden.to_vec(),
+        ..LexerConfig::default()
+    };
+    let mut lexer = Lexer::with_config(&source, config);
+    lexer.tokenize();
+
+    let uses: Vec<_> = lexer
+        .get_errors()
+        .iter()
+        .filter(|error| error.code() == "forbidden-construct")
+        .collect();
+    if uses.is_empty() {
+        return Ok(());
+    }
+    for error in uses {
+        eprintln!("{}: {}", file, error.to_string().bright_red());
+    }
+    Err(anyhow::anyhow!("'{}' uses constructs this project doesn't allow", file))
+}
+
+/// Build a project's entry module (an absolute path) into `.cheetah_build`
+/// under the project, returning the executable's path
+#[cfg(feature = "codegen")]
+fn build_project(project: &Project, entry: &str, newline: Newline) -> Result<PathBuf> {
+    let build_dir = project.root.join(".cheetah_build");
+    std::fs::create_dir_all(&build_dir)?;
+
+    let settings = &project.manifest.build;
+    let codegen = CodegenOptions {
+        debug_info: settings.debug,
+        link_libs: settings.link_libs.clone(),
+        link_search_paths: project.link_paths(),
+        newline,
+        ..CodegenOptions::default()
+    };
+
+    let name = &project.manifest.package.name;
+    let cwd = std::env::current_dir()?;
+    std::env::set_current_dir(&build_dir)?;
+    let result = compile_file(
+        entry,
+        Some(name.clone()),
+        settings.opt,
+        EmitKind::Exe,
+        TargetSpec::host(),
+        codegen,
+        ErrorFormat::Human,
+    );
+    std::env::set_current_dir(&cwd)?;
+    result?;
+
+    Ok(build_dir.join(name))
+}
+
+/// Error for a command that needs LLVM in a build without the `codegen` feature
+#[cfg(not(feature = "codegen"))]
+fn codegen_unavailable(what: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "{} needs LLVM; rebuild cheetah with the `codegen` feature or use `--backend interp`",
+        what
+    )
+}
+
+#[cfg(not(feature = "codegen"))]
+fn build_and_run(_filename: &str) -> Result<()> {
+    Err(codegen_unavailable("the aot backend"))
+}
+
+#[cfg(not(feature = "codegen"))]
+fn build_project(_project: &Project, _entry: &str, _newline: Newline) -> Result<PathBuf> {
+    Err(codegen_unavailable("building a project"))
+}
+
+#[cfg(not(feature = "codegen"))]
+fn load_jit_libraries(_libs: &[String], _search_paths: &[String]) {}
+
+#[cfg(not(feature = "codegen"))]
+fn run_file_jit(_filename: &str, _opt_level: u8, _newline: Newline) -> Result<()> {
+    Err(codegen_unavailable("the jit backend"))
+}
+
+#[cfg(not(feature = "codegen"))]
+fn run_repl_jit(_opt_level: u8, _config: ReplConfig) -> Result<()> {
+    Err(codegen_unavailable("the JIT REPL"))
+}
+
+/// Load `-l` libraries for JIT-compiled @extern calls, warning about any that can't be loaded
+#[cfg(feature = "codegen")]
+fn load_jit_libraries(libs: &[String], search_paths: &[String]) {
+    for path in cheetah::compiler::ffi::load_libraries(libs, search_paths) {
+        println!(
+            "{}",
+            format!(
+                "Warning: could not load {}; @extern functions must already be available in the process",
+                path.display()
+            )
+            .bright_yellow()
+        );
+    }
+}
+
+#[cfg(feature = "codegen")]
+fn run_file_jit(filename: &str, opt_level: u8, newline: Newline) -> Result<()> {
     buffer::init();
 
     range::init();
@@ -396,23 +1178,25 @@ fn run_file_jit(filename: &str) -> Result<()> {
 
     let source = fs::read_to_string(&filename)
         .with_context(|| format!("Failed to read file: {}", filename))?;
+    cheetah::compiler::runtime::traceback::set_source_name(&filename);
 
     match parse(&source) {
         Ok(module) => {
             let context = context::Context::create();
             let mut compiler = Compiler::new(&context, &filename);
+            compiler.set_opt_level(opt_level);
+            compiler.newline = newline;
 
             match compiler.compile_module(&module) {
                 Ok(_) => {
+                    apply_optimization_passes(&compiler)?;
                     let compiled_module = compiler.get_module();
 
-                    apply_optimization_passes(compiled_module);
-
                     let execution_engine = compiled_module
-                        .create_jit_execution_engine(inkwell::OptimizationLevel::Aggressive)
+                        .create_jit_execution_engine(codegen_level(compiler.opt_level))
                         .map_err(|e| anyhow::anyhow!("Failed to create execution engine: {}", e))?;
 
-                    if let Err(e) = register_runtime_functions(&execution_engine, compiled_module) {
+                    if let Err(e) = jit::register_runtime_functions(&execution_engine, compiled_module) {
                         println!(
                             "{}",
                             format!("Warning: Failed to register some runtime functions: {}", e)
@@ -431,17 +1215,26 @@ fn run_file_jit(filename: &str) -> Result<()> {
                                 );
 
                                 let start_time = std::time::Instant::now();
-                                main_fn.call();
+                                let result = abort::catch_abort(|| main_fn.call());
                                 let elapsed = start_time.elapsed();
 
                                 cheetah::compiler::runtime::buffer::flush();
 
                                 cheetah::compiler::runtime::range::cleanup();
 
+                                cheetah::compiler::runtime::os::cleanup();
+
+                                cheetah::compiler::runtime::traceback::reset();
+
                                 cheetah::compiler::runtime::memory_profiler::cleanup();
 
                                 cheetah::compiler::runtime::parallel_ops::cleanup();
 
+                                if let Err(error) = result {
+                                    eprintln!("{}", error.to_string().bright_red());
+                                    std::process::exit(abort::EXIT_CODE);
+                                }
+
                                 println!(
                                     "{}",
                                     format!("Execution completed in {:.2?}", elapsed)
@@ -475,135 +1268,326 @@ fn run_file_jit(filename: &str) -> Result<()> {
     }
 }
 
-fn run_repl() -> Result<()> {
-    println!("{}", "Cheetah Programming Language REPL".bright_green());
-    println!("Type 'exit' or press Ctrl+D to exit");
-
-    let mut input_buffer = String::new();
-    let mut paren_level = 0;
-    let mut bracket_level = 0;
-    let mut brace_level = 0;
-    let mut in_multiline_block = false;
+/// Run a file with the tree-walking interpreter. An uncaught exception prints
+/// its traceback and exits with status 1, like a compiled program.
+/// Run a file with the interpreter, tracing up to `trace` steps if it's set
+fn run_file_interp(filename: &str, newline: Newline, trace: Option<usize>) -> Result<()> {
+    let filename = ensure_ch_extension(filename);
+    let source = fs::read_to_string(&filename)
+        .with_context(|| format!("Failed to read file: {}", filename))?;
 
-    loop {
-        let prompt = if !input_buffer.is_empty() {
-            "... ".bright_yellow().to_string()
-        } else {
-            ">>> ".bright_green().to_string()
-        };
+    let module = match parse(&source) {
+        Ok(module) => module,
+        Err(errors) => {
+            for error in &errors {
+                let formatter = ParseErrorFormatter::new(error, Some(&source), true);
+                eprintln!("{}", formatter.format().bright_red());
+            }
+            return Err(anyhow::anyhow!("Parsing failed"));
+        }
+    };
+    let module = cheetah::transform::apply(&module).map_err(|e| anyhow::anyhow!(e))?;
+    let module = match trace {
+        Some(_) => Cow::Owned(trace::instrument(&module, &source)),
+        None => module,
+    };
 
-        print!("{}", prompt);
+    let mut interpreter = Interpreter::new();
+    interpreter.set_newline(newline);
+    if let Some(limit) = trace {
+        interpreter.set_trace_limit(limit);
+    }
+    if let Err(error) = interpreter.run(&module) {
         io::stdout().flush()?;
+        eprintln!("{}", error.traceback(&filename));
+        std::process::exit(1);
+    }
+    Ok(())
+}
 
-        let mut input = String::new();
-        if io::stdin().read_line(&mut input)? == 0 {
-            break;
-        }
+/// A line read by the REPL's editor
+enum ReplInput {
+    Line(String),
+    /// Ctrl+C: drop the statement being typed
+    Interrupt,
+    /// Ctrl+D
+    Eof,
+}
 
-        let input = input.trim_end();
+/// Tab completion for the REPL's line editor
+struct ReplHelper {
+    completer: NameCompleter,
+}
 
-        if input_buffer.is_empty() && input == "exit" {
-            break;
-        }
+impl Completer for ReplHelper {
+    type Candidate = String;
 
-        input_buffer.push_str(input);
-        input_buffer.push('\n');
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.completer.complete(line, pos))
+    }
+}
 
-        update_repl_state(
-            &input,
-            &mut paren_level,
-            &mut bracket_level,
-            &mut brace_level,
-            &mut in_multiline_block,
-        );
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
 
-        let should_execute = !in_multiline_block
-            && paren_level == 0
-            && bracket_level == 0
-            && brace_level == 0
-            && (input.trim().is_empty() || !input.trim().ends_with(':'));
+impl Highlighter for ReplHelper {}
 
-        if should_execute {
-            let complete_input = input_buffer.trim();
+impl Validator for ReplHelper {}
 
-            if !complete_input.is_empty() {
-                let mut lexer = Lexer::new(complete_input);
-                let tokens = lexer.tokenize();
+impl Helper for ReplHelper {}
 
-                let lexer_errors = lexer.get_errors();
-                if !lexer_errors.is_empty() {
-                    for error in lexer_errors {
-                        eprintln!("{}", error.to_string().bright_red());
-                    }
-                } else {
-                    match parser::parse(tokens.clone()) {
-                        Ok(_module) => {
-                            println!("{}", "✓ Parsed successfully".bright_green());
-
-                            if input.starts_with("tokens") || input.starts_with("lexer") {
-                                for token in &tokens {
-                                    match &token.token_type {
-                                        TokenType::Invalid(_) => {
-                                            println!("{}", format!("{}", token).bright_red())
-                                        }
-                                        _ => println!("{}", format_token_for_repl(token, true)),
-                                    }
-                                }
-                            }
-                        }
-                        Err(errors) => {
-                            for error in errors {
-                                let formatter =
-                                    ParseErrorFormatter::new(&error, Some(complete_input), true);
-                                eprintln!("{}", formatter.format().bright_red());
-                            }
-                        }
-                    }
-                }
+/// Line editor for the REPL: arrow-key editing, Ctrl+R search, Tab completion,
+/// and history kept in `~/.cheetah_history` between sessions
+struct ReplEditor {
+    editor: Editor<ReplHelper, DefaultHistory>,
+    history: Option<PathBuf>,
+}
+
+impl ReplEditor {
+    fn new() -> Result<Self> {
+        let mut editor = Editor::new()?;
+        editor.set_helper(Some(ReplHelper {
+            completer: NameCompleter::new(),
+        }));
+        let history = ReplConfig::history_path();
+        if let Some(path) = history.as_ref().filter(|path| path.is_file()) {
+            if let Err(e) = editor.load_history(path) {
+                eprintln!(
+                    "{}",
+                    format!("Warning: couldn't read history from {}: {}", path.display(), e)
+                        .bright_yellow()
+                );
             }
+        }
+        Ok(Self { editor, history })
+    }
 
-            input_buffer.clear();
-            paren_level = 0;
-            bracket_level = 0;
-            brace_level = 0;
-            in_multiline_block = false;
+    fn read_line(&mut self, prompt: &str) -> Result<ReplInput> {
+        match self.editor.readline(prompt) {
+            Ok(line) => Ok(ReplInput::Line(line)),
+            Err(ReadlineError::Interrupted) => Ok(ReplInput::Interrupt),
+            Err(ReadlineError::Eof) => Ok(ReplInput::Eof),
+            Err(e) => Err(e.into()),
         }
     }
 
-    println!("Goodbye!");
-    Ok(())
+    /// Offer the names `module` defines as completions from now on
+    fn learn_names(&mut self, module: &ast::Module) {
+        if let Some(helper) = self.editor.helper_mut() {
+            helper.completer.add_module(module);
+        }
+    }
+
+    /// Record a complete statement, so a block comes back as one entry
+    fn add_history(&mut self, entry: &str) {
+        let _ = self.editor.add_history_entry(entry);
+    }
+
+    /// Write the history file
+    fn save(&mut self) {
+        if let Some(path) = &self.history {
+            if let Err(e) = self.editor.save_history(path) {
+                eprintln!(
+                    "{}",
+                    format!("Warning: couldn't save history to {}: {}", path.display(), e)
+                        .bright_yellow()
+                );
+            }
+        }
+    }
+}
+
+/// Line that ends a `:paste` block
+const PASTE_END: &str = "--";
+
+/// Read a pasted block for the REPL to run as one unit. Blank lines don't end
+/// it, so functions and classes with blank lines inside paste intact.
+/// Ctrl+C abandons the block.
+fn read_paste_block(editor: &mut ReplEditor) -> Result<String> {
+    println!(
+        "{}",
+        format!("-- paste mode; end with a line holding only `{}` or press Ctrl+D", PASTE_END)
+            .bright_yellow()
+    );
+
+    let mut block = String::new();
+    loop {
+        match editor.read_line("")? {
+            ReplInput::Line(line) if line.trim_end() == PASTE_END => break,
+            ReplInput::Line(line) => {
+                block.push_str(line.trim_end_matches(['\n', '\r']));
+                block.push('\n');
+            }
+            ReplInput::Interrupt => return Ok(String::new()),
+            ReplInput::Eof => break,
+        }
+    }
+    Ok(block)
+}
+
+/// Load the REPL's startup script: `rc` if given, otherwise `~/.cheetahrc` if
+/// it exists. A script that fails to parse is reported and skipped.
+fn load_repl_config(rc: Option<&str>, no_rc: bool) -> Result<ReplConfig> {
+    if no_rc {
+        return Ok(ReplConfig::default());
+    }
+    let path = match rc {
+        Some(path) => PathBuf::from(path),
+        None => match ReplConfig::default_path() {
+            Some(path) if path.is_file() => path,
+            _ => return Ok(ReplConfig::default()),
+        },
+    };
+    if rc.is_some() && !path.is_file() {
+        return Err(anyhow::anyhow!("Startup script not found: {}", path.display()));
+    }
+
+    match ReplConfig::load(&path) {
+        Ok(config) => Ok(config),
+        Err(e) => {
+            eprintln!("{}", format!("Warning: skipping startup script {}", e).bright_yellow());
+            Ok(ReplConfig::default())
+        }
+    }
+}
+
+/// What the REPL does with complete inputs: the plain REPL parses and checks
+/// them, the JIT REPL compiles and runs them
+trait ReplEvaluator {
+    /// Evaluate a parsed input, reporting any errors; returns whether it succeeded
+    fn evaluate(&mut self, module: &ast::Module) -> bool;
+
+    /// Type of `expr` given the inputs evaluated so far
+    fn expression_type(&self, expr: &ast::Expr) -> Option<Type>;
+
+    /// LLVM IR compiled for the last input
+    fn last_ir(&self) -> Result<String, String>;
+}
+
+/// Evaluator of the plain REPL, which type checks inputs without running them
+struct CheckEvaluator {
+    checker: TypeChecker,
+}
+
+impl CheckEvaluator {
+    /// Check `module` against the inputs before it, keeping its definitions if it passes
+    fn check(&mut self, module: &ast::Module) -> bool {
+        let mut checker = self.checker.clone();
+        let passed = checker.check_module(module).is_ok();
+        if passed {
+            self.checker = checker;
+        }
+        passed
+    }
 }
 
-fn run_repl_jit() -> Result<()> {
+impl ReplEvaluator for CheckEvaluator {
+    fn evaluate(&mut self, module: &ast::Module) -> bool {
+        self.check(module);
+        println!("{}", "✓ Parsed successfully".bright_green());
+        true
+    }
+
+    fn expression_type(&self, expr: &ast::Expr) -> Option<Type> {
+        self.checker.expression_type(expr)
+    }
+
+    fn last_ir(&self) -> Result<String, String> {
+        Err("`:ir` needs the JIT REPL (`cheetah repl -j`)".to_string())
+    }
+}
+
+#[cfg(feature = "codegen")]
+impl ReplEvaluator for ReplSession<'_> {
+    fn evaluate(&mut self, module: &ast::Module) -> bool {
+        match self.run_cell(module) {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("{}", e.bright_red());
+                false
+            }
+        }
+    }
+
+    fn expression_type(&self, expr: &ast::Expr) -> Option<Type> {
+        ReplSession::expression_type(self, expr)
+    }
+
+    fn last_ir(&self) -> Result<String, String> {
+        ReplSession::last_ir(self).ok_or_else(|| "Nothing has been compiled yet".to_string())
+    }
+}
+
+fn run_repl(config: ReplConfig) -> Result<()> {
+    println!("{}", "Cheetah Programming Language REPL".bright_green());
+    println!("Type 'exit' or press Ctrl+D to exit, ':help' for commands");
+
+    let mut editor = ReplEditor::new()?;
+    let mut evaluator = CheckEvaluator {
+        checker: TypeChecker::new(),
+    };
+    if let Some(startup) = &config.startup {
+        evaluator.check(startup);
+        editor.learn_names(startup);
+    }
+
+    repl_loop(&config, &mut editor, &mut evaluator)
+}
+
+#[cfg(feature = "codegen")]
+fn run_repl_jit(opt_level: u8, config: ReplConfig) -> Result<()> {
     println!(
         "{}",
         "Cheetah Programming Language REPL (JIT Mode)".bright_green()
     );
-    println!("Type 'exit' or press Ctrl+D to exit");
+    println!("Type 'exit' or press Ctrl+D to exit, ':help' for commands");
+    cheetah::compiler::runtime::traceback::set_source_name("<stdin>");
 
-    let mut input_buffer = String::new();
-    let mut paren_level = 0;
-    let mut bracket_level = 0;
-    let mut brace_level = 0;
-    let mut in_multiline_block = false;
+    let mut editor = ReplEditor::new()?;
 
     let context = context::Context::create();
-    let mut repl_count = 0;
+    let mut session = ReplSession::new(&context, opt_level);
+    if let Some(startup) = &config.startup {
+        match session.run_cell(startup) {
+            Ok(()) => editor.learn_names(startup),
+            Err(e) => eprintln!("{}", format!("Startup script failed: {}", e).bright_red()),
+        }
+    }
+
+    repl_loop(&config, &mut editor, &mut session)
+}
+
+/// Read inputs until `exit` or Ctrl+D, evaluating each complete one
+fn repl_loop(
+    config: &ReplConfig,
+    editor: &mut ReplEditor,
+    evaluator: &mut dyn ReplEvaluator,
+) -> Result<()> {
+    let mut input_buffer = String::new();
+    let mut last_input: Option<String> = None;
 
     loop {
         let prompt = if !input_buffer.is_empty() {
-            "... ".bright_yellow().to_string()
+            config.ps2.bright_yellow().to_string()
         } else {
-            ">>> ".bright_green().to_string()
+            config.ps1.bright_green().to_string()
         };
 
-        print!("{}", prompt);
-        io::stdout().flush()?;
-
-        let mut input = String::new();
-        if io::stdin().read_line(&mut input)? == 0 {
-            break;
-        }
+        let input = match editor.read_line(&prompt)? {
+            ReplInput::Line(input) => input,
+            ReplInput::Interrupt => {
+                println!("{}", "KeyboardInterrupt".bright_yellow());
+                input_buffer.clear();
+                continue;
+            }
+            ReplInput::Eof => break,
+        };
 
         let input = input.trim_end();
 
@@ -611,158 +1595,142 @@ fn run_repl_jit() -> Result<()> {
             break;
         }
 
-        input_buffer.push_str(input);
-        input_buffer.push('\n');
-
-        update_repl_state(
-            &input,
-            &mut paren_level,
-            &mut bracket_level,
-            &mut brace_level,
-            &mut in_multiline_block,
-        );
+        let mut pasted = false;
+        if input_buffer.is_empty() {
+            match MetaCommand::parse(input) {
+                Some(Ok(MetaCommand::Paste)) => {
+                    input_buffer = read_paste_block(editor)?;
+                    pasted = true;
+                }
+                Some(Ok(command)) => {
+                    editor.add_history(input);
+                    run_meta_command(command, editor, evaluator, last_input.as_deref());
+                    continue;
+                }
+                Some(Err(e)) => {
+                    eprintln!("{}", e.bright_red());
+                    continue;
+                }
+                None => {}
+            }
+        }
+        if !pasted {
+            input_buffer.push_str(input);
+            input_buffer.push('\n');
+        }
 
-        let should_execute = !in_multiline_block
-            && paren_level == 0
-            && bracket_level == 0
-            && brace_level == 0
-            && (input.trim().is_empty() || !input.trim().ends_with(':'));
+        let should_execute = pasted || !needs_more_input(&input_buffer);
 
         if should_execute {
-            let complete_input = input_buffer.trim();
+            let complete_input = input_buffer.trim().to_string();
+            input_buffer.clear();
 
             if !complete_input.is_empty() {
-                repl_count += 1;
-                let module_name = format!("repl_{}", repl_count);
-
-                match parse(complete_input) {
-                    Ok(module) => {
-                        let mut compiler = Compiler::new(&context, &module_name);
-
-                        match compiler.compile_module(&module) {
-                            Ok(_) => {
-                                println!("{}", "✓ Compiled successfully".bright_green());
-
-                                let compiled_module = compiler.get_module();
-
-                                apply_optimization_passes(compiled_module);
-
-                                match compiled_module.create_jit_execution_engine(
-                                    inkwell::OptimizationLevel::Aggressive,
-                                ) {
-                                    Ok(execution_engine) => {
-                                        if let Err(e) = register_runtime_functions(
-                                            &execution_engine,
-                                            compiled_module,
-                                        ) {
-                                            println!("{}", format!("Warning: Failed to register some runtime functions: {}", e).bright_yellow());
-                                        }
-
-                                        unsafe {
-                                            match execution_engine
-                                                .get_function::<unsafe extern "C" fn() -> ()>(
-                                                    "main",
-                                                ) {
-                                                Ok(main_fn) => {
-                                                    println!(
-                                                        "{}",
-                                                        "Executing main function...".bright_green()
-                                                    );
-                                                    main_fn.call();
-                                                    cheetah::compiler::runtime::buffer::flush();
-
-                                                    cheetah::compiler::runtime::range::cleanup();
-
-                                                    cheetah::compiler::runtime::memory_profiler::cleanup();
-
-                                                    cheetah::compiler::runtime::parallel_ops::cleanup();
-
-                                                    println!(
-                                                        "{}",
-                                                        "Execution completed.".bright_green()
-                                                    );
-                                                }
-                                                Err(e) => {
-                                                    println!("{}", format!("Warning: Failed to find main function: {}", e).bright_yellow());
-                                                    println!(
-                                                        "{}",
-                                                        "Displaying IR instead:".bright_yellow()
-                                                    );
-                                                    println!("{}", compiler.get_ir());
-                                                }
-                                            }
-                                        }
-                                    }
-                                    Err(e) => {
-                                        eprintln!(
-                                            "{}",
-                                            format!("Failed to create execution engine: {}", e)
-                                                .bright_red()
-                                        );
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                eprintln!("{}", format!("Compilation error: {}", e).bright_red());
-                            }
-                        }
-                    }
-                    Err(errors) => {
-                        for error in errors {
-                            eprintln!("{}", error.get_message().bright_red());
-                        }
-                    }
-                }
+                editor.add_history(&complete_input);
+                evaluate_source(&complete_input, editor, evaluator);
+                last_input = Some(complete_input);
             }
-
-            input_buffer.clear();
-            paren_level = 0;
-            bracket_level = 0;
-            brace_level = 0;
-            in_multiline_block = false;
         }
     }
 
+    editor.save();
     println!("Goodbye!");
     Ok(())
 }
 
-/// Updates the REPL state based on the current line of input
-fn update_repl_state(
-    input: &str,
-    paren_level: &mut usize,
-    bracket_level: &mut usize,
-    brace_level: &mut usize,
-    in_multiline_block: &mut bool,
+/// Parse and evaluate REPL input, reporting parse errors; returns whether it succeeded
+fn evaluate_source(
+    source: &str,
+    editor: &mut ReplEditor,
+    evaluator: &mut dyn ReplEvaluator,
+) -> bool {
+    match parse(source) {
+        Ok(module) => {
+            let evaluated = evaluator.evaluate(&module);
+            if evaluated {
+                editor.learn_names(&module);
+            }
+            evaluated
+        }
+        Err(errors) => {
+            for error in errors {
+                let formatter = ParseErrorFormatter::new(&error, Some(source), true);
+                eprintln!("{}", formatter.format().bright_red());
+            }
+            false
+        }
+    }
+}
+
+/// Run a REPL meta-command. `last_input` is the last input evaluated.
+fn run_meta_command(
+    command: MetaCommand,
+    editor: &mut ReplEditor,
+    evaluator: &mut dyn ReplEvaluator,
+    last_input: Option<&str>,
 ) {
-    for c in input.chars() {
-        match c {
-            '(' => *paren_level += 1,
-            ')' => {
-                if *paren_level > 0 {
-                    *paren_level -= 1
+    match command {
+        MetaCommand::Help => println!("{}", cheetah::repl_command::HELP),
+        MetaCommand::Type(source) => match parser::parse_expression(&source) {
+            Ok(expr) => match evaluator.expression_type(&expr) {
+                Some(ty) => println!("{}", ty),
+                None => eprintln!(
+                    "{}",
+                    format!("Can't infer a type for `{}`", source).bright_red()
+                ),
+            },
+            Err(_) if parser::parse_statement(&source).is_ok() => {
+                eprintln!("{}", ":type takes an expression".bright_red())
+            }
+            Err(errors) => {
+                for error in errors {
+                    eprintln!("{}", error.get_message().bright_red());
                 }
             }
-            '[' => *bracket_level += 1,
-            ']' => {
-                if *bracket_level > 0 {
-                    *bracket_level -= 1
+        },
+        MetaCommand::Ast => {
+            let Some(source) = last_input else {
+                eprintln!("{}", "No input yet".bright_red());
+                return;
+            };
+            if let Ok(module) = parse(source) {
+                let mut printer = cheetah::visitor::AstPrinter::new();
+                println!("{}", printer.visit_module(&module));
+            }
+        }
+        MetaCommand::Ir => match evaluator.last_ir() {
+            Ok(ir) => println!("{}", ir),
+            Err(e) => eprintln!("{}", e.bright_red()),
+        },
+        MetaCommand::Tokens(code) => {
+            let Some(source) = code.as_deref().or(last_input) else {
+                eprintln!("{}", "No input yet".bright_red());
+                return;
+            };
+            let mut lexer = Lexer::new(source);
+            for token in &lexer.tokenize() {
+                match &token.token_type {
+                    TokenType::Invalid(_) => println!("{}", format!("{}", token).bright_red()),
+                    _ => println!("{}", format_token_for_repl(token, true)),
                 }
             }
-            '{' => *brace_level += 1,
-            '}' => {
-                if *brace_level > 0 {
-                    *brace_level -= 1
+        }
+        MetaCommand::Load(path) => {
+            let path = ensure_ch_extension(&path.to_string_lossy());
+            match fs::read_to_string(&path) {
+                Ok(source) => {
+                    if evaluate_source(&source, editor, evaluator) {
+                        println!("{}", format!("Loaded {}", path).bright_green());
+                    }
                 }
+                Err(e) => eprintln!(
+                    "{}",
+                    format!("Failed to read {}: {}", path, e).bright_red()
+                ),
             }
-            _ => {}
         }
-    }
-
-    if input.trim().ends_with(':') {
-        *in_multiline_block = true;
-    } else if input.trim().is_empty() && *in_multiline_block {
-        *in_multiline_block = false;
+        // Handled by the input loop, which reads the block
+        MetaCommand::Paste => {}
     }
 }
 
@@ -844,8 +1812,29 @@ fn lex_file(filename: &str, verbose: bool, use_color: bool, line_numbers: bool)
     Ok(())
 }
 
-/// New function to parse a file and print the AST
-fn parse_file(filename: &str, verbose: bool) -> Result<()> {
+/// Print the semantic token of each classified range of a file, one per line
+fn lex_file_semantic(filename: &str) -> Result<()> {
+    let filename = ensure_ch_extension(filename);
+    let source = fs::read_to_string(&filename)
+        .with_context(|| format!("Failed to read file: {}", filename))?;
+
+    let tokens = cheetah::semantic_tokens::semantic_tokens(&source).map_err(|e| anyhow::anyhow!(e))?;
+    for token in tokens {
+        let modifiers = token.modifiers();
+        println!(
+            "{}:{} {} {:?}{}",
+            token.line,
+            token.column,
+            token.kind.name(),
+            &source[token.span.start..token.span.end],
+            if modifiers.is_empty() { String::new() } else { format!(" [{}]", modifiers.join(", ")) }
+        );
+    }
+    Ok(())
+}
+
+/// New function to parse a file and print the AST, as JSON when `json` is set
+fn parse_file(filename: &str, verbose: bool, json: bool, format: ErrorFormat) -> Result<()> {
     let filename = ensure_ch_extension(filename);
     let source = fs::read_to_string(&filename)
         .with_context(|| format!("Failed to read file: {}", filename))?;
@@ -854,6 +1843,22 @@ fn parse_file(filename: &str, verbose: bool) -> Result<()> {
     let tokens = lexer.tokenize();
 
     let lexer_errors = lexer.get_errors();
+    if format != ErrorFormat::Human {
+        let diagnostics: Vec<Diagnostic> = if lexer_errors.is_empty() {
+            match parser::parse(tokens) {
+                Ok(module) if json => {
+                    println!("{}", module.to_json());
+                    return Ok(());
+                }
+                Ok(_) => Vec::new(),
+                Err(errors) => errors.iter().map(Diagnostic::from).collect(),
+            }
+        } else {
+            lexer_errors.iter().map(Diagnostic::from).collect()
+        };
+        print!("{}", diagnostics::render(format, &filename, &diagnostics));
+        return Ok(());
+    }
     if !lexer_errors.is_empty() {
         eprintln!("Lexical errors found in '{}':", filename);
         for error in lexer_errors {
@@ -863,6 +1868,7 @@ fn parse_file(filename: &str, verbose: bool) -> Result<()> {
     }
 
     match parser::parse(tokens) {
+        Ok(module) if json => println!("{}", module.to_json()),
         Ok(module) => {
             println!("Successfully parsed file: {}", filename);
 
@@ -902,176 +1908,656 @@ fn parse_file(filename: &str, verbose: bool) -> Result<()> {
     Ok(())
 }
 
-fn check_file(filename: &str, verbose: bool) -> Result<()> {
+/// Print the concrete syntax tree of `filename`
+fn parse_file_cst(filename: &str) -> Result<()> {
+    let filename = ensure_ch_extension(filename);
+    let source = fs::read_to_string(&filename)
+        .with_context(|| format!("Failed to read file: {}", filename))?;
+
+    match cheetah::cst::parse(&source) {
+        Ok(tree) => print!("{}", tree),
+        Err(errors) => {
+            eprintln!("Syntax errors found in '{}':", filename);
+            for error in errors {
+                let formatter = ParseErrorFormatter::new(&error, Some(&source), true);
+                eprintln!("  {}", formatter);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Print the typed IR `filename` lowers to
+fn parse_file_ir(filename: &str) -> Result<()> {
+    let filename = ensure_ch_extension(filename);
+    let source = fs::read_to_string(&filename)
+        .with_context(|| format!("Failed to read file: {}", filename))?;
+
+    match parse(&source) {
+        Ok(module) => {
+            let mut program = cheetah::ir::lower_module(&module).map_err(|e| anyhow::anyhow!(e))?;
+            cheetah::ir::specialize(&mut program);
+            print!("{}", program);
+            Ok(())
+        }
+        Err(errors) => {
+            for error in &errors {
+                let formatter = ParseErrorFormatter::new(error, Some(&source), true);
+                eprintln!("{}", formatter.format().bright_red());
+            }
+            Err(anyhow::anyhow!("Parsing failed"))
+        }
+    }
+}
+
+/// Print the AST of a file, and optionally the CFGs of its functions, as Graphviz graphs
+fn parse_file_dot(filename: &str, cfg: bool) -> Result<()> {
+    let filename = ensure_ch_extension(filename);
+    let source = fs::read_to_string(&filename)
+        .with_context(|| format!("Failed to read file: {}", filename))?;
+
+    match parse(&source) {
+        Ok(module) => {
+            print!("{}", cheetah::ast_dot::module_to_dot(&module));
+            if cfg {
+                print_cfg_dots(&module, &filename)?;
+            }
+            Ok(())
+        }
+        Err(errors) => {
+            for error in &errors {
+                let formatter = ParseErrorFormatter::new(error, Some(&source), true);
+                eprintln!("{}", formatter.format().bright_red());
+            }
+            Err(anyhow::anyhow!("Parsing failed"))
+        }
+    }
+}
+
+#[cfg(feature = "codegen")]
+fn print_cfg_dots(module: &ast::Module, filename: &str) -> Result<()> {
+    let context = context::Context::create();
+    let mut compiler = Compiler::new(&context, filename);
+    compiler
+        .compile_module(module)
+        .map_err(|e| anyhow::anyhow!("Compilation failed: {}", e))?;
+    for graph in compiler.cfg_graphs(None).map_err(|e| anyhow::anyhow!(e))? {
+        print!("{}", graph.to_dot());
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "codegen"))]
+fn print_cfg_dots(_module: &ast::Module, _filename: &str) -> Result<()> {
+    Err(codegen_unavailable("--cfg"))
+}
+
+/// Run the docstring examples of the files `paths` names, one file at a time
+#[cfg(feature = "codegen")]
+fn doctest_files(paths: &[String], opt_level: u8) -> Result<()> {
+    let files = source_files::expand(paths).map_err(|e| anyhow::anyhow!(e))?;
+    if files.is_empty() {
+        return Err(anyhow::anyhow!("no Cheetah files found in {}", paths.join(", ")));
+    }
+
+    let (mut passed, mut failed) = (0, 0);
+    for file in &files {
+        let filename = file.to_string_lossy();
+        let source = fs::read_to_string(file)
+            .with_context(|| format!("Failed to read file: {}", filename))?;
+        let module = match parse(&source) {
+            Ok(module) => module,
+            Err(errors) => {
+                for error in &errors {
+                    let formatter = ParseErrorFormatter::new(error, Some(&source), true);
+                    eprintln!("{}", formatter.format().bright_red());
+                }
+                return Err(anyhow::anyhow!("Parsing {} failed", filename));
+            }
+        };
+        let module_name = file.file_stem().and_then(|s| s.to_str()).unwrap_or("module");
+        cheetah::compiler::runtime::traceback::set_source_name(&filename);
+
+        for test in doctest::extract(&module, module_name) {
+            let result = doctest::run(&module, &test, opt_level).unwrap_or_else(|e| DocTestResult {
+                passed: 0,
+                failures: test
+                    .examples
+                    .iter()
+                    .map(|example| doctest::Failure {
+                        example: example.clone(),
+                        got: format!("{}\n", e),
+                    })
+                    .collect(),
+            });
+            for failure in &result.failures {
+                print_doctest_failure(&filename, &test.name, failure);
+            }
+            let status = match result.failures.len() {
+                0 => "ok".bright_green(),
+                _ => "FAILED".bright_red(),
+            };
+            println!("doctest {}:{} {} ... {}", filename, test.line, test.name, status);
+            passed += result.passed;
+            failed += result.failures.len();
+        }
+    }
+
+    let status = match failed {
+        0 => "ok".bright_green(),
+        _ => "FAILED".bright_red(),
+    };
+    println!("\ndoctest result: {}. {} passed; {} failed", status, passed, failed);
+    match failed {
+        0 => Ok(()),
+        _ => Err(anyhow::anyhow!("{} of {} examples failed", failed, passed + failed)),
+    }
+}
+
+#[cfg(not(feature = "codegen"))]
+fn doctest_files(_paths: &[String], _opt_level: u8) -> Result<()> {
+    Err(codegen_unavailable("doctest"))
+}
+
+/// Show an example that failed, with the output it should have printed and what it printed
+#[cfg(feature = "codegen")]
+fn print_doctest_failure(filename: &str, name: &str, failure: &doctest::Failure) {
+    let indented = |text: &str| -> String { text.lines().map(|line| format!("    {}\n", line)).collect() };
+    println!("{}", format!("---- {}:{} in {}", filename, failure.example.line, name).bright_red());
+    print!("Failed example:\n{}", indented(&failure.example.source));
+    match failure.example.want.as_str() {
+        "" => println!("Expected nothing"),
+        want => print!("Expected:\n{}", indented(want)),
+    }
+    match failure.got.as_str() {
+        "" => println!("Got nothing"),
+        got => print!("Got:\n{}", indented(got)),
+    }
+}
+
+/// Lint settings from `-W` flags, applied in order
+fn lint_config(flags: &[String]) -> Result<LintConfig> {
+    let mut config = LintConfig::default();
+    for flag in flags {
+        config.apply_flag(flag).map_err(|e| anyhow::anyhow!(e))?;
+    }
+    Ok(config)
+}
+
+/// Print `-W`-classified warnings for `filename`, returning an error if any were denied
+#[cfg(feature = "codegen")]
+fn report_warnings(filename: &str, warnings: &[linter::Diagnostic]) -> Result<()> {
+    let mut text = String::new();
+    write_warnings(&mut text, filename, warnings)?;
+    eprint!("{}", text);
+    denied_warnings(filename, warnings)
+}
+
+/// Write warnings for `filename` to `out`, errors in red and warnings in yellow
+fn write_warnings(out: &mut String, filename: &str, warnings: &[linter::Diagnostic]) -> Result<()> {
+    for warning in warnings {
+        let text = format!("{}: {}", filename, warning);
+        if warning.severity == Severity::Deny {
+            writeln!(out, "{}", text.bright_red())?;
+        } else {
+            writeln!(out, "{}", text.bright_yellow())?;
+        }
+    }
+    Ok(())
+}
+
+/// An error if any of the warnings for `filename` were denied
+fn denied_warnings(filename: &str, warnings: &[linter::Diagnostic]) -> Result<()> {
+    match warnings.iter().filter(|w| w.severity == Severity::Deny).count() {
+        0 => Ok(()),
+        denied => Err(anyhow::anyhow!(
+            "{} warning(s) treated as errors in {}",
+            denied,
+            filename
+        )),
+    }
+}
+
+/// Output of `format`, `check` or `lint` for one file, held back so files can
+/// be processed in parallel and still reported in order
+#[derive(Default)]
+struct FileReport {
+    stdout: String,
+    stderr: String,
+    /// Diagnostics for `--error-format json` and `sarif`
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Stack size of the threads files are processed on; deeply nested code
+/// needs more than rayon's default to parse
+const FILE_THREAD_STACK_SIZE: usize = 64 * 1024 * 1024;
+
+/// Run `run` on every source file `paths` names, in parallel, then print the
+/// reports in path order. Fails if any file did.
+fn for_each_file<F>(paths: &[String], format: ErrorFormat, run: F) -> Result<()>
+where
+    F: Fn(&str, &mut FileReport) -> Result<()> + Sync,
+{
+    let files = source_files::expand(paths).map_err(|e| anyhow::anyhow!(e))?;
+    if files.is_empty() {
+        return Err(anyhow::anyhow!("no Cheetah files found in {}", paths.join(", ")));
+    }
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .stack_size(FILE_THREAD_STACK_SIZE)
+        .build()?;
+    let mut results: Vec<(String, FileReport, Result<()>)> = pool.install(|| {
+        files
+            .par_iter()
+            .map(|file| {
+                let file = file.to_string_lossy().to_string();
+                let mut report = FileReport::default();
+                let result = run(&file, &mut report);
+                (file, report, result)
+            })
+            .collect()
+    });
+
+    for (_, report, _) in &results {
+        print!("{}", report.stdout);
+        eprint!("{}", report.stderr);
+    }
+    if format != ErrorFormat::Human {
+        let diagnostics: Vec<(&str, &[Diagnostic])> = results
+            .iter()
+            .map(|(file, report, _)| (file.as_str(), report.diagnostics.as_slice()))
+            .collect();
+        print!("{}", diagnostics::render_files(format, &diagnostics));
+    }
+
+    if results.len() == 1 {
+        return results.remove(0).2;
+    }
+    let mut failed = 0;
+    for (_, _, result) in &results {
+        if let Err(e) = result {
+            eprintln!("{}", format!("error: {}", e).bright_red());
+            failed += 1;
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        _ => Err(anyhow::anyhow!("{} of {} files failed", failed, results.len())),
+    }
+}
+
+fn check_file(
+    filename: &str,
+    verbose: bool,
+    format: ErrorFormat,
+    lints: &LintConfig,
+    report: &mut FileReport,
+) -> Result<()> {
     let filename = ensure_ch_extension(filename);
     let source = fs::read_to_string(&filename)
         .with_context(|| format!("Failed to read file: {}", filename))?;
 
+    let project = project_containing(&filename)?;
     let config = LexerConfig {
-        enforce_indent_consistency: true,
-        standard_indent_size: 4,
-        tab_width: 4,
-        allow_tabs_in_indentation: false,
         allow_trailing_semicolon: false,
+        ..project.as_ref().map(Project::lexer_config).unwrap_or_default()
     };
+    let lints = &project_lints(project.as_ref(), lints);
 
     let mut lexer = Lexer::with_config(&source, config);
     let tokens = lexer.tokenize();
 
     let lexer_errors = lexer.get_errors();
+    if format != ErrorFormat::Human {
+        if !lexer_errors.is_empty() {
+            report.diagnostics = lexer_errors.iter().map(Diagnostic::from).collect();
+            return Err(anyhow::anyhow!("Lexical errors found in '{}'", filename));
+        }
+        let module = parser::parse(tokens).map_err(|errors| {
+            report.diagnostics = errors.iter().map(Diagnostic::from).collect();
+            anyhow::anyhow!("Syntax errors found in '{}'", filename)
+        })?;
+        let warnings = lints.classify(typechecker::collect_warnings(&module, &source));
+        report.diagnostics = warnings.iter().map(Diagnostic::from).collect();
+        return denied_warnings(&filename, &warnings);
+    }
     if !lexer_errors.is_empty() {
-        eprintln!("✗ Lexical errors found in '{}':", filename);
+        let err = &mut report.stderr;
+        writeln!(err, "✗ Lexical errors found in '{}':", filename)?;
         for error in lexer_errors {
             if verbose {
-                eprintln!(
+                writeln!(
+                    err,
                     "  Line {}, Col {}: {}",
                     error.line, error.column, error.message
-                );
-                eprintln!("  {}", error.snippet);
-                eprintln!("  {}^", " ".repeat(error.column + 1));
+                )?;
+                writeln!(err, "  {}", error.snippet)?;
+                writeln!(err, "  {}^", " ".repeat(error.column + 1))?;
                 if let Some(suggestion) = &error.suggestion {
-                    eprintln!("  Suggestion: {}", suggestion);
+                    writeln!(err, "  Suggestion: {}", suggestion)?;
+                }
+                if let Some(id) = error_codes::id_for(error.code()) {
+                    writeln!(err, "  = help: run `cheetah explain {}` for more about this error", id)?;
                 }
-                eprintln!();
+                writeln!(err)?;
             } else {
-                eprintln!("  {}", error);
+                writeln!(err, "  {}", error)?;
             }
         }
-        return Ok(());
+        return Err(anyhow::anyhow!("Lexical errors found in '{}'", filename));
     }
 
     match parser::parse(tokens) {
-        Ok(_) => {
-            println!("✓ No syntax errors found in '{}'", filename);
+        Ok(module) => {
+            writeln!(report.stdout, "✓ No syntax errors found in '{}'", filename)?;
+
+            let warnings = lints.classify(typechecker::collect_warnings(&module, &source));
+            write_warnings(&mut report.stderr, &filename, &warnings)?;
+            denied_warnings(&filename, &warnings)
         }
         Err(errors) => {
-            eprintln!("✗ Syntax errors found in '{}':", filename);
+            writeln!(report.stderr, "✗ Syntax errors found in '{}':", filename)?;
             for error in errors {
                 if verbose {
                     let formatter = ParseErrorFormatter::new(&error, Some(&source), true);
-                    eprintln!("  {}", formatter);
+                    writeln!(report.stderr, "  {}", formatter)?;
                 } else {
-                    eprintln!("  {}", error.get_message());
+                    writeln!(report.stderr, "  {}", error.get_message())?;
                 }
             }
+            Err(anyhow::anyhow!("Syntax errors found in '{}'", filename))
         }
     }
+}
+
+fn lint_file(filename: &str, config: &LintConfig, report: &mut FileReport) -> Result<()> {
+    let filename = ensure_ch_extension(filename);
+    let source = fs::read_to_string(&filename)
+        .with_context(|| format!("Failed to read file: {}", filename))?;
+    let project = project_containing(&filename)?;
+    let config = &project_lints(project.as_ref(), config);
+    let lexer_config = LexerConfig {
+        forbidden: Vec::new(),
+        ..project.as_ref().map(Project::lexer_config).unwrap_or_default()
+    };
 
+    let module = match parser::tokenize_with_config(&source, lexer_config).and_then(parser::parse) {
+        Ok(module) => module,
+        Err(errors) => {
+            for error in &errors {
+                let formatter = ParseErrorFormatter::new(error, Some(&source), true);
+                writeln!(report.stderr, "{}", formatter.format().bright_red())?;
+            }
+            return Err(anyhow::anyhow!("Parsing {} failed", filename));
+        }
+    };
+
+    let diagnostics = linter::lint(&module, &source, config);
+    write_warnings(&mut report.stderr, &filename, &diagnostics)?;
+
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Deny)
+        .count();
+    if errors > 0 {
+        return Err(anyhow::anyhow!("{} lint error(s) in {}", errors, filename));
+    }
+    if diagnostics.is_empty() {
+        writeln!(report.stdout, "✓ No lint findings in '{}'", filename)?;
+    }
     Ok(())
 }
 
-fn format_file(filename: &str, write: bool, indent_size: usize) -> Result<()> {
+/// What `format` does with each file
+#[derive(Clone, Copy)]
+struct FormatOptions {
+    /// Rewrite the file in place
+    write: bool,
+    /// Fail if the file isn't formatted
+    check: bool,
+    /// Print a diff rather than the formatted code
+    diff: bool,
+    /// Indentation size, if given on the command line
+    indent: Option<usize>,
+    /// Width to keep lines within, if given on the command line
+    line_width: Option<usize>,
+}
+
+/// Format `filename` as `options` say, returning whether `--check` found it
+/// unformatted
+fn format_file(filename: &str, options: FormatOptions, report: &mut FileReport) -> Result<bool> {
     let filename = ensure_ch_extension(filename);
     let source = fs::read_to_string(&filename)
         .with_context(|| format!("Failed to read file: {}", filename))?;
 
-    let mut lexer = Lexer::new(&source);
+    // The project's cheetah.toml supplies whatever the flags leave out
+    let project = project_containing(&filename)?;
+    let settings = project
+        .as_ref()
+        .map(|project| project.manifest.format.clone())
+        .unwrap_or_default();
+    let lexer_config = LexerConfig {
+        forbidden: Vec::new(),
+        ..project.as_ref().map(Project::lexer_config).unwrap_or_default()
+    };
+
+    let mut lexer = Lexer::with_config(&source, lexer_config);
     let tokens = lexer.tokenize();
 
     let lexer_errors = lexer.get_errors();
     if !lexer_errors.is_empty() {
-        eprintln!("Cannot format file with lexical errors:");
+        writeln!(report.stderr, "Cannot format {} with lexical errors:", filename)?;
         for error in lexer_errors {
-            eprintln!("  {}", error);
+            writeln!(report.stderr, "  {}", error)?;
         }
-        return Ok(());
+        return Err(anyhow::anyhow!("Lexical errors found in '{}'", filename));
     }
 
     match parser::parse(tokens) {
         Ok(module) => {
-            let mut formatter = CodeFormatter::new(indent_size);
+            let mut formatter = CodeFormatter::new(options.indent.unwrap_or(settings.indent_size));
+            if let Some(width) = options.line_width.or(settings.line_width) {
+                formatter = formatter.with_max_width(width);
+            }
             formatter.visit_module(&module);
             let formatted_source = formatter.get_output().to_string();
 
-            if write {
+            if options.write {
                 fs::write(&filename, &formatted_source)
                     .with_context(|| format!("Failed to write to file: {}", filename))?;
-                println!("Formatted and wrote changes to '{}'", filename);
-            } else {
-                print!("{}", formatted_source);
+                writeln!(report.stdout, "Formatted and wrote changes to '{}'", filename)?;
+                return Ok(false);
+            }
+
+            if options.diff {
+                let diff = cheetah::diff::unified(&source, &formatted_source, &filename, &filename);
+                report.stdout.push_str(&diff);
+            } else if !options.check {
+                report.stdout.push_str(&formatted_source);
             }
+
+            if options.check && formatted_source != source {
+                writeln!(report.stderr, "Would reformat '{}'", filename)?;
+                return Ok(true);
+            }
+            Ok(false)
         }
         Err(errors) => {
-            eprintln!("Cannot format file with syntax errors:");
+            writeln!(report.stderr, "Cannot format {} with syntax errors:", filename)?;
             for error in errors {
                 let formatter = ParseErrorFormatter::new(&error, Some(&source), true);
-                eprintln!("  {}", formatter);
+                writeln!(report.stderr, "  {}", formatter)?;
             }
+            Err(anyhow::anyhow!("Syntax errors found in '{}'", filename))
         }
     }
+}
 
-    Ok(())
+/// Code generation settings for a build
+#[cfg(feature = "codegen")]
+#[derive(Default)]
+struct CodegenOptions {
+    /// Emit DWARF debug info
+    debug_info: bool,
+    /// Write a source map of the generated LLVM IR
+    source_map: bool,
+    /// Sanitizers to instrument the build with
+    sanitizers: Vec<Sanitizer>,
+    /// Build a WebAssembly module with this runtime
+    wasm: Option<WasmRuntime>,
+    /// Link an executable or a shared library
+    crate_type: CrateType,
+    /// Extra libraries to link against
+    link_libs: Vec<String>,
+    /// Extra library search directories
+    link_search_paths: Vec<String>,
+    /// Line endings the program writes to stdout
+    newline: Newline,
+    /// Write Graphviz CFGs of every function, or only of the one named if not empty
+    dump_cfg: Option<String>,
+    /// Levels of the warnings reported before compiling
+    lints: LintConfig,
 }
 
+#[cfg(feature = "codegen")]
 fn compile_file(
     filename: &str,
     output: Option<String>,
     opt_level: u8,
-    output_object: bool,
-    target_triple: Option<String>,
+    emit: EmitKind,
+    target: TargetSpec,
+    codegen: CodegenOptions,
+    format: ErrorFormat,
 ) -> Result<()> {
-    let _ = target_triple;
     let filename = ensure_ch_extension(filename);
-    println!(
-        "{}",
-        format!(
-            "Compiling {} with optimization level {}",
-            filename, opt_level
-        )
-        .bright_green()
-    );
+    let human = format == ErrorFormat::Human;
+    if human {
+        println!(
+            "{}",
+            format!(
+                "Compiling {} with optimization level {}",
+                filename, opt_level
+            )
+            .bright_green()
+        );
+    }
 
     let source = fs::read_to_string(&filename)
         .with_context(|| format!("Failed to read file: {}", filename))?;
 
     match parse(&source) {
         Ok(module) => {
+            let lints = codegen
+                .lints
+                .classify(typechecker::collect_warnings(&module, &source));
+            let mut warnings: Vec<Diagnostic> = Vec::new();
+            if human {
+                report_warnings(&filename, &lints)?;
+            } else {
+                warnings = lints.iter().map(Diagnostic::from).collect();
+                if lints.iter().any(|w| w.severity == Severity::Deny) {
+                    print!("{}", diagnostics::render(format, &filename, &warnings));
+                    std::process::exit(1);
+                }
+            }
+
             let context = context::Context::create();
             let mut compiler = Compiler::new(&context, &filename);
-
-            let llvm_opt = match opt_level {
-                0 => inkwell::OptimizationLevel::None,
-                1 => inkwell::OptimizationLevel::Less,
-                2 => inkwell::OptimizationLevel::Default,
-                _ => inkwell::OptimizationLevel::Aggressive,
+            let target = match codegen.wasm {
+                Some(_) if target != TargetSpec::host() => {
+                    return Err(anyhow::anyhow!(
+                        "--wasm selects its own target; drop --target/--cpu/--features"
+                    ));
+                }
+                Some(runtime) => runtime.target(),
+                None => target,
             };
-            println!(
-                "{}",
-                format!("Using optimization level: {:?}", llvm_opt).bright_green()
-            );
+            if target != TargetSpec::host() {
+                compiler
+                    .set_target(target)
+                    .map_err(|e| anyhow::anyhow!("Invalid target: {}", e))?;
+            }
+            if codegen.debug_info {
+                compiler.enable_debug_info(&filename);
+            }
+            compiler.sanitizers = codegen.sanitizers;
+            compiler.crate_type = codegen.crate_type;
+            compiler.link_libs = codegen.link_libs;
+            compiler.link_search_paths = codegen.link_search_paths;
+            compiler.newline = codegen.newline;
+            compiler.set_opt_level(opt_level);
+
+            let llvm_opt = codegen_level(compiler.opt_level);
+            if human {
+                println!(
+                    "{}",
+                    format!("Using optimization level: {:?}", llvm_opt).bright_green()
+                );
+            }
 
             match compiler.compile_module(&module) {
                 Ok(_) => {
+                    if let Some(only) = &codegen.dump_cfg {
+                        dump_cfgs(&compiler, &filename, only, compiler.opt_level)?;
+                    }
+                    apply_optimization_passes(&compiler)?;
+
                     let output_path = match output {
                         Some(path) => PathBuf::from(path),
                         None => {
                             let mut p = PathBuf::from(&filename);
-                            p.set_extension(if output_object { "o" } else { "ll" });
+                            p.set_extension(emit.extension());
                             p
                         }
                     };
 
-                    if output_object {
-                        let exe_name = output_path
-                            .file_stem()
-                            .and_then(|s| s.to_str())
-                            .ok_or_else(|| anyhow::anyhow!("Invalid output filename"))?;
-
+                    if let Some(runtime) = codegen.wasm {
+                        let stem = output_path.with_extension("");
                         compiler
-                            .emit_to_aot(exe_name)
-                            .map_err(|e| anyhow::anyhow!("AOT compilation failed: {}", e))?;
+                            .emit_wasm(&stem.to_string_lossy(), runtime)
+                            .map_err(|e| anyhow::anyhow!("WebAssembly compilation failed: {}", e))?;
                     } else {
                         compiler
-                            .write_to_file(&output_path)
-                            .map_err(|e| anyhow::anyhow!("Failed to write IR to file: {}", e))?;
-                        println!("✅ Wrote LLVM IR to {}", output_path.display());
+                            .emit(emit, &output_path)
+                            .map_err(|e| anyhow::anyhow!("Failed to emit {}: {}", emit.name(), e))?;
+                        if emit != EmitKind::Exe && human {
+                            println!("✅ Wrote {} to {}", emit.name(), output_path.display());
+                        }
+                    }
+
+                    if codegen.source_map {
+                        let ir_path = output_path.with_extension(EmitKind::LlvmIr.extension());
+                        let map_path = compiler
+                            .emit_source_map(&ir_path)
+                            .map_err(|e| anyhow::anyhow!(e))?;
+                        if human {
+                            println!("✅ Wrote source map to {}", map_path.display());
+                        }
                     }
 
+                    if !human {
+                        print!("{}", diagnostics::render(format, &filename, &warnings));
+                    }
                     Ok(())
                 }
-                Err(e) => Err(anyhow::anyhow!("Compilation failed: {}", e)),
+                Err(e) if human => Err(anyhow::anyhow!("Compilation failed: {}", e)),
+                Err(e) => {
+                    // The type checker locates the error the compiler only describes
+                    let diagnostic = cheetah::transform::apply(&module)
+                        .ok()
+                        .and_then(|module| diagnostics::type_check(&module))
+                        .unwrap_or_else(|| Diagnostic::error("compile-error", &e));
+                    warnings.push(diagnostic);
+                    print!("{}", diagnostics::render(format, &filename, &warnings));
+                    std::process::exit(1);
+                }
             }
         }
+        Err(errors) if !human => {
+            let diagnostics: Vec<Diagnostic> = errors.iter().map(Diagnostic::from).collect();
+            print!("{}", diagnostics::render(format, &filename, &diagnostics));
+            std::process::exit(1);
+        }
         Err(errors) => {
             for error in &errors {
                 let formatter = ParseErrorFormatter::new(error, Some(&source), true);
@@ -1082,6 +2568,58 @@ fn compile_file(
     }
 }
 
+/// Write `<file>.<function>.{before,after}.dot` CFG dumps, failing if a function does not verify
+#[cfg(feature = "codegen")]
+fn dump_cfgs(compiler: &Compiler<'_>, filename: &str, only: &str, opt_level: u8) -> Result<()> {
+    use cheetah::compiler::cfg_dump::{unverified_functions, CfgGraph};
+
+    let only = if only.is_empty() { None } else { Some(only) };
+    let source = PathBuf::from(filename);
+    let stem = source
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Invalid filename"))?;
+
+    let write_graphs = |graphs: &[CfgGraph], stage: &str| -> Result<()> {
+        for graph in graphs {
+            let safe_name: String = graph
+                .function
+                .chars()
+                .map(|c| if c.is_alphanumeric() || c == '_' { c } else { '_' })
+                .collect();
+            let path = source.with_file_name(format!("{}.{}.{}.dot", stem, safe_name, stage));
+            fs::write(&path, graph.to_dot())
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!("✅ Wrote {} CFG of {} to {}", stage, graph.function, path.display());
+        }
+        Ok(())
+    };
+
+    let before = compiler.cfg_graphs(only).map_err(|e| anyhow::anyhow!(e))?;
+    write_graphs(&before, "before")?;
+
+    let mut problems = Vec::new();
+    for graph in &before {
+        for block in graph.unterminated_blocks() {
+            problems.push(format!("{}: block '{}' has no terminator", graph.function, block));
+        }
+    }
+    for function in unverified_functions(compiler.get_module(), only) {
+        problems.push(format!("{}: failed LLVM verification", function));
+    }
+    if !problems.is_empty() {
+        for problem in &problems {
+            eprintln!("{}", problem.bright_red());
+        }
+        return Err(anyhow::anyhow!("CFG verification failed"));
+    }
+
+    let after = compiler
+        .optimized_cfg_graphs(only, opt_level)
+        .map_err(|e| anyhow::anyhow!(e))?;
+    write_graphs(&after, "after")
+}
+
 /// Format the token output based on token type
 fn format_token(token: &Token, use_color: bool) -> String {
     if !use_color {
@@ -1151,310 +2689,16 @@ fn format_token_for_repl(token: &Token, use_color: bool) -> String {
     format!("{} at {}:{}", token_desc, token.line, token.column)
 }
 
-/// Apply optimization passes to the LLVM module to improve performance
-fn apply_optimization_passes(module: &inkwell::module::Module<'_>) {
+/// Run the optimization pipeline for the compiler's level over the compiled module
+#[cfg(feature = "codegen")]
+fn apply_optimization_passes(compiler: &Compiler<'_>) -> Result<()> {
+    compiler
+        .optimize_module()
+        .map_err(|e| anyhow::anyhow!("Optimization failed: {}", e))?;
     println!(
         "{}",
-        "Using aggressive optimization level for improved performance".bright_green()
+        format!("Applied {} optimization pipeline", pass_pipeline(compiler.opt_level))
+            .bright_green()
     );
-    println!("{}", "Stack overflow prevention enabled".bright_green());
-
-    // Create a pass manager for the module
-    let pass_manager = inkwell::passes::PassManager::create(());
-
-    // Run the pass manager on the module
-    pass_manager.run_on(module);
-
-    // Note: The optimization level is set when creating the execution engine or target machine
-    // We're using OptimizationLevel::Aggressive (O3) in the relevant places in the code
-    // This automatically enables the following passes:
-    // - LoopUnrollPass
-    // - LoopVectorizePass
-    // - SLPVectorizePass
-    // - LICMPass (Loop-Invariant Code Motion)
-
-    println!("{}", "Applied optimization passes including: LoopUnroll, LoopVectorize, SLPVectorize, LICM".bright_green());
-}
-
-fn register_runtime_functions(
-    engine: &inkwell::execution_engine::ExecutionEngine<'_>,
-    module: &inkwell::module::Module<'_>,
-) -> Result<(), String> {
-    if let Err(e) = cheetah::compiler::runtime::list::register_list_runtime_functions(
-        engine, module,
-    ) {
-        println!(
-            "{}",
-            format!("Warning: Failed to register list runtime functions: {}", e).bright_yellow()
-        );
-    }
-
-    if let Some(function) = module.get_function("int_to_string") {
-        {
-            engine.add_global_mapping(&function, jit_int_to_string as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("float_to_string") {
-        {
-            engine.add_global_mapping(&function, jit_float_to_string as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("bool_to_string") {
-        {
-            engine.add_global_mapping(&function, jit_bool_to_string as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("range_1") {
-        {
-            engine.add_global_mapping(&function, range::range_1 as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("range_2") {
-        {
-            engine.add_global_mapping(&function, range::range_2 as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("range_3") {
-        {
-            engine.add_global_mapping(&function, range::range_3 as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("range_cleanup") {
-        {
-            engine.add_global_mapping(&function, range::range_cleanup as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("string_to_int") {
-        {
-            engine.add_global_mapping(&function, jit_string_to_int as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("string_to_float") {
-        {
-            engine.add_global_mapping(&function, jit_string_to_float as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("string_to_bool") {
-        {
-            engine.add_global_mapping(&function, jit_string_to_bool as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("char_to_string") {
-        {
-            engine.add_global_mapping(&function, jit_char_to_string as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("free_string") {
-        {
-            engine.add_global_mapping(&function, jit_free_string as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("str_int") {
-        {
-            engine.add_global_mapping(&function, jit_str_int as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("str_float") {
-        {
-            engine.add_global_mapping(&function, jit_str_float as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("str_bool") {
-        {
-            engine.add_global_mapping(&function, jit_str_bool as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("print_string") {
-        {
-            engine.add_global_mapping(&function, print_string as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("println_string") {
-        {
-            engine.add_global_mapping(&function, println_string as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("print_int") {
-        {
-            engine.add_global_mapping(&function, print_int as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("print_float") {
-        {
-            engine.add_global_mapping(&function, print_float as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("print_bool") {
-        {
-            engine.add_global_mapping(&function, print_bool as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("string_concat") {
-        {
-            engine.add_global_mapping(&function, jit_string_concat as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("string_equals") {
-        {
-            engine.add_global_mapping(&function, jit_string_equals as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("string_length") {
-        {
-            engine.add_global_mapping(&function, jit_string_length as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("min_int") {
-        {
-            engine.add_global_mapping(&function, min_max_ops::min_int as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("min_float") {
-        {
-            engine.add_global_mapping(&function, min_max_ops::min_float as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("max_int") {
-        {
-            engine.add_global_mapping(&function, min_max_ops::max_int as usize);
-        }
-    }
-
-    if let Some(function) = module.get_function("max_float") {
-        {
-            engine.add_global_mapping(&function, min_max_ops::max_float as usize);
-        }
-    }
-
     Ok(())
 }
-
-// Runtime function implementations - optimized for performance
-extern "C" fn jit_int_to_string(value: i64) -> *mut c_char {
-    let s = if value >= -9999 && value <= 9999 {
-        let mut buffer = [0u8; 16];
-        let s = value.to_string();
-        let bytes = s.as_bytes();
-        buffer[..bytes.len()].copy_from_slice(bytes);
-        buffer[bytes.len()] = 0;
-        unsafe { CString::from_raw(buffer.as_ptr() as *mut c_char) }
-    } else {
-        CString::new(value.to_string()).unwrap()
-    };
-    s.into_raw()
-}
-
-extern "C" fn jit_float_to_string(value: f64) -> *mut c_char {
-    let s = format!("{}", value);
-    let c_str = CString::new(s).unwrap();
-    c_str.into_raw()
-}
-
-extern "C" fn jit_bool_to_string(value: i64) -> *mut c_char {
-    let s = if value != 0 { "True" } else { "False" }.to_string();
-    let c_str = CString::new(s).unwrap();
-    c_str.into_raw()
-}
-
-extern "C" fn jit_char_to_string(value: i64) -> *mut c_char {
-    let c = std::char::from_u32(value as u32).unwrap_or('\0');
-
-    let s = c.to_string();
-
-    let c_str = CString::new(s).unwrap();
-    c_str.into_raw()
-}
-
-extern "C" fn jit_string_to_int(value: *const c_char) -> i64 {
-    let c_str = unsafe { CStr::from_ptr(value) };
-    let s = c_str.to_str().unwrap_or("");
-    s.parse::<i64>().unwrap_or(0)
-}
-
-extern "C" fn jit_string_to_float(value: *const c_char) -> f64 {
-    let c_str = unsafe { CStr::from_ptr(value) };
-    let s = c_str.to_str().unwrap_or("");
-    s.parse::<f64>().unwrap_or(0.0)
-}
-
-extern "C" fn jit_string_to_bool(value: *const c_char) -> bool {
-    let c_str = unsafe { CStr::from_ptr(value) };
-    let s = c_str.to_str().unwrap_or("");
-    match s.to_lowercase().as_str() {
-        "true" | "1" => true,
-        _ => false,
-    }
-}
-
-extern "C" fn jit_free_string(ptr: *mut c_char) {
-    if !ptr.is_null() {
-        unsafe {
-            let _ = CString::from_raw(ptr);
-        }
-    }
-}
-
-extern "C" fn jit_str_int(value: i64) -> *mut c_char {
-    jit_int_to_string(value)
-}
-
-extern "C" fn jit_str_float(value: f64) -> *mut c_char {
-    jit_float_to_string(value)
-}
-
-extern "C" fn jit_str_bool(value: bool) -> *mut c_char {
-    jit_bool_to_string(if value { 1 } else { 0 })
-}
-
-extern "C" fn jit_string_concat(left: *const c_char, right: *const c_char) -> *mut c_char {
-    let left_cstr = unsafe { CStr::from_ptr(left) };
-    let right_cstr = unsafe { CStr::from_ptr(right) };
-
-    let left_str = left_cstr.to_str().unwrap_or("");
-    let right_str = right_cstr.to_str().unwrap_or("");
-
-    let result = format!("{}{}", left_str, right_str);
-    let c_str = CString::new(result).unwrap();
-    c_str.into_raw()
-}
-
-extern "C" fn jit_string_equals(left: *const c_char, right: *const c_char) -> bool {
-    let left_cstr = unsafe { CStr::from_ptr(left) };
-    let right_cstr = unsafe { CStr::from_ptr(right) };
-
-    let left_str = left_cstr.to_str().unwrap_or("");
-    let right_str = right_cstr.to_str().unwrap_or("");
-
-    left_str == right_str
-}
-
-extern "C" fn jit_string_length(string: *const c_char) -> i64 {
-    let cstr = unsafe { CStr::from_ptr(string) };
-    let s = cstr.to_str().unwrap_or("");
-    s.len() as i64
-}
@@ -1,3 +1,4 @@
+use crate::error_codes;
 use crate::lexer::TokenType;
 use colored::Colorize;
 use std::fmt;
@@ -37,6 +38,13 @@ impl<'a> ParseErrorFormatter<'a> {
             }
         }
 
+        if let Some(id) = error_codes::id_for(self.error.code()) {
+            result.push_str(&format!(
+                "  = help: run `cheetah explain {}` for more about this error\n",
+                id
+            ));
+        }
+
         result
     }
 
@@ -103,7 +111,7 @@ pub enum ParseError {
     /// An unexpected token was encountered
     UnexpectedToken {
         expected: String,
-        found: TokenType,
+        found: TokenType<'static>,
         line: usize,
         column: usize,
         suggestion: Option<String>,
@@ -146,10 +154,10 @@ impl ParseError {
     }
 
     /// Create a new unexpected token error
-    pub fn unexpected_token(expected: &str, found: TokenType, line: usize, column: usize) -> Self {
+    pub fn unexpected_token(expected: &str, found: TokenType<'_>, line: usize, column: usize) -> Self {
         ParseError::UnexpectedToken {
             expected: expected.to_string(),
-            found,
+            found: found.into_owned(),
             line,
             column,
             suggestion: None,
@@ -159,14 +167,14 @@ impl ParseError {
     /// Create a new unexpected token error with suggestion
     pub fn unexpected_token_with_suggestion(
         expected: &str,
-        found: TokenType,
+        found: TokenType<'_>,
         line: usize,
         column: usize,
         suggestion: &str,
     ) -> Self {
         ParseError::UnexpectedToken {
             expected: expected.to_string(),
-            found,
+            found: found.into_owned(),
             line,
             column,
             suggestion: Some(suggestion.to_string()),
@@ -223,54 +231,50 @@ impl ParseError {
         }
     }
 
-    /// Get a user-friendly error message
-    pub fn get_message(&self) -> String {
+    /// Stable diagnostic code for the kind of error
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseError::UnexpectedToken { .. } => "unexpected-token",
+            ParseError::InvalidSyntax { .. } => "invalid-syntax",
+            ParseError::EOF { .. } => "unexpected-eof",
+        }
+    }
+
+    /// The error without its position or suggestion
+    pub fn message(&self) -> String {
         match self {
             ParseError::UnexpectedToken {
-                expected,
-                found,
-                line,
-                column,
-                suggestion,
-            } => {
-                let mut msg = format!(
-                    "Line {}, column {}: Expected {}, but found {:?}",
-                    line, column, expected, found
-                );
-                if let Some(sug) = suggestion {
-                    msg.push_str(&format!(". Suggestion: {}", sug));
-                }
-                msg
-            }
-            ParseError::InvalidSyntax {
-                message,
-                line,
-                column,
-                suggestion,
-            } => {
-                let mut msg = format!("Line {}, column {}: {}", line, column, message);
-                if let Some(sug) = suggestion {
-                    msg.push_str(&format!(". Suggestion: {}", sug));
-                }
-                msg
-            }
-            ParseError::EOF {
-                expected,
-                line,
-                column,
-                suggestion,
-            } => {
-                let mut msg = format!(
-                    "Line {}, column {}: Unexpected end of file, expected {}",
-                    line, column, expected
-                );
-                if let Some(sug) = suggestion {
-                    msg.push_str(&format!(". Suggestion: {}", sug));
-                }
-                msg
+                expected, found, ..
+            } => format!("Expected {}, but found {:?}", expected, found),
+            ParseError::InvalidSyntax { message, .. } => message.clone(),
+            ParseError::EOF { expected, .. } => {
+                format!("Unexpected end of file, expected {}", expected)
             }
         }
     }
+
+    /// How the error might be fixed
+    pub fn suggestion(&self) -> Option<&str> {
+        match self {
+            ParseError::UnexpectedToken { suggestion, .. }
+            | ParseError::InvalidSyntax { suggestion, .. }
+            | ParseError::EOF { suggestion, .. } => suggestion.as_deref(),
+        }
+    }
+
+    /// Get a user-friendly error message
+    pub fn get_message(&self) -> String {
+        let mut msg = format!(
+            "Line {}, column {}: {}",
+            self.line(),
+            self.column(),
+            self.message()
+        );
+        if let Some(sug) = self.suggestion() {
+            msg.push_str(&format!(". Suggestion: {}", sug));
+        }
+        msg
+    }
 }
 
 impl fmt::Display for ParseError {
@@ -297,7 +301,7 @@ impl ParseErrorBuilder {
     }
     #[allow(dead_code)]
     /// Build an unexpected token error
-    pub fn unexpected_token(&self, expected: &str, found: TokenType) -> ParseError {
+    pub fn unexpected_token(&self, expected: &str, found: TokenType<'_>) -> ParseError {
         ParseError::unexpected_token(expected, found, self.line, self.column)
     }
     #[allow(dead_code)]
@@ -305,7 +309,7 @@ impl ParseErrorBuilder {
     pub fn unexpected_token_with_suggestion(
         &self,
         expected: &str,
-        found: TokenType,
+        found: TokenType<'_>,
         suggestion: &str,
     ) -> ParseError {
         ParseError::unexpected_token_with_suggestion(
@@ -1,9 +1,9 @@
-use crate::ast::{
-    BoolOperator, CmpOperator, Comprehension, Expr, ExprContext, NameConstant, Number, Operator,
-    UnaryOperator,
-};
-use crate::lexer::TokenType;
+use crate::ast::{CmpOperator, Comprehension, Expr, ExprContext, NameConstant, Number, UnaryOperator};
+use crate::lexer::{Token, TokenType};
 use crate::parser::helpers::TokenMatching;
+use crate::parser::precedence::{
+    self, Associativity, OperatorKind, OperatorSpec, Precedence, PrecedenceLevel,
+};
 use crate::parser::stmt::StmtParser;
 use crate::parser::types::{GetLocation, ParserContext};
 use crate::parser::{ParseError, Parser};
@@ -13,17 +13,29 @@ pub trait ExprParser {
     /// Parse an expression
     fn parse_expression(&mut self) -> Result<Expr, ParseError>;
 
-    /// Parse an 'or' test
+    /// Parse an expression or an unparenthesized tuple of them, possibly
+    /// starred, without counting it towards the nesting limit
+    fn parse_star_expressions(&mut self) -> Result<Expr, ParseError>;
+
+    /// Parse an expression without a top-level tuple, possibly starred
     fn parse_or_test(&mut self) -> Result<Expr, ParseError>;
 
-    /// Parse an 'and' test
-    fn parse_and_test(&mut self) -> Result<Expr, ParseError>;
+    /// Parse the operators binding at least as tightly as `min`, and their
+    /// operands, by their rows of the precedence table
+    fn parse_operators(&mut self, min: Precedence) -> Result<Expr, ParseError>;
 
-    /// Parse a 'not' test
-    fn parse_not_test(&mut self) -> Result<Expr, ParseError>;
+    /// Parse an operand: a prefix operator binding at least as tightly as
+    /// `min` applied to its operand, or an await or atom expression
+    fn parse_prefix(&mut self, min: Precedence) -> Result<Expr, ParseError>;
 
-    /// Parse a comparison
-    fn parse_comparison(&mut self) -> Result<Expr, ParseError>;
+    /// Parse the infix `operator` of `level` that follows `left`, and its
+    /// right operand
+    fn parse_infix(
+        &mut self,
+        left: Expr,
+        level: &PrecedenceLevel,
+        operator: &OperatorSpec,
+    ) -> Result<Expr, ParseError>;
 
     /// Check if the current token is a comparison operator
     fn is_comparison_operator(&self) -> bool;
@@ -31,32 +43,9 @@ pub trait ExprParser {
     /// Parse a comparison operator
     fn parse_comparison_operator(&mut self) -> Result<CmpOperator, ParseError>;
 
-    /// Parse an expression (e-context)
-    fn parse_expr(&mut self) -> Result<Expr, ParseError>;
-
-    /// Parse a bitwise OR expression
-    fn parse_bitwise_or(&mut self) -> Result<Expr, ParseError>;
-
-    /// Parse a bitwise XOR expression
-    fn parse_bitwise_xor(&mut self) -> Result<Expr, ParseError>;
-
-    /// Parse a bitwise AND expression
-    fn parse_bitwise_and(&mut self) -> Result<Expr, ParseError>;
-
-    /// Parse a shift expression
-    fn parse_shift(&mut self) -> Result<Expr, ParseError>;
-
-    /// Parse an arithmetic expression
-    fn parse_arithmetic(&mut self) -> Result<Expr, ParseError>;
-
-    /// Parse a term
-    fn parse_term(&mut self) -> Result<Expr, ParseError>;
-
-    /// Parse a factor
-    fn parse_factor(&mut self) -> Result<Expr, ParseError>;
-
-    /// Parse a power expression
-    fn parse_power(&mut self) -> Result<Expr, ParseError>;
+    /// Report an arithmetic operator `token` of `precedence` that another
+    /// operator or the end of the line follows instead of its right operand
+    fn check_operand_follows(&self, token: &Token, precedence: Precedence) -> Result<(), ParseError>;
 
     /// Parse an await expression
     fn parse_await_expr(&mut self) -> Result<Expr, ParseError>;
@@ -93,8 +82,12 @@ pub trait ExprParser {
     fn parse_comprehension_target(&mut self) -> Result<Box<Expr>, ParseError>;
 }
 
-impl ExprParser for Parser {
+impl ExprParser for Parser<'_> {
     fn parse_expression(&mut self) -> Result<Expr, ParseError> {
+        self.nested(|parser| parser.parse_star_expressions())
+    }
+
+    fn parse_star_expressions(&mut self) -> Result<Expr, ParseError> {
         if self.check(TokenType::Multiply) {
             let star_token = self.current.clone().unwrap();
             self.advance();
@@ -106,6 +99,7 @@ impl ExprParser for Parser {
                 ctx: ExprContext::Load,
                 line: star_token.line,
                 column: star_token.column,
+                span: self.span_from(star_token.line, star_token.column),
             };
 
             if self.match_token(TokenType::Comma) {
@@ -140,6 +134,7 @@ impl ExprParser for Parser {
                     ctx: ExprContext::Load,
                     line,
                     column,
+                    span: self.span_from(line, column),
                 });
             }
 
@@ -148,29 +143,7 @@ impl ExprParser for Parser {
 
         let mut expr = self.parse_or_test()?;
 
-        if self.check(TokenType::If)
-            && !self.is_in_context(ParserContext::Comprehension)
-            && !self.is_in_context(ParserContext::Match)
-        {
-            let line = expr.get_line();
-            let column = expr.get_column();
-
-            self.advance();
-
-            let test = Box::new(self.parse_or_test()?);
-
-            self.consume(TokenType::Else, "else")?;
-
-            let orelse = Box::new(self.parse_expression()?);
-
-            expr = Expr::IfExp {
-                test,
-                body: Box::new(expr),
-                orelse,
-                line,
-                column,
-            };
-        } else if self.match_token(TokenType::Comma) {
+        if self.match_token(TokenType::Comma) {
             let line = expr.get_line();
             let column = expr.get_column();
 
@@ -202,6 +175,7 @@ impl ExprParser for Parser {
                 ctx: ExprContext::Load,
                 line,
                 column,
+                span: self.span_from(line, column),
             };
         }
 
@@ -219,6 +193,7 @@ impl ExprParser for Parser {
                 ctx: ExprContext::Store,
                 line: id_line,
                 column: id_column,
+                span: self.span_from(id_line, id_column),
             };
 
             self.advance();
@@ -244,6 +219,7 @@ impl ExprParser for Parser {
                         ctx: ExprContext::Store,
                         line: next_line,
                         column: next_column,
+                        span: self.span_from(next_line, next_column),
                     }));
                 } else if self.check(TokenType::LeftParen) {
                     let nested_expr = self.parse_atom_expr()?;
@@ -267,6 +243,7 @@ impl ExprParser for Parser {
                 ctx: ExprContext::Store,
                 line: id_line,
                 column: id_column,
+                span: self.span_from(id_line, id_column),
             }))
         } else {
             let expr = self.parse_atom_expr()?;
@@ -286,29 +263,77 @@ impl ExprParser for Parser {
                 ctx: ExprContext::Load,
                 line: star_token.line,
                 column: star_token.column,
+                span: self.span_from(star_token.line, star_token.column),
             });
         }
 
-        let mut expr = self.parse_and_test()?;
+        self.parse_operators(Precedence::Walrus)
+    }
 
-        if self.check(TokenType::Walrus) {
-            let line = expr.get_line();
-            let column = expr.get_column();
+    fn parse_operators(&mut self, min: Precedence) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_prefix(min)?;
 
-            match &expr {
-                Expr::Name { .. } => {
-                    self.advance();
+        while let Some((level, operator)) = self
+            .current
+            .as_ref()
+            .and_then(|token| precedence::infix(&token.token_type))
+        {
+            if level.precedence < min {
+                break;
+            }
+            // Comprehension conditions and match guards end at `if`
+            if operator.kind == OperatorKind::Conditional
+                && (self.is_in_context(ParserContext::Comprehension)
+                    || self.is_in_context(ParserContext::Match))
+            {
+                break;
+            }
+            expr = self.parse_infix(expr, level, operator)?;
+        }
 
-                    let value = Box::new(self.parse_or_test()?);
+        Ok(expr)
+    }
 
-                    expr = Expr::NamedExpr {
-                        target: Box::new(expr),
-                        value,
-                        line,
-                        column,
-                    };
-                }
-                _ => {
+    fn parse_prefix(&mut self, min: Precedence) -> Result<Expr, ParseError> {
+        let Some((level, operator)) = self
+            .current
+            .as_ref()
+            .and_then(|token| precedence::prefix(&token.token_type))
+            .filter(|(level, _)| level.precedence >= min)
+        else {
+            return self.parse_await_expr();
+        };
+        let op = match &operator.kind {
+            OperatorKind::Not => UnaryOperator::Not,
+            OperatorKind::Unary(op) => op.clone(),
+            _ => return self.parse_await_expr(),
+        };
+
+        self.advance();
+        let token = self.previous_token();
+        let operand = Box::new(self.nested(|parser| parser.parse_operators(level.right))?);
+
+        Ok(Expr::UnaryOp {
+            op,
+            operand,
+            line: token.line,
+            column: token.column,
+            span: self.span_from(token.line, token.column),
+        })
+    }
+
+    fn parse_infix(
+        &mut self,
+        left: Expr,
+        level: &PrecedenceLevel,
+        operator: &OperatorSpec,
+    ) -> Result<Expr, ParseError> {
+        let line = left.get_line();
+        let column = left.get_column();
+
+        match &operator.kind {
+            OperatorKind::Named => {
+                if !matches!(left, Expr::Name { .. }) {
                     return Err(ParseError::InvalidSyntax {
                         message: "Invalid target for walrus operator".to_string(),
                         line,
@@ -316,98 +341,93 @@ impl ExprParser for Parser {
                         suggestion: None,
                     });
                 }
-            }
-        }
-
-        if self.check(TokenType::Or) {
-            let line = expr.get_line();
-            let column = expr.get_column();
+                self.advance();
 
-            let mut values = vec![Box::new(expr)];
+                let value = Box::new(self.parse_operators(level.right)?);
 
-            while self.match_token(TokenType::Or) {
-                values.push(Box::new(self.parse_and_test()?));
+                Ok(Expr::NamedExpr {
+                    target: Box::new(left),
+                    value,
+                    line,
+                    column,
+                    span: self.span_from(line, column),
+                })
             }
+            OperatorKind::Conditional => {
+                self.advance();
 
-            expr = Expr::BoolOp {
-                op: BoolOperator::Or,
-                values,
-                line,
-                column,
-            };
-        }
-
-        Ok(expr)
-    }
-
-    fn parse_and_test(&mut self) -> Result<Expr, ParseError> {
-        let mut expr = self.parse_not_test()?;
+                let test = Box::new(self.parse_operators(Precedence::Or)?);
 
-        if self.check(TokenType::And) {
-            let line = expr.get_line();
-            let column = expr.get_column();
+                self.consume(TokenType::Else, "else")?;
 
-            let mut values = vec![Box::new(expr)];
+                let orelse = Box::new(self.nested(|parser| parser.parse_operators(level.right))?);
 
-            while self.match_token(TokenType::And) {
-                values.push(Box::new(self.parse_not_test()?));
+                Ok(Expr::IfExp {
+                    test,
+                    body: Box::new(left),
+                    orelse,
+                    line,
+                    column,
+                    span: self.span_from(line, column),
+                })
             }
+            OperatorKind::Bool(op) => {
+                let mut values = vec![Box::new(left)];
 
-            expr = Expr::BoolOp {
-                op: BoolOperator::And,
-                values,
-                line,
-                column,
-            };
-        }
-
-        Ok(expr)
-    }
-
-    fn parse_not_test(&mut self) -> Result<Expr, ParseError> {
-        if self.match_token(TokenType::Not) {
-            let token = self.previous_token();
-            let operand = Box::new(self.parse_not_test()?);
-
-            Ok(Expr::UnaryOp {
-                op: UnaryOperator::Not,
-                operand,
-                line: token.line,
-                column: token.column,
-            })
-        } else {
-            self.parse_comparison()
-        }
-    }
-
-    fn parse_comparison(&mut self) -> Result<Expr, ParseError> {
-        let mut expr = self.parse_expr()?;
+                while self.match_token(operator.token.clone()) {
+                    values.push(Box::new(self.parse_operators(level.right)?));
+                }
 
-        let mut ops = Vec::new();
-        let mut comparators = Vec::new();
+                Ok(Expr::BoolOp {
+                    op: op.clone(),
+                    values,
+                    line,
+                    column,
+                    span: self.span_from(line, column),
+                })
+            }
+            OperatorKind::Compare => {
+                let mut ops = Vec::new();
+                let mut comparators = Vec::new();
 
-        while self.is_comparison_operator() {
-            let op = self.parse_comparison_operator()?;
-            let right = self.parse_expr()?;
+                while self.is_comparison_operator() {
+                    ops.push(self.parse_comparison_operator()?);
+                    comparators.push(Box::new(self.parse_operators(level.right)?));
+                }
 
-            ops.push(op);
-            comparators.push(Box::new(right));
-        }
+                Ok(Expr::Compare {
+                    left: Box::new(left),
+                    ops,
+                    comparators,
+                    line,
+                    column,
+                    span: self.span_from(line, column),
+                })
+            }
+            OperatorKind::Binary(op) => {
+                self.advance();
+                let token = self.previous_token();
+                self.check_operand_follows(&token, level.precedence)?;
 
-        if !ops.is_empty() {
-            let line = expr.get_line();
-            let column = expr.get_column();
+                let right = if level.associativity == Associativity::Right {
+                    self.nested(|parser| parser.parse_operators(level.right))?
+                } else {
+                    self.parse_operators(level.right)?
+                };
 
-            expr = Expr::Compare {
-                left: Box::new(expr),
-                ops,
-                comparators,
-                line,
-                column,
-            };
+                Ok(Expr::BinOp {
+                    left: Box::new(left),
+                    op: op.clone(),
+                    right: Box::new(right),
+                    line: token.line,
+                    column: token.column,
+                    span: self.span_from(token.line, token.column),
+                })
+            }
+            OperatorKind::Not | OperatorKind::Unary(_) | OperatorKind::Atom => {
+                unreachable!("only infix operators follow an operand")
+            }
         }
-
-        Ok(expr)
     }
 
     /// Check if the current token is a comparison operator
@@ -497,249 +517,49 @@ impl ExprParser for Parser {
         }
     }
 
-    fn parse_expr(&mut self) -> Result<Expr, ParseError> {
-        self.parse_bitwise_or()
-    }
-
-    fn parse_bitwise_or(&mut self) -> Result<Expr, ParseError> {
-        let mut expr = self.parse_bitwise_xor()?;
-
-        while self.match_token(TokenType::BitwiseOr) {
-            let token = self.previous_token();
-            let right = self.parse_bitwise_xor()?;
-
-            expr = Expr::BinOp {
-                left: Box::new(expr),
-                op: Operator::BitOr,
-                right: Box::new(right),
-                line: token.line,
-                column: token.column,
-            };
-        }
-
-        Ok(expr)
-    }
-
-    fn parse_bitwise_xor(&mut self) -> Result<Expr, ParseError> {
-        let mut expr = self.parse_bitwise_and()?;
-
-        while self.match_token(TokenType::BitwiseXor) {
-            let token = self.previous_token();
-            let right = self.parse_bitwise_and()?;
-
-            expr = Expr::BinOp {
-                left: Box::new(expr),
-                op: Operator::BitXor,
-                right: Box::new(right),
-                line: token.line,
-                column: token.column,
-            };
-        }
-
-        Ok(expr)
-    }
-
-    fn parse_bitwise_and(&mut self) -> Result<Expr, ParseError> {
-        let mut expr = self.parse_shift()?;
-
-        while self.match_token(TokenType::BitwiseAnd) {
-            let token = self.previous_token();
-            let right = self.parse_shift()?;
-
-            expr = Expr::BinOp {
-                left: Box::new(expr),
-                op: Operator::BitAnd,
-                right: Box::new(right),
-                line: token.line,
-                column: token.column,
-            };
-        }
-
-        Ok(expr)
-    }
-
-    fn parse_shift(&mut self) -> Result<Expr, ParseError> {
-        let mut expr = self.parse_arithmetic()?;
-
-        while self.match_token(TokenType::ShiftLeft) || self.match_token(TokenType::ShiftRight) {
-            let token = self.previous_token();
-            let op = match token.token_type {
-                TokenType::ShiftLeft => Operator::LShift,
-                TokenType::ShiftRight => Operator::RShift,
-                _ => unreachable!(),
-            };
-
-            let right = self.parse_arithmetic()?;
-
-            expr = Expr::BinOp {
-                left: Box::new(expr),
-                op,
-                right: Box::new(right),
-                line: token.line,
-                column: token.column,
-            };
-        }
-
-        Ok(expr)
-    }
-
-    fn parse_arithmetic(&mut self) -> Result<Expr, ParseError> {
-        let mut expr = self.parse_term()?;
-
-        while self.match_token(TokenType::Plus) || self.match_token(TokenType::Minus) {
-            let token = self.previous_token();
-
-            if (token.token_type == TokenType::Plus && self.check(TokenType::Plus))
-                || (token.token_type == TokenType::Minus && self.check(TokenType::Minus))
-                || self.check(TokenType::Multiply)
-                || self.check(TokenType::Divide)
-                || self.check(TokenType::FloorDivide)
-                || self.check(TokenType::Modulo)
-                || self.check(TokenType::At)
+    fn check_operand_follows(&self, token: &Token, precedence: Precedence) -> Result<(), ParseError> {
+        match precedence {
+            Precedence::Arithmetic
+                if (token.token_type == TokenType::Plus && self.check(TokenType::Plus))
+                    || (token.token_type == TokenType::Minus && self.check(TokenType::Minus))
+                    || self.check(TokenType::Multiply)
+                    || self.check(TokenType::Divide)
+                    || self.check(TokenType::FloorDivide)
+                    || self.check(TokenType::Modulo)
+                    || self.check(TokenType::At) =>
             {
-                return Err(ParseError::invalid_syntax(
+                Err(ParseError::invalid_syntax(
                     "Invalid syntax: consecutive operators",
                     token.line,
                     token.column,
-                ));
+                ))
             }
-
-            let op = match token.token_type {
-                TokenType::Plus => Operator::Add,
-                TokenType::Minus => Operator::Sub,
-                _ => {
-                    return Err(ParseError::invalid_syntax_with_suggestion(
-                        &format!("Unexpected token in arithmetic: {:?}", token.token_type),
-                        token.line,
-                        token.column,
-                        "Only '+' and '-' operators are valid here",
-                    ));
-                }
-            };
-
-            let right = self.parse_term()?;
-            expr = Expr::BinOp {
-                left: Box::new(expr),
-                op,
-                right: Box::new(right),
-                line: token.line,
-                column: token.column,
-            };
-        }
-
-        Ok(expr)
-    }
-
-    fn parse_term(&mut self) -> Result<Expr, ParseError> {
-        let mut expr = self.parse_factor()?;
-
-        while self.match_token(TokenType::Multiply)
-            || self.match_token(TokenType::Divide)
-            || self.match_token(TokenType::FloorDivide)
-            || self.match_token(TokenType::Modulo)
-            || self.match_token(TokenType::At)
-        {
-            let token = self.previous_token();
-
-            if self.check(TokenType::Multiply)
-                || self.check(TokenType::Divide)
-                || self.check(TokenType::FloorDivide)
-                || self.check(TokenType::Modulo)
-                || self.check(TokenType::Plus)
-                || self.check(TokenType::Minus)
-                || self.check(TokenType::At)
+            Precedence::Term
+                if self.check(TokenType::Multiply)
+                    || self.check(TokenType::Divide)
+                    || self.check(TokenType::FloorDivide)
+                    || self.check(TokenType::Modulo)
+                    || self.check(TokenType::Plus)
+                    || self.check(TokenType::Minus)
+                    || self.check(TokenType::At) =>
             {
-                return Err(ParseError::invalid_syntax_with_suggestion(
+                Err(ParseError::invalid_syntax_with_suggestion(
                     "Invalid syntax: consecutive operators",
                     token.line,
                     token.column + token.lexeme.len(),
                     "Operators cannot be used consecutively. Did you forget an operand between them?"
-                ));
+                ))
             }
-
-            if self.check(TokenType::EOF) || self.check_newline() {
-                return Err(ParseError::invalid_syntax_with_suggestion(
+            Precedence::Term if self.check(TokenType::EOF) || self.check_newline() => {
+                Err(ParseError::invalid_syntax_with_suggestion(
                     "Incomplete expression",
                     token.line,
                     token.column + 1,
                     "This operator is missing its right operand. Add a value after the operator.",
-                ));
+                ))
             }
-
-            let op = match token.token_type {
-                TokenType::Multiply => Operator::Mult,
-                TokenType::Divide => Operator::Div,
-                TokenType::FloorDivide => Operator::FloorDiv,
-                TokenType::Modulo => Operator::Mod,
-                TokenType::At => Operator::MatMult,
-                _ => {
-                    return Err(ParseError::invalid_syntax_with_suggestion(
-                        &format!("Unexpected token in term: {:?}", token.token_type),
-                        token.line,
-                        token.column,
-                        "Only '*', '/', '//', '%', and '@' operators are valid here",
-                    ));
-                }
-            };
-
-            let right = self.parse_factor()?;
-
-            expr = Expr::BinOp {
-                left: Box::new(expr),
-                op,
-                right: Box::new(right),
-                line: token.line,
-                column: token.column,
-            };
+            _ => Ok(()),
         }
-
-        Ok(expr)
-    }
-
-    fn parse_factor(&mut self) -> Result<Expr, ParseError> {
-        if self.match_token(TokenType::Plus)
-            || self.match_token(TokenType::Minus)
-            || self.match_token(TokenType::BitwiseNot)
-        {
-            let token = self.previous_token();
-            let op = match token.token_type {
-                TokenType::Plus => UnaryOperator::UAdd,
-                TokenType::Minus => UnaryOperator::USub,
-                TokenType::BitwiseNot => UnaryOperator::Invert,
-                _ => unreachable!(),
-            };
-
-            let operand = Box::new(self.parse_factor()?);
-
-            Ok(Expr::UnaryOp {
-                op,
-                operand,
-                line: token.line,
-                column: token.column,
-            })
-        } else {
-            self.parse_power()
-        }
-    }
-
-    fn parse_power(&mut self) -> Result<Expr, ParseError> {
-        let mut expr = self.parse_await_expr()?;
-
-        if self.match_token(TokenType::Power) {
-            let token = self.previous_token();
-
-            let right = self.parse_power()?;
-
-            expr = Expr::BinOp {
-                left: Box::new(expr),
-                op: Operator::Pow,
-                right: Box::new(right),
-                line: token.line,
-                column: token.column,
-            };
-        }
-
-        Ok(expr)
     }
 
     fn parse_await_expr(&mut self) -> Result<Expr, ParseError> {
@@ -754,6 +574,7 @@ impl ExprParser for Parser {
                 value,
                 line,
                 column,
+                span: self.span_from(line, column),
             })
         } else {
             self.parse_atom_expr()
@@ -781,6 +602,7 @@ impl ExprParser for Parser {
                     value,
                     line,
                     column,
+                    span: self.span_from(line, column),
                 });
             }
 
@@ -800,6 +622,7 @@ impl ExprParser for Parser {
                 value,
                 line,
                 column,
+                span: self.span_from(line, column),
             })
         } else {
             Err(ParseError::invalid_syntax_with_suggestion(
@@ -828,6 +651,7 @@ impl ExprParser for Parser {
                     ctx: ExprContext::Load,
                     line: star_tok.line,
                     column: star_tok.column,
+                    span: self.span_from(star_tok.line, star_tok.column),
                 }));
                 saw_keyword = true;
 
@@ -842,7 +666,7 @@ impl ExprParser for Parser {
                 // consume IDENTIFIER '=' value
                 let id_token = self.current.clone().unwrap();
                 let id_name = if let TokenType::Identifier(name) = &id_token.token_type {
-                    name.clone()
+                    name.to_string()
                 } else {
                     unreachable!()
                 };
@@ -902,6 +726,7 @@ impl ExprParser for Parser {
                         keywords: Vec::new(),
                         line,
                         column,
+                        span: self.span_from(line, column),
                     };
                     continue;
                 }
@@ -915,6 +740,7 @@ impl ExprParser for Parser {
                         ctx: ExprContext::Load,
                         line: star_token.line,
                         column: star_token.column,
+                        span: self.span_from(star_token.line, star_token.column),
                     })];
                     let mut keywords = Vec::new();
 
@@ -934,6 +760,7 @@ impl ExprParser for Parser {
                         keywords,
                         line,
                         column,
+                        span: self.span_from(line, column),
                     };
                 } else if self.match_token(TokenType::Power) {
                     let _star_token = self.previous_token();
@@ -957,6 +784,7 @@ impl ExprParser for Parser {
                         keywords,
                         line,
                         column,
+                        span: self.span_from(line, column),
                     };
                 } else {
                     let first_arg = self.parse_or_test()?;
@@ -1031,6 +859,7 @@ impl ExprParser for Parser {
                                     generators,
                                     line,
                                     column,
+                                    span: this.span_from(line, column),
                                 })
                             })?;
 
@@ -1042,6 +871,7 @@ impl ExprParser for Parser {
                             keywords: Vec::new(),
                             line,
                             column,
+                            span: self.span_from(line, column),
                         };
                     } else if self.check(TokenType::Assign)
                         && matches!(&first_arg, Expr::Name { .. })
@@ -1069,6 +899,7 @@ impl ExprParser for Parser {
                                 keywords,
                                 line,
                                 column,
+                                span: self.span_from(line, column),
                             };
                         } else {
                             unreachable!("We already checked this is a Name expression");
@@ -1093,6 +924,7 @@ impl ExprParser for Parser {
                             keywords,
                             line,
                             column,
+                            span: self.span_from(line, column),
                         };
                     }
                 }
@@ -1107,6 +939,7 @@ impl ExprParser for Parser {
                     ctx: ExprContext::Load,
                     line,
                     column,
+                    span: self.span_from(line, column),
                 };
             } else if self.match_token(TokenType::LeftBracket) {
                 let line = expr.get_line();
@@ -1120,6 +953,7 @@ impl ExprParser for Parser {
                     ctx: ExprContext::Load,
                     line,
                     column,
+                    span: self.span_from(line, column),
                 };
             } else {
                 break;
@@ -1134,7 +968,7 @@ impl ExprParser for Parser {
         let column = self.current.as_ref().map_or(0, |t| t.column);
 
         if self.match_token(TokenType::Ellipsis) {
-            let ellipsis_expr = Expr::Ellipsis { line, column };
+            let ellipsis_expr = Expr::Ellipsis { line, column, span: self.span_from(line, column) };
 
             if self.match_token(TokenType::Comma) {
                 let mut indices = vec![Box::new(ellipsis_expr)];
@@ -1155,6 +989,7 @@ impl ExprParser for Parser {
                     ctx: ExprContext::Load,
                     line,
                     column,
+                    span: self.span_from(line, column),
                 });
             }
 
@@ -1193,6 +1028,7 @@ impl ExprParser for Parser {
                 step,
                 line,
                 column,
+                span: self.span_from(line, column),
             };
 
             if self.match_token(TokenType::Comma) {
@@ -1214,6 +1050,7 @@ impl ExprParser for Parser {
                     ctx: ExprContext::Load,
                     line,
                     column,
+                    span: self.span_from(line, column),
                 });
             }
 
@@ -1248,6 +1085,7 @@ impl ExprParser for Parser {
                 ctx: ExprContext::Load,
                 line,
                 column,
+                span: self.span_from(line, column),
             })
         } else {
             if let Some(expr) = lower {
@@ -1292,10 +1130,12 @@ impl ExprParser for Parser {
                     ctx: ExprContext::Load,
                     line,
                     column,
+                    span: self.span_from(line, column),
                 }),
                 ctx: ExprContext::Load,
                 line,
                 column,
+                span: self.span_from(line, column),
             };
         }
 
@@ -1442,10 +1282,11 @@ impl ExprParser for Parser {
             TokenType::Identifier(name) => {
                 self.advance();
                 Ok(Expr::Name {
-                    id: name.clone(),
+                    id: name.to_string(),
                     ctx: ExprContext::Load,
                     line,
                     column,
+                    span: self.span_from(line, column),
                 })
             }
             TokenType::Yield => {
@@ -1470,6 +1311,7 @@ impl ExprParser for Parser {
                             ctx: ExprContext::Load,
                             line,
                             column,
+                            span: self.span_from(line, column),
                         });
                     }
                     Ok(Expr::Tuple {
@@ -1477,6 +1319,7 @@ impl ExprParser for Parser {
                         ctx: ExprContext::Load,
                         line,
                         column,
+                        span: self.span_from(line, column),
                     })
                 } else {
                     let expr = self.parse_expression()?;
@@ -1539,6 +1382,7 @@ impl ExprParser for Parser {
                                 generators,
                                 line,
                                 column,
+                                span: this.span_from(line, column),
                             })
                         });
                     } else if self.check(TokenType::Async) && self.peek_matches(TokenType::For) {
@@ -1602,6 +1446,7 @@ impl ExprParser for Parser {
                                 generators,
                                 line,
                                 column,
+                                span: this.span_from(line, column),
                             })
                         });
                     } else if self.match_token(TokenType::Comma) {
@@ -1618,6 +1463,7 @@ impl ExprParser for Parser {
                             ctx: ExprContext::Load,
                             line,
                             column,
+                            span: self.span_from(line, column),
                         })
                     } else {
                         self.consume(TokenType::RightParen, ")")?;
@@ -1641,6 +1487,7 @@ impl ExprParser for Parser {
                         ctx: ExprContext::Load,
                         line,
                         column,
+                        span: self.span_from(line, column),
                     });
                 }
 
@@ -1665,6 +1512,7 @@ impl ExprParser for Parser {
                         ctx: list_ctx(self),
                         line: star_tok.line,
                         column: star_tok.column,
+                        span: self.span_from(star_tok.line, star_tok.column),
                     }
                 } else {
                     self.parse_or_test()?
@@ -1746,6 +1594,7 @@ impl ExprParser for Parser {
                             generators,
                             line,
                             column,
+                            span: this.span_from(line, column),
                         })
                     });
                 }
@@ -1767,6 +1616,7 @@ impl ExprParser for Parser {
                             ctx: list_ctx(self),
                             line: star_tok.line,
                             column: star_tok.column,
+                            span: self.span_from(star_tok.line, star_tok.column),
                         }));
                     } else {
                         elements.push(Box::new(self.parse_or_test()?));
@@ -1780,6 +1630,7 @@ impl ExprParser for Parser {
                     ctx: list_ctx(self),
                     line,
                     column,
+                    span: self.span_from(line, column),
                 })
             }
 
@@ -1803,6 +1654,7 @@ impl ExprParser for Parser {
                     value: Number::Integer(*value),
                     line,
                     column,
+                    span: self.span_from(line, column),
                 })
             }
             TokenType::FloatLiteral(value) => {
@@ -1811,6 +1663,7 @@ impl ExprParser for Parser {
                     value: Number::Float(*value),
                     line,
                     column,
+                    span: self.span_from(line, column),
                 })
             }
             TokenType::Lambda => {
@@ -1829,6 +1682,7 @@ impl ExprParser for Parser {
                     body,
                     line: line_start,
                     column: column_start,
+                    span: self.span_from(line_start, column_start),
                 })
             }
             TokenType::FString(value) => {
@@ -1853,6 +1707,7 @@ impl ExprParser for Parser {
                                 value: current_text,
                                 line,
                                 column,
+                                span: self.span_from(line, column),
                             }));
                             current_text = String::new();
                         }
@@ -1906,6 +1761,7 @@ impl ExprParser for Parser {
                                     value: format_str,
                                     line,
                                     column,
+                                    span: self.span_from(line, column),
                                 }));
 
                                 continue;
@@ -1920,13 +1776,15 @@ impl ExprParser for Parser {
                         let mut expr_parser = crate::parser::Parser::new(expr_tokens);
 
                         match expr_parser.parse_expression() {
-                            Ok(expr) => {
+                            Ok(mut expr) => {
+                                crate::parser::spans::set_spans(&mut expr, self.span_from(line, column));
                                 values.push(Box::new(Expr::FormattedValue {
                                     value: Box::new(expr),
                                     conversion,
                                     format_spec,
                                     line,
                                     column,
+                                    span: self.span_from(line, column),
                                 }));
                             },
                             Err(e) => {
@@ -1959,6 +1817,7 @@ impl ExprParser for Parser {
                         value: current_text,
                         line,
                         column,
+                        span: self.span_from(line, column),
                     }));
                 }
 
@@ -1969,6 +1828,7 @@ impl ExprParser for Parser {
                             value: value.clone(),
                             line,
                             column,
+                            span: self.span_from(line, column),
                         });
                     }
                 }
@@ -1978,14 +1838,16 @@ impl ExprParser for Parser {
                     values,
                     line,
                     column,
+                    span: self.span_from(line, column),
                 })
             }
             TokenType::RawString(value) => {
                 self.advance();
                 Ok(Expr::Str {
-                    value: value.clone(),
+                    value: value.to_string(),
                     line,
                     column,
+                    span: self.span_from(line, column),
                 })
             }
             TokenType::BytesLiteral(bytes) => {
@@ -1994,14 +1856,16 @@ impl ExprParser for Parser {
                     value: bytes.clone(),
                     line,
                     column,
+                    span: self.span_from(line, column),
                 })
             }
             TokenType::StringLiteral(value) => {
                 self.advance();
                 Ok(Expr::Str {
-                    value: value.clone(),
+                    value: value.to_string(),
                     line,
                     column,
+                    span: self.span_from(line, column),
                 })
             }
             TokenType::True => {
@@ -2010,6 +1874,7 @@ impl ExprParser for Parser {
                     value: NameConstant::True,
                     line,
                     column,
+                    span: self.span_from(line, column),
                 })
             }
             TokenType::False => {
@@ -2018,6 +1883,7 @@ impl ExprParser for Parser {
                     value: NameConstant::False,
                     line,
                     column,
+                    span: self.span_from(line, column),
                 })
             }
             TokenType::None => {
@@ -2026,15 +1892,16 @@ impl ExprParser for Parser {
                     value: NameConstant::None,
                     line,
                     column,
+                    span: self.span_from(line, column),
                 })
             }
             TokenType::Ellipsis => {
                 self.advance();
-                Ok(Expr::Ellipsis { line, column })
+                Ok(Expr::Ellipsis { line, column, span: self.span_from(line, column) })
             }
             _ => Err(ParseError::UnexpectedToken {
                 expected: "expression".to_string(),
-                found: token.token_type.clone(),
+                found: token.token_type.clone().into_owned(),
                 line,
                 column,
                 suggestion: None,
@@ -2067,6 +1934,7 @@ impl ExprParser for Parser {
                 ctx: ExprContext::Load,
                 line,
                 column,
+                span: self.span_from(line, column),
             }));
         } else {
             expressions.push(Box::new(self.parse_expression()?));
@@ -2104,6 +1972,7 @@ impl ExprParser for Parser {
                     ctx: ExprContext::Load,
                     line,
                     column,
+                    span: self.span_from(line, column),
                 }));
             } else {
                 expressions.push(Box::new(self.parse_expression()?));
@@ -2120,6 +1989,7 @@ impl ExprParser for Parser {
                 values: Vec::new(),
                 line,
                 column,
+                span: self.span_from(line, column),
             });
         }
 
@@ -2197,6 +2067,7 @@ impl ExprParser for Parser {
                             generators,
                             line,
                             column,
+                            span: this.span_from(line, column),
                         })
                     });
                 } else if self.check(TokenType::Async) && self.peek_matches(TokenType::For) {
@@ -2261,6 +2132,7 @@ impl ExprParser for Parser {
                             generators,
                             line,
                             column,
+                            span: this.span_from(line, column),
                         })
                     });
                 }
@@ -2319,6 +2191,7 @@ impl ExprParser for Parser {
                         generators,
                         line,
                         column,
+                        span: this.span_from(line, column),
                     })
                 });
             } else if self.check(TokenType::Async) && self.peek_matches(TokenType::For) {
@@ -2379,6 +2252,7 @@ impl ExprParser for Parser {
                         generators,
                         line,
                         column,
+                        span: this.span_from(line, column),
                     })
                 });
             } else {
@@ -2428,6 +2302,7 @@ impl ExprParser for Parser {
                             values,
                             line,
                             column,
+                            span: self.span_from(line, column),
                         });
                     } else {
                         elts.push(Box::new(self.parse_or_test()?));
@@ -2436,7 +2311,7 @@ impl ExprParser for Parser {
 
                 self.consume(TokenType::RightBrace, "}")?;
 
-                return Ok(Expr::Set { elts, line, column });
+                return Ok(Expr::Set { elts, line, column, span: self.span_from(line, column) });
             }
         }
 
@@ -2467,6 +2342,7 @@ impl ExprParser for Parser {
             values,
             line,
             column,
+            span: self.span_from(line, column),
         })
     }
 }
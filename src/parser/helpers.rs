@@ -1,4 +1,4 @@
-use crate::lexer::{Token, TokenType};
+use crate::lexer::{keywords, Token, TokenType};
 use crate::parser::error::ParseError;
 use crate::parser::Parser;
 
@@ -26,7 +26,7 @@ pub const ERR_EXPECTED_NAME: &str = "Expected name";
 pub const ERR_EXPECTED_NEWLINE: &str = "Expected newline";
 
 /// Trait for token matching and consumption
-pub trait TokenMatching {
+pub trait TokenMatching<'a> {
     /// Check if the current token matches the expected type
     fn check(&self, expected_type: TokenType) -> bool;
 
@@ -46,7 +46,7 @@ pub trait TokenMatching {
         &mut self,
         expected_type: TokenType,
         error_message: &str,
-    ) -> Result<Token, ParseError>;
+    ) -> Result<Token<'a>, ParseError>;
 
     fn consume_attribute_name(&mut self, expected: &str) -> Result<String, ParseError>;
 
@@ -57,7 +57,7 @@ pub trait TokenMatching {
         &mut self,
         expected_type: TokenType,
         error_message: &str,
-    ) -> Result<Token, ParseError>;
+    ) -> Result<Token<'a>, ParseError>;
 
     /// Consume a newline token
     fn consume_newline(&mut self) -> Result<(), ParseError>;
@@ -83,7 +83,7 @@ pub trait TokenMatching {
     fn token_matches(&self, a: &TokenType, b: &TokenType) -> bool;
 }
 
-impl TokenMatching for Parser {
+impl<'a> TokenMatching<'a> for Parser<'a> {
     fn check(&self, expected_type: TokenType) -> bool {
         match &self.current {
             Some(token) => self.token_matches(&token.token_type, &expected_type),
@@ -105,7 +105,7 @@ impl TokenMatching for Parser {
     }
 
     fn peek_matches(&self, expected_type: TokenType) -> bool {
-        if let Some(token) = self.tokens.front() {
+        if let Some(token) = &self.lookahead {
             self.token_matches(&token.token_type, &expected_type)
         } else {
             false
@@ -116,7 +116,7 @@ impl TokenMatching for Parser {
         &mut self,
         expected_type: TokenType,
         error_message: &str,
-    ) -> Result<Token, ParseError> {
+    ) -> Result<Token<'a>, ParseError> {
         if self.check(expected_type) {
             Ok(self.advance().unwrap())
         } else {
@@ -125,53 +125,16 @@ impl TokenMatching for Parser {
     }
 
     fn get_keyword_name(&self, token_type: &TokenType) -> String {
-        match token_type {
-            TokenType::And => "and".to_string(),
-            TokenType::As => "as".to_string(),
-            TokenType::Assert => "assert".to_string(),
-            TokenType::Async => "async".to_string(),
-            TokenType::Await => "await".to_string(),
-            TokenType::Break => "break".to_string(),
-            TokenType::Class => "class".to_string(),
-            TokenType::Continue => "continue".to_string(),
-            TokenType::Def => "def".to_string(),
-            TokenType::Del => "del".to_string(),
-            TokenType::Elif => "elif".to_string(),
-            TokenType::Else => "else".to_string(),
-            TokenType::Except => "except".to_string(),
-            TokenType::Finally => "finally".to_string(),
-            TokenType::For => "for".to_string(),
-            TokenType::From => "from".to_string(),
-            TokenType::Global => "global".to_string(),
-            TokenType::If => "if".to_string(),
-            TokenType::Import => "import".to_string(),
-            TokenType::In => "in".to_string(),
-            TokenType::Is => "is".to_string(),
-            TokenType::Lambda => "lambda".to_string(),
-            TokenType::None => "None".to_string(),
-            TokenType::Nonlocal => "nonlocal".to_string(),
-            TokenType::Not => "not".to_string(),
-            TokenType::Or => "or".to_string(),
-            TokenType::Pass => "pass".to_string(),
-            TokenType::Raise => "raise".to_string(),
-            TokenType::Return => "return".to_string(),
-            TokenType::Try => "try".to_string(),
-            TokenType::While => "while".to_string(),
-            TokenType::With => "with".to_string(),
-            TokenType::Yield => "yield".to_string(),
-            TokenType::Match => "match".to_string(),
-            TokenType::Case => "case".to_string(),
-            TokenType::True => "True".to_string(),
-            TokenType::False => "False".to_string(),
-            _ => "unknown_keyword".to_string(),
-        }
+        keywords::for_token(token_type)
+            .map_or("unknown_keyword", |keyword| keyword.text)
+            .to_string()
     }
 
     fn consume_attribute_name(&mut self, expected: &str) -> Result<String, ParseError> {
         match &self.current {
             Some(token) => match &token.token_type {
                 TokenType::Identifier(name) => {
-                    let result = name.clone();
+                    let result = name.to_string();
                     self.advance();
                     Ok(result)
                 }
@@ -199,7 +162,7 @@ impl TokenMatching for Parser {
         &mut self,
         expected_type: TokenType,
         error_message: &str,
-    ) -> Result<Token, ParseError> {
+    ) -> Result<Token<'a>, ParseError> {
         match &self.current {
             Some(token) => {
                 if matches!(expected_type, TokenType::RightParen)
@@ -237,47 +200,9 @@ impl TokenMatching for Parser {
     }
 
     fn is_keyword_token(&self) -> bool {
-        match &self.current {
-            Some(token) => matches!(
-                token.token_type,
-                TokenType::And
-                    | TokenType::As
-                    | TokenType::Assert
-                    | TokenType::Async
-                    | TokenType::Await
-                    | TokenType::Break
-                    | TokenType::Class
-                    | TokenType::Continue
-                    | TokenType::Def
-                    | TokenType::Del
-                    | TokenType::Elif
-                    | TokenType::Else
-                    | TokenType::Except
-                    | TokenType::Finally
-                    | TokenType::For
-                    | TokenType::From
-                    | TokenType::Global
-                    | TokenType::If
-                    | TokenType::Import
-                    | TokenType::In
-                    | TokenType::Is
-                    | TokenType::Lambda
-                    | TokenType::None
-                    | TokenType::Nonlocal
-                    | TokenType::Not
-                    | TokenType::Or
-                    | TokenType::Pass
-                    | TokenType::Raise
-                    | TokenType::Return
-                    | TokenType::Try
-                    | TokenType::While
-                    | TokenType::With
-                    | TokenType::Yield
-                    | TokenType::Match
-                    | TokenType::Case
-            ),
-            None => false,
-        }
+        self.current
+            .as_ref()
+            .is_some_and(|token| keywords::for_token(&token.token_type).is_some())
     }
 
     fn consume_newline(&mut self) -> Result<(), ParseError> {
@@ -343,7 +268,7 @@ impl TokenMatching for Parser {
         match &self.current {
             Some(token) => match &token.token_type {
                 TokenType::Identifier(name) => {
-                    let result = name.clone();
+                    let result = name.to_string();
                     self.advance();
                     Ok(result)
                 }
@@ -437,13 +362,14 @@ pub trait AstBuilder {
     fn create_none(&self, line: usize, column: usize) -> crate::ast::Expr;
 }
 
-impl AstBuilder for Parser {
+impl AstBuilder for Parser<'_> {
     fn create_identifier(&self, name: &str, line: usize, column: usize) -> crate::ast::Expr {
         crate::ast::Expr::Name {
             id: name.to_string(),
             ctx: crate::ast::ExprContext::Load,
             line,
             column,
+            span: self.span_from(line, column),
         }
     }
 
@@ -452,6 +378,7 @@ impl AstBuilder for Parser {
             value: value.to_string(),
             line,
             column,
+            span: self.span_from(line, column),
         }
     }
 
@@ -460,6 +387,7 @@ impl AstBuilder for Parser {
             value: crate::ast::Number::Integer(value),
             line,
             column,
+            span: self.span_from(line, column),
         }
     }
 
@@ -468,6 +396,7 @@ impl AstBuilder for Parser {
             value: crate::ast::Number::Float(value),
             line,
             column,
+            span: self.span_from(line, column),
         }
     }
 
@@ -480,6 +409,7 @@ impl AstBuilder for Parser {
             },
             line,
             column,
+            span: self.span_from(line, column),
         }
     }
 
@@ -488,6 +418,7 @@ impl AstBuilder for Parser {
             value: crate::ast::NameConstant::None,
             line,
             column,
+            span: self.span_from(line, column),
         }
     }
 }
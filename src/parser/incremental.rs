@@ -0,0 +1,125 @@
+// incremental.rs - Re-parsing only the statements an edit touches
+//
+// `IncrementalParser` keeps a file's text and syntax tree for editors and
+// file watchers that see the file change a keystroke at a time. An edit
+// re-lexes and re-parses only the top-level statements it touches. The
+// statements before them are kept as they are, and those after are kept
+// with their lines and spans moved by however much the edit grew or shrank
+// the text:
+//
+//     let mut file = IncrementalParser::new(source);
+//     let reparsed = file.edit(&edit)?;   // bytes of the new text parsed again
+//     let module = file.module();         // the same tree `cheetah::parse` gives
+//
+// Statements touching the edit are re-parsed together with their neighbours
+// on either side of a touched line break, since indenting a line or joining
+// two can move a statement into another. When the re-parsed text doesn't
+// parse on its own, as when the edit opens a bracket or a string that the
+// rest of the file now continues, the whole file is parsed instead.
+
+use super::{parse_lexed, parse_source, spans, ParseError};
+use crate::ast::{Module, Stmt};
+use crate::lexer::Lexer;
+use crate::references::TextEdit;
+use std::ops::Range;
+
+/// A file's text and its parse, kept up to date through edits
+#[derive(Debug, Clone)]
+pub struct IncrementalParser {
+    source: String,
+    parsed: Result<Module, Vec<ParseError>>,
+}
+
+impl IncrementalParser {
+    /// Parse `source` whole
+    pub fn new(source: impl Into<String>) -> Self {
+        let source = source.into();
+        let parsed = parse_source(&source);
+        Self { source, parsed }
+    }
+
+    /// The current text
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The syntax tree of the current text, or why it doesn't parse
+    pub fn module(&self) -> Result<&Module, &[ParseError]> {
+        self.parsed.as_ref().map_err(|errors| errors.as_slice())
+    }
+
+    /// Replace the bytes `edit.span` covers with `edit.new_text` and parse
+    /// the result. Returns the byte range of the new text that was parsed
+    /// again, which is all of it when the old text didn't parse.
+    pub fn edit(&mut self, edit: &TextEdit) -> Result<Range<usize>, String> {
+        let (start, end) = (edit.span.start, edit.span.end);
+        if start > end || end > self.source.len() {
+            return Err(format!(
+                "edit of bytes {}..{} is outside the {}-byte source",
+                start,
+                end,
+                self.source.len()
+            ));
+        }
+        if !self.source.is_char_boundary(start) || !self.source.is_char_boundary(end) {
+            return Err(format!("edit of bytes {}..{} splits a character", start, end));
+        }
+
+        // Top-level statements the edit touches, and the text they run over
+        let regions = match &self.parsed {
+            Ok(module) if !module.body.is_empty() => statement_regions(&self.source, &module.body),
+            _ => Vec::new(),
+        };
+        let mut touched = (0..regions.len()).filter(|&i| regions[i].start <= end && start <= regions[i].end);
+        let (first, last) = (touched.next(), touched.next_back());
+
+        let old_line_breaks = self.source[start..end].matches('\n').count() as isize;
+        self.source.replace_range(start..end, &edit.new_text);
+        let Some(first) = first else {
+            return Ok(self.parse_whole());
+        };
+        let last = last.unwrap_or(first);
+
+        let bytes = edit.new_text.len() as isize - (end - start) as isize;
+        let lines = edit.new_text.matches('\n').count() as isize - old_line_breaks;
+        let reparse_start = regions[first].start;
+        let reparse_end = regions[last].end.saturating_add_signed(bytes);
+        let line = self.source[..reparse_start].matches('\n').count() + 1;
+        let lexer = Lexer::starting_at(&self.source[..reparse_end], reparse_start, line);
+        let Ok(reparsed) = parse_lexed(lexer) else {
+            return Ok(self.parse_whole());
+        };
+
+        if let Ok(module) = &mut self.parsed {
+            let mut after = module.body.split_off(last + 1);
+            for stmt in &mut after {
+                spans::shift_stmt(stmt, lines, bytes);
+            }
+            module.body.truncate(first);
+            module.body.extend(reparsed.body);
+            module.body.extend(after);
+        }
+        Ok(reparse_start..reparse_end)
+    }
+
+    /// Parse the whole text, returning its range
+    fn parse_whole(&mut self) -> Range<usize> {
+        self.parsed = parse_source(&self.source);
+        0..self.source.len()
+    }
+}
+
+/// The text each top-level statement runs over: from the start of the line
+/// it starts on, which takes in its decorators, to the start of the line the
+/// next statement starts on. The first takes in everything before it and the
+/// last everything after it.
+fn statement_regions(source: &str, body: &[Box<Stmt>]) -> Vec<Range<usize>> {
+    let line_start = |offset: usize| source[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let mut starts: Vec<usize> = body.iter().map(|stmt| line_start(stmt.span().start)).collect();
+    starts[0] = 0;
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, &start)| start..starts.get(i + 1).copied().unwrap_or(source.len()))
+        .collect()
+}
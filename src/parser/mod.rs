@@ -1,32 +1,40 @@
 mod error;
 mod expr;
 mod helpers;
+pub mod incremental;
+pub mod precedence;
+mod spans;
 mod stmt;
 mod types;
 
 pub use error::{ParseError, ParseErrorFormatter};
+use expr::ExprParser;
 use helpers::TokenMatching;
 use stmt::StmtParser;
 use types::ParserContext;
 
-use crate::ast::Module;
-use crate::lexer::{Token, TokenType};
+use crate::ast::{Expr, Module, Span, Stmt};
+use crate::lexer::{Lexer, LexerConfig, LexerError, Token, TokenType};
 
-use std::collections::VecDeque;
+/// How deeply expressions may nest before the parser gives up on them
+pub const DEFAULT_MAX_DEPTH: usize = 100;
 
 /// Parser for Python source code
 ///
 /// This parser implements a recursive descent parser for Python syntax,
 /// producing an AST (Abstract Syntax Tree) conforming to Python's ast module.
-pub struct Parser {
-    /// Queue of tokens to be processed
-    tokens: VecDeque<Token>,
+pub struct Parser<'a> {
+    /// Tokens after the lookahead token
+    tokens: TokenStream<'a>,
+
+    /// Token after the current one
+    lookahead: Option<Token<'a>>,
 
     /// Current token being processed
-    current: Option<Token>,
+    current: Option<Token<'a>>,
 
     /// Last token that was processed
-    last_token: Option<Token>,
+    last_token: Option<Token<'a>>,
 
     /// Errors encountered during parsing
     errors: Vec<ParseError>,
@@ -36,24 +44,103 @@ pub struct Parser {
 
     /// Stack of parser contexts
     context_stack: Vec<ParserContext>,
+
+    /// Current expression nesting depth
+    depth: usize,
+
+    /// Nesting depth at which expressions are rejected
+    max_depth: usize,
+
+    /// Lines, columns and spans of the tokens in source order, dropped once
+    /// the statement they're in is parsed
+    token_spans: Vec<(usize, usize, Span)>,
+
+    /// Bracket tokens in source order, for keeping spans balanced, dropped
+    /// with the token spans
+    brackets: Vec<spans::Bracket>,
+
+    /// Span of the last token consumed that isn't a newline, indent or dedent
+    last_end: Span,
+}
+
+/// Where a parser's tokens come from
+enum TokenStream<'a> {
+    /// Tokens lexed up front
+    Buffered(std::vec::IntoIter<Token<'a>>),
+    /// Tokens lexed as the parser reaches them
+    Lexer(Box<Lexer<'a>>),
 }
 
-impl Parser {
+impl<'a> Iterator for TokenStream<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        match self {
+            TokenStream::Buffered(tokens) => tokens.next(),
+            TokenStream::Lexer(lexer) => lexer.next(),
+        }
+    }
+}
+
+impl<'a> Parser<'a> {
     /// Creates a new parser with the given tokens
-    pub fn new(tokens: Vec<Token>) -> Self {
-        let mut tokens_deque = VecDeque::from(tokens);
-        let current = tokens_deque.pop_front();
+    pub fn new(tokens: Vec<Token<'a>>) -> Self {
+        Self::from_stream(TokenStream::Buffered(tokens.into_iter()))
+    }
+
+    /// Creates a parser that takes its tokens from `lexer` as it reaches
+    /// them, so the input is never held as a token list
+    pub fn from_lexer(lexer: Lexer<'a>) -> Self {
+        Self::from_stream(TokenStream::Lexer(Box::new(lexer)))
+    }
 
-        Parser {
-            tokens: tokens_deque,
-            current,
+    fn from_stream(tokens: TokenStream<'a>) -> Self {
+        let mut parser = Parser {
+            tokens,
+            lookahead: None,
+            current: None,
             last_token: None,
             errors: Vec::new(),
             current_indent_level: 0,
             context_stack: vec![ParserContext::Normal],
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            token_spans: Vec::new(),
+            brackets: Vec::new(),
+            last_end: Span::default(),
+        };
+        parser.current = parser.pull();
+        parser.lookahead = parser.pull();
+        parser
+    }
+
+    /// Take the next token from the stream, noting its span and any bracket
+    /// until the statement it's in has been parsed
+    fn pull(&mut self) -> Option<Token<'a>> {
+        let token = self.tokens.next()?;
+        if !token.span.is_empty() {
+            self.token_spans.push((token.line, token.column, token.span));
+        }
+        self.brackets.extend(spans::bracket(&token));
+        Some(token)
+    }
+
+    /// Errors the lexer reported for the tokens pulled so far, when the
+    /// parser lexes its own input
+    pub fn lexer_errors(&self) -> &[LexerError] {
+        match &self.tokens {
+            TokenStream::Lexer(lexer) => lexer.get_errors(),
+            TokenStream::Buffered(_) => &[],
         }
     }
 
+    /// Creates a parser that rejects expressions nested more than `max_depth` deep
+    pub fn with_max_depth(tokens: Vec<Token<'a>>, max_depth: usize) -> Self {
+        let mut parser = Parser::new(tokens);
+        parser.max_depth = max_depth;
+        parser
+    }
+
     /// Parses the entire input and returns a module
     pub fn parse(&mut self) -> Result<Module, Vec<ParseError>> {
         let mut body = Vec::new();
@@ -70,7 +157,11 @@ impl Parser {
             }
 
             match self.parse_statement() {
-                Ok(stmt) => body.push(Box::new(stmt)),
+                Ok(mut stmt) => {
+                    let end = self.finish_stmt_spans(&mut stmt).end;
+                    self.forget_spans(0, end);
+                    body.push(Box::new(stmt));
+                }
                 Err(e) => {
                     self.errors.push(e);
                     self.synchronize();
@@ -120,6 +211,31 @@ impl Parser {
         result
     }
 
+    /// Parse one level of expression nesting deeper, failing with a
+    /// diagnostic rather than overflowing the stack at the nesting limit
+    pub fn nested<T, F>(&mut self, f: F) -> Result<T, ParseError>
+    where
+        F: FnOnce(&mut Self) -> Result<T, ParseError>,
+    {
+        if self.depth >= self.max_depth {
+            let (line, column) = self.current_position();
+            return Err(ParseError::invalid_syntax_with_suggestion(
+                "Expression too deeply nested",
+                line,
+                column,
+                &format!(
+                    "Expressions can nest at most {} levels deep; move inner parts into variables",
+                    self.max_depth
+                ),
+            ));
+        }
+
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
     /// Get the current token position or a default if no token exists
     pub fn current_position(&self) -> (usize, usize) {
         match &self.current {
@@ -137,12 +253,19 @@ impl Parser {
     }
 
     /// Advance to the next token, returning the current one
-    pub fn advance(&mut self) -> Option<Token> {
+    pub fn advance(&mut self) -> Option<Token<'a>> {
         let current = self.current.take();
         if let Some(token) = &current {
             self.last_token = Some(token.clone());
+            if !matches!(
+                token.token_type,
+                TokenType::Newline | TokenType::Indent | TokenType::Dedent | TokenType::EOF
+            ) {
+                self.last_end = token.span;
+            }
         }
-        self.current = self.tokens.pop_front();
+        let next = self.pull();
+        self.current = std::mem::replace(&mut self.lookahead, next);
 
         if let Some(token) = &self.current {
             match token.token_type {
@@ -161,8 +284,31 @@ impl Parser {
         current
     }
 
+    /// Span of a node starting at the token at `line` and `column` and ending
+    /// with the last token consumed. Spans are widened to cover children
+    /// and matching brackets once parsing is done.
+    pub fn span_from(&self, line: usize, column: usize) -> Span {
+        let end = self.last_end;
+        let start = self
+            .token_spans
+            .binary_search_by_key(&(line, column), |&(line, column, _)| (line, column))
+            .ok()
+            .map(|i| &self.token_spans[i].2);
+        match start {
+            Some(start) if start.start <= end.start => Span {
+                start: start.start,
+                ..end
+            },
+            Some(start) => *start,
+            None => Span {
+                start: end.end,
+                ..end
+            },
+        }
+    }
+
     /// Return the previous token (the last one that was consumed)
-    pub fn previous_token(&self) -> Token {
+    pub fn previous_token(&self) -> Token<'a> {
         self.last_token
             .clone()
             .expect("No previous token available")
@@ -183,6 +329,21 @@ impl Parser {
         }
     }
 
+    /// Fail unless only newlines and dedents are left after a single `what`
+    fn expect_end(&mut self, what: &str) -> Result<(), ParseError> {
+        while self.match_token(TokenType::Newline) || self.match_token(TokenType::Dedent) {}
+        match &self.current {
+            None => Ok(()),
+            Some(token) if token.token_type == TokenType::EOF => Ok(()),
+            Some(token) => Err(ParseError::unexpected_token(
+                &format!("end of {}", what),
+                token.token_type.clone(),
+                token.line,
+                token.column,
+            )),
+        }
+    }
+
     /// Synchronize the parser state after an error
     ///
     /// This method skips tokens until it finds a synchronization point,
@@ -215,3 +376,77 @@ pub fn parse(tokens: Vec<Token>) -> Result<Module, Vec<ParseError>> {
     let mut parser = Parser::new(tokens);
     parser.parse()
 }
+
+/// Lex `source`, reporting lexer errors as syntax errors
+pub fn tokenize(source: &str) -> Result<Vec<Token<'_>>, Vec<ParseError>> {
+    tokenize_with_config(source, LexerConfig::default())
+}
+
+/// Lex `source` with `config`, reporting lexer errors as syntax errors
+pub fn tokenize_with_config(
+    source: &str,
+    config: LexerConfig,
+) -> Result<Vec<Token<'_>>, Vec<ParseError>> {
+    let mut lexer = Lexer::with_config(source, config);
+    let tokens = lexer.tokenize();
+
+    if lexer.get_errors().is_empty() {
+        return Ok(tokens);
+    }
+    Err(syntax_errors(lexer.get_errors()))
+}
+
+/// Lex and parse `source` in one pass, the parser taking each token as the
+/// lexer produces it. Lexer errors are reported instead of parse errors.
+pub fn parse_source(source: &str) -> Result<Module, Vec<ParseError>> {
+    parse_lexed(Lexer::new(source))
+}
+
+/// Parse the tokens `lexer` produces as a module, reporting lexer errors
+/// instead of parse errors
+fn parse_lexed(lexer: Lexer) -> Result<Module, Vec<ParseError>> {
+    let mut parser = Parser::from_lexer(lexer);
+    let result = parser.parse();
+    // Lex whatever a failed parse left, so every lexer error is reported
+    while parser.advance().is_some() {}
+
+    if parser.lexer_errors().is_empty() {
+        return result;
+    }
+    Err(syntax_errors(parser.lexer_errors()))
+}
+
+/// Lexer errors as syntax errors
+fn syntax_errors(errors: &[LexerError]) -> Vec<ParseError> {
+    errors
+        .iter()
+        .map(|e| match &e.suggestion {
+            Some(suggestion) => {
+                ParseError::invalid_syntax_with_suggestion(&e.message, e.line, e.column, suggestion)
+            }
+            None => ParseError::invalid_syntax(&e.message, e.line, e.column),
+        })
+        .collect()
+}
+
+/// Parse `source` as a single expression, such as the operand of the REPL's
+/// `:type` command or the `{...}` part of an f-string
+pub fn parse_expression(source: &str) -> Result<Expr, Vec<ParseError>> {
+    let mut parser = Parser::new(tokenize(source)?);
+    while parser.match_token(TokenType::Newline) {}
+    let mut expr = parser.parse_expression().map_err(|e| vec![e])?;
+    parser.expect_end("expression").map_err(|e| vec![e])?;
+    parser.widen_expr_spans(&mut expr);
+    Ok(expr)
+}
+
+/// Parse `source` as a single statement, which may be a compound statement
+/// with a body
+pub fn parse_statement(source: &str) -> Result<Stmt, Vec<ParseError>> {
+    let mut parser = Parser::new(tokenize(source)?);
+    while parser.match_token(TokenType::Newline) {}
+    let mut stmt = parser.parse_statement().map_err(|e| vec![e])?;
+    parser.expect_end("statement").map_err(|e| vec![e])?;
+    parser.widen_stmt_spans(&mut stmt);
+    Ok(stmt)
+}
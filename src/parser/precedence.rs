@@ -0,0 +1,319 @@
+// precedence.rs - Operator precedence table for the expression parser
+//
+// `parse_operators` parses every operator from `:=` down to `**` in one
+// Pratt loop. Instead of a function per level, it looks the current token up
+// here to find how tightly the operator binds and how far its right operand
+// extends:
+//
+//     // In `a - b * c`, `*` binds more tightly than `-`, so `b * c` is the
+//     // right operand of `-`
+//     let (level, _) = precedence::infix(&TokenType::Multiply).unwrap();
+//     assert!(level.precedence > Precedence::Arithmetic);
+//
+// A new operator is a new row or a new entry in a row of `LEVELS`, and
+// `cheetah docs precedence` prints the table as a reference.
+
+use crate::ast::{BoolOperator, Operator, UnaryOperator};
+use crate::lexer::TokenType;
+
+/// How tightly an operator binds, loosest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Precedence {
+    Lambda,
+    Walrus,
+    Conditional,
+    Or,
+    And,
+    Not,
+    Comparison,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Shift,
+    Arithmetic,
+    Term,
+    Unary,
+    Power,
+    Await,
+    Primary,
+}
+
+/// How a run of operators of the same level groups
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    /// `a - b - c` is `(a - b) - c`
+    Left,
+    /// `a ** b ** c` is `a ** (b ** c)`
+    Right,
+    /// `a < b < c` is `a < b and b < c`
+    Chained,
+    /// Comes before its operand, such as `not`
+    Prefix,
+    /// Comes after its operand, such as a call
+    Postfix,
+}
+
+/// The expression an operator builds
+#[derive(Debug, Clone, PartialEq)]
+pub enum OperatorKind {
+    /// `target := value`
+    Named,
+    /// `body if test else orelse`
+    Conditional,
+    Bool(BoolOperator),
+    /// Prefix `not`
+    Not,
+    /// One operator of a comparison chain
+    Compare,
+    Binary(Operator),
+    Unary(UnaryOperator),
+    /// Parsed with its operand, rather than by `parse_operators`
+    Atom,
+}
+
+/// One spelling of an operator
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperatorSpec {
+    /// How it's written, with `x` for operands where that helps
+    pub symbol: &'static str,
+    /// The token that starts it
+    pub token: TokenType<'static>,
+    pub kind: OperatorKind,
+}
+
+/// Operators that bind equally tightly
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrecedenceLevel {
+    pub precedence: Precedence,
+    pub associativity: Associativity,
+    /// Level the operand on the right of one of these operators is parsed at
+    pub right: Precedence,
+    pub operators: &'static [OperatorSpec],
+    pub description: &'static str,
+}
+
+impl PrecedenceLevel {
+    /// Whether its operators come before their operand
+    pub fn is_prefix(&self) -> bool {
+        self.associativity == Associativity::Prefix
+    }
+}
+
+const fn spec(symbol: &'static str, token: TokenType<'static>, kind: OperatorKind) -> OperatorSpec {
+    OperatorSpec { symbol, token, kind }
+}
+
+const fn binary(symbol: &'static str, token: TokenType<'static>, op: Operator) -> OperatorSpec {
+    spec(symbol, token, OperatorKind::Binary(op))
+}
+
+/// Every level, loosest first
+pub static LEVELS: &[PrecedenceLevel] = &[
+    PrecedenceLevel {
+        precedence: Precedence::Lambda,
+        associativity: Associativity::Prefix,
+        right: Precedence::Lambda,
+        operators: &[spec("lambda", TokenType::Lambda, OperatorKind::Atom)],
+        description: "Anonymous function; the body extends as far as possible",
+    },
+    PrecedenceLevel {
+        precedence: Precedence::Walrus,
+        associativity: Associativity::Right,
+        right: Precedence::Walrus,
+        operators: &[spec(":=", TokenType::Walrus, OperatorKind::Named)],
+        description: "Assignment expression; the target must be a name",
+    },
+    PrecedenceLevel {
+        precedence: Precedence::Conditional,
+        associativity: Associativity::Right,
+        right: Precedence::Conditional,
+        operators: &[spec("if-else", TokenType::If, OperatorKind::Conditional)],
+        description: "Conditional expression; not in comprehension conditions or match patterns",
+    },
+    PrecedenceLevel {
+        precedence: Precedence::Or,
+        associativity: Associativity::Left,
+        right: Precedence::And,
+        operators: &[spec("or", TokenType::Or, OperatorKind::Bool(BoolOperator::Or))],
+        description: "Boolean OR",
+    },
+    PrecedenceLevel {
+        precedence: Precedence::And,
+        associativity: Associativity::Left,
+        right: Precedence::Not,
+        operators: &[spec("and", TokenType::And, OperatorKind::Bool(BoolOperator::And))],
+        description: "Boolean AND",
+    },
+    PrecedenceLevel {
+        precedence: Precedence::Not,
+        associativity: Associativity::Prefix,
+        right: Precedence::Not,
+        operators: &[spec("not x", TokenType::Not, OperatorKind::Not)],
+        description: "Boolean NOT",
+    },
+    PrecedenceLevel {
+        precedence: Precedence::Comparison,
+        associativity: Associativity::Chained,
+        right: Precedence::BitOr,
+        operators: &[
+            spec("in", TokenType::In, OperatorKind::Compare),
+            spec("not in", TokenType::Not, OperatorKind::Compare),
+            spec("is", TokenType::Is, OperatorKind::Compare),
+            spec("is not", TokenType::Is, OperatorKind::Compare),
+            spec("<", TokenType::LessThan, OperatorKind::Compare),
+            spec("<=", TokenType::LessEqual, OperatorKind::Compare),
+            spec(">", TokenType::GreaterThan, OperatorKind::Compare),
+            spec(">=", TokenType::GreaterEqual, OperatorKind::Compare),
+            spec("!=", TokenType::NotEqual, OperatorKind::Compare),
+            spec("==", TokenType::Equal, OperatorKind::Compare),
+        ],
+        description: "Comparisons, membership and identity tests",
+    },
+    PrecedenceLevel {
+        precedence: Precedence::BitOr,
+        associativity: Associativity::Left,
+        right: Precedence::BitXor,
+        operators: &[binary("|", TokenType::BitwiseOr, Operator::BitOr)],
+        description: "Bitwise OR",
+    },
+    PrecedenceLevel {
+        precedence: Precedence::BitXor,
+        associativity: Associativity::Left,
+        right: Precedence::BitAnd,
+        operators: &[binary("^", TokenType::BitwiseXor, Operator::BitXor)],
+        description: "Bitwise XOR",
+    },
+    PrecedenceLevel {
+        precedence: Precedence::BitAnd,
+        associativity: Associativity::Left,
+        right: Precedence::Shift,
+        operators: &[binary("&", TokenType::BitwiseAnd, Operator::BitAnd)],
+        description: "Bitwise AND",
+    },
+    PrecedenceLevel {
+        precedence: Precedence::Shift,
+        associativity: Associativity::Left,
+        right: Precedence::Arithmetic,
+        operators: &[
+            binary("<<", TokenType::ShiftLeft, Operator::LShift),
+            binary(">>", TokenType::ShiftRight, Operator::RShift),
+        ],
+        description: "Shifts",
+    },
+    PrecedenceLevel {
+        precedence: Precedence::Arithmetic,
+        associativity: Associativity::Left,
+        right: Precedence::Term,
+        operators: &[
+            binary("+", TokenType::Plus, Operator::Add),
+            binary("-", TokenType::Minus, Operator::Sub),
+        ],
+        description: "Addition and subtraction",
+    },
+    PrecedenceLevel {
+        precedence: Precedence::Term,
+        associativity: Associativity::Left,
+        right: Precedence::Unary,
+        operators: &[
+            binary("*", TokenType::Multiply, Operator::Mult),
+            binary("@", TokenType::At, Operator::MatMult),
+            binary("/", TokenType::Divide, Operator::Div),
+            binary("//", TokenType::FloorDivide, Operator::FloorDiv),
+            binary("%", TokenType::Modulo, Operator::Mod),
+        ],
+        description: "Multiplication, matrix multiplication, division, floor division and remainder",
+    },
+    PrecedenceLevel {
+        precedence: Precedence::Unary,
+        associativity: Associativity::Prefix,
+        right: Precedence::Unary,
+        operators: &[
+            spec("+x", TokenType::Plus, OperatorKind::Unary(UnaryOperator::UAdd)),
+            spec("-x", TokenType::Minus, OperatorKind::Unary(UnaryOperator::USub)),
+            spec("~x", TokenType::BitwiseNot, OperatorKind::Unary(UnaryOperator::Invert)),
+        ],
+        description: "Positive, negative and bitwise NOT",
+    },
+    PrecedenceLevel {
+        precedence: Precedence::Power,
+        associativity: Associativity::Right,
+        // `2 ** -1` is allowed, and `-2 ** 2` is `-(2 ** 2)`
+        right: Precedence::Unary,
+        operators: &[binary("**", TokenType::Power, Operator::Pow)],
+        description: "Exponentiation; binds less tightly than a unary operator on its right",
+    },
+    PrecedenceLevel {
+        precedence: Precedence::Await,
+        associativity: Associativity::Prefix,
+        right: Precedence::Primary,
+        operators: &[spec("await x", TokenType::Await, OperatorKind::Atom)],
+        description: "Await expression",
+    },
+    PrecedenceLevel {
+        precedence: Precedence::Primary,
+        associativity: Associativity::Postfix,
+        right: Precedence::Primary,
+        operators: &[
+            spec("x[index]", TokenType::LeftBracket, OperatorKind::Atom),
+            spec("x(arguments)", TokenType::LeftParen, OperatorKind::Atom),
+            spec("x.attribute", TokenType::Dot, OperatorKind::Atom),
+        ],
+        description: "Subscription, slicing, call and attribute reference",
+    },
+];
+
+/// The operator `token` starts when it follows an operand, and its level
+pub fn infix(token: &TokenType<'_>) -> Option<(&'static PrecedenceLevel, &'static OperatorSpec)> {
+    find(token, |level| !level.is_prefix())
+}
+
+/// The operator `token` starts at the beginning of an operand, and its level
+pub fn prefix(token: &TokenType<'_>) -> Option<(&'static PrecedenceLevel, &'static OperatorSpec)> {
+    find(token, PrecedenceLevel::is_prefix)
+}
+
+fn find(
+    token: &TokenType<'_>,
+    wanted: impl Fn(&PrecedenceLevel) -> bool,
+) -> Option<(&'static PrecedenceLevel, &'static OperatorSpec)> {
+    LEVELS.iter().filter(|level| wanted(level)).find_map(|level| {
+        level
+            .operators
+            .iter()
+            .find(|operator| operator.kind != OperatorKind::Atom && operator.token == *token)
+            .map(|operator| (level, operator))
+    })
+}
+
+/// The precedence table as a Markdown reference, tightest binding first as
+/// Python's documentation lists it
+pub fn reference() -> String {
+    let mut out = String::from(
+        "# Operator precedence\n\n\
+         Operators lower in the table bind more tightly: `a + b * c` is `a + (b * c)`.\n\
+         Operators in the same row bind equally tightly and group as the row says.\n\n\
+         | Operators | Associativity | Description |\n\
+         |-----------|---------------|-------------|\n",
+    );
+    for level in LEVELS.iter().rev() {
+        let operators: Vec<String> = level
+            .operators
+            .iter()
+            .map(|operator| format!("`{}`", operator.symbol.replace('|', "\\|")))
+            .collect();
+        let associativity = match level.associativity {
+            Associativity::Left => "left to right",
+            Associativity::Right => "right to left",
+            Associativity::Chained => "chained",
+            Associativity::Prefix => "prefix",
+            Associativity::Postfix => "postfix",
+        };
+        out.push_str(&format!(
+            "| {} | {} | {} |\n",
+            operators.join(", "),
+            associativity,
+            level.description
+        ));
+    }
+    out
+}
@@ -0,0 +1,448 @@
+// spans.rs - Widening node spans over their children and brackets
+//
+// While parsing, a node's span runs from the token at its `line` and
+// `column` to the last token consumed when it is built. Some nodes are
+// positioned at an operator rather than their first token (`a + b` is at
+// the `+`), and a parent starting or ending with a parenthesized child
+// would leave the parenthesis out, so once the tree is built every span is
+// widened to cover its children and then over any brackets left unmatched
+// inside it.
+//
+// So that memory doesn't grow with the file, a statement is widened as
+// soon as it's parsed and the token spans and brackets inside it are
+// dropped: nothing parsed later starts at those tokens, and the spans
+// inside it are already balanced. Only the tokens of the statements still
+// being parsed (the headers of the blocks around the current one) and the
+// lookahead are kept.
+
+use super::Parser;
+use crate::ast::{Expr, Span, Stmt};
+use crate::lexer::{Token, TokenType};
+
+/// An opening or closing bracket and where it is
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Bracket {
+    span: Span,
+    open: bool,
+}
+
+/// The bracket `token` is, if it is one
+pub(super) fn bracket(token: &Token) -> Option<Bracket> {
+    if token.span.is_empty() {
+        return None;
+    }
+    let open = match token.token_type {
+        TokenType::LeftParen | TokenType::LeftBracket | TokenType::LeftBrace => true,
+        TokenType::RightParen | TokenType::RightBracket | TokenType::RightBrace => false,
+        _ => return None,
+    };
+    Some(Bracket {
+        span: token.span,
+        open,
+    })
+}
+
+impl Parser<'_> {
+    /// Widen the spans of `stmt`, which has just been parsed, and drop the
+    /// token spans and brackets inside it, returning its span
+    pub(super) fn finish_stmt_spans(&mut self, stmt: &mut Stmt) -> Span {
+        let span = self.widen_stmt_spans(stmt);
+        self.forget_spans(span.start, span.end);
+        span
+    }
+
+    /// Drop the token spans and brackets starting from byte `start` up to `end`
+    pub(super) fn forget_spans(&mut self, start: usize, end: usize) {
+        let first = self.brackets.partition_point(|b| b.span.start < start);
+        let last = self.brackets.partition_point(|b| b.span.start < end);
+        self.brackets.drain(first..last);
+
+        let first = self.token_spans.partition_point(|t| t.2.start < start);
+        let last = self.token_spans.partition_point(|t| t.2.start < end);
+        self.token_spans.drain(first..last);
+    }
+
+    /// Widen the span of `stmt` over its children, returning the result
+    pub(super) fn widen_stmt_spans(&self, stmt: &mut Stmt) -> Span {
+        let mut span = stmt.span();
+        let (exprs, blocks) = stmt_parts(stmt);
+        for expr in exprs {
+            cover(&mut span, self.widen_expr_spans(expr));
+        }
+        for block in blocks {
+            for child in block.iter_mut() {
+                cover(&mut span, self.widen_stmt_spans(child));
+            }
+        }
+        *stmt.span_mut() = self.balance(span);
+        stmt.span()
+    }
+
+    /// Widen the span of `expr` over its children, returning the result
+    pub(super) fn widen_expr_spans(&self, expr: &mut Expr) -> Span {
+        let mut span = expr.span();
+        for child in expr_children(expr) {
+            cover(&mut span, self.widen_expr_spans(child));
+        }
+        *expr.span_mut() = self.balance(span);
+        expr.span()
+    }
+
+    /// `span` extended over the brackets matching any it leaves unmatched
+    fn balance(&self, mut span: Span) -> Span {
+        if span.is_empty() {
+            return span;
+        }
+        let first = self.brackets.partition_point(|b| b.span.start < span.start);
+        let last = self.brackets.partition_point(|b| b.span.start < span.end);
+
+        let (mut depth, mut lowest) = (0i64, 0i64);
+        for bracket in &self.brackets[first..last] {
+            depth += if bracket.open { 1 } else { -1 };
+            lowest = lowest.min(depth);
+        }
+
+        // Closing brackets without an opening one inside: take in the
+        // nearest unmatched opening brackets before the span
+        let (mut missing, mut nested) = (-lowest, 0);
+        for bracket in self.brackets[..first].iter().rev() {
+            if missing == 0 {
+                break;
+            }
+            if !bracket.open {
+                nested += 1;
+            } else if nested > 0 {
+                nested -= 1;
+            } else {
+                missing -= 1;
+                span.start = bracket.span.start;
+            }
+        }
+
+        // Opening brackets that aren't closed inside: take in their closers
+        let (mut missing, mut nested) = (depth - lowest, 0);
+        for bracket in &self.brackets[last..] {
+            if missing == 0 {
+                break;
+            }
+            if bracket.open {
+                nested += 1;
+            } else if nested > 0 {
+                nested -= 1;
+            } else {
+                missing -= 1;
+                span.end = bracket.span.end;
+                span.end_line = bracket.span.end_line;
+                span.end_column = bracket.span.end_column;
+            }
+        }
+        span
+    }
+}
+
+/// Give `expr` and everything in it `span`. Expressions parsed from a
+/// substring, like those inside f-strings, have no spans of their own in the
+/// file, so they take the span of the token they came from.
+pub(super) fn set_spans(expr: &mut Expr, span: Span) {
+    *expr.span_mut() = span;
+    for child in expr_children(expr) {
+        set_spans(child, span);
+    }
+}
+
+/// Move `stmt` and everything in it `lines` lines and `bytes` bytes further
+/// into the source, as when an edit before it changes the text's length.
+/// Columns stay the same, so `stmt` must start on a line of its own.
+pub(super) fn shift_stmt(stmt: &mut Stmt, lines: isize, bytes: isize) {
+    *stmt.line_mut() = stmt.line().saturating_add_signed(lines);
+    shift_span(stmt.span_mut(), lines, bytes);
+    if let Stmt::Try { handlers, .. } = stmt {
+        for handler in handlers {
+            handler.line = handler.line.saturating_add_signed(lines);
+        }
+    }
+    let (exprs, blocks) = stmt_parts(stmt);
+    for expr in exprs {
+        shift_expr(expr, lines, bytes);
+    }
+    for block in blocks {
+        for child in block.iter_mut() {
+            shift_stmt(child, lines, bytes);
+        }
+    }
+}
+
+fn shift_expr(expr: &mut Expr, lines: isize, bytes: isize) {
+    *expr.line_mut() = expr.line().saturating_add_signed(lines);
+    shift_span(expr.span_mut(), lines, bytes);
+    for child in expr_children(expr) {
+        shift_expr(child, lines, bytes);
+    }
+}
+
+fn shift_span(span: &mut Span, lines: isize, bytes: isize) {
+    // Nodes the parser built without a place in the source have no span to move
+    if *span == Span::default() {
+        return;
+    }
+    span.start = span.start.saturating_add_signed(bytes);
+    span.end = span.end.saturating_add_signed(bytes);
+    span.end_line = span.end_line.saturating_add_signed(lines);
+}
+
+/// Extend `span` to cover `child`
+fn cover(span: &mut Span, child: Span) {
+    if child.is_empty() {
+        return;
+    }
+    if span.is_empty() {
+        *span = child;
+        return;
+    }
+    span.start = span.start.min(child.start);
+    if child.end > span.end {
+        span.end = child.end;
+        span.end_line = child.end_line;
+        span.end_column = child.end_column;
+    }
+}
+
+/// The expressions and blocks directly inside `stmt`
+#[allow(clippy::vec_box)]
+fn stmt_parts(stmt: &mut Stmt) -> (Vec<&mut Expr>, Vec<&mut Vec<Box<Stmt>>>) {
+    let mut exprs: Vec<&mut Expr> = Vec::new();
+    let mut blocks: Vec<&mut Vec<Box<Stmt>>> = Vec::new();
+    match stmt {
+        Stmt::FunctionDef {
+            params,
+            body,
+            decorator_list,
+            returns,
+            ..
+        } => {
+            exprs.extend(decorator_list.iter_mut().map(|e| &mut **e));
+            for param in params {
+                exprs.extend(param.typ.as_deref_mut());
+                exprs.extend(param.default.as_deref_mut());
+            }
+            exprs.extend(returns.as_deref_mut());
+            blocks.push(body);
+        }
+        Stmt::ClassDef {
+            bases,
+            keywords,
+            body,
+            decorator_list,
+            ..
+        } => {
+            exprs.extend(decorator_list.iter_mut().map(|e| &mut **e));
+            exprs.extend(bases.iter_mut().map(|e| &mut **e));
+            exprs.extend(keywords.iter_mut().map(|(_, e)| &mut **e));
+            blocks.push(body);
+        }
+        Stmt::Return { value, .. } => exprs.extend(value.as_deref_mut()),
+        Stmt::Delete { targets, .. } => exprs.extend(targets.iter_mut().map(|e| &mut **e)),
+        Stmt::Assign { targets, value, .. } => {
+            exprs.extend(targets.iter_mut().map(|e| &mut **e));
+            exprs.push(value);
+        }
+        Stmt::AugAssign { target, value, .. } => {
+            exprs.push(target);
+            exprs.push(value);
+        }
+        Stmt::AnnAssign {
+            target,
+            annotation,
+            value,
+            ..
+        } => {
+            exprs.push(target);
+            exprs.push(annotation);
+            exprs.extend(value.as_deref_mut());
+        }
+        Stmt::For {
+            target,
+            iter,
+            body,
+            orelse,
+            ..
+        } => {
+            exprs.push(target);
+            exprs.push(iter);
+            blocks.push(body);
+            blocks.push(orelse);
+        }
+        Stmt::While {
+            test, body, orelse, ..
+        }
+        | Stmt::If {
+            test, body, orelse, ..
+        } => {
+            exprs.push(test);
+            blocks.push(body);
+            blocks.push(orelse);
+        }
+        Stmt::With { items, body, .. } => {
+            for (context, target) in items {
+                exprs.push(context);
+                exprs.extend(target.as_deref_mut());
+            }
+            blocks.push(body);
+        }
+        Stmt::Raise { exc, cause, .. } => {
+            exprs.extend(exc.as_deref_mut());
+            exprs.extend(cause.as_deref_mut());
+        }
+        Stmt::Try {
+            body,
+            handlers,
+            orelse,
+            finalbody,
+            ..
+        } => {
+            blocks.push(body);
+            for handler in handlers {
+                exprs.extend(handler.typ.as_deref_mut());
+                blocks.push(&mut handler.body);
+            }
+            blocks.push(orelse);
+            blocks.push(finalbody);
+        }
+        Stmt::Assert { test, msg, .. } => {
+            exprs.push(test);
+            exprs.extend(msg.as_deref_mut());
+        }
+        Stmt::Expr { value, .. } => exprs.push(value),
+        Stmt::Match { subject, cases, .. } => {
+            exprs.push(subject);
+            for (pattern, guard, body) in cases {
+                exprs.push(pattern);
+                exprs.extend(guard.as_deref_mut());
+                blocks.push(body);
+            }
+        }
+        Stmt::Import { .. }
+        | Stmt::ImportFrom { .. }
+        | Stmt::Global { .. }
+        | Stmt::Nonlocal { .. }
+        | Stmt::Pass { .. }
+        | Stmt::Break { .. }
+        | Stmt::Continue { .. } => {}
+    }
+    (exprs, blocks)
+}
+
+/// The expressions directly inside `expr`
+fn expr_children(expr: &mut Expr) -> Vec<&mut Expr> {
+    let mut children: Vec<&mut Expr> = Vec::new();
+    match expr {
+        Expr::BoolOp { values, .. } | Expr::JoinedStr { values, .. } => {
+            children.extend(values.iter_mut().map(|e| &mut **e))
+        }
+        Expr::BinOp { left, right, .. } => {
+            children.push(left);
+            children.push(right);
+        }
+        Expr::Slice {
+            lower, upper, step, ..
+        } => {
+            children.extend(lower.as_deref_mut());
+            children.extend(upper.as_deref_mut());
+            children.extend(step.as_deref_mut());
+        }
+        Expr::UnaryOp { operand, .. } => children.push(operand),
+        Expr::Lambda { args, body, .. } => {
+            for arg in args {
+                children.extend(arg.default.as_deref_mut());
+            }
+            children.push(body);
+        }
+        Expr::IfExp {
+            test, body, orelse, ..
+        } => {
+            children.push(body);
+            children.push(test);
+            children.push(orelse);
+        }
+        Expr::Dict { keys, values, .. } => {
+            children.extend(keys.iter_mut().flatten().map(|e| &mut **e));
+            children.extend(values.iter_mut().map(|e| &mut **e));
+        }
+        Expr::Set { elts, .. } | Expr::List { elts, .. } | Expr::Tuple { elts, .. } => {
+            children.extend(elts.iter_mut().map(|e| &mut **e))
+        }
+        Expr::ListComp {
+            elt, generators, ..
+        }
+        | Expr::SetComp {
+            elt, generators, ..
+        }
+        | Expr::GeneratorExp {
+            elt, generators, ..
+        } => {
+            children.push(elt);
+            for generator in generators {
+                children.push(&mut generator.target);
+                children.push(&mut generator.iter);
+                children.extend(generator.ifs.iter_mut().map(|e| &mut **e));
+            }
+        }
+        Expr::DictComp {
+            key,
+            value,
+            generators,
+            ..
+        } => {
+            children.push(key);
+            children.push(value);
+            for generator in generators {
+                children.push(&mut generator.target);
+                children.push(&mut generator.iter);
+                children.extend(generator.ifs.iter_mut().map(|e| &mut **e));
+            }
+        }
+        Expr::Await { value, .. }
+        | Expr::YieldFrom { value, .. }
+        | Expr::Attribute { value, .. }
+        | Expr::Starred { value, .. } => children.push(value),
+        Expr::Yield { value, .. } => children.extend(value.as_deref_mut()),
+        Expr::Compare {
+            left, comparators, ..
+        } => {
+            children.push(left);
+            children.extend(comparators.iter_mut().map(|e| &mut **e));
+        }
+        Expr::Call {
+            func,
+            args,
+            keywords,
+            ..
+        } => {
+            children.push(func);
+            children.extend(args.iter_mut().map(|e| &mut **e));
+            children.extend(keywords.iter_mut().map(|(_, e)| &mut **e));
+        }
+        Expr::FormattedValue {
+            value, format_spec, ..
+        } => {
+            children.push(value);
+            children.extend(format_spec.as_deref_mut());
+        }
+        Expr::Subscript { value, slice, .. } => {
+            children.push(value);
+            children.push(slice);
+        }
+        Expr::NamedExpr { target, value, .. } => {
+            children.push(target);
+            children.push(value);
+        }
+        Expr::Num { .. }
+        | Expr::Str { .. }
+        | Expr::Bytes { .. }
+        | Expr::NameConstant { .. }
+        | Expr::Ellipsis { .. }
+        | Expr::Constant { .. }
+        | Expr::Name { .. } => {}
+    }
+    children
+}
@@ -113,7 +113,7 @@ pub trait StmtParser {
     fn with_store_context(&self, expr: Expr) -> Result<Expr, ParseError>;
 }
 
-impl StmtParser for Parser {
+impl StmtParser for Parser<'_> {
     fn parse_statement(&mut self) -> Result<Stmt, ParseError> {
         let token_type;
         let line;
@@ -132,7 +132,7 @@ impl StmtParser for Parser {
 
         if matches!(token_type, TokenType::SemiColon) {
             self.advance();
-            return Ok(Stmt::Pass { line, column });
+            return Ok(Stmt::Pass { line, column, span: self.span_from(line, column) });
         }
 
         if matches!(
@@ -193,6 +193,7 @@ impl StmtParser for Parser {
                 value: Box::new(expr),
                 line: expr_line,
                 column: expr_column,
+                span: self.span_from(expr_line, expr_column),
             });
         }
 
@@ -207,6 +208,7 @@ impl StmtParser for Parser {
                 value: Box::new(yield_expr),
                 line,
                 column,
+                span: self.span_from(line, column),
             });
         }
 
@@ -359,6 +361,7 @@ impl StmtParser for Parser {
             is_async: false,
             line,
             column,
+            span: self.span_from(line, column),
         })
     }
 
@@ -625,7 +628,7 @@ impl StmtParser for Parser {
 
                 if let Some(id_token) = &self.current {
                     if let TokenType::Identifier(name) = &id_token.token_type {
-                        let args_name = name.clone();
+                        let args_name = name.to_string();
                         self.advance();
 
                         bases.push(Box::new(Expr::Starred {
@@ -634,10 +637,12 @@ impl StmtParser for Parser {
                                 ctx: ExprContext::Load,
                                 line,
                                 column: column + 1,
+                                span: self.span_from(line, column + 1),
                             }),
                             ctx: ExprContext::Load,
                             line,
                             column,
+                            span: self.span_from(line, column),
                         }));
 
                         return Ok(());
@@ -656,7 +661,7 @@ impl StmtParser for Parser {
 
                 if let Some(id_token) = &self.current {
                     if let TokenType::Identifier(name) = &id_token.token_type {
-                        let kwargs_name = name.clone();
+                        let kwargs_name = name.to_string();
                         self.advance();
 
                         keywords.push((
@@ -666,6 +671,7 @@ impl StmtParser for Parser {
                                 ctx: ExprContext::Load,
                                 line,
                                 column: column + 2,
+                                span: self.span_from(line, column + 2),
                             }),
                         ));
 
@@ -692,7 +698,7 @@ impl StmtParser for Parser {
             }
 
             TokenType::Identifier(name) => {
-                let id_name = name.clone();
+                let id_name = name.to_string();
                 self.advance();
 
                 if let Some(token) = &self.current {
@@ -722,11 +728,13 @@ impl StmtParser for Parser {
                                 ctx: ExprContext::Load,
                                 line,
                                 column,
+                                span: self.span_from(line, column),
                             }),
                             args,
                             keywords: kw_args,
                             line,
                             column,
+                            span: self.span_from(line, column),
                         }));
                         return Ok(());
                     }
@@ -737,6 +745,7 @@ impl StmtParser for Parser {
                     ctx: ExprContext::Load,
                     line,
                     column,
+                    span: self.span_from(line, column),
                 }));
                 return Ok(());
             }
@@ -800,6 +809,7 @@ impl StmtParser for Parser {
             decorator_list: Vec::new(),
             line,
             column,
+            span: self.span_from(line, column),
         })
     }
 
@@ -831,6 +841,7 @@ impl StmtParser for Parser {
             value,
             line,
             column,
+            span: self.span_from(line, column),
         })
     }
 
@@ -849,6 +860,7 @@ impl StmtParser for Parser {
             targets,
             line,
             column,
+            span: self.span_from(line, column),
         })
     }
 
@@ -913,6 +925,7 @@ impl StmtParser for Parser {
             orelse,
             line,
             column,
+            span: self.span_from(line, column),
         })
     }
 
@@ -943,6 +956,7 @@ impl StmtParser for Parser {
                     ctx: ExprContext::Store,
                     line: id_line,
                     column: id_column,
+                    span: parser.span_from(id_line, id_column),
                 };
 
                 parser.advance();
@@ -960,6 +974,7 @@ impl StmtParser for Parser {
                             ctx: ExprContext::Store,
                             line: next_line,
                             column: next_column,
+                            span: parser.span_from(next_line, next_column),
                         }));
                     } else if parser.check(TokenType::LeftParen) {
                         let nested_expr = parser.parse_atom_expr()?;
@@ -983,6 +998,7 @@ impl StmtParser for Parser {
                     ctx: ExprContext::Store,
                     line: id_line,
                     column: id_column,
+                    span: parser.span_from(id_line, id_column),
                 }))
             } else {
                 let expr = parser.parse_atom_expr()?;
@@ -1013,6 +1029,7 @@ impl StmtParser for Parser {
             is_async: false,
             line,
             column,
+            span: self.span_from(line, column),
         })
     }
 
@@ -1042,6 +1059,7 @@ impl StmtParser for Parser {
             orelse,
             line,
             column,
+            span: self.span_from(line, column),
         })
     }
 
@@ -1089,6 +1107,7 @@ impl StmtParser for Parser {
             is_async: false,
             line,
             column,
+            span: self.span_from(line, column),
         })
     }
 
@@ -1155,6 +1174,7 @@ impl StmtParser for Parser {
             finalbody,
             line,
             column,
+            span: self.span_from(line, column),
         })
     }
 
@@ -1184,6 +1204,7 @@ impl StmtParser for Parser {
             cause,
             line,
             column,
+            span: self.span_from(line, column),
         })
     }
 
@@ -1194,7 +1215,8 @@ impl StmtParser for Parser {
 
         self.advance();
 
-        let test = Box::new(self.parse_expression()?);
+        // Not parse_expression, which would read `test, msg` as a tuple
+        let test = Box::new(self.nested(|parser| parser.parse_or_test())?);
 
         let msg = if self.match_token(TokenType::Comma) {
             Some(Box::new(self.parse_expression()?))
@@ -1209,6 +1231,7 @@ impl StmtParser for Parser {
             msg,
             line,
             column,
+            span: self.span_from(line, column),
         })
     }
 
@@ -1245,6 +1268,7 @@ impl StmtParser for Parser {
             names,
             line,
             column,
+            span: self.span_from(line, column),
         })
     }
 
@@ -1328,6 +1352,7 @@ impl StmtParser for Parser {
             level,
             line,
             column,
+            span: self.span_from(line, column),
         })
     }
 
@@ -1380,6 +1405,7 @@ impl StmtParser for Parser {
             names,
             line,
             column,
+            span: self.span_from(line, column),
         })
     }
 
@@ -1398,6 +1424,7 @@ impl StmtParser for Parser {
             names,
             line,
             column,
+            span: self.span_from(line, column),
         })
     }
 
@@ -1428,7 +1455,7 @@ impl StmtParser for Parser {
 
         self.consume_newline()?;
 
-        Ok(Stmt::Pass { line, column })
+        Ok(Stmt::Pass { line, column, span: self.span_from(line, column) })
     }
 
     fn parse_break(&mut self) -> Result<Stmt, ParseError> {
@@ -1449,7 +1476,7 @@ impl StmtParser for Parser {
 
         self.consume_newline()?;
 
-        Ok(Stmt::Break { line, column })
+        Ok(Stmt::Break { line, column, span: self.span_from(line, column) })
     }
 
     fn parse_continue(&mut self) -> Result<Stmt, ParseError> {
@@ -1470,7 +1497,7 @@ impl StmtParser for Parser {
 
         self.consume_newline()?;
 
-        Ok(Stmt::Continue { line, column })
+        Ok(Stmt::Continue { line, column, span: self.span_from(line, column) })
     }
 
     fn parse_match(&mut self) -> Result<Stmt, ParseError> {
@@ -1510,6 +1537,7 @@ impl StmtParser for Parser {
             cases,
             line,
             column,
+            span: self.span_from(line, column),
         })
     }
 
@@ -1545,10 +1573,12 @@ impl StmtParser for Parser {
                     ctx: ExprContext::Store,
                     line: star_line,
                     column: star_column + 1,
+                    span: self.span_from(star_line, star_column + 1),
                 }),
                 ctx: ExprContext::Store,
                 line: star_line,
                 column: star_column,
+                span: self.span_from(star_line, star_column),
             };
 
             if self.match_token(TokenType::Comma) {
@@ -1557,6 +1587,7 @@ impl StmtParser for Parser {
                     ctx: ExprContext::Store,
                     line: star_line,
                     column: star_column,
+                    span: self.span_from(star_line, star_column),
                 };
 
                 self.consume(TokenType::Assign, "=")?;
@@ -1568,6 +1599,7 @@ impl StmtParser for Parser {
                     value,
                     line: star_line,
                     column: star_column,
+                    span: self.span_from(star_line, star_column),
                 });
             }
 
@@ -1580,6 +1612,7 @@ impl StmtParser for Parser {
                 value,
                 line: star_line,
                 column: star_column,
+                span: self.span_from(star_line, star_column),
             });
         }
 
@@ -1593,6 +1626,7 @@ impl StmtParser for Parser {
                 ctx: ExprContext::Store,
                 line,
                 column,
+                span: self.span_from(line, column),
             })];
 
             self.advance();
@@ -1622,10 +1656,12 @@ impl StmtParser for Parser {
                                 ctx: ExprContext::Store,
                                 line: star_line,
                                 column: star_column,
+                                span: self.span_from(star_line, star_column),
                             }),
                             ctx: ExprContext::Store,
                             line: star_line,
                             column: star_column - 1,
+                            span: self.span_from(star_line, star_column - 1),
                         }));
                     } else {
                         return Err(ParseError::InvalidSyntax {
@@ -1645,6 +1681,7 @@ impl StmtParser for Parser {
                         ctx: ExprContext::Store,
                         line: item_line,
                         column: item_column,
+                        span: self.span_from(item_line, item_column),
                     }));
                 } else {
                     elts.push(Box::new(self.parse_atom_expr()?));
@@ -1660,6 +1697,7 @@ impl StmtParser for Parser {
                 ctx: ExprContext::Store,
                 line,
                 column,
+                span: self.span_from(line, column),
             };
 
             self.consume(TokenType::Assign, "=")?;
@@ -1680,6 +1718,7 @@ impl StmtParser for Parser {
                 value: Box::new(current_expr),
                 line,
                 column,
+                span: self.span_from(line, column),
             });
         }
 
@@ -1707,6 +1746,7 @@ impl StmtParser for Parser {
                 value: Box::new(current_expr),
                 line,
                 column,
+                span: self.span_from(line, column),
             });
         } else if self.is_augmented_assign() {
             match &expr {
@@ -1733,6 +1773,7 @@ impl StmtParser for Parser {
                 value,
                 line,
                 column,
+                span: self.span_from(line, column),
             });
         } else if self.match_token(TokenType::Colon) {
             match &expr {
@@ -1763,6 +1804,7 @@ impl StmtParser for Parser {
                 value,
                 line,
                 column,
+                span: self.span_from(line, column),
             });
         } else {
             self.consume_newline()?;
@@ -1771,6 +1813,7 @@ impl StmtParser for Parser {
                 value: Box::new(expr),
                 line,
                 column,
+                span: self.span_from(line, column),
             });
         }
     }
@@ -1916,7 +1959,8 @@ impl StmtParser for Parser {
                         });
                     }
 
-                    let stmt = self.parse_statement()?;
+                    let mut stmt = self.parse_statement()?;
+                    self.finish_stmt_spans(&mut stmt);
                     statements.push(Box::new(stmt));
 
                     if self.current.is_none() || self.check(TokenType::Dedent) {
@@ -1955,8 +1999,9 @@ impl StmtParser for Parser {
                 ))
             }
         } else {
-            let stmt = Box::new(self.parse_statement()?);
-            Ok(vec![stmt])
+            let mut stmt = self.parse_statement()?;
+            self.finish_stmt_spans(&mut stmt);
+            Ok(vec![Box::new(stmt)])
         }
     }
 
@@ -1967,17 +2012,20 @@ impl StmtParser for Parser {
                 ctx: _,
                 line,
                 column,
+                span,
             } => Ok(Expr::Name {
                 id,
                 ctx: ExprContext::Store,
                 line,
                 column,
+                span,
             }),
             Expr::Tuple {
                 elts,
                 ctx: _,
                 line,
                 column,
+                span,
             } => {
                 let mut new_elts = Vec::new();
                 for elt in elts {
@@ -1988,6 +2036,7 @@ impl StmtParser for Parser {
                     ctx: ExprContext::Store,
                     line,
                     column,
+                    span,
                 })
             }
             Expr::List {
@@ -1995,6 +2044,7 @@ impl StmtParser for Parser {
                 ctx: _,
                 line,
                 column,
+                span,
             } => {
                 let mut new_elts = Vec::new();
                 for elt in elts {
@@ -2005,6 +2055,7 @@ impl StmtParser for Parser {
                     ctx: ExprContext::Store,
                     line,
                     column,
+                    span,
                 })
             }
             Expr::Starred {
@@ -2012,11 +2063,13 @@ impl StmtParser for Parser {
                 ctx: _,
                 line,
                 column,
+                span,
             } => Ok(Expr::Starred {
                 value: Box::new(self.with_store_context(*value)?),
                 ctx: ExprContext::Store,
                 line,
                 column,
+                span,
             }),
             Expr::Subscript {
                 value,
@@ -2024,12 +2077,14 @@ impl StmtParser for Parser {
                 ctx: _,
                 line,
                 column,
+                span,
             } => Ok(Expr::Subscript {
                 value,
                 slice,
                 ctx: ExprContext::Store,
                 line,
                 column,
+                span,
             }),
             Expr::Attribute {
                 value,
@@ -2037,12 +2092,14 @@ impl StmtParser for Parser {
                 ctx: _,
                 line,
                 column,
+                span,
             } => Ok(Expr::Attribute {
                 value,
                 attr,
                 ctx: ExprContext::Store,
                 line,
                 column,
+                span,
             }),
             _ => Err(ParseError::invalid_syntax(
                 "Invalid target for assignment",
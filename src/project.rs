@@ -0,0 +1,252 @@
+// project.rs - cheetah.toml project manifests
+
+use crate::lexer::{Construct, LexerConfig};
+use crate::linter::{LintConfig, Severity};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name of a project manifest
+pub const MANIFEST_NAME: &str = "cheetah.toml";
+
+/// Entry modules tried, in order, when the manifest doesn't name one
+const DEFAULT_ENTRIES: [&str; 2] = ["main.ch", "src/main.ch"];
+
+/// Contents of a `cheetah.toml`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub package: Package,
+    #[serde(default)]
+    pub build: BuildSettings,
+    #[serde(default)]
+    pub language: LanguageSettings,
+    #[serde(default)]
+    pub format: FormatSettings,
+    /// The `[lint]` table: a level (`allow`, `warn` or `error`) for each rule
+    /// code, or for `all` rules
+    #[serde(default)]
+    pub lint: BTreeMap<String, String>,
+}
+
+/// The `[package]` table
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Package {
+    /// Name of the built executable
+    pub name: String,
+    /// Entry module, relative to the project directory
+    pub entry: Option<String>,
+}
+
+/// The `[build]` table: settings used whenever the project is built or run
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct BuildSettings {
+    /// Optimization level (0-3)
+    pub opt: u8,
+    /// Emit DWARF debug info
+    pub debug: bool,
+    /// Libraries to link against, as with `-l`
+    pub link_libs: Vec<String>,
+    /// Library search directories relative to the project, as with `-L`
+    pub link_paths: Vec<String>,
+    /// Line endings the program writes to stdout, as with `--newline`
+    pub newline: Option<String>,
+}
+
+/// The `[language]` table: constructs the project's code may not use
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LanguageSettings {
+    /// Named set of forbidden constructs, `full` or `learning`
+    pub profile: Option<String>,
+    /// Constructs forbidden on top of the profile's
+    pub forbid: Vec<String>,
+    /// Constructs the profile forbids that are allowed after all
+    pub allow: Vec<String>,
+}
+
+/// The `[format]` table: the layout `format` produces and the lexer expects
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct FormatSettings {
+    /// Spaces per indentation level
+    pub indent_size: usize,
+    /// Width `format` keeps lines within, as with `--line-width`
+    pub line_width: Option<usize>,
+    /// Columns a tab in indentation counts for
+    pub tab_width: usize,
+    /// Accept tabs in indentation rather than reporting them
+    pub allow_tabs: bool,
+}
+
+impl Default for FormatSettings {
+    fn default() -> Self {
+        let lexer = LexerConfig::default();
+        FormatSettings {
+            indent_size: lexer.standard_indent_size,
+            line_width: None,
+            tab_width: lexer.tab_width,
+            allow_tabs: lexer.allow_tabs_in_indentation,
+        }
+    }
+}
+
+impl LanguageSettings {
+    /// The constructs forbidden by the profile and `forbid`, less those in `allow`
+    pub fn forbidden(&self) -> Result<Vec<Construct>, String> {
+        let mut forbidden = match &self.profile {
+            Some(profile) => Construct::profile(profile)?,
+            None => Vec::new(),
+        };
+        for name in &self.forbid {
+            let construct = Construct::from_name(name)?;
+            if !forbidden.contains(&construct) {
+                forbidden.push(construct);
+            }
+        }
+        for name in &self.allow {
+            let construct = Construct::from_name(name)?;
+            forbidden.retain(|forbidden| *forbidden != construct);
+        }
+        Ok(forbidden)
+    }
+}
+
+impl Manifest {
+    /// Lint severities from the `[lint]` table; `all` applies before single rules
+    pub fn lint_config(&self) -> Result<LintConfig, String> {
+        let mut config = LintConfig::default();
+        let levels = self.lint.iter().filter(|(rule, _)| *rule == "all");
+        for (rule, level) in levels.chain(self.lint.iter().filter(|(rule, _)| *rule != "all")) {
+            let severity = Severity::from_name(level).ok_or_else(|| {
+                format!(
+                    "unknown level '{}' for {} (expected error, warn or allow)",
+                    level, rule
+                )
+            })?;
+            config.set(rule, severity)?;
+        }
+        Ok(config)
+    }
+}
+
+/// A project directory and its manifest
+#[derive(Debug, Clone)]
+pub struct Project {
+    pub root: PathBuf,
+    pub manifest: Manifest,
+}
+
+impl Project {
+    /// Load the project whose manifest is in `dir`
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(MANIFEST_NAME);
+        let source = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let manifest = Self::parse_manifest(&source)
+            .map_err(|e| format!("Invalid {}: {}", path.display(), e))?;
+        Ok(Self {
+            root: dir.to_path_buf(),
+            manifest,
+        })
+    }
+
+    /// Parse the contents of a manifest
+    pub fn parse_manifest(source: &str) -> Result<Manifest, String> {
+        let manifest: Manifest = toml::from_str(source).map_err(|e| e.message().to_string())?;
+        if manifest.build.opt > 3 {
+            return Err(format!(
+                "build.opt must be between 0 and 3, not {}",
+                manifest.build.opt
+            ));
+        }
+        manifest
+            .language
+            .forbidden()
+            .map_err(|e| format!("language: {}", e))?;
+        let format = &manifest.format;
+        if format.indent_size == 0 || format.tab_width == 0 {
+            return Err("format.indent-size and format.tab-width must be at least 1".to_string());
+        }
+        manifest.lint_config().map_err(|e| format!("lint: {}", e))?;
+        Ok(manifest)
+    }
+
+    /// The project whose manifest is in the directory of `file` or the
+    /// nearest directory above it, if there is one
+    pub fn containing(file: &Path) -> Result<Option<Self>, String> {
+        let Ok(file) = fs::canonicalize(file) else {
+            return Ok(None);
+        };
+        match file
+            .ancestors()
+            .skip(1)
+            .find(|dir| dir.join(MANIFEST_NAME).is_file())
+        {
+            Some(dir) => Self::load(dir).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Constructs the project's code may not use
+    pub fn forbidden_constructs(&self) -> Vec<Construct> {
+        // Checked when the manifest was parsed
+        self.manifest.language.forbidden().unwrap_or_default()
+    }
+
+    /// Lexer settings for the project's code: its tab policy, indent size and
+    /// forbidden constructs
+    pub fn lexer_config(&self) -> LexerConfig {
+        let format = &self.manifest.format;
+        LexerConfig {
+            tab_width: format.tab_width,
+            standard_indent_size: format.indent_size,
+            allow_tabs_in_indentation: format.allow_tabs,
+            forbidden: self.forbidden_constructs(),
+            ..LexerConfig::default()
+        }
+    }
+
+    /// Lint severities the project sets
+    pub fn lint_config(&self) -> LintConfig {
+        // Checked when the manifest was parsed
+        self.manifest.lint_config().unwrap_or_default()
+    }
+
+    /// Path of the entry module: the manifest's `entry`, otherwise `main.ch`
+    /// or `src/main.ch`, whichever exists
+    pub fn entry_path(&self) -> Result<PathBuf, String> {
+        if let Some(entry) = &self.manifest.package.entry {
+            let path = self.root.join(entry);
+            return if path.is_file() {
+                Ok(path)
+            } else {
+                Err(format!("Entry module {} does not exist", path.display()))
+            };
+        }
+
+        DEFAULT_ENTRIES
+            .iter()
+            .map(|entry| self.root.join(entry))
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                format!(
+                    "{} has no main.ch or src/main.ch; set `entry` in [package]",
+                    self.root.display()
+                )
+            })
+    }
+
+    /// Library search directories from the manifest, resolved against the project
+    pub fn link_paths(&self) -> Vec<String> {
+        self.manifest
+            .build
+            .link_paths
+            .iter()
+            .map(|path| self.root.join(path).to_string_lossy().into_owned())
+            .collect()
+    }
+}
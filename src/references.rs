@@ -0,0 +1,259 @@
+// references.rs - Finding the references to a name and renaming it
+//
+// Built on the symbol table: every place a name is written is an occurrence
+// in some scope, and the occurrences that resolve to the same symbol are the
+// references to it. Renaming replaces each of them:
+//
+//     let edits = references::rename(source, 4, 9, "count")?;
+//     let renamed = references::apply_edits(source, &edits);
+//
+// Parameters, imports and `global` declarations have no position of their
+// own in the syntax tree, so their names are found in the tokens of the
+// statement that introduces them.
+
+use crate::ast::Span;
+use crate::lexer::{keywords, Token, TokenType};
+use crate::parser;
+use crate::symtable::{Occurrence, Position, ScopeId, SymbolTable, SymbolType};
+
+/// A place where a name is written
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub line: usize,
+    pub column: usize,
+    /// Column just past the name
+    pub end_column: usize,
+    /// Byte offsets of the name in the source
+    pub span: Span,
+    /// Whether the name is bound here, as by an assignment, `def` or import
+    pub is_definition: bool,
+}
+
+/// A replacement of part of the source
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    pub line: usize,
+    pub column: usize,
+    pub end_column: usize,
+    /// Byte offsets of the replaced text in the source
+    pub span: Span,
+    pub new_text: String,
+}
+
+/// A parsed module with its tokens and symbol table
+struct Analysis<'a> {
+    tokens: Vec<Token<'a>>,
+    table: SymbolTable,
+}
+
+impl<'a> Analysis<'a> {
+    fn new(source: &'a str) -> Result<Self, String> {
+        let syntax_error = |errors: Vec<parser::ParseError>| {
+            errors
+                .first()
+                .map(|error| error.get_message())
+                .unwrap_or_else(|| "invalid syntax".to_string())
+        };
+        let tokens = parser::tokenize(source).map_err(syntax_error)?;
+        let module = parser::parse(tokens.clone()).map_err(syntax_error)?;
+        Ok(Analysis {
+            tokens,
+            table: SymbolTable::build(&module),
+        })
+    }
+
+    /// The name written at `position`
+    fn name_at(&self, position: Position) -> Option<String> {
+        let occurrence = self
+            .table
+            .occurrences()
+            .iter()
+            .rev()
+            .find(|occurrence| occurrence.covers(position));
+        if let Some(occurrence) = occurrence {
+            return Some(occurrence.name.clone());
+        }
+        // Parameters and imported names only have a token
+        self.tokens.iter().find_map(|token| match &token.token_type {
+            TokenType::Identifier(id)
+                if token.line == position.line
+                    && token.column <= position.column
+                    && position.column < token.column + id.chars().count() =>
+            {
+                Some(id.to_string())
+            }
+            _ => None,
+        })
+    }
+
+    /// The scope a name written at `position` is looked up from
+    fn scope_at(&self, position: Position) -> ScopeId {
+        self.table
+            .occurrences()
+            .iter()
+            .rev()
+            .find(|occurrence| occurrence.covers(position))
+            .map(|occurrence| occurrence.scope)
+            .unwrap_or_else(|| self.table.scope_at_position(position.line, position.column))
+    }
+
+    /// The references to the symbol `name` resolves to in scope `scope`
+    fn references(&self, scope: ScopeId, name: &str) -> Result<Vec<Reference>, String> {
+        let (target, _) = self
+            .table
+            .resolve(scope, name)
+            .ok_or_else(|| format!("'{}' is not defined here", name))?;
+        let occurrences: Vec<&Occurrence> = self
+            .table
+            .occurrences()
+            .iter()
+            .filter(|occurrence| occurrence.name == name)
+            .collect();
+        let tokens = locate(&self.tokens, &occurrences);
+
+        let mut references = Vec::new();
+        for (occurrence, token) in occurrences.iter().zip(tokens) {
+            let resolved = self.table.resolve(occurrence.scope, name).map(|(id, _)| id);
+            if resolved != Some(target) {
+                continue;
+            }
+            let token = token.map(|index| &self.tokens[index]).ok_or_else(|| {
+                format!(
+                    "can't find where '{}' is written on line {}",
+                    name, occurrence.line
+                )
+            })?;
+            references.push(Reference {
+                line: token.line,
+                column: token.column,
+                end_column: token.column + name.chars().count(),
+                span: token.span,
+                is_definition: occurrence.is_definition,
+            });
+        }
+        references.sort_by_key(|reference| (reference.line, reference.column));
+        references.dedup_by_key(|reference| (reference.line, reference.column));
+        Ok(references)
+    }
+}
+
+/// The references to the symbol `name` refers to at `line`, `column` of
+/// `source`, definitions included, in source order
+pub fn find_references(source: &str, name: &str, line: usize, column: usize) -> Result<Vec<Reference>, String> {
+    let analysis = Analysis::new(source)?;
+    let scope = analysis.scope_at(Position::new(line, column));
+    analysis.references(scope, name)
+}
+
+/// Edits renaming the symbol whose name is written at `line`, `column` of
+/// `source` to `new_name` everywhere it is referenced
+pub fn rename(source: &str, line: usize, column: usize, new_name: &str) -> Result<Vec<TextEdit>, String> {
+    if !is_identifier(new_name) {
+        return Err(format!("'{}' is not a valid name", new_name));
+    }
+    let analysis = Analysis::new(source)?;
+    let position = Position::new(line, column);
+    let name = analysis
+        .name_at(position)
+        .ok_or_else(|| format!("no name at {}:{}", line, column))?;
+    let scope = analysis.scope_at(position);
+    let (target, symbol) = analysis
+        .table
+        .resolve(scope, &name)
+        .ok_or_else(|| format!("'{}' is not defined here", name))?;
+    if name == new_name {
+        return Ok(Vec::new());
+    }
+    if analysis.table.scope(target).symbol(new_name).is_some_and(|other| other.is_defined) {
+        return Err(format!("'{}' is already defined in the same scope", new_name));
+    }
+    let imported = matches!(symbol.symbol_type, SymbolType::Import | SymbolType::ImportFrom);
+
+    let references = analysis.references(scope, &name)?;
+    Ok(references
+        .into_iter()
+        .map(|reference| {
+            // An import keeps the name it imports and binds it under the new one
+            let aliased = imported && reference.is_definition && !follows_as(&analysis.tokens, reference.span);
+            let new_text = match aliased {
+                true => format!("{} as {}", name, new_name),
+                false => new_name.to_string(),
+            };
+            TextEdit {
+                line: reference.line,
+                column: reference.column,
+                end_column: reference.end_column,
+                span: reference.span,
+                new_text,
+            }
+        })
+        .collect())
+}
+
+/// `source` with `edits` applied; the edits must not overlap
+pub fn apply_edits(source: &str, edits: &[TextEdit]) -> String {
+    let mut edits: Vec<&TextEdit> = edits.iter().collect();
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.span.start));
+    let mut text = source.to_string();
+    for edit in edits {
+        text.replace_range(edit.span.start..edit.span.end, &edit.new_text);
+    }
+    text
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|first| first.is_alphabetic() || first == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+        && keywords::lookup(name).is_none_or(|keyword| keyword.soft)
+}
+
+/// Whether the token at `span` comes right after `as`
+fn follows_as(tokens: &[Token], span: Span) -> bool {
+    let index = tokens.iter().position(|token| token.span == span);
+    index.and_then(|index| index.checked_sub(1)).is_some_and(|previous| tokens[previous].token_type == TokenType::As)
+}
+
+/// The index of the token each occurrence is written as, when it can be found.
+/// Names with a position of their own are matched first; the rest take the
+/// first identifier with their name at or after their statement that isn't
+/// already taken or an attribute or module name.
+pub(crate) fn locate(tokens: &[Token], occurrences: &[&Occurrence]) -> Vec<Option<usize>> {
+    let is_name = |token: &Token, name: &str| matches!(&token.token_type, TokenType::Identifier(id) if id == name);
+    let mut taken = vec![false; tokens.len()];
+
+    let mut located: Vec<Option<usize>> = occurrences
+        .iter()
+        .map(|occurrence| {
+            if occurrence.end_column - occurrence.column != occurrence.name.chars().count() {
+                return None;
+            }
+            let index = tokens.iter().position(|token| {
+                token.line == occurrence.line && token.column == occurrence.column && is_name(token, &occurrence.name)
+            })?;
+            taken[index] = true;
+            Some(index)
+        })
+        .collect();
+
+    for (occurrence, found) in occurrences.iter().zip(located.iter_mut()) {
+        if found.is_some() {
+            continue;
+        }
+        let start = Position::new(occurrence.line, occurrence.column);
+        *found = (0..tokens.len()).find(|&index| {
+            let token = &tokens[index];
+            let after_dot_or_from = index
+                .checked_sub(1)
+                .is_some_and(|previous| matches!(tokens[previous].token_type, TokenType::Dot | TokenType::From));
+            !taken[index]
+                && Position::new(token.line, token.column) >= start
+                && is_name(token, &occurrence.name)
+                && !after_dot_or_from
+        });
+        if let Some(index) = *found {
+            taken[index] = true;
+        }
+    }
+    located
+}
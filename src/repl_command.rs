@@ -0,0 +1,60 @@
+// repl_command.rs - REPL meta-commands such as `:type` and `:load`
+
+use std::path::PathBuf;
+
+/// A line starting with `:` at the REPL's primary prompt. No Cheetah
+/// statement starts with a colon, so these never shadow code.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetaCommand {
+    /// `:type EXPR` - show the inferred type of an expression
+    Type(String),
+    /// `:ast` - show the AST of the last input
+    Ast,
+    /// `:ir` - show the LLVM IR compiled for the last input
+    Ir,
+    /// `:tokens [CODE]` - show the tokens of CODE, or of the last input
+    Tokens(Option<String>),
+    /// `:load FILE` - run a file in the session
+    Load(PathBuf),
+    /// `:paste` - read a block that may contain blank lines
+    Paste,
+    /// `:help` - list the commands
+    Help,
+}
+
+/// Summary printed by `:help`
+pub const HELP: &str = "\
+:type EXPR     show the inferred type of an expression
+:ast           show the AST of the last input
+:ir            show the LLVM IR of the last input (JIT REPL)
+:tokens [CODE] show the tokens of CODE, or of the last input
+:load FILE     run a file in this session
+:paste         enter a block containing blank lines
+:help          show this list";
+
+impl MetaCommand {
+    /// Parse `line` as a meta-command. Returns `None` if it isn't one.
+    pub fn parse(line: &str) -> Option<Result<Self, String>> {
+        let line = line.trim();
+        let rest = line.strip_prefix(':')?;
+        let (name, arg) = match rest.split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, arg.trim()),
+            None => (rest, ""),
+        };
+
+        let command = match name {
+            "type" | "t" if arg.is_empty() => Err("usage: :type EXPR".to_string()),
+            "type" | "t" => Ok(MetaCommand::Type(arg.to_string())),
+            "ast" => Ok(MetaCommand::Ast),
+            "ir" => Ok(MetaCommand::Ir),
+            "tokens" if arg.is_empty() => Ok(MetaCommand::Tokens(None)),
+            "tokens" => Ok(MetaCommand::Tokens(Some(arg.to_string()))),
+            "load" | "l" if arg.is_empty() => Err("usage: :load FILE".to_string()),
+            "load" | "l" => Ok(MetaCommand::Load(PathBuf::from(arg))),
+            "paste" => Ok(MetaCommand::Paste),
+            "help" | "h" | "?" => Ok(MetaCommand::Help),
+            _ => Err(format!("Unknown command '{}'; type :help for a list", line)),
+        };
+        Some(command)
+    }
+}
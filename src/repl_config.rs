@@ -0,0 +1,78 @@
+// repl_config.rs - REPL prompts and the ~/.cheetahrc startup script
+
+use crate::ast::{Expr, Module, Stmt};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Prompt shown for a new statement
+pub const DEFAULT_PS1: &str = ">>> ";
+/// Prompt shown while a statement continues over more lines
+pub const DEFAULT_PS2: &str = "... ";
+
+/// Prompts and startup code for an interactive session
+#[derive(Debug, Clone)]
+pub struct ReplConfig {
+    pub ps1: String,
+    pub ps2: String,
+    /// Startup script to run before the first prompt
+    pub startup: Option<Module>,
+}
+
+impl Default for ReplConfig {
+    fn default() -> Self {
+        Self {
+            ps1: DEFAULT_PS1.to_string(),
+            ps2: DEFAULT_PS2.to_string(),
+            startup: None,
+        }
+    }
+}
+
+impl ReplConfig {
+    /// Where the startup script is looked for by default: `~/.cheetahrc`
+    pub fn default_path() -> Option<PathBuf> {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cheetahrc"))
+    }
+
+    /// Where REPL history is saved between sessions: `~/.cheetah_history`
+    pub fn history_path() -> Option<PathBuf> {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cheetah_history"))
+    }
+
+    /// Load the startup script at `path`
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::from_source(&source).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Use `source` as the startup script. Top-level `ps1 = "..."` and
+    /// `ps2 = "..."` assignments set the prompts, as in Python.
+    pub fn from_source(source: &str) -> Result<Self, String> {
+        let module = crate::parse(source).map_err(|errors| {
+            errors
+                .iter()
+                .map(|e| e.get_message())
+                .collect::<Vec<String>>()
+                .join("\n")
+        })?;
+
+        let mut config = Self::default();
+        for stmt in &module.body {
+            if let Stmt::Assign { targets, value, .. } = stmt.as_ref() {
+                let Expr::Str { value: prompt, .. } = value.as_ref() else {
+                    continue;
+                };
+                for target in targets {
+                    match target.as_ref() {
+                        Expr::Name { id, .. } if id == "ps1" => config.ps1 = prompt.clone(),
+                        Expr::Name { id, .. } if id == "ps2" => config.ps2 = prompt.clone(),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        config.startup = Some(module);
+        Ok(config)
+    }
+}
@@ -0,0 +1,426 @@
+// semantic_tokens.rs - Classifying source ranges for syntax highlighting
+//
+// Each token of a file is given a kind such as function, parameter or
+// keyword, and names are classified by what they refer to in the symbol
+// table rather than by how they look:
+//
+//     def area(shape: Shape) -> float:   # `area` function, declaration
+//         return shape.width * 2         # `shape` parameter, `width` property
+//
+// The kinds and modifiers are the standard ones of the Language Server
+// Protocol, and `encode` produces the `data` of a `textDocument/semanticTokens`
+// response. Source that doesn't parse is still classified, from its tokens
+// alone, so highlighting keeps working while code is being typed.
+
+use crate::ast::Span;
+use crate::completion::builtin_names;
+use crate::lexer::{keywords, LexerConfig, Token, TokenType};
+use crate::parser;
+use crate::references::locate;
+use crate::symtable::{Occurrence, ScopeKind, SymbolTable, SymbolType};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+
+/// Builtin names that are classes rather than functions
+const BUILTIN_TYPES: &[&str] = &[
+    "int", "float", "str", "bool", "bytes", "list", "dict", "set", "tuple", "object",
+];
+
+/// What a range of source is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Namespace,
+    Class,
+    Function,
+    Method,
+    Property,
+    Parameter,
+    Variable,
+    Decorator,
+    Keyword,
+    String,
+    Number,
+    Operator,
+    Comment,
+}
+
+impl TokenKind {
+    /// Every kind, in the order of the LSP legend
+    pub const ALL: [TokenKind; 13] = [
+        TokenKind::Namespace,
+        TokenKind::Class,
+        TokenKind::Function,
+        TokenKind::Method,
+        TokenKind::Property,
+        TokenKind::Parameter,
+        TokenKind::Variable,
+        TokenKind::Decorator,
+        TokenKind::Keyword,
+        TokenKind::String,
+        TokenKind::Number,
+        TokenKind::Operator,
+        TokenKind::Comment,
+    ];
+
+    /// The LSP name of the kind
+    pub fn name(self) -> &'static str {
+        match self {
+            TokenKind::Namespace => "namespace",
+            TokenKind::Class => "class",
+            TokenKind::Function => "function",
+            TokenKind::Method => "method",
+            TokenKind::Property => "property",
+            TokenKind::Parameter => "parameter",
+            TokenKind::Variable => "variable",
+            TokenKind::Decorator => "decorator",
+            TokenKind::Keyword => "keyword",
+            TokenKind::String => "string",
+            TokenKind::Number => "number",
+            TokenKind::Operator => "operator",
+            TokenKind::Comment => "comment",
+        }
+    }
+}
+
+/// LSP names of the modifiers, in the order of their bits
+pub const MODIFIERS: [&str; 2] = ["declaration", "defaultLibrary"];
+
+/// A classified range of source
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemanticToken {
+    /// Where the range starts, from 1
+    pub line: usize,
+    pub column: usize,
+    /// Byte offsets of the range in the source
+    pub span: Span,
+    pub kind: TokenKind,
+    /// Whether the name is bound here, as by an assignment, `def` or import
+    pub declaration: bool,
+    /// Whether the name is a builtin
+    pub default_library: bool,
+}
+
+impl SemanticToken {
+    /// The modifiers as a bit set over `MODIFIERS`
+    pub fn modifier_bits(&self) -> u32 {
+        u32::from(self.declaration) | (u32::from(self.default_library) << 1)
+    }
+
+    /// The LSP names of the modifiers that are set
+    pub fn modifiers(&self) -> Vec<&'static str> {
+        MODIFIERS
+            .iter()
+            .enumerate()
+            .filter(|(bit, _)| self.modifier_bits() & (1 << bit) != 0)
+            .map(|(_, name)| *name)
+            .collect()
+    }
+}
+
+/// The `legend` of the LSP semantic tokens capability
+pub fn legend() -> Value {
+    json!({
+        "tokenTypes": TokenKind::ALL.iter().map(|kind| kind.name()).collect::<Vec<_>>(),
+        "tokenModifiers": MODIFIERS,
+    })
+}
+
+/// The classified ranges of `source` in source order, comments included
+pub fn semantic_tokens(source: &str) -> Result<Vec<SemanticToken>, String> {
+    let config = LexerConfig {
+        lossless: true,
+        ..LexerConfig::default()
+    };
+    let tokens = parser::tokenize_with_config(source, config).map_err(|errors| {
+        errors
+            .first()
+            .map(|error| error.get_message())
+            .unwrap_or_else(|| "invalid syntax".to_string())
+    })?;
+    let table = parser::parse(tokens.clone()).ok().map(|module| SymbolTable::build(&module));
+    let classifier = Classifier::new(&tokens, table.as_ref());
+
+    let mut result = comments(source, &tokens);
+    for (index, token) in tokens.iter().enumerate() {
+        if let Some((kind, declaration, default_library)) = classifier.classify(index) {
+            // Strings are positioned at their closing quotes
+            let (line, column) = match source[token.span.start..token.span.end].contains('\n') {
+                true => position(source, token.span.start),
+                false => (token.line, token.column),
+            };
+            result.push(SemanticToken {
+                line,
+                column,
+                span: token.span,
+                kind,
+                declaration,
+                default_library,
+            });
+        }
+    }
+    result.sort_by_key(|token| token.span.start);
+    Ok(result)
+}
+
+/// The `data` of an LSP semantic tokens response: five numbers per range,
+/// with lines and UTF-16 columns counted from 0 and relative to the range
+/// before. Ranges spanning lines are split at each line end.
+pub fn encode(source: &str, tokens: &[SemanticToken]) -> Vec<u32> {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(at, _)| at + 1))
+        .collect();
+    let utf16 = |text: &str| text.encode_utf16().count() as u32;
+
+    let mut data = Vec::new();
+    let (mut previous_line, mut previous_start) = (0, 0);
+    for token in tokens {
+        let text = &source[token.span.start..token.span.end];
+        let mut offset = token.span.start;
+        for part in text.split('\n') {
+            let line = line_starts.partition_point(|&start| start <= offset) - 1;
+            let start = utf16(&source[line_starts[line]..offset]);
+            offset += part.len() + 1;
+            let part = part.strip_suffix('\r').unwrap_or(part);
+            if part.is_empty() {
+                continue;
+            }
+            let line = line as u32;
+            let delta_start = if line == previous_line { start - previous_start } else { start };
+            data.extend([
+                line - previous_line,
+                delta_start,
+                utf16(part),
+                token.kind as u32,
+                token.modifier_bits(),
+            ]);
+            (previous_line, previous_start) = (line, start);
+        }
+    }
+    data
+}
+
+/// The comments in the text before each token of a lossless lex
+fn comments(source: &str, tokens: &[Token]) -> Vec<SemanticToken> {
+    let mut result = Vec::new();
+    for token in tokens {
+        let Some(leading) = token.source.as_ref().map(|source| source.leading) else {
+            continue;
+        };
+        let mut offset = token.span.start.saturating_sub(leading.len());
+        for text in leading.split_inclusive('\n') {
+            if let Some(at) = text.find('#') {
+                let start = offset + at;
+                let end = offset + text.trim_end().len();
+                let (line, column) = position(source, start);
+                result.push(SemanticToken {
+                    line,
+                    column,
+                    span: Span {
+                        start,
+                        end,
+                        end_line: line,
+                        end_column: column + source[start..end].chars().count(),
+                    },
+                    kind: TokenKind::Comment,
+                    declaration: false,
+                    default_library: false,
+                });
+            }
+            offset += text.len();
+        }
+    }
+    result
+}
+
+/// The line and column, both from 1, of byte offset `offset` of `source`
+fn position(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |at| at + 1);
+    (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+}
+
+/// Classifies tokens, resolving names through the symbol table when the
+/// source parsed
+struct Classifier<'a> {
+    tokens: &'a [Token<'a>],
+    table: Option<&'a SymbolTable>,
+    /// The occurrence each token of a name is, by token index
+    occurrences: HashMap<usize, &'a Occurrence>,
+    builtins: BTreeSet<String>,
+    /// Whether each token is inside brackets
+    bracketed: Vec<bool>,
+    /// Whether each token is part of an import statement
+    in_import: Vec<bool>,
+}
+
+impl<'a> Classifier<'a> {
+    fn new(tokens: &'a [Token<'a>], table: Option<&'a SymbolTable>) -> Self {
+        let occurrences = match table {
+            Some(table) => {
+                let all: Vec<&Occurrence> = table.occurrences().iter().collect();
+                locate(tokens, &all)
+                    .into_iter()
+                    .zip(all)
+                    .filter_map(|(index, occurrence)| Some((index?, occurrence)))
+                    .collect()
+            }
+            None => HashMap::new(),
+        };
+
+        let (mut depth, mut import) = (0usize, false);
+        let mut bracketed = Vec::with_capacity(tokens.len());
+        let mut in_import = Vec::with_capacity(tokens.len());
+        for (index, token) in tokens.iter().enumerate() {
+            match token.token_type {
+                TokenType::LeftParen | TokenType::LeftBracket | TokenType::LeftBrace => depth += 1,
+                TokenType::RightParen | TokenType::RightBracket | TokenType::RightBrace => {
+                    depth = depth.saturating_sub(1)
+                }
+                TokenType::Newline => import = false,
+                TokenType::Import | TokenType::From if depth == 0 => {
+                    import = import || starts_statement(tokens, index);
+                }
+                _ => {}
+            }
+            bracketed.push(depth > 0);
+            in_import.push(import);
+        }
+
+        Classifier {
+            tokens,
+            table,
+            occurrences,
+            builtins: builtin_names(),
+            bracketed,
+            in_import,
+        }
+    }
+
+    /// The kind of token `index` and whether it declares a name and is a
+    /// builtin, or None for punctuation and layout
+    fn classify(&self, index: usize) -> Option<(TokenKind, bool, bool)> {
+        let token = &self.tokens[index];
+        let kind = match &token.token_type {
+            TokenType::Identifier(name) => return Some(self.name(index, name)),
+            TokenType::IntLiteral(_)
+            | TokenType::FloatLiteral(_)
+            | TokenType::BinaryLiteral(_)
+            | TokenType::OctalLiteral(_)
+            | TokenType::HexLiteral(_) => TokenKind::Number,
+            TokenType::StringLiteral(_)
+            | TokenType::BytesLiteral(_)
+            | TokenType::RawString(_)
+            | TokenType::FString(_) => TokenKind::String,
+            TokenType::At if starts_statement(self.tokens, index) => TokenKind::Decorator,
+            ty if keywords::for_token(ty).is_some() => TokenKind::Keyword,
+            TokenType::Plus
+            | TokenType::Minus
+            | TokenType::Multiply
+            | TokenType::Divide
+            | TokenType::FloorDivide
+            | TokenType::Modulo
+            | TokenType::Power
+            | TokenType::Assign
+            | TokenType::PlusAssign
+            | TokenType::MinusAssign
+            | TokenType::MulAssign
+            | TokenType::DivAssign
+            | TokenType::ModAssign
+            | TokenType::PowAssign
+            | TokenType::MatrixMulAssign
+            | TokenType::FloorDivAssign
+            | TokenType::BitwiseAndAssign
+            | TokenType::BitwiseOrAssign
+            | TokenType::BitwiseXorAssign
+            | TokenType::ShiftLeftAssign
+            | TokenType::ShiftRightAssign
+            | TokenType::Equal
+            | TokenType::NotEqual
+            | TokenType::LessThan
+            | TokenType::LessEqual
+            | TokenType::GreaterThan
+            | TokenType::GreaterEqual
+            | TokenType::BitwiseAnd
+            | TokenType::BitwiseOr
+            | TokenType::BitwiseXor
+            | TokenType::BitwiseNot
+            | TokenType::ShiftLeft
+            | TokenType::ShiftRight
+            | TokenType::Walrus
+            | TokenType::Arrow
+            | TokenType::At => TokenKind::Operator,
+            _ => return None,
+        };
+        Some((kind, false, false))
+    }
+
+    /// The kind of the name at token `index`
+    fn name(&self, index: usize, name: &str) -> (TokenKind, bool, bool) {
+        let previous = index.checked_sub(1).map(|previous| &self.tokens[previous].token_type);
+        let next = self.tokens.get(index + 1).map(|next| &next.token_type);
+        let called = next == Some(&TokenType::LeftParen);
+
+        if previous == Some(&TokenType::At) && starts_statement(self.tokens, index - 1) {
+            return (TokenKind::Decorator, false, self.builtins.contains(name));
+        }
+        if self.in_import[index] && previous == Some(&TokenType::From) {
+            return (TokenKind::Namespace, false, false);
+        }
+        if previous == Some(&TokenType::Dot) {
+            return match (self.in_import[index], called) {
+                (true, _) => (TokenKind::Namespace, false, false),
+                (false, true) => (TokenKind::Method, false, false),
+                (false, false) => (TokenKind::Property, false, false),
+            };
+        }
+        // Keyword arguments name a parameter of the function called
+        if next == Some(&TokenType::Assign) && self.bracketed[index] && !self.occurrences.contains_key(&index) {
+            return (TokenKind::Parameter, false, false);
+        }
+
+        let resolved = self.table.and_then(|table| {
+            let occurrence = self.occurrences.get(&index);
+            let scope = match occurrence {
+                Some(occurrence) => occurrence.scope,
+                None => table.scope_at_position(self.tokens[index].line, self.tokens[index].column),
+            };
+            let (defining, symbol) = table.resolve(scope, name)?;
+            let in_class = table.scope(defining).kind == ScopeKind::Class;
+            let kind = match symbol.symbol_type {
+                SymbolType::Function if in_class => TokenKind::Method,
+                SymbolType::Function => TokenKind::Function,
+                SymbolType::Class => TokenKind::Class,
+                SymbolType::Parameter => TokenKind::Parameter,
+                SymbolType::Import => TokenKind::Namespace,
+                SymbolType::Variable if in_class => TokenKind::Property,
+                _ => TokenKind::Variable,
+            };
+            Some((kind, occurrence.is_some_and(|occurrence| occurrence.is_definition)))
+        });
+        if let Some((kind, declaration)) = resolved {
+            return (kind, declaration, false);
+        }
+
+        if self.in_import[index] {
+            (TokenKind::Namespace, false, false)
+        } else if BUILTIN_TYPES.contains(&name) || name.ends_with("Error") || name.ends_with("Exception") {
+            (TokenKind::Class, false, true)
+        } else if self.builtins.contains(name) {
+            (TokenKind::Function, false, true)
+        } else if called {
+            (TokenKind::Function, false, false)
+        } else {
+            (TokenKind::Variable, false, false)
+        }
+    }
+}
+
+/// Whether token `index` is the first of a statement
+fn starts_statement(tokens: &[Token], index: usize) -> bool {
+    index.checked_sub(1).is_none_or(|previous| {
+        matches!(
+            tokens[previous].token_type,
+            TokenType::Newline | TokenType::Indent | TokenType::Dedent | TokenType::SemiColon
+        )
+    })
+}
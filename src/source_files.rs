@@ -0,0 +1,142 @@
+// source_files.rs - Expanding the paths given to `format`, `check` and `lint`
+//
+// Each path may name a file, a directory, which is searched recursively for
+// `.ch` files, or a glob pattern matching `.ch` files. In patterns `*` and `?`
+// match within one path component and a `**` component matches any number of
+// directories. Hidden directories and `target` build directories are never
+// searched.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Extension of Cheetah source files
+const SOURCE_EXTENSION: &str = "ch";
+
+/// Directories skipped when searching for source files
+const SKIPPED_DIRS: [&str; 1] = ["target"];
+
+/// Source files named by `paths`, sorted and without duplicates. A file
+/// named without the `.ch` extension gets it, as elsewhere in the CLI.
+pub fn expand(paths: &[String]) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    for path in paths {
+        if is_pattern(path) {
+            let matched = expand_pattern(path)?;
+            if matched.is_empty() {
+                return Err(format!("no Cheetah files match '{}'", path));
+            }
+            files.extend(matched);
+        } else if Path::new(path).is_dir() {
+            let mut found = Vec::new();
+            walk(Path::new(path), &mut found)
+                .map_err(|e| format!("Failed to read directory {}: {}", path, e))?;
+            files.extend(found.into_iter().filter(|file| has_source_extension(file)));
+        } else {
+            files.push(Path::new(path).with_extension(SOURCE_EXTENSION));
+        }
+    }
+    let mut files: Vec<PathBuf> = files
+        .into_iter()
+        .map(|file| match file.strip_prefix(".") {
+            Ok(relative) if !relative.as_os_str().is_empty() => relative.to_path_buf(),
+            _ => file,
+        })
+        .collect();
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Whether `path` contains glob wildcards
+pub fn is_pattern(path: &str) -> bool {
+    path.contains(['*', '?'])
+}
+
+/// Whether `path` matches the glob `pattern`, comparing `/`-separated components
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    match_components(&pattern, &path)
+}
+
+fn match_components(pattern: &[&str], path: &[&str]) -> bool {
+    match (pattern.first(), path.first()) {
+        (None, None) => true,
+        (Some(&"**"), _) => {
+            match_components(&pattern[1..], path)
+                || (!path.is_empty() && match_components(pattern, &path[1..]))
+        }
+        (Some(component), Some(name)) => {
+            match_component(component.as_bytes(), name.as_bytes())
+                && match_components(&pattern[1..], &path[1..])
+        }
+        _ => false,
+    }
+}
+
+fn match_component(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => {
+            match_component(&pattern[1..], name)
+                || (!name.is_empty() && match_component(pattern, &name[1..]))
+        }
+        (Some(b'?'), Some(_)) => match_component(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => match_component(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+/// Files under the pattern's literal leading directories that match it
+fn expand_pattern(pattern: &str) -> Result<Vec<PathBuf>, String> {
+    let components: Vec<&str> = pattern.split('/').collect();
+    let literal = components
+        .iter()
+        .take_while(|component| !is_pattern(component))
+        .count();
+    let base = components[..literal].join("/");
+    let root = match (base.as_str(), pattern.starts_with('/')) {
+        ("", true) => "/",
+        ("", false) => ".",
+        (base, _) => base,
+    };
+
+    let mut files = Vec::new();
+    if Path::new(root).is_dir() {
+        walk(Path::new(root), &mut files)
+            .map_err(|e| format!("Failed to read directory {}: {}", root, e))?;
+    }
+    Ok(files
+        .into_iter()
+        .filter(|file| has_source_extension(file))
+        .filter(|file| {
+            let name = file.to_string_lossy();
+            let name = if root == "." {
+                name.trim_start_matches("./")
+            } else {
+                &name
+            };
+            glob_match(pattern, name)
+        })
+        .collect())
+}
+
+/// Every file under `dir`, outside hidden and skipped directories
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+            if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name) {
+                walk(&path, files)?;
+            }
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn has_source_extension(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == SOURCE_EXTENSION)
+}
@@ -1,6 +1,19 @@
-use crate::ast::{Expr, Module, Stmt};
+// symtable.rs - Scopes and the names defined and used in them
+//
+// SymbolTableBuilder walks a module the way the compiler resolves names.
+// Besides its working tree of `Scope`s it fills a `SymbolTable`: every
+// scope with an id, its kind and the source range it covers, the symbols
+// it defines with their declaration sites, and each place a name is
+// written. Editors and lints query the table rather than walking scopes:
+//
+//     let table = SymbolTable::build(&module);
+//     let (scope, symbol) = table.lookup_at_position(3, 12)?;
+
+use crate::ast::{Expr, Module, Span, Stmt};
+use crate::intern;
 use crate::visitor::Visitor;
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum SymbolType {
@@ -18,8 +31,11 @@ pub enum SymbolType {
 pub struct Symbol {
     pub name: String,
     pub symbol_type: SymbolType,
+    /// Where the symbol was last declared, or first used if it never was
     pub line: usize,
     pub column: usize,
+    /// Span of the statement or expression declaring it
+    pub span: Span,
     pub is_defined: bool,
     pub is_referenced: bool,
     pub is_global: bool,
@@ -33,6 +49,7 @@ impl Symbol {
             symbol_type,
             line,
             column,
+            span: Span::default(),
             is_defined: false,
             is_referenced: false,
             is_global: false,
@@ -83,132 +100,146 @@ impl Scope {
 
 pub struct SymbolTableBuilder {
     current_scope: Box<Scope>,
-    root_scope: Option<Box<Scope>>,
     used_names: HashSet<String>,
     undefined_names: HashSet<String>,
+    table: SymbolTable,
+    /// Ids of the table scopes being built, innermost last
+    open_scopes: Vec<ScopeId>,
 }
 
 impl SymbolTableBuilder {
     pub fn new() -> Self {
-        let root_scope = Box::new(Scope::new("module", false, false));
-
         SymbolTableBuilder {
-            current_scope: root_scope.clone(),
-            root_scope: Some(root_scope),
+            current_scope: Box::new(Scope::new("module", false, false)),
             used_names: HashSet::new(),
             undefined_names: HashSet::new(),
+            table: SymbolTable::new(),
+            open_scopes: vec![SymbolTable::ROOT],
         }
     }
 
     pub fn enter_scope(&mut self, name: &str, is_function: bool, is_class: bool) {
-        let new_scope = Box::new(Scope::new(name, is_function, is_class));
-
-        let mut old_scope = self.current_scope.clone();
-
-        self.current_scope = new_scope;
+        self.enter_scope_spanning(name, is_function, is_class, Position::default(), Position::default());
+    }
 
-        old_scope.add_child(self.current_scope.clone());
+    /// Enter a scope for a definition or expression covering `start..end`
+    fn enter_scope_spanning(&mut self, name: &str, is_function: bool, is_class: bool, start: Position, end: Position) {
+        let new_scope = Box::new(Scope::new(name, is_function, is_class));
+        // The enclosing scope hangs off the new one until exit_scope returns to it
+        let parent = std::mem::replace(&mut self.current_scope, new_scope);
+        self.current_scope.parent = Some(parent);
+
+        let parent = self.current_scope_id();
+        let id = self.table.scopes.len();
+        self.table.scopes.push(ScopeInfo {
+            id,
+            name: name.to_string(),
+            kind: ScopeKind::of(name, is_function, is_class),
+            parent: Some(parent),
+            children: Vec::new(),
+            start,
+            end,
+            symbols: Vec::new(),
+            index: HashMap::new(),
+        });
+        self.table.scopes[parent].children.push(id);
+        self.open_scopes.push(id);
+    }
 
-        if self.root_scope.is_some() && self.root_scope.as_ref().unwrap().name == old_scope.name {
-            self.root_scope = Some(old_scope);
+    pub fn exit_scope(&mut self) {
+        if let Some(parent) = self.current_scope.parent.take() {
+            self.close_table_scope();
+            let finished = std::mem::replace(&mut self.current_scope, parent);
+            self.current_scope.children.push(finished);
         }
     }
 
-    pub fn exit_scope(&mut self) {
-        if let Some(parent) = &self.current_scope.parent {
-            self.current_scope = parent.clone();
+    /// Copy the symbols of the current scope into its table scope and leave it
+    fn close_table_scope(&mut self) {
+        let id = self.current_scope_id();
+        let mut symbols: Vec<Symbol> = self.current_scope.symbols.values().cloned().collect();
+        symbols.sort_by(|a, b| (a.line, a.column, &a.name).cmp(&(b.line, b.column, &b.name)));
+        let scope = &mut self.table.scopes[id];
+        scope.index = symbols
+            .iter()
+            .enumerate()
+            .map(|(i, symbol)| (intern::Symbol::intern(&symbol.name), i))
+            .collect();
+        scope.symbols = symbols;
+        if self.open_scopes.len() > 1 {
+            self.open_scopes.pop();
         }
     }
 
+    fn current_scope_id(&self) -> ScopeId {
+        *self.open_scopes.last().unwrap_or(&SymbolTable::ROOT)
+    }
+
     pub fn define_symbol(
         &mut self,
         name: &str,
         symbol_type: SymbolType,
         line: usize,
         column: usize,
+    ) {
+        self.define_symbol_at(name, symbol_type, line, column, Span::default(), 0);
+    }
+
+    /// Define `name`, declared by the node covering `span`. When `width` is
+    /// non-zero the name itself is written at `line`, `column` and that many
+    /// columns wide.
+    fn define_symbol_at(
+        &mut self,
+        name: &str,
+        symbol_type: SymbolType,
+        line: usize,
+        column: usize,
+        span: Span,
+        width: usize,
     ) {
         let mut symbol = Symbol::new(name, symbol_type, line, column);
         symbol.is_defined = true;
+        symbol.span = span;
 
         if let Some(existing) = self.current_scope.get_symbol_mut(name) {
             existing.is_defined = true;
             existing.line = line;
             existing.column = column;
+            existing.span = span;
         } else {
             self.current_scope.add_symbol(symbol);
         }
 
         self.used_names.insert(name.to_string());
+        self.record_occurrence(name, line, column, width, true);
     }
 
-    fn mark_symbol_in_scope_tree_helper(
-        &self,
-        scope: &mut Box<Scope>,
-        name: &str,
-        target_scope_name: &str,
-    ) -> bool {
-        if scope.name == *target_scope_name {
-            if let Some(symbol) = scope.get_symbol_mut(name) {
-                symbol.is_referenced = true;
-                return true;
-            }
-            return false;
-        }
-
-        let mut modified_indices = Vec::new();
-
-        for (i, child) in scope.children.iter_mut().enumerate() {
-            if self.mark_symbol_in_scope_tree_helper(child, name, target_scope_name) {
-                modified_indices.push(i);
-            }
-        }
-
-        !modified_indices.is_empty()
-    }
-
-    pub fn mark_symbol_referenced_in_parent(&mut self, name: &str, parent_scope_name: String) {
-        if let Some(root) = self.root_scope.clone() {
-            let mut root_clone = root.clone();
-
-            let was_modified =
-                self.mark_symbol_in_scope_tree_helper(&mut root_clone, name, &parent_scope_name);
-
-            if was_modified {
-                self.root_scope = Some(root_clone);
-            }
-        }
+    fn record_occurrence(&mut self, name: &str, line: usize, column: usize, width: usize, is_definition: bool) {
+        let scope = self.current_scope_id();
+        self.table.occurrences.push(Occurrence {
+            name: name.to_string(),
+            scope,
+            line,
+            column,
+            end_column: column + width,
+            is_definition,
+        });
     }
 
     pub fn reference_symbol(&mut self, name: &str, line: usize, column: usize) {
-        let found_in_current = self.current_scope.symbols.contains_key(name);
-
-        if found_in_current {
-            if let Some(existing) = self.current_scope.get_symbol_mut(name) {
-                existing.is_referenced = true;
-                return;
-            }
-        }
-
-        let mut found = false;
-        let mut parent_scope_name = None;
-
-        {
-            let mut scope = &self.current_scope;
-            while let Some(parent) = &scope.parent {
-                if parent.symbols.contains_key(name) {
-                    found = true;
-                    parent_scope_name = Some(parent.name.clone());
-                    break;
+        self.record_occurrence(name, line, column, name.chars().count(), false);
+        let mut scope = &mut self.current_scope;
+        loop {
+            if scope.symbols.contains_key(name) {
+                if let Some(existing) = scope.get_symbol_mut(name) {
+                    existing.is_referenced = true;
                 }
-                scope = parent;
+                return;
             }
-        }
-
-        if found {
-            if let Some(scope_name) = parent_scope_name {
-                self.mark_symbol_referenced_in_parent(name, scope_name);
+            match scope.parent {
+                Some(ref mut parent) => scope = parent,
+                None => break,
             }
-            return;
         }
 
         self.undefined_names.insert(name.to_string());
@@ -238,16 +269,37 @@ impl SymbolTableBuilder {
         }
     }
 
+    /// The scope being built; after visiting a module, the module's own scope
+    pub fn get_current_scope(&self) -> &Scope {
+        &self.current_scope
+    }
+
+    /// The outermost scope; after visiting a module, it holds every finished
+    /// function, class and comprehension scope as a descendant
     pub fn get_root_scope(&self) -> Option<&Box<Scope>> {
-        self.root_scope.as_ref()
+        let mut scope = &self.current_scope;
+        while let Some(parent) = &scope.parent {
+            scope = parent;
+        }
+        Some(scope)
     }
 
     pub fn get_undefined_names(&self) -> &HashSet<String> {
         &self.undefined_names
     }
 
+    /// Finish building and return the symbol table. Scopes still open are
+    /// closed first.
+    pub fn into_symbol_table(mut self) -> SymbolTable {
+        while self.current_scope.parent.is_some() {
+            self.exit_scope();
+        }
+        self.close_table_scope();
+        self.table
+    }
+
     pub fn print_symbol_table(&self) {
-        if let Some(root) = &self.root_scope {
+        if let Some(root) = self.get_root_scope() {
             self.print_scope(root, 0);
         }
     }
@@ -292,17 +344,21 @@ impl<'ast> Visitor<'ast, ()> for SymbolTableBuilder {
                 line,
                 column,
                 is_async: _is_async,
+                span,
             } => {
-                self.define_symbol(name, SymbolType::Function, *line, *column);
+                // The name follows `def ` on the statement's first line
+                let width = "def ".len() + name.chars().count();
+                self.define_symbol_at(name, SymbolType::Function, *line, *column, *span, width);
 
                 for decorator in decorator_list {
                     self.visit_expr(decorator);
                 }
 
-                self.enter_scope(name, true, false);
+                let (start, end) = Position::range(*line, *column, span);
+                self.enter_scope_spanning(name, true, false, start, end);
 
                 for param in params {
-                    self.define_symbol(&param.name, SymbolType::Parameter, *line, *column);
+                    self.define_symbol_at(&param.name, SymbolType::Parameter, *line, *column, *span, 0);
 
                     if let Some(typ) = &param.typ {
                         self.visit_expr(typ);
@@ -331,8 +387,10 @@ impl<'ast> Visitor<'ast, ()> for SymbolTableBuilder {
                 decorator_list,
                 line,
                 column,
+                span,
             } => {
-                self.define_symbol(name, SymbolType::Class, *line, *column);
+                let width = "class ".len() + name.chars().count();
+                self.define_symbol_at(name, SymbolType::Class, *line, *column, *span, width);
 
                 for decorator in decorator_list {
                     self.visit_expr(decorator);
@@ -346,7 +404,8 @@ impl<'ast> Visitor<'ast, ()> for SymbolTableBuilder {
                     self.visit_expr(value);
                 }
 
-                self.enter_scope(name, false, true);
+                let (start, end) = Position::range(*line, *column, span);
+                self.enter_scope_spanning(name, false, true, start, end);
 
                 for stmt in body {
                     self.visit_stmt(stmt);
@@ -373,6 +432,8 @@ impl<'ast> Visitor<'ast, ()> for SymbolTableBuilder {
             }
             Stmt::AugAssign { target, value, .. } => {
                 self.visit_expr(value);
+                // `x += 1` reads `x` as well as storing it
+                self.visit_expr(target);
                 self.visit_expr_as_target(target);
             }
             Stmt::AnnAssign {
@@ -482,7 +543,9 @@ impl<'ast> Visitor<'ast, ()> for SymbolTableBuilder {
                     self.visit_expr(msg);
                 }
             }
-            Stmt::Import { names, .. } => {
+            Stmt::Import {
+                names, line, column, span,
+            } => {
                 for alias in names {
                     let import_name = if let Some(asname) = &alias.asname {
                         asname
@@ -490,10 +553,12 @@ impl<'ast> Visitor<'ast, ()> for SymbolTableBuilder {
                         &alias.name
                     };
 
-                    self.define_symbol(import_name, SymbolType::Import, 0, 0);
+                    self.define_symbol_at(import_name, SymbolType::Import, *line, *column, *span, 0);
                 }
             }
-            Stmt::ImportFrom { names, .. } => {
+            Stmt::ImportFrom {
+                names, line, column, span, ..
+            } => {
                 for alias in names {
                     let import_name = if let Some(asname) = &alias.asname {
                         asname
@@ -501,16 +566,18 @@ impl<'ast> Visitor<'ast, ()> for SymbolTableBuilder {
                         &alias.name
                     };
 
-                    self.define_symbol(import_name, SymbolType::ImportFrom, 0, 0);
+                    self.define_symbol_at(import_name, SymbolType::ImportFrom, *line, *column, *span, 0);
                 }
             }
-            Stmt::Global { names, .. } => {
+            Stmt::Global { names, line, column, .. } => {
                 for name in names {
+                    self.record_occurrence(name, *line, *column, 0, false);
                     self.mark_as_global(name);
                 }
             }
-            Stmt::Nonlocal { names, .. } => {
+            Stmt::Nonlocal { names, line, column, .. } => {
                 for name in names {
+                    self.record_occurrence(name, *line, *column, 0, false);
                     self.mark_as_nonlocal(name);
                 }
             }
@@ -543,6 +610,7 @@ impl<'ast> Visitor<'ast, ()> for SymbolTableBuilder {
                 ctx: _ctx,
                 line,
                 column,
+                span: _,
             } => {
                 self.reference_symbol(id, *line, *column);
             }
@@ -563,11 +631,13 @@ impl<'ast> Visitor<'ast, ()> for SymbolTableBuilder {
                 body,
                 line,
                 column,
+                span,
             } => {
-                self.enter_scope("lambda", true, false);
+                let (start, end) = Position::range(*line, *column, span);
+                self.enter_scope_spanning("lambda", true, false, start, end);
 
                 for param in args {
-                    self.define_symbol(&param.name, SymbolType::Parameter, *line, *column);
+                    self.define_symbol_at(&param.name, SymbolType::Parameter, *line, *column, *span, 0);
 
                     if let Some(typ) = &param.typ {
                         self.visit_expr(typ);
@@ -606,9 +676,10 @@ impl<'ast> Visitor<'ast, ()> for SymbolTableBuilder {
                 }
             }
             Expr::ListComp {
-                elt, generators, ..
+                elt, generators, line, column, span,
             } => {
-                self.enter_scope("listcomp", true, false);
+                let (start, end) = Position::range(*line, *column, span);
+                self.enter_scope_spanning("listcomp", true, false, start, end);
 
                 for comp in generators {
                     self.visit_comprehension(comp);
@@ -619,9 +690,10 @@ impl<'ast> Visitor<'ast, ()> for SymbolTableBuilder {
                 self.exit_scope();
             }
             Expr::SetComp {
-                elt, generators, ..
+                elt, generators, line, column, span,
             } => {
-                self.enter_scope("setcomp", true, false);
+                let (start, end) = Position::range(*line, *column, span);
+                self.enter_scope_spanning("setcomp", true, false, start, end);
 
                 for comp in generators {
                     self.visit_comprehension(comp);
@@ -635,9 +707,12 @@ impl<'ast> Visitor<'ast, ()> for SymbolTableBuilder {
                 key,
                 value,
                 generators,
-                ..
+                line,
+                column,
+                span,
             } => {
-                self.enter_scope("dictcomp", true, false);
+                let (start, end) = Position::range(*line, *column, span);
+                self.enter_scope_spanning("dictcomp", true, false, start, end);
 
                 for comp in generators {
                     self.visit_comprehension(comp);
@@ -649,9 +724,10 @@ impl<'ast> Visitor<'ast, ()> for SymbolTableBuilder {
                 self.exit_scope();
             }
             Expr::GeneratorExp {
-                elt, generators, ..
+                elt, generators, line, column, span,
             } => {
-                self.enter_scope("genexpr", true, false);
+                let (start, end) = Position::range(*line, *column, span);
+                self.enter_scope_spanning("genexpr", true, false, start, end);
 
                 for comp in generators {
                     self.visit_comprehension(comp);
@@ -748,9 +824,9 @@ impl<'ast> Visitor<'ast, ()> for SymbolTableBuilder {
     fn visit_expr_as_target(&mut self, expr: &'ast Expr) -> () {
         match expr {
             Expr::Name {
-                id, line, column, ..
+                id, line, column, span, ..
             } => {
-                self.define_symbol(id, SymbolType::Variable, *line, *column);
+                self.define_symbol_at(id, SymbolType::Variable, *line, *column, *span, id.chars().count());
             }
             Expr::Tuple { elts, .. } | Expr::List { elts, .. } => {
                 for elt in elts {
@@ -800,3 +876,277 @@ impl<'ast> Visitor<'ast, ()> for SymbolTableBuilder {
 
     fn visit_parameter(&mut self, _param: &'ast crate::ast::Parameter) -> () {}
 }
+
+/// Index of a scope in a `SymbolTable`
+pub type ScopeId = usize;
+
+/// What introduces a scope
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeKind {
+    Module,
+    Function,
+    Class,
+    Lambda,
+    /// A list, set or dict comprehension or a generator expression
+    Comprehension,
+}
+
+impl ScopeKind {
+    fn of(name: &str, is_function: bool, is_class: bool) -> Self {
+        match name {
+            _ if is_class => ScopeKind::Class,
+            "lambda" if is_function => ScopeKind::Lambda,
+            "listcomp" | "setcomp" | "dictcomp" | "genexpr" if is_function => ScopeKind::Comprehension,
+            _ if is_function => ScopeKind::Function,
+            _ => ScopeKind::Module,
+        }
+    }
+}
+
+/// A line and column in the source, both starting at 1
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub fn new(line: usize, column: usize) -> Self {
+        Position { line, column }
+    }
+
+    /// The positions a node starting at `line`, `column` with `span` covers
+    fn range(line: usize, column: usize, span: &Span) -> (Position, Position) {
+        (
+            Position::new(line, column),
+            Position::new(span.end_line, span.end_column),
+        )
+    }
+}
+
+/// One scope of a `SymbolTable`
+#[derive(Debug, Clone)]
+pub struct ScopeInfo {
+    pub id: ScopeId,
+    pub name: String,
+    pub kind: ScopeKind,
+    pub parent: Option<ScopeId>,
+    pub children: Vec<ScopeId>,
+    /// The source the scope's definition or expression covers; `end` is
+    /// exclusive. The module scope covers everything.
+    pub start: Position,
+    pub end: Position,
+    /// Names defined, declared or used in the scope, in source order
+    pub symbols: Vec<Symbol>,
+    /// Position of each name's symbol in `symbols`
+    index: HashMap<intern::Symbol, usize>,
+}
+
+impl ScopeInfo {
+    /// The symbol called `name` in this scope
+    pub fn symbol(&self, name: &str) -> Option<&Symbol> {
+        let name = intern::Symbol::lookup(name)?;
+        self.index.get(&name).map(|&i| &self.symbols[i])
+    }
+
+    /// Whether `position` is inside the scope
+    pub fn contains(&self, position: Position) -> bool {
+        self.kind == ScopeKind::Module || (self.start <= position && position < self.end)
+    }
+}
+
+/// A place where a name is written: a use, or a definition such as an
+/// assignment target or a `def` header
+#[derive(Debug, Clone, PartialEq)]
+pub struct Occurrence {
+    pub name: String,
+    /// The scope the name is written in
+    pub scope: ScopeId,
+    pub line: usize,
+    pub column: usize,
+    /// Column just past the name, equal to `column` for names with no
+    /// position of their own, like parameters, imports and `global`
+    /// declarations, which are placed at their statement
+    pub end_column: usize,
+    pub is_definition: bool,
+}
+
+impl Occurrence {
+    /// Whether the name covers `position`
+    pub fn covers(&self, position: Position) -> bool {
+        position.line == self.line && self.column <= position.column && position.column < self.end_column
+    }
+}
+
+/// Every scope of a module as a tree, with the symbols each one holds and
+/// the places names are written
+#[derive(Debug, Clone)]
+pub struct SymbolTable {
+    scopes: Vec<ScopeInfo>,
+    occurrences: Vec<Occurrence>,
+}
+
+impl SymbolTable {
+    /// Id of the module scope
+    pub const ROOT: ScopeId = 0;
+
+    fn new() -> Self {
+        SymbolTable {
+            scopes: vec![ScopeInfo {
+                id: Self::ROOT,
+                name: "module".to_string(),
+                kind: ScopeKind::Module,
+                parent: None,
+                children: Vec::new(),
+                start: Position::default(),
+                end: Position::default(),
+                symbols: Vec::new(),
+                index: HashMap::new(),
+            }],
+            occurrences: Vec::new(),
+        }
+    }
+
+    /// The symbol table of `module`
+    pub fn build(module: &Module) -> Self {
+        let mut builder = SymbolTableBuilder::new();
+        builder.visit_module(module);
+        builder.into_symbol_table()
+    }
+
+    /// The module scope
+    pub fn root(&self) -> &ScopeInfo {
+        &self.scopes[Self::ROOT]
+    }
+
+    pub fn scope(&self, id: ScopeId) -> &ScopeInfo {
+        &self.scopes[id]
+    }
+
+    /// All scopes, parents before their children
+    pub fn scopes(&self) -> &[ScopeInfo] {
+        &self.scopes
+    }
+
+    /// Every place a name is written, in the order they were visited
+    pub fn occurrences(&self) -> &[Occurrence] {
+        &self.occurrences
+    }
+
+    /// The scopes enclosing `id`, innermost first
+    pub fn enclosing(&self, id: ScopeId) -> impl Iterator<Item = &ScopeInfo> {
+        std::iter::successors(self.scopes[id].parent, |&parent| self.scopes[parent].parent)
+            .map(|parent| &self.scopes[parent])
+    }
+
+    /// The symbols defined in scope `id`, in source order
+    pub fn symbols_in_scope(&self, id: ScopeId) -> impl Iterator<Item = &Symbol> {
+        self.scopes[id].symbols.iter().filter(|symbol| symbol.is_defined)
+    }
+
+    /// The symbols code in scope `id` can refer to by name: its own and
+    /// those of enclosing scopes it doesn't shadow, sorted by name
+    pub fn visible_symbols(&self, id: ScopeId) -> Vec<&Symbol> {
+        let mut seen = HashSet::new();
+        let mut visible: Vec<&Symbol> = std::iter::once(&self.scopes[id])
+            .chain(self.enclosing(id).filter(|scope| scope.kind != ScopeKind::Class))
+            .flat_map(|scope| self.symbols_in_scope(scope.id))
+            .filter(|symbol| seen.insert(symbol.name.as_str()))
+            .collect();
+        visible.sort_by(|a, b| a.name.cmp(&b.name));
+        visible
+    }
+
+    /// The innermost scope containing `line`, `column`
+    pub fn scope_at_position(&self, line: usize, column: usize) -> ScopeId {
+        let position = Position::new(line, column);
+        let mut id = Self::ROOT;
+        while let Some(&child) = self.scopes[id]
+            .children
+            .iter()
+            .find(|&&child| self.scopes[child].contains(position))
+        {
+            id = child;
+        }
+        id
+    }
+
+    /// The scope defining the symbol `name` refers to in scope `id`, and the
+    /// symbol. Class bodies don't enclose the functions defined in them.
+    pub fn resolve(&self, id: ScopeId, name: &str) -> Option<(ScopeId, &Symbol)> {
+        let candidates = std::iter::once(&self.scopes[id])
+            .chain(self.enclosing(id).filter(|scope| scope.kind != ScopeKind::Class));
+        for scope in candidates {
+            let Some(symbol) = scope.symbol(name) else {
+                continue;
+            };
+            if symbol.is_global {
+                let root = self.root();
+                return root
+                    .symbol(name)
+                    .filter(|symbol| symbol.is_defined)
+                    .map(|symbol| (root.id, symbol));
+            }
+            if symbol.is_defined && !symbol.is_nonlocal {
+                return Some((scope.id, symbol));
+            }
+        }
+        None
+    }
+
+    /// The symbol the name written at `line`, `column` refers to, and the
+    /// scope defining it
+    pub fn lookup_at_position(&self, line: usize, column: usize) -> Option<(ScopeId, &Symbol)> {
+        let position = Position::new(line, column);
+        let occurrence = self
+            .occurrences
+            .iter()
+            .rev()
+            .find(|occurrence| occurrence.covers(position))?;
+        self.resolve(occurrence.scope, &occurrence.name)
+    }
+
+    fn fmt_scope(&self, f: &mut fmt::Formatter<'_>, id: ScopeId, depth: usize) -> fmt::Result {
+        let scope = &self.scopes[id];
+        let indent = "  ".repeat(depth);
+        if scope.kind == ScopeKind::Module {
+            writeln!(f, "{}{} ({:?})", indent, scope.name, scope.kind)?;
+        } else {
+            writeln!(
+                f,
+                "{}{} ({:?}, {}:{}-{}:{})",
+                indent, scope.name, scope.kind, scope.start.line, scope.start.column, scope.end.line, scope.end.column
+            )?;
+        }
+        for symbol in &scope.symbols {
+            let mut flags = Vec::new();
+            if !symbol.is_defined {
+                flags.push("undefined");
+            }
+            if !symbol.is_referenced {
+                flags.push("unused");
+            }
+            if symbol.is_global {
+                flags.push("global");
+            }
+            if symbol.is_nonlocal {
+                flags.push("nonlocal");
+            }
+            write!(f, "{}  {}: {:?} at {}:{}", indent, symbol.name, symbol.symbol_type, symbol.line, symbol.column)?;
+            if !flags.is_empty() {
+                write!(f, " ({})", flags.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+        for &child in &scope.children {
+            self.fmt_scope(f, child, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SymbolTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_scope(f, Self::ROOT, 0)
+    }
+}
@@ -0,0 +1,209 @@
+// trace.rs - Step-by-step execution traces for `cheetah run --trace`
+//
+// Tracing is an instrumentation pass: after every simple statement, and
+// before one that leaves its block, a call to `__trace__` is inserted with
+// the statement's line, its source text and the names it binds next to their
+// values. `for` loops are traced at the start of each iteration with the loop
+// variables. The backend prints each call as a step and stops the program
+// once the step limit is reached.
+
+use crate::ast::{Expr, ExprContext, Module, Number, Span, Stmt};
+
+/// Name of the function the inserted calls go to
+pub const TRACE_FUNCTION: &str = "__trace__";
+
+/// Steps traced before the program is stopped, unless `--max-steps` says otherwise
+pub const DEFAULT_STEP_LIMIT: usize = 1000;
+
+/// `module` with a trace call around every statement; `source` is the text
+/// it was parsed from, for the source of each step
+pub fn instrument(module: &Module, source: &str) -> Module {
+    let lines: Vec<&str> = source.lines().collect();
+    Module {
+        body: instrument_block(&module.body, &lines).collect(),
+    }
+}
+
+/// One step of a trace as the backend prints it
+pub fn format_step(line: usize, text: &str, bindings: &[(String, String)]) -> String {
+    let mut step = format!("{:>4} | {}", line, text);
+    if !bindings.is_empty() {
+        let bindings: Vec<String> = bindings
+            .iter()
+            .map(|(name, value)| format!("{} = {}", name, value))
+            .collect();
+        step.push_str(&format!("    -> {}", bindings.join(", ")));
+    }
+    step.push('\n');
+    step
+}
+
+/// The statements of `body` with trace calls inserted
+fn instrument_block<'a>(
+    body: &'a [Box<Stmt>],
+    lines: &'a [&str],
+) -> impl Iterator<Item = Box<Stmt>> + 'a {
+    body.iter()
+        .flat_map(move |stmt| instrument_stmt(stmt, lines))
+        .flatten()
+}
+
+/// `stmt` and its trace call, in the order they run
+fn instrument_stmt(stmt: &Stmt, lines: &[&str]) -> [Option<Box<Stmt>>; 2] {
+    let line = stmt.line();
+    let text = lines
+        .get(line.wrapping_sub(1))
+        .map_or("", |text| text.trim());
+    match stmt {
+        Stmt::Return { .. } | Stmt::Raise { .. } | Stmt::Break { .. } | Stmt::Continue { .. } => {
+            [Some(trace_call(line, text, &[])), Some(Box::new(stmt.clone()))]
+        }
+        Stmt::Global { .. } | Stmt::Nonlocal { .. } => [Some(Box::new(stmt.clone())), None],
+        Stmt::FunctionDef { .. }
+        | Stmt::ClassDef { .. }
+        | Stmt::For { .. }
+        | Stmt::While { .. }
+        | Stmt::If { .. }
+        | Stmt::With { .. }
+        | Stmt::Try { .. }
+        | Stmt::Match { .. } => [Some(Box::new(instrument_compound(stmt, text, lines))), None],
+        _ => [
+            Some(Box::new(stmt.clone())),
+            Some(trace_call(line, text, &bound_names(stmt))),
+        ],
+    }
+}
+
+/// A compound statement with its blocks instrumented
+fn instrument_compound(stmt: &Stmt, text: &str, lines: &[&str]) -> Stmt {
+    let mut stmt = stmt.clone();
+    match &mut stmt {
+        Stmt::FunctionDef { body, .. } | Stmt::ClassDef { body, .. } | Stmt::With { body, .. } => {
+            *body = instrument_block(body, lines).collect();
+        }
+        Stmt::For {
+            target,
+            body,
+            orelse,
+            line,
+            ..
+        } => {
+            let mut names = Vec::new();
+            target_names(target, &mut names);
+            let traced = std::iter::once(trace_call(*line, text, &names))
+                .chain(instrument_block(body, lines))
+                .collect();
+            *body = traced;
+            *orelse = instrument_block(orelse, lines).collect();
+        }
+        Stmt::While { body, orelse, .. } | Stmt::If { body, orelse, .. } => {
+            *body = instrument_block(body, lines).collect();
+            *orelse = instrument_block(orelse, lines).collect();
+        }
+        Stmt::Try {
+            body,
+            handlers,
+            orelse,
+            finalbody,
+            ..
+        } => {
+            *body = instrument_block(body, lines).collect();
+            for handler in handlers {
+                handler.body = instrument_block(&handler.body, lines).collect();
+            }
+            *orelse = instrument_block(orelse, lines).collect();
+            *finalbody = instrument_block(finalbody, lines).collect();
+        }
+        Stmt::Match { cases, .. } => {
+            for (_, _, body) in cases {
+                *body = instrument_block(body, lines).collect();
+            }
+        }
+        _ => {}
+    }
+    stmt
+}
+
+/// Names whose values are shown after `stmt`: those it assigns, and the
+/// containers it stores into
+fn bound_names(stmt: &Stmt) -> Vec<String> {
+    let mut names = Vec::new();
+    match stmt {
+        Stmt::Assign { targets, .. } => {
+            for target in targets {
+                target_names(target, &mut names);
+            }
+        }
+        Stmt::AugAssign { target, .. } => target_names(target, &mut names),
+        Stmt::AnnAssign {
+            target,
+            value: Some(_),
+            ..
+        } => target_names(target, &mut names),
+        _ => {}
+    }
+    names
+}
+
+fn target_names(target: &Expr, names: &mut Vec<String>) {
+    match target {
+        Expr::Name { id, .. } if !names.contains(id) => names.push(id.clone()),
+        Expr::Tuple { elts, .. } | Expr::List { elts, .. } => {
+            for elt in elts {
+                target_names(elt, names);
+            }
+        }
+        Expr::Starred { value, .. } => target_names(value, names),
+        Expr::Subscript { value, .. } | Expr::Attribute { value, .. } => target_names(value, names),
+        _ => {}
+    }
+}
+
+/// `__trace__(line, text, name, value, ...)` as a statement at `line`
+fn trace_call(line: usize, text: &str, names: &[String]) -> Box<Stmt> {
+    let string = |value: &str| {
+        Box::new(Expr::Str {
+            value: value.to_string(),
+            line,
+            column: 0,
+            span: Span::default(),
+        })
+    };
+    let name = |id: &str| {
+        Box::new(Expr::Name {
+            id: id.to_string(),
+            ctx: ExprContext::Load,
+            line,
+            column: 0,
+            span: Span::default(),
+        })
+    };
+
+    let mut args = vec![
+        Box::new(Expr::Num {
+            value: Number::Integer(line as i64),
+            line,
+            column: 0,
+            span: Span::default(),
+        }),
+        string(text),
+    ];
+    for id in names {
+        args.push(string(id));
+        args.push(name(id));
+    }
+
+    Box::new(Stmt::Expr {
+        value: Box::new(Expr::Call {
+            func: name(TRACE_FUNCTION),
+            args,
+            keywords: Vec::new(),
+            line,
+            column: 0,
+            span: Span::default(),
+        }),
+        line,
+        column: 0,
+        span: Span::default(),
+    })
+}
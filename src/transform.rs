@@ -0,0 +1,61 @@
+// transform.rs - AST-to-AST transform passes run between parsing and type checking
+//
+// Crates embedding Cheetah register transforms at startup to try out syntax
+// sugar or instrumentation without changing the compiler. Every module that
+// is compiled, run by the interpreter or entered in the REPL goes through the
+// registered transforms, in registration order, before it is type checked.
+
+use crate::ast::Module;
+use std::borrow::Cow;
+use std::sync::{Arc, RwLock};
+
+/// A pass that rewrites a parsed module
+pub trait AstTransform: Send + Sync {
+    /// Name used in error messages
+    fn name(&self) -> &str;
+
+    /// Rewrite `module`, or explain why it can't be compiled. Statements
+    /// keep their `line` so debug info and source maps point at the original
+    /// source; new statements with line 0 take the line of the one before.
+    fn transform(&self, module: Module) -> Result<Module, String>;
+}
+
+static TRANSFORMS: RwLock<Vec<Arc<dyn AstTransform>>> = RwLock::new(Vec::new());
+
+/// Add a transform to run on every module from now on, after those already registered
+pub fn register<T: AstTransform + 'static>(transform: T) {
+    TRANSFORMS
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .push(Arc::new(transform));
+}
+
+/// Names of the registered transforms, in the order they run
+pub fn registered() -> Vec<String> {
+    snapshot().iter().map(|t| t.name().to_string()).collect()
+}
+
+/// Run the registered transforms over `module`. The module is borrowed
+/// unchanged when no transforms are registered.
+pub fn apply(module: &Module) -> Result<Cow<'_, Module>, String> {
+    let transforms = snapshot();
+    if transforms.is_empty() {
+        return Ok(Cow::Borrowed(module));
+    }
+
+    let mut module = module.clone();
+    for transform in transforms {
+        module = transform
+            .transform(module)
+            .map_err(|e| format!("Transform '{}' failed: {}", transform.name(), e))?;
+    }
+    Ok(Cow::Owned(module))
+}
+
+/// The registered transforms, copied so none run while the registry is locked
+fn snapshot() -> Vec<Arc<dyn AstTransform>> {
+    TRANSFORMS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+}
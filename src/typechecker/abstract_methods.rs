@@ -0,0 +1,70 @@
+// abstract_methods.rs - Abstract methods and the classes that leave them unimplemented
+//
+// A method is abstract when it is decorated with `@abstractmethod` or its
+// body is only `...`. A class can't be instantiated while the first
+// definition of some method along its MRO is abstract.
+
+use crate::ast::{Expr, Stmt};
+
+/// Whether a method is abstract: decorated with `@abstractmethod` (or
+/// `@abc.abstractmethod`), or with a body of only `...` after any docstring
+pub fn is_abstract_method(decorator_list: &[Box<Expr>], body: &[Box<Stmt>]) -> bool {
+    let decorated = decorator_list.iter().any(|decorator| match &**decorator {
+        Expr::Name { id, .. } => id == "abstractmethod",
+        Expr::Attribute { attr, .. } => attr == "abstractmethod",
+        _ => false,
+    });
+
+    let statements = match body {
+        [first, rest @ ..] if is_expr_stmt(first, |value| matches!(value, Expr::Str { .. })) => rest,
+        _ => body,
+    };
+    let ellipsis_body = matches!(
+        statements,
+        [only] if is_expr_stmt(only, |value| matches!(value, Expr::Ellipsis { .. }))
+    );
+
+    decorated || ellipsis_body
+}
+
+fn is_expr_stmt(stmt: &Stmt, predicate: impl Fn(&Expr) -> bool) -> bool {
+    matches!(stmt, Stmt::Expr { value, .. } if predicate(value))
+}
+
+/// The methods of a class's body, each with whether it is abstract
+pub fn class_methods(body: &[Box<Stmt>]) -> Vec<(String, bool)> {
+    body.iter()
+        .filter_map(|stmt| match &**stmt {
+            Stmt::FunctionDef {
+                name,
+                body,
+                decorator_list,
+                ..
+            } => Some((name.clone(), is_abstract_method(decorator_list, body))),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The abstract methods no class overrides, given a class's MRO and the
+/// methods each class defines itself, sorted by name
+pub fn unimplemented<F>(mro: &[String], methods_of: F) -> Vec<String>
+where
+    F: Fn(&str) -> Vec<(String, bool)>,
+{
+    let mut seen = Vec::new();
+    let mut missing = Vec::new();
+    for class in mro {
+        for (method, is_abstract) in methods_of(class) {
+            if seen.contains(&method) {
+                continue;
+            }
+            if is_abstract {
+                missing.push(method.clone());
+            }
+            seen.push(method);
+        }
+    }
+    missing.sort();
+    missing
+}
@@ -1,15 +1,27 @@
 use crate::ast::{Expr, Module, Parameter, Stmt};
+use crate::compiler::ffi::ExternSignature;
 use crate::compiler::types::{Type, TypeError};
+use crate::typechecker::abstract_methods::is_abstract_method;
+use crate::linter::{blocks, exprs};
 use crate::typechecker::environment::TypeEnvironment;
 use crate::typechecker::inference::TypeInference;
+use crate::typechecker::lints::{self, Warning, WarningKind};
+use crate::typechecker::stubs::ModuleStub;
+use crate::typechecker::unbound;
 use crate::typechecker::TypeResult;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Type checker for Cheetah language
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TypeChecker {
     /// Type environment for tracking variable types
     env: TypeEnvironment,
+    /// Non-fatal diagnostics such as lossy implicit conversions
+    warnings: Vec<Warning>,
+    /// Line and column of the statement being checked
+    position: (usize, usize),
+    /// Types returned by each unannotated function being checked, innermost last
+    returned: Vec<Option<Vec<Type>>>,
 }
 
 impl TypeChecker {
@@ -17,28 +29,163 @@ impl TypeChecker {
     pub fn new() -> Self {
         Self {
             env: TypeEnvironment::new(),
+            warnings: Vec::new(),
+            position: (0, 0),
+            returned: Vec::new(),
         }
     }
 
+    /// Check `module.name` against `stub`, as for a native library's stub file
+    pub fn add_stub(&mut self, stub: ModuleStub) {
+        self.env.add_stub(stub);
+    }
+
+    /// The stub `name.attr` is checked against, unless the code defines `name`
+    pub fn module_stub(&self, name: &str) -> Option<&ModuleStub> {
+        self.env.lookup_module(name)
+    }
+
+    /// Warnings collected while checking
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Type of a variable defined by the code checked so far
+    pub fn variable_type(&self, name: &str) -> Option<&Type> {
+        self.env.lookup_variable(name)
+    }
+
+    /// Return type of a function defined by the code checked so far, as
+    /// annotated or inferred from its return statements
+    pub fn return_type(&self, name: &str) -> Option<Type> {
+        match self.env.lookup_function(name)? {
+            Type::Function { return_type, .. } => Some(*return_type.clone()),
+            _ => None,
+        }
+    }
+
+    /// Type of `expr` given the definitions of the code checked so far
+    pub fn expression_type(&self, expr: &Expr) -> Option<Type> {
+        TypeInference::infer_expr_immut(&self.env, expr).ok()
+    }
+
+    /// Record a warning if converting `from` to `to` moves down the numeric lattice
+    fn check_lossy_conversion(
+        &mut self,
+        from: &Type,
+        to: &Type,
+        context: &str,
+        line: usize,
+        column: usize,
+    ) {
+        if Type::is_lossy_conversion(from, to) {
+            self.warnings.push(Warning {
+                kind: WarningKind::LossyConversion,
+                message: format!(
+                    "implicit conversion from {} to {} in {} may lose information",
+                    from, to, context
+                ),
+                line,
+                column,
+            });
+        }
+    }
+
+    /// Warn about lossy argument conversions in calls to known functions
+    fn check_call_conversions(&mut self, expr: &Expr) {
+        if let Expr::Call {
+            func, args, line, column, ..
+        } = expr
+        {
+            if let Expr::Name { id, .. } = &**func {
+                if let Some(Type::Function { param_types, .. }) = self.env.lookup_function(id) {
+                    let param_types = param_types.clone();
+                    for (arg, param_type) in args.iter().zip(param_types.iter()) {
+                        if let Ok(arg_type) = TypeInference::infer_expr_immut(&self.env, arg) {
+                            let context = format!("argument to '{}'", id);
+                            self.check_lossy_conversion(
+                                &arg_type, param_type, &context, *line, *column,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        for child in lints::children(expr) {
+            self.check_call_conversions(child);
+        }
+    }
+
+    /// Line and column of the last statement checked, which is where a failed
+    /// check stopped
+    pub fn position(&self) -> (usize, usize) {
+        self.position
+    }
+
     /// Type check a module
     pub fn check_module(&mut self, module: &Module) -> TypeResult<()> {
         for stmt in &module.body {
             self.check_stmt(stmt)?;
         }
 
-        Ok(())
+        self.check_unbound_locals(module)
+    }
+
+    /// Reject reads of locals no assignment reaches and warn about those
+    /// only some paths assign
+    fn check_unbound_locals(&mut self, module: &Module) -> TypeResult<()> {
+        let reads = unbound::unbound_reads(module);
+        for read in reads.iter().filter(|read| !read.always) {
+            self.warnings.push(Warning {
+                kind: WarningKind::PossiblyUnbound,
+                message: format!(
+                    "local variable '{}' may be read before it is assigned",
+                    read.name
+                ),
+                line: read.line,
+                column: read.column,
+            });
+        }
+        match reads.into_iter().find(|read| read.always) {
+            Some(read) => {
+                self.position = (read.line, read.column);
+                Err(TypeError::UnboundLocal {
+                    name: read.name,
+                    function: read.function,
+                })
+            }
+            None => Ok(()),
+        }
     }
 
     /// Type check a statement
     pub fn check_stmt(&mut self, stmt: &Box<Stmt>) -> TypeResult<()> {
+        self.position = (stmt.line(), stmt.column());
         match &**stmt {
             Stmt::FunctionDef {
                 name,
                 params,
                 body,
+                decorator_list,
                 returns,
                 ..
-            } => self.check_function_def(name, params, body, returns),
+            } => {
+                let signature =
+                    ExternSignature::from_def(name, params, returns, decorator_list, body).map_err(
+                        |reason| TypeError::InvalidExtern {
+                            function: name.clone(),
+                            reason,
+                        },
+                    )?;
+                match signature {
+                    Some(signature) => {
+                        self.check_extern_def(name, params, signature);
+                        Ok(())
+                    }
+                    None => self.check_function_def(name, params, body, returns),
+                }
+            }
 
             Stmt::ClassDef {
                 name, bases, body, ..
@@ -48,12 +195,12 @@ impl TypeChecker {
                 value,
                 line,
                 column,
+                span: _,
             } => self.check_return(value, *line, *column),
 
             Stmt::Assign { targets, value, .. } => {
                 let value_type = TypeInference::infer_expr_immut(&self.env, value)?;
-
-                println!("Assignment value type: {:?}", value_type);
+                self.check_call_conversions(value);
 
                 let mut enhanced_value_type = value_type.clone();
                 if let Expr::Call { func, .. } = &**value {
@@ -115,7 +262,9 @@ impl TypeChecker {
                 target,
                 annotation,
                 value,
-                ..
+                line,
+                column,
+                span: _,
             } => {
                 let target_type = self.expr_to_type(annotation)?;
 
@@ -129,6 +278,15 @@ impl TypeChecker {
                             operation: "annotated assignment".to_string(),
                         });
                     }
+
+                    self.check_lossy_conversion(
+                        &value_type,
+                        &target_type,
+                        "annotated assignment",
+                        *line,
+                        *column,
+                    );
+                    self.check_call_conversions(value);
                 }
 
                 if let Expr::Name { id, .. } = &**target {
@@ -170,13 +328,7 @@ impl TypeChecker {
 
                 self.env.push_scope();
 
-                if let Expr::Name { id, .. } = &**target {
-                    self.env.add_variable(id.clone(), element_type);
-                } else {
-                    return Err(TypeError::CannotInferType(
-                        "Only simple variable names are supported for loop targets".to_string(),
-                    ));
-                }
+                TypeInference::bind_loop_target(&mut self.env, target, element_type)?;
 
                 for stmt in body {
                     self.check_stmt(stmt)?;
@@ -269,6 +421,7 @@ impl TypeChecker {
 
             Stmt::Expr { value, .. } => {
                 let _ = TypeInference::infer_expr_immut(&self.env, value)?;
+                self.check_call_conversions(value);
                 Ok(())
             }
 
@@ -284,6 +437,61 @@ impl TypeChecker {
         body: &[Box<Stmt>],
         returns: &Option<Box<Expr>>,
     ) -> TypeResult<()> {
+        let func_type = self.function_type(params, returns)?;
+        let Type::Function {
+            param_types,
+            return_type,
+            ..
+        } = func_type.clone()
+        else {
+            unreachable!("function_type returns a function type");
+        };
+
+        self.env.add_function(name.to_string(), func_type.clone());
+
+        self.env.push_scope();
+
+        self.env.set_return_type(*return_type);
+
+        for (param, param_type) in params.iter().zip(param_types.iter()) {
+            self.env
+                .add_variable(param.name.clone(), param_type.clone());
+        }
+
+        // Functions without a return annotation take the type they return
+        let infer_return = returns.is_none() && self.env.current_class().is_none();
+        self.returned.push(infer_return.then(Vec::new));
+
+        for stmt in body {
+            let _ = self.check_stmt(stmt);
+        }
+
+        let inferred = self.returned.pop().flatten().and_then(|types| single_return_type(&types, body));
+
+        // Errors in bodies are tolerated above, but a super() call that
+        // resolves to nothing can't work at runtime
+        let super_calls = match self.env.current_class() {
+            Some(_) => self.check_super_calls(body),
+            None => Ok(()),
+        };
+
+        self.env.clear_return_type();
+
+        self.env.pop_scope();
+
+        if let Some(inferred) = inferred {
+            let mut func_type = func_type;
+            if let Type::Function { return_type, .. } = &mut func_type {
+                **return_type = inferred;
+            }
+            self.env.update_function(name.to_string(), func_type);
+        }
+
+        super_calls
+    }
+
+    /// The type of a function with the given parameters and return annotation
+    fn function_type(&self, params: &[Parameter], returns: &Option<Box<Expr>>) -> TypeResult<Type> {
         let mut param_types = Vec::with_capacity(params.len());
         let mut param_names = Vec::with_capacity(params.len());
         let mut default_values = Vec::with_capacity(params.len());
@@ -312,37 +520,65 @@ impl TypeChecker {
             Type::Any
         };
 
-        let func_type = Type::Function {
-            param_types: param_types.clone(),
-            param_names: param_names.clone(),
+        Ok(Type::Function {
+            param_types,
+            param_names,
             has_varargs: params.iter().any(|p| p.is_vararg),
             has_kwargs: params.iter().any(|p| p.is_kwarg),
             default_values,
-            return_type: Box::new(return_type.clone()),
-        };
-
-        self.env.add_function(name.to_string(), func_type);
-
-        self.env.push_scope();
-
-        self.env.set_return_type(return_type);
-
-        for (param, param_type) in params.iter().zip(param_types.iter()) {
-            self.env
-                .add_variable(param.name.clone(), param_type.clone());
-        }
+            return_type: Box::new(return_type),
+        })
+    }
 
+    /// Check that every `super()` call in a method body, outside nested
+    /// functions and classes, names a method of a later class in the MRO
+    fn check_super_calls(&mut self, body: &[Box<Stmt>]) -> TypeResult<()> {
         for stmt in body {
-            let _ = self.check_stmt(stmt);
+            for expr in exprs(stmt) {
+                self.check_super_calls_in(expr)?;
+            }
+            if !matches!(**stmt, Stmt::FunctionDef { .. } | Stmt::ClassDef { .. }) {
+                for block in blocks(stmt) {
+                    self.check_super_calls(block)?;
+                }
+            }
         }
+        Ok(())
+    }
 
-        self.env.clear_return_type();
-
-        self.env.pop_scope();
+    fn check_super_calls_in(&mut self, expr: &Expr) -> TypeResult<()> {
+        let is_super = |func: &Expr| matches!(func, Expr::Name { id, .. } if id == "super");
+        let super_use = match expr {
+            Expr::Attribute { value, .. } => {
+                matches!(&**value, Expr::Call { func, .. } if is_super(func))
+            }
+            Expr::Call { func, .. } => is_super(func),
+            _ => false,
+        };
+        if super_use && self.env.lookup_function("super").is_none() {
+            self.position = (expr.line(), expr.column());
+            TypeInference::infer_expr_immut(&self.env, expr)?;
+        }
 
+        for child in lints::children(expr) {
+            self.check_super_calls_in(child)?;
+        }
         Ok(())
     }
 
+    /// Register the signature of a C function declared with `@extern`
+    fn check_extern_def(&mut self, name: &str, params: &[Parameter], signature: ExternSignature) {
+        let func_type = Type::Function {
+            param_names: params.iter().map(|param| param.name.clone()).collect(),
+            default_values: vec![false; params.len()],
+            param_types: signature.param_types,
+            has_varargs: false,
+            has_kwargs: false,
+            return_type: Box::new(signature.return_type),
+        };
+        self.env.add_function(name.to_string(), func_type);
+    }
+
     /// Type check a class definition
     fn check_class_def(
         &mut self,
@@ -374,32 +610,79 @@ impl TypeChecker {
             }
         }
 
+        // Record the method signatures first so subclasses and super()
+        // calls can find them
+        let mut methods = HashMap::new();
+        let mut abstract_methods = HashSet::new();
+        for stmt in body {
+            if let Stmt::FunctionDef {
+                name,
+                params,
+                returns,
+                body,
+                decorator_list,
+                ..
+            } = &**stmt
+            {
+                methods.insert(name.clone(), Box::new(self.function_type(params, returns)?));
+                if is_abstract_method(decorator_list, body) {
+                    abstract_methods.insert(name.clone());
+                }
+            }
+        }
+
+        // Class attributes assigned in the body are readable as
+        // `Class.attr`, from the class object or its instances
+        let mut fields = HashMap::new();
+        for stmt in body {
+            match &**stmt {
+                Stmt::Assign { targets, value, .. } => {
+                    let ty = TypeInference::infer_expr_immut(&self.env, value).unwrap_or(Type::Any);
+                    for target in targets {
+                        if let Expr::Name { id, .. } = &**target {
+                            fields.insert(id.clone(), ty.clone());
+                        }
+                    }
+                }
+                Stmt::AnnAssign {
+                    target, annotation, ..
+                } => {
+                    if let Expr::Name { id, .. } = &**target {
+                        fields.insert(id.clone(), self.expr_to_type(annotation)?);
+                    }
+                }
+                _ => {}
+            }
+        }
+
         let class_type = Type::Class {
             name: name.to_string(),
             base_classes,
-            methods: HashMap::new(),
-            fields: HashMap::new(),
+            methods,
+            fields,
         };
 
         self.env.add_class(name.to_string(), class_type);
+        self.env.set_abstract_methods(name, abstract_methods);
+        self.env.class_mro(name)?;
 
+        self.env.enter_class(name);
         self.env.push_scope();
 
-        for stmt in body {
-            self.check_stmt(stmt)?;
-        }
+        let result = body.iter().try_for_each(|stmt| self.check_stmt(stmt));
 
         self.env.pop_scope();
+        self.env.leave_class();
 
-        Ok(())
+        result
     }
 
     /// Type check a return statement
     fn check_return(
         &mut self,
         value: &Option<Box<Expr>>,
-        _line: usize,
-        _column: usize,
+        line: usize,
+        column: usize,
     ) -> TypeResult<()> {
         let return_type = if let Some(rt) = self.env.get_return_type() {
             rt.clone()
@@ -411,6 +694,9 @@ impl TypeChecker {
 
         if let Some(value) = value {
             let value_type = TypeInference::infer_expr_immut(&self.env, value)?;
+            if let Some(Some(returned)) = self.returned.last_mut() {
+                returned.push(value_type.clone());
+            }
 
             if !value_type.can_coerce_to(&return_type) {
                 return Err(TypeError::IncompatibleTypes {
@@ -419,6 +705,10 @@ impl TypeChecker {
                     operation: "return".to_string(),
                 });
             }
+
+            self.check_lossy_conversion(&value_type, &return_type, "return", line, column);
+        } else if let Some(Some(returned)) = self.returned.last_mut() {
+            returned.push(Type::None);
         } else if return_type != Type::None && return_type != Type::Any {
             return Err(TypeError::IncompatibleTypes {
                 expected: return_type,
@@ -433,7 +723,9 @@ impl TypeChecker {
     /// Check an assignment target
     fn check_assignment(&mut self, target: &Expr, value_type: &Type) -> TypeResult<()> {
         match target {
-            Expr::Name { id, .. } => {
+            Expr::Name {
+                id, line, column, ..
+            } => {
                 if let Some(target_type) = self.env.lookup_variable(id) {
                     if !value_type.can_coerce_to(target_type) {
                         return Err(TypeError::IncompatibleTypes {
@@ -442,6 +734,14 @@ impl TypeChecker {
                             operation: "assignment".to_string(),
                         });
                     }
+                    let target_type = target_type.clone();
+                    self.check_lossy_conversion(
+                        value_type,
+                        &target_type,
+                        "assignment",
+                        *line,
+                        *column,
+                    );
                 } else {
                     self.env.add_variable(id.clone(), value_type.clone());
                 }
@@ -501,9 +801,9 @@ impl TypeChecker {
             }
 
             Expr::Attribute { value, attr, .. } => {
-                let value_type = TypeInference::infer_expr_immut(&self.env, value)?;
+                let object_type = TypeInference::infer_expr_immut(&self.env, value)?;
 
-                match value_type.get_member_type(attr) {
+                match object_type.get_member_type(attr) {
                     Ok(member_type) => {
                         if !value_type.can_coerce_to(&member_type) {
                             return Err(TypeError::IncompatibleTypes {
@@ -570,6 +870,7 @@ impl TypeChecker {
                 "dict" => Ok(Type::Dict(Box::new(Type::Any), Box::new(Type::Any))),
                 "set" => Ok(Type::Set(Box::new(Type::Any))),
                 "tuple" => Ok(Type::Tuple(vec![])),
+                "Channel" if self.env.lookup_class(id).is_none() => Ok(Type::channel(Type::Any)),
                 _ => {
                     if let Some(ty) = self.env.lookup_class(id) {
                         Ok(ty.clone())
@@ -665,6 +966,8 @@ impl TypeChecker {
             Type::Set(elem_type) => Ok(*elem_type.clone()),
             Type::String => Ok(Type::String),
             Type::Bytes => Ok(Type::Int),
+            // Known only when the program runs, which iterates whatever it finds
+            Type::Any | Type::Unknown => Ok(Type::Any),
             _ => {
                 println!("Invalid iterable type: {:?}", iter_type);
                 Err(TypeError::InvalidOperator {
@@ -676,3 +979,20 @@ impl TypeChecker {
         }
     }
 }
+
+/// The one type every return statement of a function body returns, when
+/// there is one and the body can't fall off its end
+fn single_return_type(returned: &[Type], body: &[Box<Stmt>]) -> Option<Type> {
+    let first = returned.first()?;
+    let agree = returned.iter().all(|ty| ty == first);
+    (agree && !matches!(first, Type::Any | Type::Unknown) && always_returns(body)).then(|| first.clone())
+}
+
+/// Whether running `body` always ends in a return or raise
+fn always_returns(body: &[Box<Stmt>]) -> bool {
+    match body.last().map(|stmt| stmt.as_ref()) {
+        Some(Stmt::Return { .. } | Stmt::Raise { .. }) => true,
+        Some(Stmt::If { body, orelse, .. }) => always_returns(body) && always_returns(orelse),
+        _ => false,
+    }
+}
@@ -1,5 +1,8 @@
-use crate::compiler::types::Type;
-use std::collections::HashMap;
+use crate::compiler::types::{Type, TypeError};
+use crate::typechecker::stubs::{self, ModuleStub};
+use crate::typechecker::{abstract_methods, mro};
+use crate::typechecker::TypeResult;
+use std::collections::{HashMap, HashSet};
 
 /// Represents a scope in the type environment
 #[derive(Debug, Clone)]
@@ -33,6 +36,12 @@ pub struct TypeEnvironment {
     scopes: Vec<Scope>,
     /// Current return type for function checking
     current_return_type: Option<Type>,
+    /// Classes whose bodies are being checked, innermost last
+    class_stack: Vec<String>,
+    /// Methods each class declares abstract
+    abstract_methods: HashMap<String, HashSet<String>>,
+    /// Stubs loaded on top of the shipped ones, by module name
+    stubs: HashMap<String, ModuleStub>,
 }
 
 // Make Scope public so it can be accessed from outside
@@ -42,6 +51,11 @@ impl Scope {
         &self.variables
     }
 
+    /// Get a reference to the functions in this scope
+    pub fn get_functions(&self) -> &HashMap<String, Type> {
+        &self.functions
+    }
+
     /// Check if we're in a tuple context
     pub fn is_in_tuple_context(&self) -> bool {
         self.in_tuple_context
@@ -54,6 +68,9 @@ impl TypeEnvironment {
         let mut env = Self {
             scopes: Vec::new(),
             current_return_type: None,
+            class_stack: Vec::new(),
+            abstract_methods: HashMap::new(),
+            stubs: HashMap::new(),
         };
 
         env.push_scope();
@@ -65,6 +82,7 @@ impl TypeEnvironment {
 
     /// Add built-in types and functions to the environment
     fn add_builtin_types(&mut self) {
+
         self.add_function(
             "print".to_string(),
             Type::function(vec![Type::Any], Type::None),
@@ -95,6 +113,16 @@ impl TypeEnvironment {
             Type::function(vec![Type::Any], Type::Bool),
         );
 
+        self.add_function(
+            "input".to_string(),
+            Type::function(vec![Type::String], Type::String),
+        );
+
+        self.add_function(
+            "open".to_string(),
+            Type::function(vec![Type::String, Type::String], Type::file()),
+        );
+
         self.add_function(
             "min".to_string(),
             Type::function(vec![Type::Any, Type::Any], Type::Any),
@@ -104,6 +132,74 @@ impl TypeEnvironment {
             "max".to_string(),
             Type::function(vec![Type::Any, Type::Any], Type::Any),
         );
+
+        self.add_function(
+            "enumerate".to_string(),
+            Type::function(vec![Type::Any], Type::List(Box::new(Type::Any))),
+        );
+
+        self.add_function(
+            "zip".to_string(),
+            Type::function(vec![Type::Any, Type::Any], Type::List(Box::new(Type::Any))),
+        );
+
+        self.add_function(
+            "map".to_string(),
+            Type::function(vec![Type::Any, Type::Any], Type::List(Box::new(Type::Any))),
+        );
+
+        self.add_function(
+            "filter".to_string(),
+            Type::function(vec![Type::Any, Type::Any], Type::List(Box::new(Type::Any))),
+        );
+
+        self.add_function(
+            "sorted".to_string(),
+            Type::function(vec![Type::Any], Type::List(Box::new(Type::Any))),
+        );
+
+        self.add_function(
+            "reversed".to_string(),
+            Type::function(vec![Type::Any], Type::List(Box::new(Type::Any))),
+        );
+
+        self.add_function(
+            "sum".to_string(),
+            Type::function(vec![Type::Any], Type::Any),
+        );
+
+        self.add_function(
+            "abs".to_string(),
+            Type::function(vec![Type::Any], Type::Any),
+        );
+
+        self.add_function(
+            "round".to_string(),
+            Type::function(vec![Type::Any], Type::Any),
+        );
+
+        self.add_function(
+            "dir".to_string(),
+            Type::function(vec![Type::Any], Type::List(Box::new(Type::String))),
+        );
+
+        self.add_function(
+            "getattr".to_string(),
+            Type::function(vec![Type::Any, Type::String], Type::Any),
+        );
+
+        self.add_function(
+            "setattr".to_string(),
+            Type::function(vec![Type::Any, Type::String, Type::Any], Type::None),
+        );
+
+        // Base class for abstract classes, as imported from `abc`
+        self.add_class("ABC".to_string(), Type::class("ABC"));
+
+        // Builtins registered by the embedding application
+        for builtin in crate::compiler::plugins::registered_builtins() {
+            self.add_function(builtin.name.clone(), builtin.function_type());
+        }
     }
 
     /// Push a new scope onto the stack
@@ -196,6 +292,19 @@ impl TypeEnvironment {
         None
     }
 
+    /// Declare a module's members with its stub, replacing any shipped stub of that name
+    pub fn add_stub(&mut self, stub: ModuleStub) {
+        self.stubs.insert(stub.name.clone(), stub);
+    }
+
+    /// The stub of module `name`, if `name` isn't shadowed by a definition
+    pub fn lookup_module(&self, name: &str) -> Option<&ModuleStub> {
+        if self.is_defined(name) {
+            return None;
+        }
+        self.stubs.get(name).or_else(|| stubs::builtin_stub(name))
+    }
+
     /// Check if a name is defined in the environment (variable, function, or class)
     pub fn is_defined(&self, name: &str) -> bool {
         self.lookup_variable(name).is_some()
@@ -234,4 +343,62 @@ impl TypeEnvironment {
 
         self.add_variable(name.to_string(), ty);
     }
+
+    /// Start checking the body of class `name`
+    pub fn enter_class(&mut self, name: &str) {
+        self.class_stack.push(name.to_string());
+    }
+
+    /// Finish checking the innermost class body
+    pub fn leave_class(&mut self) {
+        self.class_stack.pop();
+    }
+
+    /// The class whose body is being checked, if any
+    pub fn current_class(&self) -> Option<&str> {
+        self.class_stack.last().map(String::as_str)
+    }
+
+    /// The method resolution order of class `name`
+    pub fn class_mro(&self, name: &str) -> TypeResult<Vec<String>> {
+        let bases_of = |class: &str| match self.lookup_class(class) {
+            Some(Type::Class { base_classes, .. }) => base_classes.clone(),
+            _ => Vec::new(),
+        };
+        mro::linearize(name, &bases_of).map_err(|bases| TypeError::InconsistentMro {
+            class_name: name.to_string(),
+            bases,
+        })
+    }
+
+    /// Record the methods class `name` declares abstract
+    pub fn set_abstract_methods(&mut self, name: &str, methods: HashSet<String>) {
+        self.abstract_methods.insert(name.to_string(), methods);
+    }
+
+    /// The abstract methods class `name` defines or inherits that no class
+    /// earlier in its MRO overrides, sorted by name
+    pub fn unimplemented_abstract_methods(&self, name: &str) -> Vec<String> {
+        let Ok(order) = self.class_mro(name) else {
+            return Vec::new();
+        };
+        abstract_methods::unimplemented(&order, |class| match self.lookup_class(class) {
+            Some(Type::Class { methods, .. }) => {
+                let declared = self.abstract_methods.get(class);
+                methods
+                    .keys()
+                    .map(|method| (method.clone(), declared.is_some_and(|d| d.contains(method))))
+                    .collect()
+            }
+            _ => Vec::new(),
+        })
+    }
+
+    /// The type of method `method` as defined by class `class` itself
+    pub fn lookup_method(&self, class: &str, method: &str) -> Option<&Type> {
+        match self.lookup_class(class) {
+            Some(Type::Class { methods, .. }) => methods.get(method).map(|ty| &**ty),
+            _ => None,
+        }
+    }
 }
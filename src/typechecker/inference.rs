@@ -1,8 +1,22 @@
-use crate::ast::{CmpOperator, Expr, NameConstant, Number, Operator, UnaryOperator};
+use crate::ast::{CmpOperator, Expr, ExprContext, NameConstant, Number, Operator, UnaryOperator};
+use crate::compiler::class_layout::annotation_type;
 use crate::compiler::types::{Type, TypeError};
 use crate::typechecker::environment::TypeEnvironment;
+use crate::typechecker::mro;
 use crate::typechecker::TypeResult;
 
+/// Methods every class inherits from `object`, where `super()` lookups end
+const OBJECT_METHODS: [&str; 8] = [
+    "__init__",
+    "__new__",
+    "__repr__",
+    "__str__",
+    "__eq__",
+    "__ne__",
+    "__hash__",
+    "__format__",
+];
+
 /// Type inference for expressions
 pub struct TypeInference;
 
@@ -274,6 +288,87 @@ impl TypeInference {
                         "print" => {
                             return Ok(Type::None);
                         }
+                        "super" if env.lookup_function(id).is_none() => {
+                            Self::super_search_order(env, args)?;
+                            return Ok(Type::Any);
+                        }
+                        "input" => {
+                            if args.len() > 1 {
+                                return Err(TypeError::InvalidArgumentCount {
+                                    expected: "at most 1".to_string(),
+                                    got: args.len(),
+                                    function: "input".to_string(),
+                                });
+                            }
+                            for arg in args {
+                                Self::infer_expr(env, arg)?;
+                            }
+                            return Ok(Type::String);
+                        }
+                        "open" => {
+                            if args.is_empty() || args.len() > 2 {
+                                return Err(TypeError::InvalidArgumentCount {
+                                    expected: "1 or 2".to_string(),
+                                    got: args.len(),
+                                    function: "open".to_string(),
+                                });
+                            }
+                            let mut string_args: Vec<(&Expr, &str)> =
+                                args.iter().map(|arg| arg.as_ref()).zip(["open path", "open mode"]).collect();
+                            for (name, value) in keywords {
+                                match name.as_deref() {
+                                    Some("mode") => string_args.push((value, "open mode")),
+                                    Some("encoding") => string_args.push((value, "open encoding")),
+                                    other => {
+                                        return Err(TypeError::UnexpectedKeyword {
+                                            function: "open".to_string(),
+                                            keyword: other.unwrap_or("**").to_string(),
+                                        });
+                                    }
+                                }
+                            }
+                            for (arg, operation) in string_args {
+                                let arg_type = Self::infer_expr(env, arg)?;
+                                if !matches!(arg_type, Type::String | Type::Any | Type::Unknown) {
+                                    return Err(TypeError::IncompatibleTypes {
+                                        expected: Type::String,
+                                        got: arg_type,
+                                        operation: operation.to_string(),
+                                    });
+                                }
+                            }
+                            return Ok(Type::file());
+                        }
+                        "enumerate" | "zip" | "map" | "filter" => {
+                            return Self::infer_iter_builtin(env, id, args);
+                        }
+                        "channel" | "send" | "recv" if !env.is_defined(id) => {
+                            if let Some((name, _)) = keywords.first() {
+                                return Err(TypeError::UnexpectedKeyword {
+                                    function: id.clone(),
+                                    keyword: name.as_deref().unwrap_or("**").to_string(),
+                                });
+                            }
+                            return Self::infer_channel_builtin(env, id, args);
+                        }
+                        "with_timeout" if !env.is_defined(id) => {
+                            if let Some((name, _)) = keywords.first() {
+                                return Err(TypeError::UnexpectedKeyword {
+                                    function: id.clone(),
+                                    keyword: name.as_deref().unwrap_or("**").to_string(),
+                                });
+                            }
+                            return Self::infer_with_timeout(env, args);
+                        }
+                        "dir" | "getattr" | "setattr" if keywords.is_empty() => {
+                            return Self::infer_introspection_builtin(env, id, args);
+                        }
+                        "sorted" | "reversed" | "sum" | "abs" | "round" => {
+                            for (_, value) in keywords {
+                                Self::infer_expr(env, value)?;
+                            }
+                            return Self::infer_sequence_builtin(env, id, args);
+                        }
                         "range" => {
                             match args.len() {
                                 1 => {
@@ -346,10 +441,28 @@ impl TypeInference {
 
                 let func_type = Self::infer_expr(env, func)?;
 
+                // A value fetched from an untyped container may be anything callable
+                if func_type == Type::Any {
+                    for arg in args {
+                        Self::infer_expr(env, arg)?;
+                    }
+                    return Ok(Type::Any);
+                }
+
                 if !func_type.is_callable() {
                     return Err(TypeError::NotCallable(func_type));
                 }
 
+                if let Type::Class { name, .. } = &func_type {
+                    let methods = env.unimplemented_abstract_methods(name);
+                    if !methods.is_empty() {
+                        return Err(TypeError::AbstractInstantiation {
+                            class_name: name.clone(),
+                            methods,
+                        });
+                    }
+                }
+
                 let mut arg_types = Vec::with_capacity(args.len());
                 for arg in args {
                     arg_types.push(Self::infer_expr(env, arg)?);
@@ -496,9 +609,28 @@ impl TypeInference {
             }
 
             Expr::Attribute { value, attr, .. } => {
+                if let Expr::Name { id, .. } = &**value {
+                    if let Some(stub) = env.lookup_module(id) {
+                        return stub.member(attr).cloned().ok_or_else(|| TypeError::UndefinedMember {
+                            class_name: id.clone(),
+                            member: attr.clone(),
+                        });
+                    }
+                }
+
+                if let Expr::Call { func, args, .. } = &**value {
+                    if matches!(&**func, Expr::Name { id, .. } if id == "super" && env.lookup_function(id).is_none()) {
+                        return Self::infer_super_member(env, args, attr);
+                    }
+                }
+
                 let value_type = Self::infer_expr(env, value)?;
 
-                value_type.get_member_type(attr)
+                match (value_type.get_member_type(attr), &value_type) {
+                    (Err(error), Type::Class { name, .. }) => Self::infer_inherited_member(env, name, attr)
+                        .ok_or(error),
+                    (result, _) => result,
+                }
             }
 
             Expr::Subscript { value, slice, .. } => {
@@ -589,35 +721,29 @@ impl TypeInference {
 
                     env.push_scope();
 
-                    if let Expr::Name { id, .. } = &*generator.target {
-                        let element_type = match &iter_type {
-                            Type::List(elem_type) => {
-                                println!("List element type: {:?}", *elem_type);
-                                *elem_type.clone()
-                            }
-                            Type::Tuple(elem_types) => {
-                                if !elem_types.is_empty() {
-                                    println!("Using first element of tuple: {:?}", elem_types[0]);
-                                    elem_types[0].clone()
-                                } else {
-                                    println!("Empty tuple, using Int");
-                                    Type::Int
-                                }
-                            }
-                            Type::String => Type::String,
-                            Type::Dict(key_type, _) => *key_type.clone(),
-                            _ => {
-                                println!("Unknown iterable type: {:?}, using Any", iter_type);
-                                Type::Any
+                    let item_type = match &iter_type {
+                        Type::List(elem_type) => {
+                            println!("List element type: {:?}", *elem_type);
+                            *elem_type.clone()
+                        }
+                        Type::Tuple(elem_types) => {
+                            if !elem_types.is_empty() {
+                                println!("Using first element of tuple: {:?}", elem_types[0]);
+                                elem_types[0].clone()
+                            } else {
+                                println!("Empty tuple, using Int");
+                                Type::Int
                             }
-                        };
+                        }
+                        Type::String => Type::String,
+                        Type::Dict(key_type, _) => *key_type.clone(),
+                        _ => {
+                            println!("Unknown iterable type: {:?}, using Any", iter_type);
+                            Type::Any
+                        }
+                    };
 
-                        println!(
-                            "Setting list comprehension variable '{}' to type: {:?}",
-                            id, element_type
-                        );
-                        env.add_variable(id.clone(), element_type);
-                    }
+                    Self::bind_loop_target(env, &generator.target, item_type)?;
 
                     let element_type = Self::infer_expr(env, elt)?;
 
@@ -666,8 +792,437 @@ impl TypeInference {
         }
     }
 
+    /// Infer the list produced by enumerate(), zip(), map() or filter()
+    fn infer_iter_builtin(env: &mut TypeEnvironment, name: &str, args: &[Box<Expr>]) -> TypeResult<Type> {
+        let (min_args, max_args) = match name {
+            "enumerate" => (1, 2),
+            "zip" => (1, usize::MAX),
+            "filter" => (2, 2),
+            _ => (2, usize::MAX),
+        };
+        if args.len() < min_args || args.len() > max_args {
+            return Err(TypeError::InvalidArgumentCount {
+                expected: if max_args == usize::MAX {
+                    format!("at least {}", min_args)
+                } else if min_args == max_args {
+                    min_args.to_string()
+                } else {
+                    format!("{} or {}", min_args, max_args)
+                },
+                got: args.len(),
+                function: name.to_string(),
+            });
+        }
+
+        let (func_type, iterables) = match name {
+            "map" | "filter" => (Some(Self::infer_expr(env, &args[0])?), &args[1..]),
+            _ => (None, args),
+        };
+
+        let mut item_types = Vec::with_capacity(iterables.len());
+        for arg in iterables.iter().take(if name == "enumerate" { 1 } else { iterables.len() }) {
+            item_types.push(match Self::infer_expr(env, arg)? {
+                Type::List(elem) | Type::Set(elem) => *elem,
+                Type::Dict(key, _) => *key,
+                Type::String => Type::String,
+                Type::Any | Type::Unknown => Type::Any,
+                other => {
+                    return Err(TypeError::InvalidOperator {
+                        operator: "iteration".to_string(),
+                        left_type: other,
+                        right_type: None,
+                    })
+                }
+            });
+        }
+
+        let item_type = match name {
+            "enumerate" => {
+                if let Some(start) = args.get(1) {
+                    let start_type = Self::infer_expr(env, start)?;
+                    if !matches!(start_type, Type::Int | Type::Bool | Type::Any | Type::Unknown) {
+                        return Err(TypeError::IncompatibleTypes {
+                            expected: Type::Int,
+                            got: start_type,
+                            operation: "enumerate start argument".to_string(),
+                        });
+                    }
+                }
+                Type::Tuple(vec![Type::Int, item_types.remove(0)])
+            }
+            "zip" => Type::Tuple(item_types),
+            "filter" => item_types.remove(0),
+            _ => match func_type {
+                Some(Type::Function { return_type, .. }) => *return_type,
+                _ => Type::Any,
+            },
+        };
+
+        Ok(Type::List(Box::new(item_type)))
+    }
+
+    /// Infer the result of sorted(), reversed(), sum(), abs() or round()
+    fn infer_sequence_builtin(env: &mut TypeEnvironment, name: &str, args: &[Box<Expr>]) -> TypeResult<Type> {
+        let max_args = if matches!(name, "sum" | "round") { 2 } else { 1 };
+        if args.is_empty() || args.len() > max_args {
+            return Err(TypeError::InvalidArgumentCount {
+                expected: if max_args == 1 { "1".to_string() } else { "1 or 2".to_string() },
+                got: args.len(),
+                function: name.to_string(),
+            });
+        }
+
+        let arg_type = Self::infer_expr(env, &args[0])?;
+        let extra_type = match args.get(1) {
+            Some(arg) => Some(Self::infer_expr(env, arg)?),
+            None => None,
+        };
+        let unsupported = |operator: &str, ty: Type| TypeError::InvalidOperator {
+            operator: operator.to_string(),
+            left_type: ty,
+            right_type: None,
+        };
+
+        match name {
+            "sorted" | "reversed" => match arg_type {
+                Type::List(elem) => Ok(Type::List(elem)),
+                Type::String if name == "sorted" => Ok(Type::List(Box::new(Type::String))),
+                Type::Dict(key, _) if name == "sorted" => Ok(Type::List(key)),
+                Type::Any | Type::Unknown => Ok(Type::List(Box::new(Type::Any))),
+                other => Err(unsupported("iteration", other)),
+            },
+            "sum" => {
+                let total = match arg_type {
+                    Type::List(elem) => match *elem {
+                        Type::Int | Type::Bool => Type::Int,
+                        Type::Float | Type::Any | Type::Unknown => Type::Float,
+                        other => return Err(unsupported("+", other)),
+                    },
+                    Type::Any | Type::Unknown => Type::Any,
+                    other => return Err(unsupported("iteration", other)),
+                };
+                match extra_type {
+                    None | Some(Type::Int | Type::Bool) => Ok(total),
+                    Some(Type::Float) if total != Type::Any => Ok(Type::Float),
+                    Some(Type::Float | Type::Any | Type::Unknown) => Ok(Type::Any),
+                    Some(other) => Err(unsupported("+", other)),
+                }
+            }
+            "abs" => match arg_type {
+                Type::Int | Type::Bool => Ok(Type::Int),
+                Type::Float => Ok(Type::Float),
+                Type::Any | Type::Unknown => Ok(Type::Any),
+                other => Err(unsupported("abs", other)),
+            },
+            _ => {
+                if let Some(ndigits) = extra_type {
+                    if !matches!(ndigits, Type::Int | Type::Bool | Type::Any | Type::Unknown) {
+                        return Err(TypeError::IncompatibleTypes {
+                            expected: Type::Int,
+                            got: ndigits,
+                            operation: "round ndigits argument".to_string(),
+                        });
+                    }
+                }
+                match arg_type {
+                    Type::Int | Type::Bool => Ok(Type::Int),
+                    Type::Float if args.len() == 2 => Ok(Type::Float),
+                    Type::Float => Ok(Type::Int),
+                    Type::Any | Type::Unknown => Ok(Type::Any),
+                    other => Err(unsupported("round", other)),
+                }
+            }
+        }
+    }
+
+    /// Infer the result of dir(), getattr() or setattr()
+    fn infer_introspection_builtin(env: &mut TypeEnvironment, name: &str, args: &[Box<Expr>]) -> TypeResult<Type> {
+        let (min_args, max_args, expected) = match name {
+            "dir" => (1, 1, "1"),
+            "getattr" => (2, 3, "2 or 3"),
+            _ => (3, 3, "3"),
+        };
+        if args.len() < min_args || args.len() > max_args {
+            return Err(TypeError::InvalidArgumentCount {
+                expected: expected.to_string(),
+                got: args.len(),
+                function: name.to_string(),
+            });
+        }
+
+        let object_type = Self::infer_expr(env, &args[0])?;
+        if name == "dir" {
+            return Ok(Type::List(Box::new(Type::String)));
+        }
+        let name_type = Self::infer_expr(env, &args[1])?;
+        if !matches!(name_type, Type::String | Type::Any | Type::Unknown) {
+            return Err(TypeError::IncompatibleTypes {
+                expected: Type::String,
+                got: name_type,
+                operation: format!("{} attribute name", name),
+            });
+        }
+        if name == "setattr" {
+            Self::infer_expr(env, &args[2])?;
+            return Ok(Type::None);
+        }
+
+        // A literal name reads the attribute like `obj.name` does
+        let attribute = match &*args[1] {
+            Expr::Str { value, line, column, span } if object_type != Type::Any => {
+                let access = Expr::Attribute {
+                    value: args[0].clone(),
+                    attr: value.clone(),
+                    ctx: ExprContext::Load,
+                    line: *line,
+                    column: *column,
+                    span: *span,
+                };
+                Some(Self::infer_expr(env, &access))
+            }
+            _ => None,
+        };
+        match (attribute, args.get(2)) {
+            (Some(attribute), None) => attribute,
+            (attribute, Some(default)) => {
+                let default_type = Self::infer_expr(env, default)?;
+                match attribute {
+                    Some(Ok(ty)) if ty != Type::Any => Ok(ty),
+                    _ => Ok(default_type),
+                }
+            }
+            (None, None) => Ok(Type::Any),
+        }
+    }
+
+    /// Infer the result of channel(), send() or recv()
+    fn infer_channel_builtin(env: &mut TypeEnvironment, name: &str, args: &[Box<Expr>]) -> TypeResult<Type> {
+        let (min_args, max_args, expected) = match name {
+            "channel" => (0, 1, "0 or 1"),
+            "send" => (2, 2, "2"),
+            _ => (1, 2, "1 or 2"),
+        };
+        if args.len() < min_args || args.len() > max_args {
+            return Err(TypeError::InvalidArgumentCount {
+                expected: expected.to_string(),
+                got: args.len(),
+                function: name.to_string(),
+            });
+        }
+
+        // channel(int) names the type of the values the channel carries
+        if name == "channel" {
+            let element = match args.first() {
+                Some(arg) => annotation_type(arg).ok_or_else(|| {
+                    TypeError::CannotInferType(
+                        "channel() takes the type of the values it carries, such as int or str".to_string(),
+                    )
+                })?,
+                None => Type::Any,
+            };
+            return Ok(Type::channel(element));
+        }
+
+        let channel_type = Self::infer_expr(env, &args[0])?;
+        let element = match channel_type.channel_element() {
+            Some(element) => element.clone(),
+            None if matches!(channel_type, Type::Any | Type::Unknown) => Type::Any,
+            None => {
+                return Err(TypeError::IncompatibleTypes {
+                    expected: Type::channel(Type::Any),
+                    got: channel_type,
+                    operation: format!("{} channel argument", name),
+                })
+            }
+        };
+
+        if name == "send" {
+            let value_type = Self::infer_expr(env, &args[1])?;
+            let widens = matches!((&value_type, &element), (Type::Int | Type::Bool, Type::Float));
+            if !value_type.is_compatible_with(&element) && value_type != Type::Unknown && !widens {
+                return Err(TypeError::IncompatibleTypes {
+                    expected: element,
+                    got: value_type,
+                    operation: "send value".to_string(),
+                });
+            }
+            return Ok(Type::None);
+        }
+
+        if let Some(timeout) = args.get(1) {
+            let timeout_type = Self::infer_expr(env, timeout)?;
+            if !matches!(timeout_type, Type::Int | Type::Float | Type::Bool | Type::Any | Type::Unknown) {
+                return Err(TypeError::IncompatibleTypes {
+                    expected: Type::Float,
+                    got: timeout_type,
+                    operation: "recv timeout argument".to_string(),
+                });
+            }
+        }
+        Ok(element)
+    }
+
+    /// Infer the result of with_timeout(seconds, f): what `f` returns
+    fn infer_with_timeout(env: &mut TypeEnvironment, args: &[Box<Expr>]) -> TypeResult<Type> {
+        if args.len() != 2 {
+            return Err(TypeError::InvalidArgumentCount {
+                expected: "2".to_string(),
+                got: args.len(),
+                function: "with_timeout".to_string(),
+            });
+        }
+        let seconds_type = Self::infer_expr(env, &args[0])?;
+        if !matches!(seconds_type, Type::Int | Type::Float | Type::Bool | Type::Any | Type::Unknown) {
+            return Err(TypeError::IncompatibleTypes {
+                expected: Type::Float,
+                got: seconds_type,
+                operation: "with_timeout seconds argument".to_string(),
+            });
+        }
+        match Self::infer_expr(env, &args[1])? {
+            Type::Function { param_types, .. } if !param_types.is_empty() => Err(TypeError::InvalidArgumentCount {
+                expected: param_types.len().to_string(),
+                got: 0,
+                function: "with_timeout function".to_string(),
+            }),
+            Type::Function { return_type, .. } => Ok(*return_type),
+            Type::Any | Type::Unknown => Ok(Type::Any),
+            other => Err(TypeError::NotCallable(other)),
+        }
+    }
+
+    /// The type of member `attr` that class `class` inherits through its MRO
+    fn infer_inherited_member(env: &TypeEnvironment, class: &str, attr: &str) -> Option<Type> {
+        let order = env.class_mro(class).ok()?;
+        order[1..]
+            .iter()
+            .find_map(|base| env.lookup_class(base)?.get_member_type(attr).ok())
+    }
+
+    /// The classes `super(*args)` searches, in order, for a method of the
+    /// class being checked. `super()` starts after the current class and
+    /// `super(C, self)` after `C`.
+    pub fn super_search_order(env: &TypeEnvironment, args: &[Box<Expr>]) -> TypeResult<Vec<String>> {
+        let class = match env.current_class() {
+            Some(class) if env.get_return_type().is_some() => class.to_string(),
+            _ => return Err(TypeError::InvalidSuper("used outside a method".to_string())),
+        };
+        let start = match args {
+            [] => class.clone(),
+            [first, _] => match &**first {
+                Expr::Name { id, .. } => id.clone(),
+                _ => {
+                    return Err(TypeError::InvalidSuper(
+                        "the first argument must be a class name".to_string(),
+                    ))
+                }
+            },
+            _ => {
+                return Err(TypeError::InvalidArgumentCount {
+                    function: "super".to_string(),
+                    expected: "0 or 2".to_string(),
+                    got: args.len(),
+                })
+            }
+        };
+
+        let order = env.class_mro(&class)?;
+        if !order.contains(&start) {
+            return Err(TypeError::InvalidSuper(format!(
+                "'{}' is not a base of '{}'",
+                start, class
+            )));
+        }
+        Ok(mro::following(&order, &start).to_vec())
+    }
+
+    /// Infer `super(*args).attr`: the first method named `attr` in the
+    /// search order, bound to `self`
+    fn infer_super_member(env: &TypeEnvironment, args: &[Box<Expr>], attr: &str) -> TypeResult<Type> {
+        for class in Self::super_search_order(env, args)? {
+            if let Some(method) = env.lookup_method(&class, attr) {
+                return Ok(match method {
+                    Type::Function {
+                        param_types,
+                        param_names,
+                        has_varargs,
+                        has_kwargs,
+                        default_values,
+                        return_type,
+                    } if !param_types.is_empty() => Type::Function {
+                        param_types: param_types[1..].to_vec(),
+                        param_names: param_names[1..].to_vec(),
+                        has_varargs: *has_varargs,
+                        has_kwargs: *has_kwargs,
+                        default_values: default_values[1..].to_vec(),
+                        return_type: return_type.clone(),
+                    },
+                    other => other.clone(),
+                });
+            }
+        }
+
+        match attr {
+            "__init__" => Ok(Type::function(vec![], Type::None)),
+            _ if OBJECT_METHODS.contains(&attr) => Ok(Type::Any),
+            _ => Err(TypeError::InvalidSuper(format!(
+                "no base of '{}' defines '{}'",
+                env.current_class().unwrap_or("object"),
+                attr
+            ))),
+        }
+    }
+
+    /// Bind the names in a loop or comprehension target to the parts of an item type
+    pub fn bind_loop_target(env: &mut TypeEnvironment, target: &Expr, item_type: Type) -> TypeResult<()> {
+        match target {
+            Expr::Name { id, .. } => {
+                env.add_variable(id.clone(), item_type);
+                Ok(())
+            }
+            Expr::Tuple { elts, .. } | Expr::List { elts, .. } => {
+                let part_types = match &item_type {
+                    Type::Tuple(types) if types.len() == elts.len() => types.clone(),
+                    Type::Tuple(_) => {
+                        return Err(TypeError::IncompatibleTypes {
+                            expected: Type::Tuple(vec![Type::Any; elts.len()]),
+                            got: item_type,
+                            operation: "tuple unpacking".to_string(),
+                        })
+                    }
+                    Type::List(elem) => vec![*elem.clone(); elts.len()],
+                    _ => vec![Type::Any; elts.len()],
+                };
+
+                for (elt, part_type) in elts.iter().zip(part_types) {
+                    Self::bind_loop_target(env, elt, part_type)?;
+                }
+                Ok(())
+            }
+            _ => Err(TypeError::CannotInferType(
+                "Only simple variable names and tuples are supported for loop targets".to_string(),
+            )),
+        }
+    }
+
     /// Infer the type of a binary operation
     pub fn infer_binary_op(left_type: &Type, op: &Operator, right_type: &Type) -> TypeResult<Type> {
+        let arithmetic = matches!(
+            op,
+            Operator::Add
+                | Operator::Sub
+                | Operator::Mult
+                | Operator::Div
+                | Operator::FloorDiv
+                | Operator::Mod
+                | Operator::Pow
+        );
+        if arithmetic {
+            if let Some(promoted) = Type::promote_arithmetic(left_type, right_type) {
+                return Ok(promoted);
+            }
+        }
+
         match op {
             Operator::Add => match (left_type, right_type) {
                 (Type::Int, Type::Int) => Ok(Type::Int),
@@ -752,7 +1307,7 @@ impl TypeInference {
             | Operator::BitAnd
             | Operator::LShift
             | Operator::RShift => match (left_type, right_type) {
-                (Type::Int, Type::Int) => Ok(Type::Int),
+                (Type::Int | Type::Bool, Type::Int | Type::Bool) => Ok(Type::Int),
                 _ => Err(TypeError::InvalidOperator {
                     operator: match op {
                         Operator::BitOr => "|".to_string(),
@@ -807,12 +1362,12 @@ impl TypeInference {
             CmpOperator::Eq | CmpOperator::NotEq => Ok(()),
 
             CmpOperator::Lt | CmpOperator::LtE | CmpOperator::Gt | CmpOperator::GtE => {
+                if Type::promote_numeric(left_type, right_type).is_some() {
+                    return Ok(());
+                }
+
                 match (left_type, right_type) {
-                    (Type::Int, Type::Int)
-                    | (Type::Int, Type::Float)
-                    | (Type::Float, Type::Int)
-                    | (Type::Float, Type::Float)
-                    | (Type::String, Type::String) => Ok(()),
+                    (Type::String, Type::String) => Ok(()),
 
                     _ => Err(TypeError::InvalidOperator {
                         operator: format!("{:?}", op),
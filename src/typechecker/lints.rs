@@ -0,0 +1,514 @@
+use crate::ast::{Comprehension, ExceptHandler, Expr, Module, Stmt};
+use crate::lexer::{keywords, Lexer, TokenType};
+use crate::linter::{blocks, exprs};
+use std::fmt;
+
+/// Marker that suppresses warnings on the line it appears on
+const SUPPRESSION_MARKER: &str = "# cheetah: ignore";
+
+/// Kinds of warnings produced by the lint pass
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WarningKind {
+    /// A value is stored and then overwritten before it is read
+    DeadStore,
+    /// An expression statement whose result is discarded and has no side effects
+    PureExpression,
+    /// An implicit numeric conversion down the `Bool < Int < Float` lattice
+    LossyConversion,
+    /// A soft keyword such as `match` used as a name
+    SoftKeyword,
+    /// A local variable that is assigned but never read (`cheetah lint` only)
+    UnusedVariable,
+    /// An imported name that is never used (`cheetah lint` only)
+    UnusedImport,
+    /// A local name that hides one from an enclosing scope (`cheetah lint` only)
+    ShadowedName,
+    /// Statements after a `return`, `raise`, `break` or `continue` (`cheetah lint` only)
+    UnreachableCode,
+    /// `== None` or `!= None` instead of `is None` (`cheetah lint` only)
+    NoneComparison,
+    /// A local variable read where only some paths have assigned it
+    PossiblyUnbound,
+    /// A string built with `+=` in a loop rather than with `str.join` (`cheetah lint` only)
+    StringConcatInLoop,
+}
+
+impl WarningKind {
+    /// Every kind of warning
+    pub const ALL: [WarningKind; 11] = [
+        WarningKind::DeadStore,
+        WarningKind::PureExpression,
+        WarningKind::LossyConversion,
+        WarningKind::SoftKeyword,
+        WarningKind::UnusedVariable,
+        WarningKind::UnusedImport,
+        WarningKind::ShadowedName,
+        WarningKind::UnreachableCode,
+        WarningKind::NoneComparison,
+        WarningKind::PossiblyUnbound,
+        WarningKind::StringConcatInLoop,
+    ];
+
+    /// The kind with the given diagnostic code
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.code() == code)
+    }
+
+    /// Short code used in diagnostics and suppression comments
+    pub fn code(&self) -> &'static str {
+        match self {
+            WarningKind::DeadStore => "dead-store",
+            WarningKind::PureExpression => "pure-expression",
+            WarningKind::LossyConversion => "lossy-conversion",
+            WarningKind::SoftKeyword => "soft-keyword",
+            WarningKind::UnusedVariable => "unused-variable",
+            WarningKind::UnusedImport => "unused-import",
+            WarningKind::ShadowedName => "shadowed-name",
+            WarningKind::UnreachableCode => "unreachable-code",
+            WarningKind::NoneComparison => "none-comparison",
+            WarningKind::PossiblyUnbound => "possibly-unbound",
+            WarningKind::StringConcatInLoop => "string-concat-in-loop",
+        }
+    }
+}
+
+/// A non-fatal diagnostic attached to a source location
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Line {}, Col {}: warning[{}]: {}",
+            self.line,
+            self.column,
+            self.kind.code(),
+            self.message
+        )
+    }
+}
+
+/// Collect dead store and pure expression statement warnings for a module.
+///
+/// These work on the syntax tree alone, without types, so they still run on
+/// modules that fail to type-check. A store is dead when a later statement of
+/// the same block overwrites the name before any statement in between mentions
+/// it or leaves the block. Names that functions may read, such as module
+/// globals, also stop at any statement with side effects.
+pub fn lint_module(module: &Module, source: &str) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    lint_block(&module.body, None, source, &mut warnings);
+    warnings
+}
+
+/// Warn about soft keywords used as names in `source`; they may become
+/// reserved words in a later release. Attribute names are not affected.
+pub fn lint_soft_keywords(source: &str) -> Vec<Warning> {
+    let tokens = Lexer::new(source).tokenize();
+    let mut warnings = Vec::new();
+    let mut after_dot = false;
+    for token in &tokens {
+        if let TokenType::Identifier(name) = &token.token_type {
+            if !after_dot && keywords::is_soft_keyword(name) {
+                warnings.push(Warning {
+                    kind: WarningKind::SoftKeyword,
+                    message: format!(
+                        "'{}' is a soft keyword and may become reserved; consider renaming it",
+                        name
+                    ),
+                    line: token.line,
+                    column: token.column,
+                });
+            }
+        }
+        after_dot = token.token_type == TokenType::Dot;
+    }
+    warnings
+}
+
+/// Drop warnings whose source line carries a `# cheetah: ignore` comment.
+///
+/// A bare marker silences every warning on the line; `# cheetah: ignore[dead-store]`
+/// only silences the listed codes.
+pub fn filter_suppressed(warnings: Vec<Warning>, source: &str) -> Vec<Warning> {
+    let lines: Vec<&str> = source.lines().collect();
+
+    warnings
+        .into_iter()
+        .filter(|warning| {
+            let line = match warning.line.checked_sub(1).and_then(|i| lines.get(i)) {
+                Some(line) => line,
+                None => return true,
+            };
+
+            match line.find(SUPPRESSION_MARKER) {
+                Some(pos) => {
+                    let rest = &line[pos + SUPPRESSION_MARKER.len()..];
+                    match rest.strip_prefix('[').and_then(|r| r.split(']').next()) {
+                        Some(codes) => !codes
+                            .split(',')
+                            .any(|code| code.trim() == warning.kind.code()),
+                        None => false,
+                    }
+                }
+                None => true,
+            }
+        })
+        .collect()
+}
+
+/// Lint the statements of `body`. `function` is the body of the function the
+/// block is in, or `None` at module and class level, where every name can be
+/// read from elsewhere.
+fn lint_block(
+    body: &[Box<Stmt>],
+    function: Option<&[Box<Stmt>]>,
+    source: &str,
+    warnings: &mut Vec<Warning>,
+) {
+    for (i, stmt) in body.iter().enumerate() {
+        check_dead_store(stmt, &body[i + 1..], function, warnings);
+
+        match &**stmt {
+            Stmt::Expr {
+                value,
+                line,
+                column,
+                span,
+            } if !matches!(**value, Expr::Str { .. } | Expr::Ellipsis { .. })
+                && !has_side_effects(value) =>
+            {
+                let (line, column) = match span.is_empty() {
+                    true => (*line, *column),
+                    false => span.start_position(source),
+                };
+                warnings.push(Warning {
+                    kind: WarningKind::PureExpression,
+                    message: "expression statement has no effect; its value is discarded"
+                        .to_string(),
+                    line,
+                    column,
+                });
+            }
+            Stmt::FunctionDef { body, .. } => lint_block(body, Some(body), source, warnings),
+            Stmt::ClassDef { body, .. } => lint_block(body, None, source, warnings),
+            Stmt::For { body, orelse, .. }
+            | Stmt::While { body, orelse, .. }
+            | Stmt::If { body, orelse, .. } => {
+                lint_block(body, function, source, warnings);
+                lint_block(orelse, function, source, warnings);
+            }
+            Stmt::With { body, .. } => lint_block(body, function, source, warnings),
+            Stmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+                ..
+            } => {
+                lint_block(body, function, source, warnings);
+                for ExceptHandler { body, .. } in handlers {
+                    lint_block(body, function, source, warnings);
+                }
+                lint_block(orelse, function, source, warnings);
+                lint_block(finalbody, function, source, warnings);
+            }
+            Stmt::Match { cases, .. } => {
+                for (_, _, body) in cases {
+                    lint_block(body, function, source, warnings);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Warn when `stmt` stores to a name that one of the statements `after` it
+/// overwrites without the name being read first
+fn check_dead_store(
+    stmt: &Stmt,
+    after: &[Box<Stmt>],
+    function: Option<&[Box<Stmt>]>,
+    warnings: &mut Vec<Warning>,
+) {
+    let (stored, line, column) = match stmt {
+        Stmt::Assign {
+            targets,
+            line,
+            column,
+            ..
+        } => (stored_names(targets), *line, *column),
+        Stmt::AnnAssign {
+            target,
+            value: Some(_),
+            line,
+            column,
+            ..
+        } => (stored_names(std::slice::from_ref(target)), *line, *column),
+        _ => return,
+    };
+
+    for name in stored {
+        // A call may run code that reads a name visible outside the function
+        let shared = match function {
+            Some(body) => body.iter().any(|stmt| escapes(stmt, &name)),
+            None => true,
+        };
+        for next in after {
+            let overwrite = match &**next {
+                Stmt::Assign {
+                    targets,
+                    value,
+                    line,
+                    ..
+                } => Some((stored_names(targets), value, *line)),
+                Stmt::AnnAssign {
+                    target,
+                    value: Some(value),
+                    line,
+                    ..
+                } => Some((stored_names(std::slice::from_ref(target)), value, *line)),
+                _ => None,
+            };
+            if let Some((overwritten, value, next_line)) = overwrite {
+                if overwritten.contains(&name) {
+                    let may_read =
+                        reads_name(value, &name) || (shared && has_side_effects(value));
+                    if !may_read {
+                        warnings.push(Warning {
+                            kind: WarningKind::DeadStore,
+                            message: format!(
+                                "value assigned to '{}' is never read; it is overwritten on line {}",
+                                name, next_line
+                            ),
+                            line,
+                            column,
+                        });
+                    }
+                    break;
+                }
+            }
+            if mentions_name(next, &name)
+                || leaves_block(next)
+                || (shared && stmt_has_side_effects(next))
+            {
+                break;
+            }
+        }
+    }
+}
+
+/// Whether code other than the function body containing `stmt` can read
+/// `name`: it is declared `global` or `nonlocal`, or a nested function,
+/// class or lambda uses it
+fn escapes(stmt: &Stmt, name: &str) -> bool {
+    match stmt {
+        Stmt::FunctionDef { .. } | Stmt::ClassDef { .. } => mentions_name(stmt, name),
+        Stmt::Global { names, .. } | Stmt::Nonlocal { names, .. } => {
+            names.iter().any(|declared| declared == name)
+        }
+        _ => {
+            exprs(stmt)
+                .into_iter()
+                .any(|expr| lambda_reads_name(expr, name))
+                || blocks(stmt)
+                    .into_iter()
+                    .flatten()
+                    .any(|nested| escapes(nested, name))
+        }
+    }
+}
+
+/// Whether a lambda inside `expr` mentions `name`
+fn lambda_reads_name(expr: &Expr, name: &str) -> bool {
+    match expr {
+        Expr::Lambda { body, .. } => reads_name(body, name),
+        _ => children(expr)
+            .into_iter()
+            .any(|child| lambda_reads_name(child, name)),
+    }
+}
+
+/// Whether running `stmt` may have observable effects. Function bodies are
+/// not run by their definition, but class bodies are.
+fn stmt_has_side_effects(stmt: &Stmt) -> bool {
+    exprs(stmt).into_iter().any(has_side_effects)
+        || (!matches!(stmt, Stmt::FunctionDef { .. })
+            && blocks(stmt)
+                .into_iter()
+                .flatten()
+                .any(|nested| stmt_has_side_effects(nested)))
+}
+
+/// Whether `stmt` or a statement nested in it reads, writes or declares `name`
+fn mentions_name(stmt: &Stmt, name: &str) -> bool {
+    let binds = match stmt {
+        Stmt::FunctionDef { name: defined, .. } | Stmt::ClassDef { name: defined, .. } => {
+            defined == name
+        }
+        Stmt::Global { names, .. } | Stmt::Nonlocal { names, .. } => {
+            names.iter().any(|declared| declared == name)
+        }
+        Stmt::Import { names, .. } | Stmt::ImportFrom { names, .. } => names
+            .iter()
+            .any(|alias| alias.asname.as_ref().unwrap_or(&alias.name) == name),
+        _ => false,
+    };
+    binds
+        || exprs(stmt).into_iter().any(|expr| reads_name(expr, name))
+        || blocks(stmt)
+            .into_iter()
+            .flatten()
+            .any(|nested| mentions_name(nested, name))
+}
+
+/// Whether control never reaches the statement after `stmt`
+fn leaves_block(stmt: &Stmt) -> bool {
+    matches!(
+        stmt,
+        Stmt::Return { .. } | Stmt::Raise { .. } | Stmt::Break { .. } | Stmt::Continue { .. }
+    )
+}
+
+/// Plain variable names written by a list of assignment targets
+fn stored_names(targets: &[Box<Expr>]) -> Vec<String> {
+    let mut names = Vec::new();
+    for target in targets {
+        match &**target {
+            Expr::Name { id, .. } => names.push(id.clone()),
+            Expr::Tuple { elts, .. } | Expr::List { elts, .. } => {
+                names.extend(stored_names(elts));
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+/// Whether evaluating an expression may have observable effects
+fn has_side_effects(expr: &Expr) -> bool {
+    match expr {
+        Expr::Call { .. }
+        | Expr::Await { .. }
+        | Expr::Yield { .. }
+        | Expr::YieldFrom { .. }
+        | Expr::NamedExpr { .. } => true,
+        _ => children(expr).into_iter().any(has_side_effects),
+    }
+}
+
+/// Whether an expression mentions the given variable name
+fn reads_name(expr: &Expr, name: &str) -> bool {
+    match expr {
+        Expr::Name { id, .. } => id == name,
+        _ => children(expr).into_iter().any(|child| reads_name(child, name)),
+    }
+}
+
+/// Direct sub-expressions of an expression
+pub(crate) fn children(expr: &Expr) -> Vec<&Expr> {
+    fn comprehension_children(generators: &[Comprehension]) -> Vec<&Expr> {
+        let mut result = Vec::new();
+        for comp in generators {
+            result.push(&*comp.target);
+            result.push(&*comp.iter);
+            result.extend(comp.ifs.iter().map(|e| &**e));
+        }
+        result
+    }
+
+    match expr {
+        Expr::BoolOp { values, .. } => values.iter().map(|e| &**e).collect(),
+        Expr::BinOp { left, right, .. } => vec![&**left, &**right],
+        Expr::Slice {
+            lower, upper, step, ..
+        } => [lower, upper, step]
+            .into_iter()
+            .flatten()
+            .map(|e| &**e)
+            .collect(),
+        Expr::UnaryOp { operand, .. } => vec![&**operand],
+        Expr::Lambda { body, .. } => vec![&**body],
+        Expr::IfExp {
+            test, body, orelse, ..
+        } => vec![&**test, &**body, &**orelse],
+        Expr::Dict { keys, values, .. } => keys
+            .iter()
+            .flatten()
+            .chain(values.iter())
+            .map(|e| &**e)
+            .collect(),
+        Expr::Set { elts, .. }
+        | Expr::List { elts, .. }
+        | Expr::Tuple { elts, .. }
+        | Expr::JoinedStr { values: elts, .. } => elts.iter().map(|e| &**e).collect(),
+        Expr::ListComp {
+            elt, generators, ..
+        }
+        | Expr::SetComp {
+            elt, generators, ..
+        }
+        | Expr::GeneratorExp {
+            elt, generators, ..
+        } => {
+            let mut result = vec![&**elt];
+            result.extend(comprehension_children(generators));
+            result
+        }
+        Expr::DictComp {
+            key,
+            value,
+            generators,
+            ..
+        } => {
+            let mut result = vec![&**key, &**value];
+            result.extend(comprehension_children(generators));
+            result
+        }
+        Expr::Await { value, .. }
+        | Expr::YieldFrom { value, .. }
+        | Expr::Attribute { value, .. }
+        | Expr::Starred { value, .. } => vec![&**value],
+        Expr::Yield { value, .. } => value.iter().map(|e| &**e).collect(),
+        Expr::Compare {
+            left, comparators, ..
+        } => {
+            let mut result = vec![&**left];
+            result.extend(comparators.iter().map(|e| &**e));
+            result
+        }
+        Expr::Call {
+            func,
+            args,
+            keywords,
+            ..
+        } => {
+            let mut result = vec![&**func];
+            result.extend(args.iter().map(|e| &**e));
+            result.extend(keywords.iter().map(|(_, e)| &**e));
+            result
+        }
+        Expr::FormattedValue {
+            value, format_spec, ..
+        } => {
+            let mut result = vec![&**value];
+            result.extend(format_spec.iter().map(|e| &**e));
+            result
+        }
+        Expr::Subscript { value, slice, .. } => vec![&**value, &**slice],
+        Expr::NamedExpr { target, value, .. } => vec![&**target, &**value],
+        Expr::Num { .. }
+        | Expr::Str { .. }
+        | Expr::Bytes { .. }
+        | Expr::NameConstant { .. }
+        | Expr::Ellipsis { .. }
+        | Expr::Constant { .. }
+        | Expr::Name { .. } => Vec::new(),
+    }
+}
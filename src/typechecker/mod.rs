@@ -1,12 +1,18 @@
 use crate::ast::Module;
 use crate::compiler::types::TypeError;
 
+pub mod abstract_methods;
 mod checker;
 mod environment;
 mod inference;
+pub(crate) mod lints;
+pub mod mro;
+pub mod stubs;
+pub mod unbound;
 
 pub use checker::TypeChecker;
 pub use environment::TypeEnvironment;
+pub use lints::{Warning, WarningKind};
 
 /// Result type for type checking operations
 pub type TypeResult<T> = Result<T, TypeError>;
@@ -16,3 +22,16 @@ pub fn check_module(module: &Module) -> TypeResult<()> {
     let mut checker = TypeChecker::new();
     checker.check_module(module)
 }
+
+/// Collect lint warnings for a module, honouring `# cheetah: ignore` comments in `source`
+pub fn collect_warnings(module: &Module, source: &str) -> Vec<Warning> {
+    let mut warnings = lints::lint_module(module, source);
+    warnings.extend(lints::lint_soft_keywords(source));
+
+    let mut checker = TypeChecker::new();
+    let _ = checker.check_module(module);
+    warnings.extend(checker.warnings().iter().cloned());
+    warnings.sort_by_key(|warning| (warning.line, warning.column));
+
+    lints::filter_suppressed(warnings, source)
+}
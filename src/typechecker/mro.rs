@@ -0,0 +1,84 @@
+// mro.rs - C3 method resolution order for classes
+//
+// A class's MRO lists it first, then its bases, so that every class comes
+// before its own bases and bases keep the order they were written in. It is
+// where method lookups, including `super()`, search for an attribute.
+
+/// Linearize `class` given the bases of each class. Returns the order, or
+/// the classes that couldn't be ordered consistently.
+pub fn linearize<F>(class: &str, bases_of: &F) -> Result<Vec<String>, Vec<String>>
+where
+    F: Fn(&str) -> Vec<String>,
+{
+    linearize_from(class, bases_of, &mut Vec::new())
+}
+
+fn linearize_from<F>(
+    class: &str,
+    bases_of: &F,
+    visiting: &mut Vec<String>,
+) -> Result<Vec<String>, Vec<String>>
+where
+    F: Fn(&str) -> Vec<String>,
+{
+    // A class can't inherit from itself, even through a redefinition
+    if visiting.iter().any(|name| name == class) {
+        return Err(visiting.clone());
+    }
+    visiting.push(class.to_string());
+
+    let bases = bases_of(class);
+    let mut sequences = Vec::with_capacity(bases.len() + 1);
+    for base in &bases {
+        sequences.push(linearize_from(base, bases_of, visiting)?);
+    }
+    sequences.push(bases);
+    visiting.pop();
+
+    let mut order = vec![class.to_string()];
+    order.extend(merge(sequences)?);
+    Ok(order)
+}
+
+/// Merge linearizations: repeatedly take the first head that appears in no
+/// other sequence's tail
+fn merge(mut sequences: Vec<Vec<String>>) -> Result<Vec<String>, Vec<String>> {
+    let mut merged = Vec::new();
+    loop {
+        sequences.retain(|sequence| !sequence.is_empty());
+        if sequences.is_empty() {
+            return Ok(merged);
+        }
+
+        let head = sequences
+            .iter()
+            .map(|sequence| &sequence[0])
+            .find(|candidate| {
+                sequences
+                    .iter()
+                    .all(|sequence| !sequence[1..].contains(candidate))
+            })
+            .cloned();
+        let Some(head) = head else {
+            let mut blocked: Vec<String> = sequences.iter().map(|sequence| sequence[0].clone()).collect();
+            blocked.dedup();
+            return Err(blocked);
+        };
+
+        for sequence in &mut sequences {
+            if sequence[0] == head {
+                sequence.remove(0);
+            }
+        }
+        merged.push(head);
+    }
+}
+
+/// The classes after `class` in `mro`, where `super()` in one of `class`'s
+/// methods starts looking
+pub fn following<'a>(mro: &'a [String], class: &str) -> &'a [String] {
+    match mro.iter().position(|name| name == class) {
+        Some(index) => &mro[index + 1..],
+        None => &[],
+    }
+}
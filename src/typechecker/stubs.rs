@@ -0,0 +1,189 @@
+// stubs.rs - Type stubs for modules implemented in the runtime
+//
+// A stub is a `.chi` file written in Cheetah syntax that declares what a
+// module provides without implementing it. Functions have annotated
+// signatures and a `...` body, and constants are annotated names:
+//
+//     """Operating system helpers."""
+//
+//     def tempdir() -> str: ...
+//     def getenv(name: str, default: str = ...) -> str: ...
+//
+//     sep: str
+//
+// The type checker looks `module.name` up in the stub of `module`, and
+// completion offers a stub's members after `module.`. Stubs for the runtime's
+// own modules ship with the compiler; stubs for other native code, such as
+// a C library called through `@extern`, are loaded with `ModuleStub::load`.
+
+use crate::ast::{Expr, NameConstant, Parameter, Stmt};
+use crate::compiler::ffi::{extern_symbol, is_stub_body};
+use crate::compiler::types::Type;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// File extension of stub files
+pub const STUB_EXTENSION: &str = "chi";
+
+/// Stubs of the modules the runtime implements, by module name
+const BUILTIN_STUBS: &[(&str, &str)] = &[
+    ("os", include_str!("stubs/os.chi")),
+    ("sys", include_str!("stubs/sys.chi")),
+];
+
+/// The declared members of a module
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleStub {
+    pub name: String,
+    /// Type of each function and constant, by name
+    pub members: BTreeMap<String, Type>,
+}
+
+impl ModuleStub {
+    /// Parse the stub source of module `name`
+    pub fn parse(name: &str, source: &str) -> Result<Self, String> {
+        let module = crate::parse(source).map_err(|errors| {
+            let messages: Vec<String> = errors.iter().map(|error| error.get_message()).collect();
+            format!("{}: {}", name, messages.join("\n"))
+        })?;
+
+        let mut members = BTreeMap::new();
+        for (index, stmt) in module.body.iter().enumerate() {
+            let (member, ty) = match stmt.as_ref() {
+                Stmt::Expr { value, .. } if index == 0 && matches!(value.as_ref(), Expr::Str { .. }) => continue,
+                Stmt::FunctionDef {
+                    name: function,
+                    params,
+                    body,
+                    decorator_list,
+                    returns,
+                    ..
+                } => {
+                    if !is_stub_body(body) {
+                        return Err(stub_error(name, stmt, "the body of a stub function must be '...'"));
+                    }
+                    if extern_symbol(function, decorator_list)?.is_none() && !decorator_list.is_empty() {
+                        return Err(stub_error(name, stmt, "stub functions take no decorators but @extern"));
+                    }
+                    (function.clone(), function_type(params, returns))
+                }
+                Stmt::AnnAssign { target, annotation, value: None, .. } => match target.as_ref() {
+                    Expr::Name { id, .. } => (id.clone(), stub_type(annotation)),
+                    _ => return Err(stub_error(name, stmt, "only plain names can be declared")),
+                },
+                _ => {
+                    return Err(stub_error(
+                        name,
+                        stmt,
+                        "a stub only declares functions and annotated names",
+                    ))
+                }
+            };
+            if members.insert(member.clone(), ty).is_some() {
+                return Err(stub_error(name, stmt, &format!("'{}' is declared twice", member)));
+            }
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            members,
+        })
+    }
+
+    /// Read the stub at `path`, named after the file stem: `libm.chi` declares `libm`
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| format!("{}: not a stub file name", path.display()))?;
+        let source = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(name, &source)
+    }
+
+    /// Type of member `name`, if the module declares it
+    pub fn member(&self, name: &str) -> Option<&Type> {
+        self.members.get(name)
+    }
+}
+
+/// The shipped stub of runtime module `name`
+pub fn builtin_stub(name: &str) -> Option<&'static ModuleStub> {
+    static STUBS: OnceLock<Vec<ModuleStub>> = OnceLock::new();
+    STUBS
+        .get_or_init(|| {
+            BUILTIN_STUBS
+                .iter()
+                .map(|(name, source)| ModuleStub::parse(name, source).expect("shipped stubs parse"))
+                .collect()
+        })
+        .iter()
+        .find(|stub| stub.name == name)
+}
+
+/// Names of the runtime modules with shipped stubs
+pub fn builtin_modules() -> impl Iterator<Item = &'static str> {
+    BUILTIN_STUBS.iter().map(|(name, _)| *name)
+}
+
+fn stub_error(module: &str, stmt: &Stmt, message: &str) -> String {
+    format!("{}.{}:{}: {}", module, STUB_EXTENSION, stmt.line(), message)
+}
+
+/// Type of a stub function; unannotated parameters and returns are Any
+fn function_type(params: &[Parameter], returns: &Option<Box<Expr>>) -> Type {
+    Type::Function {
+        param_types: params
+            .iter()
+            .map(|param| param.typ.as_deref().map_or(Type::Any, stub_type))
+            .collect(),
+        param_names: params.iter().map(|param| param.name.clone()).collect(),
+        has_varargs: params.iter().any(|param| param.is_vararg),
+        has_kwargs: params.iter().any(|param| param.is_kwarg),
+        default_values: params.iter().map(|param| param.default.is_some()).collect(),
+        return_type: Box::new(returns.as_deref().map_or(Type::Any, stub_type)),
+    }
+}
+
+/// Type an annotation in a stub stands for
+fn stub_type(annotation: &Expr) -> Type {
+    match annotation {
+        Expr::Name { id, .. } => match id.as_str() {
+            "int" => Type::Int,
+            "float" => Type::Float,
+            "bool" => Type::Bool,
+            "str" => Type::String,
+            "bytes" => Type::Bytes,
+            "None" => Type::None,
+            "list" => Type::list(Type::Any),
+            "dict" => Type::dict(Type::Any, Type::Any),
+            "set" => Type::Set(Box::new(Type::Any)),
+            "tuple" => Type::Tuple(vec![]),
+            "Exception" => Type::exception(),
+            "Any" => Type::Any,
+            class => Type::class(class),
+        },
+        Expr::NameConstant { value: NameConstant::None, .. } => Type::None,
+        Expr::Subscript { value, slice, .. } => {
+            let args: Vec<Type> = match slice.as_ref() {
+                Expr::Tuple { elts, .. } => elts.iter().map(|elt| stub_type(elt)).collect(),
+                single => vec![stub_type(single)],
+            };
+            let arg = |i: usize| args.get(i).cloned().unwrap_or(Type::Any);
+            match value.as_ref() {
+                Expr::Name { id, .. } => match id.as_str() {
+                    "list" | "List" => Type::list(arg(0)),
+                    "set" | "Set" => Type::Set(Box::new(arg(0))),
+                    "dict" | "Dict" => Type::dict(arg(0), arg(1)),
+                    "tuple" | "Tuple" => Type::Tuple(args),
+                    _ => Type::Generic {
+                        base_type: Box::new(stub_type(value)),
+                        type_args: args,
+                    },
+                },
+                _ => Type::Any,
+            }
+        }
+        _ => Type::Any,
+    }
+}
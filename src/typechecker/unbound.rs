@@ -0,0 +1,534 @@
+// unbound.rs - Reads of local variables before they are assigned
+//
+// A name assigned anywhere in a function body is local to the whole
+// function unless it is declared `global` or `nonlocal`, so reading it
+// before an assignment has run fails rather than finding a global:
+//
+//     def report(items):
+//         print(total)            # read before any assignment: an error
+//         total = 0
+//         for item in items:
+//             last = item
+//         return last             # unassigned when items is empty: a warning
+//
+// Each function body is walked in execution order, tracking the locals
+// assigned on every path so far and those assigned on some path. Loop
+// bodies are walked twice so reads see what earlier iterations assigned.
+
+use crate::ast::{Comprehension, Expr, ExprContext, Module, Parameter, Stmt};
+use crate::linter::blocks;
+use crate::typechecker::lints::children;
+use std::collections::HashSet;
+
+/// A read of a local variable that isn't assigned on every path leading to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnboundRead {
+    pub name: String,
+    /// The function the variable is local to
+    pub function: String,
+    pub line: usize,
+    pub column: usize,
+    /// Whether no path assigns the variable before the read, rather than only some
+    pub always: bool,
+}
+
+/// The unbound reads in the functions and methods of `module`, in source order
+pub fn unbound_reads(module: &Module) -> Vec<UnboundRead> {
+    let mut reads = Vec::new();
+    visit_functions(&module.body, &mut reads);
+    reads.sort_by_key(|read| (read.line, read.column));
+    reads
+}
+
+fn visit_functions(body: &[Box<Stmt>], reads: &mut Vec<UnboundRead>) {
+    for stmt in body {
+        if let Stmt::FunctionDef { name, params, body, .. } = stmt.as_ref() {
+            reads.extend(FunctionFlow::check(name, params, body));
+        }
+        for block in blocks(stmt) {
+            visit_functions(block, reads);
+        }
+    }
+}
+
+/// Locals assigned on every path to a point of a function, and on some path
+#[derive(Debug, Clone, Default)]
+struct Bound {
+    always: HashSet<String>,
+    maybe: HashSet<String>,
+}
+
+impl Bound {
+    fn bind(&mut self, name: &str) {
+        self.always.insert(name.to_string());
+        self.maybe.insert(name.to_string());
+    }
+
+    fn unbind(&mut self, name: &str) {
+        self.always.remove(name);
+        self.maybe.remove(name);
+    }
+}
+
+/// The state where two paths meet; `None` is a path that never gets there
+fn join(a: Option<Bound>, b: Option<Bound>) -> Option<Bound> {
+    match (a, b) {
+        (Some(mut a), Some(b)) => {
+            a.always.retain(|name| b.always.contains(name));
+            a.maybe.extend(b.maybe);
+            Some(a)
+        }
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+/// How a loop body was left
+struct Iteration {
+    end: Option<Bound>,
+    breaks: Option<Bound>,
+    continues: Option<Bound>,
+}
+
+struct FunctionFlow<'a> {
+    function: &'a str,
+    locals: HashSet<String>,
+    /// States at the `break` and `continue` statements of each enclosing loop
+    loops: Vec<(Option<Bound>, Option<Bound>)>,
+    /// Whether reads are recorded; off on the first walk of a loop body
+    recording: bool,
+    reads: Vec<UnboundRead>,
+}
+
+impl<'a> FunctionFlow<'a> {
+    fn check(function: &'a str, params: &[Parameter], body: &[Box<Stmt>]) -> Vec<UnboundRead> {
+        let mut locals = HashSet::new();
+        let mut declared = HashSet::new();
+        assigned_names(body, &mut locals, &mut declared);
+        locals.retain(|name| !declared.contains(name));
+
+        let mut flow = FunctionFlow {
+            function,
+            locals,
+            loops: Vec::new(),
+            recording: true,
+            reads: Vec::new(),
+        };
+        let mut entry = Bound::default();
+        for param in params {
+            entry.bind(&param.name);
+        }
+        flow.block(body, Some(entry));
+        flow.reads
+    }
+
+    fn block(&mut self, body: &[Box<Stmt>], mut state: Option<Bound>) -> Option<Bound> {
+        for stmt in body {
+            let bound = state?;
+            state = self.stmt(stmt, bound);
+        }
+        state
+    }
+
+    /// The state after `stmt`, or None when it never completes normally
+    fn stmt(&mut self, stmt: &Stmt, mut bound: Bound) -> Option<Bound> {
+        match stmt {
+            Stmt::FunctionDef {
+                name, params, decorator_list, ..
+            } => {
+                for decorator in decorator_list {
+                    self.read(decorator, &mut bound);
+                }
+                for default in params.iter().filter_map(|param| param.default.as_ref()) {
+                    self.read(default, &mut bound);
+                }
+                bound.bind(name);
+            }
+            Stmt::ClassDef {
+                name,
+                bases,
+                keywords,
+                decorator_list,
+                ..
+            } => {
+                let arguments = bases.iter().chain(keywords.iter().map(|(_, value)| value));
+                for expr in decorator_list.iter().chain(arguments) {
+                    self.read(expr, &mut bound);
+                }
+                bound.bind(name);
+            }
+            Stmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.read(value, &mut bound);
+                }
+                return None;
+            }
+            Stmt::Raise { exc, cause, .. } => {
+                for expr in exc.iter().chain(cause.iter()) {
+                    self.read(expr, &mut bound);
+                }
+                return None;
+            }
+            Stmt::Delete { targets, .. } => {
+                for target in targets {
+                    match target.as_ref() {
+                        Expr::Name { id, line, column, .. } => {
+                            self.use_name(id, *line, *column, &mut bound);
+                            bound.unbind(id);
+                        }
+                        _ => self.store(target, &mut bound),
+                    }
+                }
+            }
+            Stmt::Assign { targets, value, .. } => {
+                self.read(value, &mut bound);
+                for target in targets {
+                    self.store(target, &mut bound);
+                }
+            }
+            Stmt::AugAssign { target, value, .. } => {
+                self.read(value, &mut bound);
+                if let Expr::Name { id, line, column, .. } = target.as_ref() {
+                    self.use_name(id, *line, *column, &mut bound);
+                }
+                self.store(target, &mut bound);
+            }
+            Stmt::AnnAssign { target, value, .. } => {
+                if let Some(value) = value {
+                    self.read(value, &mut bound);
+                    self.store(target, &mut bound);
+                }
+            }
+            Stmt::For {
+                target, iter, body, orelse, ..
+            } => {
+                self.read(iter, &mut bound);
+                return self.loop_stmt(Some(target), body, orelse, bound, false);
+            }
+            Stmt::While { test, body, orelse, .. } => {
+                self.read(test, &mut bound);
+                let forever = matches!(
+                    test.as_ref(),
+                    Expr::NameConstant {
+                        value: crate::ast::NameConstant::True,
+                        ..
+                    }
+                );
+                return self.loop_stmt(None, body, orelse, bound, forever);
+            }
+            Stmt::If { test, body, orelse, .. } => {
+                self.read(test, &mut bound);
+                let taken = self.block(body, Some(bound.clone()));
+                return join(taken, self.block(orelse, Some(bound)));
+            }
+            Stmt::With { items, body, .. } => {
+                for (context, vars) in items {
+                    self.read(context, &mut bound);
+                    if let Some(vars) = vars {
+                        self.store(vars, &mut bound);
+                    }
+                }
+                return self.block(body, Some(bound));
+            }
+            Stmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+                ..
+            } => {
+                // An exception can leave the body after any of its assignments
+                let mut raised = bound.clone();
+                assigned_names(body, &mut raised.maybe, &mut HashSet::new());
+
+                let body_end = self.block(body, Some(bound));
+                let mut end = self.block(orelse, body_end);
+                for handler in handlers {
+                    let mut caught = raised.clone();
+                    if let Some(typ) = &handler.typ {
+                        self.read(typ, &mut caught);
+                    }
+                    if let Some(name) = &handler.name {
+                        caught.bind(name);
+                    }
+                    end = join(end, self.block(&handler.body, Some(caught)));
+                }
+                if finalbody.is_empty() {
+                    return end;
+                }
+                // `finally` also runs on the way out of an exception
+                let finally_end = self.block(finalbody, join(end.clone(), Some(raised)));
+                return match (end, finally_end) {
+                    (Some(mut end), Some(finally_end)) => {
+                        end.always.extend(finally_end.always);
+                        end.maybe.extend(finally_end.maybe);
+                        Some(end)
+                    }
+                    _ => None,
+                };
+            }
+            Stmt::Assert { test, msg, .. } => {
+                for expr in std::iter::once(test).chain(msg.iter()) {
+                    self.read(expr, &mut bound);
+                }
+            }
+            Stmt::Import { names, .. } | Stmt::ImportFrom { names, .. } => {
+                for alias in names.iter().filter(|alias| alias.name != "*") {
+                    bound.bind(&import_binding(stmt, alias));
+                }
+            }
+            Stmt::Expr { value, .. } => self.read(value, &mut bound),
+            Stmt::Break { .. } => {
+                if let Some((breaks, _)) = self.loops.last_mut() {
+                    *breaks = join(breaks.take(), Some(bound));
+                }
+                return None;
+            }
+            Stmt::Continue { .. } => {
+                if let Some((_, continues)) = self.loops.last_mut() {
+                    *continues = join(continues.take(), Some(bound));
+                }
+                return None;
+            }
+            Stmt::Match { subject, cases, .. } => {
+                self.read(subject, &mut bound);
+                let mut end = None;
+                let mut exhaustive = false;
+                for (pattern, guard, body) in cases {
+                    let mut matched = bound.clone();
+                    bind_captures(pattern, &mut matched);
+                    if let Some(guard) = guard {
+                        self.read(guard, &mut matched);
+                    }
+                    end = join(end, self.block(body, Some(matched)));
+                    exhaustive |= guard.is_none() && matches!(pattern.as_ref(), Expr::Name { .. });
+                }
+                return if exhaustive { end } else { join(end, Some(bound)) };
+            }
+            Stmt::Global { .. } | Stmt::Nonlocal { .. } | Stmt::Pass { .. } => {}
+        }
+        Some(bound)
+    }
+
+    /// The state after a `for` or `while` loop entered in state `bound`
+    fn loop_stmt(
+        &mut self,
+        target: Option<&Expr>,
+        body: &[Box<Stmt>],
+        orelse: &[Box<Stmt>],
+        bound: Bound,
+        forever: bool,
+    ) -> Option<Bound> {
+        // The first walk finds what an iteration leaves for the next one
+        let recording = std::mem::replace(&mut self.recording, false);
+        let first = self.iteration(target, body, bound.clone());
+        self.recording = recording;
+        let head = join(join(Some(bound), first.end), first.continues).unwrap_or_default();
+
+        let second = self.iteration(target, body, head.clone());
+        let finished = match forever {
+            true => None,
+            false => join(join(Some(head), second.end), second.continues),
+        };
+        join(self.block(orelse, finished), second.breaks)
+    }
+
+    fn iteration(&mut self, target: Option<&Expr>, body: &[Box<Stmt>], mut bound: Bound) -> Iteration {
+        if let Some(target) = target {
+            self.store(target, &mut bound);
+        }
+        self.loops.push((None, None));
+        let end = self.block(body, Some(bound));
+        let (breaks, continues) = self.loops.pop().unwrap_or_default();
+        Iteration { end, breaks, continues }
+    }
+
+    /// Check the names `expr` reads
+    fn read(&mut self, expr: &Expr, bound: &mut Bound) {
+        match expr {
+            Expr::Name {
+                id,
+                ctx: ExprContext::Load,
+                line,
+                column,
+                ..
+            } => self.use_name(id, *line, *column, bound),
+            // A lambda's body runs later, when it is called
+            Expr::Lambda { args, .. } => {
+                for default in args.iter().filter_map(|arg| arg.default.as_ref()) {
+                    self.read(default, bound);
+                }
+            }
+            Expr::NamedExpr { target, value, .. } => {
+                self.read(value, bound);
+                self.store(target, bound);
+            }
+            Expr::ListComp { elt, generators, .. }
+            | Expr::SetComp { elt, generators, .. }
+            | Expr::GeneratorExp { elt, generators, .. } => self.comprehension(&[elt], generators, bound),
+            Expr::DictComp {
+                key, value, generators, ..
+            } => self.comprehension(&[key, value], generators, bound),
+            _ => {
+                for child in children(expr) {
+                    self.read(child, bound);
+                }
+            }
+        }
+    }
+
+    /// Check a comprehension, whose targets hide locals of the same name
+    fn comprehension(&mut self, results: &[&Expr], generators: &[Comprehension], bound: &mut Bound) {
+        // The first iterable is evaluated in the function's scope
+        if let Some(first) = generators.first() {
+            self.read(&first.iter, bound);
+        }
+        let mut targets = HashSet::new();
+        for generator in generators {
+            target_names(&generator.target, &mut targets);
+        }
+        let hidden: Vec<String> = targets.into_iter().filter(|name| self.locals.remove(name)).collect();
+
+        for (index, generator) in generators.iter().enumerate() {
+            if index > 0 {
+                self.read(&generator.iter, bound);
+            }
+            for condition in &generator.ifs {
+                self.read(condition, bound);
+            }
+        }
+        for result in results {
+            self.read(result, bound);
+        }
+        self.locals.extend(hidden);
+    }
+
+    /// Assign to `target`, checking the names its subscripts and attributes read
+    fn store(&mut self, target: &Expr, bound: &mut Bound) {
+        match target {
+            Expr::Name { id, .. } => bound.bind(id),
+            Expr::Tuple { elts, .. } | Expr::List { elts, .. } => {
+                for elt in elts {
+                    self.store(elt, bound);
+                }
+            }
+            Expr::Starred { value, .. } => self.store(value, bound),
+            Expr::Attribute { value, .. } => self.read(value, bound),
+            Expr::Subscript { value, slice, .. } => {
+                self.read(value, bound);
+                self.read(slice, bound);
+            }
+            _ => self.read(target, bound),
+        }
+    }
+
+    fn use_name(&mut self, name: &str, line: usize, column: usize, bound: &mut Bound) {
+        if !self.locals.contains(name) || bound.always.contains(name) {
+            return;
+        }
+        if self.recording {
+            self.reads.push(UnboundRead {
+                name: name.to_string(),
+                function: self.function.to_string(),
+                line,
+                column,
+                always: !bound.maybe.contains(name),
+            });
+        }
+        // Report each unassigned local once per path
+        bound.bind(name);
+    }
+}
+
+/// The name an import binds for `alias`
+fn import_binding(stmt: &Stmt, alias: &crate::ast::Alias) -> String {
+    match (&alias.asname, stmt) {
+        (Some(asname), _) => asname.clone(),
+        // `import a.b` binds `a`
+        (None, Stmt::Import { .. }) => alias.name.split('.').next().unwrap_or(&alias.name).to_string(),
+        (None, _) => alias.name.clone(),
+    }
+}
+
+/// Names a `match` pattern captures
+fn bind_captures(pattern: &Expr, bound: &mut Bound) {
+    match pattern {
+        Expr::Name { id, .. } if id != "_" => bound.bind(id),
+        // Dotted names are values to compare with
+        Expr::Attribute { .. } => {}
+        Expr::Call { args, keywords, .. } => {
+            for arg in args.iter().chain(keywords.iter().map(|(_, value)| value)) {
+                bind_captures(arg, bound);
+            }
+        }
+        _ => {
+            for child in children(pattern) {
+                bind_captures(child, bound);
+            }
+        }
+    }
+}
+
+/// Names bound by an assignment target
+fn target_names(target: &Expr, names: &mut HashSet<String>) {
+    match target {
+        Expr::Name { id, .. } => {
+            names.insert(id.clone());
+        }
+        Expr::Tuple { elts, .. } | Expr::List { elts, .. } => {
+            for elt in elts {
+                target_names(elt, names);
+            }
+        }
+        Expr::Starred { value, .. } => target_names(value, names),
+        _ => {}
+    }
+}
+
+/// Names a block assigns outside nested functions and classes, and the
+/// names it declares `global` or `nonlocal`
+fn assigned_names(body: &[Box<Stmt>], names: &mut HashSet<String>, declared: &mut HashSet<String>) {
+    for stmt in body {
+        match stmt.as_ref() {
+            Stmt::FunctionDef { name, .. } | Stmt::ClassDef { name, .. } => {
+                names.insert(name.clone());
+                continue;
+            }
+            Stmt::Assign { targets, .. } | Stmt::Delete { targets, .. } => {
+                for target in targets {
+                    target_names(target, names);
+                }
+            }
+            Stmt::AugAssign { target, .. } | Stmt::AnnAssign { target, .. } | Stmt::For { target, .. } => {
+                target_names(target, names);
+            }
+            Stmt::With { items, .. } => {
+                for vars in items.iter().filter_map(|(_, vars)| vars.as_ref()) {
+                    target_names(vars, names);
+                }
+            }
+            Stmt::Try { handlers, .. } => {
+                names.extend(handlers.iter().filter_map(|handler| handler.name.clone()));
+            }
+            Stmt::Import { names: aliases, .. } | Stmt::ImportFrom { names: aliases, .. } => {
+                for alias in aliases.iter().filter(|alias| alias.name != "*") {
+                    names.insert(import_binding(stmt, alias));
+                }
+            }
+            Stmt::Match { cases, .. } => {
+                let mut captured = Bound::default();
+                for (pattern, _, _) in cases {
+                    bind_captures(pattern, &mut captured);
+                }
+                names.extend(captured.always);
+            }
+            Stmt::Global { names: globals, .. } | Stmt::Nonlocal { names: globals, .. } => {
+                declared.extend(globals.iter().cloned());
+            }
+            _ => {}
+        }
+        for block in blocks(stmt) {
+            assigned_names(block, names, declared);
+        }
+    }
+}
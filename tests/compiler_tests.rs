@@ -1,4 +1,5 @@
 // This file links all the compiler test files together
+#![cfg(feature = "codegen")]
 
 // Include the main compiler tests
 #[path = "more_tests/compiler/compiler_tests.rs"]
@@ -206,3 +207,155 @@ mod list_comprehension_tuple_test;
 // Include the range optimization tests
 #[path = "more_tests/compiler/range_optimization_test.rs"]
 mod range_optimization_test;
+
+// Include the string method tests
+#[path = "more_tests/compiler/string_methods_test.rs"]
+mod string_methods_test;
+
+// Include the list method tests
+#[path = "more_tests/compiler/list_methods_test.rs"]
+mod list_methods_test;
+
+// Include the iteration built-in tests
+#[path = "more_tests/compiler/iter_builtins_test.rs"]
+mod iter_builtins_test;
+
+// Include the os temporary path tests
+#[path = "more_tests/compiler/os_tempfile_test.rs"]
+mod os_tempfile_test;
+
+// Include the sequence built-in tests
+#[path = "more_tests/compiler/sequence_builtins_test.rs"]
+mod sequence_builtins_test;
+
+// Include the loop fusion tests
+#[path = "more_tests/compiler/loop_fusion_test.rs"]
+mod loop_fusion_test;
+
+// Include the input() tests
+#[path = "more_tests/compiler/input_function_test.rs"]
+mod input_function_test;
+
+// Include the file I/O tests
+#[path = "more_tests/compiler/file_io_test.rs"]
+mod file_io_test;
+
+// Include the string builder tests
+#[path = "more_tests/compiler/string_builder_test.rs"]
+mod string_builder_test;
+
+// Include the traceback tests
+#[path = "more_tests/compiler/traceback_test.rs"]
+mod traceback_test;
+
+// Include the constant pool tests
+#[path = "more_tests/compiler/constant_pool_test.rs"]
+mod constant_pool_test;
+
+// Include the CFG dump tests
+#[path = "more_tests/compiler/cfg_dump_test.rs"]
+mod cfg_dump_test;
+
+// Include the debug info tests
+#[path = "more_tests/compiler/debug_info_test.rs"]
+mod debug_info_test;
+
+// Include the cross-compilation target tests
+#[path = "more_tests/compiler/target_test.rs"]
+mod target_test;
+
+// Include the sanitizer tests
+#[path = "more_tests/compiler/sanitizer_test.rs"]
+mod sanitizer_test;
+
+// Include the runtime ABI check tests
+#[path = "more_tests/compiler/runtime_abi_test.rs"]
+mod runtime_abi_test;
+
+// Include the WebAssembly target tests
+#[path = "more_tests/compiler/wasm_test.rs"]
+mod wasm_test;
+
+// Include the optimization pipeline tests
+#[path = "more_tests/compiler/optimizer_test.rs"]
+mod optimizer_test;
+
+// Include the lazy runtime declaration tests
+#[path = "more_tests/compiler/lazy_runtime_test.rs"]
+mod lazy_runtime_test;
+
+// Include the constant range folding tests
+#[path = "more_tests/compiler/constant_range_test.rs"]
+mod constant_range_test;
+
+// Include the --emit output kind tests
+#[path = "more_tests/compiler/emit_test.rs"]
+mod emit_test;
+
+// Include the shared library build tests
+#[path = "more_tests/compiler/cdylib_test.rs"]
+mod cdylib_test;
+
+// Include the comprehension preallocation tests
+#[path = "more_tests/compiler/prealloc_test.rs"]
+mod prealloc_test;
+
+// Include the @extern foreign function tests
+#[path = "more_tests/compiler/ffi_test.rs"]
+mod ffi_test;
+
+// Include the number formatting tests
+#[path = "more_tests/compiler/number_format_test.rs"]
+mod number_format_test;
+
+// Include the embedding engine tests
+#[path = "more_tests/compiler/engine_test.rs"]
+mod engine_test;
+
+// Include the JIT REPL session tests
+#[path = "more_tests/compiler/repl_test.rs"]
+mod repl_test;
+
+// Include the output configuration tests
+#[path = "more_tests/compiler/output_test.rs"]
+mod output_test;
+
+// Include the dictionary insertion order tests
+#[path = "more_tests/compiler/dict_order_test.rs"]
+mod dict_order_test;
+
+// Include the attribute inline cache tests
+#[path = "more_tests/compiler/attribute_cache_test.rs"]
+mod attribute_cache_test;
+
+// Include the class object tests
+#[path = "more_tests/compiler/class_object_test.rs"]
+mod class_object_test;
+
+// Include the dir(), getattr() and setattr() tests
+#[path = "more_tests/compiler/introspection_test.rs"]
+mod introspection_test;
+
+// Include the docstring example tests
+#[path = "more_tests/compiler/doctest_test.rs"]
+mod doctest_test;
+
+// Include the iteration over runtime-typed values tests
+#[path = "more_tests/compiler/iteration_test.rs"]
+mod iteration_test;
+
+// Include the threaded print tests
+#[path = "more_tests/compiler/print_threads_test.rs"]
+mod print_threads_test;
+
+// Include the channel tests
+#[path = "more_tests/compiler/channel_test.rs"]
+mod channel_test;
+
+// Include the with_timeout() tests
+#[path = "more_tests/compiler/timeout_test.rs"]
+mod timeout_test;
+
+// Include the runtime abort tests
+#[path = "more_tests/compiler/runtime_abort_test.rs"]
+mod runtime_abort_test;
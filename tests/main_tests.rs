@@ -1,6 +1,7 @@
 // This file links all the test files together for easier test execution
 
 // Include the main test files
+#[cfg(feature = "codegen")]
 #[path = "compiler_tests.rs"]
 mod compiler_tests;
 
@@ -13,9 +14,66 @@ mod parser_tests;
 #[path = "type_checker_tests.rs"]
 mod type_checker_tests;
 
+// The interpreter backend needs no LLVM
+#[path = "more_tests/compiler/interpreter_test.rs"]
+mod interpreter_test;
+
+#[path = "more_tests/compiler/repl_config_test.rs"]
+mod repl_config_test;
+
+#[path = "more_tests/compiler/repl_command_test.rs"]
+mod repl_command_test;
+
+#[path = "more_tests/compiler/completion_test.rs"]
+mod completion_test;
+
+#[path = "more_tests/compiler/transform_test.rs"]
+mod transform_test;
+
+#[path = "more_tests/compiler/plugins_test.rs"]
+mod plugins_test;
+
+#[path = "more_tests/compiler/project_test.rs"]
+mod project_test;
+
+#[path = "more_tests/compiler/dap_test.rs"]
+mod dap_test;
+
+#[path = "more_tests/compiler/source_map_test.rs"]
+mod source_map_test;
+
+#[path = "more_tests/compiler/linter_test.rs"]
+mod linter_test;
+
+#[path = "more_tests/compiler/symtable_test.rs"]
+mod symtable_test;
+
+#[path = "more_tests/compiler/references_test.rs"]
+mod references_test;
+#[path = "more_tests/compiler/semantic_tokens_test.rs"]
+mod semantic_tokens_test;
+#[path = "more_tests/compiler/intern_test.rs"]
+mod intern_test;
+#[path = "more_tests/compiler/ir_test.rs"]
+mod ir_test;
+
 // This test ensures that all the test modules are properly linked
 #[test]
 fn test_all_modules_linked() {
     // This test doesn't do anything, it just ensures that all the modules are linked
     assert!(true);
 }
+
+#[path = "more_tests/compiler/diagnostics_test.rs"]
+mod diagnostics_test;
+
+#[path = "more_tests/compiler/error_codes_test.rs"]
+mod error_codes_test;
+#[path = "more_tests/compiler/source_files_test.rs"]
+mod source_files_test;
+#[path = "more_tests/compiler/diff_test.rs"]
+mod diff_test;
+#[path = "more_tests/compiler/class_layout_test.rs"]
+mod class_layout_test;
+#[path = "more_tests/compiler/cst_test.rs"]
+mod cst_test;
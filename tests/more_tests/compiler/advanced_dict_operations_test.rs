@@ -283,15 +283,14 @@ data = {"a": 1, "b": 2, "c": 3}
 keys = ["a", "b", "c", "d", "e"]
 values = []
 for key in keys:
-    if key in data:  # This won't work yet, but we're testing compilation
+    if key in data:
         values.append(data[key])
     else:
         values.append(0)
 "#;
 
     let result = compile_source(source);
-    // This test is expected to fail until 'in' operator is implemented for dictionaries
-    assert!(result.is_err(), "Dictionary 'in' operator should not be implemented yet");
+    assert!(result.is_ok(), "Failed to compile dictionary with default values: {:?}", result.err());
 }
 
 #[test]
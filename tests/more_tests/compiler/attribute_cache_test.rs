@@ -0,0 +1,136 @@
+// attribute_cache_test.rs - Tests for inline caches on class instance attributes
+
+use cheetah::compiler::runtime::exception::{clear_current_exception, exception_get_type, get_current_exception};
+use cheetah::compiler::runtime::instance::*;
+use std::ffi::{CStr, CString};
+
+fn name(attr: &str) -> CString {
+    CString::new(attr).unwrap()
+}
+
+fn new(class: &str) -> *mut Instance {
+    instance_new(name(class).as_ptr())
+}
+
+fn set(instance: *mut Instance, attr: &str, value: i64, cache: &mut AttributeCache) {
+    instance_set_attr(instance, name(attr).as_ptr(), value, cache);
+}
+
+/// Store through a site without a cache
+fn set_uncached(instance: *mut Instance, attr: &str, value: i64) {
+    instance_set_attr(instance, name(attr).as_ptr(), value, std::ptr::null_mut());
+}
+
+fn get(instance: *mut Instance, attr: &str, cache: &mut AttributeCache) -> i64 {
+    instance_get_attr(instance, name(attr).as_ptr(), cache)
+}
+
+/// The type of the pending exception, clearing it
+fn take_exception() -> Option<String> {
+    let exception = get_current_exception();
+    if exception.is_null() {
+        return None;
+    }
+    let typ = unsafe { CStr::from_ptr(exception_get_type(exception)) }
+        .to_string_lossy()
+        .into_owned();
+    clear_current_exception();
+    Some(typ)
+}
+
+#[test]
+fn test_sites_cache_the_slot_for_a_class() {
+    let (a, b) = (new("CachePoint"), new("CachePoint"));
+    let mut store_x = AttributeCache::EMPTY;
+    let mut store_y = AttributeCache::EMPTY;
+    set(a, "x", 1, &mut store_x);
+    set(a, "y", 2, &mut store_y);
+    set(b, "x", 10, &mut store_x);
+    set(b, "y", 20, &mut store_y);
+
+    let mut load = AttributeCache::EMPTY;
+    assert_eq!(get(a, "y", &mut load), 2);
+    assert_eq!(load.shape, class_shape("CachePoint"));
+    assert_eq!(load.slot, 1);
+
+    // Instances of the same class use the filled cache
+    assert_eq!(get(b, "y", &mut load), 20);
+    assert_eq!((load.shape, load.slot), (class_shape("CachePoint"), 1));
+
+    instance_free(a);
+    instance_free(b);
+}
+
+#[test]
+fn test_other_classes_miss_and_refill_the_cache() {
+    let point = new("CacheVec2");
+    let other = new("CacheVec3");
+    let mut store = AttributeCache::EMPTY;
+    set_uncached(other, "pad", 0);
+    set(point, "x", 5, &mut store);
+    set(other, "x", 7, &mut store);
+
+    let mut load = AttributeCache::EMPTY;
+    assert_eq!(get(point, "x", &mut load), 5);
+    assert_eq!(load.shape, class_shape("CacheVec2"));
+    assert_eq!(get(other, "x", &mut load), 7);
+    assert_eq!(load.shape, class_shape("CacheVec3"));
+    assert_eq!(load.slot, 1);
+    assert_eq!(get(point, "x", &mut load), 5);
+
+    instance_free(point);
+    instance_free(other);
+}
+
+#[test]
+fn test_stale_versions_are_looked_up_again() {
+    let instance = new("CacheStale");
+    set_uncached(instance, "a", 1);
+    set_uncached(instance, "b", 2);
+
+    // A cache pointing at the wrong slot is only trusted while its version is current
+    let stale = shape_version() - 1;
+    let mut cache = AttributeCache {
+        shape: class_shape("CacheStale"),
+        version: stale,
+        slot: 0,
+    };
+    assert_eq!(get(instance, "b", &mut cache), 2);
+    assert_eq!(cache.slot, 1);
+    assert!(cache.version > stale);
+
+    instance_free(instance);
+}
+
+#[test]
+fn test_missing_attributes_raise_attribute_error() {
+    let instance = new("CacheMissing");
+    let mut cache = AttributeCache::EMPTY;
+    assert_eq!(get(instance, "nope", &mut cache), 0);
+    assert_eq!(take_exception().as_deref(), Some("AttributeError"));
+    assert_eq!(cache, AttributeCache::EMPTY);
+
+    set_uncached(instance, "gone", 3);
+    assert_eq!(instance_del_attr(instance, name("gone").as_ptr()), 1);
+    assert_eq!(get(instance, "gone", &mut cache), 0);
+    assert_eq!(take_exception().as_deref(), Some("AttributeError"));
+    assert_eq!(instance_del_attr(instance, name("gone").as_ptr()), 0);
+    assert_eq!(take_exception().as_deref(), Some("AttributeError"));
+
+    instance_free(instance);
+}
+
+#[test]
+fn test_classes_with_abstract_methods_cannot_be_instantiated() {
+    instance_set_abstract_methods(name("CacheShape").as_ptr(), name("area,perimeter").as_ptr());
+    assert!(new("CacheShape").is_null());
+    assert_eq!(take_exception().as_deref(), Some("TypeError"));
+
+    // Redefining the class with everything implemented lifts the restriction
+    instance_set_abstract_methods(name("CacheShape").as_ptr(), name("").as_ptr());
+    let instance = new("CacheShape");
+    assert!(!instance.is_null());
+    assert_eq!(take_exception(), None);
+
+    instance_free(instance);
+}
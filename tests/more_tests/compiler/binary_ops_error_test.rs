@@ -49,3 +49,50 @@ fn test_unsupported_operations() {
     assert!(expect_error("{\"key\": \"value\"} | {\"other\": \"value\"}"));
     assert!(expect_error("None ^ 5"));
 }
+
+#[test]
+fn test_operator_table_supported_cells() {
+    use cheetah::ast::Operator;
+    use cheetah::compiler::types::{binary_op_lowering, BinaryOpLowering, Type};
+
+    assert_eq!(
+        binary_op_lowering(&Operator::Add, &Type::Int, &Type::Float),
+        Some(BinaryOpLowering::Numeric(Type::Float))
+    );
+    assert_eq!(
+        binary_op_lowering(&Operator::BitAnd, &Type::Bool, &Type::Int),
+        Some(BinaryOpLowering::Numeric(Type::Int))
+    );
+    assert_eq!(
+        binary_op_lowering(&Operator::Add, &Type::String, &Type::String),
+        Some(BinaryOpLowering::StringConcat)
+    );
+    assert_eq!(
+        binary_op_lowering(&Operator::Mult, &Type::Int, &Type::String),
+        Some(BinaryOpLowering::StringRepeat { swapped: true })
+    );
+    assert_eq!(
+        binary_op_lowering(
+            &Operator::Mult,
+            &Type::List(Box::new(Type::Int)),
+            &Type::Int
+        ),
+        Some(BinaryOpLowering::ListRepeat { swapped: false })
+    );
+}
+
+#[test]
+fn test_operator_table_unsupported_cells() {
+    use cheetah::ast::Operator;
+    use cheetah::compiler::types::{binary_op_lowering, unsupported_operand_types, Type};
+
+    assert_eq!(binary_op_lowering(&Operator::Mod, &Type::String, &Type::Int), None);
+    assert_eq!(binary_op_lowering(&Operator::Add, &Type::String, &Type::Int), None);
+    assert_eq!(binary_op_lowering(&Operator::BitOr, &Type::Float, &Type::Int), None);
+    assert_eq!(binary_op_lowering(&Operator::MatMult, &Type::Int, &Type::Int), None);
+
+    assert_eq!(
+        unsupported_operand_types(&Operator::Mod, &Type::String, &Type::Int),
+        "unsupported operand types for %: 'str' and 'int'"
+    );
+}
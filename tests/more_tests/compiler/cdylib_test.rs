@@ -0,0 +1,80 @@
+// cdylib_test.rs - Tests for shared library builds exporting C symbols
+
+use cheetah::compiler::cdylib::{c_header, library_file_name, symbol_prefix, CrateType, ExportedFunction};
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use inkwell::context::Context;
+use inkwell::module::Linkage;
+
+#[test]
+fn test_crate_type_names() {
+    assert_eq!(CrateType::from_name("bin"), Ok(CrateType::Bin));
+    assert_eq!(CrateType::from_name("cdylib"), Ok(CrateType::Cdylib));
+    assert!(CrateType::from_name("dylib").unwrap_err().contains("unknown crate type"));
+    assert_eq!(CrateType::default(), CrateType::Bin);
+}
+
+#[test]
+fn test_library_names_and_prefixes() {
+    let library = library_file_name("geometry");
+    assert!(library.contains("geometry"));
+    if cfg!(target_os = "linux") {
+        assert_eq!(library, "libgeometry.so");
+    }
+
+    assert_eq!(symbol_prefix("geometry"), "geometry");
+    assert_eq!(symbol_prefix("my-lib.v2"), "my_lib_v2");
+    assert_eq!(symbol_prefix("3d"), "_3d");
+}
+
+#[test]
+fn test_c_header() {
+    let exports = vec![
+        ExportedFunction {
+            symbol: "geometry_init".to_string(),
+            return_type: "void".to_string(),
+            param_types: Vec::new(),
+        },
+        ExportedFunction {
+            symbol: "geometry_area".to_string(),
+            return_type: "int64_t".to_string(),
+            param_types: vec!["int64_t".to_string(), "int64_t".to_string()],
+        },
+    ];
+    let header = c_header("geometry", &exports);
+
+    assert!(header.contains("#ifndef GEOMETRY_H"));
+    assert!(header.contains("#include <stdint.h>"));
+    assert!(header.contains("extern \"C\" {"));
+    assert!(header.contains("void geometry_init(void);"));
+    assert!(header.contains("int64_t geometry_area(int64_t, int64_t);"));
+}
+
+#[test]
+fn test_export_c_symbols() {
+    let source = r#"
+scale = 3
+
+def area(w: int, h: int) -> int:
+    return w * h * scale
+
+print(area(2, 3))
+"#;
+    let ast = parse(source).expect("source should parse");
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "cdylib_test");
+    compiler.compile_module(&ast).expect("source should compile");
+    assert_eq!(compiler.top_level_functions, vec!["area".to_string()]);
+
+    let (exports, skipped) = compiler.export_c_symbols("geometry");
+    assert!(skipped.is_empty());
+    let symbols: Vec<&str> = exports.iter().map(|export| export.symbol.as_str()).collect();
+    assert_eq!(symbols, vec!["geometry_init", "geometry_area"]);
+    assert_eq!(exports[1].prototype(), "int64_t geometry_area(int64_t, int64_t);");
+
+    let module = compiler.get_module();
+    assert!(module.get_function("main").is_none());
+    let area = module.get_function("geometry_area").expect("area should be exported");
+    assert_eq!(area.get_linkage(), Linkage::External);
+    assert!(module.verify().is_ok());
+}
@@ -0,0 +1,80 @@
+// cfg_dump_test.rs - Tests for Graphviz dumps of compiled control flow graphs
+
+use cheetah::compiler::cfg_dump::{CfgBlock, CfgGraph};
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use inkwell::context::Context;
+
+fn block(name: &str, instructions: &[&str], successors: &[(&str, Option<&'static str>)]) -> CfgBlock {
+    CfgBlock {
+        name: name.to_string(),
+        instructions: instructions.iter().map(|i| i.to_string()).collect(),
+        successors: successors.iter().map(|(s, l)| (s.to_string(), *l)).collect(),
+    }
+}
+
+fn diamond() -> CfgGraph {
+    CfgGraph {
+        function: "pick".to_string(),
+        blocks: vec![
+            block("entry", &["br i1 %c, label %then, label %else"], &[("then", Some("true")), ("else", Some("false"))]),
+            block("then", &["br label %merge"], &[("merge", None)]),
+            block("else", &["br label %merge"], &[("merge", None)]),
+            block("merge", &["ret i64 0"], &[]),
+        ],
+    }
+}
+
+#[test]
+fn test_cfg_to_dot() {
+    let dot = diamond().to_dot();
+    assert!(dot.starts_with("digraph \"CFG for pick\" {"));
+    assert!(dot.contains("\"entry\" -> \"then\" [label=\"true\"];"));
+    assert!(dot.contains("\"entry\" -> \"else\" [label=\"false\"];"));
+    assert!(dot.contains("\"then\" -> \"merge\";"));
+    assert!(dot.contains("ret i64 0\\l"));
+    assert!(dot.trim_end().ends_with('}'));
+}
+
+#[test]
+fn test_cfg_escapes_record_labels() {
+    let graph = CfgGraph {
+        function: "f".to_string(),
+        blocks: vec![block("entry", &["%s = call ptr @g(ptr @\"x\")", "ret { i64 } %s"], &[])],
+    };
+    let dot = graph.to_dot();
+    assert!(dot.contains("@\\\"x\\\""));
+    assert!(dot.contains("ret \\{ i64 \\} %s"));
+}
+
+#[test]
+fn test_unterminated_blocks() {
+    assert!(diamond().unterminated_blocks().is_empty());
+
+    let mut broken = diamond();
+    broken.blocks[1] = block("then", &["%x = add i64 1, 2"], &[]);
+    assert_eq!(broken.unterminated_blocks(), vec!["then"]);
+}
+
+#[test]
+fn test_compiled_functions_have_cfgs() {
+    let source = r#"
+def sign(x: int) -> int:
+    if x < 0:
+        return -1
+    while x > 10:
+        x = x - 10
+    return 1
+"#;
+    let ast = parse(source).expect("source should parse");
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "cfg_dump_test");
+    compiler.compile_module(&ast).expect("source should compile");
+
+    let graphs = compiler.cfg_graphs(Some("sign")).expect("sign should be compiled");
+    assert_eq!(graphs.len(), 1);
+    assert!(graphs[0].blocks.len() > 3);
+    assert!(graphs[0].unterminated_blocks().is_empty());
+
+    assert!(compiler.cfg_graphs(Some("missing")).is_err());
+}
@@ -0,0 +1,139 @@
+// channel_test.rs - Tests for channel(), send() and recv()
+
+use cheetah::compiler::runtime::channel::*;
+use cheetah::compiler::runtime::exception::{clear_current_exception, exception_get_type, get_current_exception};
+use cheetah::compiler::runtime::list::{list_append_tagged, list_free, list_get, list_len, list_new, RawList, TypeTag};
+use cheetah::compiler::runtime::string::free_string;
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use inkwell::context::Context;
+use std::ffi::{c_void, CStr, CString};
+use std::time::{Duration, Instant};
+
+pub fn compile_source(source: &str) -> Result<String, String> {
+    let ast = parse(source).map_err(|errors| format!("Parse errors: {:?}", errors))?;
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "channel_test");
+    compiler
+        .compile_module(&ast)
+        .map_err(|e| format!("Compilation error: {}", e))?;
+    Ok(compiler.get_ir())
+}
+
+fn send_int(channel: *mut Channel, mut value: i64) {
+    channel_send(channel, &mut value as *mut i64 as *mut c_void, TypeTag::Int);
+}
+
+fn recv_int(channel: *mut Channel) -> i64 {
+    unsafe { *(channel_recv(channel) as *const i64) }
+}
+
+/// Channels are never freed, so tests can hand them to threads as an address
+fn share(channel: *mut Channel) -> usize {
+    channel as usize
+}
+
+#[test]
+fn test_values_arrive_in_the_order_sent() {
+    let channel = channel_new();
+    for n in 0..5 {
+        send_int(channel, n);
+    }
+    assert_eq!(channel_len(channel), 5);
+    let received: Vec<i64> = (0..5).map(|_| recv_int(channel)).collect();
+    assert_eq!(received, vec![0, 1, 2, 3, 4]);
+    assert_eq!(channel_len(channel), 0);
+}
+
+#[test]
+fn test_sent_strings_and_lists_are_copies() {
+    let channel = channel_new();
+    let text = CString::new("cheetah").unwrap().into_raw();
+    channel_send(channel, text as *mut c_void, TypeTag::String);
+    free_string(text);
+    let received = channel_recv(channel) as *mut std::os::raw::c_char;
+    assert_eq!(unsafe { CStr::from_ptr(received) }.to_str().unwrap(), "cheetah");
+    free_string(received);
+
+    let list = list_new();
+    let item = CString::new("inner").unwrap().into_raw();
+    list_append_tagged(list, item as *mut c_void, TypeTag::String);
+    channel_send(channel, list as *mut c_void, TypeTag::List);
+    let received = channel_recv(channel) as *mut RawList;
+    assert_ne!(received, list);
+    assert_ne!(list_get(received, 0), list_get(list, 0));
+    list_free(list);
+    assert_eq!(list_len(received), 1);
+    assert_eq!(unsafe { CStr::from_ptr(list_get(received, 0) as *const _) }.to_str().unwrap(), "inner");
+    list_free(received);
+}
+
+#[test]
+fn test_many_senders_one_receiver() {
+    let channel = share(channel_new());
+    let senders: Vec<_> = (0..4)
+        .map(|sender| {
+            std::thread::spawn(move || {
+                for n in 0..100 {
+                    send_int(channel as *mut Channel, sender * 1000 + n);
+                }
+            })
+        })
+        .collect();
+
+    let mut received: Vec<i64> = (0..400).map(|_| recv_int(channel as *mut Channel)).collect();
+    for sender in senders {
+        sender.join().unwrap();
+    }
+    // Each sender's values keep their order
+    for sender in 0..4 {
+        let from: Vec<i64> = received.iter().copied().filter(|n| n / 1000 == sender).collect();
+        assert_eq!(from, (0..100).map(|n| sender * 1000 + n).collect::<Vec<_>>());
+    }
+    received.sort();
+    received.dedup();
+    assert_eq!(received.len(), 400);
+}
+
+#[test]
+fn test_recv_waits_for_a_value() {
+    let channel = share(channel_new());
+    let sender = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        send_int(channel as *mut Channel, 7);
+    });
+    let value = unsafe { *(channel_recv_timeout(channel as *mut Channel, 10.0) as *const i64) };
+    assert_eq!(value, 7);
+    assert!(get_current_exception().is_null());
+    sender.join().unwrap();
+}
+
+#[test]
+fn test_recv_timeout_raises_timeout_error() {
+    let channel = channel_new();
+    let start = Instant::now();
+    let value = unsafe { *(channel_recv_timeout(channel, 0.05) as *const i64) };
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    assert_eq!(value, 0);
+
+    let exception = get_current_exception();
+    assert!(!exception.is_null());
+    let typ = unsafe { CStr::from_ptr(exception_get_type(exception)) };
+    assert_eq!(typ.to_str().unwrap(), "TimeoutError");
+    clear_current_exception();
+}
+
+#[test]
+fn test_compile_channel_calls() {
+    let source = r#"
+jobs = channel(int)
+send(jobs, 41)
+answer = recv(jobs) + 1
+late = recv(jobs, 0.5)
+"#;
+    let ir = compile_source(source).unwrap();
+    assert!(ir.contains("@channel_new("));
+    assert!(ir.contains("@channel_send("));
+    assert!(ir.contains("@channel_recv("));
+    assert!(ir.contains("@channel_recv_timeout("));
+}
@@ -0,0 +1,135 @@
+// class_layout_test.rs - Tests for fixed instance layouts of classes
+
+use cheetah::compiler::class_layout::{analyze, AttributeStorage, ClassLayout};
+use cheetah::compiler::types::Type;
+use cheetah::parse;
+
+/// How the first class in `source` stores its attributes
+fn storage(source: &str) -> AttributeStorage {
+    let module = parse(source).expect("source should parse");
+    let class = module
+        .body
+        .iter()
+        .find(|stmt| matches!(stmt.as_ref(), cheetah::ast::Stmt::ClassDef { .. }))
+        .expect("source should define a class");
+    analyze(class, &module.body)
+}
+
+fn fixed(source: &str) -> ClassLayout {
+    match storage(source) {
+        AttributeStorage::Fixed(layout) => layout,
+        AttributeStorage::Dynamic(reason) => panic!("expected a fixed layout, got dynamic: {}", reason),
+    }
+}
+
+fn dynamic_reason(source: &str) -> String {
+    match storage(source) {
+        AttributeStorage::Dynamic(reason) => reason,
+        AttributeStorage::Fixed(layout) => panic!("expected dynamic attributes, got {:?}", layout),
+    }
+}
+
+const POINT: &str = "\
+class Point:
+    def __init__(self, x, y):
+        self.x: float = x
+        self.y: float = y
+        if x > 0:
+            self.label: str = \"right\"
+        else:
+            self.label = \"left\"
+        self.history: list[int] = []
+
+    def move(self, dx):
+        self.x = self.x + dx
+        self.history.append(dx)
+";
+
+#[test]
+fn test_annotated_init_attributes_get_fixed_fields_in_order() {
+    let layout = fixed(POINT);
+    assert_eq!(layout.name, "Point");
+    assert_eq!(
+        layout.fields,
+        vec![
+            ("x".to_string(), Type::Float),
+            ("y".to_string(), Type::Float),
+            ("label".to_string(), Type::String),
+            ("history".to_string(), Type::List(Box::new(Type::Int))),
+        ]
+    );
+    assert_eq!(layout.field_index("label"), Some(2));
+    assert_eq!(layout.field_index("z"), None);
+}
+
+#[test]
+fn test_outside_stores_to_known_fields_keep_the_layout() {
+    let source = format!("{}\np = Point(1.0, 2.0)\np.y = 3.0\nprint(p.__class__)\n", POINT);
+    assert_eq!(fixed(&source).fields.len(), 4);
+
+    // Other classes' attributes are their own business
+    let source = format!(
+        "{}\nclass Other:\n    def __init__(self):\n        self.anything = 1\n    def set(self):\n        self.more = 2\n",
+        POINT
+    );
+    assert_eq!(fixed(&source).name, "Point");
+
+    assert!(fixed("class Empty:\n    pass\n").fields.is_empty());
+}
+
+#[test]
+fn test_dynamic_attributes_fall_back_to_a_dict() {
+    let cases = [
+        ("class A:\n    def __init__(self):\n        self.x = 1\n", "'x' is assigned without an annotation"),
+        (
+            "class A:\n    def __init__(self):\n        self.x: int = 1\n    def f(self):\n        self.y = 2\n",
+            "'y' is assigned outside __init__",
+        ),
+        ("class A(Base):\n    pass\n", "A inherits from Base"),
+        ("class A:\n    pass\na = A()\na.extra = 1\n", "'extra' may be assigned from outside the class"),
+        (
+            "class A:\n    def __init__(self):\n        self.x: int = 1\n    def f(self):\n        del self.x\n",
+            "'x' is deleted",
+        ),
+        ("class A:\n    pass\nsetattr(A(), \"x\", 1)\n", "setattr is used"),
+        ("class A:\n    def f(self):\n        return self.__dict__\n", "__dict__ is used"),
+        (
+            "class A:\n    def __init__(self):\n        self.x: int = 1\n        self.x: str = \"\"\n",
+            "'x' is annotated as both int and str",
+        ),
+        (
+            "class A:\n    def __init__(self):\n        self.x: list[1] = []\n",
+            "the annotation of 'x' is not a type",
+        ),
+    ];
+    for (source, reason) in cases {
+        assert_eq!(dynamic_reason(source), reason, "for:\n{}", source);
+    }
+}
+
+#[test]
+fn test_object_base_keeps_the_layout() {
+    let layout = fixed("class A(object):\n    def __init__(self, n):\n        self.n: int = n\n");
+    assert_eq!(layout.fields, vec![("n".to_string(), Type::Int)]);
+}
+
+#[cfg(feature = "codegen")]
+#[test]
+fn test_compiler_registers_fixed_layouts() {
+    use cheetah::compiler::Compiler;
+    use inkwell::context::Context;
+
+    let module = parse(
+        "class Point:\n    def __init__(self, x, y):\n        self.x: int = x\n        self.y: float = y\n\n\
+         class Bag:\n    def __init__(self):\n        self.items = []\n",
+    )
+    .unwrap();
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "class_layout_test");
+    compiler.compile_module(&module).unwrap();
+
+    let layouts = &compiler.context.class_layouts;
+    assert_eq!(layouts["Point"].field_index("y"), Some(1));
+    assert!(compiler.context.class_types.contains_key("Point"));
+    assert!(!layouts.contains_key("Bag"));
+}
@@ -0,0 +1,100 @@
+// class_object_test.rs - Tests for classes as runtime values
+
+use cheetah::compiler::runtime::exception::{clear_current_exception, exception_get_type, get_current_exception};
+use cheetah::compiler::runtime::instance::*;
+use cheetah::compiler::types::Type;
+use std::ffi::{CStr, CString};
+
+fn name(text: &str) -> CString {
+    CString::new(text).unwrap()
+}
+
+/// Define `class` with the classes after it in its MRO
+fn define(class: &str, mro: &str) -> *mut ClassObject {
+    class_define(name(class).as_ptr(), name(mro).as_ptr())
+}
+
+/// The type of the pending exception, clearing it
+fn take_exception() -> Option<String> {
+    let exception = get_current_exception();
+    if exception.is_null() {
+        return None;
+    }
+    let typ = unsafe { CStr::from_ptr(exception_get_type(exception)) }
+        .to_string_lossy()
+        .into_owned();
+    clear_current_exception();
+    Some(typ)
+}
+
+#[test]
+fn test_class_attributes_are_looked_up_through_the_mro() {
+    let animal = define("ObjAnimal", "");
+    let dog = define("ObjDog", "ObjAnimal");
+    class_set_attr(animal, name("legs").as_ptr(), 4);
+    class_set_attr(dog, name("sound").as_ptr(), 7);
+
+    assert_eq!(class_get_attr(dog, name("legs").as_ptr()), 4);
+    assert_eq!(class_get_attr(dog, name("sound").as_ptr()), 7);
+    assert_eq!(class_get_attr(animal, name("sound").as_ptr()), 0);
+    assert_eq!(take_exception().as_deref(), Some("AttributeError"));
+
+    // The same class always has the same object
+    assert_eq!(class_object(name("ObjDog").as_ptr()), dog);
+}
+
+#[test]
+fn test_calling_a_class_object_creates_an_instance() {
+    let counter = define("ObjCounter", "");
+    class_set_attr(counter, name("count").as_ptr(), 3);
+
+    let instance = class_instantiate(counter);
+    assert!(!instance.is_null());
+
+    // Instances read class attributes they don't shadow
+    let mut cache = AttributeCache::EMPTY;
+    assert_eq!(instance_get_attr(instance, name("count").as_ptr(), &mut cache), 3);
+    instance_set_attr(instance, name("count").as_ptr(), 10, &mut cache);
+    assert_eq!(instance_get_attr(instance, name("count").as_ptr(), &mut cache), 10);
+    assert_eq!(class_get_attr(counter, name("count").as_ptr()), 3);
+    assert_eq!(take_exception(), None);
+
+    instance_free(instance);
+}
+
+#[test]
+fn test_redefining_a_class_resets_its_attributes() {
+    let first = define("ObjRedefined", "");
+    class_set_attr(first, name("old").as_ptr(), 1);
+    let second = define("ObjRedefined", "");
+    assert_eq!(first, second);
+    assert_eq!(class_get_attr(second, name("old").as_ptr()), 0);
+    assert_eq!(take_exception().as_deref(), Some("AttributeError"));
+}
+
+#[test]
+fn test_compiler_creates_class_objects() {
+    use cheetah::compiler::Compiler;
+    use inkwell::context::Context;
+
+    let module = cheetah::parse(
+        "class Config:\n    name = \"app\"\n    retries: float = 3\n\n\
+         def describe():\n    return Config.name\n\n\
+         Config.retries = Config.retries * 2\n\
+         factory = Config\n\
+         config = factory()\n",
+    )
+    .unwrap();
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "class_object_test");
+    compiler.compile_module(&module).unwrap();
+
+    let attributes = &compiler.context.class_attributes["Config"];
+    assert_eq!(attributes["name"], Type::String);
+    assert_eq!(attributes["retries"], Type::Float);
+
+    let ir = compiler.get_ir();
+    assert!(ir.contains("@class_define"), "{}", ir);
+    assert!(ir.contains("@class_get_attr"), "{}", ir);
+    assert!(ir.contains("@class_instantiate"), "{}", ir);
+}
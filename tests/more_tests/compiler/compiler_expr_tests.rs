@@ -1,4 +1,4 @@
-use cheetah::ast::{Expr, Number, NameConstant, Operator, UnaryOperator, CmpOperator, ExprContext};
+use cheetah::ast::{Expr, Number, NameConstant, Operator, UnaryOperator, CmpOperator, ExprContext, Span};
 use cheetah::compiler::context::CompilationContext;
 use cheetah::compiler::expr::ExprCompiler;
 use cheetah::compiler::types::Type;
@@ -26,34 +26,41 @@ fn test_nested_expressions() {
     let left = Expr::BinOp {
         left: Box::new(Expr::Num {
             value: Number::Integer(10),
-            line: 1, column: 1
+            line: 1, column: 1,
+            span: Span::default(),
         }),
         op: Operator::Add,
         right: Box::new(Expr::Num {
             value: Number::Integer(20),
-            line: 1, column: 5
+            line: 1, column: 5,
+            span: Span::default(),
         }),
-        line: 1, column: 3
+        line: 1, column: 3,
+        span: Span::default(),
     };
     
     let right = Expr::BinOp {
         left: Box::new(Expr::Num {
             value: Number::Integer(5),
-            line: 1, column: 11
+            line: 1, column: 11,
+            span: Span::default(),
         }),
         op: Operator::Sub,
         right: Box::new(Expr::Num {
             value: Number::Integer(2),
-            line: 1, column: 15
+            line: 1, column: 15,
+            span: Span::default(),
         }),
-        line: 1, column: 13
+        line: 1, column: 13,
+        span: Span::default(),
     };
     
     let expr = Expr::BinOp {
         left: Box::new(left),
         op: Operator::Mult,
         right: Box::new(right),
-        line: 1, column: 8
+        line: 1, column: 8,
+        span: Span::default(),
     };
     
     // Compile the nested expression
@@ -94,22 +101,26 @@ fn test_comparison_chains() {
         left: Box::new(Expr::Name {
             id: var_a.clone(),
             ctx: ExprContext::Load,
-            line: 1, column: 1
+            line: 1, column: 1,
+            span: Span::default(),
         }),
         ops: vec![CmpOperator::Lt, CmpOperator::Lt],
         comparators: vec![
             Box::new(Expr::Name {
                 id: var_b.clone(),
                 ctx: ExprContext::Load,
-                line: 1, column: 5
+                line: 1, column: 5,
+                span: Span::default(),
             }),
             Box::new(Expr::Name {
                 id: var_c.clone(),
                 ctx: ExprContext::Load,
-                line: 1, column: 9
+                line: 1, column: 9,
+                span: Span::default(),
             })
         ],
-        line: 1, column: 3
+        line: 1, column: 3,
+        span: Span::default(),
     };
     
     // Compile the comparison chain
@@ -130,14 +141,17 @@ fn test_mixed_type_operations() {
     let expr = Expr::BinOp {
         left: Box::new(Expr::Num {
             value: Number::Integer(10),
-            line: 1, column: 1
+            line: 1, column: 1,
+            span: Span::default(),
         }),
         op: Operator::Add,
         right: Box::new(Expr::Num {
             value: Number::Float(3.14),
-            line: 1, column: 5
+            line: 1, column: 5,
+            span: Span::default(),
         }),
-        line: 1, column: 3
+        line: 1, column: 3,
+        span: Span::default(),
     };
     
     // Compile the expression
@@ -159,9 +173,11 @@ fn test_boolean_operations() {
         op: UnaryOperator::Not,
         operand: Box::new(Expr::NameConstant {
             value: NameConstant::True,
-            line: 1, column: 5
+            line: 1, column: 5,
+            span: Span::default(),
         }),
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
     
     let (not_val, not_type) = ctx.compile_expr(&not_expr).unwrap();
@@ -173,9 +189,11 @@ fn test_boolean_operations() {
         op: UnaryOperator::Not,
         operand: Box::new(Expr::Num {
             value: Number::Integer(42),
-            line: 1, column: 5
+            line: 1, column: 5,
+            span: Span::default(),
         }),
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
     
     let (val, ty) = ctx.compile_expr(&non_bool_not).unwrap();
@@ -198,7 +216,8 @@ fn test_variable_updates() {
     let var_expr = Expr::Name {
         id: var_name.clone(),
         ctx: ExprContext::Load,
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
     
     // Compile the variable reference
@@ -1,7 +1,8 @@
-use cheetah::ast::{Stmt, Expr, Number, Operator, CmpOperator, ExprContext};
+use cheetah::ast::{Stmt, Expr, Number, Operator, CmpOperator, ExprContext, Span};
 use cheetah::compiler::context::CompilationContext;
 use cheetah::compiler::stmt::StmtCompiler;
 use cheetah::compiler::types::Type;
+use cheetah::intern::Symbol;
 use inkwell::context::Context;
 
 fn setup_context<'ctx>(context: &'ctx Context) -> CompilationContext<'ctx> {
@@ -39,49 +40,59 @@ fn test_nested_if_statements() {
         left: Box::new(Expr::Name {
             id: y_name.clone(),
             ctx: ExprContext::Load,
-            line: 2, column: 8
+            line: 2, column: 8,
+            span: Span::default(),
         }),
         ops: vec![CmpOperator::Gt],
         comparators: vec![
             Box::new(Expr::Num {
                 value: Number::Integer(15),
-                line: 2, column: 12
+                line: 2, column: 12,
+                span: Span::default(),
             })
         ],
-        line: 2, column: 10
+        line: 2, column: 10,
+        span: Span::default(),
     };
 
     let inner_then_stmt = Stmt::Assign {
         targets: vec![Box::new(Expr::Name {
             id: z_name.clone(),
             ctx: ExprContext::Store,
-            line: 3, column: 12
+            line: 3, column: 12,
+            span: Span::default(),
         })],
         value: Box::new(Expr::Num {
             value: Number::Integer(1),
-            line: 3, column: 16
+            line: 3, column: 16,
+            span: Span::default(),
         }),
-        line: 3, column: 14
+        line: 3, column: 14,
+        span: Span::default(),
     };
 
     let inner_else_stmt = Stmt::Assign {
         targets: vec![Box::new(Expr::Name {
             id: z_name.clone(),
             ctx: ExprContext::Store,
-            line: 5, column: 12
+            line: 5, column: 12,
+            span: Span::default(),
         })],
         value: Box::new(Expr::Num {
             value: Number::Integer(2),
-            line: 5, column: 16
+            line: 5, column: 16,
+            span: Span::default(),
         }),
-        line: 5, column: 14
+        line: 5, column: 14,
+        span: Span::default(),
     };
 
     let inner_if = Stmt::If {
         test: Box::new(inner_test),
         body: vec![Box::new(inner_then_stmt)],
         orelse: vec![Box::new(inner_else_stmt)],
-        line: 2, column: 8
+        line: 2, column: 8,
+        span: Span::default(),
     };
 
     // Create outer if statement: if x > 5: <inner_if> else: z = 3
@@ -89,36 +100,43 @@ fn test_nested_if_statements() {
         left: Box::new(Expr::Name {
             id: x_name.clone(),
             ctx: ExprContext::Load,
-            line: 1, column: 4
+            line: 1, column: 4,
+            span: Span::default(),
         }),
         ops: vec![CmpOperator::Gt],
         comparators: vec![
             Box::new(Expr::Num {
                 value: Number::Integer(5),
-                line: 1, column: 8
+                line: 1, column: 8,
+                span: Span::default(),
             })
         ],
-        line: 1, column: 6
+        line: 1, column: 6,
+        span: Span::default(),
     };
 
     let outer_else_stmt = Stmt::Assign {
         targets: vec![Box::new(Expr::Name {
             id: z_name.clone(),
             ctx: ExprContext::Store,
-            line: 7, column: 8
+            line: 7, column: 8,
+            span: Span::default(),
         })],
         value: Box::new(Expr::Num {
             value: Number::Integer(3),
-            line: 7, column: 12
+            line: 7, column: 12,
+            span: Span::default(),
         }),
-        line: 7, column: 10
+        line: 7, column: 10,
+        span: Span::default(),
     };
 
     let outer_if = Stmt::If {
         test: Box::new(outer_test),
         body: vec![Box::new(inner_if)],
         orelse: vec![Box::new(outer_else_stmt)],
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
 
     // Compile the nested if statements
@@ -144,28 +162,34 @@ fn test_complex_assignment() {
         left: Box::new(Expr::Name {
             id: b_name.clone(),
             ctx: ExprContext::Store,
-            line: 1, column: 6
+            line: 1, column: 6,
+            span: Span::default(),
         }),
         op: Operator::Add, // This isn't really an addition in Python, but we'll use it for the test
         right: Box::new(Expr::Num {
             value: Number::Integer(10),
-            line: 1, column: 10
+            line: 1, column: 10,
+            span: Span::default(),
         }),
-        line: 1, column: 8
+        line: 1, column: 8,
+        span: Span::default(),
     };
 
     let c_assign = Expr::BinOp {
         left: Box::new(Expr::Name {
             id: c_name.clone(),
             ctx: ExprContext::Store,
-            line: 1, column: 15
+            line: 1, column: 15,
+            span: Span::default(),
         }),
         op: Operator::Add,
         right: Box::new(Expr::Num {
             value: Number::Integer(20),
-            line: 1, column: 19
+            line: 1, column: 19,
+            span: Span::default(),
         }),
-        line: 1, column: 17
+        line: 1, column: 17,
+        span: Span::default(),
     };
 
     // Create the outer assignment: a = b_assign + c_assign
@@ -173,15 +197,18 @@ fn test_complex_assignment() {
         targets: vec![Box::new(Expr::Name {
             id: a_name.clone(),
             ctx: ExprContext::Store,
-            line: 1, column: 1
+            line: 1, column: 1,
+            span: Span::default(),
         })],
         value: Box::new(Expr::BinOp {
             left: Box::new(b_assign),
             op: Operator::Add,
             right: Box::new(c_assign),
-            line: 1, column: 13
+            line: 1, column: 13,
+            span: Span::default(),
         }),
-        line: 1, column: 3
+        line: 1, column: 3,
+        span: Span::default(),
     };
 
     // Try to compile this complex assignment
@@ -210,16 +237,19 @@ fn test_while_loop_with_break() {
         left: Box::new(Expr::Name {
             id: i_name.clone(),
             ctx: ExprContext::Load,
-            line: 1, column: 7
+            line: 1, column: 7,
+            span: Span::default(),
         }),
         ops: vec![CmpOperator::Lt],
         comparators: vec![
             Box::new(Expr::Num {
                 value: Number::Integer(10),
-                line: 1, column: 11
+                line: 1, column: 11,
+                span: Span::default(),
             })
         ],
-        line: 1, column: 9
+        line: 1, column: 9,
+        span: Span::default(),
     };
 
     // Create increment statement: i = i + 1
@@ -227,22 +257,27 @@ fn test_while_loop_with_break() {
         targets: vec![Box::new(Expr::Name {
             id: i_name.clone(),
             ctx: ExprContext::Store,
-            line: 2, column: 4
+            line: 2, column: 4,
+            span: Span::default(),
         })],
         value: Box::new(Expr::BinOp {
             left: Box::new(Expr::Name {
                 id: i_name.clone(),
                 ctx: ExprContext::Load,
-                line: 2, column: 8
+                line: 2, column: 8,
+                span: Span::default(),
             }),
             op: Operator::Add,
             right: Box::new(Expr::Num {
                 value: Number::Integer(1),
-                line: 2, column: 12
+                line: 2, column: 12,
+                span: Span::default(),
             }),
-            line: 2, column: 10
+            line: 2, column: 10,
+            span: Span::default(),
         }),
-        line: 2, column: 6
+        line: 2, column: 6,
+        span: Span::default(),
     };
 
     // Create the while loop without a break statement
@@ -252,7 +287,8 @@ fn test_while_loop_with_break() {
             Box::new(increment)
         ],
         orelse: vec![],
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
 
     // Compile the while loop
@@ -276,35 +312,40 @@ fn test_multiple_assignments() {
             Box::new(Expr::Name {
                 id: x_name.clone(),
                 ctx: ExprContext::Store,
-                line: 1, column: 1
+                line: 1, column: 1,
+                span: Span::default(),
             }),
             Box::new(Expr::Name {
                 id: y_name.clone(),
                 ctx: ExprContext::Store,
-                line: 1, column: 5
+                line: 1, column: 5,
+                span: Span::default(),
             }),
             Box::new(Expr::Name {
                 id: z_name.clone(),
                 ctx: ExprContext::Store,
-                line: 1, column: 9
+                line: 1, column: 9,
+                span: Span::default(),
             })
         ],
         value: Box::new(Expr::Num {
             value: Number::Integer(42),
-            line: 1, column: 13
+            line: 1, column: 13,
+            span: Span::default(),
         }),
-        line: 1, column: 3
+        line: 1, column: 3,
+        span: Span::default(),
     };
 
     // Compile the multi-target assignment
     assert!(ctx.compile_stmt(&assign).is_ok());
 
     // Verify all variables have the correct type
-    assert!(ctx.type_env.contains_key(&x_name));
-    assert!(ctx.type_env.contains_key(&y_name));
-    assert!(ctx.type_env.contains_key(&z_name));
+    assert!(ctx.type_env.contains_key(&Symbol::intern(&x_name)));
+    assert!(ctx.type_env.contains_key(&Symbol::intern(&y_name)));
+    assert!(ctx.type_env.contains_key(&Symbol::intern(&z_name)));
 
-    assert!(matches!(ctx.type_env.get(&x_name).unwrap(), Type::Int));
-    assert!(matches!(ctx.type_env.get(&y_name).unwrap(), Type::Int));
-    assert!(matches!(ctx.type_env.get(&z_name).unwrap(), Type::Int));
+    assert!(matches!(ctx.type_env.get(&Symbol::intern(&x_name)).unwrap(), Type::Int));
+    assert!(matches!(ctx.type_env.get(&Symbol::intern(&y_name)).unwrap(), Type::Int));
+    assert!(matches!(ctx.type_env.get(&Symbol::intern(&z_name)).unwrap(), Type::Int));
 }
\ No newline at end of file
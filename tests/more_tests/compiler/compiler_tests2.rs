@@ -1,4 +1,4 @@
-use cheetah::ast::{Expr, Number, NameConstant, Operator, UnaryOperator, ExprContext};
+use cheetah::ast::{Expr, Number, NameConstant, Operator, UnaryOperator, ExprContext, Span};
 use cheetah::compiler::context::CompilationContext;
 use cheetah::compiler::expr::ExprCompiler;
 use cheetah::compiler::types::Type;
@@ -25,7 +25,8 @@ fn test_number_compilation() {
     // Test integer literal
     let integer_expr = Expr::Num {
         value: Number::Integer(42),
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
     let (int_val, int_type) = ctx.compile_expr(&integer_expr).unwrap();
     assert!(matches!(int_type, Type::Int));
@@ -35,7 +36,8 @@ fn test_number_compilation() {
     // Test float literal
     let float_expr = Expr::Num {
         value: Number::Float(3.14),
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
     let (float_val, float_type) = ctx.compile_expr(&float_expr).unwrap();
     assert!(matches!(float_type, Type::Float));
@@ -51,7 +53,8 @@ fn test_constant_compilation() {
     // Test True constant
     let true_expr = Expr::NameConstant {
         value: NameConstant::True,
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
     let (true_val, true_type) = ctx.compile_expr(&true_expr).unwrap();
     assert!(matches!(true_type, Type::Bool));
@@ -61,7 +64,8 @@ fn test_constant_compilation() {
     // Test False constant
     let false_expr = Expr::NameConstant {
         value: NameConstant::False,
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
     let (false_val, false_type) = ctx.compile_expr(&false_expr).unwrap();
     assert!(matches!(false_type, Type::Bool));
@@ -71,7 +75,8 @@ fn test_constant_compilation() {
     // Test None constant
     let none_expr = Expr::NameConstant {
         value: NameConstant::None,
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
     let (none_val, none_type) = ctx.compile_expr(&none_expr).unwrap();
     assert!(matches!(none_type, Type::None));
@@ -88,12 +93,14 @@ fn test_unary_operations() {
     // Test logical not
     let true_expr = Expr::NameConstant {
         value: NameConstant::True,
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
     let not_expr = Expr::UnaryOp {
         op: UnaryOperator::Not,
         operand: Box::new(true_expr),
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
     let (not_val, not_type) = ctx.compile_expr(&not_expr).unwrap();
     assert!(matches!(not_type, Type::Bool));
@@ -102,12 +109,14 @@ fn test_unary_operations() {
     // Test numeric negation
     let int_expr = Expr::Num {
         value: Number::Integer(42),
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
     let neg_expr = Expr::UnaryOp {
         op: UnaryOperator::USub,
         operand: Box::new(int_expr),
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
     let (neg_val, neg_type) = ctx.compile_expr(&neg_expr).unwrap();
     assert!(matches!(neg_type, Type::Int));
@@ -123,11 +132,13 @@ fn test_binary_operations() {
     // Create two integer literals
     let int_expr1 = Expr::Num {
         value: Number::Integer(40),
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
     let int_expr2 = Expr::Num {
         value: Number::Integer(2),
-        line: 1, column: 5
+        line: 1, column: 5,
+        span: Span::default(),
     };
     
     // Test addition
@@ -135,7 +146,8 @@ fn test_binary_operations() {
         left: Box::new(int_expr1.clone()),
         op: Operator::Add,
         right: Box::new(int_expr2.clone()),
-        line: 1, column: 3
+        line: 1, column: 3,
+        span: Span::default(),
     };
     
     let (add_val, add_type) = ctx.compile_expr(&add_expr).unwrap();
@@ -147,7 +159,8 @@ fn test_binary_operations() {
         left: Box::new(int_expr1.clone()),
         op: Operator::Mult,
         right: Box::new(int_expr2.clone()),
-        line: 1, column: 3
+        line: 1, column: 3,
+        span: Span::default(),
     };
     
     let (mul_val, mul_type) = ctx.compile_expr(&mul_expr).unwrap();
@@ -157,14 +170,16 @@ fn test_binary_operations() {
     // Test mixed types (int + float)
     let float_expr = Expr::Num {
         value: Number::Float(3.5),
-        line: 1, column: 5
+        line: 1, column: 5,
+        span: Span::default(),
     };
     
     let mixed_expr = Expr::BinOp {
         left: Box::new(int_expr1.clone()),
         op: Operator::Add,
         right: Box::new(float_expr),
-        line: 1, column: 3
+        line: 1, column: 3,
+        span: Span::default(),
     };
     
     let (mixed_val, mixed_type) = ctx.compile_expr(&mixed_expr).unwrap();
@@ -181,7 +196,8 @@ fn test_variable_references() {
     // Create an integer literal
     let int_expr = Expr::Num {
         value: Number::Integer(42),
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
     
     // Compile the integer literal
@@ -198,7 +214,8 @@ fn test_variable_references() {
     let var_expr = Expr::Name {
         id: var_name.clone(),
         ctx: ExprContext::Load,
-        line: 2, column: 1
+        line: 2, column: 1,
+        span: Span::default(),
     };
     
     // Compile the variable reference
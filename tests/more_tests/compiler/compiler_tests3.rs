@@ -1,8 +1,9 @@
-use cheetah::ast::{Stmt, Expr, Number, ExprContext};
+use cheetah::ast::{Stmt, Expr, Number, ExprContext, Span};
 use cheetah::compiler::context::CompilationContext;
 use cheetah::compiler::stmt::StmtCompiler;
 use cheetah::compiler::expr::ExprCompiler;
 use cheetah::compiler::types::Type;
+use cheetah::intern::Symbol;
 use inkwell::context::Context;
 
 fn setup_context<'ctx>(context: &'ctx Context) -> CompilationContext<'ctx> {
@@ -26,12 +27,14 @@ fn test_expression_statement() {
     // Create a simple expression statement (just an integer literal)
     let int_expr = Expr::Num {
         value: Number::Integer(42),
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
     
     let expr_stmt = Stmt::Expr {
         value: Box::new(int_expr),
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
     
     // This should compile without error
@@ -47,7 +50,8 @@ fn test_assignment_statement() {
     // Create an integer literal
     let int_expr = Expr::Num {
         value: Number::Integer(42),
-        line: 1, column: 5
+        line: 1, column: 5,
+        span: Span::default(),
     };
     
     // First, allocate storage for the variable
@@ -58,22 +62,24 @@ fn test_assignment_statement() {
     let target_expr = Expr::Name {
         id: var_name,
         ctx: ExprContext::Store,
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
     
     // Create an assignment statement: x = 42
     let assign_stmt = Stmt::Assign {
         targets: vec![Box::new(target_expr)],
         value: Box::new(int_expr),
-        line: 1, column: 3
+        line: 1, column: 3,
+        span: Span::default(),
     };
     
     // This should compile without error
     assert!(ctx.compile_stmt(&assign_stmt).is_ok());
     
     // Verify the variable was created with the correct type
-    assert!(ctx.type_env.contains_key("x"));
-    assert!(matches!(ctx.type_env.get("x").unwrap(), Type::Int));
+    assert!(ctx.type_env.contains_key(&Symbol::intern("x")));
+    assert!(matches!(ctx.type_env.get(&Symbol::intern("x")).unwrap(), Type::Int));
 }
 
 #[test]
@@ -85,7 +91,8 @@ fn test_variable_declaration() {
     // Create an integer value
     let int_expr = Expr::Num {
         value: Number::Integer(42),
-        line: 1, column: 5
+        line: 1, column: 5,
+        span: Span::default(),
     };
     
     // Compile the integer expression
@@ -101,6 +108,6 @@ fn test_variable_declaration() {
     assert!(result.is_ok());
     
     // Verify the variable was created
-    assert!(ctx.type_env.contains_key("test_var"));
-    assert!(ctx.variables.contains_key("test_var"));
+    assert!(ctx.type_env.contains_key(&Symbol::intern("test_var")));
+    assert!(ctx.variables.contains_key(&Symbol::intern("test_var")));
 }
\ No newline at end of file
@@ -183,4 +183,30 @@ fn test_type_indexability_and_callability() {
     let bool_type = Type::Bool;
     let result = bool_type.get_indexed_type(&Type::Int);
     assert!(result.is_err());
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_numeric_promotion_lattice() {
+    // Bool < Int < Float
+    assert_eq!(Type::promote_numeric(&Type::Bool, &Type::Int), Some(Type::Int));
+    assert_eq!(Type::promote_numeric(&Type::Int, &Type::Float), Some(Type::Float));
+    assert_eq!(Type::promote_numeric(&Type::Float, &Type::Bool), Some(Type::Float));
+    assert_eq!(Type::promote_numeric(&Type::Bool, &Type::Bool), Some(Type::Bool));
+    assert_eq!(Type::promote_numeric(&Type::Int, &Type::String), None);
+
+    // Arithmetic never produces a bool
+    assert_eq!(Type::promote_arithmetic(&Type::Bool, &Type::Bool), Some(Type::Int));
+    assert_eq!(Type::promote_arithmetic(&Type::Bool, &Type::Float), Some(Type::Float));
+
+    // Unification and binary op lowering agree with the lattice
+    assert_eq!(Type::unify(&Type::Bool, &Type::Float), Some(Type::Float));
+    assert_eq!(
+        binary_op_lowering(&cheetah::ast::Operator::Add, &Type::Bool, &Type::Int),
+        Some(BinaryOpLowering::Numeric(Type::Int))
+    );
+
+    assert!(Type::is_lossy_conversion(&Type::Float, &Type::Int));
+    assert!(Type::is_lossy_conversion(&Type::Int, &Type::Bool));
+    assert!(!Type::is_lossy_conversion(&Type::Bool, &Type::Float));
+    assert!(!Type::is_lossy_conversion(&Type::String, &Type::Int));
+}
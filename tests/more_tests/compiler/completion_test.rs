@@ -0,0 +1,146 @@
+// completion_test.rs - Tests for REPL tab completion
+
+use cheetah::completion::{complete, Completion, CompletionKind, NameCompleter, INDENT};
+use cheetah::symtable::Position;
+use cheetah::typechecker::stubs::ModuleStub;
+
+#[test]
+fn test_completes_keywords_and_builtins() {
+    let completer = NameCompleter::new();
+    assert_eq!(completer.complete("whi", 3), (0, vec!["while".to_string()]));
+
+    let (start, candidates) = completer.complete("x = le", 6);
+    assert_eq!(start, 4);
+    assert_eq!(candidates, vec!["len".to_string()]);
+
+    let (_, candidates) = completer.complete("print(m", 7);
+    assert!(candidates.contains(&"max".to_string()));
+    assert!(candidates.contains(&"min".to_string()));
+    assert!(candidates.contains(&"match".to_string()));
+}
+
+#[test]
+fn test_completes_defined_names() {
+    let mut completer = NameCompleter::new();
+    assert!(completer.complete("tot", 3).1.is_empty());
+
+    let module = cheetah::parse("total = 0\ndef tote(x: int) -> int:\n    inner = x\n    return inner\n").unwrap();
+    completer.add_module(&module);
+    assert_eq!(
+        completer.complete("tot", 3).1,
+        vec!["total".to_string(), "tote".to_string()]
+    );
+    // Locals of a function aren't offered at the top level
+    assert!(completer.complete("inn", 3).1.is_empty());
+}
+
+#[test]
+fn test_completion_edges() {
+    let completer = NameCompleter::new();
+    assert_eq!(completer.complete("    ", 4), (4, vec![INDENT.to_string()]));
+    assert!(completer.complete("x = ", 4).1.is_empty());
+    assert!(completer.complete("xs.le", 5).1.is_empty());
+    assert_eq!(completer.complete("ran(1)", 3).1, vec!["range".to_string()]);
+}
+
+#[test]
+fn test_completes_module_members_from_stubs() {
+    let mut completer = NameCompleter::new();
+    assert_eq!(
+        completer.complete("p = os.te", 9),
+        (7, vec!["tempdir".to_string(), "tempfile".to_string()])
+    );
+    assert_eq!(completer.complete("sys.", 4).1, vec!["exc_info".to_string()]);
+    assert!(completer.complete("a.os.te", 7).1.is_empty());
+    assert!(completer.complete("libm.c", 6).1.is_empty());
+
+    let stub = ModuleStub::parse("libm", "def cos(x: float) -> float: ...\n").unwrap();
+    completer.add_stub(&stub);
+    assert_eq!(completer.complete("libm.c", 6).1, vec!["cos".to_string()]);
+}
+
+fn labels(items: &[Completion]) -> Vec<&str> {
+    items.iter().map(|item| item.label.as_str()).collect()
+}
+
+#[test]
+fn test_complete_in_scope_with_kinds() {
+    let source = "\
+limit = 10
+def scale(value: int) -> int:
+    factor = 2
+    return va
+";
+    let module = cheetah::parse(&source.replace("return va", "return value")).unwrap();
+    let (start, items) = complete(&module, source, Position::new(4, 14));
+    assert_eq!(start, Position::new(4, 12));
+    assert_eq!(
+        items,
+        vec![Completion {
+            label: "value".to_string(),
+            kind: CompletionKind::Parameter
+        }]
+    );
+
+    let (_, items) = complete(&module, "fa", Position::new(1, 3));
+    assert!(items.is_empty(), "locals aren't visible outside their function");
+    let (_, items) = complete(&module, &source.replace("return va", "return fa"), Position::new(4, 14));
+    assert_eq!(labels(&items), ["factor"]);
+
+    let (_, items) = complete(&module, "l", Position::new(1, 2));
+    let kinds: Vec<_> = items.iter().map(|item| (item.label.as_str(), item.kind)).collect();
+    assert!(kinds.contains(&("limit", CompletionKind::Variable)));
+    assert!(kinds.contains(&("len", CompletionKind::Builtin)));
+    assert!(kinds.contains(&("lambda", CompletionKind::Keyword)));
+}
+
+#[test]
+fn test_complete_members_of_known_types() {
+    let source = "\
+class Point:
+    def __init__(self, x: int):
+        self.x = x
+    def norm(self) -> int:
+        return self.x
+
+name = \"cheetah\"
+items = [1, 2]
+ages = {\"a\": 1}
+p = Point(1)
+";
+    let module = cheetah::parse(source).unwrap();
+    let at_end = |text: &str| {
+        let line = format!("{}{}", source, text);
+        let position = Position::new(line.lines().count(), text.chars().count() + 1);
+        complete(&module, &line, position)
+    };
+
+    let (start, items) = at_end("name.st");
+    assert_eq!(start.column, 6);
+    assert_eq!(labels(&items), ["startswith", "strip"]);
+    assert!(items.iter().all(|item| item.kind == CompletionKind::Method));
+    assert_eq!(labels(&at_end("items.ap").1), ["append"]);
+    assert_eq!(labels(&at_end("ages.k").1), ["keys"]);
+    let (_, items) = at_end("Point.n");
+    assert_eq!(items[0].kind, CompletionKind::Method);
+    assert_eq!(labels(&items), ["norm"]);
+    assert_eq!(labels(&at_end("os.temp").1), ["tempdir", "tempfile"]);
+    assert!(at_end("missing.a").1.is_empty());
+}
+
+#[test]
+fn test_repl_completes_members_of_session_values() {
+    let mut completer = NameCompleter::new();
+    completer.add_module(&cheetah::parse("words = [\"a\"]\n").unwrap());
+    assert_eq!(completer.complete("words.ex", 8), (6, vec!["extend".to_string()]));
+}
+
+#[test]
+fn test_builtin_methods_are_members() {
+    use cheetah::compiler::types::Type;
+    for ty in [Type::String, Type::list(Type::Int), Type::dict(Type::String, Type::Int)] {
+        for method in ty.builtin_methods() {
+            assert!(ty.get_member_type(method).is_ok(), "{} has no {}", ty, method);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use cheetah::{ast::{Expr, ExprContext, NameConstant, Number, Operator}, compiler::{context::CompilationContext, expr::ExprCompiler, types::Type, Compiler}, parse};
+use cheetah::{ast::{Expr, ExprContext, NameConstant, Number, Operator, Span}, compiler::{context::CompilationContext, expr::ExprCompiler, types::Type, Compiler}, parse};
 use inkwell::context::Context;
 
 fn setup_context<'ctx>(context: &'ctx Context) -> CompilationContext<'ctx> {
@@ -44,7 +44,8 @@ fn test_type_conversion_edge_cases() {
     // Test edge cases like maximum integer values
     let max_int_expr = Expr::Num {
         value: Number::Integer(i64::MAX),
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
 
     // Compile the expression and verify the type
@@ -58,7 +59,8 @@ fn test_type_conversion_edge_cases() {
     // Test boolean to string conversion
     let bool_expr = Expr::NameConstant {
         value: NameConstant::True,
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
 
     let (bool_val, bool_type) = ctx.compile_expr(&bool_expr).unwrap();
@@ -92,27 +94,27 @@ fn test_complex_expression_nesting() {
 
     // Create a complex expression: ((a + b) * (c - d)) / ((e * f) + (g / h))
     // First level: variables
-    let var_a = Box::new(Expr::Name { id: "a".to_string(), ctx: ExprContext::Load, line: 1, column: 1 });
-    let var_b = Box::new(Expr::Name { id: "b".to_string(), ctx: ExprContext::Load, line: 1, column: 5 });
-    let var_c = Box::new(Expr::Name { id: "c".to_string(), ctx: ExprContext::Load, line: 1, column: 10 });
-    let var_d = Box::new(Expr::Name { id: "d".to_string(), ctx: ExprContext::Load, line: 1, column: 14 });
-    let var_e = Box::new(Expr::Name { id: "e".to_string(), ctx: ExprContext::Load, line: 1, column: 19 });
-    let var_f = Box::new(Expr::Name { id: "f".to_string(), ctx: ExprContext::Load, line: 1, column: 23 });
-    let var_g = Box::new(Expr::Name { id: "g".to_string(), ctx: ExprContext::Load, line: 1, column: 28 });
-    let var_h = Box::new(Expr::Name { id: "h".to_string(), ctx: ExprContext::Load, line: 1, column: 32 });
+    let var_a = Box::new(Expr::Name { id: "a".to_string(), ctx: ExprContext::Load, line: 1, column: 1, span: Span::default() });
+    let var_b = Box::new(Expr::Name { id: "b".to_string(), ctx: ExprContext::Load, line: 1, column: 5, span: Span::default() });
+    let var_c = Box::new(Expr::Name { id: "c".to_string(), ctx: ExprContext::Load, line: 1, column: 10, span: Span::default() });
+    let var_d = Box::new(Expr::Name { id: "d".to_string(), ctx: ExprContext::Load, line: 1, column: 14, span: Span::default() });
+    let var_e = Box::new(Expr::Name { id: "e".to_string(), ctx: ExprContext::Load, line: 1, column: 19, span: Span::default() });
+    let var_f = Box::new(Expr::Name { id: "f".to_string(), ctx: ExprContext::Load, line: 1, column: 23, span: Span::default() });
+    let var_g = Box::new(Expr::Name { id: "g".to_string(), ctx: ExprContext::Load, line: 1, column: 28, span: Span::default() });
+    let var_h = Box::new(Expr::Name { id: "h".to_string(), ctx: ExprContext::Load, line: 1, column: 32, span: Span::default() });
 
     // Second level: basic operations
-    let add_ab = Box::new(Expr::BinOp { left: var_a, op: Operator::Add, right: var_b, line: 1, column: 3 });
-    let sub_cd = Box::new(Expr::BinOp { left: var_c, op: Operator::Sub, right: var_d, line: 1, column: 12 });
-    let mul_ef = Box::new(Expr::BinOp { left: var_e, op: Operator::Mult, right: var_f, line: 1, column: 21 });
-    let div_gh = Box::new(Expr::BinOp { left: var_g, op: Operator::Div, right: var_h, line: 1, column: 30 });
+    let add_ab = Box::new(Expr::BinOp { left: var_a, op: Operator::Add, right: var_b, line: 1, column: 3, span: Span::default() });
+    let sub_cd = Box::new(Expr::BinOp { left: var_c, op: Operator::Sub, right: var_d, line: 1, column: 12, span: Span::default() });
+    let mul_ef = Box::new(Expr::BinOp { left: var_e, op: Operator::Mult, right: var_f, line: 1, column: 21, span: Span::default() });
+    let div_gh = Box::new(Expr::BinOp { left: var_g, op: Operator::Div, right: var_h, line: 1, column: 30, span: Span::default() });
 
     // Third level: middle operations
-    let mul_ab_cd = Box::new(Expr::BinOp { left: add_ab, op: Operator::Mult, right: sub_cd, line: 1, column: 8 });
-    let add_ef_gh = Box::new(Expr::BinOp { left: mul_ef, op: Operator::Add, right: div_gh, line: 1, column: 26 });
+    let mul_ab_cd = Box::new(Expr::BinOp { left: add_ab, op: Operator::Mult, right: sub_cd, line: 1, column: 8, span: Span::default() });
+    let add_ef_gh = Box::new(Expr::BinOp { left: mul_ef, op: Operator::Add, right: div_gh, line: 1, column: 26, span: Span::default() });
 
     // Top level: final division
-    let final_expr = Expr::BinOp { left: mul_ab_cd, op: Operator::Div, right: add_ef_gh, line: 1, column: 17 };
+    let final_expr = Expr::BinOp { left: mul_ab_cd, op: Operator::Div, right: add_ef_gh, line: 1, column: 17, span: Span::default() };
 
     // Allocate variables with integer values
     for (name, value) in [("a", 1), ("b", 2), ("c", 3), ("d", 4), ("e", 5), ("f", 6), ("g", 7), ("h", 8)] {
@@ -133,7 +135,8 @@ fn test_string_operations() {
     // Test string creation
     let str_expr = Expr::Str {
         value: "Hello, world!".to_string(),
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
 
     let (str_val, str_type) = ctx.compile_expr(&str_expr).unwrap();
@@ -143,7 +146,8 @@ fn test_string_operations() {
     // Create another string for concatenation test
     let str_expr2 = Expr::Str {
         value: " How are you?".to_string(),
-        line: 1, column: 20
+        line: 1, column: 20,
+        span: Span::default(),
     };
 
     // Test string concatenation if supported
@@ -151,7 +155,8 @@ fn test_string_operations() {
         left: Box::new(str_expr),
         op: Operator::Add,
         right: Box::new(str_expr2),
-        line: 1, column: 18
+        line: 1, column: 18,
+        span: Span::default(),
     };
 
     // This may fail if string concatenation isn't implemented yet
@@ -213,7 +218,8 @@ fn test_undefined_variable() {
     let undefined_var = Expr::Name {
         id: "undefined".to_string(),
         ctx: ExprContext::Load,
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
 
     // This should return an error
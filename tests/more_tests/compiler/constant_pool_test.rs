@@ -0,0 +1,78 @@
+// constant_pool_test.rs - Tests for sharing constant globals across a module
+
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use inkwell::context::Context;
+
+pub fn compile_source(source: &str) -> Result<String, String> {
+    // Parse the source
+    let ast = match parse(source) {
+        Ok(ast) => ast,
+        Err(errors) => {
+            return Err(format!("Parse errors: {:?}", errors));
+        }
+    };
+
+    // Create a compiler
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "constant_pool_test");
+
+    // Compile the AST
+    match compiler.compile_module(&ast) {
+        Ok(_) => Ok(compiler.get_ir()),
+        Err(e) => Err(format!("Compilation error: {}", e)),
+    }
+}
+
+/// Number of globals in the IR initialized with the C string `text`
+fn globals_holding(ir: &str, text: &str) -> usize {
+    let initializer = format!("c\"{}\\00\"", text);
+    ir.lines()
+        .filter(|line| line.starts_with('@') && line.contains(&initializer))
+        .count()
+}
+
+#[test]
+fn test_repeated_literals_share_a_global() {
+    let source = r#"
+a = "shared literal"
+b = "shared literal"
+
+def f() -> str:
+    return "shared literal"
+
+c = "other literal"
+"#;
+
+    let ir = compile_source(source).expect("source should compile");
+    assert_eq!(globals_holding(&ir, "shared literal"), 1);
+    assert_eq!(globals_holding(&ir, "other literal"), 1);
+}
+
+#[test]
+fn test_fstrings_share_the_empty_string() {
+    let source = r#"
+x = 1
+a = f"{x}"
+b = f"{x}!"
+c = f"!{x}"
+"#;
+
+    let ir = compile_source(source).expect("source should compile");
+    assert_eq!(globals_holding(&ir, ""), 1);
+    assert_eq!(globals_holding(&ir, "!"), 1);
+}
+
+#[test]
+fn test_print_constants_are_pooled() {
+    let source = r#"
+print([1, 2], [3])
+print(True, False, True)
+print("done")
+"#;
+
+    let ir = compile_source(source).expect("source should compile");
+    assert_eq!(globals_holding(&ir, "["), 1);
+    assert_eq!(globals_holding(&ir, "True"), 1);
+    assert_eq!(globals_holding(&ir, "\\0A"), 1);
+}
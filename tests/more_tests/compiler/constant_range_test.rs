@@ -0,0 +1,101 @@
+// constant_range_test.rs - Tests for folding constant range() bounds into counted loops
+
+use cheetah::ast::{Expr, Stmt};
+use cheetah::compiler::loop_transformers::{module_int_constants, ConstantRange};
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use inkwell::context::Context;
+
+/// Fold the range() iterated by the first top-level for loop in `source`
+fn fold_first_range(source: &str) -> Option<ConstantRange> {
+    let module = parse(source).expect("source should parse");
+    let constants = module_int_constants(&module);
+    module.body.iter().find_map(|stmt| match stmt.as_ref() {
+        Stmt::For { iter, .. } => match iter.as_ref() {
+            Expr::Call { args, .. } => ConstantRange::fold(args, &|name| constants.get(name).copied()),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+#[test]
+fn test_trip_counts() {
+    let range = |start, stop, step| ConstantRange { start, stop, step };
+    assert_eq!(range(0, 10, 1).trip_count(), 10);
+    assert_eq!(range(0, 10, 3).trip_count(), 4);
+    assert_eq!(range(10, 0, -3).trip_count(), 4);
+    assert_eq!(range(5, 5, 1).trip_count(), 0);
+    assert_eq!(range(5, 0, 1).trip_count(), 0);
+    assert_eq!(range(i64::MIN, i64::MAX, 1).trip_count(), u64::MAX);
+
+    assert_eq!(range(0, 10, 3).last(), Some(9));
+    assert_eq!(range(10, 0, -3).last(), Some(1));
+    assert_eq!(range(0, 0, 1).last(), None);
+}
+
+#[test]
+fn test_fold_literal_and_constant_bounds() {
+    assert_eq!(
+        fold_first_range("for i in range(10):\n    pass\n"),
+        Some(ConstantRange { start: 0, stop: 10, step: 1 })
+    );
+    assert_eq!(
+        fold_first_range("N = 8\nM = N * 2 + 1\nfor i in range(N // 2, M, -1 + 3):\n    pass\n"),
+        Some(ConstantRange { start: 4, stop: 17, step: 2 })
+    );
+}
+
+#[test]
+fn test_no_fold_for_non_constant_bounds() {
+    // Zero step raises at runtime
+    assert_eq!(fold_first_range("for i in range(0, 10, 0):\n    pass\n"), None);
+    // Rebound names are not constants
+    assert_eq!(fold_first_range("N = 8\nN = 9\nfor i in range(N):\n    pass\n"), None);
+    assert_eq!(fold_first_range("N = 8\nN += 1\nfor i in range(N):\n    pass\n"), None);
+    assert_eq!(
+        fold_first_range("N = 8\ndef grow():\n    global N\n    N = 16\nfor i in range(N):\n    pass\n"),
+        None
+    );
+    // Overflowing arithmetic is left to the runtime
+    assert_eq!(
+        fold_first_range("for i in range(9223372036854775807 + 1):\n    pass\n"),
+        None
+    );
+}
+
+#[test]
+fn test_module_constants_exclude_shadowed_names() {
+    let module = parse(
+        "N = 8\nLIMIT = 100\nSTEP = 2\ndef f(LIMIT):\n    return LIMIT\nfor STEP in range(3):\n    pass\n",
+    )
+    .expect("source should parse");
+    let constants = module_int_constants(&module);
+    assert_eq!(constants.get("N"), Some(&8));
+    assert_eq!(constants.get("LIMIT"), None);
+    assert_eq!(constants.get("STEP"), None);
+}
+
+#[test]
+fn test_constant_range_compiles_to_counted_loop() {
+    let source = r#"
+N = 1000
+
+def total() -> int:
+    s = 0
+    for i in range(N):
+        s = s + i
+    return s
+
+print(total())
+"#;
+    let ast = parse(source).expect("source should parse");
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "constant_range_test");
+    compiler.compile_module(&ast).expect("source should compile");
+
+    let ir = compiler.get_ir();
+    assert!(ir.contains("counted.cond"));
+    assert!(ir.contains("icmp ult i64 %counter, 1000"));
+    assert!(!ir.contains("range.cond"));
+}
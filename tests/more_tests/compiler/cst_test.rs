@@ -0,0 +1,128 @@
+// cst_test.rs - Tests for lossless concrete syntax trees
+
+use cheetah::cst::{self, SyntaxElement, SyntaxKind, SyntaxNode};
+use cheetah::lexer::TokenType;
+
+const SOURCE: &str = "\
+# Header comment
+
+import os  # why not
+
+
+@decorator(
+    arg,  # explained
+)
+def area(w,  h):
+    '''Docstring'''
+    if w > 0 :
+        return w*h  # product
+    else:
+        return   0.50
+
+
+class Shape: pass
+for i in range(3): print(i)
+try:
+    x = [1,
+         2]
+except ValueError:
+    pass
+finally:
+    pass
+y = {'a': (1, 2)}  # last
+";
+
+fn kinds(nodes: &[&SyntaxNode]) -> Vec<SyntaxKind> {
+    nodes.iter().map(|node| node.kind).collect()
+}
+
+#[test]
+fn test_round_trip_is_byte_for_byte() {
+    let sources = [
+        "",
+        "x = 1\n\n# trailing comment",
+        "x = 1",
+        "x = 1   \n\n\n",
+        "if x:\r\n    pass\r\nelse:\r\n    pass\r\n",
+        "def f():\n    if x:\n        while y:\n            pass\n\n\n",
+        "s = f\"{a!r:>{width}}\" + r'\\d' + \"\"\"multi\n  line\"\"\"\n",
+        "total = 1 + \\\n    2\n",
+        SOURCE,
+    ];
+    for source in sources {
+        let tree = cst::parse(source).unwrap_or_else(|e| panic!("{:?} should parse: {:?}", source, e));
+        assert_eq!(tree.text(), source);
+    }
+}
+
+#[test]
+fn test_statements_and_blocks() {
+    let tree = cst::parse(SOURCE).unwrap();
+    assert_eq!(tree.kind, SyntaxKind::Module);
+
+    let statements = tree.statements();
+    use SyntaxKind::*;
+    assert_eq!(
+        kinds(&statements),
+        [SimpleStatement, CompoundStatement, CompoundStatement, CompoundStatement, CompoundStatement, SimpleStatement]
+    );
+    // Comment and blank lines between statements stay with the module
+    assert_eq!(statements[0].text(), "\nimport os  # why not\n");
+    assert!(tree.text().starts_with("# Header comment\n\nimport"));
+
+    // Decorators belong to the definition, and the else clause to its if
+    let function = statements[1];
+    assert!(function.text().starts_with("\n\n@decorator("));
+    assert_eq!(function.line(), 6);
+    let body = function.statements();
+    assert_eq!(kinds(&body), [SimpleStatement, CompoundStatement]);
+    assert!(body[1].text().contains("# product\n    else:"));
+    assert_eq!(body[1].statements().len(), 2);
+
+    // One-line compound statements have no block
+    assert_eq!(statements[2].text(), "\n\nclass Shape: pass\n");
+    assert!(statements[2].statements().is_empty());
+
+    let try_statement = statements[4];
+    assert_eq!(try_statement.nodes().filter(|node| node.kind == Block).count(), 3);
+    assert_eq!(statements[5].text(), "y = {'a': (1, 2)}  # last\n");
+}
+
+#[test]
+fn test_tokens_keep_their_original_text() {
+    let tree = cst::parse(SOURCE).unwrap();
+    let tokens = tree.tokens();
+    let number = tokens.iter().find(|token| matches!(token.token_type, TokenType::FloatLiteral(_))).unwrap();
+    assert_eq!(number.source.as_ref().unwrap().text, "0.50");
+
+    let docstring = tokens
+        .iter()
+        .find(|token| matches!(token.token_type, TokenType::StringLiteral(_)))
+        .unwrap();
+    assert_eq!(docstring.source.as_ref().unwrap().text, "'''Docstring'''");
+
+    assert!(matches!(tree.children.last(), Some(SyntaxElement::Token(token)) if token.token_type == TokenType::EOF));
+}
+
+#[test]
+fn test_brackets_are_grouped() {
+    let tree = cst::parse("x = foo(a, [b, {c: (d)}])\n").unwrap();
+    let statement = tree.statements()[0];
+    let call = statement.nodes().next().unwrap();
+    assert_eq!(call.kind, SyntaxKind::Group);
+    assert_eq!(call.text(), "(a, [b, {c: (d)}])");
+
+    let mut depth = 0;
+    let mut group = call;
+    while let Some(nested) = group.nodes().next() {
+        depth += 1;
+        group = nested;
+    }
+    assert_eq!((depth, group.text().as_str()), (3, " (d)"));
+}
+
+#[test]
+fn test_invalid_source_reports_parse_errors() {
+    assert!(cst::parse("def f(:\n    pass\n").is_err());
+    assert!(cst::parse("x = 'unterminated\n").is_err());
+}
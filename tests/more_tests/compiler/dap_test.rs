@@ -0,0 +1,67 @@
+// dap_test.rs - Tests for the Debug Adapter Protocol bridge
+
+use cheetah::dap::{
+    error_response, is_launch_request, read_message, set_launch_program, write_message,
+};
+use serde_json::json;
+use std::io::Cursor;
+use std::path::Path;
+
+#[test]
+fn test_message_round_trip() {
+    let first = json!({"seq": 1, "type": "request", "command": "initialize"});
+    let second = json!({"seq": 2, "type": "request", "command": "threads"});
+
+    let mut buffer = Vec::new();
+    write_message(&mut buffer, &first).unwrap();
+    write_message(&mut buffer, &second).unwrap();
+    assert!(buffer.starts_with(b"Content-Length: "));
+
+    let mut reader = Cursor::new(buffer);
+    assert_eq!(read_message(&mut reader).unwrap(), Some(first));
+    assert_eq!(read_message(&mut reader).unwrap(), Some(second));
+    assert_eq!(read_message(&mut reader).unwrap(), None);
+}
+
+#[test]
+fn test_read_message_ignores_other_headers() {
+    let body = r#"{"seq":3}"#;
+    let framed = format!(
+        "Content-Type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    let message = read_message(&mut Cursor::new(framed)).unwrap();
+    assert_eq!(message, Some(json!({"seq": 3})));
+}
+
+#[test]
+fn test_launch_program_is_replaced() {
+    let mut launch = json!({
+        "seq": 4,
+        "type": "request",
+        "command": "launch",
+        "arguments": {"program": "hello.ch", "stopOnEntry": true}
+    });
+    assert!(is_launch_request(&launch));
+    set_launch_program(&mut launch, Path::new("/tmp/.cheetah_build/hello"));
+    assert_eq!(launch["arguments"]["program"], "/tmp/.cheetah_build/hello");
+    assert_eq!(launch["arguments"]["stopOnEntry"], true);
+
+    let mut bare = json!({"seq": 5, "type": "request", "command": "launch"});
+    set_launch_program(&mut bare, Path::new("hello"));
+    assert_eq!(bare["arguments"]["program"], "hello");
+
+    assert!(!is_launch_request(&json!({"type": "request", "command": "attach"})));
+}
+
+#[test]
+fn test_error_response() {
+    let request = json!({"seq": 7, "type": "request", "command": "launch"});
+    let response = error_response(&request, "Build failed");
+    assert_eq!(response["type"], "response");
+    assert_eq!(response["request_seq"], 7);
+    assert_eq!(response["command"], "launch");
+    assert_eq!(response["success"], false);
+    assert_eq!(response["message"], "Build failed");
+}
@@ -0,0 +1,49 @@
+// debug_info_test.rs - Tests for DWARF debug info emission
+
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use inkwell::context::Context;
+
+fn compile_with_debug_info(source: &str, debug: bool) -> Result<String, String> {
+    let ast = parse(source).map_err(|errors| format!("Parse errors: {:?}", errors))?;
+
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "debug_info_test");
+    if debug {
+        compiler.enable_debug_info("/src/program.ch");
+    }
+
+    compiler
+        .compile_module(&ast)
+        .map_err(|e| format!("Compilation error: {}", e))?;
+    Ok(compiler.get_ir())
+}
+
+const SOURCE: &str = r#"
+def add(a: int, b: int) -> int:
+    total = a + b
+    return total
+
+x = add(1, 2)
+print(x)
+"#;
+
+#[test]
+fn test_debug_info_emits_subprograms_and_lines() {
+    let ir = compile_with_debug_info(SOURCE, true).expect("source should compile");
+
+    assert!(ir.contains("!DICompileUnit("));
+    assert!(ir.contains("!DIFile(filename: \"program.ch\", directory: \"/src\")"));
+    assert!(ir.contains("!DISubprogram(name: \"add\""));
+    assert!(ir.contains("!DISubprogram(name: \"<module>\""));
+    assert!(ir.contains("!DILocation(line: 3,"));
+    assert!(ir.contains("!DILocation(line: 6,"));
+    assert!(ir.contains("\"Debug Info Version\", i32 3"));
+}
+
+#[test]
+fn test_no_debug_info_by_default() {
+    let ir = compile_with_debug_info(SOURCE, false).expect("source should compile");
+    assert!(!ir.contains("!DICompileUnit("));
+    assert!(!ir.contains("!dbg"));
+}
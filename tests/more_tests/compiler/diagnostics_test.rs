@@ -0,0 +1,130 @@
+// diagnostics_test.rs - Tests for machine-readable diagnostics
+
+use cheetah::diagnostics::{
+    render, render_files, to_sarif, type_check, Diagnostic, ErrorFormat, Level, Span,
+};
+use cheetah::lexer::Lexer;
+use serde_json::Value;
+
+fn lexer_diagnostics(source: &str) -> Vec<Diagnostic> {
+    let mut lexer = Lexer::new(source);
+    lexer.tokenize();
+    lexer.get_errors().iter().map(Diagnostic::from).collect()
+}
+
+#[test]
+fn test_error_format_names() {
+    assert_eq!(ErrorFormat::from_name("human"), Ok(ErrorFormat::Human));
+    assert_eq!(ErrorFormat::from_name("json"), Ok(ErrorFormat::Json));
+    assert_eq!(ErrorFormat::from_name("sarif"), Ok(ErrorFormat::Sarif));
+    assert!(ErrorFormat::from_name("xml").is_err());
+}
+
+#[test]
+fn test_lexer_error_codes() {
+    let codes = |source: &str| -> Vec<&str> {
+        lexer_diagnostics(source).iter().map(|d| d.code).collect()
+    };
+    assert_eq!(codes("x = 'abc\n"), vec!["unterminated-literal"]);
+    assert_eq!(codes("x = 0b102\n"), vec!["invalid-literal"]);
+    assert_eq!(codes("x = '\\xZZ'\n"), vec!["invalid-escape"]);
+    assert_eq!(codes("x = $\n"), vec!["unexpected-character"]);
+}
+
+#[test]
+fn test_parse_error_diagnostic() {
+    let errors = cheetah::parse("def f(:\n    pass\n").unwrap_err();
+    let diagnostic = Diagnostic::from(&errors[0]);
+
+    assert_eq!(diagnostic.code, errors[0].code());
+    assert_eq!(diagnostic.level, Level::Error);
+    assert_eq!(diagnostic.span, Some(Span::at(errors[0].line(), errors[0].column())));
+    assert_eq!(diagnostic.message, errors[0].message());
+    assert_eq!(diagnostic.suggestion.as_deref(), errors[0].suggestion());
+}
+
+#[test]
+fn test_type_error_located_at_statement() {
+    let source = "x = 1\nif x > 0:\n    y = 2\n    z = 10 + \"h\"\n";
+    let module = cheetah::parse(source).unwrap();
+    let diagnostic = type_check(&module).unwrap();
+
+    assert_eq!(diagnostic.code, "invalid-operator");
+    assert_eq!(diagnostic.span, Some(Span::at(4, 5)));
+    assert!(type_check(&cheetah::parse("x = 1\n").unwrap()).is_none());
+}
+
+#[test]
+fn test_json_lines() {
+    let diagnostics = lexer_diagnostics("x = 'abc\ny = $\n");
+    let output = render(ErrorFormat::Json, "bad.ch", &diagnostics);
+    let lines: Vec<Value> = output
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    assert_eq!(lines.len(), diagnostics.len());
+    assert_eq!(lines[0]["file"], "bad.ch");
+    assert_eq!(lines[0]["code"], "unterminated-literal");
+    assert_eq!(lines[0]["severity"], "error");
+    assert_eq!(lines[0]["span"]["line"], 1);
+    assert!(lines[0]["suggestion"].is_string());
+}
+
+#[test]
+fn test_json_without_diagnostics_prints_nothing() {
+    // One object per line, so a clean run is zero lines rather than another shape
+    assert_eq!(render(ErrorFormat::Json, "ok.ch", &[]), "");
+    assert_eq!(render_files(ErrorFormat::Json, &[("a.ch", &[]), ("b.ch", &[])]), "");
+}
+
+#[test]
+fn test_sarif_log() {
+    let mut diagnostics = lexer_diagnostics("x = 'abc\n");
+    diagnostics.push(Diagnostic::error("compile-error", "linking failed"));
+    let log = to_sarif("bad.ch", &diagnostics);
+
+    assert_eq!(log["version"], "2.1.0");
+    let run = &log["runs"][0];
+    assert_eq!(run["tool"]["driver"]["name"], "cheetah");
+    let rules: Vec<&str> = run["tool"]["driver"]["rules"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|rule| rule["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(rules, vec!["compile-error", "unterminated-literal"]);
+
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results[0]["ruleId"], "unterminated-literal");
+    assert_eq!(results[0]["ruleIndex"], 1);
+    assert_eq!(results[0]["level"], "error");
+    let location = &results[0]["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "bad.ch");
+    assert_eq!(location["region"]["startLine"], 1);
+    assert!(results[1]["locations"][0]["physicalLocation"]["region"].is_null());
+
+    // A clean run is still a valid log
+    let empty: Value = serde_json::from_str(&render(ErrorFormat::Sarif, "ok.ch", &[])).unwrap();
+    assert_eq!(empty["runs"][0]["results"].as_array().map(Vec::len), Some(0));
+}
+
+#[test]
+fn test_several_files_share_one_sarif_log() {
+    let first = lexer_diagnostics("s = \"open\n");
+    let second = vec![Diagnostic::error("compile-error", "backend failed")];
+    let files = [("a.ch", first.as_slice()), ("ok.ch", &[][..]), ("b.ch", second.as_slice())];
+
+    let log: Value = serde_json::from_str(&render_files(ErrorFormat::Sarif, &files)).unwrap();
+    let results = log["runs"][0]["results"].as_array().unwrap();
+    let uris: Vec<&str> = results
+        .iter()
+        .map(|r| r["locations"][0]["physicalLocation"]["artifactLocation"]["uri"].as_str().unwrap())
+        .collect();
+    assert_eq!(uris.len(), first.len() + 1);
+    assert_eq!(uris.first(), Some(&"a.ch"));
+    assert_eq!(uris.last(), Some(&"b.ch"));
+
+    let json = render_files(ErrorFormat::Json, &files);
+    assert_eq!(json.lines().count(), first.len() + 1);
+}
@@ -1,6 +1,9 @@
 use cheetah::parse;
 use cheetah::compiler::Compiler;
+use cheetah::compiler::runtime::dict::*;
+use cheetah::compiler::runtime::list::TypeTag;
 use inkwell::context::Context;
+use std::ffi::{c_void, CString};
 
 pub fn compile_source(source: &str) -> Result<String, String> {
     // Parse the source
@@ -152,3 +155,126 @@ for item in items:
     let result = compile_source(source);
     assert!(result.is_ok(), "Failed to compile dict methods with iteration: {:?}", result.err());
 }
+
+fn int_ptr(value: &mut i64) -> *mut c_void {
+    value as *mut i64 as *mut c_void
+}
+
+fn read_int(ptr: *mut c_void) -> i64 {
+    unsafe { *(ptr as *const i64) }
+}
+
+#[test]
+fn test_runtime_set_get_and_missing_keys() {
+    let dict = dict_new();
+    for i in 0..100i64 {
+        let (mut key, mut value) = (i, i * 10);
+        dict_set(dict, int_ptr(&mut key), int_ptr(&mut value), TypeTag::Int, TypeTag::Int);
+    }
+    assert_eq!(dict_len(dict), 100);
+
+    let mut key = 42i64;
+    assert_eq!(read_int(dict_get(dict, int_ptr(&mut key), TypeTag::Int)), 420);
+
+    // 42.0 and 42 are the same key
+    let mut float_key = 42.0f64;
+    let float_ptr = &mut float_key as *mut f64 as *mut c_void;
+    assert_eq!(dict_contains(dict, float_ptr, TypeTag::Float), 1);
+
+    // A missing key yields zeroed storage instead of crashing
+    let mut missing = 1000i64;
+    assert_eq!(read_int(dict_get(dict, int_ptr(&mut missing), TypeTag::Int)), 0);
+    let mut fallback = -1i64;
+    let fallback_ptr = int_ptr(&mut fallback);
+    assert_eq!(
+        dict_get_default(dict, int_ptr(&mut missing), fallback_ptr, TypeTag::Int),
+        fallback_ptr
+    );
+
+    dict_free(dict);
+}
+
+#[test]
+fn test_runtime_nan_keys_never_match() {
+    let dict = dict_new();
+    let mut one = 1i64;
+    let mut nan = f64::NAN;
+    let nan_ptr = &mut nan as *mut f64 as *mut c_void;
+    dict_set(dict, nan_ptr, int_ptr(&mut one), TypeTag::Float, TypeTag::Int);
+    dict_set(dict, nan_ptr, int_ptr(&mut one), TypeTag::Float, TypeTag::Int);
+
+    // Each NaN is a distinct key that no lookup finds
+    assert_eq!(dict_len(dict), 2);
+    assert_eq!(dict_contains(dict, nan_ptr, TypeTag::Float), 0);
+    let mut key = 1.0f64;
+    assert_eq!(dict_contains(dict, &mut key as *mut f64 as *mut c_void, TypeTag::Float), 0);
+
+    dict_free(dict);
+}
+
+#[test]
+fn test_runtime_pop_setdefault_and_update() {
+    let dict = dict_new();
+    let apple = CString::new("apple").unwrap();
+    let pear = CString::new("pear").unwrap();
+    let key = |s: &CString| s.as_ptr() as *mut c_void;
+
+    let mut one = 1i64;
+    let stored = dict_setdefault(dict, key(&apple), int_ptr(&mut one), TypeTag::String, TypeTag::Int);
+    assert_eq!(read_int(stored), 1);
+    let mut two = 2i64;
+    let existing = dict_setdefault(dict, key(&apple), int_ptr(&mut two), TypeTag::String, TypeTag::Int);
+    assert_eq!(read_int(existing), 1);
+
+    let other = dict_new();
+    dict_set(other, key(&pear), int_ptr(&mut two), TypeTag::String, TypeTag::Int);
+    dict_update(dict, other);
+    assert_eq!(dict_len(dict), 2);
+
+    let merged = dict_merge(dict, other);
+    assert_eq!(dict_len(merged), 2);
+
+    assert_eq!(read_int(dict_pop(dict, key(&pear), std::ptr::null_mut(), TypeTag::String)), 2);
+    assert_eq!(dict_contains(dict, key(&pear), TypeTag::String), 0);
+    assert_eq!(dict_remove(dict, key(&pear), TypeTag::String), 0);
+    assert_eq!(read_int(dict_pop(dict, key(&pear), std::ptr::null_mut(), TypeTag::String)), 0);
+
+    dict_clear(dict);
+    assert_eq!(dict_len(dict), 0);
+
+    dict_free(dict);
+    dict_free(other);
+    dict_free(merged);
+}
+
+#[test]
+fn test_dict_get_pop_update_compile() {
+    let source = r#"
+counts = {"a": 1, "b": 2}
+a = counts.get("a", 0)
+z = counts.get("z", 0)
+b = counts.pop("b")
+c = counts.pop("c", 5)
+counts.update({"d": 4})
+n = counts.setdefault("e", 7)
+has_a = "a" in counts
+counts["f"] = 6
+counts.clear()
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Failed to compile dict methods: {:?}", result.err());
+}
+
+#[test]
+fn test_dict_method_errors() {
+    let unknown = compile_source("d = {\"a\": 1}\nx = d.popitem()\n");
+    assert!(unknown.is_err());
+
+    let wrong_arity = compile_source("d = {\"a\": 1}\nx = d.get()\n");
+    assert!(wrong_arity.is_err());
+
+    let wrong_update = compile_source("d = {\"a\": 1}\nd.update([1, 2])\n");
+    assert!(wrong_update.is_err());
+}
+
@@ -0,0 +1,160 @@
+// dict_order_test.rs - Conformance tests for dictionary insertion order
+//
+// The compiled runtime must iterate dictionaries in insertion order, as the
+// interpreter and Python 3.7+ do, however the table underneath is laid out.
+
+use cheetah::compiler::runtime::dict::*;
+use cheetah::compiler::runtime::list::TypeTag;
+use std::ffi::c_void;
+
+#[repr(C)]
+struct List {
+    length: i64,
+    capacity: i64,
+    data: *mut *mut c_void,
+}
+
+#[repr(C)]
+struct Tuple {
+    length: i64,
+    data: *mut *mut c_void,
+}
+
+fn set(dict: *mut Dict, key: i64, value: i64) {
+    let (mut key, mut value) = (key, value);
+    dict_set(
+        dict,
+        &mut key as *mut i64 as *mut c_void,
+        &mut value as *mut i64 as *mut c_void,
+        TypeTag::Int,
+        TypeTag::Int,
+    );
+}
+
+fn remove(dict: *mut Dict, key: i64) {
+    let mut key = key;
+    assert_eq!(dict_remove(dict, &mut key as *mut i64 as *mut c_void, TypeTag::Int), 1);
+}
+
+fn read_int(ptr: *mut c_void) -> i64 {
+    unsafe { *(ptr as *const i64) }
+}
+
+/// The elements of a list returned by the runtime
+fn elements(list: *mut c_void) -> Vec<*mut c_void> {
+    let list = list as *mut List;
+    unsafe { (0..(*list).length as usize).map(|i| *(*list).data.add(i)).collect() }
+}
+
+fn keys(dict: *mut Dict) -> Vec<i64> {
+    let list = unsafe { dict_keys(dict) } as *mut c_void;
+    elements(list).into_iter().map(read_int).collect()
+}
+
+fn values(dict: *mut Dict) -> Vec<i64> {
+    let list = unsafe { dict_values(dict) } as *mut c_void;
+    elements(list).into_iter().map(read_int).collect()
+}
+
+fn items(dict: *mut Dict) -> Vec<(i64, i64)> {
+    let list = unsafe { dict_items(dict) } as *mut c_void;
+    elements(list)
+        .into_iter()
+        .map(|tuple| {
+            let tuple = tuple as *mut Tuple;
+            unsafe {
+                assert_eq!((*tuple).length, 2);
+                (read_int(*(*tuple).data), read_int(*(*tuple).data.add(1)))
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_keys_values_and_items_follow_insertion_order() {
+    let dict = dict_new();
+    for key in [42, 7, -3, 1000, 0, 19] {
+        set(dict, key, key * 2);
+    }
+    assert_eq!(keys(dict), [42, 7, -3, 1000, 0, 19]);
+    assert_eq!(values(dict), [84, 14, -6, 2000, 0, 38]);
+    assert_eq!(items(dict)[..2], [(42, 84), (7, 14)]);
+    dict_free(dict);
+}
+
+#[test]
+fn test_assigning_an_existing_key_keeps_its_place() {
+    let dict = dict_new();
+    for key in [3, 1, 2] {
+        set(dict, key, 0);
+    }
+    set(dict, 3, 30);
+    assert_eq!(items(dict), [(3, 30), (1, 0), (2, 0)]);
+    dict_free(dict);
+}
+
+#[test]
+fn test_removed_keys_are_readded_at_the_end() {
+    let dict = dict_new();
+    for key in [5, 6, 7, 8] {
+        set(dict, key, key);
+    }
+    remove(dict, 6);
+    let mut key = 8i64;
+    let popped = dict_pop(dict, &mut key as *mut i64 as *mut c_void, std::ptr::null_mut(), TypeTag::Int);
+    assert_eq!(read_int(popped), 8);
+    set(dict, 6, 60);
+    set(dict, 9, 90);
+    assert_eq!(keys(dict), [5, 7, 6, 9]);
+    assert_eq!(dict_len(dict), 4);
+
+    dict_clear(dict);
+    set(dict, 2, 0);
+    set(dict, 1, 0);
+    assert_eq!(keys(dict), [2, 1]);
+    dict_free(dict);
+}
+
+#[test]
+fn test_order_survives_growth_and_churn() {
+    let dict = dict_with_capacity(2);
+    let mut expected = Vec::new();
+    for key in (0..500).rev() {
+        set(dict, key * 7919 % 1009, key);
+        expected.push(key * 7919 % 1009);
+    }
+    // Removing every other key and adding more forces the table to be rebuilt
+    for key in expected.clone().into_iter().step_by(2) {
+        remove(dict, key);
+    }
+    expected = expected.into_iter().skip(1).step_by(2).collect();
+    for key in 2000..2300 {
+        set(dict, key, key);
+        expected.push(key);
+    }
+    assert_eq!(dict_len(dict), expected.len() as i64);
+    assert_eq!(keys(dict), expected);
+    dict_free(dict);
+}
+
+#[test]
+fn test_update_and_merge_keep_order() {
+    let a = dict_new();
+    let b = dict_new();
+    for key in [1, 2, 3] {
+        set(a, key, key);
+    }
+    for key in [9, 2, 8] {
+        set(b, key, key * 10);
+    }
+
+    let merged = dict_merge(a, b);
+    assert_eq!(items(merged), [(1, 1), (2, 20), (3, 3), (9, 90), (8, 80)]);
+
+    dict_update(b, a);
+    assert_eq!(items(b), [(9, 90), (2, 2), (8, 80), (1, 1), (3, 3)]);
+
+    dict_free(a);
+    dict_free(b);
+    dict_free(merged);
+}
@@ -0,0 +1,43 @@
+// diff_test.rs - Tests for the unified diffs printed by `format --diff`
+
+use cheetah::diff::unified;
+
+#[test]
+fn test_identical_texts_have_no_diff() {
+    assert_eq!(unified("x = 1\n", "x = 1\n", "a.ch", "a.ch"), "");
+}
+
+#[test]
+fn test_changed_line_with_context() {
+    let old = "a\nb\nc\nd\ne\nf\ng\nh\n";
+    let new = "a\nb\nc\nd\nE\nf\ng\nh\n";
+    assert_eq!(
+        unified(old, new, "old.ch", "new.ch"),
+        "--- old.ch\n+++ new.ch\n@@ -2,7 +2,7 @@\n b\n c\n d\n-e\n+E\n f\n g\n h\n"
+    );
+}
+
+#[test]
+fn test_distant_changes_get_separate_hunks() {
+    let line = |i: i32| match i {
+        2 | 19 => format!("changed {}\n", i),
+        _ => format!("{}\n", i),
+    };
+    let old: String = (1..=20).map(|i| format!("{}\n", i)).collect();
+    let new: String = (1..=20).map(line).collect();
+    let diff = unified(&old, &new, "a.ch", "a.ch");
+    let headers: Vec<&str> = diff.lines().filter(|line| line.starts_with("@@")).collect();
+    assert_eq!(headers, ["@@ -1,5 +1,5 @@", "@@ -16,5 +16,5 @@"]);
+}
+
+#[test]
+fn test_insertions_and_missing_newline() {
+    assert_eq!(
+        unified("", "x = 1\n", "a.ch", "a.ch"),
+        "--- a.ch\n+++ a.ch\n@@ -0,0 +1 @@\n+x = 1\n"
+    );
+    assert_eq!(
+        unified("x = 1", "x = 1\n", "a.ch", "a.ch"),
+        "--- a.ch\n+++ a.ch\n@@ -1 +1 @@\n-x = 1\n\\ No newline at end of file\n+x = 1\n"
+    );
+}
@@ -0,0 +1,103 @@
+// doctest_test.rs - Tests for running docstring examples
+
+use cheetah::compiler::runtime::buffer;
+use cheetah::doctest::{self, Example};
+
+const SOURCE: &str = r#""""Squares and cubes
+
+>>> square(3) + 1
+10
+"""
+
+def square(x: int) -> int:
+    """
+    >>> square(4)
+    16
+    >>> for i in range(3):
+    ...     print(square(i))
+    0
+    1
+    4
+    """
+    return x * x
+
+class Shapes:
+    """Nothing to run here"""
+
+    def area(self, side: int) -> int:
+        """
+        >>> print("")
+        <BLANKLINE>
+        >>> total = 2
+
+        >>> total
+        2
+        """
+        return side * side
+"#;
+
+#[test]
+fn test_extracts_examples_from_docstrings() {
+    let module = cheetah::parse(SOURCE).unwrap();
+    let tests = doctest::extract(&module, "shapes");
+    let names: Vec<&str> = tests.iter().map(|test| test.name.as_str()).collect();
+    assert_eq!(names, ["shapes", "shapes.square", "shapes.Shapes.area"]);
+
+    assert_eq!(
+        tests[0].examples,
+        [Example {
+            source: "square(3) + 1\n".to_string(),
+            want: "10\n".to_string(),
+            line: 3,
+        }]
+    );
+    assert_eq!(
+        tests[1].examples[1],
+        Example {
+            source: "for i in range(3):\n    print(square(i))\n".to_string(),
+            want: "0\n1\n4\n".to_string(),
+            line: 11,
+        }
+    );
+}
+
+#[test]
+fn test_blank_lines_end_expected_output() {
+    let module = cheetah::parse(SOURCE).unwrap();
+    let area = &doctest::extract(&module, "shapes")[2];
+    let examples: Vec<(&str, &str)> = area
+        .examples
+        .iter()
+        .map(|example| (example.source.as_str(), example.want.as_str()))
+        .collect();
+    assert_eq!(
+        examples,
+        [("print(\"\")\n", "\n"), ("total = 2\n", ""), ("total\n", "2\n")]
+    );
+    assert!(doctest::output_matches("2\n", "2  \n"));
+    assert!(!doctest::output_matches("2\n", "2\n3\n"));
+}
+
+#[test]
+fn test_buffer_captures_output() {
+    buffer::start_capture();
+    buffer::write_str("captured");
+    buffer::write_int(42);
+    buffer::write_newline();
+    assert_eq!(buffer::finish_capture(), "captured42\n");
+    assert_eq!(buffer::finish_capture(), "");
+}
+
+#[test]
+fn test_runs_examples_in_a_jit_session() {
+    let module = cheetah::parse(SOURCE).unwrap();
+    let tests = doctest::extract(&module, "shapes");
+    let result = doctest::run(&module, &tests[1], 0).unwrap();
+    assert_eq!((result.passed, result.failures.len()), (2, 0));
+
+    let mut wrong = tests[1].clone();
+    wrong.examples[0].want = "17\n".to_string();
+    let result = doctest::run(&module, &wrong, 0).unwrap();
+    assert_eq!(result.failures.len(), 1);
+    assert_eq!(result.failures[0].got, "16\n");
+}
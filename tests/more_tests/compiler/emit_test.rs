@@ -0,0 +1,45 @@
+// emit_test.rs - Tests for the output kinds of `cheetah compile --emit`
+
+use cheetah::compiler::emit::EmitKind;
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use inkwell::context::Context;
+use inkwell::targets::{InitializationConfig, Target};
+
+#[test]
+fn test_emit_kind_names() {
+    for name in ["asm", "llvm-ir", "llvm-bc", "obj", "exe"] {
+        let kind = EmitKind::from_name(name).expect("kind should parse");
+        assert_eq!(kind.name(), name);
+    }
+    assert_eq!(EmitKind::from_name("asm").unwrap().extension(), "s");
+    assert_eq!(EmitKind::from_name("llvm-bc").unwrap().extension(), "bc");
+    assert_eq!(EmitKind::from_name("exe").unwrap().extension(), "");
+
+    let err = EmitKind::from_name("wasm").unwrap_err();
+    assert!(err.contains("unknown emit kind 'wasm'"));
+}
+
+#[test]
+fn test_emit_asm_and_bitcode() {
+    Target::initialize_native(&InitializationConfig::default()).expect("native target");
+    let ast = parse("x = 6 * 7\nprint(x)\n").expect("source should parse");
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "emit_test");
+    compiler.compile_module(&ast).expect("source should compile");
+
+    let dir = std::env::temp_dir();
+    let asm_path = dir.join("cheetah_emit_test.s");
+    let bc_path = dir.join("cheetah_emit_test.bc");
+
+    compiler.emit(EmitKind::LlvmBc, &bc_path).expect("bitcode should be written");
+    let bitcode = std::fs::read(&bc_path).expect("bitcode file should exist");
+    assert!(bitcode.starts_with(b"BC\xC0\xDE"));
+
+    compiler.emit(EmitKind::Asm, &asm_path).expect("assembly should be written");
+    let asm = std::fs::read_to_string(&asm_path).expect("assembly file should exist");
+    assert!(asm.contains("main"));
+
+    let _ = std::fs::remove_file(asm_path);
+    let _ = std::fs::remove_file(bc_path);
+}
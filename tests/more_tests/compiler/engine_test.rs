@@ -0,0 +1,108 @@
+// engine_test.rs - Tests for embedding Cheetah programs with the Engine API
+
+use cheetah::engine::{Engine, Value};
+use inkwell::context::Context;
+
+const PROGRAM: &str = r#"
+scale = 10
+
+def add(a: int, b: int) -> int:
+    return a + b
+
+def scaled(x: int) -> int:
+    return x * scale
+
+def half(x: float) -> float:
+    return x / 2.0
+
+def greet(name: str) -> str:
+    return "Hello, " + name
+
+def total(xs: list) -> int:
+    return len(xs)
+"#;
+
+#[test]
+fn test_value_conversions() {
+    assert_eq!(Value::from(3), Value::Int(3));
+    assert_eq!(Value::from(1.5), Value::Float(1.5));
+    assert_eq!(Value::from(true), Value::Bool(true));
+    assert_eq!(Value::from("hi"), Value::Str("hi".to_string()));
+    assert_eq!(Value::Float(2.0).to_string(), "2.0");
+    assert_eq!(Value::Bool(false).to_string(), "False");
+    assert_eq!(Value::None.to_string(), "None");
+}
+
+#[test]
+fn test_call_before_load_fails() {
+    let context = Context::create();
+    let engine = Engine::new(&context);
+    assert_eq!(engine.call("add", &[]), Err("No program is loaded".to_string()));
+    assert!(engine.functions().is_empty());
+}
+
+#[test]
+fn test_load_reports_parse_errors() {
+    let context = Context::create();
+    let mut engine = Engine::new(&context);
+    assert!(engine.load("def broken(:\n    pass\n").is_err());
+}
+
+#[test]
+fn test_call_functions() {
+    let context = Context::create();
+    let mut engine = Engine::new(&context);
+    engine.load(PROGRAM).expect("program should load");
+
+    assert_eq!(engine.functions(), vec!["add", "greet", "half", "scaled", "total"]);
+    assert_eq!(engine.call("add", &[2.into(), 3.into()]), Ok(Value::Int(5)));
+    assert_eq!(engine.call("scaled", &[4.into()]), Ok(Value::Int(40)));
+    assert_eq!(engine.call("half", &[5.0.into()]), Ok(Value::Float(2.5)));
+    assert_eq!(engine.call("half", &[3.into()]), Ok(Value::Float(1.5)));
+    assert_eq!(
+        engine.call("greet", &["Rust".into()]),
+        Ok(Value::Str("Hello, Rust".to_string()))
+    );
+}
+
+#[test]
+fn test_call_errors() {
+    let context = Context::create();
+    let mut engine = Engine::new(&context);
+    engine.load(PROGRAM).expect("program should load");
+
+    let error = engine.call("missing", &[]).unwrap_err();
+    assert!(error.contains("No function named 'missing'"), "{}", error);
+
+    let error = engine.call("add", &[1.into()]).unwrap_err();
+    assert!(error.contains("takes 2 arguments but 1 were given"), "{}", error);
+
+    let error = engine.call("greet", &[1.into()]).unwrap_err();
+    assert!(error.contains("must be str"), "{}", error);
+
+    let error = engine.call("total", &[]).unwrap_err();
+    assert!(error.contains("can't be passed to the host"), "{}", error);
+}
+
+extern "C" fn engine_plugin_triple(x: i64) -> i64 {
+    x * 3
+}
+
+#[test]
+fn test_call_plugin_builtin() {
+    use cheetah::compiler::plugins::{register_builtin, PluginBuiltin};
+    use cheetah::compiler::types::Type;
+
+    register_builtin(
+        PluginBuiltin::new("engine_triple", "engine_plugin_triple", vec![Type::Int], Type::Int)
+            .with_address(engine_plugin_triple as *const () as usize),
+    )
+    .unwrap();
+
+    let context = Context::create();
+    let mut engine = Engine::new(&context);
+    engine
+        .load("def nine_times(x: int) -> int:\n    return engine_triple(engine_triple(x))\n")
+        .expect("program should load");
+    assert_eq!(engine.call("nine_times", &[2.into()]), Ok(Value::Int(18)));
+}
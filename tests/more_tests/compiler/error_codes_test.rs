@@ -0,0 +1,70 @@
+// error_codes_test.rs - Tests for error ids and the explanations of `cheetah explain`
+
+use cheetah::diagnostics::{type_check, Diagnostic};
+use cheetah::error_codes::{id_for, lookup, ERROR_CODES};
+use cheetah::lexer::Lexer;
+
+/// Code of the first error in `source`, from whichever stage finds one
+fn first_error(source: &str) -> Option<&'static str> {
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.tokenize();
+    if let Some(error) = lexer.get_errors().first() {
+        return Some(error.code());
+    }
+    match cheetah::parser::parse(tokens) {
+        Ok(module) => type_check(&module).map(|diagnostic| diagnostic.code),
+        Err(errors) => Some(errors[0].code()),
+    }
+}
+
+#[test]
+fn test_ids_are_stable_and_unique() {
+    for (i, error) in ERROR_CODES.iter().enumerate() {
+        assert_eq!(error.id, format!("CH{:04}", i + 1));
+        assert_eq!(ERROR_CODES.iter().filter(|e| e.name == error.name).count(), 1);
+        assert!(error.summary().ends_with('.'), "{} has no summary", error.id);
+    }
+}
+
+#[test]
+fn test_lookup() {
+    assert_eq!(lookup("CH0006").map(|e| e.name), Some("unexpected-token"));
+    assert_eq!(lookup("ch0006").map(|e| e.name), Some("unexpected-token"));
+    assert_eq!(lookup("unexpected-token").map(|e| e.id), Some("CH0006"));
+    assert!(lookup("CH9999").is_none());
+    assert_eq!(id_for("not-callable"), Some("CH0017"));
+    assert_eq!(id_for("unused-import"), None);
+}
+
+#[test]
+fn test_explanation_examples() {
+    // Errors the checker can't currently reach from source with default settings
+    let unreachable = [
+        "unexpected-eof",
+        "invalid-argument",
+        "undefined-member",
+        "forbidden-construct",
+    ];
+
+    for error in ERROR_CODES.iter().filter(|e| !unreachable.contains(&e.name)) {
+        let example = error
+            .explanation
+            .split("```cheetah\n")
+            .nth(1)
+            .and_then(|rest| rest.split("```").next())
+            .unwrap_or_else(|| panic!("{} has no example", error.id));
+        assert_eq!(first_error(example), Some(error.name), "example of {}", error.id);
+    }
+}
+
+#[test]
+fn test_diagnostics_carry_ids() {
+    let errors = cheetah::parse("def f(x)\n    return x\n").unwrap_err();
+    let diagnostic = Diagnostic::from(&errors[0]);
+    assert_eq!(diagnostic.id(), Some("CH0006"));
+    assert_eq!(diagnostic.to_json("a.ch")["id"], "CH0006");
+    assert!(diagnostic.to_string().contains("error[CH0006]"));
+
+    let formatted = cheetah::ParseErrorFormatter::new(&errors[0], None, false).format();
+    assert!(formatted.contains("cheetah explain CH0006"));
+}
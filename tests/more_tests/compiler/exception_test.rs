@@ -2,7 +2,10 @@
 
 use cheetah::parse;
 use cheetah::compiler::Compiler;
+use cheetah::compiler::runtime::exception::*;
+use cheetah::compiler::runtime::list::{list_append_tagged, list_get, list_len, list_new, TypeTag};
 use inkwell::context::Context;
+use std::ffi::{c_void, CStr, CString};
 
 pub fn compile_source(source: &str) -> Result<String, String> {
     // Parse the source
@@ -273,3 +276,176 @@ def test_func():
     let result = compile_source(source);
     assert!(result.is_ok(), "Failed to compile exception as variable: {:?}", result.err());
 }
+
+#[test]
+fn test_exception_runtime_args_and_message() {
+    let typ = CString::new("ValueError").unwrap();
+    let args = list_new();
+    let text = CString::new("bad value").unwrap().into_raw();
+    list_append_tagged(args, text as *mut c_void, TypeTag::String);
+    let code = Box::into_raw(Box::new(3i64));
+    list_append_tagged(args, code as *mut c_void, TypeTag::Int);
+
+    let exc = exception_new_with_args(typ.as_ptr(), args);
+    let message = unsafe { CStr::from_ptr(exception_get_message(exc)) };
+    assert_eq!(message.to_str().unwrap(), "('bad value', 3)");
+    assert_eq!(exception_get_args(exc), args);
+    exception_free(exc);
+
+    // Exceptions created from a plain message expose it as their only argument
+    let msg = CString::new("oops").unwrap();
+    let exc = exception_new(typ.as_ptr(), msg.as_ptr());
+    let args = exception_get_args(exc);
+    assert_eq!(list_len(args), 1);
+    let first = unsafe { CStr::from_ptr(list_get(args, 0) as *const std::os::raw::c_char) };
+    assert_eq!(first.to_str().unwrap(), "oops");
+    exception_free(exc);
+}
+
+#[test]
+fn test_exception_info_access() {
+    let source = r#"
+def test_func():
+    result = ""
+    try:
+        raise ValueError("bad value", 3)
+    except ValueError as e:
+        result = e.message
+        args = e.args
+        text = str(e)
+        info = sys.exc_info()
+    return result
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Failed to compile exception info access: {:?}", result.err());
+}
+
+#[test]
+fn test_exception_unknown_attribute() {
+    let source = r#"
+def test_func():
+    try:
+        raise KeyError
+    except KeyError as e:
+        code = e.errno
+    return 0
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_err());
+}
+
+fn new_exception(typ: &str) -> *mut Exception {
+    let typ = CString::new(typ).unwrap();
+    let msg = CString::new("").unwrap();
+    exception_new(typ.as_ptr(), msg.as_ptr())
+}
+
+#[test]
+fn test_nested_handlers_restore_outer_exception() {
+    let outer = new_exception("ValueError");
+    let inner = new_exception("KeyError");
+
+    set_current_exception(outer);
+    assert!(exception_is_pending());
+    assert_eq!(exception_enter_handler(), outer);
+    assert!(!exception_is_pending());
+
+    // A try/except inside the handler
+    set_current_exception(inner);
+    assert_eq!(exception_enter_handler(), inner);
+    assert_eq!(get_current_exception(), inner);
+    assert_eq!(exception_handler_depth(), 2);
+    exception_exit_handler();
+
+    // Leaving the inner handler makes the outer exception current again
+    assert_eq!(get_current_exception(), outer);
+    exception_exit_handler();
+    assert!(get_current_exception().is_null());
+    assert_eq!(exception_handler_depth(), 0);
+
+    exception_free(outer);
+    exception_free(inner);
+}
+
+#[test]
+fn test_rethrown_exception_stays_pending() {
+    let exc = new_exception("RuntimeError");
+
+    set_current_exception(exc);
+    exception_enter_handler();
+    // A bare `raise` re-raises the exception being handled
+    set_current_exception(get_current_exception());
+    exception_exit_handler();
+
+    assert!(exception_is_pending());
+    assert_eq!(exception_enter_handler(), exc);
+    exception_exit_handler();
+    assert!(!exception_is_pending());
+
+    exception_free(exc);
+}
+
+#[test]
+fn test_exception_state_is_per_thread() {
+    let exc = new_exception("ValueError");
+    set_current_exception(exc);
+
+    let other = std::thread::spawn(|| {
+        let seen = get_current_exception().is_null();
+        set_current_exception(new_exception("KeyError"));
+        let own = exception_enter_handler();
+        exception_exit_handler();
+        exception_free(own);
+        seen
+    })
+    .join()
+    .unwrap();
+
+    assert!(other, "another thread saw this thread's exception");
+    assert_eq!(get_current_exception(), exc);
+    clear_current_exception();
+    exception_free(exc);
+}
+
+#[test]
+fn test_nested_and_rethrown_exceptions_compile() {
+    let source = r#"
+def test_func():
+    result = 0
+    try:
+        try:
+            raise ValueError("inner")
+        except ValueError as inner:
+            result = 1
+            raise
+    except ValueError as outer:
+        try:
+            raise KeyError("nested")
+        except KeyError:
+            result = 2
+        message = outer.message
+    return result
+
+def early_exit():
+    for i in range(3):
+        try:
+            raise ValueError("stop")
+        except ValueError:
+            if i == 1:
+                break
+            continue
+    try:
+        raise KeyError
+    except KeyError:
+        return 1
+    finally:
+        done = 1
+    return 0
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Failed to compile nested exceptions: {:?}", result.err());
+}
+
@@ -0,0 +1,127 @@
+// ffi_test.rs - Tests for calling C functions declared with @extern
+
+use cheetah::ast::Stmt;
+use cheetah::compiler::cdylib::library_file_name;
+use cheetah::compiler::ffi::{library_path, ExternSignature};
+use cheetah::compiler::types::Type;
+use cheetah::compiler::Compiler;
+use cheetah::{parse, typechecker};
+use inkwell::context::Context;
+
+/// Signature of the first function defined in `source`
+fn first_signature(source: &str) -> Result<Option<ExternSignature>, String> {
+    let module = parse(source).expect("source should parse");
+    module
+        .body
+        .iter()
+        .find_map(|stmt| match stmt.as_ref() {
+            Stmt::FunctionDef { name, params, body, decorator_list, returns, .. } => {
+                Some(ExternSignature::from_def(name, params, returns, decorator_list, body))
+            }
+            _ => None,
+        })
+        .expect("source should define a function")
+}
+
+#[test]
+fn test_extern_signatures() {
+    assert_eq!(
+        first_signature("@extern\ndef cos(x: float) -> float: ...\n"),
+        Ok(Some(ExternSignature {
+            symbol: "cos".to_string(),
+            param_types: vec![Type::Float],
+            return_type: Type::Float,
+        }))
+    );
+    assert_eq!(
+        first_signature("@extern(\"strlen\")\ndef c_strlen(s: str) -> int:\n    \"\"\"Length of s\"\"\"\n"),
+        Ok(Some(ExternSignature {
+            symbol: "strlen".to_string(),
+            param_types: vec![Type::String],
+            return_type: Type::Int,
+        }))
+    );
+    assert_eq!(
+        first_signature("@extern\ndef srand(seed: int):\n    pass\n"),
+        Ok(Some(ExternSignature {
+            symbol: "srand".to_string(),
+            param_types: vec![Type::Int],
+            return_type: Type::None,
+        }))
+    );
+    assert_eq!(first_signature("def square(x: int) -> int:\n    return x * x\n"), Ok(None));
+}
+
+#[test]
+fn test_invalid_extern_signatures() {
+    let error = first_signature("@extern\ndef cos(x) -> float: ...\n").unwrap_err();
+    assert!(error.contains("needs a type annotation"), "{}", error);
+
+    let error = first_signature("@extern\ndef total(xs: list) -> int: ...\n").unwrap_err();
+    assert!(error.contains("no C equivalent"), "{}", error);
+
+    let error = first_signature("@extern\ndef twice(x: int) -> int:\n    return x * 2\n").unwrap_err();
+    assert!(error.contains("must be '...' or 'pass'"), "{}", error);
+
+    let error = first_signature("@extern(42)\ndef f() -> int: ...\n").unwrap_err();
+    assert!(error.contains("symbol name"), "{}", error);
+}
+
+#[test]
+fn test_extern_calls_typecheck() {
+    let module = parse("@extern\ndef sqrt(x: float) -> float: ...\n\ny = sqrt(2.0) + 1.0\nz = sqrt(9)\n")
+        .expect("source should parse");
+    assert!(typechecker::check_module(&module).is_ok());
+
+    let module = parse("@extern\ndef bad(x: list) -> int: ...\n").expect("source should parse");
+    let error = typechecker::check_module(&module).unwrap_err().to_string();
+    assert!(error.contains("Invalid extern declaration of 'bad'"), "{}", error);
+}
+
+#[test]
+fn test_library_path_lookup() {
+    assert_eq!(library_path("/opt/lib/libfoo.so", &[]), std::path::PathBuf::from("/opt/lib/libfoo.so"));
+    assert_eq!(library_path("m", &[]), std::path::PathBuf::from(library_file_name("m")));
+
+    let dir = std::env::temp_dir().join(format!("cheetah_ffi_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let lib = dir.join(library_file_name("point"));
+    std::fs::write(&lib, b"").unwrap();
+    let search = vec!["/nonexistent".to_string(), dir.to_string_lossy().into_owned()];
+    assert_eq!(library_path("point", &search), lib);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_extern_call_compiles_to_declaration() {
+    let source = r#"
+@extern
+def cos(x: float) -> float: ...
+
+@extern("abs")
+def c_abs(x: int) -> int: ...
+
+print(cos(0))
+print(c_abs(-3))
+"#;
+    let ast = parse(source).expect("source should parse");
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "ffi_test");
+    compiler.compile_module(&ast).expect("source should compile");
+
+    let ir = compiler.get_ir();
+    assert!(ir.contains("declare double @cos(double)"));
+    assert!(ir.contains("declare i64 @abs(i64)"));
+    assert!(ir.contains("call double @cos(double"));
+    assert!(!compiler.top_level_functions.contains(&"cos".to_string()));
+}
+
+#[test]
+fn test_nested_extern_is_rejected() {
+    let source = "def outer() -> float:\n    @extern\n    def cos(x: float) -> float: ...\n    return cos(0.0)\n";
+    let ast = parse(source).expect("source should parse");
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "ffi_nested_test");
+    let error = compiler.compile_module(&ast).unwrap_err();
+    assert!(error.contains("must be declared at module level"), "{}", error);
+}
@@ -0,0 +1,213 @@
+// file_io_test.rs - Tests for file objects returned by open()
+
+use cheetah::compiler::runtime::file::*;
+use cheetah::compiler::runtime::string::free_string;
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use cheetah::typechecker;
+use inkwell::context::Context;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::path::PathBuf;
+
+pub fn compile_source(source: &str) -> Result<String, String> {
+    // Parse the source
+    let ast = match parse(source) {
+        Ok(ast) => ast,
+        Err(errors) => {
+            return Err(format!("Parse errors: {:?}", errors));
+        }
+    };
+
+    // Create a compiler
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "file_io_test");
+
+    // Compile the AST
+    match compiler.compile_module(&ast) {
+        Ok(_) => Ok(compiler.get_ir()),
+        Err(e) => Err(format!("Compilation error: {}", e)),
+    }
+}
+
+fn check_source(source: &str) -> Result<(), String> {
+    let module = parse(source).map_err(|e| format!("Parse errors: {:?}", e))?;
+    typechecker::check_module(&module).map_err(|e| e.to_string())
+}
+
+fn scratch_path(name: &str) -> (PathBuf, CString) {
+    let path = std::env::temp_dir().join(format!("cheetah_file_io_{}_{}", std::process::id(), name));
+    let raw = CString::new(path.to_str().unwrap()).unwrap();
+    (path, raw)
+}
+
+fn take_string(ptr: *mut c_char) -> String {
+    let text = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+    free_string(ptr);
+    text
+}
+
+#[test]
+fn test_runtime_write_then_read() {
+    let (path, raw) = scratch_path("round_trip.txt");
+    let write = CString::new("w").unwrap();
+    let read = CString::new("r").unwrap();
+
+    let file = file_open(raw.as_ptr(), write.as_ptr());
+    assert!(!file.is_null());
+    let text = CString::new("first\nsecond é\n").unwrap();
+    assert_eq!(file_write(file, text.as_ptr()), 15);
+    assert!(!file_closed(file));
+    file_close(file);
+    assert!(file_closed(file));
+
+    // Closing twice is harmless and writes after close do nothing
+    file_close(file);
+    assert_eq!(file_write(file, text.as_ptr()), 0);
+
+    let file = file_open(raw.as_ptr(), read.as_ptr());
+    assert!(!file.is_null());
+    assert_eq!(take_string(file_readline(file)), "first\n");
+    assert_eq!(take_string(file_read(file)), "second é\n");
+    assert_eq!(take_string(file_readline(file)), "");
+    file_close(file);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_runtime_append_and_exclusive_modes() {
+    let (path, raw) = scratch_path("append.txt");
+    let append = CString::new("a").unwrap();
+    let exclusive = CString::new("xb").unwrap();
+
+    for chunk in ["one", "two"] {
+        let file = file_open(raw.as_ptr(), append.as_ptr());
+        let text = CString::new(chunk).unwrap();
+        file_write(file, text.as_ptr());
+        file_close(file);
+    }
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "onetwo");
+
+    // "x" refuses to overwrite an existing file
+    assert!(file_open(raw.as_ptr(), exclusive.as_ptr()).is_null());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_runtime_open_errors() {
+    let (_, missing) = scratch_path("does_not_exist.txt");
+    let read = CString::new("r").unwrap();
+    assert!(file_open(missing.as_ptr(), read.as_ptr()).is_null());
+
+    let (_, raw) = scratch_path("bad_mode.txt");
+    let bad_mode = CString::new("rw+q").unwrap();
+    assert!(file_open(raw.as_ptr(), bad_mode.as_ptr()).is_null());
+}
+
+#[test]
+fn test_file_methods_typecheck() {
+    let source = r#"
+f = open("data.txt", "w")
+n = f.write("hello") + 1
+f.close()
+g = open("data.txt")
+line = g.readline()
+rest = g.read()
+"#;
+    let result = check_source(source);
+    assert!(result.is_ok(), "Failed to type check: {:?}", result.err());
+
+    assert!(check_source("f = open()\n").is_err());
+    assert!(check_source("f = open(1)\n").is_err());
+    assert!(check_source("f = open(\"a\", \"r\", \"b\")\n").is_err());
+}
+
+#[test]
+fn test_file_io_compiles() {
+    let source = r#"
+with open("data.txt", "w") as f:
+    f.write("hello\n")
+
+def first_line(path: str) -> str:
+    with open(path) as f:
+        return f.readline()
+
+log = open("log.txt", "a")
+log.write("entry\n")
+done = log.closed
+log.close()
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Failed to compile file I/O: {:?}", result.err());
+}
+
+#[test]
+fn test_file_method_errors() {
+    let unknown = compile_source("f = open(\"a.txt\")\nf.seek(0)\n");
+    assert!(unknown.is_err());
+
+    let not_a_string = compile_source("f = open(\"a.txt\", \"w\")\nf.write(1)\n");
+    assert!(not_a_string.is_err());
+}
+
+#[test]
+fn test_encoding_codecs() {
+    assert_eq!(Encoding::from_name("UTF_8"), Some(Encoding::Utf8));
+    assert_eq!(Encoding::from_name("latin1"), Some(Encoding::Latin1));
+    assert_eq!(Encoding::from_name("ascii"), Some(Encoding::Ascii));
+    assert_eq!(Encoding::from_name("klingon"), None);
+
+    assert_eq!(Encoding::Latin1.encode("café"), Ok(vec![b'c', b'a', b'f', 0xe9]));
+    assert_eq!(Encoding::Latin1.decode(vec![b'c', b'a', b'f', 0xe9]), Ok("café".to_string()));
+
+    let error = Encoding::Ascii.encode("café").unwrap_err();
+    assert!(error.contains("'ascii' codec can't encode character '\\u00e9' in position 3"), "{}", error);
+    let error = Encoding::Utf8.decode(vec![b'o', b'k', 0xff]).unwrap_err();
+    assert!(error.contains("can't decode byte 0xff in position 2"), "{}", error);
+}
+
+#[test]
+fn test_runtime_open_with_encoding() {
+    let (path, raw) = scratch_path("latin1.txt");
+    let write = CString::new("w").unwrap();
+    let read = CString::new("r").unwrap();
+    let latin1 = CString::new("latin-1").unwrap();
+    let ascii = CString::new("ascii").unwrap();
+
+    let file = file_open_encoded(raw.as_ptr(), write.as_ptr(), latin1.as_ptr());
+    let text = CString::new("café\n").unwrap();
+    assert_eq!(file_write(file, text.as_ptr()), 5);
+    file_close(file);
+    assert_eq!(std::fs::read(&path).unwrap(), vec![b'c', b'a', b'f', 0xe9, b'\n']);
+
+    let file = file_open_encoded(raw.as_ptr(), read.as_ptr(), latin1.as_ptr());
+    assert_eq!(take_string(file_readline(file)), "café\n");
+    file_close(file);
+
+    // Text the encoding can't represent is not written
+    let file = file_open_encoded(raw.as_ptr(), write.as_ptr(), ascii.as_ptr());
+    assert_eq!(file_write(file, text.as_ptr()), 0);
+    file_close(file);
+
+    let unknown = CString::new("klingon").unwrap();
+    assert!(file_open_encoded(raw.as_ptr(), read.as_ptr(), unknown.as_ptr()).is_null());
+    let binary = CString::new("rb").unwrap();
+    assert!(file_open_encoded(raw.as_ptr(), binary.as_ptr(), latin1.as_ptr()).is_null());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_open_encoding_keyword() {
+    assert!(check_source("f = open(\"a.txt\", \"w\", encoding=\"latin-1\")\n").is_ok());
+    assert!(check_source("f = open(\"a.txt\", mode=\"r\", encoding=\"ascii\")\n").is_ok());
+    assert!(check_source("f = open(\"a.txt\", encoding=1)\n").is_err());
+    let error = check_source("f = open(\"a.txt\", buffering=1)\n").unwrap_err();
+    assert!(error.contains("unexpected keyword argument 'buffering'"), "{}", error);
+
+    let ir = compile_source("f = open(\"a.txt\", \"w\", encoding=\"latin-1\")\nf.close()\n").unwrap();
+    assert!(ir.contains("@file_open_encoded("));
+}
@@ -0,0 +1,68 @@
+// input_function_test.rs - Tests for the input() built-in
+
+use cheetah::compiler::runtime::print_ops::read_input_line;
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use cheetah::typechecker;
+use inkwell::context::Context;
+use std::io::Cursor;
+
+pub fn compile_source(source: &str) -> Result<String, String> {
+    // Parse the source
+    let ast = match parse(source) {
+        Ok(ast) => ast,
+        Err(errors) => {
+            return Err(format!("Parse errors: {:?}", errors));
+        }
+    };
+
+    // Create a compiler
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "input_function_test");
+
+    // Compile the AST
+    match compiler.compile_module(&ast) {
+        Ok(_) => Ok(compiler.get_ir()),
+        Err(e) => Err(format!("Compilation error: {}", e)),
+    }
+}
+
+fn check_source(source: &str) -> Result<(), String> {
+    let module = parse(source).map_err(|e| format!("Parse errors: {:?}", e))?;
+    typechecker::check_module(&module).map_err(|e| e.to_string())
+}
+
+#[test]
+fn test_read_input_line_strips_line_endings() {
+    let mut input = Cursor::new("first\nsecond\r\nlast");
+    assert_eq!(read_input_line(&mut input).as_deref(), Some("first"));
+    assert_eq!(read_input_line(&mut input).as_deref(), Some("second"));
+    assert_eq!(read_input_line(&mut input).as_deref(), Some("last"));
+    assert_eq!(read_input_line(&mut input), None);
+
+    let mut blank = Cursor::new("\n");
+    assert_eq!(read_input_line(&mut blank).as_deref(), Some(""));
+}
+
+#[test]
+fn test_input_typecheck() {
+    let result = check_source("name = input(\"Name: \")\ngreeting = \"Hello, \" + name\n");
+    assert!(result.is_ok(), "Failed to type check: {:?}", result.err());
+
+    assert!(check_source("x = input(\"a\", \"b\")\n").is_err());
+}
+
+#[test]
+fn test_input_compile() {
+    let source = r#"
+name = input("Name: ")
+line = input()
+print("Hello, " + name)
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Failed to compile input(): {:?}", result.err());
+
+    let bad_prompt = compile_source("x = input(5)\n");
+    assert!(bad_prompt.is_err());
+}
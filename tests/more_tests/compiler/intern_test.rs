@@ -0,0 +1,41 @@
+// intern_test.rs - Tests for interning names as symbols
+
+use cheetah::intern::Symbol;
+use cheetah::symtable::SymbolTable;
+
+#[test]
+fn test_interning_is_stable() {
+    let count = Symbol::intern("intern_test_count");
+    assert_eq!(count, Symbol::intern("intern_test_count"));
+    assert_eq!(count, Symbol::from(String::from("intern_test_count")));
+    assert_ne!(count, Symbol::intern("intern_test_total"));
+
+    assert_eq!(count.as_str(), "intern_test_count");
+    assert_eq!(count.to_string(), "intern_test_count");
+    assert_eq!(format!("{:?}", count), "Symbol(\"intern_test_count\")");
+    assert!(count == "intern_test_count");
+
+    // Looking a name up doesn't intern it
+    assert_eq!(Symbol::lookup("intern_test_never_seen"), None);
+    assert_eq!(Symbol::lookup("intern_test_count"), Some(count));
+}
+
+#[test]
+fn test_interning_across_threads() {
+    let symbols: Vec<Symbol> = (0..4)
+        .map(|_| std::thread::spawn(|| Symbol::intern("intern_test_shared")))
+        .map(|handle| handle.join().unwrap())
+        .collect();
+    assert!(symbols.iter().all(|&symbol| symbol == symbols[0]));
+}
+
+#[test]
+fn test_symbol_table_finds_interned_names() {
+    let module = cheetah::parse("total = 1\ndef f(x):\n    return x + total\n").unwrap();
+    let table = SymbolTable::build(&module);
+
+    let function = table.scope(table.root().children[0]);
+    assert!(function.symbol("x").is_some());
+    assert!(function.symbol("missing_name_in_intern_test").is_none());
+    assert!(table.root().symbol("total").unwrap().is_defined);
+}
@@ -0,0 +1,336 @@
+// interpreter_test.rs - Tests for the tree-walking interpreter backend
+
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use cheetah::interpreter::format::format_value;
+use cheetah::interpreter::value::Value;
+use cheetah::interpreter::{Interpreter, RuntimeError};
+use cheetah::parse;
+
+/// Output sink the test can read after the interpreter is done with it
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn run_with_input(source: &str, input: &str) -> (String, Result<(), RuntimeError>) {
+    let module = parse(source).expect("source should parse");
+    let output = SharedBuffer::default();
+    let mut interpreter = Interpreter::with_io(
+        Box::new(output.clone()),
+        Box::new(io::Cursor::new(input.as_bytes().to_vec())),
+    );
+    let result = interpreter.run(&module);
+    let text = String::from_utf8(output.0.borrow().clone()).expect("output should be UTF-8");
+    (text, result)
+}
+
+/// Run a program that should finish without an uncaught exception
+fn run(source: &str) -> String {
+    let (output, result) = run_with_input(source, "");
+    if let Err(error) = result {
+        panic!("program raised {}\noutput so far:\n{}", error, output);
+    }
+    output
+}
+
+/// Run a program that should end with an uncaught exception
+fn run_err(source: &str) -> RuntimeError {
+    run_with_input(source, "").1.expect_err("program should raise")
+}
+
+#[test]
+fn test_arithmetic_matches_python() {
+    let output = run(
+        "print(7 // 2, -7 // 2, 7 % (-3), -7 % 3)\n\
+         print(7 / 2, 1 / 3, 0.1 + 0.2)\n\
+         print(2 ** 10, 2 ** (-1), 1e16, 1e-05)\n\
+         print(True + 1, 3 == 3.0, 1 < 2 < 3, 1 < 3 < 2)\n",
+    );
+    assert_eq!(
+        output,
+        "3 -4 -2 2\n\
+         3.5 0.3333333333333333 0.30000000000000004\n\
+         1024 0.5 1e+16 1e-05\n\
+         2 True True False\n"
+    );
+}
+
+#[test]
+fn test_int_overflow_raises() {
+    let error = run_err("x = 9223372036854775807\nx = x + 1\n");
+    assert_eq!(error.exception.typ, "OverflowError");
+
+    let error = run_err("print(1 // 0)\n");
+    assert_eq!(error.exception.typ, "ZeroDivisionError");
+}
+
+#[test]
+fn test_functions_and_closures() {
+    let output = run(
+        "def fib(n):\n    if n < 2:\n        return n\n    return fib(n - 1) + fib(n - 2)\n\
+         def greet(name, greeting=\"Hello\", *rest, **options):\n    return f\"{greeting}, {name}! {rest} {options}\"\n\
+         def counter():\n    count = 0\n    def step():\n        nonlocal count\n        count += 1\n        return count\n    return step\n\
+         total = 0\n\
+         def add(n):\n    global total\n    total += n\n\
+         print(fib(15))\n\
+         print(greet(\"Ada\"))\n\
+         print(greet(\"Ada\", \"Hi\", 1, 2, loud=True))\n\
+         step = counter()\n\
+         step()\n\
+         print(step())\n\
+         add(5)\nadd(6)\n\
+         print(total)\n\
+         square = lambda x: x * x\n\
+         print(list(map(square, [1, 2, 3])))\n",
+    );
+    assert_eq!(
+        output,
+        "610\nHello, Ada! () {}\nHi, Ada! (1, 2) {'loud': True}\n2\n11\n[1, 4, 9]\n"
+    );
+}
+
+#[test]
+fn test_argument_errors() {
+    let error = run_err("def f(a, b):\n    return a\nf(1)\n");
+    assert_eq!(error.exception.typ, "TypeError");
+    assert_eq!(error.exception.message, "f() missing 1 required positional argument: 'b'");
+
+    let error = run_err("def f(a):\n    return a\nf(1, c=2)\n");
+    assert_eq!(error.exception.message, "f() got an unexpected keyword argument 'c'");
+
+    let error = run_err("print(undefined_name)\n");
+    assert_eq!(error.exception.typ, "NameError");
+}
+
+#[test]
+fn test_loops() {
+    let output = run(
+        "for i in range(10, 0, -3):\n    print(i, end=\" \")\nprint()\n\
+         for i in range(5):\n    if i == 1:\n        continue\n    if i == 3:\n        break\n    print(i)\nelse:\n    print(\"not reached\")\n\
+         for i in []:\n    pass\nelse:\n    print(\"empty loop else\")\n\
+         n = 0\n\
+         while n < 3:\n    n += 1\n\
+         print(n)\n",
+    );
+    assert_eq!(output, "10 7 4 1 \n0\n2\nempty loop else\n3\n");
+}
+
+#[test]
+fn test_collections() {
+    let output = run(
+        "items = [3, 1, 2]\n\
+         items.append(5)\n\
+         items += [0]\n\
+         items.sort()\n\
+         print(items, items[-1], items[1:3], items[::-1])\n\
+         d = {\"a\": 1}\n\
+         d[\"b\"] = 2\n\
+         print(d, d.get(\"c\", 0), list(d.items()), \"a\" in d)\n\
+         s = {1, 2, 2, 3}\n\
+         s.add(4)\n\
+         print(len(s), 2 in s, s - {1})\n\
+         print([x * 2 for x in range(5) if x % 2 == 0])\n\
+         print({k: v for k, v in zip(\"xy\", [1, 2])})\n\
+         first, *middle, last = [1, 2, 3, 4]\n\
+         print(first, middle, last)\n\
+         a, b = 1, 2\n\
+         a, b = b, a\n\
+         print(a, b, (1,), ())\n",
+    );
+    assert_eq!(
+        output,
+        "[0, 1, 2, 3, 5] 5 [1, 2] [5, 3, 2, 1, 0]\n\
+         {'a': 1, 'b': 2} 0 [('a', 1), ('b', 2)] True\n\
+         4 True {2, 3, 4}\n\
+         [0, 4, 8]\n\
+         {'x': 1, 'y': 2}\n\
+         1 [2, 3] 4\n\
+         2 1 (1,) ()\n"
+    );
+}
+
+#[test]
+fn test_dicts_keep_insertion_order() {
+    let output = run(
+        "d = {\"b\": 1, \"a\": 2, 10: 3, 2: 4}\n\
+         d[\"b\"] = 5\n\
+         print(d, list(d.keys()), list(d.values()))\n\
+         del d[\"a\"]\n\
+         d[\"a\"] = 6\n\
+         d.pop(10)\n\
+         d.setdefault(0, 7)\n\
+         print(d, [k for k in d], list(d.items()))\n\
+         d.update({2: 8, \"z\": 9})\n\
+         print(d, {**{\"y\": 1}, **{\"x\": 2, \"y\": 3}})\n\
+         print({k: k * k for k in [3, 1, 2]})\n",
+    );
+    assert_eq!(
+        output,
+        "{'b': 5, 'a': 2, 10: 3, 2: 4} ['b', 'a', 10, 2] [5, 2, 3, 4]\n\
+         {'b': 5, 2: 4, 'a': 6, 0: 7} ['b', 2, 'a', 0] [('b', 5), (2, 4), ('a', 6), (0, 7)]\n\
+         {'b': 5, 2: 8, 'a': 6, 0: 7, 'z': 9} {'y': 3, 'x': 2}\n\
+         {3: 9, 1: 1, 2: 4}\n"
+    );
+}
+
+#[test]
+fn test_lists_are_shared_by_reference() {
+    let output = run("a = [1]\nb = a\nb.append(2)\nprint(a, a is b, a == [1, 2])\n");
+    assert_eq!(output, "[1, 2] True True\n");
+}
+
+#[test]
+fn test_strings_and_formatting() {
+    let output = run(
+        "s = \"  Hello, World  \"\n\
+         print(s.strip().upper(), s.split(), \"-\".join([\"a\", \"b\"]))\n\
+         print(\"abc\"[1], \"abcdef\"[1:4], \"abc\" * 2, \"b\" in \"abc\")\n\
+         name = \"pi\"\n\
+         print(f\"{name!r} = {3.14159:.2f} | {42:>5} | {42:05d} | {1234567:,} | {255:x}\")\n\
+         print(repr(\"it's\"), str(1.5), int(\"42\") + 1, float(\"2.5\"))\n",
+    );
+    assert_eq!(
+        output,
+        "HELLO, WORLD ['Hello,', 'World'] a-b\n\
+         b bcd abcabc True\n\
+         'pi' = 3.14 |    42 | 00042 | 1,234,567 | ff\n\
+         \"it's\" 1.5 43 2.5\n"
+    );
+}
+
+#[test]
+fn test_format_value() {
+    assert_eq!(format_value(&Value::Float(0.5), ".1%").unwrap(), "50.0%");
+    assert_eq!(format_value(&Value::Float(12345.678), "e").unwrap(), "1.234568e+04");
+    assert_eq!(format_value(&Value::Float(-1.5), "+08.2f").unwrap(), "-0001.50");
+    assert_eq!(format_value(&Value::Int(7), "+d").unwrap(), "+7");
+    assert_eq!(format_value(&Value::str("ab"), "*^6").unwrap(), "**ab**");
+    assert_eq!(format_value(&Value::Float(0.0001234), "g").unwrap(), "0.0001234");
+    assert!(format_value(&Value::str("ab"), "d").is_err());
+}
+
+#[test]
+fn test_exception_handling() {
+    let output = run(
+        "def check(x):\n    if x < 0:\n        raise ValueError(\"negative\")\n    return x\n\
+         try:\n    check(-1)\nexcept ValueError as e:\n    print(\"caught\", e)\nelse:\n    print(\"no error\")\nfinally:\n    print(\"finally\")\n\
+         try:\n    d = {}\n    d[\"missing\"]\nexcept LookupError as e:\n    print(\"lookup\", e)\n\
+         try:\n    items = [1]\n    items[5]\nexcept (KeyError, IndexError):\n    print(\"index\")\n\
+         def f():\n    try:\n        return 1\n    finally:\n        print(\"cleanup\")\n\
+         print(f())\n",
+    );
+    assert_eq!(
+        output,
+        "caught negative\nfinally\nlookup 'missing'\nindex\ncleanup\n1\n"
+    );
+}
+
+#[test]
+fn test_uncaught_exception_traceback() {
+    let error = run_err("def inner():\n    raise KeyError(\"k\")\n\ndef outer():\n    inner()\n\nouter()\n");
+    assert_eq!(error.exception.typ, "KeyError");
+    assert_eq!(
+        error.traceback("prog.ch"),
+        "Traceback (most recent call last):\n\
+         \x20 File \"prog.ch\", line 7, in <module>\n\
+         \x20 File \"prog.ch\", line 5, in outer\n\
+         \x20 File \"prog.ch\", line 2, in inner\n\
+         KeyError: k"
+    );
+
+    let error = run_err("try:\n    1 / 0\nexcept ZeroDivisionError:\n    raise\n");
+    assert_eq!(error.to_string(), "ZeroDivisionError: division by zero");
+}
+
+#[test]
+fn test_builtins() {
+    let output = run(
+        "words = [\"pear\", \"fig\", \"banana\"]\n\
+         print(sorted(words), sorted(words, key=len, reverse=True))\n\
+         print(min(3, 1, 2), max(words, key=len), sum([1, 2, 3]), abs(-4))\n\
+         print(list(enumerate(\"ab\", 1)), any([0, 1]), all([]), round(2.5), round(2.675, 1))\n\
+         print(isinstance(True, int), isinstance(\"x\", (int, float)), ord(\"a\"), chr(98))\n",
+    );
+    assert_eq!(
+        output,
+        "['banana', 'fig', 'pear'] ['banana', 'pear', 'fig']\n\
+         1 banana 6 4\n\
+         [(1, 'a'), (2, 'b')] True True 2 2.7\n\
+         True False 97 b\n"
+    );
+}
+
+#[test]
+fn test_input() {
+    let (output, result) = run_with_input("name = input(\"Name? \")\nprint(\"Hi\", name)\n", "Grace\n");
+    assert!(result.is_ok());
+    assert_eq!(output, "Name? Hi Grace\n");
+
+    let (_, result) = run_with_input("input()\n", "");
+    assert_eq!(result.unwrap_err().exception.typ, "EOFError");
+}
+
+#[test]
+fn test_unsupported_features_raise() {
+    let error = run_err("class Point:\n    pass\n");
+    assert_eq!(error.exception.typ, "NotImplementedError");
+    assert!(error.exception.message.contains("interpreter backend"));
+}
+
+#[test]
+fn test_globals_persist_between_runs() {
+    let mut interpreter = Interpreter::with_io(Box::new(io::sink()), Box::new(io::empty()));
+    interpreter.run(&parse("x = 40\n").unwrap()).unwrap();
+    interpreter.run(&parse("y = x + 2\n").unwrap()).unwrap();
+    assert_eq!(interpreter.global("y"), Some(Value::Int(42)));
+}
+
+/// Output of a program run with `--trace` and a step limit
+fn run_traced(source: &str, limit: usize) -> (String, Result<(), RuntimeError>) {
+    let module = cheetah::trace::instrument(&parse(source).unwrap(), source);
+    let output = SharedBuffer::default();
+    let mut interpreter = Interpreter::with_io(Box::new(output.clone()), Box::new(io::empty()));
+    interpreter.set_trace_limit(limit);
+    let result = interpreter.run(&module);
+    let text = String::from_utf8(output.0.borrow().clone()).unwrap();
+    (text, result)
+}
+
+#[test]
+fn test_trace_prints_steps_with_bindings() {
+    let source = "def double(n):\n    return n * 2\n\nfor i in range(2):\n    x, y = i, double(i)\nprint(y)\n";
+    let (output, result) = run_traced(source, 100);
+    result.unwrap();
+    assert_eq!(
+        output,
+        "   4 | for i in range(2):    -> i = 0\n\
+         \x20  2 | return n * 2\n\
+         \x20  5 | x, y = i, double(i)    -> x = 0, y = 0\n\
+         \x20  4 | for i in range(2):    -> i = 1\n\
+         \x20  2 | return n * 2\n\
+         \x20  5 | x, y = i, double(i)    -> x = 1, y = 2\n\
+         2\n\
+         \x20  6 | print(y)\n"
+    );
+}
+
+#[test]
+fn test_trace_step_limit() {
+    let (output, result) = run_traced("while True:\n    pass\n", 3);
+    assert_eq!(output.lines().count(), 3);
+    let error = result.unwrap_err();
+    assert_eq!(error.exception.typ, "RuntimeError");
+    assert!(error.exception.message.contains("--max-steps"));
+}
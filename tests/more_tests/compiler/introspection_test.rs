@@ -0,0 +1,95 @@
+// introspection_test.rs - Tests for dir(), getattr() and setattr() on instances and classes
+
+use cheetah::compiler::runtime::exception::get_current_exception;
+use cheetah::compiler::runtime::instance::*;
+use cheetah::compiler::runtime::list::{list_get, list_len, RawList};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+
+fn name(text: &str) -> CString {
+    CString::new(text).unwrap()
+}
+
+/// The strings in a list returned by dir()
+fn names(list: *mut RawList) -> Vec<String> {
+    (0..list_len(list))
+        .map(|index| {
+            unsafe { CStr::from_ptr(list_get(list, index) as *const c_char) }
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect()
+}
+
+#[test]
+fn test_dir_lists_instance_and_class_names_sorted() {
+    let base = class_define(name("DirBase").as_ptr(), name("").as_ptr());
+    class_set_attr(base, name("kind").as_ptr(), 1);
+    class_set_methods(name("DirBase").as_ptr(), name("describe,__init__").as_ptr());
+    let point = class_define(name("DirPoint").as_ptr(), name("DirBase").as_ptr());
+    class_set_attr(point, name("origin").as_ptr(), 0);
+
+    let instance = class_instantiate(point);
+    let mut cache = AttributeCache::EMPTY;
+    instance_set_attr(instance, name("y").as_ptr(), 2, &mut cache);
+    let mut cache = AttributeCache::EMPTY;
+    instance_set_attr(instance, name("x").as_ptr(), 1, &mut cache);
+
+    assert_eq!(names(class_dir(point)), ["__init__", "describe", "kind", "origin"]);
+    assert_eq!(
+        names(instance_dir(instance)),
+        ["__init__", "describe", "kind", "origin", "x", "y"]
+    );
+
+    // Deleted attributes are no longer listed
+    instance_del_attr(instance, name("y").as_ptr());
+    assert!(!names(instance_dir(instance)).contains(&"y".to_string()));
+    instance_free(instance);
+}
+
+#[test]
+fn test_getattr_falls_back_to_the_default_without_raising() {
+    let config = class_define(name("GetattrConfig").as_ptr(), name("").as_ptr());
+    class_set_attr(config, name("retries").as_ptr(), 3);
+    let instance = class_instantiate(config);
+    instance_set_attr(instance, name("port").as_ptr(), 8080, std::ptr::null_mut());
+
+    assert_eq!(instance_get_attr_or(instance, name("port").as_ptr(), -1), 8080);
+    assert_eq!(instance_get_attr_or(instance, name("retries").as_ptr(), -1), 3);
+    assert_eq!(instance_get_attr_or(instance, name("host").as_ptr(), -1), -1);
+    assert_eq!(class_get_attr_or(config, name("retries").as_ptr(), -1), 3);
+    assert_eq!(class_get_attr_or(config, name("port").as_ptr(), -1), -1);
+    assert!(get_current_exception().is_null());
+    instance_free(instance);
+}
+
+#[test]
+fn test_redefining_a_class_forgets_its_methods() {
+    class_define(name("DirRedefined").as_ptr(), name("").as_ptr());
+    class_set_methods(name("DirRedefined").as_ptr(), name("old").as_ptr());
+    let class = class_define(name("DirRedefined").as_ptr(), name("").as_ptr());
+    assert!(names(class_dir(class)).is_empty());
+}
+
+#[test]
+fn test_compiler_compiles_introspection_builtins() {
+    use cheetah::compiler::Compiler;
+    use inkwell::context::Context;
+
+    let module = cheetah::parse(
+        "class Settings:\n    debug = False\n\n\
+         settings = Settings()\n\
+         for key in [\"port\", \"host\"]:\n    setattr(settings, key, 0)\n\
+         port = getattr(settings, \"port\", 80)\n\
+         names = dir(settings)\n",
+    )
+    .unwrap();
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "introspection_test");
+    compiler.compile_module(&module).unwrap();
+
+    let ir = compiler.get_ir();
+    assert!(ir.contains("@instance_set_attr"), "{}", ir);
+    assert!(ir.contains("@instance_get_attr_or"), "{}", ir);
+    assert!(ir.contains("@instance_dir"), "{}", ir);
+}
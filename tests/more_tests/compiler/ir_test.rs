@@ -0,0 +1,253 @@
+// ir_test.rs - Tests for the typed mid-level IR
+
+use cheetah::compiler::types::Type;
+#[cfg(feature = "codegen")]
+use cheetah::compiler::Compiler;
+use cheetah::ir::{self, Const, Inst, Operand, Program, Rvalue, Terminator};
+use cheetah::parse;
+#[cfg(feature = "codegen")]
+use inkwell::context::Context;
+
+fn lower(source: &str) -> Result<Program, String> {
+    let ast = parse(source).map_err(|errors| format!("Parse errors: {:?}", errors))?;
+    ir::lower_module(&ast)
+}
+
+fn lower_error(source: &str) -> String {
+    lower(source).expect_err("lowering should fail")
+}
+
+#[cfg(feature = "codegen")]
+pub fn compile_source(source: &str) -> Result<String, String> {
+    let ast = parse(source).map_err(|errors| format!("Parse errors: {:?}", errors))?;
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "ir_test");
+    compiler
+        .compile_module_ir(&ast)
+        .map_err(|e| format!("Compilation error: {}", e))?;
+    Ok(compiler.get_ir())
+}
+
+#[test]
+fn test_top_level_statements_become_main() {
+    let program = lower("x = 1 + 2\nprint(x)\n").unwrap();
+    assert_eq!(program.functions.len(), 1);
+    let main = program.function(ir::MAIN).unwrap();
+    assert_eq!(main.signature.returns, Type::None);
+    assert_eq!(
+        main.to_string(),
+        "fn main(): () -> None\n  let %0: int\n  let %1 x: int\nbb0:\n  line 1\n  %0 = 1 + 2\n  %1 = %0\n  line 2\n  print %1\n  return None\n"
+    );
+}
+
+#[test]
+fn test_annotated_parameters_are_unboxed() {
+    let program = lower("def half(n: int) -> float:\n    return n / 2\n").unwrap();
+    let half = program.function("half").unwrap();
+    assert_eq!(half.signature.params, vec![Type::Int]);
+    assert_eq!(half.signature.returns, Type::Float);
+    assert_eq!(half.local_type(half.params[0]), &Type::Any);
+
+    let insts = &half.blocks[0].insts;
+    assert!(matches!(&insts[0], Inst::Assign { value: Rvalue::Unbox { to: Type::Int, .. }, .. }));
+    // True division converts both sides to float first
+    assert!(insts
+        .iter()
+        .any(|inst| matches!(inst, Inst::Assign { value: Rvalue::Cast { to: Type::Float, .. }, .. })));
+    assert!(insts
+        .iter()
+        .any(|inst| matches!(inst, Inst::Assign { value: Rvalue::Binary { right: Operand::Const(Const::Float(_)), .. }, .. })));
+    assert!(matches!(insts.last(), Some(Inst::Assign { value: Rvalue::Box(_), .. })));
+}
+
+#[test]
+fn test_calls_box_arguments_and_unbox_results() {
+    let source = "def twice(n: int) -> int:\n    return n * 2\n\nprint(twice(4))\n";
+    let program = lower(source).unwrap();
+    let main = program.function(ir::MAIN).unwrap();
+    let text = main.to_string();
+    assert!(text.contains("= box 4"), "{}", text);
+    assert!(text.contains("= call twice(%"), "{}", text);
+    assert!(text.contains("as int"), "{}", text);
+}
+
+#[test]
+fn test_loops_become_blocks() {
+    let source = "total = 0\nfor i in range(10):\n    if i == 5:\n        break\n    total += i\nprint(total)\n";
+    let main = lower(source).unwrap().functions.pop().unwrap();
+    assert!(main.blocks.len() >= 4);
+    assert!(main
+        .blocks
+        .iter()
+        .any(|block| matches!(block.terminator, Terminator::Branch { .. })));
+    // Every jump lands on a block of the function
+    for block in &main.blocks {
+        match &block.terminator {
+            Terminator::Goto(target) => assert!(target.0 < main.blocks.len()),
+            Terminator::Branch {
+                then_block, else_block, ..
+            } => {
+                assert!(then_block.0 < main.blocks.len());
+                assert!(else_block.0 < main.blocks.len());
+            }
+            Terminator::Return(_) => {}
+        }
+    }
+}
+
+#[test]
+fn test_code_after_return_is_dropped() {
+    let program = lower("def f() -> int:\n    return 1\n    print(2)\n").unwrap();
+    let f = program.function("f").unwrap();
+    assert_eq!(f.blocks.len(), 1);
+    assert!(!f.blocks[0].insts.iter().any(|inst| matches!(inst, Inst::Print(_))));
+}
+
+#[test]
+fn test_list_comprehension_pushes_boxed_items() {
+    let program = lower("def scale(xs: list[int], k: float) -> list[float]:\n    return [x * k for x in xs]\n").unwrap();
+    let text = program.to_string();
+    assert!(text.contains("fn scale(%0, %1): (list[int], float) -> list[float]"), "{}", text);
+    assert!(text.contains("list.new"), "{}", text);
+    assert!(text.contains("list.push %4, "), "{}", text);
+}
+
+#[test]
+fn test_unsupported_constructs_are_errors() {
+    assert_eq!(
+        lower_error("a, b = 1, 2\n"),
+        "line 1: tuples are not supported by the IR yet"
+    );
+    assert!(lower_error("class Point:\n    pass\n").contains("not supported by the IR yet"));
+    assert!(lower_error("def f(n=1):\n    return n\n").contains("default arguments are not supported"));
+    assert!(lower_error("def main():\n    pass\n").contains("a function named `main`"));
+}
+
+#[test]
+fn test_changing_a_variables_type_is_an_error() {
+    let error = lower_error("x = 1\nx = \"one\"\n");
+    assert!(error.starts_with("line 2: "), "{}", error);
+}
+
+#[test]
+fn test_type_errors_are_reported() {
+    assert!(lower_error("with_timeout(1, 2)\n").starts_with("Type error: "));
+}
+
+fn specialized(source: &str) -> Program {
+    let mut program = lower(source).unwrap();
+    ir::specialize(&mut program);
+    program
+}
+
+fn calls<'a>(function: &'a ir::Function) -> Vec<(&'a str, &'a [Operand])> {
+    function
+        .blocks
+        .iter()
+        .flat_map(|block| &block.insts)
+        .filter_map(|inst| match inst {
+            Inst::Assign {
+                value: Rvalue::Call { function, args },
+                ..
+            } => Some((function.as_str(), args.as_slice())),
+            _ => None,
+        })
+        .collect()
+}
+
+fn boxes(function: &ir::Function) -> usize {
+    function
+        .blocks
+        .iter()
+        .flat_map(|block| &block.insts)
+        .filter(|inst| matches!(inst, Inst::Assign { value: Rvalue::Box(_) | Rvalue::Unbox { .. }, .. }))
+        .count()
+}
+
+#[test]
+fn test_specialize_adds_an_unboxed_copy_and_a_shim() {
+    let source = "def twice(n: int) -> int:\n    return n * 2\n\nprint(twice(4))\n";
+    let program = specialized(source);
+    let names: Vec<&str> = program.functions.iter().map(|function| function.name.as_str()).collect();
+    assert_eq!(names, vec!["twice", "twice.unboxed", "main"]);
+
+    let copy = program.function("twice.unboxed").unwrap();
+    assert!(copy.returns_unboxed);
+    assert_eq!(copy.local_type(copy.params[0]), &Type::Int);
+    assert_eq!(boxes(copy), 0);
+    assert!(matches!(copy.blocks[0].terminator, Terminator::Return(Operand::Local(local)) if copy.local_type(local) == &Type::Int));
+
+    let shim = program.function("twice").unwrap();
+    assert!(!shim.returns_unboxed);
+    assert_eq!(shim.local_type(shim.params[0]), &Type::Any);
+    assert_eq!(calls(shim), vec![("twice.unboxed", &[Operand::Local(ir::LocalId(1))][..])]);
+    assert_eq!(boxes(shim), 2);
+}
+
+#[test]
+fn test_specialize_drops_boxes_at_static_call_sites() {
+    let source = "def fib(n: int) -> int:\n    if n < 2:\n        return n\n    return fib(n - 1) + fib(n - 2)\n\nprint(fib(20))\n";
+    let program = specialized(source);
+
+    let main = program.function(ir::MAIN).unwrap();
+    assert_eq!(calls(main), vec![("fib.unboxed", &[Operand::Const(Const::Int(20))][..])]);
+    assert_eq!(boxes(main), 0);
+
+    // The recursive calls go to the copy too
+    let copy = program.function("fib.unboxed").unwrap();
+    let callees: Vec<&str> = calls(copy).into_iter().map(|(callee, _)| callee).collect();
+    assert_eq!(callees, vec!["fib.unboxed", "fib.unboxed"]);
+    assert_eq!(boxes(copy), 0);
+
+    // Nothing is left holding the boxes the copy no longer takes or makes
+    assert!(copy.locals.iter().all(|local| local.ty == Type::Int || local.ty == Type::Bool), "{}", copy);
+    assert!(main.locals.iter().all(|local| local.ty == Type::Int), "{}", main);
+}
+
+#[test]
+fn test_specialize_unboxes_dynamic_arguments() {
+    let source = "def inc(n: int) -> int:\n    return n + 1\n\ndef apply(x):\n    return inc(x)\n";
+    let program = specialized(source);
+    // `apply` returns an int, so it gets an unboxed copy of its own
+    let apply = program.function("apply.unboxed").unwrap();
+    let (callee, args) = calls(apply)[0];
+    assert_eq!(callee, "inc.unboxed");
+    assert_eq!(apply.operand_type(&args[0]), Type::Int);
+}
+
+#[test]
+fn test_specialize_leaves_boxed_only_functions_alone() {
+    let source = "def first(xs: list[str]) -> str:\n    return xs[0]\n\nprint(first([\"a\"]))\n";
+    let lowered = lower(source).unwrap();
+    assert_eq!(specialized(source), lowered);
+}
+
+#[cfg(feature = "codegen")]
+#[test]
+fn test_compile_module_ir() {
+    let source = "def fib(n: int) -> int:\n    a = 0\n    b = 1\n    for i in range(n):\n        t = a + b\n        a = b\n        b = t\n    return a\n\nprint(fib(10))\n";
+    let ir = compile_source(source).unwrap();
+    assert!(ir.contains("define ptr @fib(ptr"), "{}", ir);
+    assert!(ir.contains("define i64 @fib.unboxed(i64"), "{}", ir);
+    assert!(ir.contains("define void @main()"), "{}", ir);
+}
+
+#[cfg(feature = "codegen")]
+#[test]
+fn test_compile_module_goes_through_the_ir_when_it_can() {
+    let compile = |source: &str| {
+        let ast = parse(source).unwrap();
+        let context = Context::create();
+        let mut compiler = Compiler::new(&context, "ir_test");
+        compiler.compile_module(&ast).unwrap();
+        (compiler.get_ir(), compiler.top_level_functions.clone())
+    };
+
+    let (ir, functions) = compile("def twice(n: int) -> int:\n    return n * 2\n\nprint(twice(4))\n");
+    assert!(ir.contains("define i64 @twice.unboxed(i64"), "{}", ir);
+    assert_eq!(functions, vec!["twice"]);
+
+    // Classes aren't in the IR yet, so this one is compiled from the AST
+    let (ir, _) = compile("class C:\n    pass\n\ndef twice(n: int) -> int:\n    return n * 2\n\nprint(twice(4))\n");
+    assert!(!ir.contains("twice.unboxed"), "{}", ir);
+}
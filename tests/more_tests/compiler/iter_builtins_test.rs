@@ -0,0 +1,110 @@
+// iter_builtins_test.rs - Tests for the enumerate, zip, map and filter built-ins
+
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use cheetah::typechecker;
+use inkwell::context::Context;
+
+pub fn compile_source(source: &str) -> Result<String, String> {
+    // Parse the source
+    let ast = match parse(source) {
+        Ok(ast) => ast,
+        Err(errors) => {
+            return Err(format!("Parse errors: {:?}", errors));
+        }
+    };
+
+    // Create a compiler
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "iter_builtins_test");
+
+    // Compile the AST
+    match compiler.compile_module(&ast) {
+        Ok(_) => Ok(compiler.get_ir()),
+        Err(e) => Err(format!("Compilation error: {}", e)),
+    }
+}
+
+fn check_source(source: &str) -> Result<(), String> {
+    let module = parse(source).map_err(|e| format!("Parse errors: {:?}", e))?;
+    typechecker::check_module(&module).map_err(|e| e.to_string())
+}
+
+#[test]
+fn test_iter_builtins_typecheck() {
+    let source = r#"
+names = ["a", "b", "c"]
+scores = [1, 2, 3]
+total = 0
+for i, name in enumerate(names, 1):
+    total = total + i
+for name, score in zip(names, scores):
+    total = total + score
+"#;
+    let result = check_source(source);
+    assert!(result.is_ok(), "Failed to type check: {:?}", result.err());
+}
+
+#[test]
+fn test_iter_builtins_typecheck_errors() {
+    // Unpacking into the wrong number of targets
+    assert!(check_source("xs = [1, 2]\nfor a, b, c in enumerate(xs):\n    pass\n").is_err());
+
+    // Non-integer start
+    assert!(check_source("xs = [1, 2]\nys = enumerate(xs, \"one\")\n").is_err());
+
+    // filter() takes exactly two arguments
+    assert!(check_source("xs = [1, 2]\nys = filter(xs)\n").is_err());
+}
+
+#[test]
+fn test_enumerate_and_zip_compile() {
+    let source = r#"
+names = ["a", "b", "c"]
+scores = [10, 20, 30]
+total = 0
+for i, name in enumerate(names):
+    total = total + i
+for name, score in zip(names, scores):
+    total = total + score
+pairs = [i + s for i, s in zip(scores, scores)]
+indexed = [i for i, n in enumerate(names, 1)]
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Failed to compile enumerate/zip: {:?}", result.err());
+}
+
+#[test]
+fn test_map_and_filter_compile() {
+    let source = r#"
+def double(x: int) -> int:
+    return x * 2
+
+def is_even(x: int) -> bool:
+    return x % 2 == 0
+
+def add(a: int, b: int) -> int:
+    return a + b
+
+values = [1, 2, 3, 4]
+doubled = map(double, values)
+evens = filter(is_even, values)
+sums = map(add, values, doubled)
+total = 0
+for v in filter(is_even, map(double, values)):
+    total = total + v
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Failed to compile map/filter: {:?}", result.err());
+}
+
+#[test]
+fn test_iter_builtin_errors() {
+    let not_a_list = compile_source("x = 5\nfor i, v in enumerate(x):\n    pass\n");
+    assert!(not_a_list.is_err());
+
+    let unknown_function = compile_source("xs = [1, 2]\nys = map(missing, xs)\n");
+    assert!(unknown_function.is_err());
+}
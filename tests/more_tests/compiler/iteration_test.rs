@@ -0,0 +1,65 @@
+// iteration_test.rs - Tests for for loops over call results and values of runtime-only type
+
+use cheetah::compiler::runtime::exception::{clear_current_exception, get_current_exception};
+use cheetah::compiler::runtime::list::{list_get, list_len, list_new, list_append_tagged, RawList, TypeTag};
+use cheetah::compiler::runtime::sequence_ops::iter_items;
+use std::ffi::{c_void, CStr, CString};
+use std::os::raw::c_char;
+
+fn strings(list: *mut RawList) -> Vec<String> {
+    (0..list_len(list))
+        .map(|index| {
+            unsafe { CStr::from_ptr(list_get(list, index) as *const c_char) }
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect()
+}
+
+#[test]
+fn test_iter_items_dispatches_on_the_runtime_tag() {
+    let list = list_new();
+    let item = CString::new("a").unwrap().into_raw();
+    list_append_tagged(list, item as *mut c_void, TypeTag::String);
+    assert_eq!(iter_items(list as *mut c_void, TypeTag::List), list);
+    assert_eq!(iter_items(list as *mut c_void, TypeTag::Any), list);
+
+    let text = CString::new("héé").unwrap();
+    let chars = iter_items(text.as_ptr() as *mut c_void, TypeTag::String);
+    assert_eq!(strings(chars), ["h", "é", "é"]);
+
+    assert_eq!(list_len(iter_items(std::ptr::null_mut(), TypeTag::Any)), 0);
+    assert!(get_current_exception().is_null());
+}
+
+#[test]
+fn test_iter_items_raises_type_error_for_scalars() {
+    let items = iter_items(7 as *mut c_void, TypeTag::Int);
+    assert_eq!(list_len(items), 0);
+    assert!(!get_current_exception().is_null());
+    clear_current_exception();
+}
+
+#[test]
+fn test_compiler_iterates_over_call_results() {
+    use cheetah::compiler::Compiler;
+    use inkwell::context::Context;
+
+    let module = cheetah::parse(
+        "def make_list():\n    return [1, 2, 3]\n\n\
+         def make_names():\n    return {\"a\": 1}\n\n\
+         total = 0\n\
+         for item in make_list():\n    total = total + item\n\
+         for name in make_names():\n    print(name)\n\
+         for c in \"abc\":\n    print(c)\n",
+    )
+    .unwrap();
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "iteration_test");
+    compiler.compile_module(&module).unwrap();
+
+    let ir = compiler.get_ir();
+    assert!(ir.contains("define ptr @make_list"), "{}", ir);
+    assert!(ir.contains("@dict_keys"), "{}", ir);
+    assert!(ir.contains("@iter_items"), "{}", ir);
+}
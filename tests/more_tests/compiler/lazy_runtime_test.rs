@@ -0,0 +1,47 @@
+// lazy_runtime_test.rs - Tests for declaring runtime functions on first use
+
+use cheetah::compiler::runtime::RuntimeCatalog;
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use inkwell::context::Context;
+
+#[test]
+fn test_catalog_declares_on_request() {
+    let context = Context::create();
+    let module = context.create_module("lazy_runtime_test");
+    let catalog = RuntimeCatalog::new();
+
+    assert!(catalog.provides(&context, "list_new"));
+    assert!(!catalog.provides(&context, "not_a_runtime_function"));
+
+    assert!(module.get_function("list_new").is_none());
+    let list_new = catalog.declare(&context, &module, "list_new").expect("list_new is a runtime function");
+    assert_eq!(module.get_function("list_new"), Some(list_new));
+    assert!(catalog.declare(&context, &module, "not_a_runtime_function").is_none());
+}
+
+#[test]
+fn test_small_programs_declare_only_what_they_use() {
+    let ast = parse("x = 40 + 2\nprint(x)\n").expect("source should parse");
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "lazy_runtime_test");
+    compiler.compile_module(&ast).expect("source should compile");
+
+    let module = compiler.get_module();
+    assert!(module.get_function("print_int").is_some());
+    assert!(module.get_function("file_open").is_none());
+    assert!(module.get_function("dict_new").is_none());
+    assert!(module.get_function("string_builder_new").is_none());
+}
+
+#[test]
+fn test_lists_declare_list_functions() {
+    let ast = parse("xs = [1, 2]\nxs.append(3)\nprint(len(xs))\n").expect("source should parse");
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "lazy_runtime_test");
+    compiler.compile_module(&ast).expect("source should compile");
+
+    let ir = compiler.get_ir();
+    assert!(ir.contains("@list_append"));
+    assert!(!ir.contains("@file_open"));
+}
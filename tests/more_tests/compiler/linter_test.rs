@@ -0,0 +1,227 @@
+// linter_test.rs - Tests for the rules behind `cheetah lint`
+
+use cheetah::linter::{lint, Diagnostic, LintConfig, Severity};
+use cheetah::typechecker::WarningKind;
+
+fn lint_with(source: &str, config: &LintConfig) -> Vec<Diagnostic> {
+    let module = cheetah::parse(source).unwrap();
+    lint(&module, source, config)
+}
+
+fn kinds(source: &str) -> Vec<(WarningKind, usize)> {
+    lint_with(source, &LintConfig::default())
+        .into_iter()
+        .map(|d| (d.warning.kind, d.warning.line))
+        .collect()
+}
+
+fn kinds_of(diagnostics: &[Diagnostic]) -> Vec<WarningKind> {
+    diagnostics.iter().map(|d| d.warning.kind).collect()
+}
+
+#[test]
+fn test_unused_imports() {
+    let source = "import math\nimport os\nfrom sys import argv\nprint(math.sqrt(4.0))\n";
+    assert_eq!(
+        kinds(source),
+        vec![(WarningKind::UnusedImport, 2), (WarningKind::UnusedImport, 3)]
+    );
+}
+
+#[test]
+fn test_unused_local_variables() {
+    let source = r#"
+def f(n: int) -> int:
+    unused = n * 2
+    _scratch = 1
+    total = 0
+    total += n
+    return total
+
+top_level = 1
+"#;
+    assert_eq!(kinds(source), vec![(WarningKind::UnusedVariable, 3)]);
+}
+
+#[test]
+fn test_names_used_by_nested_functions_are_used() {
+    let source = r#"
+def outer() -> int:
+    base = 10
+    def inner() -> int:
+        return base
+    return inner()
+
+print(outer())
+"#;
+    assert!(kinds(source).is_empty());
+}
+
+#[test]
+fn test_shadowed_names() {
+    let source = r#"
+limit = 10
+
+def check(limit: int) -> int:
+    return limit
+
+class Box:
+    size = 1
+    def grow(self, size: int) -> int:
+        return size
+
+print(check(limit))
+"#;
+    let diagnostics = lint_with(source, &LintConfig::default());
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].warning.kind, WarningKind::ShadowedName);
+    assert_eq!(diagnostics[0].warning.line, 4);
+    assert!(diagnostics[0].warning.message.contains("line 2"));
+}
+
+#[test]
+fn test_unreachable_code() {
+    let source = r#"
+def f(n: int) -> int:
+    if n > 0:
+        return 1
+        print("never")
+        print("also never")
+    while True:
+        break
+        n = 0
+    return n
+
+print(f(1))
+"#;
+    assert_eq!(
+        kinds(source),
+        vec![(WarningKind::UnreachableCode, 5), (WarningKind::UnreachableCode, 9)]
+    );
+}
+
+#[test]
+fn test_none_comparisons() {
+    let source = r#"
+x = None
+if x == None:
+    print("none")
+y = x != None and x is not None
+print(y)
+"#;
+    let diagnostics = lint_with(source, &LintConfig::default());
+    let messages: Vec<&str> = diagnostics
+        .iter()
+        .map(|d| d.warning.message.as_str())
+        .collect();
+    assert_eq!(
+        messages,
+        vec![
+            "comparison to None should use 'is'",
+            "comparison to None should use 'is not'"
+        ]
+    );
+}
+
+#[test]
+fn test_string_concat_in_loops() {
+    let source = r#"
+def render(words: list) -> str:
+    out = ""
+    for word in words:
+        if word:
+            out += word
+        out += ", "
+    count = 0
+    label = ""
+    while count < 3:
+        count += 1
+        label = str(count)
+        label += "!"
+    out += "."
+    return out + label
+
+print(render(["a"]))
+"#;
+    assert_eq!(
+        kinds(source),
+        vec![
+            (WarningKind::StringConcatInLoop, 6),
+            (WarningKind::StringConcatInLoop, 7),
+            (WarningKind::StringConcatInLoop, 13),
+        ]
+    );
+    let diagnostics = lint_with(source, &LintConfig::default());
+    assert!(diagnostics[0].warning.message.contains("\"\".join()"));
+}
+
+#[test]
+fn test_rule_severities() {
+    let source = "import os\nx = None\nprint(x == None)\n";
+
+    let mut config = LintConfig::default();
+    config.set("unused-import", Severity::Deny).unwrap();
+    config.set("none-comparison", Severity::Allow).unwrap();
+    let diagnostics = lint_with(source, &config);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Deny);
+    assert_eq!(
+        diagnostics[0].to_string(),
+        "Line 1, Col 1: error[unused-import]: 'os' is imported but never used"
+    );
+
+    config.set("all", Severity::Allow).unwrap();
+    assert!(lint_with(source, &config).is_empty());
+
+    assert!(config.set("no-such-rule", Severity::Warn).is_err());
+}
+
+#[test]
+fn test_suppression_comments() {
+    let source = "import os  # cheetah: ignore[unused-import]\nimport sys\n";
+    assert_eq!(kinds(source), vec![(WarningKind::UnusedImport, 2)]);
+}
+
+#[test]
+fn test_warning_flags() {
+    let source = "x = 1\nx = 2\nprint(x == None)\n";
+
+    let mut config = LintConfig::default();
+    config.apply_flag("error").unwrap();
+    config.apply_flag("allow=none-comparison").unwrap();
+    let diagnostics = lint_with(source, &config);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].warning.kind, WarningKind::DeadStore);
+    assert_eq!(diagnostics[0].severity, Severity::Deny);
+
+    config.apply_flag("warn=dead-store, none-comparison").unwrap();
+    assert!(lint_with(source, &config)
+        .iter()
+        .all(|d| d.severity == Severity::Warn));
+
+    // A bare rule name makes it a warning, as `cheetah lint -W RULE` always has
+    config.apply_flag("allow").unwrap();
+    config.apply_flag("dead-store").unwrap();
+    assert_eq!(kinds_of(&lint_with(source, &config)), vec![WarningKind::DeadStore]);
+
+    assert!(config.apply_flag("fatal=dead-store").is_err());
+    assert!(config.apply_flag("error=no-such-rule").is_err());
+
+    // An unknown bare flag is reported as a level, naming the valid ones
+    let error = config.apply_flag("bogus").unwrap_err();
+    assert!(error.starts_with("unknown warning level 'bogus'"), "{}", error);
+    assert!(error.contains("error, deny, warn or allow"), "{}", error);
+}
+
+#[test]
+fn test_denied_warnings_are_error_diagnostics() {
+    use cheetah::diagnostics::{self, Level};
+
+    let source = "import os\nprint(1)\n";
+    let mut config = LintConfig::default();
+    config.apply_flag("error=unused-import").unwrap();
+    let diagnostics = lint_with(source, &config);
+    let converted = diagnostics::Diagnostic::from(&diagnostics[0]);
+    assert_eq!(converted.level, Level::Error);
+    assert_eq!(converted.code, "unused-import");
+}
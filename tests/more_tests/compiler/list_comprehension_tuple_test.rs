@@ -1,4 +1,4 @@
-use cheetah::ast::{Expr, Number, ExprContext, Comprehension};
+use cheetah::ast::{Expr, Number, ExprContext, Span, Comprehension};
 use cheetah::compiler::context::CompilationContext;
 use cheetah::compiler::expr::ExprCompiler;
 use cheetah::compiler::types::Type;
@@ -118,18 +118,21 @@ fn test_list_comprehension_with_tuple() {
     let tuple_elements = vec![
         Box::new(Expr::Num {
             value: Number::Integer(1),
-            line: 1, column: 2
+            line: 1, column: 2,
+            span: Span::default(),
         }),
         Box::new(Expr::Num {
             value: Number::Integer(2),
-            line: 1, column: 5
+            line: 1, column: 5,
+            span: Span::default(),
         })
     ];
 
     let tuple_expr = Expr::Tuple {
         elts: tuple_elements,
         ctx: ExprContext::Load,
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
 
     // Compile the tuple expression
@@ -164,13 +167,15 @@ fn test_list_comprehension_with_tuple() {
     let target = Box::new(Expr::Name {
         id: "x".to_string(),
         ctx: ExprContext::Store,
-        line: 1, column: 2
+        line: 1, column: 2,
+        span: Span::default(),
     });
 
     let iter = Box::new(Expr::Name {
         id: list_name.clone(),
         ctx: ExprContext::Load,
-        line: 1, column: 10
+        line: 1, column: 10,
+        span: Span::default(),
     });
 
     let generator = Comprehension {
@@ -183,13 +188,15 @@ fn test_list_comprehension_with_tuple() {
     let elt = Box::new(Expr::Name {
         id: "x".to_string(),
         ctx: ExprContext::Load,
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     });
 
     let list_comp = Expr::ListComp {
         elt,
         generators: vec![generator],
-        line: 1, column: 1
+        line: 1, column: 1,
+        span: Span::default(),
     };
 
     // Compile the list comprehension
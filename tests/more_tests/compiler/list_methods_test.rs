@@ -0,0 +1,223 @@
+use cheetah::compiler::runtime::exception::{
+    clear_current_exception, exception_get_message, exception_get_type, get_current_exception,
+};
+use cheetah::compiler::runtime::list::*;
+use cheetah::compiler::Compiler;
+use cheetah::engine::{Engine, Value};
+use cheetah::parse;
+use inkwell::context::Context;
+use std::ffi::{c_void, CStr};
+
+pub fn compile_source(source: &str) -> Result<String, String> {
+    // Parse the source
+    let ast = match parse(source) {
+        Ok(ast) => ast,
+        Err(errors) => {
+            return Err(format!("Parse errors: {:?}", errors));
+        }
+    };
+
+    // Create a compiler
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "list_methods_test");
+
+    // Compile the AST
+    match compiler.compile_module(&ast) {
+        Ok(_) => Ok(compiler.get_ir()),
+        Err(e) => Err(format!("Compilation error: {}", e)),
+    }
+}
+
+fn boxed_int(value: i64) -> *mut c_void {
+    Box::into_raw(Box::new(value)) as *mut c_void
+}
+
+fn int_at(list: *mut RawList, index: i64) -> i64 {
+    unsafe { *(list_get(list, index) as *const i64) }
+}
+
+fn int_list(values: &[i64]) -> *mut RawList {
+    let list = list_new();
+    for &value in values {
+        list_append_tagged(list, boxed_int(value), TypeTag::Int);
+    }
+    list
+}
+
+fn contents(list: *mut RawList) -> Vec<i64> {
+    (0..list_len(list)).map(|i| int_at(list, i)).collect()
+}
+
+/// Type and message of the pending exception, clearing it
+fn take_exception() -> Option<(String, String)> {
+    let exception = get_current_exception();
+    if exception.is_null() {
+        return None;
+    }
+    let text = |ptr| unsafe { CStr::from_ptr(ptr) }.to_string_lossy().into_owned();
+    let raised = (text(exception_get_type(exception)), text(exception_get_message(exception)));
+    clear_current_exception();
+    Some(raised)
+}
+
+fn raised(typ: &str, message: &str) -> Option<(String, String)> {
+    Some((typ.to_string(), message.to_string()))
+}
+
+#[test]
+fn test_runtime_pop_and_insert() {
+    let list = int_list(&[1, 2, 3]);
+
+    let last = list_pop(list, -1);
+    assert_eq!(unsafe { *(last as *const i64) }, 3);
+    assert_eq!(contents(list), vec![1, 2]);
+    assert_eq!(take_exception(), None);
+
+    // Out of range raises IndexError and yields a zeroed item
+    assert_eq!(unsafe { *(list_pop(list, 5) as *const i64) }, 0);
+    assert_eq!(take_exception(), raised("IndexError", "pop index out of range"));
+    assert_eq!(contents(list), vec![1, 2]);
+    let empty = list_new();
+    assert_eq!(unsafe { *(list_pop(empty, -1) as *const i64) }, 0);
+    assert_eq!(take_exception(), raised("IndexError", "pop from empty list"));
+
+    list_insert(list, 0, boxed_int(0), TypeTag::Int);
+    list_insert(list, 100, boxed_int(9), TypeTag::Int);
+    list_insert(list, -1, boxed_int(7), TypeTag::Int);
+    assert_eq!(contents(list), vec![0, 1, 2, 7, 9]);
+}
+
+#[test]
+fn test_runtime_search_and_remove() {
+    let list = int_list(&[4, 5, 4, 6]);
+    let mut probe = 4i64;
+    let probe_ptr = &mut probe as *mut i64 as *mut c_void;
+
+    assert_eq!(list_index(list, probe_ptr, TypeTag::Int), 0);
+    assert_eq!(list_count(list, probe_ptr, TypeTag::Int), 2);
+
+    // Numeric elements compare across tags
+    let mut float_probe = 6.0f64;
+    let float_ptr = &mut float_probe as *mut f64 as *mut c_void;
+    assert_eq!(list_index(list, float_ptr, TypeTag::Float), 3);
+
+    assert!(list_remove(list, probe_ptr, TypeTag::Int));
+    assert_eq!(contents(list), vec![5, 4, 6]);
+    assert_eq!(take_exception(), None);
+
+    // A missing value raises ValueError
+    let mut missing = 42i64;
+    assert!(!list_remove(list, &mut missing as *mut i64 as *mut c_void, TypeTag::Int));
+    assert_eq!(take_exception(), raised("ValueError", "list.remove(x): x not in list"));
+    assert_eq!(list_index(list, &mut missing as *mut i64 as *mut c_void, TypeTag::Int), -1);
+    assert_eq!(take_exception(), raised("ValueError", "42 is not in list"));
+    assert_eq!(contents(list), vec![5, 4, 6]);
+}
+
+#[test]
+fn test_runtime_search_never_matches_nan() {
+    let list = list_new();
+    for value in [1.0, f64::NAN, 2.0] {
+        list_append_tagged(list, Box::into_raw(Box::new(value)) as *mut c_void, TypeTag::Float);
+    }
+    let mut nan = f64::NAN;
+    let nan_ptr = &mut nan as *mut f64 as *mut c_void;
+    let mut two = 2.0f64;
+    let two_ptr = &mut two as *mut f64 as *mut c_void;
+
+    // NaN is not equal to itself, and no number is equal to NaN
+    assert_eq!(list_count(list, nan_ptr, TypeTag::Float), 0);
+    assert_eq!(list_index(list, nan_ptr, TypeTag::Float), -1);
+    assert_eq!(take_exception(), raised("ValueError", "nan is not in list"));
+    assert!(!list_remove(list, nan_ptr, TypeTag::Float));
+    assert_eq!(take_exception(), raised("ValueError", "list.remove(x): x not in list"));
+    assert_eq!(list_index(list, two_ptr, TypeTag::Float), 2);
+    assert_eq!(list_count(list, two_ptr, TypeTag::Float), 1);
+    assert_eq!(list_len(list), 3);
+}
+
+#[test]
+fn test_runtime_extend_and_sort() {
+    let list = int_list(&[3, -1]);
+    let other = int_list(&[10, 2]);
+    list_extend(list, other);
+    assert_eq!(contents(list), vec![3, -1, 10, 2]);
+
+    list_sort(list);
+    assert_eq!(contents(list), vec![-1, 2, 3, 10]);
+    assert_eq!(list_get_tag(list, 0), TypeTag::Int);
+}
+
+#[test]
+fn test_list_methods_compile() {
+    let source = r#"
+numbers = [3, 1, 2]
+numbers.append(4)
+numbers.insert(0, 10)
+numbers.extend([7, 8])
+numbers.sort()
+numbers.remove(10)
+last = numbers.pop()
+first = numbers.pop(0)
+where = numbers.index(3)
+twos = numbers.count(2)
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Failed to compile list methods: {:?}", result.err());
+}
+
+#[test]
+fn test_list_methods_on_any_list_expression() {
+    let source = r#"
+rows = [[1, 2], [3]]
+rows[0].append(5)
+size = [1, 2, 2].count(2)
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Failed to compile list methods on expressions: {:?}", result.err());
+}
+
+#[test]
+fn test_list_method_errors() {
+    assert!(compile_source("xs = [1]\nxs.push(2)\n").is_err());
+    assert!(compile_source("xs = [1]\nxs.insert(1)\n").is_err());
+    assert!(compile_source("xs = [1]\nxs.extend(3)\n").is_err());
+}
+
+const FAILING_METHODS: &str = r#"
+def pop_empty() -> int:
+    xs = [1]
+    xs.pop()
+    return xs.pop()
+
+def pop_out_of_range() -> int:
+    xs = [1, 2]
+    return xs.pop(5)
+
+def remove_missing() -> int:
+    xs = [1, 2]
+    xs.remove(3)
+    return len(xs)
+
+def index_missing() -> int:
+    xs = [1, 2]
+    return xs.index(3)
+"#;
+
+#[test]
+fn test_compiled_list_methods_raise() {
+    let context = Context::create();
+    let mut engine = Engine::new(&context);
+    engine.load(FAILING_METHODS).expect("program should load");
+
+    assert_eq!(engine.call("pop_empty", &[]), Ok(Value::Int(0)));
+    assert_eq!(take_exception(), raised("IndexError", "pop from empty list"));
+    assert_eq!(engine.call("pop_out_of_range", &[]), Ok(Value::Int(0)));
+    assert_eq!(take_exception(), raised("IndexError", "pop index out of range"));
+    assert_eq!(engine.call("remove_missing", &[]), Ok(Value::Int(2)));
+    assert_eq!(take_exception(), raised("ValueError", "list.remove(x): x not in list"));
+    assert_eq!(engine.call("index_missing", &[]), Ok(Value::Int(-1)));
+    assert_eq!(take_exception(), raised("ValueError", "3 is not in list"));
+}
@@ -0,0 +1,102 @@
+// loop_fusion_test.rs - Tests for fusing chained comprehensions and map/filter calls
+
+use cheetah::ast::{Expr, Stmt};
+use cheetah::compiler::loop_fusion::fuse_module;
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use inkwell::context::Context;
+
+pub fn compile_source(source: &str) -> Result<String, String> {
+    // Parse the source
+    let ast = match parse(source) {
+        Ok(ast) => ast,
+        Err(errors) => {
+            return Err(format!("Parse errors: {:?}", errors));
+        }
+    };
+
+    // Create a compiler
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "loop_fusion_test");
+
+    // Compile the AST
+    match compiler.compile_module(&ast) {
+        Ok(_) => Ok(compiler.get_ir()),
+        Err(e) => Err(format!("Compilation error: {}", e)),
+    }
+}
+
+/// The value assigned by the last statement of the fused source
+fn fused_value(source: &str) -> Expr {
+    let module = fuse_module(&parse(source).expect("source should parse"));
+    match module.body.last().map(|stmt| stmt.as_ref()) {
+        Some(Stmt::Assign { value, .. }) => *value.clone(),
+        other => panic!("expected an assignment, got {:?}", other),
+    }
+}
+
+/// Name of the iterable and number of conditions of a single-generator comprehension
+fn comprehension_source(expr: &Expr) -> Option<(String, usize)> {
+    match expr {
+        Expr::ListComp { generators, .. }
+        | Expr::SetComp { generators, .. }
+        | Expr::DictComp { generators, .. } if generators.len() == 1 => match generators[0].iter.as_ref() {
+            Expr::Name { id, .. } => Some((id.clone(), generators[0].ifs.len())),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[test]
+fn test_fuses_nested_comprehensions() {
+    let fused = fused_value("data = [1, 2, 3]\nresult = [x * 2 for x in [y for y in data if y > 1] if x < 10]\n");
+    assert_eq!(comprehension_source(&fused), Some(("data".to_string(), 2)));
+
+    match fused {
+        Expr::ListComp { elt, .. } => assert!(matches!(*elt, Expr::BinOp { .. })),
+        other => panic!("expected a list comprehension, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_fuses_chains_and_tuple_targets() {
+    let chain = fused_value("data = [1, 2]\nr = [a for a in [b for b in [c * 2 for c in data]]]\n");
+    assert_eq!(comprehension_source(&chain), Some(("data".to_string(), 0)));
+
+    let pairs = fused_value("data = [1, 2]\nr = {k: v for k, v in [(y, y * 2) for y in data]}\n");
+    assert_eq!(comprehension_source(&pairs), Some(("data".to_string(), 0)));
+}
+
+#[test]
+fn test_keeps_intermediate_when_fusion_is_unsafe() {
+    // The producer's element would be evaluated twice
+    let twice = fused_value("data = [1]\nr = [x + x for x in [y * 2 for y in data]]\n");
+    assert_eq!(comprehension_source(&twice), None);
+
+    // The outer `y` would be captured by the producer's loop variable
+    let shadowed = fused_value("y = 5\ndata = [1]\nr = [x + y for x in [y * 2 for y in data]]\n");
+    assert_eq!(comprehension_source(&shadowed), None);
+
+    // The loop variable is used inside a nested comprehension
+    let nested = fused_value("data = [1]\nr = [[x for i in data] for x in [y * 2 for y in data]]\n");
+    assert_eq!(comprehension_source(&nested), None);
+}
+
+#[test]
+fn test_fused_chains_compile() {
+    let source = r#"
+def double(x: int) -> int:
+    return x * 2
+
+def is_even(x: int) -> bool:
+    return x % 2 == 0
+
+data = [1, 2, 3, 4]
+squares = [x * x for x in [y + 1 for y in data if y > 1]]
+evens = map(double, filter(is_even, map(double, data)))
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Failed to compile fused chains: {:?}", result.err());
+}
@@ -0,0 +1,41 @@
+// number_format_test.rs - Tests for locale-independent float formatting
+
+use cheetah::compiler::runtime::number_format::format_float;
+use cheetah::compiler::runtime::string::float_to_string;
+use std::ffi::CString;
+
+#[test]
+fn test_fixed_notation_matches_python_repr() {
+    assert_eq!(format_float(1.0), "1.0");
+    assert_eq!(format_float(-2.5), "-2.5");
+    assert_eq!(format_float(0.1), "0.1");
+    assert_eq!(format_float(0.1 + 0.2), "0.30000000000000004");
+    assert_eq!(format_float(123456.789), "123456.789");
+    assert_eq!(format_float(0.0001), "0.0001");
+    assert_eq!(format_float(1e15), "1000000000000000.0");
+    assert_eq!(format_float(0.0), "0.0");
+    assert_eq!(format_float(-0.0), "-0.0");
+}
+
+#[test]
+fn test_scientific_notation_matches_python_repr() {
+    assert_eq!(format_float(1e16), "1e+16");
+    assert_eq!(format_float(1.5e20), "1.5e+20");
+    assert_eq!(format_float(0.00001), "1e-05");
+    assert_eq!(format_float(-1.25e-7), "-1.25e-07");
+    assert_eq!(format_float(f64::MAX), "1.7976931348623157e+308");
+    assert_eq!(format_float(5e-324), "5e-324");
+}
+
+#[test]
+fn test_special_values() {
+    assert_eq!(format_float(f64::INFINITY), "inf");
+    assert_eq!(format_float(f64::NEG_INFINITY), "-inf");
+    assert_eq!(format_float(f64::NAN), "nan");
+}
+
+#[test]
+fn test_float_to_string_uses_decimal_point() {
+    let text = unsafe { CString::from_raw(float_to_string(3.25)) };
+    assert_eq!(text.to_str().unwrap(), "3.25");
+}
@@ -0,0 +1,55 @@
+// optimizer_test.rs - Tests for the optimization pipelines
+
+use cheetah::compiler::optimizer::{codegen_level, pass_pipeline};
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use inkwell::context::Context;
+use inkwell::OptimizationLevel;
+
+#[test]
+fn test_levels_map_to_pipelines() {
+    assert_eq!(pass_pipeline(0), "default<O0>");
+    assert_eq!(pass_pipeline(2), "default<O2>");
+    assert_eq!(pass_pipeline(3), "default<O3>");
+    assert_eq!(pass_pipeline(9), "default<O3>");
+
+    assert_eq!(codegen_level(0), OptimizationLevel::None);
+    assert_eq!(codegen_level(1), OptimizationLevel::Less);
+    assert_eq!(codegen_level(3), OptimizationLevel::Aggressive);
+}
+
+#[test]
+fn test_optimization_simplifies_code() {
+    let source = r#"
+def square(x: int) -> int:
+    y = x * x
+    return y
+
+print(square(7))
+"#;
+    let ast = parse(source).expect("source should parse");
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "optimizer_test");
+    compiler.compile_module(&ast).expect("source should compile");
+
+    let before = compiler.get_ir();
+    compiler.optimize(2).expect("optimization should succeed");
+    let after = compiler.get_ir();
+
+    // Locals are promoted out of stack slots
+    assert!(before.contains("alloca"));
+    assert!(after.matches("alloca").count() < before.matches("alloca").count());
+    assert!(compiler.get_module().verify().is_ok());
+}
+
+#[test]
+fn test_opt_level_defaults_to_zero_and_is_clamped() {
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "optimizer_test");
+    assert_eq!(compiler.opt_level, 0);
+
+    compiler.set_opt_level(2);
+    assert_eq!(compiler.opt_level, 2);
+    compiler.set_opt_level(7);
+    assert_eq!(compiler.opt_level, 3);
+}
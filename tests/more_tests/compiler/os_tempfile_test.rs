@@ -0,0 +1,111 @@
+// os_tempfile_test.rs - Tests for the os.tempdir() and os.tempfile() helpers
+
+use cheetah::compiler::runtime::os::*;
+use cheetah::compiler::runtime::string::free_string;
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use inkwell::context::Context;
+use std::ffi::{CStr, CString};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// The runtime keeps one registry per process; cleanup() would race the other tests
+static REGISTRY_LOCK: Mutex<()> = Mutex::new(());
+
+pub fn compile_source(source: &str) -> Result<String, String> {
+    // Parse the source
+    let ast = match parse(source) {
+        Ok(ast) => ast,
+        Err(errors) => {
+            return Err(format!("Parse errors: {:?}", errors));
+        }
+    };
+
+    // Create a compiler
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "os_tempfile_test");
+
+    // Compile the AST
+    match compiler.compile_module(&ast) {
+        Ok(_) => Ok(compiler.get_ir()),
+        Err(e) => Err(format!("Compilation error: {}", e)),
+    }
+}
+
+fn take_path(ptr: *mut std::os::raw::c_char) -> (PathBuf, CString) {
+    let path = unsafe { CStr::from_ptr(ptr) }.to_owned();
+    free_string(ptr);
+    (PathBuf::from(path.to_str().unwrap()), path)
+}
+
+#[test]
+fn test_runtime_tempdir_removed_with_contents() {
+    let _guard = REGISTRY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let (dir, raw) = take_path(os_tempdir());
+    assert!(dir.is_dir());
+    std::fs::write(dir.join("scratch.txt"), "data").unwrap();
+    std::fs::create_dir(dir.join("nested")).unwrap();
+
+    assert!(os_temp_cleanup(raw.as_ptr()));
+    assert!(!dir.exists());
+
+    // A second cleanup of the same path is a no-op
+    assert!(!os_temp_cleanup(raw.as_ptr()));
+}
+
+#[test]
+fn test_runtime_tempfile_and_foreign_paths() {
+    let _guard = REGISTRY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let (file, raw) = take_path(os_tempfile());
+    assert!(file.is_file());
+    assert!(os_temp_cleanup(raw.as_ptr()));
+    assert!(!file.exists());
+
+    // Paths the helpers did not create are never removed
+    let foreign = std::env::temp_dir();
+    let foreign_raw = CString::new(foreign.to_str().unwrap()).unwrap();
+    assert!(!os_temp_cleanup(foreign_raw.as_ptr()));
+    assert!(foreign.is_dir());
+    assert!(!os_temp_cleanup(std::ptr::null()));
+}
+
+#[test]
+fn test_runtime_cleanup_removes_leftovers() {
+    let _guard = REGISTRY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let (first, _) = take_path(os_tempdir());
+    let (second, _) = take_path(os_tempfile());
+    assert!(os_temp_live_count() >= 2);
+
+    cleanup();
+    assert!(!first.exists());
+    assert!(!second.exists());
+}
+
+#[test]
+fn test_tempdir_with_compile() {
+    let source = r#"
+def scratch(n: int) -> int:
+    with os.tempdir() as path:
+        if n > 1:
+            return n
+        print(path)
+    return 0
+
+for i in range(3):
+    with os.tempfile() as name:
+        if i == 1:
+            break
+        print(name)
+
+leftover = os.tempdir()
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Failed to compile temp helpers: {:?}", result.err());
+}
+
+#[test]
+fn test_os_helper_errors() {
+    let with_args = compile_source("p = os.tempdir(\"x\")\n");
+    assert!(with_args.is_err());
+}
@@ -0,0 +1,43 @@
+// output_test.rs - Tests for stdout newline translation and output configuration
+
+use cheetah::compiler::runtime::output::{set_newline, translate_newlines, Newline};
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use inkwell::context::Context;
+
+#[test]
+fn test_newline_modes() {
+    assert_eq!(Newline::from_name("lf"), Ok(Newline::Lf));
+    assert_eq!(Newline::from_name("crlf"), Ok(Newline::CrLf));
+    assert_eq!(Newline::from_name("native"), Ok(Newline::Native));
+    assert!(Newline::from_name("cr").is_err());
+
+    for newline in [Newline::Lf, Newline::CrLf, Newline::Native] {
+        assert_eq!(Newline::from_code(newline.code()), newline);
+    }
+    assert!(!Newline::Lf.translates());
+    assert!(Newline::CrLf.translates());
+    assert_eq!(Newline::Native.translates(), cfg!(windows));
+}
+
+#[test]
+fn test_translate_newlines() {
+    set_newline(Newline::CrLf);
+    assert_eq!(&*translate_newlines(b"a\nb\n"), b"a\r\nb\r\n");
+    assert_eq!(&*translate_newlines(b"no newline"), b"no newline");
+
+    set_newline(Newline::Lf);
+    assert_eq!(&*translate_newlines(b"a\nb\n"), b"a\nb\n");
+}
+
+#[test]
+fn test_programs_configure_output_at_startup() {
+    let ast = parse("print(\"hi\")\n").expect("source should parse");
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "output_test");
+    compiler.newline = Newline::CrLf;
+    compiler.compile_module(&ast).expect("source should compile");
+
+    let expected = format!("call void @cheetah_configure_output(i64 {})", Newline::CrLf.code());
+    assert!(compiler.get_ir().contains(&expected));
+}
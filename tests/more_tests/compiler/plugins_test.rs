@@ -0,0 +1,79 @@
+// plugins_test.rs - Tests for builtins registered by embedding crates
+//
+// The registry is shared by the whole test binary, so every builtin here has
+// a name no other test uses.
+
+use cheetah::compiler::plugins::{lookup_builtin, register_builtin, ConstValue, PluginBuiltin};
+use cheetah::compiler::types::Type;
+use cheetah::parse;
+
+fn fold_double(args: &[ConstValue]) -> Option<ConstValue> {
+    match args {
+        [ConstValue::Float(x)] => Some(ConstValue::Float(x * 2.0)),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_register_and_lookup() {
+    register_builtin(PluginBuiltin::new(
+        "plugin_lookup_test",
+        "plugin_lookup_impl",
+        vec![Type::Int],
+        Type::None,
+    ))
+    .unwrap();
+
+    let builtin = lookup_builtin("plugin_lookup_test").unwrap();
+    assert_eq!(builtin.signature.symbol, "plugin_lookup_impl");
+    assert_eq!(builtin.function_type(), Type::function(vec![Type::Int], Type::None));
+    assert!(lookup_builtin("plugin_never_registered").is_none());
+}
+
+#[test]
+fn test_invalid_builtins_are_rejected() {
+    let bad_name = PluginBuiltin::new("not valid", "sym", vec![], Type::Int);
+    assert!(register_builtin(bad_name).is_err());
+    let keyword = PluginBuiltin::new("while", "sym", vec![], Type::Int);
+    assert!(register_builtin(keyword).is_err());
+    let list_param = PluginBuiltin::new("plugin_list", "sym", vec![Type::List(Box::new(Type::Int))], Type::Int);
+    assert!(register_builtin(list_param).is_err());
+    let no_symbol = PluginBuiltin::new("plugin_nosym", "", vec![], Type::Int);
+    assert!(register_builtin(no_symbol).is_err());
+}
+
+#[test]
+fn test_const_eval_folds_literal_calls() {
+    let builtin = PluginBuiltin::new("plugin_fold_test", "plugin_fold_impl", vec![Type::Float], Type::Float)
+        .with_const_eval(fold_double);
+
+    let call = |source: &str| {
+        let module = parse(source).unwrap();
+        match module.body[0].as_ref() {
+            cheetah::ast::Stmt::Expr { value, .. } => match value.as_ref() {
+                cheetah::ast::Expr::Call { args, .. } => builtin.fold_call(args),
+                _ => panic!("expected a call"),
+            },
+            _ => panic!("expected an expression"),
+        }
+    };
+    assert_eq!(call("plugin_fold_test(1.5)\n"), Some(ConstValue::Float(3.0)));
+    assert_eq!(call("plugin_fold_test(x)\n"), None);
+    // A result of the wrong type is not used
+    assert_eq!(call("plugin_fold_test(1)\n"), None);
+}
+
+#[test]
+fn test_type_checker_knows_registered_builtins() {
+    let module = parse("y = plugin_typed_test(2.0) + 1.0\n").unwrap();
+    assert!(cheetah::typechecker::check_module(&module).is_err());
+
+    register_builtin(PluginBuiltin::new(
+        "plugin_typed_test",
+        "plugin_typed_impl",
+        vec![Type::Float],
+        Type::Float,
+    ))
+    .unwrap();
+    assert!(cheetah::typechecker::check_module(&module).is_ok());
+}
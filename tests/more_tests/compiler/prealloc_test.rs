@@ -0,0 +1,71 @@
+// prealloc_test.rs - Tests for preallocating comprehension results of known length
+
+use cheetah::ast::Stmt;
+use cheetah::compiler::prealloc::literal_length;
+use cheetah::compiler::runtime::list::{list_free, list_get, list_len, list_push_presized, list_with_capacity, TypeTag};
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use inkwell::context::Context;
+use std::ffi::c_void;
+
+fn compile_ir(source: &str) -> String {
+    let ast = parse(source).expect("source should parse");
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "prealloc_test");
+    compiler.compile_module(&ast).expect("source should compile");
+    compiler.get_ir()
+}
+
+#[test]
+fn test_literal_lengths() {
+    let module = parse("[1, 2, 3]\n(1, 2)\n{1, 2, 3, 4}\n\"abc\"\nxs\n").expect("source should parse");
+    let lengths: Vec<Option<u64>> = module
+        .body
+        .iter()
+        .map(|stmt| match stmt.as_ref() {
+            Stmt::Expr { value, .. } => literal_length(value),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(lengths, vec![Some(3), Some(2), Some(4), Some(3), None]);
+}
+
+#[test]
+fn test_push_presized_fills_capacity() {
+    let list = list_with_capacity(3);
+    for value in [10i64, 20, 30] {
+        let boxed = unsafe { libc::malloc(8) as *mut i64 };
+        unsafe { *boxed = value };
+        unsafe { list_push_presized(list, boxed as *mut c_void, TypeTag::Int) };
+    }
+    assert_eq!(list_len(list), 3);
+    assert_eq!(unsafe { *(list_get(list, 2) as *const i64) }, 30);
+    list_free(list);
+}
+
+#[test]
+fn test_literal_comprehension_is_preallocated() {
+    let ir = compile_ir("squares = [x * x for x in [1, 2, 3, 4]]\n");
+    assert!(ir.contains("@list_with_capacity(i64 4)"));
+    assert!(ir.contains("@list_push_presized("));
+}
+
+#[test]
+fn test_constant_range_comprehension_is_preallocated() {
+    let ir = compile_ir("N = 50\nevens = [i * 2 for i in range(0, N, 5) if i > 10]\n");
+    assert!(ir.contains("@list_with_capacity(i64 10)"));
+    assert!(ir.contains("@list_push_presized("));
+}
+
+#[test]
+fn test_constant_range_dict_comprehension_is_preallocated() {
+    let ir = compile_ir("table = {i: i * i for i in range(12)}\n");
+    assert!(ir.contains("@dict_with_capacity(i64 12)"));
+}
+
+#[test]
+fn test_unknown_length_comprehension_appends_with_checks() {
+    let ir = compile_ir("def f(n: int) -> list:\n    return [i * 2 for i in range(n)]\n");
+    assert!(!ir.contains("@list_push_presized("));
+    assert!(ir.contains("@list_append_tagged("));
+}
@@ -0,0 +1,109 @@
+// print_threads_test.rs - Tests that output printed from many threads at once stays whole lines
+
+use cheetah::compiler::runtime::{buffer, parallel_ops, print_ops};
+use std::ffi::CString;
+use std::process::Command;
+
+const THREADS: usize = 16;
+const LINES: usize = 400;
+/// Set in the child process that does the printing
+const CHILD_ENV: &str = "CHEETAH_PRINT_THREADS_CHILD";
+
+fn print(text: &str) {
+    let text = CString::new(text).unwrap();
+    print_ops::print_string(text.as_ptr());
+}
+
+/// Print one line from several runtime calls, the way compiled `print` does.
+/// Every tenth line is long enough to fill the buffer mid-line; the others
+/// stay buffered until a later line fills it or the thread flushes.
+fn print_line(thread: usize, line: usize) {
+    print("thread ");
+    print_ops::print_int(thread as i64);
+    print(" line ");
+    print_ops::print_int(line as i64);
+    print(" ");
+    let width = if line % 10 == 0 { 6000 } else { 40 };
+    for _ in 0..width / 20 {
+        print(&"x".repeat(20));
+    }
+    print("\n");
+}
+
+/// Run `test` of this module alone in a child process and return what it printed
+fn run_child(test: &str) -> String {
+    let module = module_path!().split_once("::").map_or("", |(_, module)| module);
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args([&format!("{}::{}", module, test), "--exact", "--nocapture", "--test-threads=1"])
+        .env(CHILD_ENV, "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Check that every line printed is whole and each thread's lines are in order
+fn check_lines(stdout: &str, threads: usize, lines: usize) {
+    let mut next = vec![0; threads];
+    for text in stdout.lines().filter(|line| line.starts_with("thread ")) {
+        let mut words = text.split(' ');
+        let (Some("thread"), Some(thread), Some("line"), Some(line), Some(xs), None) = (
+            words.next(),
+            words.next(),
+            words.next(),
+            words.next(),
+            words.next(),
+            words.next(),
+        ) else {
+            panic!("interleaved line: {:.120}", text);
+        };
+        let (thread, line): (usize, usize) = (thread.parse().unwrap(), line.parse().unwrap());
+        assert_eq!(line, next[thread], "thread {}'s lines out of order", thread);
+        assert_eq!(xs.len(), if line % 10 == 0 { 6000 } else { 40 });
+        assert!(xs.bytes().all(|b| b == b'x'), "interleaved line: {:.120}", text);
+        next[thread] += 1;
+    }
+    assert_eq!(next, vec![lines; threads]);
+}
+
+#[test]
+fn test_print_from_many_threads_keeps_lines_whole() {
+    if std::env::var_os(CHILD_ENV).is_some() {
+        // End the line the test harness started with the test's name
+        print("\n");
+        buffer::flush();
+        std::thread::scope(|scope| {
+            for thread in 0..THREADS {
+                scope.spawn(move || {
+                    for line in 0..LINES {
+                        print_line(thread, line);
+                    }
+                    buffer::flush();
+                });
+            }
+        });
+        return;
+    }
+    let stdout = run_child("test_print_from_many_threads_keeps_lines_whole");
+    check_lines(&stdout, THREADS, LINES);
+}
+
+#[test]
+fn test_parallel_loop_output_is_ordered_around_the_loop() {
+    if std::env::var_os(CHILD_ENV).is_some() {
+        print("\nbefore\n");
+        // The workers' buffers hold the last lines each printed until the loop flushes them
+        parallel_ops::parallel_range_for_each(0, 2000, 1, |i| print_line(0, i as usize * 10 + 1));
+        print("after\n");
+        buffer::flush();
+        return;
+    }
+    let stdout = run_child("test_parallel_loop_output_is_ordered_around_the_loop");
+    let lines: Vec<&str> = stdout
+        .lines()
+        .filter(|line| matches!(*line, "before" | "after") || line.starts_with("thread "))
+        .collect();
+    assert_eq!(lines.first(), Some(&"before"));
+    assert_eq!(lines.last(), Some(&"after"));
+    assert_eq!(lines.iter().filter(|line| line.starts_with("thread 0 line ")).count(), 2000);
+}
@@ -0,0 +1,156 @@
+// project_test.rs - Tests for cheetah.toml project manifests
+
+use cheetah::project::{Project, MANIFEST_NAME};
+use std::fs;
+use std::path::PathBuf;
+
+/// Create a fresh project directory holding `manifest` and the given files
+fn make_project(name: &str, manifest: &str, files: &[&str]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("cheetah_project_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join(MANIFEST_NAME), manifest).unwrap();
+    for file in files {
+        fs::write(dir.join(file), "print(\"hi\")\n").unwrap();
+    }
+    dir
+}
+
+#[test]
+fn test_manifest_defaults() {
+    let manifest = Project::parse_manifest("[package]\nname = \"app\"\n").unwrap();
+    assert_eq!(manifest.package.name, "app");
+    assert!(manifest.package.entry.is_none());
+    assert_eq!(manifest.build.opt, 0);
+    assert!(!manifest.build.debug);
+    assert!(manifest.build.link_libs.is_empty());
+    assert!(manifest.build.newline.is_none());
+}
+
+#[test]
+fn test_manifest_build_settings() {
+    let manifest = Project::parse_manifest(
+        "[package]\nname = \"app\"\nentry = \"app.ch\"\n\n\
+         [build]\nopt = 2\ndebug = true\nlink-libs = [\"m\"]\nlink-paths = [\"lib\"]\nnewline = \"lf\"\n",
+    )
+    .unwrap();
+    assert_eq!(manifest.package.entry.as_deref(), Some("app.ch"));
+    assert_eq!(manifest.build.opt, 2);
+    assert!(manifest.build.debug);
+    assert_eq!(manifest.build.link_libs, vec!["m".to_string()]);
+    assert_eq!(manifest.build.link_paths, vec!["lib".to_string()]);
+    assert_eq!(manifest.build.newline.as_deref(), Some("lf"));
+}
+
+#[test]
+fn test_manifest_errors() {
+    assert!(Project::parse_manifest("[build]\nopt = 1\n").is_err());
+    assert!(Project::parse_manifest("[package]\nname = \"app\"\n[build]\nopt = 7\n").is_err());
+    assert!(Project::parse_manifest("[package]\nname = \"app\"\nversion = 1\n").is_err());
+}
+
+#[test]
+fn test_manifest_language_settings() {
+    use cheetah::lexer::Construct;
+
+    let manifest = Project::parse_manifest("[package]\nname = \"app\"\n").unwrap();
+    assert!(manifest.language.forbidden().unwrap().is_empty());
+
+    let manifest = Project::parse_manifest(
+        "[package]\nname = \"app\"\n\n\
+         [language]\nprofile = \"learning\"\nforbid = [\"yield\"]\nallow = [\"lambda\", \"async\"]\n",
+    )
+    .unwrap();
+    let forbidden = manifest.language.forbidden().unwrap();
+    assert!(forbidden.contains(&Construct::Yield));
+    assert!(forbidden.contains(&Construct::Walrus));
+    assert!(!forbidden.contains(&Construct::Lambda));
+    assert!(!forbidden.contains(&Construct::Async));
+
+    let err = Project::parse_manifest("[package]\nname = \"app\"\n[language]\nforbid = [\"goto\"]\n")
+        .unwrap_err();
+    assert!(err.contains("goto"));
+    assert!(Project::parse_manifest("[package]\nname = \"app\"\n[language]\nprofile = \"x\"\n").is_err());
+}
+
+#[test]
+fn test_manifest_format_and_lint_settings() {
+    use cheetah::linter::Severity;
+    use cheetah::typechecker::WarningKind;
+
+    let manifest = Project::parse_manifest("[package]\nname = \"app\"\n").unwrap();
+    assert_eq!(manifest.format.indent_size, 4);
+    assert_eq!(manifest.format.tab_width, 4);
+    assert!(manifest.format.line_width.is_none());
+    assert!(!manifest.format.allow_tabs);
+
+    let manifest = Project::parse_manifest(
+        "[package]\nname = \"app\"\n\n\
+         [format]\nindent-size = 2\nline-width = 100\ntab-width = 8\nallow-tabs = true\n\n\
+         [lint]\nunused-variable = \"error\"\nall = \"allow\"\n",
+    )
+    .unwrap();
+    assert_eq!(manifest.format.indent_size, 2);
+    assert_eq!(manifest.format.line_width, Some(100));
+    let lints = manifest.lint_config().unwrap();
+    // `all` applies first, wherever it's written
+    assert_eq!(lints.severity(WarningKind::UnusedVariable), Severity::Deny);
+    assert_eq!(lints.severity(WarningKind::UnusedImport), Severity::Allow);
+
+    let err = Project::parse_manifest("[package]\nname = \"app\"\n[lint]\nunused-variable = \"loud\"\n")
+        .unwrap_err();
+    assert!(err.contains("loud"));
+    assert!(Project::parse_manifest("[package]\nname = \"app\"\n[lint]\nno-such-rule = \"warn\"\n").is_err());
+    assert!(Project::parse_manifest("[package]\nname = \"app\"\n[format]\nindent-size = 0\n").is_err());
+    assert!(Project::parse_manifest("[package]\nname = \"app\"\n[format]\ntabs = true\n").is_err());
+}
+
+#[test]
+fn test_project_lexer_config() {
+    let dir = make_project(
+        "lexer_config",
+        "[package]\nname = \"app\"\n[format]\nindent-size = 2\nallow-tabs = true\n[language]\nforbid = [\"lambda\"]\n",
+        &["src/main.ch"],
+    );
+    let project = Project::containing(&dir.join("src/main.ch")).unwrap().unwrap();
+    let config = project.lexer_config();
+    assert_eq!(config.standard_indent_size, 2);
+    assert!(config.allow_tabs_in_indentation);
+    assert!(config.forbids(cheetah::lexer::Construct::Lambda));
+
+    let mut lexer = cheetah::lexer::Lexer::with_config("if x:\n  y = 1\n", config);
+    lexer.tokenize();
+    assert!(lexer.get_errors().is_empty());
+}
+
+#[test]
+fn test_containing_project() {
+    let dir = make_project("containing", "[package]\nname = \"app\"\n", &["src/main.ch"]);
+    let project = Project::containing(&dir.join("src/main.ch")).unwrap().unwrap();
+    assert_eq!(project.manifest.package.name, "app");
+    assert!(Project::containing(&dir.join("src/missing.ch")).unwrap().is_none());
+}
+
+#[test]
+fn test_entry_resolution() {
+    let dir = make_project("default", "[package]\nname = \"app\"\n", &["src/main.ch"]);
+    let project = Project::load(&dir).unwrap();
+    assert_eq!(project.entry_path().unwrap(), dir.join("src/main.ch"));
+
+    fs::write(dir.join("main.ch"), "print(1)\n").unwrap();
+    assert_eq!(project.entry_path().unwrap(), dir.join("main.ch"));
+
+    let dir = make_project("explicit", "[package]\nname = \"app\"\nentry = \"app.ch\"\n", &[]);
+    let project = Project::load(&dir).unwrap();
+    assert!(project.entry_path().is_err());
+    fs::write(dir.join("app.ch"), "print(1)\n").unwrap();
+    assert_eq!(project.entry_path().unwrap(), dir.join("app.ch"));
+}
+
+#[test]
+fn test_missing_manifest() {
+    let dir = std::env::temp_dir().join(format!("cheetah_project_none_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let err = Project::load(&dir).unwrap_err();
+    assert!(err.contains(MANIFEST_NAME));
+}
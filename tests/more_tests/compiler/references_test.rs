@@ -0,0 +1,86 @@
+// references_test.rs - Tests for finding references and renaming
+
+use cheetah::references::{apply_edits, find_references, rename};
+
+const SOURCE: &str = "\
+import math
+total = 0
+
+def scale(value, factor=2):
+    global total
+    total = total + value * factor
+    return math.sqrt(value)
+
+def other(value):
+    return value
+
+class Shape:
+    def scale(self):
+        return scale(1)
+";
+
+#[test]
+fn test_find_references_follows_scopes() {
+    let positions = |name: &str, line: usize, column: usize| -> Vec<(usize, usize, bool)> {
+        find_references(SOURCE, name, line, column)
+            .unwrap()
+            .iter()
+            .map(|reference| (reference.line, reference.column, reference.is_definition))
+            .collect()
+    };
+
+    // The global declaration and both uses in scale refer to the module's total
+    assert_eq!(
+        positions("total", 2, 1),
+        [(2, 1, true), (5, 12, false), (6, 5, true), (6, 13, false)]
+    );
+
+    // scale's parameter, not other's
+    assert_eq!(
+        positions("value", 6, 21),
+        [(4, 11, true), (6, 21, false), (7, 22, false)]
+    );
+
+    // The module function, not the method of the same name
+    assert_eq!(positions("scale", 14, 16), [(4, 5, true), (14, 16, false)]);
+
+    assert!(find_references(SOURCE, "missing", 1, 1).is_err());
+}
+
+#[test]
+fn test_rename_edits_every_reference() {
+    let edits = rename(SOURCE, 10, 12, "item").unwrap();
+    assert_eq!(edits.len(), 2);
+    let renamed = apply_edits(SOURCE, &edits);
+    assert!(renamed.contains("def other(item):\n    return item\n"));
+    assert!(renamed.contains("def scale(value, factor=2):"));
+
+    let renamed = apply_edits(SOURCE, &rename(SOURCE, 4, 18, "times").unwrap());
+    assert!(renamed.contains("def scale(value, times=2):"));
+    assert!(renamed.contains("total + value * times\n"));
+}
+
+#[test]
+fn test_rename_imports_and_definitions() {
+    let renamed = apply_edits(SOURCE, &rename(SOURCE, 7, 12, "m").unwrap());
+    assert!(renamed.starts_with("import math as m\n"));
+    assert!(renamed.contains("return m.sqrt(value)"));
+
+    let renamed = apply_edits(SOURCE, &rename(SOURCE, 4, 5, "resize").unwrap());
+    assert!(renamed.contains("def resize(value, factor=2):"));
+    assert!(renamed.contains("    def scale(self):\n        return resize(1)\n"));
+
+    let renamed = apply_edits(SOURCE, &rename(SOURCE, 6, 5, "sum_so_far").unwrap());
+    assert!(renamed.contains("sum_so_far = 0\n"));
+    assert!(renamed.contains("global sum_so_far\n"));
+    assert!(renamed.contains("sum_so_far = sum_so_far + value"));
+}
+
+#[test]
+fn test_rename_rejects_bad_names() {
+    assert!(rename(SOURCE, 10, 12, "while").is_err());
+    assert!(rename(SOURCE, 10, 12, "2x").is_err());
+    assert!(rename(SOURCE, 2, 1, "math").unwrap_err().contains("already defined"));
+    assert!(rename(SOURCE, 3, 1, "blank").is_err());
+    assert!(rename(SOURCE, 2, 1, "total").unwrap().is_empty());
+}
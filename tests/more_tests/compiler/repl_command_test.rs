@@ -0,0 +1,38 @@
+// repl_command_test.rs - Tests for parsing REPL meta-commands
+
+use cheetah::repl_command::MetaCommand;
+use std::path::PathBuf;
+
+#[test]
+fn test_code_is_not_a_command() {
+    assert_eq!(MetaCommand::parse("x = 1"), None);
+    assert_eq!(MetaCommand::parse("tokens = [1]"), None);
+}
+
+#[test]
+fn test_parse_commands() {
+    assert_eq!(MetaCommand::parse(":ast"), Some(Ok(MetaCommand::Ast)));
+    assert_eq!(MetaCommand::parse(":ir"), Some(Ok(MetaCommand::Ir)));
+    assert_eq!(MetaCommand::parse(":paste"), Some(Ok(MetaCommand::Paste)));
+    assert_eq!(MetaCommand::parse(":help"), Some(Ok(MetaCommand::Help)));
+    assert_eq!(
+        MetaCommand::parse(":type  len([1, 2]) "),
+        Some(Ok(MetaCommand::Type("len([1, 2])".to_string())))
+    );
+    assert_eq!(MetaCommand::parse(":tokens"), Some(Ok(MetaCommand::Tokens(None))));
+    assert_eq!(
+        MetaCommand::parse(":tokens x + 1"),
+        Some(Ok(MetaCommand::Tokens(Some("x + 1".to_string()))))
+    );
+    assert_eq!(
+        MetaCommand::parse(":load lib/util.ch"),
+        Some(Ok(MetaCommand::Load(PathBuf::from("lib/util.ch"))))
+    );
+}
+
+#[test]
+fn test_command_errors() {
+    assert!(matches!(MetaCommand::parse(":type"), Some(Err(_))));
+    assert!(matches!(MetaCommand::parse(":load"), Some(Err(_))));
+    assert!(matches!(MetaCommand::parse(":frobnicate"), Some(Err(e)) if e.contains(":help")));
+}
@@ -0,0 +1,36 @@
+// repl_config_test.rs - Tests for REPL prompts and the startup script
+
+use cheetah::repl_config::{ReplConfig, DEFAULT_PS1, DEFAULT_PS2};
+
+#[test]
+fn test_default_prompts() {
+    let config = ReplConfig::default();
+    assert_eq!(config.ps1, DEFAULT_PS1);
+    assert_eq!(config.ps2, DEFAULT_PS2);
+    assert!(config.startup.is_none());
+}
+
+#[test]
+fn test_startup_script_sets_prompts() {
+    let config = ReplConfig::from_source(
+        "ps1 = \"cheetah> \"\n\
+         def square(x: int) -> int:\n    return x * x\n",
+    )
+    .unwrap();
+    assert_eq!(config.ps1, "cheetah> ");
+    assert_eq!(config.ps2, DEFAULT_PS2);
+    assert_eq!(config.startup.unwrap().body.len(), 2);
+}
+
+#[test]
+fn test_startup_script_parse_error() {
+    assert!(ReplConfig::from_source("def broken(:\n").is_err());
+}
+
+#[test]
+fn test_history_path_is_in_home() {
+    if let Some(path) = ReplConfig::history_path() {
+        assert_eq!(path.file_name().unwrap(), ".cheetah_history");
+        assert_eq!(path.parent(), ReplConfig::default_path().unwrap().parent());
+    }
+}
@@ -0,0 +1,67 @@
+// repl_test.rs - Tests for the JIT REPL's state across cells
+
+use cheetah::compiler::repl::ReplSession;
+use cheetah::parse;
+use inkwell::context::Context;
+
+fn run_cell(session: &mut ReplSession<'_>, source: &str) -> Result<(), String> {
+    let module = parse(source).expect("cell should parse");
+    session.run_cell(&module)
+}
+
+#[test]
+fn test_variables_persist_between_cells() {
+    let context = Context::create();
+    let mut session = ReplSession::new(&context, 0);
+
+    run_cell(&mut session, "x = 5\n").unwrap();
+    run_cell(&mut session, "y = x + 1\n").unwrap();
+    run_cell(&mut session, "x = y * 2\nprint(x)\n").unwrap();
+}
+
+#[test]
+fn test_functions_persist_between_cells() {
+    let context = Context::create();
+    let mut session = ReplSession::new(&context, 0);
+
+    run_cell(&mut session, "def double(n: int) -> int:\n    return n * 2\n").unwrap();
+    run_cell(&mut session, "z = double(21)\n").unwrap();
+    run_cell(&mut session, "def double(n: int) -> int:\n    return n + n\n").unwrap();
+    run_cell(&mut session, "print(double(z))\n").unwrap();
+}
+
+#[test]
+fn test_failed_cell_defines_nothing() {
+    let context = Context::create();
+    let mut session = ReplSession::new(&context, 0);
+
+    assert!(run_cell(&mut session, "w = missing + 1\n").is_err());
+    assert!(run_cell(&mut session, "print(w)\n").is_err());
+}
+
+#[test]
+fn test_expression_cells_echo_their_value() {
+    let context = Context::create();
+    let mut session = ReplSession::new(&context, 0);
+
+    run_cell(&mut session, "1 + 2\n").unwrap();
+    run_cell(&mut session, "\"abc\"\n").unwrap();
+    run_cell(&mut session, "n = 4\n").unwrap();
+    run_cell(&mut session, "n * 2\n").unwrap();
+    run_cell(&mut session, "print(n)\n").unwrap();
+}
+
+#[test]
+fn test_inspecting_the_session() {
+    let context = Context::create();
+    let mut session = ReplSession::new(&context, 0);
+    assert!(session.last_ir().is_none());
+
+    run_cell(&mut session, "ratio = 1.5\n").unwrap();
+    let module = parse("ratio * 2\n").unwrap();
+    let cheetah::ast::Stmt::Expr { value, .. } = module.body[0].as_ref() else {
+        panic!("expected an expression");
+    };
+    assert_eq!(session.expression_type(value), Some(cheetah::compiler::types::Type::Float));
+    assert!(session.last_ir().unwrap().contains("__repl_cell_1"));
+}
@@ -0,0 +1,35 @@
+// runtime_abi_test.rs - Tests for the runtime ABI version check
+
+use cheetah::compiler::runtime::abi::*;
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use inkwell::context::Context;
+
+#[test]
+fn test_matching_abi_passes() {
+    assert_eq!(cheetah_runtime_abi_version(), RUNTIME_ABI_VERSION);
+    assert_eq!(abi_mismatch_message(RUNTIME_ABI_VERSION), None);
+
+    // Returns normally when the versions agree
+    cheetah_check_abi(RUNTIME_ABI_VERSION);
+}
+
+#[test]
+fn test_mismatched_abi_message() {
+    let message = abi_mismatch_message(RUNTIME_ABI_VERSION + 1).expect("versions differ");
+    assert!(message.contains("runtime ABI mismatch"));
+    assert!(message.contains(&format!("version {}", RUNTIME_ABI_VERSION + 1)));
+    assert!(message.contains(&format!("provides version {}", RUNTIME_ABI_VERSION)));
+}
+
+#[test]
+fn test_main_checks_abi_at_startup() {
+    let ast = parse("x = 1\n").expect("source should parse");
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "runtime_abi_test");
+    compiler.compile_module(&ast).expect("source should compile");
+
+    let ir = compiler.get_ir();
+    let expected = format!("call void @cheetah_check_abi(i64 {})", RUNTIME_ABI_VERSION);
+    assert!(ir.contains(&expected));
+}
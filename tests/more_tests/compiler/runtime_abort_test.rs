@@ -0,0 +1,119 @@
+// runtime_abort_test.rs - Tests for runtime errors that stop the program
+
+use cheetah::compiler::runtime::abort::*;
+use cheetah::compiler::runtime::traceback::{set_source_name, trace_pop_frames, trace_push_frame, trace_set_line};
+use cheetah::compiler::Compiler;
+use cheetah::engine::Engine;
+use cheetah::parse;
+use inkwell::context::Context;
+use std::ffi::CString;
+use std::process::Command;
+
+/// Set in the child process that aborts
+const CHILD_ENV: &str = "CHEETAH_RUNTIME_ABORT_CHILD";
+
+pub fn compile_source(source: &str) -> Result<String, String> {
+    let ast = parse(source).map_err(|errors| format!("Parse errors: {:?}", errors))?;
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "runtime_abort_test");
+    compiler
+        .compile_module(&ast)
+        .map_err(|e| format!("Compilation error: {}", e))?;
+    Ok(compiler.get_ir())
+}
+
+fn abort_with(message: &str) {
+    let message = CString::new(message).unwrap();
+    cheetah_runtime_abort(message.as_ptr());
+}
+
+#[test]
+fn test_caught_abort_becomes_an_error_with_its_location() {
+    set_source_name("shapes.ch");
+    let name = CString::new("unpack").unwrap();
+    let depth = trace_push_frame(name.as_ptr());
+    trace_set_line(12);
+
+    let result = catch_abort(|| {
+        abort_with("list length does not match number of targets");
+        // Compiled code returns at its next statement
+        assert!(trace_set_line(13));
+        assert!(aborting());
+    });
+    trace_pop_frames(depth);
+
+    let error = result.unwrap_err();
+    assert_eq!(error.location, Some(("shapes.ch".to_string(), 12, "unpack".to_string())));
+    assert_eq!(
+        error.to_string(),
+        "shapes.ch:12: runtime error in unpack: list length does not match number of targets"
+    );
+    assert!(!aborting());
+    assert_eq!(catch_abort(|| 42), Ok(42));
+}
+
+#[test]
+fn test_only_the_first_abort_is_reported() {
+    let error = catch_abort(|| {
+        abort_with("first");
+        abort_with("second");
+    })
+    .unwrap_err();
+    assert_eq!(error.to_string(), "runtime error: first");
+}
+
+#[test]
+fn test_uncaught_abort_exits_with_its_code() {
+    if std::env::var_os(CHILD_ENV).is_some() {
+        abort_with("list length does not match number of targets");
+        unreachable!("the abort exits");
+    }
+    let module = module_path!().split_once("::").map_or("", |(_, module)| module);
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args([
+            &format!("{}::test_uncaught_abort_exits_with_its_code", module),
+            "--exact",
+            "--nocapture",
+            "--test-threads=1",
+        ])
+        .env(CHILD_ENV, "1")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(EXIT_CODE));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.lines().any(|line| line == "runtime error: list length does not match number of targets"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_compile_unpacking_check_calls_the_runtime_abort() {
+    let ir = compile_source("values = [1, 2, 3]\na, b = values\n").unwrap();
+    assert!(ir.contains("@cheetah_runtime_abort("));
+    assert!(!ir.contains("@abort("));
+}
+
+const NESTED_ABORT: &str = r#"
+def pair():
+    values = [1, 2, 3]
+    a, b = values
+    return [a, b]
+
+def first() -> int:
+    return pair()[0]
+"#;
+
+#[test]
+fn test_abort_in_nested_call_reaches_the_host() {
+    // pair() returns a null list after aborting; first() must return before
+    // indexing it, so the host sees the error instead of a crash
+    let context = Context::create();
+    let mut engine = Engine::new(&context);
+    engine.load(NESTED_ABORT).expect("program should load");
+
+    let error = engine.call("first", &[]).unwrap_err();
+    assert!(error.contains("list length does not match number of targets"), "{}", error);
+    assert!(!aborting());
+}
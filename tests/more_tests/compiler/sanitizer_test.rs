@@ -0,0 +1,41 @@
+// sanitizer_test.rs - Tests for sanitizer instrumentation of AOT builds
+
+use cheetah::compiler::sanitizer::Sanitizer;
+use cheetah::compiler::target::TargetSpec;
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use inkwell::context::Context;
+
+#[test]
+fn test_sanitizer_names() {
+    assert_eq!(Sanitizer::from_name("address"), Ok(Sanitizer::Address));
+    assert_eq!(Sanitizer::from_name("undefined"), Ok(Sanitizer::Undefined));
+    assert!(Sanitizer::from_name("thread").is_err());
+
+    assert_eq!(Sanitizer::Address.name(), "address");
+    assert_eq!(Sanitizer::Undefined.runtime_target_dir(), "ubsan");
+}
+
+#[test]
+fn test_address_sanitizer_instruments_functions() {
+    let source = r#"
+def first(xs: list) -> int:
+    return xs[0]
+
+print(first([1, 2, 3]))
+"#;
+    let ast = parse(source).expect("source should parse");
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "sanitizer_test");
+    compiler.compile_module(&ast).expect("source should compile");
+
+    compiler.sanitizers = vec![Sanitizer::Address];
+    let machine = TargetSpec::host()
+        .create_target_machine(inkwell::OptimizationLevel::None)
+        .expect("host target should be available");
+    compiler.apply_sanitizers(&machine).expect("instrumentation should succeed");
+
+    let ir = compiler.get_ir();
+    assert!(ir.contains("sanitize_address"));
+    assert!(ir.contains("__asan"));
+}
@@ -0,0 +1,108 @@
+// semantic_tokens_test.rs - Tests for classifying source for syntax highlighting
+
+use cheetah::semantic_tokens::{encode, legend, semantic_tokens, TokenKind};
+
+const SOURCE: &str = "\
+import math
+
+@cache
+def area(shape, scale=2):  # doubled
+    total = shape.width * len(shape.sides)
+    return math.sqrt(total)
+
+class Shape:
+    sides = 4
+    def grow(self):
+        return area(self, scale=3)
+";
+
+/// The kind and modifiers of each range, with its text
+fn classified(source: &str) -> Vec<(String, &'static str, Vec<&'static str>)> {
+    semantic_tokens(source)
+        .unwrap()
+        .iter()
+        .map(|token| {
+            (
+                source[token.span.start..token.span.end].to_string(),
+                token.kind.name(),
+                token.modifiers(),
+            )
+        })
+        .collect()
+}
+
+fn kind_of(source: &str, text: &str, nth: usize) -> (&'static str, Vec<&'static str>) {
+    classified(source)
+        .into_iter()
+        .filter(|(range, ..)| range == text)
+        .nth(nth)
+        .map(|(_, kind, modifiers)| (kind, modifiers))
+        .unwrap_or_else(|| panic!("no range {:?}", text))
+}
+
+#[test]
+fn test_names_are_classified_by_what_they_refer_to() {
+    assert_eq!(kind_of(SOURCE, "math", 0), ("namespace", vec!["declaration"]));
+    assert_eq!(kind_of(SOURCE, "math", 1), ("namespace", vec![]));
+    assert_eq!(kind_of(SOURCE, "cache", 0), ("decorator", vec![]));
+    assert_eq!(kind_of(SOURCE, "area", 0), ("function", vec!["declaration"]));
+    assert_eq!(kind_of(SOURCE, "area", 1), ("function", vec![]));
+    assert_eq!(kind_of(SOURCE, "shape", 0), ("parameter", vec!["declaration"]));
+    assert_eq!(kind_of(SOURCE, "shape", 1), ("parameter", vec![]));
+    assert_eq!(kind_of(SOURCE, "width", 0), ("property", vec![]));
+    assert_eq!(kind_of(SOURCE, "sqrt", 0), ("method", vec![]));
+    assert_eq!(kind_of(SOURCE, "len", 0), ("function", vec!["defaultLibrary"]));
+    assert_eq!(kind_of(SOURCE, "total", 0), ("variable", vec!["declaration"]));
+    assert_eq!(kind_of(SOURCE, "Shape", 0), ("class", vec!["declaration"]));
+    assert_eq!(kind_of(SOURCE, "sides", 1), ("property", vec!["declaration"]));
+    assert_eq!(kind_of(SOURCE, "grow", 0), ("method", vec!["declaration"]));
+    // A keyword argument names a parameter, not the variable in scope
+    assert_eq!(kind_of(SOURCE, "scale", 1), ("parameter", vec![]));
+}
+
+#[test]
+fn test_keywords_literals_and_comments() {
+    assert_eq!(kind_of(SOURCE, "def", 0).0, "keyword");
+    assert_eq!(kind_of(SOURCE, "return", 0).0, "keyword");
+    assert_eq!(kind_of(SOURCE, "2", 0).0, "number");
+    assert_eq!(kind_of(SOURCE, "*", 0).0, "operator");
+    assert_eq!(kind_of(SOURCE, "# doubled", 0).0, "comment");
+    assert!(classified(SOURCE).iter().all(|(text, ..)| text != "(" && text != ":"));
+
+    let tokens = semantic_tokens("x = \"\"\"one\ntwo\"\"\"\n").unwrap();
+    let string = tokens.iter().find(|token| token.kind == TokenKind::String).unwrap();
+    assert_eq!((string.line, string.column), (1, 5));
+}
+
+#[test]
+fn test_unparsable_source_is_still_classified() {
+    let source = "def broken(:\n    print(value\n";
+    assert_eq!(kind_of(source, "def", 0).0, "keyword");
+    assert_eq!(kind_of(source, "print", 0), ("function", vec!["defaultLibrary"]));
+    assert!(semantic_tokens("x = \"unterminated\n").is_err());
+}
+
+#[test]
+fn test_lsp_encoding_is_relative_and_splits_lines() {
+    let source = "é = 1\ns = \"\"\"a\nbc\"\"\"\n";
+    let tokens = semantic_tokens(source).unwrap();
+    let data = encode(source, &tokens);
+    let variable = TokenKind::Variable as u32;
+    let string = TokenKind::String as u32;
+    assert_eq!(
+        data,
+        vec![
+            0, 0, 1, variable, 1, // é, one UTF-16 unit
+            0, 2, 1, TokenKind::Operator as u32, 0,
+            0, 2, 1, TokenKind::Number as u32, 0,
+            1, 0, 1, variable, 1,
+            0, 2, 1, TokenKind::Operator as u32, 0,
+            0, 2, 4, string, 0, // `"""a` on the first line
+            1, 0, 5, string, 0, // `bc"""` on the next
+        ]
+    );
+
+    let legend = legend();
+    assert_eq!(legend["tokenTypes"][variable as usize], "variable");
+    assert_eq!(legend["tokenModifiers"][0], "declaration");
+}
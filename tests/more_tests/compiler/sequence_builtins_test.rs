@@ -0,0 +1,257 @@
+// sequence_builtins_test.rs - Tests for the sorted, reversed, sum, abs and round built-ins
+
+use cheetah::compiler::runtime::list::*;
+use cheetah::compiler::runtime::sequence_ops::*;
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use cheetah::typechecker;
+use inkwell::context::Context;
+use std::ffi::c_void;
+
+pub fn compile_source(source: &str) -> Result<String, String> {
+    // Parse the source
+    let ast = match parse(source) {
+        Ok(ast) => ast,
+        Err(errors) => {
+            return Err(format!("Parse errors: {:?}", errors));
+        }
+    };
+
+    // Create a compiler
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "sequence_builtins_test");
+
+    // Compile the AST
+    match compiler.compile_module(&ast) {
+        Ok(_) => Ok(compiler.get_ir()),
+        Err(e) => Err(format!("Compilation error: {}", e)),
+    }
+}
+
+fn check_source(source: &str) -> Result<(), String> {
+    let module = parse(source).map_err(|e| format!("Parse errors: {:?}", e))?;
+    typechecker::check_module(&module).map_err(|e| e.to_string())
+}
+
+fn int_list(values: &[i64]) -> *mut RawList {
+    let list = list_new();
+    for value in values {
+        let boxed = unsafe { libc::malloc(8) as *mut i64 };
+        unsafe { *boxed = *value };
+        list_append_tagged(list, boxed as *mut c_void, TypeTag::Int);
+    }
+    list
+}
+
+fn float_list(values: &[f64]) -> *mut RawList {
+    let list = list_new();
+    for value in values {
+        let boxed = unsafe { libc::malloc(8) as *mut f64 };
+        unsafe { *boxed = *value };
+        list_append_tagged(list, boxed as *mut c_void, TypeTag::Float);
+    }
+    list
+}
+
+fn ints(list: *mut RawList) -> Vec<i64> {
+    (0..list_len(list))
+        .map(|i| unsafe { *(list_get(list, i) as *const i64) })
+        .collect()
+}
+
+#[test]
+fn test_sorted_and_reversed_runtime() {
+    let list = int_list(&[3, 1, 2, 1]);
+
+    let ascending = list_sorted(list, 0);
+    let descending = list_sorted(list, 1);
+    let reversed = list_reversed(list);
+    assert_eq!(ints(ascending), vec![1, 1, 2, 3]);
+    assert_eq!(ints(descending), vec![3, 2, 1, 1]);
+    assert_eq!(ints(reversed), vec![1, 2, 1, 3]);
+
+    // The source list is left untouched
+    assert_eq!(ints(list), vec![3, 1, 2, 1]);
+
+    for l in [ascending, descending, reversed, list] {
+        list_free(l);
+    }
+}
+
+#[test]
+fn test_sorted_by_keys_is_stable() {
+    let list = int_list(&[10, 20, 30, 40]);
+
+    // Equal keys keep their original order in both directions
+    let by_parity = list_sorted_by_keys(list, int_list(&[1, 0, 1, 0]), 0);
+    assert_eq!(ints(by_parity), vec![20, 40, 10, 30]);
+    let by_parity_reversed = list_sorted_by_keys(list, int_list(&[1, 0, 1, 0]), 1);
+    assert_eq!(ints(by_parity_reversed), vec![10, 30, 20, 40]);
+
+    for l in [by_parity, by_parity_reversed, list] {
+        list_free(l);
+    }
+}
+
+#[test]
+fn test_sort_by_keys_in_place_is_stable() {
+    let list = int_list(&[10, 20, 30, 40]);
+
+    // The list itself is reordered, and equal keys keep their order
+    list_sort_by_keys(list, int_list(&[1, 0, 1, 0]), 0);
+    assert_eq!(ints(list), vec![20, 40, 10, 30]);
+    list_sort_by_keys(list, int_list(&[0, 0, 1, 1]), 1);
+    assert_eq!(ints(list), vec![10, 30, 20, 40]);
+
+    // Without keys the items are their own keys
+    list_sort_by_keys(list, std::ptr::null_mut(), 1);
+    assert_eq!(ints(list), vec![40, 30, 20, 10]);
+
+    list_free(list);
+}
+
+#[test]
+fn test_sum_runtime() {
+    let list = int_list(&[1, 2, 3, 4]);
+    assert_eq!(sum_int_list(list), 10);
+    assert_eq!(sum_float_list(list), 10.0);
+    list_free(list);
+
+    let list = float_list(&[0.5, 0.25]);
+    assert_eq!(sum_float_list(list), 0.75);
+    list_free(list);
+
+    assert_eq!(sum_int_list(std::ptr::null_mut()), 0);
+}
+
+#[test]
+fn test_abs_and_round_runtime() {
+    assert_eq!(abs_int(-5), 5);
+    assert_eq!(abs_float(-2.5), 2.5);
+
+    // Halves round to the nearest even number
+    assert_eq!(round_float(2.5), 2);
+    assert_eq!(round_float(3.5), 4);
+    assert_eq!(round_float(-0.5), 0);
+    assert_eq!(round_float_digits(1.25, 1), 1.2);
+    assert_eq!(round_float_digits(1234.0, -2), 1200.0);
+    assert_eq!(round_int_digits(1250, -2), 1200);
+    assert_eq!(round_int_digits(1350, -2), 1400);
+    assert_eq!(round_int_digits(-1251, -2), -1300);
+    assert_eq!(round_int_digits(42, 3), 42);
+}
+
+#[test]
+fn test_sequence_builtins_typecheck() {
+    let source = r#"
+xs = [3, 1, 2]
+ys = sorted(xs)
+zs = reversed(ys)
+total = sum(xs) + abs(-3) + round(2.5)
+scale = round(2.567, 2) * 1.0
+"#;
+    let result = check_source(source);
+    assert!(result.is_ok(), "Failed to type check: {:?}", result.err());
+
+    assert!(check_source("x = abs(\"a\")\n").is_err());
+    assert!(check_source("x = round(1.5, \"2\")\n").is_err());
+    assert!(check_source("x = sum([\"a\", \"b\"])\n").is_err());
+    assert!(check_source("x = sorted()\n").is_err());
+}
+
+#[test]
+fn test_sort_with_key_typechecks() {
+    let source = r#"
+def neg(x: int) -> int:
+    return -x
+
+xs = [3, 1, 2]
+xs.sort(key=neg, reverse=True)
+letters = sorted("cab")
+names = sorted({"b": 1, "a": 2}, key=None)
+"#;
+    let result = check_source(source);
+    assert!(result.is_ok(), "Failed to type check: {:?}", result.err());
+}
+
+#[test]
+fn test_sequence_builtins_compile() {
+    let source = r#"
+def neg(x: int) -> int:
+    return -x
+
+xs = [3, 1, 2]
+ascending = sorted(xs)
+descending = sorted(xs, reverse=True)
+by_key = sorted(xs, key=neg)
+backwards = reversed(xs)
+total = sum(xs)
+offset = sum(xs, 10)
+prices = [1.5, 2.25]
+cost = sum(prices, start=1)
+magnitude = abs(-4) + abs(-2.5)
+whole = round(2.5)
+cents = round(2.567, 2)
+hundreds = round(1250, -2)
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Failed to compile sequence built-ins: {:?}", result.err());
+}
+
+#[test]
+fn test_list_sort_with_key_compiles() {
+    let source = r#"
+def neg(x: int) -> int:
+    return -x
+
+xs = [3, 1, 2]
+xs.sort()
+xs.sort(reverse=True)
+xs.sort(key=neg)
+xs.sort(key=neg, reverse=False)
+letters = sorted("cab", reverse=True)
+"#;
+
+    let ir = compile_source(source).expect("list.sort() with a key should compile");
+    assert!(ir.contains("@list_sort_by_keys"));
+
+    let bad_keyword = compile_source("xs = [1]\nxs.sort(cmp=1)\n");
+    assert!(bad_keyword.is_err());
+    let keyword_on_append = compile_source("xs = [1]\nxs.append(1, key=2)\n");
+    assert!(keyword_on_append.is_err());
+}
+
+#[test]
+fn test_scalar_min_max_abs_lower_inline() {
+    let source = r#"
+def clamp(x: int, y: float) -> float:
+    a = max(min(x, 10), 0)
+    b = abs(x)
+    c = abs(y) + min(y, 1.5) + max(x, y)
+    return a + b + c
+"#;
+
+    let ir = compile_source(source).expect("scalar built-ins should compile");
+    for runtime_name in ["min_int", "max_int", "min_float", "max_float", "abs_int", "abs_float"] {
+        assert!(
+            !ir.contains(&format!("call i64 @{}", runtime_name))
+                && !ir.contains(&format!("call double @{}", runtime_name)),
+            "{} should be lowered inline",
+            runtime_name
+        );
+    }
+    assert!(ir.contains("select"));
+}
+
+#[test]
+fn test_sequence_builtin_errors() {
+    let bad_keyword = compile_source("xs = [1]\nys = sorted(xs, cmp=1)\n");
+    assert!(bad_keyword.is_err());
+
+    let duplicate_start = compile_source("xs = [1]\nt = sum(xs, 1, start=2)\n");
+    assert!(duplicate_start.is_err());
+
+    let not_a_number = compile_source("x = abs(\"a\")\n");
+    assert!(not_a_number.is_err());
+}
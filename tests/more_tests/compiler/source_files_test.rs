@@ -0,0 +1,68 @@
+// source_files_test.rs - Tests for expanding the paths given to format, check and lint
+
+use cheetah::source_files::{expand, glob_match, is_pattern};
+use std::fs;
+use std::path::PathBuf;
+
+/// Create a fresh directory holding empty files at the given relative paths
+fn make_tree(name: &str, files: &[&str]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("cheetah_sources_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    for file in files {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "").unwrap();
+    }
+    dir
+}
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match("*.ch", "main.ch"));
+    assert!(!glob_match("*.ch", "src/main.ch"));
+    assert!(glob_match("src/?.ch", "src/a.ch"));
+    assert!(!glob_match("src/?.ch", "src/ab.ch"));
+    assert!(glob_match("src/**/*.ch", "src/main.ch"));
+    assert!(glob_match("src/**/*.ch", "src/a/b/main.ch"));
+    assert!(glob_match("**/test_*.ch", "tests/unit/test_list.ch"));
+    assert!(!glob_match("**/test_*.ch", "tests/unit/list.ch"));
+
+    assert!(is_pattern("src/*.ch"));
+    assert!(!is_pattern("src/main.ch"));
+}
+
+#[test]
+fn test_expand_directories_and_patterns() {
+    let dir = make_tree(
+        "expand",
+        &[
+            "main.ch",
+            "src/lib.ch",
+            "src/util/strings.ch",
+            "src/notes.txt",
+            ".cache/old.ch",
+            "target/generated.ch",
+        ],
+    );
+    let root = dir.to_string_lossy().to_string();
+
+    let files = expand(&[root.clone()]).unwrap();
+    assert_eq!(
+        files,
+        vec![
+            dir.join("main.ch"),
+            dir.join("src/lib.ch"),
+            dir.join("src/util/strings.ch"),
+        ]
+    );
+
+    let files = expand(&[format!("{}/src/**/*.ch", root)]).unwrap();
+    assert_eq!(files, vec![dir.join("src/lib.ch"), dir.join("src/util/strings.ch")]);
+
+    // Files may be named without their extension, and are only listed once
+    let files = expand(&[format!("{}/main", root), format!("{}/*.ch", root)]).unwrap();
+    assert_eq!(files, vec![dir.join("main.ch")]);
+
+    assert!(expand(&[format!("{}/*.txt", root)]).is_err());
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,61 @@
+// source_map_test.rs - Tests for source maps of generated LLVM IR
+
+use cheetah::compiler::source_map::{Mapping, SourceMap};
+
+const IR: &str = r#"; ModuleID = 'hello.ch'
+source_filename = "hello.ch"
+
+define i64 @main() !dbg !5 {
+entry:
+  %x = alloca i64, align 8, !dbg !9
+  store i64 1, ptr %x, align 8, !dbg !9
+  call void @print_int(i64 1), !dbg !10
+  ret i64 0
+}
+
+!llvm.dbg.cu = !{!0}
+
+!0 = distinct !DICompileUnit(language: DW_LANG_C, file: !1, producer: "Cheetah", isOptimized: false, runtimeVersion: 0, emissionKind: FullDebug)
+!1 = !DIFile(filename: "hello.ch", directory: "/src")
+!5 = distinct !DISubprogram(name: "main", linkageName: "main", scope: null, file: !1, line: 1, type: !6, scopeLine: 1, spFlags: DISPFlagDefinition, unit: !0)
+!6 = !DISubroutineType(types: !7)
+!7 = !{}
+!9 = !DILocation(line: 2, column: 0, scope: !5)
+!10 = !DILocation(line: 4, column: 3, scope: !5)
+"#;
+
+#[test]
+fn test_map_from_debug_locations() {
+    let map = SourceMap::from_llvm_ir(IR, "hello.ll");
+    assert_eq!(map.file, "hello.ll");
+    assert_eq!(map.sources, vec!["/src/hello.ch".to_string()]);
+    assert_eq!(
+        map.mappings,
+        vec![
+            Mapping { generated_line: 4, source: 0, line: 1, column: 0 },
+            Mapping { generated_line: 6, source: 0, line: 2, column: 0 },
+            Mapping { generated_line: 7, source: 0, line: 2, column: 0 },
+            Mapping { generated_line: 8, source: 0, line: 4, column: 3 },
+        ]
+    );
+    assert_eq!(map.lookup(8), Some(("/src/hello.ch", 4)));
+    assert_eq!(map.lookup(9), None);
+}
+
+#[test]
+fn test_ir_without_debug_info_has_no_mappings() {
+    let map = SourceMap::from_llvm_ir("define i64 @main() {\n  ret i64 0\n}\n", "plain.ll");
+    assert!(map.mappings.is_empty());
+    assert!(map.sources.is_empty());
+}
+
+#[test]
+fn test_json_uses_source_map_v3_encoding() {
+    let map = SourceMap::from_llvm_ir(IR, "hello.ll");
+    let json: serde_json::Value = serde_json::from_str(&map.to_json()).unwrap();
+    assert_eq!(json["version"], 3);
+    assert_eq!(json["file"], "hello.ll");
+    assert_eq!(json["sources"][0], "/src/hello.ch");
+    // Lines 4, 6, 7 and 8 map to source lines 1, 2, 2 and 4 (column 3)
+    assert_eq!(json["mappings"], ";;;AAAA;;AACA;AAAA;AAEG");
+}
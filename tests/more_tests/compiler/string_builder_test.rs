@@ -0,0 +1,120 @@
+// string_builder_test.rs - Tests for linear-time string accumulation in loops
+
+use cheetah::ast::Stmt;
+use cheetah::compiler::runtime::string::*;
+use cheetah::compiler::string_builder::accumulated_strings;
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use inkwell::context::Context;
+use std::ffi::{CStr, CString};
+
+pub fn compile_source(source: &str) -> Result<String, String> {
+    // Parse the source
+    let ast = match parse(source) {
+        Ok(ast) => ast,
+        Err(errors) => {
+            return Err(format!("Parse errors: {:?}", errors));
+        }
+    };
+
+    // Create a compiler
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "string_builder_test");
+
+    // Compile the AST
+    match compiler.compile_module(&ast) {
+        Ok(_) => Ok(compiler.get_ir()),
+        Err(e) => Err(format!("Compilation error: {}", e)),
+    }
+}
+
+/// The strings accumulated by the last loop in `source`, or in its last function
+fn accumulated(source: &str) -> Vec<String> {
+    let module = parse(source).expect("source should parse");
+    let mut last = module.body.last().map(|stmt| stmt.as_ref());
+    if let Some(Stmt::FunctionDef { body, .. }) = last {
+        last = body.last().map(|stmt| stmt.as_ref());
+    }
+    match last {
+        Some(Stmt::For { target, body, orelse, .. }) => {
+            accumulated_strings(&[body, orelse], &[target.as_ref()], &|name| name == "helper")
+        }
+        Some(Stmt::While { test, body, orelse, .. }) => {
+            accumulated_strings(&[body, orelse], &[test.as_ref()], &|name| name == "helper")
+        }
+        other => panic!("expected a loop, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_runtime_string_builder() {
+    let initial = CString::new("ab").unwrap();
+    let builder = string_builder_new(initial.as_ptr());
+    for piece in ["c", "dé", ""] {
+        let piece = CString::new(piece).unwrap();
+        string_builder_append(builder, piece.as_ptr());
+    }
+
+    let result = string_builder_finish(builder);
+    assert_eq!(unsafe { CStr::from_ptr(result) }.to_str().unwrap(), "abcdé");
+    free_string(result);
+}
+
+#[test]
+fn test_detects_append_only_strings() {
+    let source = "out = \"\"\nother = \"\"\nfor w in words:\n    out += w\n    if w:\n        other += \"!\"\n        out += \" \"\n";
+    assert_eq!(accumulated(source), vec!["out".to_string(), "other".to_string()]);
+}
+
+#[test]
+fn test_rejects_observed_strings() {
+    // Read inside the loop
+    assert!(accumulated("for w in words:\n    out += w\n    print(out)\n").is_empty());
+    // Read by a while loop's condition
+    assert!(accumulated("while len(out) < 10:\n    out += \"x\"\n").is_empty());
+    // Reassigned inside the loop
+    assert!(accumulated("for w in words:\n    out += w\n    out = \"\"\n").is_empty());
+    // Used as the loop variable
+    assert!(accumulated("for out in words:\n    out += \"x\"\n").is_empty());
+    // Read by the loop's else block, which runs before the loop exits
+    assert!(accumulated("for w in words:\n    out += w\nelse:\n    print(out)\n").is_empty());
+    // A user function could read a global
+    assert!(accumulated("for w in words:\n    out += w\n    helper()\n").is_empty());
+    // The function can return before the loop finishes
+    assert!(accumulated("def f(words):\n    for w in words:\n        out += w\n        if w:\n            return 1\n").is_empty());
+}
+
+#[test]
+fn test_string_accumulation_compiles() {
+    let source = r#"
+def build(n: int) -> str:
+    out = ""
+    for i in range(n):
+        out += str(i)
+        out += ","
+    return out
+
+words = ["a", "b", "c"]
+line = ""
+for w in words:
+    line += w
+print(line)
+
+count = 0
+stars = ""
+while count < 3:
+    stars += "*"
+    count += 1
+print(stars)
+joined = ", ".join(words)
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Failed to compile string accumulation: {:?}", result.err());
+}
+
+#[test]
+fn test_string_accumulation_type_error() {
+    let result = compile_source("s = \"\"\nfor i in range(3):\n    s += i\n");
+    assert!(result.is_err());
+}
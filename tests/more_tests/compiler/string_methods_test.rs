@@ -0,0 +1,200 @@
+use cheetah::compiler::runtime::exception::{
+    clear_current_exception, exception_get_message, exception_get_type, get_current_exception,
+};
+use cheetah::compiler::runtime::list::{
+    list_append, list_append_tagged, list_concat, list_free, list_get, list_len, list_new, list_slice,
+    TypeTag,
+};
+use cheetah::compiler::runtime::string::*;
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use inkwell::context::Context;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+
+pub fn compile_source(source: &str) -> Result<String, String> {
+    // Parse the source
+    let ast = match parse(source) {
+        Ok(ast) => ast,
+        Err(errors) => {
+            return Err(format!("Parse errors: {:?}", errors));
+        }
+    };
+
+    // Create a compiler
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "string_methods_test");
+
+    // Compile the AST
+    match compiler.compile_module(&ast) {
+        Ok(_) => Ok(compiler.get_ir()),
+        Err(e) => Err(format!("Compilation error: {}", e)),
+    }
+}
+
+fn take_string(ptr: *mut c_char) -> String {
+    let s = unsafe { CStr::from_ptr(ptr).to_str().unwrap().to_string() };
+    free_string(ptr);
+    s
+}
+
+/// Type and message of the pending exception, clearing it
+fn take_exception() -> Option<(String, String)> {
+    let exception = get_current_exception();
+    if exception.is_null() {
+        return None;
+    }
+    let text = |ptr| unsafe { CStr::from_ptr(ptr) }.to_string_lossy().into_owned();
+    let raised = (text(exception_get_type(exception)), text(exception_get_message(exception)));
+    clear_current_exception();
+    Some(raised)
+}
+
+#[test]
+fn test_runtime_case_and_strip() {
+    let s = CString::new("  Hello World  ").unwrap();
+    assert_eq!(take_string(string_strip(s.as_ptr())), "Hello World");
+    assert_eq!(take_string(string_upper(s.as_ptr())), "  HELLO WORLD  ");
+    assert_eq!(take_string(string_lower(s.as_ptr())), "  hello world  ");
+}
+
+#[test]
+fn test_runtime_replace_find_and_affixes() {
+    let s = CString::new("banana").unwrap();
+    let an = CString::new("an").unwrap();
+    let x = CString::new("x").unwrap();
+    let ba = CString::new("ba").unwrap();
+    let na = CString::new("na").unwrap();
+
+    assert_eq!(take_string(string_replace(s.as_ptr(), an.as_ptr(), x.as_ptr())), "bxxa");
+    assert_eq!(string_find(s.as_ptr(), na.as_ptr()), 2);
+    assert_eq!(string_find(s.as_ptr(), x.as_ptr()), -1);
+    assert!(string_startswith(s.as_ptr(), ba.as_ptr()));
+    assert!(!string_endswith(s.as_ptr(), ba.as_ptr()));
+    assert!(string_endswith(s.as_ptr(), na.as_ptr()));
+}
+
+#[test]
+fn test_runtime_split_and_join() {
+    let s = CString::new("a,b,,c").unwrap();
+    let comma = CString::new(",").unwrap();
+    let list = string_split(s.as_ptr(), comma.as_ptr());
+    assert_eq!(list_len(list), 4);
+    // The list is allocated at its final size
+    assert_eq!(unsafe { (*list).capacity }, 4);
+    let third = list_get(list, 2) as *const c_char;
+    assert_eq!(unsafe { CStr::from_ptr(third) }.to_str().unwrap(), "");
+
+    let dash = CString::new("-").unwrap();
+    assert_eq!(take_string(string_join(dash.as_ptr(), list)), "a-b--c");
+    list_free(list);
+
+    let spaced = CString::new("  one  two\tthree ").unwrap();
+    let words = string_split(spaced.as_ptr(), std::ptr::null());
+    assert_eq!(list_len(words), 3);
+    list_free(words);
+
+    let empty = list_new();
+    let part = CString::new("solo").unwrap().into_raw();
+    list_append_tagged(empty, part as *mut std::ffi::c_void, TypeTag::String);
+    assert_eq!(take_string(string_join(dash.as_ptr(), empty)), "solo");
+    list_free(empty);
+    assert_eq!(take_exception(), None);
+}
+
+#[test]
+fn test_runtime_split_on_empty_separator_raises() {
+    let s = CString::new("a b").unwrap();
+    let empty = CString::new("").unwrap();
+    let list = string_split(s.as_ptr(), empty.as_ptr());
+    assert_eq!(list_len(list), 0);
+    assert_eq!(
+        take_exception(),
+        Some(("ValueError".to_string(), "empty separator".to_string()))
+    );
+    list_free(list);
+}
+
+#[test]
+fn test_runtime_join_of_non_strings_raises() {
+    let list = list_new();
+    let part = CString::new("a").unwrap().into_raw();
+    list_append_tagged(list, part as *mut std::ffi::c_void, TypeTag::String);
+    let number = Box::into_raw(Box::new(2i64));
+    list_append_tagged(list, number as *mut std::ffi::c_void, TypeTag::Int);
+
+    let dash = CString::new("-").unwrap();
+    assert_eq!(take_string(string_join(dash.as_ptr(), list)), "");
+    assert_eq!(
+        take_exception(),
+        Some((
+            "TypeError".to_string(),
+            "sequence item 1: expected str instance, int found".to_string()
+        ))
+    );
+    list_free(list);
+}
+
+#[test]
+fn test_runtime_join_of_untyped_elements_raises() {
+    // An element of unknown type is never read as string memory
+    let list = list_new();
+    let number = Box::into_raw(Box::new(2i64));
+    list_append(list, number as *mut std::ffi::c_void);
+
+    let dash = CString::new("-").unwrap();
+    assert_eq!(take_string(string_join(dash.as_ptr(), list)), "");
+    assert_eq!(
+        take_exception(),
+        Some((
+            "TypeError".to_string(),
+            "sequence item 0: expected str instance, object found".to_string()
+        ))
+    );
+    list_free(list);
+}
+
+#[test]
+fn test_runtime_join_of_sliced_and_concatenated_strings() {
+    // Slicing and concatenation keep the string tags join checks
+    let s = CString::new("a b c").unwrap();
+    let words = string_split(s.as_ptr(), std::ptr::null());
+    let tail = list_slice(words, 1, 3, 1);
+    let both = list_concat(tail, words);
+
+    let dash = CString::new("-").unwrap();
+    assert_eq!(take_string(string_join(dash.as_ptr(), tail)), "b-c");
+    assert_eq!(take_string(string_join(dash.as_ptr(), both)), "b-c-a-b-c");
+    assert_eq!(take_exception(), None);
+}
+
+#[test]
+fn test_string_methods_compile() {
+    let source = r#"
+line = "  name=Alice  "
+clean = line.strip()
+parts = clean.split("=")
+key = parts[0].upper()
+joined = ", ".join(parts)
+pos = clean.find("=")
+fixed = clean.replace("Alice", "Bob").lower()
+is_name = clean.startswith("name")
+ends = clean.endswith("e")
+words = "a b c".split()
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Failed to compile string methods: {:?}", result.err());
+}
+
+#[test]
+fn test_string_method_errors() {
+    let unknown = compile_source("s = \"abc\"\nt = s.title()\n");
+    assert!(unknown.is_err());
+
+    let wrong_arity = compile_source("s = \"abc\"\nt = s.replace(\"a\")\n");
+    assert!(wrong_arity.is_err());
+
+    let wrong_type = compile_source("s = \"abc\"\nt = s.startswith(1)\n");
+    assert!(wrong_type.is_err());
+}
@@ -0,0 +1,126 @@
+// symtable_test.rs - Tests for the structured symbol table
+
+use cheetah::symtable::{Position, ScopeKind, SymbolTable, SymbolType};
+
+fn table(source: &str) -> SymbolTable {
+    SymbolTable::build(&cheetah::parse(source).unwrap())
+}
+
+const SOURCE: &str = "\
+import os
+total = 0
+
+def add(value):
+    global total
+    step = value * 2
+    total = total + step
+    return [step + n for n in range(3)]
+
+class Counter:
+    count = 0
+
+    def bump(self):
+        count = 1
+        return count
+";
+
+#[test]
+fn test_scopes_form_a_tree_with_source_ranges() {
+    let table = table(SOURCE);
+    let root = table.root();
+    assert_eq!(root.kind, ScopeKind::Module);
+
+    let names: Vec<(&str, ScopeKind)> = table
+        .scopes()
+        .iter()
+        .map(|scope| (scope.name.as_str(), scope.kind))
+        .collect();
+    assert_eq!(
+        names,
+        [
+            ("module", ScopeKind::Module),
+            ("add", ScopeKind::Function),
+            ("listcomp", ScopeKind::Comprehension),
+            ("Counter", ScopeKind::Class),
+            ("bump", ScopeKind::Function),
+        ]
+    );
+
+    let add = table.scope(1);
+    assert_eq!(add.parent, Some(SymbolTable::ROOT));
+    assert_eq!(add.children, [2]);
+    assert_eq!(add.start, Position::new(4, 1));
+    assert_eq!(add.end.line, 8);
+
+    assert_eq!(table.scope_at_position(6, 5), 1);
+    assert_eq!(table.scope_at_position(8, 26), 2);
+    assert_eq!(table.scope_at_position(14, 9), 4);
+    assert_eq!(table.scope_at_position(2, 1), SymbolTable::ROOT);
+}
+
+#[test]
+fn test_symbols_carry_kind_and_declaration_site() {
+    let table = table(SOURCE);
+    let root: Vec<(&str, SymbolType, usize)> = table
+        .symbols_in_scope(SymbolTable::ROOT)
+        .map(|symbol| (symbol.name.as_str(), symbol.symbol_type.clone(), symbol.line))
+        .collect();
+    assert_eq!(
+        root,
+        [
+            ("os", SymbolType::Import, 1),
+            ("total", SymbolType::Variable, 2),
+            ("add", SymbolType::Function, 4),
+            ("Counter", SymbolType::Class, 10),
+        ]
+    );
+
+    let step = table.scope(1).symbol("step").unwrap();
+    assert_eq!((step.line, step.column), (6, 5));
+    assert_eq!(step.span.end_column, 9);
+    assert!(step.is_referenced);
+
+    // Undefined names aren't symbols of the scope that uses them
+    assert!(table.symbols_in_scope(1).all(|symbol| symbol.name != "range"));
+}
+
+#[test]
+fn test_lookup_at_position_resolves_names() {
+    let table = table(SOURCE);
+
+    // `step` in `total + step` is the local of add
+    let (scope, symbol) = table.lookup_at_position(7, 21).unwrap();
+    assert_eq!((scope, symbol.name.as_str(), symbol.line), (1, "step", 6));
+
+    // `total` is declared global in add
+    let (scope, symbol) = table.lookup_at_position(7, 13).unwrap();
+    assert_eq!((scope, symbol.line), (SymbolTable::ROOT, 2));
+
+    // The `def add` header names the function
+    let (scope, symbol) = table.lookup_at_position(4, 6).unwrap();
+    assert_eq!((scope, symbol.symbol_type.clone()), (SymbolTable::ROOT, SymbolType::Function));
+
+    // Class bodies don't enclose their methods: `count` is bump's local
+    let (scope, _) = table.lookup_at_position(15, 16).unwrap();
+    assert_eq!(scope, 4);
+
+    // Comprehension variables belong to the comprehension
+    let (scope, symbol) = table.lookup_at_position(8, 20).unwrap();
+    assert_eq!((scope, symbol.name.as_str()), (2, "n"));
+
+    assert!(table.lookup_at_position(8, 31).is_none());
+    assert!(table.lookup_at_position(3, 1).is_none());
+}
+
+#[test]
+fn test_visible_symbols_skip_class_scopes() {
+    let table = table(SOURCE);
+    let names: Vec<&str> = table
+        .visible_symbols(4)
+        .iter()
+        .map(|symbol| symbol.name.as_str())
+        .collect();
+    assert_eq!(names, ["Counter", "add", "count", "os", "self", "total"]);
+    let count = table.visible_symbols(4).into_iter().find(|s| s.name == "count").unwrap();
+    assert_eq!(count.line, 14);
+}
@@ -0,0 +1,56 @@
+// target_test.rs - Tests for selecting the target triple, CPU and features
+
+use cheetah::compiler::target::TargetSpec;
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use inkwell::context::Context;
+
+fn aarch64() -> TargetSpec {
+    TargetSpec {
+        triple: Some("aarch64-unknown-linux-gnu".to_string()),
+        cpu: None,
+        features: None,
+    }
+}
+
+#[test]
+fn test_cross_target_defaults() {
+    let target = aarch64();
+    assert!(target.is_cross());
+    assert_eq!(target.cpu(), "generic");
+    assert_eq!(target.features(), "");
+
+    let tuned = TargetSpec {
+        cpu: Some("cortex-a72".to_string()),
+        features: Some("+neon".to_string()),
+        ..aarch64()
+    };
+    assert_eq!(tuned.cpu(), "cortex-a72");
+    assert_eq!(tuned.features(), "+neon");
+
+    assert!(!TargetSpec::host().is_cross());
+}
+
+#[test]
+fn test_set_target_sets_triple_and_data_layout() {
+    let ast = parse("x = 1 + 2\nprint(x)\n").expect("source should parse");
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "target_test");
+    compiler.set_target(aarch64()).expect("aarch64 should be available");
+    compiler.compile_module(&ast).expect("source should compile");
+
+    let ir = compiler.get_ir();
+    assert!(ir.contains("target triple = \"aarch64-unknown-linux-gnu\""));
+    assert!(ir.contains("target datalayout = \"e-m:e-"));
+}
+
+#[test]
+fn test_unknown_target_is_an_error() {
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "target_test");
+    let result = compiler.set_target(TargetSpec {
+        triple: Some("nonsense-unknown-nowhere".to_string()),
+        ..TargetSpec::host()
+    });
+    assert!(result.is_err());
+}
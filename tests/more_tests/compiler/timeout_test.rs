@@ -0,0 +1,166 @@
+// timeout_test.rs - Tests for with_timeout() deadlines
+
+use cheetah::compiler::runtime::exception::{
+    clear_current_exception, exception_enter_handler, exception_get_type, exception_handler_depth,
+    exception_raise_new, get_current_exception,
+};
+use cheetah::compiler::runtime::timeout::*;
+use cheetah::compiler::runtime::traceback::{depth, trace_pop_frames, trace_push_frame, trace_set_line};
+use cheetah::compiler::Compiler;
+use cheetah::engine::{Engine, Value};
+use cheetah::parse;
+use inkwell::context::Context;
+use std::ffi::{CStr, CString};
+use std::thread::sleep;
+use std::time::Duration;
+
+pub fn compile_source(source: &str) -> Result<String, String> {
+    let ast = parse(source).map_err(|errors| format!("Parse errors: {:?}", errors))?;
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "timeout_test");
+    compiler
+        .compile_module(&ast)
+        .map_err(|e| format!("Compilation error: {}", e))?;
+    Ok(compiler.get_ir())
+}
+
+/// Type of the pending exception, clearing it
+fn take_exception_type() -> Option<String> {
+    let exception = get_current_exception();
+    if exception.is_null() {
+        return None;
+    }
+    let typ = unsafe { CStr::from_ptr(exception_get_type(exception)) };
+    let typ = typ.to_string_lossy().into_owned();
+    clear_current_exception();
+    Some(typ)
+}
+
+/// Frames left above `outer`, counting its own
+fn depth_after(outer: i64) -> usize {
+    depth() - outer as usize
+}
+
+/// Run statements the way a compiled loop does until told to return
+fn run_until_interrupted(limit: Duration) -> bool {
+    let start = std::time::Instant::now();
+    while start.elapsed() < limit {
+        if trace_set_line(2) {
+            return true;
+        }
+        sleep(Duration::from_millis(1));
+    }
+    false
+}
+
+#[test]
+fn test_function_that_finishes_in_time_is_not_interrupted() {
+    let depth = timeout_enter(5.0);
+    assert!(!trace_set_line(1));
+    assert!(!timeout_exit(depth));
+    assert!(!interrupted());
+    assert_eq!(take_exception_type(), None);
+}
+
+#[test]
+fn test_deadline_interrupts_and_raises_timeout_error() {
+    let name = CString::new("main").unwrap();
+    let outer = trace_push_frame(name.as_ptr());
+    let depth = timeout_enter(0.02);
+
+    // The interrupted functions return without popping their frames or
+    // leaving their except blocks
+    let name = CString::new("work").unwrap();
+    trace_push_frame(name.as_ptr());
+    let typ = CString::new("ValueError").unwrap();
+    exception_raise_new(typ.as_ptr(), typ.as_ptr());
+    exception_enter_handler();
+    assert!(run_until_interrupted(Duration::from_secs(5)));
+
+    assert!(timeout_exit(depth));
+    assert!(!interrupted());
+    assert_eq!(depth_after(outer), 1);
+    assert_eq!(exception_handler_depth(), 0);
+    assert_eq!(take_exception_type().as_deref(), Some("TimeoutError"));
+    trace_pop_frames(outer);
+}
+
+#[test]
+fn test_outer_deadline_interrupts_through_inner_one() {
+    let outer = timeout_enter(0.02);
+    let inner = timeout_enter(10.0);
+    assert!(run_until_interrupted(Duration::from_secs(5)));
+
+    // The inner call returns normally and leaves the flag set for the outer one
+    assert!(!timeout_exit(inner));
+    assert!(interrupted());
+    assert_eq!(take_exception_type(), None);
+    assert!(timeout_exit(outer));
+    assert!(!interrupted());
+    assert_eq!(take_exception_type().as_deref(), Some("TimeoutError"));
+}
+
+#[test]
+fn test_disarmed_deadline_never_fires() {
+    let depth = timeout_enter(0.01);
+    assert!(!timeout_exit(depth));
+    sleep(Duration::from_millis(50));
+    assert!(!interrupted());
+
+    // A deadline too far away to represent never fires either
+    let depth = timeout_enter(f64::INFINITY);
+    assert!(!trace_set_line(1));
+    assert!(!timeout_exit(depth));
+}
+
+#[test]
+fn test_compile_with_timeout_call() {
+    let source = r#"
+def work() -> int:
+    total = 0
+    while True:
+        total += 1
+    return total
+
+try:
+    result = with_timeout(0.5, work)
+except TimeoutError:
+    result = -1
+"#;
+    let ir = compile_source(source).unwrap();
+    assert!(ir.contains("@timeout_enter("));
+    assert!(ir.contains("@timeout_exit("));
+    assert!(ir.contains("interrupted"));
+
+    let error = compile_source("def add(a, b):\n    return a + b\n\nwith_timeout(1, add)\n").unwrap_err();
+    assert!(error.contains("no arguments"), "{}", error);
+}
+
+const NESTED_INTERRUPT: &str = r#"
+def items():
+    xs = [1, 2, 3]
+    while True:
+        xs[0] = 1
+    return xs
+
+def first() -> int:
+    return items()[0]
+
+def run() -> int:
+    return with_timeout(0.05, first)
+"#;
+
+#[test]
+fn test_interrupted_call_result_is_never_indexed() {
+    // items() returns a null list when interrupted; first() must return
+    // before indexing it rather than crash
+    let ir = compile_source(NESTED_INTERRUPT).unwrap();
+    assert!(ir.contains("@trace_stopping("));
+
+    let context = Context::create();
+    let mut engine = Engine::new(&context);
+    engine.load(NESTED_INTERRUPT).expect("program should load");
+    assert_eq!(engine.call("run", &[]), Ok(Value::Int(0)));
+    assert_eq!(take_exception_type().as_deref(), Some("TimeoutError"));
+    assert!(!interrupted());
+}
@@ -0,0 +1,117 @@
+// traceback_test.rs - Tests for runtime tracebacks with source line numbers
+
+use cheetah::compiler::runtime::exception::*;
+use cheetah::compiler::runtime::list::*;
+use cheetah::compiler::runtime::traceback::*;
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use inkwell::context::Context;
+use std::ffi::{c_void, CStr, CString};
+
+pub fn compile_source(source: &str) -> Result<String, String> {
+    // Parse the source
+    let ast = match parse(source) {
+        Ok(ast) => ast,
+        Err(errors) => {
+            return Err(format!("Parse errors: {:?}", errors));
+        }
+    };
+
+    // Create a compiler
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "traceback_test");
+
+    // Compile the AST
+    match compiler.compile_module(&ast) {
+        Ok(_) => Ok(compiler.get_ir()),
+        Err(e) => Err(format!("Compilation error: {}", e)),
+    }
+}
+
+#[test]
+fn test_traceback_lists_frames_outermost_first() {
+    reset();
+    assert_eq!(format_traceback(), None);
+
+    set_source_name("script.ch");
+    let module = CString::new("<module>").unwrap();
+    let divide = CString::new("divide").unwrap();
+
+    let module_depth = trace_push_frame(module.as_ptr());
+    trace_set_line(7);
+    let divide_depth = trace_push_frame(divide.as_ptr());
+    trace_set_line(3);
+    assert_eq!((module_depth, divide_depth), (0, 1));
+
+    assert_eq!(
+        format_traceback().unwrap(),
+        "Traceback (most recent call last):\n  \
+         File \"script.ch\", line 7, in <module>\n  \
+         File \"script.ch\", line 3, in divide\n"
+    );
+
+    // Returning restores the caller's frame and its line
+    trace_pop_frames(divide_depth);
+    assert_eq!(
+        format_traceback().unwrap(),
+        "Traceback (most recent call last):\n  File \"script.ch\", line 7, in <module>\n"
+    );
+
+    trace_pop_frames(module_depth);
+    assert_eq!(format_traceback(), None);
+}
+
+#[test]
+fn test_checked_list_index() {
+    let list = list_new();
+    for value in [10i64, 20, 30] {
+        let boxed = unsafe { libc::malloc(8) as *mut i64 };
+        unsafe { *boxed = value };
+        list_append_tagged(list, boxed as *mut c_void, TypeTag::Int);
+    }
+
+    let item = |index: i64| unsafe { *(list_get_checked(list, index) as *const i64) };
+    assert_eq!(item(0), 10);
+    assert_eq!(item(-1), 30);
+    assert!(!exception_is_pending());
+
+    // Out of range raises IndexError and yields a zeroed item
+    assert_eq!(item(3), 0);
+    assert!(exception_is_pending());
+    let exception = get_current_exception();
+    let typ = unsafe { CStr::from_ptr(exception_get_type(exception)) };
+    assert_eq!(typ.to_str().unwrap(), "IndexError");
+    clear_current_exception();
+
+    assert_eq!(item(-4), 0);
+    assert!(exception_is_pending());
+    clear_current_exception();
+
+    list_free(list);
+}
+
+#[test]
+fn test_statement_lines() {
+    let module = parse("x = 1\n\nif x:\n    y = 2\n").unwrap();
+    let lines: Vec<usize> = module.body.iter().map(|stmt| stmt.line()).collect();
+    assert_eq!(lines, vec![1, 3]);
+}
+
+#[test]
+fn test_runtime_errors_compile() {
+    let source = r#"
+def divide(a: int, b: int) -> float:
+    return a / b
+
+def remainder(a: int, b: int) -> int:
+    return a % b
+
+items = [1, 2, 3]
+last = items[-1]
+ratio = divide(1, 0)
+rest = remainder(5, 0) + 7 // 0
+"#;
+
+    let result = compile_source(source);
+    assert!(result.is_ok(), "Failed to compile runtime error checks: {:?}", result.err());
+}
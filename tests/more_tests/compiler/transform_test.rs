@@ -0,0 +1,74 @@
+// transform_test.rs - Tests for AST transform registration
+//
+// The registry is shared by every test in the binary, so these transforms
+// only touch modules that start with their own marker assignment.
+
+use cheetah::ast::{Expr, Module, Stmt};
+use cheetah::transform::{self, AstTransform};
+use std::borrow::Cow;
+
+/// Whether `module` starts with an assignment to `marker`
+fn starts_with_marker(module: &Module, marker: &str) -> bool {
+    match module.body.first().map(|stmt| stmt.as_ref()) {
+        Some(Stmt::Assign { targets, .. }) => {
+            matches!(targets[0].as_ref(), Expr::Name { id, .. } if id == marker)
+        }
+        _ => false,
+    }
+}
+
+/// Drops `pass` statements from marked modules
+struct DropPass;
+
+impl AstTransform for DropPass {
+    fn name(&self) -> &str {
+        "drop-pass"
+    }
+
+    fn transform(&self, mut module: Module) -> Result<Module, String> {
+        if starts_with_marker(&module, "__drop_pass_test") {
+            module.body.retain(|stmt| !matches!(stmt.as_ref(), Stmt::Pass { .. }));
+        }
+        Ok(module)
+    }
+}
+
+/// Rejects marked modules
+struct Reject;
+
+impl AstTransform for Reject {
+    fn name(&self) -> &str {
+        "reject"
+    }
+
+    fn transform(&self, module: Module) -> Result<Module, String> {
+        if starts_with_marker(&module, "__reject_test") {
+            return Err("marked module".to_string());
+        }
+        Ok(module)
+    }
+}
+
+#[test]
+fn test_registered_transform_rewrites_module() {
+    transform::register(DropPass);
+    assert!(transform::registered().contains(&"drop-pass".to_string()));
+
+    let module = cheetah::parse("__drop_pass_test = 1\npass\nx = 2\npass\n").unwrap();
+    let transformed = transform::apply(&module).unwrap();
+    assert!(matches!(transformed, Cow::Owned(_)));
+    assert_eq!(transformed.body.len(), 2);
+
+    let untouched = cheetah::parse("y = 1\npass\n").unwrap();
+    assert_eq!(transform::apply(&untouched).unwrap().body.len(), 2);
+}
+
+#[test]
+fn test_transform_error_names_the_transform() {
+    transform::register(Reject);
+
+    let module = cheetah::parse("__reject_test = 1\n").unwrap();
+    let err = transform::apply(&module).unwrap_err();
+    assert!(err.contains("reject"));
+    assert!(err.contains("marked module"));
+}
@@ -0,0 +1,51 @@
+// wasm_test.rs - Tests for compiling to WebAssembly
+
+use cheetah::compiler::wasm::{js_shim, WasmRuntime};
+use cheetah::compiler::Compiler;
+use cheetah::parse;
+use inkwell::context::Context;
+
+#[test]
+fn test_wasm_runtime_names_and_targets() {
+    assert_eq!(WasmRuntime::from_name("wasi"), Ok(WasmRuntime::Wasi));
+    assert_eq!(WasmRuntime::from_name("shim"), Ok(WasmRuntime::Shim));
+    assert!(WasmRuntime::from_name("emscripten").is_err());
+
+    assert_eq!(
+        WasmRuntime::Wasi.target().triple.as_deref(),
+        Some("wasm32-unknown-wasi")
+    );
+    assert_eq!(
+        WasmRuntime::Shim.target().triple.as_deref(),
+        Some("wasm32-unknown-unknown")
+    );
+}
+
+#[test]
+fn test_js_shim_provides_imports() {
+    let imports = vec!["print_int".to_string(), "list_new".to_string()];
+    let shim = js_shim("program.wasm", &imports);
+
+    assert!(shim.contains("fetch(\"program.wasm\")"));
+    assert!(shim.contains("print_int: (value) => write(value.toString()),"));
+    assert!(shim.contains("list_new: unavailable(\"list_new\"),"));
+    assert!(shim.contains("export async function run(bytes)"));
+    assert!(shim.contains("instance.exports.main();"));
+
+    // Functions the shim implements are not listed twice
+    assert_eq!(shim.matches("print_int:").count(), 1);
+}
+
+#[test]
+fn test_compiles_for_wasm32() {
+    let ast = parse("x = 6 * 7\nprint(x)\n").expect("source should parse");
+    let context = Context::create();
+    let mut compiler = Compiler::new(&context, "wasm_test");
+    compiler
+        .set_target(WasmRuntime::Shim.target())
+        .expect("the WebAssembly target should be available");
+    compiler.compile_module(&ast).expect("source should compile");
+
+    assert!(compiler.get_ir().contains("target triple = \"wasm32-unknown-unknown\""));
+    assert!(compiler.imported_functions().contains(&"print_int".to_string()));
+}
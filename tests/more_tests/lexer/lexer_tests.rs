@@ -16,7 +16,7 @@ mod lexer_tests {
         // Define the expected token types in order
         let expected_types = vec![
             TokenType::Def,
-            TokenType::Identifier("foo".to_string()),
+            TokenType::Identifier("foo".into()),
             TokenType::LeftParen,
             TokenType::RightParen,
             TokenType::Colon,
@@ -107,17 +107,17 @@ mod lexer_tests {
         assert_tokens_ignore_indentation(
             "π = 3.14159\nñame = \"José\"\n你好 = \"Hello\"",
             vec![
-                TokenType::Identifier("π".to_string()),
+                TokenType::Identifier("π".into()),
                 TokenType::Assign,
                 TokenType::FloatLiteral(3.14159),
                 TokenType::Newline,
-                TokenType::Identifier("ñame".to_string()),
+                TokenType::Identifier("ñame".into()),
                 TokenType::Assign,
-                TokenType::StringLiteral("José".to_string()),
+                TokenType::StringLiteral("José".into()),
                 TokenType::Newline,
-                TokenType::Identifier("你好".to_string()),
+                TokenType::Identifier("你好".into()),
                 TokenType::Assign,
-                TokenType::StringLiteral("Hello".to_string()),
+                TokenType::StringLiteral("Hello".into()),
             ]
         );
         
@@ -125,9 +125,9 @@ mod lexer_tests {
         assert_tokens_ignore_indentation(
             "message = \"Hello, 世界!\"",
             vec![
-                TokenType::Identifier("message".to_string()),
+                TokenType::Identifier("message".into()),
                 TokenType::Assign,
-                TokenType::StringLiteral("Hello, 世界!".to_string()),
+                TokenType::StringLiteral("Hello, 世界!".into()),
             ]
         );
         
@@ -135,9 +135,9 @@ mod lexer_tests {
         assert_tokens_ignore_indentation(
             r#"emoji = "\u{1F600}""#, // 😀 emoji
             vec![
-                TokenType::Identifier("emoji".to_string()),
+                TokenType::Identifier("emoji".into()),
                 TokenType::Assign,
-                TokenType::StringLiteral("😀".to_string()),
+                TokenType::StringLiteral("😀".into()),
             ]
         );
     }
@@ -220,11 +220,11 @@ mod lexer_tests {
         assert_tokens(
             "variable _private name123 camelCase snake_case",
             vec![
-                TokenType::Identifier("variable".to_string()),
-                TokenType::Identifier("_private".to_string()),
-                TokenType::Identifier("name123".to_string()),
-                TokenType::Identifier("camelCase".to_string()),
-                TokenType::Identifier("snake_case".to_string()),
+                TokenType::Identifier("variable".into()),
+                TokenType::Identifier("_private".into()),
+                TokenType::Identifier("name123".into()),
+                TokenType::Identifier("camelCase".into()),
+                TokenType::Identifier("snake_case".into()),
             ]
         );
         
@@ -232,8 +232,8 @@ mod lexer_tests {
         assert_tokens(
             "defining ifdef",
             vec![
-                TokenType::Identifier("defining".to_string()),
-                TokenType::Identifier("ifdef".to_string()),
+                TokenType::Identifier("defining".into()),
+                TokenType::Identifier("ifdef".into()),
             ]
         );
     }
@@ -292,8 +292,8 @@ mod lexer_tests {
         assert_tokens(
             r#""hello" 'world'"#,
             vec![
-                TokenType::StringLiteral("hello".to_string()),
-                TokenType::StringLiteral("world".to_string()),
+                TokenType::StringLiteral("hello".into()),
+                TokenType::StringLiteral("world".into()),
             ]
         );
         
@@ -301,10 +301,10 @@ mod lexer_tests {
         assert_tokens(
             r#""hello\nworld" 'escaped\'quote' "tab\tchar" 'bell\a'"#,
             vec![
-                TokenType::StringLiteral("hello\nworld".to_string()),
-                TokenType::StringLiteral("escaped'quote".to_string()),
-                TokenType::StringLiteral("tab\tchar".to_string()),
-                TokenType::StringLiteral("bell\u{0007}".to_string()),
+                TokenType::StringLiteral("hello\nworld".into()),
+                TokenType::StringLiteral("escaped'quote".into()),
+                TokenType::StringLiteral("tab\tchar".into()),
+                TokenType::StringLiteral("bell\u{0007}".into()),
             ]
         );
         
@@ -312,8 +312,8 @@ mod lexer_tests {
         assert_tokens(
             r#""\x41\x42C" "\u00A9 copyright""#,
             vec![
-                TokenType::StringLiteral("ABC".to_string()),
-                TokenType::StringLiteral("© copyright".to_string()),
+                TokenType::StringLiteral("ABC".into()),
+                TokenType::StringLiteral("© copyright".into()),
             ]
         );
     }
@@ -324,8 +324,8 @@ mod lexer_tests {
         assert_tokens(
             r#"r"raw\nstring" R'another\tone'"#,
             vec![
-                TokenType::RawString("raw\\nstring".to_string()),
-                TokenType::RawString("another\\tone".to_string()),
+                TokenType::RawString("raw\\nstring".into()),
+                TokenType::RawString("another\\tone".into()),
             ]
         );
     }
@@ -336,8 +336,8 @@ mod lexer_tests {
         assert_tokens(
             r#"f"Hello, {name}!" F'Value: {2 + 2}'"#,
             vec![
-                TokenType::FString("Hello, {name}!".to_string()),
-                TokenType::FString("Value: {2 + 2}".to_string()),
+                TokenType::FString("Hello, {name}!".into()),
+                TokenType::FString("Value: {2 + 2}".into()),
             ]
         );
         
@@ -345,7 +345,7 @@ mod lexer_tests {
         assert_tokens(
             r#"f"Nested: {value if condition else {inner}}""#,
             vec![
-                TokenType::FString("Nested: {value if condition else {inner}}".to_string()),
+                TokenType::FString("Nested: {value if condition else {inner}}".into()),
             ]
         );
     }
@@ -368,8 +368,8 @@ mod lexer_tests {
         assert_tokens(
             r#""""Triple quoted string"""'''Another triple quoted'''"#,
             vec![
-                TokenType::StringLiteral("Triple quoted string".to_string()),
-                TokenType::StringLiteral("Another triple quoted".to_string()),
+                TokenType::StringLiteral("Triple quoted string".into()),
+                TokenType::StringLiteral("Another triple quoted".into()),
             ]
         );
         
@@ -377,7 +377,7 @@ mod lexer_tests {
         assert_tokens(
             "\"\"\"Multi\nline\nstring\"\"\"",
             vec![
-                TokenType::StringLiteral("Multi\nline\nstring".to_string()),
+                TokenType::StringLiteral("Multi\nline\nstring".into()),
             ]
         );
     }
@@ -388,8 +388,8 @@ mod lexer_tests {
         assert_tokens(
             r#"r"""Raw\nTriple"""f'''Format {x}'''"#,
             vec![
-                TokenType::RawString("Raw\\nTriple".to_string()),
-                TokenType::FString("Format {x}".to_string()),
+                TokenType::RawString("Raw\\nTriple".into()),
+                TokenType::FString("Format {x}".into()),
             ]
         );
         
@@ -511,15 +511,15 @@ mod lexer_tests {
         // Expected sequence of token types
         let expected = vec![
             TokenType::Def,
-            TokenType::Identifier("test".to_string()),
+            TokenType::Identifier("test".into()),
             TokenType::LeftParen,
             TokenType::RightParen,
             TokenType::Colon,
             TokenType::Newline,
             TokenType::Indent,
-            TokenType::Identifier("print".to_string()),
+            TokenType::Identifier("print".into()),
             TokenType::LeftParen,
-            TokenType::StringLiteral("indented".to_string()),
+            TokenType::StringLiteral("indented".into()),
             TokenType::RightParen,
             TokenType::Newline,
             TokenType::If,
@@ -527,9 +527,9 @@ mod lexer_tests {
             TokenType::Colon,
             TokenType::Newline,
             TokenType::Indent,
-            TokenType::Identifier("print".to_string()),
+            TokenType::Identifier("print".into()),
             TokenType::LeftParen,
-            TokenType::StringLiteral("nested".to_string()),
+            TokenType::StringLiteral("nested".into()),
             TokenType::RightParen,
             TokenType::Newline,
             TokenType::Dedent,
@@ -562,11 +562,11 @@ mod lexer_tests {
         let mut lexer = Lexer::new(input);
         let tokens = lexer.tokenize();
         let expected = vec![
-            Token::new(TokenType::Identifier("x".to_string()), 1, 1, "x".to_string()),
+            Token::new(TokenType::Identifier("x".into()), 1, 1, "x".to_string()),
             Token::new(TokenType::Assign, 1, 3, "=".to_string()),
             Token::new(TokenType::IntLiteral(5), 1, 5, "5".to_string()),
             Token::new(TokenType::Newline, 1, 16, "\n".to_string()), // Corrected to column 16
-            Token::new(TokenType::Identifier("y".to_string()), 2, 1, "y".to_string()),
+            Token::new(TokenType::Identifier("y".into()), 2, 1, "y".to_string()),
             Token::new(TokenType::Assign, 2, 3, "=".to_string()),
             Token::new(TokenType::IntLiteral(10), 2, 5, "10".to_string()),
             Token::new(TokenType::EOF, 2, 7, "".to_string()),
@@ -578,12 +578,12 @@ mod lexer_tests {
         let mut lexer = Lexer::new(input);
         let tokens = lexer.tokenize();
         let expected = vec![
-            Token::new(TokenType::Identifier("x".to_string()), 1, 1, "x".to_string()),
+            Token::new(TokenType::Identifier("x".into()), 1, 1, "x".to_string()),
             Token::new(TokenType::Assign, 1, 3, "=".to_string()),
             Token::new(TokenType::IntLiteral(5), 1, 5, "5".to_string()),
             Token::new(TokenType::Newline, 1, 6, "\n".to_string()),
             Token::new(TokenType::Newline, 2, 10, "\n".to_string()), // Corrected column
-            Token::new(TokenType::Identifier("y".to_string()), 3, 1, "y".to_string()),
+            Token::new(TokenType::Identifier("y".into()), 3, 1, "y".to_string()),
             Token::new(TokenType::Assign, 3, 3, "=".to_string()),
             Token::new(TokenType::IntLiteral(10), 3, 5, "10".to_string()),
             Token::new(TokenType::EOF, 3, 7, "".to_string()),
@@ -603,7 +603,7 @@ mod lexer_tests {
         
         // Expected sequence of token types
         let expected = vec![
-            TokenType::Identifier("x".to_string()),
+            TokenType::Identifier("x".into()),
             TokenType::Assign,
             TokenType::IntLiteral(1),
             TokenType::Plus,
@@ -626,24 +626,24 @@ mod lexer_tests {
         
         // Expected sequence of token types
         let expected = vec![
-            TokenType::Identifier("result".to_string()),
+            TokenType::Identifier("result".into()),
             TokenType::Assign,
             TokenType::LeftParen,
-            TokenType::Identifier("a".to_string()),
+            TokenType::Identifier("a".into()),
             TokenType::Plus,
-            TokenType::Identifier("b".to_string()),
+            TokenType::Identifier("b".into()),
             TokenType::RightParen,
             TokenType::Multiply,
             TokenType::LeftParen,
-            TokenType::Identifier("c".to_string()),
+            TokenType::Identifier("c".into()),
             TokenType::Minus,
-            TokenType::Identifier("d".to_string()),
+            TokenType::Identifier("d".into()),
             TokenType::RightParen,
             TokenType::Divide,
             TokenType::LeftParen,
-            TokenType::Identifier("e".to_string()),
+            TokenType::Identifier("e".into()),
             TokenType::Power,
-            TokenType::Identifier("f".to_string()),
+            TokenType::Identifier("f".into()),
             TokenType::RightParen,
             TokenType::EOF,
         ];
@@ -708,7 +708,40 @@ mod lexer_tests {
                 "Invalid number format should produce an Invalid token");
         assert_eq!(lexer.get_errors().len(), 1, "Should report exactly one error");
     }
-    
+
+    // Test integer literals that don't fit in 64 bits
+    #[test]
+    fn test_integer_literal_overflow() {
+        let mut lexer = Lexer::new("x = 99999999999999999999");
+        let tokens = lexer.tokenize();
+        assert!(matches!(tokens[2].token_type, TokenType::Invalid(_)),
+                "Oversized integer should produce an Invalid token");
+
+        let errors = lexer.get_errors();
+        assert_eq!(errors.len(), 1, "Should report exactly one error");
+        assert!(errors[0].message.contains("out of range"));
+        assert!(errors[0].message.contains("-9223372036854775808 and 9223372036854775807"));
+        assert_eq!(errors[0].column, 5);
+        let suggestion = errors[0].suggestion.as_deref().unwrap_or("");
+        assert!(suggestion.contains("99999999999999999999.0"));
+        assert!(suggestion.contains("\"99999999999999999999\""));
+
+        let mut lexer = Lexer::new("9223372036854775808");
+        lexer.tokenize();
+        let suggestion = lexer.get_errors()[0].suggestion.clone().unwrap_or_default();
+        assert!(suggestion.contains("-9223372036854775807 - 1"));
+
+        for input in ["0xFFFFFFFFFFFFFFFF", "0o7777777777777777777777", "0b1_0000000000000000000000000000000000000000000000000000000000000000"] {
+            let mut lexer = Lexer::new(input);
+            lexer.tokenize();
+            assert!(lexer.get_errors()[0].message.contains("out of range"), "{} should overflow", input);
+        }
+
+        let mut lexer = Lexer::new("9223372036854775807");
+        lexer.tokenize();
+        assert!(lexer.get_errors().is_empty(), "i64::MAX should lex");
+    }
+
     // Test invalid escape sequences
     #[test]
     fn test_invalid_escape_sequences() {
@@ -761,11 +794,11 @@ mod lexer_tests {
         
         // No newline tokens should appear between parentheses
         let expected = vec![
-            TokenType::Identifier("func".to_string()),
+            TokenType::Identifier("func".into()),
             TokenType::LeftParen,
-            TokenType::Identifier("arg1".to_string()),
+            TokenType::Identifier("arg1".into()),
             TokenType::Comma,
-            TokenType::Identifier("arg2".to_string()),
+            TokenType::Identifier("arg2".into()),
             TokenType::RightParen,
             TokenType::EOF,
         ];
@@ -883,7 +916,7 @@ bytes_data = b"\x00\x01\x02"
         // The lexer should tokenize this as IntLiteral(123) followed by Identifier("abc"), 
         // not as an Invalid token
         assert_eq!(tokens[0].token_type, TokenType::IntLiteral(123), "Should recognize 123 as an integer");
-        assert_eq!(tokens[1].token_type, TokenType::Identifier("abc".to_string()), "Should recognize abc as an identifier");
+        assert_eq!(tokens[1].token_type, TokenType::Identifier("abc".into()), "Should recognize abc as an identifier");
     }
 
     // Test edge cases for indentation with empty lines and comments
@@ -921,19 +954,19 @@ bytes_data = b"\x00\x01\x02"
             vec![
                 TokenType::If,
                 TokenType::LeftParen,
-                TokenType::Identifier("n".to_string()),
+                TokenType::Identifier("n".into()),
                 TokenType::Walrus,
-                TokenType::Identifier("len".to_string()),
+                TokenType::Identifier("len".into()),
                 TokenType::LeftParen,
-                TokenType::Identifier("items".to_string()),
+                TokenType::Identifier("items".into()),
                 TokenType::RightParen,
                 TokenType::RightParen,
                 TokenType::GreaterThan,
                 TokenType::IntLiteral(0),
                 TokenType::Colon,
-                TokenType::Identifier("print".to_string()),
+                TokenType::Identifier("print".into()),
                 TokenType::LeftParen,
-                TokenType::Identifier("n".to_string()),
+                TokenType::Identifier("n".into()),
                 TokenType::RightParen,
             ]
         );
@@ -963,7 +996,7 @@ bytes_data = b"\x00\x01\x02"
         assert_tokens(
             "items = [\n    1,\n    2,\n    3\n]",
             vec![
-                TokenType::Identifier("items".to_string()),
+                TokenType::Identifier("items".into()),
                 TokenType::Assign,
                 TokenType::LeftBracket,
                 TokenType::IntLiteral(1),
@@ -979,13 +1012,13 @@ bytes_data = b"\x00\x01\x02"
         assert_tokens(
             "result = func(\n    arg1,\n    arg2\n)",
             vec![
-                TokenType::Identifier("result".to_string()),
+                TokenType::Identifier("result".into()),
                 TokenType::Assign,
-                TokenType::Identifier("func".to_string()),
+                TokenType::Identifier("func".into()),
                 TokenType::LeftParen,
-                TokenType::Identifier("arg1".to_string()),
+                TokenType::Identifier("arg1".into()),
                 TokenType::Comma,
-                TokenType::Identifier("arg2".to_string()),
+                TokenType::Identifier("arg2".into()),
                 TokenType::RightParen,
             ]
         );
@@ -994,7 +1027,7 @@ bytes_data = b"\x00\x01\x02"
         assert_tokens(
             "result = 1 + \\\n    2 + \\\n    3",
             vec![
-                TokenType::Identifier("result".to_string()),
+                TokenType::Identifier("result".into()),
                 TokenType::Assign,
                 TokenType::IntLiteral(1),
                 TokenType::Plus,
@@ -1030,19 +1063,19 @@ bytes_data = b"\x00\x01\x02"
         assert_tokens(
             "a = 1e10\nb = 1.5e+20\nc = 1.5e-10\nd = .5e3",
             vec![
-                TokenType::Identifier("a".to_string()),
+                TokenType::Identifier("a".into()),
                 TokenType::Assign,
                 TokenType::FloatLiteral(1e10),
                 TokenType::Newline,
-                TokenType::Identifier("b".to_string()),
+                TokenType::Identifier("b".into()),
                 TokenType::Assign,
                 TokenType::FloatLiteral(1.5e20),
                 TokenType::Newline,
-                TokenType::Identifier("c".to_string()),
+                TokenType::Identifier("c".into()),
                 TokenType::Assign,
                 TokenType::FloatLiteral(1.5e-10),
                 TokenType::Newline,
-                TokenType::Identifier("d".to_string()),
+                TokenType::Identifier("d".into()),
                 TokenType::Assign,
                 TokenType::FloatLiteral(0.5e3),
             ]
@@ -1105,9 +1138,9 @@ bytes_data = b"\x00\x01\x02"
         assert_tokens(
             "long_string = \"This is a very \\\n    long string that \\\n    spans multiple lines\"",
             vec![
-                TokenType::Identifier("long_string".to_string()),
+                TokenType::Identifier("long_string".into()),
                 TokenType::Assign,
-                TokenType::StringLiteral("This is a very long string that spans multiple lines".to_string()),
+                TokenType::StringLiteral("This is a very long string that spans multiple lines".into()),
             ]
         );
         
@@ -1115,7 +1148,7 @@ bytes_data = b"\x00\x01\x02"
         assert_tokens(
             "result = (1 + \\\n          2) * \\\n         3",
             vec![
-                TokenType::Identifier("result".to_string()),
+                TokenType::Identifier("result".into()),
                 TokenType::Assign,
                 TokenType::LeftParen,
                 TokenType::IntLiteral(1),
@@ -1134,7 +1167,7 @@ bytes_data = b"\x00\x01\x02"
         assert_tokens(
             "x = [1, (2, 3), {'a': 4, 'b': [5, 6]}]",
             vec![
-                TokenType::Identifier("x".to_string()),
+                TokenType::Identifier("x".into()),
                 TokenType::Assign,
                 TokenType::LeftBracket,
                 TokenType::IntLiteral(1),
@@ -1146,11 +1179,11 @@ bytes_data = b"\x00\x01\x02"
                 TokenType::RightParen,
                 TokenType::Comma,
                 TokenType::LeftBrace,
-                TokenType::StringLiteral("a".to_string()),
+                TokenType::StringLiteral("a".into()),
                 TokenType::Colon,
                 TokenType::IntLiteral(4),
                 TokenType::Comma,
-                TokenType::StringLiteral("b".to_string()),
+                TokenType::StringLiteral("b".into()),
                 TokenType::Colon,
                 TokenType::LeftBracket,
                 TokenType::IntLiteral(5),
@@ -1170,7 +1203,7 @@ bytes_data = b"\x00\x01\x02"
         assert_tokens(
             r#""\1\22\377""#,
             vec![
-                TokenType::StringLiteral("\u{0001}\u{0012}\u{00FF}".to_string()),
+                TokenType::StringLiteral("\u{0001}\u{0012}\u{00FF}".into()),
             ]
         );
         
@@ -1178,7 +1211,7 @@ bytes_data = b"\x00\x01\x02"
         assert_tokens(
             r#""\u00A9\u2764\u{1F600}""#, // copyright, heart, smile emoji
             vec![
-                TokenType::StringLiteral("©❤😀".to_string()),
+                TokenType::StringLiteral("©❤😀".into()),
             ]
         );
         
@@ -1186,8 +1219,8 @@ bytes_data = b"\x00\x01\x02"
         assert_tokens(
             r#"r"C:\path\to\file" r'\'quoted\''"#,
             vec![
-                TokenType::RawString(r"C:\path\to\file".to_string()),
-                TokenType::RawString(r"\'quoted\'".to_string()),
+                TokenType::RawString(r"C:\path\to\file".into()),
+                TokenType::RawString(r"\'quoted\'".into()),
             ]
         );
     }
@@ -1213,23 +1246,23 @@ bytes_data = b"\x00\x01\x02"
         assert_tokens(
             "a = 1_000_000\nb = 0b1010_1010\nc = 0o777_333\nd = 0xFF_FF_FF\ne = 3.14_15_92",
             vec![
-                TokenType::Identifier("a".to_string()),
+                TokenType::Identifier("a".into()),
                 TokenType::Assign,
                 TokenType::IntLiteral(1000000),
                 TokenType::Newline,
-                TokenType::Identifier("b".to_string()),
+                TokenType::Identifier("b".into()),
                 TokenType::Assign,
                 TokenType::BinaryLiteral(170), // 0b10101010
                 TokenType::Newline,
-                TokenType::Identifier("c".to_string()),
+                TokenType::Identifier("c".into()),
                 TokenType::Assign,
                 TokenType::OctalLiteral(261851), // 0o777333
                 TokenType::Newline,
-                TokenType::Identifier("d".to_string()),
+                TokenType::Identifier("d".into()),
                 TokenType::Assign,
                 TokenType::HexLiteral(16777215), // 0xFFFFFF
                 TokenType::Newline,
-                TokenType::Identifier("e".to_string()),
+                TokenType::Identifier("e".into()),
                 TokenType::Assign,
                 TokenType::FloatLiteral(3.141592),
             ]
@@ -1254,7 +1287,7 @@ bytes_data = b"\x00\x01\x02"
         // Extract just the docstring
         let docstring = tokens.iter().find_map(|t| {
             if let TokenType::StringLiteral(s) = &t.token_type {
-                Some(s.as_str())
+                Some(&**s)
             } else {
                 None
             }
@@ -1270,7 +1303,7 @@ bytes_data = b"\x00\x01\x02"
         assert_tokens(
             r#"f"Hello, {name}!""#,
             vec![
-                TokenType::FString("Hello, {name}!".to_string()),
+                TokenType::FString("Hello, {name}!".into()),
             ]
         );
         
@@ -1278,7 +1311,7 @@ bytes_data = b"\x00\x01\x02"
         assert_tokens(
             r#"f"Value: {2 + 3 * {4 + 5}}""#,
             vec![
-                TokenType::FString("Value: {2 + 3 * {4 + 5}}".to_string()),
+                TokenType::FString("Value: {2 + 3 * {4 + 5}}".into()),
             ]
         );
         
@@ -1286,7 +1319,7 @@ bytes_data = b"\x00\x01\x02"
         assert_tokens(
             r#"f"Items: {', '.join(f'{k}={v}' for k, v in items.items())}""#,
             vec![
-                TokenType::FString("Items: {', '.join(f'{k}={v}' for k, v in items.items())}".to_string()),
+                TokenType::FString("Items: {', '.join(f'{k}={v}' for k, v in items.items())}".into()),
             ]
         );
         
@@ -1320,7 +1353,7 @@ bytes_data = b"\x00\x01\x02"
         let tokens = lexer.tokenize();
         
         assert_eq!(tokens.len(), 2, "Should have StringLiteral and EOF");
-        assert_eq!(tokens[0].token_type, TokenType::StringLiteral(large_string), 
+        assert_eq!(tokens[0].token_type, TokenType::StringLiteral(large_string.into()), 
                     "Should handle large string correctly");
         assert_eq!(lexer.get_errors().len(), 0, "Should process large string without errors");
     }
@@ -1353,7 +1386,7 @@ bytes_data = b"\x00\x01\x02"
         let tokens = lexer.tokenize();
         
         let backslash_idx = tokens.iter().position(|t| t.token_type == TokenType::BackSlash).unwrap();
-        assert_eq!(tokens[backslash_idx + 1].token_type, TokenType::Identifier("y".to_string()), 
+        assert_eq!(tokens[backslash_idx + 1].token_type, TokenType::Identifier("y".into()), 
                     "Should tokenize content after standalone backslash");
     }
 
@@ -1362,14 +1395,14 @@ bytes_data = b"\x00\x01\x02"
         assert_tokens(
             "x = ... y = .. z = . . .",
             vec![
-                TokenType::Identifier("x".to_string()),
+                TokenType::Identifier("x".into()),
                 TokenType::Assign,
                 TokenType::Ellipsis,
-                TokenType::Identifier("y".to_string()),
+                TokenType::Identifier("y".into()),
                 TokenType::Assign,
                 TokenType::Dot,
                 TokenType::Dot,
-                TokenType::Identifier("z".to_string()),
+                TokenType::Identifier("z".into()),
                 TokenType::Assign,
                 TokenType::Dot,
                 TokenType::Dot,
@@ -1383,7 +1416,7 @@ bytes_data = b"\x00\x01\x02"
         assert_tokens(
             r#""\U0001F600""#, // 😀 emoji (requires surrogate pair in UTF-16)
             vec![
-                TokenType::StringLiteral("😀".to_string()),
+                TokenType::StringLiteral("😀".into()),
             ]
         );
     }
@@ -1443,7 +1476,7 @@ bytes_data = b"\x00\x01\x02"
         assert_tokens(
             r#"'He said \"Hello\"'"#,
             vec![
-                TokenType::StringLiteral("He said \"Hello\"".to_string()),
+                TokenType::StringLiteral("He said \"Hello\"".into()),
             ]
         );
     }
@@ -1451,7 +1484,7 @@ bytes_data = b"\x00\x01\x02"
     #[test]
     fn test_string_with_line_continuation() {
         let input = "\"Line split \\\n    here\"";
-        assert_tokens(input, vec![TokenType::StringLiteral("Line split here".to_string())]);
+        assert_tokens(input, vec![TokenType::StringLiteral("Line split here".into())]);
     }
 
     #[test]
@@ -1471,7 +1504,7 @@ bytes_data = b"\x00\x01\x02"
         assert_tokens(input, vec![
             TokenType::Newline,
             TokenType::Newline,
-            TokenType::Identifier("x".to_string()),
+            TokenType::Identifier("x".into()),
             TokenType::Assign,
             TokenType::IntLiteral(1),
         ]);
@@ -1502,7 +1535,7 @@ bytes_data = b"\x00\x01\x02"
     fn test_comment_after_line_continuation() {
         let input = "x = 1 + \\\n# Comment\n    2";
         assert_tokens(input, vec![
-            TokenType::Identifier("x".to_string()),
+            TokenType::Identifier("x".into()),
             TokenType::Assign,
             TokenType::IntLiteral(1),
             TokenType::Plus,
@@ -1520,6 +1553,96 @@ bytes_data = b"\x00\x01\x02"
     }
 
     #[test]
+fn test_soft_keywords_as_names() {
+    let input = "match = 1\ncase = match + 1\nf(match, x.case)\nmatch(x)\n";
+    let mut lexer = Lexer::new(input);
+    let tokens = lexer.tokenize();
+
+    assert!(lexer.get_errors().is_empty());
+    assert!(!tokens
+        .iter()
+        .any(|t| matches!(t.token_type, TokenType::Match | TokenType::Case)));
+    let names = tokens
+        .iter()
+        .filter(|t| matches!(&t.token_type, TokenType::Identifier(s) if s == "match" || s == "case"))
+        .count();
+    assert_eq!(names, 6);
+}
+
+#[test]
+fn test_soft_keywords_start_statements() {
+    let input = "match (point):\n    case (0, 0):\n        pass\n    case [x, *rest]:  # comment:\n        pass\n";
+    let tokens = Lexer::new(input).tokenize();
+
+    assert_eq!(tokens.iter().filter(|t| t.token_type == TokenType::Match).count(), 1);
+    assert_eq!(tokens.iter().filter(|t| t.token_type == TokenType::Case).count(), 2);
+}
+
+#[test]
+fn test_reserved_soft_keywords() {
+    let config = LexerConfig {
+        reserve_soft_keywords: true,
+        ..LexerConfig::default()
+    };
+    let tokens = Lexer::with_config("match = 1\n", config).tokenize();
+    assert_eq!(tokens[0].token_type, TokenType::Match);
+}
+
+fn lex_lossless(input: &str) -> Vec<Token<'_>> {
+    let config = LexerConfig {
+        lossless: true,
+        ..LexerConfig::default()
+    };
+    Lexer::with_config(input, config).tokenize()
+}
+
+#[test]
+fn test_lossless_round_trip() {
+    use cheetah::lexer::tokens_to_source;
+
+    let sources = [
+        "",
+        "x = 1",
+        "# header\n\n\ndef f(a,  b):  # trailing\n    return a + \\\n        b\n\n\n",
+        "if x:\r\n\tpass\r\n",
+        "items = [\n    1,  # one\n    2,\n]\nprint(items)  ",
+        "s = 'a\\'b' + \"\"\"multi\nline\"\"\" + f\"{x!r:>4}\"\n",
+        "class A:\n    def m(self):\n        pass\n# done",
+    ];
+    for source in sources {
+        assert_eq!(tokens_to_source(&lex_lossless(source)), source);
+    }
+}
+
+#[test]
+fn test_lossless_token_source() {
+    let tokens = lex_lossless("x  =  1  # one\n");
+
+    let one = tokens.iter().find(|t| t.lexeme == "1").unwrap();
+    let source = one.source.as_ref().unwrap();
+    assert_eq!(source.leading, "  ");
+    assert_eq!(source.text, "1");
+
+    let newline = tokens.iter().find(|t| t.token_type == TokenType::Newline).unwrap();
+    assert_eq!(newline.source.as_ref().unwrap().leading, "  # one");
+
+    // Tokens only carry their source when asked for
+    assert!(Lexer::new("x = 1\n").tokenize().iter().all(|t| t.source.is_none()));
+}
+
+#[test]
+fn test_keyword_table() {
+    use cheetah::lexer::keywords;
+
+    assert_eq!(keywords::lookup("def").map(|k| &k.token), Some(&TokenType::Def));
+    assert!(keywords::lookup("define").is_none());
+    assert!(keywords::is_soft_keyword("match"));
+    assert!(keywords::is_soft_keyword("case"));
+    assert!(!keywords::is_soft_keyword("while"));
+    assert_eq!(keywords::for_token(&TokenType::Case).map(|k| k.text), Some("case"));
+}
+
+#[test]
 fn test_match_and_case_keywords() {
     // Test basic match/case structure
     let input = "
@@ -1832,4 +1955,129 @@ print('No indentation')
         prev_line = token.line;
     }
 }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_needs_more_input() {
+        use cheetah::lexer::needs_more_input;
+
+        // Complete single statements
+        assert!(!needs_more_input("x = 1\n"));
+        assert!(!needs_more_input("print(\"{\")\n"));
+        assert!(!needs_more_input("x = 1  # (unclosed in a comment\n"));
+
+        // Open brackets, strings and continuations
+        assert!(needs_more_input("x = [1,\n"));
+        assert!(needs_more_input("d = {\"a\": (\n"));
+        assert!(needs_more_input("s = \"\"\"first line\n"));
+        assert!(!needs_more_input("s = \"\"\"first line\nsecond\"\"\"\n"));
+        assert!(needs_more_input("x = 1 + \\\n"));
+
+        // Blocks run once a blank line closes them
+        assert!(needs_more_input("if x:\n"));
+        assert!(needs_more_input("if x:\n    print(\"}\")\n"));
+        assert!(!needs_more_input("if x:\n    print(\"}\")\n\n"));
+    }
+
+    #[test]
+    fn test_forbidden_constructs() {
+        use cheetah::lexer::Construct;
+
+        let config = LexerConfig {
+            forbidden: Construct::profile("learning").unwrap(),
+            ..LexerConfig::default()
+        };
+        let source = "f = lambda x: x\nif (n := 1):\n    pass\nasync def g():\n    await h()\n";
+        let mut lexer = Lexer::with_config(source, config);
+        lexer.tokenize();
+
+        let errors = lexer.get_errors();
+        let positions: Vec<(usize, usize)> = errors.iter().map(|e| (e.line, e.column)).collect();
+        assert_eq!(positions, [(1, 5), (2, 7), (4, 1), (5, 5)]);
+        assert!(errors.iter().all(|e| e.code() == "forbidden-construct"));
+        assert_eq!(errors[0].message, "Lambda expressions are not allowed in this project");
+        assert!(errors[0].suggestion.as_deref().unwrap().contains("def"));
+
+        // Nothing is forbidden by default, and `yield` isn't by the learning profile
+        let mut lexer = Lexer::new(source);
+        lexer.tokenize();
+        assert!(lexer.get_errors().is_empty());
+        let mut lexer = Lexer::with_config("def g():\n    yield 1\n", LexerConfig {
+            forbidden: Construct::profile("learning").unwrap(),
+            ..LexerConfig::default()
+        });
+        lexer.tokenize();
+        assert!(lexer.get_errors().is_empty());
+
+        assert_eq!(Construct::from_name("walrus"), Ok(Construct::Walrus));
+        assert!(Construct::from_name("goto").is_err());
+        assert!(Construct::profile("expert").is_err());
+    }
+
+    #[test]
+    fn test_tokens_borrow_source() {
+        use std::borrow::Cow;
+
+        let source = "name = 'plain' + \"esc\\n\" + r'\\d' + f'{name}!' + '''doc'''\n";
+        let mut lexer = Lexer::new(source);
+        let tokens = lexer.tokenize();
+        assert!(lexer.get_errors().is_empty());
+
+        // Every lexeme is a slice of the source
+        assert!(tokens.iter().all(|t| matches!(t.lexeme, Cow::Borrowed(_))));
+        assert!(matches!(&tokens[0].token_type, TokenType::Identifier(Cow::Borrowed("name"))));
+
+        // Only a string with escapes to decode owns its value
+        let values: Vec<(&str, bool)> = tokens
+            .iter()
+            .filter_map(|t| match &t.token_type {
+                TokenType::StringLiteral(value) | TokenType::RawString(value) | TokenType::FString(value) => {
+                    Some((&**value, matches!(value, Cow::Borrowed(_))))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            values,
+            [("plain", true), ("esc\n", false), ("\\d", true), ("{name}!", true), ("doc", true)]
+        );
+
+        // Token types outlive the source once owned
+        let owned = {
+            let text = String::from("other");
+            Lexer::new(&text).tokenize()[0].token_type.clone().into_owned()
+        };
+        assert_eq!(owned, TokenType::Identifier("other".into()));
+    }
+
+    #[test]
+    fn test_lexer_streams_tokens_on_demand() {
+        let input = "def f(x):\n    if x:\n        return [x,\n            1]\n    return 0\ny = $\n";
+
+        // Iterating yields what tokenize() collects, indents and dedents included
+        let streamed: Vec<Token> = Lexer::new(input).collect();
+        assert_eq!(streamed, Lexer::new(input).tokenize());
+
+        // Nothing past the tokens asked for is lexed
+        let mut lexer = Lexer::new(input);
+        let first: Vec<TokenType> = lexer.by_ref().take(3).map(|t| t.token_type).collect();
+        assert_eq!(first, [TokenType::Def, TokenType::Identifier("f".into()), TokenType::LeftParen]);
+        assert!(lexer.get_errors().is_empty());
+
+        let rest: Vec<Token> = lexer.by_ref().collect();
+        assert_eq!(rest.last().map(|t| &t.token_type), Some(&TokenType::EOF));
+        assert_eq!(lexer.get_errors().len(), 1);
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn test_streamed_tokens_are_lossless() {
+        let input = "if a:\n    b = 1  # one\n";
+        let config = LexerConfig {
+            lossless: true,
+            ..Default::default()
+        };
+        let tokens: Vec<Token> = Lexer::with_config(input, config).collect();
+        assert!(tokens.iter().all(|token| token.source.is_some()));
+        assert_eq!(cheetah::lexer::tokens_to_source(&tokens), input);
+    }
+}
@@ -0,0 +1,50 @@
+use cheetah::ast_dot::module_to_dot;
+use cheetah::parse;
+
+fn dot(source: &str) -> String {
+    module_to_dot(&parse(source).unwrap())
+}
+
+/// The label of the node with `id`
+fn label<'a>(graph: &'a str, id: &str) -> &'a str {
+    let prefix = format!("    {} [label=\"", id);
+    let line = graph.lines().find(|line| line.starts_with(&prefix)).unwrap();
+    &line[prefix.len()..line.len() - 3]
+}
+
+#[test]
+fn test_dot_graph_has_a_node_per_ast_node() {
+    let graph = dot("x = 1 + y\n");
+    assert!(graph.starts_with("digraph AST {\n"), "{}", graph);
+    assert!(graph.ends_with("}\n"), "{}", graph);
+
+    // Module, Assign, Name x, BinOp, Num, Name y
+    assert_eq!(graph.matches("[label=\"").count() - graph.matches(" -> ").count(), 6, "{}", graph);
+    assert_eq!(graph.matches(" -> ").count(), 5, "{}", graph);
+    assert_eq!(label(&graph, "n0"), "Module");
+    assert_eq!(label(&graph, "n1"), "Assign");
+    assert!(graph.contains("    n0 -> n1 [label=\"body\"];"), "{}", graph);
+    assert!(graph.contains("BinOp\\nop = Add"), "{}", graph);
+    assert!(graph.contains("Num\\nvalue = 1"), "{}", graph);
+    assert!(graph.contains("Name\\nctx = Load\\nid = y"), "{}", graph);
+    assert!(!graph.contains("line"), "{}", graph);
+}
+
+#[test]
+fn test_dot_graph_labels_helper_structs_and_strings() {
+    let graph = dot("def greet(name):\n    print(\"hi \\\"there\\\"\", name)\n");
+    assert!(graph.contains("FunctionDef\\nis_async = false\\nname = greet"), "{}", graph);
+    assert!(graph.contains("Parameter\\nis_kwarg = false\\nis_vararg = false\\nname = name"), "{}", graph);
+    assert!(graph.contains("[label=\"params\"]"), "{}", graph);
+    // Quotes inside string constants are escaped for Graphviz
+    assert!(graph.contains(r#"[label="Str\nvalue = hi \"there\""]"#), "{}", graph);
+}
+
+#[test]
+fn test_dot_graph_names_tuple_parts() {
+    let graph = dot("match x:\n    case 1:\n        pass\n");
+    assert!(graph.contains("[label=\"MatchCase\"]"), "{}", graph);
+    assert!(graph.contains("[label=\"pattern\"]"), "{}", graph);
+    assert!(graph.contains("[label=\"body\"]"), "{}", graph);
+    assert!(!graph.contains("[label=\"guard\"]"), "{}", graph);
+}
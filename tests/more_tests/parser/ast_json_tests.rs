@@ -0,0 +1,69 @@
+use cheetah::ast::{Expr, Module, Stmt};
+use cheetah::formatter::CodeFormatter;
+use cheetah::parse;
+use cheetah::visitor::Visitor;
+use serde_json::Value;
+
+#[test]
+fn test_json_round_trip_keeps_the_tree() {
+    let source = r#"
+import math as m
+
+class Point(Base):
+    def __init__(self, x: float = 1.5, *rest, **extra):
+        self.x = x if x > 0 else -x
+
+def area(points):
+    total = 0
+    for p in points:
+        try:
+            total += p.x ** 2
+        except ValueError as e:
+            raise
+    return [q for q in points if q], {k: v for k, v in pairs}, f"{total}!", b"ok", None
+"#;
+    let module = parse(source).unwrap();
+    let loaded = Module::from_json(&module.to_json()).unwrap();
+    assert_eq!(loaded.to_json(), module.to_json());
+    assert_eq!(format!("{:?}", loaded), format!("{:?}", module));
+}
+
+#[test]
+fn test_json_nodes_are_tagged_with_their_kind() {
+    let source = "x = y + 1\n";
+    let module = parse(source).unwrap();
+    let json: Value = serde_json::from_str(&module.to_json()).unwrap();
+
+    let assign = &json["body"][0];
+    assert_eq!(assign["node"], "Assign");
+    assert_eq!(assign["targets"][0]["node"], "Name");
+    assert_eq!(assign["value"]["left"]["ctx"], "Load");
+    assert_eq!(assign["value"]["node"], "BinOp");
+    assert_eq!(assign["value"]["op"], "Add");
+    assert_eq!(assign["value"]["right"]["value"]["Integer"], 1);
+    assert_eq!(assign["span"]["start"], 0);
+    assert_eq!(assign["span"]["end"], 9);
+}
+
+#[test]
+fn test_trees_edited_as_json_load_back() {
+    let module = parse("total = count\n").unwrap();
+    let mut json: Value = serde_json::from_str(&module.to_json()).unwrap();
+    json["body"][0]["value"]["id"] = "limit".into();
+
+    let edited = Module::from_json(&json.to_string()).unwrap();
+    let Stmt::Assign { value, .. } = &*edited.body[0] else {
+        panic!("expected an assignment, got {:?}", edited.body[0]);
+    };
+    assert!(matches!(&**value, Expr::Name { id, .. } if id == "limit"));
+    let mut formatter = CodeFormatter::new(4);
+    formatter.visit_module(&edited);
+    assert_eq!(formatter.get_output(), "total = limit\n");
+}
+
+#[test]
+fn test_malformed_json_is_an_error() {
+    let error = Module::from_json(r#"{"body": [{"node": "Frobnicate"}]}"#).unwrap_err();
+    assert!(error.starts_with("Invalid syntax tree JSON"), "{}", error);
+    assert!(Module::from_json("not json").is_err());
+}
@@ -64,3 +64,44 @@ for in range(10): # Missing target
         assert!(has_for_error, "Should have an error for the 'for in range' line");
     }
 }
+
+fn nested_parens(depth: usize) -> String {
+    format!("x = {}1{}\ny = 2\n", "(".repeat(depth), ")".repeat(depth))
+}
+
+#[test]
+fn test_nesting_limit() {
+    use cheetah::parser::Parser;
+
+    let source = nested_parens(10);
+    let tokens = Lexer::new(&source).tokenize();
+    assert!(Parser::with_max_depth(tokens, 20).parse().is_ok());
+
+    let sources = [
+        nested_parens(500),
+        format!("x = {}1\ny = 2\n", "-".repeat(500)),
+        format!("x = {}1\ny = 2\n", "2 ** ".repeat(500)),
+    ];
+    for source in sources {
+        let tokens = Lexer::new(&source).tokenize();
+        let errors = Parser::with_max_depth(tokens, 20).parse().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message().contains("too deeply nested"));
+        assert_eq!(errors[0].line(), 1);
+    }
+}
+
+#[test]
+fn test_default_nesting_limit() {
+    // Reaching the default limit takes more stack than a test thread has in debug builds
+    let result = std::thread::Builder::new()
+        .stack_size(64 * 1024 * 1024)
+        .spawn(|| parse_code(&nested_parens(5000)))
+        .unwrap()
+        .join()
+        .unwrap();
+
+    let errors = result.unwrap_err();
+    assert!(errors[0].message().contains("too deeply nested"));
+    assert!(errors[0].suggestion().unwrap().contains(&cheetah::parser::DEFAULT_MAX_DEPTH.to_string()));
+}
@@ -0,0 +1,156 @@
+// format_corpus_tests.rs - Snapshot tests for the formatter over tests/format
+//
+// Each `tests/format/<name>.ch` is formatted at a fixed line width and
+// compared against `<name>.expected`. Formatting must also be a fixed point,
+// and the formatted code must parse to the same tree as the original,
+// positions aside. Run with
+// `CHEETAH_BLESS=1` to rewrite the expected outputs after an intended change.
+
+use cheetah::formatter::CodeFormatter;
+use cheetah::visitor::Visitor;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const INDENT_SIZE: usize = 4;
+
+const LINE_WIDTH: usize = 88;
+
+fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("format")
+}
+
+/// Every input in the corpus, sorted by name
+fn corpus() -> Vec<PathBuf> {
+    let mut inputs: Vec<PathBuf> = fs::read_dir(corpus_dir())
+        .expect("tests/format should exist")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ch"))
+        .collect();
+    inputs.sort();
+    assert!(!inputs.is_empty(), "the formatter corpus is empty");
+    inputs
+}
+
+fn format(path: &Path, source: &str) -> String {
+    let module = cheetah::parse(source)
+        .unwrap_or_else(|e| panic!("{} doesn't parse: {:?}", path.display(), e));
+    let mut formatter = CodeFormatter::new(INDENT_SIZE).with_max_width(LINE_WIDTH);
+    formatter.visit_module(&module);
+    formatter.get_output().to_string()
+}
+
+/// Debug form of the parsed module with every line and column zeroed
+fn structure(path: &Path, source: &str) -> String {
+    let module = cheetah::parse(source)
+        .unwrap_or_else(|e| panic!("{} doesn't parse: {:?}", path.display(), e));
+    let tree = format!("{:?}", module);
+
+    let mut normalized = String::with_capacity(tree.len());
+    let mut rest = tree.as_str();
+    while let Some(start) = rest.find(|c: char| c.is_ascii_digit()) {
+        let (before, digits) = rest.split_at(start);
+        let end = digits
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(digits.len());
+        normalized.push_str(before);
+        let position = ["line: ", "column: ", "start: ", "end: "];
+        if position.iter().any(|field| before.ends_with(field)) {
+            normalized.push('0');
+        } else {
+            normalized.push_str(&digits[..end]);
+        }
+        rest = &digits[end..];
+    }
+    normalized.push_str(rest);
+    normalized
+}
+
+#[test]
+fn test_corpus_matches_expected_output() {
+    let bless = std::env::var_os("CHEETAH_BLESS").is_some();
+    let mut mismatched = Vec::new();
+
+    for input in corpus() {
+        let source = fs::read_to_string(&input).unwrap();
+        let formatted = format(&input, &source);
+        let expected_path = input.with_extension("expected");
+
+        if bless {
+            fs::write(&expected_path, &formatted).unwrap();
+            continue;
+        }
+
+        let expected = fs::read_to_string(&expected_path)
+            .unwrap_or_else(|_| panic!("{} is missing", expected_path.display()));
+        if formatted != expected {
+            eprintln!(
+                "--- {}\n{}--- formatted\n{}",
+                expected_path.display(),
+                expected,
+                formatted
+            );
+            mismatched.push(input.display().to_string());
+        }
+    }
+
+    assert!(
+        mismatched.is_empty(),
+        "formatted output differs from the expected output for {:?}; rerun with CHEETAH_BLESS=1 if the change is intended",
+        mismatched
+    );
+}
+
+#[test]
+fn test_formatting_is_idempotent() {
+    for input in corpus() {
+        let once = format(&input, &fs::read_to_string(&input).unwrap());
+        let twice = format(&input, &once);
+        assert_eq!(
+            once,
+            twice,
+            "formatting {} again changed it",
+            input.display()
+        );
+    }
+}
+
+#[test]
+fn test_formatting_preserves_structure() {
+    for input in corpus() {
+        let source = fs::read_to_string(&input).unwrap();
+        let formatted = format(&input, &source);
+        assert_eq!(
+            structure(&input, &source),
+            structure(&input, &formatted),
+            "formatting {} changed what it parses to",
+            input.display()
+        );
+    }
+}
+
+#[test]
+fn test_formatted_lines_fit_the_line_width() {
+    for input in corpus() {
+        let formatted = format(&input, &fs::read_to_string(&input).unwrap());
+        for line in formatted.lines() {
+            assert!(
+                line.chars().count() <= LINE_WIDTH,
+                "formatting {} left a line wider than {}: {}",
+                input.display(),
+                LINE_WIDTH,
+                line
+            );
+        }
+    }
+}
+
+#[test]
+fn test_no_line_width_keeps_lines_whole() {
+    let source = fs::read_to_string(corpus_dir().join("wrapping.ch")).unwrap();
+    let formatted = cheetah::format_code(&source, INDENT_SIZE).unwrap();
+    assert!(
+        formatted.lines().all(|line| !line.ends_with(['(', '[', '{'])),
+        "formatting without a line width split a line:\n{}",
+        formatted
+    );
+}
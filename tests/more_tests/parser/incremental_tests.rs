@@ -0,0 +1,115 @@
+use cheetah::ast::Span;
+use cheetah::parse;
+use cheetah::parser::incremental::IncrementalParser;
+use cheetah::references::TextEdit;
+use std::ops::Range;
+
+const SOURCE: &str = "# totals\nimport math\n\ndef area(r):\n    return math.pi * r ** 2\n\nx = area(2)\nprint(x)\n";
+
+/// Replace the first occurrence of `old` after byte `from` with `new`
+fn replace(file: &mut IncrementalParser, from: usize, old: &str, new: &str) -> Result<Range<usize>, String> {
+    let start = from + file.source()[from..].find(old).unwrap();
+    file.edit(&TextEdit {
+        line: 0,
+        column: 0,
+        end_column: 0,
+        span: Span {
+            start,
+            end: start + old.len(),
+            ..Span::default()
+        },
+        new_text: new.to_string(),
+    })
+}
+
+/// Check the kept tree, lines and spans included, is the one a whole parse gives
+fn assert_same_as_whole_parse(file: &IncrementalParser) {
+    let whole = parse(file.source()).unwrap();
+    assert_eq!(format!("{:?}", file.module().unwrap()), format!("{:?}", whole));
+}
+
+#[test]
+fn test_edit_reparses_only_the_statement_it_touches() {
+    let mut file = IncrementalParser::new(SOURCE);
+    let reparsed = replace(&mut file, 0, "area(2)", "area(3)").unwrap();
+    assert_eq!(&file.source()[reparsed], "x = area(3)\n");
+    assert_same_as_whole_parse(&file);
+
+    let reparsed = replace(&mut file, 0, "r ** 2", "r * r").unwrap();
+    assert_eq!(&file.source()[reparsed], "def area(r):\n    return math.pi * r * r\n\n");
+    assert_same_as_whole_parse(&file);
+}
+
+#[test]
+fn test_edit_moves_the_statements_after_it() {
+    let mut file = IncrementalParser::new(SOURCE);
+    replace(&mut file, 0, "    return", "    \"\"\"Area of a circle\"\"\"\n\n    return").unwrap();
+    assert_same_as_whole_parse(&file);
+
+    let module = file.module().unwrap();
+    let print = module.body.last().unwrap();
+    assert_eq!(print.line(), 10);
+    assert_eq!(print.span().text(file.source()), "print(x)");
+
+    replace(&mut file, 0, "\"\"\"Area of a circle\"\"\"\n\n", "").unwrap();
+    assert_same_as_whole_parse(&file);
+    assert_eq!(file.module().unwrap().body.last().unwrap().line(), 8);
+}
+
+#[test]
+fn test_edit_can_join_and_split_statements() {
+    let mut file = IncrementalParser::new(SOURCE);
+    // Indenting a line moves it into the function before it
+    replace(&mut file, 0, "x = area(2)\n", "    x = area(2)\n").unwrap();
+    assert_same_as_whole_parse(&file);
+    assert_eq!(file.module().unwrap().body.len(), 3);
+
+    replace(&mut file, 0, "    x = area(2)\n", "x = area(2)\n").unwrap();
+    assert_same_as_whole_parse(&file);
+    assert_eq!(file.module().unwrap().body.len(), 4);
+
+    // Indenting a line after a statement with no block can't parse on its own
+    let reparsed = replace(&mut file, 0, "print(x)", "    print(x)").unwrap();
+    assert_eq!(reparsed, 0..file.source().len());
+    assert!(file.module().is_err());
+}
+
+#[test]
+fn test_edit_that_opens_a_bracket_parses_the_whole_file() {
+    let mut file = IncrementalParser::new(SOURCE);
+    let reparsed = replace(&mut file, 0, "x = area(2)\n", "x = area(\n").unwrap();
+    assert_eq!(reparsed, 0..file.source().len());
+    assert!(file.module().is_err());
+
+    // Once the file parses again, edits are incremental again
+    replace(&mut file, 0, "x = area(\n", "x = area(2)\n").unwrap();
+    assert_same_as_whole_parse(&file);
+    // An edit at the start of a line could join it to the statement before
+    let reparsed = replace(&mut file, 0, "print(x)", "print(x, x)").unwrap();
+    assert_eq!(&file.source()[reparsed], "x = area(2)\nprint(x, x)\n");
+    assert_same_as_whole_parse(&file);
+
+    // A comment before the first statement belongs to it
+    let reparsed = replace(&mut file, 0, "# totals", "# areas").unwrap();
+    assert_eq!(&file.source()[reparsed], "# areas\nimport math\n\n");
+    assert_same_as_whole_parse(&file);
+}
+
+#[test]
+fn test_edit_outside_the_source_is_an_error() {
+    let mut file = IncrementalParser::new("x = \"é\"\n");
+    let edit = |start, end| TextEdit {
+        line: 0,
+        column: 0,
+        end_column: 0,
+        span: Span {
+            start,
+            end,
+            ..Span::default()
+        },
+        new_text: String::new(),
+    };
+    assert!(file.edit(&edit(3, 100)).unwrap_err().contains("outside"));
+    assert!(file.edit(&edit(6, 7)).unwrap_err().contains("splits a character"));
+    assert_eq!(file.source(), "x = \"é\"\n");
+}
@@ -482,7 +482,7 @@ mod parser_specialized_tests {
                 // Test simple number literal
                 let module = assert_parses("42");
                 if let Some(stmt) = module.body.first() {
-                    if let Stmt::Expr { value, line: _, column: _ } = &**stmt {
+                    if let Stmt::Expr { value, line: _, column: _, span: _ } = &**stmt {
                         if let Expr::Num { value: num, .. } = &**value {
                             assert_eq!(*num, Number::Integer(42));
                         } else {
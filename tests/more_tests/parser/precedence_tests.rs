@@ -0,0 +1,115 @@
+use cheetah::ast::{Expr, Operator, UnaryOperator};
+use cheetah::parser::parse_expression;
+use cheetah::lexer::TokenType;
+use cheetah::parser::precedence::{self, Precedence, LEVELS};
+
+#[test]
+fn test_levels_are_in_precedence_order() {
+    for (i, level) in LEVELS.iter().enumerate() {
+        assert_eq!(level.precedence as usize, i, "{:?} is out of order", level.precedence);
+    }
+}
+
+#[test]
+fn test_infix_and_prefix_lookup() {
+    let (level, _) = precedence::infix(&TokenType::Minus).unwrap();
+    assert_eq!(level.precedence, Precedence::Arithmetic);
+    let (level, _) = precedence::prefix(&TokenType::Minus).unwrap();
+    assert_eq!(level.precedence, Precedence::Unary);
+
+    // `not` starts `not in` after an operand and `not x` before one
+    let (level, _) = precedence::infix(&TokenType::Not).unwrap();
+    assert_eq!(level.precedence, Precedence::Comparison);
+    let (level, _) = precedence::prefix(&TokenType::Not).unwrap();
+    assert_eq!(level.precedence, Precedence::Not);
+
+    let (level, _) = precedence::infix(&TokenType::At).unwrap();
+    assert_eq!(level.precedence, Precedence::Term);
+    assert!(precedence::infix(&TokenType::LeftParen).is_none());
+}
+
+#[test]
+fn test_matmul_groups_with_multiplication() {
+    match parse_expression("a @ b * c").unwrap() {
+        Expr::BinOp { op, left, .. } => {
+            assert_eq!(op, Operator::Mult);
+            assert!(matches!(*left, Expr::BinOp { op: Operator::MatMult, .. }));
+        }
+        other => panic!("expected a binary operation, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_power_and_unary_operators() {
+    match parse_expression("-2 ** 2").unwrap() {
+        Expr::UnaryOp { op, operand, .. } => {
+            assert_eq!(op, UnaryOperator::USub);
+            assert!(matches!(*operand, Expr::BinOp { op: Operator::Pow, .. }));
+        }
+        other => panic!("expected a unary operation, got {:?}", other),
+    }
+    match parse_expression("2 ** -1").unwrap() {
+        Expr::BinOp { op, right, .. } => {
+            assert_eq!(op, Operator::Pow);
+            assert!(matches!(*right, Expr::UnaryOp { op: UnaryOperator::USub, .. }));
+        }
+        other => panic!("expected a binary operation, got {:?}", other),
+    }
+    match parse_expression("2 ** 3 ** 2").unwrap() {
+        Expr::BinOp { right, .. } => assert!(matches!(*right, Expr::BinOp { op: Operator::Pow, .. })),
+        other => panic!("expected a binary operation, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_conditional_expressions_wherever_an_expression_goes() {
+    match parse_expression("f(a if b else c)").unwrap() {
+        Expr::Call { args, .. } => assert!(matches!(*args[0], Expr::IfExp { .. })),
+        other => panic!("expected a call, got {:?}", other),
+    }
+    match parse_expression("[a if b else c for a in d if a]").unwrap() {
+        Expr::ListComp { elt, generators, .. } => {
+            assert!(matches!(*elt, Expr::IfExp { .. }));
+            assert_eq!(generators[0].ifs.len(), 1);
+        }
+        other => panic!("expected a list comprehension, got {:?}", other),
+    }
+    // The conditional binds more tightly than the tuple's comma
+    match parse_expression("a if b else c, d").unwrap() {
+        Expr::Tuple { elts, .. } => assert!(matches!(*elts[0], Expr::IfExp { .. })),
+        other => panic!("expected a tuple, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_walrus_binds_most_loosely() {
+    match parse_expression("(n := a if b else c)").unwrap() {
+        Expr::NamedExpr { value, .. } => assert!(matches!(*value, Expr::IfExp { .. })),
+        other => panic!("expected an assignment expression, got {:?}", other),
+    }
+    match parse_expression("(n := a or b)").unwrap() {
+        Expr::NamedExpr { value, .. } => assert!(matches!(*value, Expr::BoolOp { .. })),
+        other => panic!("expected an assignment expression, got {:?}", other),
+    }
+    assert!(parse_expression("(a + b := 1)").is_err());
+}
+
+#[test]
+fn test_not_only_where_a_boolean_test_goes() {
+    assert!(matches!(
+        parse_expression("not a == b").unwrap(),
+        Expr::UnaryOp { op: UnaryOperator::Not, .. }
+    ));
+    assert!(parse_expression("a == not b").is_err());
+    assert!(parse_expression("a + not b").is_err());
+}
+
+#[test]
+fn test_reference_lists_every_level_tightest_first() {
+    let reference = precedence::reference();
+    let rows: Vec<&str> = reference.lines().filter(|line| line.starts_with("| `")).collect();
+    assert_eq!(rows.len(), LEVELS.len());
+    assert!(rows[0].contains("`x(arguments)`"));
+    assert!(rows.last().unwrap().contains("`lambda`"));
+    assert!(reference.contains("| `\\|` | left to right | Bitwise OR |"));
+}
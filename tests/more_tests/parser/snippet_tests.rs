@@ -0,0 +1,53 @@
+use cheetah::ast::{Expr, Operator, Stmt};
+use cheetah::parser::{parse_expression, parse_statement};
+
+#[test]
+fn test_parse_expression() {
+    match parse_expression("1 + x * 2").unwrap() {
+        Expr::BinOp { op, .. } => assert_eq!(op, Operator::Add),
+        other => panic!("expected a binary operation, got {:?}", other),
+    }
+    assert!(matches!(parse_expression("f(a, b=1)\n"), Ok(Expr::Call { .. })));
+}
+
+#[test]
+fn test_parse_expression_rejects_more_input() {
+    let errors = parse_expression("x = 1").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].message().contains("end of expression"));
+
+    assert!(parse_expression("1 +").is_err());
+    assert!(parse_expression("'unterminated").is_err());
+}
+
+#[test]
+fn test_parse_statement() {
+    assert!(matches!(parse_statement("x = 1"), Ok(Stmt::Assign { .. })));
+
+    let source = "def f(n):\n    if n:\n        return 1\n    return 0\n";
+    match parse_statement(source).unwrap() {
+        Stmt::FunctionDef { name, body, .. } => {
+            assert_eq!(name, "f");
+            assert_eq!(body.len(), 2);
+        }
+        other => panic!("expected a function definition, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_statement_rejects_more_input() {
+    let errors = parse_statement("x = 1\ny = 2\n").unwrap_err();
+    assert!(errors[0].message().contains("end of statement"));
+    assert_eq!(errors[0].line(), 2);
+}
+
+#[test]
+fn test_parse_assert_with_message() {
+    match parse_statement("assert x > 0, \"x must be positive\"").unwrap() {
+        Stmt::Assert { test, msg, .. } => {
+            assert!(matches!(*test, Expr::Compare { .. }));
+            assert!(matches!(msg.as_deref(), Some(Expr::Str { .. })));
+        }
+        other => panic!("expected an assert, got {:?}", other),
+    }
+}
@@ -0,0 +1,127 @@
+use cheetah::ast::{Expr, Span, Stmt};
+use cheetah::parse;
+use cheetah::parser::parse_expression;
+
+fn text(source: &str, span: Span) -> &str {
+    span.text(source)
+}
+
+#[test]
+fn test_expression_spans_cover_their_source() {
+    let source = "(a + b) * foo(1, [2, 3])";
+    let expr = parse_expression(source).unwrap();
+    assert_eq!(text(source, expr.span()), source);
+
+    let Expr::BinOp { left, right, .. } = &expr else {
+        panic!("expected a binary operation, got {:?}", expr);
+    };
+    // Like Python's, a parenthesized node's own span leaves the parentheses out
+    assert_eq!(text(source, left.span()), "a + b");
+    assert_eq!(text(source, right.span()), "foo(1, [2, 3])");
+
+    let Expr::Call { func, args, .. } = &**right else {
+        panic!("expected a call, got {:?}", right);
+    };
+    assert_eq!(text(source, func.span()), "foo");
+    assert_eq!(text(source, args[1].span()), "[2, 3]");
+}
+
+#[test]
+fn test_operators_are_covered_from_their_left_operand() {
+    let source = "x.y[1:2] - f(-z)";
+    let expr = parse_expression(source).unwrap();
+    assert_eq!(text(source, expr.span()), source);
+    let Expr::BinOp { left, right, .. } = &expr else {
+        panic!("expected a binary operation, got {:?}", expr);
+    };
+    assert_eq!(text(source, left.span()), "x.y[1:2]");
+    assert_eq!(text(source, right.span()), "f(-z)");
+}
+
+#[test]
+fn test_statement_spans_include_their_blocks() {
+    let source = "def f(a, b=2):\n    return a + b\n\nif f(1):\n    y = 1\nelse:\n    y = 2\n";
+    let module = parse(source).unwrap();
+
+    let def = &module.body[0];
+    assert_eq!(text(source, def.span()), "def f(a, b=2):\n    return a + b");
+    let Stmt::FunctionDef { body, .. } = &**def else {
+        panic!("expected a function, got {:?}", def);
+    };
+    assert_eq!(text(source, body[0].span()), "return a + b");
+
+    let branch = &module.body[1];
+    assert_eq!(text(source, branch.span()), "if f(1):\n    y = 1\nelse:\n    y = 2");
+    assert_eq!((branch.span().end_line, branch.span().end_column), (7, 10));
+}
+
+#[test]
+fn test_f_string_parts_take_the_string_span() {
+    let source = "s = f\"x={x + 1}!\"\n";
+    let module = parse(source).unwrap();
+    let Stmt::Assign { value, .. } = &*module.body[0] else {
+        panic!("expected an assignment, got {:?}", module.body[0]);
+    };
+    assert_eq!(text(source, value.span()), "f\"x={x + 1}!\"");
+    let Expr::JoinedStr { values, .. } = &**value else {
+        panic!("expected an f-string, got {:?}", value);
+    };
+    for part in values {
+        assert_eq!(part.span(), value.span());
+    }
+}
+
+#[test]
+fn test_nodes_built_without_source_have_empty_spans() {
+    let expr = Expr::Name {
+        id: "x".to_string(),
+        ctx: cheetah::ast::ExprContext::Load,
+        line: 1,
+        column: 1,
+        span: Span::default(),
+    };
+    assert!(expr.span().is_empty());
+    assert_eq!(expr.span().text("x"), "");
+}
+
+#[test]
+fn test_streaming_parse_matches_buffered() {
+    let source = "def f(a, b):\n    return (a +\n        b) * [1, 2][0]\n\nclass C:\n    x = f(1, 2)\n";
+    let streamed = cheetah::parser::parse_source(source).unwrap();
+    let buffered = cheetah::parser::parse(cheetah::parser::tokenize(source).unwrap()).unwrap();
+    assert_eq!(format!("{:?}", streamed), format!("{:?}", buffered));
+
+    let Stmt::FunctionDef { body, .. } = streamed.body[0].as_ref() else {
+        panic!("expected a function");
+    };
+    assert_eq!(text(source, body[0].span()), "return (a +\n        b) * [1, 2][0]");
+
+    // Lexer errors still win over the parse errors they cause
+    let errors = cheetah::parser::parse_source("x = 1\ny = $\n").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].get_message().contains('$'), "{}", errors[0].get_message());
+}
+
+#[test]
+fn test_spans_after_nested_blocks_are_balanced() {
+    // Statements are widened as soon as they're parsed and the brackets in
+    // them dropped, so the ones around and after a block still balance
+    let source = "class C:\n    def f(self, a):\n        if (a):\n            return (a\n                + 1)\n        x = [a,\n            a]\n    y = (1)\nz = (C(), [2])\n";
+    let module = cheetah::parser::parse_source(source).unwrap();
+
+    let Stmt::ClassDef { body, .. } = module.body[0].as_ref() else {
+        panic!("expected a class");
+    };
+    let Stmt::FunctionDef { body: f_body, .. } = body[0].as_ref() else {
+        panic!("expected a function");
+    };
+    let Stmt::If { test, body: if_body, .. } = f_body[0].as_ref() else {
+        panic!("expected an if");
+    };
+    assert_eq!(text(source, test.span()), "a");
+    assert_eq!(text(source, if_body[0].span()), "return (a\n                + 1)");
+    assert_eq!(text(source, f_body[1].span()), "x = [a,\n            a]");
+    assert_eq!(text(source, body[1].span()), "y = (1)");
+    assert_eq!(text(source, module.body[1].span()), "z = (C(), [2])");
+    assert!(text(source, module.body[0].span()).ends_with("y = (1)"));
+}
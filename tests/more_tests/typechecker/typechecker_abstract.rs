@@ -0,0 +1,113 @@
+use cheetah::compiler::types::TypeError;
+use cheetah::typechecker;
+
+fn check(source: &str) -> Result<(), TypeError> {
+    let module = cheetah::parse(source).unwrap();
+    typechecker::check_module(&module)
+}
+
+#[test]
+fn test_instantiating_an_abstract_class_is_an_error() {
+    let source = r#"
+from abc import ABC, abstractmethod
+
+class Shape(ABC):
+    @abstractmethod
+    def area(self):
+        pass
+
+    @abstractmethod
+    def perimeter(self):
+        pass
+
+shape = Shape()
+"#;
+    let error = check(source).unwrap_err();
+    assert_eq!(error.code(), "abstract-instantiation");
+    assert_eq!(
+        error.to_string(),
+        "Cannot instantiate abstract class 'Shape' with abstract methods area, perimeter"
+    );
+}
+
+#[test]
+fn test_ellipsis_bodies_are_abstract() {
+    let source = r#"
+class Reader:
+    def read(self):
+        """Read the next chunk."""
+        ...
+
+    def close(self):
+        return None
+
+reader = Reader()
+"#;
+    let error = check(source).unwrap_err();
+    assert_eq!(error.code(), "abstract-instantiation");
+    assert!(error.to_string().ends_with("methods read"), "{}", error);
+}
+
+#[test]
+fn test_subclasses_must_implement_every_abstract_method() {
+    let source = r#"
+class Shape:
+    @abstractmethod
+    def area(self):
+        pass
+
+    @abstractmethod
+    def name(self):
+        pass
+
+class Named(Shape):
+    def name(self):
+        return "shape"
+
+class Square(Named):
+    def area(self):
+        return 4
+
+square = Square()
+"#;
+    assert!(check(source).is_ok(), "{:?}", check(source));
+
+    let source = r#"
+class Shape:
+    @abstractmethod
+    def area(self):
+        pass
+
+    @abstractmethod
+    def name(self):
+        pass
+
+class Named(Shape):
+    def name(self):
+        return "shape"
+
+named = Named()
+"#;
+    let error = check(source).unwrap_err();
+    assert_eq!(error.code(), "abstract-instantiation");
+    assert!(error.to_string().contains("'Named' with abstract methods area"), "{}", error);
+}
+
+#[test]
+fn test_redeclaring_an_implemented_method_abstract() {
+    let source = r#"
+class Base:
+    def run(self):
+        return 1
+
+class Task(Base):
+    @abc.abstractmethod
+    def run(self):
+        pass
+
+base = Base()
+task = Task()
+"#;
+    let error = check(source).unwrap_err();
+    assert!(error.to_string().contains("'Task'"), "{}", error);
+}
@@ -0,0 +1,54 @@
+use cheetah::compiler::types::TypeError;
+use cheetah::typechecker;
+
+fn check(source: &str) -> Result<(), TypeError> {
+    let module = cheetah::parse(source).unwrap();
+    typechecker::check_module(&module)
+}
+
+#[test]
+fn test_channel_builtins_check() {
+    let source = r#"
+jobs = channel(int)
+send(jobs, 21)
+doubled = recv(jobs) * 2
+names = channel(str)
+send(names, "cheetah")
+shout = recv(names, 0.5).upper()
+prices = channel(float)
+send(prices, 3)
+anything = channel()
+send(anything, [1, 2])
+"#;
+    assert!(check(source).is_ok(), "{:?}", check(source));
+}
+
+#[test]
+fn test_channel_annotation() {
+    let source = "def drain(results: Channel[int]) -> int:\n    return recv(results) + 1\n";
+    assert!(check(source).is_ok(), "{:?}", check(source));
+}
+
+#[test]
+fn test_channel_misuse_is_an_error() {
+    let error = check("jobs = channel(int)\nsend(jobs, \"seven\")\n").unwrap_err();
+    assert_eq!(error.code(), "incompatible-types");
+
+    let error = check("jobs = channel(int)\nname = recv(jobs).upper()\n").unwrap_err();
+    assert_eq!(error.code(), "not-a-class");
+
+    let error = check("send([1], 2)\n").unwrap_err();
+    assert_eq!(error.code(), "incompatible-types");
+
+    let error = check("jobs = channel(int)\nn = recv(jobs, \"soon\")\n").unwrap_err();
+    assert_eq!(error.code(), "incompatible-types");
+
+    let error = check("jobs = channel(int)\nsend(jobs)\n").unwrap_err();
+    assert_eq!(error.code(), "wrong-argument-count");
+}
+
+#[test]
+fn test_channel_builtins_can_be_shadowed() {
+    let source = "def send(to: str, text: str) -> bool:\n    return True\n\nok = send(\"ops\", \"hello\")\n";
+    assert!(check(source).is_ok(), "{:?}", check(source));
+}
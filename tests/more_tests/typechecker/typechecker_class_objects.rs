@@ -0,0 +1,44 @@
+use cheetah::compiler::types::TypeError;
+use cheetah::typechecker;
+
+fn check(source: &str) -> Result<(), TypeError> {
+    let module = cheetah::parse(source).unwrap();
+    typechecker::check_module(&module)
+}
+
+#[test]
+fn test_class_attributes_are_readable_through_the_class() {
+    let source = r#"
+class Animal:
+    legs = 4
+    kingdom: str = "animalia"
+
+class Dog(Animal):
+    sound = "woof"
+
+total = Dog.legs + Animal.legs
+label = Dog.kingdom + Dog.sound
+Animal.legs = 6
+"#;
+    assert!(check(source).is_ok(), "{:?}", check(source));
+
+    let error = check("class Animal:\n    legs = 4\n\nx = Animal.wings\n").unwrap_err();
+    assert_eq!(error.code(), "undefined-member");
+}
+
+#[test]
+fn test_classes_are_values() {
+    let source = r#"
+class Cat:
+    pass
+
+class Dog:
+    pass
+
+make = Dog
+dog = make()
+kinds = {"cat": Cat, "dog": Dog}
+cat = kinds["cat"]()
+"#;
+    assert!(check(source).is_ok(), "{:?}", check(source));
+}
@@ -0,0 +1,39 @@
+use cheetah::compiler::types::TypeError;
+use cheetah::typechecker;
+
+fn check(source: &str) -> Result<(), TypeError> {
+    let module = cheetah::parse(source).unwrap();
+    typechecker::check_module(&module)
+}
+
+#[test]
+fn test_introspection_builtins_check() {
+    let source = r#"
+class Plugin:
+    name = "base"
+
+plugin = Plugin()
+names = dir(plugin) + dir(Plugin)
+label = getattr(Plugin, "name") + "!"
+key = "version"
+version = getattr(plugin, key, 1) + 1
+setattr(plugin, key, 2)
+setattr(Plugin, "name", "renamed")
+"#;
+    assert!(check(source).is_ok(), "{:?}", check(source));
+}
+
+#[test]
+fn test_introspection_builtin_misuse_is_an_error() {
+    let error = check("class P:\n    x = 1\n\np = P()\ny = getattr(p)\n").unwrap_err();
+    assert_eq!(error.code(), "wrong-argument-count");
+
+    let error = check("class P:\n    x = 1\n\np = P()\nsetattr(p, 1, 2)\n").unwrap_err();
+    assert_eq!(error.code(), "incompatible-types");
+
+    // A literal name is checked like `P.size`
+    let error = check("class P:\n    x = 1\n\ny = getattr(P, \"size\")\n").unwrap_err();
+    assert_eq!(error.code(), "undefined-member");
+    let error = check("class P:\n    x = 1\n\ny = getattr(P, \"x\") + \"!\"\n").unwrap_err();
+    assert_eq!(error.code(), "invalid-operator");
+}
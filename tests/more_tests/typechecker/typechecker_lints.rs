@@ -0,0 +1,219 @@
+use cheetah::typechecker::{self, WarningKind};
+
+fn warnings_for(source: &str) -> Vec<typechecker::Warning> {
+    let module = cheetah::parse(source).unwrap();
+    typechecker::collect_warnings(&module, source)
+}
+
+#[test]
+fn test_pure_expression_statement() {
+    let source = r#"
+x = 1
+x + 1
+"#;
+
+    let warnings = warnings_for(source);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind, WarningKind::PureExpression);
+    assert_eq!(warnings[0].line, 3);
+    assert_eq!(warnings[0].column, 1);
+}
+
+#[test]
+fn test_calls_are_not_pure() {
+    let source = r#"
+print(1 + 2)
+x = [1, 2]
+len(x) + 1
+"#;
+
+    assert!(warnings_for(source).is_empty());
+}
+
+#[test]
+fn test_docstrings_are_ignored() {
+    let source = r#"
+def f():
+    "Docstring"
+    return 1
+"#;
+
+    assert!(warnings_for(source).is_empty());
+}
+
+#[test]
+fn test_dead_store() {
+    let source = r#"
+x = 1
+x = 2
+print(x)
+"#;
+
+    let warnings = warnings_for(source);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind, WarningKind::DeadStore);
+    assert_eq!(warnings[0].line, 2);
+    assert!(warnings[0].message.contains("'x'"));
+}
+
+#[test]
+fn test_dead_store_past_unrelated_statements() {
+    let source = r#"
+def f(y):
+    x = 1
+    print(y)
+    z = 3
+    x = 2
+    return x + z
+"#;
+
+    let warnings = warnings_for(source);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind, WarningKind::DeadStore);
+    assert_eq!(warnings[0].line, 3);
+    assert!(warnings[0].message.contains("overwritten on line 6"));
+}
+
+#[test]
+fn test_call_may_read_module_level_store() {
+    let source = r#"
+def show():
+    print(x)
+
+x = 1
+show()
+x = 2
+show()
+"#;
+
+    assert!(warnings_for(source).is_empty());
+}
+
+#[test]
+fn test_call_may_read_captured_or_global_store() {
+    let source = r#"
+def f():
+    global y
+    x = 1
+    def show():
+        print(x)
+    show()
+    x = 2
+    y = 1
+    print(0)
+    y = 2
+    show()
+"#;
+
+    let warnings: Vec<_> = warnings_for(source)
+        .into_iter()
+        .filter(|w| w.kind == WarningKind::DeadStore)
+        .collect();
+    assert!(warnings.is_empty(), "{:?}", warnings);
+}
+
+#[test]
+fn test_store_read_before_a_later_overwrite_is_not_dead() {
+    let source = r#"
+def f(items):
+    x = 1
+    if items:
+        print(x)
+    x = 2
+    y = 1
+    for item in items:
+        y = item
+    y = 0
+    z = 1
+    return z
+    z = 2
+    return x + y
+"#;
+
+    let warnings: Vec<_> = warnings_for(source)
+        .into_iter()
+        .filter(|w| w.kind == WarningKind::DeadStore)
+        .collect();
+    assert!(warnings.is_empty(), "{:?}", warnings);
+}
+
+#[test]
+fn test_overwrite_that_reads_value_is_not_dead() {
+    let source = r#"
+x = 1
+x = x + 1
+y = 2
+y, z = y, 3
+"#;
+
+    assert!(warnings_for(source).is_empty());
+}
+
+#[test]
+fn test_dead_store_in_nested_block() {
+    let source = r#"
+def f(a):
+    if a:
+        total = 0
+        total = 5
+        return total
+    return 0
+"#;
+
+    let warnings = warnings_for(source);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind, WarningKind::DeadStore);
+    assert_eq!(warnings[0].line, 4);
+}
+
+#[test]
+fn test_suppression_comments() {
+    let source = r#"
+x = 1  # cheetah: ignore
+x = 2
+x * 2  # cheetah: ignore[pure-expression]
+x - 2  # cheetah: ignore[dead-store]
+"#;
+
+    let warnings = warnings_for(source);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind, WarningKind::PureExpression);
+    assert_eq!(warnings[0].line, 5);
+}
+
+#[test]
+fn test_lossy_conversion_warnings() {
+    let source = r#"
+def scale(factor: int) -> bool:
+    return factor * 2
+
+flag: bool = 3
+widened: float = 1
+scale(2.5)
+"#;
+
+    let warnings: Vec<_> = warnings_for(source)
+        .into_iter()
+        .filter(|w| w.kind == WarningKind::LossyConversion)
+        .collect();
+    let lines: Vec<usize> = warnings.iter().map(|w| w.line).collect();
+    assert_eq!(lines, vec![3, 5, 7]);
+    assert!(warnings[0].message.contains("int to bool"));
+    assert!(warnings[2].message.contains("argument to 'scale'"));
+}
+
+#[test]
+fn test_soft_keyword_names() {
+    let source = r#"
+match = [1, 2]
+total = len(match)
+print(total)
+case = 1  # cheetah: ignore[soft-keyword]
+"#;
+
+    let warnings = warnings_for(source);
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings.iter().all(|w| w.kind == WarningKind::SoftKeyword));
+    assert_eq!((warnings[0].line, warnings[0].column), (2, 1));
+    assert_eq!((warnings[1].line, warnings[1].column), (3, 13));
+}
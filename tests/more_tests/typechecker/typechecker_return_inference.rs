@@ -0,0 +1,46 @@
+use cheetah::compiler::types::Type;
+use cheetah::typechecker::{self, TypeChecker};
+
+fn return_type(source: &str, function: &str) -> Option<Type> {
+    let module = cheetah::parse(source).unwrap();
+    let mut checker = TypeChecker::new();
+    checker.check_module(&module).unwrap();
+    checker.return_type(function)
+}
+
+#[test]
+fn test_unannotated_functions_return_what_they_return() {
+    let source = "def make_list():\n    return [1, 2, 3]\n";
+    assert_eq!(return_type(source, "make_list"), Some(Type::List(Box::new(Type::Int))));
+
+    let source = "def sign(x: int):\n    if x < 0:\n        return \"-\"\n    else:\n        return \"+\"\n";
+    assert_eq!(return_type(source, "sign"), Some(Type::String));
+
+    // Annotations win, and functions that may fall off their end or return
+    // different types stay dynamic
+    let source = "def f() -> list[float]:\n    return [1.0]\n";
+    assert_eq!(return_type(source, "f"), Some(Type::List(Box::new(Type::Float))));
+    let source = "def f(x: int):\n    if x:\n        return 1\n";
+    assert_eq!(return_type(source, "f"), Some(Type::Any));
+    let source = "def f(x: int):\n    if x:\n        return 1\n    return \"one\"\n";
+    assert_eq!(return_type(source, "f"), Some(Type::Any));
+}
+
+#[test]
+fn test_for_loops_over_call_results_check() {
+    let source = r#"
+def make_list():
+    return [1, 2, 3]
+
+def anything(x):
+    return x
+
+total = 0
+for item in make_list():
+    total = total + item
+for value in anything([1]):
+    print(value)
+"#;
+    let module = cheetah::parse(source).unwrap();
+    assert!(typechecker::check_module(&module).is_ok(), "{:?}", typechecker::check_module(&module));
+}
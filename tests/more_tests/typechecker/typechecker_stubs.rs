@@ -0,0 +1,84 @@
+use cheetah::compiler::types::{Type, TypeError};
+use cheetah::typechecker::stubs::{builtin_stub, ModuleStub};
+use cheetah::typechecker::TypeChecker;
+
+const LIBM: &str = r#"
+"""The C math library."""
+
+@extern("cos")
+def cos(x: float) -> float: ...
+
+def hypot(x: float, y: float = ...) -> float:
+    """Length of the vector (x, y)."""
+    ...
+
+pi: float
+"#;
+
+fn check_with(stub: Option<ModuleStub>, source: &str) -> Result<TypeChecker, TypeError> {
+    let module = cheetah::parse(source).unwrap();
+    let mut checker = TypeChecker::new();
+    if let Some(stub) = stub {
+        checker.add_stub(stub);
+    }
+    checker.check_module(&module).map(|_| checker)
+}
+
+#[test]
+fn test_parse_stub_members() {
+    let stub = ModuleStub::parse("libm", LIBM).unwrap();
+    assert_eq!(stub.name, "libm");
+    assert_eq!(stub.members.keys().collect::<Vec<_>>(), ["cos", "hypot", "pi"]);
+    assert_eq!(stub.member("pi"), Some(&Type::Float));
+    match stub.member("hypot") {
+        Some(Type::Function {
+            param_types,
+            default_values,
+            return_type,
+            ..
+        }) => {
+            assert_eq!(param_types, &[Type::Float, Type::Float]);
+            assert_eq!(default_values, &[false, true]);
+            assert_eq!(**return_type, Type::Float);
+        }
+        other => panic!("expected a function, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_stub_rejects_definitions() {
+    let error = ModuleStub::parse("bad", "def f() -> int:\n    return 1\n").unwrap_err();
+    assert!(error.contains("bad.chi:1"), "{}", error);
+    assert!(ModuleStub::parse("bad", "x = 1\n").is_err());
+    assert!(ModuleStub::parse("bad", "x: int\nx: str\n").unwrap_err().contains("twice"));
+}
+
+#[test]
+fn test_builtin_stubs_declare_runtime_modules() {
+    let os = builtin_stub("os").unwrap();
+    assert_eq!(os.member("tempdir"), Some(&Type::function(vec![], Type::String)));
+    let sys = builtin_stub("sys").unwrap();
+    assert!(matches!(sys.member("exc_info"), Some(Type::Function { return_type, .. }) if **return_type == Type::exc_info()));
+    assert!(builtin_stub("libm").is_none());
+}
+
+#[test]
+fn test_checks_module_members_against_stubs() {
+    let checker = check_with(None, "import os\npath = os.tempfile()\n").unwrap();
+    assert_eq!(checker.variable_type("path"), Some(&Type::String));
+
+    let error = check_with(None, "import os\npath = os.tempname()\n").unwrap_err();
+    assert_eq!(error.code(), "undefined-member");
+
+    let stub = ModuleStub::parse("libm", LIBM).unwrap();
+    let checker = check_with(Some(stub.clone()), "import libm\nd = libm.hypot(3.0, 4.0) + libm.pi\n").unwrap();
+    assert_eq!(checker.variable_type("d"), Some(&Type::Float));
+    assert!(check_with(Some(stub), "import libm\nd = libm.hypot(3.0) + \"!\"\n").is_err());
+    assert!(check_with(None, "import libm\nd = libm.pi\n").is_err());
+}
+
+#[test]
+fn test_definitions_shadow_stubs() {
+    let source = "os = \"/tmp\"\npath = os.upper()\n";
+    assert!(check_with(None, source).is_ok(), "{:?}", check_with(None, source).err());
+}
@@ -0,0 +1,139 @@
+use cheetah::compiler::types::TypeError;
+use cheetah::typechecker;
+use cheetah::typechecker::mro::{following, linearize};
+
+fn check(source: &str) -> Result<(), TypeError> {
+    let module = cheetah::parse(source).unwrap();
+    typechecker::check_module(&module)
+}
+
+fn bases(class: &str) -> Vec<String> {
+    let bases: &[&str] = match class {
+        "B" | "C" => &["A"],
+        "D" => &["B", "C"],
+        "E" => &["A", "B"],
+        _ => &[],
+    };
+    bases.iter().map(|base| base.to_string()).collect()
+}
+
+#[test]
+fn test_c3_linearization() {
+    assert_eq!(linearize("A", &bases).unwrap(), ["A"]);
+    assert_eq!(linearize("D", &bases).unwrap(), ["D", "B", "C", "A"]);
+    assert_eq!(following(&linearize("D", &bases).unwrap(), "B"), ["C", "A"]);
+
+    // A listed before its own subclass B
+    assert!(linearize("E", &bases).is_err());
+}
+
+#[test]
+fn test_super_calls_resolve_against_the_mro() {
+    let source = r#"
+class Animal:
+    def __init__(self, name):
+        self.name = name
+
+    def speak(self):
+        return "..."
+
+class Dog(Animal):
+    def __init__(self, name):
+        super().__init__(name)
+
+    def speak(self):
+        return super().speak() + " woof"
+
+class Puppy(Dog):
+    def speak(self):
+        return super(Dog, self).speak()
+"#;
+    assert!(check(source).is_ok(), "{:?}", check(source));
+}
+
+#[test]
+fn test_super_init_falls_back_to_object() {
+    let source = r#"
+class Point:
+    def __init__(self):
+        super().__init__()
+        self.x = 0
+"#;
+    assert!(check(source).is_ok(), "{:?}", check(source));
+}
+
+#[test]
+fn test_diamond_super_calls_follow_the_next_class() {
+    let source = r#"
+class Base:
+    def describe(self):
+        return "base"
+
+class Left(Base):
+    def describe(self):
+        return "left " + super().describe()
+
+class Right(Base):
+    def extra(self):
+        return "right"
+
+class Both(Left, Right):
+    def describe(self):
+        return super().describe()
+
+    def more(self):
+        return super().extra()
+"#;
+    assert!(check(source).is_ok(), "{:?}", check(source));
+}
+
+#[test]
+fn test_super_calls_to_missing_methods_are_errors() {
+    let source = r#"
+class Animal:
+    def speak(self):
+        return "..."
+
+class Dog(Animal):
+    def fetch(self):
+        return super().fetch()
+"#;
+    let error = check(source).unwrap_err();
+    assert_eq!(error.code(), "invalid-super");
+    assert!(error.to_string().contains("fetch"), "{}", error);
+
+    // A class's own methods aren't visible through super()
+    let source = r#"
+class Animal:
+    def speak(self):
+        return super().speak()
+"#;
+    assert_eq!(check(source).unwrap_err().code(), "invalid-super");
+}
+
+#[test]
+fn test_super_outside_a_method_is_an_error() {
+    assert_eq!(check("super().__init__()\n").unwrap_err().code(), "invalid-super");
+
+    let source = r#"
+class Dog:
+    def bark(self):
+        return super(Cat, self).bark()
+"#;
+    assert_eq!(check(source).unwrap_err().code(), "invalid-super");
+}
+
+#[test]
+fn test_inconsistent_mro_is_an_error() {
+    let source = r#"
+class Animal:
+    pass
+
+class Dog(Animal):
+    pass
+
+class Puppy(Animal, Dog):
+    pass
+"#;
+    assert_eq!(check(source).unwrap_err().code(), "inconsistent-mro");
+}
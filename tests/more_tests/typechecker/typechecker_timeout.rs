@@ -0,0 +1,37 @@
+use cheetah::compiler::types::TypeError;
+use cheetah::typechecker;
+
+fn check(source: &str) -> Result<(), TypeError> {
+    let module = cheetah::parse(source).unwrap();
+    typechecker::check_module(&module)
+}
+
+#[test]
+fn test_with_timeout_returns_what_the_function_does() {
+    let source = r#"
+def work() -> int:
+    return 42
+
+def greet() -> str:
+    return "hello"
+
+doubled = with_timeout(0.5, work) * 2
+shout = with_timeout(1, greet).upper()
+"#;
+    assert!(check(source).is_ok(), "{:?}", check(source));
+}
+
+#[test]
+fn test_with_timeout_misuse_is_an_error() {
+    let error = check("def add(a: int, b: int) -> int:\n    return a + b\n\nwith_timeout(1, add)\n").unwrap_err();
+    assert_eq!(error.code(), "wrong-argument-count");
+
+    let error = check("def work() -> int:\n    return 1\n\nwith_timeout(\"soon\", work)\n").unwrap_err();
+    assert_eq!(error.code(), "incompatible-types");
+
+    let error = check("with_timeout(1, 2)\n").unwrap_err();
+    assert_eq!(error.code(), "not-callable");
+
+    let error = check("def work() -> int:\n    return 1\n\nwith_timeout(work)\n").unwrap_err();
+    assert_eq!(error.code(), "wrong-argument-count");
+}
@@ -0,0 +1,122 @@
+use cheetah::compiler::types::TypeError;
+use cheetah::typechecker::{TypeChecker, WarningKind};
+
+fn check(source: &str) -> (Result<(), TypeError>, TypeChecker) {
+    let module = cheetah::parse(source).unwrap();
+    let mut checker = TypeChecker::new();
+    let result = checker.check_module(&module);
+    (result, checker)
+}
+
+/// Lines of the possibly-unbound warnings for `source`, which must check
+fn possibly_unbound(source: &str) -> Vec<usize> {
+    let (result, checker) = check(source);
+    assert!(result.is_ok(), "{:?}", result);
+    checker
+        .warnings()
+        .iter()
+        .filter(|warning| warning.kind == WarningKind::PossiblyUnbound)
+        .map(|warning| warning.line)
+        .collect()
+}
+
+#[test]
+fn test_read_before_any_assignment_is_an_error() {
+    let source = "count = 0\n\ndef increment():\n    print(count)\n    count = count + 1\n";
+    let (result, checker) = check(source);
+    let error = result.unwrap_err();
+    assert_eq!(error.code(), "unbound-local");
+    assert_eq!(checker.position(), (4, 11));
+
+    // Augmented assignment reads first
+    let (result, _) = check("def f():\n    total += 1\n    return total\n");
+    assert_eq!(result.unwrap_err().code(), "unbound-local");
+}
+
+#[test]
+fn test_reads_assigned_on_some_paths_are_warnings() {
+    let source = r#"
+def sign(n: int):
+    if n > 0:
+        label = "positive"
+    return label
+
+def last(items):
+    for item in items:
+        found = item
+    return found
+
+def guarded():
+    try:
+        value = 1
+    except ValueError:
+        pass
+    return value
+"#;
+    assert_eq!(possibly_unbound(source), [5, 10, 17]);
+}
+
+#[test]
+fn test_reads_every_path_assigns_check_cleanly() {
+    let source = r#"
+counter = 0
+
+def bump(step):
+    global counter
+    counter = counter + step
+    return counter
+
+def both(flag: bool):
+    if flag:
+        text = "yes"
+    else:
+        text = "no"
+    return text
+
+def read_until(limit: int):
+    n = 0
+    while True:
+        n = n + 1
+        if n > limit:
+            result = n
+            break
+    return result
+
+def pairs(items):
+    previous = 0
+    for item in items:
+        if item > 0:
+            print(previous)
+        previous = item
+    squares = [x * x for x in items]
+    return squares
+
+def outer():
+    value = 1
+    def inner():
+        return value
+    return inner()
+
+def caught():
+    try:
+        number = 1
+    except ValueError:
+        number = 0
+    return number
+"#;
+    assert_eq!(possibly_unbound(source), Vec::<usize>::new());
+}
+
+#[test]
+fn test_loop_carried_reads_are_warnings_not_errors() {
+    let source = r#"
+def deltas(items):
+    first = True
+    for item in items:
+        if not first:
+            print(item - prev)
+        prev = item
+        first = False
+"#;
+    assert_eq!(possibly_unbound(source), [6]);
+}
@@ -19,3 +19,31 @@ mod error_recovery_comprehensive;
 // Include the simple error tests
 #[path = "more_tests/parser/simple_error_test.rs"]
 mod simple_error_test;
+
+// Include the single expression and statement parsing tests
+#[path = "more_tests/parser/snippet_tests.rs"]
+mod snippet_tests;
+
+// Include the formatter snapshot tests over tests/format
+#[path = "more_tests/parser/format_corpus_tests.rs"]
+mod format_corpus_tests;
+
+// Include the source span tests
+#[path = "more_tests/parser/span_tests.rs"]
+mod span_tests;
+
+// Include the AST JSON export and import tests
+#[path = "more_tests/parser/ast_json_tests.rs"]
+mod ast_json_tests;
+
+// Include the Graphviz AST graph tests
+#[path = "more_tests/parser/ast_dot_tests.rs"]
+mod ast_dot_tests;
+
+// Include the incremental re-parsing tests
+#[path = "more_tests/parser/incremental_tests.rs"]
+mod incremental_tests;
+
+// Include the operator precedence table tests
+#[path = "more_tests/parser/precedence_tests.rs"]
+mod precedence_tests;
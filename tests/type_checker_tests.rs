@@ -19,3 +19,43 @@ mod typechecker_functions_control;
 // Include the type annotations tests
 #[path = "more_tests/typechecker/typechecker_annotations.rs"]
 mod typechecker_annotations;
+
+// Include the lint warning tests
+#[path = "more_tests/typechecker/typechecker_lints.rs"]
+mod typechecker_lints;
+
+// Include the super() and method resolution order tests
+#[path = "more_tests/typechecker/typechecker_super.rs"]
+mod typechecker_super;
+
+// Include the abstract method tests
+#[path = "more_tests/typechecker/typechecker_abstract.rs"]
+mod typechecker_abstract;
+
+// Include the class attribute and class object tests
+#[path = "more_tests/typechecker/typechecker_class_objects.rs"]
+mod typechecker_class_objects;
+
+// Include the dir(), getattr() and setattr() tests
+#[path = "more_tests/typechecker/typechecker_introspection.rs"]
+mod typechecker_introspection;
+
+// Include the inferred return type tests
+#[path = "more_tests/typechecker/typechecker_return_inference.rs"]
+mod typechecker_return_inference;
+
+// Include the unbound local variable tests
+#[path = "more_tests/typechecker/typechecker_unbound.rs"]
+mod typechecker_unbound;
+
+// Include the module stub tests
+#[path = "more_tests/typechecker/typechecker_stubs.rs"]
+mod typechecker_stubs;
+
+// Include the channel built-in tests
+#[path = "more_tests/typechecker/typechecker_channels.rs"]
+mod typechecker_channels;
+
+// Include the with_timeout() built-in tests
+#[path = "more_tests/typechecker/typechecker_timeout.rs"]
+mod typechecker_timeout;